@@ -0,0 +1,38 @@
+//! Build script
+//!
+//! Generates the gRPC client for the Python sidecar protocol from
+//! `proto/hegel/sidecar/v2/sidecar.proto` when the `grpc` feature is enabled.
+//! Uses the `protoc` named by `PROTOC` when set, otherwise the vendored binary
+//! from `protoc-bin-vendored`, so no system install is needed.
+//!
+//! Also records the git revision being built as `HEGEL_BUILD_REVISION`, part
+//! of the code version in pipeline fingerprints, unless it is already set.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-env-changed=HEGEL_BUILD_REVISION");
+    if std::env::var_os("HEGEL_BUILD_REVISION").is_none() {
+        println!("cargo:rerun-if-changed=../.git/HEAD");
+        println!("cargo:rerun-if-changed=../.git/index");
+        let revision = std::process::Command::new("git")
+            .args(["rev-parse", "--short=12", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success());
+        if let Some(output) = revision {
+            println!("cargo:rustc-env=HEGEL_BUILD_REVISION={}", String::from_utf8_lossy(&output.stdout).trim());
+        }
+    }
+
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/hegel/sidecar/v2/sidecar.proto");
+        println!("cargo:rerun-if-env-changed=PROTOC");
+        if std::env::var_os("PROTOC").is_none() {
+            std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+        }
+        tonic_build::configure()
+            .build_server(false)
+            .compile(&["proto/hegel/sidecar/v2/sidecar.proto"], &["proto"])?;
+    }
+    Ok(())
+}
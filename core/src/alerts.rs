@@ -0,0 +1,618 @@
+//! Alerting Rules
+//!
+//! Rules such as "alert if any molecule in project X drops below 0.4" or
+//! "alert if a molecule has more than 3 conflicting evidence pairs" are
+//! evaluated against every integration result. Alerts are edge-triggered: a
+//! rule fires when its condition becomes true for a molecule and stays quiet
+//! until the condition has cleared and become true again. Raised alerts are
+//! handed to every configured sink (stdout, webhooks and a persisted
+//! JSON-lines log that the API queries).
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::processing::evidence::IntegratedEvidence;
+use crate::webhooks::{WebhookDispatcher, WebhookEvent};
+
+/// Initialize the alerts module
+pub fn initialize() -> Result<()> {
+    info!("Initializing alerts module");
+    info!("Alerts module initialized successfully");
+    Ok(())
+}
+
+/// How urgent an alert is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    /// Worth knowing about
+    Info,
+    /// Needs attention
+    #[default]
+    Warning,
+    /// Needs attention now
+    Critical,
+}
+
+impl fmt::Display for AlertSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlertSeverity::Info => write!(f, "info"),
+            AlertSeverity::Warning => write!(f, "warning"),
+            AlertSeverity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+impl FromStr for AlertSeverity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "info" => Ok(AlertSeverity::Info),
+            "warning" => Ok(AlertSeverity::Warning),
+            "critical" => Ok(AlertSeverity::Critical),
+            _ => Err(anyhow!("Unknown alert severity: {}", s)),
+        }
+    }
+}
+
+/// Condition a rule watches for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Integrated confidence is below a threshold
+    ConfidenceBelow {
+        /// Confidence under which the rule fires
+        threshold: f64,
+    },
+    /// Integrated confidence fell by at least `delta` since the previous integration
+    ConfidenceDrop {
+        /// Smallest fall that fires the rule
+        delta: f64,
+    },
+    /// More conflicts than allowed were detected
+    ConflictCountAbove {
+        /// Largest number of conflicts that does not fire the rule
+        count: usize,
+    },
+    /// A conflict is more severe than allowed
+    ConflictSeverityAbove {
+        /// Severity above which the rule fires
+        severity: f64,
+    },
+    /// Fewer evidence items than required were integrated
+    EvidenceCountBelow {
+        /// Number of evidence items under which the rule fires
+        count: usize,
+    },
+}
+
+impl AlertCondition {
+    /// Check that thresholds are in range
+    pub fn validate(&self) -> Result<()> {
+        let (name, value) = match self {
+            AlertCondition::ConfidenceBelow { threshold } => ("threshold", *threshold),
+            AlertCondition::ConfidenceDrop { delta } => ("delta", *delta),
+            AlertCondition::ConflictSeverityAbove { severity } => ("severity", *severity),
+            AlertCondition::ConflictCountAbove { .. } | AlertCondition::EvidenceCountBelow { .. } => return Ok(()),
+        };
+        if !(0.0..=1.0).contains(&value) {
+            return Err(anyhow!("Alert {} must be between 0 and 1, got {}", name, value));
+        }
+        Ok(())
+    }
+
+    /// Value that fired the condition and a description of it, if it holds
+    fn check(&self, observation: &AlertObservation, previous: Option<f64>) -> Option<(f64, String)> {
+        match self {
+            AlertCondition::ConfidenceBelow { threshold } => (observation.confidence < *threshold).then(|| (
+                observation.confidence,
+                format!("confidence {:.3} is below {:.3}", observation.confidence, threshold),
+            )),
+            AlertCondition::ConfidenceDrop { delta } => {
+                let fall = previous? - observation.confidence;
+                (fall >= *delta).then(|| (
+                    fall,
+                    format!("confidence fell by {:.3} to {:.3}", fall, observation.confidence),
+                ))
+            }
+            AlertCondition::ConflictCountAbove { count } => (observation.conflict_count > *count).then(|| (
+                observation.conflict_count as f64,
+                format!("{} conflicts detected (more than {})", observation.conflict_count, count),
+            )),
+            AlertCondition::ConflictSeverityAbove { severity } => observation.max_conflict_severity
+                .filter(|s| s > severity)
+                .map(|s| (s, format!("conflict severity {:.3} exceeds {:.3}", s, severity))),
+            AlertCondition::EvidenceCountBelow { count } => (observation.evidence_count < *count).then(|| (
+                observation.evidence_count as f64,
+                format!("only {} evidence items integrated (fewer than {})", observation.evidence_count, count),
+            )),
+        }
+    }
+}
+
+/// A user-defined alerting rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Rule identifier
+    #[serde(default = "new_rule_id")]
+    pub id: String,
+
+    /// Human-readable name
+    pub name: String,
+
+    /// Project the rule is limited to; every project when unset
+    #[serde(default)]
+    pub project_id: Option<String>,
+
+    /// Condition that fires the rule
+    pub condition: AlertCondition,
+
+    /// Severity of the alerts raised
+    #[serde(default)]
+    pub severity: AlertSeverity,
+
+    /// Whether the rule is evaluated
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn new_rule_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl AlertRule {
+    /// Create an enabled warning rule covering every project
+    pub fn new(name: &str, condition: AlertCondition) -> Self {
+        Self {
+            id: new_rule_id(),
+            name: name.to_string(),
+            project_id: None,
+            condition,
+            severity: AlertSeverity::default(),
+            enabled: true,
+        }
+    }
+
+    /// Limit the rule to one project
+    pub fn with_project(mut self, project_id: &str) -> Self {
+        self.project_id = Some(project_id.to_string());
+        self
+    }
+
+    /// Raise alerts with the given severity
+    pub fn with_severity(mut self, severity: AlertSeverity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Check that the rule can be evaluated
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("Alert rule name must not be empty"));
+        }
+        self.condition.validate()
+    }
+
+    /// Whether the rule is evaluated for a project
+    pub fn applies_to(&self, project_id: &str) -> bool {
+        self.enabled && self.project_id.as_deref().is_none_or(|p| p == project_id)
+    }
+}
+
+/// What an integration run concluded about one molecule
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertObservation {
+    /// Project the molecule belongs to
+    pub project_id: String,
+
+    /// Molecule that was integrated
+    pub molecule_id: String,
+
+    /// Integrated confidence
+    pub confidence: f64,
+
+    /// Number of conflicts detected
+    pub conflict_count: usize,
+
+    /// Severity of the worst conflict, if any
+    pub max_conflict_severity: Option<f64>,
+
+    /// Number of evidence items integrated
+    pub evidence_count: usize,
+}
+
+impl AlertObservation {
+    /// Observe an integration result
+    pub fn from_integrated(project_id: &str, integrated: &IntegratedEvidence) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+            molecule_id: integrated.molecule_id.clone(),
+            confidence: integrated.aggregate_confidence,
+            conflict_count: integrated.conflicts.len(),
+            max_conflict_severity: integrated.conflicts.iter().map(|c| c.severity).reduce(f64::max),
+            evidence_count: integrated.evidence_items.len(),
+        }
+    }
+}
+
+/// An alert raised by a rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    /// Alert identifier
+    pub id: String,
+
+    /// Rule that raised the alert
+    pub rule_id: String,
+
+    /// Name of that rule
+    pub rule_name: String,
+
+    /// Project of the molecule
+    pub project_id: String,
+
+    /// Molecule the alert is about
+    pub molecule_id: String,
+
+    /// Severity of the rule
+    pub severity: AlertSeverity,
+
+    /// What fired the rule
+    pub message: String,
+
+    /// Value that fired the rule
+    pub value: f64,
+
+    /// When the alert was raised
+    pub raised_at: DateTime<Utc>,
+}
+
+/// Destination for raised alerts
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Deliver one alert
+    async fn send(&self, alert: &Alert) -> Result<()>;
+}
+
+/// Sink printing one line per alert to stdout
+pub struct StdoutSink;
+
+#[async_trait]
+impl AlertSink for StdoutSink {
+    fn name(&self) -> &str {
+        "stdout"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        println!("[{}] {} {}/{}: {}", alert.severity, alert.rule_name, alert.project_id, alert.molecule_id, alert.message);
+        Ok(())
+    }
+}
+
+/// Sink emitting an `alert_raised` event to subscribed webhooks
+pub struct WebhookSink {
+    /// Dispatcher of the registered webhooks
+    dispatcher: Arc<WebhookDispatcher>,
+}
+
+impl WebhookSink {
+    /// Emit alerts through the given dispatcher
+    pub fn new(dispatcher: Arc<WebhookDispatcher>) -> Self {
+        Self { dispatcher }
+    }
+}
+
+#[async_trait]
+impl AlertSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        self.dispatcher.emit(WebhookEvent::AlertRaised { alert: alert.clone() }).await;
+        Ok(())
+    }
+}
+
+/// Append-only JSON-lines log of raised alerts
+#[derive(Debug, Clone)]
+pub struct AlertLog {
+    /// Log file
+    path: PathBuf,
+}
+
+impl AlertLog {
+    /// Use the log at the given path, created on first append
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Use the log named by `HEGEL_ALERTS_LOG`, or `alerts.jsonl`
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("HEGEL_ALERTS_LOG").unwrap_or_else(|_| "alerts.jsonl".to_string()))
+    }
+
+    /// Path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append alerts and flush them to disk
+    pub fn append(&self, alerts: &[Alert]) -> Result<()> {
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open alerts log: {}", self.path.display()))?;
+        for alert in alerts {
+            serde_json::to_writer(&mut file, alert)?;
+            file.write_all(b"\n")?;
+        }
+        file.sync_all().context("Failed to flush alerts log")?;
+        Ok(())
+    }
+
+    /// Read every alert in the log, oldest first
+    pub fn records(&self) -> Result<Vec<Alert>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = std::fs::File::open(&self.path)
+            .with_context(|| format!("Failed to open alerts log: {}", self.path.display()))?;
+        std::io::BufReader::new(file).lines()
+            .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect()
+    }
+
+    /// Most recent alerts of a project, newest first, optionally for one rule or molecule
+    pub fn query(&self, project_id: &str, rule_id: Option<&str>, molecule_id: Option<&str>, limit: usize) -> Result<Vec<Alert>> {
+        Ok(self.records()?
+            .into_iter()
+            .rev()
+            .filter(|a| a.project_id == project_id)
+            .filter(|a| rule_id.is_none_or(|id| a.rule_id == id))
+            .filter(|a| molecule_id.is_none_or(|id| a.molecule_id == id))
+            .take(limit)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl AlertSink for AlertLog {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        self.append(std::slice::from_ref(alert))
+    }
+}
+
+/// Evaluates rules against integration results and dispatches alerts
+pub struct AlertEngine {
+    /// Rules by ID
+    rules: RwLock<HashMap<String, AlertRule>>,
+
+    /// (rule, project, molecule) combinations whose condition currently holds
+    active: Mutex<HashSet<(String, String, String)>>,
+
+    /// Last observed confidence per (project, molecule)
+    last_confidence: Mutex<HashMap<(String, String), f64>>,
+
+    /// Where raised alerts go
+    sinks: Vec<Arc<dyn AlertSink>>,
+}
+
+impl AlertEngine {
+    /// Create an engine without rules or sinks
+    pub fn new() -> Self {
+        Self {
+            rules: RwLock::new(HashMap::new()),
+            active: Mutex::new(HashSet::new()),
+            last_confidence: Mutex::new(HashMap::new()),
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Create an engine with the rules in the file named by `HEGEL_ALERT_RULES`, if set
+    pub fn from_env() -> Result<Self> {
+        let mut engine = Self::new();
+        if let Ok(path) = std::env::var("HEGEL_ALERT_RULES") {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read alert rules: {}", path))?;
+            let rules: Vec<AlertRule> = serde_json::from_str(&content).context("Failed to parse alert rules")?;
+            let mut map = HashMap::new();
+            for rule in rules {
+                rule.validate()?;
+                if map.insert(rule.id.clone(), rule).is_some() {
+                    return Err(anyhow!("Duplicate alert rule ID in {}", path));
+                }
+            }
+            info!("Loaded {} alert rules from {}", map.len(), path);
+            engine.rules = RwLock::new(map);
+        }
+        Ok(engine)
+    }
+
+    /// Deliver raised alerts to a sink
+    pub fn with_sink(mut self, sink: Arc<dyn AlertSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Add a rule, returning its ID
+    pub async fn add_rule(&self, rule: AlertRule) -> Result<String> {
+        rule.validate()?;
+        let id = rule.id.clone();
+        let mut rules = self.rules.write().await;
+        if rules.contains_key(&id) {
+            return Err(anyhow!("Alert rule already exists: {}", id));
+        }
+        rules.insert(id.clone(), rule);
+        Ok(id)
+    }
+
+    /// Remove a rule, returning whether it existed
+    pub async fn remove_rule(&self, rule_id: &str) -> bool {
+        self.active.lock().await.retain(|(id, _, _)| id != rule_id);
+        self.rules.write().await.remove(rule_id).is_some()
+    }
+
+    /// Rules that apply to a project, by name
+    pub async fn rules(&self, project_id: Option<&str>) -> Vec<AlertRule> {
+        let mut rules: Vec<AlertRule> = self.rules.read().await.values()
+            .filter(|r| project_id.is_none_or(|p| r.project_id.as_deref().is_none_or(|rp| rp == p)))
+            .cloned()
+            .collect();
+        rules.sort_by(|a, b| a.name.cmp(&b.name));
+        rules
+    }
+
+    /// Evaluate every rule against an observation and return the newly raised alerts
+    ///
+    /// Nothing is delivered; see `observe` for that.
+    pub async fn evaluate(&self, observation: &AlertObservation) -> Vec<Alert> {
+        let molecule_key = (observation.project_id.clone(), observation.molecule_id.clone());
+        let previous = self.last_confidence.lock().await.insert(molecule_key, observation.confidence);
+
+        let rules = self.rules.read().await;
+        let mut active = self.active.lock().await;
+        let mut alerts = Vec::new();
+        for rule in rules.values().filter(|r| r.applies_to(&observation.project_id)) {
+            let key = (rule.id.clone(), observation.project_id.clone(), observation.molecule_id.clone());
+            match rule.condition.check(observation, previous) {
+                Some((value, message)) => {
+                    if active.insert(key) {
+                        alerts.push(Alert {
+                            id: uuid::Uuid::new_v4().to_string(),
+                            rule_id: rule.id.clone(),
+                            rule_name: rule.name.clone(),
+                            project_id: observation.project_id.clone(),
+                            molecule_id: observation.molecule_id.clone(),
+                            severity: rule.severity,
+                            message,
+                            value,
+                            raised_at: Utc::now(),
+                        });
+                    }
+                }
+                None => {
+                    active.remove(&key);
+                }
+            }
+        }
+        alerts.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.rule_name.cmp(&b.rule_name)));
+        alerts
+    }
+
+    /// Evaluate an observation and deliver the raised alerts to every sink
+    ///
+    /// A failing sink is logged and does not keep the others from receiving the alert.
+    pub async fn observe(&self, observation: &AlertObservation) -> Vec<Alert> {
+        let alerts = self.evaluate(observation).await;
+        self.deliver(&alerts).await;
+        alerts
+    }
+
+    /// Deliver alerts raised outside the rules, e.g. by drift detection, to every sink
+    pub async fn deliver(&self, alerts: &[Alert]) {
+        for alert in alerts {
+            for sink in &self.sinks {
+                if let Err(e) = sink.send(alert).await {
+                    warn!("Failed to deliver alert {} to {}: {:#}", alert.id, sink.name(), e);
+                }
+            }
+        }
+    }
+
+    /// Evaluate an integration result and deliver the raised alerts
+    pub async fn observe_integrated(&self, project_id: &str, integrated: &IntegratedEvidence) -> Vec<Alert> {
+        self.observe(&AlertObservation::from_integrated(project_id, integrated)).await
+    }
+}
+
+impl Default for AlertEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(confidence: f64, conflict_count: usize) -> AlertObservation {
+        AlertObservation {
+            project_id: "alpha".to_string(),
+            molecule_id: "glucose".to_string(),
+            confidence,
+            conflict_count,
+            max_conflict_severity: (conflict_count > 0).then_some(0.5),
+            evidence_count: 4,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rules_fire_on_edges() {
+        let engine = AlertEngine::new();
+        engine.add_rule(AlertRule::new("low confidence", AlertCondition::ConfidenceBelow { threshold: 0.4 })
+            .with_project("alpha")
+            .with_severity(AlertSeverity::Critical)).await.unwrap();
+        engine.add_rule(AlertRule::new("conflicting", AlertCondition::ConflictCountAbove { count: 3 })).await.unwrap();
+        engine.add_rule(AlertRule::new("other project", AlertCondition::ConfidenceBelow { threshold: 0.9 })
+            .with_project("beta")).await.unwrap();
+
+        assert!(engine.evaluate(&observation(0.7, 1)).await.is_empty());
+
+        let alerts = engine.evaluate(&observation(0.3, 4)).await;
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].rule_name, "low confidence");
+        assert_eq!(alerts[0].severity, AlertSeverity::Critical);
+        assert_eq!(alerts[1].value, 4.0);
+
+        // Still true: no repeat until the condition clears
+        assert!(engine.evaluate(&observation(0.35, 5)).await.is_empty());
+        assert!(engine.evaluate(&observation(0.6, 5)).await.is_empty());
+        assert_eq!(engine.evaluate(&observation(0.2, 5)).await.len(), 1);
+
+        assert!(AlertRule::new("bad", AlertCondition::ConfidenceDrop { delta: 1.5 }).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_log_sink_persists_alerts() {
+        let log = AlertLog::new(std::env::temp_dir().join(format!("hegel-alerts-{}.jsonl", uuid::Uuid::new_v4())));
+        let engine = AlertEngine::new().with_sink(Arc::new(log.clone()));
+        engine.add_rule(AlertRule::new("drop", AlertCondition::ConfidenceDrop { delta: 0.2 })).await.unwrap();
+
+        assert!(engine.observe(&observation(0.9, 0)).await.is_empty());
+        assert_eq!(engine.observe(&observation(0.6, 0)).await.len(), 1);
+
+        let stored = log.query("alpha", None, Some("glucose"), 10).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].message.contains("fell by 0.300"));
+        assert!(log.query("beta", None, None, 10).unwrap().is_empty());
+        std::fs::remove_file(log.path()).unwrap();
+    }
+}
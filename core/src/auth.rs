@@ -0,0 +1,142 @@
+//! API Authentication
+//!
+//! Verifies the bearer tokens issued by the backend's `/auth/login` endpoint.
+//! Tokens are HS256 JWTs carrying the user ID in `sub` and the account role,
+//! signed with the secret shared through `JWT_SECRET_KEY`.
+
+use anyhow::{anyhow, Result};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use log::{info, warn};
+use serde::{Serialize, Deserialize};
+
+/// Initialize the authentication module
+pub fn initialize() -> Result<()> {
+    info!("Initializing authentication module");
+    if std::env::var("JWT_SECRET_KEY").is_err() {
+        warn!("JWT_SECRET_KEY environment variable not set; the API will refuse to start");
+    }
+    info!("Authentication module initialized successfully");
+    Ok(())
+}
+
+/// Account role assigned by the backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    /// Full access to every project
+    Admin,
+    /// Regular account
+    #[default]
+    Researcher,
+    /// Read-only account
+    Viewer,
+}
+
+/// Claims carried by an access token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// User ID
+    pub sub: String,
+
+    /// Account role
+    #[serde(default)]
+    pub role: UserRole,
+
+    /// Expiry as a Unix timestamp
+    pub exp: i64,
+}
+
+/// The authenticated caller of a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Principal {
+    /// User ID
+    pub user_id: String,
+
+    /// Account role
+    pub role: UserRole,
+}
+
+impl Principal {
+    /// Whether the caller bypasses project membership
+    pub fn is_admin(&self) -> bool {
+        self.role == UserRole::Admin
+    }
+
+    /// Whether the caller's account is limited to reading
+    pub fn is_read_only(&self) -> bool {
+        self.role == UserRole::Viewer
+    }
+}
+
+/// Verifier of backend-issued access tokens
+#[derive(Clone)]
+pub struct TokenVerifier {
+    /// Key the tokens are signed with
+    key: DecodingKey,
+
+    /// Accepted algorithm and expiry checks
+    validation: Validation,
+}
+
+impl TokenVerifier {
+    /// Create a verifier for tokens signed with the given secret
+    pub fn new(secret: &str) -> Self {
+        Self {
+            key: DecodingKey::from_secret(secret.as_bytes()),
+            validation: Validation::new(Algorithm::HS256),
+        }
+    }
+
+    /// Create a verifier using the secret shared with the backend
+    ///
+    /// Fails if `JWT_SECRET_KEY` is unset or empty, rather than falling back to
+    /// a well-known secret that anyone could sign tokens with.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("JWT_SECRET_KEY") {
+            Ok(secret) if !secret.trim().is_empty() => Ok(Self::new(&secret)),
+            _ => Err(anyhow!("JWT_SECRET_KEY environment variable must be set to the backend's token secret")),
+        }
+    }
+
+    /// Verify a token and return its caller
+    pub fn verify(&self, token: &str) -> Result<Principal> {
+        let data = decode::<Claims>(token, &self.key, &self.validation)
+            .map_err(|e| anyhow!("Invalid access token: {}", e))?;
+        Ok(Principal {
+            user_id: data.claims.sub,
+            role: data.claims.role,
+        })
+    }
+
+    /// Verify the value of an `Authorization: Bearer <token>` header
+    pub fn verify_header(&self, header: &str) -> Result<Principal> {
+        let token = header.strip_prefix("Bearer ")
+            .ok_or_else(|| anyhow!("Authorization header must use the Bearer scheme"))?;
+        self.verify(token.trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    fn token(secret: &str, role: &str, exp: i64) -> String {
+        let claims = serde_json::json!({ "sub": "user2", "role": role, "exp": exp });
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn test_verify_backend_token() {
+        let verifier = TokenVerifier::new("secret");
+        let exp = chrono::Utc::now().timestamp() + 600;
+
+        let principal = verifier.verify_header(&format!("Bearer {}", token("secret", "viewer", exp))).unwrap();
+        assert_eq!(principal.user_id, "user2");
+        assert!(principal.is_read_only());
+
+        assert!(verifier.verify(&token("other", "admin", exp)).is_err());
+        assert!(verifier.verify(&token("secret", "admin", exp - 7200)).is_err());
+        assert!(verifier.verify_header(&token("secret", "admin", exp)).is_err());
+    }
+}
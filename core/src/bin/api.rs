@@ -1,200 +1,314 @@
 use actix_cors::Cors;
-use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{delete, get, http::header, middleware, post, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use hegel::{
-    graph::{schema::MoleculeNode, neo4j::Neo4jClient},
-    metacognition::{llm::LLMClient, memory::MemorySystem},
-    processing::{evidence::{EvidenceProcessor, Evidence, EvidenceType}, 
+    graph::schema::MoleculeNode,
+    graph::similarity::{SimilarityRegistry, DEFAULT_METRIC},
+    graph::conflicts::{ConflictGraph, ConflictGraphFormat},
+    graph::stats::StatsCache,
+    graph::neighborhood::NeighborhoodOptions,
+    graph::pathways::coherence_view,
+    graph::rdf::{RdfFormat, RdfGraph, RdfOptions},
+    graph::store::{self as graph_store, GraphStore, MoleculeInteraction, StoreConfig},
+    graph::pathways::PathwayMembership,
+    search::{IndexedStore, SearchIndex, DEFAULT_SEARCH_LIMIT},
+    http_cache::{CachedResponse, ResponseCache},
+    metacognition::{llm::LLMClient, memory::MemorySystem, planner::{AcquisitionPlanner, PlannerOptions}},
+    processing::{evidence::{Evidence, EvidenceProcessingOptions, EvidenceProcessor, EvidenceType}, 
+                confidence_policy::ConfidencePolicy,
                 rectifier::EvidenceRectifier,
-                genomics::{GenomicsData, GenomicsProcessor},
-                mass_spec::{MassSpecData, MassSpecProcessor}},
+                reliability::ReliabilityTracker,
+                genomics::GenomicsProcessor,
+                mass_spec::{InstrumentProfile, MassSpecProcessingOptions, MassSpecProcessor},
+                versioning::{ConfidenceTrend, ConfidenceTrigger, VersionedEvidenceStore},
+                anomaly::{AnomalyDetector, QuarantineStore},
+                reevaluation::{ReevaluationOptions, ReevaluationScheduler},
+                pipeline::{AblationMode, IdentityPipeline},
+                fingerprint::PipelineFingerprint,
+                evidence_schema::EvidenceSchemaRegistry,
+                proposals::{ProposalStore, ProposedAdjustment, RectificationProposal, ReviewDecision},
+                time_budget::{AnalysisStage, BudgetClock, TimeBudget}},
+    identity::xref::XrefService,
+    cancellation::{self, CancellationToken},
+    auth::{Principal, TokenVerifier},
+    projects::{scoped_key, Access, ProjectRegistry, ProjectRole, DEFAULT_PROJECT},
+    curation::CurationStore,
+    webhooks::{Webhook, WebhookDispatcher, WebhookEvent, WebhookEventKind},
+    alerts::{AlertEngine, AlertLog, AlertObservation, AlertRule, StdoutSink, WebhookSink},
+    client::{PROJECT_HEADER, types::{
+        AnalysisRequest, RectificationRequest, SourceEvidence, AnalysisResponse, MoleculeAnalysis,
+        RectifiedEvidence, PathwayData, InteractionData, AnalysisMeta, MassSpecRequest,
+        AblationRequest, PlanRequest, IngestEvidenceRequest, IngestEvidenceResponse, SnapshotQuery, DiffQuery, ConfidenceHistoryQuery, ConfidenceHistoryResponse, CreateProjectRequest, ProjectMemberRequest,
+        RegisterWebhookRequest, DeliveriesQuery, CompareRequest, CompareResponse, SimilarityMetrics, OfflineStatus,
+        PathQuery, PathResponse, NeighborhoodQuery, RdfQuery, SearchQuery, SearchResponse, QuarantineQuery, ResolveQuarantineRequest,
+        CurationRequest, CurationStatus, ReviewQueueQuery, AlertsQuery, CreateAlertRuleRequest,
+        ProposalsQuery, ReviewProposalRequest, MoleculeInclude, MoleculeQueryRequest, MoleculeQueryResponse, MoleculeRecord,
+    }},
 };
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::{BTreeMap, HashMap, HashSet}, sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 
-// Data structures for API requests and responses
-#[derive(Debug, Serialize, Deserialize)]
-struct AnalysisRequest {
-    molecule_ids: Vec<String>,
-    evidence_type: String,
-    confidence_threshold: Option<f64>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct RectificationRequest {
-    evidence_data: HashMap<String, Vec<Evidence>>,
-    rectification_options: RectificationOptions,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct RectificationOptions {
-    use_ai_guidance: bool,
-    confidence_threshold: f64,
-    include_pathway_analysis: bool,
-    include_interactome_analysis: bool,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Evidence {
-    source: String,
-    data: serde_json::Value,
-    confidence: f64,
-}
+/// Prefix of Reactome stable identifiers, e.g. `R-HSA-71403`
+const REACTOME_ID_PREFIX: &str = "R-";
 
-#[derive(Debug, Serialize, Deserialize)]
-struct AnalysisResponse {
-    results: HashMap<String, MoleculeAnalysis>,
-    meta: AnalysisMeta,
-}
+/// Time allowed for a request when `HEGEL_API_REQUEST_TIMEOUT_SECONDS` is not set
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
 
-#[derive(Debug, Serialize, Deserialize)]
-struct MoleculeAnalysis {
-    molecule_id: String,
-    evidence_count: usize,
-    rectified_evidence: Vec<RectifiedEvidence>,
-    pathways: Vec<PathwayData>,
-    interactions: Vec<InteractionData>,
-    confidence_score: f64,
+// Shared application state
+struct AppState {
+    graph_store: Arc<dyn GraphStore>,
+    search_index: Arc<SearchIndex>,
+    llm_client: Arc<Mutex<LLMClient>>,
+    memory_system: Arc<Mutex<MemorySystem>>,
+    evidence_processor: Arc<Mutex<EvidenceProcessor>>,
+    evidence_rectifier: Arc<Mutex<EvidenceRectifier>>,
+    genomics_processor: Arc<Mutex<GenomicsProcessor>>,
+    mass_spec_processor: Arc<Mutex<MassSpecProcessor>>,
+    evidence_history: Arc<Mutex<VersionedEvidenceStore>>,
+    anomaly_detector: Arc<Mutex<AnomalyDetector>>,
+    quarantine: Arc<Mutex<QuarantineStore>>,
+    curation: Arc<Mutex<CurationStore>>,
+    proposals: Arc<Mutex<ProposalStore>>,
+    reliability: Arc<Mutex<ReliabilityTracker>>,
+    xref_service: Arc<XrefService>,
+    webhooks: Arc<WebhookDispatcher>,
+    alerts: Arc<AlertEngine>,
+    alert_log: AlertLog,
+    projects: Arc<Mutex<ProjectRegistry>>,
+    token_verifier: Arc<TokenVerifier>,
+    request_timeout: Duration,
+    evidence_schemas: Arc<EvidenceSchemaRegistry>,
+    project_stats: Arc<Mutex<StatsCache>>,
+    responses: Arc<Mutex<ResponseCache>>,
+    confidence_policy: Arc<ConfidencePolicy>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct RectifiedEvidence {
-    source: String,
-    original_confidence: f64,
-    rectified_confidence: f64,
-    data: serde_json::Value,
+/// Identity pipeline bounded by the server's confidence policy and weighting sources by their reliability
+async fn identity_pipeline(state: &AppState) -> IdentityPipeline {
+    IdentityPipeline::with_options(EvidenceProcessingOptions {
+        confidence_policy: state.confidence_policy.as_ref().clone(),
+        source_weights: state.reliability.lock().await.learned_weights(),
+        ..Default::default()
+    })
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct PathwayData {
-    pathway_id: String,
-    name: String,
-    molecules: Vec<String>,
-    confidence: f64,
+/// Learn source reliability from a reviewer's verdict on a molecule
+///
+/// The molecule's stored evidence is scored against the reviewed confidence,
+/// and the updated weights are handed to the shared processor and rectifier.
+async fn record_reliability(state: &AppState, project_id: &str, molecule_id: &str, reviewed_confidence: f64) {
+    let evidence = match state.graph_store.molecule_evidence(project_id, molecule_id).await {
+        Ok(evidence) => evidence,
+        Err(e) => {
+            warn!("Failed to read evidence of {} to learn source reliability: {}", molecule_id, e);
+            return;
+        }
+    };
+    
+    let weights = {
+        let mut reliability = state.reliability.lock().await;
+        if let Err(e) = reliability.record_verdict(&evidence, reviewed_confidence) {
+            warn!("Failed to record source reliability for {}: {}", molecule_id, e);
+        }
+        reliability.learned_weights()
+    };
+    state.evidence_processor.lock().await.set_source_weights(weights.clone());
+    state.evidence_rectifier.lock().await.set_source_weights(weights);
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct InteractionData {
-    source_molecule: String,
-    target_molecule: String,
-    interaction_type: String,
-    evidence_count: usize,
-    confidence: f64,
+/// Record evidence revisions under the project-scoped keys snapshots are queried with
+///
+/// The recorded copies carry scoped molecule and evidence IDs, so items of
+/// different projects never share a history.
+fn record_evidence_history(history: &mut VersionedEvidenceStore, project_id: &str, evidence: &[Evidence]) {
+    for item in evidence {
+        history.record_evidence_if_changed(Evidence {
+            id: scoped_key(project_id, &item.id),
+            molecule_id: scoped_key(project_id, &item.molecule_id),
+            ..item.clone()
+        });
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct AnalysisMeta {
-    timestamp: String,
-    version: String,
-    execution_time_ms: u64,
+/// Save the evidence history after recording, keeping the in-memory revisions if that fails
+fn save_evidence_history(history: &VersionedEvidenceStore) {
+    if let Err(e) = history.save() {
+        warn!("Failed to save evidence history: {}", e);
+    }
 }
 
-// New request structures for genomics and mass spec data
-#[derive(Debug, Serialize, Deserialize)]
-struct GenomicsRequest {
-    /// Molecule ID this data relates to
-    molecule_id: String,
+/// Identify the caller from their bearer token
+fn authenticate(req: &HttpRequest, state: &AppState) -> Result<Principal, HttpResponse> {
+    let header = match req.headers().get(header::AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        Some(header) => header,
+        None => {
+            return Err(HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Missing bearer token"
+            })));
+        }
+    };
     
-    /// The genomics data to process
-    data: GenomicsData,
+    state.token_verifier.verify_header(header).map_err(|e| {
+        HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": format!("{}", e)
+        }))
+    })
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct MassSpecRequest {
-    /// Molecule ID this data relates to
-    molecule_id: String,
+/// Identify the caller and check their access to the project named in the request
+///
+/// Requests without a project header operate on the default project.
+async fn authorize(req: &HttpRequest, state: &AppState, access: Access) -> Result<(Principal, String), HttpResponse> {
+    let principal = authenticate(req, state)?;
+    let project_id = req.headers().get(PROJECT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or(DEFAULT_PROJECT)
+        .to_string();
     
-    /// The mass spec data to process
-    data: MassSpecData,
+    authorize_project(state, &principal, &project_id, access).await?;
+    Ok((principal, project_id))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ProcessedDataResponse {
-    /// Molecule ID the results relate to
-    molecule_id: String,
-    
-    /// Evidence generated from the data
-    evidence: Vec<Evidence>,
-    
-    /// Overall confidence score
-    confidence_score: f64,
+/// Check a caller's access to a project, hiding projects they have no role in
+async fn authorize_project(state: &AppState, principal: &Principal, project_id: &str, access: Access) -> Result<(), HttpResponse> {
+    let projects = state.projects.lock().await;
     
-    /// Processing metadata
-    metadata: HashMap<String, serde_json::Value>,
+    if projects.get(project_id).and_then(|p| p.role_of(principal)).is_none() {
+        return Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Project not found: {}", project_id)
+        })));
+    }
+    projects.authorize(principal, project_id, access).map(|_| ()).map_err(|e| {
+        HttpResponse::Forbidden().json(serde_json::json!({
+            "error": format!("{}", e)
+        }))
+    })
 }
 
-// Shared application state
-struct AppState {
-    neo4j_client: Arc<Mutex<Neo4jClient>>,
-    llm_client: Arc<Mutex<LLMClient>>,
-    memory_system: Arc<Mutex<MemorySystem>>,
-    evidence_processor: Arc<Mutex<EvidenceProcessor>>,
-    evidence_rectifier: Arc<Mutex<EvidenceRectifier>>,
-    genomics_processor: Arc<Mutex<GenomicsProcessor>>,
-    mass_spec_processor: Arc<Mutex<MassSpecProcessor>>,
+/// Answer a read of project data from the response cache, computing it on a miss
+///
+/// The body's fingerprint is sent as its ETag, and a request whose
+/// `If-None-Match` names it gets an empty 304 instead of the body.
+async fn cached_json<T, F>(req: &HttpRequest, state: &AppState, project_id: &str, compute: F) -> HttpResponse
+where
+    T: serde::Serialize,
+    F: std::future::Future<Output = Result<T, HttpResponse>>,
+{
+    let request = req.uri().to_string();
+    let cached = state.responses.lock().await.get(project_id, &request).cloned();
+    let response = match cached {
+        Some(response) => response,
+        None => {
+            let result = match compute.await {
+                Ok(result) => result,
+                Err(response) => return response,
+            };
+            let response = match CachedResponse::from_json(&result) {
+                Ok(response) => response,
+                Err(e) => {
+                    error!("Failed to serialize response: {}", e);
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": format!("Serialization error: {}", e)
+                    }));
+                }
+            };
+            state.responses.lock().await.insert(project_id, &request, response.clone());
+            response
+        }
+    };
+    
+    let if_none_match = req.headers().get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok());
+    if if_none_match.is_some_and(|value| response.matches(value)) {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, response.etag))
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .finish();
+    }
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((header::ETAG, response.etag))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .body(response.body)
 }
 
 // API routes
 #[post("/api/analyze")]
 async fn analyze_evidence(
+    req: HttpRequest,
     data: web::Json<AnalysisRequest>,
     state: web::Data<AppState>,
 ) -> impl Responder {
     println!("Received analysis request: {:?}", data);
+    
+    let (_, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let time_budget = match data.time_budget_ms.map(TimeBudget::from_millis).transpose() {
+        Ok(budget) => budget,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("{}", e)
+            }));
+        }
+    };
+    let mut planned_stages = vec![AnalysisStage::Pathways, AnalysisStage::Interactions];
+    if data.conflict_graph_format.is_some() {
+        planned_stages.push(AnalysisStage::ConflictGraph);
+    }
 
     // Process the evidence using the Rust orchestrator
     let evidence_processor = state.evidence_processor.lock().await;
     let evidence_rectifier = state.evidence_rectifier.lock().await;
-    let neo4j_client = state.neo4j_client.lock().await;
 
     // Process evidence with the full implementation
     let start_time = std::time::Instant::now();
+    let cancel = cancellation::with_timeout(&CancellationToken::new(), state.request_timeout);
     let mut results = HashMap::new();
     
     for molecule_id in &data.molecule_ids {
+        if cancel.is_cancelled() {
+            warn!("Analysis timed out after {} of {} molecules", results.len(), data.molecule_ids.len());
+            break;
+        }
         info!("Processing evidence for molecule: {}", molecule_id);
+        let mut clock = BudgetClock::start(time_budget.as_ref(), &planned_stages);
         
-        // Fetch evidence from Neo4j
-        let evidence_fetch_query = format!(
-            "MATCH (e:Evidence)-[:RELATED_TO]->(m:Molecule {{id: $molecule_id}}) 
-             RETURN e.id as id, e.source as source, e.confidence as confidence, 
-             e.data as data, e.type as type"
-        );
-        
-        let params = serde_json::json!({
-            "molecule_id": molecule_id,
-        });
-        
-        let driver = neo4j_client.connect().await.map_err(|e| {
-            error!("Failed to connect to Neo4j: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Database connection error: {}", e)
-            }))
-        })?;
-        
-        let evidence_results = driver.run_query(&evidence_fetch_query, params).await.map_err(|e| {
+        // Fetch the stored evidence, linked to the molecule by SUPPORTS
+        let stored_evidence = state.graph_store.molecule_evidence(&project_id, molecule_id).await.map_err(|e| {
             error!("Failed to fetch evidence: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": format!("Evidence retrieval error: {}", e)
             }))
         })?;
         
-        // Convert to Evidence objects
+        // Convert to source evidence, holding back anything anomalous for review
         let mut evidences = Vec::new();
-        for result in evidence_results {
-            let id = result.get("id").and_then(|v| v.as_str()).unwrap_or("unknown");
-            let source = result.get("source").and_then(|v| v.as_str()).unwrap_or("unknown");
-            let confidence = result.get("confidence").and_then(|v| v.as_f64()).unwrap_or(0.5);
-            let data = result.get("data").unwrap_or(&serde_json::Value::Null);
+        let mut accepted = Vec::new();
+        let mut anomaly_detector = state.anomaly_detector.lock().await;
+        let mut quarantine = state.quarantine.lock().await;
+        for core_evidence in stored_evidence {
+            if quarantine.is_held(&project_id, &core_evidence.id) {
+                continue;
+            }
+            if !quarantine.is_released(&project_id, &core_evidence.id) {
+                let findings = anomaly_detector.inspect(&core_evidence);
+                if !findings.is_empty() {
+                    quarantine.quarantine(&project_id, core_evidence, findings);
+                    continue;
+                }
+            }
             
-            let evidence = Evidence {
-                source: source.to_string(),
-                data: data.clone(),
-                confidence,
+            accepted.push(core_evidence.clone());
+            let evidence = SourceEvidence {
+                source: core_evidence.source,
+                data: core_evidence.data,
+                confidence: core_evidence.confidence,
             };
             
             evidences.push(evidence);
         }
+        drop(quarantine);
+        drop(anomaly_detector);
         
         // Filter evidence by type if specified
         if let Some(evidence_type) = data.evidence_type.strip_prefix("type:") {
@@ -224,11 +338,17 @@ async fn analyze_evidence(
             })
             .collect::<Vec<_>>();
         
-        // Get pathway data
-        let pathways = get_molecule_pathways(&driver, molecule_id).await?;
-        
-        // Get interaction data
-        let interactions = get_molecule_interactions(&driver, molecule_id).await?;
+        // Get pathway and interaction data, unless the time budget has run short
+        let pathways = if clock.admit(AnalysisStage::Pathways) {
+            get_molecule_pathways(state.graph_store.as_ref(), &project_id, molecule_id).await?
+        } else {
+            Vec::new()
+        };
+        let interactions = if clock.admit(AnalysisStage::Interactions) {
+            get_molecule_interactions(state.graph_store.as_ref(), &project_id, molecule_id).await?
+        } else {
+            Vec::new()
+        };
         
         // Apply rectification if confidence_threshold was specified
         let rectified_evidences = if data.confidence_threshold.is_some() {
@@ -241,6 +361,7 @@ async fn analyze_evidence(
                         source: evidence.source.clone(),
                         original_confidence: evidence.confidence,
                         rectified_confidence: evidence.confidence,
+                        adjustment_reason: None,
                         data: evidence.data.clone(),
                     };
                     
@@ -269,6 +390,7 @@ async fn analyze_evidence(
                     source: evidence.source.clone(),
                     original_confidence: evidence.confidence,
                     rectified_confidence: evidence.confidence,
+                    adjustment_reason: None,
                     data: evidence.data.clone(),
                 })
                 .collect()
@@ -283,6 +405,97 @@ async fn analyze_evidence(
                 .sum::<f64>() / rectified_evidences.len() as f64
         };
         
+        // Build the optional conflict graph attachment
+        let mut conflict_count = 0;
+        let mut max_conflict_severity = None;
+        let conflict_graph = match &data.conflict_graph_format {
+            Some(format) => {
+                let format: ConflictGraphFormat = match format.parse() {
+                    Ok(format) => format,
+                    Err(e) => {
+                        return HttpResponse::BadRequest().json(serde_json::json!({
+                            "error": format!("{}", e)
+                        }));
+                    }
+                };
+                
+                if clock.admit(AnalysisStage::ConflictGraph) {
+                    let core_evidence = processed_evidences.iter()
+                        .enumerate()
+                        .map(|(idx, e)| hegel::processing::evidence::Evidence {
+                            id: format!("{}-{}", molecule_id, idx),
+                            molecule_id: molecule_id.clone(),
+                            evidence_type: EvidenceType::Other,
+                            source: e.source.clone(),
+                            confidence: e.confidence,
+                            data: e.data.clone(),
+                            metadata: HashMap::new(),
+                            timestamp: chrono::Utc::now(),
+                        })
+                        .collect();
+                    
+                    match evidence_processor.process_evidence(molecule_id, core_evidence).await {
+                        Ok(integrated) => {
+                            conflict_count = integrated.conflicts.len();
+                            max_conflict_severity = integrated.conflicts.iter().map(|c| c.severity).reduce(f64::max);
+                            if !integrated.conflicts.is_empty() {
+                                let webhooks = state.webhooks.clone();
+                                let event = WebhookEvent::ConflictDetected {
+                                    molecule_id: molecule_id.clone(),
+                                    conflicts: integrated.conflicts.clone(),
+                                };
+                                tokio::spawn(async move { webhooks.emit(event).await });
+                            }
+                            let graph = ConflictGraph::from_integrated(&integrated);
+                            Some(match format {
+                                ConflictGraphFormat::Dot => serde_json::Value::String(graph.to_dot()),
+                                ConflictGraphFormat::Cytoscape => graph.to_cytoscape(),
+                            })
+                        }
+                        Err(e) => {
+                            error!("Failed to build conflict graph: {}", e);
+                            None
+                        }
+                    }
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+        
+        // A curator's lock overrides the model; the model's view is kept for review
+        let (confidence_score, curator_assertion) = {
+            let mut curation = state.curation.lock().await;
+            let reported = curation.apply(&project_id, molecule_id, confidence_score, conflict_count);
+            (reported, curation.assertion(&project_id, molecule_id).cloned())
+        };
+        
+        // Record the conclusion so it can be queried historically
+        let (previous_confidence, confidence_trend) = {
+            let history_key = scoped_key(&project_id, molecule_id);
+            let mut history = state.evidence_history.lock().await;
+            let previous = history.confidence_history(&history_key).last().map(|r| r.confidence);
+            record_evidence_history(&mut history, &project_id, &accepted);
+            history.record_confidence(&history_key, confidence_score, ConfidenceTrigger::Analysis);
+            save_evidence_history(&history);
+            (previous, history.confidence_trend(&history_key))
+        };
+        if let Some(previous) = previous_confidence {
+            let webhooks = state.webhooks.clone();
+            let molecule_id = molecule_id.clone();
+            tokio::spawn(async move { webhooks.confidence_changed(&molecule_id, previous, confidence_score).await });
+        }
+        
+        state.alerts.observe(&AlertObservation {
+            project_id: project_id.clone(),
+            molecule_id: molecule_id.clone(),
+            confidence: confidence_score,
+            conflict_count,
+            max_conflict_severity,
+            evidence_count: rectified_evidences.len(),
+        }).await;
+        
         results.insert(
             molecule_id.clone(),
             MoleculeAnalysis {
@@ -292,18 +505,49 @@ async fn analyze_evidence(
                 pathways,
                 interactions,
                 confidence_score,
+                conflict_graph,
+                curator_assertion,
+                confidence_trend,
+                proposal_id: None,
+                skipped_stages: clock.into_skipped(),
             },
         );
     }
     
     let elapsed = start_time.elapsed().as_millis() as u64;
+    let cancelled = cancel.is_cancelled();
+    cancel.cancel();
+    
+    let webhooks = state.webhooks.clone();
+    let event = WebhookEvent::JobCompleted {
+        job_id: uuid::Uuid::new_v4().to_string(),
+        job_type: "analyze".to_string(),
+        success: !cancelled,
+        summary: serde_json::json!({
+            "molecule_ids": results.keys().collect::<Vec<_>>(),
+            "execution_time_ms": elapsed,
+        }),
+    };
+    tokio::spawn(async move { webhooks.emit(event).await });
+
+    let pipeline_fingerprint = evidence_processor.fingerprint()
+        .and_then(|f| f.with_parameters("request", &serde_json::json!({
+            "evidence_type": data.evidence_type,
+            "confidence_threshold": data.confidence_threshold,
+            "time_budget_ms": data.time_budget_ms,
+        })))
+        .map_err(|e| warn!("Failed to fingerprint the analysis pipeline: {}", e))
+        .ok();
 
     let response = AnalysisResponse {
         results,
         meta: AnalysisMeta {
             timestamp: chrono::Utc::now().to_rfc3339(),
-            version: "0.1.0".to_string(),
+            version: hegel::VERSION.to_string(),
             execution_time_ms: elapsed,
+            dry_run: false,
+            cancelled,
+            pipeline_fingerprint,
         },
     };
 
@@ -311,94 +555,79 @@ async fn analyze_evidence(
 }
 
 // Helper function to get pathway data for a molecule
-async fn get_molecule_pathways(driver: &Neo4jDriver, molecule_id: &str) -> Result<Vec<PathwayData>, HttpResponse> {
-    let pathway_query = format!(
-        "MATCH (m:Molecule {{id: $molecule_id}})-[:PART_OF]->(p:Pathway) 
-         MATCH (other:Molecule)-[:PART_OF]->(p) 
-         WITH p, COLLECT(other.id) as molecules 
-         RETURN p.id as pathway_id, p.name as name, molecules, p.confidence as confidence"
-    );
-    
-    let params = serde_json::json!({
-        "molecule_id": molecule_id,
-    });
-    
-    let pathway_results = driver.run_query(&pathway_query, params).await.map_err(|e| {
+async fn get_molecule_pathways(store: &dyn GraphStore, project_id: &str, molecule_id: &str) -> Result<Vec<PathwayData>, HttpResponse> {
+    let pathway_results = store.molecule_pathways(project_id, molecule_id).await.map_err(|e| {
         error!("Failed to fetch pathway data: {}", e);
         HttpResponse::InternalServerError().json(serde_json::json!({
             "error": format!("Pathway data retrieval error: {}", e)
         }))
     })?;
     
-    let mut pathways = Vec::new();
-    for result in pathway_results {
-        let pathway_id = result.get("pathway_id").and_then(|v| v.as_str()).unwrap_or("unknown");
-        let name = result.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown Pathway");
-        let confidence = result.get("confidence").and_then(|v| v.as_f64()).unwrap_or(0.5);
-        
-        let molecules = if let Some(mol_arr) = result.get("molecules").and_then(|v| v.as_array()) {
-            mol_arr.iter()
-                .filter_map(|m| m.as_str().map(|s| s.to_string()))
-                .collect()
-        } else {
-            Vec::new()
-        };
-        
-        pathways.push(PathwayData {
-            pathway_id: pathway_id.to_string(),
-            name: name.to_string(),
-            molecules,
-            confidence,
-        });
+    Ok(pathway_results.into_iter().map(pathway_data).collect())
+}
+
+/// Pathway of a molecule as returned by the API
+fn pathway_data(pathway: PathwayMembership) -> PathwayData {
+    PathwayData {
+        pathway_id: pathway.pathway_id,
+        name: pathway.name.unwrap_or_else(|| "Unknown Pathway".to_string()),
+        molecules: pathway.members,
+        confidence: 0.5,
     }
-    
-    Ok(pathways)
 }
 
 // Helper function to get interaction data for a molecule
-async fn get_molecule_interactions(driver: &Neo4jDriver, molecule_id: &str) -> Result<Vec<InteractionData>, HttpResponse> {
-    let interaction_query = format!(
-        "MATCH (m:Molecule {{id: $molecule_id}})-[r]->(target:Molecule) 
-         RETURN target.id as target_id, type(r) as type, target.name as target_name, 
-         r.evidence_count as evidence_count, r.confidence as confidence"
-    );
-    
-    let params = serde_json::json!({
-        "molecule_id": molecule_id,
-    });
-    
-    let interaction_results = driver.run_query(&interaction_query, params).await.map_err(|e| {
+async fn get_molecule_interactions(store: &dyn GraphStore, project_id: &str, molecule_id: &str) -> Result<Vec<InteractionData>, HttpResponse> {
+    let interaction_results = store.molecule_interactions(project_id, molecule_id).await.map_err(|e| {
         error!("Failed to fetch interaction data: {}", e);
         HttpResponse::InternalServerError().json(serde_json::json!({
             "error": format!("Interaction data retrieval error: {}", e)
         }))
     })?;
     
-    let mut interactions = Vec::new();
-    for result in interaction_results {
-        let target_id = result.get("target_id").and_then(|v| v.as_str()).unwrap_or("unknown");
-        let interaction_type = result.get("type").and_then(|v| v.as_str()).unwrap_or("interacts_with");
-        let evidence_count = result.get("evidence_count").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
-        let confidence = result.get("confidence").and_then(|v| v.as_f64()).unwrap_or(0.5);
-        
-        interactions.push(InteractionData {
-            source_molecule: molecule_id.to_string(),
-            target_molecule: target_id.to_string(),
-            interaction_type: interaction_type.to_string(),
-            evidence_count,
-            confidence,
-        });
+    Ok(interaction_results.into_iter().map(interaction_data).collect())
+}
+
+/// Interaction of a molecule as returned by the API
+fn interaction_data(interaction: MoleculeInteraction) -> InteractionData {
+    InteractionData {
+        source_molecule: interaction.source_molecule,
+        target_molecule: interaction.target_molecule,
+        interaction_type: interaction.interaction_type,
+        evidence_count: interaction.evidence_count,
+        confidence: interaction.confidence.unwrap_or(0.5),
     }
-    
-    Ok(interactions)
 }
 
 #[post("/api/rectify")]
 async fn rectify_evidence(
+    req: HttpRequest,
     data: web::Json<RectificationRequest>,
     state: web::Data<AppState>,
 ) -> impl Responder {
     println!("Received rectification request: {:?}", data);
+    
+    // A dry run only needs to read the project; proposals are stored, so they need write access
+    let dry_run = data.dry_run || data.propose;
+    let access = if dry_run && !data.propose { Access::Read } else { Access::Write };
+    let (principal, project_id) = match authorize(&req, &state, access).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+
+    let time_budget = match data.time_budget_ms.map(TimeBudget::from_millis).transpose() {
+        Ok(budget) => budget,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("{}", e)
+            }));
+        }
+    };
+    let planned_stages: Vec<AnalysisStage> = [
+        (data.rectification_options.use_ai_guidance, AnalysisStage::Llm),
+        (data.rectification_options.include_pathway_analysis, AnalysisStage::Pathways),
+        (data.rectification_options.include_interactome_analysis, AnalysisStage::Interactions),
+    ].into_iter().filter(|(enabled, _)| *enabled).map(|(_, stage)| stage).collect();
 
     // Use the AI-guided evidence rectifier
     let evidence_rectifier = state.evidence_rectifier.lock().await;
@@ -406,10 +635,18 @@ async fn rectify_evidence(
     let memory_system = state.memory_system.lock().await;
 
     let start_time = std::time::Instant::now();
+    // A timed-out request stops at the LLM call in progress; only finished molecules are reported
+    let cancel = cancellation::with_timeout(&CancellationToken::new(), state.request_timeout);
+    let mut cancelled = false;
     let mut results = HashMap::new();
     
-    for (molecule_id, evidences) in &data.evidence_data {
+    'molecules: for (molecule_id, evidences) in &data.evidence_data {
+        if cancel.is_cancelled() {
+            cancelled = true;
+            break;
+        }
         info!("Rectifying evidence for molecule: {}", molecule_id);
+        let mut clock = BudgetClock::start(time_budget.as_ref(), &planned_stages);
         
         let mut rectified_evidences = Vec::new();
         let mut all_explanations = Vec::new();
@@ -419,21 +656,17 @@ async fn rectify_evidence(
                             data.rectification_options.include_interactome_analysis {
             let mut context = serde_json::Map::new();
             
-            // Connect to Neo4j
-            let neo4j_client = state.neo4j_client.lock().await;
-            if let Ok(driver) = neo4j_client.connect().await {
-                // Get pathway data if requested
-                if data.rectification_options.include_pathway_analysis {
-                    if let Ok(pathways) = get_molecule_pathways(&driver, molecule_id).await {
-                        context.insert("pathways".to_string(), serde_json::to_value(pathways).unwrap_or_default());
-                    }
+            // Get pathway data if requested
+            if data.rectification_options.include_pathway_analysis && clock.admit(AnalysisStage::Pathways) {
+                if let Ok(pathways) = get_molecule_pathways(state.graph_store.as_ref(), &project_id, molecule_id).await {
+                    context.insert("pathways".to_string(), serde_json::to_value(pathways).unwrap_or_default());
                 }
-                
-                // Get interactome data if requested
-                if data.rectification_options.include_interactome_analysis {
-                    if let Ok(interactions) = get_molecule_interactions(&driver, molecule_id).await {
-                        context.insert("interactions".to_string(), serde_json::to_value(interactions).unwrap_or_default());
-                    }
+            }
+            
+            // Get interactome data if requested
+            if data.rectification_options.include_interactome_analysis && clock.admit(AnalysisStage::Interactions) {
+                if let Ok(interactions) = get_molecule_interactions(state.graph_store.as_ref(), &project_id, molecule_id).await {
+                    context.insert("interactions".to_string(), serde_json::to_value(interactions).unwrap_or_default());
                 }
             }
             
@@ -442,12 +675,14 @@ async fn rectify_evidence(
             serde_json::Value::Null
         };
         
-        // Process each evidence with or without AI guidance
+        // Process each evidence with or without AI guidance; without time for
+        // the LLM, the rule-based rectification is used instead
+        let use_ai_guidance = data.rectification_options.use_ai_guidance && clock.admit(AnalysisStage::Llm);
         for evidence in evidences {
             let mut rectified_confidence = evidence.confidence;
             let mut explanation = String::new();
             
-            if data.rectification_options.use_ai_guidance {
+            if use_ai_guidance {
                 // Use LLM for guidance on rectification
                 let prompt = format!(
                     "Analyze the following molecular evidence for '{}' with original confidence {:.2}:\n\n{}\n\n",
@@ -467,7 +702,15 @@ async fn rectify_evidence(
                 };
                 
                 // Call LLM service for guidance
-                if let Ok(llm_response) = llm_client.query(&prompt).await {
+                let llm_result = match cancellation::run(&cancel, "LLM rectification", async { Ok(llm_client.query(&prompt).await) }).await {
+                    Ok(result) => result,
+                    Err(e) => {
+                        warn!("Rectification of molecule {} stopped: {}", molecule_id, e);
+                        cancelled = true;
+                        break 'molecules;
+                    }
+                };
+                if let Ok(llm_response) = llm_result {
                     // Parse the response - in a real implementation this would be more robust
                     if let Some(score_str) = llm_response.response.split_whitespace()
                         .find(|s| s.parse::<f64>().is_ok()) {
@@ -497,16 +740,18 @@ async fn rectify_evidence(
                     }
                     
                     // Record decision in memory system
-                    let _ = memory_system.record_decision(
-                        "evidence_rectification",
-                        serde_json::json!({
-                            "molecule_id": molecule_id,
-                            "evidence_source": evidence.source,
-                            "original_confidence": evidence.confidence,
-                            "rectified_confidence": rectified_confidence,
-                            "reasoning": explanation.clone(),
-                        }),
-                    ).await;
+                    if !dry_run {
+                        let _ = memory_system.record_decision(
+                            "evidence_rectification",
+                            serde_json::json!({
+                                "molecule_id": molecule_id,
+                                "evidence_source": evidence.source,
+                                "original_confidence": evidence.confidence,
+                                "rectified_confidence": rectified_confidence,
+                                "reasoning": explanation.clone(),
+                            }),
+                        ).await;
+                    }
                 } else {
                     // LLM call failed, fall back to rule-based rectification
                     let factor = match evidence.source.to_lowercase().as_str() {
@@ -546,6 +791,7 @@ async fn rectify_evidence(
                 source: evidence.source.clone(),
                 original_confidence: evidence.confidence,
                 rectified_confidence,
+                adjustment_reason: (!explanation.is_empty()).then(|| explanation.clone()),
                 data: evidence.data.clone(),
             });
             
@@ -590,6 +836,70 @@ async fn rectify_evidence(
                 .sum::<f64>() / rectified_evidences.len() as f64
         };
         
+        // A dry run reports what a curator's lock would make of the proposal
+        // without recording the model's assessment
+        let (confidence_score, curator_assertion) = {
+            let mut curation = state.curation.lock().await;
+            let reported = if dry_run {
+                curation.assertion(&project_id, molecule_id).map(|a| a.confidence).unwrap_or(confidence_score)
+            } else {
+                curation.apply(&project_id, molecule_id, confidence_score, 0)
+            };
+            (reported, curation.assertion(&project_id, molecule_id).cloned())
+        };
+        
+        // Record the conclusion so it can be queried historically
+        let history_key = scoped_key(&project_id, molecule_id);
+        let confidence_trend = if dry_run {
+            state.evidence_history.lock().await.confidence_trend(&history_key)
+        } else {
+            let (previous_confidence, confidence_trend) = {
+                let mut history = state.evidence_history.lock().await;
+                let previous = history.confidence_history(&history_key).last().map(|r| r.confidence);
+                history.record_confidence(&history_key, confidence_score, ConfidenceTrigger::Rectification);
+                save_evidence_history(&history);
+                (previous, history.confidence_trend(&history_key))
+            };
+            if let Some(previous) = previous_confidence {
+                let webhooks = state.webhooks.clone();
+                let molecule_id = molecule_id.clone();
+                tokio::spawn(async move { webhooks.confidence_changed(&molecule_id, previous, confidence_score).await });
+            }
+            
+            state.alerts.observe(&AlertObservation {
+                project_id: project_id.clone(),
+                molecule_id: molecule_id.clone(),
+                confidence: confidence_score,
+                conflict_count: 0,
+                max_conflict_severity: None,
+                evidence_count: rectified_evidences.len(),
+            }).await;
+            confidence_trend
+        };
+        
+        // Keep the adjustments for review instead of applying them
+        let proposal_id = if data.propose {
+            let adjustments = rectified_evidences.iter()
+                .map(|e| ProposedAdjustment::new(&e.source, e.original_confidence, e.rectified_confidence, e.adjustment_reason.clone()))
+                .collect();
+            let proposal = RectificationProposal::new(&project_id, molecule_id, adjustments, &principal.user_id);
+            let stored = match state.proposals.lock().await.insert(proposal) {
+                Ok(stored) => stored.clone(),
+                Err(e) => {
+                    error!("Failed to store rectification proposal: {}", e);
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": format!("Proposal storage error: {}", e)
+                    }));
+                }
+            };
+            if let Err(e) = stored.record_trail(&memory_system, "proposed", &principal.user_id) {
+                warn!("Failed to record decision trail of proposal {}: {}", stored.id, e);
+            }
+            Some(stored.id)
+        } else {
+            None
+        };
+        
         results.insert(
             molecule_id.clone(),
             MoleculeAnalysis {
@@ -599,159 +909,228 @@ async fn rectify_evidence(
                 pathways: Vec::new(), // We don't return pathways in rectification response
                 interactions: Vec::new(), // We don't return interactions in rectification response
                 confidence_score,
+                conflict_graph: None,
+                curator_assertion,
+                confidence_trend,
+                proposal_id,
+                skipped_stages: clock.into_skipped(),
             },
         );
     }
     
     let elapsed = start_time.elapsed().as_millis() as u64;
+    cancel.cancel();
+    if cancelled {
+        warn!("Rectification timed out after {} of {} molecules", results.len(), data.evidence_data.len());
+    }
+    
+    if !dry_run && !cancelled {
+        let webhooks = state.webhooks.clone();
+        let event = WebhookEvent::JobCompleted {
+            job_id: uuid::Uuid::new_v4().to_string(),
+            job_type: "rectify".to_string(),
+            success: true,
+            summary: serde_json::json!({
+                "molecule_ids": results.keys().collect::<Vec<_>>(),
+                "execution_time_ms": elapsed,
+            }),
+        };
+        tokio::spawn(async move { webhooks.emit(event).await });
+    }
+    
+    let pipeline_fingerprint = PipelineFingerprint::new()
+        .with_parameters("rectification", evidence_rectifier.options())
+        .and_then(|f| f.with_parameters("request", &data.rectification_options))
+        .map_err(|e| warn!("Failed to fingerprint the rectification pipeline: {}", e))
+        .ok();
     
     let response = AnalysisResponse {
         results,
         meta: AnalysisMeta {
             timestamp: chrono::Utc::now().to_rfc3339(),
-            version: "0.1.0".to_string(),
+            version: hegel::VERSION.to_string(),
             execution_time_ms: elapsed,
+            dry_run,
+            cancelled,
+            pipeline_fingerprint,
         },
     };
 
     HttpResponse::Ok().json(response)
 }
 
-#[get("/api/reactome/pathways/{molecule_id}")]
-async fn get_reactome_pathways(
+#[get("/api/rectify/proposals")]
+async fn list_proposals(req: HttpRequest, query: web::Query<ProposalsQuery>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    let proposals = state.proposals.lock().await;
+    let items: Vec<_> = proposals.list(&project_id, query.status).into_iter().cloned().collect();
+    HttpResponse::Ok().json(items)
+}
+
+#[get("/api/rectify/proposals/{id}")]
+async fn get_proposal(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    let proposal_id = path.into_inner();
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    match state.proposals.lock().await.get(&project_id, &proposal_id) {
+        Some(proposal) => HttpResponse::Ok().json(proposal),
+        None => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Unknown rectification proposal: {}", proposal_id)
+        })),
+    }
+}
+
+#[post("/api/rectify/proposals/{id}/review")]
+async fn review_proposal(
+    req: HttpRequest,
     path: web::Path<String>,
+    data: web::Json<ReviewProposalRequest>,
     state: web::Data<AppState>,
 ) -> impl Responder {
-    let molecule_id = path.into_inner();
-    println!("Getting reactome pathways for molecule: {}", molecule_id);
-
-    // Query Neo4j for reactome pathways
-    let neo4j_client = state.neo4j_client.lock().await;
+    let proposal_id = path.into_inner();
+    let (principal, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
     
-    // Connect to Neo4j
-    let driver = match neo4j_client.connect().await {
-        Ok(driver) => driver,
-        Err(e) => {
-            error!("Failed to connect to Neo4j: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Database connection error: {}", e)
-            }));
+    let reviewed = {
+        let mut proposals = state.proposals.lock().await;
+        match proposals.review(&project_id, &proposal_id, data.adjustments.as_deref(), data.decision,
+                               &principal.user_id, data.note.clone()) {
+            Ok(proposal) => proposal.clone(),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("{}", e)
+                }));
+            }
         }
     };
     
-    // Query for Reactome pathways
-    let query = format!(
-        "MATCH (m:Molecule {{id: $molecule_id}})-[:PART_OF]->(p:Pathway) 
-         WHERE p.database = 'reactome' 
-         MATCH (other:Molecule)-[:PART_OF]->(p) 
-         WITH p, COLLECT(other.id) as molecules 
-         RETURN p.id as pathway_id, p.name as name, molecules, p.confidence as confidence"
-    );
-    
-    let params = serde_json::json!({
-        "molecule_id": molecule_id,
-    });
+    let action = match data.decision {
+        ReviewDecision::Approve => "approved",
+        ReviewDecision::Reject => "rejected",
+    };
+    if let Err(e) = reviewed.record_trail(&*state.memory_system.lock().await, action, &principal.user_id) {
+        warn!("Failed to record decision trail of proposal {}: {}", reviewed.id, e);
+    }
+    HttpResponse::Ok().json(reviewed)
+}
+
+/// Apply the approved adjustments of a reviewed proposal
+///
+/// The resulting confidence is recorded like a rectification: it passes the
+/// curator's lock, enters the confidence history and is checked by webhooks
+/// and alerting rules.
+#[post("/api/rectify/proposals/{id}/apply")]
+async fn apply_proposal(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    let proposal_id = path.into_inner();
+    let (principal, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
     
-    let results = match driver.run_query(&query, params).await {
-        Ok(results) => results,
-        Err(e) => {
-            error!("Failed to fetch Reactome pathways: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Pathway data retrieval error: {}", e)
-            }));
+    let applied = {
+        let mut proposals = state.proposals.lock().await;
+        match proposals.apply(&project_id, &proposal_id, &principal.user_id) {
+            Ok(proposal) => proposal.clone(),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("{}", e)
+                }));
+            }
         }
     };
+    let molecule_id = applied.molecule_id.clone();
+    record_reliability(&state, &project_id, &molecule_id, applied.applied_confidence.unwrap_or(0.0)).await;
     
-    // Parse the results
-    let pathways = results.iter().map(|row| {
-        let pathway_id = row.get("pathway_id").and_then(|v| v.as_str()).unwrap_or("unknown");
-        let name = row.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown Pathway");
-        let confidence = row.get("confidence").and_then(|v| v.as_f64()).unwrap_or(0.5);
-        
-        let molecules = if let Some(mol_arr) = row.get("molecules").and_then(|v| v.as_array()) {
-            mol_arr.iter()
-                .filter_map(|m| m.as_str().map(|s| s.to_string()))
-                .collect()
-        } else {
-            Vec::new()
-        };
-        
-        PathwayData {
-            pathway_id: pathway_id.to_string(),
-            name: name.to_string(),
-            molecules,
-            confidence,
-        }
-    }).collect::<Vec<_>>();
+    let confidence_score = state.curation.lock().await
+        .apply(&project_id, &molecule_id, applied.applied_confidence.unwrap_or(0.0), 0);
+    let previous_confidence = {
+        let history_key = scoped_key(&project_id, &molecule_id);
+        let mut history = state.evidence_history.lock().await;
+        let previous = history.confidence_history(&history_key).last().map(|r| r.confidence);
+        history.record_confidence(&history_key, confidence_score, ConfidenceTrigger::Rectification);
+        save_evidence_history(&history);
+        previous
+    };
+    if let Some(previous) = previous_confidence {
+        let webhooks = state.webhooks.clone();
+        let molecule_id = molecule_id.clone();
+        tokio::spawn(async move { webhooks.confidence_changed(&molecule_id, previous, confidence_score).await });
+    }
+    state.alerts.observe(&AlertObservation {
+        project_id: project_id.clone(),
+        molecule_id: molecule_id.clone(),
+        confidence: confidence_score,
+        conflict_count: 0,
+        max_conflict_severity: None,
+        evidence_count: applied.adjustments.len(),
+    }).await;
+    
+    if let Err(e) = applied.record_trail(&*state.memory_system.lock().await, "applied", &principal.user_id) {
+        warn!("Failed to record decision trail of proposal {}: {}", applied.id, e);
+    }
+    HttpResponse::Ok().json(applied)
+}
+
+#[get("/api/reactome/pathways/{molecule_id}")]
+async fn get_reactome_pathways(
+    req: HttpRequest,
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let molecule_id = path.into_inner();
+    println!("Getting reactome pathways for molecule: {}", molecule_id);
 
-    HttpResponse::Ok().json(pathways)
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+
+    // Pathways of the molecule, keeping those with a Reactome stable ID
+    match get_molecule_pathways(state.graph_store.as_ref(), &project_id, &molecule_id).await {
+        Ok(pathways) => {
+            let pathways: Vec<PathwayData> = pathways.into_iter()
+                .filter(|pathway| pathway.pathway_id.starts_with(REACTOME_ID_PREFIX))
+                .collect();
+            HttpResponse::Ok().json(pathways)
+        }
+        Err(response) => response,
+    }
 }
 
 #[get("/api/interactome/{molecule_id}")]
-async fn get_interactome(path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+async fn get_interactome(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
     let molecule_id = path.into_inner();
     println!("Getting interactome data for molecule: {}", molecule_id);
 
-    // Query Neo4j for interactome data
-    let neo4j_client = state.neo4j_client.lock().await;
-    
-    // Connect to Neo4j
-    let driver = match neo4j_client.connect().await {
-        Ok(driver) => driver,
-        Err(e) => {
-            error!("Failed to connect to Neo4j: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Database connection error: {}", e)
-            }));
-        }
-    };
-    
-    // Query for interactions - both outgoing and incoming
-    let query = format!(
-        "MATCH (m:Molecule {{id: $molecule_id}})-[r]->(target:Molecule) 
-         RETURN target.id as target_id, type(r) as type, r.evidence_count as evidence_count, r.confidence as confidence
-         UNION
-         MATCH (source:Molecule)-[r]->(m:Molecule {{id: $molecule_id}}) 
-         RETURN source.id as target_id, type(r) as type, r.evidence_count as evidence_count, r.confidence as confidence"
-    );
-    
-    let params = serde_json::json!({
-        "molecule_id": molecule_id,
-    });
-    
-    let results = match driver.run_query(&query, params).await {
-        Ok(results) => results,
-        Err(e) => {
-            error!("Failed to fetch interactome data: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Interactome data retrieval error: {}", e)
-            }));
-        }
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
     };
-    
-    // Parse the results
-    let interactions = results.iter().map(|row| {
-        let target_id = row.get("target_id").and_then(|v| v.as_str()).unwrap_or("unknown");
-        let interaction_type = row.get("type").and_then(|v| v.as_str()).unwrap_or("interacts_with");
-        let evidence_count = row.get("evidence_count").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
-        let confidence = row.get("confidence").and_then(|v| v.as_f64()).unwrap_or(0.5);
-        
-        InteractionData {
-            source_molecule: molecule_id.clone(),
-            target_molecule: target_id.to_string(),
-            interaction_type: interaction_type.to_string(),
-            evidence_count,
-            confidence,
-        }
-    }).collect::<Vec<_>>();
 
-    HttpResponse::Ok().json(interactions)
+    match get_molecule_interactions(state.graph_store.as_ref(), &project_id, &molecule_id).await {
+        Ok(interactions) => HttpResponse::Ok().json(interactions),
+        Err(response) => response,
+    }
 }
 
 #[get("/api/genomics/analysis")]
-async fn get_genomics_analysis(state: web::Data<AppState>) -> impl Responder {
+async fn get_genomics_analysis(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
     println!("Getting genomics analysis results");
 
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+
     // Get the genomics processor
     let genomics_processor = state.genomics_processor.lock().await;
     
@@ -766,61 +1145,27 @@ async fn get_genomics_analysis(state: web::Data<AppState>) -> impl Responder {
         }
     };
     
-    // Query the Neo4j database for additional genomics insights
-    let neo4j_client = state.neo4j_client.lock().await;
-    
-    let driver = match neo4j_client.connect().await {
-        Ok(driver) => driver,
-        Err(e) => {
-            // We can still return the summary without the Neo4j data
-            warn!("Failed to connect to Neo4j for genomics network analysis: {}", e);
-            return HttpResponse::Ok().json(serde_json::json!({
-                "genome_scoring": analysis_summary,
-                "network_analysis": {
-                    "status": "unavailable",
-                    "error": format!("Database connection error: {}", e)
-                }
-            }));
-        }
-    };
-    
-    // Query for network analysis
-    let network_query = format!(
-        "MATCH (g:Gene)-[:ASSOCIATED_WITH]->(p:Phenotype) 
-         WITH g, COUNT(p) as phenotype_count 
-         ORDER BY phenotype_count DESC LIMIT 20 
-         RETURN g.id as gene_id, g.name as gene_name, phenotype_count"
-    );
-    
-    let network_results = match driver.run_query(&network_query, serde_json::json!({})).await {
-        Ok(results) => {
-            // Process network results
-            let gene_phenotype_counts = results.iter().map(|row| {
-                let gene_id = row.get("gene_id").and_then(|v| v.as_str()).unwrap_or("unknown");
-                let gene_name = row.get("gene_name").and_then(|v| v.as_str()).unwrap_or("Unknown");
-                let phenotype_count = row.get("phenotype_count").and_then(|v| v.as_u64()).unwrap_or(0);
-                
-                (gene_id.to_string(), gene_name.to_string(), phenotype_count)
-            }).collect::<Vec<_>>();
-            
+    // Query the project's graph for additional genomics insights
+    let network_results = match state.graph_store.gene_phenotypes(&project_id, 20).await {
+        Ok(genes) => {
             // Calculate centrality measures
             let mut centrality = serde_json::Map::new();
-            for (gene_id, gene_name, count) in &gene_phenotype_counts {
+            for gene in &genes {
                 // Normalize the centrality score between 0 and 1
-                let score = (*count as f64) / 100.0;  // Assuming 100 is the max possible connections
-                centrality.insert(gene_id.clone(), serde_json::json!(score.min(0.99)));
+                let score = gene.phenotype_count as f64 / 100.0;  // Assuming 100 is the max possible connections
+                centrality.insert(gene.gene_id.clone(), serde_json::json!(score.min(0.99)));
             }
             
             // Generate community clusters (simplified)
             let mut communities = serde_json::Map::new();
-            if !gene_phenotype_counts.is_empty() {
-                let num_communities = std::cmp::min(5, gene_phenotype_counts.len() / 4 + 1);
+            if !genes.is_empty() {
+                let num_communities = std::cmp::min(5, genes.len() / 4 + 1);
                 
                 for i in 0..num_communities {
-                    let community_genes = gene_phenotype_counts.iter()
+                    let community_genes = genes.iter()
                         .skip(i)
                         .step_by(num_communities)
-                        .map(|(id, _, _)| serde_json::json!(id))
+                        .map(|gene| serde_json::json!(gene.gene_id))
                         .collect::<Vec<_>>();
                     
                     communities.insert(format!("community{}", i+1), serde_json::json!(community_genes));
@@ -831,14 +1176,15 @@ async fn get_genomics_analysis(state: web::Data<AppState>) -> impl Responder {
                 "centrality": centrality,
                 "communities": communities,
                 "summary": {
-                    "num_nodes": gene_phenotype_counts.len(),
-                    "num_edges": gene_phenotype_counts.iter().map(|(_, _, c)| c).sum::<u64>(),
+                    "num_nodes": genes.len(),
+                    "num_edges": genes.iter().map(|gene| gene.phenotype_count).sum::<usize>(),
                     "num_communities": communities.len()
                 }
             })
         },
         Err(e) => {
-            warn!("Failed to fetch network analysis: {}", e);
+            // We can still return the summary without the network data
+            warn!("Failed to fetch network analysis for project {}: {}", project_id, e);
             serde_json::json!({
                 "status": "error",
                 "error": format!("Network analysis error: {}", e)
@@ -856,9 +1202,13 @@ async fn get_genomics_analysis(state: web::Data<AppState>) -> impl Responder {
 }
 
 #[get("/api/mass-spec/analysis")]
-async fn get_mass_spec_analysis(state: web::Data<AppState>) -> impl Responder {
+async fn get_mass_spec_analysis(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
     println!("Getting mass spec analysis results");
 
+    if let Err(response) = authorize(&req, &state, Access::Read).await {
+        return response;
+    }
+
     // Get the mass spec processor
     let mass_spec_processor = state.mass_spec_processor.lock().await;
     
@@ -902,82 +1252,1000 @@ async fn get_mass_spec_analysis(state: web::Data<AppState>) -> impl Responder {
     HttpResponse::Ok().json(response)
 }
 
+#[post("/api/mass-spec/process")]
+async fn process_mass_spec(req: HttpRequest, data: web::Json<MassSpecRequest>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = authorize(&req, &state, Access::Read).await {
+        return response;
+    }
+    let results = match &data.profile {
+        Some(profile) => match MassSpecProcessor::with_profile(profile) {
+            Ok(processor) => processor.process(&data.molecule_id, &data.data),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("Invalid instrument profile: {}", e)
+                }));
+            }
+        },
+        None => state.mass_spec_processor.lock().await.process(&data.molecule_id, &data.data),
+    };
+    
+    match results {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Mass spec processing error: {}", e)
+        })),
+    }
+}
+
+#[get("/api/mass-spec/profiles")]
+async fn list_mass_spec_profiles() -> impl Responder {
+    let profiles: serde_json::Map<String, serde_json::Value> = InstrumentProfile::ALL.iter()
+        .map(|profile| {
+            let options = MassSpecProcessingOptions::for_profile(*profile);
+            (profile.to_string(), serde_json::to_value(options).unwrap_or_default())
+        })
+        .collect();
+    
+    HttpResponse::Ok().json(profiles)
+}
+
 #[get("/api/molecules/{id}")]
-async fn get_molecule_data(path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+async fn get_molecule_data(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
     let molecule_id = path.into_inner();
     println!("Getting molecule data for: {}", molecule_id);
 
-    // Query Neo4j for molecule data
-    let neo4j_client = state.neo4j_client.lock().await;
-    
-    let driver = match neo4j_client.connect().await {
-        Ok(driver) => driver,
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+
+    let molecule = match state.graph_store.get_molecule(&project_id, &molecule_id).await {
+        Ok(Some(molecule)) => molecule,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("Molecule not found: {}", molecule_id)
+            }));
+        }
         Err(e) => {
-            error!("Failed to connect to Neo4j: {}", e);
+            error!("Failed to fetch molecule data: {}", e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Database connection error: {}", e)
+                "error": format!("Molecule data retrieval error: {}", e)
             }));
         }
     };
     
-    // Query for molecule details
-    let query = format!(
-        "MATCH (m:Molecule {{id: $molecule_id}}) 
-         OPTIONAL MATCH (m)-[:HAS_ALIAS]->(a:Alias) 
-         WITH m, COLLECT(a.name) as aliases 
-         RETURN m.id as id, m.name as name, m.type as type, m.description as description, 
-                m.properties as properties, aliases"
-    );
+    let text = |key: &str| molecule.get_property(key).and_then(|v| v.as_str());
+    let mol_type = text("type").unwrap_or("unknown");
+    let description = text("description").unwrap_or("No description available");
+    let aliases = match molecule.get_property("aliases") {
+        Some(serde_json::Value::Array(arr)) => arr.clone(),
+        _ => vec![],
+    };
+    
+    // External IDs stored on the node, completed by cross-reference resolution
+    let mut external_ids = molecule.external_ids.clone();
+    match state.xref_service.resolve_raw(&molecule.id).await {
+        Ok(xrefs) => {
+            for (system, external_id) in xrefs.external_ids {
+                external_ids.entry(system).or_insert(external_id);
+            }
+        }
+        Err(e) => debug!("No cross-references resolved for {}: {}", molecule.id, e),
+    }
+    
+    // Create molecule data response
+    let molecule_data = serde_json::json!({
+        "id": &molecule.id,
+        "name": &molecule.name,
+        "type": mol_type,
+        "description": description,
+        "properties": &molecule.properties,
+        "aliases": aliases,
+        "external_ids": external_ids
+    });
+
+    HttpResponse::Ok().json(molecule_data)
+}
+
+/// Most molecules a bulk query may name
+const MAX_QUERY_MOLECULES: usize = 100;
+
+/// Evidence items per molecule when a bulk query sets no limit
+const DEFAULT_QUERY_EVIDENCE_LIMIT: usize = 50;
+
+#[post("/api/molecules/query")]
+async fn query_molecules(req: HttpRequest, data: web::Json<MoleculeQueryRequest>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    if data.ids.len() > MAX_QUERY_MOLECULES {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("At most {} molecules can be queried at once, got {}", MAX_QUERY_MOLECULES, data.ids.len())
+        }));
+    }
+    let mut seen = HashSet::new();
+    let ids: Vec<&str> = data.ids.iter()
+        .map(String::as_str)
+        .filter(|id| seen.insert(*id))
+        .collect();
+    let include: Vec<MoleculeInclude> = data.include.clone().unwrap_or_else(|| MoleculeInclude::ALL.to_vec());
+    let evidence_limit = data.evidence_limit.unwrap_or(DEFAULT_QUERY_EVIDENCE_LIMIT).min(500);
     
-    let params = serde_json::json!({
-        "molecule_id": molecule_id,
+    // Molecules are fetched concurrently; a failed part is reported on its
+    // molecule instead of failing the whole query
+    let (state, project_id, include) = (&state, &project_id, &include);
+    let lookups = ids.iter().map(|molecule_id| async move {
+        let molecule = match state.graph_store.get_molecule(project_id, molecule_id).await {
+            Ok(Some(molecule)) => molecule,
+            Ok(None) => return None,
+            Err(e) => {
+                warn!("Failed to fetch molecule {}: {}", molecule_id, e);
+                let mut record = MoleculeRecord { molecule_id: molecule_id.to_string(), ..Default::default() };
+                record.errors.insert(MoleculeInclude::Molecule, e.to_string());
+                return Some(record);
+            }
+        };
+        
+        let mut record = MoleculeRecord { molecule_id: molecule_id.to_string(), ..Default::default() };
+        if include.contains(&MoleculeInclude::Confidence) {
+            record.confidence = molecule.get_property("confidence").and_then(|v| v.as_f64());
+        }
+        if include.contains(&MoleculeInclude::Molecule) {
+            record.molecule = Some(molecule);
+        }
+        if include.contains(&MoleculeInclude::Pathways) {
+            match state.graph_store.molecule_pathways(project_id, molecule_id).await {
+                Ok(pathways) => record.pathways = Some(pathways.into_iter().map(pathway_data).collect()),
+                Err(e) => { record.errors.insert(MoleculeInclude::Pathways, e.to_string()); }
+            }
+        }
+        if include.contains(&MoleculeInclude::Interactions) {
+            match state.graph_store.molecule_interactions(project_id, molecule_id).await {
+                Ok(interactions) => record.interactions = Some(interactions.into_iter().map(interaction_data).collect()),
+                Err(e) => { record.errors.insert(MoleculeInclude::Interactions, e.to_string()); }
+            }
+        }
+        if include.contains(&MoleculeInclude::Evidence) {
+            match state.graph_store.molecule_evidence(project_id, molecule_id).await {
+                Ok(mut evidence) => {
+                    evidence.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+                    record.evidence_total = Some(evidence.len());
+                    evidence.truncate(evidence_limit);
+                    record.evidence = Some(evidence);
+                }
+                Err(e) => { record.errors.insert(MoleculeInclude::Evidence, e.to_string()); }
+            }
+        }
+        Some(record)
     });
+    let records = futures::future::join_all(lookups).await;
+    
+    let mut response = MoleculeQueryResponse { molecules: Vec::new(), not_found: Vec::new(), truncated: false };
+    for (molecule_id, record) in ids.iter().zip(records) {
+        match record {
+            Some(record) => {
+                response.truncated |= record.evidence.as_ref()
+                    .zip(record.evidence_total)
+                    .is_some_and(|(evidence, total)| evidence.len() < total);
+                response.molecules.push(record);
+            }
+            None => response.not_found.push(molecule_id.to_string()),
+        }
+    }
+    HttpResponse::Ok().json(response)
+}
+
+#[get("/api/molecules/{id}/xrefs")]
+async fn get_molecule_xrefs(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = authorize(&req, &state, Access::Read).await {
+        return response;
+    }
+    let identifier = path.into_inner();
+    
+    match state.xref_service.resolve_raw(&identifier).await {
+        Ok(xrefs) => HttpResponse::Ok().json(xrefs),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Cross-reference resolution error: {}", e)
+        })),
+    }
+}
+
+#[post("/api/evidence")]
+async fn ingest_evidence(req: HttpRequest, data: web::Json<IngestEvidenceRequest>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let IngestEvidenceRequest { molecule_id, mut evidence } = data.into_inner();
     
-    let results = match driver.run_query(&query, params).await {
-        Ok(results) => results,
+    if let Some(other) = evidence.iter().find(|e| e.molecule_id != molecule_id) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Evidence {} belongs to molecule {}, not {}", other.id, other.molecule_id, molecule_id)
+        }));
+    }
+    if let Err(errors) = state.evidence_schemas.validate_all(&mut evidence) {
+        return HttpResponse::UnprocessableEntity().json(serde_json::json!({
+            "error": errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n"),
+            "violations": errors,
+        }));
+    }
+    
+    let mut items = match state.graph_store.molecule_evidence(&project_id, &molecule_id).await {
+        Ok(items) => items,
         Err(e) => {
-            error!("Failed to fetch molecule data: {}", e);
+            error!("Failed to load evidence for {}: {}", molecule_id, e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Molecule data retrieval error: {}", e)
+                "error": format!("Evidence retrieval error: {}", e)
             }));
         }
     };
+    items.extend(evidence.iter().cloned());
     
-    // Check if molecule was found
-    if results.is_empty() {
-        return HttpResponse::NotFound().json(serde_json::json!({
-            "error": format!("Molecule not found: {}", molecule_id)
+    let integrated = match identity_pipeline(&state).await.run(&molecule_id, items).await {
+        Ok(integrated) => integrated,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Integration error: {}", e)
+            }));
+        }
+    };
+    let stored = state.graph_store.store_integrated_evidence(&project_id, &integrated, ConfidenceTrigger::Analysis).await;
+    state.project_stats.lock().await.invalidate(&project_id);
+    state.responses.lock().await.invalidate(&project_id);
+    if let Err(e) = stored {
+        error!("Failed to store evidence for {}: {}", molecule_id, e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Evidence storage error: {}", e)
         }));
     }
+    {
+        let mut history = state.evidence_history.lock().await;
+        record_evidence_history(&mut history, &project_id, &integrated.evidence_items);
+        history.record_confidence(&scoped_key(&project_id, &molecule_id), integrated.aggregate_confidence, ConfidenceTrigger::Analysis);
+        save_evidence_history(&history);
+    }
     
-    // Parse the results
-    let row = &results[0];
-    let id = row.get("id").and_then(|v| v.as_str()).unwrap_or(&molecule_id);
-    let name = row.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown");
-    let mol_type = row.get("type").and_then(|v| v.as_str()).unwrap_or("unknown");
-    let description = row.get("description").and_then(|v| v.as_str()).unwrap_or("No description available");
+    HttpResponse::Ok().json(IngestEvidenceResponse { evidence, integrated_evidence: integrated })
+}
+
+#[get("/api/evidence/schemas")]
+async fn list_evidence_schemas(state: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(state.evidence_schemas.documents())
+}
+
+#[post("/api/ablate")]
+async fn ablate_evidence(req: HttpRequest, data: web::Json<AblationRequest>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = authorize(&req, &state, Access::Read).await {
+        return response;
+    }
+    let mode = if data.by_source { AblationMode::Source } else { AblationMode::Item };
     
-    let properties = row.get("properties")
-        .and_then(|v| v.as_object())
-        .cloned()
-        .unwrap_or_default();
+    match identity_pipeline(&state).await.ablate_evidence(&data.molecule_id, &data.evidence, mode) {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Ablation error: {}", e)
+        })),
+    }
+}
+
+#[post("/api/decompose")]
+async fn decompose_confidence(req: HttpRequest, data: web::Json<AblationRequest>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = authorize(&req, &state, Access::Read).await {
+        return response;
+    }
+    let mode = if data.by_source { AblationMode::Source } else { AblationMode::Item };
     
-    let aliases = match row.get("aliases") {
-        Some(serde_json::Value::Array(arr)) => arr.clone(),
-        _ => vec![],
+    match identity_pipeline(&state).await.decompose_confidence(&data.molecule_id, &data.evidence, mode) {
+        Ok(decomposition) => HttpResponse::Ok().json(decomposition),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Decomposition error: {}", e)
+        })),
+    }
+}
+
+#[post("/api/plan")]
+async fn plan_acquisition(req: HttpRequest, data: web::Json<PlanRequest>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = authorize(&req, &state, Access::Read).await {
+        return response;
+    }
+    let mut options = PlannerOptions::default();
+    if let Some(threshold) = data.confidence_threshold {
+        options = options.with_confidence_threshold(threshold);
+    }
+    if let Some(max_recommendations) = data.max_recommendations {
+        options = options.with_max_recommendations(max_recommendations);
+    }
+    let mut by_molecule: BTreeMap<String, Vec<Evidence>> = BTreeMap::new();
+    for item in &data.evidence {
+        by_molecule.entry(item.molecule_id.clone()).or_default().push(item.clone());
+    }
+    
+    let pipeline = identity_pipeline(&state).await;
+    let plan = AcquisitionPlanner::new(options)
+        .and_then(|planner| planner.plan(&pipeline, &by_molecule));
+    match plan {
+        Ok(plan) => HttpResponse::Ok().json(plan),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Planning error: {}", e)
+        })),
+    }
+}
+
+#[get("/api/molecules/{id}/snapshot")]
+async fn get_molecule_snapshot(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<SnapshotQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let molecule_id = path.into_inner();
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
     };
+    let at = query.at.unwrap_or_else(chrono::Utc::now);
     
-    // Create molecule data response
-    let molecule_data = serde_json::json!({
-        "id": id,
-        "name": name,
-        "type": mol_type,
-        "description": description,
-        "properties": properties,
-        "aliases": aliases
-    });
+    let history = state.evidence_history.lock().await;
+    HttpResponse::Ok().json(history.snapshot_at(&scoped_key(&project_id, &molecule_id), at))
+}
 
-    HttpResponse::Ok().json(molecule_data)
+#[get("/api/molecules/{id}/diff")]
+async fn get_molecule_diff(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<DiffQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let molecule_id = path.into_inner();
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let to = query.to.unwrap_or_else(chrono::Utc::now);
+    
+    if query.from > to {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid range: {} is after {}", query.from, to)
+        }));
+    }
+    
+    let history = state.evidence_history.lock().await;
+    HttpResponse::Ok().json(history.diff(&scoped_key(&project_id, &molecule_id), query.from, to))
+}
+
+#[get("/api/molecules/{id}/pathway-coherence")]
+async fn get_pathway_coherence(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    let molecule_id = path.into_inner();
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    cached_json(&req, &state, &project_id, async {
+        let pathways = state.graph_store.molecule_pathways(&project_id, &molecule_id).await.map_err(|e| {
+            error!("Failed to fetch pathway data: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Pathway data retrieval error: {}", e)
+            }))
+        })?;
+        let molecules = state.graph_store.list_molecules(&project_id).await.map_err(|e| {
+            error!("Failed to list project molecules: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Molecule listing error: {}", e)
+            }))
+        })?;
+        
+        // Molecules count as observed once they have an integrated confidence
+        let observed: HashMap<String, f64> = molecules.iter()
+            .filter_map(|m| Some((m.molecule_id.clone(), m.confidence?)))
+            .collect();
+        let names: HashMap<String, String> = molecules.into_iter()
+            .filter_map(|m| Some((m.molecule_id, m.name?)))
+            .collect();
+        Ok::<_, HttpResponse>(coherence_view(&molecule_id, &pathways, &observed, &names))
+    }).await
+}
+
+#[get("/api/molecules/{id}/confidence-history")]
+async fn get_confidence_history(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<ConfidenceHistoryQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let molecule_id = path.into_inner();
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    let history = match state.graph_store.confidence_history(&project_id, &molecule_id).await {
+        Ok(history) => history,
+        Err(e) => {
+            error!("Failed to read confidence history of {}: {}", molecule_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to read confidence history: {}", e)
+            }));
+        }
+    };
+    let confidences: Vec<f64> = history.iter().map(|point| point.confidence).collect();
+    let trend = ConfidenceTrend::from_series(&confidences);
+    let mut revisions: Vec<_> = history.into_iter()
+        .filter(|point| query.since.is_none_or(|since| point.recorded_at >= since))
+        .collect();
+    if let Some(limit) = query.limit {
+        revisions.drain(..revisions.len().saturating_sub(limit));
+    }
+    
+    HttpResponse::Ok().json(ConfidenceHistoryResponse {
+        trend,
+        molecule_id,
+        revisions,
+    })
+}
+
+#[get("/api/path")]
+async fn find_paths(req: HttpRequest, query: web::Query<PathQuery>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let max_hops = query.max_hops.unwrap_or(4);
+    if !(1..=10).contains(&max_hops) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("max_hops must be between 1 and 10, got {}", max_hops)
+        }));
+    }
+    let limit = query.limit.unwrap_or(5).min(50);
+    
+    cached_json(&req, &state, &project_id, async {
+        match state.graph_store.find_paths(&project_id, &query.from, &query.to, max_hops, limit).await {
+            Ok(paths) => Ok(PathResponse {
+                from: query.from.clone(),
+                to: query.to.clone(),
+                max_hops,
+                paths,
+            }),
+            Err(e) => {
+                error!("Path query failed: {}", e);
+                Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Path query error: {}", e)
+                })))
+            }
+        }
+    }).await
+}
+
+#[get("/api/network/neighborhood/{molecule_id}")]
+async fn get_neighborhood(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<NeighborhoodQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let molecule_id = path.into_inner();
+    let defaults = NeighborhoodOptions::default();
+    let options = NeighborhoodOptions::default()
+        .with_radius(query.radius.unwrap_or(defaults.radius))
+        .with_min_similarity(query.min_similarity.unwrap_or(defaults.min_similarity))
+        .with_max_nodes(query.max_nodes.unwrap_or(defaults.max_nodes).min(5000));
+    if let Err(e) = options.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("{}", e)
+        }));
+    }
+    
+    cached_json(&req, &state, &project_id, async {
+        match state.graph_store.neighborhood(&project_id, &molecule_id, &options).await {
+            Ok(Some(network)) => Ok(network.to_serializable()),
+            Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("Molecule not found: {}", molecule_id)
+            }))),
+            Err(e) => {
+                error!("Neighborhood query failed: {}", e);
+                Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Neighborhood query error: {}", e)
+                })))
+            }
+        }
+    }).await
+}
+
+#[get("/api/search")]
+async fn search(req: HttpRequest, query: web::Query<SearchQuery>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    if query.q.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Search query must not be empty"
+        }));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, 100);
+    
+    match state.search_index.search(&project_id, &query.q, limit, query.fuzzy.unwrap_or(true)) {
+        Ok(hits) => HttpResponse::Ok().json(SearchResponse {
+            query: query.q.clone(),
+            hits,
+        }),
+        Err(e) => {
+            error!("Search failed: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Search error: {}", e)
+            }))
+        }
+    }
+}
+
+#[get("/api/quarantine")]
+async fn list_quarantine(req: HttpRequest, query: web::Query<QuarantineQuery>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    let quarantine = state.quarantine.lock().await;
+    HttpResponse::Ok().json(quarantine.list(&project_id, query.status))
+}
+
+#[post("/api/quarantine/{evidence_id}/resolve")]
+async fn resolve_quarantine(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<ResolveQuarantineRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let evidence_id = path.into_inner();
+    let (principal, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    let mut quarantine = state.quarantine.lock().await;
+    match quarantine.resolve(&project_id, &evidence_id, data.resolution, &principal.user_id, data.note.clone()) {
+        Ok(item) => HttpResponse::Ok().json(item),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("{}", e)
+        })),
+    }
+}
+
+#[get("/api/molecules/{id}/curation")]
+async fn get_curation(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    let molecule_id = path.into_inner();
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    let curation = state.curation.lock().await;
+    HttpResponse::Ok().json(CurationStatus {
+        molecule_id: molecule_id.clone(),
+        assertion: curation.assertion(&project_id, &molecule_id).cloned(),
+        assessment: curation.assessment(&project_id, &molecule_id).cloned(),
+        disagreements: curation.disagreements(&project_id, Some(&molecule_id)).into_iter().cloned().collect(),
+    })
+}
+
+#[post("/api/molecules/{id}/curation")]
+async fn lock_molecule(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<CurationRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let molecule_id = path.into_inner();
+    let (principal, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    let assertion = match state.curation.lock().await
+        .lock(&project_id, &molecule_id, &principal.user_id, data.confidence, &data.reason, data.identity.clone()) {
+        Ok(assertion) => assertion.clone(),
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("{}", e)
+            }));
+        }
+    };
+    
+    // A curated identity other than the molecule's own rejects the molecule's evidence
+    let reviewed_confidence = if assertion.identity.is_some() { 0.0 } else { assertion.confidence };
+    record_reliability(&state, &project_id, &molecule_id, reviewed_confidence).await;
+    
+    HttpResponse::Ok().json(assertion)
+}
+
+#[delete("/api/molecules/{id}/curation")]
+async fn unlock_molecule(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    let molecule_id = path.into_inner();
+    let (_, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    match state.curation.lock().await.unlock(&project_id, &molecule_id) {
+        Ok(assertion) => HttpResponse::Ok().json(assertion),
+        Err(e) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("{}", e)
+        })),
+    }
+}
+
+#[get("/api/review/queue")]
+async fn review_queue(req: HttpRequest, query: web::Query<ReviewQueueQuery>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    let curation = state.curation.lock().await;
+    HttpResponse::Ok().json(curation.review_queue(&project_id, query.limit.unwrap_or(50)))
+}
+
+#[post("/api/projects")]
+async fn create_project(req: HttpRequest, data: web::Json<CreateProjectRequest>, state: web::Data<AppState>) -> impl Responder {
+    let principal = match authenticate(&req, &state) {
+        Ok(principal) => principal,
+        Err(response) => return response,
+    };
+    
+    let data = data.into_inner();
+    let project = match state.projects.lock().await.create(&data.name, data.description, &principal) {
+        Ok(project) => project,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Invalid project: {}", e)
+            }));
+        }
+    };
+    
+    if let Err(e) = state.graph_store.store_project(&project).await {
+        warn!("Failed to store project {} in the graph store: {}", project.id, e);
+    }
+    
+    HttpResponse::Created().json(project)
+}
+
+#[get("/api/projects")]
+async fn list_projects(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+    let principal = match authenticate(&req, &state) {
+        Ok(principal) => principal,
+        Err(response) => return response,
+    };
+    
+    let projects = state.projects.lock().await;
+    HttpResponse::Ok().json(projects.visible_to(&principal))
+}
+
+#[get("/api/projects/{id}")]
+async fn get_project(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    let project_id = path.into_inner();
+    let principal = match authenticate(&req, &state) {
+        Ok(principal) => principal,
+        Err(response) => return response,
+    };
+    if let Err(response) = authorize_project(&state, &principal, &project_id, Access::Read).await {
+        return response;
+    }
+    
+    let projects = state.projects.lock().await;
+    HttpResponse::Ok().json(projects.get(&project_id))
+}
+
+#[get("/api/projects/{id}/stats")]
+async fn get_project_stats(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    let project_id = path.into_inner();
+    let principal = match authenticate(&req, &state) {
+        Ok(principal) => principal,
+        Err(response) => return response,
+    };
+    if let Err(response) = authorize_project(&state, &principal, &project_id, Access::Read).await {
+        return response;
+    }
+    
+    if let Some(stats) = state.project_stats.lock().await.get(&project_id) {
+        return HttpResponse::Ok().json(stats);
+    }
+    let stats = match state.graph_store.project_stats(&project_id).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            error!("Failed to compute statistics for project {}: {}", project_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Statistics error: {}", e)
+            }));
+        }
+    };
+    let response = HttpResponse::Ok().json(&stats);
+    state.project_stats.lock().await.insert(stats);
+    response
+}
+
+#[get("/api/projects/{id}/rdf")]
+async fn get_project_rdf(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<RdfQuery>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let principal = match authenticate(&req, &state) {
+        Ok(principal) => principal,
+        Err(response) => return response,
+    };
+    if let Err(response) = authorize_project(&state, &principal, &project_id, Access::Read).await {
+        return response;
+    }
+    
+    let bundle = match state.graph_store.export_project(&project_id).await {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            error!("Failed to export project {} as RDF: {}", project_id, e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("RDF export error: {}", e)
+            }));
+        }
+    };
+    let format = query.format.unwrap_or(RdfFormat::Turtle);
+    let graph = RdfGraph::from_bundle(&bundle, &RdfOptions::default());
+    HttpResponse::Ok().content_type(format.media_type()).body(graph.write(format))
+}
+
+#[post("/api/projects/{id}/members")]
+async fn set_project_member(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Json<ProjectMemberRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let project_id = path.into_inner();
+    let principal = match authenticate(&req, &state) {
+        Ok(principal) => principal,
+        Err(response) => return response,
+    };
+    if let Err(response) = authorize_project(&state, &principal, &project_id, Access::Manage).await {
+        return response;
+    }
+    
+    let mut projects = state.projects.lock().await;
+    match projects.set_member(&project_id, &data.user_id, data.role) {
+        Ok(()) => HttpResponse::Ok().json(projects.get(&project_id)),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("{}", e)
+        })),
+    }
+}
+
+#[delete("/api/projects/{id}/members/{user_id}")]
+async fn remove_project_member(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let (project_id, user_id) = path.into_inner();
+    let principal = match authenticate(&req, &state) {
+        Ok(principal) => principal,
+        Err(response) => return response,
+    };
+    if let Err(response) = authorize_project(&state, &principal, &project_id, Access::Manage).await {
+        return response;
+    }
+    
+    match state.projects.lock().await.remove_member(&project_id, &user_id) {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("{}", e)
+        })),
+    }
+}
+
+/// Require an admin caller; webhooks receive events from every project
+fn require_admin(req: &HttpRequest, state: &AppState) -> Result<Principal, HttpResponse> {
+    let principal = authenticate(req, state)?;
+    if !principal.is_admin() {
+        return Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "Managing webhooks requires the admin role"
+        })));
+    }
+    Ok(principal)
+}
+
+#[post("/api/webhooks")]
+async fn register_webhook(req: HttpRequest, data: web::Json<RegisterWebhookRequest>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = require_admin(&req, &state) {
+        return response;
+    }
+    
+    let data = data.into_inner();
+    let mut webhook = Webhook::new(&data.url, &data.secret);
+    
+    if let Some(events) = data.events {
+        match events.iter().map(|e| e.parse()).collect::<Result<Vec<WebhookEventKind>, _>>() {
+            Ok(events) => webhook = webhook.with_events(events),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("{}", e)
+                }));
+            }
+        }
+    }
+    if let Some(thresholds) = data.thresholds {
+        webhook = webhook.with_thresholds(thresholds);
+    }
+    
+    let registered = webhook.clone();
+    match state.webhooks.register(webhook).await {
+        Ok(_) => HttpResponse::Created().json(registered),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid webhook: {}", e)
+        })),
+    }
+}
+
+#[get("/api/webhooks")]
+async fn list_webhooks(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = require_admin(&req, &state) {
+        return response;
+    }
+    
+    HttpResponse::Ok().json(state.webhooks.webhooks().await)
+}
+
+#[delete("/api/webhooks/{id}")]
+async fn delete_webhook(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = require_admin(&req, &state) {
+        return response;
+    }
+    let webhook_id = path.into_inner();
+    
+    if state.webhooks.unregister(&webhook_id).await {
+        HttpResponse::NoContent().finish()
+    } else {
+        HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Unknown webhook: {}", webhook_id)
+        }))
+    }
+}
+
+#[get("/api/webhooks/deliveries")]
+async fn list_webhook_deliveries(req: HttpRequest, query: web::Query<DeliveriesQuery>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = require_admin(&req, &state) {
+        return response;
+    }
+    let limit = query.limit.unwrap_or(50);
+    HttpResponse::Ok().json(state.webhooks.deliveries(query.webhook_id.as_deref(), limit).await)
+}
+
+#[post("/api/webhooks/deliveries/{id}/redeliver")]
+async fn redeliver_webhook(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = require_admin(&req, &state) {
+        return response;
+    }
+    let delivery_id = path.into_inner();
+    
+    match state.webhooks.redeliver(&delivery_id).await {
+        Ok(delivery) => HttpResponse::Ok().json(delivery),
+        Err(e) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("{}", e)
+        })),
+    }
+}
+
+#[get("/api/alerts")]
+async fn list_alerts(req: HttpRequest, query: web::Query<AlertsQuery>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let limit = query.limit.unwrap_or(50);
+    
+    match state.alert_log.query(&project_id, query.rule_id.as_deref(), query.molecule_id.as_deref(), limit) {
+        Ok(alerts) => HttpResponse::Ok().json(alerts),
+        Err(e) => {
+            error!("Failed to read alerts log: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Alerts log error: {}", e)
+            }))
+        }
+    }
+}
+
+#[get("/api/alerts/rules")]
+async fn list_alert_rules(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Read).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    HttpResponse::Ok().json(state.alerts.rules(Some(&project_id)).await)
+}
+
+#[post("/api/alerts/rules")]
+async fn create_alert_rule(req: HttpRequest, data: web::Json<CreateAlertRuleRequest>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    
+    let data = data.into_inner();
+    let mut rule = AlertRule::new(&data.name, data.condition).with_project(&project_id);
+    if let Some(severity) = data.severity {
+        rule = rule.with_severity(severity);
+    }
+    
+    let created = rule.clone();
+    match state.alerts.add_rule(rule).await {
+        Ok(_) => HttpResponse::Created().json(created),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid alert rule: {}", e)
+        })),
+    }
+}
+
+/// Remove a rule of the caller's project
+///
+/// Rules covering every project come from the rules file and cannot be removed here.
+#[delete("/api/alerts/rules/{id}")]
+async fn delete_alert_rule(req: HttpRequest, path: web::Path<String>, state: web::Data<AppState>) -> impl Responder {
+    let (_, project_id) = match authorize(&req, &state, Access::Write).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let rule_id = path.into_inner();
+    
+    let owned = state.alerts.rules(Some(&project_id)).await.iter()
+        .any(|r| r.id == rule_id && r.project_id.as_deref() == Some(project_id.as_str()));
+    if owned && state.alerts.remove_rule(&rule_id).await {
+        HttpResponse::NoContent().finish()
+    } else {
+        HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Unknown alert rule: {}", rule_id)
+        }))
+    }
+}
+
+#[post("/api/compare")]
+async fn compare_molecules(req: HttpRequest, data: web::Json<CompareRequest>, state: web::Data<AppState>) -> impl Responder {
+    if let Err(response) = authorize(&req, &state, Access::Read).await {
+        return response;
+    }
+    let metric = data.metric.as_deref().unwrap_or(DEFAULT_METRIC);
+    
+    match hegel::api::compare_molecules(&data.smiles1, &data.smiles2, metric) {
+        Ok(similarity) => HttpResponse::Ok().json(CompareResponse {
+            smiles1: data.smiles1.clone(),
+            smiles2: data.smiles2.clone(),
+            metric: metric.to_string(),
+            similarity,
+        }),
+        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Comparison error: {}", e)
+        })),
+    }
+}
+
+#[get("/api/similarity/metrics")]
+async fn list_similarity_metrics() -> impl Responder {
+    HttpResponse::Ok().json(SimilarityMetrics {
+        default: DEFAULT_METRIC.to_string(),
+        metrics: SimilarityRegistry::global().names(),
+    })
+}
+
+#[get("/api/offline")]
+async fn offline_status() -> impl Responder {
+    HttpResponse::Ok().json(OfflineStatus {
+        offline: hegel::offline::is_offline(),
+        unavailable_features: hegel::offline::unavailable_features(),
+    })
 }
 
 #[actix_web::main]
@@ -997,22 +2265,166 @@ async fn main() -> std::io::Result<()> {
     }
     
     // Create shared application state
-    let neo4j_client = Arc::new(Mutex::new(Neo4jClient::new("bolt://neo4j:7687", "neo4j", "password")));
     let llm_client = Arc::new(Mutex::new(LLMClient::new("http://llm-service:8000")));
     let memory_system = Arc::new(Mutex::new(MemorySystem::new()));
-    let evidence_processor = Arc::new(Mutex::new(EvidenceProcessor::new(Default::default())));
-    let evidence_rectifier = Arc::new(Mutex::new(EvidenceRectifier::default()));
+    let confidence_policy = match ConfidencePolicy::from_env() {
+        Ok(policy) => Arc::new(policy),
+        Err(e) => {
+            error!("Failed to load confidence policy: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    let reliability = match ReliabilityTracker::from_env() {
+        Ok(tracker) => tracker,
+        Err(e) => {
+            error!("Failed to load source reliability: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    let evidence_processor = Arc::new(Mutex::new(
+        EvidenceProcessor::new(Default::default())
+            .with_confidence_policy(confidence_policy.as_ref().clone())
+            .with_source_weights(reliability.learned_weights())
+    ));
+    let evidence_rectifier = Arc::new(Mutex::new(
+        EvidenceRectifier::default().with_source_weights(reliability.learned_weights())
+    ));
+    let reliability = Arc::new(Mutex::new(reliability));
     let genomics_processor = Arc::new(Mutex::new(GenomicsProcessor::new()));
     let mass_spec_processor = Arc::new(Mutex::new(MassSpecProcessor::new()));
+    let evidence_history = match VersionedEvidenceStore::from_env() {
+        Ok(history) => Arc::new(Mutex::new(history)),
+        Err(e) => {
+            error!("Failed to load evidence history: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    let anomaly_detector = Arc::new(Mutex::new(AnomalyDetector::default()));
+    let quarantine = Arc::new(Mutex::new(QuarantineStore::new()));
+    let curation = Arc::new(Mutex::new(CurationStore::new()));
+
+    let webhooks = match WebhookDispatcher::from_env().await {
+        Ok(dispatcher) => Arc::new(dispatcher),
+        Err(e) => {
+            error!("Failed to configure webhooks: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+
+    // Periodically re-evaluate identities whose evidence has gone stale
+    let reevaluation = ReevaluationScheduler::new(evidence_history.clone(), ReevaluationOptions::from_env())
+        .with_notifier(Box::new(webhooks.clone()));
+    tokio::spawn(reevaluation.run());
+
+    let xref_service = match XrefService::from_env() {
+        Ok(service) => Arc::new(service),
+        Err(e) => {
+            error!("Failed to load cross-reference tables: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    
+    let proposals = match ProposalStore::from_env() {
+        Ok(store) => Arc::new(Mutex::new(store)),
+        Err(e) => {
+            error!("Failed to load rectification proposals: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    
+    let alert_log = AlertLog::from_env();
+    let alerts = match AlertEngine::from_env() {
+        Ok(engine) => Arc::new(engine
+            .with_sink(Arc::new(StdoutSink))
+            .with_sink(Arc::new(WebhookSink::new(webhooks.clone())))
+            .with_sink(Arc::new(alert_log.clone()))),
+        Err(e) => {
+            error!("Failed to load alert rules: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    
+    let request_timeout = match std::env::var("HEGEL_API_REQUEST_TIMEOUT_SECONDS") {
+        Ok(value) => match value.parse() {
+            Ok(seconds) => Duration::from_secs(seconds),
+            Err(_) => {
+                error!("Invalid HEGEL_API_REQUEST_TIMEOUT_SECONDS value: {}", value);
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid request timeout"));
+            }
+        },
+        Err(_) => DEFAULT_REQUEST_TIMEOUT,
+    };
+    
+    let evidence_schemas = match EvidenceSchemaRegistry::from_env() {
+        Ok(registry) => Arc::new(registry),
+        Err(e) => {
+            error!("Failed to load evidence schemas: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    
+    let search_index = match SearchIndex::from_env() {
+        Ok(index) => Arc::new(index),
+        Err(e) => {
+            error!("Failed to open the search index: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+        }
+    };
+    
+    let graph_store = match StoreConfig::from_env() {
+        Ok(config) => match graph_store::open(&config).await {
+            Ok(store) => Arc::new(IndexedStore::new(store, search_index.clone())) as Arc<dyn GraphStore>,
+            Err(e) => {
+                error!("Failed to open the graph store: {}", e);
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string()));
+            }
+        },
+        Err(e) => {
+            error!("Invalid graph store configuration: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()));
+        }
+    };
+    
+    let projects = Arc::new(Mutex::new(ProjectRegistry::new()));
+    let token_verifier = match TokenVerifier::from_env() {
+        Ok(verifier) => Arc::new(verifier),
+        Err(e) => {
+            error!("Failed to configure authentication: {}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()));
+        }
+    };
+    if let Some(default) = projects.lock().await.get(DEFAULT_PROJECT) {
+        if let Err(e) = graph_store.store_project(default).await {
+            warn!("Failed to store the default project: {}", e);
+        }
+    }
     
     let app_state = web::Data::new(AppState {
-        neo4j_client,
+        graph_store,
+        search_index,
         llm_client,
         memory_system,
         evidence_processor,
         evidence_rectifier,
         genomics_processor,
         mass_spec_processor,
+        evidence_history,
+        anomaly_detector,
+        quarantine,
+        curation,
+        proposals,
+        reliability,
+        xref_service,
+        webhooks,
+        alerts,
+        alert_log,
+        projects,
+        token_verifier,
+        request_timeout,
+        evidence_schemas,
+        project_stats: Arc::new(Mutex::new(StatsCache::default())),
+        responses: Arc::new(Mutex::new(ResponseCache::default())),
+        confidence_policy,
     });
     
     // Start HTTP server
@@ -1026,17 +2438,221 @@ async fn main() -> std::io::Result<()> {
         
         App::new()
             .wrap(cors)
+            .wrap(middleware::Compress::default())
             .app_data(app_state.clone())
             // API routes
             .service(analyze_evidence)
             .service(rectify_evidence)
+            .service(list_proposals)
+            .service(get_proposal)
+            .service(review_proposal)
+            .service(apply_proposal)
             .service(get_reactome_pathways)
             .service(get_interactome)
             .service(get_genomics_analysis)
             .service(get_mass_spec_analysis)
+            .service(process_mass_spec)
+            .service(list_mass_spec_profiles)
             .service(get_molecule_data)
+            .service(query_molecules)
+            .service(get_molecule_xrefs)
+            .service(compare_molecules)
+            .service(list_similarity_metrics)
+            .service(offline_status)
+            .service(get_molecule_snapshot)
+            .service(get_molecule_diff)
+            .service(get_confidence_history)
+            .service(get_pathway_coherence)
+            .service(find_paths)
+            .service(get_neighborhood)
+            .service(search)
+            .service(list_quarantine)
+            .service(resolve_quarantine)
+            .service(get_curation)
+            .service(lock_molecule)
+            .service(unlock_molecule)
+            .service(review_queue)
+            .service(ingest_evidence)
+            .service(list_evidence_schemas)
+            .service(ablate_evidence)
+            .service(decompose_confidence)
+            .service(plan_acquisition)
+            .service(create_project)
+            .service(list_projects)
+            .service(get_project)
+            .service(get_project_stats)
+            .service(get_project_rdf)
+            .service(set_project_member)
+            .service(remove_project_member)
+            .service(register_webhook)
+            .service(list_webhooks)
+            .service(delete_webhook)
+            .service(list_webhook_deliveries)
+            .service(redeliver_webhook)
+            .service(list_alerts)
+            .service(list_alert_rules)
+            .service(create_alert_rule)
+            .service(delete_alert_rule)
     })
     .bind(("0.0.0.0", 8080))?
     .run()
     .await
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test};
+    use hegel::auth::{Claims, UserRole};
+    use hegel::graph::embedded::EmbeddedStore;
+    use hegel::graph::schema::{Edge, EdgeType, MolecularGraph, Node, NodeType};
+    use hegel::processing::evidence::IntegratedEvidence;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    const SECRET: &str = "test-secret";
+
+    /// Server state over a temporary embedded graph store, with no database server
+    fn test_state(graph_store: Arc<dyn GraphStore>, dir: &std::path::Path) -> AppState {
+        std::env::set_var("HEGEL_MEMORY_STORAGE_DIR", dir.join("memory"));
+        let webhooks = Arc::new(WebhookDispatcher::new());
+        AppState {
+            graph_store,
+            search_index: Arc::new(SearchIndex::in_memory().unwrap()),
+            llm_client: Arc::new(Mutex::new(LLMClient::new("http://localhost:8000"))),
+            memory_system: Arc::new(Mutex::new(MemorySystem::new().unwrap())),
+            evidence_processor: Arc::new(Mutex::new(EvidenceProcessor::new(Default::default()))),
+            evidence_rectifier: Arc::new(Mutex::new(EvidenceRectifier::default())),
+            genomics_processor: Arc::new(Mutex::new(GenomicsProcessor::new())),
+            mass_spec_processor: Arc::new(Mutex::new(MassSpecProcessor::new())),
+            evidence_history: Arc::new(Mutex::new(VersionedEvidenceStore::new())),
+            anomaly_detector: Arc::new(Mutex::new(AnomalyDetector::default())),
+            quarantine: Arc::new(Mutex::new(QuarantineStore::new())),
+            curation: Arc::new(Mutex::new(CurationStore::new())),
+            proposals: Arc::new(Mutex::new(ProposalStore::new())),
+            reliability: Arc::new(Mutex::new(ReliabilityTracker::new())),
+            xref_service: Arc::new(XrefService::new()),
+            webhooks,
+            alerts: Arc::new(AlertEngine::new()),
+            alert_log: AlertLog::new(dir.join("alerts.jsonl")),
+            projects: Arc::new(Mutex::new(ProjectRegistry::new())),
+            token_verifier: Arc::new(TokenVerifier::new(SECRET)),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            evidence_schemas: Arc::new(EvidenceSchemaRegistry::builtin().unwrap()),
+            project_stats: Arc::new(Mutex::new(StatsCache::default())),
+            responses: Arc::new(Mutex::new(ResponseCache::default())),
+            confidence_policy: Arc::new(ConfidencePolicy::default()),
+        }
+    }
+
+    fn bearer() -> String {
+        let claims = Claims { sub: "user1".to_string(), role: UserRole::Researcher, exp: 4_102_444_800 };
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET.as_bytes())).unwrap();
+        format!("Bearer {}", token)
+    }
+
+    fn node(id: &str, node_type: NodeType, name: &str) -> Node {
+        Node::new(id.to_string(), node_type, name.to_string())
+    }
+
+    /// Citrate in a Reactome and a non-Reactome pathway, interacting with isocitrate
+    fn citrate_graph() -> MolecularGraph {
+        let mut graph = MolecularGraph::new("tca".to_string(), "TCA cycle".to_string());
+        let mut citrate = node("citrate", NodeType::Molecule, "Citrate");
+        citrate.add_property("description", serde_json::json!("Tricarboxylic acid"));
+        citrate.add_external_id("chebi", "CHEBI:30769");
+        graph.add_node(citrate);
+        graph.add_node(node("isocitrate", NodeType::Molecule, "Isocitrate"));
+        graph.add_node(node("R-HSA-71403", NodeType::Pathway, "Citric acid cycle"));
+        graph.add_node(node("map00020", NodeType::Pathway, "Citrate cycle"));
+        graph.add_edge(Edge::new("citrate".to_string(), "R-HSA-71403".to_string(), EdgeType::PartOf));
+        graph.add_edge(Edge::new("citrate".to_string(), "map00020".to_string(), EdgeType::PartOf));
+        graph.add_edge(Edge::new("citrate".to_string(), "isocitrate".to_string(), EdgeType::TransformsTo));
+        graph
+    }
+
+    #[actix_web::test]
+    async fn test_molecule_and_project_reads_use_the_embedded_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EmbeddedStore::temporary().unwrap();
+        store.store_graph(&citrate_graph()).unwrap();
+        let state = web::Data::new(test_state(Arc::new(store), dir.path()));
+        let default = state.projects.lock().await.get(DEFAULT_PROJECT).unwrap().clone();
+        state.graph_store.store_project(&default).await.unwrap();
+
+        let app = test::init_service(App::new()
+            .app_data(state.clone())
+            .service(get_molecule_data)
+            .service(get_reactome_pathways)
+            .service(get_interactome)
+            .service(get_project_stats)
+            .service(get_project_rdf)).await;
+        let get = |uri: &str| test::TestRequest::get().uri(uri).insert_header((header::AUTHORIZATION, bearer()));
+
+        let unauthenticated = test::TestRequest::get().uri("/api/molecules/citrate").to_request();
+        assert_eq!(test::call_service(&app, unauthenticated).await.status(), StatusCode::UNAUTHORIZED);
+
+        let molecule: serde_json::Value = test::call_and_read_body_json(&app, get("/api/molecules/citrate").to_request()).await;
+        assert_eq!(molecule["name"], "Citrate");
+        assert_eq!(molecule["description"], "Tricarboxylic acid");
+        assert_eq!(molecule["external_ids"]["chebi"], "CHEBI:30769");
+        let missing = test::call_service(&app, get("/api/molecules/malate").to_request()).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        let pathways: Vec<PathwayData> = test::call_and_read_body_json(&app, get("/api/reactome/pathways/citrate").to_request()).await;
+        assert_eq!(pathways.len(), 1);
+        assert_eq!(pathways[0].pathway_id, "R-HSA-71403");
+
+        let interactions: Vec<InteractionData> = test::call_and_read_body_json(&app, get("/api/interactome/citrate").to_request()).await;
+        assert_eq!(interactions.len(), 1);
+        assert_eq!(interactions[0].target_molecule, "isocitrate");
+
+        let stats: serde_json::Value = test::call_and_read_body_json(&app, get("/api/projects/default/stats").to_request()).await;
+        assert_eq!(stats["molecules"], 2);
+
+        let rdf = test::call_and_read_body(&app, get("/api/projects/default/rdf").to_request()).await;
+        assert!(String::from_utf8_lossy(&rdf).contains("Citric acid cycle"));
+    }
+
+    #[actix_web::test]
+    async fn test_confidence_is_read_from_the_graph_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = EmbeddedStore::temporary().unwrap();
+        store.store_graph(&citrate_graph()).unwrap();
+        let evidence = Evidence::manual("citrate", EvidenceType::MassSpec, 0.8, None, "lab").unwrap();
+        store.store_integrated_evidence("default", &IntegratedEvidence {
+            molecule_id: "citrate".to_string(),
+            evidence_items: vec![evidence],
+            aggregate_confidence: 0.8,
+            conflicts: Vec::new(),
+            integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        }, ConfidenceTrigger::Analysis).unwrap();
+        // Nothing is recorded in the server's own evidence history
+        let state = web::Data::new(test_state(Arc::new(store), dir.path()));
+
+        let app = test::init_service(App::new()
+            .app_data(state.clone())
+            .service(query_molecules)
+            .service(get_confidence_history)).await;
+
+        let query = MoleculeQueryRequest {
+            ids: vec!["citrate".to_string(), "isocitrate".to_string()],
+            include: Some(vec![MoleculeInclude::Confidence]),
+            evidence_limit: None,
+        };
+        let request = test::TestRequest::post().uri("/api/molecules/query")
+            .insert_header((header::AUTHORIZATION, bearer()))
+            .set_json(&query)
+            .to_request();
+        let response: MoleculeQueryResponse = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(response.molecules[0].confidence, Some(0.8));
+        assert_eq!(response.molecules[1].confidence, None);
+
+        let request = test::TestRequest::get().uri("/api/molecules/citrate/confidence-history")
+            .insert_header((header::AUTHORIZATION, bearer()))
+            .to_request();
+        let history: ConfidenceHistoryResponse = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(history.revisions.len(), 1);
+        assert_eq!(history.revisions[0].trigger, Some(ConfidenceTrigger::Analysis));
+    }
+}
@@ -4,15 +4,63 @@
 //! allowing users to validate molecules, build networks, and more.
 
 use anyhow::{Result, Context, anyhow};
-use clap::{Parser, Subcommand};
-use log::{info, debug, error};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use log::{info, debug, error, warn};
+use serde::Serialize;
 use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use hegel::processing::{Molecule, MoleculeFormat};
-use hegel::graph::{MoleculeNetwork, NetworkBuilder};
+use hegel::graph::{MoleculeNetwork, NetworkBuilder, SerializableNetwork};
+use hegel::graph::diff::MergePolicy;
+use hegel::graph::migration::ID_SCHEME;
+use hegel::graph::paths::{PathCost, PathOptions};
+use hegel::graph::embeddings::EmbeddingOptions;
+use hegel::graph::layout::{LayoutAlgorithm, LayoutOptions};
+use hegel::graph::similarity::{ModifiedCosineSimilarity, SimilarityMetric, SimilarityRegistry};
+use hegel::graph::families::{feature_molecule, FamilyOptions};
+use hegel::graph::propagation::PropagationOptions;
+use hegel::processing::spectral::parse_mgf;
+use hegel::graph::conflicts::{ConflictGraph, ConflictGraphFormat};
+use hegel::processing::evidence::{Evidence, EvidenceType, IntegratedEvidence};
+use hegel::processing::pipeline::{AblationMode, IdentityPipeline};
+use hegel::processing::attribution::ConfidenceDecomposition;
+use hegel::processing::drift::{flag_drifted_evidence, DriftDetector, RUN_METADATA_KEY};
+use hegel::processing::qc::{apply_qc, QcAction, QcEvaluator, QcPanel};
+use hegel::processing::profiles::{ClusterMethod, EvidenceProfile, ProfileClusterer};
+use hegel::processing::results::{AnalysisRow, ResultFormat, ResultsWriter};
+use hegel::processing::spill::MemoryBudget;
+use hegel::processing::library::{validate_library, FailureKind, LibraryFormat, ValidationOptions};
+use hegel::processing::versioning::ConfidenceTrigger;
 use hegel::metacognition::{MetacognitionSystem, ValidationResult};
+use hegel::metacognition::policy::IdentityPolicy;
+use hegel::metacognition::planner::{AcquisitionPlanner, PlannerOptions};
+use hegel::metacognition::claims::ArticleAbstract;
+use hegel::metacognition::llm::LLMInterface;
+use hegel::metacognition::templates::TemplateSet;
+use hegel::identity::MoleculeIdType;
+use hegel::identity::linkage::{link_records, DatabaseRecord, LabelledPair, LinkDecision, LinkageModel};
+use hegel::processing::mass_spec::{MassSpecData, MassSpecProcessor};
+use hegel::processing::features::{feature_evidence, match_feature, CompoundStore, FeatureMatchOptions, FeatureTable, FeatureTableFormat, Polarity};
+use hegel::processing::mztab::{MzTabExport, MzTabOptions};
+use hegel::processing::alignment::{align_runs, AlignmentOptions, Run};
+use hegel::processing::genomics::{GenomicsData, GenomicsProcessor};
+use hegel::processing::genomics::metabolites::{enzyme_linkage_evidence, significant_genes, EnzymeLinkOptions, GeneMetaboliteMap, ReactomeEntity};
+use hegel::processing::targets::{ActivitySource, TargetActivities};
+use hegel::processing::reference_library::{LibrarySearchOptions, PromotionCriteria, ReferenceLibrary};
+use hegel::processing::reliability::ReliabilityTracker;
+use hegel::processing::units::Quantity;
+use hegel::graph::neo4j::{Neo4jClient, Neo4jConfig};
+use hegel::bundle::ProjectBundle;
+use hegel::graph::rdf::{RdfFormat, RdfGraph, RdfOptions};
+use hegel::cohorts::StudyDesign;
+use hegel::cancellation::CancellationToken;
+use hegel::projects::DEFAULT_PROJECT;
+use hegel::processing::retention::{RetentionPolicy, RedactionAuditLog};
 
 /// CLI arguments
 #[derive(Parser)]
@@ -20,7 +68,14 @@ use hegel::metacognition::{MetacognitionSystem, ValidationResult};
     name = "hegel",
     about = "Hegel molecular identity platform",
     version = env!("CARGO_PKG_VERSION"),
-    author = "Hegel Project Team"
+    author = "Hegel Project Team",
+    after_help = "Examples:
+  hegel validate --molecule 'C(C1C(C(C(C(O1)O)O)O)O)O' --id-type smiles
+  hegel network build --input molecules.txt --output network.json
+  hegel batch --input evidence.json --output-file results.parquet --output-format parquet
+  hegel doctor
+
+Run `hegel completions <shell>` to generate shell completions."
 )]
 struct Cli {
     /// Subcommand to run
@@ -31,27 +86,72 @@ struct Cli {
     #[clap(short, long, global = true)]
     verbose: bool,
     
-    /// Output format (text, json, csv)
+    /// Output format (text, json, jsonl, csv)
     #[clap(short, long, global = true, default_value = "text")]
     output: String,
+    
+    /// Seed for all random number generation (overrides HEGEL_SEED)
+    #[clap(long, global = true)]
+    seed: Option<u64>,
+    
+    /// Disable every external call (overrides HEGEL_OFFLINE)
+    #[clap(long, global = true)]
+    offline: bool,
 }
 
 /// Available subcommands
 #[derive(Subcommand)]
 enum Commands {
     /// Validate a molecule's identity
+    #[clap(after_help = "Examples:
+  hegel validate --molecule glucose --id-type name
+  hegel validate --molecule WQZGKKKJIJFFOK-GASJEMHNSA-N --policy strict.json -o json")]
     Validate {
         /// Molecule identifier (SMILES, InChI, etc.)
         #[clap(short, long)]
         molecule: String,
         
-        /// Type of identifier (smiles, inchi, name)
+        /// Type of identifier (smiles, inchi, inchikey, cas, pubchem, name, ...; auto to detect)
         #[clap(short, long, default_value = "smiles")]
         id_type: String,
         
         /// Validation confidence threshold (0.0-1.0)
         #[clap(short, long, default_value = "0.5")]
         threshold: f64,
+        
+        /// JSON identity policy (hysteresis thresholds, evidence requirements)
+        #[clap(long)]
+        policy: Option<PathBuf>,
+    },
+    
+    /// Validate and standardize a SMILES or SD compound library
+    #[clap(after_help = "Examples:
+  hegel validate-file --input library.smi --report errors.csv
+  hegel validate-file --input library.sdf --report errors.csv --output clean.smi --allow-elements C,H,N,O,S,P,F,Cl,Br,I")]
+    ValidateFile {
+        /// Library to validate (.smi or .sdf)
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// CSV file to write rejected records to, with their line numbers
+        #[clap(long)]
+        report: PathBuf,
+        
+        /// SMILES file to write the standardized library to
+        #[clap(long)]
+        output: Option<PathBuf>,
+        
+        /// Library format (smiles, sdf); detected from the extension by default
+        #[clap(long)]
+        format: Option<String>,
+        
+        /// Comma-separated elements structures may contain
+        #[clap(long)]
+        allow_elements: Option<String>,
+        
+        /// Keep every fragment instead of stripping counter-ions and solvents
+        #[clap(long)]
+        keep_fragments: bool,
     },
     
     /// Process a molecule to extract properties and relationships
@@ -60,7 +160,7 @@ enum Commands {
         #[clap(short, long)]
         molecule: String,
         
-        /// Type of identifier (smiles, inchi, name)
+        /// Type of identifier (smiles, inchi, inchikey, cas, pubchem, name, ...; auto to detect)
         #[clap(short, long, default_value = "smiles")]
         id_type: String,
         
@@ -76,389 +176,3309 @@ enum Commands {
     /// Compare two molecules
     Compare {
         /// First molecule identifier
-        #[clap(short, long)]
+        #[clap(short = '1', long)]
         molecule1: String,
         
         /// Second molecule identifier
-        #[clap(short, long)]
+        #[clap(short = '2', long)]
         molecule2: String,
         
-        /// Type of identifier (smiles, inchi, name)
+        /// Type of identifier (smiles, inchi, inchikey, cas, pubchem, name, ...; auto to detect)
         #[clap(short, long, default_value = "smiles")]
         id_type: String,
+        
+        /// Similarity metric to use (tanimoto, dice, or any registered metric)
+        #[clap(long, default_value = "tanimoto")]
+        metric: String,
     },
     
-    /// Build a network from a set of molecules
+    /// Build, compare or merge molecular networks
     Network {
-        /// Input file with molecules (one per line)
+        #[clap(subcommand)]
+        command: NetworkCommands,
+    },
+    
+    /// Measure how much each evidence item drives a molecule's confidence
+    Ablate {
+        /// JSON file containing an array of evidence items
         #[clap(short, long)]
         input: PathBuf,
         
-        /// Output file for the network
+        /// Molecule the evidence relates to
         #[clap(short, long)]
-        output: PathBuf,
+        molecule: String,
         
-        /// Input format (smiles, sdf, csv)
-        #[clap(short, long, default_value = "smiles")]
-        format: String,
+        /// Hold out whole sources instead of individual items
+        #[clap(long)]
+        by_source: bool,
+    },
+    
+    /// Cluster molecules by their evidence profiles to find shared biases
+    Cluster {
+        /// JSON file containing an array of evidence items for several molecules
+        #[clap(short, long)]
+        input: PathBuf,
         
-        /// Similarity threshold for network connections (0.0-1.0)
-        #[clap(short, long, default_value = "0.7")]
-        threshold: f64,
+        /// Number of clusters; chosen by silhouette if absent
+        #[clap(short, long)]
+        k: Option<usize>,
         
-        /// Maximum neighbors per molecule
-        #[clap(short, long, default_value = "10")]
-        max_neighbors: usize,
+        /// Largest number of clusters tried when choosing automatically
+        #[clap(long, default_value = "8")]
+        max_k: usize,
+        
+        /// Clustering method (k-medoids, hierarchical)
+        #[clap(long, default_value = "k-medoids")]
+        method: String,
     },
     
-    /// Start the Hegel API server
-    Serve {
-        /// Host to bind to
-        #[clap(short, long, default_value = "127.0.0.1")]
-        host: String,
+    /// Detect instrument runs whose confidence distribution drifted and flag their evidence
+    #[clap(after_help = "Examples:
+  hegel drift --input evidence.json --output flagged.json
+  hegel batch --input flagged.json --output-file results.csv --output-format csv")]
+    Drift {
+        /// JSON file containing an array of evidence items with a `run_id` in their metadata
+        #[clap(short, long)]
+        input: PathBuf,
         
-        /// Port to listen on
-        #[clap(short, long, default_value = "8080")]
-        port: u16,
+        /// Write the evidence, with drifted runs flagged, to this file
+        #[clap(long)]
+        output: Option<PathBuf>,
+        
+        /// Project the alerts are raised in
+        #[clap(long, default_value = DEFAULT_PROJECT)]
+        project: String,
+        
+        /// Most previous runs pooled into each baseline
+        #[clap(long, default_value = "5")]
+        baseline_runs: usize,
     },
-}
-
-/// Main entry point
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Parse command-line arguments
-    let cli = Cli::parse();
     
-    // Configure logging
-    if std::env::var("RUST_LOG").is_err() {
-        if cli.verbose {
-            std::env::set_var("RUST_LOG", "debug");
-        } else {
-            std::env::set_var("RUST_LOG", "info");
-        }
-    }
-    env_logger::init();
-    
-    // Initialize the Hegel core engine
-    hegel::initialize()?;
-    
-    // Process the requested command
-    match &cli.command {
-        Commands::Validate { molecule, id_type, threshold } => {
-            validate_molecule(molecule, id_type, *threshold, &cli.output).await?;
-        }
+    /// Check each run's QC compounds against system suitability thresholds and act on failing runs
+    #[clap(after_help = "Examples:
+  hegel qc --input evidence.json --panel qc-panel.json --output checked.json
+  hegel qc --input evidence.json --panel qc-panel.json --action block --output checked.json")]
+    Qc {
+        /// JSON file containing an array of evidence items with a `run_id` in their metadata
+        #[clap(short, long)]
+        input: PathBuf,
         
-        Commands::Process { molecule, id_type, pathways, interactions } => {
-            process_molecule(molecule, id_type, *pathways, *interactions, &cli.output).await?;
-        }
+        /// JSON file listing the QC compounds and internal standards
+        #[clap(long)]
+        panel: PathBuf,
         
-        Commands::Compare { molecule1, molecule2, id_type } => {
-            compare_molecules(molecule1, molecule2, id_type, &cli.output).await?;
-        }
+        /// Write the evidence, with failing runs blocked or flagged, to this file
+        #[clap(long)]
+        output: Option<PathBuf>,
         
-        Commands::Network { input, output, format, threshold, max_neighbors } => {
-            build_network(input, output, format, *threshold, *max_neighbors, &cli.output).await?;
-        }
+        /// Project the alerts are raised in
+        #[clap(long, default_value = DEFAULT_PROJECT)]
+        project: String,
         
-        Commands::Serve { host, port } => {
-            serve_api(host, *port).await?;
-        }
-    }
-    
-    Ok(())
-}
-
-/// Validate a molecule's identity
-async fn validate_molecule(molecule: &str, id_type: &str, threshold: f64, output_format: &str) -> Result<()> {
-    info!("Validating molecule: {}", molecule);
-    let start_time = Instant::now();
+        /// What to do with evidence of failed runs (block, down-weight)
+        #[clap(long, default_value = "down-weight")]
+        action: String,
+    },
     
-    // Create a metacognition system
-    let system = MetacognitionSystem::new()?;
+    /// Integrate evidence for many molecules and write one result row per molecule
+    #[clap(after_help = "Examples:
+  hegel batch --input evidence.json --output-file results.csv --output-format csv
+  HEGEL_MEMORY_BUDGET_MB=512 hegel batch --input evidence.json --output-file results.parquet --output-format parquet -o jsonl")]
+    Batch {
+        /// JSON file containing an array of evidence items for several molecules
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// File to write the results to
+        #[clap(long)]
+        output_file: PathBuf,
+        
+        /// Format of the results file (json, csv, parquet)
+        #[clap(long, default_value = "json")]
+        output_format: String,
+    },
     
-    // Parse the ID type
-    let mol_id_type = parse_id_type(id_type)?;
+    /// Integrate evidence for a molecule and report the conclusion
+    Report {
+        /// JSON file containing an array of evidence items
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// Molecule the evidence relates to
+        #[clap(short, long)]
+        molecule: String,
+        
+        /// Write the conflict graph to this file
+        #[clap(long)]
+        conflict_graph: Option<PathBuf>,
+        
+        /// Conflict graph format (dot, cytoscape)
+        #[clap(long, default_value = "dot")]
+        conflict_format: String,
+    },
     
-    // Process the molecule
-    let validation = system.validate_molecule_identity(molecule).await?;
+    /// Recommend the experiments that would most raise confidence in uncertain identifications
+    #[clap(after_help = "Examples:
+  hegel plan --input evidence.json
+  hegel plan --input evidence.json --threshold 0.9 --max-recommendations 1 -o json")]
+    Plan {
+        /// JSON file containing an array of evidence items for one or more molecules
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// Molecules at or above this confidence are not planned for (0.0-1.0)
+        #[clap(short, long, default_value = "0.8")]
+        threshold: f64,
+        
+        /// Most experiments recommended per molecule
+        #[clap(long, default_value = "3")]
+        max_recommendations: usize,
+    },
     
-    // Output the results based on the format
-    let elapsed = start_time.elapsed();
+    /// Process mass spectrometry data into evidence for a molecule
+    MassSpec {
+        /// JSON file containing the mass spec data
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// Molecule the data relates to
+        #[clap(short, long)]
+        molecule: String,
+        
+        /// Instrument profile (orbitrap, q-tof, tof-maldi)
+        #[clap(short, long)]
+        profile: Option<String>,
+    },
     
-    match output_format {
-        "json" => {
-            println!("{}", serde_json::to_string_pretty(&validation)?);
-        }
-        "csv" => {
-            println!("molecule_id,is_valid,confidence,explanation");
-            println!("{},{},{},\"{}\"", 
-                     validation.molecule_id,
-                     validation.is_valid,
-                     validation.confidence,
-                     validation.explanation.replace("\"", "\"\""));
-        }
-        _ => {
-            println!("Validation Results:");
-            println!("  Molecule ID: {}", validation.molecule_id);
-            println!("  Valid: {}", if validation.is_valid { "YES" } else { "NO" });
-            println!("  Confidence: {:.1}%", validation.confidence * 100.0);
-            println!("  Explanation: {}", validation.explanation);
-            println!();
-            println!("Time taken: {:.2?}", elapsed);
-        }
-    }
+    /// Match an XCMS or MZmine feature table against local compounds and write evidence
+    #[clap(after_help = "Examples:
+  hegel features --input features.csv --compounds compounds.json --output evidence.json
+  hegel features --input features.csv --compounds compounds.json --library lab-library.json --output evidence.json
+  hegel batch --input evidence.json --output-file results.csv --output-format csv")]
+    Features {
+        /// Feature table exported by XCMS or MZmine (CSV or TSV)
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// JSON file containing an array of compounds with a monoisotopic mass
+        #[clap(short, long)]
+        compounds: PathBuf,
+        
+        /// File to write the evidence array to
+        #[clap(long)]
+        output: PathBuf,
+        
+        /// Reference library whose compounds take precedence over the compounds file
+        #[clap(short, long)]
+        library: Option<PathBuf>,
+        
+        /// Table format (xcms, mzmine); detected from the header when omitted
+        #[clap(long)]
+        format: Option<String>,
+        
+        /// Mass tolerance in ppm
+        #[clap(long, default_value = "10")]
+        ppm: f64,
+        
+        /// Retention time tolerance in minutes
+        #[clap(long, default_value = "0.5")]
+        rt_tolerance: f64,
+        
+        /// Ionization polarity (positive, negative)
+        #[clap(long, default_value = "positive")]
+        polarity: String,
+    },
     
-    Ok(())
-}
-
-/// Process a molecule to extract properties and relationships
-async fn process_molecule(molecule: &str, id_type: &str, include_pathways: bool, include_interactions: bool, output_format: &str) -> Result<()> {
-    info!("Processing molecule: {}", molecule);
-    let start_time = Instant::now();
+    /// Align the feature tables of several runs and merge their evidence per compound
+    #[clap(after_help = "Examples:
+  hegel align --input jan.csv --input mar.csv --input jun.csv --compounds compounds.json --output evidence.json
+  hegel align --input jan.csv --input jun.csv --compounds compounds.json --output evidence.json --alignment alignment.json --reference jun")]
+    Align {
+        /// Feature tables of the runs, in acquisition order; each run is named after its file
+        #[clap(short, long = "input", required = true)]
+        inputs: Vec<PathBuf>,
+        
+        /// JSON file containing an array of compounds with a monoisotopic mass
+        #[clap(short, long)]
+        compounds: PathBuf,
+        
+        /// File to write the merged evidence array to
+        #[clap(long)]
+        output: PathBuf,
+        
+        /// File to write the consensus features and drift corrections to
+        #[clap(long)]
+        alignment: Option<PathBuf>,
+        
+        /// Run the others are corrected against (defaults to the first)
+        #[clap(long)]
+        reference: Option<String>,
+        
+        /// Table format (xcms, mzmine); detected from the header when omitted
+        #[clap(long)]
+        format: Option<String>,
+        
+        /// Mass tolerance in ppm, for linking features and matching compounds
+        #[clap(long, default_value = "10")]
+        ppm: f64,
+        
+        /// Retention time tolerance in minutes after drift correction
+        #[clap(long, default_value = "0.2")]
+        rt_tolerance: f64,
+        
+        /// Largest retention time drift between runs, in minutes
+        #[clap(long, default_value = "1.0")]
+        max_drift: f64,
+        
+        /// Ionization polarity (positive, negative)
+        #[clap(long, default_value = "positive")]
+        polarity: String,
+    },
     
-    // Create a metacognition system
-    let system = MetacognitionSystem::new()?;
+    /// Follow significant genes to the metabolites of their reactions and write pathway evidence
+    #[clap(after_help = "Examples:
+  hegel gene-links --input samples.json --kegg hsa_enzyme.tsv --kegg enzyme_reaction.tsv --kegg reaction_compound.tsv --output evidence.json
+  hegel gene-links --input samples.json --reactome-genes NCBI2ReactomeReactions.txt --reactome-metabolites ChEBI2ReactomeReactions.txt --species \"Homo sapiens\" --output evidence.json")]
+    GeneLinks {
+        /// JSON file containing an array of genomics samples (gene expression or read counts)
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// KEGG link files (gene-EC, EC-reaction, reaction-compound)
+        #[clap(long)]
+        kegg: Vec<PathBuf>,
+        
+        /// Reactome gene to lowest-level reaction mapping file
+        #[clap(long)]
+        reactome_genes: Option<PathBuf>,
+        
+        /// Reactome ChEBI to lowest-level reaction mapping file
+        #[clap(long)]
+        reactome_metabolites: Option<PathBuf>,
+        
+        /// Species kept from the Reactome files (e.g. "Homo sapiens")
+        #[clap(long)]
+        species: Option<String>,
+        
+        /// Most reaction steps between a gene's reaction and a linked metabolite
+        #[clap(long, default_value = "2")]
+        max_distance: usize,
+        
+        /// File to write the pathway evidence array to
+        #[clap(long)]
+        output: PathBuf,
+    },
     
-    // Parse the ID type
-    let mol_id_type = parse_id_type(id_type)?;
+    /// Load ChEMBL or BindingDB activities and write target edges and evidence for a molecule
+    #[clap(after_help = "Examples:
+  hegel targets --chembl CHEMBL25_activities.csv --molecule CHEMBL25 --graph targets.json --output evidence.json
+  hegel targets --bindingdb BindingDB_subset.tsv --molecule BSYNRYMUTXBXSQ-UHFFFAOYSA-N --observed proteins.json --output evidence.json")]
+    Targets {
+        /// ChEMBL activity exports
+        #[clap(long)]
+        chembl: Vec<PathBuf>,
+        
+        /// BindingDB TSV dumps
+        #[clap(long)]
+        bindingdb: Vec<PathBuf>,
+        
+        /// Molecule ID to generate evidence for, as it appears in the activity files
+        #[clap(short, long)]
+        molecule: String,
+        
+        /// JSON object mapping targets observed in the sample to their confidence
+        #[clap(long)]
+        observed: Option<PathBuf>,
+        
+        /// File to write the molecule-target graph to
+        #[clap(long)]
+        graph: Option<PathBuf>,
+        
+        /// File to write the evidence array to
+        #[clap(long)]
+        output: PathBuf,
+    },
     
-    // Process the molecule
-    let response = system.process_molecule(molecule, mol_id_type).await?;
+    /// Extract claims about a molecule from abstracts and turn the verified ones into evidence
+    #[clap(after_help = "Examples:
+  hegel claims --input abstracts.json --molecule TMAO --synonym \"trimethylamine N-oxide\" --output evidence.json
+  hegel claims --input abstracts.json --molecule glucose --id HMDB0000122 --output evidence.json --claims claims.json")]
+    Claims {
+        /// JSON file containing an array of abstracts with a pmid and text
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// Name of the molecule to extract claims about
+        #[clap(short, long)]
+        molecule: String,
+        
+        /// Other names of the molecule, accepted in a claim's sentence
+        #[clap(long = "synonym")]
+        synonyms: Vec<String>,
+        
+        /// Molecule ID recorded on the evidence (defaults to the name)
+        #[clap(long)]
+        id: Option<String>,
+        
+        /// File to write the evidence from verified claims to
+        #[clap(long)]
+        output: PathBuf,
+        
+        /// File to write every extracted claim and the outcome of its check to
+        #[clap(long)]
+        claims: Option<PathBuf>,
+    },
     
-    // Output the results based on the format
-    let elapsed = start_time.elapsed();
+    /// Score whether database records from different sources describe the same compound
+    #[clap(after_help = "Examples:
+  hegel link --input records.json
+  hegel link --input records.json --train labelled_pairs.json --save-model linkage_model.json
+  hegel link --input records.json --model linkage_model.json --molecule glucose --output evidence.json")]
+    Link {
+        /// JSON file containing an array of database records
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// JSON file with a trained linkage model (defaults to the built-in probabilities)
+        #[clap(long)]
+        model: Option<PathBuf>,
+        
+        /// JSON file of labelled record pairs to train the model on
+        #[clap(long)]
+        train: Option<PathBuf>,
+        
+        /// File to write the trained model to
+        #[clap(long, requires = "train")]
+        save_model: Option<PathBuf>,
+        
+        /// Molecule the linked records identify; linked and review pairs become its evidence
+        #[clap(long)]
+        molecule: Option<String>,
+        
+        /// File to write the linkage evidence array to
+        #[clap(long, requires = "molecule")]
+        output: Option<PathBuf>,
+    },
     
-    match output_format {
+    /// Identify the features of a feature table and write the results as mzTab-M
+    #[clap(after_help = "Examples:
+  hegel mztab --input features.csv --compounds compounds.json --output study.mzTab --id MTBLS1234
+  hegel mztab --input features.csv --compounds compounds.json --evidence msms.json --output study.mzTab --id MTBLS1234")]
+    Mztab {
+        /// Feature table exported by XCMS or MZmine (CSV or TSV)
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// JSON file containing an array of compounds with a monoisotopic mass
+        #[clap(short, long)]
+        compounds: PathBuf,
+        
+        /// Further evidence (e.g. MS/MS library matches, NMR) integrated with the feature matches
+        #[clap(short, long)]
+        evidence: Option<PathBuf>,
+        
+        /// mzTab-M file to write
+        #[clap(long)]
+        output: PathBuf,
+        
+        /// mzTab-ID of the file, such as the study accession
+        #[clap(long)]
+        id: String,
+        
+        /// Description of the study
+        #[clap(long)]
+        description: Option<String>,
+        
+        /// Table format (xcms, mzmine); detected from the header when omitted
+        #[clap(long)]
+        format: Option<String>,
+        
+        /// Mass tolerance in ppm
+        #[clap(long, default_value = "10")]
+        ppm: f64,
+        
+        /// Retention time tolerance in minutes
+        #[clap(long, default_value = "0.5")]
+        rt_tolerance: f64,
+        
+        /// Ionization polarity (positive, negative)
+        #[clap(long, default_value = "positive")]
+        polarity: String,
+    },
+    
+    /// Export a project's molecules, evidence, networks and reports to a bundle
+    ExportProject {
+        /// Project to export
+        id: String,
+        
+        /// Bundle file to write (e.g. bundle.tar.zst)
+        #[clap(short, long)]
+        output: PathBuf,
+    },
+    
+    /// Import a project bundle into Neo4j
+    ImportProject {
+        /// Bundle file to read
+        input: PathBuf,
+        
+        /// Import under this project ID instead of the bundled one
+        #[clap(long)]
+        project_id: Option<String>,
+    },
+    
+    /// Export a project's molecules, evidence and networks as RDF for triple stores
+    #[clap(after_help = "Examples:
+  hegel export-rdf my-project -o project.ttl
+  hegel export-rdf my-project -o project.nt --format ntriples --base-iri https://example.org/hegel/")]
+    ExportRdf {
+        /// Project to export
+        id: String,
+        
+        /// File to write
+        #[clap(short, long)]
+        output: PathBuf,
+        
+        /// RDF serialization (turtle, ntriples)
+        #[clap(long, default_value = "turtle")]
+        format: String,
+        
+        /// Prefix of the IRIs minted for the project's molecules and evidence
+        #[clap(long, default_value = "urn:hegel:")]
+        base_iri: String,
+    },
+    
+    /// Show a stored molecule with its evidence, conflicts and confidence history
+    Inspect {
+        /// Molecule to inspect
+        molecule_id: String,
+        
+        /// Project the molecule belongs to
+        #[clap(long, default_value = DEFAULT_PROJECT)]
+        project: String,
+        
+        /// Number of past confidence values to show
+        #[clap(long, default_value = "10")]
+        history: usize,
+    },
+    
+    /// Record evidence for stored molecules
+    Evidence {
+        #[clap(subcommand)]
+        command: EvidenceCommands,
+    },
+    
+    /// Manage samples and study groups, and compare identifications between groups
+    Cohort {
+        #[clap(subcommand)]
+        command: CohortCommands,
+    },
+    
+    /// Build the in-house reference library and match spectra against it
+    Library {
+        #[clap(subcommand)]
+        command: LibraryCommands,
+    },
+    
+    /// Show the learned reliability of evidence sources and override their weights
+    Reliability {
+        #[clap(subcommand)]
+        command: ReliabilityCommands,
+    },
+    
+    /// Remove raw evidence payloads older than the retention policy allows
+    Gc {
+        /// JSON file containing the retention policy
+        #[clap(short, long)]
+        policy: PathBuf,
+        
+        /// Audit log to append redactions to (defaults to HEGEL_REDACTION_AUDIT_LOG)
+        #[clap(long)]
+        audit_log: Option<PathBuf>,
+        
+        /// List the payloads that would be removed without removing them
+        #[clap(long)]
+        dry_run: bool,
+    },
+    
+    /// Merge stored molecules that share an InChIKey or structure
+    Reconcile {
+        /// Project to reconcile
+        #[clap(long, default_value = DEFAULT_PROJECT)]
+        project: String,
+        
+        /// File to write the merge audit report to
+        #[clap(long, default_value = "reconciliation-report.json")]
+        report: PathBuf,
+        
+        /// Report the merges without making them
+        #[clap(long)]
+        dry_run: bool,
+    },
+    
+    /// Consume evidence messages from Kafka or NATS (configured by HEGEL_STREAM_* variables)
+    #[cfg(feature = "streams")]
+    Stream {
+        /// Stop after this many messages
+        #[clap(long)]
+        limit: Option<u64>,
+        
+        /// Deliveries after which a failing message is dead-lettered
+        #[clap(long, default_value = "3")]
+        max_attempts: u32,
+    },
+    
+    /// Browse stored molecules, their evidence and confidence history interactively
+    #[cfg(feature = "tui")]
+    #[clap(after_help = "Keys: Up/Down move, Enter inspect, Esc back, r re-validate, R reload, ? help, q quit")]
+    Tui {
+        /// Project to browse
+        #[clap(long, default_value = DEFAULT_PROJECT)]
+        project: String,
+    },
+    
+    /// Start the Hegel API server
+    Serve {
+        /// Host to bind to
+        #[clap(short = 'H', long, default_value = "127.0.0.1")]
+        host: String,
+        
+        /// Port to listen on
+        #[clap(short, long, default_value = "8080")]
+        port: u16,
+    },
+    
+    /// Check that Neo4j, the LLM endpoint and the Python API are reachable
+    #[clap(after_help = "Each check prints what was tried and, when it fails, what to change.
+The command exits with an error if any check fails.")]
+    Doctor {
+        /// Seconds to wait for each service
+        #[clap(long, default_value = "5")]
+        timeout: u64,
+    },
+    
+    /// Print a shell completion script
+    #[clap(after_help = "Examples:
+  hegel completions bash > /etc/bash_completion.d/hegel
+  hegel completions zsh > \"${fpath[1]}/_hegel\"
+  hegel completions fish > ~/.config/fish/completions/hegel.fish")]
+    Completions {
+        /// Shell to generate completions for
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+}
+
+/// Subcommands of `hegel network`
+#[derive(Subcommand)]
+enum NetworkCommands {
+    /// Build a network from a set of molecules
+    Build {
+        /// Input file with molecules (one per line)
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// Output file for the network
+        #[clap(short, long)]
+        output: PathBuf,
+        
+        /// Input format (smiles, sdf, csv)
+        #[clap(short, long, default_value = "smiles")]
+        format: String,
+        
+        /// Similarity threshold for network connections (0.0-1.0)
+        #[clap(short, long, default_value = "0.7")]
+        threshold: f64,
+        
+        /// Maximum neighbors per molecule
+        #[clap(short, long, default_value = "10")]
+        max_neighbors: usize,
+        
+        /// Similarity metric to use (tanimoto, dice, or any registered metric)
+        #[clap(long, default_value = "tanimoto")]
+        metric: String,
+    },
+    
+    /// Show the nodes, edges and weights that differ between two networks
+    Diff {
+        /// Network file to compare from
+        base: PathBuf,
+        
+        /// Network file to compare to
+        other: PathBuf,
+    },
+    
+    /// Merge one network into another
+    Merge {
+        /// Network file to merge into
+        base: PathBuf,
+        
+        /// Network file to merge from; treated as newer unless timestamps say otherwise
+        other: PathBuf,
+        
+        /// Output file for the merged network
+        #[clap(short, long)]
+        output: PathBuf,
+        
+        /// How to resolve differing edge weights (max-weight, average, prefer-newer)
+        #[clap(short, long, default_value = "max-weight")]
+        policy: String,
+    },
+    
+    /// Find the cheapest paths between two molecules of a network
+    Path {
+        /// Network file to search
+        network: PathBuf,
+        
+        /// Molecule the paths start at
+        from: String,
+        
+        /// Molecule the paths end at
+        to: String,
+        
+        /// Number of paths to find
+        #[clap(short, long, default_value = "1")]
+        k: usize,
+        
+        /// Longest path allowed, in edges
+        #[clap(long)]
+        max_hops: Option<usize>,
+        
+        /// Count hops instead of costing edges by dissimilarity
+        #[clap(long)]
+        hops: bool,
+    },
+    
+    /// Learn node2vec embeddings and store them as molecule properties
+    Embed {
+        /// Network file to embed
+        network: PathBuf,
+        
+        /// Output file for the network with embeddings
+        #[clap(short, long)]
+        output: PathBuf,
+        
+        /// Length of the embedding vectors
+        #[clap(long, default_value = "32")]
+        dimensions: usize,
+        
+        /// Return parameter p of the walks
+        #[clap(long, default_value = "1.0")]
+        p: f64,
+        
+        /// In-out parameter q of the walks
+        #[clap(long, default_value = "1.0")]
+        q: f64,
+    },
+    
+    /// Compute 2D coordinates for the molecules of a network
+    Layout {
+        /// Network file to lay out
+        network: PathBuf,
+        
+        /// Output file for the network with positions
+        #[clap(short, long)]
+        output: PathBuf,
+        
+        /// Layout algorithm (force-directed, stress)
+        #[clap(short, long, default_value = "force-directed")]
+        algorithm: String,
+        
+        /// Iterations to run (algorithm default if not set)
+        #[clap(long)]
+        iterations: Option<usize>,
+        
+        /// Seed for the starting positions
+        #[clap(long)]
+        seed: Option<u64>,
+    },
+    
+    /// Build a network from MS/MS spectra and group it into molecular families
+    Families {
+        /// MGF file with one spectrum per feature
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// Output file for the network
+        #[clap(short, long)]
+        output: PathBuf,
+        
+        /// Modified cosine threshold for network connections (0.0-1.0)
+        #[clap(short, long, default_value = "0.7")]
+        threshold: f64,
+        
+        /// Fewest shared peaks for two features to be connected
+        #[clap(long, default_value = "6")]
+        min_matched_peaks: usize,
+        
+        /// Fragment m/z tolerance in Da
+        #[clap(long, default_value = "0.02")]
+        tolerance: f64,
+        
+        /// Maximum neighbors per feature
+        #[clap(short, long, default_value = "10")]
+        max_neighbors: usize,
+        
+        /// Largest family before its weakest edges are removed
+        #[clap(long, default_value = "100")]
+        max_family_size: usize,
+        
+        /// Lowest identification confidence to propagate to family members
+        #[clap(long, default_value = "0.7")]
+        min_confidence: f64,
+    },
+    
+    /// Suggest identities for unannotated molecules from identified neighbors
+    Propagate {
+        /// Network file to propagate annotations across
+        network: PathBuf,
+        
+        /// Lowest edge similarity a suggestion travels across
+        #[clap(long, default_value = "0.7")]
+        min_similarity: f64,
+        
+        /// Factor applied to the confidence at every hop
+        #[clap(long, default_value = "0.8")]
+        hop_discount: f64,
+        
+        /// Most edges a suggestion travels across
+        #[clap(long, default_value = "3")]
+        max_hops: usize,
+    },
+    
+    /// Rewrite molecule IDs to InChIKeys, keeping former IDs as aliases
+    MigrateIds {
+        /// Network file to migrate
+        network: PathBuf,
+        
+        /// Output file for the migrated network
+        #[clap(short, long)]
+        output: PathBuf,
+        
+        /// Also rename the molecules stored in Neo4j for this project
+        #[clap(long)]
+        project: Option<String>,
+    },
+}
+
+/// Subcommands of `hegel evidence`
+#[derive(Subcommand)]
+enum EvidenceCommands {
+    /// Enter evidence by hand and re-integrate the molecule
+    #[clap(after_help = "Examples:
+  hegel evidence add --molecule glucose --type structural --confidence 0.95 --note \"1H NMR matches reference standard\"")]
+    Add {
+        /// Molecule the evidence is for
+        #[clap(short, long)]
+        molecule: String,
+        
+        /// Evidence type (genomics, mass_spec, literature, pathway, reactome, structural, other)
+        #[clap(short = 't', long = "type")]
+        evidence_type: String,
+        
+        /// Confidence of the evidence (0.0 - 1.0)
+        #[clap(short, long)]
+        confidence: f64,
+        
+        /// Free-text note, e.g. how the evidence was obtained
+        #[clap(short, long)]
+        note: Option<String>,
+        
+        /// Project the molecule belongs to
+        #[clap(long, default_value = DEFAULT_PROJECT)]
+        project: String,
+        
+        /// Person entering the evidence (defaults to the current user)
+        #[clap(long)]
+        user: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CohortCommands {
+    /// Store the samples and study groups of a study design
+    Import {
+        /// JSON file with `groups` and `samples` arrays
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// Project the samples belong to
+        #[clap(long, default_value = DEFAULT_PROJECT)]
+        project: String,
+    },
+    
+    /// Compare how confidently a molecule is identified in study groups
+    #[clap(after_help = "Examples:
+  hegel cohort compare --molecule kynurenine --group cases --group controls")]
+    Compare {
+        /// Molecule to compare
+        #[clap(short, long)]
+        molecule: String,
+        
+        /// Study groups to compare, in order
+        #[clap(short, long = "group", required = true)]
+        groups: Vec<String>,
+        
+        /// Project the molecule belongs to
+        #[clap(long, default_value = DEFAULT_PROJECT)]
+        project: String,
+    },
+}
+
+/// Subcommands of `hegel reliability`
+#[derive(Subcommand)]
+enum ReliabilityCommands {
+    /// List the reliability learned for each source and the weight integration uses
+    Show,
+    
+    /// Weigh a source by hand instead of by its learned reliability
+    #[clap(after_help = "Examples:
+  hegel reliability override --source in-house-nmr --weight 0.95")]
+    Override {
+        /// Evidence source to weigh
+        #[clap(short, long)]
+        source: String,
+        
+        /// Weight of the source (0.0 - 1.0)
+        #[clap(short, long)]
+        weight: f64,
+    },
+    
+    /// Return a source to its learned weight
+    Clear {
+        /// Evidence source whose override is removed
+        #[clap(short, long)]
+        source: String,
+    },
+}
+
+/// Subcommands of `hegel library`
+#[derive(Subcommand)]
+enum LibraryCommands {
+    /// Promote confidently identified molecules and their best spectra into the library
+    #[clap(after_help = "Examples:
+  hegel library promote --input evidence.json --compounds compounds.json --library lab-library.json
+  hegel library promote --input evidence.json --compounds compounds.json --min-confidence 0.95 --require-spectrum")]
+    Promote {
+        /// JSON file containing an array of evidence items
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// JSON file containing the structures of the molecules
+        #[clap(short, long)]
+        compounds: PathBuf,
+        
+        /// Library file (defaults to HEGEL_REFERENCE_LIBRARY or reference_library.json)
+        #[clap(short, long)]
+        library: Option<PathBuf>,
+        
+        /// Lowest aggregate confidence promoted
+        #[clap(long, default_value = "0.9")]
+        min_confidence: f64,
+        
+        /// Only promote molecules with an MS/MS spectrum
+        #[clap(long)]
+        require_spectrum: bool,
+    },
+    
+    /// Search MS/MS spectra against the library and write the hits as evidence
+    #[clap(after_help = "Examples:
+  hegel library match --input features.mgf --output library-evidence.json
+  hegel batch --input library-evidence.json --output-file results.csv --output-format csv")]
+    Match {
+        /// MGF file of the spectra to identify
+        #[clap(short, long)]
+        input: PathBuf,
+        
+        /// Library file (defaults to HEGEL_REFERENCE_LIBRARY or reference_library.json)
+        #[clap(short, long)]
+        library: Option<PathBuf>,
+        
+        /// File to write the evidence array to
+        #[clap(long)]
+        output: PathBuf,
+        
+        /// Lowest modified cosine similarity reported
+        #[clap(long, default_value = "0.7")]
+        min_score: f64,
+        
+        /// Precursor tolerance in ppm
+        #[clap(long, default_value = "10")]
+        ppm: f64,
+    },
+}
+
+/// Main entry point
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Parse command-line arguments
+    let cli = Cli::parse();
+    
+    // Completion scripts go to stdout untouched by logging or engine start-up
+    if let Commands::Completions { shell } = &cli.command {
+        clap_complete::generate(*shell, &mut Cli::command(), "hegel", &mut std::io::stdout());
+        return Ok(());
+    }
+    
+    // Configure logging
+    if std::env::var("RUST_LOG").is_err() {
+        if cli.verbose {
+            std::env::set_var("RUST_LOG", "debug");
+        } else {
+            std::env::set_var("RUST_LOG", "info");
+        }
+    }
+    env_logger::init();
+    
+    // Initialize the Hegel core engine
+    hegel::initialize()?;
+    if let Some(seed) = cli.seed {
+        hegel::rng::set_global_seed(Some(seed));
+    }
+    if cli.offline {
+        hegel::offline::set_offline(true);
+    }
+    
+    // Process the requested command
+    match &cli.command {
+        Commands::Validate { molecule, id_type, threshold, policy } => {
+            validate_molecule(molecule, id_type, *threshold, policy.as_ref(), &cli.output).await?;
+        }
+        
+        Commands::ValidateFile { input, report, output, format, allow_elements, keep_fragments } => {
+            validate_library_file(input, report, output.as_ref(), format.as_deref(), allow_elements.as_deref(), *keep_fragments, &cli.output)?;
+        }
+        
+        Commands::Process { molecule, id_type, pathways, interactions } => {
+            process_molecule(molecule, id_type, *pathways, *interactions, &cli.output).await?;
+        }
+        
+        Commands::Compare { molecule1, molecule2, id_type, metric } => {
+            compare_molecules(molecule1, molecule2, id_type, metric, &cli.output).await?;
+        }
+        
+        Commands::Network { command } => match command {
+            NetworkCommands::Build { input, output, format, threshold, max_neighbors, metric } => {
+                build_network(input, output, format, *threshold, *max_neighbors, metric, &cli.output).await?;
+            }
+            NetworkCommands::Diff { base, other } => {
+                diff_networks(base, other, &cli.output)?;
+            }
+            NetworkCommands::Merge { base, other, output, policy } => {
+                merge_networks(base, other, output, policy, &cli.output)?;
+            }
+            NetworkCommands::Path { network, from, to, k, max_hops, hops } => {
+                find_network_paths(network, from, to, *k, *max_hops, *hops, &cli.output)?;
+            }
+            NetworkCommands::Embed { network, output, dimensions, p, q } => {
+                embed_network(network, output, *dimensions, *p, *q, &cli.output)?;
+            }
+            NetworkCommands::Layout { network, output, algorithm, iterations, seed } => {
+                let mut options = LayoutOptions::default().with_algorithm(algorithm.parse::<LayoutAlgorithm>()?);
+                options.iterations = *iterations;
+                options.seed = *seed;
+                layout_network(network, output, &options, &cli.output)?;
+            }
+            NetworkCommands::Families { input, output, threshold, min_matched_peaks, tolerance, max_neighbors, max_family_size, min_confidence } => {
+                let similarity = ModifiedCosineSimilarity { tolerance: *tolerance, min_matched_peaks: *min_matched_peaks };
+                let options = FamilyOptions {
+                    max_family_size: *max_family_size,
+                    min_identification_confidence: *min_confidence,
+                    ..Default::default()
+                };
+                build_molecular_families(input, output, *threshold, *max_neighbors, similarity, &options, &cli.output)?;
+            }
+            NetworkCommands::Propagate { network, min_similarity, hop_discount, max_hops } => {
+                let options = PropagationOptions {
+                    min_similarity: *min_similarity,
+                    hop_discount: *hop_discount,
+                    max_hops: *max_hops,
+                    ..Default::default()
+                };
+                propagate_annotations(network, &options, &cli.output)?;
+            }
+            NetworkCommands::MigrateIds { network, output, project } => {
+                migrate_network_ids(network, output, project.as_deref(), &cli.output).await?;
+            }
+        },
+        
+        Commands::Ablate { input, molecule, by_source } => {
+            ablate_evidence(input, molecule, *by_source, &cli.output).await?;
+        }
+        
+        Commands::Cluster { input, k, max_k, method } => {
+            cluster_evidence(input, *k, *max_k, method, &cli.output)?;
+        }
+        
+        Commands::Drift { input, output, project, baseline_runs } => {
+            detect_drift(input, output.as_ref(), project, *baseline_runs, &cli.output).await?;
+        }
+        
+        Commands::Qc { input, panel, output, project, action } => {
+            check_qc(input, panel, output.as_ref(), project, action, &cli.output).await?;
+        }
+        
+        Commands::Batch { input, output_file, output_format } => {
+            batch_integrate(input, output_file, output_format, &cli.output).await?;
+        }
+        
+        Commands::Report { input, molecule, conflict_graph, conflict_format } => {
+            report(input, molecule, conflict_graph.as_ref(), conflict_format, &cli.output).await?;
+        }
+        
+        Commands::Plan { input, threshold, max_recommendations } => {
+            plan_acquisition(input, *threshold, *max_recommendations, &cli.output)?;
+        }
+        
+        Commands::MassSpec { input, molecule, profile } => {
+            process_mass_spec(input, molecule, profile.as_deref(), &cli.output).await?;
+        }
+        
+        Commands::Features { input, compounds, output, library, format, ppm, rt_tolerance, polarity } => {
+            let format = format.as_deref().map(str::parse::<FeatureTableFormat>).transpose()?;
+            let options = FeatureMatchOptions {
+                mass_tolerance: Quantity::ppm(*ppm),
+                rt_tolerance: Quantity::minutes(*rt_tolerance),
+                polarity: polarity.parse::<Polarity>()?,
+                ..Default::default()
+            };
+            match_features(input, compounds, library.as_ref(), output, format, &options, &cli.output)?;
+        }
+        
+        Commands::Align { inputs, compounds, output, alignment, reference, format, ppm, rt_tolerance, max_drift, polarity } => {
+            let format = format.as_deref().map(str::parse::<FeatureTableFormat>).transpose()?;
+            let match_options = FeatureMatchOptions {
+                mass_tolerance: Quantity::ppm(*ppm),
+                polarity: polarity.parse::<Polarity>()?,
+                ..Default::default()
+            };
+            let options = AlignmentOptions {
+                mass_tolerance: Quantity::ppm(*ppm),
+                rt_tolerance: Quantity::minutes(*rt_tolerance),
+                max_rt_drift: Quantity::minutes(*max_drift),
+                reference_run: reference.clone(),
+                ..Default::default()
+            };
+            align_feature_runs(inputs, format, compounds, output, alignment.as_ref(), &match_options, &options, &cli.output)?;
+        }
+        
+        Commands::GeneLinks { input, kegg, reactome_genes, reactome_metabolites, species, max_distance, output } => {
+            let mut map = GeneMetaboliteMap::new();
+            for path in kegg {
+                map.load_kegg(path)?;
+            }
+            if let Some(path) = reactome_genes {
+                map.load_reactome(path, ReactomeEntity::Gene, species.as_deref())?;
+            }
+            if let Some(path) = reactome_metabolites {
+                map.load_reactome(path, ReactomeEntity::Metabolite, species.as_deref())?;
+            }
+            let options = EnzymeLinkOptions { max_distance: *max_distance, ..Default::default() };
+            link_genes_to_metabolites(input, &map, &options, output, &cli.output)?;
+        }
+        
+        Commands::Targets { chembl, bindingdb, molecule, observed, graph, output } => {
+            let mut activities = TargetActivities::new();
+            for path in chembl {
+                activities.load(path, ActivitySource::ChEMBL)?;
+            }
+            for path in bindingdb {
+                activities.load(path, ActivitySource::BindingDB)?;
+            }
+            report_target_evidence(&activities, molecule, observed.as_ref(), graph.as_ref(), output, &cli.output)?;
+        }
+        
+        Commands::Claims { input, molecule, synonyms, id, output, claims } => {
+            let molecule_id = id.as_deref().unwrap_or(molecule);
+            extract_literature_claims(input, molecule, synonyms, molecule_id, output, claims.as_ref(), &cli.output).await?;
+        }
+        
+        Commands::Link { input, model, train, save_model, molecule, output } => {
+            link_database_records(
+                input,
+                model.as_ref(),
+                train.as_ref(),
+                save_model.as_ref(),
+                molecule.as_deref(),
+                output.as_ref(),
+                &cli.output,
+            )?;
+        }
+        
+        Commands::Mztab { input, compounds, evidence, output, id, description, format, ppm, rt_tolerance, polarity } => {
+            let format = format.as_deref().map(str::parse::<FeatureTableFormat>).transpose()?;
+            let polarity = polarity.parse::<Polarity>()?;
+            let options = FeatureMatchOptions {
+                mass_tolerance: Quantity::ppm(*ppm),
+                rt_tolerance: Quantity::minutes(*rt_tolerance),
+                polarity,
+                ..Default::default()
+            };
+            let mztab_options = MzTabOptions {
+                description: description.clone(),
+                polarity,
+                ..MzTabOptions::new(id)
+            };
+            export_mztab(input, format, compounds, evidence.as_ref(), output, &options, mztab_options, &cli.output).await?;
+        }
+        
+        Commands::ExportProject { id, output } => {
+            export_project(id, output, &cli.output).await?;
+        }
+        
+        Commands::ImportProject { input, project_id } => {
+            import_project(input, project_id.as_deref(), &cli.output).await?;
+        }
+        
+        Commands::ExportRdf { id, output, format, base_iri } => {
+            export_rdf(id, output, format, base_iri, &cli.output).await?;
+        }
+        
+        Commands::Inspect { molecule_id, project, history } => {
+            inspect_molecule(molecule_id, project, *history, &cli.output).await?;
+        }
+        
+        Commands::Evidence { command } => match command {
+            EvidenceCommands::Add { molecule, evidence_type, confidence, note, project, user } => {
+                add_evidence(molecule, evidence_type, *confidence, note.as_deref(), project, user.as_deref(), &cli.output).await?;
+            }
+        },
+        
+        Commands::Cohort { command } => match command {
+            CohortCommands::Import { input, project } => {
+                import_study_design(input, project, &cli.output).await?;
+            }
+            CohortCommands::Compare { molecule, groups, project } => {
+                compare_study_groups(molecule, groups, project, &cli.output).await?;
+            }
+        },
+        
+        Commands::Library { command } => match command {
+            LibraryCommands::Promote { input, compounds, library, min_confidence, require_spectrum } => {
+                let criteria = PromotionCriteria {
+                    min_confidence: *min_confidence,
+                    require_spectrum: *require_spectrum,
+                    ..Default::default()
+                };
+                promote_to_library(input, compounds, library.as_ref(), &criteria, &cli.output).await?;
+            }
+            LibraryCommands::Match { input, library, output, min_score, ppm } => {
+                let options = LibrarySearchOptions {
+                    precursor_tolerance: Quantity::ppm(*ppm),
+                    min_score: *min_score,
+                    ..Default::default()
+                };
+                match_library_spectra(input, library.as_ref(), output, &options, &cli.output)?;
+            }
+        },
+        
+        Commands::Reliability { command } => {
+            let mut tracker = ReliabilityTracker::from_env()?;
+            match command {
+                ReliabilityCommands::Show => {}
+                ReliabilityCommands::Override { source, weight } => tracker.set_override(source, *weight)?,
+                ReliabilityCommands::Clear { source } => tracker.clear_override(source)?,
+            }
+            show_reliability(&tracker, &cli.output);
+        }
+        
+        Commands::Gc { policy, audit_log, dry_run } => {
+            collect_garbage(policy, audit_log.as_ref(), *dry_run, &cli.output).await?;
+        }
+        
+        Commands::Reconcile { project, report, dry_run } => {
+            reconcile_molecules(project, report, *dry_run, &cli.output).await?;
+        }
+        
+        #[cfg(feature = "streams")]
+        Commands::Stream { limit, max_attempts } => {
+            consume_stream(*limit, *max_attempts, &cli.output).await?;
+        }
+        
+        #[cfg(feature = "tui")]
+        Commands::Tui { project } => {
+            hegel::tui::run(&Neo4jClient::from_env()?, project).await?;
+        }
+        
+        Commands::Serve { host, port } => {
+            serve_api(host, *port).await?;
+        }
+        
+        Commands::Doctor { timeout } => {
+            run_doctor(Duration::from_secs(*timeout), &cli.output).await?;
+        }
+        
+        Commands::Completions { .. } => unreachable!("completions are generated before start-up"),
+    }
+    
+    Ok(())
+}
+
+/// Print a record as one line of JSON, flushed so piped consumers see it immediately
+fn emit_jsonl<T: Serialize>(record: &T) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer(&mut stdout, record)?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Print how much of a confidence each contributor accounts for
+fn print_contributions(decomposition: &ConfidenceDecomposition) {
+    println!("  Contributions ({}):", decomposition.method);
+    for contribution in &decomposition.contributions {
+        println!("    {:+.1}% {}", contribution.contribution * 100.0, contribution.key);
+    }
+}
+
+/// Token cancelled when the user presses Ctrl-C, so long work stops cleanly
+fn interrupt_token() -> CancellationToken {
+    let token = CancellationToken::new();
+    let interrupt = token.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            info!("Interrupted, cancelling");
+            interrupt.cancel();
+        }
+    });
+    token
+}
+
+/// Validate a molecule's identity
+async fn validate_molecule(molecule: &str, id_type: &str, threshold: f64, policy: Option<&PathBuf>, output_format: &str) -> Result<()> {
+    info!("Validating molecule: {}", molecule);
+    let start_time = Instant::now();
+    
+    // Create a metacognition system
+    let mut system = MetacognitionSystem::new()?;
+    if let Some(path) = policy {
+        system = system.with_identity_policy(IdentityPolicy::from_file(path)?);
+    }
+    
+    // Parse the ID type
+    let mol_id_type = parse_id_type(molecule, id_type)?;
+    
+    // Process the molecule
+    let validation = system.validate_molecule_identity(molecule).await?;
+    
+    // Output the results based on the format
+    let elapsed = start_time.elapsed();
+    
+    match output_format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&validation)?);
+        }
+        "jsonl" => emit_jsonl(&validation)?,
+        "csv" => {
+            println!("molecule_id,is_valid,confidence,trend,explanation");
+            println!("{},{},{},{},\"{}\"", 
+                     validation.molecule_id,
+                     validation.is_valid,
+                     validation.confidence,
+                     validation.trend.map(|t| t.to_string()).unwrap_or_default(),
+                     validation.explanation.replace("\"", "\"\""));
+        }
+        _ => {
+            println!("Validation Results:");
+            println!("  Molecule ID: {}", validation.molecule_id);
+            println!("  Valid: {}", if validation.is_valid { "YES" } else { "NO" });
+            println!("  Confidence: {:.1}%", validation.confidence * 100.0);
+            if let Some(trend) = validation.trend {
+                println!("  Trend: {}", trend);
+            }
+            println!("  Explanation: {}", validation.explanation);
+            for requirement in &validation.unmet_requirements {
+                println!("  Missing: {}", requirement);
+            }
+            if let Some(coverage) = &validation.coverage {
+                println!("  Evidence coverage: {:.0}%", coverage.score * 100.0);
+                for suggestion in &coverage.suggestions {
+                    println!("    +{:.0}% {}: {}", suggestion.score_gain * 100.0, suggestion.category, suggestion.rationale);
+                }
+            }
+            if let Some(decomposition) = &validation.contributions {
+                print_contributions(decomposition);
+            }
+            println!();
+            println!("Time taken: {:.2?}", elapsed);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Validate and standardize a compound library, writing rejected records to a CSV report
+fn validate_library_file(
+    input: &PathBuf,
+    report_path: &PathBuf,
+    output: Option<&PathBuf>,
+    format: Option<&str>,
+    allow_elements: Option<&str>,
+    keep_fragments: bool,
+    output_format: &str,
+) -> Result<()> {
+    let format = match format {
+        Some(format) => format.parse()?,
+        None => LibraryFormat::from_path(input)?,
+    };
+    let mut options = ValidationOptions::default().with_keep_largest_fragment(!keep_fragments);
+    if let Some(elements) = allow_elements {
+        options = options.with_allowed_elements(ValidationOptions::parse_elements(elements));
+    }
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read library: {}", input.display()))?;
+    
+    let start = Instant::now();
+    let report = validate_library(&content, format, &options)?;
+    report.write_error_csv(report_path)?;
+    if let Some(path) = output {
+        report.write_smiles(path)?;
+    }
+    let elapsed = start.elapsed();
+    let failures = report.failure_counts();
+    
+    match output_format {
+        "json" => {
+            let summary = json!({
+                "records": report.records,
+                "standardized": report.entries.len(),
+                "rejected": report.errors.len(),
+                "fragments_stripped": report.stripped(),
+                "failures": failures,
+                "report": report_path,
+                "output": output,
+                "elapsed_ms": elapsed.as_millis(),
+            });
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        }
+        "jsonl" => {
+            for error in &report.errors {
+                emit_jsonl(error)?;
+            }
+        }
+        "csv" => {
+            println!("records,standardized,rejected,syntax,valence,disallowed_element");
+            println!("{},{},{},{},{},{}", report.records, report.entries.len(), report.errors.len(),
+                     failures.get(&FailureKind::Syntax).unwrap_or(&0),
+                     failures.get(&FailureKind::Valence).unwrap_or(&0),
+                     failures.get(&FailureKind::DisallowedElement).unwrap_or(&0));
+        }
+        _ => {
+            println!("Library Validation:");
+            println!("  Records: {}", report.records);
+            println!("  Standardized: {} ({} with fragments stripped)", report.entries.len(), report.stripped());
+            println!("  Rejected: {}", report.errors.len());
+            for (kind, count) in &failures {
+                println!("    {}: {}", kind, count);
+            }
+            println!("  Error report: {}", report_path.display());
+            if let Some(path) = output {
+                println!("  Standardized library: {}", path.display());
+            }
+            println!("  Time: {:.2?}", elapsed);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Process a molecule to extract properties and relationships
+async fn process_molecule(molecule: &str, id_type: &str, include_pathways: bool, include_interactions: bool, output_format: &str) -> Result<()> {
+    info!("Processing molecule: {}", molecule);
+    let start_time = Instant::now();
+    
+    // Create a metacognition system
+    let system = MetacognitionSystem::new()?;
+    
+    // Parse the ID type
+    let mol_id_type = parse_id_type(molecule, id_type)?;
+    
+    // Process the molecule
+    let response = system.process_molecule(molecule, mol_id_type, &interrupt_token()).await?;
+    
+    // Output the results based on the format
+    let elapsed = start_time.elapsed();
+    
+    match output_format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&response)?);
+        }
+        "csv" => {
+            // Basic molecule info
+            println!("type,key,value");
+            println!("info,id,{}", response.id);
+            println!("info,name,{}", response.name.unwrap_or_default());
+            println!("info,formula,{}", response.formula.unwrap_or_default());
+            println!("info,smiles,{}", response.smiles);
+            
+            // Properties
+            for (key, value) in response.properties {
+                println!("property,{},{}", key, value);
+            }
+            
+            // Related entities
+            for related in response.related_entities {
+                println!("related,{},{},{}", 
+                         related.relation_type,
+                         related.entity_id,
+                         related.entity_name.unwrap_or_default());
+            }
+        }
+        _ => {
+            println!("Molecule Processing Results:");
+            println!("  ID: {}", response.id);
+            if let Some(name) = &response.name {
+                println!("  Name: {}", name);
+            }
+            if let Some(formula) = &response.formula {
+                println!("  Formula: {}", formula);
+            }
+            println!("  SMILES: {}", response.smiles);
+            
+            println!("\nProperties:");
+            for (key, value) in &response.properties {
+                println!("  {}: {}", key, value);
+            }
+            
+            if !response.related_entities.is_empty() {
+                println!("\nRelated Entities:");
+                for related in &response.related_entities {
+                    println!("  {} {} ({})", 
+                             related.relation_type,
+                             related.entity_name.as_deref().unwrap_or(&related.entity_id),
+                             related.entity_id);
+                }
+            }
+            
+            println!();
+            println!("Time taken: {:.2?}", elapsed);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Compare two molecules
+async fn compare_molecules(
+    molecule1: &str,
+    molecule2: &str,
+    id_type: &str,
+    metric: &str,
+    output_format: &str,
+) -> Result<()> {
+    info!("Comparing molecules: {} and {}", molecule1, molecule2);
+    let start_time = Instant::now();
+    
+    // Parse the ID type
+    let type1 = parse_id_type(molecule1, id_type)?;
+    let type2 = parse_id_type(molecule2, id_type)?;
+    
+    // Create molecules
+    let mol1 = Molecule::from_identifier(molecule1, &type1)?;
+    let mol2 = Molecule::from_identifier(molecule2, &type2)?;
+    
+    // Calculate similarity with the requested metric
+    let similarity = SimilarityRegistry::global().compute(metric, &mol1, &mol2)?;
+    
+    // Create a metacognition system
+    let system = MetacognitionSystem::new()?;
+    
+    // Get additional analysis via LLM (if available)
+    let llm_interface = system.get_llm_interface();
+    let analysis = if let Some(interface) = llm_interface {
+        // Convert molecules to the format expected by the LLM interface
+        let mol1_data = convert_to_llm_molecule(&mol1);
+        let mol2_data = convert_to_llm_molecule(&mol2);
+        
+        // Get analysis
+        match interface.compare_molecules(&mol1_data, &mol2_data).await {
+            Ok(comparison) => Some(comparison),
+            Err(_) => None,
+        }
+    } else {
+        None
+    };
+    
+    // Output the results based on the format
+    let elapsed = start_time.elapsed();
+    
+    match output_format {
+        "json" | "jsonl" => {
+            let result = json!({
+                "molecule1": {
+                    "id": mol1.id,
+                    "smiles": mol1.smiles,
+                    "name": mol1.name,
+                },
+                "molecule2": {
+                    "id": mol2.id,
+                    "smiles": mol2.smiles,
+                    "name": mol2.name,
+                },
+                "similarity": similarity,
+                "metric": metric,
+                "analysis": analysis.map(|a| a.analysis),
+                "same_entity": analysis.map(|a| a.same_entity),
+            });
+            if output_format == "jsonl" {
+                emit_jsonl(&result)?;
+            } else {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            }
+        }
+        "csv" => {
+            println!("molecule1,molecule2,similarity,same_entity");
+            println!("{},{},{},{}",
+                     mol1.id,
+                     mol2.id,
+                     similarity,
+                     analysis.as_ref().map(|a| a.same_entity).unwrap_or(similarity > 0.8));
+        }
+        _ => {
+            println!("Molecule Comparison:");
+            println!("  Molecule 1: {} ({})", mol1.name.as_deref().unwrap_or(&mol1.id), mol1.smiles);
+            println!("  Molecule 2: {} ({})", mol2.name.as_deref().unwrap_or(&mol2.id), mol2.smiles);
+            println!("  Similarity ({}): {:.1}%", metric, similarity * 100.0);
+            
+            if let Some(a) = analysis {
+                println!("\nAnalysis:");
+                println!("  {}", a.analysis);
+                println!("  Same entity: {}", if a.same_entity { "YES" } else { "NO" });
+            }
+            
+            println!();
+            println!("Time taken: {:.2?}", elapsed);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Build a network from a set of molecules
+async fn build_network(
+    input: &PathBuf,
+    output: &PathBuf,
+    format: &str,
+    threshold: f64,
+    max_neighbors: usize,
+    metric: &str,
+    output_format: &str,
+) -> Result<()> {
+    info!("Building network from file: {}", input.display());
+    let start_time = Instant::now();
+    
+    // Parse the input format
+    let mol_format = match format {
+        "smiles" => MoleculeFormat::Smiles,
+        "sdf" => MoleculeFormat::Sdf,
+        "csv" => MoleculeFormat::Csv,
+        _ => return Err(anyhow!("Unsupported input format: {}", format)),
+    };
+    
+    // Read molecules from the input file
+    let molecules = Molecule::read_from_file(input, mol_format)?;
+    info!("Read {} molecules from input file", molecules.len());
+    
+    // Create a network builder
+    let mut builder = NetworkBuilder::new(threshold, max_neighbors).with_metric(metric);
+    
+    // Add molecules to the network
+    builder.add_molecules(&molecules)?;
+    builder.build_similarities()?;
+    
+    // Build the network
+    let network = builder.build();
+    info!("Built network with {} nodes and {} edges", 
+          network.get_molecules().len(), 
+          network.calculate_metrics().edge_count);
+    
+    // Calculate network metrics
+    let metrics = network.calculate_metrics();
+    
+    // Serialize the network
+    let serialized = network.to_serializable();
+    
+    // Write the network to the output file
+    let json = serde_json::to_string_pretty(&serialized)?;
+    std::fs::write(output, json)?;
+    info!("Wrote network to file: {}", output.display());
+    
+    // Output the results based on the format
+    let elapsed = start_time.elapsed();
+    
+    match output_format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&metrics)?);
+        }
+        "jsonl" => emit_jsonl(&metrics)?,
+        "csv" => {
+            println!("metric,value");
+            println!("nodes,{}", metrics.node_count);
+            println!("edges,{}", metrics.edge_count);
+            println!("density,{}", metrics.density);
+            println!("avg_degree,{}", metrics.avg_degree);
+            println!("max_degree,{}", metrics.max_degree);
+        }
+        _ => {
+            println!("Network Building Results:");
+            println!("  Input file: {}", input.display());
+            println!("  Output file: {}", output.display());
+            println!("  Molecules read: {}", molecules.len());
+            println!("  Nodes in network: {}", metrics.node_count);
+            println!("  Edges in network: {}", metrics.edge_count);
+            println!("  Network density: {:.3}", metrics.density);
+            println!("  Average degree: {:.2}", metrics.avg_degree);
+            println!("  Maximum degree: {}", metrics.max_degree);
+            if let Some(seed) = serialized.metadata.get(hegel::rng::SEED_METADATA_KEY) {
+                println!("  RNG seed: {}", seed);
+            }
+            
+            if !metrics.clusters.is_empty() {
+                println!("\nClusters:");
+                for (i, size) in metrics.clusters.iter().enumerate() {
+                    println!("  Cluster {}: {} nodes", i + 1, size);
+                }
+            }
+            
+            println!();
+            println!("Time taken: {:.2?}", elapsed);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Read a network written by `hegel network build`
+fn read_network(path: &PathBuf) -> Result<MoleculeNetwork> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read network file: {}", path.display()))?;
+    let serialized: SerializableNetwork = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid network file: {}", path.display()))?;
+    let network = MoleculeNetwork::from_serializable(&serialized)?;
+    if !network.uses_current_ids() {
+        warn!("Network {} uses legacy molecule IDs; run `hegel network migrate-ids` to update it", path.display());
+    }
+    Ok(network)
+}
+
+/// Compare two network files
+fn diff_networks(base: &PathBuf, other: &PathBuf, output_format: &str) -> Result<()> {
+    let diff = read_network(base)?.diff(&read_network(other)?);
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&diff)?),
+        "jsonl" => {
+            for id in &diff.added_nodes {
+                emit_jsonl(&json!({"change": "added_node", "id": id}))?;
+            }
+            for id in &diff.removed_nodes {
+                emit_jsonl(&json!({"change": "removed_node", "id": id}))?;
+            }
+            for edge in &diff.added_edges {
+                emit_jsonl(&json!({"change": "added_edge", "source": edge.source, "target": edge.target, "after": edge.weight}))?;
+            }
+            for edge in &diff.removed_edges {
+                emit_jsonl(&json!({"change": "removed_edge", "source": edge.source, "target": edge.target, "before": edge.weight}))?;
+            }
+            for change in &diff.weight_changes {
+                emit_jsonl(&json!({"change": "weight_change", "source": change.source, "target": change.target,
+                                   "before": change.before, "after": change.after}))?;
+            }
+        }
+        "csv" => {
+            println!("change,source,target,before,after");
+            for id in &diff.added_nodes {
+                println!("added_node,{},,,", id);
+            }
+            for id in &diff.removed_nodes {
+                println!("removed_node,{},,,", id);
+            }
+            for edge in &diff.added_edges {
+                println!("added_edge,{},{},,{}", edge.source, edge.target, edge.weight);
+            }
+            for edge in &diff.removed_edges {
+                println!("removed_edge,{},{},{},", edge.source, edge.target, edge.weight);
+            }
+            for change in &diff.weight_changes {
+                println!("weight_change,{},{},{},{}", change.source, change.target, change.before, change.after);
+            }
+        }
+        _ => {
+            if diff.is_empty() {
+                println!("Networks are identical");
+                return Ok(());
+            }
+            println!("Network Diff ({} -> {}):", base.display(), other.display());
+            for id in &diff.added_nodes {
+                println!("  + node {}", id);
+            }
+            for id in &diff.removed_nodes {
+                println!("  - node {}", id);
+            }
+            for edge in &diff.added_edges {
+                println!("  + edge {} - {} ({:.3})", edge.source, edge.target, edge.weight);
+            }
+            for edge in &diff.removed_edges {
+                println!("  - edge {} - {} ({:.3})", edge.source, edge.target, edge.weight);
+            }
+            for change in &diff.weight_changes {
+                println!("  ~ edge {} - {} ({:.3} -> {:.3})", change.source, change.target, change.before, change.after);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Merge two network files into a third
+fn merge_networks(base: &PathBuf, other: &PathBuf, output: &PathBuf, policy: &str, output_format: &str) -> Result<()> {
+    let policy: MergePolicy = policy.parse()?;
+    let mut network = read_network(base)?;
+    let report = network.merge(&read_network(other)?, policy);
+    
+    let json = serde_json::to_string_pretty(&network.to_serializable())?;
+    std::fs::write(output, json)?;
+    info!("Wrote merged network to file: {}", output.display());
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "jsonl" => emit_jsonl(&report)?,
+        _ => {
+            println!("Merged {} into {} ({:?})", other.display(), base.display(), policy);
+            println!("  Output file: {}", output.display());
+            println!("  Nodes added: {}", report.nodes_added);
+            println!("  Edges added: {}", report.edges_added);
+            println!("  Conflicting weights resolved: {}", report.conflicts.len());
+            for conflict in &report.conflicts {
+                println!("    {} - {}: {:.3} -> {:.3}", conflict.source, conflict.target, conflict.before, conflict.after);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Print the cheapest paths between two molecules of a network file
+fn find_network_paths(
+    network: &PathBuf,
+    from: &str,
+    to: &str,
+    k: usize,
+    max_hops: Option<usize>,
+    hops: bool,
+    output_format: &str,
+) -> Result<()> {
+    let mut options = PathOptions::default();
+    if let Some(max_hops) = max_hops {
+        options = options.with_max_hops(max_hops);
+    }
+    if hops {
+        options = options.with_cost(PathCost::Hops);
+    }
+    let paths = read_network(network)?.k_shortest_paths(from, to, k, &options)?;
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&paths)?),
+        "jsonl" => {
+            for path in &paths {
+                emit_jsonl(path)?;
+            }
+        }
+        _ => {
+            if paths.is_empty() {
+                println!("No path between {} and {}", from, to);
+                return Ok(());
+            }
+            println!("Paths from {} to {}:", from, to);
+            for (i, path) in paths.iter().enumerate() {
+                println!("  {}. {} (cost {:.3}, {} hops)", i + 1, path.molecules.join(" -> "), path.cost, path.hops());
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Embed the molecules of a network file and write it back out
+fn embed_network(network: &PathBuf, output: &PathBuf, dimensions: usize, p: f64, q: f64, output_format: &str) -> Result<()> {
+    let mut network = read_network(network)?;
+    let options = EmbeddingOptions::default()
+        .with_dimensions(dimensions)
+        .with_bias(p, q);
+    let embeddings = network.embed(&options)?;
+    let stored = network.store_embeddings(&embeddings);
+    
+    let json = serde_json::to_string_pretty(&network.to_serializable())?;
+    std::fs::write(output, json)?;
+    info!("Wrote embedded network to file: {}", output.display());
+    
+    match output_format {
+        "json" | "jsonl" => {
+            let summary = json!({
+                "molecules": stored,
+                "dimensions": embeddings.dimensions,
+                "seed": embeddings.seed,
+            });
+            if output_format == "jsonl" {
+                emit_jsonl(&summary)?;
+            } else {
+                println!("{}", serde_json::to_string_pretty(&summary)?);
+            }
+        }
+        _ => {
+            println!("Embedded {} molecules in {} dimensions (seed {})", stored, embeddings.dimensions, embeddings.seed);
+            println!("  Output file: {}", output.display());
+            println!("  Compare molecules with the `embedding` similarity metric");
+        }
+    }
+    
+    Ok(())
+}
+
+/// Lay out the molecules of a network file and write it back out with their positions
+fn layout_network(network: &PathBuf, output: &PathBuf, options: &LayoutOptions, output_format: &str) -> Result<()> {
+    let mut network = read_network(network)?;
+    let layout = network.layout(options)?;
+    let stored = network.store_layout(&layout);
+    
+    let json = serde_json::to_string_pretty(&network.to_serializable())?;
+    std::fs::write(output, json)?;
+    info!("Wrote laid out network to file: {}", output.display());
+    
+    match output_format {
+        "json" | "jsonl" => {
+            let summary = json!({
+                "molecules": stored,
+                "algorithm": layout.algorithm,
+                "seed": layout.seed,
+            });
+            if output_format == "jsonl" {
+                emit_jsonl(&summary)?;
+            } else {
+                println!("{}", serde_json::to_string_pretty(&summary)?);
+            }
+        }
+        _ => {
+            println!("Laid out {} molecules with the {} layout (seed {})", stored, layout.algorithm, layout.seed);
+            println!("  Output file: {}", output.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Build a modified cosine network from an MGF file and annotate its molecular families
+fn build_molecular_families(
+    input: &PathBuf,
+    output: &PathBuf,
+    threshold: f64,
+    max_neighbors: usize,
+    similarity: ModifiedCosineSimilarity,
+    options: &FamilyOptions,
+    output_format: &str,
+) -> Result<()> {
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read MGF file: {}", input.display()))?;
+    let features = parse_mgf(&content).map_err(|e| anyhow!("Invalid MGF file {}: {}", input.display(), e))?;
+    info!("Read {} MS/MS spectra from {}", features.len(), input.display());
+    
+    let registry = SimilarityRegistry::global();
+    let metric = similarity.name().to_string();
+    registry.register(std::sync::Arc::new(similarity));
+    
+    let mut builder = NetworkBuilder::new(threshold, max_neighbors).with_metric(&metric);
+    let molecules: Vec<Molecule> = features.iter().map(feature_molecule).collect();
+    builder.add_molecules(&molecules)?;
+    builder.build_similarities()?;
+    let mut network = builder.build();
+    let families = network.molecular_families(options);
+    
+    let json = serde_json::to_string_pretty(&network.to_serializable())?;
+    std::fs::write(output, json)?;
+    info!("Wrote spectral network to file: {}", output.display());
+    
+    let annotated: usize = families.iter().map(|f| f.annotations.len()).sum();
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&families)?),
+        "jsonl" => {
+            for family in &families {
+                emit_jsonl(family)?;
+            }
+        }
+        _ => {
+            println!("Molecular Families:");
+            println!("  Features: {}", features.len());
+            println!("  Families: {}", families.len());
+            println!("  Singletons: {}", features.len() - families.iter().map(|f| f.members.len()).sum::<usize>());
+            println!("  Annotations propagated: {}", annotated);
+            for family in &families {
+                println!("  Family {}: {} members, {} identified, {} annotated",
+                         family.id, family.members.len(), family.identified.len(), family.annotations.len());
+                for (member, annotation) in &family.annotations {
+                    println!("    {} ~ {} via {} ({} hops, {:.2})",
+                             member, annotation.name, annotation.source_id, annotation.hops, annotation.confidence);
+                }
+            }
+            println!("  Output file: {}", output.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Print the identities suggested for the unannotated molecules of a network
+fn propagate_annotations(network: &PathBuf, options: &PropagationOptions, output_format: &str) -> Result<()> {
+    let network = read_network(network)?;
+    let suggestions = network.propagate_annotations(options);
+    
+    match output_format {
+        "json" => {
+            let predictions: Vec<_> = suggestions.iter().map(|s| s.to_prediction()).collect();
+            println!("{}", serde_json::to_string_pretty(&json!({
+                "suggestions": suggestions,
+                "predictions": predictions,
+            }))?);
+        }
+        "jsonl" => {
+            for suggestion in &suggestions {
+                emit_jsonl(suggestion)?;
+            }
+        }
+        _ => {
+            println!("Annotation suggestions: {}", suggestions.len());
+            for suggestion in &suggestions {
+                println!("  {} ~ {} ({:.2}) via {}",
+                         suggestion.molecule_id, suggestion.name, suggestion.confidence, suggestion.path.join(" - "));
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Migrate a network file to InChIKey-based molecule IDs
+///
+/// With a project, the molecules stored for it in Neo4j are renamed the same way.
+async fn migrate_network_ids(network: &PathBuf, output: &PathBuf, project: Option<&str>, output_format: &str) -> Result<()> {
+    let content = std::fs::read_to_string(network)
+        .with_context(|| format!("Failed to read network file: {}", network.display()))?;
+    let serialized: SerializableNetwork = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid network file: {}", network.display()))?;
+    let mut migrated = MoleculeNetwork::from_serializable(&serialized)?;
+    let report = migrated.migrate_ids();
+    
+    let json = serde_json::to_string_pretty(&migrated.to_serializable())?;
+    std::fs::write(output, json)?;
+    info!("Wrote migrated network to file: {}", output.display());
+    
+    let stored = match project {
+        Some(project) => Some(Neo4jClient::from_env()?.migrate_molecule_ids(project, &report).await?),
+        None => None,
+    };
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "jsonl" => emit_jsonl(&report)?,
+        _ => {
+            println!("Migrated {} to {} IDs", network.display(), ID_SCHEME);
+            println!("  Output file: {}", output.display());
+            println!("  Molecules renamed: {}", report.renamed.len());
+            println!("  Molecules merged: {}", report.nodes_merged);
+            println!("  Edges merged: {}", report.edges_merged);
+            if let (Some(project), Some(stored)) = (project, stored) {
+                println!("  Stored molecules migrated in project {}: {}", project, stored);
+            }
+            for (old, new) in &report.renamed {
+                println!("    {} -> {}", old, new);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Recompute confidence with each evidence item or source held out
+async fn ablate_evidence(input: &PathBuf, molecule: &str, by_source: bool, output_format: &str) -> Result<()> {
+    info!("Running ablation analysis for molecule: {}", molecule);
+    
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read evidence file: {}", input.display()))?;
+    let evidence: Vec<Evidence> = serde_json::from_str(&content)
+        .context("Failed to parse evidence file")?;
+    
+    let mode = if by_source { AblationMode::Source } else { AblationMode::Item };
+    let report = IdentityPipeline::from_env()?.ablate_evidence(molecule, &evidence, mode)?;
+    
+    match output_format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        "csv" => {
+            println!("key,held_out,confidence_without,influence");
+            for influence in &report.influences {
+                println!("{},{},{},{}",
+                         influence.key,
+                         influence.held_out.len(),
+                         influence.confidence_without,
+                         influence.influence);
+            }
+        }
+        _ => {
+            println!("Ablation Results:");
+            println!("  Molecule ID: {}", report.molecule_id);
+            println!("  Baseline confidence: {:.1}%", report.baseline_confidence * 100.0);
+            println!("\nInfluence (strongest first):");
+            for influence in &report.influences {
+                println!("  {:<30} {:+.3} (without: {:.1}%)",
+                         influence.key,
+                         influence.influence,
+                         influence.confidence_without * 100.0);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Cluster molecules by evidence profile and report possible systematic biases
+fn cluster_evidence(input: &PathBuf, k: Option<usize>, max_k: usize, method: &str, output_format: &str) -> Result<()> {
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read evidence file: {}", input.display()))?;
+    let evidence: Vec<Evidence> = serde_json::from_str(&content)
+        .context("Failed to parse evidence file")?;
+    
+    let profiles = EvidenceProfile::from_evidence_set(&evidence);
+    let clusterer = ProfileClusterer::default().with_method(method.parse::<ClusterMethod>()?);
+    let report = match k {
+        Some(k) => clusterer.cluster(&profiles, k)?,
+        None => clusterer.cluster_auto(&profiles, max_k)?,
+    };
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "csv" => {
+            println!("cluster,molecule,medoid,silhouette");
+            for cluster in &report.clusters {
+                for member in &cluster.members {
+                    println!("{},{},{},{}", cluster.id, member, *member == cluster.medoid, cluster.silhouette);
+                }
+            }
+        }
+        _ => {
+            println!("Evidence Profile Clusters ({} molecules, silhouette {:.3}):", profiles.len(), report.silhouette);
+            for cluster in &report.clusters {
+                println!("  Cluster {}: {} molecules (medoid {}, silhouette {:.3})",
+                         cluster.id, cluster.members.len(), cluster.medoid, cluster.silhouette);
+                for (source, share) in cluster.source_shares.iter().take(3) {
+                    println!("    {:<30} {:.0}% of members", source, share * 100.0);
+                }
+            }
+            if !report.bias_candidates.is_empty() {
+                println!("\nPossible systematic biases:");
+                for candidate in &report.bias_candidates {
+                    println!("  Cluster {}: {} molecules, {:.0}% resting mainly on {}",
+                             candidate.cluster, candidate.molecules, candidate.share * 100.0, candidate.source);
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Assess instrument runs for confidence drift, alert on drifted runs and flag their evidence
+async fn detect_drift(input: &PathBuf, output: Option<&PathBuf>, project_id: &str, baseline_runs: usize, output_format: &str) -> Result<()> {
+    use hegel::alerts::{AlertEngine, AlertLog};
+    
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read evidence file: {}", input.display()))?;
+    let mut evidence: Vec<Evidence> = serde_json::from_str(&content)
+        .context("Failed to parse evidence file")?;
+    
+    let drifts = DriftDetector::new().with_baseline_runs(baseline_runs).detect(&evidence);
+    let flagged = flag_drifted_evidence(&mut evidence, &drifts);
+    let alerts: Vec<_> = drifts.iter().filter_map(|d| d.to_alert(project_id)).collect();
+    AlertEngine::from_env()?
+        .with_sink(std::sync::Arc::new(AlertLog::from_env()))
+        .deliver(&alerts)
+        .await;
+    if let Some(output) = output {
+        std::fs::write(output, serde_json::to_string_pretty(&evidence)?)
+            .with_context(|| format!("Failed to write evidence file: {}", output.display()))?;
+    }
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&json!({"runs": drifts, "flagged_evidence": flagged}))?),
+        "jsonl" => {
+            for drift in &drifts {
+                emit_jsonl(drift)?;
+            }
+        }
+        _ => {
+            println!("Instrument Drift:");
+            for drift in &drifts {
+                let status = drift.severity.map(|s| s.to_string()).unwrap_or_else(|| "stable".to_string());
+                println!("  {} / {}: {} (PSI {:.3}, KS {:.3}, p = {:.2e}, mean shift {:+.3}, {} items)",
+                         drift.source, drift.run_id, status, drift.psi, drift.ks_statistic, drift.ks_p_value,
+                         drift.mean_shift, drift.run_size);
+            }
+            println!("  Alerts raised: {}", alerts.len());
+            println!("  Evidence flagged: {}", flagged);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Check the system suitability of each run, alert on runs that did not pass and block or flag their evidence
+async fn check_qc(input: &PathBuf, panel: &PathBuf, output: Option<&PathBuf>, project_id: &str, action: &str, output_format: &str) -> Result<()> {
+    use hegel::alerts::{AlertEngine, AlertLog};
+    
+    let action: QcAction = action.parse()?;
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read evidence file: {}", input.display()))?;
+    let mut evidence: Vec<Evidence> = serde_json::from_str(&content)
+        .context("Failed to parse evidence file")?;
+    
+    let runs = QcEvaluator::new(QcPanel::load(panel)?).evaluate(&evidence);
+    let affected = apply_qc(&mut evidence, &runs, action);
+    let alerts: Vec<_> = runs.iter().filter_map(|r| r.to_alert(project_id)).collect();
+    AlertEngine::from_env()?
+        .with_sink(std::sync::Arc::new(AlertLog::from_env()))
+        .deliver(&alerts)
+        .await;
+    if let Some(output) = output {
+        std::fs::write(output, serde_json::to_string_pretty(&evidence)?)
+            .with_context(|| format!("Failed to write evidence file: {}", output.display()))?;
+    }
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&json!({"runs": runs, "affected_evidence": affected}))?),
+        "jsonl" => {
+            for run in &runs {
+                emit_jsonl(run)?;
+            }
+        }
+        _ => {
+            println!("System Suitability:");
+            for run in &runs {
+                println!("  {}: {} ({:.0}% of QC compounds detected, mean confidence {})",
+                         run.run_id, run.status, run.detection_rate * 100.0,
+                         run.mean_confidence.map_or_else(|| "-".to_string(), |c| format!("{:.2}", c)));
+                for failure in &run.failures {
+                    println!("    {}", failure);
+                }
+            }
+            println!("  Alerts raised: {}", alerts.len());
+            println!("  Evidence {}: {}", if action == QcAction::Block { "blocked or flagged" } else { "flagged" }, affected);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Integrate evidence for every molecule in a file and write the results table
+async fn batch_integrate(input: &PathBuf, output_file: &PathBuf, results_format: &str, output_format: &str) -> Result<()> {
+    let format: ResultFormat = results_format.parse()?;
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read evidence file: {}", input.display()))?;
+    let evidence: Vec<Evidence> = serde_json::from_str(&content)
+        .context("Failed to parse evidence file")?;
+    
+    let mut by_molecule: BTreeMap<String, Vec<Evidence>> = BTreeMap::new();
+    for item in evidence {
+        by_molecule.entry(item.molecule_id.clone()).or_default().push(item);
+    }
+    info!("Integrating evidence for {} molecules", by_molecule.len());
+    
+    let start = Instant::now();
+    let results = IdentityPipeline::from_env()?
+        .with_generators(hegel::plugins::EvidenceGeneratorRegistry::global())
+        .run_batch(by_molecule, &MemoryBudget::from_env(), &interrupt_token()).await?;
+    let spill = results.stats().clone();
+    let mut writer = ResultsWriter::create(output_file, format)?;
+    for integrated in results {
+        let row = AnalysisRow::from_integrated(&integrated?);
+        if output_format == "jsonl" {
+            emit_jsonl(&row.to_json())?;
+        }
+        writer.write(row)?;
+    }
+    let rows = writer.finish()?;
+    let elapsed = start.elapsed();
+    
+    match output_format {
+        "json" => {
+            let summary = json!({
+                "molecules": rows,
+                "output_file": output_file,
+                "format": format,
+                "spill": spill,
+                "elapsed_ms": elapsed.as_millis(),
+            });
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        }
+        "jsonl" => info!("Wrote {} result rows to {}", rows, output_file.display()),
+        "csv" => {
+            println!("molecules,output_file,format,items_spilled,bytes_spilled");
+            println!("{},{},{:?},{},{}", rows, output_file.display(), format, spill.items_spilled, spill.bytes_spilled);
+        }
+        _ => {
+            println!("Batch Integration:");
+            println!("  Molecules: {}", rows);
+            println!("  Results: {} ({:?})", output_file.display(), format);
+            if spill.spill_files > 0 {
+                println!("  Spilled: {} results, {} bytes in {} files",
+                         spill.items_spilled, spill.bytes_spilled, spill.spill_files);
+            }
+            println!("  Time: {:.2?}", elapsed);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Plan the next experiments for every molecule below the confidence threshold
+fn plan_acquisition(input: &PathBuf, threshold: f64, max_recommendations: usize, output_format: &str) -> Result<()> {
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read evidence file: {}", input.display()))?;
+    let evidence: Vec<Evidence> = serde_json::from_str(&content)
+        .context("Failed to parse evidence file")?;
+    let mut by_molecule: BTreeMap<String, Vec<Evidence>> = BTreeMap::new();
+    for item in evidence {
+        by_molecule.entry(item.molecule_id.clone()).or_default().push(item);
+    }
+    
+    let options = PlannerOptions::default()
+        .with_confidence_threshold(threshold)
+        .with_max_recommendations(max_recommendations);
+    let plan = AcquisitionPlanner::new(options)?.plan(&IdentityPipeline::from_env()?, &by_molecule)?;
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&plan)?),
+        "jsonl" => {
+            for molecule_plan in &plan.plans {
+                emit_jsonl(molecule_plan)?;
+            }
+        }
+        "csv" => {
+            println!("molecule_id,confidence,rank,experiment,expected_confidence,expected_gain,cost");
+            for molecule_plan in &plan.plans {
+                for (rank, recommendation) in molecule_plan.recommendations.iter().enumerate() {
+                    println!("{},{:.4},{},{},{:.4},{:.4},{}",
+                             molecule_plan.molecule_id, molecule_plan.confidence, rank + 1, recommendation.experiment,
+                             recommendation.expected_confidence, recommendation.expected_gain, recommendation.cost);
+                }
+            }
+        }
+        _ => {
+            println!("Evidence Acquisition Plan:");
+            println!("  Molecules below {:.0}%: {} ({} already confident)", threshold * 100.0, plan.plans.len(), plan.confident.len());
+            for molecule_plan in &plan.plans {
+                println!();
+                println!("  {} (confidence {:.1}%, coverage {:.0}%)",
+                         molecule_plan.molecule_id, molecule_plan.confidence * 100.0, molecule_plan.coverage * 100.0);
+                if molecule_plan.recommendations.is_empty() {
+                    println!("    No experiment is expected to help");
+                }
+                for recommendation in &molecule_plan.recommendations {
+                    println!("    {}: expected {:.1}% (+{:.1}%), cost {}: {}",
+                             recommendation.experiment, recommendation.expected_confidence * 100.0,
+                             recommendation.expected_gain * 100.0, recommendation.cost, recommendation.rationale);
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Integrate evidence for a molecule and report the conclusion and its conflicts
+async fn report(
+    input: &PathBuf,
+    molecule: &str,
+    conflict_graph: Option<&PathBuf>,
+    conflict_format: &str,
+    output_format: &str,
+) -> Result<()> {
+    info!("Generating report for molecule: {}", molecule);
+    
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read evidence file: {}", input.display()))?;
+    let evidence: Vec<Evidence> = serde_json::from_str(&content)
+        .context("Failed to parse evidence file")?;
+    
+    let pipeline = IdentityPipeline::from_env()?;
+    let integrated = pipeline.run(molecule, evidence).await?;
+    let graph = ConflictGraph::from_integrated(&integrated);
+    let contributions = pipeline.decompose_confidence(molecule, &integrated.evidence_items, AblationMode::Source)?;
+    
+    if let Some(path) = conflict_graph {
+        let format: ConflictGraphFormat = conflict_format.parse()?;
+        std::fs::write(path, graph.export(format)?)
+            .with_context(|| format!("Failed to write conflict graph: {}", path.display()))?;
+        info!("Wrote conflict graph to file: {}", path.display());
+    }
+    
+    match output_format {
         "json" => {
-            println!("{}", serde_json::to_string_pretty(&response)?);
+            let result = json!({
+                "integrated_evidence": integrated,
+                "conflict_graph": graph,
+                "contributions": contributions,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
         }
         "csv" => {
-            // Basic molecule info
-            println!("type,key,value");
-            println!("info,id,{}", response.id);
-            println!("info,name,{}", response.name.unwrap_or_default());
-            println!("info,formula,{}", response.formula.unwrap_or_default());
-            println!("info,smiles,{}", response.smiles);
-            
-            // Properties
-            for (key, value) in response.properties {
-                println!("property,{},{}", key, value);
+            println!("evidence_a,evidence_b,severity,description");
+            for edge in &graph.edges {
+                println!("{},{},{},\"{}\"", edge.source, edge.target, edge.severity,
+                         edge.description.replace("\"", "\"\""));
             }
+        }
+        _ => {
+            println!("Identity Report:");
+            println!("  Molecule ID: {}", integrated.molecule_id);
+            println!("  Evidence items: {}", integrated.evidence_items.len());
+            println!("  Aggregate confidence: {:.1}%", integrated.aggregate_confidence * 100.0);
+            println!("  Conflicts: {}", integrated.conflicts.len());
             
-            // Related entities
-            for related in response.related_entities {
-                println!("related,{},{},{}", 
-                         related.relation_type,
-                         related.entity_id,
-                         related.entity_name.unwrap_or_default());
+            for conflict in &integrated.conflicts {
+                println!("    - {} (severity {:.2})", conflict.description, conflict.severity);
+            }
+            for violation in &integrated.policy_violations {
+                println!("  Policy: {}", violation.explanation);
+            }
+            print_contributions(&contributions);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Process a mass spec data file, optionally with a named instrument profile
+async fn process_mass_spec(input: &PathBuf, molecule: &str, profile: Option<&str>, output_format: &str) -> Result<()> {
+    info!("Processing mass spec data for molecule: {}", molecule);
+    
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read mass spec file: {}", input.display()))?;
+    let data: MassSpecData = serde_json::from_str(&content)
+        .context("Failed to parse mass spec file")?;
+    
+    let processor = match profile {
+        Some(profile) => MassSpecProcessor::with_profile(profile)?,
+        None => MassSpecProcessor::new(),
+    };
+    let results = processor.process(molecule, &data)?;
+    
+    match output_format {
+        "json" => {
+            println!("{}", serde_json::to_string_pretty(&results)?);
+        }
+        "csv" => {
+            println!("evidence_type,finding_type,score,description");
+            for result in &results {
+                for finding in &result.findings {
+                    println!("{},{},{},\"{}\"", result.evidence_type, finding.finding_type, finding.score,
+                             finding.description.replace("\"", "\"\""));
+                }
             }
         }
         _ => {
-            println!("Molecule Processing Results:");
-            println!("  ID: {}", response.id);
-            if let Some(name) = &response.name {
-                println!("  Name: {}", name);
+            println!("Mass Spec Results:");
+            println!("  Molecule ID: {}", molecule);
+            println!("  Instrument profile: {}", profile.unwrap_or("default"));
+            for result in &results {
+                println!("\n  {} (confidence {:.1}%)", result.evidence_type, result.confidence * 100.0);
+                for finding in &result.findings {
+                    println!("    - [{}] {}", finding.finding_type, finding.description);
+                }
             }
-            if let Some(formula) = &response.formula {
-                println!("  Formula: {}", formula);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Turn a feature table into evidence for the compounds its features match
+fn match_features(
+    input: &PathBuf,
+    compounds: &PathBuf,
+    library: Option<&PathBuf>,
+    output: &PathBuf,
+    format: Option<FeatureTableFormat>,
+    options: &FeatureMatchOptions,
+    output_format: &str,
+) -> Result<()> {
+    let table = FeatureTable::load(input, format)?;
+    let store = match library {
+        Some(path) => {
+            let content = std::fs::read_to_string(compounds)
+                .with_context(|| format!("Failed to read compound store: {}", compounds.display()))?;
+            let molecules: Vec<Molecule> = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse compound store: {}", compounds.display()))?;
+            ReferenceLibrary::open(path)?.compound_store_over(&molecules)
+        }
+        None => CompoundStore::load(compounds)?,
+    };
+    if store.is_empty() {
+        return Err(anyhow!("No compound in {} has a monoisotopic mass", compounds.display()));
+    }
+    let evidence = feature_evidence(&table, &store, options)?;
+    std::fs::write(output, serde_json::to_string_pretty(&evidence)?)
+        .with_context(|| format!("Failed to write evidence file: {}", output.display()))?;
+    
+    let matched: std::collections::BTreeSet<&str> = evidence.iter()
+        .filter_map(|e| e.metadata.get("feature_id")?.as_str())
+        .collect();
+    let summary = json!({
+        "format": table.format,
+        "samples": table.samples.len(),
+        "features": table.features.len(),
+        "matched_features": matched.len(),
+        "compounds": store.len(),
+        "evidence": evidence.len(),
+        "output": output,
+    });
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&summary)?),
+        "jsonl" => emit_jsonl(&summary)?,
+        _ => {
+            println!("Feature Table ({}):", table.format);
+            println!("  Samples: {}", table.samples.len());
+            println!("  Features matched: {} of {}", matched.len(), table.features.len());
+            println!("  Compounds: {}", store.len());
+            println!("  Evidence written: {} to {}", evidence.len(), output.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Align runs, match every run's features and merge the evidence of aligned features
+#[allow(clippy::too_many_arguments)]
+fn align_feature_runs(
+    inputs: &[PathBuf],
+    format: Option<FeatureTableFormat>,
+    compounds: &PathBuf,
+    output: &PathBuf,
+    alignment_output: Option<&PathBuf>,
+    match_options: &FeatureMatchOptions,
+    options: &AlignmentOptions,
+    output_format: &str,
+) -> Result<()> {
+    let runs = inputs.iter()
+        .map(|path| Ok(Run {
+            id: path.file_stem().and_then(|s| s.to_str()).unwrap_or("run").to_string(),
+            acquired_at: None,
+            table: FeatureTable::load(path, format)?,
+        }))
+        .collect::<Result<Vec<_>>>()?;
+    let store = CompoundStore::load(compounds)?;
+    if store.is_empty() {
+        return Err(anyhow!("No compound in {} has a monoisotopic mass", compounds.display()));
+    }
+    
+    let alignment = align_runs(&runs, options)?;
+    if let Some(path) = alignment_output {
+        std::fs::write(path, serde_json::to_string_pretty(&alignment)?)
+            .with_context(|| format!("Failed to write alignment file: {}", path.display()))?;
+    }
+    
+    let mut evidence = Vec::new();
+    for run in &runs {
+        for mut item in feature_evidence(&run.table, &store, match_options)? {
+            item.metadata.insert(RUN_METADATA_KEY.to_string(), json!(run.id));
+            evidence.push(item);
+        }
+    }
+    let per_run = evidence.len();
+    let evidence = alignment.merge_evidence(evidence);
+    std::fs::write(output, serde_json::to_string_pretty(&evidence)?)
+        .with_context(|| format!("Failed to write evidence file: {}", output.display()))?;
+    
+    let shared = alignment.found_in(2).count();
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&json!({
+            "reference_run": alignment.reference_run,
+            "corrections": alignment.corrections,
+            "consensus_features": alignment.features.len(),
+            "shared_features": shared,
+            "run_evidence": per_run,
+            "merged_evidence": evidence.len(),
+            "output": output,
+        }))?),
+        "jsonl" => {
+            for correction in &alignment.corrections {
+                emit_jsonl(correction)?;
             }
-            println!("  SMILES: {}", response.smiles);
-            
-            println!("\nProperties:");
-            for (key, value) in &response.properties {
-                println!("  {}: {}", key, value);
+        }
+        _ => {
+            println!("Feature Alignment (reference {}):", alignment.reference_run);
+            for correction in &alignment.corrections {
+                let largest_shift = correction.rt_shifts.iter().map(|(_, shift)| shift.abs()).fold(0.0, f64::max);
+                println!("  {}: {} anchors, mass offset {:+.2} ppm, RT shift up to {:.2} min",
+                         correction.run_id, correction.anchors, correction.mass_offset_ppm, largest_shift);
             }
-            
-            if !response.related_entities.is_empty() {
-                println!("\nRelated Entities:");
-                for related in &response.related_entities {
-                    println!("  {} {} ({})", 
-                             related.relation_type,
-                             related.entity_name.as_deref().unwrap_or(&related.entity_id),
-                             related.entity_id);
-                }
+            println!("  Consensus features: {} ({} in more than one run)", alignment.features.len(), shared);
+            println!("  Evidence: {} per-run items merged into {}", per_run, evidence.len());
+            println!("  Written to: {}", output.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Find the significant genes of a genomics dataset and write evidence for their metabolites
+fn link_genes_to_metabolites(
+    input: &PathBuf,
+    map: &GeneMetaboliteMap,
+    options: &EnzymeLinkOptions,
+    output: &PathBuf,
+    output_format: &str,
+) -> Result<()> {
+    if map.gene_count() == 0 {
+        return Err(anyhow!("No gene-reaction mappings loaded; pass --kegg or --reactome-genes"));
+    }
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read genomics file: {}", input.display()))?;
+    let samples: Vec<GenomicsData> = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse genomics file: {}", input.display()))?;
+    
+    let results = GenomicsProcessor::new().process_dataset("dataset", &samples)?;
+    let genes = significant_genes(&results);
+    let evidence = enzyme_linkage_evidence(&genes, map, options);
+    std::fs::write(output, serde_json::to_string_pretty(&evidence)?)
+        .with_context(|| format!("Failed to write evidence file: {}", output.display()))?;
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&json!({
+            "samples": samples.len(),
+            "significant_genes": genes.len(),
+            "reactions": map.reaction_count(),
+            "metabolites": evidence.len(),
+            "output": output,
+        }))?),
+        "jsonl" => {
+            for item in &evidence {
+                emit_jsonl(item)?;
             }
-            
-            println!();
-            println!("Time taken: {:.2?}", elapsed);
+        }
+        _ => {
+            println!("Gene-Metabolite Links ({} samples, {} significant genes):", samples.len(), genes.len());
+            for item in &evidence {
+                println!("  {}: confidence {:.2} via {}", item.molecule_id, item.confidence, item.metadata["genes"]);
+            }
+            println!("  Written to: {}", output.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Write the target graph and the structural and pathway evidence for one molecule
+fn report_target_evidence(
+    activities: &TargetActivities,
+    molecule: &str,
+    observed: Option<&PathBuf>,
+    graph: Option<&PathBuf>,
+    output: &PathBuf,
+    output_format: &str,
+) -> Result<()> {
+    if activities.records().is_empty() {
+        return Err(anyhow!("No activities loaded; pass --chembl or --bindingdb"));
+    }
+    let observed: HashMap<String, f64> = match observed {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read observed targets file: {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse observed targets file: {}", path.display()))?
+        }
+        None => HashMap::new(),
+    };
+    
+    if let Some(path) = graph {
+        let graph = activities.to_graph(&format!("targets-{}", molecule), &format!("Targets of {}", molecule));
+        std::fs::write(path, serde_json::to_string_pretty(&graph)?)
+            .with_context(|| format!("Failed to write graph file: {}", path.display()))?;
+    }
+    
+    let targets = activities.consistency(molecule);
+    let evidence: Vec<Evidence> = activities.structural_evidence(molecule).into_iter()
+        .chain(activities.pathway_evidence(molecule, &observed))
+        .collect();
+    std::fs::write(output, serde_json::to_string_pretty(&evidence)?)
+        .with_context(|| format!("Failed to write evidence file: {}", output.display()))?;
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&json!({
+            "molecule": molecule,
+            "activities": activities.records().len(),
+            "targets": targets,
+            "evidence": evidence.len(),
+            "output": output,
+        }))?),
+        "jsonl" => {
+            for target in &targets {
+                emit_jsonl(target)?;
+            }
+        }
+        _ => {
+            println!("Protein Targets for {} ({} activities loaded):", molecule, activities.records().len());
+            for target in &targets {
+                println!("  {} ({}): pAffinity {:.2} from {} measurements, consistency {:.2}",
+                         target.target_id,
+                         target.target_name.as_deref().unwrap_or("unnamed"),
+                         target.p_affinity, target.measurements, target.consistency);
+            }
+            for item in &evidence {
+                println!("  {} evidence: confidence {:.2}", item.evidence_type, item.confidence);
+            }
+            println!("  Written to: {}", output.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Extract claims from each abstract with the LLM and keep the verified ones as evidence
+async fn extract_literature_claims(
+    input: &PathBuf,
+    molecule: &str,
+    synonyms: &[String],
+    molecule_id: &str,
+    output: &PathBuf,
+    claims_output: Option<&PathBuf>,
+    output_format: &str,
+) -> Result<()> {
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read abstracts file: {}", input.display()))?;
+    let articles: Vec<ArticleAbstract> = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse abstracts file: {}", input.display()))?;
+    
+    let llm = LLMInterface::new()?;
+    let templates = TemplateSet::from_env()?;
+    let mut checked = Vec::new();
+    for article in &articles {
+        match llm.extract_claims(&templates, molecule, synonyms, article).await {
+            Ok(claims) => checked.extend(claims),
+            Err(e) => warn!("Skipping PMID {}: {:#}", article.pmid, e),
+        }
+    }
+    
+    let evidence: Vec<Evidence> = checked.iter()
+        .filter(|claim| claim.check.is_verified())
+        .map(|claim| claim.to_evidence(molecule_id))
+        .collect();
+    std::fs::write(output, serde_json::to_string_pretty(&evidence)?)
+        .with_context(|| format!("Failed to write evidence file: {}", output.display()))?;
+    if let Some(path) = claims_output {
+        std::fs::write(path, serde_json::to_string_pretty(&checked)?)
+            .with_context(|| format!("Failed to write claims file: {}", path.display()))?;
+    }
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&json!({
+            "abstracts": articles.len(),
+            "claims": checked.len(),
+            "verified": evidence.len(),
+            "output": output,
+        }))?),
+        "jsonl" => {
+            for claim in &checked {
+                emit_jsonl(claim)?;
+            }
+        }
+        _ => {
+            println!("Literature Claims for {} ({} abstracts):", molecule, articles.len());
+            for claim in &checked {
+                println!("  PMID {} [{}] {} {}: {}",
+                         claim.pmid, claim.check, claim.claim.kind, claim.claim.object, claim.claim.sentence);
+            }
+            println!("  Verified: {} of {} claims", evidence.len(), checked.len());
+            println!("  Written to: {}", output.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Score cross-source pairs of database records, optionally training the model first
+fn link_database_records(
+    input: &PathBuf,
+    model_path: Option<&PathBuf>,
+    training: Option<&PathBuf>,
+    save_model: Option<&PathBuf>,
+    molecule: Option<&str>,
+    output: Option<&PathBuf>,
+    output_format: &str,
+) -> Result<()> {
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read records file: {}", input.display()))?;
+    let records: Vec<DatabaseRecord> = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse records file: {}", input.display()))?;
+    
+    let mut model = match model_path {
+        Some(path) => LinkageModel::load(path)?,
+        None => LinkageModel::default(),
+    };
+    if let Some(path) = training {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read training file: {}", path.display()))?;
+        let pairs: Vec<LabelledPair> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse training file: {}", path.display()))?;
+        model = model.train(&pairs)?;
+    }
+    if let Some(path) = save_model {
+        std::fs::write(path, serde_json::to_string_pretty(&model)?)
+            .with_context(|| format!("Failed to write linkage model: {}", path.display()))?;
+    }
+    
+    let scores = link_records(&records, &model);
+    if let (Some(molecule), Some(path)) = (molecule, output) {
+        let evidence: Vec<Evidence> = scores.iter()
+            .filter(|score| score.decision != LinkDecision::NonLink)
+            .map(|score| score.to_evidence(molecule))
+            .collect();
+        std::fs::write(path, serde_json::to_string_pretty(&evidence)?)
+            .with_context(|| format!("Failed to write evidence file: {}", path.display()))?;
+    }
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&scores)?),
+        "jsonl" => {
+            for score in &scores {
+                emit_jsonl(score)?;
+            }
+        }
+        _ => {
+            println!("Record Linkage ({} records, {} pairs):", records.len(), scores.len());
+            for score in &scores {
+                println!("  {} ~ {}: p = {:.3} (weight {:+.1}) -> {}",
+                         score.left, score.right, score.probability, score.weight, score.decision);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Integrate feature matches with any further evidence and write them as mzTab-M
+#[allow(clippy::too_many_arguments)]
+async fn export_mztab(
+    input: &PathBuf,
+    format: Option<FeatureTableFormat>,
+    compounds: &PathBuf,
+    extra_evidence: Option<&PathBuf>,
+    output: &PathBuf,
+    options: &FeatureMatchOptions,
+    mztab_options: MzTabOptions,
+    output_format: &str,
+) -> Result<()> {
+    let table = FeatureTable::load(input, format)?;
+    let content = std::fs::read_to_string(compounds)
+        .with_context(|| format!("Failed to read compound store: {}", compounds.display()))?;
+    let molecules: Vec<Molecule> = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse compound store: {}", compounds.display()))?;
+    let store = CompoundStore::from_molecules(&molecules);
+    if store.is_empty() {
+        return Err(anyhow!("No compound in {} has a monoisotopic mass", compounds.display()));
+    }
+    
+    let candidates = table.features.iter()
+        .map(|feature| match_feature(feature, &store, options))
+        .collect::<Result<Vec<_>>>()?;
+    let mut evidence = feature_evidence(&table, &store, options)?;
+    if let Some(path) = extra_evidence {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read evidence file: {}", path.display()))?;
+        let extra: Vec<Evidence> = serde_json::from_str(&content)
+            .context("Failed to parse evidence file")?;
+        evidence.extend(extra);
+    }
+    
+    let mut by_molecule: BTreeMap<String, Vec<Evidence>> = BTreeMap::new();
+    for item in evidence {
+        by_molecule.entry(item.molecule_id.clone()).or_default().push(item);
+    }
+    let results = IdentityPipeline::from_env()?
+        .run_batch(by_molecule, &MemoryBudget::from_env(), &interrupt_token()).await?
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+    
+    let summary = MzTabExport::new(&table, candidates, mztab_options)?
+        .with_compounds(&molecules)
+        .with_results(results)
+        .save(output)?;
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&json!({ "output": output, "summary": summary }))?),
+        "jsonl" => emit_jsonl(&json!({ "output": output, "summary": summary }))?,
+        _ => {
+            println!("mzTab-M Export:");
+            println!("  Features: {}", summary.features);
+            println!("  Small molecules: {}", summary.small_molecules);
+            println!("  Candidate evidence: {}", summary.evidence);
+            for (level, count) in &summary.levels {
+                println!("  MSI level {}: {}", level, count);
+            }
+            println!("  Written to: {}", output.display());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Export a project from Neo4j to a bundle file
+async fn export_project(project_id: &str, path: &PathBuf, output_format: &str) -> Result<()> {
+    info!("Exporting project {} to {}", project_id, path.display());
+    
+    let bundle = Neo4jClient::from_env()?.export_project(project_id).await?;
+    let manifest = bundle.save(path)?;
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&manifest)?),
+        _ => {
+            println!("Exported project {} ({}) to {}", bundle.project.id, bundle.project.name, path.display());
+            for (entry, details) in &manifest.entries {
+                println!("  {} - {} records, {} bytes", entry, details.records, details.bytes);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Export a project from Neo4j as Turtle or N-Triples
+async fn export_rdf(project_id: &str, path: &PathBuf, format: &str, base_iri: &str, output_format: &str) -> Result<()> {
+    let format: RdfFormat = format.parse()?;
+    info!("Exporting project {} as RDF to {}", project_id, path.display());
+    
+    let bundle = Neo4jClient::from_env()?.export_project(project_id).await?;
+    let graph = RdfGraph::from_bundle(&bundle, &RdfOptions { base_iri: base_iri.to_string() });
+    std::fs::write(path, graph.write(format))
+        .with_context(|| format!("Failed to write RDF file: {}", path.display()))?;
+    
+    match output_format {
+        "json" => println!("{}", json!({
+            "project_id": project_id,
+            "format": format,
+            "triples": graph.triples.len(),
+            "output": path,
+        })),
+        _ => {
+            println!("Exported project {} to {}", project_id, path.display());
+            println!("  Molecules: {}", bundle.molecules.len());
+            println!("  Evidence items: {}", bundle.evidence.len());
+            println!("  Triples: {}", graph.triples.len());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Import a bundle file into Neo4j
+async fn import_project(path: &PathBuf, project_id: Option<&str>, output_format: &str) -> Result<()> {
+    info!("Importing project bundle {}", path.display());
+    
+    let (manifest, mut bundle) = ProjectBundle::load(path)?;
+    // Retarget even onto the bundle's own project, so no record keeps a foreign project ID
+    let project_id = project_id.map(str::to_string).unwrap_or_else(|| bundle.project.id.clone());
+    bundle.retarget(&project_id);
+    Neo4jClient::from_env()?.import_project(&bundle).await?;
+    
+    match output_format {
+        "json" => println!("{}", json!({
+            "project_id": bundle.project.id,
+            "schema_version": manifest.schema_version,
+            "exported_at": manifest.exported_at,
+            "molecules": bundle.molecules.len(),
+            "evidence": bundle.evidence.len(),
+            "networks": bundle.networks.len(),
+            "fuzzy_networks": bundle.fuzzy_networks.len(),
+            "reports": bundle.reports.len(),
+        })),
+        _ => {
+            println!("Imported project {} ({})", bundle.project.id, bundle.project.name);
+            println!("  Exported by Hegel {} at {}", manifest.hegel_version, manifest.exported_at);
+            println!("  Molecules: {}", bundle.molecules.len());
+            println!("  Evidence items: {}", bundle.evidence.len());
+            println!("  Networks: {}", bundle.networks.len());
+            println!("  Fuzzy networks: {}", bundle.fuzzy_networks.len());
+            println!("  Reports: {}", bundle.reports.len());
         }
     }
     
     Ok(())
 }
 
-/// Compare two molecules
-async fn compare_molecules(molecule1: &str, molecule2: &str, id_type: &str, output_format: &str) -> Result<()> {
-    info!("Comparing molecules: {} and {}", molecule1, molecule2);
-    let start_time = Instant::now();
+/// Print everything stored in Neo4j about a molecule
+async fn inspect_molecule(molecule_id: &str, project_id: &str, history: usize, output_format: &str) -> Result<()> {
+    let inspection = Neo4jClient::from_env()?
+        .inspect_molecule(project_id, molecule_id, history)
+        .await?;
     
-    // Parse the ID type
-    let mol_id_type = parse_id_type(id_type)?;
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&inspection)?),
+        "jsonl" => emit_jsonl(&inspection)?,
+        _ => print!("{}", inspection.render()),
+    }
     
-    // Create molecules
-    let mol1 = Molecule::from_identifier(molecule1, mol_id_type)?;
-    let mol2 = Molecule::from_identifier(molecule2, mol_id_type)?;
+    Ok(())
+}
+
+/// Store a manually entered evidence item and re-integrate its molecule
+async fn add_evidence(
+    molecule: &str,
+    evidence_type: &str,
+    confidence: f64,
+    note: Option<&str>,
+    project_id: &str,
+    user: Option<&str>,
+    output_format: &str,
+) -> Result<()> {
+    let evidence_type: EvidenceType = evidence_type.parse()?;
+    let entered_by = match user {
+        Some(user) => user.to_string(),
+        None => std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .map_err(|_| anyhow!("Could not determine the current user; pass --user"))?,
+    };
+    let evidence = Evidence::manual(molecule, evidence_type, confidence, note, &entered_by)?;
     
-    // Calculate similarity
-    let similarity = mol1.calculate_similarity(&mol2)?;
+    info!("Adding {} evidence {} for molecule {} (entered by {})", evidence_type, evidence.id, molecule, entered_by);
     
-    // Create a metacognition system
-    let system = MetacognitionSystem::new()?;
+    let client = Neo4jClient::from_env()?;
+    let mut items = client.molecule_evidence(project_id, molecule).await?;
+    items.push(evidence.clone());
     
-    // Get additional analysis via LLM (if available)
-    let llm_interface = system.get_llm_interface();
-    let analysis = if let Some(interface) = llm_interface {
-        // Convert molecules to the format expected by the LLM interface
-        let mol1_data = convert_to_llm_molecule(&mol1);
-        let mol2_data = convert_to_llm_molecule(&mol2);
-        
-        // Get analysis
-        match interface.compare_molecules(&mol1_data, &mol2_data).await {
-            Ok(comparison) => Some(comparison),
-            Err(_) => None,
+    let integrated = IdentityPipeline::from_env()?.run(molecule, items).await?;
+    client.store_integrated_evidence(project_id, &integrated, ConfidenceTrigger::ManualEntry).await?;
+    
+    let result = json!({
+        "evidence": evidence,
+        "integrated_evidence": integrated,
+    });
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&result)?),
+        "jsonl" => emit_jsonl(&result)?,
+        _ => {
+            println!("Added evidence {} to molecule {}", evidence.id, molecule);
+            println!("  Type: {}", evidence_type);
+            println!("  Confidence: {:.1}%", confidence * 100.0);
+            println!("  Entered by: {}", entered_by);
+            if let Some(note) = note {
+                println!("  Note: {}", note);
+            }
+            println!("  Evidence items: {}", integrated.evidence_items.len());
+            println!("  Aggregate confidence: {:.1}%", integrated.aggregate_confidence * 100.0);
+            println!("  Conflicts: {}", integrated.conflicts.len());
+            for violation in &integrated.policy_violations {
+                println!("  Policy: {}", violation.explanation);
+            }
         }
-    } else {
-        None
-    };
+    }
     
-    // Output the results based on the format
-    let elapsed = start_time.elapsed();
+    Ok(())
+}
+
+/// Open the reference library at a path, or the one named by the environment
+fn open_reference_library(path: Option<&PathBuf>) -> Result<ReferenceLibrary> {
+    match path {
+        Some(path) => ReferenceLibrary::open(path),
+        None => ReferenceLibrary::from_env(),
+    }
+}
+
+/// Integrate evidence per molecule and promote the confident identifications
+async fn promote_to_library(
+    input: &PathBuf,
+    compounds: &PathBuf,
+    library: Option<&PathBuf>,
+    criteria: &PromotionCriteria,
+    output_format: &str,
+) -> Result<()> {
+    let mut library = open_reference_library(library)?;
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read evidence file: {}", input.display()))?;
+    let evidence: Vec<Evidence> = serde_json::from_str(&content)
+        .context("Failed to parse evidence file")?;
+    let content = std::fs::read_to_string(compounds)
+        .with_context(|| format!("Failed to read compounds file: {}", compounds.display()))?;
+    let molecules: HashMap<String, Molecule> = serde_json::from_str::<Vec<Molecule>>(&content)
+        .with_context(|| format!("Failed to parse compounds file: {}", compounds.display()))?
+        .into_iter()
+        .map(|molecule| (molecule.id.clone(), molecule))
+        .collect();
+    
+    let mut by_molecule: BTreeMap<String, Vec<Evidence>> = BTreeMap::new();
+    for item in evidence {
+        by_molecule.entry(item.molecule_id.clone()).or_default().push(item);
+    }
+    let results = IdentityPipeline::from_env()?
+        .run_batch(by_molecule, &MemoryBudget::from_env(), &interrupt_token()).await?
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+    
+    let (known, unknown): (Vec<&IntegratedEvidence>, Vec<&IntegratedEvidence>) = results.iter()
+        .partition(|integrated| molecules.contains_key(&integrated.molecule_id));
+    let mut report = library.promote(
+        known.into_iter().map(|integrated| (&molecules[&integrated.molecule_id], integrated)),
+        criteria,
+    )?;
+    report.rejected.extend(unknown.into_iter()
+        .map(|integrated| (integrated.molecule_id.clone(), "no structure in the compounds file".to_string())));
     
     match output_format {
-        "json" => {
-            let result = json!({
-                "molecule1": {
-                    "id": mol1.id,
-                    "smiles": mol1.smiles,
-                    "name": mol1.name,
-                },
-                "molecule2": {
-                    "id": mol2.id,
-                    "smiles": mol2.smiles,
-                    "name": mol2.name,
-                },
-                "similarity": similarity,
-                "analysis": analysis.map(|a| a.analysis),
-                "same_entity": analysis.map(|a| a.same_entity),
-            });
-            println!("{}", serde_json::to_string_pretty(&result)?);
-        }
-        "csv" => {
-            println!("molecule1,molecule2,similarity,same_entity");
-            println!("{},{},{},{}",
-                     mol1.id,
-                     mol2.id,
-                     similarity,
-                     analysis.as_ref().map(|a| a.same_entity).unwrap_or(similarity > 0.8));
-        }
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "jsonl" => emit_jsonl(&report)?,
         _ => {
-            println!("Molecule Comparison:");
-            println!("  Molecule 1: {} ({})", mol1.name.as_deref().unwrap_or(&mol1.id), mol1.smiles);
-            println!("  Molecule 2: {} ({})", mol2.name.as_deref().unwrap_or(&mol2.id), mol2.smiles);
-            println!("  Similarity: {:.1}%", similarity * 100.0);
-            
-            if let Some(a) = analysis {
-                println!("\nAnalysis:");
-                println!("  {}", a.analysis);
-                println!("  Same entity: {}", if a.same_entity { "YES" } else { "NO" });
+            println!("Reference Library {} (version {}):", library.name, report.version);
+            println!("  Added: {}", report.added.len());
+            for molecule_id in &report.added {
+                println!("    {}", molecule_id);
             }
-            
-            println!();
-            println!("Time taken: {:.2?}", elapsed);
+            println!("  Updated: {}", report.updated.len());
+            for molecule_id in &report.updated {
+                println!("    {}", molecule_id);
+            }
+            println!("  Not promoted: {}", report.rejected.len());
+            for (molecule_id, reason) in &report.rejected {
+                println!("    {}: {}", molecule_id, reason);
+            }
+            println!("  Compounds in library: {}", library.len());
         }
     }
     
     Ok(())
 }
 
-/// Build a network from a set of molecules
-async fn build_network(
+/// Search the spectra of an MGF file against the reference library
+fn match_library_spectra(
     input: &PathBuf,
+    library: Option<&PathBuf>,
     output: &PathBuf,
-    format: &str,
-    threshold: f64,
-    max_neighbors: usize,
+    options: &LibrarySearchOptions,
     output_format: &str,
 ) -> Result<()> {
-    info!("Building network from file: {}", input.display());
-    let start_time = Instant::now();
-    
-    // Parse the input format
-    let mol_format = match format {
-        "smiles" => MoleculeFormat::Smiles,
-        "sdf" => MoleculeFormat::Sdf,
-        "csv" => MoleculeFormat::Csv,
-        _ => return Err(anyhow!("Unsupported input format: {}", format)),
-    };
+    let library = open_reference_library(library)?;
+    if library.is_empty() {
+        return Err(anyhow!("Reference library is empty; promote identifications with `hegel library promote` first"));
+    }
+    let content = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read MGF file: {}", input.display()))?;
+    let spectra = parse_mgf(&content).map_err(|e| anyhow!("Invalid MGF file {}: {}", input.display(), e))?;
     
-    // Read molecules from the input file
-    let molecules = Molecule::read_from_file(input, mol_format)?;
-    info!("Read {} molecules from input file", molecules.len());
+    let mut hits = Vec::new();
+    for spectrum in &spectra {
+        hits.extend(library.search(spectrum, options)?);
+    }
+    let evidence: Vec<Evidence> = hits.iter().map(|hit| library.hit_evidence(hit)).collect();
+    std::fs::write(output, serde_json::to_string_pretty(&evidence)?)
+        .with_context(|| format!("Failed to write evidence file: {}", output.display()))?;
     
-    // Create a network builder
-    let mut builder = NetworkBuilder::new(threshold, max_neighbors);
+    let matched: std::collections::BTreeSet<&str> = hits.iter().map(|hit| hit.query_id.as_str()).collect();
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&json!({
+            "library": library.name,
+            "library_version": library.version,
+            "spectra": spectra.len(),
+            "matched_spectra": matched.len(),
+            "hits": hits,
+            "output": output,
+        }))?),
+        "jsonl" => {
+            for hit in &hits {
+                emit_jsonl(hit)?;
+            }
+        }
+        _ => {
+            println!("Library Match ({} version {}):", library.name, library.version);
+            println!("  Spectra matched: {} of {}", matched.len(), spectra.len());
+            for hit in &hits {
+                println!("    {} ~ {} ({:.3}, {} peaks, {:+.1} ppm)",
+                         hit.query_id, hit.name.as_deref().unwrap_or(&hit.molecule_id),
+                         hit.score, hit.matched_peaks, hit.precursor_ppm_error);
+            }
+            println!("  Evidence written: {} to {}", evidence.len(), output.display());
+        }
+    }
     
-    // Add molecules to the network
-    builder.add_molecules(&molecules)?;
+    Ok(())
+}
+
+/// Store a study design's samples and groups in a project
+async fn import_study_design(input: &PathBuf, project_id: &str, output_format: &str) -> Result<()> {
+    let design = StudyDesign::load(input)?;
+    Neo4jClient::from_env()?.store_study_design(project_id, &design).await?;
     
-    // Build the network
-    let network = builder.build();
-    info!("Built network with {} nodes and {} edges", 
-          network.get_molecules().len(), 
-          network.calculate_metrics().edge_count);
+    let summary = json!({
+        "project": project_id,
+        "groups": design.groups.len(),
+        "samples": design.samples.len(),
+    });
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&summary)?),
+        "jsonl" => emit_jsonl(&summary)?,
+        _ => println!("Stored {} samples in {} study groups for project {}", design.samples.len(), design.groups.len(), project_id),
+    }
     
-    // Calculate network metrics
-    let metrics = network.calculate_metrics();
+    Ok(())
+}
+
+/// Compare a stored molecule's identification confidence between study groups
+async fn compare_study_groups(molecule_id: &str, groups: &[String], project_id: &str, output_format: &str) -> Result<()> {
+    let group_ids: Vec<&str> = groups.iter().map(String::as_str).collect();
+    let comparison = Neo4jClient::from_env()?
+        .compare_groups(project_id, molecule_id, &group_ids)
+        .await?;
     
-    // Serialize the network
-    let serialized = network.to_serializable();
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&comparison)?),
+        "jsonl" => emit_jsonl(&comparison)?,
+        _ => {
+            println!("Identification of {} by study group:", molecule_id);
+            for group in &comparison.groups {
+                let confidence = group.mean_confidence
+                    .map(|c| format!("{:.1}%", c * 100.0))
+                    .unwrap_or_else(|| "-".to_string());
+                println!("  {} ({}): mean confidence {}, detected in {} of {} samples",
+                         group.name, group.group_id, confidence, group.samples_with_evidence, group.samples);
+            }
+            if let Some(difference) = comparison.difference {
+                println!("  Difference: {:+.1} percentage points", difference * 100.0);
+            }
+        }
+    }
     
-    // Write the network to the output file
-    let json = serde_json::to_string_pretty(&serialized)?;
-    std::fs::write(output, json)?;
-    info!("Wrote network to file: {}", output.display());
+    Ok(())
+}
+
+/// Apply a retention policy to the evidence stored in Neo4j
+async fn collect_garbage(policy_path: &PathBuf, audit_log: Option<&PathBuf>, dry_run: bool, output_format: &str) -> Result<()> {
+    let policy = RetentionPolicy::from_file(policy_path)?;
+    let audit_log = audit_log.map(RedactionAuditLog::new).unwrap_or_else(RedactionAuditLog::from_env);
+    info!("Applying retention policy {} ({} rules)", policy_path.display(), policy.rules.len());
     
-    // Output the results based on the format
-    let elapsed = start_time.elapsed();
+    let records = Neo4jClient::from_env()?
+        .apply_retention(&policy, &audit_log, chrono::Utc::now(), dry_run)
+        .await?;
+    let bytes: usize = records.iter().map(|r| r.payload_bytes).sum();
     
     match output_format {
-        "json" => {
-            println!("{}", serde_json::to_string_pretty(&metrics)?);
+        "json" => println!("{}", json!({
+            "dry_run": dry_run,
+            "audit_log": audit_log.path(),
+            "redacted": records.len(),
+            "bytes": bytes,
+            "records": records,
+        })),
+        _ => {
+            let verb = if dry_run { "Would redact" } else { "Redacted" };
+            println!("{} {} evidence payloads ({} bytes)", verb, records.len(), bytes);
+            for record in &records {
+                println!("  {} [{}] {} in project {} - recorded {}, kept {} days",
+                         record.evidence_id, record.evidence_type, record.molecule_id,
+                         record.project_id, record.recorded_at.format("%Y-%m-%d"), record.max_age_days);
+            }
+            if !dry_run && !records.is_empty() {
+                println!("Audit log: {}", audit_log.path().display());
+            }
         }
-        "csv" => {
-            println!("metric,value");
-            println!("nodes,{}", metrics.node_count);
-            println!("edges,{}", metrics.edge_count);
-            println!("density,{}", metrics.density);
-            println!("avg_degree,{}", metrics.avg_degree);
-            println!("max_degree,{}", metrics.max_degree);
+    }
+    
+    Ok(())
+}
+
+/// Print the reliability of every known source
+fn show_reliability(tracker: &ReliabilityTracker, output_format: &str) {
+    let reliabilities = tracker.all_reliabilities();
+    match output_format {
+        "json" => println!("{}", json!({
+            "file": tracker.path(),
+            "sources": reliabilities,
+        })),
+        _ => {
+            if reliabilities.is_empty() {
+                println!("No source reliability recorded yet");
+            }
+            for r in &reliabilities {
+                let weight = match r.override_weight {
+                    Some(weight) => format!("{:.3} (override)", weight),
+                    None => format!("{:.3}", r.weight()),
+                };
+                println!("  {}: weight {} - learned {:.3} [{:.3}, {:.3}] from {} outcomes",
+                         r.source, weight, r.mean, r.lower_bound, r.upper_bound, r.observations);
+            }
         }
+    }
+}
+
+/// Merge duplicate molecules stored in Neo4j and write the audit report
+async fn reconcile_molecules(project_id: &str, report_path: &PathBuf, dry_run: bool, output_format: &str) -> Result<()> {
+    info!("Reconciling duplicate molecules in project {}", project_id);
+    
+    let report = Neo4jClient::from_env()?
+        .reconcile_duplicates(project_id, dry_run)
+        .await?;
+    report.write(report_path)?;
+    
+    match output_format {
+        "json" => println!("{}", json!({
+            "dry_run": dry_run,
+            "report": report_path,
+            "molecules_scanned": report.molecules_scanned,
+            "molecules_merged": report.molecules_merged(),
+            "conflicts": report.conflict_count(),
+        })),
         _ => {
-            println!("Network Building Results:");
-            println!("  Input file: {}", input.display());
-            println!("  Output file: {}", output.display());
-            println!("  Molecules read: {}", molecules.len());
-            println!("  Nodes in network: {}", metrics.node_count);
-            println!("  Edges in network: {}", metrics.edge_count);
-            println!("  Network density: {:.3}", metrics.density);
-            println!("  Average degree: {:.2}", metrics.avg_degree);
-            println!("  Maximum degree: {}", metrics.max_degree);
-            
-            if !metrics.clusters.is_empty() {
-                println!("\nClusters:");
-                for (i, size) in metrics.clusters.iter().enumerate() {
-                    println!("  Cluster {}: {} nodes", i + 1, size);
-                }
+            let verb = if dry_run { "Would merge" } else { "Merged" };
+            println!("{} {} of {} molecules into {} canonical molecules",
+                     verb, report.molecules_merged(), report.molecules_scanned, report.merges.len());
+            for merge in &report.merges {
+                println!("  {} <- {} ({} evidence, {} conflicting values)",
+                         merge.canonical_id, merge.merged_ids.join(", "), merge.evidence_moved, merge.conflicts.len());
             }
-            
-            println!();
-            println!("Time taken: {:.2?}", elapsed);
+            println!("Audit report: {}", report_path.display());
         }
     }
     
     Ok(())
 }
 
+/// Consume evidence messages and write the integrated results to Neo4j
+#[cfg(feature = "streams")]
+async fn consume_stream(limit: Option<u64>, max_attempts: u32, output_format: &str) -> Result<()> {
+    use hegel::alerts::{AlertEngine, AlertLog, StdoutSink};
+    use hegel::processing::evidence_schema::EvidenceSchemaRegistry;
+    use hegel::streams::{EvidenceStreamConsumer, StreamConfig, StreamOptions};
+    use std::sync::Arc;
+    
+    let config = StreamConfig::from_env()?;
+    info!("Connecting to {} stream {} at {}", config.backend, config.topic, config.url);
+    
+    let (source, dead_letter) = config.connect().await?;
+    let alerts = AlertEngine::from_env()?
+        .with_sink(Arc::new(StdoutSink))
+        .with_sink(Arc::new(AlertLog::from_env()));
+    let mut consumer = EvidenceStreamConsumer::new(source, Box::new(Neo4jClient::from_env()?))
+        .with_options(StreamOptions { max_attempts, ..Default::default() })
+        .with_alerts(Arc::new(alerts))
+        .with_schemas(Arc::new(EvidenceSchemaRegistry::from_env()?));
+    if let Some(dead_letter) = dead_letter {
+        consumer = consumer.with_dead_letter(dead_letter);
+    }
+    
+    let stats = consumer.run(limit).await?;
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&stats)?),
+        _ => println!("Processed {} messages ({} retried, {} dead-lettered)",
+                      stats.processed, stats.retried, stats.dead_lettered),
+    }
+    
+    Ok(())
+}
+
 /// Start the API server
 async fn serve_api(host: &str, port: u16) -> Result<()> {
     info!("Starting API server on {}:{}", host, port);
@@ -482,20 +3502,175 @@ async fn serve_api(host: &str, port: u16) -> Result<()> {
     Ok(())
 }
 
-/// Parse molecule ID type
-fn parse_id_type(id_type: &str) -> Result<hegel::metacognition::molecule_processor::MoleculeIdType> {
-    use hegel::metacognition::molecule_processor::MoleculeIdType;
+/// Outcome of one `hegel doctor` check
+#[derive(Debug, Serialize)]
+struct DoctorCheck {
+    /// Service that was checked
+    service: &'static str,
+    
+    /// Address that was tried
+    target: String,
+    
+    /// Whether the service answered as expected
+    ok: bool,
+    
+    /// What happened
+    detail: String,
+    
+    /// What to change when the check failed
+    hint: Option<String>,
+}
+
+impl DoctorCheck {
+    fn pass(service: &'static str, target: String, detail: String) -> Self {
+        Self { service, target, ok: true, detail, hint: None }
+    }
+    
+    fn fail(service: &'static str, target: String, detail: String, hint: &str) -> Self {
+        Self { service, target, ok: false, detail, hint: Some(hint.to_string()) }
+    }
+}
+
+/// Check the services Hegel depends on and report what to fix
+async fn run_doctor(timeout: Duration, output_format: &str) -> Result<()> {
+    let llm_base_url = std::env::var("HEGEL_LLM_BASE_URL").unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
+    let llm_api_key = std::env::var("HEGEL_LLM_API_KEY").ok();
+    let python_api = std::env::var("HEGEL_PYTHON_API_ENDPOINT").unwrap_or_else(|_| "http://localhost:8000".to_string());
+    
+    let checks = vec![
+        check_neo4j(timeout).await,
+        check_http(
+            "llm",
+            format!("{}/models", llm_base_url.trim_end_matches('/')),
+            llm_api_key.as_deref(),
+            timeout,
+            "Set HEGEL_LLM_BASE_URL to a reachable OpenAI-compatible endpoint",
+            match llm_api_key {
+                Some(_) => "HEGEL_LLM_API_KEY was rejected; check that it is valid for this endpoint",
+                None => "Set HEGEL_LLM_API_KEY",
+            },
+        ).await,
+        check_http(
+            "python-api",
+            format!("{}/api/health", python_api.trim_end_matches('/')),
+            None,
+            timeout,
+            "Start the Python API (backend/api) or point HEGEL_PYTHON_API_ENDPOINT at it",
+            "The Python API refused the health check; check its authentication settings",
+        ).await,
+    ];
+    
+    match output_format {
+        "json" => println!("{}", serde_json::to_string_pretty(&checks)?),
+        "jsonl" => {
+            for check in &checks {
+                emit_jsonl(check)?;
+            }
+        }
+        "csv" => {
+            println!("service,target,ok,detail,hint");
+            for check in &checks {
+                println!("{},{},{},\"{}\",\"{}\"", check.service, check.target, check.ok,
+                         check.detail.replace("\"", "\"\""),
+                         check.hint.as_deref().unwrap_or("").replace("\"", "\"\""));
+            }
+        }
+        _ => {
+            println!("Hegel doctor:");
+            for check in &checks {
+                let status = if check.ok { "ok" } else { "FAIL" };
+                println!("  [{:<4}] {:<11} {}", status, check.service, check.target);
+                println!("         {}", check.detail);
+                if let Some(hint) = &check.hint {
+                    println!("         -> {}", hint);
+                }
+            }
+        }
+    }
+    
+    let failed = checks.iter().filter(|check| !check.ok).count();
+    if failed > 0 {
+        return Err(anyhow!("{} of {} checks failed", failed, checks.len()));
+    }
+    Ok(())
+}
+
+/// Check that the configured Neo4j server accepts connections
+async fn check_neo4j(timeout: Duration) -> DoctorCheck {
+    let config = match Neo4jConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            let uri = std::env::var("HEGEL_NEO4J_URI").unwrap_or_else(|_| "bolt://localhost:7687".to_string());
+            return DoctorCheck::fail("neo4j", uri, format!("{:#}", e), "Set HEGEL_NEO4J_PASSWORD for the Neo4j user");
+        }
+    };
+    
+    // Bolt URIs look like scheme://host[:port][/...]; 7687 is the Bolt default
+    let host = config.uri.split_once("://").map(|(_, rest)| rest).unwrap_or(&config.uri);
+    let host = host.split('/').next().unwrap_or(host);
+    let address = if host.contains(':') { host.to_string() } else { format!("{}:7687", host) };
+    
+    match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(&address)).await {
+        Ok(Ok(_)) => DoctorCheck::pass("neo4j", config.uri,
+                                       format!("Accepted a connection on {} (database {})", address, config.database)),
+        Ok(Err(e)) => DoctorCheck::fail("neo4j", config.uri, format!("Could not connect to {}: {}", address, e),
+                                        "Start Neo4j or point HEGEL_NEO4J_URI at a running server"),
+        Err(_) => DoctorCheck::fail("neo4j", config.uri, format!("No answer from {} within {}s", address, timeout.as_secs()),
+                                    "Check HEGEL_NEO4J_URI and that no firewall blocks the Bolt port"),
+    }
+}
+
+/// Check that an HTTP service answers a GET request successfully
+async fn check_http(
+    service: &'static str,
+    url: String,
+    bearer: Option<&str>,
+    timeout: Duration,
+    unreachable_hint: &str,
+    unauthorized_hint: &str,
+) -> DoctorCheck {
+    let client = match reqwest::Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(e) => return DoctorCheck::fail(service, url, format!("Could not create HTTP client: {}", e), unreachable_hint),
+    };
+    let mut request = client.get(&url);
+    if let Some(token) = bearer {
+        request = request.bearer_auth(token);
+    }
     
-    match id_type.to_lowercase().as_str() {
-        "smiles" => Ok(MoleculeIdType::Smiles),
-        "inchi" => Ok(MoleculeIdType::InChI),
-        "name" => Ok(MoleculeIdType::Name),
-        "cas" => Ok(MoleculeIdType::CasNumber),
-        "pubchem" => Ok(MoleculeIdType::PubChemId),
-        _ => Err(anyhow!("Unsupported ID type: {}", id_type)),
+    match request.send().await {
+        Ok(response) if response.status().is_success() => {
+            let detail = format!("Answered with {}", response.status());
+            DoctorCheck::pass(service, url, detail)
+        }
+        Ok(response) if matches!(response.status().as_u16(), 401 | 403) => {
+            let detail = format!("Answered with {}", response.status());
+            DoctorCheck::fail(service, url, detail, unauthorized_hint)
+        }
+        Ok(response) => {
+            let detail = format!("Answered with {}", response.status());
+            DoctorCheck::fail(service, url, detail, "Check that the URL points at the right service and version")
+        }
+        Err(e) if e.is_timeout() => {
+            let detail = format!("No answer within {}s", timeout.as_secs());
+            DoctorCheck::fail(service, url, detail, unreachable_hint)
+        }
+        Err(e) => DoctorCheck::fail(service, url, format!("Request failed: {}", e), unreachable_hint),
     }
 }
 
+/// Parse molecule ID type, detecting it from the identifier when `auto`
+fn parse_id_type(molecule: &str, id_type: &str) -> Result<MoleculeIdType> {
+    let mol_id_type = if id_type.eq_ignore_ascii_case("auto") {
+        MoleculeIdType::detect(molecule)
+    } else {
+        id_type.parse()?
+    };
+    
+    mol_id_type.validate(molecule)?;
+    Ok(mol_id_type)
+}
+
 /// Convert a molecule to the format expected by the LLM interface
 fn convert_to_llm_molecule(molecule: &Molecule) -> hegel::metacognition::llm::MoleculeData {
     use hegel::metacognition::llm::MoleculeData;
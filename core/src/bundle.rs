@@ -0,0 +1,514 @@
+//! Project Bundles
+//!
+//! A bundle is a zstd-compressed tar archive holding everything that makes up
+//! a project — molecules, evidence, networks, fuzzy network state and reports —
+//! so a complete analysis can be moved between deployments. The archive starts
+//! with a manifest recording the schema version and a SHA-256 checksum of
+//! every other entry; imports refuse bundles with a newer schema, damaged
+//! entries, entries the manifest does not list, more than
+//! [`MAX_BUNDLE_ENTRIES`] entries, or more than [`MAX_ENTRY_BYTES`] in one
+//! entry or [`MAX_BUNDLE_BYTES`] in all of them.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::fuzzy_evidence::FuzzyBayesianNetwork;
+use crate::graph::schema::{MolecularGraph, Node};
+use crate::processing::evidence::Evidence;
+use crate::projects::Project;
+
+/// Version of the bundle layout written by this build
+pub const BUNDLE_SCHEMA_VERSION: u32 = 1;
+
+/// Path of the manifest inside the archive
+const MANIFEST_PATH: &str = "manifest.json";
+
+/// Largest archive entry a bundle may contain, so a hostile bundle cannot exhaust memory
+pub const MAX_ENTRY_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Largest total size of all entries of a bundle
+pub const MAX_BUNDLE_BYTES: u64 = 4 * MAX_ENTRY_BYTES;
+
+/// Most entries a bundle's manifest may list
+pub const MAX_BUNDLE_ENTRIES: usize = 10_000;
+
+/// Size limits applied while reading a bundle
+#[derive(Debug, Clone, Copy)]
+struct ReadLimits {
+    /// Largest single entry
+    entry_bytes: u64,
+
+    /// Largest total of all entries, the manifest included
+    total_bytes: u64,
+
+    /// Most entries the manifest may list
+    entries: usize,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            entry_bytes: MAX_ENTRY_BYTES,
+            total_bytes: MAX_BUNDLE_BYTES,
+            entries: MAX_BUNDLE_ENTRIES,
+        }
+    }
+}
+
+/// Initialize the bundle module
+pub fn initialize() -> Result<()> {
+    info!("Initializing bundle module");
+    info!("Bundle module initialized successfully");
+    Ok(())
+}
+
+/// A stored report about a project's molecules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectReport {
+    /// Report identifier
+    pub id: String,
+
+    /// Molecule the report is about, if it concerns a single molecule
+    pub molecule_id: Option<String>,
+
+    /// When the report was generated
+    pub created_at: DateTime<Utc>,
+
+    /// Report body
+    pub content: serde_json::Value,
+}
+
+/// Checksummed entry of a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleEntry {
+    /// SHA-256 of the entry, hex encoded
+    pub sha256: String,
+
+    /// Size in bytes
+    pub bytes: u64,
+
+    /// Number of records the entry holds
+    pub records: usize,
+}
+
+/// Description of a bundle's contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Bundle layout version
+    pub schema_version: u32,
+
+    /// Version of Hegel that wrote the bundle
+    pub hegel_version: String,
+
+    /// Exported project
+    pub project_id: String,
+
+    /// When the bundle was written
+    pub exported_at: DateTime<Utc>,
+
+    /// Entries by path inside the archive
+    pub entries: BTreeMap<String, BundleEntry>,
+}
+
+/// Complete contents of a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectBundle {
+    /// Project metadata and members
+    pub project: Project,
+
+    /// Molecule node properties
+    pub molecules: Vec<serde_json::Value>,
+
+    /// Evidence node properties, each with the `molecule_id` it supports
+    pub evidence: Vec<serde_json::Value>,
+
+    /// Molecular networks
+    pub networks: Vec<MolecularGraph>,
+
+    /// Fuzzy-Bayesian network state by name
+    pub fuzzy_networks: BTreeMap<String, FuzzyBayesianNetwork>,
+
+    /// Stored reports
+    pub reports: Vec<ProjectReport>,
+}
+
+impl ProjectBundle {
+    /// Create an empty bundle for a project
+    pub fn new(project: Project) -> Self {
+        Self {
+            project,
+            molecules: Vec::new(),
+            evidence: Vec::new(),
+            networks: Vec::new(),
+            fuzzy_networks: BTreeMap::new(),
+            reports: Vec::new(),
+        }
+    }
+
+    /// Add a molecule as the Neo4j store keeps it: its properties with its ID, name and `ext_`-prefixed external IDs
+    pub fn add_molecule(&mut self, molecule: &Node) {
+        let mut record: serde_json::Map<String, serde_json::Value> = molecule.properties.iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        for (system, id) in &molecule.external_ids {
+            record.insert(format!("ext_{}", system), serde_json::json!(id));
+        }
+        record.insert("id".to_string(), serde_json::json!(molecule.id));
+        record.insert("name".to_string(), serde_json::json!(molecule.name));
+        record.insert("project_id".to_string(), serde_json::json!(self.project.id));
+        self.molecules.push(serde_json::Value::Object(record));
+    }
+
+    /// Add an evidence item as the Neo4j store keeps it, with the molecule it supports
+    pub fn add_evidence(&mut self, evidence: &Evidence) -> Result<()> {
+        self.evidence.push(serde_json::json!({
+            "id": evidence.id,
+            "molecule_id": evidence.molecule_id,
+            "project_id": self.project.id,
+            "type": evidence.evidence_type.to_string(),
+            "source": evidence.source,
+            "confidence": evidence.confidence,
+            "timestamp": evidence.timestamp.to_rfc3339(),
+            "data": serde_json::to_string(&evidence.data)?,
+            "metadata": serde_json::to_string(&evidence.metadata)?,
+        }));
+        Ok(())
+    }
+
+    /// Move the bundle's contents to another project ID, e.g. to import alongside the original
+    pub fn retarget(&mut self, project_id: &str) {
+        self.project.id = project_id.to_string();
+        for record in self.molecules.iter_mut().chain(self.evidence.iter_mut()) {
+            if let Some(fields) = record.as_object_mut() {
+                fields.insert("project_id".to_string(), serde_json::json!(project_id));
+            }
+        }
+        for network in &mut self.networks {
+            network.set_project(project_id);
+        }
+    }
+
+    /// Write the bundle as a zstd-compressed tar archive
+    pub fn write<W: Write>(&self, writer: W) -> Result<BundleManifest> {
+        let mut files: BTreeMap<String, (Vec<u8>, usize)> = BTreeMap::new();
+        files.insert("project.json".to_string(), (serde_json::to_vec_pretty(&self.project)?, 1));
+        files.insert("molecules.jsonl".to_string(), (to_json_lines(&self.molecules)?, self.molecules.len()));
+        files.insert("evidence.jsonl".to_string(), (to_json_lines(&self.evidence)?, self.evidence.len()));
+        files.insert("reports.jsonl".to_string(), (to_json_lines(&self.reports)?, self.reports.len()));
+        for network in &self.networks {
+            files.insert(format!("networks/{}.json", entry_name(&network.id)?), (serde_json::to_vec(network)?, 1));
+        }
+        for (name, network) in &self.fuzzy_networks {
+            files.insert(format!("fuzzy/{}.json", entry_name(name)?), (serde_json::to_vec(network)?, 1));
+        }
+
+        let manifest = BundleManifest {
+            schema_version: BUNDLE_SCHEMA_VERSION,
+            hegel_version: crate::VERSION.to_string(),
+            project_id: self.project.id.clone(),
+            exported_at: Utc::now(),
+            entries: files.iter()
+                .map(|(path, (bytes, records))| (path.clone(), BundleEntry {
+                    sha256: sha256_hex(bytes),
+                    bytes: bytes.len() as u64,
+                    records: *records,
+                }))
+                .collect(),
+        };
+
+        let encoder = zstd::Encoder::new(writer, 0).context("Failed to start zstd stream")?;
+        let mut archive = tar::Builder::new(encoder);
+        append(&mut archive, MANIFEST_PATH, &serde_json::to_vec_pretty(&manifest)?)?;
+        for (path, (bytes, _)) in &files {
+            append(&mut archive, path, bytes)?;
+        }
+        archive.into_inner()?.finish().context("Failed to finish zstd stream")?;
+
+        debug!("Wrote bundle for project {} with {} entries", self.project.id, files.len());
+        Ok(manifest)
+    }
+
+    /// Read and verify a bundle archive
+    pub fn read<R: Read>(reader: R) -> Result<(BundleManifest, Self)> {
+        Self::read_with_limits(reader, ReadLimits::default())
+    }
+
+    /// Read a bundle, refusing entries and archives over the given limits
+    ///
+    /// The manifest comes first, so entries it does not list are rejected
+    /// before any of their bytes are buffered.
+    fn read_with_limits<R: Read>(reader: R, limits: ReadLimits) -> Result<(BundleManifest, Self)> {
+        let decoder = zstd::Decoder::new(reader).context("Failed to start zstd stream")?;
+        let mut archive = tar::Archive::new(decoder);
+        let mut entries = archive.entries().context("Failed to read bundle archive")?;
+        let mut total_bytes = 0;
+
+        let manifest: BundleManifest = match entries.next() {
+            Some(entry) => {
+                let mut entry = entry?;
+                if entry.path()?.to_string_lossy() != MANIFEST_PATH {
+                    return Err(anyhow!("Bundle does not start with a manifest"));
+                }
+                let bytes = read_entry(&mut entry, MANIFEST_PATH, limits, &mut total_bytes)?;
+                serde_json::from_slice(&bytes).context("Invalid bundle manifest")?
+            }
+            None => return Err(anyhow!("Bundle has no manifest")),
+        };
+        if manifest.schema_version > BUNDLE_SCHEMA_VERSION {
+            return Err(anyhow!("Bundle schema version {} is newer than the supported version {}",
+                               manifest.schema_version, BUNDLE_SCHEMA_VERSION));
+        }
+        if manifest.entries.len() > limits.entries {
+            return Err(anyhow!("Bundle lists {} entries, more than the {} allowed",
+                               manifest.entries.len(), limits.entries));
+        }
+
+        let mut files: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+        for entry in entries {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let expected = manifest.entries.get(&path)
+                .ok_or_else(|| anyhow!("Bundle entry not in manifest: {}", path))?;
+            if files.contains_key(&path) {
+                return Err(anyhow!("Duplicate bundle entry: {}", path));
+            }
+            if entry.header().size()? != expected.bytes {
+                return Err(anyhow!("Size mismatch for bundle entry: {}", path));
+            }
+            let bytes = read_entry(&mut entry, &path, limits, &mut total_bytes)?;
+            files.insert(path, bytes);
+        }
+
+        for (path, expected) in &manifest.entries {
+            let bytes = files.get(path).ok_or_else(|| anyhow!("Bundle entry missing: {}", path))?;
+            if sha256_hex(bytes) != expected.sha256 {
+                return Err(anyhow!("Checksum mismatch for bundle entry: {}", path));
+            }
+        }
+
+        // Only entries listed in the manifest are trusted
+        let entry = |path: &str| -> Result<&[u8]> {
+            if !manifest.entries.contains_key(path) {
+                return Err(anyhow!("Bundle entry not in manifest: {}", path));
+            }
+            Ok(files[path].as_slice())
+        };
+
+        let mut bundle = Self::new(serde_json::from_slice(entry("project.json")?).context("Invalid project entry")?);
+        bundle.molecules = from_json_lines(entry("molecules.jsonl")?)?;
+        bundle.evidence = from_json_lines(entry("evidence.jsonl")?)?;
+        bundle.reports = from_json_lines(entry("reports.jsonl")?)?;
+        for path in manifest.entries.keys() {
+            if let Some(name) = path.strip_prefix("networks/").and_then(|p| p.strip_suffix(".json")) {
+                bundle.networks.push(serde_json::from_slice(&files[path])
+                    .with_context(|| format!("Invalid network entry: {}", name))?);
+            } else if let Some(name) = path.strip_prefix("fuzzy/").and_then(|p| p.strip_suffix(".json")) {
+                bundle.fuzzy_networks.insert(name.to_string(), serde_json::from_slice(&files[path])
+                    .with_context(|| format!("Invalid fuzzy network entry: {}", name))?);
+            }
+        }
+
+        Ok((manifest, bundle))
+    }
+
+    /// Write the bundle to a file
+    pub fn save(&self, path: &Path) -> Result<BundleManifest> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create bundle: {}", path.display()))?;
+        self.write(std::io::BufWriter::new(file))
+    }
+
+    /// Read a bundle from a file
+    pub fn load(path: &Path) -> Result<(BundleManifest, Self)> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open bundle: {}", path.display()))?;
+        Self::read(std::io::BufReader::new(file))
+    }
+}
+
+/// Read an archive entry within the per-entry limit and what is left of the total limit
+fn read_entry<R: Read>(entry: &mut tar::Entry<R>, path: &str, limits: ReadLimits, total_bytes: &mut u64) -> Result<Vec<u8>> {
+    let remaining = limits.total_bytes.saturating_sub(*total_bytes);
+    let limit = limits.entry_bytes.min(remaining);
+    let too_large = || if limit == limits.entry_bytes {
+        anyhow!("Bundle entry {} exceeds {} bytes", path, limits.entry_bytes)
+    } else {
+        anyhow!("Bundle exceeds {} bytes in total at entry {}", limits.total_bytes, path)
+    };
+
+    // The header size is only a claim, so the read itself is bounded too
+    if entry.header().size()? > limit {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::new();
+    entry.take(limit + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > limit {
+        return Err(too_large());
+    }
+    *total_bytes += bytes.len() as u64;
+    Ok(bytes)
+}
+
+fn append<W: Write>(archive: &mut tar::Builder<W>, path: &str, bytes: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, path, bytes)
+        .with_context(|| format!("Failed to write bundle entry: {}", path))
+}
+
+/// Check that an ID can be used as a file name inside the archive
+fn entry_name(id: &str) -> Result<&str> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return Err(anyhow!("Cannot store {:?} in a bundle", id));
+    }
+    Ok(id)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn to_json_lines<T: Serialize>(records: &[T]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut out, record)?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+fn from_json_lines<T: DeserializeOwned>(bytes: &[u8]) -> Result<Vec<T>> {
+    bytes.split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(i, line)| serde_json::from_slice(line).with_context(|| format!("Invalid record on line {}", i + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projects::ProjectRegistry;
+    use crate::auth::{Principal, UserRole};
+
+    fn bundle() -> ProjectBundle {
+        let owner = Principal { user_id: "alice".to_string(), role: UserRole::Researcher };
+        let project = ProjectRegistry::new().create("Lipidomics", None, &owner).unwrap();
+        let mut bundle = ProjectBundle::new(project);
+        bundle.molecules.push(serde_json::json!({"id": "mol-1", "name": "Glucose", "project_id": bundle.project.id}));
+        bundle.evidence.push(serde_json::json!({"id": "ev-1", "molecule_id": "mol-1", "confidence": 0.9}));
+        bundle.networks.push(MolecularGraph::new("net-1".to_string(), "Similarity".to_string()));
+        bundle.fuzzy_networks.insert("identity".to_string(), FuzzyBayesianNetwork::new());
+        bundle
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let mut original = bundle();
+        original.retarget("copy");
+
+        let mut archive = Vec::new();
+        let manifest = original.write(&mut archive).unwrap();
+        assert_eq!(manifest.entries["molecules.jsonl"].records, 1);
+
+        let (read_manifest, restored) = ProjectBundle::read(archive.as_slice()).unwrap();
+        assert_eq!(read_manifest.project_id, "copy");
+        assert_eq!(restored.molecules[0]["project_id"], "copy");
+        assert_eq!(restored.evidence.len(), 1);
+        assert_eq!(restored.networks[0].project_id, "copy");
+        assert!(restored.fuzzy_networks.contains_key("identity"));
+    }
+
+    /// Unpack a bundle archive into its entries, in archive order
+    fn unpack(archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut files = Vec::new();
+        let mut reader = tar::Archive::new(zstd::Decoder::new(archive).unwrap());
+        for entry in reader.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes).unwrap();
+            files.push((path, bytes));
+        }
+        files
+    }
+
+    /// Pack entries into a bundle archive without updating the manifest
+    fn pack(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        {
+            let mut builder = tar::Builder::new(zstd::Encoder::new(&mut archive, 0).unwrap().auto_finish());
+            for (path, bytes) in files {
+                append(&mut builder, path, bytes).unwrap();
+            }
+            builder.finish().unwrap();
+        }
+        archive
+    }
+
+    #[test]
+    fn test_rejects_oversized_entry() {
+        let mut archive = Vec::new();
+        bundle().write(&mut archive).unwrap();
+
+        let limits = ReadLimits { entry_bytes: 16, ..Default::default() };
+        let err = ProjectBundle::read_with_limits(archive.as_slice(), limits).unwrap_err();
+        assert!(err.to_string().contains("exceeds 16 bytes"));
+        assert!(ProjectBundle::read(archive.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_oversized_or_crowded_bundle() {
+        let mut archive = Vec::new();
+        let manifest = bundle().write(&mut archive).unwrap();
+        let listed: u64 = manifest.entries.values().map(|e| e.bytes).sum();
+
+        // Every entry fits on its own, but not together with the manifest
+        let limits = ReadLimits { entry_bytes: 1024 * 1024, total_bytes: listed, ..Default::default() };
+        let err = ProjectBundle::read_with_limits(archive.as_slice(), limits).unwrap_err();
+        assert!(err.to_string().contains("in total"));
+
+        let limits = ReadLimits { entries: 2, ..Default::default() };
+        let err = ProjectBundle::read_with_limits(archive.as_slice(), limits).unwrap_err();
+        assert!(err.to_string().contains("more than the 2 allowed"));
+    }
+
+    #[test]
+    fn test_rejects_unlisted_entry() {
+        let mut archive = Vec::new();
+        bundle().write(&mut archive).unwrap();
+
+        let mut files = unpack(&archive);
+        files.insert(1, ("padding.bin".to_string(), vec![0; 64]));
+        let err = ProjectBundle::read(pack(&files).as_slice()).unwrap_err();
+        assert!(err.to_string().contains("not in manifest: padding.bin"));
+    }
+
+    #[test]
+    fn test_rejects_tampered_bundle() {
+        let mut archive = Vec::new();
+        bundle().write(&mut archive).unwrap();
+
+        // Recompress with a modified evidence entry of the same size but the original manifest
+        let mut files = unpack(&archive);
+        for (path, bytes) in &mut files {
+            if path == "evidence.jsonl" {
+                let confidence = bytes.windows(3).position(|w| w == b"0.9").unwrap();
+                bytes[confidence..confidence + 3].copy_from_slice(b"1.0");
+            }
+        }
+
+        let err = ProjectBundle::read(pack(&files).as_slice()).unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+    }
+}
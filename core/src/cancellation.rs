@@ -0,0 +1,117 @@
+//! Request Cancellation
+//!
+//! Long operations (LLM completions, graph queries, sidecar calls) run on
+//! behalf of a request that may time out or be abandoned. Each request carries
+//! a `CancellationToken`; the pipeline checks it between steps and races
+//! awaited calls against it, so cancelling the token drops the in-flight call,
+//! releasing its connection, and the work done so far is reported as cancelled
+//! instead of being discarded.
+
+use anyhow::Result;
+use log::{debug, info};
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Initialize the cancellation module
+pub fn initialize() -> Result<()> {
+    info!("Initializing request cancellation module");
+    info!("Request cancellation module initialized successfully");
+    Ok(())
+}
+
+/// Token cancelled after a timeout, or earlier when `parent` is cancelled
+///
+/// Must be called within a Tokio runtime; the timer stops once the token is
+/// cancelled either way.
+pub fn with_timeout(parent: &CancellationToken, timeout: Duration) -> CancellationToken {
+    let token = parent.child_token();
+    let timer = token.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = tokio::time::sleep(timeout) => {
+                debug!("Request timed out after {}s, cancelling", timeout.as_secs_f64());
+                timer.cancel();
+            }
+            _ = timer.cancelled() => {}
+        }
+    });
+    token
+}
+
+/// Fail with a `Cancelled` error if the token has been cancelled
+pub fn check(token: &CancellationToken, operation: &str) -> Result<()> {
+    if token.is_cancelled() {
+        Err(Cancelled::new(operation).into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Run a future to completion unless the token is cancelled first
+///
+/// On cancellation the future is dropped, aborting whatever it was waiting on.
+pub async fn run<T, F>(token: &CancellationToken, operation: &str, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    check(token, operation)?;
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(Cancelled::new(operation).into()),
+        result = future => result,
+    }
+}
+
+/// Whether an error, or any error in its chain, is a cancellation
+pub fn is_cancelled(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<Cancelled>())
+}
+
+/// An operation was abandoned because its request was cancelled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancelled {
+    /// Operation that was cancelled
+    pub operation: String,
+}
+
+impl Cancelled {
+    /// Cancellation of the named operation
+    pub fn new(operation: &str) -> Self {
+        Self { operation: operation.to_string() }
+    }
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} was cancelled", self.operation)
+    }
+}
+
+impl Error for Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[tokio::test]
+    async fn test_cancellation_aborts_pending_work() {
+        let token = CancellationToken::new();
+        let timed = with_timeout(&token, Duration::from_millis(20));
+
+        let pending = std::future::pending::<Result<()>>();
+        let error = run(&timed, "LLM completion", pending).await
+            .context("Rectification failed")
+            .unwrap_err();
+        assert!(is_cancelled(&error));
+        assert!(!token.is_cancelled());
+
+        assert_eq!(run(&token, "graph query", async { Ok(7) }).await.unwrap(), 7);
+        token.cancel();
+        assert!(check(&token, "graph query").is_err());
+    }
+}
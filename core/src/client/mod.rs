@@ -0,0 +1,592 @@
+//! REST API Client
+//!
+//! Typed async client for the Hegel API server. Requests and responses use the
+//! same types as the server (see [`types`]). Idempotent requests are retried
+//! on connection failures, rate limiting and server errors; other requests
+//! are only retried when the connection could not be established, so they are
+//! never applied twice.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug, warn};
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::alerts::{Alert, AlertRule};
+use crate::curation::{CuratorAssertion, ReviewItem};
+use crate::graph::stats::ProjectStats;
+use crate::graph::SerializableNetwork;
+use crate::graph::pathways::PathwayCoherenceView;
+use crate::graph::rdf::RdfFormat;
+use crate::identity::xref::CrossReferences;
+use crate::metacognition::planner::AcquisitionPlan;
+use crate::processing::anomaly::QuarantinedEvidence;
+use crate::processing::mass_spec::{MassSpecProcessingOptions, MassSpecResult};
+use crate::processing::attribution::ConfidenceDecomposition;
+use crate::processing::pipeline::AblationReport;
+use crate::processing::proposals::RectificationProposal;
+use crate::processing::versioning::{MoleculeSnapshot, SnapshotDiff};
+use crate::projects::{Project, ProjectRole};
+use crate::webhooks::{RetryPolicy, Webhook, WebhookDelivery};
+
+pub mod types;
+
+use types::*;
+
+/// Header selecting the project a request operates on
+pub const PROJECT_HEADER: &str = "X-Hegel-Project";
+
+/// Initialize the client module
+pub fn initialize() -> Result<()> {
+    info!("Initializing API client module");
+    info!("API client module initialized successfully");
+    Ok(())
+}
+
+/// Connection settings of an API client
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// Base URL of the API server
+    pub base_url: String,
+
+    /// Bearer token sent with every request
+    pub token: Option<String>,
+
+    /// Project requests operate on; the server's default project if absent
+    pub project_id: Option<String>,
+
+    /// Timeout of a single attempt
+    pub timeout: Duration,
+
+    /// Timeout for establishing a connection
+    pub connect_timeout: Duration,
+
+    /// Retry schedule for failed requests
+    pub retry: RetryPolicy,
+
+    /// Idle connections kept per host; `None` keeps reqwest's default, 0 disables pooling
+    pub max_idle_connections: Option<usize>,
+
+    /// How long an idle pooled connection is kept
+    pub idle_timeout: Duration,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:8080".to_string(),
+            token: None,
+            project_id: None,
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(5),
+            retry: RetryPolicy {
+                max_attempts: 3,
+                initial_backoff_ms: 200,
+                multiplier: 2.0,
+                max_backoff_ms: 5_000,
+            },
+            max_idle_connections: None,
+            idle_timeout: Duration::from_secs(90),
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Options for the server at the given URL
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Read `HEGEL_API_URL`, `HEGEL_API_TOKEN` and `HEGEL_PROJECT`
+    pub fn from_env() -> Self {
+        let mut options = Self::new(&std::env::var("HEGEL_API_URL").unwrap_or_else(|_| Self::default().base_url));
+        options.token = std::env::var("HEGEL_API_TOKEN").ok();
+        options.project_id = std::env::var("HEGEL_PROJECT").ok();
+        options
+    }
+
+    /// Authenticate with a bearer token
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Operate on a project
+    pub fn with_project(mut self, project_id: &str) -> Self {
+        self.project_id = Some(project_id.to_string());
+        self
+    }
+
+    /// Set the timeout of a single attempt
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the retry schedule
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Keep at most this many idle connections per host
+    pub fn with_connection_pool(mut self, max_idle_connections: usize, idle_timeout: Duration) -> Self {
+        self.max_idle_connections = Some(max_idle_connections);
+        self.idle_timeout = idle_timeout;
+        self
+    }
+}
+
+/// Error response of the API
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    /// HTTP status
+    pub status: u16,
+
+    /// Message from the response's `error` field, or its body
+    pub message: String,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "API request failed with status {}: {}", self.status, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Typed client for the Hegel API
+#[derive(Debug, Clone)]
+pub struct HegelClient {
+    /// Pooled HTTP client
+    http: reqwest::Client,
+
+    /// Connection settings
+    options: ClientOptions,
+}
+
+impl HegelClient {
+    /// Create a client with the given options
+    pub fn new(options: ClientOptions) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(options.timeout)
+            .connect_timeout(options.connect_timeout)
+            .pool_idle_timeout(options.idle_timeout);
+        if let Some(max_idle) = options.max_idle_connections {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        let http = builder.build().context("Failed to build HTTP client")?;
+        Ok(Self { http, options })
+    }
+
+    /// Create a client configured from the environment
+    pub fn from_env() -> Result<Self> {
+        Self::new(ClientOptions::from_env())
+    }
+
+    /// Connection settings of this client
+    pub fn options(&self) -> &ClientOptions {
+        &self.options
+    }
+
+    /// A client for the same server operating on another project
+    ///
+    /// The clone shares this client's connection pool.
+    pub fn for_project(&self, project_id: &str) -> Self {
+        Self {
+            http: self.http.clone(),
+            options: self.options.clone().with_project(project_id),
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut request = self.http.request(method, format!("{}{}", self.options.base_url, path));
+        if let Some(token) = &self.options.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(project_id) = &self.options.project_id {
+            request = request.header(PROJECT_HEADER, project_id);
+        }
+        request
+    }
+
+    /// Send a request, retrying according to the policy, and return the response body
+    async fn send(&self, method: Method, path: &str, build: impl Fn(RequestBuilder) -> RequestBuilder) -> Result<Option<Vec<u8>>> {
+        let idempotent = matches!(method, Method::GET | Method::DELETE);
+        let max_attempts = self.options.retry.max_attempts.max(1);
+
+        for attempt in 1..=max_attempts {
+            if attempt > 1 {
+                tokio::time::sleep(self.options.retry.backoff(attempt - 1)).await;
+            }
+            let last = attempt == max_attempts;
+
+            let response = match build(self.request(method.clone(), path)).send().await {
+                Ok(response) => response,
+                Err(e) if !last && (e.is_connect() || (idempotent && e.is_timeout())) => {
+                    warn!("{} {} failed (attempt {}/{}): {}", method, path, attempt, max_attempts, e);
+                    continue;
+                }
+                Err(e) => return Err(anyhow!(e).context(format!("{} {} failed", method, path))),
+            };
+
+            let status = response.status();
+            if status.is_success() {
+                debug!("{} {} -> {}", method, path, status);
+                if status == StatusCode::NO_CONTENT {
+                    return Ok(None);
+                }
+                return Ok(Some(response.bytes().await?.to_vec()));
+            }
+            if !last && idempotent && (status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                warn!("{} {} returned {} (attempt {}/{})", method, path, status, attempt, max_attempts);
+                continue;
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            let message = serde_json::from_str::<serde_json::Value>(&body).ok()
+                .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string))
+                .unwrap_or(body);
+            return Err(ApiError { status: status.as_u16(), message }.into());
+        }
+        unreachable!("the last attempt always returns")
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &(impl Serialize + ?Sized)) -> Result<T> {
+        let body = self.send(Method::GET, path, |r| r.query(query)).await?;
+        decode(path, body)
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, payload: &(impl Serialize + ?Sized)) -> Result<T> {
+        let body = self.send(Method::POST, path, |r| r.json(payload)).await?;
+        decode(path, body)
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        self.send(Method::DELETE, path, |r| r).await?;
+        Ok(())
+    }
+
+    /// Analyze stored evidence for molecules
+    pub async fn analyze(&self, request: &AnalysisRequest) -> Result<AnalysisResponse> {
+        self.post("/api/analyze", request).await
+    }
+
+    /// Rectify submitted evidence
+    pub async fn rectify(&self, request: &RectificationRequest) -> Result<AnalysisResponse> {
+        self.post("/api/rectify", request).await
+    }
+
+    /// Rectification proposals of the project, newest first
+    pub async fn rectification_proposals(&self, query: &ProposalsQuery) -> Result<Vec<RectificationProposal>> {
+        self.get("/api/rectify/proposals", query).await
+    }
+
+    /// A rectification proposal
+    pub async fn rectification_proposal(&self, proposal_id: &str) -> Result<RectificationProposal> {
+        self.get(&format!("/api/rectify/proposals/{}", encode(proposal_id)), &()).await
+    }
+
+    /// Approve or reject adjustments of a rectification proposal
+    pub async fn review_rectification_proposal(&self, proposal_id: &str, request: &ReviewProposalRequest) -> Result<RectificationProposal> {
+        self.post(&format!("/api/rectify/proposals/{}/review", encode(proposal_id)), request).await
+    }
+
+    /// Apply the approved adjustments of a reviewed proposal
+    pub async fn apply_rectification_proposal(&self, proposal_id: &str) -> Result<RectificationProposal> {
+        self.post(&format!("/api/rectify/proposals/{}/apply", encode(proposal_id)), &()).await
+    }
+
+    /// Reactome pathways of a molecule
+    pub async fn reactome_pathways(&self, molecule_id: &str) -> Result<Vec<PathwayData>> {
+        self.get(&format!("/api/reactome/pathways/{}", encode(molecule_id)), &()).await
+    }
+
+    /// Known interactions of a molecule
+    pub async fn interactome(&self, molecule_id: &str) -> Result<Vec<InteractionData>> {
+        self.get(&format!("/api/interactome/{}", encode(molecule_id)), &()).await
+    }
+
+    /// Summary of the genomics analysis
+    pub async fn genomics_analysis(&self) -> Result<serde_json::Value> {
+        self.get("/api/genomics/analysis", &()).await
+    }
+
+    /// Summary of the mass spectrometry analysis
+    pub async fn mass_spec_analysis(&self) -> Result<serde_json::Value> {
+        self.get("/api/mass-spec/analysis", &()).await
+    }
+
+    /// Process mass spectrometry data
+    pub async fn process_mass_spec(&self, request: &MassSpecRequest) -> Result<Vec<MassSpecResult>> {
+        self.post("/api/mass-spec/process", request).await
+    }
+
+    /// Processing options of each instrument profile
+    pub async fn mass_spec_profiles(&self) -> Result<HashMap<String, MassSpecProcessingOptions>> {
+        self.get("/api/mass-spec/profiles", &()).await
+    }
+
+    /// Stored data about a molecule
+    pub async fn molecule(&self, molecule_id: &str) -> Result<serde_json::Value> {
+        self.get(&format!("/api/molecules/{}", encode(molecule_id)), &()).await
+    }
+
+    /// Cross-references of a molecule identifier
+    pub async fn molecule_xrefs(&self, identifier: &str) -> Result<CrossReferences> {
+        self.get(&format!("/api/molecules/{}/xrefs", encode(identifier)), &()).await
+    }
+
+    /// Several molecules with the requested parts joined in one round-trip
+    pub async fn query_molecules(&self, request: &MoleculeQueryRequest) -> Result<MoleculeQueryResponse> {
+        self.post("/api/molecules/query", request).await
+    }
+
+    /// What was believed about a molecule at a point in time (now if `None`)
+    pub async fn molecule_snapshot(&self, molecule_id: &str, at: Option<chrono::DateTime<chrono::Utc>>) -> Result<MoleculeSnapshot> {
+        self.get(&format!("/api/molecules/{}/snapshot", encode(molecule_id)), &SnapshotQuery { at }).await
+    }
+
+    /// How beliefs about a molecule changed between two points in time
+    pub async fn molecule_diff(&self, molecule_id: &str, query: &DiffQuery) -> Result<SnapshotDiff> {
+        self.get(&format!("/api/molecules/{}/diff", encode(molecule_id)), query).await
+    }
+
+    /// Recorded confidence revisions of a molecule and the direction they are moving in
+    pub async fn confidence_history(&self, molecule_id: &str, query: &ConfidenceHistoryQuery) -> Result<ConfidenceHistoryResponse> {
+        self.get(&format!("/api/molecules/{}/confidence-history", encode(molecule_id)), query).await
+    }
+
+    /// Each pathway of a molecule with its co-members, those observed in the project and its coherence
+    pub async fn pathway_coherence(&self, molecule_id: &str) -> Result<PathwayCoherenceView> {
+        self.get(&format!("/api/molecules/{}/pathway-coherence", encode(molecule_id)), &()).await
+    }
+
+    /// Shortest paths between two molecules in the evidence graph
+    pub async fn paths(&self, query: &PathQuery) -> Result<PathResponse> {
+        self.get("/api/path", query).await
+    }
+
+    /// Molecules within a few similarity hops of a molecule, laid out for drawing
+    pub async fn neighborhood(&self, molecule_id: &str, query: &NeighborhoodQuery) -> Result<SerializableNetwork> {
+        self.get(&format!("/api/network/neighborhood/{}", encode(molecule_id)), query).await
+    }
+
+    /// Molecules and evidence matching a text search
+    pub async fn search(&self, query: &SearchQuery) -> Result<SearchResponse> {
+        self.get("/api/search", query).await
+    }
+
+    /// Curation state of a molecule
+    pub async fn curation(&self, molecule_id: &str) -> Result<CurationStatus> {
+        self.get(&format!("/api/molecules/{}/curation", encode(molecule_id)), &()).await
+    }
+
+    /// Lock a molecule's identity with a curator assertion
+    pub async fn lock_molecule(&self, molecule_id: &str, request: &CurationRequest) -> Result<CuratorAssertion> {
+        self.post(&format!("/api/molecules/{}/curation", encode(molecule_id)), request).await
+    }
+
+    /// Remove a curator lock
+    pub async fn unlock_molecule(&self, molecule_id: &str) -> Result<()> {
+        self.delete(&format!("/api/molecules/{}/curation", encode(molecule_id))).await
+    }
+
+    /// Molecules needing a curator's attention, most urgent first
+    pub async fn review_queue(&self, query: &ReviewQueueQuery) -> Result<Vec<ReviewItem>> {
+        self.get("/api/review/queue", query).await
+    }
+
+    /// Evidence held back from integration as anomalous
+    pub async fn quarantine(&self, query: &QuarantineQuery) -> Result<Vec<QuarantinedEvidence>> {
+        self.get("/api/quarantine", query).await
+    }
+
+    /// Release or reject quarantined evidence
+    pub async fn resolve_quarantine(&self, evidence_id: &str, request: &ResolveQuarantineRequest) -> Result<QuarantinedEvidence> {
+        self.post(&format!("/api/quarantine/{}/resolve", encode(evidence_id)), request).await
+    }
+
+    /// Add evidence for a molecule, validated against the evidence schemas
+    ///
+    /// Evidence that breaks its schema is rejected with status 422.
+    pub async fn ingest_evidence(&self, request: &IngestEvidenceRequest) -> Result<IngestEvidenceResponse> {
+        self.post("/api/evidence", request).await
+    }
+
+    /// JSON Schema for the data of each evidence type, by type name
+    pub async fn evidence_schemas(&self) -> Result<HashMap<String, serde_json::Value>> {
+        self.get("/api/evidence/schemas", &()).await
+    }
+
+    /// What-if analysis holding out evidence items or sources
+    pub async fn ablate(&self, request: &AblationRequest) -> Result<AblationReport> {
+        self.post("/api/ablate", request).await
+    }
+
+    /// Confidence broken down into per-item or per-source contributions
+    pub async fn decompose(&self, request: &AblationRequest) -> Result<ConfidenceDecomposition> {
+        self.post("/api/decompose", request).await
+    }
+
+    /// Experiments most likely to raise confidence in uncertain molecules
+    pub async fn plan(&self, request: &PlanRequest) -> Result<AcquisitionPlan> {
+        self.post("/api/plan", request).await
+    }
+
+    /// Similarity of two molecules
+    pub async fn compare(&self, request: &CompareRequest) -> Result<CompareResponse> {
+        self.post("/api/compare", request).await
+    }
+
+    /// Registered similarity metrics
+    pub async fn similarity_metrics(&self) -> Result<SimilarityMetrics> {
+        self.get("/api/similarity/metrics", &()).await
+    }
+
+    /// Whether the server runs offline, and which features that disables
+    pub async fn offline_status(&self) -> Result<OfflineStatus> {
+        self.get("/api/offline", &()).await
+    }
+
+    /// Create a project owned by the caller
+    pub async fn create_project(&self, name: &str, description: Option<&str>) -> Result<Project> {
+        let request = CreateProjectRequest {
+            name: name.to_string(),
+            description: description.map(str::to_string),
+        };
+        self.post("/api/projects", &request).await
+    }
+
+    /// Projects the caller has a role in
+    pub async fn projects(&self) -> Result<Vec<Project>> {
+        self.get("/api/projects", &()).await
+    }
+
+    /// A project by ID
+    pub async fn project(&self, project_id: &str) -> Result<Project> {
+        self.get(&format!("/api/projects/{}", encode(project_id)), &()).await
+    }
+
+    /// Molecule, evidence, conflict and activity figures for a project
+    pub async fn project_stats(&self, project_id: &str) -> Result<ProjectStats> {
+        self.get(&format!("/api/projects/{}/stats", encode(project_id)), &()).await
+    }
+
+    /// A project's molecules, evidence and networks serialized as RDF
+    pub async fn project_rdf(&self, project_id: &str, format: RdfFormat) -> Result<String> {
+        let query = RdfQuery { format: Some(format) };
+        let body = self.send(Method::GET, &format!("/api/projects/{}/rdf", encode(project_id)), |r| r.query(&query)).await?;
+        String::from_utf8(body.unwrap_or_default()).context("RDF export is not valid UTF-8")
+    }
+
+    /// Add a member to a project or change their role
+    pub async fn set_project_member(&self, project_id: &str, user_id: &str, role: ProjectRole) -> Result<Project> {
+        let request = ProjectMemberRequest {
+            user_id: user_id.to_string(),
+            role,
+        };
+        self.post(&format!("/api/projects/{}/members", encode(project_id)), &request).await
+    }
+
+    /// Remove a member from a project
+    pub async fn remove_project_member(&self, project_id: &str, user_id: &str) -> Result<()> {
+        self.delete(&format!("/api/projects/{}/members/{}", encode(project_id), encode(user_id))).await
+    }
+
+    /// Register a webhook (admin only)
+    pub async fn register_webhook(&self, request: &RegisterWebhookRequest) -> Result<Webhook> {
+        self.post("/api/webhooks", request).await
+    }
+
+    /// Registered webhooks (admin only)
+    pub async fn webhooks(&self) -> Result<Vec<Webhook>> {
+        self.get("/api/webhooks", &()).await
+    }
+
+    /// Remove a webhook (admin only)
+    pub async fn delete_webhook(&self, webhook_id: &str) -> Result<()> {
+        self.delete(&format!("/api/webhooks/{}", encode(webhook_id))).await
+    }
+
+    /// Recent webhook deliveries, newest first (admin only)
+    pub async fn webhook_deliveries(&self, query: &DeliveriesQuery) -> Result<Vec<WebhookDelivery>> {
+        self.get("/api/webhooks/deliveries", query).await
+    }
+
+    /// Send a past delivery again (admin only)
+    pub async fn redeliver_webhook(&self, delivery_id: &str) -> Result<WebhookDelivery> {
+        self.post(&format!("/api/webhooks/deliveries/{}/redeliver", encode(delivery_id)), &()).await
+    }
+
+    /// Alerts raised in the project, newest first
+    pub async fn alerts(&self, query: &AlertsQuery) -> Result<Vec<Alert>> {
+        self.get("/api/alerts", query).await
+    }
+
+    /// Alerting rules evaluated for the project
+    pub async fn alert_rules(&self) -> Result<Vec<AlertRule>> {
+        self.get("/api/alerts/rules", &()).await
+    }
+
+    /// Add an alerting rule to the project
+    pub async fn create_alert_rule(&self, request: &CreateAlertRuleRequest) -> Result<AlertRule> {
+        self.post("/api/alerts/rules", request).await
+    }
+
+    /// Remove an alerting rule of the project
+    pub async fn delete_alert_rule(&self, rule_id: &str) -> Result<()> {
+        self.delete(&format!("/api/alerts/rules/{}", encode(rule_id))).await
+    }
+}
+
+/// Percent-encode a path segment
+fn encode(segment: &str) -> String {
+    segment.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Parse a response body, treating an empty response as JSON `null`
+fn decode<T: DeserializeOwned>(path: &str, body: Option<Vec<u8>>) -> Result<T> {
+    let body = body.unwrap_or_else(|| b"null".to_vec());
+    serde_json::from_slice(&body).with_context(|| format!("Unexpected response from {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_segments_are_encoded() {
+        assert_eq!(encode("HMDB0000122"), "HMDB0000122");
+        assert_eq!(encode("InChI=1S/CH4/h1H4"), "InChI%3D1S%2FCH4%2Fh1H4");
+        assert_eq!(encode("a b"), "a%20b");
+
+        let options = ClientOptions::new("http://hegel:8080/").with_project("lipidomics");
+        assert_eq!(options.base_url, "http://hegel:8080");
+        let client = HegelClient::new(options).unwrap();
+        assert_eq!(client.for_project("other").options().project_id.as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn test_molecule_query_wire_format() {
+        let request: MoleculeQueryRequest = serde_json::from_str(r#"{"ids": ["HMDB0000122"], "include": ["pathways", "confidence"]}"#).unwrap();
+        assert_eq!(request.include, Some(vec![MoleculeInclude::Pathways, MoleculeInclude::Confidence]));
+        assert!(request.evidence_limit.is_none());
+
+        let mut record = MoleculeRecord { molecule_id: "HMDB0000122".to_string(), ..Default::default() };
+        record.errors.insert(MoleculeInclude::Interactions, "connection refused".to_string());
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["errors"]["interactions"], "connection refused");
+        assert!(json["pathways"].is_null());
+    }
+}
@@ -0,0 +1,726 @@
+//! API Request and Response Types
+//!
+//! Bodies and query strings of the REST API. The server deserializes requests
+//! into these types and serializes its responses from them, so a client built
+//! on them cannot drift from the server.
+
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::alerts::{AlertCondition, AlertSeverity};
+use crate::curation::{CuratorAssertion, Disagreement, ModelAssessment};
+use crate::offline::NetworkFeature;
+use crate::graph::inspect::ConfidencePoint;
+use crate::graph::paths::MoleculePath;
+use crate::graph::schema::Node;
+use crate::graph::rdf::RdfFormat;
+use crate::processing::anomaly::{QuarantineStatus, Resolution};
+use crate::processing::evidence::{Evidence, IntegratedEvidence};
+use crate::processing::fingerprint::PipelineFingerprint;
+use crate::processing::genomics::GenomicsData;
+use crate::processing::mass_spec::MassSpecData;
+use crate::processing::proposals::{ProposalStatus, ReviewDecision};
+use crate::processing::time_budget::AnalysisStage;
+use crate::processing::versioning::ConfidenceTrend;
+use crate::projects::ProjectRole;
+use crate::search::SearchHit;
+
+/// Body of `POST /api/analyze`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisRequest {
+    /// Molecules to analyze
+    pub molecule_ids: Vec<String>,
+
+    /// Type of evidence to analyze (genomics, mass_spec, ...)
+    pub evidence_type: String,
+
+    /// Evidence below this confidence is ignored
+    pub confidence_threshold: Option<f64>,
+
+    /// Attach a conflict graph in this format (dot, cytoscape)
+    #[serde(default)]
+    pub conflict_graph_format: Option<String>,
+
+    /// Milliseconds each molecule may take; expensive stages are skipped to stay within it
+    #[serde(default)]
+    pub time_budget_ms: Option<u64>,
+}
+
+/// Body of `POST /api/rectify`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RectificationRequest {
+    /// Evidence to rectify by molecule ID
+    pub evidence_data: HashMap<String, Vec<SourceEvidence>>,
+
+    /// How to rectify it
+    pub rectification_options: RectificationOptions,
+
+    /// Return the proposed adjustments without recording anything
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Store the adjustments as proposals awaiting approval instead of applying them
+    #[serde(default)]
+    pub propose: bool,
+
+    /// Milliseconds each molecule may take; the LLM and graph lookups are skipped to stay within it
+    #[serde(default)]
+    pub time_budget_ms: Option<u64>,
+}
+
+/// Options of an evidence rectification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RectificationOptions {
+    /// Ask the LLM to arbitrate conflicting evidence
+    pub use_ai_guidance: bool,
+
+    /// Evidence below this confidence is ignored
+    pub confidence_threshold: f64,
+
+    /// Adjust confidences using pathway membership
+    pub include_pathway_analysis: bool,
+
+    /// Adjust confidences using known interactions
+    pub include_interactome_analysis: bool,
+}
+
+/// Evidence item as submitted by or returned to API clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceEvidence {
+    /// Source of the evidence (mass_spec, genomics, literature, ...)
+    pub source: String,
+
+    /// Raw evidence content
+    pub data: serde_json::Value,
+
+    /// Confidence score (0.0 - 1.0)
+    pub confidence: f64,
+}
+
+/// Response of `POST /api/analyze` and `POST /api/rectify`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisResponse {
+    /// Results by molecule ID
+    pub results: HashMap<String, MoleculeAnalysis>,
+
+    /// Details of the run
+    pub meta: AnalysisMeta,
+}
+
+/// Analysis of one molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoleculeAnalysis {
+    /// Analyzed molecule
+    pub molecule_id: String,
+
+    /// Number of evidence items considered
+    pub evidence_count: usize,
+
+    /// Evidence with adjusted confidences
+    pub rectified_evidence: Vec<RectifiedEvidence>,
+
+    /// Pathways the molecule takes part in
+    pub pathways: Vec<PathwayData>,
+
+    /// Known interactions of the molecule
+    pub interactions: Vec<InteractionData>,
+
+    /// Integrated confidence in the molecule's identity
+    pub confidence_score: f64,
+
+    /// Conflict graph in the requested format, if one was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflict_graph: Option<serde_json::Value>,
+
+    /// Curator lock whose confidence replaced the model's, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curator_assertion: Option<CuratorAssertion>,
+
+    /// Direction the molecule's confidence has been moving in, once it has been analyzed twice
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence_trend: Option<ConfidenceTrend>,
+
+    /// Rectification proposal awaiting approval, when one was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proposal_id: Option<String>,
+
+    /// Stages left out to stay within the request's time budget
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_stages: Vec<AnalysisStage>,
+}
+
+/// Evidence item with its confidence before and after rectification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RectifiedEvidence {
+    /// Source of the evidence
+    pub source: String,
+
+    /// Confidence as submitted
+    pub original_confidence: f64,
+
+    /// Confidence after rectification
+    pub rectified_confidence: f64,
+
+    /// Why the confidence was adjusted, when rectification gave a reason
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adjustment_reason: Option<String>,
+
+    /// Raw evidence content
+    pub data: serde_json::Value,
+}
+
+/// Pathway a molecule takes part in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathwayData {
+    /// Pathway identifier
+    pub pathway_id: String,
+
+    /// Pathway name
+    pub name: String,
+
+    /// Other molecules in the pathway
+    pub molecules: Vec<String>,
+
+    /// Confidence in the pathway membership
+    pub confidence: f64,
+}
+
+/// Interaction between two molecules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionData {
+    /// Queried molecule
+    pub source_molecule: String,
+
+    /// Interacting molecule
+    pub target_molecule: String,
+
+    /// Kind of interaction
+    pub interaction_type: String,
+
+    /// Number of evidence items supporting the interaction
+    pub evidence_count: usize,
+
+    /// Confidence in the interaction
+    pub confidence: f64,
+}
+
+/// Details of an analysis run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisMeta {
+    /// When the analysis finished (RFC 3339)
+    pub timestamp: String,
+
+    /// Server version
+    pub version: String,
+
+    /// Time taken in milliseconds
+    pub execution_time_ms: u64,
+
+    /// Whether the results are proposals that were not recorded
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Whether the request timed out; `results` then holds only the molecules
+    /// finished before it did
+    #[serde(default)]
+    pub cancelled: bool,
+
+    /// Pipeline that produced the results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_fingerprint: Option<PipelineFingerprint>,
+}
+
+/// Genomics data submitted for processing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenomicsRequest {
+    /// Molecule ID this data relates to
+    pub molecule_id: String,
+
+    /// The genomics data to process
+    pub data: GenomicsData,
+}
+
+/// Body of `POST /api/mass-spec/process`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassSpecRequest {
+    /// Molecule ID this data relates to
+    pub molecule_id: String,
+
+    /// The mass spec data to process
+    pub data: MassSpecData,
+
+    /// Instrument profile to process with (orbitrap, q-tof, tof-maldi); server defaults if absent
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+/// Evidence generated from submitted data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessedDataResponse {
+    /// Molecule ID the results relate to
+    pub molecule_id: String,
+
+    /// Evidence generated from the data
+    pub evidence: Vec<SourceEvidence>,
+
+    /// Overall confidence score
+    pub confidence_score: f64,
+
+    /// Processing metadata
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Body of `POST /api/ablate` and `POST /api/decompose`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AblationRequest {
+    /// Molecule the evidence relates to
+    pub molecule_id: String,
+
+    /// Evidence items to analyze
+    pub evidence: Vec<Evidence>,
+
+    /// Group evidence by source instead of by item
+    #[serde(default)]
+    pub by_source: bool,
+}
+
+/// Body of `POST /api/plan`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanRequest {
+    /// Evidence items for one or more molecules, grouped by their molecule ids
+    pub evidence: Vec<Evidence>,
+
+    /// Molecules at or above this confidence are not planned for; defaults to 0.8
+    #[serde(default)]
+    pub confidence_threshold: Option<f64>,
+
+    /// Most experiments recommended per molecule; defaults to 3
+    #[serde(default)]
+    pub max_recommendations: Option<usize>,
+}
+
+/// Body of `POST /api/evidence`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestEvidenceRequest {
+    /// Molecule the evidence relates to
+    pub molecule_id: String,
+
+    /// Evidence items to add; each must match the schema for its type
+    pub evidence: Vec<Evidence>,
+}
+
+/// Response of `POST /api/evidence`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestEvidenceResponse {
+    /// The added evidence, with the schema version it was validated against in its metadata
+    pub evidence: Vec<Evidence>,
+
+    /// The molecule's evidence integrated with the new items
+    pub integrated_evidence: IntegratedEvidence,
+}
+
+/// Query of `GET /api/molecules/{id}/snapshot`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotQuery {
+    /// Point in time to reconstruct (RFC 3339, defaults to now)
+    pub at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Query of `GET /api/molecules/{id}/diff`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffQuery {
+    /// Earlier point in time (RFC 3339)
+    pub from: chrono::DateTime<chrono::Utc>,
+
+    /// Later point in time (RFC 3339, defaults to now)
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Query of `GET /api/molecules/{id}/confidence-history`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfidenceHistoryQuery {
+    /// Only revisions recorded at or after this time (RFC 3339)
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Keep only the most recent revisions
+    pub limit: Option<usize>,
+}
+
+/// Response of `GET /api/molecules/{id}/confidence-history`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceHistoryResponse {
+    /// Molecule ID
+    pub molecule_id: String,
+
+    /// Confidence at each integration recorded in the graph store, oldest first
+    pub revisions: Vec<ConfidencePoint>,
+
+    /// Direction of the most recent revisions, over the whole history
+    pub trend: Option<ConfidenceTrend>,
+}
+
+/// Body of `POST /api/projects`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateProjectRequest {
+    /// Display name of the project
+    pub name: String,
+
+    /// Optional description
+    pub description: Option<String>,
+}
+
+/// Body of `POST /api/projects/{id}/members`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectMemberRequest {
+    /// User to add or update
+    pub user_id: String,
+
+    /// Role in the project (viewer, editor, owner)
+    pub role: ProjectRole,
+}
+
+/// Body of `POST /api/webhooks`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterWebhookRequest {
+    /// Endpoint events are posted to
+    pub url: String,
+
+    /// Shared secret used to sign payloads
+    pub secret: String,
+
+    /// Events to subscribe to (defaults to all)
+    pub events: Option<Vec<String>>,
+
+    /// Confidence thresholds that trigger notifications
+    pub thresholds: Option<Vec<f64>>,
+}
+
+/// Query of `GET /api/webhooks/deliveries`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeliveriesQuery {
+    /// Only deliveries to this webhook
+    pub webhook_id: Option<String>,
+
+    /// Maximum number of deliveries to return (defaults to 50)
+    pub limit: Option<usize>,
+}
+
+/// Query of `GET /api/alerts`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertsQuery {
+    /// Only alerts raised by this rule
+    pub rule_id: Option<String>,
+
+    /// Only alerts about this molecule
+    pub molecule_id: Option<String>,
+
+    /// Maximum number of alerts to return (defaults to 50)
+    pub limit: Option<usize>,
+}
+
+/// Body of `POST /api/alerts/rules`
+///
+/// The rule is limited to the project the request is made in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAlertRuleRequest {
+    /// Human-readable name
+    pub name: String,
+
+    /// Condition that fires the rule
+    pub condition: AlertCondition,
+
+    /// Severity of the alerts raised (defaults to warning)
+    #[serde(default)]
+    pub severity: Option<AlertSeverity>,
+}
+
+/// Query of `GET /api/rectify/proposals`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProposalsQuery {
+    /// Only proposals in this state
+    pub status: Option<ProposalStatus>,
+}
+
+/// Body of `POST /api/rectify/proposals/{id}/review`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewProposalRequest {
+    /// Approve or reject
+    pub decision: ReviewDecision,
+
+    /// Positions of the adjustments decided (defaults to all)
+    #[serde(default)]
+    pub adjustments: Option<Vec<usize>>,
+
+    /// Reviewer's note
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Body of `POST /api/compare`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareRequest {
+    /// SMILES of the first molecule
+    pub smiles1: String,
+
+    /// SMILES of the second molecule
+    pub smiles2: String,
+
+    /// Name of the similarity metric (defaults to tanimoto)
+    pub metric: Option<String>,
+}
+
+/// Response of `POST /api/compare`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareResponse {
+    /// SMILES of the first molecule
+    pub smiles1: String,
+
+    /// SMILES of the second molecule
+    pub smiles2: String,
+
+    /// Metric that was used
+    pub metric: String,
+
+    /// Similarity score
+    pub similarity: f64,
+}
+
+/// Response of `GET /api/similarity/metrics`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarityMetrics {
+    /// Metric used when a request names none
+    pub default: String,
+
+    /// Names of all registered metrics
+    pub metrics: Vec<String>,
+}
+
+/// Response of `GET /api/offline`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineStatus {
+    /// Whether external calls are disabled
+    pub offline: bool,
+
+    /// Features that cannot be used in the current mode
+    pub unavailable_features: Vec<NetworkFeature>,
+}
+
+/// Query of `GET /api/path`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathQuery {
+    /// Molecule the paths start at
+    pub from: String,
+
+    /// Molecule the paths end at
+    pub to: String,
+
+    /// Longest path allowed, in relationships (defaults to 4, at most 10)
+    pub max_hops: Option<usize>,
+
+    /// Maximum number of paths to return (defaults to 5)
+    pub limit: Option<usize>,
+}
+
+/// Response of `GET /api/path`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathResponse {
+    /// Molecule the paths start at
+    pub from: String,
+
+    /// Molecule the paths end at
+    pub to: String,
+
+    /// Hop limit that was applied
+    pub max_hops: usize,
+
+    /// Paths found, cheapest first
+    pub paths: Vec<MoleculePath>,
+}
+
+/// Query of `GET /api/network/neighborhood/{molecule_id}`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NeighborhoodQuery {
+    /// Similarity hops from the molecule (defaults to 2, at most 5)
+    pub radius: Option<usize>,
+
+    /// Minimum similarity of the edges followed (defaults to 0.6)
+    pub min_similarity: Option<f64>,
+
+    /// Maximum number of molecules to return (defaults to 500, at most 5000)
+    pub max_nodes: Option<usize>,
+}
+
+/// Query of `GET /api/projects/{id}/rdf`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RdfQuery {
+    /// Serialization to return (defaults to turtle)
+    pub format: Option<RdfFormat>,
+}
+
+/// Query of `GET /api/search`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchQuery {
+    /// Text to search for in molecule names, synonyms, descriptions and evidence
+    pub q: String,
+
+    /// Maximum number of hits to return (defaults to 20, at most 100)
+    pub limit: Option<usize>,
+
+    /// Also match terms one edit away (defaults to true)
+    pub fuzzy: Option<bool>,
+}
+
+/// Response of `GET /api/search`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponse {
+    /// Text searched for
+    pub query: String,
+
+    /// Matching molecules and evidence, best first
+    pub hits: Vec<SearchHit>,
+}
+
+/// Query of `GET /api/quarantine`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuarantineQuery {
+    /// Only items in this state (pending, released, rejected)
+    pub status: Option<QuarantineStatus>,
+}
+
+/// Body of `POST /api/quarantine/{evidence_id}/resolve`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolveQuarantineRequest {
+    /// Release the evidence into integration or reject it
+    pub resolution: Resolution,
+
+    /// Reason for the decision
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Body of `POST /api/molecules/{id}/curation`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurationRequest {
+    /// Confidence to lock the identity at (0.0 - 1.0)
+    pub confidence: f64,
+
+    /// Why the curator overrides the model
+    pub reason: String,
+
+    /// Identity the curator asserts, if it differs from the molecule's own
+    #[serde(default)]
+    pub identity: Option<String>,
+}
+
+/// Response of `GET /api/molecules/{id}/curation`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurationStatus {
+    /// Molecule concerned
+    pub molecule_id: String,
+
+    /// Current curator lock, if any
+    pub assertion: Option<CuratorAssertion>,
+
+    /// Model's latest conclusion, if any
+    pub assessment: Option<ModelAssessment>,
+
+    /// Times the model disagreed with a curator lock, oldest first
+    pub disagreements: Vec<Disagreement>,
+}
+
+/// Query of `GET /api/review/queue`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReviewQueueQuery {
+    /// Maximum number of molecules to return (defaults to 50)
+    pub limit: Option<usize>,
+}
+
+/// Part of a molecule returned by `POST /api/molecules/query`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoleculeInclude {
+    /// Stored molecule node
+    Molecule,
+
+    /// Pathways the molecule takes part in
+    Pathways,
+
+    /// Interactions with other molecules
+    Interactions,
+
+    /// Live evidence
+    Evidence,
+
+    /// Current confidence score
+    Confidence,
+}
+
+impl MoleculeInclude {
+    /// Every part, the default include mask
+    pub const ALL: [MoleculeInclude; 5] = [
+        MoleculeInclude::Molecule,
+        MoleculeInclude::Pathways,
+        MoleculeInclude::Interactions,
+        MoleculeInclude::Evidence,
+        MoleculeInclude::Confidence,
+    ];
+}
+
+/// Body of `POST /api/molecules/query`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoleculeQueryRequest {
+    /// Molecules to return, at most 100 entries including duplicates, which are returned once
+    pub ids: Vec<String>,
+
+    /// Parts to return for each molecule (defaults to all of them)
+    #[serde(default)]
+    pub include: Option<Vec<MoleculeInclude>>,
+
+    /// Maximum evidence items per molecule (defaults to 50, at most 500)
+    #[serde(default)]
+    pub evidence_limit: Option<usize>,
+}
+
+/// One molecule of a `POST /api/molecules/query` response
+///
+/// Parts that were not requested, or could not be fetched, are `null`; the
+/// reason for each failed part is in `errors`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MoleculeRecord {
+    /// Requested molecule ID
+    pub molecule_id: String,
+
+    /// Stored molecule node
+    pub molecule: Option<Node>,
+
+    /// Pathways the molecule takes part in
+    pub pathways: Option<Vec<PathwayData>>,
+
+    /// Interactions with other molecules
+    pub interactions: Option<Vec<InteractionData>>,
+
+    /// Live evidence, most confident first, cut at the evidence limit
+    pub evidence: Option<Vec<Evidence>>,
+
+    /// Number of live evidence items before the limit was applied
+    pub evidence_total: Option<usize>,
+
+    /// Current confidence score, if one has been recorded
+    pub confidence: Option<f64>,
+
+    /// Parts that could not be fetched, with the error
+    pub errors: BTreeMap<MoleculeInclude, String>,
+}
+
+/// Response of `POST /api/molecules/query`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoleculeQueryResponse {
+    /// Found molecules, in request order
+    pub molecules: Vec<MoleculeRecord>,
+
+    /// Requested IDs with no molecule in the project
+    pub not_found: Vec<String>,
+
+    /// Whether any molecule's evidence was cut at the evidence limit
+    pub truncated: bool,
+}
@@ -0,0 +1,278 @@
+//! Samples and Study Groups
+//!
+//! Evidence is measured in a biological sample, and samples belong to the
+//! groups of a study: cases and controls, treatment arms, time points. A
+//! study design records both, and evidence points at its sample through the
+//! `sample_id` metadata key, so identifications can be compared between
+//! groups, e.g. how confidently a metabolite is identified in cases versus
+//! controls.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::processing::evidence::Evidence;
+
+/// Initialize the cohorts module
+pub fn initialize() -> Result<()> {
+    info!("Initializing cohorts module");
+    info!("Cohorts module initialized successfully");
+    Ok(())
+}
+
+/// A group of samples compared in a study
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StudyGroup {
+    /// Group ID, unique within the project
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// What the group's samples have in common
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A biological sample that evidence was measured in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Sample ID, unique within the project
+    pub id: String,
+
+    /// Study group the sample belongs to, if assigned
+    #[serde(default)]
+    pub group_id: Option<String>,
+
+    /// Subject or patient the sample was taken from
+    #[serde(default)]
+    pub subject_id: Option<String>,
+
+    /// Sample matrix, e.g. plasma or urine
+    #[serde(default)]
+    pub matrix: Option<String>,
+
+    /// When the sample was collected
+    #[serde(default)]
+    pub collected_at: Option<DateTime<Utc>>,
+
+    /// Further attributes, e.g. age or sex of the subject
+    #[serde(default)]
+    pub attributes: HashMap<String, serde_json::Value>,
+}
+
+/// Samples of a study and the groups they belong to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StudyDesign {
+    /// Study groups
+    #[serde(default)]
+    pub groups: Vec<StudyGroup>,
+
+    /// Samples
+    #[serde(default)]
+    pub samples: Vec<Sample>,
+}
+
+impl StudyDesign {
+    /// Load a study design from a JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read study design: {}", path.display()))?;
+        let design: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse study design: {}", path.display()))?;
+        design.validate()?;
+        Ok(design)
+    }
+
+    /// Check that IDs are unique and every sample's group exists
+    pub fn validate(&self) -> Result<()> {
+        let mut group_ids = HashSet::new();
+        for group in &self.groups {
+            if !group_ids.insert(group.id.as_str()) {
+                return Err(anyhow!("Duplicate study group: {}", group.id));
+            }
+        }
+        let mut sample_ids = HashSet::new();
+        for sample in &self.samples {
+            if !sample_ids.insert(sample.id.as_str()) {
+                return Err(anyhow!("Duplicate sample: {}", sample.id));
+            }
+            if let Some(group_id) = &sample.group_id {
+                if !group_ids.contains(group_id.as_str()) {
+                    return Err(anyhow!("Sample {} belongs to unknown study group {}", sample.id, group_id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get a study group by ID
+    pub fn group(&self, group_id: &str) -> Option<&StudyGroup> {
+        self.groups.iter().find(|g| g.id == group_id)
+    }
+
+    /// Get a sample by ID
+    pub fn sample(&self, sample_id: &str) -> Option<&Sample> {
+        self.samples.iter().find(|s| s.id == sample_id)
+    }
+
+    /// Samples of a study group
+    pub fn samples_in<'a>(&'a self, group_id: &'a str) -> impl Iterator<Item = &'a Sample> + 'a {
+        self.samples.iter().filter(move |s| s.group_id.as_deref() == Some(group_id))
+    }
+
+    /// Compare how confidently a molecule is identified in each of the given groups
+    ///
+    /// A sample's confidence is that of the strongest evidence for the
+    /// molecule measured in it. Evidence without a sample, or for a sample
+    /// outside the design, is left out.
+    pub fn compare_groups(&self, molecule_id: &str, evidence: &[Evidence], group_ids: &[&str]) -> Result<GroupComparison> {
+        let mut per_sample: HashMap<&str, (usize, f64)> = HashMap::new();
+        let mut unassigned = 0;
+        for item in evidence.iter().filter(|e| e.molecule_id == molecule_id) {
+            match item.sample_id().filter(|id| self.sample(id).is_some()) {
+                Some(sample_id) => {
+                    let entry = per_sample.entry(sample_id).or_insert((0, 0.0));
+                    entry.0 += 1;
+                    entry.1 = entry.1.max(item.confidence);
+                }
+                None => unassigned += 1,
+            }
+        }
+        if unassigned > 0 {
+            warn!("{} evidence items for {} are not linked to a sample of the study", unassigned, molecule_id);
+        }
+
+        let mut groups = Vec::new();
+        for &group_id in group_ids {
+            let group = self.group(group_id)
+                .ok_or_else(|| anyhow!("Unknown study group: {}", group_id))?;
+            let samples: Vec<&Sample> = self.samples_in(group_id).collect();
+            let measured: BTreeMap<&str, (usize, f64)> = samples.iter()
+                .filter_map(|s| Some((s.id.as_str(), *per_sample.get(s.id.as_str())?)))
+                .collect();
+            let confidences: Vec<f64> = measured.values().map(|(_, c)| *c).collect();
+            groups.push(GroupConfidence {
+                group_id: group.id.clone(),
+                name: group.name.clone(),
+                samples: samples.len(),
+                samples_with_evidence: measured.len(),
+                evidence_count: measured.values().map(|(n, _)| n).sum(),
+                detection_rate: if samples.is_empty() { 0.0 } else { measured.len() as f64 / samples.len() as f64 },
+                mean_confidence: (!confidences.is_empty())
+                    .then(|| confidences.iter().sum::<f64>() / confidences.len() as f64),
+                sample_confidence: measured.into_iter().map(|(id, (_, c))| (id.to_string(), c)).collect(),
+            });
+        }
+
+        let difference = match groups.as_slice() {
+            [first, second] => first.mean_confidence.zip(second.mean_confidence).map(|(a, b)| a - b),
+            _ => None,
+        };
+        debug!("Compared {} across {} study groups", molecule_id, groups.len());
+        Ok(GroupComparison {
+            molecule_id: molecule_id.to_string(),
+            groups,
+            difference,
+        })
+    }
+}
+
+/// How confidently a molecule is identified in the samples of one group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupConfidence {
+    /// Study group ID
+    pub group_id: String,
+
+    /// Study group name
+    pub name: String,
+
+    /// Samples in the group
+    pub samples: usize,
+
+    /// Samples with evidence for the molecule
+    pub samples_with_evidence: usize,
+
+    /// Evidence items for the molecule across the group's samples
+    pub evidence_count: usize,
+
+    /// Share of the group's samples with evidence (0.0 - 1.0)
+    pub detection_rate: f64,
+
+    /// Mean confidence over the samples with evidence
+    pub mean_confidence: Option<f64>,
+
+    /// Confidence in each sample with evidence, by sample ID
+    pub sample_confidence: BTreeMap<String, f64>,
+}
+
+/// Identification confidence of a molecule compared between study groups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupComparison {
+    /// Molecule compared
+    pub molecule_id: String,
+
+    /// Confidence per group, in the order asked for
+    pub groups: Vec<GroupConfidence>,
+
+    /// Mean confidence of the first group minus the second, when exactly two are compared
+    pub difference: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    fn sample(id: &str, group_id: &str) -> Sample {
+        Sample {
+            id: id.to_string(),
+            group_id: Some(group_id.to_string()),
+            subject_id: None,
+            matrix: Some("plasma".to_string()),
+            collected_at: None,
+            attributes: HashMap::new(),
+        }
+    }
+
+    fn group(id: &str) -> StudyGroup {
+        StudyGroup { id: id.to_string(), name: id.to_string(), description: None }
+    }
+
+    #[test]
+    fn test_compare_cases_and_controls() {
+        let design = StudyDesign {
+            groups: vec![group("cases"), group("controls")],
+            samples: vec![sample("s1", "cases"), sample("s2", "cases"), sample("s3", "controls"), sample("s4", "controls")],
+        };
+        let evidence = vec![
+            Evidence::manual("kynurenine", EvidenceType::MassSpec, 0.9, None, "lab").unwrap().with_sample("s1"),
+            Evidence::manual("kynurenine", EvidenceType::MassSpec, 0.6, None, "lab").unwrap().with_sample("s1"),
+            Evidence::manual("kynurenine", EvidenceType::MassSpec, 0.7, None, "lab").unwrap().with_sample("s2"),
+            Evidence::manual("kynurenine", EvidenceType::MassSpec, 0.4, None, "lab").unwrap().with_sample("s3"),
+            Evidence::manual("kynurenine", EvidenceType::MassSpec, 1.0, None, "lab").unwrap(),
+            Evidence::manual("tryptophan", EvidenceType::MassSpec, 1.0, None, "lab").unwrap().with_sample("s4"),
+        ];
+
+        let comparison = design.compare_groups("kynurenine", &evidence, &["cases", "controls"]).unwrap();
+        let cases = &comparison.groups[0];
+        assert_eq!((cases.samples_with_evidence, cases.evidence_count), (2, 3));
+        assert!((cases.mean_confidence.unwrap() - 0.8).abs() < 1e-12);
+        let controls = &comparison.groups[1];
+        assert_eq!(controls.detection_rate, 0.5);
+        assert!((comparison.difference.unwrap() - 0.4).abs() < 1e-12);
+
+        assert!(design.compare_groups("kynurenine", &evidence, &["unknown"]).is_err());
+    }
+
+    #[test]
+    fn test_design_validation() {
+        let mut design = StudyDesign { groups: vec![group("cases")], samples: vec![sample("s1", "cases")] };
+        assert!(design.validate().is_ok());
+        design.samples.push(sample("s2", "controls"));
+        assert!(design.validate().is_err());
+    }
+}
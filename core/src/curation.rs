@@ -0,0 +1,338 @@
+//! Curation Module
+//!
+//! Lets experts overrule the model. A curator can lock a molecule's identity
+//! with an assertion recording who decided, when and why; integration then
+//! reports the curator's confidence instead of its own, while still noting
+//! where the two disagree. Molecules the model is unsure or conflicted about
+//! are collected into a review queue.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::projects::scoped_key;
+
+/// Initialize the curation module
+pub fn initialize() -> Result<()> {
+    info!("Initializing curation module");
+    info!("Curation module initialized successfully");
+    Ok(())
+}
+
+/// Curator's decision about a molecule's identity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CuratorAssertion {
+    /// Project the molecule belongs to
+    pub project_id: String,
+
+    /// Curated molecule
+    pub molecule_id: String,
+
+    /// User who made the assertion
+    pub curator: String,
+
+    /// When the assertion was made
+    pub asserted_at: chrono::DateTime<chrono::Utc>,
+
+    /// Why the curator decided as they did
+    pub reason: String,
+
+    /// Confidence the curator assigns to the identity (0.0 - 1.0)
+    pub confidence: f64,
+
+    /// Identity the curator asserts, if it differs from the molecule's own
+    pub identity: Option<String>,
+}
+
+/// Model's latest conclusion about a molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelAssessment {
+    /// Project the molecule belongs to
+    pub project_id: String,
+
+    /// Assessed molecule
+    pub molecule_id: String,
+
+    /// Integrated confidence
+    pub confidence: f64,
+
+    /// Number of evidence conflicts
+    pub conflicts: usize,
+
+    /// When the assessment was made
+    pub assessed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Model conclusion that differs from a curator's lock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Disagreement {
+    /// Project the molecule belongs to
+    pub project_id: String,
+
+    /// Molecule concerned
+    pub molecule_id: String,
+
+    /// Curator who locked the molecule
+    pub curator: String,
+
+    /// Confidence asserted by the curator
+    pub curator_confidence: f64,
+
+    /// Confidence the model arrived at
+    pub model_confidence: f64,
+
+    /// When the model arrived at it
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Disagreement {
+    /// Model confidence minus curator confidence
+    pub fn difference(&self) -> f64 {
+        self.model_confidence - self.curator_confidence
+    }
+}
+
+/// Molecule waiting for a curator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    /// Molecule to review
+    pub molecule_id: String,
+
+    /// Model's latest confidence
+    pub confidence: f64,
+
+    /// Number of evidence conflicts
+    pub conflicts: usize,
+
+    /// Higher values should be reviewed first
+    pub priority: f64,
+
+    /// Why the molecule needs review
+    pub reasons: Vec<String>,
+}
+
+/// When a molecule needs review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewCriteria {
+    /// Confidence below which a molecule needs review
+    pub max_confidence: f64,
+
+    /// Conflicts at or above which a molecule needs review
+    pub min_conflicts: usize,
+
+    /// Gap between curator and model confidence worth recording and re-reviewing
+    pub disagreement_threshold: f64,
+}
+
+impl Default for ReviewCriteria {
+    fn default() -> Self {
+        Self {
+            max_confidence: 0.6,
+            min_conflicts: 2,
+            disagreement_threshold: 0.3,
+        }
+    }
+}
+
+/// Curator assertions, model assessments and their disagreements
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CurationStore {
+    /// What needs review
+    pub criteria: ReviewCriteria,
+
+    /// Locks by project-scoped molecule ID
+    assertions: HashMap<String, CuratorAssertion>,
+
+    /// Latest model conclusions by project-scoped molecule ID
+    assessments: HashMap<String, ModelAssessment>,
+
+    /// Recorded disagreements, oldest first
+    disagreements: Vec<Disagreement>,
+}
+
+impl CurationStore {
+    /// Create an empty store with the default review criteria
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use different review criteria
+    pub fn with_criteria(mut self, criteria: ReviewCriteria) -> Self {
+        self.criteria = criteria;
+        self
+    }
+
+    /// Lock a molecule's identity, replacing any earlier lock
+    pub fn lock(
+        &mut self,
+        project_id: &str,
+        molecule_id: &str,
+        curator: &str,
+        confidence: f64,
+        reason: &str,
+        identity: Option<String>,
+    ) -> Result<&CuratorAssertion> {
+        if !(0.0..=1.0).contains(&confidence) {
+            return Err(anyhow!("Curated confidence must be between 0 and 1, got {}", confidence));
+        }
+        if reason.trim().is_empty() {
+            return Err(anyhow!("A curator assertion needs a reason"));
+        }
+        info!("Molecule {} of project {} locked by {} at confidence {:.2}", molecule_id, project_id, curator, confidence);
+
+        let key = scoped_key(project_id, molecule_id);
+        self.assertions.insert(key.clone(), CuratorAssertion {
+            project_id: project_id.to_string(),
+            molecule_id: molecule_id.to_string(),
+            curator: curator.to_string(),
+            asserted_at: chrono::Utc::now(),
+            reason: reason.to_string(),
+            confidence,
+            identity,
+        });
+        Ok(&self.assertions[&key])
+    }
+
+    /// Remove a molecule's lock, handing it back to the model
+    pub fn unlock(&mut self, project_id: &str, molecule_id: &str) -> Result<CuratorAssertion> {
+        self.assertions.remove(&scoped_key(project_id, molecule_id))
+            .ok_or_else(|| anyhow!("Molecule {} is not locked", molecule_id))
+    }
+
+    /// Lock on a molecule, if any
+    pub fn assertion(&self, project_id: &str, molecule_id: &str) -> Option<&CuratorAssertion> {
+        self.assertions.get(&scoped_key(project_id, molecule_id))
+    }
+
+    /// Model's latest conclusion about a molecule, if any
+    pub fn assessment(&self, project_id: &str, molecule_id: &str) -> Option<&ModelAssessment> {
+        self.assessments.get(&scoped_key(project_id, molecule_id))
+    }
+
+    /// Record the model's conclusion and return the confidence to report
+    ///
+    /// For a locked molecule that is the curator's confidence; the model's is
+    /// kept, and logged as a disagreement if it is far from the curator's.
+    pub fn apply(&mut self, project_id: &str, molecule_id: &str, model_confidence: f64, conflicts: usize) -> f64 {
+        let now = chrono::Utc::now();
+        let key = scoped_key(project_id, molecule_id);
+        self.assessments.insert(key.clone(), ModelAssessment {
+            project_id: project_id.to_string(),
+            molecule_id: molecule_id.to_string(),
+            confidence: model_confidence,
+            conflicts,
+            assessed_at: now,
+        });
+
+        let assertion = match self.assertions.get(&key) {
+            Some(assertion) => assertion,
+            None => return model_confidence,
+        };
+        if (model_confidence - assertion.confidence).abs() >= self.criteria.disagreement_threshold {
+            debug!("Model confidence {:.2} for {} disagrees with curator {} ({:.2})",
+                   model_confidence, molecule_id, assertion.curator, assertion.confidence);
+            self.disagreements.push(Disagreement {
+                project_id: project_id.to_string(),
+                molecule_id: molecule_id.to_string(),
+                curator: assertion.curator.clone(),
+                curator_confidence: assertion.confidence,
+                model_confidence,
+                recorded_at: now,
+            });
+        }
+        assertion.confidence
+    }
+
+    /// Disagreements recorded in a project, optionally for one molecule
+    pub fn disagreements(&self, project_id: &str, molecule_id: Option<&str>) -> Vec<&Disagreement> {
+        self.disagreements.iter()
+            .filter(|d| d.project_id == project_id)
+            .filter(|d| molecule_id.is_none_or(|m| d.molecule_id == m))
+            .collect()
+    }
+
+    /// Molecules of a project needing review, most urgent first
+    ///
+    /// Unlocked molecules qualify through low confidence or many conflicts;
+    /// locked ones only once the model's latest conclusion disagrees with the
+    /// curator's.
+    pub fn review_queue(&self, project_id: &str, limit: usize) -> Vec<ReviewItem> {
+        let criteria = &self.criteria;
+        let mut queue: Vec<ReviewItem> = self.assessments.iter()
+            .filter(|(_, assessment)| assessment.project_id == project_id)
+            .filter_map(|(key, assessment)| {
+                let mut reasons = Vec::new();
+                match self.assertions.get(key) {
+                    Some(assertion) => {
+                        let gap = (assessment.confidence - assertion.confidence).abs();
+                        if gap >= criteria.disagreement_threshold {
+                            reasons.push(format!("model confidence {:.2} disagrees with {}'s {:.2}",
+                                                 assessment.confidence, assertion.curator, assertion.confidence));
+                        }
+                    }
+                    None => {
+                        if assessment.confidence < criteria.max_confidence {
+                            reasons.push(format!("low confidence ({:.2})", assessment.confidence));
+                        }
+                        if assessment.conflicts >= criteria.min_conflicts {
+                            reasons.push(format!("{} evidence conflicts", assessment.conflicts));
+                        }
+                    }
+                }
+                if reasons.is_empty() {
+                    return None;
+                }
+                Some(ReviewItem {
+                    molecule_id: assessment.molecule_id.clone(),
+                    confidence: assessment.confidence,
+                    conflicts: assessment.conflicts,
+                    priority: (1.0 - assessment.confidence) + assessment.conflicts.min(10) as f64 / 10.0,
+                    reasons,
+                })
+            })
+            .collect();
+        queue.sort_by(|a, b| b.priority.total_cmp(&a.priority).then_with(|| a.molecule_id.cmp(&b.molecule_id)));
+        queue.truncate(limit);
+        queue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_overrides_model() {
+        let mut store = CurationStore::new();
+        assert_eq!(store.apply("p1", "m1", 0.4, 0), 0.4);
+
+        store.lock("p1", "m1", "alice", 0.95, "confirmed against an authentic standard", None).unwrap();
+        assert!(store.lock("p1", "m2", "alice", 1.5, "typo", None).is_err());
+        assert_eq!(store.apply("p1", "m1", 0.4, 0), 0.95);
+        assert_eq!(store.apply("p1", "m1", 0.9, 0), 0.95);
+
+        let disagreements = store.disagreements("p1", Some("m1"));
+        assert_eq!(disagreements.len(), 1);
+        assert!((disagreements[0].difference() + 0.55).abs() < 1e-9);
+
+        store.unlock("p1", "m1").unwrap();
+        assert_eq!(store.apply("p1", "m1", 0.4, 0), 0.4);
+    }
+
+    #[test]
+    fn test_review_queue() {
+        let mut store = CurationStore::new();
+        store.apply("p1", "confident", 0.9, 0);
+        store.apply("p1", "unsure", 0.3, 0);
+        store.apply("p1", "conflicted", 0.7, 4);
+        store.apply("p2", "elsewhere", 0.1, 0);
+        store.lock("p1", "curated", "bob", 0.9, "literature consensus", None).unwrap();
+        store.apply("p1", "curated", 0.2, 0);
+
+        let queue = store.review_queue("p1", 10);
+        let ids: Vec<&str> = queue.iter().map(|i| i.molecule_id.as_str()).collect();
+        assert_eq!(ids, vec!["curated", "conflicted", "unsure"]);
+        assert_eq!(store.review_queue("p1", 1).len(), 1);
+    }
+}
@@ -2,6 +2,15 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use anyhow::{Result, Context};
 
+pub mod propagation;
+pub mod optimization;
+pub mod objectives;
+pub mod variables;
+pub mod structure;
+
+use propagation::{PropagationMethod, PropagationReport};
+use optimization::{OptimizationOptions, OptimizationReport, Optimizer, OptimizerKind, Parameter, ParameterChange};
+
 /// Fuzzy membership function types for evidence evaluation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FuzzyMembershipFunction {
@@ -56,6 +65,8 @@ pub struct FuzzyLinguisticVariable {
     pub name: String,
     pub universe: (f64, f64), // (min, max) range
     pub terms: HashMap<String, FuzzyMembershipFunction>,
+    #[serde(default)]
+    pub term_values: HashMap<String, f64>, // Crisp value of each term when defuzzifying
 }
 
 impl FuzzyLinguisticVariable {
@@ -75,10 +86,16 @@ impl FuzzyLinguisticVariable {
         terms.insert("very_high".to_string(), 
             FuzzyMembershipFunction::Triangular { low: 0.8, peak: 1.0, high: 1.0 });
         
+        let term_values = [("very_low", 0.1), ("low", 0.3), ("medium", 0.5), ("high", 0.8), ("very_high", 0.95)]
+            .into_iter()
+            .map(|(term, value)| (term.to_string(), value))
+            .collect();
+        
         FuzzyLinguisticVariable {
             name: "evidence_confidence".to_string(),
             universe: (0.0, 1.0),
             terms,
+            term_values,
         }
     }
     
@@ -93,10 +110,15 @@ impl FuzzyLinguisticVariable {
         terms.insert("supporting".to_string(), 
             FuzzyMembershipFunction::Trapezoidal { low: 0.5, low_peak: 0.7, high_peak: 1.0, high: 1.0 });
         
+        let term_values = terms.iter()
+            .map(|(term, function)| (term.clone(), function.representative_value()))
+            .collect();
+        
         FuzzyLinguisticVariable {
             name: "evidence_agreement".to_string(),
             universe: (0.0, 1.0),
             terms,
+            term_values,
         }
     }
     
@@ -120,6 +142,8 @@ pub struct FuzzyEvidence {
     pub contextual_factors: HashMap<String, f64>,     // Additional fuzzy factors
     pub temporal_decay: f64,                          // Time-based confidence decay
     pub uncertainty_bounds: (f64, f64),               // Confidence interval bounds
+    #[serde(default)]
+    pub term_values: HashMap<String, f64>,            // Crisp values of the confidence terms
 }
 
 impl FuzzyEvidence {
@@ -131,8 +155,19 @@ impl FuzzyEvidence {
         raw_value: f64,
         timestamp: chrono::DateTime<chrono::Utc>,
     ) -> Self {
-        let confidence_var = FuzzyLinguisticVariable::evidence_confidence();
-        
+        Self::from_raw_evidence_with(id, source, evidence_type, raw_value, timestamp,
+                                     &FuzzyLinguisticVariable::evidence_confidence())
+    }
+    
+    /// Create new fuzzy evidence, fuzzifying its confidence with a custom variable
+    pub fn from_raw_evidence_with(
+        id: String,
+        source: String,
+        evidence_type: String,
+        raw_value: f64,
+        timestamp: chrono::DateTime<chrono::Utc>,
+        confidence_var: &FuzzyLinguisticVariable,
+    ) -> Self {
         // Calculate temporal decay (evidence gets less reliable over time)
         let age_hours = chrono::Utc::now().signed_duration_since(timestamp).num_hours() as f64;
         let temporal_decay = (-age_hours / (24.0 * 30.0)).exp(); // Decay over ~30 days
@@ -155,6 +190,7 @@ impl FuzzyEvidence {
             contextual_factors: HashMap::new(),
             temporal_decay,
             uncertainty_bounds,
+            term_values: confidence_var.term_values.clone(),
         }
     }
     
@@ -164,13 +200,16 @@ impl FuzzyEvidence {
         let mut denominator = 0.0;
         
         for (term, membership) in &self.confidence_memberships {
-            let term_value = match term.as_str() {
-                "very_low" => 0.1,
-                "low" => 0.3,
-                "medium" => 0.5,
-                "high" => 0.8,
-                "very_high" => 0.95,
-                _ => 0.5,
+            let term_value = match self.term_values.get(term) {
+                Some(value) => *value,
+                None => match term.as_str() {
+                    "very_low" => 0.1,
+                    "low" => 0.3,
+                    "medium" => 0.5,
+                    "high" => 0.8,
+                    "very_high" => 0.95,
+                    _ => 0.5,
+                },
             };
             
             numerator += term_value * membership * self.temporal_decay;
@@ -217,13 +256,20 @@ pub struct FuzzyConsequent {
 }
 
 /// Hybrid Fuzzy-Bayesian Evidence Network
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FuzzyBayesianNetwork {
     pub nodes: HashMap<String, EvidenceNode>,
     pub edges: Vec<EvidenceEdge>,
     pub fuzzy_rules: Vec<FuzzyRule>,
     pub linguistic_variables: HashMap<String, FuzzyLinguisticVariable>,
     pub objective_functions: HashMap<String, ObjectiveFunction>,
+    pub propagation: PropagationMethod,
+    pub last_propagation: Option<PropagationReport>,
+    #[serde(default)]
+    pub optimizer: OptimizerKind,
+    #[serde(default)]
+    pub optimization_options: OptimizationOptions,
+    pub last_optimization: Option<OptimizationReport>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -276,6 +322,8 @@ pub enum ObjectiveFunctionType {
     MaximizeConsistency,
     MinimizeConflicts,
     MaximizeNetworkCoherence,
+    /// Evaluator registered under this name in the global `ObjectiveRegistry`
+    Custom(String),
 }
 
 impl FuzzyBayesianNetwork {
@@ -294,6 +342,11 @@ impl FuzzyBayesianNetwork {
             fuzzy_rules: Self::default_fuzzy_rules(),
             linguistic_variables,
             objective_functions,
+            propagation: PropagationMethod::default(),
+            last_propagation: None,
+            optimizer: OptimizerKind::default(),
+            optimization_options: OptimizationOptions::default(),
+            last_optimization: None,
         }
     }
     
@@ -384,6 +437,23 @@ impl FuzzyBayesianNetwork {
     
     /// Calculate network influence between connected nodes
     fn calculate_network_influence(&mut self) -> Result<()> {
+        self.propagate_influence()?;
+        if let (PropagationMethod::LoopyBelief(_), Some(report)) = (&self.propagation, &self.last_propagation) {
+            if !report.converged {
+                log::warn!("Belief propagation did not converge after {} iterations (max delta {:.2e})",
+                           report.iterations, report.max_delta);
+            }
+        }
+        Ok(())
+    }
+    
+    fn propagate_influence(&mut self) -> Result<()> {
+        if let PropagationMethod::LoopyBelief(config) = &self.propagation {
+            let report = propagation::loopy_belief_propagation(&mut self.nodes, &self.edges, config);
+            self.last_propagation = Some(report);
+            return Ok(());
+        }
+        
         for edge in &self.edges.clone() {
             let influence = self.calculate_edge_influence(edge)?;
             
@@ -396,13 +466,139 @@ impl FuzzyBayesianNetwork {
     
     /// Optimize network using granular objective functions
     fn optimize_with_objective_functions(&mut self) -> Result<()> {
-        for (name, objective) in &self.objective_functions.clone() {
-            let optimization_result = self.evaluate_objective_function(objective)?;
-            self.apply_optimization_adjustments(name, &optimization_result)?;
+        let optimizer = self.optimizer.clone();
+        let report = self.optimize_with(optimizer.optimizer())?;
+        if !report.converged {
+            log::warn!("Objective optimization did not converge after {} iterations", report.iterations);
         }
         Ok(())
     }
     
+    /// Adjustable parameters: every edge strength, then every node prior by node ID
+    pub fn parameters(&self) -> Vec<Parameter> {
+        let mut node_ids: Vec<&String> = self.nodes.keys().collect();
+        node_ids.sort();
+        (0..self.edges.len()).map(Parameter::EdgeStrength)
+            .chain(node_ids.into_iter().map(|id| Parameter::Prior(id.clone())))
+            .collect()
+    }
+    
+    fn parameter_value(&self, parameter: &Parameter) -> f64 {
+        match parameter {
+            Parameter::EdgeStrength(i) => self.edges.get(*i).map(|e| e.strength).unwrap_or(0.0),
+            Parameter::Prior(id) => self.nodes.get(id).map(|n| n.prior_probability).unwrap_or(0.5),
+        }
+    }
+    
+    fn set_parameters(&mut self, parameters: &[Parameter], values: &[f64]) {
+        for (parameter, value) in parameters.iter().zip(values) {
+            match parameter {
+                Parameter::EdgeStrength(i) => {
+                    if let Some(edge) = self.edges.get_mut(*i) {
+                        edge.strength = *value;
+                    }
+                }
+                Parameter::Prior(id) => {
+                    if let Some(node) = self.nodes.get_mut(id) {
+                        node.prior_probability = *value;
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Recompute posteriors and influence from the current parameters
+    fn recompute(&mut self) -> Result<()> {
+        self.update_bayesian_probabilities()?;
+        for node in self.nodes.values_mut() {
+            node.network_influence = 0.0;
+        }
+        self.propagate_influence()
+    }
+    
+    /// Add the objective functions of a config, replacing the built-in ones if it asks to
+    pub fn apply_objective_config(&mut self, config: &objectives::ObjectiveConfig) {
+        if config.replace_defaults {
+            self.objective_functions.clear();
+        }
+        for (name, objective) in &config.objectives {
+            self.objective_functions.insert(name.clone(), objective.clone());
+        }
+    }
+    
+    /// Sum of the weighted scores of all objective functions
+    pub fn objective_score(&self) -> Result<f64> {
+        let mut total = 0.0;
+        for objective in self.objective_functions.values() {
+            total += self.evaluate_objective_function(objective)?.total_score;
+        }
+        Ok(total)
+    }
+    
+    /// Objective score the network would have with the given parameter values
+    fn score_with(&self, parameters: &[Parameter], values: &[f64]) -> f64 {
+        let mut candidate = self.clone();
+        candidate.set_parameters(parameters, values);
+        candidate.recompute()
+            .and_then(|_| candidate.objective_score())
+            .ok()
+            .filter(|score| score.is_finite())
+            .unwrap_or(f64::NEG_INFINITY)
+    }
+    
+    /// Search edge strengths and priors for the values maximising the objective
+    /// functions, and keep them if they improve on the current ones
+    pub fn optimize_with(&mut self, optimizer: &dyn Optimizer) -> Result<OptimizationReport> {
+        let parameters = self.parameters();
+        let start: Vec<f64> = parameters.iter().map(|p| self.parameter_value(p)).collect();
+        let bounds: Vec<(f64, f64)> = parameters.iter().map(Parameter::bounds).collect();
+        let seed = crate::rng::resolve_seed(self.optimization_options.seed);
+        let mut rng = crate::rng::stream_rng(seed, "objective_optimization");
+        
+        let outcome = if parameters.is_empty() || self.objective_functions.is_empty() {
+            None
+        } else {
+            // Fail on unknown custom components here rather than scoring every candidate as worthless
+            self.objective_score()?;
+            let mut objective = |values: &[f64]| self.score_with(&parameters, values);
+            Some(optimizer.optimize(&start, &bounds, &mut objective, &self.optimization_options, &mut rng))
+        };
+        
+        let mut report = OptimizationReport {
+            optimizer: optimizer.name().to_string(),
+            seed,
+            initial_score: 0.0,
+            final_score: 0.0,
+            iterations: 0,
+            evaluations: 0,
+            converged: true,
+            history: Vec::new(),
+            changes: Vec::new(),
+        };
+        if let Some(outcome) = outcome {
+            report.initial_score = outcome.initial_score;
+            report.final_score = outcome.initial_score;
+            report.iterations = outcome.iterations;
+            report.evaluations = outcome.evaluations;
+            report.converged = outcome.converged;
+            if outcome.best_score > outcome.initial_score {
+                report.changes = parameters.iter().zip(&start).zip(&outcome.best)
+                    .filter(|((_, from), to)| (*from - *to).abs() > f64::EPSILON)
+                    .map(|((parameter, from), to)| ParameterChange { parameter: parameter.clone(), from: *from, to: *to })
+                    .collect();
+                self.set_parameters(&parameters, &outcome.best);
+                self.recompute()?;
+                report.final_score = outcome.best_score;
+            }
+            report.history = outcome.history;
+        }
+        
+        log::debug!("{} raised the objective from {:.4} to {:.4} in {} iterations ({} parameters changed)",
+                    report.optimizer, report.initial_score, report.final_score, report.iterations, report.changes.len());
+        self.last_optimization = Some(report.clone());
+        Ok(report)
+    }
+    
     // Helper methods for network operations
     fn get_connected_evidence(&self, node_id: &str) -> Vec<&EvidenceNode> {
         self.edges.iter()
@@ -497,12 +693,11 @@ impl FuzzyBayesianNetwork {
         Ok(ObjectiveResult {
             total_score,
             component_scores,
-            recommendations: self.generate_optimization_recommendations(objective, &component_scores)?,
         })
     }
     
     fn evaluate_objective_component(&self, component: &ObjectiveComponent) -> Result<f64> {
-        match component.function_type {
+        match &component.function_type {
             ObjectiveFunctionType::MaximizeConfidence => {
                 let avg_confidence: f64 = self.nodes.values()
                     .filter_map(|node| node.fuzzy_evidence.as_ref())
@@ -559,53 +754,10 @@ impl FuzzyBayesianNetwork {
                 })?;
                 Ok((connectivity + consistency) / 2.0)
             }
-        }
-    }
-    
-    fn apply_optimization_adjustments(&mut self, _objective_name: &str, result: &ObjectiveResult) -> Result<()> {
-        // Apply adjustments based on optimization results
-        for recommendation in &result.recommendations {
-            self.apply_optimization_recommendation(recommendation)?;
-        }
-        Ok(())
-    }
-    
-    fn apply_optimization_recommendation(&mut self, recommendation: &OptimizationRecommendation) -> Result<()> {
-        match recommendation.action_type {
-            OptimizationAction::AdjustConfidence => {
-                if let Some(node) = self.nodes.get_mut(&recommendation.target_node) {
-                    node.posterior_probability = (node.posterior_probability + recommendation.adjustment).clamp(0.0, 1.0);
-                }
-            }
-            OptimizationAction::AddEdge => {
-                // Add new edge based on recommendation
-            }
-            OptimizationAction::RemoveEdge => {
-                // Remove edge based on recommendation
-            }
-            OptimizationAction::UpdateWeight => {
-                // Update edge weight
-            }
-        }
-        Ok(())
-    }
-    
-    fn generate_optimization_recommendations(&self, _objective: &ObjectiveFunction, scores: &HashMap<String, f64>) -> Result<Vec<OptimizationRecommendation>> {
-        let mut recommendations = Vec::new();
-        
-        // Generate recommendations based on objective function performance
-        for (component_name, &score) in scores {
-            if score < 0.5 { // Below threshold
-                recommendations.push(OptimizationRecommendation {
-                    target_node: "global".to_string(),
-                    action_type: OptimizationAction::AdjustConfidence,
-                    adjustment: 0.1,
-                    reasoning: format!("Improve {} score from {:.2}", component_name, score),
-                });
+            ObjectiveFunctionType::Custom(name) => {
+                objectives::ObjectiveRegistry::global().get(name)?.evaluate(self, &component.parameters)
             }
         }
-        
-        Ok(recommendations)
     }
     
     /// Default fuzzy rules for molecular evidence
@@ -706,24 +858,6 @@ pub struct EvidencePrediction {
 pub struct ObjectiveResult {
     pub total_score: f64,
     pub component_scores: HashMap<String, f64>,
-    pub recommendations: Vec<OptimizationRecommendation>,
-}
-
-/// Optimization recommendation
-#[derive(Debug, Clone)]
-pub struct OptimizationRecommendation {
-    pub target_node: String,
-    pub action_type: OptimizationAction,
-    pub adjustment: f64,
-    pub reasoning: String,
-}
-
-#[derive(Debug, Clone)]
-pub enum OptimizationAction {
-    AdjustConfidence,
-    AddEdge,
-    RemoveEdge,
-    UpdateWeight,
 }
 
 #[cfg(test)]
@@ -765,4 +899,32 @@ mod tests {
         assert!(network.add_evidence(evidence).is_ok());
         assert!(network.nodes.contains_key("test_evidence"));
     }
+    
+    #[test]
+    fn test_objective_optimization_adjusts_priors() {
+        let mut network = FuzzyBayesianNetwork::new();
+        network.optimization_options = optimization::OptimizationOptions::default().with_seed(5);
+        for (id, value) in [("a", 0.9), ("b", 0.2)] {
+            let evidence = FuzzyEvidence::from_raw_evidence(
+                id.to_string(), "mass_spec".to_string(), "spectral_match".to_string(), value, chrono::Utc::now());
+            network.add_evidence(evidence).unwrap();
+        }
+        network.edges.push(EvidenceEdge {
+            from_node: "a".to_string(),
+            to_node: "b".to_string(),
+            relationship_type: EvidenceRelationship::Supports,
+            strength: 0.8,
+            fuzzy_strength: HashMap::new(),
+        });
+        network.update_network().unwrap();
+        
+        let report = network.last_optimization.clone().unwrap();
+        assert_eq!(report.optimizer, "hill_climbing");
+        assert!(report.final_score > report.initial_score);
+        assert!(!report.changes.is_empty());
+        assert!((network.objective_score().unwrap() - report.final_score).abs() < 1e-9);
+        
+        let annealed = network.optimize_with(&optimization::SimulatedAnnealing::default()).unwrap();
+        assert!(annealed.final_score >= annealed.initial_score);
+    }
 } 
\ No newline at end of file
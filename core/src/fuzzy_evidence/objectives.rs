@@ -0,0 +1,241 @@
+//! Custom Objective Components
+//!
+//! Lets users score the evidence network by their own criteria. Evaluators are
+//! registered by name and referenced from an objective function as
+//! `ObjectiveFunctionType::Custom(name)`, so they are weighted and optimized
+//! alongside the built-in components. Objective functions themselves can be
+//! loaded from a JSON config file.
+
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use super::{FuzzyBayesianNetwork, ObjectiveFunction};
+
+/// A named score of the network's state; higher is better
+pub trait ObjectiveEvaluator: Send + Sync {
+    /// Name under which the evaluator is registered
+    fn name(&self) -> &str;
+
+    /// Score the network, usually between 0.0 and 1.0, using the component's parameters
+    fn evaluate(&self, network: &FuzzyBayesianNetwork, parameters: &HashMap<String, f64>) -> Result<f64>;
+}
+
+/// Agreement of node posteriors with a curated reference set
+///
+/// The component's parameters map node IDs to their reference probability;
+/// the score is one minus the mean absolute difference over the nodes present.
+pub struct ReferenceAgreement;
+
+impl ObjectiveEvaluator for ReferenceAgreement {
+    fn name(&self) -> &str {
+        "reference_agreement"
+    }
+
+    fn evaluate(&self, network: &FuzzyBayesianNetwork, parameters: &HashMap<String, f64>) -> Result<f64> {
+        let differences: Vec<f64> = parameters.iter()
+            .filter_map(|(id, reference)| network.nodes.get(id).map(|n| (n.posterior_probability - reference).abs()))
+            .collect();
+        if differences.is_empty() {
+            return Ok(0.5);
+        }
+        Ok(1.0 - differences.iter().sum::<f64>() / differences.len() as f64)
+    }
+}
+
+/// Objective evaluator backed by a closure
+pub struct FnObjective<F> {
+    /// Name of the evaluator
+    name: String,
+
+    /// Function computing the score
+    func: F,
+}
+
+impl<F> FnObjective<F>
+where
+    F: Fn(&FuzzyBayesianNetwork, &HashMap<String, f64>) -> Result<f64> + Send + Sync,
+{
+    /// Wrap a closure as a named objective evaluator
+    pub fn new(name: &str, func: F) -> Self {
+        Self {
+            name: name.to_string(),
+            func,
+        }
+    }
+}
+
+impl<F> ObjectiveEvaluator for FnObjective<F>
+where
+    F: Fn(&FuzzyBayesianNetwork, &HashMap<String, f64>) -> Result<f64> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn evaluate(&self, network: &FuzzyBayesianNetwork, parameters: &HashMap<String, f64>) -> Result<f64> {
+        (self.func)(network, parameters)
+    }
+}
+
+/// Registry of custom objective evaluators keyed by name
+pub struct ObjectiveRegistry {
+    /// Registered evaluators
+    evaluators: RwLock<HashMap<String, Arc<dyn ObjectiveEvaluator>>>,
+}
+
+impl ObjectiveRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            evaluators: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create a registry containing the bundled evaluators
+    pub fn with_defaults() -> Self {
+        let registry = Self::new();
+        registry.register(Arc::new(ReferenceAgreement));
+        registry
+    }
+
+    /// Get the process-wide registry networks evaluate custom components with
+    pub fn global() -> &'static ObjectiveRegistry {
+        static REGISTRY: OnceLock<ObjectiveRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(ObjectiveRegistry::with_defaults)
+    }
+
+    /// Register an evaluator, replacing any evaluator with the same name
+    pub fn register(&self, evaluator: Arc<dyn ObjectiveEvaluator>) {
+        let name = evaluator.name().to_string();
+        debug!("Registering objective component: {}", name);
+        self.evaluators.write().unwrap().insert(name, evaluator);
+    }
+
+    /// Register a closure as a named evaluator
+    pub fn register_fn<F>(&self, name: &str, func: F)
+    where
+        F: Fn(&FuzzyBayesianNetwork, &HashMap<String, f64>) -> Result<f64> + Send + Sync + 'static,
+    {
+        self.register(Arc::new(FnObjective::new(name, func)));
+    }
+
+    /// Get an evaluator by name
+    pub fn get(&self, name: &str) -> Result<Arc<dyn ObjectiveEvaluator>> {
+        self.evaluators.read().unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown objective component: {}", name))
+    }
+
+    /// Names of all registered evaluators
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.evaluators.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for ObjectiveRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+/// Objective functions read from a config file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObjectiveConfig {
+    /// Objective functions by name; an entry named like an existing one replaces it
+    pub objectives: HashMap<String, ObjectiveFunction>,
+
+    /// Drop the built-in objective functions before adding these
+    pub replace_defaults: bool,
+}
+
+impl ObjectiveConfig {
+    /// Load objective functions from a JSON file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read objective config: {}", path.display()))?;
+        let config: Self = serde_json::from_str(&content).context("Failed to parse objective config")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load the config named by `HEGEL_OBJECTIVES`, or an empty one
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("HEGEL_OBJECTIVES") {
+            Ok(path) => Self::from_file(Path::new(&path)),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Check every objective has components and no negative weights
+    ///
+    /// Custom components are resolved when evaluated, so evaluators may be
+    /// registered after the config is loaded.
+    pub fn validate(&self) -> Result<()> {
+        for (name, objective) in &self.objectives {
+            if objective.components.is_empty() {
+                return Err(anyhow!("Objective '{}' has no components", name));
+            }
+            if let Some((component, weight)) = objective.weights.iter().find(|(_, w)| **w < 0.0) {
+                return Err(anyhow!("Objective '{}' gives component '{}' negative weight {}", name, component, weight));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy_evidence::{FuzzyEvidence, ObjectiveComponent, ObjectiveFunctionType};
+
+    #[test]
+    fn test_custom_component_from_config() {
+        ObjectiveRegistry::global().register_fn("test_mean_prior", |network, _| {
+            let total: f64 = network.nodes.values().map(|n| n.prior_probability).sum();
+            Ok(total / network.nodes.len().max(1) as f64)
+        });
+        assert!(ObjectiveRegistry::global().get("missing").is_err());
+
+        let config: ObjectiveConfig = serde_json::from_str(r#"{
+            "replace_defaults": true,
+            "objectives": {
+                "curated": {
+                    "name": "curated",
+                    "components": [
+                        {"name": "reference", "function_type": {"Custom": "reference_agreement"}, "parameters": {"a": 0.9}},
+                        {"name": "prior", "function_type": {"Custom": "test_mean_prior"}, "parameters": {}}
+                    ],
+                    "weights": {"reference": 0.7, "prior": 0.3}
+                }
+            }
+        }"#).unwrap();
+        config.validate().unwrap();
+
+        let mut network = FuzzyBayesianNetwork::new();
+        network.apply_objective_config(&config);
+        assert_eq!(network.objective_functions.len(), 1);
+        network.add_evidence(FuzzyEvidence::from_raw_evidence(
+            "a".to_string(), "mass_spec".to_string(), "spectral_match".to_string(), 0.6, chrono::Utc::now())).unwrap();
+
+        let before = network.objective_score().unwrap();
+        network.update_network().unwrap();
+        let report = network.last_optimization.clone().unwrap();
+        assert!(report.final_score > before);
+        assert!(network.nodes["a"].prior_probability > 0.5);
+
+        network.objective_functions.get_mut("curated").unwrap().components.push(ObjectiveComponent {
+            name: "broken".to_string(),
+            function_type: ObjectiveFunctionType::Custom("not_registered".to_string()),
+            parameters: HashMap::new(),
+        });
+        assert!(network.objective_score().is_err());
+    }
+}
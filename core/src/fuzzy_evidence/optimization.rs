@@ -0,0 +1,512 @@
+//! Objective Optimization for the Evidence Network
+//!
+//! Searches the adjustable parameters of the network — edge strengths and node
+//! priors — for the values that maximise its weighted objective functions.
+//! Optimizers see only a bounded parameter vector and a scoring function, so
+//! any search strategy implementing `Optimizer` can be plugged in; hill
+//! climbing, simulated annealing and a genetic algorithm are provided.
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Network value an optimizer may adjust
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Parameter {
+    /// Strength of the edge at this index of the network's edges
+    EdgeStrength(usize),
+
+    /// Prior probability of the node with this ID
+    Prior(String),
+}
+
+impl Parameter {
+    /// Range the parameter may take; priors stay clear of 0 and 1 so evidence can still move them
+    pub fn bounds(&self) -> (f64, f64) {
+        match self {
+            Parameter::EdgeStrength(_) => (0.0, 1.0),
+            Parameter::Prior(_) => (0.01, 0.99),
+        }
+    }
+}
+
+/// Change an optimization made to one parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterChange {
+    /// Parameter changed
+    pub parameter: Parameter,
+
+    /// Value before optimization
+    pub from: f64,
+
+    /// Value after optimization
+    pub to: f64,
+}
+
+/// Limits shared by all optimizers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationOptions {
+    /// Maximum number of iterations (generations for the genetic algorithm)
+    pub max_iterations: usize,
+
+    /// Smallest gain in the best score that counts as progress
+    pub tolerance: f64,
+
+    /// Iterations without progress after which the search has converged
+    pub patience: usize,
+
+    /// RNG seed; the global seed or a random one is used if absent
+    pub seed: Option<u64>,
+}
+
+impl Default for OptimizationOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 200,
+            tolerance: 1e-6,
+            patience: 20,
+            seed: None,
+        }
+    }
+}
+
+impl OptimizationOptions {
+    /// Set the iteration limit
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Set when the search is considered converged
+    pub fn with_convergence(mut self, tolerance: f64, patience: usize) -> Self {
+        self.tolerance = tolerance;
+        self.patience = patience.max(1);
+        self
+    }
+
+    /// Seed the random number generator
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// Best point an optimizer found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchOutcome {
+    /// Score of the starting point
+    pub initial_score: f64,
+
+    /// Best score found
+    pub best_score: f64,
+
+    /// Parameter values achieving the best score
+    pub best: Vec<f64>,
+
+    /// Iterations performed
+    pub iterations: usize,
+
+    /// Times the objective was evaluated
+    pub evaluations: usize,
+
+    /// Whether the search stopped for lack of progress rather than at the iteration limit
+    pub converged: bool,
+
+    /// Best score after each iteration
+    pub history: Vec<f64>,
+}
+
+/// Outcome of optimizing a network's objectives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationReport {
+    /// Optimizer used
+    pub optimizer: String,
+
+    /// Seed the search used
+    pub seed: u64,
+
+    /// Weighted objective before optimization
+    pub initial_score: f64,
+
+    /// Weighted objective after optimization
+    pub final_score: f64,
+
+    /// Iterations performed
+    pub iterations: usize,
+
+    /// Times the objective was evaluated
+    pub evaluations: usize,
+
+    /// Whether the search converged before hitting the iteration limit
+    pub converged: bool,
+
+    /// Best score after each iteration
+    pub history: Vec<f64>,
+
+    /// Parameters the optimization changed
+    pub changes: Vec<ParameterChange>,
+}
+
+/// Search strategy maximising a score over a bounded parameter vector
+pub trait Optimizer {
+    /// Name of the strategy, for reports
+    fn name(&self) -> &str;
+
+    /// Search from `start`, keeping each value within its `bounds`
+    fn optimize(
+        &self,
+        start: &[f64],
+        bounds: &[(f64, f64)],
+        objective: &mut dyn FnMut(&[f64]) -> f64,
+        options: &OptimizationOptions,
+        rng: &mut StdRng,
+    ) -> SearchOutcome;
+}
+
+/// Optimizer a network uses during updates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OptimizerKind {
+    HillClimbing(HillClimbing),
+    SimulatedAnnealing(SimulatedAnnealing),
+    Genetic(GeneticAlgorithm),
+}
+
+impl Default for OptimizerKind {
+    fn default() -> Self {
+        OptimizerKind::HillClimbing(HillClimbing::default())
+    }
+}
+
+impl OptimizerKind {
+    /// The configured optimizer
+    pub fn optimizer(&self) -> &dyn Optimizer {
+        match self {
+            OptimizerKind::HillClimbing(o) => o,
+            OptimizerKind::SimulatedAnnealing(o) => o,
+            OptimizerKind::Genetic(o) => o,
+        }
+    }
+}
+
+/// Best point so far and convergence bookkeeping common to all optimizers
+struct Progress {
+    initial_score: f64,
+    best_score: f64,
+    best: Vec<f64>,
+    evaluations: usize,
+    history: Vec<f64>,
+    stalled: usize,
+}
+
+impl Progress {
+    fn new(start: &[f64], objective: &mut dyn FnMut(&[f64]) -> f64) -> Self {
+        let score = objective(start);
+        Self {
+            initial_score: score,
+            best_score: score,
+            best: start.to_vec(),
+            evaluations: 1,
+            history: Vec::new(),
+            stalled: 0,
+        }
+    }
+
+    /// Score a candidate, remembering it if it is the best yet
+    fn evaluate(&mut self, candidate: &[f64], objective: &mut dyn FnMut(&[f64]) -> f64) -> f64 {
+        let score = objective(candidate);
+        self.evaluations += 1;
+        if score > self.best_score {
+            self.best_score = score;
+            self.best = candidate.to_vec();
+        }
+        score
+    }
+
+    /// Close an iteration that started from `previous_best`; true once converged
+    fn end_iteration(&mut self, previous_best: f64, options: &OptimizationOptions) -> bool {
+        self.history.push(self.best_score);
+        if self.best_score - previous_best > options.tolerance {
+            self.stalled = 0;
+        } else {
+            self.stalled += 1;
+        }
+        self.stalled >= options.patience
+    }
+
+    fn finish(self, converged: bool) -> SearchOutcome {
+        SearchOutcome {
+            initial_score: self.initial_score,
+            best_score: self.best_score,
+            best: self.best,
+            iterations: self.history.len(),
+            evaluations: self.evaluations,
+            converged,
+            history: self.history,
+        }
+    }
+}
+
+/// Copy of `values` with one randomly chosen value moved by up to `scale` of its range
+fn perturb(values: &[f64], bounds: &[(f64, f64)], scale: f64, rng: &mut StdRng) -> Vec<f64> {
+    let mut candidate = values.to_vec();
+    if candidate.is_empty() {
+        return candidate;
+    }
+    let i = rng.gen_range(0..candidate.len());
+    let (low, high) = bounds[i];
+    let step = rng.gen_range(-1.0..=1.0) * scale * (high - low);
+    candidate[i] = (candidate[i] + step).clamp(low, high);
+    candidate
+}
+
+/// Steepest-ascent hill climbing over random single-parameter moves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HillClimbing {
+    /// Largest move, as a fraction of a parameter's range
+    pub step_size: f64,
+
+    /// Moves tried per iteration
+    pub neighbours: usize,
+}
+
+impl Default for HillClimbing {
+    fn default() -> Self {
+        Self {
+            step_size: 0.1,
+            neighbours: 8,
+        }
+    }
+}
+
+impl Optimizer for HillClimbing {
+    fn name(&self) -> &str {
+        "hill_climbing"
+    }
+
+    fn optimize(
+        &self,
+        start: &[f64],
+        bounds: &[(f64, f64)],
+        objective: &mut dyn FnMut(&[f64]) -> f64,
+        options: &OptimizationOptions,
+        rng: &mut StdRng,
+    ) -> SearchOutcome {
+        let mut progress = Progress::new(start, objective);
+        let mut step_size = self.step_size;
+        for _ in 0..options.max_iterations {
+            let previous_best = progress.best_score;
+            let current = progress.best.clone();
+            for _ in 0..self.neighbours.max(1) {
+                progress.evaluate(&perturb(&current, bounds, step_size, rng), objective);
+            }
+            // Narrow the search around a local optimum instead of stepping over it
+            if progress.best_score <= previous_best {
+                step_size = (step_size * 0.5).max(self.step_size * 1e-3);
+            }
+            if progress.end_iteration(previous_best, options) {
+                return progress.finish(true);
+            }
+        }
+        progress.finish(false)
+    }
+}
+
+/// Simulated annealing with geometric cooling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedAnnealing {
+    /// Starting temperature; worse moves are accepted with probability exp(-loss / temperature)
+    pub initial_temperature: f64,
+
+    /// Factor the temperature is multiplied by each iteration (0.0 - 1.0)
+    pub cooling_rate: f64,
+
+    /// Largest move, as a fraction of a parameter's range
+    pub step_size: f64,
+}
+
+impl Default for SimulatedAnnealing {
+    fn default() -> Self {
+        Self {
+            initial_temperature: 0.1,
+            cooling_rate: 0.95,
+            step_size: 0.2,
+        }
+    }
+}
+
+impl Optimizer for SimulatedAnnealing {
+    fn name(&self) -> &str {
+        "simulated_annealing"
+    }
+
+    fn optimize(
+        &self,
+        start: &[f64],
+        bounds: &[(f64, f64)],
+        objective: &mut dyn FnMut(&[f64]) -> f64,
+        options: &OptimizationOptions,
+        rng: &mut StdRng,
+    ) -> SearchOutcome {
+        let mut progress = Progress::new(start, objective);
+        let mut current = start.to_vec();
+        let mut current_score = progress.initial_score;
+        let mut temperature = self.initial_temperature;
+        for _ in 0..options.max_iterations {
+            let previous_best = progress.best_score;
+            let candidate = perturb(&current, bounds, self.step_size, rng);
+            let score = progress.evaluate(&candidate, objective);
+            let accept = score >= current_score
+                || (temperature > 0.0 && rng.gen::<f64>() < ((score - current_score) / temperature).exp());
+            if accept {
+                current = candidate;
+                current_score = score;
+            }
+            temperature *= self.cooling_rate.clamp(0.0, 1.0);
+            if progress.end_iteration(previous_best, options) {
+                return progress.finish(true);
+            }
+        }
+        progress.finish(false)
+    }
+}
+
+/// Genetic algorithm with elitism, tournament selection, uniform crossover
+/// and per-gene mutation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneticAlgorithm {
+    /// Individuals per generation
+    pub population_size: usize,
+
+    /// Best individuals copied unchanged into the next generation
+    pub elite: usize,
+
+    /// Individuals competing in each selection tournament
+    pub tournament_size: usize,
+
+    /// Probability that two parents are crossed rather than copied
+    pub crossover_rate: f64,
+
+    /// Probability that each gene of a child is mutated
+    pub mutation_rate: f64,
+
+    /// Largest mutation, as a fraction of a parameter's range
+    pub mutation_scale: f64,
+}
+
+impl Default for GeneticAlgorithm {
+    fn default() -> Self {
+        Self {
+            population_size: 20,
+            elite: 2,
+            tournament_size: 3,
+            crossover_rate: 0.8,
+            mutation_rate: 0.1,
+            mutation_scale: 0.2,
+        }
+    }
+}
+
+impl GeneticAlgorithm {
+    /// Index of the fittest of a random handful of individuals
+    fn select(&self, scores: &[f64], rng: &mut StdRng) -> usize {
+        (0..self.tournament_size.max(1))
+            .map(|_| rng.gen_range(0..scores.len()))
+            .max_by(|a, b| scores[*a].total_cmp(&scores[*b]))
+            .unwrap_or(0)
+    }
+
+    fn mutate(&self, genes: &mut [f64], bounds: &[(f64, f64)], rng: &mut StdRng) {
+        for (gene, (low, high)) in genes.iter_mut().zip(bounds) {
+            if rng.gen::<f64>() < self.mutation_rate {
+                *gene = (*gene + rng.gen_range(-1.0..=1.0) * self.mutation_scale * (high - low)).clamp(*low, *high);
+            }
+        }
+    }
+}
+
+impl Optimizer for GeneticAlgorithm {
+    fn name(&self) -> &str {
+        "genetic"
+    }
+
+    fn optimize(
+        &self,
+        start: &[f64],
+        bounds: &[(f64, f64)],
+        objective: &mut dyn FnMut(&[f64]) -> f64,
+        options: &OptimizationOptions,
+        rng: &mut StdRng,
+    ) -> SearchOutcome {
+        let mut progress = Progress::new(start, objective);
+        let size = self.population_size.max(2);
+
+        // Seed the population with the starting point and mutants of it
+        let mut population = vec![start.to_vec()];
+        let mut scores = vec![progress.initial_score];
+        while population.len() < size {
+            let mut individual = start.to_vec();
+            for (gene, (low, high)) in individual.iter_mut().zip(bounds) {
+                *gene = (*gene + rng.gen_range(-1.0..=1.0) * self.mutation_scale * (high - low)).clamp(*low, *high);
+            }
+            scores.push(progress.evaluate(&individual, objective));
+            population.push(individual);
+        }
+
+        for _ in 0..options.max_iterations {
+            let previous_best = progress.best_score;
+            let mut ranked: Vec<usize> = (0..size).collect();
+            ranked.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+
+            let mut next: Vec<Vec<f64>> = ranked.iter().take(self.elite.min(size)).map(|i| population[*i].clone()).collect();
+            let mut next_scores: Vec<f64> = ranked.iter().take(next.len()).map(|i| scores[*i]).collect();
+            while next.len() < size {
+                let mother = &population[self.select(&scores, rng)];
+                let father = &population[self.select(&scores, rng)];
+                let mut child: Vec<f64> = if rng.gen::<f64>() < self.crossover_rate {
+                    mother.iter().zip(father).map(|(m, f)| if rng.gen::<bool>() { *m } else { *f }).collect()
+                } else {
+                    mother.clone()
+                };
+                self.mutate(&mut child, bounds, rng);
+                next_scores.push(progress.evaluate(&child, objective));
+                next.push(child);
+            }
+            population = next;
+            scores = next_scores;
+
+            if progress.end_iteration(previous_best, options) {
+                return progress.finish(true);
+            }
+        }
+        progress.finish(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng;
+
+    #[test]
+    fn test_optimizers_find_maximum() {
+        // Concave bowl peaking at (0.3, 0.7)
+        let bounds = [(0.0, 1.0), (0.0, 1.0)];
+        let options = OptimizationOptions::default().with_max_iterations(500);
+        let optimizers: Vec<Box<dyn Optimizer>> = vec![
+            Box::new(HillClimbing::default()),
+            Box::new(SimulatedAnnealing::default()),
+            Box::new(GeneticAlgorithm::default()),
+        ];
+
+        for optimizer in optimizers {
+            let mut objective = |x: &[f64]| -((x[0] - 0.3).powi(2) + (x[1] - 0.7).powi(2));
+            let mut rng = rng::stream_rng(11, "optimization");
+            let outcome = optimizer.optimize(&[0.9, 0.1], &bounds, &mut objective, &options, &mut rng);
+            assert!(outcome.best_score > outcome.initial_score, "{} made no progress", optimizer.name());
+            assert!(outcome.best_score > -1e-3, "{} stopped at {}", optimizer.name(), outcome.best_score);
+            assert!(outcome.iterations <= 500);
+            assert_eq!(outcome.history.len(), outcome.iterations);
+            assert!(outcome.best.iter().zip(&bounds).all(|(x, (low, high))| x >= low && x <= high));
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Evidence Network Structure Learning
+//!
+//! Infers which kinds of evidence tend to agree or disagree from past
+//! integrations, instead of relying on hand-added edges. Evidence is grouped
+//! into channels (evidence type plus source); for every pair of channels seen
+//! together often enough, their confidences are dichotomised and tested for
+//! association with a chi-square test on the 2x2 table. Significant positive
+//! associations become Supports edges (same evidence type) or Corroborates
+//! edges (independent types), negative ones Contradicts edges, with the phi
+//! coefficient as strength. The learned structure is saved as JSON and can be
+//! applied to any network whose nodes fall into the learned channels.
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use super::{EvidenceEdge, EvidenceRelationship, FuzzyBayesianNetwork};
+use crate::processing::chromatography::erfc;
+
+/// Channel an evidence item falls into
+pub fn channel(evidence_type: &str, source: &str) -> String {
+    format!("{}:{}", evidence_type, source)
+}
+
+/// Evidence seen together in one past integration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrationRecord {
+    /// Highest confidence per channel
+    pub observations: BTreeMap<String, f64>,
+}
+
+impl IntegrationRecord {
+    /// Record (evidence type, source, confidence) observations
+    pub fn from_observations<T: AsRef<str>, S: AsRef<str>>(observations: impl IntoIterator<Item = (T, S, f64)>) -> Self {
+        let mut record = Self::default();
+        for (evidence_type, source, confidence) in observations {
+            let key = channel(evidence_type.as_ref(), source.as_ref());
+            let entry = record.observations.entry(key).or_insert(confidence);
+            *entry = entry.max(confidence);
+        }
+        record
+    }
+}
+
+/// Edge between two channels inferred from history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedEdge {
+    /// First channel, in alphabetical order
+    pub from: String,
+
+    /// Second channel
+    pub to: String,
+
+    /// Inferred relationship
+    pub relationship: EvidenceRelationship,
+
+    /// Magnitude of the phi coefficient (0.0 - 1.0)
+    pub strength: f64,
+
+    /// Integrations in which both channels appeared
+    pub co_occurrences: usize,
+
+    /// Share of those integrations in which both were confident or both were not
+    pub agreement: f64,
+
+    /// Chi-square p-value of the association
+    pub p_value: f64,
+}
+
+/// Settings for structure learning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructureLearner {
+    /// Confidence at which an observation counts as positive
+    pub confidence_threshold: f64,
+
+    /// Fewest shared integrations for a pair to be tested
+    pub min_co_occurrences: usize,
+
+    /// Largest p-value accepted as significant
+    pub significance: f64,
+
+    /// Smallest phi magnitude worth an edge
+    pub min_strength: f64,
+}
+
+impl Default for StructureLearner {
+    fn default() -> Self {
+        Self {
+            confidence_threshold: 0.5,
+            min_co_occurrences: 10,
+            significance: 0.01,
+            min_strength: 0.2,
+        }
+    }
+}
+
+impl StructureLearner {
+    /// Set the confidence at which observations count as positive
+    pub fn with_confidence_threshold(mut self, threshold: f64) -> Self {
+        self.confidence_threshold = threshold;
+        self
+    }
+
+    /// Set how much evidence an edge needs
+    pub fn with_significance(mut self, significance: f64, min_co_occurrences: usize) -> Self {
+        self.significance = significance;
+        self.min_co_occurrences = min_co_occurrences;
+        self
+    }
+
+    /// Learn edges between channels from past integrations
+    pub fn learn(&self, history: &[IntegrationRecord]) -> LearnedStructure {
+        let mut channels: Vec<&String> = history.iter().flat_map(|r| r.observations.keys()).collect();
+        channels.sort();
+        channels.dedup();
+
+        let mut edges = Vec::new();
+        let mut tested = 0;
+        for (i, a) in channels.iter().enumerate() {
+            for b in &channels[i + 1..] {
+                // 2x2 table of (a positive, b positive) over integrations containing both
+                let mut table = [[0usize; 2]; 2];
+                for record in history {
+                    if let (Some(x), Some(y)) = (record.observations.get(*a), record.observations.get(*b)) {
+                        let row = usize::from(*x >= self.confidence_threshold);
+                        let col = usize::from(*y >= self.confidence_threshold);
+                        table[row][col] += 1;
+                    }
+                }
+                let n = table.iter().flatten().sum::<usize>();
+                if n < self.min_co_occurrences {
+                    continue;
+                }
+                tested += 1;
+                let (phi, p_value) = match phi_test(&table) {
+                    Some(result) => result,
+                    None => continue,
+                };
+                if p_value > self.significance || phi.abs() < self.min_strength {
+                    continue;
+                }
+
+                let same_type = a.split(':').next() == b.split(':').next();
+                let relationship = if phi < 0.0 {
+                    EvidenceRelationship::Contradicts
+                } else if same_type {
+                    EvidenceRelationship::Supports
+                } else {
+                    EvidenceRelationship::Corroborates
+                };
+                edges.push(LearnedEdge {
+                    from: a.to_string(),
+                    to: b.to_string(),
+                    relationship,
+                    strength: phi.abs().min(1.0),
+                    co_occurrences: n,
+                    agreement: (table[0][0] + table[1][1]) as f64 / n as f64,
+                    p_value,
+                });
+            }
+        }
+
+        info!("Learned {} evidence edges from {} integrations ({} channel pairs tested)",
+              edges.len(), history.len(), tested);
+        LearnedStructure {
+            learned_at: chrono::Utc::now(),
+            integrations: history.len(),
+            edges,
+        }
+    }
+}
+
+/// Phi coefficient and chi-square p-value of a 2x2 table; `None` if a margin is empty
+fn phi_test(table: &[[usize; 2]; 2]) -> Option<(f64, f64)> {
+    let [[n00, n01], [n10, n11]] = table.map(|row| row.map(|c| c as f64));
+    let n = n00 + n01 + n10 + n11;
+    let margins = (n00 + n01) * (n10 + n11) * (n00 + n10) * (n01 + n11);
+    if margins == 0.0 {
+        return None;
+    }
+    let phi = (n11 * n00 - n10 * n01) / margins.sqrt();
+    // Chi-square with one degree of freedom: P(X > n * phi^2)
+    let chi_square = n * phi * phi;
+    Some((phi, erfc((chi_square / 2.0).sqrt())))
+}
+
+/// Edges learned from past integrations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedStructure {
+    /// When the structure was learned
+    pub learned_at: chrono::DateTime<chrono::Utc>,
+
+    /// Number of integrations learned from
+    pub integrations: usize,
+
+    /// Learned edges
+    pub edges: Vec<LearnedEdge>,
+}
+
+impl LearnedStructure {
+    /// Edge learned between two channels, in either order
+    pub fn edge_between(&self, a: &str, b: &str) -> Option<&LearnedEdge> {
+        self.edges.iter().find(|e| (e.from == a && e.to == b) || (e.from == b && e.to == a))
+    }
+
+    /// Add an edge between every pair of network nodes whose channels have a
+    /// learned edge, unless the nodes are already connected
+    ///
+    /// Returns the number of edges added.
+    pub fn apply(&self, network: &mut FuzzyBayesianNetwork) -> usize {
+        let mut nodes: Vec<(&String, String)> = network.nodes.iter()
+            .filter_map(|(id, node)| {
+                let evidence = node.fuzzy_evidence.as_ref()?;
+                Some((id, channel(&evidence.evidence_type, &evidence.source)))
+            })
+            .collect();
+        nodes.sort();
+        let mut connected: HashSet<(&str, &str)> = network.edges.iter()
+            .flat_map(|e| [(e.from_node.as_str(), e.to_node.as_str()), (e.to_node.as_str(), e.from_node.as_str())])
+            .collect();
+
+        let mut added = Vec::new();
+        for (i, (a, channel_a)) in nodes.iter().enumerate() {
+            for (b, channel_b) in &nodes[i + 1..] {
+                let learned = match self.edge_between(channel_a, channel_b) {
+                    Some(learned) => learned,
+                    None => continue,
+                };
+                if !connected.insert((a.as_str(), b.as_str())) {
+                    continue;
+                }
+                added.push(EvidenceEdge {
+                    from_node: a.to_string(),
+                    to_node: b.to_string(),
+                    relationship_type: learned.relationship.clone(),
+                    strength: learned.strength,
+                    fuzzy_strength: HashMap::new(),
+                });
+            }
+        }
+
+        debug!("Applied learned structure: {} edges added", added.len());
+        let count = added.len();
+        network.edges.extend(added);
+        count
+    }
+
+    /// Load a structure saved with `save`
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read learned structure: {}", path.display()))?;
+        serde_json::from_str(&content).context("Failed to parse learned structure")
+    }
+
+    /// Write the structure to a JSON file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write learned structure: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy_evidence::FuzzyEvidence;
+
+    fn history() -> Vec<IntegrationRecord> {
+        (0..36)
+            .map(|i| {
+                let level = |positive: bool| if positive { 0.9 } else { 0.2 };
+                let spectral = i % 2 == 0;
+                IntegrationRecord::from_observations([
+                    ("mass_spec", "qtof", level(spectral)),
+                    ("pathway", "kegg", level(spectral != (i % 9 == 0))),
+                    ("literature", "pubmed", level(!spectral)),
+                    ("genomics", "ensembl", level(i % 3 == 0)),
+                ])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_learns_significant_edges() {
+        let structure = StructureLearner::default().learn(&history());
+        assert_eq!(structure.integrations, 36);
+
+        let corroborates = structure.edge_between("mass_spec:qtof", "pathway:kegg").unwrap();
+        assert!(matches!(corroborates.relationship, EvidenceRelationship::Corroborates));
+        assert!(corroborates.strength > 0.5 && corroborates.p_value < 0.01);
+        let contradicts = structure.edge_between("literature:pubmed", "mass_spec:qtof").unwrap();
+        assert!(matches!(contradicts.relationship, EvidenceRelationship::Contradicts));
+        assert_eq!(contradicts.agreement, 0.0);
+        assert!(structure.edge_between("genomics:ensembl", "mass_spec:qtof").is_none());
+
+        let strict = StructureLearner::default().with_significance(0.01, 50).learn(&history());
+        assert!(strict.edges.is_empty());
+    }
+
+    #[test]
+    fn test_apply_persisted_structure() {
+        let path = std::env::temp_dir().join(format!("hegel-structure-{}.json", std::process::id()));
+        StructureLearner::default().learn(&history()).save(&path).unwrap();
+        let structure = LearnedStructure::from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let mut network = FuzzyBayesianNetwork::new();
+        for (id, evidence_type, source) in [("e1", "mass_spec", "qtof"), ("e2", "pathway", "kegg"), ("e3", "genomics", "ensembl")] {
+            network.add_evidence(FuzzyEvidence::from_raw_evidence(
+                id.to_string(), source.to_string(), evidence_type.to_string(), 0.8, chrono::Utc::now())).unwrap();
+        }
+        assert_eq!(structure.apply(&mut network), 1);
+        assert_eq!(network.edges[0].from_node, "e1");
+        assert_eq!(structure.apply(&mut network), 0);
+    }
+}
@@ -0,0 +1,309 @@
+//! Custom Linguistic Variables
+//!
+//! Builders for fuzzy linguistic variables beyond the built-in confidence and
+//! agreement scales, with checks that a variable's terms are well formed and
+//! leave no large stretch of its universe without any membership. Variables
+//! can be saved to and loaded from a JSON config that also says which
+//! evidence types each variable fuzzifies.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{FuzzyLinguisticVariable, FuzzyMembershipFunction};
+
+/// Largest share of the universe a variable may leave uncovered by default
+pub const DEFAULT_MAX_GAP: f64 = 0.05;
+
+/// Points at which coverage of the universe is sampled
+const COVERAGE_SAMPLES: usize = 1000;
+
+impl FuzzyMembershipFunction {
+    /// Crisp value the term stands for: the peak, the middle of the plateau,
+    /// the centre of a Gaussian or the midpoint of a sigmoid
+    pub fn representative_value(&self) -> f64 {
+        match self {
+            FuzzyMembershipFunction::Triangular { peak, .. } => *peak,
+            FuzzyMembershipFunction::Trapezoidal { low_peak, high_peak, .. } => (low_peak + high_peak) / 2.0,
+            FuzzyMembershipFunction::Gaussian { center, .. } => *center,
+            FuzzyMembershipFunction::Sigmoid { center, .. } => *center,
+        }
+    }
+
+    /// Check the function's parameters describe a valid shape
+    pub fn validate(&self) -> Result<()> {
+        let ordered = |points: &[f64]| points.windows(2).all(|w| w[0] <= w[1]) && points[0] < points[points.len() - 1];
+        match self {
+            FuzzyMembershipFunction::Triangular { low, peak, high } if !ordered(&[*low, *peak, *high]) => {
+                Err(anyhow!("Triangular term needs low <= peak <= high and low < high, got ({}, {}, {})", low, peak, high))
+            }
+            FuzzyMembershipFunction::Trapezoidal { low, low_peak, high_peak, high } if !ordered(&[*low, *low_peak, *high_peak, *high]) => {
+                Err(anyhow!("Trapezoidal term needs ascending points with low < high, got ({}, {}, {}, {})",
+                            low, low_peak, high_peak, high))
+            }
+            FuzzyMembershipFunction::Gaussian { sigma, .. } if *sigma <= 0.0 => {
+                Err(anyhow!("Gaussian term needs a positive sigma, got {}", sigma))
+            }
+            FuzzyMembershipFunction::Sigmoid { slope, .. } if *slope == 0.0 => {
+                Err(anyhow!("Sigmoid term needs a non-zero slope"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FuzzyLinguisticVariable {
+    /// Start defining a variable over the unit interval
+    pub fn builder(name: &str) -> LinguisticVariableBuilder {
+        LinguisticVariableBuilder {
+            name: name.to_string(),
+            universe: (0.0, 1.0),
+            terms: Vec::new(),
+            max_gap: DEFAULT_MAX_GAP,
+        }
+    }
+
+    /// Stretches of the universe where no term has membership of at least
+    /// `min_membership`, as (start, end) pairs
+    pub fn coverage_gaps(&self, min_membership: f64) -> Vec<(f64, f64)> {
+        let (min, max) = self.universe;
+        let step = (max - min) / COVERAGE_SAMPLES as f64;
+        let mut gaps = Vec::new();
+        let mut start: Option<f64> = None;
+        for i in 0..=COVERAGE_SAMPLES {
+            let x = min + step * i as f64;
+            let membership = self.terms.values().map(|f| f.membership(x)).fold(0.0, f64::max);
+            let covered = membership > 0.0 && membership >= min_membership;
+            match (covered, start) {
+                (false, None) => start = Some(x),
+                (true, Some(s)) => {
+                    gaps.push((s, x));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            gaps.push((s, max));
+        }
+        gaps
+    }
+
+    /// Check the universe, every term, and that no gap in coverage is wider
+    /// than `max_gap` of the universe
+    pub fn validate(&self, max_gap: f64) -> Result<()> {
+        let (min, max) = self.universe;
+        if max <= min {
+            return Err(anyhow!("Variable '{}' has an empty universe ({}, {})", self.name, min, max));
+        }
+        if self.terms.is_empty() {
+            return Err(anyhow!("Variable '{}' has no terms", self.name));
+        }
+        for (term, function) in &self.terms {
+            function.validate().with_context(|| format!("Invalid term '{}' of variable '{}'", term, self.name))?;
+        }
+        if let Some(term) = self.term_values.keys().find(|t| !self.terms.contains_key(*t)) {
+            return Err(anyhow!("Variable '{}' gives a value to unknown term '{}'", self.name, term));
+        }
+        let widest = self.coverage_gaps(0.0).into_iter()
+            .max_by(|a, b| (a.1 - a.0).total_cmp(&(b.1 - b.0)));
+        if let Some((start, end)) = widest.filter(|(s, e)| (e - s) / (max - min) > max_gap) {
+            return Err(anyhow!("Variable '{}' leaves {:.3} to {:.3} without any term", self.name, start, end));
+        }
+        Ok(())
+    }
+}
+
+/// Builder for a custom linguistic variable
+pub struct LinguisticVariableBuilder {
+    /// Name of the variable
+    name: String,
+
+    /// Range of values the variable covers
+    universe: (f64, f64),
+
+    /// Terms with an optional explicit crisp value, in the order added
+    terms: Vec<(String, FuzzyMembershipFunction, Option<f64>)>,
+
+    /// Largest uncovered share of the universe accepted by `build`
+    max_gap: f64,
+}
+
+impl LinguisticVariableBuilder {
+    /// Set the range of values the variable covers
+    pub fn universe(mut self, min: f64, max: f64) -> Self {
+        self.universe = (min, max);
+        self
+    }
+
+    /// Add a term, replacing any term with the same name
+    pub fn term(mut self, name: &str, function: FuzzyMembershipFunction) -> Self {
+        self.terms.retain(|(t, _, _)| t != name);
+        self.terms.push((name.to_string(), function, None));
+        self
+    }
+
+    /// Add a term that defuzzifies to `value` instead of its representative value
+    pub fn term_with_value(mut self, name: &str, function: FuzzyMembershipFunction, value: f64) -> Self {
+        self = self.term(name, function);
+        if let Some(last) = self.terms.last_mut() {
+            last.2 = Some(value);
+        }
+        self
+    }
+
+    /// Add a triangular term
+    pub fn triangular(self, name: &str, low: f64, peak: f64, high: f64) -> Self {
+        self.term(name, FuzzyMembershipFunction::Triangular { low, peak, high })
+    }
+
+    /// Add a trapezoidal term
+    pub fn trapezoidal(self, name: &str, low: f64, low_peak: f64, high_peak: f64, high: f64) -> Self {
+        self.term(name, FuzzyMembershipFunction::Trapezoidal { low, low_peak, high_peak, high })
+    }
+
+    /// Add a Gaussian term
+    pub fn gaussian(self, name: &str, center: f64, sigma: f64) -> Self {
+        self.term(name, FuzzyMembershipFunction::Gaussian { center, sigma })
+    }
+
+    /// Set the largest share of the universe that may be left uncovered
+    pub fn max_gap(mut self, max_gap: f64) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Build and validate the variable
+    pub fn build(self) -> Result<FuzzyLinguisticVariable> {
+        let mut terms = HashMap::new();
+        let mut term_values = HashMap::new();
+        for (name, function, value) in self.terms {
+            term_values.insert(name.clone(), value.unwrap_or_else(|| function.representative_value()));
+            terms.insert(name, function);
+        }
+        let variable = FuzzyLinguisticVariable {
+            name: self.name,
+            universe: self.universe,
+            terms,
+            term_values,
+        };
+        variable.validate(self.max_gap)?;
+        Ok(variable)
+    }
+}
+
+/// Linguistic variables and the evidence types they apply to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinguisticVariableConfig {
+    /// Variables by name
+    pub variables: HashMap<String, FuzzyLinguisticVariable>,
+
+    /// Name of the variable fuzzifying each evidence type's confidence
+    pub assignments: HashMap<String, String>,
+}
+
+impl LinguisticVariableConfig {
+    /// Add a variable, replacing any variable with the same name
+    pub fn with_variable(mut self, variable: FuzzyLinguisticVariable) -> Self {
+        self.variables.insert(variable.name.clone(), variable);
+        self
+    }
+
+    /// Fuzzify an evidence type's confidence with the named variable
+    pub fn assign(mut self, evidence_type: &str, variable: &str) -> Self {
+        self.assignments.insert(evidence_type.to_string(), variable.to_string());
+        self
+    }
+
+    /// Variable assigned to an evidence type, if any
+    pub fn variable_for(&self, evidence_type: &str) -> Option<&FuzzyLinguisticVariable> {
+        self.assignments.get(evidence_type).and_then(|name| self.variables.get(name))
+    }
+
+    /// Load variables from a JSON file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read linguistic variables: {}", path.display()))?;
+        let config: Self = serde_json::from_str(&content).context("Failed to parse linguistic variables")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Write the variables to a JSON file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write linguistic variables: {}", path.display()))
+    }
+
+    /// Check every variable is valid and every assignment names one of them
+    pub fn validate(&self) -> Result<()> {
+        for variable in self.variables.values() {
+            variable.validate(DEFAULT_MAX_GAP)?;
+        }
+        if let Some((evidence_type, name)) = self.assignments.iter().find(|(_, name)| !self.variables.contains_key(*name)) {
+            return Err(anyhow!("Evidence type '{}' is assigned unknown variable '{}'", evidence_type, name));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy_evidence::FuzzyEvidence;
+
+    #[test]
+    fn test_builder_validates_coverage() {
+        assert!(FuzzyLinguisticVariable::evidence_confidence().validate(DEFAULT_MAX_GAP).is_ok());
+        assert!(FuzzyLinguisticVariable::evidence_agreement().validate(DEFAULT_MAX_GAP).is_ok());
+
+        let gappy = FuzzyLinguisticVariable::builder("gappy")
+            .triangular("low", 0.0, 0.0, 0.3)
+            .triangular("high", 0.6, 1.0, 1.0)
+            .build();
+        assert!(gappy.is_err());
+        assert!(FuzzyLinguisticVariable::builder("bad").triangular("x", 0.5, 0.2, 0.9).max_gap(1.0).build().is_err());
+
+        let mass_error = FuzzyLinguisticVariable::builder("mass_error")
+            .universe(0.0, 20.0)
+            .trapezoidal("accurate", 0.0, 0.0, 2.0, 5.0)
+            .triangular("acceptable", 2.0, 5.0, 10.0)
+            .trapezoidal("poor", 5.0, 10.0, 20.0, 20.0)
+            .build()
+            .unwrap();
+        assert_eq!(mass_error.term_values["accurate"], 1.0);
+        assert!(mass_error.coverage_gaps(0.0).iter().all(|(s, e)| e - s < 0.1));
+    }
+
+    #[test]
+    fn test_config_round_trip_and_assignment() {
+        let strict = FuzzyLinguisticVariable::builder("strict_confidence")
+            .trapezoidal("doubtful", 0.0, 0.0, 0.7, 0.9)
+            .term_with_value("certain", FuzzyMembershipFunction::Trapezoidal { low: 0.7, low_peak: 0.9, high_peak: 1.0, high: 1.0 }, 0.9)
+            .build()
+            .unwrap();
+        let config = LinguisticVariableConfig::default()
+            .with_variable(strict)
+            .assign("literature", "strict_confidence");
+
+        let path = std::env::temp_dir().join(format!("hegel-variables-{}.json", std::process::id()));
+        config.save(&path).unwrap();
+        let loaded = LinguisticVariableConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(loaded.variable_for("mass_spec").is_none());
+        let variable = loaded.variable_for("literature").unwrap();
+
+        let now = chrono::Utc::now();
+        let strict = FuzzyEvidence::from_raw_evidence_with(
+            "e1".to_string(), "pubmed".to_string(), "literature".to_string(), 0.8, now, variable);
+        let default = FuzzyEvidence::from_raw_evidence(
+            "e2".to_string(), "pubmed".to_string(), "literature".to_string(), 0.8, now);
+        assert!(strict.confidence_memberships.contains_key("doubtful"));
+        assert!(strict.defuzzified_confidence() < default.defuzzified_confidence());
+
+        assert!(LinguisticVariableConfig::default().assign("genomics", "missing").validate().is_err());
+    }
+}
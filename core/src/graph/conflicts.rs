@@ -0,0 +1,236 @@
+//! Conflict Graph Module
+//!
+//! This module builds a graph of disagreements between evidence items and
+//! exports it as Graphviz DOT or Cytoscape.js JSON for visualization.
+
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use crate::processing::evidence::IntegratedEvidence;
+
+/// Output format for conflict graph export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictGraphFormat {
+    /// Graphviz DOT
+    Dot,
+
+    /// Cytoscape.js elements JSON
+    Cytoscape,
+}
+
+impl std::str::FromStr for ConflictGraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "dot" | "graphviz" => Ok(ConflictGraphFormat::Dot),
+            "cytoscape" | "json" => Ok(ConflictGraphFormat::Cytoscape),
+            _ => Err(anyhow!("Unsupported conflict graph format: {}", s)),
+        }
+    }
+}
+
+/// Evidence node in a conflict graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictNode {
+    /// Evidence ID
+    pub id: String,
+
+    /// Source of the evidence
+    pub source: String,
+
+    /// Type of the evidence
+    pub evidence_type: String,
+
+    /// Confidence of the evidence
+    pub confidence: f64,
+}
+
+/// Contradiction between two evidence items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictEdge {
+    /// First evidence ID
+    pub source: String,
+
+    /// Second evidence ID
+    pub target: String,
+
+    /// Severity of the conflict (0.0 - 1.0)
+    pub severity: f64,
+
+    /// Description of the conflict
+    pub description: String,
+}
+
+/// Graph of conflicting evidence for a molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictGraph {
+    /// Molecule the evidence relates to
+    pub molecule_id: String,
+
+    /// Evidence items involved in at least one conflict
+    pub nodes: Vec<ConflictNode>,
+
+    /// Contradiction edges
+    pub edges: Vec<ConflictEdge>,
+}
+
+impl ConflictGraph {
+    /// Build the conflict graph from integrated evidence
+    ///
+    /// Each reported conflict connects every pair of evidence items it names;
+    /// when several conflicts touch the same pair, the most severe one is kept.
+    pub fn from_integrated(integrated: &IntegratedEvidence) -> Self {
+        let mut pairs: BTreeMap<(String, String), ConflictEdge> = BTreeMap::new();
+
+        for conflict in &integrated.conflicts {
+            for (i, a) in conflict.evidence_ids.iter().enumerate() {
+                for b in conflict.evidence_ids.iter().skip(i + 1) {
+                    if a == b {
+                        continue;
+                    }
+                    let key = if a < b { (a.clone(), b.clone()) } else { (b.clone(), a.clone()) };
+                    let replace = pairs.get(&key).is_none_or(|e| conflict.severity > e.severity);
+                    if replace {
+                        pairs.insert(key.clone(), ConflictEdge {
+                            source: key.0,
+                            target: key.1,
+                            severity: conflict.severity,
+                            description: conflict.description.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let involved: HashSet<&String> = pairs.keys().flat_map(|(a, b)| [a, b]).collect();
+        let nodes = integrated.evidence_items.iter()
+            .filter(|e| involved.contains(&e.id))
+            .map(|e| ConflictNode {
+                id: e.id.clone(),
+                source: e.source.clone(),
+                evidence_type: e.evidence_type.to_string(),
+                confidence: e.confidence,
+            })
+            .collect();
+
+        Self {
+            molecule_id: integrated.molecule_id.clone(),
+            nodes,
+            edges: pairs.into_values().collect(),
+        }
+    }
+
+    /// Render as Graphviz DOT
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "graph \"conflicts_{}\" {{", escape(&self.molecule_id));
+        let _ = writeln!(dot, "  node [shape=box, style=rounded];");
+
+        for node in &self.nodes {
+            let _ = writeln!(
+                dot,
+                "  \"{}\" [label=\"{}\\n{} ({:.2})\"];",
+                escape(&node.id), escape(&node.source), escape(&node.evidence_type), node.confidence
+            );
+        }
+
+        for edge in &self.edges {
+            // Thicker, redder edges for more severe conflicts
+            let _ = writeln!(
+                dot,
+                "  \"{}\" -- \"{}\" [label=\"{:.2}\", penwidth={:.1}, color=\"red\", tooltip=\"{}\"];",
+                escape(&edge.source), escape(&edge.target), edge.severity,
+                1.0 + 4.0 * edge.severity, escape(&edge.description)
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render as Cytoscape.js elements JSON
+    pub fn to_cytoscape(&self) -> serde_json::Value {
+        let nodes = self.nodes.iter().map(|node| serde_json::json!({
+            "data": {
+                "id": node.id,
+                "source_name": node.source,
+                "evidence_type": node.evidence_type,
+                "confidence": node.confidence,
+            }
+        }));
+        let edges = self.edges.iter().map(|edge| serde_json::json!({
+            "data": {
+                "id": format!("{}--{}", edge.source, edge.target),
+                "source": edge.source,
+                "target": edge.target,
+                "severity": edge.severity,
+                "description": edge.description,
+            }
+        }));
+
+        serde_json::json!({
+            "elements": {
+                "nodes": nodes.collect::<Vec<_>>(),
+                "edges": edges.collect::<Vec<_>>(),
+            }
+        })
+    }
+
+    /// Render in the requested format
+    pub fn export(&self, format: ConflictGraphFormat) -> Result<String> {
+        match format {
+            ConflictGraphFormat::Dot => Ok(self.to_dot()),
+            ConflictGraphFormat::Cytoscape => Ok(serde_json::to_string_pretty(&self.to_cytoscape())?),
+        }
+    }
+}
+
+/// Escape a string for use inside a DOT quoted identifier
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::{Evidence, EvidenceConflict, EvidenceType};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_conflict_graph_export() {
+        let evidence = |id: &str| Evidence {
+            id: id.to_string(),
+            molecule_id: "mol-1".to_string(),
+            evidence_type: EvidenceType::MassSpec,
+            source: "lab".to_string(),
+            confidence: 0.5,
+            data: serde_json::Value::Null,
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        };
+        let integrated = IntegratedEvidence {
+            molecule_id: "mol-1".to_string(),
+            evidence_items: vec![evidence("a"), evidence("b"), evidence("c")],
+            aggregate_confidence: 0.5,
+            conflicts: vec![EvidenceConflict {
+                description: "mass mismatch".to_string(),
+                evidence_ids: vec!["a".to_string(), "b".to_string()],
+                severity: 0.6,
+                resolution_suggestions: Vec::new(),
+            }],
+            integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        };
+
+        let graph = ConflictGraph::from_integrated(&integrated);
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+        assert!(graph.to_dot().contains("\"a\" -- \"b\""));
+        assert_eq!(graph.to_cytoscape()["elements"]["edges"][0]["data"]["severity"], 0.6);
+    }
+}
@@ -0,0 +1,354 @@
+//! Network Diff and Merge
+//!
+//! Compares molecular networks built by different collaborators and merges
+//! them into one. Nodes are matched by molecule ID and edges by the unordered
+//! pair of molecules they connect. When both networks connect the same pair
+//! with different weights, a merge policy decides which weight survives.
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::{EdgeWeight, MoleculeNetwork};
+
+/// Weights closer than this are considered equal
+const WEIGHT_TOLERANCE: f64 = 1e-9;
+
+/// Metadata keys consulted to decide which network is newer
+const TIMESTAMP_KEYS: [&str; 2] = ["updated_at", "built_at"];
+
+/// An edge present in only one of two networks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdgeChange {
+    /// Molecule ID ordered first
+    pub source: String,
+
+    /// Molecule ID ordered second
+    pub target: String,
+
+    /// Similarity carried by the edge
+    pub weight: f64,
+}
+
+/// An edge whose weight differs between two networks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightChange {
+    /// Molecule ID ordered first
+    pub source: String,
+
+    /// Molecule ID ordered second
+    pub target: String,
+
+    /// Weight in the first network
+    pub before: f64,
+
+    /// Weight in the second network
+    pub after: f64,
+}
+
+/// Differences between two molecular networks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkDiff {
+    /// Molecules only in the second network
+    pub added_nodes: Vec<String>,
+
+    /// Molecules only in the first network
+    pub removed_nodes: Vec<String>,
+
+    /// Edges only in the second network
+    pub added_edges: Vec<EdgeChange>,
+
+    /// Edges only in the first network
+    pub removed_edges: Vec<EdgeChange>,
+
+    /// Edges in both networks with different weights
+    pub weight_changes: Vec<WeightChange>,
+}
+
+impl NetworkDiff {
+    /// Whether the networks have the same nodes, edges and weights
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.weight_changes.is_empty()
+    }
+}
+
+/// How a merge resolves an edge whose weight differs between the networks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergePolicy {
+    /// Keep the higher weight
+    MaxWeight,
+    /// Use the mean of both weights
+    Average,
+    /// Keep the weight from the newer network
+    PreferNewer,
+}
+
+impl std::str::FromStr for MergePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "max-weight" | "max" => Ok(MergePolicy::MaxWeight),
+            "average" | "avg" => Ok(MergePolicy::Average),
+            "prefer-newer" | "newer" => Ok(MergePolicy::PreferNewer),
+            _ => Err(anyhow!("Unknown merge policy: {} (expected max-weight, average or prefer-newer)", s)),
+        }
+    }
+}
+
+/// Outcome of merging one network into another
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeReport {
+    /// Molecules taken from the other network
+    pub nodes_added: usize,
+
+    /// Edges taken from the other network
+    pub edges_added: usize,
+
+    /// Edges whose weights differed, with the weight before and after the merge
+    pub conflicts: Vec<WeightChange>,
+}
+
+/// Unordered pair of molecule IDs identifying an edge
+fn edge_key(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+impl MoleculeNetwork {
+    /// Edge weights by molecule pair, keeping the strongest of parallel edges
+    fn edges_by_pair(&self) -> BTreeMap<(String, String), EdgeWeight> {
+        let mut edges: BTreeMap<(String, String), EdgeWeight> = BTreeMap::new();
+        for edge in self.graph.edge_indices() {
+            let (a, b, weight) = match (self.graph.edge_endpoints(edge), self.graph.edge_weight(edge)) {
+                (Some((a, b)), Some(weight)) => (a, b, weight),
+                _ => continue,
+            };
+            let key = edge_key(&self.graph[a].id, &self.graph[b].id);
+            match edges.get(&key) {
+                Some(existing) if existing.similarity() >= weight.similarity() => {}
+                _ => {
+                    edges.insert(key, weight.clone());
+                }
+            }
+        }
+        edges
+    }
+
+    /// Time the network was last built or updated, from its metadata
+    fn timestamp(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        TIMESTAMP_KEYS.iter()
+            .filter_map(|key| self.metadata.get(*key)?.as_str())
+            .filter_map(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&chrono::Utc))
+            .next()
+    }
+
+    /// Differences from this network to another
+    pub fn diff(&self, other: &MoleculeNetwork) -> NetworkDiff {
+        let mut added_nodes: Vec<String> = other.id_to_node.keys()
+            .filter(|id| !self.id_to_node.contains_key(*id))
+            .cloned()
+            .collect();
+        let mut removed_nodes: Vec<String> = self.id_to_node.keys()
+            .filter(|id| !other.id_to_node.contains_key(*id))
+            .cloned()
+            .collect();
+        added_nodes.sort();
+        removed_nodes.sort();
+        let mut diff = NetworkDiff {
+            added_nodes,
+            removed_nodes,
+            ..NetworkDiff::default()
+        };
+
+        let ours = self.edges_by_pair();
+        let theirs = other.edges_by_pair();
+        for ((source, target), weight) in &ours {
+            match theirs.get(&(source.clone(), target.clone())) {
+                None => diff.removed_edges.push(EdgeChange {
+                    source: source.clone(),
+                    target: target.clone(),
+                    weight: weight.similarity(),
+                }),
+                Some(other_weight) if (other_weight.similarity() - weight.similarity()).abs() > WEIGHT_TOLERANCE => {
+                    diff.weight_changes.push(WeightChange {
+                        source: source.clone(),
+                        target: target.clone(),
+                        before: weight.similarity(),
+                        after: other_weight.similarity(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        for ((source, target), weight) in &theirs {
+            if !ours.contains_key(&(source.clone(), target.clone())) {
+                diff.added_edges.push(EdgeChange {
+                    source: source.clone(),
+                    target: target.clone(),
+                    weight: weight.similarity(),
+                });
+            }
+        }
+
+        debug!("Network diff: +{}/-{} nodes, +{}/-{} edges, {} weight changes",
+               diff.added_nodes.len(), diff.removed_nodes.len(),
+               diff.added_edges.len(), diff.removed_edges.len(), diff.weight_changes.len());
+        diff
+    }
+
+    /// Merge another network into this one
+    ///
+    /// Molecules and edges missing from this network are copied over. For
+    /// `PreferNewer`, the networks' `updated_at` or `built_at` metadata decides
+    /// which is newer; without timestamps on both, the other network is
+    /// treated as the newer one.
+    pub fn merge(&mut self, other: &MoleculeNetwork, policy: MergePolicy) -> MergeReport {
+        let mut report = MergeReport::default();
+        let other_is_newer = match (self.timestamp(), other.timestamp()) {
+            (Some(ours), Some(theirs)) => theirs > ours,
+            _ => true,
+        };
+
+        for node in other.graph.node_weights() {
+            if let Some(&idx) = self.id_to_node.get(&node.id) {
+                if policy == MergePolicy::PreferNewer && other_is_newer {
+                    self.graph[idx] = node.clone();
+                }
+            } else {
+                self.insert_node(node.clone());
+                report.nodes_added += 1;
+            }
+        }
+
+        for ((source, target), theirs) in other.edges_by_pair() {
+            let (a, b) = (self.id_to_node[&source], self.id_to_node[&target]);
+            let edge = match self.graph.find_edge(a, b) {
+                Some(edge) => edge,
+                None => {
+                    self.graph.add_edge(a, b, theirs);
+                    report.edges_added += 1;
+                    continue;
+                }
+            };
+
+            let ours = &self.graph[edge];
+            if (ours.similarity() - theirs.similarity()).abs() <= WEIGHT_TOLERANCE {
+                continue;
+            }
+            let before = ours.similarity();
+            let merged = match policy {
+                MergePolicy::MaxWeight if theirs.similarity() > before => theirs,
+                MergePolicy::MaxWeight => ours.clone(),
+                MergePolicy::PreferNewer if other_is_newer => theirs,
+                MergePolicy::PreferNewer => ours.clone(),
+                MergePolicy::Average => average(ours, &theirs),
+            };
+            report.conflicts.push(WeightChange {
+                source,
+                target,
+                before,
+                after: merged.similarity(),
+            });
+            self.graph[edge] = merged;
+        }
+
+        let sources = self.metadata.entry("merged_from".to_string())
+            .or_insert_with(|| serde_json::json!([]));
+        if let Some(sources) = sources.as_array_mut() {
+            sources.push(serde_json::to_value(&other.metadata).unwrap_or_default());
+        }
+
+        debug!("Merged network: {} nodes and {} edges added, {} conflicts resolved with {:?}",
+               report.nodes_added, report.edges_added, report.conflicts.len(), policy);
+        report
+    }
+}
+
+/// Mean of two edge weights, averaging component scores both edges carry
+fn average(a: &EdgeWeight, b: &EdgeWeight) -> EdgeWeight {
+    let similarity = (a.similarity() + b.similarity()) / 2.0;
+    match (a, b) {
+        (EdgeWeight::Composite { components: ca, .. }, EdgeWeight::Composite { components: cb, .. }) => {
+            let components: HashMap<String, f64> = ca.iter()
+                .map(|(name, score)| (name.clone(), cb.get(name).map_or(*score, |other| (score + other) / 2.0)))
+                .chain(cb.iter().filter(|(name, _)| !ca.contains_key(*name)).map(|(n, s)| (n.clone(), *s)))
+                .collect();
+            EdgeWeight::Composite { similarity, components }
+        }
+        _ => EdgeWeight::Similarity(similarity),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::Molecule;
+
+    fn network(ids: &[&str], edges: &[(&str, &str, f64)]) -> MoleculeNetwork {
+        let mut network = MoleculeNetwork::new();
+        for id in ids {
+            network.add_molecule(&Molecule {
+                id: id.to_string(),
+                smiles: "C".to_string(),
+                inchi: None,
+                inchi_key: None,
+                name: None,
+                formula: None,
+                molecular_weight: None,
+                properties: HashMap::new(),
+                ..Default::default()
+            });
+        }
+        for (a, b, weight) in edges {
+            network.add_similarity(a, b, *weight);
+        }
+        network
+    }
+
+    #[test]
+    fn test_diff() {
+        let ours = network(&["a", "b", "c"], &[("a", "b", 0.8), ("b", "c", 0.7)]);
+        let theirs = network(&["a", "b", "d"], &[("b", "a", 0.9), ("a", "d", 0.75)]);
+
+        let diff = ours.diff(&theirs);
+        assert_eq!(diff.added_nodes, vec!["d"]);
+        assert_eq!(diff.removed_nodes, vec!["c"]);
+        assert_eq!(diff.added_edges.len(), 1);
+        assert_eq!(diff.removed_edges[0].target, "c");
+        assert_eq!(diff.weight_changes[0].after, 0.9);
+        assert!(ours.diff(&ours).is_empty());
+    }
+
+    #[test]
+    fn test_merge_policies() {
+        let ours = network(&["a", "b"], &[("a", "b", 0.8)]);
+        let theirs = network(&["a", "b", "c"], &[("a", "b", 0.6), ("b", "c", 0.9)]);
+
+        let mut merged = ours.clone();
+        let report = merged.merge(&theirs, MergePolicy::MaxWeight);
+        assert_eq!((report.nodes_added, report.edges_added), (1, 1));
+        assert_eq!(report.conflicts[0].after, 0.8);
+
+        let mut merged = ours.clone();
+        assert!((merged.merge(&theirs, MergePolicy::Average).conflicts[0].after - 0.7).abs() < 1e-9);
+
+        // Timestamps decide which side is newer
+        let mut newer = ours.clone();
+        newer.metadata.insert("built_at".to_string(), serde_json::json!("2025-01-02T00:00:00Z"));
+        let mut older = theirs.clone();
+        older.metadata.insert("built_at".to_string(), serde_json::json!("2025-01-01T00:00:00Z"));
+        assert_eq!(newer.merge(&older, MergePolicy::PreferNewer).conflicts[0].after, 0.8);
+        assert!(newer.diff(&older).added_edges.is_empty());
+    }
+}
@@ -0,0 +1,794 @@
+//! Embedded Graph Store
+//!
+//! A graph store that needs no database server, for laptops and CI. Nodes,
+//! edges and evidence are persisted to an embedded sled database; a
+//! project's graph is loaded into a petgraph graph the first time it is
+//! used and queried in memory from then on, every write going to both.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::{info, debug, warn};
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::inspect::{append_history, stored_history, ConfidencePoint, MoleculeSummary};
+use super::neighborhood::{molecule_node, NeighborEdge, NeighborhoodOptions, NeighborhoodSearch};
+use super::paths::MoleculePath;
+use super::pathways::PathwayMembership;
+use super::schema::{Edge, EdgeType, MolecularGraph, Node, NodeType};
+use super::stats::{confidence_band, trend_start, ProjectStats};
+use super::store::{GenePhenotypes, GraphStore, MoleculeInteraction, StoreBackend};
+use super::MoleculeNetwork;
+use crate::bundle::ProjectBundle;
+use crate::processing::evidence::{Evidence, IntegratedEvidence};
+use crate::processing::versioning::ConfidenceTrigger;
+use crate::projects::Project;
+
+/// Separator between the parts of a key
+const KEY_SEPARATOR: u8 = 0;
+
+/// Membership steps between a molecule and a pathway, e.g. through a reaction
+const MEMBERSHIP_HOPS: usize = 2;
+
+/// Paths enumerated before the cheapest are picked
+const MAX_ENUMERATED_PATHS: usize = 1000;
+
+/// Key made of the given parts
+fn key(parts: &[&str]) -> Vec<u8> {
+    let mut key = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            key.push(KEY_SEPARATOR);
+        }
+        key.extend_from_slice(part.as_bytes());
+    }
+    key
+}
+
+/// Prefix of all keys starting with the given parts
+fn prefix(parts: &[&str]) -> Vec<u8> {
+    let mut prefix = key(parts);
+    prefix.push(KEY_SEPARATOR);
+    prefix
+}
+
+/// Key of an edge, one per type between two nodes
+fn edge_key(project_id: &str, edge: &Edge) -> Vec<u8> {
+    key(&[project_id, &edge.source_id, &edge.target_id, &edge.edge_type.to_string()])
+}
+
+/// Similarity, weight or confidence of an edge, in that order of preference
+fn edge_weight(edge: &Edge) -> f64 {
+    ["similarity", "weight", "confidence"].iter()
+        .find_map(|key| edge.get_property(key).and_then(|v| v.as_f64()))
+        .unwrap_or(1.0)
+}
+
+/// A project's graph, held in memory
+#[derive(Debug, Default)]
+struct ProjectGraph {
+    /// Nodes and edges; indices stay valid across removals
+    graph: StableDiGraph<Node, Edge>,
+
+    /// Node index by node ID
+    index: HashMap<String, NodeIndex>,
+}
+
+impl ProjectGraph {
+    /// Add a node, or replace the one with its ID
+    fn upsert_node(&mut self, node: Node) -> NodeIndex {
+        match self.index.get(&node.id) {
+            Some(&idx) => {
+                self.graph[idx] = node;
+                idx
+            }
+            None => {
+                let id = node.id.clone();
+                let idx = self.graph.add_node(node);
+                self.index.insert(id, idx);
+                idx
+            }
+        }
+    }
+
+    /// Add an edge, or replace the one of its type between the same nodes
+    fn upsert_edge(&mut self, edge: Edge) -> Result<()> {
+        let source = *self.index.get(&edge.source_id)
+            .ok_or_else(|| anyhow!("Edge source {} is not in the graph", edge.source_id))?;
+        let target = *self.index.get(&edge.target_id)
+            .ok_or_else(|| anyhow!("Edge target {} is not in the graph", edge.target_id))?;
+        let existing = self.graph.edges_connecting(source, target)
+            .find(|e| e.weight().edge_type == edge.edge_type)
+            .map(|e| e.id());
+        match existing {
+            Some(idx) => self.graph[idx] = edge,
+            None => {
+                self.graph.add_edge(source, target, edge);
+            }
+        }
+        Ok(())
+    }
+
+    /// Index of a molecule node
+    fn molecule(&self, molecule_id: &str) -> Option<NodeIndex> {
+        self.index.get(molecule_id)
+            .copied()
+            .filter(|&idx| self.graph[idx].node_type == NodeType::Molecule)
+    }
+
+    /// Nodes reached from `start` over at most `MEMBERSHIP_HOPS` `PART_OF` edges in one direction
+    fn membership_reach(&self, start: NodeIndex, direction: Direction) -> HashSet<NodeIndex> {
+        let mut reached = HashSet::from([start]);
+        let mut frontier = vec![start];
+        for _ in 0..MEMBERSHIP_HOPS {
+            frontier = frontier.iter()
+                .flat_map(|&idx| self.graph.edges_directed(idx, direction))
+                .filter(|e| e.weight().edge_type == EdgeType::PartOf)
+                .map(|e| if direction == Direction::Outgoing { e.target() } else { e.source() })
+                .filter(|&idx| reached.insert(idx))
+                .collect();
+        }
+        reached
+    }
+
+    /// Pathways a molecule takes part in, with their member molecules
+    fn pathways(&self, molecule_id: &str) -> Vec<PathwayMembership> {
+        let start = match self.molecule(molecule_id) {
+            Some(idx) => idx,
+            None => return Vec::new(),
+        };
+        let mut pathways: Vec<PathwayMembership> = self.membership_reach(start, Direction::Outgoing).into_iter()
+            .filter(|&idx| self.graph[idx].node_type == NodeType::Pathway)
+            .map(|idx| {
+                let members: BTreeSet<&str> = self.membership_reach(idx, Direction::Incoming).into_iter()
+                    .filter(|&m| self.graph[m].node_type == NodeType::Molecule)
+                    .map(|m| self.graph[m].id.as_str())
+                    .collect();
+                PathwayMembership {
+                    pathway_id: self.graph[idx].id.clone(),
+                    name: Some(self.graph[idx].name.clone()),
+                    members: members.into_iter().map(str::to_string).collect(),
+                }
+            })
+            .collect();
+        pathways.sort_by(|a, b| a.pathway_id.cmp(&b.pathway_id));
+        pathways
+    }
+
+    /// Relationships from a molecule to other molecules
+    fn interactions(&self, molecule_id: &str) -> Vec<MoleculeInteraction> {
+        let start = match self.molecule(molecule_id) {
+            Some(idx) => idx,
+            None => return Vec::new(),
+        };
+        let mut interactions: Vec<MoleculeInteraction> = self.graph.edges_directed(start, Direction::Outgoing)
+            .filter(|e| self.graph[e.target()].node_type == NodeType::Molecule)
+            .map(|e| {
+                let edge = e.weight();
+                MoleculeInteraction {
+                    source_molecule: molecule_id.to_string(),
+                    target_molecule: edge.target_id.clone(),
+                    target_name: Some(self.graph[e.target()].name.clone()),
+                    interaction_type: edge.edge_type.to_string(),
+                    confidence: edge.get_property("confidence").and_then(|v| v.as_f64()),
+                    evidence_count: edge.get_property("evidence_count").and_then(|v| v.as_u64()).unwrap_or(1) as usize,
+                }
+            })
+            .collect();
+        interactions.sort_by(|a, b| (&a.target_molecule, &a.interaction_type).cmp(&(&b.target_molecule, &b.interaction_type)));
+        interactions
+    }
+
+    /// Genes linked to the most `Disease` nodes, most linked first
+    fn gene_phenotypes(&self, limit: usize) -> Vec<GenePhenotypes> {
+        let mut genes: Vec<GenePhenotypes> = self.graph.node_indices()
+            .filter(|&idx| self.graph[idx].node_type == NodeType::Gene)
+            .map(|idx| {
+                let phenotypes: HashSet<NodeIndex> = self.graph.edges_directed(idx, Direction::Outgoing)
+                    .map(|e| e.target())
+                    .filter(|&target| self.graph[target].node_type == NodeType::Disease)
+                    .collect();
+                GenePhenotypes {
+                    gene_id: self.graph[idx].id.clone(),
+                    gene_name: Some(self.graph[idx].name.clone()),
+                    phenotype_count: phenotypes.len(),
+                }
+            })
+            .filter(|gene| gene.phenotype_count > 0)
+            .collect();
+        genes.sort_by(|a, b| b.phenotype_count.cmp(&a.phenotype_count).then_with(|| a.gene_id.cmp(&b.gene_id)));
+        genes.truncate(limit);
+        genes
+    }
+
+    /// Similarity edges, in either direction, from the given molecules to other molecules
+    fn similar_molecules(&self, frontier: &[String], min_similarity: f64) -> Vec<NeighborEdge> {
+        frontier.iter()
+            .filter_map(|id| self.molecule(id).map(|idx| (id, idx)))
+            .flat_map(|(id, idx)| {
+                self.graph.edges_directed(idx, Direction::Outgoing).map(|e| (e.target(), e.weight()))
+                    .chain(self.graph.edges_directed(idx, Direction::Incoming).map(|e| (e.source(), e.weight())))
+                    .filter(|(next, edge)| {
+                        edge.edge_type == EdgeType::SimilarTo && self.graph[*next].node_type == NodeType::Molecule
+                    })
+                    .map(move |(next, edge)| (id, next, edge_weight(edge)))
+            })
+            .filter(|(_, _, similarity)| *similarity >= min_similarity)
+            .map(|(id, next, similarity)| NeighborEdge {
+                from: id.clone(),
+                neighbor: molecule_node(&self.graph[next]),
+                similarity,
+            })
+            .collect()
+    }
+
+    /// All shortest paths of at most `max_hops` edges between two molecules, ignoring edge direction
+    fn shortest_paths(&self, from: &str, to: &str, max_hops: usize) -> Vec<MoleculePath> {
+        let (start, end) = match (self.molecule(from), self.molecule(to)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Vec::new(),
+        };
+
+        // Breadth-first search recording every predecessor on a shortest path
+        let mut depth = HashMap::from([(start, 0)]);
+        let mut predecessors: HashMap<NodeIndex, Vec<(NodeIndex, EdgeIndex)>> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(idx) = queue.pop_front() {
+            let d = depth[&idx];
+            if idx == end || d == max_hops || depth.get(&end).is_some_and(|&found| d >= found) {
+                continue;
+            }
+            let neighbours = self.graph.edges_directed(idx, Direction::Outgoing).map(|e| (e.target(), e.id()))
+                .chain(self.graph.edges_directed(idx, Direction::Incoming).map(|e| (e.source(), e.id())));
+            for (next, edge) in neighbours {
+                match depth.get(&next) {
+                    None => {
+                        depth.insert(next, d + 1);
+                        predecessors.entry(next).or_default().push((idx, edge));
+                        queue.push_back(next);
+                    }
+                    Some(&nd) if nd == d + 1 => predecessors.entry(next).or_default().push((idx, edge)),
+                    _ => {}
+                }
+            }
+        }
+        if !depth.contains_key(&end) {
+            return Vec::new();
+        }
+
+        // Walk the predecessors back from the end
+        let mut paths = Vec::new();
+        let mut stack = vec![(end, Vec::<EdgeIndex>::new())];
+        while let Some((idx, steps)) = stack.pop() {
+            if paths.len() >= MAX_ENUMERATED_PATHS {
+                break;
+            }
+            if idx == start {
+                paths.push(self.path(start, steps.iter().rev()));
+                continue;
+            }
+            for &(previous, edge) in predecessors.get(&idx).into_iter().flatten() {
+                let mut steps = steps.clone();
+                steps.push(edge);
+                stack.push((previous, steps));
+            }
+        }
+        paths
+    }
+
+    /// Path from `start` along the given edges
+    fn path<'a>(&self, start: NodeIndex, steps: impl Iterator<Item = &'a EdgeIndex>) -> MoleculePath {
+        let mut molecules = vec![self.graph[start].id.clone()];
+        let mut relationships = Vec::new();
+        let mut weights = Vec::new();
+        for &step in steps {
+            let edge = &self.graph[step];
+            let next = if *molecules.last().unwrap() == edge.source_id { &edge.target_id } else { &edge.source_id };
+            molecules.push(next.clone());
+            relationships.push(edge.edge_type.to_string());
+            weights.push(edge_weight(edge));
+        }
+        MoleculePath {
+            molecules,
+            relationships,
+            cost: weights.iter().map(|w| (1.0 - w).max(0.0)).sum(),
+            weights,
+        }
+    }
+}
+
+/// Graph store persisted to an embedded sled database
+pub struct EmbeddedStore {
+    /// Embedded database
+    db: sled::Db,
+
+    /// Nodes, keyed by project and node ID
+    nodes: sled::Tree,
+
+    /// Edges, keyed by project, source, target and type
+    edges: sled::Tree,
+
+    /// Evidence, keyed by project, molecule and evidence ID
+    evidence: sled::Tree,
+
+    /// Project metadata and members, keyed by project ID
+    project_records: sled::Tree,
+
+    /// Graphs of the projects used so far
+    projects: Mutex<HashMap<String, ProjectGraph>>,
+}
+
+impl EmbeddedStore {
+    /// Open (or create) a store at the given directory
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path)
+            .with_context(|| format!("Failed to open embedded graph store: {}", path.display()))?;
+        info!("Opened embedded graph store at {}", path.display());
+        Self::from_db(db)
+    }
+
+    /// Create a store that lives only in memory
+    pub fn temporary() -> Result<Self> {
+        Self::from_db(sled::Config::new().temporary(true).open()?)
+    }
+
+    fn from_db(db: sled::Db) -> Result<Self> {
+        Ok(Self {
+            nodes: db.open_tree("nodes")?,
+            edges: db.open_tree("edges")?,
+            evidence: db.open_tree("evidence")?,
+            project_records: db.open_tree("projects")?,
+            db,
+            projects: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Run `f` on a project's graph, loading it from disk on first use
+    fn with_project<T>(&self, project_id: &str, f: impl FnOnce(&mut ProjectGraph) -> Result<T>) -> Result<T> {
+        let mut projects = self.projects.lock().map_err(|_| anyhow!("Embedded graph store lock poisoned"))?;
+        if !projects.contains_key(project_id) {
+            let graph = self.load_project(project_id)?;
+            projects.insert(project_id.to_string(), graph);
+        }
+        f(projects.get_mut(project_id).expect("project graph was just loaded"))
+    }
+
+    /// Read a project's nodes and edges from disk
+    fn load_project(&self, project_id: &str) -> Result<ProjectGraph> {
+        let mut graph = ProjectGraph::default();
+        for entry in self.nodes.scan_prefix(prefix(&[project_id])) {
+            let (_, value) = entry?;
+            graph.upsert_node(serde_json::from_slice(&value)?);
+        }
+        for entry in self.edges.scan_prefix(prefix(&[project_id])) {
+            let (_, value) = entry?;
+            let edge: Edge = serde_json::from_slice(&value)?;
+            if let Err(e) = graph.upsert_edge(edge) {
+                warn!("Skipping stored edge in project {}: {}", project_id, e);
+            }
+        }
+        debug!("Loaded project {} with {} nodes and {} edges", project_id, graph.graph.node_count(), graph.graph.edge_count());
+        Ok(graph)
+    }
+
+    /// Create or replace a node of any type
+    pub fn store_node(&self, project_id: &str, node: &Node) -> Result<()> {
+        self.with_project(project_id, |graph| {
+            self.nodes.insert(key(&[project_id, &node.id]), serde_json::to_vec(node)?)?;
+            graph.upsert_node(node.clone());
+            Ok(())
+        })
+    }
+
+    /// Create or replace an edge; both of its nodes must be stored
+    pub fn store_edge(&self, project_id: &str, edge: &Edge) -> Result<()> {
+        self.with_project(project_id, |graph| {
+            graph.upsert_edge(edge.clone())?;
+            self.edges.insert(edge_key(project_id, edge), serde_json::to_vec(edge)?)?;
+            Ok(())
+        })
+    }
+
+    /// Store every node and edge of a molecular graph
+    pub fn store_graph(&self, graph: &MolecularGraph) -> Result<()> {
+        for node in &graph.nodes {
+            self.store_node(&graph.project_id, node)?;
+        }
+        for edge in &graph.edges {
+            self.store_edge(&graph.project_id, edge)?;
+        }
+        self.db.flush()?;
+        info!("Stored graph {} with {} nodes and {} edges", graph.id, graph.nodes.len(), graph.edges.len());
+        Ok(())
+    }
+
+    /// Store the result of evidence integration for a molecule
+    ///
+    /// Evidence items are stored by ID and the molecule, created if
+    /// missing, takes the integrated confidence and the ID of the pipeline
+    /// fingerprint that produced it. The confidence is appended to the
+    /// molecule's history with what triggered the integration, as in Neo4j.
+    pub fn store_integrated_evidence(&self, project_id: &str, integrated: &IntegratedEvidence, trigger: ConfidenceTrigger) -> Result<()> {
+        for item in &integrated.evidence_items {
+            self.evidence.insert(key(&[project_id, &integrated.molecule_id, &item.id]), serde_json::to_vec(item)?)?;
+        }
+        let mut molecule = self.with_project(project_id, |graph| {
+            Ok(graph.molecule(&integrated.molecule_id).map(|idx| graph.graph[idx].clone()))
+        })?.unwrap_or_else(|| Node::new(integrated.molecule_id.clone(), NodeType::Molecule, integrated.molecule_id.clone()));
+        molecule.add_property("confidence", serde_json::json!(integrated.aggregate_confidence));
+        molecule.add_property("conflict_count", serde_json::json!(integrated.conflicts.len()));
+        molecule.add_property("last_integrated", serde_json::json!(integrated.integration_timestamp.to_rfc3339()));
+        append_history(&mut molecule.properties, integrated.aggregate_confidence, integrated.integration_timestamp, trigger);
+        if let Some(fingerprint) = &integrated.pipeline_fingerprint {
+            molecule.add_property("pipeline_fingerprint", serde_json::json!(fingerprint.id));
+        }
+        self.store_node(project_id, &molecule)
+    }
+
+    /// Evidence stored for a molecule
+    pub fn molecule_evidence(&self, project_id: &str, molecule_id: &str) -> Result<Vec<Evidence>> {
+        self.evidence.scan_prefix(prefix(&[project_id, molecule_id]))
+            .map(|entry| Ok(serde_json::from_slice(&entry?.1)?))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl GraphStore for EmbeddedStore {
+    fn backend(&self) -> StoreBackend {
+        StoreBackend::Embedded
+    }
+
+    async fn store_molecule(&self, project_id: &str, molecule: &Node) -> Result<()> {
+        if molecule.node_type != NodeType::Molecule {
+            return Err(anyhow!("{} is a {} node, not a molecule", molecule.id, molecule.node_type));
+        }
+        self.store_node(project_id, molecule)
+    }
+
+    async fn get_molecule(&self, project_id: &str, molecule_id: &str) -> Result<Option<Node>> {
+        self.with_project(project_id, |graph| {
+            Ok(graph.molecule(molecule_id).map(|idx| graph.graph[idx].clone()))
+        })
+    }
+
+    async fn list_molecules(&self, project_id: &str) -> Result<Vec<MoleculeSummary>> {
+        let mut molecules: Vec<MoleculeSummary> = self.with_project(project_id, |graph| {
+            Ok(graph.graph.node_weights()
+                .filter(|node| node.node_type == NodeType::Molecule)
+                .map(|node| MoleculeSummary {
+                    molecule_id: node.id.clone(),
+                    name: Some(node.name.clone()),
+                    confidence: node.get_property("confidence").and_then(|v| v.as_f64()),
+                    evidence_count: 0,
+                })
+                .collect())
+        })?;
+        for molecule in &mut molecules {
+            molecule.evidence_count = self.evidence.scan_prefix(prefix(&[project_id, &molecule.molecule_id])).count();
+        }
+        molecules.sort_by(|a, b| a.molecule_id.cmp(&b.molecule_id));
+        Ok(molecules)
+    }
+
+    /// Delete a molecule, its relationships and its evidence
+    async fn delete_molecule(&self, project_id: &str, molecule_id: &str) -> Result<bool> {
+        let removed = self.with_project(project_id, |graph| {
+            let idx = match graph.molecule(molecule_id) {
+                Some(idx) => idx,
+                None => return Ok(false),
+            };
+            let incident: Vec<Vec<u8>> = graph.graph.edges_directed(idx, Direction::Outgoing)
+                .chain(graph.graph.edges_directed(idx, Direction::Incoming))
+                .map(|e| edge_key(project_id, e.weight()))
+                .collect();
+            for edge in incident {
+                self.edges.remove(edge)?;
+            }
+            self.nodes.remove(key(&[project_id, molecule_id]))?;
+            graph.graph.remove_node(idx);
+            graph.index.remove(molecule_id);
+            Ok(true)
+        })?;
+        if removed {
+            for entry in self.evidence.scan_prefix(prefix(&[project_id, molecule_id])) {
+                self.evidence.remove(entry?.0)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    async fn molecule_pathways(&self, project_id: &str, molecule_id: &str) -> Result<Vec<PathwayMembership>> {
+        self.with_project(project_id, |graph| Ok(graph.pathways(molecule_id)))
+    }
+
+    async fn molecule_interactions(&self, project_id: &str, molecule_id: &str) -> Result<Vec<MoleculeInteraction>> {
+        self.with_project(project_id, |graph| Ok(graph.interactions(molecule_id)))
+    }
+
+    async fn find_paths(&self, project_id: &str, from: &str, to: &str, max_hops: usize, limit: usize) -> Result<Vec<MoleculePath>> {
+        if !(1..=10).contains(&max_hops) {
+            return Err(anyhow!("max_hops must be between 1 and 10, got {}", max_hops));
+        }
+        let mut paths = self.with_project(project_id, |graph| Ok(graph.shortest_paths(from, to, max_hops)))?;
+        paths.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+        paths.truncate(limit);
+        debug!("Found {} paths between {} and {} in project {}", paths.len(), from, to, project_id);
+        Ok(paths)
+    }
+
+    async fn neighborhood(&self, project_id: &str, molecule_id: &str, options: &NeighborhoodOptions) -> Result<Option<MoleculeNetwork>> {
+        self.with_project(project_id, |graph| {
+            let center = match graph.molecule(molecule_id) {
+                Some(idx) => molecule_node(&graph.graph[idx]),
+                None => return Ok(None),
+            };
+            let mut search = NeighborhoodSearch::new(center, options)?;
+            while !search.frontier().is_empty() {
+                let edges = graph.similar_molecules(search.frontier(), search.min_similarity());
+                search.expand(edges);
+            }
+            search.finish().map(Some)
+        })
+    }
+
+    async fn store_integrated_evidence(&self, project_id: &str, integrated: &IntegratedEvidence, trigger: ConfidenceTrigger) -> Result<()> {
+        EmbeddedStore::store_integrated_evidence(self, project_id, integrated, trigger)
+    }
+
+    async fn molecule_evidence(&self, project_id: &str, molecule_id: &str) -> Result<Vec<Evidence>> {
+        EmbeddedStore::molecule_evidence(self, project_id, molecule_id)
+    }
+
+    async fn gene_phenotypes(&self, project_id: &str, limit: usize) -> Result<Vec<GenePhenotypes>> {
+        self.with_project(project_id, |graph| Ok(graph.gene_phenotypes(limit)))
+    }
+
+    async fn store_project(&self, project: &Project) -> Result<()> {
+        self.project_records.insert(project.id.as_bytes(), serde_json::to_vec(project)?)?;
+        Ok(())
+    }
+
+    async fn project_stats(&self, project_id: &str) -> Result<ProjectStats> {
+        let since = trend_start();
+        let mut stats = ProjectStats::new(project_id);
+        let molecules: Vec<(Option<f64>, bool, Vec<ConfidencePoint>)> = self.with_project(project_id, |graph| {
+            Ok(graph.graph.node_weights()
+                .filter(|node| node.node_type == NodeType::Molecule)
+                .map(|node| (
+                    node.get_property("confidence").and_then(|v| v.as_f64()),
+                    node.get_property("conflict_count").and_then(|v| v.as_u64()).unwrap_or(0) > 0,
+                    stored_history(|key| node.get_property(key)),
+                ))
+                .collect())
+        })?;
+        for (confidence, conflicted, history) in molecules {
+            stats.add_molecules(confidence.map(confidence_band), 1, usize::from(conflicted));
+            for point in history {
+                let day = point.recorded_at.format("%Y-%m-%d").to_string();
+                if day >= since {
+                    stats.add_integration_day(&day, 1, Some(point.confidence));
+                }
+            }
+        }
+
+        for entry in self.evidence.scan_prefix(prefix(&[project_id])) {
+            let evidence: Evidence = serde_json::from_slice(&entry?.1)?;
+            stats.add_evidence(&evidence.evidence_type.to_string(), &evidence.source, 1);
+            let day = evidence.timestamp.format("%Y-%m-%d").to_string();
+            if day >= since {
+                stats.add_evidence_day(&day, 1);
+            }
+        }
+
+        debug!("Computed statistics for project {}: {} molecules, {} evidence items", project_id, stats.molecules, stats.evidence);
+        Ok(stats)
+    }
+
+    /// Export a project, its whole graph as a single network named after the project
+    async fn export_project(&self, project_id: &str) -> Result<ProjectBundle> {
+        let record = self.project_records.get(project_id.as_bytes())?
+            .ok_or_else(|| anyhow!("Project not found: {}", project_id))?;
+        let mut bundle = ProjectBundle::new(serde_json::from_slice(&record).context("Invalid stored project")?);
+
+        let name = bundle.project.name.clone();
+        let network = self.with_project(project_id, |graph| {
+            let mut network = MolecularGraph::new(project_id.to_string(), name);
+            network.set_project(project_id);
+            for node in graph.graph.node_weights() {
+                network.add_node(node.clone());
+            }
+            for edge in graph.graph.edge_weights() {
+                network.add_edge(edge.clone());
+            }
+            Ok(network)
+        })?;
+        for molecule in network.nodes.iter().filter(|node| node.node_type == NodeType::Molecule) {
+            bundle.add_molecule(molecule);
+        }
+        for entry in self.evidence.scan_prefix(prefix(&[project_id])) {
+            bundle.add_evidence(&serde_json::from_slice(&entry?.1)?)?;
+        }
+        if !network.nodes.is_empty() {
+            bundle.networks.push(network);
+        }
+
+        info!("Exported project {}: {} molecules, {} evidence items", project_id, bundle.molecules.len(), bundle.evidence.len());
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    fn node(id: &str, node_type: NodeType) -> Node {
+        Node::new(id.to_string(), node_type, id.to_string())
+    }
+
+    fn edge(source: &str, target: &str, edge_type: EdgeType, similarity: f64) -> Edge {
+        let mut edge = Edge::new(source.to_string(), target.to_string(), edge_type);
+        edge.add_property("similarity", serde_json::json!(similarity));
+        edge
+    }
+
+    fn tca_cycle() -> MolecularGraph {
+        let mut graph = MolecularGraph::new("tca".to_string(), "TCA cycle".to_string());
+        for id in ["citrate", "isocitrate", "succinate", "aconitase-reaction"] {
+            let node_type = if id.ends_with("reaction") { NodeType::Protein } else { NodeType::Molecule };
+            graph.add_node(node(id, node_type));
+        }
+        graph.add_node(node("tca", NodeType::Pathway));
+        graph.add_edge(edge("citrate", "aconitase-reaction", EdgeType::PartOf, 1.0));
+        graph.add_edge(edge("aconitase-reaction", "tca", EdgeType::PartOf, 1.0));
+        graph.add_edge(edge("succinate", "tca", EdgeType::PartOf, 1.0));
+        graph.add_edge(edge("citrate", "isocitrate", EdgeType::InteractsWith, 0.9));
+        graph.add_edge(edge("isocitrate", "succinate", EdgeType::TransformsTo, 0.5));
+        graph.add_edge(edge("citrate", "succinate", EdgeType::SimilarTo, 0.2));
+        graph
+    }
+
+    #[tokio::test]
+    async fn test_queries_and_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = EmbeddedStore::open(dir.path()).unwrap();
+            store.store_graph(&tca_cycle()).unwrap();
+            let evidence = Evidence::manual("citrate", EvidenceType::MassSpec, 0.8, None, "lab").unwrap();
+            let integrated = IntegratedEvidence {
+                molecule_id: "citrate".to_string(),
+                evidence_items: vec![evidence],
+                aggregate_confidence: 0.8,
+                conflicts: Vec::new(),
+                integration_timestamp: chrono::Utc::now(),
+                pipeline_fingerprint: None,
+                policy_violations: Vec::new(),
+            };
+            // Storing the same integration again leaves the history unchanged
+            store.store_integrated_evidence("default", &integrated, ConfidenceTrigger::ManualEntry).unwrap();
+            store.store_integrated_evidence("default", &integrated, ConfidenceTrigger::ManualEntry).unwrap();
+        }
+
+        let store = EmbeddedStore::open(dir.path()).unwrap();
+        let pathways = store.molecule_pathways("default", "citrate").await.unwrap();
+        assert_eq!(pathways.len(), 1);
+        assert_eq!(pathways[0].members, vec!["citrate", "succinate"]);
+
+        let interactions = store.molecule_interactions("default", "citrate").await.unwrap();
+        assert_eq!(interactions.len(), 2);
+        assert_eq!(interactions[0].target_molecule, "isocitrate");
+
+        let molecules = store.list_molecules("default").await.unwrap();
+        assert_eq!(molecules.len(), 3);
+        assert_eq!((molecules[0].confidence, molecules[0].evidence_count), (Some(0.8), 1));
+        assert!(store.get_molecule("other-project", "citrate").await.unwrap().is_none());
+
+        let history = store.confidence_history("default", "citrate").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].confidence, history[0].trigger), (0.8, Some(ConfidenceTrigger::ManualEntry)));
+        assert!(store.confidence_history("other-project", "citrate").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ingested_evidence_reaches_analysis() {
+        use crate::processing::pipeline::IdentityPipeline;
+
+        let store: Box<dyn GraphStore> = Box::new(EmbeddedStore::temporary().unwrap());
+        let pipeline = IdentityPipeline::new();
+        let ingested = vec![
+            Evidence::manual("citrate", EvidenceType::MassSpec, 0.8, None, "lab").unwrap(),
+            Evidence::manual("citrate", EvidenceType::Structural, 0.9, None, "nmr").unwrap(),
+        ];
+        let integrated = pipeline.run("citrate", ingested).await.unwrap();
+        store.store_integrated_evidence("default", &integrated, ConfidenceTrigger::Analysis).await.unwrap();
+
+        // Analysis reads back exactly what ingestion wrote, through the same store
+        let stored = store.molecule_evidence("default", "citrate").await.unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(store.molecule_evidence("other-project", "citrate").await.unwrap().is_empty());
+
+        let analyzed = pipeline.run("citrate", stored).await.unwrap();
+        assert_eq!(analyzed.evidence_items.len(), 2);
+        assert!((analyzed.aggregate_confidence - integrated.aggregate_confidence).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_paths_and_deletion() {
+        let store = EmbeddedStore::temporary().unwrap();
+        store.store_graph(&tca_cycle()).unwrap();
+
+        let paths = store.find_paths("default", "citrate", "succinate", 3, 5).await.unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].relationships, vec!["SIMILAR_TO"]);
+
+        let options = NeighborhoodOptions::default().with_min_similarity(0.1);
+        let neighborhood = store.neighborhood("default", "succinate", &options).await.unwrap().unwrap();
+        assert_eq!(neighborhood.get_molecules().len(), 2);
+        assert!(store.neighborhood("default", "succinate", &NeighborhoodOptions::default()).await.unwrap()
+            .is_some_and(|n| n.get_molecules().len() == 1));
+
+        assert!(store.delete_molecule("default", "succinate").await.unwrap());
+        assert!(!store.delete_molecule("default", "succinate").await.unwrap());
+        assert!(store.find_paths("default", "citrate", "succinate", 3, 5).await.unwrap().is_empty());
+        assert_eq!(store.molecule_interactions("default", "citrate").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_project_stats_and_export() {
+        use crate::projects::{ProjectRegistry, DEFAULT_PROJECT};
+
+        let store = EmbeddedStore::temporary().unwrap();
+        store.store_graph(&tca_cycle()).unwrap();
+        let evidence = Evidence::manual("citrate", EvidenceType::MassSpec, 0.9, None, "lab").unwrap();
+        store.store_integrated_evidence("default", &IntegratedEvidence {
+            molecule_id: "citrate".to_string(),
+            evidence_items: vec![evidence],
+            aggregate_confidence: 0.9,
+            conflicts: Vec::new(),
+            integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        }, ConfidenceTrigger::Analysis).unwrap();
+
+        let stats = store.project_stats("default").await.unwrap();
+        assert_eq!((stats.molecules, stats.evidence), (3, 1));
+        assert_eq!(stats.molecules_by_band["0.8-1.0"], 1);
+        assert_eq!(stats.evidence_by_type["mass_spec"], 1);
+        assert_eq!(stats.trend.len(), 1);
+        assert_eq!(stats.trend[0].integrations, 1);
+
+        assert!(store.export_project("default").await.is_err());
+        let project = ProjectRegistry::new().get(DEFAULT_PROJECT).unwrap().clone();
+        store.store_project(&project).await.unwrap();
+        let bundle = store.export_project("default").await.unwrap();
+        assert_eq!((bundle.molecules.len(), bundle.evidence.len(), bundle.networks.len()), (3, 1, 1));
+        assert_eq!(bundle.evidence[0]["molecule_id"], "citrate");
+        assert_eq!(bundle.networks[0].edges.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_gene_phenotypes_are_project_scoped() {
+        let store = EmbeddedStore::temporary().unwrap();
+        let mut graph = MolecularGraph::new("genes".to_string(), "Genes".to_string());
+        graph.add_node(node("BRCA1", NodeType::Gene));
+        graph.add_node(node("TP53", NodeType::Gene));
+        for disease in ["breast-cancer", "ovarian-cancer"] {
+            graph.add_node(node(disease, NodeType::Disease));
+            graph.add_edge(edge("BRCA1", disease, EdgeType::Causes, 1.0));
+        }
+        graph.add_edge(edge("TP53", "breast-cancer", EdgeType::Causes, 1.0));
+        graph.add_edge(edge("TP53", "BRCA1", EdgeType::InteractsWith, 1.0));
+        store.store_graph(&graph).unwrap();
+
+        let genes = store.gene_phenotypes("default", 10).await.unwrap();
+        let counts: Vec<(&str, usize)> = genes.iter().map(|g| (g.gene_id.as_str(), g.phenotype_count)).collect();
+        assert_eq!(counts, vec![("BRCA1", 2), ("TP53", 1)]);
+        assert_eq!(store.gene_phenotypes("default", 1).await.unwrap().len(), 1);
+        assert!(store.gene_phenotypes("other-project", 10).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,381 @@
+//! Node Embeddings
+//!
+//! Learns a vector per molecule from the shape of the network, node2vec
+//! style: biased random walks are sampled over similarity edges and a
+//! skip-gram model with negative sampling is trained on them, so molecules
+//! that share neighbourhoods end up close together even when their own
+//! structures differ. Embeddings are stored in the nodes' `embedding`
+//! property, where the `embedding` similarity metric reads them.
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use petgraph::visit::EdgeRef;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+
+use super::MoleculeNetwork;
+use crate::rng;
+
+/// Node property the embeddings are stored in
+pub const EMBEDDING_PROPERTY: &str = "embedding";
+
+/// Parameters of walk sampling and skip-gram training
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingOptions {
+    /// Length of the embedding vectors
+    pub dimensions: usize,
+
+    /// Nodes visited per walk, including the start
+    pub walk_length: usize,
+
+    /// Walks started from each node
+    pub walks_per_node: usize,
+
+    /// Neighbours on each side of a node treated as its context
+    pub window: usize,
+
+    /// Return parameter p; higher values make walks less likely to step back
+    pub return_param: f64,
+
+    /// In-out parameter q; higher values keep walks local, lower ones explore
+    pub in_out_param: f64,
+
+    /// Passes of training over the walks
+    pub epochs: usize,
+
+    /// Initial learning rate, decayed linearly to zero
+    pub learning_rate: f64,
+
+    /// Noise nodes sampled per context pair
+    pub negative_samples: usize,
+
+    /// RNG seed; the global seed or a random one is used if absent
+    pub seed: Option<u64>,
+}
+
+impl Default for EmbeddingOptions {
+    fn default() -> Self {
+        Self {
+            dimensions: 32,
+            walk_length: 20,
+            walks_per_node: 10,
+            window: 5,
+            return_param: 1.0,
+            in_out_param: 1.0,
+            epochs: 1,
+            learning_rate: 0.025,
+            negative_samples: 5,
+            seed: None,
+        }
+    }
+}
+
+impl EmbeddingOptions {
+    /// Set the length of the embedding vectors
+    pub fn with_dimensions(mut self, dimensions: usize) -> Self {
+        self.dimensions = dimensions;
+        self
+    }
+
+    /// Set the number and length of walks started from each node
+    pub fn with_walks(mut self, walks_per_node: usize, walk_length: usize) -> Self {
+        self.walks_per_node = walks_per_node;
+        self.walk_length = walk_length;
+        self
+    }
+
+    /// Set the node2vec return (p) and in-out (q) parameters
+    pub fn with_bias(mut self, return_param: f64, in_out_param: f64) -> Self {
+        self.return_param = return_param;
+        self.in_out_param = in_out_param;
+        self
+    }
+
+    /// Set the number of training passes
+    pub fn with_epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    /// Seed the random number generator
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Check the options describe a trainable model
+    pub fn validate(&self) -> Result<()> {
+        if self.dimensions == 0 || self.walk_length < 2 || self.walks_per_node == 0 || self.window == 0 {
+            return Err(anyhow!("Embedding dimensions, walks and window must be positive and walks at least 2 long"));
+        }
+        if self.return_param <= 0.0 || self.in_out_param <= 0.0 {
+            return Err(anyhow!("node2vec parameters p and q must be positive"));
+        }
+        if self.learning_rate <= 0.0 {
+            return Err(anyhow!("Learning rate must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Learned embedding of every molecule in a network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeEmbeddings {
+    /// Length of each vector
+    pub dimensions: usize,
+
+    /// Seed the walks and training used
+    pub seed: u64,
+
+    /// Vector of each molecule by ID
+    pub vectors: HashMap<String, Vec<f64>>,
+}
+
+impl NodeEmbeddings {
+    /// Vector of a molecule
+    pub fn get(&self, molecule_id: &str) -> Option<&Vec<f64>> {
+        self.vectors.get(molecule_id)
+    }
+
+    /// Cosine similarity of two molecules' vectors
+    pub fn similarity(&self, a: &str, b: &str) -> Option<f64> {
+        Some(cosine(self.get(a)?, self.get(b)?))
+    }
+
+    /// The `k` molecules whose vectors are closest to the given molecule's
+    pub fn most_similar(&self, molecule_id: &str, k: usize) -> Vec<(String, f64)> {
+        let target = match self.get(molecule_id) {
+            Some(target) => target,
+            None => return Vec::new(),
+        };
+        let mut scored: Vec<(String, f64)> = self.vectors.iter()
+            .filter(|(id, _)| id.as_str() != molecule_id)
+            .map(|(id, vector)| (id.clone(), cosine(target, vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scored.truncate(k);
+        scored
+    }
+}
+
+/// Cosine similarity of two vectors; zero if either has no length
+pub fn cosine(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Pick an index with probability proportional to its weight
+fn sample_weighted<R: Rng>(rng: &mut R, weights: &[f64]) -> usize {
+    let total: f64 = weights.iter().sum();
+    let mut target = rng.gen::<f64>() * total;
+    for (i, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return i;
+        }
+        target -= weight;
+    }
+    weights.len() - 1
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+impl MoleculeNetwork {
+    /// Learn an embedding of every molecule from random walks over the network
+    pub fn embed(&self, options: &EmbeddingOptions) -> Result<NodeEmbeddings> {
+        options.validate()?;
+        let seed = rng::resolve_seed(options.seed);
+        let mut rng = rng::stream_rng(seed, "node2vec");
+
+        // Neighbours by node index, weighted by similarity
+        let count = self.graph.node_count();
+        let mut neighbours: Vec<Vec<(usize, f64)>> = vec![Vec::new(); count];
+        let mut adjacent: HashSet<(usize, usize)> = HashSet::new();
+        for edge in self.graph.edge_references() {
+            let (a, b) = (edge.source().index(), edge.target().index());
+            if a == b {
+                continue;
+            }
+            let weight = edge.weight().similarity().max(1e-6);
+            neighbours[a].push((b, weight));
+            neighbours[b].push((a, weight));
+            adjacent.insert((a, b));
+            adjacent.insert((b, a));
+        }
+
+        let walks = random_walks(&neighbours, &adjacent, options, &mut rng);
+        debug!("Sampled {} walks over {} molecules with seed {}", walks.len(), count, seed);
+        let vectors = train_skip_gram(&walks, count, options, &mut rng);
+
+        Ok(NodeEmbeddings {
+            dimensions: options.dimensions,
+            seed,
+            vectors: self.graph.node_indices()
+                .map(|idx| (self.graph[idx].id.clone(), vectors[idx.index()].clone()))
+                .collect(),
+        })
+    }
+
+    /// Store embeddings in the molecules' `embedding` property
+    ///
+    /// Returns the number of molecules that received a vector.
+    pub fn store_embeddings(&mut self, embeddings: &NodeEmbeddings) -> usize {
+        let mut stored = 0;
+        for node in self.graph.node_weights_mut() {
+            if let Some(vector) = embeddings.get(&node.id) {
+                node.properties.insert(EMBEDDING_PROPERTY.to_string(), serde_json::json!(vector));
+                stored += 1;
+            }
+        }
+        self.metadata.insert("embedding".to_string(), serde_json::json!({
+            "dimensions": embeddings.dimensions,
+            "seed": embeddings.seed,
+        }));
+        stored
+    }
+}
+
+/// Second-order biased walks: stepping back costs 1/p, staying next to
+/// the previous node costs 1, moving away costs 1/q
+fn random_walks<R: Rng>(
+    neighbours: &[Vec<(usize, f64)>],
+    adjacent: &HashSet<(usize, usize)>,
+    options: &EmbeddingOptions,
+    rng: &mut R,
+) -> Vec<Vec<usize>> {
+    let mut starts: Vec<usize> = (0..neighbours.len()).collect();
+    let mut walks = Vec::with_capacity(starts.len() * options.walks_per_node);
+
+    for _ in 0..options.walks_per_node {
+        starts.shuffle(rng);
+        for &start in &starts {
+            let mut walk = vec![start];
+            while walk.len() < options.walk_length {
+                let current = walk[walk.len() - 1];
+                let candidates = &neighbours[current];
+                if candidates.is_empty() {
+                    break;
+                }
+                let weights: Vec<f64> = match walk.len().checked_sub(2).map(|i| walk[i]) {
+                    None => candidates.iter().map(|(_, w)| *w).collect(),
+                    Some(previous) => candidates.iter()
+                        .map(|(next, w)| {
+                            if *next == previous {
+                                w / options.return_param
+                            } else if adjacent.contains(&(previous, *next)) {
+                                *w
+                            } else {
+                                w / options.in_out_param
+                            }
+                        })
+                        .collect(),
+                };
+                walk.push(candidates[sample_weighted(rng, &weights)].0);
+            }
+            walks.push(walk);
+        }
+    }
+    walks
+}
+
+/// Skip-gram with negative sampling; returns the input vector of each node
+fn train_skip_gram<R: Rng>(walks: &[Vec<usize>], count: usize, options: &EmbeddingOptions, rng: &mut R) -> Vec<Vec<f64>> {
+    let dimensions = options.dimensions;
+    let mut input: Vec<Vec<f64>> = (0..count)
+        .map(|_| (0..dimensions).map(|_| (rng.gen::<f64>() - 0.5) / dimensions as f64).collect())
+        .collect();
+    let mut output = vec![vec![0.0; dimensions]; count];
+
+    // Noise distribution: visit frequency raised to 3/4, as in word2vec
+    let mut frequency = vec![0.0; count];
+    for node in walks.iter().flatten() {
+        frequency[*node] += 1.0;
+    }
+    let mut cumulative = Vec::with_capacity(count);
+    let mut total = 0.0;
+    for f in &frequency {
+        total += f64::powf(*f, 0.75);
+        cumulative.push(total);
+    }
+
+    let steps = (options.epochs * walks.len()).max(1) as f64;
+    let mut step = 0.0;
+    let mut gradient = vec![0.0; dimensions];
+    for _ in 0..options.epochs {
+        for walk in walks {
+            let learning_rate = (options.learning_rate * (1.0 - step / steps)).max(options.learning_rate * 1e-4);
+            step += 1.0;
+
+            for (i, &center) in walk.iter().enumerate() {
+                let low = i.saturating_sub(options.window);
+                let high = (i + options.window + 1).min(walk.len());
+                for (j, &context) in walk.iter().enumerate().take(high).skip(low) {
+                    if j == i {
+                        continue;
+                    }
+                    gradient.iter_mut().for_each(|g| *g = 0.0);
+                    let negatives: Vec<usize> = (0..options.negative_samples)
+                        .map(|_| {
+                            let draw = rng.gen::<f64>() * total;
+                            cumulative.partition_point(|c| *c <= draw).min(count - 1)
+                        })
+                        .filter(|n| *n != context)
+                        .collect();
+                    for (target, label) in std::iter::once((context, 1.0)).chain(negatives.into_iter().map(|n| (n, 0.0))) {
+                        let score: f64 = input[center].iter().zip(&output[target]).map(|(a, b)| a * b).sum();
+                        let step_size = learning_rate * (label - sigmoid(score));
+                        for ((g, o), x) in gradient.iter_mut().zip(output[target].iter_mut()).zip(&input[center]) {
+                            *g += step_size * *o;
+                            *o += step_size * x;
+                        }
+                    }
+                    for (x, g) in input[center].iter_mut().zip(&gradient) {
+                        *x += g;
+                    }
+                }
+            }
+        }
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::Molecule;
+
+    #[test]
+    fn test_embeddings_follow_communities() {
+        // Two tight triangles joined by one weak bridge
+        let mut network = MoleculeNetwork::new();
+        for id in ["a1", "a2", "a3", "b1", "b2", "b3"] {
+            let mut molecule = Molecule::from_smiles("C").unwrap();
+            molecule.id = id.to_string();
+            network.add_molecule(&molecule);
+        }
+        for (x, y) in [("a1", "a2"), ("a2", "a3"), ("a1", "a3"), ("b1", "b2"), ("b2", "b3"), ("b1", "b3")] {
+            network.add_similarity(x, y, 0.9);
+        }
+        network.add_similarity("a3", "b1", 0.1);
+
+        let options = EmbeddingOptions::default().with_dimensions(8).with_epochs(3).with_seed(7);
+        let embeddings = network.embed(&options).unwrap();
+        assert_eq!(embeddings.vectors.len(), 6);
+        assert!(embeddings.similarity("a1", "a2").unwrap() > embeddings.similarity("a1", "b2").unwrap());
+        assert_eq!(network.embed(&options).unwrap().get("a1"), embeddings.get("a1"));
+
+        assert_eq!(network.store_embeddings(&embeddings), 6);
+        let a1 = network.get_molecule("a1").unwrap().to_molecule();
+        let a2 = network.get_molecule("a2").unwrap().to_molecule();
+        let registry = crate::graph::similarity::SimilarityRegistry::with_defaults();
+        assert!(registry.compute("embedding", &a1, &a2).unwrap() > 0.5);
+    }
+}
@@ -0,0 +1,313 @@
+//! Molecular Families
+//!
+//! In a network built from MS/MS similarity, connected features tend to be
+//! structural relatives: a molecular family. Families are the connected
+//! components of the network, split by dropping their weakest edges when they
+//! grow too large to mean anything, as GNPS does. Unidentified members then
+//! borrow the identification of the closest confidently identified member,
+//! discounted by the spectral similarity along the way.
+
+use log::debug;
+use petgraph::graph::NodeIndex;
+use petgraph::unionfind::UnionFind;
+use petgraph::visit::EdgeRef;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::similarity::{PRECURSOR_MZ_PROPERTY, SPECTRUM_PROPERTY};
+use super::MoleculeNetwork;
+use crate::processing::spectral::MsMsSpectrum;
+use crate::processing::Molecule;
+
+/// Node property holding the ID of the molecule's family
+pub const FAMILY_PROPERTY: &str = "family";
+
+/// Node property holding the confidence of a library identification
+pub const IDENTIFICATION_CONFIDENCE_PROPERTY: &str = "identification_confidence";
+
+/// Node property holding an identification propagated from a family member
+pub const PROPAGATED_ANNOTATION_PROPERTY: &str = "propagated_annotation";
+
+/// Options for finding and annotating molecular families
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FamilyOptions {
+    /// Largest family; bigger components lose their weakest edges until they split
+    pub max_family_size: usize,
+
+    /// Lowest identification confidence for a member to annotate others
+    pub min_identification_confidence: f64,
+
+    /// Most edges an identification is propagated across
+    pub max_hops: usize,
+}
+
+impl Default for FamilyOptions {
+    fn default() -> Self {
+        Self {
+            max_family_size: 100,
+            min_identification_confidence: 0.7,
+            max_hops: 2,
+        }
+    }
+}
+
+/// An identification carried over from another member of the family
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropagatedAnnotation {
+    /// Identified member the annotation comes from
+    pub source_id: String,
+
+    /// Name of the identified compound
+    pub name: String,
+
+    /// Structure of the identified compound
+    pub smiles: Option<String>,
+
+    /// Edges between the two members
+    pub hops: usize,
+
+    /// Source confidence times the similarities along the path (0.0 - 1.0)
+    pub confidence: f64,
+}
+
+/// A connected group of spectrally similar molecules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MolecularFamily {
+    /// Family ID, by decreasing size
+    pub id: usize,
+
+    /// Member molecule IDs
+    pub members: Vec<String>,
+
+    /// Members identified with enough confidence to annotate others
+    pub identified: Vec<String>,
+
+    /// Annotations propagated to unidentified members, by member ID
+    pub annotations: BTreeMap<String, PropagatedAnnotation>,
+}
+
+/// A molecule for an MS/MS feature, to build a spectral network from
+///
+/// The spectrum and precursor m/z are kept in the properties read by the
+/// `modified_cosine` metric; identified features keep their confidence.
+pub fn feature_molecule(feature: &MsMsSpectrum) -> Molecule {
+    let mut properties = HashMap::new();
+    let peaks: Vec<[f64; 2]> = feature.spectrum.peaks.iter().map(|p| [p.mz, p.intensity]).collect();
+    properties.insert(SPECTRUM_PROPERTY.to_string(), serde_json::json!(peaks));
+    properties.insert(PRECURSOR_MZ_PROPERTY.to_string(), serde_json::json!(feature.precursor_mz));
+    if let Some(confidence) = feature.identification_confidence {
+        properties.insert(IDENTIFICATION_CONFIDENCE_PROPERTY.to_string(), serde_json::json!(confidence));
+    }
+
+    Molecule {
+        id: feature.id.clone(),
+        smiles: feature.smiles.clone().unwrap_or_default(),
+        inchi: None,
+        inchi_key: None,
+        name: feature.name.clone(),
+        formula: None,
+        molecular_weight: None,
+        properties,
+        ..Default::default()
+    }
+}
+
+impl MoleculeNetwork {
+    /// Group the network into molecular families and propagate identifications
+    ///
+    /// Oversized components are split by removing edges from the network.
+    /// Each member of a family of two or more gets the `family` property, and
+    /// each unidentified member that can be reached from an identified one
+    /// gets a `propagated_annotation`. Singletons are not families.
+    pub fn molecular_families(&mut self, options: &FamilyOptions) -> Vec<MolecularFamily> {
+        let max_size = options.max_family_size.max(2);
+        let mut removed = 0;
+        loop {
+            let components = self.components();
+            let oversized: Vec<&Vec<usize>> = components.iter().filter(|c| c.len() > max_size).collect();
+            if oversized.is_empty() {
+                break;
+            }
+            for component in oversized {
+                let weakest = self.graph.edge_references()
+                    .filter(|edge| component.contains(&edge.source().index()))
+                    .min_by(|a, b| a.weight().similarity().total_cmp(&b.weight().similarity()))
+                    .map(|edge| edge.id());
+                if let Some(edge) = weakest {
+                    self.graph.remove_edge(edge);
+                    removed += 1;
+                }
+            }
+        }
+        if removed > 0 {
+            debug!("Removed {} weak edges to keep families under {} members", removed, max_size);
+        }
+
+        let identified: HashMap<usize, f64> = self.graph.node_indices()
+            .filter_map(|index| {
+                let node = &self.graph[index];
+                node.name.as_ref()?;
+                let confidence = node.properties.get(IDENTIFICATION_CONFIDENCE_PROPERTY)?.as_f64()?;
+                (confidence >= options.min_identification_confidence).then_some((index.index(), confidence))
+            })
+            .collect();
+
+        let mut families = Vec::new();
+        for (family_id, component) in self.components().into_iter().filter(|c| c.len() > 1).enumerate() {
+            let best = self.propagate(&component, &identified, options.max_hops);
+
+            let mut family = MolecularFamily {
+                id: family_id,
+                members: component.iter().map(|&i| self.graph[NodeIndex::new(i)].id.clone()).collect(),
+                identified: component.iter().filter(|i| identified.contains_key(i)).map(|&i| self.graph[NodeIndex::new(i)].id.clone()).collect(),
+                annotations: BTreeMap::new(),
+            };
+            for &member in &component {
+                if identified.contains_key(&member) {
+                    continue;
+                }
+                if let Some(&(source, hops, confidence)) = best.get(&member) {
+                    let source = &self.graph[NodeIndex::new(source)];
+                    family.annotations.insert(self.graph[NodeIndex::new(member)].id.clone(), PropagatedAnnotation {
+                        source_id: source.id.clone(),
+                        name: source.name.clone().unwrap_or_default(),
+                        smiles: Some(source.smiles.clone()).filter(|s| !s.is_empty()),
+                        hops,
+                        confidence,
+                    });
+                }
+            }
+
+            for &member in &component {
+                let node = &mut self.graph[NodeIndex::new(member)];
+                node.properties.insert(FAMILY_PROPERTY.to_string(), serde_json::json!(family_id));
+                match family.annotations.get(&node.id) {
+                    Some(annotation) => {
+                        node.properties.insert(PROPAGATED_ANNOTATION_PROPERTY.to_string(), serde_json::json!(annotation));
+                    }
+                    None => {
+                        node.properties.remove(PROPAGATED_ANNOTATION_PROPERTY);
+                    }
+                }
+            }
+            families.push(family);
+        }
+
+        debug!("Found {} molecular families, {} annotations propagated",
+               families.len(), families.iter().map(|f| f.annotations.len()).sum::<usize>());
+        families
+    }
+
+    /// Connected components as node index lists, largest first
+    fn components(&self) -> Vec<Vec<usize>> {
+        let mut sets = UnionFind::new(self.graph.node_count());
+        for edge in self.graph.edge_references() {
+            sets.union(edge.source().index(), edge.target().index());
+        }
+        let mut components: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for index in 0..self.graph.node_count() {
+            components.entry(sets.find(index)).or_default().push(index);
+        }
+        let mut components: Vec<Vec<usize>> = components.into_values().collect();
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then(a[0].cmp(&b[0])));
+        components
+    }
+
+    /// Best (source, hops, confidence) for each member reachable from an identified one
+    ///
+    /// Confidence is the source's confidence times the similarity of every
+    /// edge on the path, maximized over paths of at most `max_hops` edges.
+    fn propagate(&self, component: &[usize], identified: &HashMap<usize, f64>, max_hops: usize) -> HashMap<usize, (usize, usize, f64)> {
+        let mut best: HashMap<usize, (usize, usize, f64)> = component.iter()
+            .filter_map(|i| Some((*i, (*i, 0, *identified.get(i)?))))
+            .collect();
+
+        for hop in 1..=max_hops {
+            let mut improved = Vec::new();
+            for (&node, &(source, hops, confidence)) in &best {
+                if hops != hop - 1 {
+                    continue;
+                }
+                for edge in self.graph.edges(NodeIndex::new(node)) {
+                    let neighbor = if edge.source().index() == node { edge.target() } else { edge.source() }.index();
+                    let reached = confidence * edge.weight().similarity();
+                    if best.get(&neighbor).is_none_or(|&(_, _, c)| reached > c) {
+                        improved.push((neighbor, (source, hop, reached)));
+                    }
+                }
+            }
+            for (node, candidate) in improved {
+                if best.get(&node).is_none_or(|&(_, _, c)| candidate.2 > c) {
+                    best.insert(node, candidate);
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::spectral::{Peak, Spectrum};
+
+    fn feature(id: &str, name: Option<&str>, confidence: Option<f64>) -> Molecule {
+        feature_molecule(&MsMsSpectrum {
+            id: id.to_string(),
+            precursor_mz: 200.0,
+            spectrum: Spectrum::new(vec![Peak { mz: 100.0, intensity: 1.0 }]),
+            name: name.map(str::to_string),
+            smiles: None,
+            identification_confidence: confidence,
+        })
+    }
+
+    #[test]
+    fn test_families_propagate_identifications() {
+        let mut network = MoleculeNetwork::new();
+        for molecule in [
+            feature("f1", Some("caffeine"), Some(0.95)),
+            feature("f2", None, None),
+            feature("f3", None, None),
+            feature("f4", Some("guess"), Some(0.3)),
+            feature("f5", None, None),
+            feature("f6", None, None),
+        ] {
+            network.add_molecule(&molecule);
+        }
+        network.add_similarity("f1", "f2", 0.9);
+        network.add_similarity("f2", "f3", 0.8);
+        network.add_similarity("f4", "f5", 0.9);
+
+        let families = network.molecular_families(&FamilyOptions::default());
+        assert_eq!(families.len(), 2);
+        assert_eq!(families[0].members, vec!["f1", "f2", "f3"]);
+        assert_eq!(families[0].identified, vec!["f1"]);
+
+        let f3 = &families[0].annotations["f3"];
+        assert_eq!((f3.source_id.as_str(), f3.name.as_str(), f3.hops), ("f1", "caffeine", 2));
+        assert!((f3.confidence - 0.95 * 0.9 * 0.8).abs() < 1e-12);
+
+        // A low-confidence identification annotates nobody
+        assert!(families[1].identified.is_empty() && families[1].annotations.is_empty());
+        assert_eq!(network.get_molecule("f2").unwrap().properties[FAMILY_PROPERTY], 0);
+        assert!(!network.get_molecule("f6").unwrap().properties.contains_key(FAMILY_PROPERTY));
+    }
+
+    #[test]
+    fn test_oversized_families_are_split() {
+        let mut network = MoleculeNetwork::new();
+        for id in ["a", "b", "c", "d"] {
+            network.add_molecule(&feature(id, None, None));
+        }
+        network.add_similarity("a", "b", 0.9);
+        network.add_similarity("b", "c", 0.7);
+        network.add_similarity("c", "d", 0.95);
+
+        let options = FamilyOptions { max_family_size: 2, ..Default::default() };
+        let families = network.molecular_families(&options);
+        assert_eq!(families.len(), 2);
+        assert!(families.iter().all(|f| f.members.len() == 2));
+        assert_eq!(network.to_serializable().edges.len(), 2);
+    }
+}
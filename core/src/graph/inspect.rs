@@ -0,0 +1,334 @@
+//! Molecule Inspection
+//!
+//! Assembles everything the graph store holds about one molecule: its stored
+//! properties, each evidence item with provenance, the current confidence,
+//! the conflicts found at the last integration and the recent confidence
+//! history. `MoleculeInspection::render` lays this out as a terminal report.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::processing::evidence::EvidenceConflict;
+use crate::processing::versioning::{ConfidenceTrend, ConfidenceTrigger};
+
+/// Confidence revisions kept on each molecule
+pub const MAX_CONFIDENCE_HISTORY: usize = 100;
+
+/// Molecule properties that are shown in their own sections rather than as plain properties
+const STRUCTURED_PROPERTIES: [&str; 9] = [
+    "id", "project_id", "confidence", "conflicts", "conflict_details", "integrated_at",
+    "confidence_history", "confidence_history_at", "confidence_history_trigger",
+];
+
+/// A stored evidence item and where it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InspectedEvidence {
+    /// Evidence ID
+    pub id: String,
+
+    /// Evidence type
+    pub evidence_type: String,
+
+    /// Source the evidence came from
+    pub source: String,
+
+    /// Confidence of the item (0.0 - 1.0)
+    pub confidence: f64,
+
+    /// When the evidence was recorded
+    pub recorded_at: Option<DateTime<Utc>>,
+
+    /// Hash of the payload if it was redacted under a retention policy
+    pub payload_sha256: Option<String>,
+
+    /// Who entered the evidence, for manually entered items
+    pub entered_by: Option<String>,
+}
+
+/// Confidence of a molecule at one integration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidencePoint {
+    /// When the integration happened
+    pub recorded_at: DateTime<Utc>,
+
+    /// Aggregate confidence (0.0 - 1.0)
+    pub confidence: f64,
+
+    /// What caused the integration, if it was recorded
+    pub trigger: Option<ConfidenceTrigger>,
+}
+
+/// One line of a project's molecule listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoleculeSummary {
+    /// Molecule ID
+    pub molecule_id: String,
+
+    /// Common name, if one is stored
+    pub name: Option<String>,
+
+    /// Current aggregate confidence, if the molecule has been integrated
+    pub confidence: Option<f64>,
+
+    /// Number of attached evidence items
+    pub evidence_count: usize,
+}
+
+/// Everything stored about a molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoleculeInspection {
+    /// Project the molecule belongs to
+    pub project_id: String,
+
+    /// Molecule ID
+    pub molecule_id: String,
+
+    /// Current aggregate confidence, if the molecule has been integrated
+    pub confidence: Option<f64>,
+
+    /// When the molecule was last integrated
+    pub integrated_at: Option<DateTime<Utc>>,
+
+    /// Other stored properties (name, identifiers, ...)
+    pub properties: Map<String, Value>,
+
+    /// Attached evidence, most confident first
+    pub evidence: Vec<InspectedEvidence>,
+
+    /// Conflicts found at the last integration
+    pub conflicts: Vec<EvidenceConflict>,
+
+    /// Recent confidence history, oldest first
+    pub history: Vec<ConfidencePoint>,
+
+    /// Direction the confidence has been moving in, once it has been integrated twice
+    pub trend: Option<ConfidenceTrend>,
+}
+
+impl MoleculeInspection {
+    /// Assemble an inspection from stored molecule and evidence properties,
+    /// keeping the last `history_limit` confidence revisions
+    pub fn from_properties(project_id: &str, molecule: Value, evidence: Vec<Value>, history_limit: usize) -> Result<Self> {
+        let mut properties = match molecule {
+            Value::Object(properties) => properties,
+            _ => return Err(anyhow!("Stored molecule is not an object")),
+        };
+        let molecule_id = properties.get("id").and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Stored molecule has no ID"))?
+            .to_string();
+
+        let mut history = stored_history(|key| properties.get(key));
+        let confidences: Vec<f64> = history.iter().map(|point| point.confidence).collect();
+        let trend = ConfidenceTrend::from_series(&confidences);
+        history.drain(..history.len().saturating_sub(history_limit));
+
+        // Conflict details are stored as a JSON string, like evidence payloads
+        let conflicts = properties.get("conflict_details")
+            .and_then(Value::as_str)
+            .and_then(|details| serde_json::from_str(details).ok())
+            .unwrap_or_default();
+
+        let mut evidence: Vec<InspectedEvidence> = evidence.iter()
+            .filter_map(|e| {
+                Some(InspectedEvidence {
+                    id: e.get("id")?.as_str()?.to_string(),
+                    evidence_type: e.get("type").and_then(Value::as_str).unwrap_or("other").to_string(),
+                    source: e.get("source").and_then(Value::as_str).unwrap_or("unknown").to_string(),
+                    confidence: e.get("confidence").and_then(Value::as_f64).unwrap_or(0.0),
+                    recorded_at: e.get("timestamp").and_then(timestamp),
+                    payload_sha256: e.get("payload_sha256").and_then(Value::as_str).map(str::to_string),
+                    entered_by: e.get("metadata")
+                        .and_then(Value::as_str)
+                        .and_then(|m| serde_json::from_str::<Value>(m).ok())
+                        .and_then(|m| m.get("entered_by").and_then(Value::as_str).map(str::to_string)),
+                })
+            })
+            .collect();
+        evidence.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then_with(|| a.id.cmp(&b.id)));
+
+        let confidence = properties.get("confidence").and_then(Value::as_f64);
+        let integrated_at = properties.get("integrated_at").and_then(timestamp);
+        properties.retain(|key, _| !STRUCTURED_PROPERTIES.contains(&key.as_str()));
+
+        Ok(Self {
+            project_id: project_id.to_string(),
+            molecule_id,
+            confidence,
+            integrated_at,
+            properties,
+            evidence,
+            conflicts,
+            history,
+            trend,
+        })
+    }
+
+    /// Readable multi-line report
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Molecule {} (project {})", self.molecule_id, self.project_id);
+        match (self.confidence, self.integrated_at) {
+            (Some(confidence), Some(at)) => {
+                let _ = writeln!(out, "  Confidence: {:.1}% (integrated {})", confidence * 100.0, at.format("%Y-%m-%d %H:%M UTC"));
+            }
+            (Some(confidence), None) => {
+                let _ = writeln!(out, "  Confidence: {:.1}%", confidence * 100.0);
+            }
+            _ => {
+                let _ = writeln!(out, "  Confidence: not yet integrated");
+            }
+        }
+        for (key, value) in &self.properties {
+            let _ = writeln!(out, "  {}: {}", key, value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()));
+        }
+
+        let _ = writeln!(out, "\nEvidence ({}):", self.evidence.len());
+        for item in &self.evidence {
+            let recorded = item.recorded_at.map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "-".to_string());
+            let redacted = if item.payload_sha256.is_some() { "  [payload redacted]" } else { "" };
+            let entered_by = item.entered_by.as_ref().map(|user| format!("  (entered by {})", user)).unwrap_or_default();
+            let _ = writeln!(out, "  {:<24} {:<12} {:<20} {:>6.1}%  {}{}{}",
+                             item.id, item.evidence_type, item.source, item.confidence * 100.0, recorded, redacted, entered_by);
+        }
+
+        if !self.conflicts.is_empty() {
+            let _ = writeln!(out, "\nConflicts ({}):", self.conflicts.len());
+            for conflict in &self.conflicts {
+                let _ = writeln!(out, "  - {} (severity {:.2}; {})",
+                                 conflict.description, conflict.severity, conflict.evidence_ids.join(", "));
+            }
+        }
+
+        if !self.history.is_empty() {
+            match self.trend {
+                Some(trend) => {
+                    let _ = writeln!(out, "\nConfidence history ({}):", trend);
+                }
+                None => {
+                    let _ = writeln!(out, "\nConfidence history:");
+                }
+            }
+            let mut previous: Option<f64> = None;
+            for point in &self.history {
+                let change = previous.map(|p| format!(" ({:+.1})", (point.confidence - p) * 100.0)).unwrap_or_default();
+                let trigger = point.trigger.map(|t| format!("  [{}]", t)).unwrap_or_default();
+                let _ = writeln!(out, "  {}  {:>6.1}%{}{}",
+                                 point.recorded_at.format("%Y-%m-%d %H:%M"), point.confidence * 100.0, change, trigger);
+                previous = Some(point.confidence);
+            }
+        }
+        out
+    }
+}
+
+/// Confidence history kept in a molecule's `confidence_history*` properties, oldest first
+///
+/// `property` looks up a stored property by name.
+pub fn stored_history<'a>(property: impl Fn(&str) -> Option<&'a Value>) -> Vec<ConfidencePoint> {
+    let array = |key: &str| property(key).and_then(Value::as_array).into_iter().flatten();
+    let confidences: Vec<f64> = array("confidence_history").filter_map(Value::as_f64).collect();
+    let times: Vec<Option<DateTime<Utc>>> = array("confidence_history_at").map(timestamp).collect();
+    // Triggers were recorded later than the other lists, so they line up from the end
+    let mut triggers: Vec<Option<ConfidenceTrigger>> = array("confidence_history_trigger")
+        .map(|t| t.as_str().and_then(|t| t.parse().ok()))
+        .collect();
+    triggers.splice(0..0, std::iter::repeat_n(None, times.len().saturating_sub(triggers.len())));
+    confidences.into_iter().zip(times).zip(triggers)
+        .filter_map(|((confidence, recorded_at), trigger)| {
+            Some(ConfidencePoint { recorded_at: recorded_at?, confidence, trigger })
+        })
+        .collect()
+}
+
+/// Append an integration to a molecule's `confidence_history*` properties, keeping the last `MAX_CONFIDENCE_HISTORY`
+///
+/// An integration at the time of the last recorded one is already in the
+/// history, so appending it again changes nothing.
+pub fn append_history(properties: &mut HashMap<String, Value>, confidence: f64, at: DateTime<Utc>, trigger: ConfidenceTrigger) {
+    let at = at.to_rfc3339();
+    let seen = properties.get("confidence_history_at")
+        .and_then(Value::as_array)
+        .and_then(|times| times.last())
+        .is_some_and(|last| last.as_str() == Some(at.as_str()));
+    if seen {
+        return;
+    }
+    for (key, value) in [
+        ("confidence_history", serde_json::json!(confidence)),
+        ("confidence_history_at", serde_json::json!(at)),
+        ("confidence_history_trigger", serde_json::json!(trigger.to_string())),
+    ] {
+        let mut values = properties.remove(key)
+            .and_then(|v| match v {
+                Value::Array(values) => Some(values),
+                _ => None,
+            })
+            .unwrap_or_default();
+        values.push(value);
+        values.drain(..values.len().saturating_sub(MAX_CONFIDENCE_HISTORY));
+        properties.insert(key.to_string(), Value::Array(values));
+    }
+}
+
+/// Parse an RFC 3339 timestamp property
+fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    value.as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_inspection_from_stored_properties() {
+        let conflicts = json!([{
+            "description": "Spectral match contradicts retention time",
+            "evidence_ids": ["ms1", "rt1"],
+            "severity": 0.6,
+            "resolution_suggestions": []
+        }]);
+        let molecule = json!({
+            "id": "glucose",
+            "project_id": "default",
+            "name": "D-glucose",
+            "confidence": 0.82,
+            "integrated_at": "2026-03-02T10:00:00Z",
+            "conflicts": 1,
+            "conflict_details": conflicts.to_string(),
+            "confidence_history": [0.6, 0.75, 0.82],
+            "confidence_history_at": ["2026-03-01T10:00:00Z", "2026-03-01T18:00:00Z", "2026-03-02T10:00:00Z"],
+            "confidence_history_trigger": ["manual_entry", "stream"],
+        });
+        let evidence = vec![
+            json!({"id": "rt1", "type": "other", "source": "lab", "confidence": 0.4, "timestamp": "2026-03-01T09:00:00Z"}),
+            json!({"id": "ms1", "type": "mass_spec", "source": "qtof", "confidence": 0.9, "payload_sha256": "ab12"}),
+            json!({"id": "nmr1", "type": "structural", "source": "manual-entry", "confidence": 0.5,
+                   "metadata": json!({"entered_by": "ada"}).to_string()}),
+        ];
+
+        let inspection = MoleculeInspection::from_properties("default", molecule, evidence, 2).unwrap();
+        assert_eq!(inspection.confidence, Some(0.82));
+        assert_eq!(inspection.evidence[0].id, "ms1");
+        assert_eq!(inspection.conflicts.len(), 1);
+        assert_eq!(inspection.history.len(), 2);
+        assert_eq!(inspection.history[0].confidence, 0.75);
+        assert_eq!(inspection.history[0].trigger, Some(ConfidenceTrigger::ManualEntry));
+        assert_eq!(inspection.trend, Some(ConfidenceTrend::Rising));
+        assert_eq!(inspection.properties.keys().collect::<Vec<_>>(), vec!["name"]);
+
+        let report = inspection.render();
+        assert!(report.contains("Confidence: 82.0%"));
+        assert!(report.contains("[payload redacted]"));
+        assert!(report.contains("(+7.0)  [stream]"));
+        assert!(report.contains("Confidence history (rising)"));
+        assert!(report.contains("(entered by ada)"));
+        assert!(MoleculeInspection::from_properties("default", json!({"name": "x"}), vec![], 10).is_err());
+    }
+}
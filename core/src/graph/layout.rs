@@ -0,0 +1,591 @@
+//! Network Layout
+//!
+//! Computes 2D coordinates for the molecules of a network on the server, so
+//! large networks need not be laid out in the browser. Two algorithms are
+//! available:
+//!
+//! - force-directed (Fruchterman-Reingold): similarity edges pull molecules
+//!   together while all molecules repel each other; the repulsion is
+//!   approximated with a Barnes-Hut quadtree, so an iteration costs
+//!   O(n log n) rather than O(n²).
+//! - stress majorization: places molecules so that their distances match
+//!   their shortest-path distances through the network. It converges in far
+//!   fewer iterations, but keeps all pairwise distances in memory, so it is
+//!   limited to `STRESS_MAX_NODES` molecules.
+//!
+//! Both start from seeded random positions and visit molecules in a fixed
+//! order, so the same network, options and seed always give the same layout.
+//! Positions are centred on the origin, scaled to fit a square of the
+//! configured size, and stored on the nodes so they are serialized with them.
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use petgraph::visit::EdgeRef;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::MoleculeNetwork;
+use crate::rng;
+
+/// Network metadata key recording the algorithm and seed of the stored layout
+pub const LAYOUT_METADATA_KEY: &str = "layout";
+
+/// Largest network stress majorization will lay out
+pub const STRESS_MAX_NODES: usize = 10_000;
+
+/// Deepest quadtree level; molecules closer than this share a cell
+const MAX_QUADTREE_DEPTH: usize = 24;
+
+/// Pull of the force-directed layout towards the origin, keeping components together
+const GRAVITY: f64 = 0.05;
+
+/// Layout algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutAlgorithm {
+    /// Fruchterman-Reingold with Barnes-Hut repulsion
+    ForceDirected,
+
+    /// Stress majorization over shortest-path distances
+    Stress,
+}
+
+impl LayoutAlgorithm {
+    /// Iterations used when the options do not set them
+    pub fn default_iterations(&self) -> usize {
+        match self {
+            LayoutAlgorithm::ForceDirected => 300,
+            LayoutAlgorithm::Stress => 100,
+        }
+    }
+}
+
+impl std::fmt::Display for LayoutAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutAlgorithm::ForceDirected => write!(f, "force-directed"),
+            LayoutAlgorithm::Stress => write!(f, "stress"),
+        }
+    }
+}
+
+impl std::str::FromStr for LayoutAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "force" | "force-directed" | "force_directed" | "fr" => Ok(LayoutAlgorithm::ForceDirected),
+            "stress" | "stress-majorization" | "stress_majorization" => Ok(LayoutAlgorithm::Stress),
+            other => Err(anyhow!("Unknown layout algorithm: {}", other)),
+        }
+    }
+}
+
+/// Parameters of a layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutOptions {
+    /// Algorithm to use
+    pub algorithm: LayoutAlgorithm,
+
+    /// Iterations to run; the algorithm's default if absent
+    pub iterations: Option<usize>,
+
+    /// Barnes-Hut opening angle; larger values are faster and less exact
+    pub theta: f64,
+
+    /// Width and height of the square the layout is scaled to
+    pub size: f64,
+
+    /// RNG seed; the global seed or a random one is used if absent
+    pub seed: Option<u64>,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: LayoutAlgorithm::ForceDirected,
+            iterations: None,
+            theta: 0.8,
+            size: 1000.0,
+            seed: None,
+        }
+    }
+}
+
+impl LayoutOptions {
+    /// Set the layout algorithm
+    pub fn with_algorithm(mut self, algorithm: LayoutAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set the number of iterations
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = Some(iterations);
+        self
+    }
+
+    /// Seed the random starting positions
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Check the options describe a layout that can be run
+    pub fn validate(&self) -> Result<()> {
+        if self.iterations == Some(0) {
+            return Err(anyhow!("Layout iterations must be positive"));
+        }
+        if !(self.theta >= 0.0 && self.theta.is_finite()) {
+            return Err(anyhow!("Barnes-Hut theta must be a non-negative number"));
+        }
+        if !(self.size > 0.0 && self.size.is_finite()) {
+            return Err(anyhow!("Layout size must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Coordinates of a molecule in a layout
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    /// Horizontal coordinate
+    pub x: f64,
+
+    /// Vertical coordinate
+    pub y: f64,
+}
+
+impl Position {
+    fn distance(&self, other: &Position) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// Computed positions of every molecule in a network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkLayout {
+    /// Algorithm that produced the layout
+    pub algorithm: LayoutAlgorithm,
+
+    /// Seed the starting positions used
+    pub seed: u64,
+
+    /// Position of each molecule by ID
+    pub positions: HashMap<String, Position>,
+}
+
+impl MoleculeNetwork {
+    /// Compute a 2D layout of the network
+    pub fn layout(&self, options: &LayoutOptions) -> Result<NetworkLayout> {
+        options.validate()?;
+        let count = self.graph.node_count();
+        if options.algorithm == LayoutAlgorithm::Stress && count > STRESS_MAX_NODES {
+            return Err(anyhow!(
+                "Stress layout is limited to {} molecules, the network has {}; use the force-directed layout",
+                STRESS_MAX_NODES, count
+            ));
+        }
+
+        let seed = rng::resolve_seed(options.seed);
+        let mut rng = rng::stream_rng(seed, "layout");
+        let iterations = options.iterations.unwrap_or_else(|| options.algorithm.default_iterations());
+
+        // Similarity edges by node index, without self-loops
+        let edges: Vec<(usize, usize, f64)> = self.graph.edge_references()
+            .filter(|edge| edge.source() != edge.target())
+            .map(|edge| (edge.source().index(), edge.target().index(), edge.weight().similarity().clamp(0.0, 1.0)))
+            .collect();
+
+        let mut positions: Vec<Position> = (0..count)
+            .map(|_| Position {
+                x: (rng.gen::<f64>() - 0.5) * options.size,
+                y: (rng.gen::<f64>() - 0.5) * options.size,
+            })
+            .collect();
+        match options.algorithm {
+            LayoutAlgorithm::ForceDirected => force_directed(&mut positions, &edges, iterations, options),
+            LayoutAlgorithm::Stress => stress_majorization(&mut positions, &edges, iterations),
+        }
+        fit(&mut positions, options.size);
+        debug!("Laid out {} molecules with {} ({} iterations, seed {})", count, options.algorithm, iterations, seed);
+
+        Ok(NetworkLayout {
+            algorithm: options.algorithm,
+            seed,
+            positions: self.graph.node_indices()
+                .map(|idx| (self.graph[idx].id.clone(), positions[idx.index()]))
+                .collect(),
+        })
+    }
+
+    /// Store a layout's positions on the molecules
+    ///
+    /// Returns the number of molecules that received a position.
+    pub fn store_layout(&mut self, layout: &NetworkLayout) -> usize {
+        let mut stored = 0;
+        for node in self.graph.node_weights_mut() {
+            node.position = layout.positions.get(&node.id).copied();
+            if node.position.is_some() {
+                stored += 1;
+            }
+        }
+        self.metadata.insert(LAYOUT_METADATA_KEY.to_string(), serde_json::json!({
+            "algorithm": layout.algorithm,
+            "seed": layout.seed,
+        }));
+        stored
+    }
+}
+
+/// Fruchterman-Reingold with linear cooling; stronger similarity pulls harder
+fn force_directed(positions: &mut [Position], edges: &[(usize, usize, f64)], iterations: usize, options: &LayoutOptions) {
+    let count = positions.len();
+    if count < 2 {
+        return;
+    }
+    let k = options.size / (count as f64).sqrt();
+    let initial_temperature = options.size / 10.0;
+    let mut displacement = vec![(0.0, 0.0); count];
+
+    for iteration in 0..iterations {
+        let tree = QuadTree::build(positions);
+        for (i, position) in positions.iter().enumerate() {
+            let (fx, fy) = tree.repulsion(0, i, position, options.theta, k * k);
+            displacement[i] = (fx - position.x * GRAVITY, fy - position.y * GRAVITY);
+        }
+        for &(a, b, similarity) in edges {
+            let dx = positions[a].x - positions[b].x;
+            let dy = positions[a].y - positions[b].y;
+            let distance = dx.hypot(dy);
+            if distance < f64::EPSILON {
+                continue;
+            }
+            let force = distance * distance / k * similarity.max(0.05);
+            let (fx, fy) = (dx / distance * force, dy / distance * force);
+            displacement[a].0 -= fx;
+            displacement[a].1 -= fy;
+            displacement[b].0 += fx;
+            displacement[b].1 += fy;
+        }
+
+        let temperature = initial_temperature * (1.0 - iteration as f64 / iterations as f64);
+        for (position, (dx, dy)) in positions.iter_mut().zip(&displacement) {
+            let length = dx.hypot(*dy);
+            if length > f64::EPSILON {
+                let step = length.min(temperature);
+                position.x += dx / length * step;
+                position.y += dy / length * step;
+            }
+        }
+    }
+}
+
+/// Barnes-Hut quadtree over molecule positions, stored as an arena
+struct QuadTree {
+    cells: Vec<Cell>,
+}
+
+/// Square cell of a quadtree
+struct Cell {
+    /// Centre of the square
+    centre: Position,
+
+    /// Half the width of the square
+    half: f64,
+
+    /// Number of molecules in the cell
+    mass: f64,
+
+    /// Sum of their coordinates, for the centre of mass
+    sum_x: f64,
+    sum_y: f64,
+
+    /// Molecule held by a leaf with a single molecule
+    body: Option<usize>,
+
+    /// Indices of the four quadrants of a split cell
+    children: Option<[usize; 4]>,
+}
+
+impl Cell {
+    fn new(centre: Position, half: f64) -> Self {
+        Self { centre, half, mass: 0.0, sum_x: 0.0, sum_y: 0.0, body: None, children: None }
+    }
+}
+
+impl QuadTree {
+    fn build(positions: &[Position]) -> Self {
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for p in positions {
+            min_x = min_x.min(p.x);
+            min_y = min_y.min(p.y);
+            max_x = max_x.max(p.x);
+            max_y = max_y.max(p.y);
+        }
+        let half = ((max_x - min_x).max(max_y - min_y) / 2.0).max(1e-6) * 1.0001;
+        let centre = Position { x: (min_x + max_x) / 2.0, y: (min_y + max_y) / 2.0 };
+
+        let mut tree = Self { cells: vec![Cell::new(centre, half)] };
+        for (i, position) in positions.iter().enumerate() {
+            tree.insert(0, i, position, positions, 0);
+        }
+        tree
+    }
+
+    fn insert(&mut self, cell: usize, body: usize, position: &Position, positions: &[Position], depth: usize) {
+        let current = &mut self.cells[cell];
+        current.mass += 1.0;
+        current.sum_x += position.x;
+        current.sum_y += position.y;
+
+        if current.children.is_none() {
+            if current.mass == 1.0 {
+                current.body = Some(body);
+                return;
+            }
+            if depth >= MAX_QUADTREE_DEPTH {
+                // Coincident molecules share the leaf as one aggregate
+                return;
+            }
+            let resident = current.body.take();
+            self.split(cell);
+            if let Some(resident) = resident {
+                let child = self.quadrant(cell, &positions[resident]);
+                self.insert(child, resident, &positions[resident], positions, depth + 1);
+            }
+        }
+        let child = self.quadrant(cell, position);
+        self.insert(child, body, position, positions, depth + 1);
+    }
+
+    fn split(&mut self, cell: usize) {
+        let (centre, half) = (self.cells[cell].centre, self.cells[cell].half / 2.0);
+        let first = self.cells.len();
+        for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            self.cells.push(Cell::new(Position { x: centre.x + dx * half, y: centre.y + dy * half }, half));
+        }
+        self.cells[cell].children = Some([first, first + 1, first + 2, first + 3]);
+    }
+
+    fn quadrant(&self, cell: usize, position: &Position) -> usize {
+        let current = &self.cells[cell];
+        let children = current.children.expect("quadrant of a leaf cell");
+        let east = position.x >= current.centre.x;
+        let south = position.y >= current.centre.y;
+        children[(east as usize) + 2 * (south as usize)]
+    }
+
+    /// Repulsion on molecule `body` at `position` from everything in `cell`
+    fn repulsion(&self, cell: usize, body: usize, position: &Position, theta: f64, k2: f64) -> (f64, f64) {
+        let current = &self.cells[cell];
+        if current.mass == 0.0 || (current.body == Some(body) && current.mass == 1.0) {
+            return (0.0, 0.0);
+        }
+        let dx = position.x - current.sum_x / current.mass;
+        let dy = position.y - current.sum_y / current.mass;
+        let distance = dx.hypot(dy);
+
+        match current.children {
+            Some(children) if 2.0 * current.half >= theta * distance => children.iter()
+                .map(|child| self.repulsion(*child, body, position, theta, k2))
+                .fold((0.0, 0.0), |(x, y), (fx, fy)| (x + fx, y + fy)),
+            _ if distance < f64::EPSILON => (0.0, 0.0),
+            _ => {
+                let force = k2 * current.mass / distance;
+                (dx / distance * force, dy / distance * force)
+            }
+        }
+    }
+}
+
+/// Shortest-path distances between all molecules; edges cost 2 - similarity
+///
+/// Pairs in different components are placed one step beyond the longest distance.
+fn graph_distances(count: usize, edges: &[(usize, usize, f64)]) -> Vec<f32> {
+    let mut neighbours: Vec<Vec<(usize, f64)>> = vec![Vec::new(); count];
+    for &(a, b, similarity) in edges {
+        neighbours[a].push((b, 2.0 - similarity));
+        neighbours[b].push((a, 2.0 - similarity));
+    }
+
+    let mut distances = vec![f32::INFINITY; count * count];
+    let mut longest: f32 = 1.0;
+    let mut best = vec![f64::INFINITY; count];
+    for source in 0..count {
+        best.iter_mut().for_each(|d| *d = f64::INFINITY);
+        best[source] = 0.0;
+        let mut queue = BinaryHeap::from([Visit { cost: 0.0, node: source }]);
+        while let Some(Visit { cost, node }) = queue.pop() {
+            if cost > best[node] {
+                continue;
+            }
+            for &(next, length) in &neighbours[node] {
+                let candidate = cost + length;
+                if candidate < best[next] {
+                    best[next] = candidate;
+                    queue.push(Visit { cost: candidate, node: next });
+                }
+            }
+        }
+        for (target, distance) in best.iter().enumerate() {
+            if distance.is_finite() {
+                distances[source * count + target] = *distance as f32;
+                longest = longest.max(*distance as f32);
+            }
+        }
+    }
+    for distance in distances.iter_mut().filter(|d| d.is_infinite()) {
+        *distance = longest + 1.0;
+    }
+    distances
+}
+
+/// Entry of the Dijkstra queue, ordered cheapest first
+struct Visit {
+    cost: f64,
+    node: usize,
+}
+
+impl PartialEq for Visit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Visit {}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+/// Localized stress majorization (Gansner, Koren and North), weighting pairs by 1/d²
+fn stress_majorization(positions: &mut [Position], edges: &[(usize, usize, f64)], iterations: usize) {
+    let count = positions.len();
+    if count < 2 {
+        return;
+    }
+    let distances = graph_distances(count, edges);
+
+    // Start from the random positions rescaled to graph units
+    let longest = distances.iter().copied().fold(0.0f32, f32::max) as f64;
+    let spread = positions.iter().map(|p| p.x.abs().max(p.y.abs())).fold(f64::EPSILON, f64::max);
+    for position in positions.iter_mut() {
+        position.x *= longest / spread;
+        position.y *= longest / spread;
+    }
+
+    for _ in 0..iterations {
+        let mut largest_move: f64 = 0.0;
+        for i in 0..count {
+            let (mut x, mut y, mut total_weight) = (0.0, 0.0, 0.0);
+            for j in 0..count {
+                if i == j {
+                    continue;
+                }
+                let target = distances[i * count + j] as f64;
+                let weight = 1.0 / (target * target);
+                let current = positions[i].distance(&positions[j]).max(1e-9);
+                x += weight * (positions[j].x + target * (positions[i].x - positions[j].x) / current);
+                y += weight * (positions[j].y + target * (positions[i].y - positions[j].y) / current);
+                total_weight += weight;
+            }
+            let moved = Position { x: x / total_weight, y: y / total_weight };
+            largest_move = largest_move.max(moved.distance(&positions[i]));
+            positions[i] = moved;
+        }
+        if largest_move < 1e-4 * longest {
+            break;
+        }
+    }
+}
+
+/// Centre positions on the origin and scale them to fit a square of `size`
+fn fit(positions: &mut [Position], size: f64) {
+    if positions.is_empty() {
+        return;
+    }
+    let count = positions.len() as f64;
+    let centre_x = positions.iter().map(|p| p.x).sum::<f64>() / count;
+    let centre_y = positions.iter().map(|p| p.y).sum::<f64>() / count;
+    let extent = positions.iter()
+        .map(|p| (p.x - centre_x).abs().max((p.y - centre_y).abs()))
+        .fold(0.0, f64::max);
+    let scale = if extent > f64::EPSILON { size / 2.0 / extent } else { 0.0 };
+    for position in positions.iter_mut() {
+        position.x = (position.x - centre_x) * scale;
+        position.y = (position.y - centre_y) * scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::Molecule;
+
+    /// Two five-molecule cliques joined by a single weak edge
+    fn two_communities() -> MoleculeNetwork {
+        let mut network = MoleculeNetwork::new();
+        for community in ["a", "b"] {
+            for i in 0..5 {
+                let mut molecule = Molecule::from_smiles("C").unwrap();
+                molecule.id = format!("{}{}", community, i);
+                network.add_molecule(&molecule);
+            }
+            for i in 0..5 {
+                for j in (i + 1)..5 {
+                    network.add_similarity(&format!("{}{}", community, i), &format!("{}{}", community, j), 0.9);
+                }
+            }
+        }
+        network.add_similarity("a0", "b0", 0.2);
+        network
+    }
+
+    fn mean_distance(layout: &NetworkLayout, pairs: &[(String, String)]) -> f64 {
+        pairs.iter()
+            .map(|(a, b)| layout.positions[a].distance(&layout.positions[b]))
+            .sum::<f64>() / pairs.len() as f64
+    }
+
+    #[test]
+    fn test_layouts_separate_communities() {
+        let network = two_communities();
+        let within: Vec<(String, String)> = (1..5).map(|i| ("a0".to_string(), format!("a{}", i))).collect();
+        let across: Vec<(String, String)> = (1..5).map(|i| ("a1".to_string(), format!("b{}", i))).collect();
+
+        for algorithm in [LayoutAlgorithm::ForceDirected, LayoutAlgorithm::Stress] {
+            let options = LayoutOptions::default().with_algorithm(algorithm).with_seed(11);
+            let layout = network.layout(&options).unwrap();
+            assert_eq!(layout.positions.len(), 10);
+            assert!(layout.positions.values().all(|p| p.x.abs() <= 500.0 + 1e-9 && p.y.abs() <= 500.0 + 1e-9));
+            assert!(mean_distance(&layout, &within) < mean_distance(&layout, &across), "{}", algorithm);
+
+            assert_eq!(network.layout(&options).unwrap().positions, layout.positions);
+            assert_ne!(network.layout(&options.clone().with_seed(12)).unwrap().positions, layout.positions);
+        }
+    }
+
+    #[test]
+    fn test_stored_layout_is_serialized() {
+        let mut network = two_communities();
+        let layout = network.layout(&LayoutOptions::default().with_iterations(20).with_seed(3)).unwrap();
+        assert_eq!(network.store_layout(&layout), 10);
+
+        let serialized = network.to_serializable();
+        assert_eq!(serialized.metadata[LAYOUT_METADATA_KEY]["algorithm"], "force_directed");
+        let restored = MoleculeNetwork::from_serializable(&serialized).unwrap();
+        assert_eq!(restored.get_molecule("b3").unwrap().position, layout.positions.get("b3").copied());
+        assert!("sideways".parse::<LayoutAlgorithm>().is_err());
+    }
+}
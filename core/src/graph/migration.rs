@@ -0,0 +1,201 @@
+//! Molecule ID Migration
+//!
+//! Molecule IDs were once hashes of the SMILES string, so one compound drawn
+//! two ways got two IDs. IDs are now the canonical InChIKey, or a structure
+//! hash when no InChIKey is known. Migrating a network re-derives the ID of
+//! every node, merges nodes that turn out to be the same compound and keeps
+//! each former ID as an alias, so stored references to it still resolve.
+
+use log::{debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+
+use super::{EdgeWeight, MoleculeNetwork};
+
+/// Metadata key naming the ID scheme a network's molecule IDs follow
+pub const ID_SCHEME_KEY: &str = "id_scheme";
+
+/// Current ID scheme: InChIKey, falling back to a SHA-256 structure hash
+pub const ID_SCHEME: &str = "inchikey-v1";
+
+/// Outcome of migrating a network to the current ID scheme
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdMigration {
+    /// New ID of every molecule whose ID changed, by former ID
+    pub renamed: BTreeMap<String, String>,
+
+    /// Nodes folded into another node with the same new ID
+    pub nodes_merged: usize,
+
+    /// Edges dropped because their molecules were merged, or folded into a parallel edge
+    pub edges_merged: usize,
+}
+
+impl IdMigration {
+    /// Whether the network already followed the current scheme
+    pub fn is_empty(&self) -> bool {
+        self.renamed.is_empty() && self.nodes_merged == 0 && self.edges_merged == 0
+    }
+}
+
+impl MoleculeNetwork {
+    /// Whether the network's molecule IDs follow the current ID scheme
+    pub fn uses_current_ids(&self) -> bool {
+        self.metadata.get(ID_SCHEME_KEY).and_then(|scheme| scheme.as_str()) == Some(ID_SCHEME)
+    }
+
+    /// Re-derive every molecule ID under the current ID scheme
+    ///
+    /// Nodes that end up with the same ID are merged: the first keeps its
+    /// name, SMILES and properties and gains the other's external IDs and
+    /// aliases. Edges between merged molecules keep the strongest weight.
+    pub fn migrate_ids(&mut self) -> IdMigration {
+        let mut report = IdMigration::default();
+        let mut migrated = MoleculeNetwork::new();
+        migrated.metadata.extend(self.metadata.clone());
+        migrated.metadata.insert(ID_SCHEME_KEY.to_string(), serde_json::json!(ID_SCHEME));
+
+        let mut new_ids = Vec::with_capacity(self.graph.node_count());
+        for node in self.graph.node_weights() {
+            let id = node.to_molecule().canonical_id();
+            new_ids.push(id.clone());
+
+            let mut node = node.clone();
+            if id != node.id {
+                report.renamed.insert(node.id.clone(), id.clone());
+                node.aliases.push(std::mem::replace(&mut node.id, id.clone()));
+            }
+
+            let existing = match migrated.id_to_node.get(&id) {
+                Some(&existing) => existing,
+                None => {
+                    migrated.insert_node(node);
+                    continue;
+                }
+            };
+            debug!("Merging molecule {} into {}", node.aliases.last().unwrap_or(&id), id);
+            for alias in node.aliases.iter().chain(node.external_curies().iter()) {
+                migrated.aliases.entry(alias.clone()).or_insert_with(|| id.clone());
+            }
+            let target = &mut migrated.graph[existing];
+            for alias in node.aliases {
+                if !target.aliases.contains(&alias) {
+                    target.aliases.push(alias);
+                }
+            }
+            for (system, value) in node.external_ids {
+                target.external_ids.entry(system).or_insert(value);
+            }
+            for (key, value) in node.properties {
+                target.properties.entry(key).or_insert(value);
+            }
+            report.nodes_merged += 1;
+        }
+
+        let mut edges: BTreeMap<(String, String), EdgeWeight> = BTreeMap::new();
+        for edge in self.graph.edge_indices() {
+            let ((a, b), weight) = match (self.graph.edge_endpoints(edge), self.graph.edge_weight(edge)) {
+                (Some(ends), Some(weight)) => (ends, weight),
+                _ => continue,
+            };
+            let (a, b) = (&new_ids[a.index()], &new_ids[b.index()]);
+            if a == b {
+                report.edges_merged += 1;
+                continue;
+            }
+            let key = if a <= b { (a.clone(), b.clone()) } else { (b.clone(), a.clone()) };
+            if let Some(existing) = edges.get(&key) {
+                report.edges_merged += 1;
+                if existing.similarity() >= weight.similarity() {
+                    continue;
+                }
+            }
+            edges.insert(key, weight.clone());
+        }
+        for ((a, b), weight) in edges {
+            let (a, b) = (migrated.id_to_node[&a], migrated.id_to_node[&b]);
+            migrated.graph.add_edge(a, b, weight);
+        }
+
+        if report.nodes_merged > 0 {
+            warn!("Merged {} molecules that share an ID under the {} scheme", report.nodes_merged, ID_SCHEME);
+        }
+        debug!("Migrated network IDs: {} renamed, {} nodes and {} edges merged",
+               report.renamed.len(), report.nodes_merged, report.edges_merged);
+        *self = migrated;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::MoleculeIdType;
+    use crate::processing::Molecule;
+    use std::collections::HashMap;
+
+    const CAFFEINE: &str = "RYYVLZVUVIJVGH-UHFFFAOYSA-N";
+
+    fn legacy_node(network: &mut MoleculeNetwork, id: &str, smiles: &str, inchi_key: Option<&str>) {
+        network.add_molecule(&Molecule {
+            id: id.to_string(),
+            smiles: smiles.to_string(),
+            inchi: None,
+            inchi_key: inchi_key.map(str::to_string),
+            name: None,
+            formula: None,
+            molecular_weight: None,
+            properties: HashMap::new(),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_canonical_ids() {
+        let drawn = |smiles: &str| {
+            let mut molecule = Molecule::from_smiles(smiles).unwrap();
+            molecule.set_inchi_key(&CAFFEINE.to_lowercase()).unwrap();
+            molecule.id
+        };
+        assert_eq!(drawn("Cn1cnc2c1c(=O)n(C)c(=O)n2C"), drawn("CN1C=NC2=C1C(=O)N(C(=O)N2C)C"));
+
+        let by_key = Molecule::from_identifier(CAFFEINE, &MoleculeIdType::InChIKey).unwrap();
+        assert_eq!(by_key.id, CAFFEINE);
+
+        // Without an InChIKey the ID is a stable structure hash
+        let hashed = Molecule::from_smiles(" CCO ").unwrap();
+        assert_eq!(hashed.id, Molecule::from_smiles("CCO").unwrap().id);
+        assert!(hashed.id.starts_with("mol-") && hashed.id.len() == 20);
+    }
+
+    #[test]
+    fn test_migrate_ids_merges_and_keeps_aliases() {
+        let mut network = MoleculeNetwork::new();
+        network.metadata.remove(ID_SCHEME_KEY);
+        legacy_node(&mut network, "mol-0000000000000001", "Cn1cnc2c1c(=O)n(C)c(=O)n2C", Some(CAFFEINE));
+        legacy_node(&mut network, "mol-0000000000000002", "CN1C=NC2=C1C(=O)N(C(=O)N2C)C", Some(CAFFEINE));
+        legacy_node(&mut network, "mol-0000000000000003", "CCO", None);
+        network.add_similarity("mol-0000000000000001", "mol-0000000000000002", 0.6);
+        network.add_similarity("mol-0000000000000001", "mol-0000000000000003", 0.2);
+        network.add_similarity("mol-0000000000000002", "mol-0000000000000003", 0.3);
+        assert!(!network.uses_current_ids());
+
+        let report = network.migrate_ids();
+        assert!(network.uses_current_ids());
+        assert_eq!(report.renamed.len(), 3);
+        assert_eq!(report.nodes_merged, 1);
+        assert_eq!(report.edges_merged, 2);
+
+        let caffeine = network.get_molecule("mol-0000000000000002").unwrap();
+        assert_eq!(caffeine.id, CAFFEINE);
+        assert_eq!(caffeine.aliases.len(), 2);
+        assert_eq!(network.resolve_id(&format!("inchikey:{}", CAFFEINE)), Some(CAFFEINE));
+
+        let edges = network.to_serializable().edges;
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].weight, 0.3);
+        assert!([&edges[0].source, &edges[0].target].contains(&&report.renamed["mol-0000000000000003"]));
+
+        assert!(network.migrate_ids().is_empty());
+    }
+}
@@ -11,7 +11,35 @@ use serde::{Serialize, Deserialize};
 use std::collections::{HashMap, HashSet};
 
 use crate::processing::Molecule;
+use crate::processing::formula::Formula;
 use crate::HegelError;
+use crate::rng;
+use crate::identity::MoleculeIdType;
+use crate::identity::xref::CrossReferences;
+
+pub mod similarity;
+pub mod conflicts;
+pub mod schema;
+pub mod diff;
+pub mod paths;
+pub mod embeddings;
+pub mod inspect;
+pub mod migration;
+pub mod reconcile;
+pub mod pathways;
+pub mod families;
+pub mod propagation;
+pub mod stats;
+pub mod neo4j;
+pub mod store;
+pub mod postgres;
+pub mod embedded;
+pub mod layout;
+pub mod neighborhood;
+pub mod rdf;
+
+use similarity::{CompositeSimilarity, SimilarityRegistry, DEFAULT_METRIC};
+use pathways::{PathwayCoherence, PathwayMembership};
 
 /// Initialize the graph module
 pub fn initialize() -> Result<()> {
@@ -28,6 +56,12 @@ pub struct MoleculeNetwork {
     
     /// Mapping from molecule IDs to node indices
     id_to_node: HashMap<String, NodeIndex>,
+    
+    /// Mapping from aliases (former IDs, external identifiers) to molecule IDs
+    aliases: HashMap<String, String>,
+    
+    /// Provenance metadata, such as the RNG seed used while building
+    metadata: HashMap<String, serde_json::Value>,
 }
 
 impl MoleculeNetwork {
@@ -36,59 +70,112 @@ impl MoleculeNetwork {
         Self {
             graph: Graph::new_undirected(),
             id_to_node: HashMap::new(),
+            aliases: HashMap::new(),
+            metadata: HashMap::from([
+                (migration::ID_SCHEME_KEY.to_string(), serde_json::json!(migration::ID_SCHEME)),
+            ]),
         }
     }
     
     /// Add a molecule to the network
+    ///
+    /// The molecule's identifiers become external IDs of its node, which it is
+    /// also found by in `type:value` form.
     pub fn add_molecule(&mut self, molecule: &Molecule) -> NodeIndex {
         // Check if the molecule is already in the network
-        if let Some(&node_idx) = self.id_to_node.get(&molecule.id) {
+        if let Some(node_idx) = self.node_index(&molecule.id) {
             return node_idx;
         }
         
-        // Create a new node for the molecule
-        let node = MoleculeNode {
-            id: molecule.id.clone(),
-            smiles: molecule.smiles.clone(),
-            name: molecule.name.clone(),
-            formula: molecule.formula.clone(),
-            properties: molecule.properties.clone(),
-        };
-        
-        // Add the node to the graph
+        self.insert_node(MoleculeNode::from(molecule))
+    }
+    
+    /// Add a node, indexing its ID and aliases
+    fn insert_node(&mut self, node: MoleculeNode) -> NodeIndex {
+        for alias in node.aliases.iter().chain(node.external_curies().iter()) {
+            self.aliases.entry(alias.clone()).or_insert_with(|| node.id.clone());
+        }
+        let id = node.id.clone();
         let node_idx = self.graph.add_node(node);
-        
-        // Add the mapping
-        self.id_to_node.insert(molecule.id.clone(), node_idx);
-        
+        self.id_to_node.insert(id, node_idx);
         node_idx
     }
     
+    /// Node of a molecule, looked up by ID or alias
+    fn node_index(&self, id: &str) -> Option<NodeIndex> {
+        self.id_to_node.get(id)
+            .or_else(|| self.id_to_node.get(self.aliases.get(id)?))
+            .copied()
+    }
+    
+    /// Current ID of a molecule referred to by ID or alias
+    pub fn resolve_id(&self, id: &str) -> Option<&str> {
+        let node_idx = self.node_index(id)?;
+        self.graph.node_weight(node_idx).map(|node| node.id.as_str())
+    }
+    
     /// Add a similarity edge between two molecules
     pub fn add_similarity(&mut self, mol1_id: &str, mol2_id: &str, similarity: f64) -> Option<usize> {
         // Get the node indices for the molecules
-        let node1 = self.id_to_node.get(mol1_id)?;
-        let node2 = self.id_to_node.get(mol2_id)?;
+        let node1 = self.node_index(mol1_id)?;
+        let node2 = self.node_index(mol2_id)?;
         
         // Add an edge between the nodes
         let edge_idx = self.graph.add_edge(
-            *node1,
-            *node2,
+            node1,
+            node2,
             EdgeWeight::Similarity(similarity)
         );
         
         Some(edge_idx.index())
     }
     
+    /// Add a composite similarity edge, keeping the per-metric component scores
+    pub fn add_composite_similarity(
+        &mut self,
+        mol1_id: &str,
+        mol2_id: &str,
+        similarity: f64,
+        components: HashMap<String, f64>,
+    ) -> Option<usize> {
+        let node1 = self.node_index(mol1_id)?;
+        let node2 = self.node_index(mol2_id)?;
+        
+        let edge_idx = self.graph.add_edge(
+            node1,
+            node2,
+            EdgeWeight::Composite { similarity, components }
+        );
+        
+        Some(edge_idx.index())
+    }
+    
     /// Get all molecules in the network
     pub fn get_molecules(&self) -> Vec<&MoleculeNode> {
         self.graph.node_weights().collect()
     }
     
-    /// Get a molecule by ID
+    /// Attach resolved cross-references to a molecule's node, making them aliases of it
+    pub fn set_external_ids(&mut self, id: &str, xrefs: &CrossReferences) -> Result<()> {
+        let node_idx = self.node_index(id)
+            .ok_or_else(|| HegelError::DataError(format!("Molecule not found: {}", id)))?;
+        if let Some(node) = self.graph.node_weight_mut(node_idx) {
+            node.external_ids.extend(xrefs.to_external_ids());
+            for alias in node.external_curies() {
+                self.aliases.entry(alias).or_insert_with(|| node.id.clone());
+            }
+        }
+        Ok(())
+    }
+    
+    /// Get the provenance metadata of the network
+    pub fn metadata(&self) -> &HashMap<String, serde_json::Value> {
+        &self.metadata
+    }
+    
+    /// Get a molecule by ID or alias
     pub fn get_molecule(&self, id: &str) -> Option<&MoleculeNode> {
-        let node_idx = self.id_to_node.get(id)?;
-        self.graph.node_weight(*node_idx)
+        self.graph.node_weight(self.node_index(id)?)
     }
     
     /// Get similar molecules to a given molecule
@@ -96,7 +183,7 @@ impl MoleculeNetwork {
         let mut similar_molecules = Vec::new();
         
         // Get the node index for the molecule
-        if let Some(&node_idx) = self.id_to_node.get(id) {
+        if let Some(node_idx) = self.node_index(id) {
             // Iterate through neighbors
             for edge in self.graph.edges(node_idx) {
                 let neighbor_idx = edge.target();
@@ -107,12 +194,11 @@ impl MoleculeNetwork {
                 }
                 
                 // Check the similarity
-                if let EdgeWeight::Similarity(similarity) = edge.weight() {
-                    if *similarity >= min_similarity {
-                        // Get the neighbor molecule
-                        if let Some(molecule) = self.graph.node_weight(neighbor_idx) {
-                            similar_molecules.push((molecule.clone(), *similarity));
-                        }
+                let similarity = edge.weight().similarity();
+                if similarity >= min_similarity {
+                    // Get the neighbor molecule
+                    if let Some(molecule) = self.graph.node_weight(neighbor_idx) {
+                        similar_molecules.push((molecule.clone(), similarity));
                     }
                 }
             }
@@ -202,7 +288,17 @@ impl MoleculeNetwork {
                                 source: source_mol.id.clone(),
                                 target: target_mol.id.clone(),
                                 weight: *similarity,
-                                edge_type: "similarity".to_string(),
+                                edge_type: weight.type_name().to_string(),
+                                components: None,
+                            });
+                        }
+                        EdgeWeight::Composite { similarity, components } => {
+                            edges.push(SerializableEdge {
+                                source: source_mol.id.clone(),
+                                target: target_mol.id.clone(),
+                                weight: *similarity,
+                                edge_type: weight.type_name().to_string(),
+                                components: Some(components.clone()),
                             });
                         }
                     }
@@ -210,7 +306,33 @@ impl MoleculeNetwork {
             }
         }
         
-        SerializableNetwork { nodes, edges }
+        SerializableNetwork { nodes, edges, metadata: self.metadata.clone() }
+    }
+    
+    /// Rebuild a network from its serializable format
+    pub fn from_serializable(serialized: &SerializableNetwork) -> Result<Self> {
+        let mut network = Self::new();
+        network.metadata = serialized.metadata.clone();
+        
+        for node in &serialized.nodes {
+            if !network.id_to_node.contains_key(&node.id) {
+                network.insert_node(node.clone());
+            }
+        }
+        
+        for edge in &serialized.edges {
+            let added = match &edge.components {
+                Some(components) => network.add_composite_similarity(&edge.source, &edge.target, edge.weight, components.clone()),
+                None => network.add_similarity(&edge.source, &edge.target, edge.weight),
+            };
+            if added.is_none() {
+                return Err(HegelError::DataError(format!(
+                    "Edge {} - {} refers to an unknown molecule", edge.source, edge.target
+                )).into());
+            }
+        }
+        
+        Ok(network)
     }
 }
 
@@ -227,17 +349,111 @@ pub struct MoleculeNode {
     pub name: Option<String>,
     
     /// Optional molecular formula
-    pub formula: Option<String>,
+    pub formula: Option<Formula>,
     
     /// Additional properties and metadata
     pub properties: HashMap<String, serde_json::Value>,
+    
+    /// Identifiers of the molecule in external databases, keyed by identifier type
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub external_ids: HashMap<String, String>,
+    
+    /// Other IDs the molecule is found by, such as former internal IDs and `type:value` identifiers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+
+    /// Coordinates from the last stored layout, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<layout::Position>,
+}
+
+impl MoleculeNode {
+    /// External identifiers in `type:value` form
+    pub fn external_curies(&self) -> Vec<String> {
+        self.external_ids.iter()
+            .map(|(id_type, value)| format!("{}:{}", id_type, value))
+            .collect()
+    }
+    
+    /// Convert the node back into a molecule
+    pub fn to_molecule(&self) -> Molecule {
+        Molecule::from(self)
+    }
+}
+
+impl From<&Molecule> for MoleculeNode {
+    /// Node for a molecule; evidence and confidence stay with the molecule
+    fn from(molecule: &Molecule) -> Self {
+        let mut external_ids = molecule.identifiers.clone();
+        if let Some(key) = &molecule.inchi_key {
+            external_ids.insert(MoleculeIdType::InChIKey.to_string(), key.clone());
+        }
+        if let Some(inchi) = &molecule.inchi {
+            external_ids.insert(MoleculeIdType::InChI.to_string(), inchi.clone());
+        }
+        
+        MoleculeNode {
+            id: molecule.id.clone(),
+            smiles: molecule.smiles.clone(),
+            name: molecule.name.clone(),
+            formula: molecule.formula.clone(),
+            properties: molecule.properties.clone(),
+            external_ids,
+            aliases: Vec::new(),
+            position: None,
+        }
+    }
+}
+
+impl From<&MoleculeNode> for Molecule {
+    fn from(node: &MoleculeNode) -> Self {
+        Molecule {
+            id: node.id.clone(),
+            smiles: node.smiles.clone(),
+            inchi: node.external_ids.get(MoleculeIdType::InChI.as_str()).cloned(),
+            inchi_key: node.external_ids.get(MoleculeIdType::InChIKey.as_str()).cloned(),
+            name: node.name.clone(),
+            molecular_weight: node.formula.as_ref().map(|formula| formula.average_mass()),
+            formula: node.formula.clone(),
+            properties: node.properties.clone(),
+            identifiers: node.external_ids.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 /// Edge weight in a molecular network
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum EdgeWeight {
     /// Similarity between molecules (0.0 - 1.0)
     Similarity(f64),
+    
+    /// Weighted composite similarity with the score of each component metric
+    Composite {
+        /// Combined similarity (0.0 - 1.0)
+        similarity: f64,
+        
+        /// Raw score of each component metric, by metric name
+        components: HashMap<String, f64>,
+    },
+}
+
+impl EdgeWeight {
+    /// Overall similarity carried by the edge
+    pub fn similarity(&self) -> f64 {
+        match self {
+            EdgeWeight::Similarity(similarity) => *similarity,
+            EdgeWeight::Composite { similarity, .. } => *similarity,
+        }
+    }
+    
+    /// Name of the edge type, as used in serialized networks
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            EdgeWeight::Similarity(_) => "similarity",
+            EdgeWeight::Composite { .. } => "composite_similarity",
+        }
+    }
 }
 
 /// Network metrics for a molecular network
@@ -273,6 +489,10 @@ pub struct SerializableNetwork {
     
     /// Edges in the network
     pub edges: Vec<SerializableEdge>,
+    
+    /// Provenance metadata, including the RNG seed
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
 }
 
 /// Serializable edge in a molecular network
@@ -289,6 +509,10 @@ pub struct SerializableEdge {
     
     /// Type of the edge
     pub edge_type: String,
+    
+    /// Per-metric component scores for composite similarity edges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub components: Option<HashMap<String, f64>>,
 }
 
 /// Builder for constructing a molecular network
@@ -301,6 +525,18 @@ pub struct NetworkBuilder {
     
     /// Maximum number of neighbors per molecule
     max_neighbors: usize,
+    
+    /// Name of the similarity metric used to score pairs
+    metric: String,
+    
+    /// Composite metric that takes precedence over `metric` when set
+    composite: Option<CompositeSimilarity>,
+    
+    /// Seed for pair sampling (falls back to the global seed)
+    seed: Option<u64>,
+    
+    /// Score only a random sample of this many pairs instead of all pairs
+    max_pairs: Option<usize>,
 }
 
 impl NetworkBuilder {
@@ -310,9 +546,37 @@ impl NetworkBuilder {
             network: MoleculeNetwork::new(),
             similarity_threshold,
             max_neighbors,
+            metric: DEFAULT_METRIC.to_string(),
+            composite: None,
+            seed: None,
+            max_pairs: None,
         }
     }
     
+    /// Use the named similarity metric from the global registry
+    pub fn with_metric(mut self, metric: &str) -> Self {
+        self.metric = metric.to_string();
+        self
+    }
+    
+    /// Score pairs with a weighted composite of several metrics
+    pub fn with_composite(mut self, composite: CompositeSimilarity) -> Self {
+        self.composite = Some(composite);
+        self
+    }
+    
+    /// Seed the random number generator used while building
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    
+    /// Score a random sample of at most `max_pairs` pairs rather than every pair
+    pub fn with_pair_sampling(mut self, max_pairs: usize) -> Self {
+        self.max_pairs = Some(max_pairs);
+        self
+    }
+    
     /// Add a molecule to the network
     pub fn add_molecule(&mut self, molecule: &Molecule) -> Result<()> {
         self.network.add_molecule(molecule);
@@ -330,15 +594,48 @@ impl NetworkBuilder {
     /// Calculate similarities and add edges
     pub fn build_similarities(&mut self) -> Result<()> {
         // Get all molecules in the network
-        let molecules = self.network.get_molecules();
-        
-        // Calculate similarities between all pairs of molecules
-        for (i, mol1) in molecules.iter().enumerate() {
-            for mol2 in molecules.iter().skip(i + 1) {
-                // Calculate similarity between the molecules
-                // In a real implementation, this would use RDKit or another library
-                // For now, just use a random value
-                let similarity = rand::random::<f64>();
+        let molecules: Vec<Molecule> = self.network.get_molecules()
+            .into_iter()
+            .map(MoleculeNode::to_molecule)
+            .collect();
+        
+        let seed = rng::resolve_seed(self.seed);
+        rng::record_seed(&mut self.network.metadata, seed);
+        
+        // All pairs of molecules, optionally down-sampled by pair index so
+        // the full pair list is never built
+        let n = molecules.len();
+        let total_pairs = n * n.saturating_sub(1) / 2;
+        let pairs: Box<dyn Iterator<Item = (usize, usize)>> = match self.max_pairs {
+            Some(max_pairs) if total_pairs > max_pairs => {
+                debug!("Sampling {} of {} pairs with seed {}", max_pairs, total_pairs, seed);
+                let mut rng = rng::stream_rng(seed, "network_pairs");
+                let mut sampled = rand::seq::index::sample(&mut rng, total_pairs, max_pairs).into_vec();
+                sampled.sort_unstable();
+                Box::new(sampled.into_iter().map(move |k| nth_pair(n, k)))
+            }
+            _ => Box::new((0..n).flat_map(move |i| (i + 1..n).map(move |j| (i, j)))),
+        };
+        
+        if let Some(composite) = self.composite.clone() {
+            debug!("Building similarities with composite metric: {:?}", composite.weights());
+            
+            for (i, j) in pairs {
+                let (mol1, mol2) = (&molecules[i], &molecules[j]);
+                let (similarity, components) = composite.score_components(mol1, mol2)?;
+                
+                if similarity >= self.similarity_threshold {
+                    self.network.add_composite_similarity(&mol1.id, &mol2.id, similarity, components);
+                }
+            }
+        } else {
+            let registry = SimilarityRegistry::global();
+            registry.get(&self.metric)?;
+            debug!("Building similarities with metric: {}", self.metric);
+            
+            for (i, j) in pairs {
+                let (mol1, mol2) = (&molecules[i], &molecules[j]);
+                let similarity = registry.compute(&self.metric, mol1, mol2)?;
                 
                 // Add an edge if the similarity is above the threshold
                 if similarity >= self.similarity_threshold {
@@ -365,6 +662,22 @@ impl NetworkBuilder {
     }
 }
 
+/// The `k`-th pair `(i, j)` with `i < j < n`, in row-major order
+fn nth_pair(n: usize, k: usize) -> (usize, usize) {
+    // Index of the first pair of row i
+    let row_start = |i: usize| i * (2 * n - i - 1) / 2;
+    let (mut lo, mut hi) = (0, n - 1);
+    while lo + 1 < hi {
+        let mid = (lo + hi) / 2;
+        if row_start(mid) <= k {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo, lo + 1 + k - row_start(lo))
+}
+
 // Graph module for Neo4j database interactions
 // Handles molecular relationship data storage and retrieval
 
@@ -398,7 +711,8 @@ impl GraphDbClient {
         // This would create a Cypher query to insert the molecule
         let _cypher = format!(
             "CREATE (m:Molecule {{id: '{}', name: '{}', formula: '{}', confidence: {}}}) RETURN m",
-            molecule.id, molecule.name, molecule.formula, molecule.confidence_score
+            molecule.id, molecule.display_name(),
+            molecule.formula.as_ref().map(Formula::hill).unwrap_or_default(), molecule.confidence_score
         );
         
         // In a real implementation, this would execute the Cypher query
@@ -418,7 +732,7 @@ impl GraphDbClient {
             let mut molecule = Molecule::new(
                 id.to_string(), 
                 "Mock Molecule".to_string(),
-                "C6H12O6".to_string()
+                "C6H12O6".parse().unwrap_or_default()
             );
             molecule.confidence_score = 0.85;
             return Ok(Some(molecule));
@@ -475,7 +789,7 @@ impl GraphDbClient {
             let mut mol1 = Molecule::new(
                 "mol-1".to_string(),
                 "Glucose".to_string(),
-                "C6H12O6".to_string()
+                "C6H12O6".parse().unwrap_or_default()
             );
             mol1.confidence_score = 0.92;
             molecules.push(mol1);
@@ -483,7 +797,7 @@ impl GraphDbClient {
             let mut mol2 = Molecule::new(
                 "mol-2".to_string(),
                 "Pyruvate".to_string(),
-                "C3H4O3".to_string()
+                "C3H4O3".parse().unwrap_or_default()
             );
             mol2.confidence_score = 0.88;
             molecules.push(mol2);
@@ -495,24 +809,62 @@ impl GraphDbClient {
         Ok(Vec::new())
     }
     
-    /// Calculate pathway coherence score for a molecule
-    pub fn calculate_pathway_coherence(&self, molecule_id: &str) -> Result<f64, HegelError> {
-        // This would create a Cypher query to calculate pathway coherence
-        let _cypher = format!(
-            "MATCH (m:Molecule {{id: '{}'}})-[:PARTICIPATES_IN]->(r:Reaction)-[:PART_OF]->(p:Pathway)
-             WITH p, count(r) as reaction_count
-             MATCH (p)<-[:PART_OF]-(r:Reaction)
-             WITH p, reaction_count, count(r) as total_reactions
-             RETURN p.id, reaction_count, total_reactions",
-            molecule_id
-        );
-        
-        // For demonstration, return a mock coherence score
-        if molecule_id.starts_with("mol-") {
-            return Ok(0.75);
+    /// Calculate the pathway coherence of a molecule
+    ///
+    /// This client has no driver to run queries with, so the molecule's
+    /// pathways are passed in, e.g. from `Neo4jClient::molecule_pathways`.
+    /// `observed` maps the molecules identified in the current dataset to
+    /// their confidence.
+    pub fn calculate_pathway_coherence(
+        &self,
+        molecule_id: &str,
+        pathways: &[PathwayMembership],
+        observed: &HashMap<String, f64>,
+    ) -> Result<PathwayCoherence, HegelError> {
+        if molecule_id.trim().is_empty() {
+            return Err(HegelError::DataError("Pathway coherence needs a molecule ID".to_string()));
+        }
+        Ok(pathways::pathway_coherence(molecule_id, pathways, observed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EvidenceType, MolecularEvidence};
+
+    #[test]
+    fn test_molecule_converts_to_node_and_back() {
+        let mut molecule = Molecule::from_identifier("50-78-2", &MoleculeIdType::CAS).unwrap();
+        molecule.smiles = "CC(=O)OC1=CC=CC=C1C(=O)O".to_string();
+        molecule.add_evidence(MolecularEvidence {
+            source: "nmr".to_string(),
+            confidence: 0.9,
+            data_type: EvidenceType::Spectral,
+            value: "aspirin".to_string(),
+        });
+
+        let mut network = MoleculeNetwork::new();
+        network.add_molecule(&molecule);
+        assert_eq!(network.resolve_id("cas:50-78-2"), Some(molecule.id.as_str()));
+
+        let node = network.get_molecule(&molecule.id).unwrap();
+        assert_eq!(node.external_ids["cas"], "50-78-2");
+        let restored = Molecule::from(node);
+        assert_eq!(restored.identifiers, molecule.identifiers);
+        assert_eq!(restored.smiles, molecule.smiles);
+        assert!(restored.evidences.is_empty());
+    }
+
+    #[test]
+    fn test_nth_pair_enumerates_pairs_in_order() {
+        for n in 2..9 {
+            let pairs: Vec<(usize, usize)> = (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+                .collect();
+            for (k, pair) in pairs.iter().enumerate() {
+                assert_eq!(nth_pair(n, k), *pair);
+            }
         }
-        
-        // In a real implementation, this would execute the query and calculate coherence
-        Ok(0.0)
     }
 }
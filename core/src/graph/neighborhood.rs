@@ -0,0 +1,312 @@
+//! Molecule Neighborhoods
+//!
+//! Extracts the local context of a molecule: every molecule within a number
+//! of similarity hops of it, over edges at or above a minimum similarity,
+//! together with the edges between them. The result is a small network the
+//! frontend can draw without downloading the whole graph, carrying layout
+//! positions and the hop distance of every molecule as hints.
+//!
+//! The breadth-first search is driven one hop at a time by
+//! `NeighborhoodSearch`, so a graph store fetches each hop with a single
+//! query for the whole frontier rather than one query per molecule.
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashSet};
+
+use super::layout::LayoutOptions;
+use super::schema::Node;
+use super::{MoleculeNetwork, MoleculeNode};
+
+/// Network metadata key describing how a neighborhood was extracted
+pub const NEIGHBORHOOD_METADATA_KEY: &str = "neighborhood";
+
+/// Largest radius a neighborhood may be extracted with
+pub const MAX_RADIUS: usize = 5;
+
+/// Parameters of a neighborhood extraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeighborhoodOptions {
+    /// Maximum number of similarity hops from the molecule
+    pub radius: usize,
+
+    /// Minimum similarity of the edges followed and returned
+    pub min_similarity: f64,
+
+    /// Maximum number of molecules returned, including the molecule itself
+    pub max_nodes: usize,
+
+    /// Layout computed for the neighborhood; seeded so it is drawn the same way every time
+    pub layout: LayoutOptions,
+}
+
+impl Default for NeighborhoodOptions {
+    fn default() -> Self {
+        Self {
+            radius: 2,
+            min_similarity: 0.6,
+            max_nodes: 500,
+            layout: LayoutOptions::default().with_seed(0),
+        }
+    }
+}
+
+impl NeighborhoodOptions {
+    /// Set the number of hops
+    pub fn with_radius(mut self, radius: usize) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Set the minimum edge similarity
+    pub fn with_min_similarity(mut self, min_similarity: f64) -> Self {
+        self.min_similarity = min_similarity;
+        self
+    }
+
+    /// Set the maximum number of molecules
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Check the options describe an extraction that can be run
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=MAX_RADIUS).contains(&self.radius) {
+            return Err(anyhow!("Neighborhood radius must be between 1 and {}, got {}", MAX_RADIUS, self.radius));
+        }
+        if !(0.0..=1.0).contains(&self.min_similarity) {
+            return Err(anyhow!("Minimum similarity must be between 0 and 1, got {}", self.min_similarity));
+        }
+        if self.max_nodes == 0 {
+            return Err(anyhow!("Neighborhood must allow at least one molecule"));
+        }
+        self.layout.validate()
+    }
+}
+
+/// Similarity edge from a molecule of the frontier to one of its neighbors
+#[derive(Debug, Clone)]
+pub struct NeighborEdge {
+    /// Frontier molecule the edge starts from
+    pub from: String,
+
+    /// Molecule at the other end of the edge
+    pub neighbor: MoleculeNode,
+
+    /// Similarity of the two molecules
+    pub similarity: f64,
+}
+
+/// Breadth-first search around a molecule, expanded one hop at a time
+///
+/// Callers repeatedly fetch the similarity edges of `frontier()` and pass
+/// them to `expand` until the frontier is empty, then `finish` the search.
+/// The last expansion only adds edges between molecules already found.
+#[derive(Debug)]
+pub struct NeighborhoodSearch {
+    /// Extraction parameters
+    options: NeighborhoodOptions,
+
+    /// Molecule the neighborhood is centred on
+    center: String,
+
+    /// Molecules found so far, by ID
+    nodes: BTreeMap<String, MoleculeNode>,
+
+    /// Hops from the centre of each molecule found
+    hops: BTreeMap<String, usize>,
+
+    /// Similarity of each edge found, keyed by its ordered endpoints
+    edges: BTreeMap<(String, String), f64>,
+
+    /// Molecules whose edges are needed next
+    frontier: Vec<String>,
+
+    /// Hops from the centre of the frontier molecules
+    depth: usize,
+
+    /// Whether molecules were left out to stay within `max_nodes`
+    truncated: bool,
+}
+
+impl NeighborhoodSearch {
+    /// Start a search at a molecule
+    pub fn new(center: MoleculeNode, options: &NeighborhoodOptions) -> Result<Self> {
+        options.validate()?;
+        let id = center.id.clone();
+        Ok(Self {
+            options: options.clone(),
+            center: id.clone(),
+            nodes: BTreeMap::from([(id.clone(), center)]),
+            hops: BTreeMap::from([(id.clone(), 0)]),
+            edges: BTreeMap::new(),
+            frontier: vec![id],
+            depth: 0,
+            truncated: false,
+        })
+    }
+
+    /// Molecules whose similarity edges are needed next; empty once the search is complete
+    pub fn frontier(&self) -> &[String] {
+        &self.frontier
+    }
+
+    /// Minimum similarity of the edges the search follows
+    pub fn min_similarity(&self) -> f64 {
+        self.options.min_similarity
+    }
+
+    /// Add the similarity edges of the frontier molecules and move to the next hop
+    ///
+    /// When the neighborhood is full, the most similar neighbors are kept.
+    pub fn expand(&mut self, mut edges: Vec<NeighborEdge>) {
+        let frontier: HashSet<&String> = self.frontier.iter().collect();
+        edges.retain(|edge| {
+            frontier.contains(&edge.from) && edge.neighbor.id != edge.from && edge.similarity >= self.options.min_similarity
+        });
+        edges.sort_by(|a, b| b.similarity.total_cmp(&a.similarity).then_with(|| a.neighbor.id.cmp(&b.neighbor.id)));
+
+        let mut next = Vec::new();
+        for edge in edges {
+            let id = edge.neighbor.id.clone();
+            if !self.hops.contains_key(&id) {
+                if self.depth == self.options.radius {
+                    continue;
+                }
+                if self.nodes.len() >= self.options.max_nodes {
+                    self.truncated = true;
+                    continue;
+                }
+                self.hops.insert(id.clone(), self.depth + 1);
+                self.nodes.insert(id.clone(), edge.neighbor);
+                next.push(id.clone());
+            }
+            let key = if edge.from < id { (edge.from, id) } else { (id, edge.from) };
+            let similarity = self.edges.entry(key).or_insert(edge.similarity);
+            *similarity = similarity.max(edge.similarity);
+        }
+
+        next.sort();
+        self.frontier = next;
+        self.depth += 1;
+    }
+
+    /// Build the neighborhood network, laid out and annotated with hop distances
+    pub fn finish(self) -> Result<MoleculeNetwork> {
+        let mut network = MoleculeNetwork::new();
+        for node in self.nodes.into_values() {
+            network.insert_node(node);
+        }
+        for ((source, target), similarity) in &self.edges {
+            network.add_similarity(source, target, *similarity);
+        }
+
+        let layout = network.layout(&self.options.layout)?;
+        network.store_layout(&layout);
+        network.metadata.insert(NEIGHBORHOOD_METADATA_KEY.to_string(), serde_json::json!({
+            "center": self.center,
+            "radius": self.options.radius,
+            "min_similarity": self.options.min_similarity,
+            "hops": self.hops,
+            "truncated": self.truncated,
+        }));
+        debug!("Extracted neighborhood of {} with {} molecules and {} edges{}",
+               self.center, self.hops.len(), self.edges.len(), if self.truncated { " (truncated)" } else { "" });
+        Ok(network)
+    }
+}
+
+/// Network node for a molecule read from a graph store
+pub fn molecule_node(node: &Node) -> MoleculeNode {
+    let text = |key: &str| node.get_property(key).and_then(|v| v.as_str());
+    MoleculeNode {
+        id: node.id.clone(),
+        smiles: text("smiles").unwrap_or_default().to_string(),
+        name: Some(node.name.clone()),
+        formula: text("formula").and_then(|formula| formula.parse().ok()),
+        properties: node.properties.clone(),
+        external_ids: node.external_ids.clone(),
+        aliases: Vec::new(),
+        position: None,
+    }
+}
+
+impl MoleculeNetwork {
+    /// Extract the neighborhood of a molecule, or `None` if it is not in the network
+    pub fn neighborhood(&self, molecule_id: &str, options: &NeighborhoodOptions) -> Result<Option<MoleculeNetwork>> {
+        let center = match self.get_molecule(molecule_id) {
+            Some(center) => center.clone(),
+            None => return Ok(None),
+        };
+        let mut search = NeighborhoodSearch::new(center, options)?;
+        while !search.frontier().is_empty() {
+            let edges = search.frontier().iter()
+                .flat_map(|id| self.get_similar_molecules(id, search.min_similarity()).into_iter()
+                    .map(move |(neighbor, similarity)| NeighborEdge { from: id.clone(), neighbor, similarity }))
+                .collect();
+            search.expand(edges);
+        }
+        search.finish().map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::Molecule;
+
+    /// a - b - c - d in a chain of strong edges, with e weakly attached to a
+    fn chain() -> MoleculeNetwork {
+        let mut network = MoleculeNetwork::new();
+        for id in ["a", "b", "c", "d", "e"] {
+            let mut molecule = Molecule::from_smiles("C").unwrap();
+            molecule.id = id.to_string();
+            network.add_molecule(&molecule);
+        }
+        network.add_similarity("a", "b", 0.9);
+        network.add_similarity("b", "c", 0.8);
+        network.add_similarity("c", "d", 0.9);
+        network.add_similarity("a", "c", 0.7);
+        network.add_similarity("a", "e", 0.3);
+        network
+    }
+
+    #[test]
+    fn test_neighborhood_follows_strong_edges() {
+        let network = chain();
+        let neighborhood = network.neighborhood("a", &NeighborhoodOptions::default()).unwrap().unwrap();
+        let serialized = neighborhood.to_serializable();
+
+        let ids: Vec<&str> = serialized.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids.len(), 4);
+        assert!(!ids.contains(&"e"));
+        // b-c is found between two molecules of the first hop
+        assert_eq!(serialized.edges.len(), 4);
+        assert!(serialized.nodes.iter().all(|n| n.position.is_some()));
+
+        let hints = &serialized.metadata[NEIGHBORHOOD_METADATA_KEY];
+        assert_eq!(hints["hops"]["c"], 1);
+        assert_eq!(hints["hops"]["d"], 2);
+        assert_eq!(hints["truncated"], false);
+
+        let again = network.neighborhood("a", &NeighborhoodOptions::default()).unwrap().unwrap();
+        assert_eq!(again.get_molecule("d").unwrap().position, neighborhood.get_molecule("d").unwrap().position);
+        assert!(network.neighborhood("z", &NeighborhoodOptions::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_neighborhood_limits() {
+        let network = chain();
+        let one_hop = network.neighborhood("a", &NeighborhoodOptions::default().with_radius(1)).unwrap().unwrap();
+        assert_eq!(one_hop.get_molecules().len(), 3);
+
+        let capped = network.neighborhood("a", &NeighborhoodOptions::default().with_max_nodes(2)).unwrap().unwrap();
+        assert!(capped.get_molecule("b").is_some());
+        assert_eq!(capped.metadata()[NEIGHBORHOOD_METADATA_KEY]["truncated"], true);
+
+        assert!(network.neighborhood("a", &NeighborhoodOptions::default().with_radius(0)).is_err());
+        assert!(network.neighborhood("a", &NeighborhoodOptions::default().with_min_similarity(1.5)).is_err());
+    }
+}
@@ -10,7 +10,28 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::time::Duration;
 
-use super::schema::{Node, Edge, NodeType, EdgeType, MolecularGraph};
+use super::schema::{Node, Edge, NodeType, EdgeType, MolecularGraph, RESERVED_EDGE_PROPERTIES, RESERVED_NODE_PROPERTIES};
+use super::paths::MoleculePath;
+use super::neighborhood::{molecule_node, NeighborEdge, NeighborhoodOptions, NeighborhoodSearch};
+use super::MoleculeNetwork;
+use super::inspect::{MoleculeInspection, MoleculeSummary, MAX_CONFIDENCE_HISTORY};
+use super::migration::IdMigration;
+use super::pathways::{pathway_coherence, PathwayCoherence, PathwayMembership};
+use super::stats::{trend_start, ProjectStats, CONFIDENCE_BANDS};
+use super::store::{GenePhenotypes, MoleculeInteraction};
+use super::reconcile::{find_duplicates, merge_properties, MergeRecord, ReconciliationReport, StoredMolecule};
+use crate::identity::MoleculeIdentifier;
+use crate::identity::xref::XrefService;
+use crate::processing::evidence::{Evidence, EvidenceType, IntegratedEvidence, SAMPLE_METADATA_KEY};
+use crate::processing::versioning::ConfidenceTrigger;
+use crate::processing::retention::{RetentionPolicy, RedactionRecord, RedactionAuditLog};
+use crate::projects::{Project, DEFAULT_PROJECT};
+use crate::cohorts::{GroupComparison, StudyDesign};
+use crate::bundle::{ProjectBundle, ProjectReport};
+use crate::fuzzy_evidence::FuzzyBayesianNetwork;
+
+/// Labels of nodes that belong to a project
+const PROJECT_SCOPED_LABELS: [&str; 7] = ["Molecule", "Evidence", "Graph", "FuzzyNetwork", "Report", "Sample", "StudyGroup"];
 
 /// Neo4j database configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,32 +119,257 @@ impl Neo4jClient {
         })
     }
     
+    /// Create the project constraints and move unscoped nodes into the default project
+    ///
+    /// Node IDs are unique within a project rather than globally, so the same
+    /// molecule can be studied independently in several projects.
+    pub async fn ensure_project_schema(&self) -> Result<()> {
+        let driver = self.connect().await?;
+        
+        for label in PROJECT_SCOPED_LABELS {
+            let constraint = format!(
+                "CREATE CONSTRAINT {}_project_id IF NOT EXISTS FOR (n:{}) REQUIRE (n.project_id, n.id) IS UNIQUE",
+                label.to_lowercase(), label
+            );
+            driver.run_query(&constraint, serde_json::json!({})).await?;
+            
+            let backfill = format!("MATCH (n:{}) WHERE n.project_id IS NULL SET n.project_id = $project_id RETURN count(n) as moved", label);
+            driver.run_query(&backfill, serde_json::json!({"project_id": DEFAULT_PROJECT})).await?;
+        }
+        
+        info!("Project schema ensured for {}", PROJECT_SCOPED_LABELS.join(", "));
+        Ok(())
+    }
+    
+    /// Store a project's metadata and members
+    pub async fn store_project(&self, project: &Project) -> Result<()> {
+        let driver = self.connect().await?;
+        
+        let query = "MERGE (p:Project {id: $id}) \
+                     SET p.name = $name, p.description = $description, p.created_at = $created_at, p.record = $record \
+                     RETURN p";
+        let params = serde_json::json!({
+            "id": project.id,
+            "name": project.name,
+            "description": project.description,
+            "created_at": project.created_at.to_rfc3339(),
+            "record": serde_json::to_string(project)?,
+        });
+        driver.run_query(query, params).await?;
+        
+        Ok(())
+    }
+    
+    /// Load a project stored with `store_project`
+    pub async fn load_project(&self, project_id: &str) -> Result<Project> {
+        let driver = self.connect().await?;
+        
+        let rows = driver.run_query("MATCH (p:Project {id: $id}) RETURN p.record as record",
+                                    serde_json::json!({"id": project_id})).await?;
+        let record = rows.first()
+            .and_then(|row| row.get("record"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Project not found: {}", project_id))?;
+        serde_json::from_str(record).context("Invalid stored project")
+    }
+    
+    /// Store the state of a fuzzy-Bayesian network under a name
+    pub async fn store_fuzzy_network(&self, project_id: &str, name: &str, network: &FuzzyBayesianNetwork) -> Result<()> {
+        let driver = self.connect().await?;
+        
+        let query = "MERGE (f:FuzzyNetwork {id: $name, project_id: $project_id}) \
+                     SET f.state = $state, f.updated_at = $updated_at \
+                     RETURN f";
+        let params = serde_json::json!({
+            "name": name,
+            "project_id": project_id,
+            "state": serde_json::to_string(network)?,
+            "updated_at": chrono::Utc::now().to_rfc3339(),
+        });
+        driver.run_query(query, params).await?;
+        
+        Ok(())
+    }
+    
+    /// Store a report about a project's molecules
+    pub async fn store_report(&self, project_id: &str, report: &ProjectReport) -> Result<()> {
+        let driver = self.connect().await?;
+        
+        let query = "MERGE (r:Report {id: $id, project_id: $project_id}) \
+                     SET r.molecule_id = $molecule_id, r.created_at = $created_at, r.content = $content \
+                     RETURN r";
+        let params = serde_json::json!({
+            "id": report.id,
+            "project_id": project_id,
+            "molecule_id": report.molecule_id,
+            "created_at": report.created_at.to_rfc3339(),
+            "content": serde_json::to_string(&report.content)?,
+        });
+        driver.run_query(query, params).await?;
+        
+        Ok(())
+    }
+    
+    /// Collect everything stored for a project into a bundle
+    pub async fn export_project(&self, project_id: &str) -> Result<ProjectBundle> {
+        let mut bundle = ProjectBundle::new(self.load_project(project_id).await?);
+        let driver = self.connect().await?;
+        let params = serde_json::json!({"project_id": project_id});
+        
+        info!("Exporting project {} from Neo4j", project_id);
+        
+        let molecules = driver.run_query("MATCH (m:Molecule {project_id: $project_id}) RETURN properties(m) as m",
+                                         params.clone()).await?;
+        bundle.molecules = molecules.into_iter().filter_map(|mut row| row.remove("m")).collect();
+        
+        let evidence = driver.run_query(
+            "MATCH (e:Evidence {project_id: $project_id})-[:SUPPORTS]->(m:Molecule {project_id: $project_id}) \
+             RETURN properties(e) as e, m.id as molecule_id",
+            params.clone(),
+        ).await?;
+        for mut row in evidence {
+            if let (Some(mut properties), Some(molecule_id)) = (row.remove("e"), row.remove("molecule_id")) {
+                if let Some(fields) = properties.as_object_mut() {
+                    fields.insert("molecule_id".to_string(), molecule_id);
+                }
+                bundle.evidence.push(properties);
+            }
+        }
+        
+        let graphs = driver.run_query("MATCH (g:Graph {project_id: $project_id}) RETURN g.id as id",
+                                      params.clone()).await?;
+        for graph_id in graphs.iter().filter_map(|row| row.get("id").and_then(|v| v.as_str())) {
+            bundle.networks.push(self.retrieve_graph(project_id, graph_id).await?);
+        }
+        
+        let fuzzy = driver.run_query("MATCH (f:FuzzyNetwork {project_id: $project_id}) RETURN f.id as name, f.state as state",
+                                     params.clone()).await?;
+        for row in fuzzy {
+            if let (Some(name), Some(state)) = (row.get("name").and_then(|v| v.as_str()), row.get("state").and_then(|v| v.as_str())) {
+                let network = serde_json::from_str(state)
+                    .with_context(|| format!("Invalid stored fuzzy network: {}", name))?;
+                bundle.fuzzy_networks.insert(name.to_string(), network);
+            }
+        }
+        
+        let reports = driver.run_query(
+            "MATCH (r:Report {project_id: $project_id}) \
+             RETURN r.id as id, r.molecule_id as molecule_id, r.created_at as created_at, r.content as content",
+            params,
+        ).await?;
+        for row in reports {
+            let id = match row.get("id").and_then(|v| v.as_str()) {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+            bundle.reports.push(ProjectReport {
+                id,
+                molecule_id: row.get("molecule_id").and_then(|v| v.as_str()).map(str::to_string),
+                created_at: row.get("created_at")
+                    .and_then(|v| v.as_str())
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&chrono::Utc))
+                    .unwrap_or_else(chrono::Utc::now),
+                content: row.get("content")
+                    .and_then(|v| v.as_str())
+                    .and_then(|c| serde_json::from_str(c).ok())
+                    .unwrap_or(Value::Null),
+            });
+        }
+        
+        info!("Exported project {}: {} molecules, {} evidence items, {} networks",
+              project_id, bundle.molecules.len(), bundle.evidence.len(), bundle.networks.len());
+        Ok(bundle)
+    }
+    
+    /// Store the contents of a bundle under its project ID
+    ///
+    /// Any `project_id` carried by the bundled records or networks is
+    /// overwritten with the bundle's project ID, so a bundle can only ever
+    /// write into its own project.
+    pub async fn import_project(&self, bundle: &ProjectBundle) -> Result<()> {
+        let project_id = bundle.project.id.as_str();
+        info!("Importing project {} into Neo4j", project_id);
+        
+        self.store_project(&bundle.project).await?;
+        let driver = self.connect().await?;
+        
+        let molecule_query = "MERGE (m:Molecule {id: $id, project_id: $project_id}) SET m += $properties RETURN m";
+        for record in &bundle.molecules {
+            let mut properties = record.clone();
+            let fields = properties.as_object_mut()
+                .ok_or_else(|| anyhow!("Bundled molecule is not an object"))?;
+            fields.insert("project_id".to_string(), serde_json::json!(project_id));
+            let id = fields.get("id").cloned()
+                .ok_or_else(|| anyhow!("Bundled molecule has no ID"))?;
+            let params = serde_json::json!({"id": id, "project_id": project_id, "properties": properties});
+            driver.run_query(molecule_query, params).await?;
+        }
+        
+        let evidence_query = "MATCH (m:Molecule {id: $molecule_id, project_id: $project_id}) \
+                              MERGE (e:Evidence {id: $id, project_id: $project_id}) \
+                              SET e += $properties \
+                              MERGE (e)-[:SUPPORTS]->(m) \
+                              RETURN e";
+        for record in &bundle.evidence {
+            let mut properties = record.clone();
+            let fields = properties.as_object_mut()
+                .ok_or_else(|| anyhow!("Bundled evidence is not an object"))?;
+            let molecule_id = fields.remove("molecule_id")
+                .ok_or_else(|| anyhow!("Bundled evidence has no molecule ID"))?;
+            fields.insert("project_id".to_string(), serde_json::json!(project_id));
+            let id = fields.get("id").cloned()
+                .ok_or_else(|| anyhow!("Bundled evidence has no ID"))?;
+            let params = serde_json::json!({
+                "id": id,
+                "molecule_id": molecule_id,
+                "project_id": project_id,
+                "properties": properties,
+            });
+            driver.run_query(evidence_query, params).await?;
+        }
+        
+        for network in &bundle.networks {
+            let mut network = network.clone();
+            network.set_project(project_id);
+            self.store_graph(&network).await?;
+        }
+        for (name, network) in &bundle.fuzzy_networks {
+            self.store_fuzzy_network(project_id, name, network).await?;
+        }
+        for report in &bundle.reports {
+            self.store_report(project_id, report).await?;
+        }
+        
+        info!("Imported project {}", project_id);
+        Ok(())
+    }
+    
     /// Store a molecular graph in Neo4j
     pub async fn store_graph(&self, graph: &MolecularGraph) -> Result<()> {
         let driver = self.connect().await?;
         
-        info!("Storing graph {} in Neo4j", graph.id);
+        info!("Storing graph {} in Neo4j for project {}", graph.id, graph.project_id);
         
-        // Store graph metadata
-        let metadata_query = format!(
-            "CREATE (g:Graph {{id: $graph_id, name: $graph_name}}) RETURN g",
-        );
+        // Store graph metadata, merged so that storing a graph again updates it
+        let metadata_query = "MERGE (g:Graph {id: $graph_id, project_id: $project_id}) SET g.name = $graph_name RETURN g";
         
         let metadata_params = serde_json::json!({
             "graph_id": graph.id,
             "graph_name": graph.name,
+            "project_id": graph.project_id,
         });
         
-        driver.run_query(&metadata_query, metadata_params).await?;
+        driver.run_query(metadata_query, metadata_params).await?;
         
         // Store nodes
         for node in &graph.nodes {
-            self.store_node(&driver, node).await?;
+            self.store_node(&driver, &graph.project_id, node).await?;
         }
         
         // Store edges
         for edge in &graph.edges {
-            self.store_edge(&driver, edge).await?;
+            self.store_edge(&driver, &graph.project_id, edge).await?;
         }
         
         info!("Graph {} stored successfully with {} nodes and {} edges", 
@@ -132,14 +378,767 @@ impl Neo4jClient {
         Ok(())
     }
     
+    /// Store the result of evidence integration for a molecule
+    ///
+    /// Evidence nodes are merged by ID, and the confidence is appended to the
+    /// molecule's history, together with what triggered the integration, only
+    /// for a new integration time, so writing the same result twice leaves the
+    /// graph unchanged. Payloads removed under a retention policy are not
+    /// written back. The molecule records the ID of the pipeline fingerprint
+    /// and links to a `Pipeline` node holding the full fingerprint.
+    pub async fn store_integrated_evidence(&self, project_id: &str, integrated: &IntegratedEvidence, trigger: ConfidenceTrigger) -> Result<()> {
+        let driver = self.connect().await?;
+        
+        debug!("Storing integrated evidence for molecule {} in project {}", integrated.molecule_id, project_id);
+        
+        // All history lists are computed from the stored timestamps before any is updated
+        let molecule_query = format!(
+            "MERGE (m:Molecule {{id: $id, project_id: $project_id}}) \
+             WITH m, last(coalesce(m.confidence_history_at, [])) = $timestamp AS seen \
+             SET m.confidence = $confidence, m.conflicts = $conflicts, m.conflict_details = $conflict_details, \
+                 m.integrated_at = $timestamp, m.pipeline_fingerprint = $pipeline_fingerprint, \
+                 m.confidence_history = CASE WHEN seen THEN m.confidence_history \
+                     ELSE (coalesce(m.confidence_history, []) + $confidence)[-{limit}..] END, \
+                 m.confidence_history_at = CASE WHEN seen THEN m.confidence_history_at \
+                     ELSE (coalesce(m.confidence_history_at, []) + $timestamp)[-{limit}..] END, \
+                 m.confidence_history_trigger = CASE WHEN seen THEN m.confidence_history_trigger \
+                     ELSE (coalesce(m.confidence_history_trigger, []) + $trigger)[-{limit}..] END \
+             RETURN m",
+            limit = MAX_CONFIDENCE_HISTORY,
+        );
+        let molecule_params = serde_json::json!({
+            "id": integrated.molecule_id,
+            "project_id": project_id,
+            "confidence": integrated.aggregate_confidence,
+            "conflicts": integrated.conflicts.len(),
+            "conflict_details": serde_json::to_string(&integrated.conflicts)?,
+            "timestamp": integrated.integration_timestamp.to_rfc3339(),
+            "trigger": trigger.to_string(),
+            "pipeline_fingerprint": integrated.pipeline_fingerprint.as_ref().map(|f| f.id.as_str()),
+        });
+        driver.run_query(&molecule_query, molecule_params).await?;
+        
+        if let Some(fingerprint) = &integrated.pipeline_fingerprint {
+            let pipeline_query = "MATCH (m:Molecule {id: $molecule_id, project_id: $project_id}) \
+                                  MERGE (p:Pipeline {id: $id, project_id: $project_id}) \
+                                  ON CREATE SET p.code_version = $code_version, p.record = $record, p.first_seen = $timestamp \
+                                  MERGE (m)-[:CONCLUDED_BY]->(p) \
+                                  RETURN p";
+            let params = serde_json::json!({
+                "molecule_id": integrated.molecule_id,
+                "project_id": project_id,
+                "id": fingerprint.id,
+                "code_version": fingerprint.code_version,
+                "record": serde_json::to_string(fingerprint)?,
+                "timestamp": integrated.integration_timestamp.to_rfc3339(),
+            });
+            driver.run_query(pipeline_query, params).await?;
+        }
+        
+        let evidence_query = "MATCH (m:Molecule {id: $molecule_id, project_id: $project_id}) \
+                              MERGE (e:Evidence {id: $id, project_id: $project_id}) \
+                              SET e.type = $type, e.source = $source, e.confidence = $confidence, e.timestamp = $timestamp, \
+                                  e.metadata = $metadata, \
+                                  e.data = CASE WHEN e.payload_sha256 IS NULL THEN $data ELSE null END \
+                              MERGE (e)-[:SUPPORTS]->(m) \
+                              WITH e \
+                              OPTIONAL MATCH (s:Sample {id: $sample_id, project_id: $project_id}) \
+                              FOREACH (_ IN CASE WHEN s IS NULL THEN [] ELSE [1] END | MERGE (e)-[:MEASURED_IN]->(s)) \
+                              RETURN e";
+        for evidence in &integrated.evidence_items {
+            let params = serde_json::json!({
+                "molecule_id": integrated.molecule_id,
+                "project_id": project_id,
+                "id": evidence.id,
+                "type": evidence.evidence_type.to_string(),
+                "source": evidence.source,
+                "confidence": evidence.confidence,
+                "timestamp": evidence.timestamp.to_rfc3339(),
+                "data": serde_json::to_string(&evidence.data)?,
+                "metadata": serde_json::to_string(&evidence.metadata)?,
+                "sample_id": evidence.sample_id(),
+            });
+            driver.run_query(evidence_query, params).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Store the samples and study groups of a project
+    ///
+    /// Samples are linked to their group with `MEMBER_OF`, replacing any
+    /// earlier assignment, and evidence already stored for a sample is
+    /// linked to it with `MEASURED_IN`.
+    pub async fn store_study_design(&self, project_id: &str, design: &StudyDesign) -> Result<()> {
+        design.validate()?;
+        let driver = self.connect().await?;
+        
+        for group in &design.groups {
+            driver.run_query(
+                "MERGE (g:StudyGroup {id: $id, project_id: $project_id}) \
+                 SET g.name = $name, g.description = $description, g.record = $record \
+                 RETURN g",
+                serde_json::json!({
+                    "id": group.id,
+                    "project_id": project_id,
+                    "name": group.name,
+                    "description": group.description,
+                    "record": serde_json::to_string(group)?,
+                }),
+            ).await?;
+        }
+        
+        for sample in &design.samples {
+            driver.run_query(
+                "MERGE (s:Sample {id: $id, project_id: $project_id}) \
+                 SET s.subject_id = $subject_id, s.matrix = $matrix, s.record = $record \
+                 WITH s \
+                 OPTIONAL MATCH (s)-[old:MEMBER_OF]->(:StudyGroup) \
+                 DELETE old \
+                 WITH DISTINCT s \
+                 OPTIONAL MATCH (g:StudyGroup {id: $group_id, project_id: $project_id}) \
+                 FOREACH (_ IN CASE WHEN g IS NULL THEN [] ELSE [1] END | MERGE (s)-[:MEMBER_OF]->(g)) \
+                 WITH s \
+                 OPTIONAL MATCH (e:Evidence {project_id: $project_id}) WHERE e.metadata CONTAINS $sample_key \
+                 FOREACH (_ IN CASE WHEN e IS NULL THEN [] ELSE [1] END | MERGE (e)-[:MEASURED_IN]->(s)) \
+                 RETURN DISTINCT s",
+                serde_json::json!({
+                    "id": sample.id,
+                    "project_id": project_id,
+                    "group_id": sample.group_id,
+                    "subject_id": sample.subject_id,
+                    "matrix": sample.matrix,
+                    "record": serde_json::to_string(sample)?,
+                    "sample_key": format!("\"{}\":{}", SAMPLE_METADATA_KEY, serde_json::to_string(&sample.id)?),
+                }),
+            ).await?;
+        }
+        
+        info!("Stored {} samples in {} study groups for project {}", design.samples.len(), design.groups.len(), project_id);
+        Ok(())
+    }
+    
+    /// Samples and study groups stored for a project
+    pub async fn load_study_design(&self, project_id: &str) -> Result<StudyDesign> {
+        let driver = self.connect().await?;
+        let params = serde_json::json!({"project_id": project_id});
+        let records = |rows: Vec<HashMap<String, Value>>| -> Vec<String> {
+            rows.into_iter()
+                .filter_map(|row| row.get("record")?.as_str().map(str::to_string))
+                .collect()
+        };
+        
+        let groups = records(driver.run_query(
+            "MATCH (g:StudyGroup {project_id: $project_id}) RETURN g.record as record ORDER BY g.id",
+            params.clone(),
+        ).await?);
+        let samples = records(driver.run_query(
+            "MATCH (s:Sample {project_id: $project_id}) RETURN s.record as record ORDER BY s.id",
+            params,
+        ).await?);
+        
+        Ok(StudyDesign {
+            groups: groups.iter()
+                .map(|record| serde_json::from_str(record).context("Invalid stored study group"))
+                .collect::<Result<_>>()?,
+            samples: samples.iter()
+                .map(|record| serde_json::from_str(record).context("Invalid stored sample"))
+                .collect::<Result<_>>()?,
+        })
+    }
+    
+    /// Identification confidence of a molecule compared between study groups of a project
+    pub async fn compare_groups(&self, project_id: &str, molecule_id: &str, group_ids: &[&str]) -> Result<GroupComparison> {
+        let design = self.load_study_design(project_id).await?;
+        let evidence = self.molecule_evidence(project_id, molecule_id).await?;
+        design.compare_groups(molecule_id, &evidence, group_ids)
+    }
+    
+    /// Evidence stored for a molecule, ready to be integrated again
+    ///
+    /// Redacted payloads come back as `null`; items whose stored type is not
+    /// recognised are treated as `Other`.
+    pub async fn molecule_evidence(&self, project_id: &str, molecule_id: &str) -> Result<Vec<Evidence>> {
+        let driver = self.connect().await?;
+        let params = serde_json::json!({"id": molecule_id, "project_id": project_id});
+        
+        let rows = driver.run_query(
+            "MATCH (e:Evidence {project_id: $project_id})-[:SUPPORTS]->(m:Molecule {id: $id, project_id: $project_id}) \
+             RETURN properties(e) as e",
+            params,
+        ).await?;
+        
+        let mut evidence = Vec::new();
+        for mut row in rows {
+            let properties = match row.remove("e") {
+                Some(properties) => properties,
+                None => continue,
+            };
+            let id = match properties.get("id").and_then(|v| v.as_str()) {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+            let text = |key: &str| properties.get(key).and_then(|v| v.as_str());
+            evidence.push(Evidence {
+                id,
+                molecule_id: molecule_id.to_string(),
+                evidence_type: text("type")
+                    .and_then(|t| t.parse().ok())
+                    .unwrap_or(EvidenceType::Other),
+                source: text("source").unwrap_or("unknown").to_string(),
+                confidence: properties.get("confidence").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                data: text("data")
+                    .and_then(|d| serde_json::from_str(d).ok())
+                    .unwrap_or(Value::Null),
+                metadata: text("metadata")
+                    .and_then(|m| serde_json::from_str(m).ok())
+                    .unwrap_or_default(),
+                timestamp: text("timestamp")
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&chrono::Utc))
+                    .unwrap_or_else(chrono::Utc::now),
+            });
+        }
+        
+        debug!("Loaded {} stored evidence items for molecule {}", evidence.len(), molecule_id);
+        Ok(evidence)
+    }
+    
+    /// Molecule, evidence, conflict and activity figures for a project
+    ///
+    /// Every figure is aggregated in the database, so the cost does not grow
+    /// with the size of the evidence payloads.
+    pub async fn project_stats(&self, project_id: &str) -> Result<ProjectStats> {
+        let driver = self.connect().await?;
+        let since = trend_start();
+        let params = serde_json::json!({"project_id": project_id, "bands": CONFIDENCE_BANDS, "since": since});
+        let count = |row: &HashMap<String, Value>, key: &str| row.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let mut stats = ProjectStats::new(project_id);
+        
+        for row in driver.run_query(
+            "MATCH (m:Molecule {project_id: $project_id}) \
+             WITH CASE WHEN m.confidence IS NULL THEN -1 \
+                       ELSE toInteger(floor(m.confidence * $bands)) END AS band, \
+                  coalesce(m.conflicts, 0) > 0 AS conflicted \
+             RETURN band, count(*) AS molecules, sum(CASE WHEN conflicted THEN 1 ELSE 0 END) AS conflicted",
+            params.clone(),
+        ).await? {
+            let band = row.get("band").and_then(|v| v.as_i64()).unwrap_or(-1);
+            let band = usize::try_from(band).ok().map(|b| b.min(CONFIDENCE_BANDS - 1));
+            stats.add_molecules(band, count(&row, "molecules"), count(&row, "conflicted"));
+        }
+        
+        for row in driver.run_query(
+            "MATCH (e:Evidence {project_id: $project_id}) \
+             RETURN coalesce(e.type, 'other') AS type, coalesce(e.source, 'unknown') AS source, count(e) AS evidence",
+            params.clone(),
+        ).await? {
+            let text = |key: &str| row.get(key).and_then(|v| v.as_str()).unwrap_or("unknown");
+            stats.add_evidence(text("type"), text("source"), count(&row, "evidence"));
+        }
+        
+        for row in driver.run_query(
+            "MATCH (e:Evidence {project_id: $project_id}) \
+             WITH substring(e.timestamp, 0, 10) AS day WHERE day >= $since \
+             RETURN day, count(*) AS evidence",
+            params.clone(),
+        ).await? {
+            if let Some(day) = row.get("day").and_then(|v| v.as_str()) {
+                stats.add_evidence_day(day, count(&row, "evidence"));
+            }
+        }
+        
+        for row in driver.run_query(
+            "MATCH (m:Molecule {project_id: $project_id}) WHERE m.confidence_history_at IS NOT NULL \
+             UNWIND range(0, size(m.confidence_history_at) - 1) AS i \
+             WITH substring(m.confidence_history_at[i], 0, 10) AS day, m.confidence_history[i] AS confidence \
+             WHERE day >= $since \
+             RETURN day, count(*) AS integrations, avg(confidence) AS mean_confidence",
+            params,
+        ).await? {
+            if let Some(day) = row.get("day").and_then(|v| v.as_str()) {
+                let mean = row.get("mean_confidence").and_then(|v| v.as_f64());
+                stats.add_integration_day(day, count(&row, "integrations"), mean);
+            }
+        }
+        
+        debug!("Computed statistics for project {}: {} molecules, {} evidence items", project_id, stats.molecules, stats.evidence);
+        Ok(stats)
+    }
+    
+    /// Create or replace a molecule of a project
+    pub async fn store_molecule(&self, project_id: &str, molecule: &Node) -> Result<()> {
+        let driver = self.connect().await?;
+        self.store_node(&driver, project_id, molecule).await
+    }
+    
+    /// Get a molecule of a project, if stored
+    pub async fn get_molecule(&self, project_id: &str, molecule_id: &str) -> Result<Option<Node>> {
+        let driver = self.connect().await?;
+        let row = driver.run_query("MATCH (m:Molecule {id: $id, project_id: $project_id}) RETURN properties(m) as m",
+                                   serde_json::json!({"id": molecule_id, "project_id": project_id})).await?
+            .into_iter()
+            .find_map(|mut row| row.remove("m"));
+        
+        match row {
+            Some(data) => {
+                let mut node = self.parse_node(&data)?;
+                node.properties.remove("project_id");
+                Ok(Some(node))
+            }
+            None => Ok(None),
+        }
+    }
+    
+    /// Delete a molecule of a project with its relationships, returning whether it existed
+    pub async fn delete_molecule(&self, project_id: &str, molecule_id: &str) -> Result<bool> {
+        let driver = self.connect().await?;
+        let rows = driver.run_query(
+            "MATCH (m:Molecule {id: $id, project_id: $project_id}) DETACH DELETE m RETURN count(m) as deleted",
+            serde_json::json!({"id": molecule_id, "project_id": project_id}),
+        ).await?;
+        
+        let deleted = rows.first()
+            .and_then(|row| row.get("deleted"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        debug!("Deleted {} molecule nodes for {} in project {}", deleted, molecule_id, project_id);
+        Ok(deleted > 0)
+    }
+    
+    /// Molecules of a project with their confidence, ordered by ID
+    pub async fn list_molecules(&self, project_id: &str) -> Result<Vec<MoleculeSummary>> {
+        let driver = self.connect().await?;
+        let rows = driver.run_query(
+            "MATCH (m:Molecule {project_id: $project_id}) \
+             OPTIONAL MATCH (e:Evidence {project_id: $project_id})-[:SUPPORTS]->(m) \
+             RETURN m.id as id, m.name as name, m.confidence as confidence, count(e) as evidence_count \
+             ORDER BY m.id",
+            serde_json::json!({"project_id": project_id}),
+        ).await?;
+        
+        Ok(rows.into_iter()
+            .filter_map(|row| {
+                Some(MoleculeSummary {
+                    molecule_id: row.get("id")?.as_str()?.to_string(),
+                    name: row.get("name").and_then(|v| v.as_str()).map(str::to_string),
+                    confidence: row.get("confidence").and_then(|v| v.as_f64()),
+                    evidence_count: row.get("evidence_count").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+                })
+            })
+            .collect())
+    }
+    
+    /// Everything stored about a molecule, with its last `history_limit` confidence revisions
+    pub async fn inspect_molecule(&self, project_id: &str, molecule_id: &str, history_limit: usize) -> Result<MoleculeInspection> {
+        let driver = self.connect().await?;
+        let params = serde_json::json!({"id": molecule_id, "project_id": project_id});
+        
+        let molecule = driver.run_query("MATCH (m:Molecule {id: $id, project_id: $project_id}) RETURN properties(m) as m",
+                                        params.clone()).await?
+            .into_iter()
+            .find_map(|mut row| row.remove("m"))
+            .ok_or_else(|| anyhow!("Molecule {} not found in project {}", molecule_id, project_id))?;
+        let evidence = driver.run_query(
+            "MATCH (e:Evidence {project_id: $project_id})-[:SUPPORTS]->(m:Molecule {id: $id, project_id: $project_id}) \
+             RETURN properties(e) as e",
+            params,
+        ).await?
+            .into_iter()
+            .filter_map(|mut row| row.remove("e"))
+            .collect();
+        
+        MoleculeInspection::from_properties(project_id, molecule, evidence, history_limit)
+    }
+    
+    /// Pathways a molecule takes part in, with their member molecules
+    ///
+    /// A molecule is in a pathway directly or through a reaction it
+    /// participates in.
+    pub async fn molecule_pathways(&self, project_id: &str, molecule_id: &str) -> Result<Vec<PathwayMembership>> {
+        let driver = self.connect().await?;
+        let rows = driver.run_query(
+            "MATCH (m:Molecule {id: $id, project_id: $project_id})-[:PARTICIPATES_IN|PART_OF*1..2]->(p:Pathway) \
+             MATCH (p)<-[:PARTICIPATES_IN|PART_OF*1..2]-(n:Molecule {project_id: $project_id}) \
+             RETURN p.id as pathway_id, p.name as name, collect(DISTINCT n.id) as members \
+             ORDER BY pathway_id",
+            serde_json::json!({"id": molecule_id, "project_id": project_id}),
+        ).await?;
+        
+        Ok(rows.into_iter()
+            .filter_map(|row| {
+                Some(PathwayMembership {
+                    pathway_id: row.get("pathway_id")?.as_str()?.to_string(),
+                    name: row.get("name").and_then(|v| v.as_str()).map(str::to_string),
+                    members: row.get("members")?.as_array()?.iter()
+                        .filter_map(|id| id.as_str().map(str::to_string))
+                        .collect(),
+                })
+            })
+            .collect())
+    }
+    
+    /// Relationships from a molecule to other molecules of its project
+    pub async fn molecule_interactions(&self, project_id: &str, molecule_id: &str) -> Result<Vec<MoleculeInteraction>> {
+        let driver = self.connect().await?;
+        let rows = driver.run_query(
+            "MATCH (m:Molecule {id: $id, project_id: $project_id})-[r]->(target:Molecule {project_id: $project_id}) \
+             RETURN target.id as target_id, target.name as target_name, type(r) as type, \
+                    r.confidence as confidence, r.evidence_count as evidence_count \
+             ORDER BY target_id, type",
+            serde_json::json!({"id": molecule_id, "project_id": project_id}),
+        ).await?;
+        
+        Ok(rows.into_iter()
+            .filter_map(|row| {
+                Some(MoleculeInteraction {
+                    source_molecule: molecule_id.to_string(),
+                    target_molecule: row.get("target_id")?.as_str()?.to_string(),
+                    target_name: row.get("target_name").and_then(|v| v.as_str()).map(str::to_string),
+                    interaction_type: row.get("type")?.as_str()?.to_string(),
+                    confidence: row.get("confidence").and_then(|v| v.as_f64()),
+                    evidence_count: row.get("evidence_count").and_then(|v| v.as_u64()).unwrap_or(1) as usize,
+                })
+            })
+            .collect())
+    }
+    
+    /// Genes of a project linked to the most phenotypes, most linked first
+    ///
+    /// Phenotypes are `Disease` nodes, or `Phenotype` nodes written by
+    /// earlier genomics imports, of the same project.
+    pub async fn gene_phenotypes(&self, project_id: &str, limit: usize) -> Result<Vec<GenePhenotypes>> {
+        let driver = self.connect().await?;
+        let rows = driver.run_query(
+            "MATCH (g:Gene {project_id: $project_id})-->(p {project_id: $project_id}) \
+             WHERE p:Disease OR p:Phenotype \
+             WITH g, count(DISTINCT p) as phenotype_count \
+             ORDER BY phenotype_count DESC, g.id LIMIT $limit \
+             RETURN g.id as gene_id, g.name as gene_name, phenotype_count",
+            serde_json::json!({"project_id": project_id, "limit": limit}),
+        ).await?;
+        
+        Ok(rows.into_iter()
+            .filter_map(|row| {
+                Some(GenePhenotypes {
+                    gene_id: row.get("gene_id")?.as_str()?.to_string(),
+                    gene_name: row.get("gene_name").and_then(|v| v.as_str()).map(str::to_string),
+                    phenotype_count: row.get("phenotype_count").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+                })
+            })
+            .collect())
+    }
+    
+    /// Pathway coherence of a molecule against the molecules observed in a dataset
+    ///
+    /// `observed` maps each molecule identified in the dataset to its confidence.
+    pub async fn pathway_coherence(&self, project_id: &str, molecule_id: &str, observed: &HashMap<String, f64>) -> Result<PathwayCoherence> {
+        let pathways = self.molecule_pathways(project_id, molecule_id).await?;
+        let coherence = pathway_coherence(molecule_id, &pathways, observed);
+        debug!("Pathway coherence of {}: {:.3} over {} pathways", molecule_id, coherence.score, coherence.pathways.len());
+        Ok(coherence)
+    }
+    
+    /// Remove raw evidence payloads that have outlived the retention policy
+    ///
+    /// Each expired payload is replaced by its hash; confidences and the
+    /// molecules' integrated results are left untouched. Records are written
+    /// to the audit log before the payloads are removed, so a redaction is
+    /// never missing from the log. With `dry_run` nothing is changed.
+    pub async fn apply_retention(
+        &self,
+        policy: &RetentionPolicy,
+        audit_log: &RedactionAuditLog,
+        now: chrono::DateTime<chrono::Utc>,
+        dry_run: bool,
+    ) -> Result<Vec<RedactionRecord>> {
+        let driver = self.connect().await?;
+        
+        let rows = driver.run_query(
+            "MATCH (e:Evidence)-[:SUPPORTS]->(m:Molecule) \
+             WHERE e.data IS NOT NULL AND e.payload_sha256 IS NULL \
+             RETURN e.id as id, e.project_id as project_id, e.type as type, e.timestamp as timestamp, \
+                    e.data as data, m.id as molecule_id",
+            serde_json::json!({}),
+        ).await?;
+        
+        let mut records = Vec::new();
+        for row in &rows {
+            let field = |name: &str| row.get(name).and_then(|v| v.as_str());
+            let (id, molecule_id, evidence_type) = match (field("id"), field("molecule_id"), field("type")) {
+                (Some(id), Some(molecule_id), Some(evidence_type)) => (id, molecule_id, evidence_type),
+                _ => continue,
+            };
+            let recorded_at = match field("timestamp").and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok()) {
+                Some(t) => t.with_timezone(&chrono::Utc),
+                None => {
+                    warn!("Evidence {} has no valid timestamp; skipping retention check", id);
+                    continue;
+                }
+            };
+            let project_id = field("project_id").unwrap_or(DEFAULT_PROJECT);
+            
+            if let Some(rule) = policy.expired_rule(project_id, evidence_type, recorded_at, now) {
+                // Payloads are stored as JSON strings; hash the value they encode
+                let payload = field("data")
+                    .and_then(|d| serde_json::from_str(d).ok())
+                    .unwrap_or_else(|| row.get("data").cloned().unwrap_or(Value::Null));
+                records.push(RedactionRecord::new(id, molecule_id, project_id, evidence_type, &payload,
+                                                  recorded_at, rule, now));
+            }
+        }
+        
+        if dry_run || records.is_empty() {
+            return Ok(records);
+        }
+        
+        audit_log.append(&records)?;
+        let redact_query = "MATCH (e:Evidence {id: $id, project_id: $project_id}) \
+                            SET e.data = null, e.payload_sha256 = $payload_sha256, e.redacted_at = $redacted_at \
+                            RETURN e";
+        for record in &records {
+            let params = serde_json::json!({
+                "id": record.evidence_id,
+                "project_id": record.project_id,
+                "payload_sha256": record.payload_sha256,
+                "redacted_at": record.redacted_at.to_rfc3339(),
+            });
+            driver.run_query(redact_query, params).await?;
+        }
+        
+        info!("Redacted {} expired evidence payloads", records.len());
+        Ok(records)
+    }
+    
+    /// Rename the stored molecules of a project after an ID migration
+    ///
+    /// Each molecule keeps its former ID as an `Alias`. When a molecule with
+    /// the new ID is already stored, the evidence and aliases of the old node
+    /// move to it and the old node is removed. Returns the number of stored
+    /// molecules renamed or merged.
+    pub async fn migrate_molecule_ids(&self, project_id: &str, migration: &IdMigration) -> Result<usize> {
+        let driver = self.connect().await?;
+        let mut migrated = 0;
+        
+        for (old, new) in &migration.renamed {
+            let params = serde_json::json!({"old": old, "new": new, "project_id": project_id});
+            let rows = driver.run_query(
+                "OPTIONAL MATCH (m:Molecule {id: $old, project_id: $project_id}) \
+                 OPTIONAL MATCH (n:Molecule {id: $new, project_id: $project_id}) \
+                 RETURN count(m) as old, count(n) as new",
+                params.clone(),
+            ).await?;
+            let count = |key: &str| rows.first().and_then(|row| row.get(key)?.as_u64()).unwrap_or(0);
+            if count("old") == 0 {
+                continue;
+            }
+            
+            if count("new") > 0 {
+                self.merge_molecule(&driver, project_id, old, new).await?;
+            } else {
+                driver.run_query(
+                    "MATCH (m:Molecule {id: $old, project_id: $project_id}) SET m.id = $new",
+                    params.clone(),
+                ).await?;
+                driver.run_query(
+                    "MATCH (n:Molecule {id: $new, project_id: $project_id}) \
+                     MERGE (a:Alias {name: $old}) \
+                     MERGE (n)-[:HAS_ALIAS]->(a)",
+                    params,
+                ).await?;
+            }
+            migrated += 1;
+        }
+        
+        info!("Migrated {} stored molecule IDs in project {}", migrated, project_id);
+        Ok(migrated)
+    }
+    
+    /// Merge the stored duplicates of each molecule of a project into one node
+    ///
+    /// Duplicates are found by InChIKey or structure. Evidence, aliases and
+    /// relationships move to the canonical node, which gains the properties it
+    /// lacks; the duplicate IDs are kept as aliases. With `dry_run`, the merges
+    /// are only planned and reported.
+    pub async fn reconcile_duplicates(&self, project_id: &str, dry_run: bool) -> Result<ReconciliationReport> {
+        let driver = self.connect().await?;
+        let rows = driver.run_query(
+            "MATCH (m:Molecule {project_id: $project_id}) \
+             OPTIONAL MATCH (e:Evidence {project_id: $project_id})-[:SUPPORTS]->(m) \
+             RETURN properties(m) as m, count(e) as evidence_count",
+            serde_json::json!({"project_id": project_id}),
+        ).await?;
+        
+        let molecules: Vec<StoredMolecule> = rows.into_iter()
+            .filter_map(|row| {
+                let properties = row.get("m")?.as_object()?.clone();
+                Some(StoredMolecule {
+                    id: properties.get("id")?.as_str()?.to_string(),
+                    evidence_count: row.get("evidence_count").and_then(|v| v.as_u64()).unwrap_or(0) as usize,
+                    properties,
+                })
+            })
+            .collect();
+        let by_id: HashMap<&str, &StoredMolecule> = molecules.iter().map(|m| (m.id.as_str(), m)).collect();
+        
+        let mut report = ReconciliationReport::new(project_id, dry_run, molecules.len());
+        for group in find_duplicates(&molecules) {
+            let mut properties = by_id[group.canonical_id.as_str()].properties.clone();
+            let mut record = MergeRecord {
+                canonical_id: group.canonical_id.clone(),
+                merged_ids: group.duplicate_ids.clone(),
+                matched_by: group.matched_by,
+                evidence_moved: 0,
+                relationships_moved: 0,
+                properties_added: Vec::new(),
+                conflicts: Vec::new(),
+            };
+            for duplicate in &group.duplicate_ids {
+                let added = merge_properties(&mut properties, duplicate, &by_id[duplicate.as_str()].properties, &mut record.conflicts);
+                record.properties_added.extend(added);
+                
+                if dry_run {
+                    record.evidence_moved += by_id[duplicate.as_str()].evidence_count;
+                } else {
+                    let (evidence, relationships) = self.merge_molecule(&driver, project_id, duplicate, &group.canonical_id).await?;
+                    record.evidence_moved += evidence;
+                    record.relationships_moved += relationships;
+                }
+            }
+            
+            if !dry_run && !record.properties_added.is_empty() {
+                let additions: serde_json::Map<String, Value> = record.properties_added.iter()
+                    .filter_map(|key| Some((key.clone(), properties.get(key)?.clone())))
+                    .collect();
+                driver.run_query(
+                    "MATCH (n:Molecule {id: $id, project_id: $project_id}) SET n += $properties",
+                    serde_json::json!({"id": group.canonical_id, "project_id": project_id, "properties": additions}),
+                ).await?;
+            }
+            if !record.conflicts.is_empty() {
+                warn!("Molecule {} kept {} conflicting values from its duplicates", group.canonical_id, record.conflicts.len());
+            }
+            report.merges.push(record);
+        }
+        
+        info!("Reconciled project {}: {} duplicates {} into {} molecules",
+              project_id, report.molecules_merged(), if dry_run { "would be merged" } else { "merged" }, report.merges.len());
+        Ok(report)
+    }
+    
+    /// Merge a stored molecule into another molecule of the same project
+    ///
+    /// The evidence, aliases and relationships of `from` move to `into`, and
+    /// `from` is deleted with its ID kept as an alias. Properties are left to
+    /// the caller. Returns the number of evidence items and of other
+    /// relationships moved.
+    async fn merge_molecule(&self, driver: &Neo4jDriver, project_id: &str, from: &str, into: &str) -> Result<(usize, usize)> {
+        let params = serde_json::json!({"old": from, "new": into, "project_id": project_id});
+        let moved = |rows: Vec<HashMap<String, Value>>| {
+            rows.first().and_then(|row| row.get("moved")?.as_u64()).unwrap_or(0) as usize
+        };
+        
+        let evidence = moved(driver.run_query(
+            "MATCH (e:Evidence {project_id: $project_id})-[r:SUPPORTS]->(:Molecule {id: $old, project_id: $project_id}) \
+             MATCH (n:Molecule {id: $new, project_id: $project_id}) \
+             MERGE (e)-[:SUPPORTS]->(n) \
+             DELETE r \
+             RETURN count(r) as moved",
+            params.clone(),
+        ).await?);
+        driver.run_query(
+            "MATCH (m:Molecule {id: $old, project_id: $project_id})-[r:HAS_ALIAS]->(a:Alias) \
+             MATCH (n:Molecule {id: $new, project_id: $project_id}) \
+             MERGE (n)-[:HAS_ALIAS]->(a) \
+             DELETE r",
+            params.clone(),
+        ).await?;
+        
+        // Relationship types can't be parameters, so each type is moved in turn;
+        // a relationship already present on the canonical node keeps its properties
+        let mut relationships = 0;
+        for edge_type in EdgeType::ALL {
+            relationships += moved(driver.run_query(
+                &format!(
+                    "MATCH (m:Molecule {{id: $old, project_id: $project_id}})-[r:{edge_type}]->(o) \
+                     MATCH (n:Molecule {{id: $new, project_id: $project_id}}) \
+                     WHERE o <> n \
+                     MERGE (n)-[s:{edge_type}]->(o) ON CREATE SET s = properties(r) \
+                     DELETE r \
+                     RETURN count(r) as moved",
+                    edge_type = edge_type,
+                ),
+                params.clone(),
+            ).await?);
+            relationships += moved(driver.run_query(
+                &format!(
+                    "MATCH (o)-[r:{edge_type}]->(m:Molecule {{id: $old, project_id: $project_id}}) \
+                     MATCH (n:Molecule {{id: $new, project_id: $project_id}}) \
+                     WHERE o <> n \
+                     MERGE (o)-[s:{edge_type}]->(n) ON CREATE SET s = properties(r) \
+                     DELETE r \
+                     RETURN count(r) as moved",
+                    edge_type = edge_type,
+                ),
+                params.clone(),
+            ).await?);
+        }
+        
+        driver.run_query(
+            "MATCH (m:Molecule {id: $old, project_id: $project_id}) DETACH DELETE m",
+            params.clone(),
+        ).await?;
+        driver.run_query(
+            "MATCH (n:Molecule {id: $new, project_id: $project_id}) \
+             MERGE (a:Alias {name: $old}) \
+             MERGE (n)-[:HAS_ALIAS]->(a)",
+            params,
+        ).await?;
+        
+        debug!("Merged molecule {} into {}: {} evidence, {} relationships", from, into, evidence, relationships);
+        Ok((evidence, relationships))
+    }
+    
+    /// Fill in the external IDs of molecule nodes before storing them
+    ///
+    /// Each node is resolved from its first valid external ID, or from its ID
+    /// otherwise. Returns the number of nodes that gained identifiers.
+    pub async fn populate_external_ids(&self, graph: &mut MolecularGraph, xrefs: &XrefService) -> Result<usize> {
+        let mut updated = 0;
+        
+        for node in graph.nodes.iter_mut().filter(|n| matches!(n.node_type, NodeType::Molecule)) {
+            let seed = node.external_ids.iter()
+                .find_map(|(system, id)| MoleculeIdentifier::new(system.parse().ok()?, id).ok())
+                .or_else(|| MoleculeIdentifier::parse(&node.id).ok());
+            let seed = match seed {
+                Some(seed) => seed,
+                None => continue,
+            };
+            
+            let resolved = xrefs.resolve(&seed).await?;
+            let before = node.external_ids.len();
+            for (system, id) in resolved.external_ids {
+                node.external_ids.entry(system).or_insert(id);
+            }
+            if node.external_ids.len() > before {
+                updated += 1;
+            }
+        }
+        
+        debug!("Populated external IDs for {} nodes in graph {}", updated, graph.id);
+        Ok(updated)
+    }
+    
     /// Store a node in Neo4j
-    async fn store_node(&self, driver: &Neo4jDriver, node: &Node) -> Result<()> {
+    async fn store_node(&self, driver: &Neo4jDriver, project_id: &str, node: &Node) -> Result<()> {
         debug!("Storing node {} in Neo4j", node.id);
+        node.validate()?;
         
         // Convert node properties to a JSON object
         let mut properties = serde_json::Map::new();
         properties.insert("id".to_string(), serde_json::json!(node.id));
         properties.insert("name".to_string(), serde_json::json!(node.name));
+        properties.insert("project_id".to_string(), serde_json::json!(project_id));
         
         // Add custom properties
         for (key, value) in &node.properties {
@@ -153,12 +1152,13 @@ impl Neo4jClient {
         
         // Create Cypher query
         let query = format!(
-            "MERGE (n:{} {{id: $id}}) SET n = $properties RETURN n",
+            "MERGE (n:{} {{id: $id, project_id: $project_id}}) SET n = $properties RETURN n",
             node.node_type.to_string()
         );
         
         let params = serde_json::json!({
             "id": node.id,
+            "project_id": project_id,
             "properties": properties,
         });
         
@@ -169,8 +1169,9 @@ impl Neo4jClient {
     }
     
     /// Store an edge in Neo4j
-    async fn store_edge(&self, driver: &Neo4jDriver, edge: &Edge) -> Result<()> {
+    async fn store_edge(&self, driver: &Neo4jDriver, project_id: &str, edge: &Edge) -> Result<()> {
         debug!("Storing edge {} in Neo4j", edge.id);
+        edge.validate()?;
         
         // Convert edge properties to a JSON object
         let mut properties = serde_json::Map::new();
@@ -183,7 +1184,7 @@ impl Neo4jClient {
         
         // Create Cypher query
         let query = format!(
-            "MATCH (source {{id: $source_id}}), (target {{id: $target_id}}) \
+            "MATCH (source {{id: $source_id, project_id: $project_id}}), (target {{id: $target_id, project_id: $project_id}}) \
              MERGE (source)-[r:{}]->(target) \
              SET r = $properties \
              RETURN r",
@@ -193,6 +1194,7 @@ impl Neo4jClient {
         let params = serde_json::json!({
             "source_id": edge.source_id,
             "target_id": edge.target_id,
+            "project_id": project_id,
             "properties": properties,
         });
         
@@ -203,14 +1205,14 @@ impl Neo4jClient {
     }
     
     /// Retrieve a molecular graph from Neo4j
-    pub async fn retrieve_graph(&self, graph_id: &str) -> Result<MolecularGraph> {
+    pub async fn retrieve_graph(&self, project_id: &str, graph_id: &str) -> Result<MolecularGraph> {
         let driver = self.connect().await?;
         
-        info!("Retrieving graph {} from Neo4j", graph_id);
+        info!("Retrieving graph {} of project {} from Neo4j", graph_id, project_id);
         
         // Retrieve graph metadata
-        let metadata_query = "MATCH (g:Graph {id: $graph_id}) RETURN g";
-        let metadata_params = serde_json::json!({"graph_id": graph_id});
+        let metadata_query = "MATCH (g:Graph {id: $graph_id, project_id: $project_id}) RETURN g";
+        let metadata_params = serde_json::json!({"graph_id": graph_id, "project_id": project_id});
         
         let metadata_result = driver.run_query(metadata_query, metadata_params).await?;
         
@@ -230,10 +1232,11 @@ impl Neo4jClient {
         
         // Create empty graph
         let mut graph = MolecularGraph::new(graph_id.to_string(), graph_name);
+        graph.set_project(project_id);
         
         // Retrieve nodes
-        let nodes_query = "MATCH (n)-[:PART_OF]->(g:Graph {id: $graph_id}) RETURN n";
-        let nodes_params = serde_json::json!({"graph_id": graph_id});
+        let nodes_query = "MATCH (n {project_id: $project_id})-[:PART_OF]->(g:Graph {id: $graph_id, project_id: $project_id}) RETURN n";
+        let nodes_params = serde_json::json!({"graph_id": graph_id, "project_id": project_id});
         
         let nodes_result = driver.run_query(nodes_query, nodes_params).await?;
         
@@ -246,8 +1249,8 @@ impl Neo4jClient {
         }
         
         // Retrieve edges
-        let edges_query = "MATCH (s)-[r]->(t) WHERE (s)-[:PART_OF]->(:Graph {id: $graph_id}) AND (t)-[:PART_OF]->(:Graph {id: $graph_id}) RETURN s.id as source, t.id as target, type(r) as type, r";
-        let edges_params = serde_json::json!({"graph_id": graph_id});
+        let edges_query = "MATCH (s {project_id: $project_id})-[r]->(t {project_id: $project_id}) WHERE (s)-[:PART_OF]->(:Graph {id: $graph_id, project_id: $project_id}) AND (t)-[:PART_OF]->(:Graph {id: $graph_id, project_id: $project_id}) RETURN s.id as source, t.id as target, type(r) as type, r";
+        let edges_params = serde_json::json!({"graph_id": graph_id, "project_id": project_id});
         
         let edges_result = driver.run_query(edges_query, edges_params).await?;
         
@@ -270,6 +1273,98 @@ impl Neo4jClient {
         Ok(graph)
     }
     
+    /// Shortest paths between two molecules of a project, cheapest first
+    ///
+    /// Neo4j finds all shortest paths of at most `max_hops` relationships
+    /// (1 - 10); they are then costed like network paths, each step costing
+    /// one minus its similarity, weight or confidence.
+    pub async fn find_paths(&self, project_id: &str, from: &str, to: &str, max_hops: usize, limit: usize) -> Result<Vec<MoleculePath>> {
+        if !(1..=10).contains(&max_hops) {
+            return Err(anyhow!("max_hops must be between 1 and 10, got {}", max_hops));
+        }
+        let driver = self.connect().await?;
+        
+        // Variable-length bounds cannot be parameters, hence the formatted query
+        let query = format!(
+            "MATCH (a:Molecule {{id: $from, project_id: $project_id}}), (b:Molecule {{id: $to, project_id: $project_id}}) \
+             MATCH p = allShortestPaths((a)-[*..{}]-(b)) \
+             WHERE all(n IN nodes(p) WHERE n.project_id = $project_id) \
+             RETURN [n IN nodes(p) | n.id] as molecules, [r IN relationships(p) | type(r)] as relationships, \
+                    [r IN relationships(p) | coalesce(r.similarity, r.weight, r.confidence, 1.0)] as weights \
+             LIMIT $limit",
+            max_hops
+        );
+        let params = serde_json::json!({"project_id": project_id, "from": from, "to": to, "limit": limit});
+        let rows = driver.run_query(&query, params).await?;
+        
+        let strings = |value: Option<&Value>| -> Vec<String> {
+            value.and_then(|v| v.as_array())
+                .map(|items| items.iter().filter_map(|i| i.as_str().map(String::from)).collect())
+                .unwrap_or_default()
+        };
+        let mut paths: Vec<MoleculePath> = rows.iter()
+            .map(|row| {
+                let weights: Vec<f64> = row.get("weights")
+                    .and_then(|v| v.as_array())
+                    .map(|items| items.iter().map(|w| w.as_f64().unwrap_or(1.0)).collect())
+                    .unwrap_or_default();
+                MoleculePath {
+                    molecules: strings(row.get("molecules")),
+                    relationships: strings(row.get("relationships")),
+                    cost: weights.iter().map(|w| (1.0 - w).max(0.0)).sum(),
+                    weights,
+                }
+            })
+            .collect();
+        paths.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+        
+        debug!("Found {} paths between {} and {} in project {}", paths.len(), from, to, project_id);
+        Ok(paths)
+    }
+    
+    /// Molecules within `options.radius` similarity hops of a molecule, or `None` if it is not stored
+    ///
+    /// Each hop is one query for the neighbors of the whole frontier.
+    pub async fn neighborhood(&self, project_id: &str, molecule_id: &str, options: &NeighborhoodOptions) -> Result<Option<MoleculeNetwork>> {
+        let center = match self.get_molecule(project_id, molecule_id).await? {
+            Some(center) => center,
+            None => return Ok(None),
+        };
+        let mut search = NeighborhoodSearch::new(molecule_node(&center), options)?;
+        let driver = self.connect().await?;
+        
+        while !search.frontier().is_empty() {
+            let rows = driver.run_query(
+                "MATCH (m:Molecule {project_id: $project_id})-[r:SIMILAR_TO]-(n:Molecule {project_id: $project_id}) \
+                 WHERE m.id IN $frontier AND coalesce(r.similarity, r.weight, 0.0) >= $min_similarity \
+                 RETURN m.id as from, properties(n) as neighbor, coalesce(r.similarity, r.weight, 0.0) as similarity",
+                serde_json::json!({
+                    "project_id": project_id,
+                    "frontier": search.frontier(),
+                    "min_similarity": search.min_similarity(),
+                }),
+            ).await?;
+            
+            let mut edges = Vec::with_capacity(rows.len());
+            for row in &rows {
+                let (from, data) = match (row.get("from").and_then(|v| v.as_str()), row.get("neighbor")) {
+                    (Some(from), Some(data)) => (from, data),
+                    _ => continue,
+                };
+                let mut neighbor = self.parse_node(data)?;
+                neighbor.properties.remove("project_id");
+                edges.push(NeighborEdge {
+                    from: from.to_string(),
+                    neighbor: molecule_node(&neighbor),
+                    similarity: row.get("similarity").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                });
+            }
+            search.expand(edges);
+        }
+        
+        search.finish().map(Some)
+    }
+    
     /// Run a custom Cypher query
     pub async fn run_query(&self, query: &str, params: serde_json::Value) -> Result<Vec<HashMap<String, Value>>> {
         let driver = self.connect().await?;
@@ -289,14 +1384,11 @@ impl Neo4jClient {
             .unwrap_or("Unknown")
             .to_string();
             
-        // Attempt to determine node type from labels
-        let node_type = if data.get("labels").is_some() {
-            // In a real implementation, we would parse the labels
-            // For now, default to Molecule
-            NodeType::Molecule
-        } else {
-            NodeType::Molecule
-        };
+        // The node type is the first label that names one, defaulting to Molecule
+        let node_type = data.get("labels")
+            .and_then(|v| v.as_array())
+            .and_then(|labels| labels.iter().filter_map(|l| l.as_str()).find_map(|l| l.parse::<NodeType>().ok()))
+            .unwrap_or(NodeType::Molecule);
         
         // Create node
         let mut node = Node::new(id, node_type, name);
@@ -305,7 +1397,7 @@ impl Neo4jClient {
         if let Some(obj) = data.as_object() {
             for (key, value) in obj {
                 // Skip special fields
-                if !["id", "name", "labels"].contains(&key.as_str()) {
+                if !RESERVED_NODE_PROPERTIES.contains(&key.as_str()) {
                     // Check if it's an external ID (prefixed with ext_)
                     if key.starts_with("ext_") {
                         if let Some(id_str) = value.as_str() {
@@ -326,20 +1418,7 @@ impl Neo4jClient {
     /// Parse an edge from Neo4j data
     fn parse_edge(&self, source_id: &str, target_id: &str, edge_type: &str, data: &Value) -> Result<Edge> {
         // Parse edge type
-        let edge_type_enum = match edge_type {
-            "SIMILAR_TO" => EdgeType::SimilarTo,
-            "PART_OF" => EdgeType::PartOf,
-            "INTERACTS_WITH" => EdgeType::InteractsWith,
-            "INHIBITS" => EdgeType::Inhibits,
-            "ACTIVATES" => EdgeType::Activates,
-            "TREATS" => EdgeType::Treats,
-            "CAUSES" => EdgeType::Causes,
-            "REFERENCED_BY" => EdgeType::ReferencedBy,
-            "SOURCED_FROM" => EdgeType::SourcedFrom,
-            "TRANSFORMS_TO" => EdgeType::TransformsTo,
-            "METABOLIZED_BY" => EdgeType::MetabolizedBy,
-            _ => return Err(anyhow!("Unknown edge type: {}", edge_type)),
-        };
+        let edge_type_enum: EdgeType = edge_type.parse()?;
         
         // Create edge
         let mut edge = Edge::new(
@@ -352,7 +1431,7 @@ impl Neo4jClient {
         if let Some(obj) = data.as_object() {
             for (key, value) in obj {
                 // Skip special fields
-                if !["id"].contains(&key.as_str()) {
+                if !RESERVED_EDGE_PROPERTIES.contains(&key.as_str()) {
                     edge.add_property(key, value.clone());
                 }
             }
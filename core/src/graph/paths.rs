@@ -0,0 +1,330 @@
+//! Path Queries
+//!
+//! Finds how two molecules are related through a network: the cheapest path
+//! between them and, with Yen's algorithm, the next-cheapest alternatives.
+//! By default a step costs `1 - similarity`, so the best path runs through
+//! the most similar neighbours; costs can instead be set per edge type or be
+//! a plain hop count. Paths can be limited in length and kept away from
+//! particular molecules.
+
+use anyhow::{anyhow, Result};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::{Serialize, Deserialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::{EdgeWeight, MoleculeNetwork};
+
+/// Cost of traversing an edge
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathCost {
+    /// One minus the edge's similarity
+    #[default]
+    Dissimilarity,
+    /// Every edge costs one
+    Hops,
+    /// Fixed cost per edge type (e.g. `similarity`); unlisted types cost one
+    EdgeType(HashMap<String, f64>),
+}
+
+impl PathCost {
+    /// Cost of an edge; never negative
+    pub fn cost(&self, weight: &EdgeWeight) -> f64 {
+        let cost = match self {
+            PathCost::Dissimilarity => 1.0 - weight.similarity(),
+            PathCost::Hops => 1.0,
+            PathCost::EdgeType(costs) => costs.get(weight.type_name()).copied().unwrap_or(1.0),
+        };
+        cost.max(0.0)
+    }
+}
+
+/// Constraints on the paths searched for
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PathOptions {
+    /// How edges are costed
+    pub cost: PathCost,
+
+    /// Longest path allowed, in edges
+    pub max_hops: Option<usize>,
+
+    /// Molecules the path must not pass through
+    pub avoid: HashSet<String>,
+}
+
+impl PathOptions {
+    /// Cost edges differently
+    pub fn with_cost(mut self, cost: PathCost) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// Allow at most `max_hops` edges
+    pub fn with_max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = Some(max_hops);
+        self
+    }
+
+    /// Keep paths away from a molecule
+    pub fn avoiding(mut self, molecule_id: &str) -> Self {
+        self.avoid.insert(molecule_id.to_string());
+        self
+    }
+}
+
+/// Path between two molecules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoleculePath {
+    /// Molecule IDs from start to end
+    pub molecules: Vec<String>,
+
+    /// Type of each step's edge
+    pub relationships: Vec<String>,
+
+    /// Similarity of each step's edge
+    pub weights: Vec<f64>,
+
+    /// Total cost of the path
+    pub cost: f64,
+}
+
+impl MoleculePath {
+    /// Number of edges in the path
+    pub fn hops(&self) -> usize {
+        self.molecules.len().saturating_sub(1)
+    }
+}
+
+/// Path as node and edge indices, used during search
+#[derive(Debug, Clone)]
+struct IndexPath {
+    nodes: Vec<NodeIndex>,
+    edges: Vec<EdgeIndex>,
+    cost: f64,
+}
+
+/// Search frontier entry, ordered so the heap pops the cheapest first
+struct Frontier {
+    cost: f64,
+    node: NodeIndex,
+    hops: usize,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| other.hops.cmp(&self.hops))
+    }
+}
+
+/// Undirected node pair identifying the edges between two molecules
+fn pair(a: NodeIndex, b: NodeIndex) -> (NodeIndex, NodeIndex) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+impl MoleculeNetwork {
+    /// Cheapest path between two molecules, costing each step `1 - similarity`
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<MoleculePath> {
+        self.find_path(from, to, &PathOptions::default()).ok().flatten()
+    }
+
+    /// Cheapest path between two molecules under the given constraints
+    ///
+    /// Fails if either molecule is not in the network; returns `None` if they
+    /// are not connected within the constraints.
+    pub fn find_path(&self, from: &str, to: &str, options: &PathOptions) -> Result<Option<MoleculePath>> {
+        Ok(self.k_shortest_paths(from, to, 1, options)?.into_iter().next())
+    }
+
+    /// Up to `k` cheapest loop-free paths between two molecules, cheapest first
+    pub fn k_shortest_paths(&self, from: &str, to: &str, k: usize, options: &PathOptions) -> Result<Vec<MoleculePath>> {
+        let source = self.node_index(from).ok_or_else(|| anyhow!("Molecule not found: {}", from))?;
+        let target = self.node_index(to).ok_or_else(|| anyhow!("Molecule not found: {}", to))?;
+        let avoided: HashSet<NodeIndex> = options.avoid.iter()
+            .filter_map(|id| self.id_to_node.get(id).copied())
+            .filter(|idx| *idx != source && *idx != target)
+            .collect();
+
+        let mut found: Vec<IndexPath> = Vec::new();
+        let first = self.cheapest(source, target, options, &avoided, &HashSet::new(), options.max_hops);
+        let mut candidates: Vec<IndexPath> = first.into_iter().collect();
+
+        // Yen's algorithm: each further path deviates from an earlier one at some spur node
+        while found.len() < k && !candidates.is_empty() {
+            let best = (0..candidates.len())
+                .min_by(|&a, &b| candidates[a].cost.total_cmp(&candidates[b].cost))
+                .unwrap_or(0);
+            let path = candidates.swap_remove(best);
+
+            for i in 0..path.edges.len() {
+                let root = &path.nodes[..=i];
+                let banned_edges: HashSet<(NodeIndex, NodeIndex)> = found.iter()
+                    .chain(std::iter::once(&path))
+                    .filter(|p| p.nodes.len() > i + 1 && p.nodes[..=i] == *root)
+                    .map(|p| pair(p.nodes[i], p.nodes[i + 1]))
+                    .collect();
+                let mut banned_nodes = avoided.clone();
+                banned_nodes.extend(root[..i].iter().copied());
+
+                let budget = options.max_hops.map(|max| max.saturating_sub(i));
+                if let Some(spur) = self.cheapest(root[i], target, options, &banned_nodes, &banned_edges, budget) {
+                    let root_cost: f64 = path.edges[..i].iter()
+                        .map(|e| options.cost.cost(&self.graph[*e]))
+                        .sum();
+                    let mut nodes = root[..i].to_vec();
+                    nodes.extend(spur.nodes);
+                    let mut edges = path.edges[..i].to_vec();
+                    edges.extend(spur.edges);
+                    let candidate = IndexPath { nodes, edges, cost: root_cost + spur.cost };
+
+                    let duplicate = candidates.iter().chain(found.iter()).chain(std::iter::once(&path))
+                        .any(|p| p.nodes == candidate.nodes);
+                    if !duplicate {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+            found.push(path);
+        }
+
+        Ok(found.into_iter().map(|p| self.to_molecule_path(p)).collect())
+    }
+
+    /// Dijkstra, over (node, hops) states when a hop limit makes them matter
+    fn cheapest(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+        options: &PathOptions,
+        banned_nodes: &HashSet<NodeIndex>,
+        banned_edges: &HashSet<(NodeIndex, NodeIndex)>,
+        max_hops: Option<usize>,
+    ) -> Option<IndexPath> {
+        // Without a hop limit every route to a node is interchangeable
+        let state = |node: NodeIndex, hops: usize| (node, if max_hops.is_some() { hops } else { 0 });
+        let mut best: HashMap<(NodeIndex, usize), f64> = HashMap::from([((source, 0), 0.0)]);
+        let mut previous: HashMap<(NodeIndex, usize), (NodeIndex, EdgeIndex)> = HashMap::new();
+        let mut heap = BinaryHeap::from([Frontier { cost: 0.0, node: source, hops: 0 }]);
+
+        while let Some(Frontier { cost, node, hops }) = heap.pop() {
+            if node == target {
+                let mut nodes = vec![target];
+                let mut edges = Vec::new();
+                let mut current = (target, hops);
+                while let Some(&(prev, edge)) = previous.get(&state(current.0, current.1)) {
+                    nodes.push(prev);
+                    edges.push(edge);
+                    current = (prev, current.1 - 1);
+                }
+                nodes.reverse();
+                edges.reverse();
+                return Some(IndexPath { nodes, edges, cost });
+            }
+            if best.get(&state(node, hops)).is_some_and(|&c| cost > c) || max_hops.is_some_and(|max| hops >= max) {
+                continue;
+            }
+
+            for edge in self.graph.edges(node) {
+                let next = if edge.source() == node { edge.target() } else { edge.source() };
+                if next == node || banned_nodes.contains(&next) || banned_edges.contains(&pair(node, next)) {
+                    continue;
+                }
+                let next_cost = cost + options.cost.cost(edge.weight());
+                let next_state = state(next, hops + 1);
+                if best.get(&next_state).is_none_or(|&c| next_cost < c) {
+                    best.insert(next_state, next_cost);
+                    previous.insert(next_state, (node, edge.id()));
+                    heap.push(Frontier { cost: next_cost, node: next, hops: hops + 1 });
+                }
+            }
+        }
+        None
+    }
+
+    fn to_molecule_path(&self, path: IndexPath) -> MoleculePath {
+        MoleculePath {
+            molecules: path.nodes.iter().map(|n| self.graph[*n].id.clone()).collect(),
+            relationships: path.edges.iter().map(|e| self.graph[*e].type_name().to_string()).collect(),
+            weights: path.edges.iter().map(|e| self.graph[*e].similarity()).collect(),
+            cost: path.cost,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::Molecule;
+
+    fn network() -> MoleculeNetwork {
+        let mut network = MoleculeNetwork::new();
+        for id in ["a", "b", "c", "d", "e"] {
+            network.add_molecule(&Molecule {
+                id: id.to_string(),
+                smiles: "C".to_string(),
+                inchi: None,
+                inchi_key: None,
+                name: None,
+                formula: None,
+                molecular_weight: None,
+                properties: HashMap::new(),
+                ..Default::default()
+            });
+        }
+        // a-d directly is one weak hop; a-b-c-d is three strong ones
+        network.add_similarity("a", "d", 0.2);
+        network.add_similarity("a", "b", 0.9);
+        network.add_similarity("b", "c", 0.9);
+        network.add_similarity("c", "d", 0.9);
+        network.add_similarity("b", "d", 0.5);
+        network
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let network = network();
+        let path = network.shortest_path("a", "d").unwrap();
+        assert_eq!(path.molecules, vec!["a", "b", "c", "d"]);
+        assert!((path.cost - 0.3).abs() < 1e-9);
+
+        let limited = network.find_path("a", "d", &PathOptions::default().with_max_hops(2)).unwrap().unwrap();
+        assert_eq!(limited.molecules, vec!["a", "b", "d"]);
+
+        let hops = network.find_path("a", "d", &PathOptions::default().with_cost(PathCost::Hops)).unwrap().unwrap();
+        assert_eq!(hops.hops(), 1);
+
+        assert!(network.find_path("a", "e", &PathOptions::default()).unwrap().is_none());
+        assert!(network.find_path("a", "z", &PathOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_k_shortest_paths() {
+        let network = network();
+        let paths = network.k_shortest_paths("a", "d", 5, &PathOptions::default()).unwrap();
+        let routes: Vec<Vec<String>> = paths.iter().map(|p| p.molecules.clone()).collect();
+        assert_eq!(routes, vec![
+            vec!["a", "b", "c", "d"],
+            vec!["a", "b", "d"],
+            vec!["a", "d"],
+        ]);
+        assert!(paths.windows(2).all(|w| w[0].cost <= w[1].cost));
+
+        let avoiding = network.k_shortest_paths("a", "d", 5, &PathOptions::default().avoiding("b")).unwrap();
+        assert_eq!(avoiding.len(), 1);
+    }
+}
@@ -0,0 +1,329 @@
+//! Pathway Coherence
+//!
+//! A molecule identified in a sample is more plausible when the other members
+//! of its pathways were identified too. The coherence of a pathway is the
+//! share of the molecule's co-members observed in the current dataset, each
+//! counted by its identification confidence, and the molecule's score is that
+//! of its most coherent pathway: one active pathway is enough to explain it.
+
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::processing::evidence::{Evidence, EvidenceType};
+
+/// Source of the evidence derived from pathway coherence
+pub const PATHWAY_COHERENCE_SOURCE: &str = "pathway-coherence";
+
+/// A pathway and the molecules taking part in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathwayMembership {
+    /// Pathway ID
+    pub pathway_id: String,
+
+    /// Pathway name
+    pub name: Option<String>,
+
+    /// IDs of the molecules in the pathway
+    pub members: Vec<String>,
+}
+
+/// Coherence of one of a molecule's pathways
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathwayScore {
+    /// Pathway ID
+    pub pathway_id: String,
+
+    /// Pathway name
+    pub name: Option<String>,
+
+    /// Other molecules in the pathway
+    pub co_members: usize,
+
+    /// Co-members observed in the dataset
+    pub observed: Vec<String>,
+
+    /// Confidence-weighted share of co-members observed (0.0 - 1.0)
+    pub coherence: f64,
+}
+
+/// Pathway coherence of a molecule, with a breakdown per pathway
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathwayCoherence {
+    /// Molecule ID
+    pub molecule_id: String,
+
+    /// Coherence of the most coherent pathway (0.0 - 1.0)
+    pub score: f64,
+
+    /// Pathways with other members, most coherent first
+    pub pathways: Vec<PathwayScore>,
+}
+
+impl PathwayCoherence {
+    /// Pathway evidence for the molecule, with the breakdown as its data
+    ///
+    /// `None` when none of the molecule's pathways has other members, since
+    /// the molecule's pathways then say nothing about the sample.
+    pub fn to_evidence(&self) -> Option<Evidence> {
+        if self.pathways.is_empty() {
+            return None;
+        }
+        let best = &self.pathways[0];
+        let mut metadata = HashMap::new();
+        metadata.insert("pathway_id".to_string(), serde_json::json!(best.pathway_id));
+
+        Some(Evidence {
+            id: format!("{}-{}", PATHWAY_COHERENCE_SOURCE, self.molecule_id),
+            molecule_id: self.molecule_id.clone(),
+            evidence_type: EvidenceType::Pathway,
+            source: PATHWAY_COHERENCE_SOURCE.to_string(),
+            confidence: self.score,
+            data: serde_json::json!({ "pathways": self.pathways }),
+            metadata,
+            timestamp: chrono::Utc::now(),
+        })
+    }
+}
+
+/// Score a molecule's pathways against the molecules observed in a dataset
+///
+/// `observed` maps the ID of each molecule identified in the dataset to its
+/// confidence. Pathways whose only member is the molecule itself are left out.
+pub fn pathway_coherence(
+    molecule_id: &str,
+    pathways: &[PathwayMembership],
+    observed: &HashMap<String, f64>,
+) -> PathwayCoherence {
+    let mut scores: Vec<PathwayScore> = pathways.iter()
+        .filter_map(|pathway| {
+            let co_members: BTreeSet<&str> = pathway.members.iter()
+                .map(String::as_str)
+                .filter(|id| *id != molecule_id)
+                .collect();
+            if co_members.is_empty() {
+                return None;
+            }
+
+            let seen: Vec<(&str, f64)> = co_members.iter()
+                .filter_map(|id| Some((*id, observed.get(*id)?.clamp(0.0, 1.0))))
+                .collect();
+            let weight: f64 = seen.iter().map(|(_, confidence)| confidence).sum();
+            Some(PathwayScore {
+                pathway_id: pathway.pathway_id.clone(),
+                name: pathway.name.clone(),
+                co_members: co_members.len(),
+                observed: seen.iter().map(|(id, _)| id.to_string()).collect(),
+                coherence: weight / co_members.len() as f64,
+            })
+        })
+        .collect();
+    scores.sort_by(|a, b| b.coherence.total_cmp(&a.coherence).then_with(|| a.pathway_id.cmp(&b.pathway_id)));
+
+    PathwayCoherence {
+        molecule_id: molecule_id.to_string(),
+        score: scores.first().map(|s| s.coherence).unwrap_or(0.0),
+        pathways: scores,
+    }
+}
+
+/// A co-member of a pathway and whether it was observed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoMember {
+    /// Molecule ID
+    pub molecule_id: String,
+
+    /// Common name, if one is known
+    pub name: Option<String>,
+
+    /// Whether the molecule was identified in the dataset
+    pub observed: bool,
+
+    /// Identification confidence, when observed
+    pub confidence: Option<f64>,
+}
+
+/// One pathway of a coherence view with all of its co-members
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathwayView {
+    /// Pathway ID
+    pub pathway_id: String,
+
+    /// Pathway name
+    pub name: Option<String>,
+
+    /// Confidence-weighted share of co-members observed (0.0 - 1.0)
+    pub coherence: f64,
+
+    /// Co-members, observed ones first by confidence
+    pub co_members: Vec<CoMember>,
+}
+
+/// Pathway by co-member matrix for heatmaps
+///
+/// `values[i][j]` is the confidence of `molecules[j]` in `pathways[i]`: `null`
+/// when the molecule is not in the pathway and 0.0 when it is but was not
+/// observed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoherenceHeatmap {
+    /// Pathway IDs, one per row, in the order of the view
+    pub pathways: Vec<String>,
+
+    /// Co-member IDs, one per column, shared ones first
+    pub molecules: Vec<String>,
+
+    /// Confidence cells, by row then column
+    pub values: Vec<Vec<Option<f64>>>,
+}
+
+/// Pathway coherence of a molecule laid out for rendering
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathwayCoherenceView {
+    /// Molecule ID
+    pub molecule_id: String,
+
+    /// Coherence of the most coherent pathway (0.0 - 1.0)
+    pub score: f64,
+
+    /// Pathways with other members, most coherent first
+    pub pathways: Vec<PathwayView>,
+
+    /// The same pathways as a matrix over their co-members
+    pub heatmap: CoherenceHeatmap,
+}
+
+/// Score a molecule's pathways and list every co-member with its observation
+///
+/// Scores are those of `pathway_coherence`; `names` maps molecule IDs to the
+/// names shown for them.
+pub fn coherence_view(
+    molecule_id: &str,
+    pathways: &[PathwayMembership],
+    observed: &HashMap<String, f64>,
+    names: &HashMap<String, String>,
+) -> PathwayCoherenceView {
+    let coherence = pathway_coherence(molecule_id, pathways, observed);
+    let by_id: HashMap<&str, &PathwayMembership> = pathways.iter().map(|p| (p.pathway_id.as_str(), p)).collect();
+
+    let views: Vec<PathwayView> = coherence.pathways.iter()
+        .map(|score| {
+            let members: BTreeSet<&str> = by_id.get(score.pathway_id.as_str())
+                .map(|p| p.members.iter().map(String::as_str).filter(|id| *id != molecule_id).collect())
+                .unwrap_or_default();
+            let mut co_members: Vec<CoMember> = members.into_iter()
+                .map(|id| {
+                    let confidence = observed.get(id).map(|c| c.clamp(0.0, 1.0));
+                    CoMember {
+                        molecule_id: id.to_string(),
+                        name: names.get(id).cloned(),
+                        observed: confidence.is_some(),
+                        confidence,
+                    }
+                })
+                .collect();
+            co_members.sort_by(|a, b| {
+                b.confidence.unwrap_or(-1.0).total_cmp(&a.confidence.unwrap_or(-1.0))
+                    .then_with(|| a.molecule_id.cmp(&b.molecule_id))
+            });
+            PathwayView {
+                pathway_id: score.pathway_id.clone(),
+                name: score.name.clone(),
+                coherence: score.coherence,
+                co_members,
+            }
+        })
+        .collect();
+
+    let mut shared: BTreeMap<&str, usize> = BTreeMap::new();
+    for view in &views {
+        for member in &view.co_members {
+            *shared.entry(member.molecule_id.as_str()).or_default() += 1;
+        }
+    }
+    let mut molecules: Vec<(&str, usize)> = shared.into_iter().collect();
+    molecules.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let molecules: Vec<String> = molecules.into_iter().map(|(id, _)| id.to_string()).collect();
+
+    let values = views.iter()
+        .map(|view| {
+            molecules.iter()
+                .map(|id| view.co_members.iter()
+                    .find(|m| &m.molecule_id == id)
+                    .map(|m| m.confidence.unwrap_or(0.0)))
+                .collect()
+        })
+        .collect();
+    let heatmap = CoherenceHeatmap {
+        pathways: views.iter().map(|v| v.pathway_id.clone()).collect(),
+        molecules,
+        values,
+    };
+
+    PathwayCoherenceView {
+        molecule_id: coherence.molecule_id,
+        score: coherence.score,
+        pathways: views,
+        heatmap,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pathway(id: &str, members: &[&str]) -> PathwayMembership {
+        PathwayMembership {
+            pathway_id: id.to_string(),
+            name: None,
+            members: members.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_coherence_weights_observed_co_members() {
+        let pathways = vec![
+            pathway("glycolysis", &["glucose", "pyruvate", "lactate", "glucose"]),
+            pathway("tca", &["pyruvate", "citrate", "succinate", "fumarate", "malate"]),
+            pathway("orphan", &["pyruvate"]),
+        ];
+        let observed: HashMap<String, f64> = [("glucose", 0.9), ("lactate", 0.5), ("citrate", 0.8)]
+            .iter()
+            .map(|(id, c)| (id.to_string(), *c))
+            .collect();
+
+        let coherence = pathway_coherence("pyruvate", &pathways, &observed);
+        assert_eq!(coherence.pathways.len(), 2);
+        assert_eq!(coherence.pathways[0].pathway_id, "glycolysis");
+        assert_eq!(coherence.pathways[0].co_members, 2);
+        assert!((coherence.score - 0.7).abs() < 1e-12);
+        assert!((coherence.pathways[1].coherence - 0.2).abs() < 1e-12);
+
+        let evidence = coherence.to_evidence().unwrap();
+        assert_eq!(evidence.evidence_type, EvidenceType::Pathway);
+        assert_eq!(evidence.data["pathways"][1]["observed"][0], "citrate");
+    }
+
+    #[test]
+    fn test_view_lists_unobserved_co_members() {
+        let pathways = vec![
+            pathway("glycolysis", &["glucose", "pyruvate", "lactate"]),
+            pathway("tca", &["pyruvate", "citrate", "lactate"]),
+        ];
+        let observed: HashMap<String, f64> = [("citrate".to_string(), 0.8)].into_iter().collect();
+        let names: HashMap<String, String> = [("citrate".to_string(), "Citric acid".to_string())].into_iter().collect();
+
+        let view = coherence_view("pyruvate", &pathways, &observed, &names);
+        assert_eq!(view.pathways[0].pathway_id, "tca");
+        assert_eq!(view.pathways[0].co_members[0].name.as_deref(), Some("Citric acid"));
+        assert!(!view.pathways[1].co_members[0].observed);
+        assert_eq!(view.heatmap.molecules, vec!["lactate", "citrate", "glucose"]);
+        assert_eq!(view.heatmap.values[0], vec![Some(0.0), Some(0.8), None]);
+        assert_eq!(view.heatmap.values[1], vec![Some(0.0), None, Some(0.0)]);
+    }
+
+    #[test]
+    fn test_no_co_members_gives_no_evidence() {
+        let coherence = pathway_coherence("pyruvate", &[pathway("orphan", &["pyruvate"])], &HashMap::new());
+        assert_eq!(coherence.score, 0.0);
+        assert!(coherence.to_evidence().is_none());
+    }
+}
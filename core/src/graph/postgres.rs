@@ -0,0 +1,618 @@
+//! Postgres Graph Store
+//!
+//! Keeps a project's graph in two tables, `graph_nodes` and `graph_edges`,
+//! with node properties and external IDs as JSONB. Traversals that Neo4j
+//! expresses as variable-length patterns are recursive CTEs here: pathway
+//! membership walks up to two `PARTICIPATES_IN`/`PART_OF` steps each way,
+//! and path finding extends acyclic walks one edge at a time up to the hop
+//! limit. Evidence items are `Evidence` nodes holding the serialized item,
+//! linked to their molecule by `SUPPORTS` edges.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::{debug, info};
+use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
+use sqlx::Row;
+use std::collections::HashMap;
+
+use super::inspect::{append_history, MoleculeSummary};
+use super::neighborhood::{molecule_node, NeighborEdge, NeighborhoodOptions, NeighborhoodSearch};
+use super::paths::MoleculePath;
+use super::pathways::PathwayMembership;
+use super::schema::{Edge, MolecularGraph, Node, NodeType};
+use super::stats::{trend_start, ProjectStats, CONFIDENCE_BANDS};
+use super::store::{GenePhenotypes, GraphStore, MoleculeInteraction, StoreBackend};
+use super::MoleculeNetwork;
+use crate::bundle::ProjectBundle;
+use crate::processing::evidence::{Evidence, EvidenceType, IntegratedEvidence};
+use crate::processing::versioning::ConfidenceTrigger;
+use crate::projects::Project;
+
+/// Connections kept in the pool
+const MAX_CONNECTIONS: u32 = 8;
+
+/// Tables and indexes of the graph, created if missing
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS graph_nodes (
+        project_id TEXT NOT NULL,
+        id TEXT NOT NULL,
+        label TEXT NOT NULL,
+        name TEXT NOT NULL,
+        properties JSONB NOT NULL DEFAULT '{}',
+        external_ids JSONB NOT NULL DEFAULT '{}',
+        PRIMARY KEY (project_id, id)
+    )",
+    "CREATE TABLE IF NOT EXISTS graph_edges (
+        project_id TEXT NOT NULL,
+        source_id TEXT NOT NULL,
+        target_id TEXT NOT NULL,
+        edge_type TEXT NOT NULL,
+        properties JSONB NOT NULL DEFAULT '{}',
+        PRIMARY KEY (project_id, source_id, target_id, edge_type)
+    )",
+    "CREATE TABLE IF NOT EXISTS graph_projects (
+        id TEXT PRIMARY KEY,
+        record JSONB NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS graph_nodes_label ON graph_nodes (project_id, label)",
+    "CREATE INDEX IF NOT EXISTS graph_edges_target ON graph_edges (project_id, target_id)",
+];
+
+/// Pathways reached from a molecule and the molecules reaching them, over one or two membership steps
+const PATHWAYS_QUERY: &str = "
+    WITH RECURSIVE up(node_id, depth) AS (
+        SELECT $2::text, 0
+        UNION
+        SELECT e.target_id, up.depth + 1
+        FROM up JOIN graph_edges e ON e.project_id = $1 AND e.source_id = up.node_id
+        WHERE e.edge_type IN ('PARTICIPATES_IN', 'PART_OF') AND up.depth < 2
+    ),
+    pathways AS (
+        SELECT DISTINCT p.id, p.name
+        FROM up JOIN graph_nodes p ON p.project_id = $1 AND p.id = up.node_id
+        WHERE p.label = 'Pathway'
+    ),
+    down(pathway_id, node_id, depth) AS (
+        SELECT id, id, 0 FROM pathways
+        UNION
+        SELECT down.pathway_id, e.source_id, down.depth + 1
+        FROM down JOIN graph_edges e ON e.project_id = $1 AND e.target_id = down.node_id
+        WHERE e.edge_type IN ('PARTICIPATES_IN', 'PART_OF') AND down.depth < 2
+    )
+    SELECT p.id AS pathway_id, p.name AS name, array_agg(DISTINCT m.id ORDER BY m.id) AS members
+    FROM pathways p
+    JOIN down ON down.pathway_id = p.id
+    JOIN graph_nodes m ON m.project_id = $1 AND m.id = down.node_id AND m.label = 'Molecule'
+    GROUP BY p.id, p.name
+    ORDER BY p.id";
+
+/// Acyclic walks between two molecules, in either edge direction, of the fewest hops found within the limit
+const PATHS_QUERY: &str = "
+    WITH RECURSIVE weighted AS (
+        SELECT source_id, target_id, edge_type,
+               coalesce((properties->>'similarity')::float8, (properties->>'weight')::float8,
+                        (properties->>'confidence')::float8, 1.0) AS weight
+        FROM graph_edges WHERE project_id = $1
+    ),
+    steps(a, b, edge_type, weight) AS (
+        SELECT source_id, target_id, edge_type, weight FROM weighted
+        UNION ALL
+        SELECT target_id, source_id, edge_type, weight FROM weighted
+    ),
+    walk(node_id, molecules, relationships, weights) AS (
+        SELECT $2::text, ARRAY[$2::text], ARRAY[]::text[], ARRAY[]::float8[]
+        UNION ALL
+        SELECT s.b, w.molecules || s.b, w.relationships || s.edge_type, w.weights || s.weight
+        FROM walk w JOIN steps s ON s.a = w.node_id
+        WHERE w.node_id <> $3 AND cardinality(w.relationships) < $4 AND NOT s.b = ANY(w.molecules)
+    ),
+    arrivals AS (
+        SELECT molecules, relationships, weights FROM walk WHERE node_id = $3
+    )
+    SELECT molecules, relationships, weights FROM arrivals
+    WHERE cardinality(relationships) = (SELECT min(cardinality(relationships)) FROM arrivals)";
+
+/// Molecules joined by a similarity edge, in either direction, to any molecule of a frontier
+const NEIGHBORS_QUERY: &str = "
+    WITH steps AS (
+        SELECT source_id AS from_id, target_id AS to_id, properties
+        FROM graph_edges WHERE project_id = $1 AND edge_type = 'SIMILAR_TO' AND source_id = ANY($2)
+        UNION ALL
+        SELECT target_id, source_id, properties
+        FROM graph_edges WHERE project_id = $1 AND edge_type = 'SIMILAR_TO' AND target_id = ANY($2)
+    )
+    SELECT s.from_id, n.id, n.name, n.properties, n.external_ids,
+           coalesce((s.properties->>'similarity')::float8, (s.properties->>'weight')::float8, 0.0) AS similarity
+    FROM steps s
+    JOIN graph_nodes n ON n.project_id = $1 AND n.id = s.to_id AND n.label = 'Molecule'
+    WHERE coalesce((s.properties->>'similarity')::float8, (s.properties->>'weight')::float8, 0.0) >= $3
+";
+
+/// Graph store backed by Postgres
+#[derive(Debug, Clone)]
+pub struct PostgresStore {
+    /// Connection pool
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    /// Connect to the database at `url`
+    pub async fn connect(url: &str) -> Result<Self> {
+        info!("Connecting to the Postgres graph store");
+        let pool = PgPoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .connect(url)
+            .await
+            .context("Failed to connect to Postgres")?;
+        Ok(Self { pool })
+    }
+
+    /// Create the graph tables if they do not exist
+    pub async fn ensure_schema(&self) -> Result<()> {
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(&self.pool).await
+                .context("Failed to create the graph schema")?;
+        }
+        Ok(())
+    }
+
+    /// Build a molecule node from a `graph_nodes` row
+    fn parse_node(row: &PgRow) -> Result<Node> {
+        let mut node = Node::new(row.try_get("id")?, NodeType::Molecule, row.try_get("name")?);
+        let properties: serde_json::Value = row.try_get("properties")?;
+        node.properties = serde_json::from_value(properties)?;
+        let external_ids: serde_json::Value = row.try_get("external_ids")?;
+        node.external_ids = serde_json::from_value(external_ids)?;
+        Ok(node)
+    }
+}
+
+#[async_trait]
+impl GraphStore for PostgresStore {
+    fn backend(&self) -> StoreBackend {
+        StoreBackend::Postgres
+    }
+
+    async fn store_molecule(&self, project_id: &str, molecule: &Node) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO graph_nodes (project_id, id, label, name, properties, external_ids) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (project_id, id) DO UPDATE SET label = EXCLUDED.label, name = EXCLUDED.name, \
+                 properties = EXCLUDED.properties, external_ids = EXCLUDED.external_ids",
+        )
+            .bind(project_id)
+            .bind(&molecule.id)
+            .bind(NodeType::Molecule.to_string())
+            .bind(&molecule.name)
+            .bind(serde_json::to_value(&molecule.properties)?)
+            .bind(serde_json::to_value(&molecule.external_ids)?)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to store molecule {}", molecule.id))?;
+        debug!("Stored molecule {} in project {}", molecule.id, project_id);
+        Ok(())
+    }
+
+    async fn get_molecule(&self, project_id: &str, molecule_id: &str) -> Result<Option<Node>> {
+        let row = sqlx::query(
+            "SELECT id, name, properties, external_ids FROM graph_nodes \
+             WHERE project_id = $1 AND id = $2 AND label = 'Molecule'",
+        )
+            .bind(project_id)
+            .bind(molecule_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(Self::parse_node).transpose()
+    }
+
+    async fn list_molecules(&self, project_id: &str) -> Result<Vec<MoleculeSummary>> {
+        let rows = sqlx::query(
+            "SELECT m.id, m.name, (m.properties->>'confidence')::float8 AS confidence, \
+                    count(e.source_id) AS evidence_count \
+             FROM graph_nodes m \
+             LEFT JOIN graph_edges e ON e.project_id = m.project_id AND e.target_id = m.id AND e.edge_type = 'SUPPORTS' \
+             WHERE m.project_id = $1 AND m.label = 'Molecule' \
+             GROUP BY m.id, m.name, m.properties \
+             ORDER BY m.id",
+        )
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(MoleculeSummary {
+                    molecule_id: row.try_get("id")?,
+                    name: row.try_get("name")?,
+                    confidence: row.try_get("confidence")?,
+                    evidence_count: row.try_get::<i64, _>("evidence_count")? as usize,
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_molecule(&self, project_id: &str, molecule_id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM graph_edges WHERE project_id = $1 AND (source_id = $2 OR target_id = $2)")
+            .bind(project_id)
+            .bind(molecule_id)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM graph_nodes WHERE project_id = $1 AND id = $2 AND label = 'Molecule'")
+            .bind(project_id)
+            .bind(molecule_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+
+    async fn molecule_pathways(&self, project_id: &str, molecule_id: &str) -> Result<Vec<PathwayMembership>> {
+        let rows = sqlx::query(PATHWAYS_QUERY)
+            .bind(project_id)
+            .bind(molecule_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query pathways from Postgres")?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(PathwayMembership {
+                    pathway_id: row.try_get("pathway_id")?,
+                    name: row.try_get("name")?,
+                    members: row.try_get("members")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn molecule_interactions(&self, project_id: &str, molecule_id: &str) -> Result<Vec<MoleculeInteraction>> {
+        let rows = sqlx::query(
+            "SELECT e.target_id, t.name AS target_name, e.edge_type, e.properties \
+             FROM graph_edges e \
+             JOIN graph_nodes t ON t.project_id = e.project_id AND t.id = e.target_id AND t.label = 'Molecule' \
+             WHERE e.project_id = $1 AND e.source_id = $2 \
+             ORDER BY e.target_id, e.edge_type",
+        )
+            .bind(project_id)
+            .bind(molecule_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query interactions from Postgres")?;
+
+        rows.iter()
+            .map(|row| {
+                let properties: HashMap<String, serde_json::Value> = serde_json::from_value(row.try_get("properties")?)?;
+                Ok(MoleculeInteraction {
+                    source_molecule: molecule_id.to_string(),
+                    target_molecule: row.try_get("target_id")?,
+                    target_name: row.try_get("target_name")?,
+                    interaction_type: row.try_get("edge_type")?,
+                    confidence: properties.get("confidence").and_then(|v| v.as_f64()),
+                    evidence_count: properties.get("evidence_count").and_then(|v| v.as_u64()).unwrap_or(1) as usize,
+                })
+            })
+            .collect()
+    }
+
+    async fn find_paths(&self, project_id: &str, from: &str, to: &str, max_hops: usize, limit: usize) -> Result<Vec<MoleculePath>> {
+        if !(1..=10).contains(&max_hops) {
+            return Err(anyhow!("max_hops must be between 1 and 10, got {}", max_hops));
+        }
+        let rows = sqlx::query(PATHS_QUERY)
+            .bind(project_id)
+            .bind(from)
+            .bind(to)
+            .bind(max_hops as i32)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query paths from Postgres")?;
+
+        let mut paths = rows.iter()
+            .map(|row| {
+                let weights: Vec<f64> = row.try_get("weights")?;
+                Ok(MoleculePath {
+                    molecules: row.try_get("molecules")?,
+                    relationships: row.try_get("relationships")?,
+                    cost: weights.iter().map(|w| (1.0 - w).max(0.0)).sum(),
+                    weights,
+                })
+            })
+            .collect::<Result<Vec<MoleculePath>>>()?;
+        paths.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+        paths.truncate(limit);
+
+        debug!("Found {} paths between {} and {} in project {}", paths.len(), from, to, project_id);
+        Ok(paths)
+    }
+
+    async fn neighborhood(&self, project_id: &str, molecule_id: &str, options: &NeighborhoodOptions) -> Result<Option<MoleculeNetwork>> {
+        let center = match self.get_molecule(project_id, molecule_id).await? {
+            Some(center) => center,
+            None => return Ok(None),
+        };
+        let mut search = NeighborhoodSearch::new(molecule_node(&center), options)?;
+        while !search.frontier().is_empty() {
+            let rows = sqlx::query(NEIGHBORS_QUERY)
+                .bind(project_id)
+                .bind(search.frontier())
+                .bind(search.min_similarity())
+                .fetch_all(&self.pool)
+                .await
+                .context("Failed to query neighbors from Postgres")?;
+            let edges = rows.iter()
+                .map(|row| Ok(NeighborEdge {
+                    from: row.try_get("from_id")?,
+                    neighbor: molecule_node(&Self::parse_node(row)?),
+                    similarity: row.try_get("similarity")?,
+                }))
+                .collect::<Result<Vec<NeighborEdge>>>()?;
+            search.expand(edges);
+        }
+        search.finish().map(Some)
+    }
+
+    /// Store integrated evidence, appending the confidence to the molecule's history as in Neo4j
+    async fn store_integrated_evidence(&self, project_id: &str, integrated: &IntegratedEvidence, trigger: ConfidenceTrigger) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // The history is extended from the stored lists, so the row stays locked until it is written back
+        let stored = sqlx::query("SELECT properties FROM graph_nodes WHERE project_id = $1 AND id = $2 FOR UPDATE")
+            .bind(project_id)
+            .bind(&integrated.molecule_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to read molecule {}", integrated.molecule_id))?;
+        let mut molecule_properties: HashMap<String, serde_json::Value> = match stored {
+            Some(row) => match row.try_get::<serde_json::Value, _>("properties")? {
+                serde_json::Value::Object(properties) => properties.into_iter()
+                    .filter(|(key, _)| key.starts_with("confidence_history"))
+                    .collect(),
+                _ => HashMap::new(),
+            },
+            None => HashMap::new(),
+        };
+        append_history(&mut molecule_properties, integrated.aggregate_confidence, integrated.integration_timestamp, trigger);
+        molecule_properties.insert("confidence".to_string(), serde_json::json!(integrated.aggregate_confidence));
+        molecule_properties.insert("conflict_count".to_string(), serde_json::json!(integrated.conflicts.len()));
+        molecule_properties.insert("last_integrated".to_string(), serde_json::json!(integrated.integration_timestamp.to_rfc3339()));
+        if let Some(fingerprint) = &integrated.pipeline_fingerprint {
+            molecule_properties.insert("pipeline_fingerprint".to_string(), serde_json::json!(fingerprint.id));
+        }
+
+        sqlx::query(
+            "INSERT INTO graph_nodes (project_id, id, label, name, properties) VALUES ($1, $2, 'Molecule', $2, $3) \
+             ON CONFLICT (project_id, id) DO UPDATE SET properties = graph_nodes.properties || EXCLUDED.properties",
+        )
+            .bind(project_id)
+            .bind(&integrated.molecule_id)
+            .bind(serde_json::to_value(&molecule_properties)?)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to store molecule {}", integrated.molecule_id))?;
+
+        for item in &integrated.evidence_items {
+            sqlx::query(
+                "INSERT INTO graph_nodes (project_id, id, label, name, properties) VALUES ($1, $2, 'Evidence', $3, $4) \
+                 ON CONFLICT (project_id, id) DO UPDATE SET name = EXCLUDED.name, properties = EXCLUDED.properties \
+                 WHERE graph_nodes.label = 'Evidence'",
+            )
+                .bind(project_id)
+                .bind(&item.id)
+                .bind(&item.source)
+                .bind(serde_json::to_value(item)?)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to store evidence {}", item.id))?;
+            sqlx::query(
+                "INSERT INTO graph_edges (project_id, source_id, target_id, edge_type) VALUES ($1, $2, $3, 'SUPPORTS') \
+                 ON CONFLICT DO NOTHING",
+            )
+                .bind(project_id)
+                .bind(&item.id)
+                .bind(&integrated.molecule_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        debug!("Stored {} evidence items for molecule {} in project {}",
+               integrated.evidence_items.len(), integrated.molecule_id, project_id);
+        Ok(())
+    }
+
+    async fn molecule_evidence(&self, project_id: &str, molecule_id: &str) -> Result<Vec<Evidence>> {
+        let rows = sqlx::query(
+            "SELECT n.properties FROM graph_edges e \
+             JOIN graph_nodes n ON n.project_id = e.project_id AND n.id = e.source_id AND n.label = 'Evidence' \
+             WHERE e.project_id = $1 AND e.target_id = $2 AND e.edge_type = 'SUPPORTS' \
+             ORDER BY n.id",
+        )
+            .bind(project_id)
+            .bind(molecule_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query evidence from Postgres")?;
+
+        rows.iter()
+            .map(|row| Ok(serde_json::from_value(row.try_get("properties")?)?))
+            .collect()
+    }
+
+    async fn gene_phenotypes(&self, project_id: &str, limit: usize) -> Result<Vec<GenePhenotypes>> {
+        let rows = sqlx::query(
+            "SELECT g.id AS gene_id, g.name AS gene_name, count(DISTINCT p.id) AS phenotype_count \
+             FROM graph_nodes g \
+             JOIN graph_edges e ON e.project_id = g.project_id AND e.source_id = g.id \
+             JOIN graph_nodes p ON p.project_id = e.project_id AND p.id = e.target_id AND p.label = 'Disease' \
+             WHERE g.project_id = $1 AND g.label = 'Gene' \
+             GROUP BY g.id, g.name \
+             ORDER BY phenotype_count DESC, g.id \
+             LIMIT $2",
+        )
+            .bind(project_id)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query gene phenotypes from Postgres")?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(GenePhenotypes {
+                    gene_id: row.try_get("gene_id")?,
+                    gene_name: row.try_get("gene_name")?,
+                    phenotype_count: row.try_get::<i64, _>("phenotype_count")? as usize,
+                })
+            })
+            .collect()
+    }
+
+    async fn store_project(&self, project: &Project) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO graph_projects (id, record) VALUES ($1, $2) \
+             ON CONFLICT (id) DO UPDATE SET record = EXCLUDED.record",
+        )
+            .bind(&project.id)
+            .bind(serde_json::to_value(project)?)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to store project {}", project.id))?;
+        Ok(())
+    }
+
+    async fn project_stats(&self, project_id: &str) -> Result<ProjectStats> {
+        let since = trend_start();
+        let mut stats = ProjectStats::new(project_id);
+        let count = |row: &PgRow, column: &str| -> Result<usize> { Ok(row.try_get::<i64, _>(column)? as usize) };
+
+        for row in sqlx::query(
+            "SELECT CASE WHEN properties->>'confidence' IS NULL THEN -1 \
+                         ELSE floor((properties->>'confidence')::float8 * $2)::int8 END AS band, \
+                    count(*) AS molecules, \
+                    count(*) FILTER (WHERE coalesce((properties->>'conflict_count')::int8, 0) > 0) AS conflicted \
+             FROM graph_nodes WHERE project_id = $1 AND label = 'Molecule' \
+             GROUP BY band",
+        )
+            .bind(project_id)
+            .bind(CONFIDENCE_BANDS as f64)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query molecule statistics from Postgres")?
+        {
+            let band = usize::try_from(row.try_get::<i64, _>("band")?).ok();
+            stats.add_molecules(band, count(&row, "molecules")?, count(&row, "conflicted")?);
+        }
+
+        for row in sqlx::query(
+            "SELECT coalesce(properties->>'evidence_type', 'Other') AS evidence_type, \
+                    coalesce(properties->>'source', 'unknown') AS source, count(*) AS evidence \
+             FROM graph_nodes WHERE project_id = $1 AND label = 'Evidence' \
+             GROUP BY 1, 2",
+        )
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query evidence statistics from Postgres")?
+        {
+            let stored: String = row.try_get("evidence_type")?;
+            let evidence_type = serde_json::from_value::<EvidenceType>(serde_json::json!(stored))
+                .map(|t| t.to_string())
+                .unwrap_or(stored);
+            stats.add_evidence(&evidence_type, &row.try_get::<String, _>("source")?, count(&row, "evidence")?);
+        }
+
+        for row in sqlx::query(
+            "SELECT left(properties->>'timestamp', 10) AS day, count(*) AS evidence \
+             FROM graph_nodes WHERE project_id = $1 AND label = 'Evidence' AND left(properties->>'timestamp', 10) >= $2 \
+             GROUP BY day",
+        )
+            .bind(project_id)
+            .bind(&since)
+            .fetch_all(&self.pool)
+            .await?
+        {
+            stats.add_evidence_day(&row.try_get::<String, _>("day")?, count(&row, "evidence")?);
+        }
+
+        for row in sqlx::query(
+            "SELECT left(h.at, 10) AS day, count(*) AS integrations, avg(c.confidence::float8) AS mean_confidence \
+             FROM graph_nodes n \
+             CROSS JOIN LATERAL jsonb_array_elements_text(coalesce(n.properties->'confidence_history_at', '[]')) \
+                 WITH ORDINALITY AS h(at, i) \
+             JOIN LATERAL jsonb_array_elements_text(coalesce(n.properties->'confidence_history', '[]')) \
+                 WITH ORDINALITY AS c(confidence, i) ON c.i = h.i \
+             WHERE n.project_id = $1 AND n.label = 'Molecule' AND left(h.at, 10) >= $2 \
+             GROUP BY day",
+        )
+            .bind(project_id)
+            .bind(&since)
+            .fetch_all(&self.pool)
+            .await?
+        {
+            let mean: Option<f64> = row.try_get("mean_confidence")?;
+            stats.add_integration_day(&row.try_get::<String, _>("day")?, count(&row, "integrations")?, mean);
+        }
+
+        debug!("Computed statistics for project {}: {} molecules, {} evidence items", project_id, stats.molecules, stats.evidence);
+        Ok(stats)
+    }
+
+    /// Export a project, its whole graph as a single network named after the project
+    async fn export_project(&self, project_id: &str) -> Result<ProjectBundle> {
+        let record: serde_json::Value = sqlx::query("SELECT record FROM graph_projects WHERE id = $1")
+            .bind(project_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| anyhow!("Project not found: {}", project_id))?
+            .try_get("record")?;
+        let mut bundle = ProjectBundle::new(serde_json::from_value(record).context("Invalid stored project")?);
+        info!("Exporting project {} from Postgres", project_id);
+
+        let mut network = MolecularGraph::new(project_id.to_string(), bundle.project.name.clone());
+        network.set_project(project_id);
+        for row in sqlx::query(
+            "SELECT id, label, name, properties, external_ids FROM graph_nodes \
+             WHERE project_id = $1 AND label <> 'Evidence' ORDER BY id",
+        )
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await?
+        {
+            let mut node = Self::parse_node(&row)?;
+            node.node_type = row.try_get::<String, _>("label")?.parse()?;
+            if node.node_type == NodeType::Molecule {
+                bundle.add_molecule(&node);
+            }
+            network.add_node(node);
+        }
+        for row in sqlx::query(
+            "SELECT source_id, target_id, edge_type, properties FROM graph_edges \
+             WHERE project_id = $1 AND edge_type <> 'SUPPORTS' ORDER BY source_id, target_id, edge_type",
+        )
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await?
+        {
+            let mut edge = Edge::new(row.try_get("source_id")?, row.try_get("target_id")?,
+                                     row.try_get::<String, _>("edge_type")?.parse()?);
+            edge.properties = serde_json::from_value(row.try_get("properties")?)?;
+            network.add_edge(edge);
+        }
+
+        for row in sqlx::query("SELECT properties FROM graph_nodes WHERE project_id = $1 AND label = 'Evidence' ORDER BY id")
+            .bind(project_id)
+            .fetch_all(&self.pool)
+            .await?
+        {
+            let evidence: Evidence = serde_json::from_value(row.try_get("properties")?)?;
+            bundle.add_evidence(&evidence)?;
+        }
+        if !network.nodes.is_empty() {
+            bundle.networks.push(network);
+        }
+
+        info!("Exported project {}: {} molecules, {} evidence items", project_id, bundle.molecules.len(), bundle.evidence.len());
+        Ok(bundle)
+    }
+}
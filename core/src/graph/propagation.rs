@@ -0,0 +1,238 @@
+//! Annotation Propagation
+//!
+//! Identified molecules suggest identities for their unannotated neighbors.
+//! A suggestion travels only across edges at or above a similarity cutoff and
+//! loses confidence with every hop, both from the edge similarity and from a
+//! fixed per-hop discount, so distant or weakly linked nodes get weak
+//! suggestions. Paths never revisit a node, and suggestions are ranked per
+//! node, keeping the best path for each candidate identity.
+
+use log::debug;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::families::IDENTIFICATION_CONFIDENCE_PROPERTY;
+use super::MoleculeNetwork;
+use crate::fuzzy_evidence::EvidencePrediction;
+
+/// Options for propagating annotations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropagationOptions {
+    /// Lowest identification confidence for a molecule to act as a source
+    pub min_source_confidence: f64,
+
+    /// Lowest edge similarity a suggestion travels across
+    pub min_similarity: f64,
+
+    /// Factor applied to the confidence at every hop (0.0 - 1.0)
+    pub hop_discount: f64,
+
+    /// Most edges a suggestion travels across
+    pub max_hops: usize,
+
+    /// Suggestions weaker than this are dropped
+    pub min_confidence: f64,
+
+    /// Most suggestions kept per node
+    pub max_candidates: usize,
+}
+
+impl Default for PropagationOptions {
+    fn default() -> Self {
+        Self {
+            min_source_confidence: 0.7,
+            min_similarity: 0.7,
+            hop_discount: 0.8,
+            max_hops: 3,
+            min_confidence: 0.1,
+            max_candidates: 3,
+        }
+    }
+}
+
+/// A candidate identity suggested for an unannotated molecule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationSuggestion {
+    /// Molecule the identity is suggested for
+    pub molecule_id: String,
+
+    /// Suggested compound name
+    pub name: String,
+
+    /// Suggested structure, when the source has one
+    pub smiles: Option<String>,
+
+    /// Identified molecule the suggestion comes from
+    pub source_id: String,
+
+    /// Molecules from the source to the suggested one, both included
+    pub path: Vec<String>,
+
+    /// Product of the edge similarities and hop discounts along the path
+    pub path_strength: f64,
+
+    /// Source confidence times the path strength (0.0 - 1.0)
+    pub confidence: f64,
+}
+
+impl AnnotationSuggestion {
+    /// Edges between the source and the suggested molecule
+    pub fn hops(&self) -> usize {
+        self.path.len().saturating_sub(1)
+    }
+
+    /// The suggestion as an evidence prediction for the molecule
+    pub fn to_prediction(&self) -> EvidencePrediction {
+        EvidencePrediction {
+            node_id: self.molecule_id.clone(),
+            predicted_value: self.confidence,
+            confidence: self.path_strength,
+            supporting_evidence: self.path[..self.path.len() - 1].to_vec(),
+            reasoning: format!("Similar to {} (identified as {}) across {} hop(s)",
+                               self.source_id, self.name, self.hops()),
+        }
+    }
+}
+
+impl MoleculeNetwork {
+    /// Suggest identities for unannotated molecules from their identified neighbors
+    ///
+    /// A molecule is identified when it has a name and an identification
+    /// confidence of at least `min_source_confidence`; every other molecule
+    /// can receive suggestions. Suggestions are ordered by molecule, then by
+    /// decreasing confidence.
+    pub fn propagate_annotations(&self, options: &PropagationOptions) -> Vec<AnnotationSuggestion> {
+        let sources: Vec<(NodeIndex, f64)> = self.graph.node_indices()
+            .filter_map(|index| {
+                let node = &self.graph[index];
+                node.name.as_ref()?;
+                let confidence = node.properties.get(IDENTIFICATION_CONFIDENCE_PROPERTY)?.as_f64()?;
+                (confidence >= options.min_source_confidence).then_some((index, confidence.min(1.0)))
+            })
+            .collect();
+        let is_source = |index: NodeIndex| sources.iter().any(|(source, _)| *source == index);
+
+        // Best suggestion per (molecule, name)
+        let mut best: HashMap<(NodeIndex, String), AnnotationSuggestion> = HashMap::new();
+        for &(source, source_confidence) in &sources {
+            let name = self.graph[source].name.clone().unwrap_or_default();
+            let mut path = vec![source];
+            self.walk(&mut path, 1.0, options, &mut |path, strength| {
+                let target = *path.last().expect("paths start at the source");
+                let confidence = source_confidence * strength;
+                if is_source(target) || confidence < options.min_confidence {
+                    return;
+                }
+                let key = (target, name.clone());
+                if best.get(&key).is_some_and(|existing| existing.confidence >= confidence) {
+                    return;
+                }
+                let source_node = &self.graph[source];
+                best.insert(key, AnnotationSuggestion {
+                    molecule_id: self.graph[target].id.clone(),
+                    name: name.clone(),
+                    smiles: Some(source_node.smiles.clone()).filter(|s| !s.is_empty()),
+                    source_id: source_node.id.clone(),
+                    path: path.iter().map(|&i| self.graph[i].id.clone()).collect(),
+                    path_strength: strength,
+                    confidence,
+                });
+            });
+        }
+
+        let mut by_molecule: BTreeMap<String, Vec<AnnotationSuggestion>> = BTreeMap::new();
+        for suggestion in best.into_values() {
+            by_molecule.entry(suggestion.molecule_id.clone()).or_default().push(suggestion);
+        }
+        let suggestions: Vec<AnnotationSuggestion> = by_molecule.into_values()
+            .flat_map(|mut candidates| {
+                candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then_with(|| a.name.cmp(&b.name)));
+                candidates.truncate(options.max_candidates);
+                candidates
+            })
+            .collect();
+
+        debug!("Propagated {} annotation suggestions from {} identified molecules", suggestions.len(), sources.len());
+        suggestions
+    }
+
+    /// Extend `path` across strong enough edges, reporting every node reached
+    ///
+    /// Nodes already on the path are skipped, so cycles are never followed,
+    /// and branches are abandoned once they can only fall below the cutoff.
+    fn walk<F>(&self, path: &mut Vec<NodeIndex>, strength: f64, options: &PropagationOptions, visit: &mut F)
+    where
+        F: FnMut(&[NodeIndex], f64),
+    {
+        if path.len() > options.max_hops {
+            return;
+        }
+        let current = *path.last().expect("paths start at the source");
+        for edge in self.graph.edges(current) {
+            let similarity = edge.weight().similarity();
+            let next = if edge.source() == current { edge.target() } else { edge.source() };
+            if similarity < options.min_similarity || path.contains(&next) {
+                continue;
+            }
+            let reached = strength * similarity * options.hop_discount;
+            if reached < options.min_confidence {
+                continue;
+            }
+            path.push(next);
+            visit(path, reached);
+            self.walk(path, reached, options, visit);
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::Molecule;
+
+    fn node(network: &mut MoleculeNetwork, id: &str, name: Option<&str>, confidence: Option<f64>) {
+        let mut molecule = Molecule::from_smiles("C").unwrap();
+        molecule.id = id.to_string();
+        molecule.name = name.map(str::to_string);
+        if let Some(confidence) = confidence {
+            molecule.properties.insert(IDENTIFICATION_CONFIDENCE_PROPERTY.to_string(), serde_json::json!(confidence));
+        }
+        network.add_molecule(&molecule);
+    }
+
+    #[test]
+    fn test_propagation_discounts_each_hop() {
+        let mut network = MoleculeNetwork::new();
+        node(&mut network, "a", Some("caffeine"), Some(1.0));
+        node(&mut network, "b", None, None);
+        node(&mut network, "c", None, None);
+        node(&mut network, "d", None, None);
+        node(&mut network, "e", Some("theobromine"), Some(0.9));
+        network.add_similarity("a", "b", 0.9);
+        network.add_similarity("b", "c", 0.9);
+        network.add_similarity("c", "a", 0.8);
+        network.add_similarity("c", "d", 0.5);
+        network.add_similarity("e", "b", 0.75);
+
+        let options = PropagationOptions::default();
+        let suggestions = network.propagate_annotations(&options);
+        assert!(suggestions.iter().all(|s| s.molecule_id != "d" && s.molecule_id != "a" && s.molecule_id != "e"));
+
+        let for_b: Vec<&AnnotationSuggestion> = suggestions.iter().filter(|s| s.molecule_id == "b").collect();
+        assert_eq!(for_b.len(), 2);
+        assert_eq!(for_b[0].name, "caffeine");
+        assert!((for_b[0].confidence - 0.9 * 0.8).abs() < 1e-12);
+        assert!((for_b[1].confidence - 0.9 * 0.75 * 0.8).abs() < 1e-12);
+
+        // c is reached directly (0.8 * 0.8) rather than through b (0.9 * 0.8)^2
+        let for_c = suggestions.iter().find(|s| s.molecule_id == "c" && s.name == "caffeine").unwrap();
+        assert_eq!(for_c.path, vec!["a", "c"]);
+
+        let prediction = for_c.to_prediction();
+        assert_eq!(prediction.node_id, "c");
+        assert_eq!(prediction.supporting_evidence, vec!["a"]);
+    }
+}
@@ -0,0 +1,470 @@
+//! RDF Export Module
+//!
+//! This module maps a project's molecules, evidence and networks onto standard
+//! ontologies (CHEMINF for chemical descriptors, SIO for attributes, values and
+//! evidence, CiTO for cited literature) and serializes them as Turtle or
+//! N-Triples for loading into triple stores. Relationships without a standard
+//! term use the Hegel vocabulary.
+
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::bundle::ProjectBundle;
+use crate::graph::schema::{EdgeType, MolecularGraph, NodeType};
+
+/// Namespace prefixes used in Turtle output
+///
+/// SIO and CHEMINF share a namespace, so CHEMINF classes appear as `sio:CHEMINF_…`.
+pub const PREFIXES: [(&str, &str); 8] = [
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+    ("dcterms", "http://purl.org/dc/terms/"),
+    ("sio", "http://semanticscience.org/resource/"),
+    ("cito", "http://purl.org/spar/cito/"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+    ("hegel", "https://w3id.org/hegel/vocab#"),
+];
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+const XSD_DATE_TIME: &str = "http://www.w3.org/2001/XMLSchema#dateTime";
+const DCTERMS_CREATED: &str = "http://purl.org/dc/terms/created";
+const DCTERMS_SOURCE: &str = "http://purl.org/dc/terms/source";
+const DCTERMS_IS_PART_OF: &str = "http://purl.org/dc/terms/isPartOf";
+const SIO_HAS_ATTRIBUTE: &str = "http://semanticscience.org/resource/SIO_000008";
+const SIO_HAS_VALUE: &str = "http://semanticscience.org/resource/SIO_000300";
+const SIO_HAS_EVIDENCE: &str = "http://semanticscience.org/resource/SIO_000772";
+const SIO_IS_PART_OF: &str = "http://semanticscience.org/resource/SIO_000068";
+const SIO_IS_RELATED_TO: &str = "http://semanticscience.org/resource/SIO_000001";
+const SIO_PROBABILITY: &str = "http://semanticscience.org/resource/SIO_000638";
+const CHEMINF_CHEMICAL_ENTITY: &str = "http://semanticscience.org/resource/CHEMINF_000000";
+const CITO_CITES_AS_EVIDENCE: &str = "http://purl.org/spar/cito/citesAsEvidence";
+const CITO_IS_CITED_BY: &str = "http://purl.org/spar/cito/isCitedBy";
+const SKOS_RELATED_MATCH: &str = "http://www.w3.org/2004/02/skos/core#relatedMatch";
+const HEGEL: &str = "https://w3id.org/hegel/vocab#";
+
+/// Chemical descriptors stored on molecules, with their CHEMINF classes
+const DESCRIPTORS: [(&str, &str); 4] = [
+    ("smiles", "http://semanticscience.org/resource/CHEMINF_000018"),
+    ("inchi", "http://semanticscience.org/resource/CHEMINF_000113"),
+    ("inchikey", "http://semanticscience.org/resource/CHEMINF_000059"),
+    ("formula", "http://semanticscience.org/resource/CHEMINF_000042"),
+];
+
+/// Serialization of an RDF export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RdfFormat {
+    /// Turtle, with prefixes and triples grouped by subject
+    Turtle,
+
+    /// N-Triples, one fully expanded triple per line
+    #[serde(rename = "ntriples")]
+    NTriples,
+}
+
+impl RdfFormat {
+    /// Media type of the serialization
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            RdfFormat::Turtle => "text/turtle",
+            RdfFormat::NTriples => "application/n-triples",
+        }
+    }
+}
+
+impl std::str::FromStr for RdfFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "turtle" | "ttl" => Ok(RdfFormat::Turtle),
+            "ntriples" | "n-triples" | "nt" => Ok(RdfFormat::NTriples),
+            _ => Err(anyhow!("Unsupported RDF format: {} (expected turtle or ntriples)", s)),
+        }
+    }
+}
+
+/// Object of a triple
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RdfTerm {
+    /// Resource named by an IRI
+    Iri { iri: String },
+
+    /// Literal value, typed when `datatype` is set
+    Literal { value: String, datatype: Option<String> },
+}
+
+impl RdfTerm {
+    fn iri(iri: &str) -> Self {
+        RdfTerm::Iri { iri: iri.to_string() }
+    }
+
+    fn string(value: &str) -> Self {
+        RdfTerm::Literal { value: value.to_string(), datatype: None }
+    }
+
+    fn typed(value: String, datatype: &str) -> Self {
+        RdfTerm::Literal { value, datatype: Some(datatype.to_string()) }
+    }
+}
+
+/// Statement of an RDF graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Triple {
+    /// Subject IRI
+    pub subject: String,
+
+    /// Predicate IRI
+    pub predicate: String,
+
+    /// Object
+    pub object: RdfTerm,
+}
+
+/// Options of an RDF export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RdfOptions {
+    /// Prefix of the IRIs minted for the project's resources
+    pub base_iri: String,
+}
+
+impl Default for RdfOptions {
+    fn default() -> Self {
+        Self {
+            base_iri: "urn:hegel:".to_string(),
+        }
+    }
+}
+
+/// Triples describing a project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RdfGraph {
+    /// Triples in the order they were added
+    pub triples: Vec<Triple>,
+}
+
+impl RdfGraph {
+    /// Map a project bundle onto RDF
+    pub fn from_bundle(bundle: &ProjectBundle, options: &RdfOptions) -> Self {
+        let mut graph = RdfExport { options, project_id: &bundle.project.id, graph: RdfGraph::default() };
+        let project = graph.project_iri();
+        graph.graph.add(&project, RDF_TYPE, RdfTerm::iri(&format!("{}Project", HEGEL)));
+        graph.graph.add(&project, RDFS_LABEL, RdfTerm::string(&bundle.project.name));
+
+        for molecule in &bundle.molecules {
+            graph.add_molecule(molecule);
+        }
+        for evidence in &bundle.evidence {
+            graph.add_evidence(evidence);
+        }
+        for network in &bundle.networks {
+            graph.add_network(network);
+        }
+        graph.graph
+    }
+
+    fn add(&mut self, subject: &str, predicate: &str, object: RdfTerm) {
+        self.triples.push(Triple { subject: subject.to_string(), predicate: predicate.to_string(), object });
+    }
+
+    /// Serialize in the given format
+    pub fn write(&self, format: RdfFormat) -> String {
+        match format {
+            RdfFormat::Turtle => self.to_turtle(),
+            RdfFormat::NTriples => self.to_ntriples(),
+        }
+    }
+
+    /// Serialize as N-Triples
+    pub fn to_ntriples(&self) -> String {
+        let mut out = String::new();
+        for triple in &self.triples {
+            let _ = writeln!(out, "<{}> <{}> {} .", triple.subject, triple.predicate, ntriples_term(&triple.object));
+        }
+        out
+    }
+
+    /// Serialize as Turtle, grouping the triples of each subject
+    pub fn to_turtle(&self) -> String {
+        let mut out = String::new();
+        for (prefix, namespace) in PREFIXES {
+            let _ = writeln!(out, "@prefix {}: <{}> .", prefix, namespace);
+        }
+
+        let mut by_subject: BTreeMap<&str, Vec<&Triple>> = BTreeMap::new();
+        for triple in &self.triples {
+            by_subject.entry(&triple.subject).or_default().push(triple);
+        }
+        for (subject, triples) in by_subject {
+            let _ = write!(out, "\n{}", turtle_iri(subject));
+            for (idx, triple) in triples.iter().enumerate() {
+                let object = match &triple.object {
+                    RdfTerm::Iri { iri } => turtle_iri(iri),
+                    RdfTerm::Literal { value, datatype } => match datatype {
+                        Some(datatype) => format!("\"{}\"^^{}", escape_literal(value), turtle_iri(datatype)),
+                        None => format!("\"{}\"", escape_literal(value)),
+                    },
+                };
+                let predicate = if triple.predicate == RDF_TYPE { "a".to_string() } else { turtle_iri(&triple.predicate) };
+                let separator = if idx + 1 == triples.len() { " ." } else { " ;" };
+                let _ = write!(out, "\n    {} {}{}", predicate, object, separator);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Export of one project in progress
+struct RdfExport<'a> {
+    options: &'a RdfOptions,
+    project_id: &'a str,
+    graph: RdfGraph,
+}
+
+impl RdfExport<'_> {
+    /// IRI of the project, e.g. `urn:hegel:my-project`
+    fn project_iri(&self) -> String {
+        format!("{}{}", self.options.base_iri, encode_segment(self.project_id))
+    }
+
+    /// IRI of a project resource, e.g. `urn:hegel:my-project:molecule:glucose`
+    fn resource(&self, kind: &str, id: &str) -> String {
+        format!("{}:{}:{}", self.project_iri(), kind, encode_segment(id))
+    }
+
+    /// Attach a value to a subject through an attribute node of the given class
+    fn add_attribute(&mut self, subject: &str, name: &str, class: &str, value: RdfTerm) {
+        let attribute = format!("{}:{}", subject, name);
+        self.graph.add(subject, SIO_HAS_ATTRIBUTE, RdfTerm::iri(&attribute));
+        self.graph.add(&attribute, RDF_TYPE, RdfTerm::iri(class));
+        self.graph.add(&attribute, SIO_HAS_VALUE, value);
+    }
+
+    fn add_confidence(&mut self, subject: &str, record: &serde_json::Value) {
+        if let Some(confidence) = record.get("confidence").and_then(|v| v.as_f64()) {
+            self.add_attribute(subject, "confidence", SIO_PROBABILITY, RdfTerm::typed(confidence.to_string(), XSD_DOUBLE));
+        }
+    }
+
+    fn add_molecule(&mut self, molecule: &serde_json::Value) {
+        let id = match molecule.get("id").and_then(|v| v.as_str()) {
+            Some(id) => id,
+            None => return,
+        };
+        let subject = self.resource("molecule", id);
+        let project = self.project_iri();
+        self.graph.add(&subject, RDF_TYPE, RdfTerm::iri(CHEMINF_CHEMICAL_ENTITY));
+        self.graph.add(&subject, DCTERMS_IS_PART_OF, RdfTerm::iri(&project));
+        if let Some(name) = molecule.get("name").and_then(|v| v.as_str()) {
+            self.graph.add(&subject, RDFS_LABEL, RdfTerm::string(name));
+        }
+        for (property, class) in DESCRIPTORS {
+            if let Some(value) = molecule.get(property).and_then(|v| v.as_str()).filter(|v| !v.is_empty()) {
+                self.add_attribute(&subject, property, class, RdfTerm::string(value));
+            }
+        }
+        self.add_confidence(&subject, molecule);
+    }
+
+    fn add_evidence(&mut self, evidence: &serde_json::Value) {
+        let (id, molecule_id) = match (evidence.get("id").and_then(|v| v.as_str()), evidence.get("molecule_id").and_then(|v| v.as_str())) {
+            (Some(id), Some(molecule_id)) => (id, molecule_id),
+            _ => return,
+        };
+        let subject = self.resource("evidence", id);
+        let molecule = self.resource("molecule", molecule_id);
+        self.graph.add(&molecule, SIO_HAS_EVIDENCE, RdfTerm::iri(&subject));
+        self.graph.add(&subject, RDF_TYPE, RdfTerm::iri(&format!("{}Evidence", HEGEL)));
+        if let Some(evidence_type) = evidence.get("type").and_then(|v| v.as_str()) {
+            self.graph.add(&subject, &format!("{}evidenceType", HEGEL), RdfTerm::string(evidence_type));
+        }
+        if let Some(source) = evidence.get("source").and_then(|v| v.as_str()) {
+            self.graph.add(&subject, DCTERMS_SOURCE, RdfTerm::string(source));
+        }
+        if let Some(timestamp) = evidence.get("timestamp").and_then(|v| v.as_str()) {
+            self.graph.add(&subject, DCTERMS_CREATED, RdfTerm::typed(timestamp.to_string(), XSD_DATE_TIME));
+        }
+        self.add_confidence(&subject, evidence);
+        for citation in citations(evidence) {
+            self.graph.add(&subject, CITO_CITES_AS_EVIDENCE, RdfTerm::iri(&citation));
+        }
+    }
+
+    fn add_network(&mut self, network: &MolecularGraph) {
+        let node_iri = |export: &Self, id: &str, node_type: NodeType| match node_type {
+            NodeType::Molecule => export.resource("molecule", id),
+            other => export.resource(&other.to_string().to_lowercase(), id),
+        };
+        let mut iris = BTreeMap::new();
+        for node in &network.nodes {
+            let subject = node_iri(self, &node.id, node.node_type);
+            let class = match node.node_type {
+                NodeType::Molecule => CHEMINF_CHEMICAL_ENTITY.to_string(),
+                other => format!("{}{}", HEGEL, other),
+            };
+            self.graph.add(&subject, RDF_TYPE, RdfTerm::iri(&class));
+            if !node.name.is_empty() {
+                self.graph.add(&subject, RDFS_LABEL, RdfTerm::string(&node.name));
+            }
+            iris.insert(node.id.as_str(), subject);
+        }
+        for edge in &network.edges {
+            if let (Some(source), Some(target)) = (iris.get(edge.source_id.as_str()), iris.get(edge.target_id.as_str())) {
+                self.graph.add(source, &edge_predicate(edge.edge_type), RdfTerm::iri(target));
+            }
+        }
+    }
+}
+
+/// Predicate a graph relationship is exported as
+pub fn edge_predicate(edge_type: EdgeType) -> String {
+    match edge_type {
+        EdgeType::PartOf => SIO_IS_PART_OF.to_string(),
+        EdgeType::ReferencedBy => CITO_IS_CITED_BY.to_string(),
+        EdgeType::SimilarTo => SKOS_RELATED_MATCH.to_string(),
+        EdgeType::SourcedFrom => DCTERMS_SOURCE.to_string(),
+        EdgeType::InteractsWith => SIO_IS_RELATED_TO.to_string(),
+        other => {
+            // SHOUTING_CASE edge names become camelCase vocabulary terms
+            let mut term = String::from(HEGEL);
+            for (idx, word) in other.to_string().split('_').enumerate() {
+                let word = word.to_lowercase();
+                if idx == 0 {
+                    term.push_str(&word);
+                } else {
+                    let mut chars = word.chars();
+                    if let Some(first) = chars.next() {
+                        term.extend(first.to_uppercase());
+                        term.push_str(chars.as_str());
+                    }
+                }
+            }
+            term
+        }
+    }
+}
+
+/// Resolvable IRIs of the DOIs and PubMed IDs in an evidence item's data
+fn citations(evidence: &serde_json::Value) -> Vec<String> {
+    let data = match evidence.get("data") {
+        // Evidence data is stored in Neo4j as a JSON string
+        Some(serde_json::Value::String(text)) => serde_json::from_str(text).unwrap_or(serde_json::Value::Null),
+        Some(value) => value.clone(),
+        None => serde_json::Value::Null,
+    };
+    let mut citations = Vec::new();
+    if let Some(doi) = data.get("doi").and_then(|v| v.as_str()) {
+        citations.push(format!("https://doi.org/{}", encode_segment(doi.trim_start_matches("https://doi.org/")).replace("%2F", "/")));
+    }
+    if let Some(pmid) = data.get("pmid").and_then(|v| v.as_str().map(str::to_string).or_else(|| v.as_u64().map(|n| n.to_string()))) {
+        citations.push(format!("https://pubmed.ncbi.nlm.nih.gov/{}/", encode_segment(&pmid)));
+    }
+    citations
+}
+
+/// Percent-encode characters that may not appear in an IRI segment
+fn encode_segment(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{:02X}", byte);
+        }
+    }
+    encoded
+}
+
+fn escape_literal(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn ntriples_term(term: &RdfTerm) -> String {
+    match term {
+        RdfTerm::Iri { iri } => format!("<{}>", iri),
+        RdfTerm::Literal { value, datatype: Some(datatype) } => format!("\"{}\"^^<{}>", escape_literal(value), datatype),
+        RdfTerm::Literal { value, datatype: None } => format!("\"{}\"", escape_literal(value)),
+    }
+}
+
+/// IRI in Turtle, shortened to a prefixed name when the local part allows it
+fn turtle_iri(iri: &str) -> String {
+    for (prefix, namespace) in PREFIXES {
+        if let Some(local) = iri.strip_prefix(namespace) {
+            if !local.is_empty() && local.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return format!("{}:{}", prefix, local);
+            }
+        }
+    }
+    format!("<{}>", iri)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projects::Project;
+    use std::collections::HashMap;
+
+    fn bundle() -> ProjectBundle {
+        let mut bundle = ProjectBundle::new(Project {
+            id: "demo".to_string(),
+            name: "Demo project".to_string(),
+            description: None,
+            members: HashMap::new(),
+            default_role: None,
+            created_at: chrono::Utc::now(),
+        });
+        bundle.molecules.push(serde_json::json!({
+            "id": "glucose", "name": "D-glucose", "smiles": "OC[C@H]1OC(O)[C@H](O)[C@@H](O)[C@@H]1O", "confidence": 0.9,
+        }));
+        bundle.evidence.push(serde_json::json!({
+            "id": "ev 1", "molecule_id": "glucose", "type": "literature", "source": "pubmed",
+            "confidence": 0.8, "data": "{\"pmid\": \"12345\"}",
+        }));
+        bundle
+    }
+
+    #[test]
+    fn maps_molecules_and_evidence_to_standard_terms() {
+        let graph = RdfGraph::from_bundle(&bundle(), &RdfOptions::default());
+        let molecule = "urn:hegel:demo:molecule:glucose";
+        assert!(graph.triples.iter().any(|t| t.subject == molecule && t.object == RdfTerm::iri(CHEMINF_CHEMICAL_ENTITY)));
+        assert!(graph.triples.iter().any(|t| t.subject == molecule && t.predicate == SIO_HAS_EVIDENCE
+            && t.object == RdfTerm::iri("urn:hegel:demo:evidence:ev%201")));
+        assert!(graph.triples.iter().any(|t| t.predicate == CITO_CITES_AS_EVIDENCE
+            && t.object == RdfTerm::iri("https://pubmed.ncbi.nlm.nih.gov/12345/")));
+        assert_eq!(edge_predicate(EdgeType::MetabolizedBy), "https://w3id.org/hegel/vocab#metabolizedBy");
+    }
+
+    #[test]
+    fn serializes_turtle_and_ntriples() {
+        let graph = RdfGraph::from_bundle(&bundle(), &RdfOptions::default());
+
+        let ntriples = graph.to_ntriples();
+        assert_eq!(ntriples.lines().count(), graph.triples.len());
+        assert!(ntriples.contains("<urn:hegel:demo:molecule:glucose:confidence> <http://semanticscience.org/resource/SIO_000300> \"0.9\"^^<http://www.w3.org/2001/XMLSchema#double> ."));
+
+        let turtle = graph.to_turtle();
+        assert!(turtle.starts_with("@prefix rdf:"));
+        assert!(turtle.contains("\n<urn:hegel:demo:molecule:glucose>\n    a sio:CHEMINF_000000 ;"));
+        assert!(turtle.contains("rdfs:label \"D-glucose\""));
+    }
+}
@@ -0,0 +1,324 @@
+//! Duplicate Molecule Reconciliation
+//!
+//! Databases filled before molecule IDs were InChIKeys often hold one compound
+//! under several IDs. Reconciliation groups the stored molecules of a project
+//! that share an InChIKey or a standardized structure and merges each group
+//! into a single canonical node. Properties are merged without overwriting:
+//! the canonical node keeps its values and gains the ones it lacks, and every
+//! value that disagreed is recorded in the audit report instead of being lost.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::identity::MoleculeIdType;
+use crate::processing::structure_hash;
+
+/// Molecule properties that may hold an InChIKey
+const INCHIKEY_PROPERTIES: [&str; 3] = ["ext_inchikey", "inchi_key", "inchikey"];
+
+/// Properties owned by the store or recomputed at the next integration, never merged
+const MANAGED_PROPERTIES: [&str; 9] = [
+    "id", "project_id", "confidence", "conflicts", "conflict_details", "integrated_at",
+    "confidence_history", "confidence_history_at", "confidence_history_trigger",
+];
+
+/// A molecule as stored, with its evidence count
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMolecule {
+    /// Molecule ID
+    pub id: String,
+
+    /// Stored properties
+    pub properties: Map<String, Value>,
+
+    /// Number of evidence items supporting the molecule
+    pub evidence_count: usize,
+}
+
+impl StoredMolecule {
+    /// InChIKey of the molecule, from its properties or its ID
+    pub fn inchi_key(&self) -> Option<String> {
+        INCHIKEY_PROPERTIES.iter()
+            .filter_map(|key| self.properties.get(*key)?.as_str())
+            .chain(std::iter::once(self.id.as_str()))
+            .find_map(|value| MoleculeIdType::InChIKey.normalize(value).ok())
+    }
+
+    /// Keys under which the molecule is a duplicate of another
+    ///
+    /// Its InChIKey, and a hash of its SMILES with whitespace removed. SMILES
+    /// are compared as text, so differently drawn structures only match
+    /// through an InChIKey.
+    pub fn duplicate_keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        if let Some(key) = self.inchi_key() {
+            keys.push(format!("inchikey:{}", key));
+        }
+        let smiles: String = self.properties.get("smiles")
+            .and_then(|s| s.as_str())
+            .map(|s| s.chars().filter(|c| !c.is_whitespace()).collect())
+            .unwrap_or_default();
+        if !smiles.is_empty() {
+            keys.push(format!("structure:{}", structure_hash(&smiles)));
+        }
+        keys
+    }
+}
+
+/// Stored molecules found to be the same compound
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Molecule the others are merged into
+    pub canonical_id: String,
+
+    /// Molecules merged into it
+    pub duplicate_ids: Vec<String>,
+
+    /// Keys the molecules were matched by
+    pub matched_by: Vec<String>,
+}
+
+/// Group the molecules that share a duplicate key
+///
+/// Molecules are linked transitively, so one with both an InChIKey and a
+/// SMILES joins the molecules matching either. The canonical molecule of a
+/// group is the one whose ID is its InChIKey, then the one with the most
+/// evidence, then the lowest ID.
+pub fn find_duplicates(molecules: &[StoredMolecule]) -> Vec<DuplicateGroup> {
+    let mut parent: Vec<usize> = (0..molecules.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut first_with_key: HashMap<String, usize> = HashMap::new();
+    let keys: Vec<Vec<String>> = molecules.iter().map(StoredMolecule::duplicate_keys).collect();
+    for (i, molecule_keys) in keys.iter().enumerate() {
+        for key in molecule_keys {
+            match first_with_key.get(key) {
+                Some(&j) => {
+                    let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                    parent[a] = b;
+                }
+                None => {
+                    first_with_key.insert(key.clone(), i);
+                }
+            }
+        }
+    }
+
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..molecules.len() {
+        let r = root(&mut parent, i);
+        members.entry(r).or_default().push(i);
+    }
+
+    let mut groups: Vec<DuplicateGroup> = members.into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let canonical = *group.iter()
+                .max_by(|&&a, &&b| {
+                    let (a, b) = (&molecules[a], &molecules[b]);
+                    let named = |m: &StoredMolecule| m.inchi_key().as_deref() == Some(m.id.as_str());
+                    named(a).cmp(&named(b))
+                        .then(a.evidence_count.cmp(&b.evidence_count))
+                        .then(b.id.cmp(&a.id))
+                })
+                .expect("groups are not empty");
+            let mut duplicate_ids: Vec<String> = group.iter()
+                .filter(|&&i| i != canonical)
+                .map(|&i| molecules[i].id.clone())
+                .collect();
+            duplicate_ids.sort();
+            let mut matched_by: Vec<String> = group.iter()
+                .flat_map(|&i| keys[i].iter().cloned())
+                .filter(|key| group.iter().filter(|&&i| keys[i].contains(key)).count() > 1)
+                .collect();
+            matched_by.sort();
+            matched_by.dedup();
+            DuplicateGroup { canonical_id: molecules[canonical].id.clone(), duplicate_ids, matched_by }
+        })
+        .collect();
+    groups.sort_by(|a, b| a.canonical_id.cmp(&b.canonical_id));
+    groups
+}
+
+/// A property whose value differed between merged molecules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyConflict {
+    /// Property name
+    pub property: String,
+
+    /// Value kept on the canonical molecule
+    pub kept: Value,
+
+    /// Value of the duplicate that was not kept
+    pub discarded: Value,
+
+    /// Duplicate the discarded value came from
+    pub from: String,
+}
+
+/// Merge a duplicate's properties into the canonical molecule's
+///
+/// Properties the canonical molecule lacks (or holds as null) are copied;
+/// differing values are left as they are and reported as conflicts. Returns
+/// the names of the properties that were added.
+pub fn merge_properties(
+    canonical: &mut Map<String, Value>,
+    duplicate_id: &str,
+    duplicate: &Map<String, Value>,
+    conflicts: &mut Vec<PropertyConflict>,
+) -> Vec<String> {
+    let mut added = Vec::new();
+    for (property, value) in duplicate {
+        if MANAGED_PROPERTIES.contains(&property.as_str()) || value.is_null() {
+            continue;
+        }
+        match canonical.get(property) {
+            None | Some(Value::Null) => {
+                canonical.insert(property.clone(), value.clone());
+                added.push(property.clone());
+            }
+            Some(kept) if kept == value => {}
+            Some(kept) => conflicts.push(PropertyConflict {
+                property: property.clone(),
+                kept: kept.clone(),
+                discarded: value.clone(),
+                from: duplicate_id.to_string(),
+            }),
+        }
+    }
+    added.sort();
+    added
+}
+
+/// How one group of duplicates was merged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeRecord {
+    /// Molecule the duplicates were merged into
+    pub canonical_id: String,
+
+    /// Molecules merged into it and removed; their IDs remain as aliases
+    pub merged_ids: Vec<String>,
+
+    /// Keys the molecules were matched by
+    pub matched_by: Vec<String>,
+
+    /// Evidence items moved to the canonical molecule
+    pub evidence_moved: usize,
+
+    /// Other relationships moved to the canonical molecule
+    pub relationships_moved: usize,
+
+    /// Properties copied from the duplicates
+    pub properties_added: Vec<String>,
+
+    /// Values that differed and were not copied
+    pub conflicts: Vec<PropertyConflict>,
+}
+
+/// Audit report of a reconciliation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    /// Project that was reconciled
+    pub project_id: String,
+
+    /// Whether the merges were only planned
+    pub dry_run: bool,
+
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+
+    /// Molecules examined
+    pub molecules_scanned: usize,
+
+    /// One record per group of duplicates
+    pub merges: Vec<MergeRecord>,
+}
+
+impl ReconciliationReport {
+    /// Empty report for a run starting now
+    pub fn new(project_id: &str, dry_run: bool, molecules_scanned: usize) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+            dry_run,
+            started_at: Utc::now(),
+            molecules_scanned,
+            merges: Vec::new(),
+        }
+    }
+
+    /// Number of molecules merged away
+    pub fn molecules_merged(&self) -> usize {
+        self.merges.iter().map(|m| m.merged_ids.len()).sum()
+    }
+
+    /// Number of property values that differed between duplicates
+    pub fn conflict_count(&self) -> usize {
+        self.merges.iter().map(|m| m.conflicts.len()).sum()
+    }
+
+    /// Write the report as JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write reconciliation report {}", path.display()))?;
+        info!("Wrote reconciliation report to {}", path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CAFFEINE: &str = "RYYVLZVUVIJVGH-UHFFFAOYSA-N";
+
+    fn molecule(id: &str, properties: Value, evidence_count: usize) -> StoredMolecule {
+        StoredMolecule {
+            id: id.to_string(),
+            properties: properties.as_object().cloned().unwrap_or_default(),
+            evidence_count,
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates_links_inchikey_and_structure() {
+        let molecules = vec![
+            molecule("mol-a", json!({"smiles": "Cn1cnc2c1c(=O)n(C)c(=O)n2C", "ext_inchikey": CAFFEINE}), 1),
+            molecule(CAFFEINE, json!({}), 0),
+            molecule("mol-b", json!({"smiles": " Cn1cnc2c1c(=O)n(C)c(=O)n2C"}), 5),
+            molecule("mol-c", json!({"smiles": "CCO"}), 2),
+        ];
+
+        let groups = find_duplicates(&molecules);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].canonical_id, CAFFEINE);
+        assert_eq!(groups[0].duplicate_ids, vec!["mol-a", "mol-b"]);
+        assert_eq!(groups[0].matched_by.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_properties_keeps_canonical_values() {
+        let mut canonical = json!({"name": "caffeine", "formula": null, "confidence": 0.9}).as_object().cloned().unwrap();
+        let duplicate = json!({"name": "guaranine", "formula": "C8H10N4O2", "confidence": 0.4}).as_object().cloned().unwrap();
+
+        let mut conflicts = Vec::new();
+        let added = merge_properties(&mut canonical, "mol-b", &duplicate, &mut conflicts);
+        assert_eq!(added, vec!["formula"]);
+        assert_eq!(canonical["name"], "caffeine");
+        assert_eq!(canonical["confidence"], 0.9);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].discarded, "guaranine");
+    }
+}
@@ -2,11 +2,21 @@
 //! 
 //! This module defines the schema for molecular graphs and networks, providing
 //! strongly-typed representations of nodes, edges, and their properties.
+//!
+//! Each node and edge type declares the properties it knows, with their types
+//! and whether they are required. Properties are checked as they are added
+//! and a whole node or edge can be validated before it is written, so a value
+//! Neo4j cannot store, or one that would overwrite a reserved property such
+//! as `id` or `project_id`, is caught instead of being mis-serialized.
+//! Undeclared properties are allowed as long as Neo4j can store them.
 
-use anyhow::Result;
-use log::{debug, info};
+use anyhow::{anyhow, Result};
+use log::warn;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::projects::DEFAULT_PROJECT;
 
 /// Node types in the molecular knowledge graph
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -34,21 +44,76 @@ pub enum NodeType {
     
     /// A data source or database
     Source,
+    
+    /// A biological sample that evidence was measured in
+    Sample,
+    
+    /// A study group or cohort of samples, e.g. cases or controls
+    StudyGroup,
 }
 
-impl std::fmt::Display for NodeType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl NodeType {
+    /// All node types
+    pub const ALL: [NodeType; 10] = [
+        NodeType::Molecule,
+        NodeType::Organism,
+        NodeType::Protein,
+        NodeType::Gene,
+        NodeType::Pathway,
+        NodeType::Disease,
+        NodeType::Publication,
+        NodeType::Source,
+        NodeType::Sample,
+        NodeType::StudyGroup,
+    ];
+
+    /// Neo4j label of the node type
+    pub fn label(&self) -> &'static str {
         match self {
-            NodeType::Molecule => write!(f, "Molecule"),
-            NodeType::Organism => write!(f, "Organism"),
-            NodeType::Protein => write!(f, "Protein"),
-            NodeType::Gene => write!(f, "Gene"),
-            NodeType::Pathway => write!(f, "Pathway"),
-            NodeType::Disease => write!(f, "Disease"),
-            NodeType::Publication => write!(f, "Publication"),
-            NodeType::Source => write!(f, "Source"),
+            NodeType::Molecule => "Molecule",
+            NodeType::Organism => "Organism",
+            NodeType::Protein => "Protein",
+            NodeType::Gene => "Gene",
+            NodeType::Pathway => "Pathway",
+            NodeType::Disease => "Disease",
+            NodeType::Publication => "Publication",
+            NodeType::Source => "Source",
+            NodeType::Sample => "Sample",
+            NodeType::StudyGroup => "StudyGroup",
         }
     }
+
+    /// Properties the node type declares
+    pub fn properties(&self) -> &'static [PropertySpec] {
+        match self {
+            NodeType::Molecule => MOLECULE_NODE_PROPERTIES,
+            NodeType::Organism => ORGANISM_NODE_PROPERTIES,
+            NodeType::Protein => PROTEIN_NODE_PROPERTIES,
+            NodeType::Gene => GENE_NODE_PROPERTIES,
+            NodeType::Pathway => PATHWAY_NODE_PROPERTIES,
+            NodeType::Disease => DISEASE_NODE_PROPERTIES,
+            NodeType::Publication => PUBLICATION_NODE_PROPERTIES,
+            NodeType::Source => SOURCE_NODE_PROPERTIES,
+            NodeType::Sample => SAMPLE_NODE_PROPERTIES,
+            NodeType::StudyGroup => STUDY_GROUP_NODE_PROPERTIES,
+        }
+    }
+}
+
+impl std::fmt::Display for NodeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl FromStr for NodeType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        NodeType::ALL.into_iter()
+            .find(|node_type| node_type.label() == s)
+            .ok_or_else(|| anyhow!("Unknown node type: {}", s))
+    }
 }
 
 /// Edge types in the molecular knowledge graph
@@ -86,26 +151,325 @@ pub enum EdgeType {
     
     /// Metabolized by an organism
     MetabolizedBy,
+    
+    /// Member of a study group
+    MemberOf,
+    
+    /// Measured in a sample
+    MeasuredIn,
+}
+
+impl EdgeType {
+    /// All edge types
+    pub const ALL: [EdgeType; 13] = [
+        EdgeType::SimilarTo,
+        EdgeType::PartOf,
+        EdgeType::InteractsWith,
+        EdgeType::Inhibits,
+        EdgeType::Activates,
+        EdgeType::Treats,
+        EdgeType::Causes,
+        EdgeType::ReferencedBy,
+        EdgeType::SourcedFrom,
+        EdgeType::TransformsTo,
+        EdgeType::MetabolizedBy,
+        EdgeType::MemberOf,
+        EdgeType::MeasuredIn,
+    ];
+}
+
+impl EdgeType {
+    /// Neo4j relationship type of the edge type
+    pub fn label(&self) -> &'static str {
+        match self {
+            EdgeType::SimilarTo => "SIMILAR_TO",
+            EdgeType::PartOf => "PART_OF",
+            EdgeType::InteractsWith => "INTERACTS_WITH",
+            EdgeType::Inhibits => "INHIBITS",
+            EdgeType::Activates => "ACTIVATES",
+            EdgeType::Treats => "TREATS",
+            EdgeType::Causes => "CAUSES",
+            EdgeType::ReferencedBy => "REFERENCED_BY",
+            EdgeType::SourcedFrom => "SOURCED_FROM",
+            EdgeType::TransformsTo => "TRANSFORMS_TO",
+            EdgeType::MetabolizedBy => "METABOLIZED_BY",
+            EdgeType::MemberOf => "MEMBER_OF",
+            EdgeType::MeasuredIn => "MEASURED_IN",
+        }
+    }
+
+    /// Properties the edge type declares
+    pub fn properties(&self) -> &'static [PropertySpec] {
+        match self {
+            EdgeType::SimilarTo => SIMILAR_TO_EDGE_PROPERTIES,
+            EdgeType::PartOf | EdgeType::MemberOf => MEMBERSHIP_EDGE_PROPERTIES,
+            EdgeType::InteractsWith | EdgeType::Inhibits | EdgeType::Activates => INTERACTION_EDGE_PROPERTIES,
+            EdgeType::Treats | EdgeType::Causes => ASSOCIATION_EDGE_PROPERTIES,
+            EdgeType::ReferencedBy | EdgeType::SourcedFrom => PROVENANCE_EDGE_PROPERTIES,
+            EdgeType::TransformsTo => TRANSFORMS_TO_EDGE_PROPERTIES,
+            EdgeType::MetabolizedBy => METABOLIZED_BY_EDGE_PROPERTIES,
+            EdgeType::MeasuredIn => MEASURED_IN_EDGE_PROPERTIES,
+        }
+    }
 }
 
 impl std::fmt::Display for EdgeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl FromStr for EdgeType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        EdgeType::ALL.into_iter()
+            .find(|edge_type| edge_type.label() == s)
+            .ok_or_else(|| anyhow!("Unknown edge type: {}", s))
+    }
+}
+
+/// Node properties written by the store itself, which node properties may not overwrite
+pub const RESERVED_NODE_PROPERTIES: [&str; 4] = ["id", "name", "project_id", "labels"];
+
+/// Edge properties written by the store itself
+pub const RESERVED_EDGE_PROPERTIES: [&str; 1] = ["id"];
+
+/// Prefix of the node properties external IDs are stored under
+const EXTERNAL_ID_PREFIX: &str = "ext_";
+
+/// Type of a property value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyType {
+    /// Text
+    String,
+
+    /// Any number
+    Number,
+
+    /// Whole number
+    Integer,
+
+    /// True or false
+    Boolean,
+
+    /// List of texts
+    StringList,
+
+    /// List of numbers
+    NumberList,
+}
+
+impl PropertyType {
+    /// Whether a value has this type
+    pub fn matches(&self, value: &serde_json::Value) -> bool {
+        use serde_json::Value;
+        let all = |check: fn(&Value) -> bool| value.as_array().is_some_and(|items| items.iter().all(check));
+        match self {
+            PropertyType::String => value.is_string(),
+            PropertyType::Number => value.is_number(),
+            PropertyType::Integer => value.is_i64() || value.is_u64(),
+            PropertyType::Boolean => value.is_boolean(),
+            PropertyType::StringList => all(Value::is_string),
+            PropertyType::NumberList => all(Value::is_number),
+        }
+    }
+}
+
+impl std::fmt::Display for PropertyType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            EdgeType::SimilarTo => write!(f, "SIMILAR_TO"),
-            EdgeType::PartOf => write!(f, "PART_OF"),
-            EdgeType::InteractsWith => write!(f, "INTERACTS_WITH"),
-            EdgeType::Inhibits => write!(f, "INHIBITS"),
-            EdgeType::Activates => write!(f, "ACTIVATES"),
-            EdgeType::Treats => write!(f, "TREATS"),
-            EdgeType::Causes => write!(f, "CAUSES"),
-            EdgeType::ReferencedBy => write!(f, "REFERENCED_BY"),
-            EdgeType::SourcedFrom => write!(f, "SOURCED_FROM"),
-            EdgeType::TransformsTo => write!(f, "TRANSFORMS_TO"),
-            EdgeType::MetabolizedBy => write!(f, "METABOLIZED_BY"),
+            PropertyType::String => write!(f, "string"),
+            PropertyType::Number => write!(f, "number"),
+            PropertyType::Integer => write!(f, "integer"),
+            PropertyType::Boolean => write!(f, "boolean"),
+            PropertyType::StringList => write!(f, "list of strings"),
+            PropertyType::NumberList => write!(f, "list of numbers"),
         }
     }
 }
 
+/// A property declared by a node or edge type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertySpec {
+    /// Property name
+    pub name: &'static str,
+
+    /// Type its value must have
+    pub property_type: PropertyType,
+
+    /// Whether every node or edge of the type must have it
+    pub required: bool,
+}
+
+impl PropertySpec {
+    /// A property every node or edge of the type must have
+    pub const fn required(name: &'static str, property_type: PropertyType) -> Self {
+        Self { name, property_type, required: true }
+    }
+
+    /// A property nodes or edges of the type may have
+    pub const fn optional(name: &'static str, property_type: PropertyType) -> Self {
+        Self { name, property_type, required: false }
+    }
+}
+
+// Properties declared by each node and edge type; edge types that play the
+// same part share a list
+
+const MOLECULE_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("formula", PropertyType::String),
+    PropertySpec::optional("smiles", PropertyType::String),
+    PropertySpec::optional("inchi", PropertyType::String),
+    PropertySpec::optional("inchi_key", PropertyType::String),
+    PropertySpec::optional("monoisotopic_mass", PropertyType::Number),
+    PropertySpec::optional("synonyms", PropertyType::StringList),
+    PropertySpec::optional("confidence", PropertyType::Number),
+    PropertySpec::optional("conflict_count", PropertyType::Integer),
+    PropertySpec::optional("last_integrated", PropertyType::String),
+    PropertySpec::optional("pipeline_fingerprint", PropertyType::String),
+];
+
+const ORGANISM_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("taxonomy_id", PropertyType::String),
+    PropertySpec::optional("rank", PropertyType::String),
+];
+
+const PROTEIN_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("organism", PropertyType::String),
+    PropertySpec::optional("gene", PropertyType::String),
+    PropertySpec::optional("sequence", PropertyType::String),
+];
+
+const GENE_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("symbol", PropertyType::String),
+    PropertySpec::optional("organism", PropertyType::String),
+    PropertySpec::optional("chromosome", PropertyType::String),
+];
+
+const PATHWAY_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("source", PropertyType::String),
+    PropertySpec::optional("organism", PropertyType::String),
+];
+
+const DISEASE_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("ontology_id", PropertyType::String),
+];
+
+const PUBLICATION_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("doi", PropertyType::String),
+    PropertySpec::optional("pmid", PropertyType::String),
+    PropertySpec::optional("year", PropertyType::Integer),
+    PropertySpec::optional("journal", PropertyType::String),
+];
+
+const SOURCE_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("url", PropertyType::String),
+    PropertySpec::optional("version", PropertyType::String),
+];
+
+const SAMPLE_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("organism", PropertyType::String),
+    PropertySpec::optional("tissue", PropertyType::String),
+    PropertySpec::optional("collected_at", PropertyType::String),
+];
+
+const STUDY_GROUP_NODE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("role", PropertyType::String),
+    PropertySpec::optional("sample_count", PropertyType::Integer),
+];
+
+const SIMILAR_TO_EDGE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::required("similarity", PropertyType::Number),
+    PropertySpec::optional("metric", PropertyType::String),
+];
+
+const MEMBERSHIP_EDGE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("confidence", PropertyType::Number),
+];
+
+const INTERACTION_EDGE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("confidence", PropertyType::Number),
+    PropertySpec::optional("evidence_count", PropertyType::Integer),
+    PropertySpec::optional("activity_type", PropertyType::String),
+    PropertySpec::optional("value_nm", PropertyType::Number),
+    PropertySpec::optional("relation", PropertyType::String),
+    PropertySpec::optional("p_affinity", PropertyType::Number),
+    PropertySpec::optional("source", PropertyType::String),
+];
+
+const ASSOCIATION_EDGE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("confidence", PropertyType::Number),
+    PropertySpec::optional("evidence_count", PropertyType::Integer),
+];
+
+const PROVENANCE_EDGE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("accession", PropertyType::String),
+];
+
+const TRANSFORMS_TO_EDGE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::required("transformation", PropertyType::String),
+    PropertySpec::optional("formula_change", PropertyType::String),
+    PropertySpec::optional("mass_delta", PropertyType::Number),
+    PropertySpec::optional("error_ppm", PropertyType::Number),
+    PropertySpec::optional("confidence", PropertyType::Number),
+];
+
+const METABOLIZED_BY_EDGE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("enzyme", PropertyType::String),
+    PropertySpec::optional("confidence", PropertyType::Number),
+];
+
+const MEASURED_IN_EDGE_PROPERTIES: &[PropertySpec] = &[
+    PropertySpec::optional("intensity", PropertyType::Number),
+    PropertySpec::optional("confidence", PropertyType::Number),
+];
+
+/// Check one property against the declared properties of its node or edge type
+///
+/// Declared properties must have their declared type. Undeclared ones must be
+/// something Neo4j can store as a property: a string, number or boolean, or a
+/// list of one of those. Maps and nulls cannot be stored.
+fn validate_property(specs: &[PropertySpec], reserved: &[&str], key: &str, value: &serde_json::Value) -> Result<()> {
+    if reserved.contains(&key) {
+        return Err(anyhow!("Property {} is reserved", key));
+    }
+    if let Some(spec) = specs.iter().find(|spec| spec.name == key) {
+        if !spec.property_type.matches(value) {
+            return Err(anyhow!("Property {} must be a {}, got {}", key, spec.property_type, value));
+        }
+        return Ok(());
+    }
+
+    let scalar = |v: &serde_json::Value| v.is_string() || v.is_number() || v.is_boolean();
+    let storable = match value.as_array() {
+        Some(items) => items.iter().all(scalar)
+            && (items.iter().all(|v| v.is_string())
+                || items.iter().all(|v| v.is_number())
+                || items.iter().all(|v| v.is_boolean())),
+        None => scalar(value),
+    };
+    if storable {
+        Ok(())
+    } else {
+        Err(anyhow!("Property {} cannot be stored in the graph: {}", key, value))
+    }
+}
+
+/// Check that the required properties are present
+fn validate_required(specs: &[PropertySpec], properties: &HashMap<String, serde_json::Value>) -> Result<()> {
+    let missing: Vec<&str> = specs.iter()
+        .filter(|spec| spec.required && !properties.contains_key(spec.name))
+        .map(|spec| spec.name)
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("Missing required properties: {}", missing.join(", ")))
+    }
+}
+
 /// A node in the molecular knowledge graph
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
@@ -137,11 +501,42 @@ impl Node {
         }
     }
     
-    /// Add a property to the node
+    /// Add a property to the node, skipping it with a warning if it is invalid
+    ///
+    /// See `try_add_property` for the checks.
     pub fn add_property(&mut self, key: &str, value: serde_json::Value) -> &mut Self {
-        self.properties.insert(key.to_string(), value);
+        if let Err(e) = self.try_add_property(key, value) {
+            warn!("Skipping property of {} node {}: {}", self.node_type, self.id, e);
+        }
         self
     }
+
+    /// Add a property to the node if it fits the node type's schema
+    ///
+    /// Reserved names, values of the wrong type for a declared property and
+    /// values Neo4j cannot store are rejected.
+    pub fn try_add_property(&mut self, key: &str, value: serde_json::Value) -> Result<&mut Self> {
+        if key.starts_with(EXTERNAL_ID_PREFIX) {
+            return Err(anyhow!("Property {} is reserved for external IDs; use add_external_id", key));
+        }
+        validate_property(self.node_type.properties(), &RESERVED_NODE_PROPERTIES, key, &value)?;
+        self.properties.insert(key.to_string(), value);
+        Ok(self)
+    }
+
+    /// Check the node's properties against its type's schema
+    pub fn validate(&self) -> Result<()> {
+        validate_required(self.node_type.properties(), &self.properties)
+            .map_err(|e| anyhow!("{} node {}: {}", self.node_type, self.id, e))?;
+        for (key, value) in &self.properties {
+            if key.starts_with(EXTERNAL_ID_PREFIX) {
+                return Err(anyhow!("{} node {}: property {} is reserved for external IDs", self.node_type, self.id, key));
+            }
+            validate_property(self.node_type.properties(), &RESERVED_NODE_PROPERTIES, key, value)
+                .map_err(|e| anyhow!("{} node {}: {}", self.node_type, self.id, e))?;
+        }
+        Ok(())
+    }
     
     /// Add an external identifier to the node
     pub fn add_external_id(&mut self, system: &str, id: &str) -> &mut Self {
@@ -195,11 +590,31 @@ impl Edge {
         }
     }
     
-    /// Add a property to the edge
+    /// Add a property to the edge, skipping it with a warning if it is invalid
     pub fn add_property(&mut self, key: &str, value: serde_json::Value) -> &mut Self {
-        self.properties.insert(key.to_string(), value);
+        if let Err(e) = self.try_add_property(key, value) {
+            warn!("Skipping property of {} edge {}: {}", self.edge_type, self.id, e);
+        }
         self
     }
+
+    /// Add a property to the edge if it fits the edge type's schema
+    pub fn try_add_property(&mut self, key: &str, value: serde_json::Value) -> Result<&mut Self> {
+        validate_property(self.edge_type.properties(), &RESERVED_EDGE_PROPERTIES, key, &value)?;
+        self.properties.insert(key.to_string(), value);
+        Ok(self)
+    }
+
+    /// Check the edge's properties against its type's schema
+    pub fn validate(&self) -> Result<()> {
+        validate_required(self.edge_type.properties(), &self.properties)
+            .map_err(|e| anyhow!("{} edge {}: {}", self.edge_type, self.id, e))?;
+        for (key, value) in &self.properties {
+            validate_property(self.edge_type.properties(), &RESERVED_EDGE_PROPERTIES, key, value)
+                .map_err(|e| anyhow!("{} edge {}: {}", self.edge_type, self.id, e))?;
+        }
+        Ok(())
+    }
     
     /// Get a property value
     pub fn get_property(&self, key: &str) -> Option<&serde_json::Value> {
@@ -216,6 +631,10 @@ pub struct MolecularGraph {
     /// Name of the graph
     pub name: String,
     
+    /// Project the graph belongs to
+    #[serde(default = "default_project")]
+    pub project_id: String,
+    
     /// Nodes in the graph
     pub nodes: Vec<Node>,
     
@@ -232,12 +651,19 @@ impl MolecularGraph {
         Self {
             id,
             name,
+            project_id: DEFAULT_PROJECT.to_string(),
             nodes: Vec::new(),
             edges: Vec::new(),
             metadata: HashMap::new(),
         }
     }
     
+    /// Move the graph into a project
+    pub fn set_project(&mut self, project_id: &str) -> &mut Self {
+        self.project_id = project_id.to_string();
+        self
+    }
+    
     /// Add a node to the graph
     pub fn add_node(&mut self, node: Node) -> &mut Self {
         self.nodes.push(node);
@@ -250,6 +676,19 @@ impl MolecularGraph {
         self
     }
     
+    /// Check every node and edge against its type's schema, listing all problems
+    pub fn validate(&self) -> Result<()> {
+        let problems: Vec<String> = self.nodes.iter().map(Node::validate)
+            .chain(self.edges.iter().map(Edge::validate))
+            .filter_map(|result| result.err().map(|e| e.to_string()))
+            .collect();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Graph {} does not match the schema: {}", self.id, problems.join("; ")))
+        }
+    }
+    
     /// Find a node by ID
     pub fn find_node(&self, id: &str) -> Option<&Node> {
         self.nodes.iter().find(|node| node.id == id)
@@ -298,6 +737,10 @@ impl MolecularGraph {
     }
 }
 
+fn default_project() -> String {
+    DEFAULT_PROJECT.to_string()
+}
+
 /// A path in a molecular graph
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphPath {
@@ -380,6 +823,44 @@ mod tests {
         assert_eq!(edge.get_property("affinity"), Some(&serde_json::json!(0.89)));
     }
     
+    #[test]
+    fn test_properties_are_checked_against_the_schema() {
+        let mut node = Node::new("mol_123".to_string(), NodeType::Molecule, "Glucose".to_string());
+        assert!(node.try_add_property("monoisotopic_mass", serde_json::json!(180.0634)).is_ok());
+        assert!(node.try_add_property("monoisotopic_mass", serde_json::json!("180.06")).is_err());
+        assert!(node.try_add_property("project_id", serde_json::json!("other")).is_err());
+        assert!(node.try_add_property("ext_hmdb", serde_json::json!("HMDB0000122")).is_err());
+        assert!(node.try_add_property("spectrum", serde_json::json!({"peaks": []})).is_err());
+        assert!(node.try_add_property("tags", serde_json::json!(["sugar", 1])).is_err());
+        node.add_property("tags", serde_json::json!(["sugar", "hexose"]));
+        node.add_property("spectrum", serde_json::json!(null));
+        assert!(node.get_property("spectrum").is_none());
+        assert!(node.validate().is_ok());
+
+        let mut edge = Edge::new("a".to_string(), "b".to_string(), EdgeType::SimilarTo);
+        assert!(edge.validate().is_err());
+        edge.add_property("similarity", serde_json::json!(0.8));
+        let mut graph = MolecularGraph::new("g".to_string(), "G".to_string());
+        graph.add_node(node).add_edge(edge);
+        assert!(graph.validate().is_ok());
+
+        node = Node::new("mol_456".to_string(), NodeType::Molecule, "Fructose".to_string());
+        node.properties.insert("name".to_string(), serde_json::json!("overwritten"));
+        graph.add_node(node);
+        assert!(graph.validate().unwrap_err().to_string().contains("mol_456"));
+    }
+
+    #[test]
+    fn test_type_labels_round_trip() {
+        for node_type in NodeType::ALL {
+            assert_eq!(node_type.to_string().parse::<NodeType>().unwrap(), node_type);
+        }
+        for edge_type in EdgeType::ALL {
+            assert_eq!(edge_type.to_string().parse::<EdgeType>().unwrap(), edge_type);
+        }
+        assert!("SIMILAR".parse::<EdgeType>().is_err());
+    }
+    
     #[test]
     fn test_graph_operations() {
         let mut graph = MolecularGraph::new(
@@ -0,0 +1,537 @@
+//! Similarity Metrics Module
+//!
+//! This module provides a pluggable registry of molecular similarity metrics.
+//! Metrics are looked up by name, so network construction, the public API and
+//! the REST endpoints can all dispatch to built-in or user-registered metrics.
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use super::embeddings::{cosine, EMBEDDING_PROPERTY};
+use crate::processing::Molecule;
+use crate::processing::spectral::{modified_cosine, Peak, Spectrum};
+
+/// Name of the metric used when none is specified
+pub const DEFAULT_METRIC: &str = "tanimoto";
+
+/// Molecule property holding an MS/MS spectrum as `[mz, intensity]` pairs
+pub const SPECTRUM_PROPERTY: &str = "spectrum";
+
+/// Molecule property holding the precursor m/z of its MS/MS spectrum
+pub const PRECURSOR_MZ_PROPERTY: &str = "precursor_mz";
+
+/// A named similarity measure between two molecules
+pub trait SimilarityMetric: Send + Sync {
+    /// Name under which the metric is registered
+    fn name(&self) -> &str;
+
+    /// Calculate the similarity between two molecules (0.0 - 1.0)
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64>;
+}
+
+/// Tanimoto coefficient over SMILES substring fingerprints
+pub struct TanimotoSimilarity;
+
+impl SimilarityMetric for TanimotoSimilarity {
+    fn name(&self) -> &str {
+        "tanimoto"
+    }
+
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64> {
+        let fp_a = smiles_fingerprint(&a.smiles);
+        let fp_b = smiles_fingerprint(&b.smiles);
+
+        let union = fp_a.union(&fp_b).count();
+        if union == 0 {
+            return Ok(0.0);
+        }
+
+        let intersection = fp_a.intersection(&fp_b).count();
+        Ok(intersection as f64 / union as f64)
+    }
+}
+
+/// Dice coefficient over SMILES substring fingerprints
+pub struct DiceSimilarity;
+
+impl SimilarityMetric for DiceSimilarity {
+    fn name(&self) -> &str {
+        "dice"
+    }
+
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64> {
+        let fp_a = smiles_fingerprint(&a.smiles);
+        let fp_b = smiles_fingerprint(&b.smiles);
+
+        let total = fp_a.len() + fp_b.len();
+        if total == 0 {
+            return Ok(0.0);
+        }
+
+        let intersection = fp_a.intersection(&fp_b).count();
+        Ok(2.0 * intersection as f64 / total as f64)
+    }
+}
+
+/// Cosine similarity between spectra stored in the `spectrum` property
+///
+/// The property is expected to hold `[mz, intensity]` pairs. Peaks are matched
+/// one-to-one within a fixed m/z tolerance.
+pub struct SpectralSimilarity {
+    /// Tolerance for matching peaks (m/z units)
+    pub tolerance: f64,
+}
+
+impl Default for SpectralSimilarity {
+    fn default() -> Self {
+        Self { tolerance: 0.01 }
+    }
+}
+
+impl SimilarityMetric for SpectralSimilarity {
+    fn name(&self) -> &str {
+        "spectral"
+    }
+
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64> {
+        let (peaks_a, peaks_b) = match (spectrum_property(a), spectrum_property(b)) {
+            (Some(pa), Some(pb)) => (pa, pb),
+            _ => return Ok(0.0),
+        };
+
+        // Pair peaks one-to-one, greedily by intensity product, so that no
+        // peak contributes to the dot product twice
+        let mut candidates = Vec::new();
+        for (i, &(mz_a, int_a)) in peaks_a.iter().enumerate() {
+            for (j, &(mz_b, int_b)) in peaks_b.iter().enumerate() {
+                if (mz_a - mz_b).abs() <= self.tolerance {
+                    candidates.push((int_a * int_b, i, j));
+                }
+            }
+        }
+        candidates.sort_by(|x, y| y.0.total_cmp(&x.0));
+
+        let (mut used_a, mut used_b) = (vec![false; peaks_a.len()], vec![false; peaks_b.len()]);
+        let mut dot = 0.0;
+        for (product, i, j) in candidates {
+            if used_a[i] || used_b[j] {
+                continue;
+            }
+            used_a[i] = true;
+            used_b[j] = true;
+            dot += product;
+        }
+
+        let norm_a = peaks_a.iter().map(|(_, i)| i * i).sum::<f64>().sqrt();
+        let norm_b = peaks_b.iter().map(|(_, i)| i * i).sum::<f64>().sqrt();
+        if norm_a == 0.0 || norm_b == 0.0 {
+            return Ok(0.0);
+        }
+
+        Ok(dot / (norm_a * norm_b))
+    }
+}
+
+/// Modified cosine between MS/MS spectra, as used for molecular networking
+///
+/// Reads the `spectrum` property and the precursor m/z from `precursor_mz`.
+/// Pairs sharing fewer than `min_matched_peaks` peaks score zero, so that a
+/// single intense shared fragment doesn't link unrelated features.
+pub struct ModifiedCosineSimilarity {
+    /// Tolerance for matching peaks (m/z units)
+    pub tolerance: f64,
+
+    /// Fewest paired peaks for a non-zero score
+    pub min_matched_peaks: usize,
+}
+
+impl Default for ModifiedCosineSimilarity {
+    fn default() -> Self {
+        Self { tolerance: 0.02, min_matched_peaks: 6 }
+    }
+}
+
+impl SimilarityMetric for ModifiedCosineSimilarity {
+    fn name(&self) -> &str {
+        "modified_cosine"
+    }
+
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64> {
+        let precursor = |m: &Molecule| m.properties.get(PRECURSOR_MZ_PROPERTY).and_then(|v| v.as_f64());
+        let (peaks_a, peaks_b, precursor_a, precursor_b) =
+            match (spectrum_property(a), spectrum_property(b), precursor(a), precursor(b)) {
+                (Some(pa), Some(pb), Some(ma), Some(mb)) => (pa, pb, ma, mb),
+                _ => return Ok(0.0),
+            };
+
+        let spectrum = |peaks: Vec<(f64, f64)>| Spectrum::new(peaks.into_iter().map(|(mz, intensity)| Peak { mz, intensity }).collect());
+        let result = modified_cosine(&spectrum(peaks_a), precursor_a, &spectrum(peaks_b), precursor_b, self.tolerance, 0.5);
+        if result.matched_peaks < self.min_matched_peaks {
+            return Ok(0.0);
+        }
+        Ok(result.score)
+    }
+}
+
+/// Jaccard overlap of the pathway IDs stored in the `pathways` property
+pub struct PathwayOverlapSimilarity;
+
+impl SimilarityMetric for PathwayOverlapSimilarity {
+    fn name(&self) -> &str {
+        "pathway_overlap"
+    }
+
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64> {
+        let pathways_a = string_set_property(a, "pathways");
+        let pathways_b = string_set_property(b, "pathways");
+
+        let union = pathways_a.union(&pathways_b).count();
+        if union == 0 {
+            return Ok(0.0);
+        }
+
+        Ok(pathways_a.intersection(&pathways_b).count() as f64 / union as f64)
+    }
+}
+
+/// Cosine similarity of the learned vectors stored in the `embedding` property
+///
+/// See [`MoleculeNetwork::embed`](super::MoleculeNetwork::embed). Molecules
+/// without embeddings, or pointing in opposite directions, score zero.
+pub struct EmbeddingSimilarity;
+
+impl SimilarityMetric for EmbeddingSimilarity {
+    fn name(&self) -> &str {
+        "embedding"
+    }
+
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64> {
+        match (vector_property(a, EMBEDDING_PROPERTY), vector_property(b, EMBEDDING_PROPERTY)) {
+            (Some(va), Some(vb)) if va.len() == vb.len() => Ok(cosine(&va, &vb).max(0.0)),
+            (Some(va), Some(vb)) => Err(anyhow!(
+                "Embeddings of {} and {} differ in length ({} vs {})", a.id, b.id, va.len(), vb.len()
+            )),
+            _ => Ok(0.0),
+        }
+    }
+}
+
+/// Weighted combination of several registered metrics
+#[derive(Clone)]
+pub struct CompositeSimilarity {
+    /// Component metrics with their normalized weights
+    components: Vec<(Arc<dyn SimilarityMetric>, f64)>,
+}
+
+impl CompositeSimilarity {
+    /// Start building a composite metric
+    pub fn builder() -> CompositeSimilarityBuilder {
+        CompositeSimilarityBuilder::default()
+    }
+
+    /// Names and weights of the component metrics
+    pub fn weights(&self) -> Vec<(String, f64)> {
+        self.components.iter()
+            .map(|(metric, weight)| (metric.name().to_string(), *weight))
+            .collect()
+    }
+
+    /// Calculate the composite score along with each component's raw score
+    pub fn score_components(&self, a: &Molecule, b: &Molecule) -> Result<(f64, HashMap<String, f64>)> {
+        let mut total = 0.0;
+        let mut components = HashMap::new();
+
+        for (metric, weight) in &self.components {
+            let score = bounded_score(metric.as_ref(), a, b)?;
+            total += weight * score;
+            components.insert(metric.name().to_string(), score);
+        }
+
+        Ok((total, components))
+    }
+}
+
+impl SimilarityMetric for CompositeSimilarity {
+    fn name(&self) -> &str {
+        "composite"
+    }
+
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64> {
+        Ok(self.score_components(a, b)?.0)
+    }
+}
+
+/// Builder for a weighted composite similarity
+#[derive(Default)]
+pub struct CompositeSimilarityBuilder {
+    /// Metric names and raw weights
+    components: Vec<(String, f64)>,
+}
+
+impl CompositeSimilarityBuilder {
+    /// Add a registered metric with the given weight
+    pub fn metric(mut self, name: &str, weight: f64) -> Self {
+        self.components.push((name.to_string(), weight));
+        self
+    }
+
+    /// Resolve metrics from the global registry and normalize the weights
+    pub fn build(self) -> Result<CompositeSimilarity> {
+        self.build_with(SimilarityRegistry::global())
+    }
+
+    /// Resolve metrics from the given registry and normalize the weights
+    pub fn build_with(self, registry: &SimilarityRegistry) -> Result<CompositeSimilarity> {
+        if self.components.is_empty() {
+            return Err(anyhow!("Composite similarity requires at least one metric"));
+        }
+        if self.components.iter().any(|(_, weight)| *weight < 0.0) {
+            return Err(anyhow!("Composite similarity weights must be non-negative"));
+        }
+
+        let total_weight: f64 = self.components.iter().map(|(_, weight)| weight).sum();
+        if total_weight <= 0.0 {
+            return Err(anyhow!("Composite similarity weights must not all be zero"));
+        }
+
+        let components = self.components.into_iter()
+            .map(|(name, weight)| Ok((registry.get(&name)?, weight / total_weight)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CompositeSimilarity { components })
+    }
+}
+
+/// Similarity metric backed by a closure
+pub struct FnSimilarity<F> {
+    /// Name of the metric
+    name: String,
+
+    /// Function computing the similarity
+    func: F,
+}
+
+impl<F> FnSimilarity<F>
+where
+    F: Fn(&Molecule, &Molecule) -> Result<f64> + Send + Sync,
+{
+    /// Wrap a closure as a named similarity metric
+    pub fn new(name: &str, func: F) -> Self {
+        Self {
+            name: name.to_string(),
+            func,
+        }
+    }
+}
+
+impl<F> SimilarityMetric for FnSimilarity<F>
+where
+    F: Fn(&Molecule, &Molecule) -> Result<f64> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn similarity(&self, a: &Molecule, b: &Molecule) -> Result<f64> {
+        (self.func)(a, b)
+    }
+}
+
+/// Registry of similarity metrics keyed by name
+pub struct SimilarityRegistry {
+    /// Registered metrics
+    metrics: RwLock<HashMap<String, Arc<dyn SimilarityMetric>>>,
+}
+
+impl SimilarityRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            metrics: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create a registry containing the built-in metrics
+    pub fn with_defaults() -> Self {
+        let registry = Self::new();
+        registry.register(Arc::new(TanimotoSimilarity));
+        registry.register(Arc::new(DiceSimilarity));
+        registry.register(Arc::new(SpectralSimilarity::default()));
+        registry.register(Arc::new(ModifiedCosineSimilarity::default()));
+        registry.register(Arc::new(PathwayOverlapSimilarity));
+        registry.register(Arc::new(EmbeddingSimilarity));
+        registry
+    }
+
+    /// Get the process-wide registry
+    pub fn global() -> &'static SimilarityRegistry {
+        static REGISTRY: OnceLock<SimilarityRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(SimilarityRegistry::with_defaults)
+    }
+
+    /// Register a metric, replacing any metric with the same name
+    pub fn register(&self, metric: Arc<dyn SimilarityMetric>) {
+        let name = metric.name().to_string();
+        debug!("Registering similarity metric: {}", name);
+        self.metrics.write().unwrap().insert(name, metric);
+    }
+
+    /// Register a closure as a named metric
+    pub fn register_fn<F>(&self, name: &str, func: F)
+    where
+        F: Fn(&Molecule, &Molecule) -> Result<f64> + Send + Sync + 'static,
+    {
+        self.register(Arc::new(FnSimilarity::new(name, func)));
+    }
+
+    /// Get a metric by name
+    pub fn get(&self, name: &str) -> Result<Arc<dyn SimilarityMetric>> {
+        self.metrics.read().unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown similarity metric: {}", name))
+    }
+
+    /// Names of all registered metrics
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.metrics.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Calculate the similarity between two molecules with the named metric
+    ///
+    /// Scores are clamped to 0.0 - 1.0; a metric that returns NaN or an
+    /// infinite score is an error.
+    pub fn compute(&self, metric: &str, a: &Molecule, b: &Molecule) -> Result<f64> {
+        bounded_score(self.get(metric)?.as_ref(), a, b)
+    }
+}
+
+impl Default for SimilarityRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+/// Score a pair with a metric, rejecting non-finite scores and clamping the rest to 0.0 - 1.0
+fn bounded_score(metric: &dyn SimilarityMetric, a: &Molecule, b: &Molecule) -> Result<f64> {
+    let similarity = metric.similarity(a, b)?;
+    if !similarity.is_finite() {
+        return Err(anyhow!("Similarity metric {} returned {} for {} and {}", metric.name(), similarity, a.id, b.id));
+    }
+    Ok(similarity.clamp(0.0, 1.0))
+}
+
+/// Build a set of character n-grams (1 to 3) from a SMILES string
+pub(crate) fn smiles_fingerprint(smiles: &str) -> HashSet<String> {
+    let chars: Vec<char> = smiles.chars().collect();
+    let mut fingerprint = HashSet::new();
+
+    for n in 1..=3 {
+        for window in chars.windows(n) {
+            fingerprint.insert(window.iter().collect());
+        }
+    }
+
+    fingerprint
+}
+
+/// Read `[mz, intensity]` pairs from a molecule's `spectrum` property
+fn spectrum_property(molecule: &Molecule) -> Option<Vec<(f64, f64)>> {
+    let peaks = molecule.properties.get(SPECTRUM_PROPERTY)?.as_array()?;
+    Some(peaks.iter()
+        .filter_map(|peak| {
+            let pair = peak.as_array()?;
+            Some((pair.first()?.as_f64()?, pair.get(1)?.as_f64()?))
+        })
+        .collect())
+}
+
+/// Read a vector of numbers from an array-valued molecule property
+fn vector_property(molecule: &Molecule, key: &str) -> Option<Vec<f64>> {
+    molecule.properties.get(key)?
+        .as_array()?
+        .iter()
+        .map(|value| value.as_f64())
+        .collect()
+}
+
+/// Read a set of strings from an array-valued molecule property
+fn string_set_property(molecule: &Molecule, key: &str) -> HashSet<String> {
+    molecule.properties.get(key)
+        .and_then(|value| value.as_array())
+        .map(|values| values.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_metrics() {
+        let registry = SimilarityRegistry::with_defaults();
+        let ethanol = Molecule::from_smiles("CCO").unwrap();
+        let propanol = Molecule::from_smiles("CCCO").unwrap();
+
+        assert_eq!(registry.compute("tanimoto", &ethanol, &ethanol).unwrap(), 1.0);
+        let sim = registry.compute("dice", &ethanol, &propanol).unwrap();
+        assert!(sim > 0.0 && sim < 1.0);
+        assert!(registry.compute("unknown", &ethanol, &propanol).is_err());
+    }
+
+    #[test]
+    fn test_spectral_matches_peaks_one_to_one() {
+        let mut query = Molecule::from_smiles("CCO").unwrap();
+        let mut reference = Molecule::from_smiles("CCO").unwrap();
+        query.properties.insert("spectrum".into(), serde_json::json!([[100.000, 1.0], [100.005, 1.0]]));
+        reference.properties.insert("spectrum".into(), serde_json::json!([[100.002, 1.0]]));
+
+        // Only one query peak may pair with the single reference peak
+        let sim = SpectralSimilarity::default().similarity(&query, &reference).unwrap();
+        assert!((sim - 1.0 / 2f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_register_closure() {
+        let registry = SimilarityRegistry::new();
+        registry.register_fn("length", |a, b| {
+            let (la, lb) = (a.smiles.len() as f64, b.smiles.len() as f64);
+            Ok(la.min(lb) / la.max(lb))
+        });
+
+        let a = Molecule::from_smiles("CC").unwrap();
+        let b = Molecule::from_smiles("CCCC").unwrap();
+        assert_eq!(registry.compute("length", &a, &b).unwrap(), 0.5);
+        assert_eq!(registry.names(), vec!["length".to_string()]);
+
+        registry.register_fn("broken", |_, _| Ok(f64::NAN));
+        registry.register_fn("unbounded", |_, _| Ok(2.5));
+        assert!(registry.compute("broken", &a, &b).is_err());
+        assert_eq!(registry.compute("unbounded", &a, &b).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_composite_similarity() {
+        let mut a = Molecule::from_smiles("CCO").unwrap();
+        let mut b = Molecule::from_smiles("CCO").unwrap();
+        a.properties.insert("pathways".into(), serde_json::json!(["R-1", "R-2"]));
+        b.properties.insert("pathways".into(), serde_json::json!(["R-2"]));
+
+        let composite = CompositeSimilarity::builder()
+            .metric("tanimoto", 0.6)
+            .metric("spectral", 0.3)
+            .metric("pathway_overlap", 0.1)
+            .build_with(&SimilarityRegistry::with_defaults())
+            .unwrap();
+
+        let (score, components) = composite.score_components(&a, &b).unwrap();
+        assert_eq!(components["tanimoto"], 1.0);
+        assert_eq!(components["spectral"], 0.0);
+        assert_eq!(components["pathway_overlap"], 0.5);
+        assert!((score - 0.65).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,238 @@
+//! Project Statistics
+//!
+//! A project's state at a glance: how many molecules sit in each confidence
+//! band, what evidence backs them, how often integration finds conflicts and
+//! how identification activity has moved over recent days. The graph store
+//! computes the aggregates; the figures are collected here and cached per
+//! project until the next write or for a short time.
+
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// Number of equal-width confidence bands between 0.0 and 1.0
+pub const CONFIDENCE_BANDS: usize = 5;
+
+/// Label of the band for molecules that were never integrated
+pub const UNSCORED_BAND: &str = "unscored";
+
+/// Days covered by the activity trend
+pub const TREND_DAYS: i64 = 30;
+
+/// First day of the activity trend, as `YYYY-MM-DD`
+pub fn trend_start() -> String {
+    (Utc::now() - chrono::Duration::days(TREND_DAYS - 1)).format("%Y-%m-%d").to_string()
+}
+
+/// Label of a confidence band, e.g. `0.6-0.8`
+pub fn band_label(band: usize) -> String {
+    let width = 1.0 / CONFIDENCE_BANDS as f64;
+    format!("{:.1}-{:.1}", band as f64 * width, (band + 1) as f64 * width)
+}
+
+/// Band a confidence falls in, the top band including 1.0
+pub fn confidence_band(confidence: f64) -> usize {
+    ((confidence.clamp(0.0, 1.0) * CONFIDENCE_BANDS as f64) as usize).min(CONFIDENCE_BANDS - 1)
+}
+
+/// Identification activity on one day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrendPoint {
+    /// Day, as `YYYY-MM-DD`
+    pub date: String,
+
+    /// Evidence items recorded
+    pub evidence_added: usize,
+
+    /// Molecule integrations
+    pub integrations: usize,
+
+    /// Mean confidence produced by the day's integrations
+    pub mean_confidence: Option<f64>,
+}
+
+/// Aggregate figures for a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectStats {
+    /// Project the figures describe
+    pub project_id: String,
+
+    /// When the figures were computed
+    pub computed_at: DateTime<Utc>,
+
+    /// Molecules in the project
+    pub molecules: usize,
+
+    /// Molecules per confidence band, including `unscored`
+    pub molecules_by_band: BTreeMap<String, usize>,
+
+    /// Molecules whose last integration found conflicting evidence
+    pub molecules_with_conflicts: usize,
+
+    /// Share of integrated molecules with conflicts (0.0 - 1.0)
+    pub conflict_rate: f64,
+
+    /// Evidence items in the project
+    pub evidence: usize,
+
+    /// Evidence items per type
+    pub evidence_by_type: BTreeMap<String, usize>,
+
+    /// Evidence items per source
+    pub evidence_by_source: BTreeMap<String, usize>,
+
+    /// Activity per day over the last `TREND_DAYS` days, oldest first
+    pub trend: Vec<TrendPoint>,
+}
+
+impl ProjectStats {
+    /// Empty figures for a project, with every band present
+    pub fn new(project_id: &str) -> Self {
+        let mut molecules_by_band: BTreeMap<String, usize> = (0..CONFIDENCE_BANDS).map(|b| (band_label(b), 0)).collect();
+        molecules_by_band.insert(UNSCORED_BAND.to_string(), 0);
+        Self {
+            project_id: project_id.to_string(),
+            computed_at: Utc::now(),
+            molecules: 0,
+            molecules_by_band,
+            molecules_with_conflicts: 0,
+            conflict_rate: 0.0,
+            evidence: 0,
+            evidence_by_type: BTreeMap::new(),
+            evidence_by_source: BTreeMap::new(),
+            trend: Vec::new(),
+        }
+    }
+
+    /// Count molecules of a band (`None` for never integrated), of which some had conflicts
+    pub fn add_molecules(&mut self, band: Option<usize>, molecules: usize, with_conflicts: usize) {
+        let label = match band {
+            Some(band) => band_label(band.min(CONFIDENCE_BANDS - 1)),
+            None => UNSCORED_BAND.to_string(),
+        };
+        *self.molecules_by_band.entry(label).or_default() += molecules;
+        self.molecules += molecules;
+        self.molecules_with_conflicts += with_conflicts;
+
+        let integrated = self.molecules - self.molecules_by_band[UNSCORED_BAND];
+        self.conflict_rate = if integrated == 0 { 0.0 } else { self.molecules_with_conflicts as f64 / integrated as f64 };
+    }
+
+    /// Count evidence items of a type and source
+    pub fn add_evidence(&mut self, evidence_type: &str, source: &str, count: usize) {
+        *self.evidence_by_type.entry(evidence_type.to_string()).or_default() += count;
+        *self.evidence_by_source.entry(source.to_string()).or_default() += count;
+        self.evidence += count;
+    }
+
+    /// Count evidence recorded on a day
+    pub fn add_evidence_day(&mut self, date: &str, count: usize) {
+        self.trend_point(date).evidence_added += count;
+    }
+
+    /// Count integrations on a day and their mean confidence
+    pub fn add_integration_day(&mut self, date: &str, count: usize, mean_confidence: Option<f64>) {
+        let point = self.trend_point(date);
+        let total = point.integrations + count;
+        point.mean_confidence = match (point.mean_confidence, mean_confidence) {
+            (Some(a), Some(b)) if total > 0 => Some((a * point.integrations as f64 + b * count as f64) / total as f64),
+            (a, b) => a.or(b),
+        };
+        point.integrations = total;
+    }
+
+    /// Trend point of a day, kept in date order
+    fn trend_point(&mut self, date: &str) -> &mut TrendPoint {
+        let index = match self.trend.binary_search_by(|p| p.date.as_str().cmp(date)) {
+            Ok(index) => index,
+            Err(index) => {
+                self.trend.insert(index, TrendPoint { date: date.to_string(), ..Default::default() });
+                index
+            }
+        };
+        &mut self.trend[index]
+    }
+}
+
+/// Project statistics cached until a write to the project or until they expire
+#[derive(Debug)]
+pub struct StatsCache {
+    /// Figures and when they were cached, by project
+    entries: HashMap<String, (Instant, ProjectStats)>,
+
+    /// How long cached figures are served
+    ttl: Duration,
+}
+
+impl StatsCache {
+    /// Create a cache serving figures for at most `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self { entries: HashMap::new(), ttl }
+    }
+
+    /// Cached figures of a project, unless expired
+    pub fn get(&self, project_id: &str) -> Option<&ProjectStats> {
+        self.entries.get(project_id)
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+            .map(|(_, stats)| stats)
+    }
+
+    /// Cache a project's figures
+    pub fn insert(&mut self, stats: ProjectStats) {
+        self.entries.insert(stats.project_id.clone(), (Instant::now(), stats));
+    }
+
+    /// Drop a project's figures after a write
+    pub fn invalidate(&mut self, project_id: &str) {
+        self.entries.remove(project_id);
+    }
+}
+
+impl Default for StatsCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_accumulate_bands_and_trend() {
+        let mut stats = ProjectStats::new("lipids");
+        stats.add_molecules(Some(confidence_band(1.0)), 3, 1);
+        stats.add_molecules(Some(confidence_band(0.1)), 1, 1);
+        stats.add_molecules(None, 2, 0);
+        assert_eq!(stats.molecules, 6);
+        assert_eq!(stats.molecules_by_band["0.8-1.0"], 3);
+        assert_eq!(stats.molecules_by_band["0.4-0.6"], 0);
+        assert_eq!(stats.conflict_rate, 0.5);
+
+        stats.add_evidence("mass_spec", "feature-table", 4);
+        stats.add_evidence("mass_spec", "manual-entry", 1);
+        assert_eq!(stats.evidence_by_type["mass_spec"], 5);
+
+        stats.add_integration_day("2026-03-02", 1, Some(0.9));
+        stats.add_evidence_day("2026-03-01", 2);
+        stats.add_integration_day("2026-03-02", 3, Some(0.5));
+        assert_eq!(stats.trend.len(), 2);
+        assert_eq!(stats.trend[0].date, "2026-03-01");
+        assert_eq!(stats.trend[1].integrations, 4);
+        assert!((stats.trend[1].mean_confidence.unwrap() - 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_cache_expires_and_invalidates() {
+        let mut cache = StatsCache::default();
+        cache.insert(ProjectStats::new("a"));
+        assert!(cache.get("a").is_some());
+        cache.invalidate("a");
+        assert!(cache.get("a").is_none());
+
+        let mut expired = StatsCache::new(Duration::ZERO);
+        expired.insert(ProjectStats::new("a"));
+        assert!(expired.get("a").is_none());
+    }
+}
@@ -0,0 +1,323 @@
+//! Graph Store
+//!
+//! The operations the rectifier and the API need from a graph database:
+//! molecule reads and writes, pathway and interaction lookups, path
+//! finding and neighborhood extraction. Neo4j is the default backend;
+//! Postgres keeps the same graph in node and edge tables and walks it with
+//! recursive CTEs, for deployments that would rather not run a graph
+//! database, and the embedded backend needs no server at all. `HEGEL_GRAPH_STORE` selects the backend.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::info;
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::embedded::EmbeddedStore;
+use super::inspect::{stored_history, ConfidencePoint, MoleculeSummary};
+use super::neighborhood::NeighborhoodOptions;
+use super::neo4j::Neo4jClient;
+use super::paths::MoleculePath;
+use super::pathways::PathwayMembership;
+use super::postgres::PostgresStore;
+use super::schema::Node;
+use super::stats::ProjectStats;
+use super::MoleculeNetwork;
+use crate::bundle::ProjectBundle;
+use crate::processing::evidence::{Evidence, IntegratedEvidence};
+use crate::processing::versioning::ConfidenceTrigger;
+use crate::projects::Project;
+
+/// Environment variable selecting the graph store backend
+pub const STORE_BACKEND_ENV: &str = "HEGEL_GRAPH_STORE";
+
+/// Environment variable holding the Postgres connection URL
+pub const POSTGRES_URL_ENV: &str = "HEGEL_POSTGRES_URL";
+
+/// Environment variable naming the embedded store's directory
+pub const EMBEDDED_DIR_ENV: &str = "HEGEL_GRAPH_STORE_DIR";
+
+/// Directory of the embedded store when `HEGEL_GRAPH_STORE_DIR` is not set
+pub const DEFAULT_EMBEDDED_DIR: &str = "hegel-graph";
+
+/// Database a graph store keeps the graph in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    /// Neo4j, queried with Cypher
+    #[default]
+    Neo4j,
+
+    /// Postgres, with the graph in node and edge tables
+    Postgres,
+
+    /// Embedded sled database, with no server
+    Embedded,
+}
+
+impl std::str::FromStr for StoreBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "neo4j" => Ok(StoreBackend::Neo4j),
+            "postgres" | "postgresql" => Ok(StoreBackend::Postgres),
+            "embedded" | "sled" => Ok(StoreBackend::Embedded),
+            _ => Err(anyhow!("Unsupported graph store backend: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for StoreBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreBackend::Neo4j => write!(f, "neo4j"),
+            StoreBackend::Postgres => write!(f, "postgres"),
+            StoreBackend::Embedded => write!(f, "embedded"),
+        }
+    }
+}
+
+/// Which graph store to open and how to reach it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoreConfig {
+    /// Backend to use
+    pub backend: StoreBackend,
+
+    /// Postgres connection URL, required for the Postgres backend
+    pub postgres_url: Option<String>,
+
+    /// Directory of the embedded store
+    pub embedded_dir: Option<PathBuf>,
+}
+
+impl StoreConfig {
+    /// Read the configuration from `HEGEL_GRAPH_STORE`, `HEGEL_POSTGRES_URL` and `HEGEL_GRAPH_STORE_DIR`
+    ///
+    /// Neo4j is used when no backend is set; its connection settings are
+    /// read by `Neo4jConfig::from_env`.
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::from_values(
+            std::env::var(STORE_BACKEND_ENV).ok().as_deref(),
+            std::env::var(POSTGRES_URL_ENV).ok(),
+        )?;
+        config.embedded_dir = std::env::var(EMBEDDED_DIR_ENV).ok().map(PathBuf::from);
+        Ok(config)
+    }
+
+    /// Build the configuration from a backend name and a Postgres URL
+    fn from_values(backend: Option<&str>, postgres_url: Option<String>) -> Result<Self> {
+        let backend = match backend.map(str::trim).filter(|b| !b.is_empty()) {
+            Some(name) => name.parse()?,
+            None => StoreBackend::default(),
+        };
+        if backend == StoreBackend::Postgres && postgres_url.is_none() {
+            return Err(anyhow!("{} must be set to use the Postgres graph store", POSTGRES_URL_ENV));
+        }
+        Ok(Self { backend, postgres_url, embedded_dir: None })
+    }
+}
+
+/// A relationship from a molecule to another molecule of its project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoleculeInteraction {
+    /// Molecule the relationship starts from
+    pub source_molecule: String,
+
+    /// Molecule the relationship points to
+    pub target_molecule: String,
+
+    /// Name of the target molecule
+    pub target_name: Option<String>,
+
+    /// Relationship type, e.g. `INTERACTS_WITH`
+    pub interaction_type: String,
+
+    /// Confidence recorded on the relationship
+    pub confidence: Option<f64>,
+
+    /// Evidence items behind the relationship
+    pub evidence_count: usize,
+}
+
+/// A gene with the number of phenotypes linked to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenePhenotypes {
+    /// Gene node ID
+    pub gene_id: String,
+
+    /// Name of the gene
+    pub gene_name: Option<String>,
+
+    /// Distinct phenotypes the gene links to
+    pub phenotype_count: usize,
+}
+
+/// Graph operations used by the rectifier and the API
+///
+/// All operations are scoped to a project; molecule IDs are unique within
+/// a project only. Evidence is linked to the molecule it bears on by a
+/// `SUPPORTS` relationship in every backend.
+#[async_trait]
+pub trait GraphStore: Send + Sync {
+    /// Backend the store keeps the graph in
+    fn backend(&self) -> StoreBackend;
+
+    /// Create or replace a molecule
+    async fn store_molecule(&self, project_id: &str, molecule: &Node) -> Result<()>;
+
+    /// Get a molecule, if stored
+    async fn get_molecule(&self, project_id: &str, molecule_id: &str) -> Result<Option<Node>>;
+
+    /// Molecules of a project with their confidence, ordered by ID
+    async fn list_molecules(&self, project_id: &str) -> Result<Vec<MoleculeSummary>>;
+
+    /// Delete a molecule and its relationships, returning whether it existed
+    async fn delete_molecule(&self, project_id: &str, molecule_id: &str) -> Result<bool>;
+
+    /// Pathways a molecule takes part in, directly or through a reaction, with their members
+    async fn molecule_pathways(&self, project_id: &str, molecule_id: &str) -> Result<Vec<PathwayMembership>>;
+
+    /// Relationships from a molecule to other molecules
+    async fn molecule_interactions(&self, project_id: &str, molecule_id: &str) -> Result<Vec<MoleculeInteraction>>;
+
+    /// Shortest paths of at most `max_hops` (1 - 10) between two molecules, cheapest first
+    async fn find_paths(&self, project_id: &str, from: &str, to: &str, max_hops: usize, limit: usize) -> Result<Vec<MoleculePath>>;
+
+    /// Molecules within `options.radius` similarity hops of a molecule, or `None` if it is not stored
+    async fn neighborhood(&self, project_id: &str, molecule_id: &str, options: &NeighborhoodOptions) -> Result<Option<MoleculeNetwork>>;
+
+    /// Store the result of evidence integration: its evidence items and the molecule's confidence
+    async fn store_integrated_evidence(&self, project_id: &str, integrated: &IntegratedEvidence, trigger: ConfidenceTrigger) -> Result<()>;
+
+    /// Confidence recorded at each integration of a molecule, oldest first, or nothing if it is not stored
+    async fn confidence_history(&self, project_id: &str, molecule_id: &str) -> Result<Vec<ConfidencePoint>> {
+        Ok(self.get_molecule(project_id, molecule_id).await?
+            .map(|molecule| stored_history(|key| molecule.properties.get(key)))
+            .unwrap_or_default())
+    }
+
+    /// Evidence stored for a molecule, ready to be integrated again
+    async fn molecule_evidence(&self, project_id: &str, molecule_id: &str) -> Result<Vec<Evidence>>;
+
+    /// Genes linked to the most phenotypes (`Disease` nodes), most linked first, at most `limit`
+    async fn gene_phenotypes(&self, project_id: &str, limit: usize) -> Result<Vec<GenePhenotypes>>;
+
+    /// Create or replace a project's metadata and members
+    async fn store_project(&self, project: &Project) -> Result<()>;
+
+    /// Molecule, evidence, conflict and activity figures for a project
+    async fn project_stats(&self, project_id: &str) -> Result<ProjectStats>;
+
+    /// Everything stored for a project, collected into a bundle
+    async fn export_project(&self, project_id: &str) -> Result<ProjectBundle>;
+}
+
+#[async_trait]
+impl GraphStore for Neo4jClient {
+    fn backend(&self) -> StoreBackend {
+        StoreBackend::Neo4j
+    }
+
+    async fn store_molecule(&self, project_id: &str, molecule: &Node) -> Result<()> {
+        Neo4jClient::store_molecule(self, project_id, molecule).await
+    }
+
+    async fn get_molecule(&self, project_id: &str, molecule_id: &str) -> Result<Option<Node>> {
+        Neo4jClient::get_molecule(self, project_id, molecule_id).await
+    }
+
+    async fn list_molecules(&self, project_id: &str) -> Result<Vec<MoleculeSummary>> {
+        Neo4jClient::list_molecules(self, project_id).await
+    }
+
+    async fn delete_molecule(&self, project_id: &str, molecule_id: &str) -> Result<bool> {
+        Neo4jClient::delete_molecule(self, project_id, molecule_id).await
+    }
+
+    async fn molecule_pathways(&self, project_id: &str, molecule_id: &str) -> Result<Vec<PathwayMembership>> {
+        Neo4jClient::molecule_pathways(self, project_id, molecule_id).await
+    }
+
+    async fn molecule_interactions(&self, project_id: &str, molecule_id: &str) -> Result<Vec<MoleculeInteraction>> {
+        Neo4jClient::molecule_interactions(self, project_id, molecule_id).await
+    }
+
+    async fn find_paths(&self, project_id: &str, from: &str, to: &str, max_hops: usize, limit: usize) -> Result<Vec<MoleculePath>> {
+        Neo4jClient::find_paths(self, project_id, from, to, max_hops, limit).await
+    }
+
+    async fn neighborhood(&self, project_id: &str, molecule_id: &str, options: &NeighborhoodOptions) -> Result<Option<MoleculeNetwork>> {
+        Neo4jClient::neighborhood(self, project_id, molecule_id, options).await
+    }
+
+    async fn store_integrated_evidence(&self, project_id: &str, integrated: &IntegratedEvidence, trigger: ConfidenceTrigger) -> Result<()> {
+        Neo4jClient::store_integrated_evidence(self, project_id, integrated, trigger).await
+    }
+
+    async fn molecule_evidence(&self, project_id: &str, molecule_id: &str) -> Result<Vec<Evidence>> {
+        Neo4jClient::molecule_evidence(self, project_id, molecule_id).await
+    }
+
+    async fn gene_phenotypes(&self, project_id: &str, limit: usize) -> Result<Vec<GenePhenotypes>> {
+        Neo4jClient::gene_phenotypes(self, project_id, limit).await
+    }
+
+    async fn store_project(&self, project: &Project) -> Result<()> {
+        Neo4jClient::store_project(self, project).await
+    }
+
+    async fn project_stats(&self, project_id: &str) -> Result<ProjectStats> {
+        Neo4jClient::project_stats(self, project_id).await
+    }
+
+    async fn export_project(&self, project_id: &str) -> Result<ProjectBundle> {
+        Neo4jClient::export_project(self, project_id).await
+    }
+}
+
+/// Open the configured graph store
+pub async fn open(config: &StoreConfig) -> Result<Arc<dyn GraphStore>> {
+    info!("Opening {} graph store", config.backend);
+    match config.backend {
+        StoreBackend::Neo4j => {
+            let client = Neo4jClient::from_env()?;
+            client.ensure_project_schema().await
+                .context("Failed to ensure the Neo4j project schema")?;
+            Ok(Arc::new(client))
+        }
+        StoreBackend::Postgres => {
+            let url = config.postgres_url.as_deref()
+                .ok_or_else(|| anyhow!("No Postgres URL configured for the graph store"))?;
+            let store = PostgresStore::connect(url).await
+                .context("Failed to open the Postgres graph store")?;
+            store.ensure_schema().await?;
+            Ok(Arc::new(store))
+        }
+        StoreBackend::Embedded => {
+            let dir = config.embedded_dir.as_deref().unwrap_or(Path::new(DEFAULT_EMBEDDED_DIR));
+            Ok(Arc::new(EmbeddedStore::open(dir)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_selection() {
+        let config = StoreConfig::from_values(None, None).unwrap();
+        assert_eq!(config.backend, StoreBackend::Neo4j);
+
+        let config = StoreConfig::from_values(Some("PostgreSQL"), Some("postgres://localhost/hegel".to_string())).unwrap();
+        assert_eq!(config.backend, StoreBackend::Postgres);
+        assert_eq!(config.backend.to_string(), "postgres");
+
+        assert_eq!(StoreConfig::from_values(Some("sled"), None).unwrap().backend, StoreBackend::Embedded);
+        assert!(StoreConfig::from_values(Some("postgres"), None).is_err());
+        assert!(StoreConfig::from_values(Some("sqlite"), None).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! HTTP Response Caching
+//!
+//! ETags and a response cache for the API server. A response's ETag is a
+//! fingerprint of its JSON body: a SHA-256 over the body with object keys in
+//! sorted order, so a recomputed result that has not changed keeps its ETag.
+//! Clients polling an endpoint send the ETag back in `If-None-Match` and get
+//! an empty `304 Not Modified` while the result stays the same.
+//!
+//! Bodies are cached per project and request until a write to the project or
+//! until they expire, so polls between writes are answered without querying
+//! the graph store again.
+
+use anyhow::Result;
+use log::info;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Initialize the HTTP response caching module
+pub fn initialize() -> Result<()> {
+    info!("Initializing HTTP response caching module");
+    info!("HTTP response caching module initialized successfully");
+    Ok(())
+}
+
+/// A serialized response body and its ETag
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    /// Quoted strong ETag, e.g. `"3f2a..."`
+    pub etag: String,
+
+    /// JSON body
+    pub body: Vec<u8>,
+}
+
+impl CachedResponse {
+    /// Serialize a result, with object keys in sorted order, and fingerprint it
+    pub fn from_json<T: Serialize>(value: &T) -> Result<Self> {
+        // Going through `Value` sorts map keys, so the bytes do not depend on
+        // the iteration order of any `HashMap` in the result
+        let body = serde_json::to_vec(&serde_json::to_value(value)?)?;
+        Ok(Self { etag: etag(&body), body })
+    }
+
+    /// Whether an `If-None-Match` header value names this response
+    pub fn matches(&self, if_none_match: &str) -> bool {
+        etag_matches(if_none_match, &self.etag)
+    }
+}
+
+/// Quoted strong ETag of a response body: the first 128 bits of its SHA-256
+pub fn etag(body: &[u8]) -> String {
+    let digest = hex::encode(Sha256::digest(body));
+    format!("\"{}\"", &digest[..32])
+}
+
+/// Whether an `If-None-Match` header value matches an ETag
+///
+/// The header holds `*` or a comma-separated list of ETags. Comparison is
+/// weak, as RFC 9110 requires for `If-None-Match`, so a `W/` prefix on
+/// either side is ignored.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match.split(',').any(|candidate| candidate.trim() == "*" || opaque(candidate) == etag)
+}
+
+/// Response bodies cached per project until a write to the project or until they expire
+#[derive(Debug)]
+pub struct ResponseCache {
+    /// Responses and when they were cached, by project and then request
+    entries: HashMap<String, HashMap<String, (Instant, CachedResponse)>>,
+
+    /// How long cached responses are served
+    ttl: Duration,
+
+    /// Most responses kept per project; the oldest is dropped beyond it
+    max_entries: usize,
+}
+
+impl ResponseCache {
+    /// Create a cache serving responses for at most `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self { entries: HashMap::new(), ttl, max_entries: 256 }
+    }
+
+    /// Keep at most `max_entries` responses per project
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Cached response to a project's request, unless expired
+    pub fn get(&self, project_id: &str, request: &str) -> Option<&CachedResponse> {
+        self.entries.get(project_id)?
+            .get(request)
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+            .map(|(_, response)| response)
+    }
+
+    /// Cache the response to a project's request
+    pub fn insert(&mut self, project_id: &str, request: &str, response: CachedResponse) {
+        let ttl = self.ttl;
+        let entries = self.entries.entry(project_id.to_string()).or_default();
+        entries.retain(|_, (cached_at, _)| cached_at.elapsed() < ttl);
+        if entries.len() >= self.max_entries && !entries.contains_key(request) {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, (cached_at, _))| *cached_at).map(|(key, _)| key.clone()) {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(request.to_string(), (Instant::now(), response));
+    }
+
+    /// Drop a project's responses after a write
+    pub fn invalidate(&mut self, project_id: &str) {
+        self.entries.remove(project_id);
+    }
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_ignores_map_order_and_matches_weakly() {
+        let keys = ["citrate", "lactate", "malate", "pyruvate", "succinate"];
+        let a: HashMap<&str, usize> = keys.iter().enumerate().map(|(i, k)| (*k, i)).collect();
+        let b: HashMap<&str, usize> = keys.iter().enumerate().rev().map(|(i, k)| (*k, i)).collect();
+        let response = CachedResponse::from_json(&a).unwrap();
+        assert_eq!(response.etag, CachedResponse::from_json(&b).unwrap().etag);
+        assert_eq!(response.etag.len(), 34);
+
+        assert!(response.matches(&response.etag));
+        assert!(response.matches(&format!("\"other\", W/{}", response.etag)));
+        assert!(response.matches("*"));
+        assert!(!response.matches("\"other\""));
+    }
+
+    #[test]
+    fn test_cache_expires_invalidates_and_evicts() {
+        let response = CachedResponse::from_json(&[1, 2, 3]).unwrap();
+        let mut cache = ResponseCache::default().with_max_entries(2);
+        cache.insert("a", "/api/path?from=x", response.clone());
+        std::thread::sleep(Duration::from_millis(1));
+        cache.insert("a", "/api/path?from=y", response.clone());
+        cache.insert("a", "/api/path?from=z", response.clone());
+        assert!(cache.get("a", "/api/path?from=x").is_none());
+        assert!(cache.get("a", "/api/path?from=z").is_some());
+        assert!(cache.get("b", "/api/path?from=z").is_none());
+        cache.invalidate("a");
+        assert!(cache.get("a", "/api/path?from=z").is_none());
+
+        let mut expired = ResponseCache::new(Duration::ZERO);
+        expired.insert("a", "/api/path", response);
+        assert!(expired.get("a", "/api/path").is_none());
+    }
+}
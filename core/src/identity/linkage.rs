@@ -0,0 +1,556 @@
+//! Record Linkage
+//!
+//! Decides whether entries from different databases (a PubChem record and an
+//! HMDB record, say) describe the same compound. Records are compared field
+//! by field (InChIKey, monoisotopic mass, formula, name and cross-references)
+//! and the comparisons are combined with a Fellegi–Sunter model: each field
+//! has a probability of agreeing among true matches (m) and among non-matches
+//! (u), and the log-ratios of those probabilities add up to a match weight.
+//! The model's probabilities can be trained from labelled pairs, and its
+//! thresholds sort each pair into link, review or non-link. The resulting
+//! linkage probability becomes evidence for the identity decision.
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use super::synonyms::normalize_name;
+use crate::processing::evidence::{Evidence, EvidenceType};
+use crate::processing::formula::Formula;
+
+/// Source recorded on evidence produced from a linkage score
+pub const LINKAGE_SOURCE: &str = "record-linkage";
+
+/// Mass difference, in Da, within which two monoisotopic masses agree
+const MASS_AGREE_DA: f64 = 0.002;
+
+/// Mass difference, in Da, within which two monoisotopic masses partially agree
+const MASS_PARTIAL_DA: f64 = 0.02;
+
+/// Name similarity at or above which two names count as agreeing outright
+const NAME_AGREE_SIMILARITY: f64 = 0.95;
+
+/// An entry for a compound as retrieved from one external database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseRecord {
+    /// Identifier type of the database the record came from (e.g. `hmdb_id`, `pubchem_cid`)
+    pub source: String,
+
+    /// Accession of the record in its database
+    pub accession: String,
+
+    /// Preferred name
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Alternative names
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+
+    /// Molecular formula
+    #[serde(default)]
+    pub formula: Option<Formula>,
+
+    /// Monoisotopic mass in Da
+    #[serde(default)]
+    pub monoisotopic_mass: Option<f64>,
+
+    /// Standard InChIKey
+    #[serde(default)]
+    pub inchi_key: Option<String>,
+
+    /// Accessions in other databases, keyed by identifier type (e.g. `chebi_id`)
+    #[serde(default)]
+    pub xrefs: BTreeMap<String, String>,
+}
+
+impl DatabaseRecord {
+    /// Compact `source:accession` label of the record
+    pub fn label(&self) -> String {
+        format!("{}:{}", self.source, self.accession)
+    }
+
+    /// Preferred name followed by the synonyms
+    fn names(&self) -> impl Iterator<Item = &String> {
+        self.name.iter().chain(self.synonyms.iter())
+    }
+
+    /// Accession in another database, including the record's own
+    fn accession_in(&self, source: &str) -> Option<&str> {
+        if self.source == source {
+            Some(&self.accession)
+        } else {
+            self.xrefs.get(source).map(|s| s.as_str())
+        }
+    }
+}
+
+/// Fields two records are compared on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkageField {
+    /// Standard InChIKey; a shared first block is a partial agreement
+    InchiKey,
+
+    /// Monoisotopic mass
+    Mass,
+
+    /// Molecular formula; equal apart from hydrogens is a partial agreement
+    Formula,
+
+    /// Best similarity between the normalized names and synonyms
+    Name,
+
+    /// Accessions both records give for the same database
+    Xrefs,
+}
+
+impl LinkageField {
+    /// Every field, in comparison order
+    pub const ALL: [LinkageField; 5] = [
+        LinkageField::InchiKey,
+        LinkageField::Mass,
+        LinkageField::Formula,
+        LinkageField::Name,
+        LinkageField::Xrefs,
+    ];
+}
+
+impl fmt::Display for LinkageField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinkageField::InchiKey => write!(f, "inchi_key"),
+            LinkageField::Mass => write!(f, "mass"),
+            LinkageField::Formula => write!(f, "formula"),
+            LinkageField::Name => write!(f, "name"),
+            LinkageField::Xrefs => write!(f, "xrefs"),
+        }
+    }
+}
+
+/// Outcome of comparing one field of two records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldComparison {
+    /// Field compared
+    pub field: LinkageField,
+
+    /// Degree of agreement from 0.0 (disagree) to 1.0 (agree); `None` when a record lacks the field
+    pub agreement: Option<f64>,
+
+    /// Contribution of the field to the match weight, in log2 units
+    pub weight: f64,
+}
+
+/// Match and non-match agreement probabilities for one field
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FieldProbabilities {
+    /// Probability the field agrees when the records are the same compound
+    pub m: f64,
+
+    /// Probability the field agrees when the records are different compounds
+    pub u: f64,
+}
+
+impl FieldProbabilities {
+    /// Weight of a full agreement, `log2(m / u)`
+    pub fn agree_weight(&self) -> f64 {
+        (self.m / self.u).log2()
+    }
+
+    /// Weight of a disagreement, `log2((1 - m) / (1 - u))`
+    pub fn disagree_weight(&self) -> f64 {
+        ((1.0 - self.m) / (1.0 - self.u)).log2()
+    }
+
+    /// Weight of a graded agreement, interpolated between disagreement and agreement
+    pub fn weight(&self, agreement: f64) -> f64 {
+        agreement * self.agree_weight() + (1.0 - agreement) * self.disagree_weight()
+    }
+}
+
+/// Whether a pair of records should be treated as the same compound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkDecision {
+    /// The records describe the same compound
+    Link,
+
+    /// The evidence is mixed; a curator should look at the pair
+    Review,
+
+    /// The records describe different compounds
+    NonLink,
+}
+
+impl fmt::Display for LinkDecision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinkDecision::Link => write!(f, "link"),
+            LinkDecision::Review => write!(f, "review"),
+            LinkDecision::NonLink => write!(f, "non_link"),
+        }
+    }
+}
+
+/// A pair of records labelled as matching or not, for training
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelledPair {
+    /// First record
+    pub left: DatabaseRecord,
+
+    /// Second record
+    pub right: DatabaseRecord,
+
+    /// Whether the records describe the same compound
+    pub is_match: bool,
+}
+
+/// Fellegi–Sunter linkage model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkageModel {
+    /// Agreement probabilities per field
+    pub fields: BTreeMap<LinkageField, FieldProbabilities>,
+
+    /// Prior probability that a candidate pair is a match
+    pub prior: f64,
+
+    /// Probability at or above which a pair is linked
+    pub link_threshold: f64,
+
+    /// Probability below which a pair is not linked; pairs in between go to review
+    pub review_threshold: f64,
+}
+
+/// Probabilities for candidate pairs drawn from a mass search, where mass and
+/// formula also agree for many non-matches (isomers)
+impl Default for LinkageModel {
+    fn default() -> Self {
+        let fields = [
+            (LinkageField::InchiKey, FieldProbabilities { m: 0.97, u: 0.0001 }),
+            (LinkageField::Mass, FieldProbabilities { m: 0.98, u: 0.3 }),
+            (LinkageField::Formula, FieldProbabilities { m: 0.95, u: 0.2 }),
+            (LinkageField::Name, FieldProbabilities { m: 0.85, u: 0.02 }),
+            (LinkageField::Xrefs, FieldProbabilities { m: 0.95, u: 0.001 }),
+        ];
+        Self {
+            fields: fields.into_iter().collect(),
+            prior: 0.1,
+            link_threshold: 0.95,
+            review_threshold: 0.5,
+        }
+    }
+}
+
+impl LinkageModel {
+    /// Load a model from a JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open linkage model {}", path.display()))?;
+        let model: Self = serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Failed to parse linkage model {}", path.display()))?;
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// Estimate the field probabilities and prior from labelled pairs
+    ///
+    /// Graded agreements count fractionally, and every count is smoothed with
+    /// one pseudo-observation each way so a field never gets a probability of
+    /// exactly 0 or 1. Thresholds are kept from the current model.
+    pub fn train(&self, pairs: &[LabelledPair]) -> Result<Self> {
+        let matches = pairs.iter().filter(|p| p.is_match).count();
+        if matches == 0 || matches == pairs.len() {
+            return Err(anyhow!("Training needs both matching and non-matching pairs"));
+        }
+
+        // (agreement sum, observations) per field, among matches and non-matches
+        let mut m_counts: HashMap<LinkageField, (f64, f64)> = HashMap::new();
+        let mut u_counts: HashMap<LinkageField, (f64, f64)> = HashMap::new();
+        for pair in pairs {
+            let counts = if pair.is_match { &mut m_counts } else { &mut u_counts };
+            for field in LinkageField::ALL {
+                if let Some(agreement) = compare_field(field, &pair.left, &pair.right) {
+                    let entry = counts.entry(field).or_insert((0.0, 0.0));
+                    entry.0 += agreement;
+                    entry.1 += 1.0;
+                }
+            }
+        }
+
+        let smoothed = |counts: &HashMap<LinkageField, (f64, f64)>, field| {
+            let (agreed, seen) = counts.get(&field).copied().unwrap_or((0.0, 0.0));
+            (agreed + 1.0) / (seen + 2.0)
+        };
+        let fields = LinkageField::ALL.iter()
+            .map(|field| (*field, FieldProbabilities {
+                m: smoothed(&m_counts, *field),
+                u: smoothed(&u_counts, *field),
+            }))
+            .collect();
+
+        info!("Trained linkage model on {} pairs ({} matches)", pairs.len(), matches);
+        Ok(Self {
+            fields,
+            prior: matches as f64 / pairs.len() as f64,
+            link_threshold: self.link_threshold,
+            review_threshold: self.review_threshold,
+        })
+    }
+
+    /// Check that the probabilities and thresholds are usable
+    pub fn validate(&self) -> Result<()> {
+        for (field, p) in &self.fields {
+            if !(p.m > 0.0 && p.m < 1.0 && p.u > 0.0 && p.u < 1.0) {
+                return Err(anyhow!("Probabilities for {} must be strictly between 0 and 1", field));
+            }
+        }
+        if !(self.prior > 0.0 && self.prior < 1.0) {
+            return Err(anyhow!("Prior must be strictly between 0 and 1, got {}", self.prior));
+        }
+        if self.review_threshold > self.link_threshold {
+            return Err(anyhow!(
+                "Review threshold {} is above the link threshold {}",
+                self.review_threshold, self.link_threshold
+            ));
+        }
+        Ok(())
+    }
+
+    /// Score a pair of records
+    pub fn score(&self, left: &DatabaseRecord, right: &DatabaseRecord) -> LinkageScore {
+        let comparisons: Vec<FieldComparison> = LinkageField::ALL.iter()
+            .filter_map(|field| {
+                let probabilities = self.fields.get(field)?;
+                let agreement = compare_field(*field, left, right);
+                let weight = agreement.map(|a| probabilities.weight(a)).unwrap_or(0.0);
+                Some(FieldComparison { field: *field, agreement, weight })
+            })
+            .collect();
+
+        let weight: f64 = comparisons.iter().map(|c| c.weight).sum();
+        let log_odds = (self.prior / (1.0 - self.prior)).log2() + weight;
+        let probability = 1.0 / (1.0 + (-log_odds).exp2());
+        let decision = if probability >= self.link_threshold {
+            LinkDecision::Link
+        } else if probability >= self.review_threshold {
+            LinkDecision::Review
+        } else {
+            LinkDecision::NonLink
+        };
+        debug!("Linkage {} ~ {}: weight {:.2}, p = {:.4}", left.label(), right.label(), weight, probability);
+
+        LinkageScore {
+            left: left.label(),
+            right: right.label(),
+            probability,
+            weight,
+            decision,
+            comparisons,
+        }
+    }
+}
+
+/// Linkage score of a pair of records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkageScore {
+    /// `source:accession` of the first record
+    pub left: String,
+
+    /// `source:accession` of the second record
+    pub right: String,
+
+    /// Posterior probability that the records are the same compound
+    pub probability: f64,
+
+    /// Total match weight in log2 units
+    pub weight: f64,
+
+    /// Decision under the model's thresholds
+    pub decision: LinkDecision,
+
+    /// Per-field comparisons behind the weight
+    pub comparisons: Vec<FieldComparison>,
+}
+
+impl LinkageScore {
+    /// Evidence that the linked records identify the given molecule
+    pub fn to_evidence(&self, molecule_id: &str) -> Evidence {
+        let mut metadata = HashMap::new();
+        metadata.insert("decision".to_string(), serde_json::json!(self.decision));
+        metadata.insert("weight".to_string(), serde_json::json!(self.weight));
+
+        Evidence {
+            id: format!("linkage-{}-{}", self.left, self.right),
+            molecule_id: molecule_id.to_string(),
+            evidence_type: EvidenceType::Literature,
+            source: LINKAGE_SOURCE.to_string(),
+            confidence: self.probability,
+            data: serde_json::json!({
+                "records": [self.left, self.right],
+                "comparisons": self.comparisons,
+            }),
+            metadata,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
+
+/// Score every pair of records that come from different sources
+///
+/// Pairs are returned most probable first.
+pub fn link_records(records: &[DatabaseRecord], model: &LinkageModel) -> Vec<LinkageScore> {
+    let mut scores = Vec::new();
+    for (i, left) in records.iter().enumerate() {
+        for right in records.iter().skip(i + 1).filter(|r| r.source != left.source) {
+            scores.push(model.score(left, right));
+        }
+    }
+    scores.sort_by(|a, b| b.probability.partial_cmp(&a.probability).unwrap_or(std::cmp::Ordering::Equal));
+    scores
+}
+
+/// Degree of agreement of one field, `None` when either record lacks it
+fn compare_field(field: LinkageField, left: &DatabaseRecord, right: &DatabaseRecord) -> Option<f64> {
+    match field {
+        LinkageField::InchiKey => {
+            let (a, b) = (left.inchi_key.as_deref()?.trim(), right.inchi_key.as_deref()?.trim());
+            if a.eq_ignore_ascii_case(b) {
+                Some(1.0)
+            } else if a.get(..14).is_some_and(|block| b.get(..14).is_some_and(|other| block.eq_ignore_ascii_case(other))) {
+                // Same skeleton, different stereochemistry or protonation
+                Some(0.5)
+            } else {
+                Some(0.0)
+            }
+        }
+        LinkageField::Mass => {
+            let difference = (left.monoisotopic_mass? - right.monoisotopic_mass?).abs();
+            Some(if difference <= MASS_AGREE_DA {
+                1.0
+            } else if difference <= MASS_PARTIAL_DA {
+                0.5
+            } else {
+                0.0
+            })
+        }
+        LinkageField::Formula => {
+            let (a, b) = (left.formula.as_ref()?, right.formula.as_ref()?);
+            let heavy = |f: &Formula| f.elements().filter(|(e, _)| *e != "H").map(|(e, n)| (e.to_string(), n)).collect::<Vec<_>>();
+            Some(if a == b {
+                1.0
+            } else if heavy(a) == heavy(b) {
+                // Charged or salt forms differ only in hydrogens
+                0.5
+            } else {
+                0.0
+            })
+        }
+        LinkageField::Name => {
+            let left_names: Vec<String> = left.names().map(|n| normalize_name(n)).collect();
+            let right_names: Vec<String> = right.names().map(|n| normalize_name(n)).collect();
+            if left_names.is_empty() || right_names.is_empty() {
+                return None;
+            }
+            let best = left_names.iter()
+                .flat_map(|a| right_names.iter().map(move |b| name_similarity(a, b)))
+                .fold(0.0, f64::max);
+            Some(if best >= NAME_AGREE_SIMILARITY { 1.0 } else { best })
+        }
+        LinkageField::Xrefs => {
+            let mut shared = 0;
+            let mut agreeing = 0;
+            let sources: std::collections::BTreeSet<&str> = left.xrefs.keys().map(|s| s.as_str())
+                .chain(std::iter::once(left.source.as_str()))
+                .collect();
+            for source in sources {
+                if let (Some(a), Some(b)) = (left.accession_in(source), right.accession_in(source)) {
+                    shared += 1;
+                    if a.eq_ignore_ascii_case(b) {
+                        agreeing += 1;
+                    }
+                }
+            }
+            (shared > 0).then(|| agreeing as f64 / shared as f64)
+        }
+    }
+}
+
+/// Similarity of two names as one minus their normalized Levenshtein distance
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(source: &str, accession: &str, name: &str, formula: &str, mass: f64) -> DatabaseRecord {
+        DatabaseRecord {
+            source: source.to_string(),
+            accession: accession.to_string(),
+            name: Some(name.to_string()),
+            synonyms: Vec::new(),
+            formula: Some(formula.parse().unwrap()),
+            monoisotopic_mass: Some(mass),
+            inchi_key: None,
+            xrefs: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn agreeing_records_link_and_conflicting_ones_do_not() {
+        let model = LinkageModel::default();
+        let mut pubchem = record("pubchem_cid", "5793", "D-Glucose", "C6H12O6", 180.0634);
+        pubchem.xrefs.insert("hmdb_id".to_string(), "HMDB0000122".to_string());
+        let hmdb = record("hmdb_id", "HMDB0000122", "glucose", "C6H12O6", 180.06339);
+        let fructose = record("hmdb_id", "HMDB0000660", "D-Fructose", "C6H12O6", 180.0634);
+
+        let same = model.score(&pubchem, &hmdb);
+        assert_eq!(same.decision, LinkDecision::Link);
+        // Same formula and mass but a different name and accession
+        let different = model.score(&pubchem, &fructose);
+        assert_eq!(different.decision, LinkDecision::NonLink);
+        assert!(different.probability < same.probability);
+
+        let evidence = same.to_evidence("glucose");
+        assert_eq!(evidence.source, LINKAGE_SOURCE);
+        assert!((evidence.confidence - same.probability).abs() < 1e-12);
+    }
+
+    #[test]
+    fn training_learns_field_reliability() {
+        let glucose = record("pubchem_cid", "5793", "glucose", "C6H12O6", 180.0634);
+        let pairs = vec![
+            LabelledPair { left: glucose.clone(), right: record("hmdb_id", "HMDB0000122", "glucose", "C6H12O6", 180.0634), is_match: true },
+            LabelledPair { left: glucose.clone(), right: record("hmdb_id", "HMDB0000660", "fructose", "C6H12O6", 180.0634), is_match: false },
+            LabelledPair { left: glucose, right: record("hmdb_id", "HMDB0000190", "lactate", "C3H6O3", 90.0317), is_match: false },
+        ];
+
+        let model = LinkageModel::default().train(&pairs).unwrap();
+        model.validate().unwrap();
+        let name = model.fields[&LinkageField::Name];
+        let formula = model.fields[&LinkageField::Formula];
+        // Names separate the isomers, formulas don't
+        assert!(name.agree_weight() > formula.agree_weight());
+        assert!((model.prior - 1.0 / 3.0).abs() < 1e-12);
+    }
+}
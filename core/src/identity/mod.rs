@@ -0,0 +1,409 @@
+//! Molecular Identity Module
+//!
+//! This module defines the identifier types a molecule can be referred to by,
+//! with syntax validation, auto-detection of the type of a raw identifier and
+//! normalization into a canonical form.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::processing::formula::Formula;
+
+pub mod xref;
+pub mod unichem;
+pub mod synonyms;
+pub mod linkage;
+
+/// Initialize the identity module
+pub fn initialize() -> Result<()> {
+    info!("Initializing identity module");
+    info!("Identity module initialized successfully");
+    Ok(())
+}
+
+/// Molecule identifier types
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MoleculeIdType {
+    InChIKey,
+    InChI,
+    SMILES,
+    Name,
+    Formula,
+    CAS,
+    PubChemCID,
+    ChEMBLID,
+    KEGGID,
+    HMDBID,
+    DrugBankID,
+    ChEBIID,
+    Custom(String),
+}
+
+impl MoleculeIdType {
+    /// Identifier types tried by `detect`, most specific first
+    const DETECTION_ORDER: [MoleculeIdType; 11] = [
+        MoleculeIdType::InChIKey,
+        MoleculeIdType::InChI,
+        MoleculeIdType::CAS,
+        MoleculeIdType::ChEBIID,
+        MoleculeIdType::ChEMBLID,
+        MoleculeIdType::HMDBID,
+        MoleculeIdType::DrugBankID,
+        MoleculeIdType::KEGGID,
+        MoleculeIdType::PubChemCID,
+        MoleculeIdType::Formula,
+        MoleculeIdType::SMILES,
+    ];
+
+    /// Canonical lowercase name of the identifier type
+    pub fn as_str(&self) -> &str {
+        match self {
+            MoleculeIdType::InChIKey => "inchikey",
+            MoleculeIdType::InChI => "inchi",
+            MoleculeIdType::SMILES => "smiles",
+            MoleculeIdType::Name => "name",
+            MoleculeIdType::Formula => "formula",
+            MoleculeIdType::CAS => "cas",
+            MoleculeIdType::PubChemCID => "pubchem_cid",
+            MoleculeIdType::ChEMBLID => "chembl_id",
+            MoleculeIdType::KEGGID => "kegg_id",
+            MoleculeIdType::HMDBID => "hmdb_id",
+            MoleculeIdType::DrugBankID => "drugbank_id",
+            MoleculeIdType::ChEBIID => "chebi_id",
+            MoleculeIdType::Custom(name) => name,
+        }
+    }
+
+    /// Check that a raw identifier is syntactically valid for this type
+    pub fn validate(&self, raw: &str) -> Result<()> {
+        let value = raw.trim();
+        if value.is_empty() {
+            return Err(anyhow!("Empty {} identifier", self));
+        }
+
+        let valid = match self {
+            MoleculeIdType::InChIKey => is_inchikey(&value.to_uppercase()),
+            MoleculeIdType::InChI => value.starts_with("InChI=1") && value.contains('/'),
+            MoleculeIdType::SMILES => return check_smiles(value),
+            MoleculeIdType::Formula => is_formula(value) && value.parse::<Formula>().is_ok(),
+            MoleculeIdType::CAS => return check_cas(value),
+            MoleculeIdType::PubChemCID => is_digits(strip_prefix_ci(value, "CID").trim_start_matches(':').trim()),
+            MoleculeIdType::ChEMBLID => has_numeric_suffix(value, "CHEMBL", 1..=9),
+            MoleculeIdType::KEGGID => {
+                ["C", "D", "G"].iter().any(|p| has_numeric_suffix(value, p, 5..=5))
+            }
+            MoleculeIdType::HMDBID => has_numeric_suffix(value, "HMDB", 5..=7),
+            MoleculeIdType::DrugBankID => has_numeric_suffix(value, "DB", 5..=5),
+            MoleculeIdType::ChEBIID => {
+                let rest = strip_prefix_ci(value, "CHEBI");
+                rest.len() != value.len() && is_digits(rest.trim_start_matches(':'))
+            }
+            MoleculeIdType::Name | MoleculeIdType::Custom(_) => true,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid {} identifier: {}", self, value))
+        }
+    }
+
+    /// Validate and rewrite an identifier into its canonical form
+    ///
+    /// e.g. lowercase InChIKeys are uppercased, `CID 2244` becomes `2244`,
+    /// `chebi:15365` becomes `CHEBI:15365`, HMDB IDs are zero-padded to 7 digits
+    /// and formulas are rewritten in Hill order.
+    pub fn normalize(&self, raw: &str) -> Result<String> {
+        self.validate(raw)?;
+        let value = raw.trim();
+
+        let normalized = match self {
+            MoleculeIdType::InChIKey => value.to_uppercase(),
+            MoleculeIdType::PubChemCID => {
+                let digits = strip_prefix_ci(value, "CID").trim_start_matches(':').trim();
+                digits.trim_start_matches('0').to_string()
+            }
+            MoleculeIdType::ChEMBLID | MoleculeIdType::KEGGID | MoleculeIdType::DrugBankID => {
+                value.to_uppercase()
+            }
+            MoleculeIdType::Formula => value.parse::<Formula>()?.hill(),
+            MoleculeIdType::HMDBID => {
+                format!("HMDB{:0>7}", &value[4..])
+            }
+            MoleculeIdType::ChEBIID => {
+                format!("CHEBI:{}", strip_prefix_ci(value, "CHEBI").trim_start_matches(':'))
+            }
+            _ => value.to_string(),
+        };
+
+        Ok(normalized)
+    }
+
+    /// Guess the type of a raw identifier
+    ///
+    /// Falls back to `Name` when no structured identifier type matches.
+    pub fn detect(raw: &str) -> MoleculeIdType {
+        let value = raw.trim();
+        let detected = Self::DETECTION_ORDER.iter()
+            .find(|id_type| id_type.validate(value).is_ok())
+            .cloned()
+            .unwrap_or(MoleculeIdType::Name);
+
+        debug!("Detected identifier type {} for {}", detected, value);
+        detected
+    }
+}
+
+impl fmt::Display for MoleculeIdType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MoleculeIdType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "inchikey" | "inchi_key" => Ok(MoleculeIdType::InChIKey),
+            "inchi" => Ok(MoleculeIdType::InChI),
+            "smiles" => Ok(MoleculeIdType::SMILES),
+            "name" => Ok(MoleculeIdType::Name),
+            "formula" => Ok(MoleculeIdType::Formula),
+            "cas" | "cas_number" => Ok(MoleculeIdType::CAS),
+            "pubchem" | "pubchem_cid" | "cid" => Ok(MoleculeIdType::PubChemCID),
+            "chembl" | "chembl_id" => Ok(MoleculeIdType::ChEMBLID),
+            "kegg" | "kegg_id" => Ok(MoleculeIdType::KEGGID),
+            "hmdb" | "hmdb_id" => Ok(MoleculeIdType::HMDBID),
+            "drugbank" | "drugbank_id" => Ok(MoleculeIdType::DrugBankID),
+            "chebi" | "chebi_id" => Ok(MoleculeIdType::ChEBIID),
+            _ => Err(anyhow!("Unsupported ID type: {}", s)),
+        }
+    }
+}
+
+/// A validated, normalized molecule identifier
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MoleculeIdentifier {
+    /// Type of the identifier
+    pub id_type: MoleculeIdType,
+
+    /// Normalized identifier value
+    pub value: String,
+}
+
+impl MoleculeIdentifier {
+    /// Validate and normalize an identifier of a known type
+    pub fn new(id_type: MoleculeIdType, raw: &str) -> Result<Self> {
+        let value = id_type.normalize(raw)?;
+        Ok(Self { id_type, value })
+    }
+
+    /// Detect the type of a raw identifier and normalize it
+    pub fn parse(raw: &str) -> Result<Self> {
+        Self::new(MoleculeIdType::detect(raw), raw)
+    }
+
+    /// Parse an identifier with a type name, where `auto` means detect the type
+    pub fn parse_as(raw: &str, id_type: &str) -> Result<Self> {
+        if id_type.eq_ignore_ascii_case("auto") {
+            Self::parse(raw)
+        } else {
+            Self::new(id_type.parse()?, raw)
+        }
+    }
+
+    /// Compact `type:value` form, e.g. `cas:50-78-2`
+    pub fn to_curie(&self) -> String {
+        format!("{}:{}", self.id_type, self.value)
+    }
+}
+
+impl fmt::Display for MoleculeIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+/// InChIKey layout: 14 uppercase letters, 10 uppercase letters, 1 uppercase letter
+///
+/// Equivalent to the regex `^[A-Z]{14}-[A-Z]{10}-[A-Z]$`.
+fn is_inchikey(value: &str) -> bool {
+    let blocks: Vec<&str> = value.split('-').collect();
+    blocks.len() == 3
+        && [14, 10, 1].iter().zip(&blocks)
+            .all(|(len, block)| block.len() == *len && block.bytes().all(|b| b.is_ascii_uppercase()))
+}
+
+/// Check the CAS registry number layout and its check digit
+fn check_cas(value: &str) -> Result<()> {
+    let parts: Vec<&str> = value.split('-').collect();
+    let well_formed = parts.len() == 3
+        && (2..=7).contains(&parts[0].len())
+        && parts[1].len() == 2
+        && parts[2].len() == 1
+        && parts.iter().all(|p| is_digits(p));
+    if !well_formed {
+        return Err(anyhow!("Invalid cas identifier: {}", value));
+    }
+
+    // Check digit: digits right-to-left weighted 1, 2, 3, ... modulo 10
+    let body: Vec<u32> = format!("{}{}", parts[0], parts[1]).chars()
+        .filter_map(|c| c.to_digit(10))
+        .collect();
+    let checksum: u32 = body.iter().rev().enumerate()
+        .map(|(i, d)| (i as u32 + 1) * d)
+        .sum::<u32>() % 10;
+    let check_digit = parts[2].chars().next().and_then(|c| c.to_digit(10)).unwrap_or(10);
+
+    if checksum == check_digit {
+        Ok(())
+    } else {
+        Err(anyhow!("CAS number {} fails its checksum (expected check digit {})", value, checksum))
+    }
+}
+
+/// Basic SMILES sanity checks: allowed characters, organic-subset atoms outside
+/// brackets, balanced branches and brackets, and paired ring closures
+fn check_smiles(value: &str) -> Result<()> {
+    const ALLOWED: &str = "()[]=#$:/\\.%@+-*";
+    if value.chars().any(|c| c.is_whitespace() || !(c.is_ascii_alphanumeric() || ALLOWED.contains(c))) {
+        return Err(anyhow!("SMILES contains invalid characters: {}", value));
+    }
+    if !value.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '[' || c == '*') {
+        return Err(anyhow!("SMILES must start with an atom: {}", value));
+    }
+
+    let mut depth = 0i32;
+    let mut in_bracket = false;
+    let mut open_rings = std::collections::HashSet::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' if in_bracket => return Err(anyhow!("Nested bracket atom in SMILES: {}", value)),
+            '[' => in_bracket = true,
+            ']' if !in_bracket => return Err(anyhow!("Unmatched ']' in SMILES: {}", value)),
+            ']' => in_bracket = false,
+            _ if in_bracket => {}
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err(anyhow!("Unmatched ')' in SMILES: {}", value));
+                }
+            }
+            'C' if chars.peek() == Some(&'l') => { chars.next(); }
+            'B' if chars.peek() == Some(&'r') => { chars.next(); }
+            a if a.is_ascii_alphabetic() && !"BCNOPSFIbcnops".contains(a) => {
+                return Err(anyhow!("Unexpected atom '{}' outside brackets in SMILES: {}", a, value));
+            }
+            '%' => {
+                let label: String = (0..2).filter_map(|_| chars.next()).collect();
+                if label.len() != 2 || !is_digits(&label) {
+                    return Err(anyhow!("Invalid ring closure label in SMILES: {}", value));
+                }
+                toggle(&mut open_rings, label);
+            }
+            d if d.is_ascii_digit() => toggle(&mut open_rings, d.to_string()),
+            _ => {}
+        }
+    }
+
+    if in_bracket || depth != 0 {
+        return Err(anyhow!("Unbalanced brackets in SMILES: {}", value));
+    }
+    if !open_rings.is_empty() {
+        return Err(anyhow!("Unclosed ring bond in SMILES: {}", value));
+    }
+    Ok(())
+}
+
+fn toggle(set: &mut std::collections::HashSet<String>, label: String) {
+    if !set.remove(&label) {
+        set.insert(label);
+    }
+}
+
+/// Molecular formula: element symbols with optional counts, each element once
+/// and at least one count, e.g. `C6H12O6` (plain `CCO` is left to SMILES)
+fn is_formula(value: &str) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    let mut has_count = false;
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_uppercase() {
+            return false;
+        }
+        let mut symbol = c.to_string();
+        if let Some(&next) = chars.peek() {
+            if next.is_ascii_lowercase() {
+                symbol.push(next);
+                chars.next();
+            }
+        }
+        if !seen.insert(symbol) {
+            return false;
+        }
+        while let Some(&next) = chars.peek() {
+            if !next.is_ascii_digit() {
+                break;
+            }
+            has_count = true;
+            chars.next();
+        }
+    }
+
+    has_count
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn strip_prefix_ci<'a>(value: &'a str, prefix: &str) -> &'a str {
+    match value.get(..prefix.len()) {
+        Some(head) if head.eq_ignore_ascii_case(prefix) => &value[prefix.len()..],
+        _ => value,
+    }
+}
+
+fn has_numeric_suffix(value: &str, prefix: &str, digits: std::ops::RangeInclusive<usize>) -> bool {
+    let rest = strip_prefix_ci(value, prefix);
+    rest.len() != value.len() && digits.contains(&rest.len()) && is_digits(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validators() {
+        assert!(MoleculeIdType::InChIKey.validate("BSYNRYMUTXBXSQ-UHFFFAOYSA-N").is_ok());
+        assert!(MoleculeIdType::InChIKey.validate("BSYNRYMUTXBXSQ-UHFFFAOYSA").is_err());
+        assert!(MoleculeIdType::CAS.validate("50-78-2").is_ok());
+        assert!(MoleculeIdType::CAS.validate("50-78-3").is_err());
+        assert!(MoleculeIdType::SMILES.validate("CC(=O)Oc1ccccc1C(=O)O").is_ok());
+        assert!(MoleculeIdType::SMILES.validate("CC(=O").is_err());
+        assert!(MoleculeIdType::SMILES.validate("c1ccccc").is_err());
+    }
+
+    #[test]
+    fn test_detect_and_normalize() {
+        assert_eq!(MoleculeIdType::detect("bsynrymutxbxsq-uhfffaoysa-n"), MoleculeIdType::InChIKey);
+        assert_eq!(MoleculeIdType::detect("50-78-2"), MoleculeIdType::CAS);
+        assert_eq!(MoleculeIdType::detect("CHEBI:15365"), MoleculeIdType::ChEBIID);
+        assert_eq!(MoleculeIdType::detect("C9H8O4"), MoleculeIdType::Formula);
+        assert_eq!(MoleculeIdType::detect("CCO"), MoleculeIdType::SMILES);
+        assert_eq!(MoleculeIdType::detect("aspirin tablets"), MoleculeIdType::Name);
+
+        let id = MoleculeIdentifier::parse_as("CID 2244", "pubchem").unwrap();
+        assert_eq!(id.value, "2244");
+        assert_eq!(MoleculeIdType::HMDBID.normalize("HMDB01879").unwrap(), "HMDB0001879");
+        assert_eq!("cas_number".parse::<MoleculeIdType>().unwrap(), MoleculeIdType::CAS);
+    }
+}
@@ -0,0 +1,237 @@
+//! Synonym Dictionary
+//!
+//! This module maps compound names to candidate database identifiers using
+//! synonym dumps from ChEBI (`names.tsv`) and PubChem (`CID-Synonym-filtered`).
+//! Names are normalized before they are indexed or looked up, so spelling
+//! variants such as "L-ascorbic acid", "Sodium ascorbate" and "ascorbate"
+//! share one entry and a name resolves locally before any external query.
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use super::xref::XrefStore;
+use super::{MoleculeIdType, MoleculeIdentifier};
+
+/// Greek letters and the spelled-out form they are normalized to
+const GREEK_LETTERS: [(char, &str); 12] = [
+    ('α', "alpha"), ('β', "beta"), ('γ', "gamma"), ('δ', "delta"),
+    ('ε', "epsilon"), ('ζ', "zeta"), ('η', "eta"), ('θ', "theta"),
+    ('κ', "kappa"), ('λ', "lambda"), ('μ', "mu"), ('ω', "omega"),
+];
+
+/// Stereo descriptors dropped when they prefix another part of a name
+const STEREO_PREFIXES: [&str; 11] = [
+    "l", "d", "dl", "(+)", "(-)", "(±)", "(r)", "(s)", "(rs)", "l(+)", "d(-)",
+];
+
+/// Counter-ions and hydration words dropped from salt names
+const SALT_WORDS: [&str; 16] = [
+    "sodium", "disodium", "potassium", "dipotassium", "calcium", "magnesium",
+    "ammonium", "lithium", "hydrochloride", "dihydrochloride", "hydrobromide", "hcl",
+    "hydrate", "monohydrate", "dihydrate", "anhydrous",
+];
+
+/// Normalize a compound name for synonym matching
+///
+/// Lowercases, spells out Greek letters, drops stereo prefixes (`L-`, `(+)-`),
+/// salt counter-ions and hydrate words, and rewrites free acids as their anion
+/// (`ascorbic acid` becomes `ascorbate`, `sulfurous acid` becomes `sulfite`).
+pub fn normalize_name(name: &str) -> String {
+    let mut spelled = String::with_capacity(name.len());
+    for c in name.trim().to_lowercase().chars() {
+        match GREEK_LETTERS.iter().find(|(letter, _)| *letter == c) {
+            Some((_, word)) => spelled.push_str(word),
+            None => spelled.push(c),
+        }
+    }
+
+    let mut tokens: Vec<String> = Vec::new();
+    for word in spelled.split(|c: char| c.is_whitespace() || c == '_') {
+        let parts: Vec<&str> = word.split('-').filter(|p| !p.is_empty()).collect();
+        for (i, part) in parts.iter().enumerate() {
+            let is_prefix = i + 1 < parts.len() && STEREO_PREFIXES.contains(part);
+            if !is_prefix {
+                tokens.push(part.to_string());
+            }
+        }
+    }
+
+    if tokens.len() > 1 {
+        tokens.retain(|t| !SALT_WORDS.contains(&t.as_str()));
+    }
+
+    if tokens.len() > 1 && tokens.last().is_some_and(|t| t == "acid") {
+        tokens.pop();
+        if let Some(last) = tokens.last_mut() {
+            if let Some(stem) = last.strip_suffix("ic") {
+                *last = format!("{}ate", stem);
+            } else if let Some(stem) = last.strip_suffix("ous") {
+                *last = format!("{}ite", stem);
+            } else {
+                last.push_str(" acid");
+            }
+        }
+    }
+
+    tokens.join(" ")
+}
+
+/// Local dictionary from normalized compound names to candidate identifiers
+#[derive(Debug, Clone, Default)]
+pub struct SynonymDictionary {
+    /// Candidate identifiers keyed by normalized name
+    index: HashMap<String, Vec<MoleculeIdentifier>>,
+}
+
+impl SynonymDictionary {
+    /// Create an empty dictionary
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of distinct normalized names
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether the dictionary holds no names
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Record that a name refers to the compound with the given identifier
+    pub fn insert(&mut self, name: &str, id: MoleculeIdentifier) {
+        let key = normalize_name(name);
+        if key.is_empty() {
+            return;
+        }
+        let ids = self.index.entry(key).or_default();
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    /// Candidate identifiers for a name, ordered by their `type:value` form
+    pub fn candidates(&self, name: &str) -> Vec<MoleculeIdentifier> {
+        let mut ids = self.index.get(&normalize_name(name)).cloned().unwrap_or_default();
+        ids.sort_by_key(|id| id.to_curie());
+        ids
+    }
+
+    /// Load a ChEBI `names.tsv` or PubChem `CID-Synonym` dump, returning the number of synonyms added
+    pub fn load(&mut self, path: &Path) -> Result<usize> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open synonym dump: {}", path.display()))?;
+        let loaded = self.read(BufReader::new(file))
+            .with_context(|| format!("Failed to read synonym dump: {}", path.display()))?;
+        info!("Loaded {} synonyms from {}", loaded, path.display());
+        Ok(loaded)
+    }
+
+    /// Read a synonym dump, detecting its format from the first line
+    ///
+    /// A ChEBI dump has a header naming its `COMPOUND_ID` and `NAME` columns;
+    /// anything else is read as PubChem's headerless `CID<TAB>synonym` lines.
+    pub fn read<R: BufRead>(&mut self, reader: R) -> Result<usize> {
+        let mut lines = reader.lines();
+        let first = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(0),
+        };
+
+        let header: Vec<String> = first.split('\t').map(|c| c.trim().to_uppercase()).collect();
+        let chebi_columns = header.iter().position(|c| c == "COMPOUND_ID")
+            .zip(header.iter().position(|c| c == "NAME"));
+        if header.contains(&"COMPOUND_ID".to_string()) && chebi_columns.is_none() {
+            return Err(anyhow!("ChEBI names dump has no NAME column"));
+        }
+
+        let mut loaded = 0;
+        let mut skipped = 0;
+        let pending = if chebi_columns.is_some() { None } else { Some(Ok(first)) };
+        for line in pending.into_iter().chain(lines) {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let entry = match chebi_columns {
+                Some((id_col, name_col)) => fields.get(id_col).zip(fields.get(name_col))
+                    .and_then(|(id, name)| {
+                        let id = format!("CHEBI:{}", id.trim().trim_start_matches("CHEBI:"));
+                        MoleculeIdentifier::new(MoleculeIdType::ChEBIID, &id).ok().map(|id| (id, *name))
+                    }),
+                None => fields.first().zip(fields.get(1))
+                    .and_then(|(cid, name)| {
+                        MoleculeIdentifier::new(MoleculeIdType::PubChemCID, cid).ok().map(|id| (id, *name))
+                    }),
+            };
+
+            match entry {
+                Some((id, name)) => {
+                    self.insert(name, id);
+                    loaded += 1;
+                }
+                None => skipped += 1,
+            }
+        }
+
+        if skipped > 0 {
+            warn!("Skipped {} malformed synonym lines", skipped);
+        }
+        debug!("Synonym dictionary now holds {} names", self.len());
+        Ok(loaded)
+    }
+}
+
+impl XrefStore for SynonymDictionary {
+    fn name(&self) -> &str {
+        "synonyms"
+    }
+
+    fn lookup(&self, id: &MoleculeIdentifier) -> Result<Vec<MoleculeIdentifier>> {
+        if id.id_type == MoleculeIdType::Name {
+            Ok(self.candidates(&id.value))
+        } else {
+            Ok(Vec::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("L-Ascorbic acid"), "ascorbate");
+        assert_eq!(normalize_name("Sodium L-ascorbate monohydrate"), "ascorbate");
+        assert_eq!(normalize_name("α-D-Glucose"), "alpha glucose");
+        assert_eq!(normalize_name("alpha-D-glucose"), "alpha glucose");
+        assert_eq!(normalize_name("Vitamin D"), "vitamin d");
+        assert_eq!(normalize_name("Sodium"), "sodium");
+    }
+
+    #[test]
+    fn test_synonyms_resolve_to_same_candidates() {
+        let chebi = "ID\tCOMPOUND_ID\tTYPE\tSOURCE\tNAME\tADAPTED\tLANGUAGE\n\
+                     1\t29073\tSYNONYM\tKEGG COMPOUND\tVitamin C\tF\ten\n\
+                     2\t29073\tSYNONYM\tIUPAC\tL-ascorbic acid\tF\ten\n\
+                     3\t29073\tSYNONYM\tChEBI\tL-ascorbate\tF\ten\n";
+        let pubchem = "54670067\tascorbic acid\n54670067\tvitamin C\n54670067\tSodium ascorbate\nnot-a-cid\tjunk\n";
+
+        let mut dictionary = SynonymDictionary::new();
+        assert_eq!(dictionary.read(chebi.as_bytes()).unwrap(), 3);
+        assert_eq!(dictionary.read(pubchem.as_bytes()).unwrap(), 3);
+
+        let expected = dictionary.candidates("L-ascorbic acid");
+        assert_eq!(expected.iter().map(|id| id.to_curie()).collect::<Vec<_>>(),
+                   vec!["chebi_id:CHEBI:29073", "pubchem_cid:54670067"]);
+        assert_eq!(dictionary.candidates("vitamin C"), expected);
+        assert_eq!(dictionary.candidates("ascorbate"), expected);
+    }
+}
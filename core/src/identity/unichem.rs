@@ -0,0 +1,222 @@
+//! UniChem Mapping Store
+//!
+//! This module loads UniChem source-mapping dumps (`src1src22.txt` and friends,
+//! one pair of compound IDs per line) into an embedded sled database, so
+//! cross-references can be resolved without network access.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug, warn};
+use std::path::Path;
+
+use super::xref::{UniChemResolver, XrefStore};
+use super::{MoleculeIdType, MoleculeIdentifier};
+
+/// Separator between the two identifiers in a mapping key
+const KEY_SEPARATOR: u8 = 0;
+
+/// Cross-reference store backed by UniChem mapping dumps
+pub struct UniChemStore {
+    /// Embedded key-value database holding one key per directed mapping
+    db: sled::Db,
+}
+
+impl UniChemStore {
+    /// Open (or create) a store at the given directory
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path)
+            .with_context(|| format!("Failed to open UniChem store: {}", path.display()))?;
+        Ok(Self { db })
+    }
+
+    /// Create a store that lives only in memory
+    pub fn temporary() -> Result<Self> {
+        let db = sled::Config::new().temporary(true).open()?;
+        Ok(Self { db })
+    }
+
+    /// Number of directed mappings in the store
+    pub fn len(&self) -> usize {
+        self.db.len()
+    }
+
+    /// Whether the store holds no mappings
+    pub fn is_empty(&self) -> bool {
+        self.db.is_empty()
+    }
+
+    /// Record that two identifiers refer to the same compound
+    pub fn insert(&self, a: &MoleculeIdentifier, b: &MoleculeIdentifier) -> Result<()> {
+        self.db.insert(mapping_key(a, b), &[])?;
+        self.db.insert(mapping_key(b, a), &[])?;
+        Ok(())
+    }
+
+    /// Load a UniChem source-mapping file, returning the number of pairs loaded
+    ///
+    /// The source IDs are taken from the header (`From src:'1'<TAB>To src:'22'`),
+    /// falling back to the `src<from>src<to>` file name. Pairs between sources
+    /// without a corresponding `MoleculeIdType` are rejected.
+    pub fn load_mapping_file(&self, path: &Path) -> Result<usize> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read UniChem mapping file: {}", path.display()))?;
+        let mut lines = content.lines();
+
+        let header = lines.next().ok_or_else(|| anyhow!("UniChem mapping file {} is empty", path.display()))?;
+        let sources = parse_header(header)
+            .or_else(|| path.file_name().and_then(|n| n.to_str()).and_then(parse_file_name))
+            .ok_or_else(|| anyhow!("Cannot determine UniChem sources for {}", path.display()))?;
+
+        let from_type = UniChemResolver::id_type(sources.0)
+            .ok_or_else(|| anyhow!("Unsupported UniChem source: {}", sources.0))?;
+        let to_type = UniChemResolver::id_type(sources.1)
+            .ok_or_else(|| anyhow!("Unsupported UniChem source: {}", sources.1))?;
+
+        // A header that doesn't name sources is a data line
+        let data = if parse_header(header).is_some() { None } else { Some(header) };
+
+        let mut batch = sled::Batch::default();
+        let mut loaded = 0;
+        let mut skipped = 0;
+        for line in data.into_iter().chain(lines) {
+            let mut cells = line.split('\t');
+            let pair = match (cells.next(), cells.next()) {
+                (Some(from), Some(to)) => (
+                    MoleculeIdentifier::new(from_type.clone(), &unichem_value(&from_type, from)),
+                    MoleculeIdentifier::new(to_type.clone(), &unichem_value(&to_type, to)),
+                ),
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            match pair {
+                (Ok(from), Ok(to)) => {
+                    batch.insert(mapping_key(&from, &to), &[]);
+                    batch.insert(mapping_key(&to, &from), &[]);
+                    loaded += 1;
+                }
+                _ => skipped += 1,
+            }
+        }
+
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+
+        if skipped > 0 {
+            warn!("Skipped {} malformed lines in {}", skipped, path.display());
+        }
+        info!("Loaded {} {} -> {} mappings from {}", loaded, from_type, to_type, path.display());
+        Ok(loaded)
+    }
+
+    /// Load every `src*src*.txt` mapping file in a directory
+    pub fn load_dir(&self, dir: &Path) -> Result<usize> {
+        let mut total = 0;
+        let mut entries: Vec<_> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read UniChem dump directory: {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.ends_with(".txt") && parse_file_name(n).is_some())
+            })
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            match self.load_mapping_file(&path) {
+                Ok(count) => total += count,
+                Err(e) => warn!("Skipping {}: {}", path.display(), e),
+            }
+        }
+        Ok(total)
+    }
+}
+
+impl XrefStore for UniChemStore {
+    fn name(&self) -> &str {
+        "unichem_dump"
+    }
+
+    fn lookup(&self, id: &MoleculeIdentifier) -> Result<Vec<MoleculeIdentifier>> {
+        let mut prefix = id.to_curie().into_bytes();
+        prefix.push(KEY_SEPARATOR);
+
+        let mut found = Vec::new();
+        for entry in self.db.scan_prefix(&prefix) {
+            let (key, _) = entry?;
+            let target = std::str::from_utf8(&key[prefix.len()..])?;
+            match parse_curie(target) {
+                Some(target) => found.push(target),
+                None => debug!("Ignoring malformed mapping key for {}", id.to_curie()),
+            }
+        }
+        Ok(found)
+    }
+}
+
+fn mapping_key(from: &MoleculeIdentifier, to: &MoleculeIdentifier) -> Vec<u8> {
+    let mut key = from.to_curie().into_bytes();
+    key.push(KEY_SEPARATOR);
+    key.extend_from_slice(to.to_curie().as_bytes());
+    key
+}
+
+fn parse_curie(curie: &str) -> Option<MoleculeIdentifier> {
+    let (id_type, value) = curie.split_once(':')?;
+    Some(MoleculeIdentifier {
+        id_type: id_type.parse().ok()?,
+        value: value.to_string(),
+    })
+}
+
+/// UniChem stores ChEBI IDs without their `CHEBI:` prefix
+fn unichem_value(id_type: &MoleculeIdType, raw: &str) -> String {
+    match id_type {
+        MoleculeIdType::ChEBIID => format!("CHEBI:{}", raw.trim()),
+        _ => raw.trim().to_string(),
+    }
+}
+
+/// Parse `From src:'1'<TAB>To src:'22'`
+fn parse_header(header: &str) -> Option<(u64, u64)> {
+    let ids: Vec<u64> = header.split('\t')
+        .filter_map(|cell| {
+            let start = cell.find("src:")? + 4;
+            cell[start..].trim_matches(|c| c == '\'' || c == '"').trim().parse().ok()
+        })
+        .collect();
+    match ids.as_slice() {
+        [from, to] => Some((*from, *to)),
+        _ => None,
+    }
+}
+
+/// Parse `src1src22.txt`
+fn parse_file_name(name: &str) -> Option<(u64, u64)> {
+    let stem = name.split('.').next()?.strip_prefix("src")?;
+    let (from, to) = stem.split_once("src")?;
+    Some((from.parse().ok()?, to.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_mapping_file() {
+        let dir = std::env::temp_dir().join(format!("hegel-unichem-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("src7src22.txt"), "From src:'7'\tTo src:'22'\n15365\t2244\nbad\n").unwrap();
+
+        let store = UniChemStore::temporary().unwrap();
+        assert_eq!(store.load_dir(&dir).unwrap(), 1);
+
+        let cid = MoleculeIdentifier::new(MoleculeIdType::PubChemCID, "2244").unwrap();
+        let found = store.lookup(&cid).unwrap();
+        assert_eq!(found, vec![MoleculeIdentifier::new(MoleculeIdType::ChEBIID, "CHEBI:15365").unwrap()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,474 @@
+//! Cross-Reference Resolution
+//!
+//! This module maps a molecule identifier to the identifiers of the same
+//! compound in other databases (PubChem, ChEBI, HMDB, KEGG, DrugBank). Local
+//! mapping stores are consulted first; an optional online resolver (UniChem)
+//! fills in whatever the local stores don't know.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::time::Duration;
+
+use super::synonyms::SynonymDictionary;
+use super::unichem::UniChemStore;
+use super::{MoleculeIdType, MoleculeIdentifier};
+use crate::offline::{self, NetworkFeature};
+
+/// Database identifier types the resolver tries to fill in
+pub const XREF_TYPES: [MoleculeIdType; 5] = [
+    MoleculeIdType::PubChemCID,
+    MoleculeIdType::ChEBIID,
+    MoleculeIdType::HMDBID,
+    MoleculeIdType::KEGGID,
+    MoleculeIdType::DrugBankID,
+];
+
+/// A local source of identifier mappings
+pub trait XrefStore: Send + Sync {
+    /// Name of the store, reported as the provenance of its mappings
+    fn name(&self) -> &str;
+
+    /// Identifiers directly mapped to the given identifier
+    fn lookup(&self, id: &MoleculeIdentifier) -> Result<Vec<MoleculeIdentifier>>;
+}
+
+/// A remote service used when the local stores are incomplete
+#[async_trait]
+pub trait OnlineResolver: Send + Sync {
+    /// Name of the resolver, reported as the provenance of its mappings
+    fn name(&self) -> &str;
+
+    /// Identifiers of the same compound known to the remote service
+    async fn resolve(&self, id: &MoleculeIdentifier) -> Result<Vec<MoleculeIdentifier>>;
+}
+
+/// Resolved cross-references for one molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossReferences {
+    /// Identifier the resolution started from
+    pub query: MoleculeIdentifier,
+
+    /// External identifiers keyed by identifier type (e.g. `chebi_id`)
+    pub external_ids: BTreeMap<String, String>,
+
+    /// Names of the stores and resolvers that contributed mappings
+    pub sources: Vec<String>,
+}
+
+impl CrossReferences {
+    /// Get the external identifier of the given type
+    pub fn get(&self, id_type: &MoleculeIdType) -> Option<&str> {
+        self.external_ids.get(id_type.as_str()).map(|s| s.as_str())
+    }
+
+    /// Database identifier types that could not be resolved
+    pub fn missing(&self) -> Vec<MoleculeIdType> {
+        XREF_TYPES.iter()
+            .filter(|t| self.get(t).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// External identifiers as a plain map, as stored on graph nodes
+    pub fn to_external_ids(&self) -> HashMap<String, String> {
+        self.external_ids.clone().into_iter().collect()
+    }
+
+    fn insert(&mut self, id: &MoleculeIdentifier) {
+        self.external_ids
+            .entry(id.id_type.to_string())
+            .or_insert_with(|| id.value.clone());
+    }
+
+    fn add_source(&mut self, source: &str) {
+        if !self.sources.iter().any(|s| s == source) {
+            self.sources.push(source.to_string());
+        }
+    }
+}
+
+/// In-memory mapping table in which each row lists the identifiers of one compound
+#[derive(Debug, Clone, Default)]
+pub struct MappingTable {
+    /// Name of the table
+    name: String,
+
+    /// Rows of equivalent identifiers
+    rows: Vec<Vec<MoleculeIdentifier>>,
+
+    /// Index from identifier to the rows containing it
+    index: HashMap<MoleculeIdentifier, Vec<usize>>,
+}
+
+impl MappingTable {
+    /// Create an empty mapping table
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a row of identifiers that all refer to the same compound
+    pub fn add_row(&mut self, ids: Vec<MoleculeIdentifier>) {
+        let row = self.rows.len();
+        for id in &ids {
+            self.index.entry(id.clone()).or_default().push(row);
+        }
+        self.rows.push(ids);
+    }
+
+    /// Number of rows in the table
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the table has no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Load a delimited table whose header names the identifier type of each column
+    ///
+    /// Tab-separated if the header contains a tab, otherwise comma-separated.
+    /// Empty and invalid cells are skipped.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mapping table: {}", path.display()))?;
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("mapping_table");
+        Self::parse(name, &content)
+    }
+
+    /// Parse a delimited mapping table from a string
+    pub fn parse(name: &str, content: &str) -> Result<Self> {
+        let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+        let header = lines.next().ok_or_else(|| anyhow!("Mapping table {} is empty", name))?;
+        let delimiter = if header.contains('\t') { '\t' } else { ',' };
+        let columns: Vec<MoleculeIdType> = header.split(delimiter)
+            .map(|column| column.parse())
+            .collect::<Result<_>>()
+            .with_context(|| format!("Invalid header in mapping table {}", name))?;
+
+        let mut table = Self::new(name);
+        let mut skipped = 0;
+        for line in lines {
+            let row: Vec<MoleculeIdentifier> = columns.iter()
+                .zip(line.split(delimiter))
+                .filter(|(_, cell)| !cell.trim().is_empty())
+                .filter_map(|(id_type, cell)| match MoleculeIdentifier::new(id_type.clone(), cell) {
+                    Ok(id) => Some(id),
+                    Err(_) => {
+                        skipped += 1;
+                        None
+                    }
+                })
+                .collect();
+            if row.len() > 1 {
+                table.add_row(row);
+            }
+        }
+
+        if skipped > 0 {
+            warn!("Skipped {} invalid identifiers in mapping table {}", skipped, name);
+        }
+        debug!("Loaded {} rows into mapping table {}", table.len(), name);
+        Ok(table)
+    }
+}
+
+impl XrefStore for MappingTable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn lookup(&self, id: &MoleculeIdentifier) -> Result<Vec<MoleculeIdentifier>> {
+        Ok(self.index.get(id)
+            .into_iter()
+            .flatten()
+            .flat_map(|&row| self.rows[row].iter().cloned())
+            .collect())
+    }
+}
+
+/// Online resolver backed by the EBI UniChem REST API
+pub struct UniChemResolver {
+    /// Base URL of the UniChem API
+    endpoint: String,
+
+    /// Request timeout
+    timeout: Duration,
+}
+
+impl UniChemResolver {
+    /// Create a resolver for the public UniChem API
+    pub fn new() -> Self {
+        Self::with_endpoint("https://www.ebi.ac.uk/unichem/api/v1")
+    }
+
+    /// Create a resolver for a UniChem-compatible API at the given URL
+    pub fn with_endpoint(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// UniChem source ID for an identifier type
+    pub fn source_id(id_type: &MoleculeIdType) -> Option<u32> {
+        match id_type {
+            MoleculeIdType::ChEMBLID => Some(1),
+            MoleculeIdType::DrugBankID => Some(2),
+            MoleculeIdType::KEGGID => Some(6),
+            MoleculeIdType::ChEBIID => Some(7),
+            MoleculeIdType::HMDBID => Some(18),
+            MoleculeIdType::PubChemCID => Some(22),
+            _ => None,
+        }
+    }
+
+    /// Identifier type for a UniChem source ID
+    pub fn id_type(source_id: u64) -> Option<MoleculeIdType> {
+        match source_id {
+            1 => Some(MoleculeIdType::ChEMBLID),
+            2 => Some(MoleculeIdType::DrugBankID),
+            6 => Some(MoleculeIdType::KEGGID),
+            7 => Some(MoleculeIdType::ChEBIID),
+            18 => Some(MoleculeIdType::HMDBID),
+            22 => Some(MoleculeIdType::PubChemCID),
+            _ => None,
+        }
+    }
+}
+
+impl Default for UniChemResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl OnlineResolver for UniChemResolver {
+    fn name(&self) -> &str {
+        "unichem"
+    }
+
+    async fn resolve(&self, id: &MoleculeIdentifier) -> Result<Vec<MoleculeIdentifier>> {
+        let payload = match &id.id_type {
+            MoleculeIdType::InChIKey => serde_json::json!({ "type": "inchikey", "compound": id.value }),
+            other => match Self::source_id(other) {
+                // UniChem stores ChEBI IDs without the prefix
+                Some(source) => serde_json::json!({
+                    "type": "sourceID",
+                    "sourceID": source,
+                    "compound": id.value.trim_start_matches("CHEBI:"),
+                }),
+                None => return Ok(Vec::new()),
+            },
+        };
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/compounds", self.endpoint))
+            .json(&payload)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .context("Failed to send request to UniChem")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("UniChem request failed with status {}", response.status()));
+        }
+
+        let data = response.json::<serde_json::Value>().await
+            .context("Failed to parse UniChem response")?;
+
+        let mut ids = Vec::new();
+        for compound in data["compounds"].as_array().into_iter().flatten() {
+            if let Some(key) = compound["standardInchiKey"].as_str() {
+                ids.extend(MoleculeIdentifier::new(MoleculeIdType::InChIKey, key).ok());
+            }
+            for source in compound["sources"].as_array().into_iter().flatten() {
+                let id_type = source["id"].as_u64().and_then(Self::id_type);
+                let value = source["compoundId"].as_str();
+                if let (Some(id_type), Some(value)) = (id_type, value) {
+                    ids.extend(MoleculeIdentifier::new(id_type, value).ok());
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+}
+
+/// Cross-reference resolution service
+pub struct XrefService {
+    /// Local mapping stores, consulted in order
+    stores: Vec<Box<dyn XrefStore>>,
+
+    /// Optional online fallback
+    online: Option<Box<dyn OnlineResolver>>,
+}
+
+impl XrefService {
+    /// Create a service with no mapping stores
+    pub fn new() -> Self {
+        Self {
+            stores: Vec::new(),
+            online: None,
+        }
+    }
+
+    /// Add a local mapping store
+    pub fn with_store(mut self, store: Box<dyn XrefStore>) -> Self {
+        self.stores.push(store);
+        self
+    }
+
+    /// Fall back to an online resolver when local stores are incomplete
+    pub fn with_online_fallback(mut self, resolver: Box<dyn OnlineResolver>) -> Self {
+        self.online = Some(resolver);
+        self
+    }
+
+    /// Build a service from the environment
+    ///
+    /// `HEGEL_SYNONYM_DUMPS` lists ChEBI and PubChem synonym dumps used to
+    /// resolve names; `HEGEL_XREF_TABLES` is a list of mapping table paths,
+    /// both separated by the platform path separator; `HEGEL_UNICHEM_STORE` opens an offline UniChem
+    /// store, loading any dumps in `HEGEL_UNICHEM_DUMPS` into it first;
+    /// `HEGEL_XREF_ONLINE=1` enables the UniChem fallback.
+    pub fn from_env() -> Result<Self> {
+        let mut service = Self::new();
+
+        if let Some(paths) = std::env::var_os("HEGEL_SYNONYM_DUMPS") {
+            let mut dictionary = SynonymDictionary::new();
+            for path in std::env::split_paths(&paths) {
+                dictionary.load(&path)?;
+            }
+            service = service.with_store(Box::new(dictionary));
+        }
+
+        if let Some(store_path) = std::env::var_os("HEGEL_UNICHEM_STORE") {
+            let store = UniChemStore::open(Path::new(&store_path))?;
+            if let Some(dumps) = std::env::var_os("HEGEL_UNICHEM_DUMPS") {
+                store.load_dir(Path::new(&dumps))?;
+            }
+            service = service.with_store(Box::new(store));
+        }
+
+        if let Some(paths) = std::env::var_os("HEGEL_XREF_TABLES") {
+            for path in std::env::split_paths(&paths) {
+                service = service.with_store(Box::new(MappingTable::load(&path)?));
+            }
+        }
+
+        if std::env::var("HEGEL_XREF_ONLINE").map(|v| v == "1" || v == "true").unwrap_or(false) {
+            if offline::is_offline() {
+                warn!("HEGEL_XREF_ONLINE ignored: {} is unavailable in offline mode", NetworkFeature::OnlineXrefs);
+            } else {
+                service = service.with_online_fallback(Box::new(UniChemResolver::new()));
+            }
+        }
+
+        Ok(service)
+    }
+
+    /// Resolve the cross-references of a raw identifier, detecting its type
+    pub async fn resolve_raw(&self, raw: &str) -> Result<CrossReferences> {
+        self.resolve(&MoleculeIdentifier::parse(raw)?).await
+    }
+
+    /// Resolve all cross-references of an identifier
+    ///
+    /// Mappings are followed transitively through the local stores. An online
+    /// failure is logged and the local result returned; offline, the online
+    /// resolver is not consulted at all.
+    pub async fn resolve(&self, id: &MoleculeIdentifier) -> Result<CrossReferences> {
+        let mut xrefs = CrossReferences {
+            query: id.clone(),
+            external_ids: BTreeMap::new(),
+            sources: Vec::new(),
+        };
+
+        let known = self.resolve_local(id, &mut xrefs)?;
+
+        if let Some(online) = self.online.as_ref().filter(|_| !offline::is_offline()) {
+            if !xrefs.missing().is_empty() {
+                debug!("Querying {} for {} missing cross-references of {}",
+                       online.name(), xrefs.missing().len(), id.to_curie());
+                // Prefer asking with an InChIKey or database ID over a name or SMILES
+                let probe = known.iter()
+                    .find(|k| k.id_type == MoleculeIdType::InChIKey)
+                    .or_else(|| known.iter().find(|k| UniChemResolver::source_id(&k.id_type).is_some()))
+                    .unwrap_or(id);
+
+                match online.resolve(probe).await {
+                    Ok(ids) if !ids.is_empty() => {
+                        for found in &ids {
+                            xrefs.insert(found);
+                        }
+                        xrefs.add_source(online.name());
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Online cross-reference lookup failed for {}: {}", id.to_curie(), e),
+                }
+            }
+        }
+
+        Ok(xrefs)
+    }
+
+    /// Breadth-first closure over the local stores, returning every identifier reached
+    fn resolve_local(&self, id: &MoleculeIdentifier, xrefs: &mut CrossReferences) -> Result<Vec<MoleculeIdentifier>> {
+        let mut seen: HashSet<MoleculeIdentifier> = HashSet::new();
+        let mut queue = VecDeque::from(vec![id.clone()]);
+        seen.insert(id.clone());
+
+        while let Some(current) = queue.pop_front() {
+            for store in &self.stores {
+                for found in store.lookup(&current)? {
+                    xrefs.add_source(store.name());
+                    if seen.insert(found.clone()) {
+                        queue.push_back(found);
+                    }
+                }
+            }
+        }
+
+        let mut known: Vec<MoleculeIdentifier> = seen.into_iter().collect();
+        known.sort_by_key(|k| k.to_curie());
+        for found in &known {
+            xrefs.insert(found);
+        }
+        Ok(known)
+    }
+}
+
+impl Default for XrefService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_through_local_tables() {
+        let pubchem = MappingTable::parse("pubchem", "pubchem_cid,chebi_id\n2244,CHEBI:15365\n").unwrap();
+        let hmdb = MappingTable::parse("hmdb", "chebi_id\thmdb_id\tkegg_id\nCHEBI:15365\tHMDB01879\tD00109\n").unwrap();
+        let service = XrefService::new()
+            .with_store(Box::new(pubchem))
+            .with_store(Box::new(hmdb));
+
+        let xrefs = service.resolve_raw("CID 2244").await.unwrap();
+        assert_eq!(xrefs.get(&MoleculeIdType::HMDBID), Some("HMDB0001879"));
+        assert_eq!(xrefs.get(&MoleculeIdType::KEGGID), Some("D00109"));
+        assert_eq!(xrefs.missing(), vec![MoleculeIdType::DrugBankID]);
+        assert_eq!(xrefs.sources, vec!["pubchem", "hmdb"]);
+    }
+}
@@ -12,6 +12,25 @@ pub mod processing;
 pub mod graph;
 pub mod metacognition;
 pub mod fuzzy_evidence;
+pub mod identity;
+pub mod rng;
+pub mod auth;
+pub mod projects;
+pub mod cohorts;
+pub mod search;
+pub mod plugins;
+pub mod curation;
+pub mod bundle;
+pub mod webhooks;
+pub mod alerts;
+pub mod offline;
+pub mod cancellation;
+pub mod client;
+pub mod http_cache;
+#[cfg(feature = "streams")]
+pub mod streams;
+#[cfg(feature = "tui")]
+pub mod tui;
 
 /// Version of the Hegel core library
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -27,9 +46,28 @@ pub fn initialize() -> Result<()> {
     env_logger::init();
     
     // Initialize other components
+    rng::initialize()?;
+    offline::initialize()?;
+    cancellation::initialize()?;
+    auth::initialize()?;
+    projects::initialize()?;
+    cohorts::initialize()?;
+    search::initialize()?;
+    plugins::initialize()?;
+    curation::initialize()?;
+    bundle::initialize()?;
+    identity::initialize()?;
     processing::initialize()?;
     graph::initialize()?;
     metacognition::initialize()?;
+    webhooks::initialize()?;
+    alerts::initialize()?;
+    client::initialize()?;
+    http_cache::initialize()?;
+    #[cfg(feature = "streams")]
+    streams::initialize()?;
+    #[cfg(feature = "tui")]
+    tui::initialize()?;
     
     info!("Hegel core engine initialized successfully");
     
@@ -65,6 +103,7 @@ impl From<std::io::Error> for HegelError {
 }
 
 /// Core data structures for molecular evidence
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct MolecularEvidence {
     pub source: String,
     pub confidence: f64,
@@ -72,6 +111,7 @@ pub struct MolecularEvidence {
     pub value: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum EvidenceType {
     Spectral,
     Sequence,
@@ -114,38 +154,8 @@ impl ConfidenceCalculator {
     }
 }
 
-/// Molecule representation
-pub struct Molecule {
-    pub id: String,
-    pub name: String,
-    pub formula: String,
-    pub smiles: Option<String>,
-    pub inchi: Option<String>,
-    pub evidences: Vec<MolecularEvidence>,
-    pub confidence_score: f64,
-}
-
-impl Molecule {
-    pub fn new(id: String, name: String, formula: String) -> Self {
-        Molecule {
-            id,
-            name,
-            formula,
-            smiles: None,
-            inchi: None,
-            evidences: Vec::new(),
-            confidence_score: 0.0,
-        }
-    }
-    
-    pub fn add_evidence(&mut self, evidence: MolecularEvidence) {
-        self.evidences.push(evidence);
-    }
-    
-    pub fn update_confidence(&mut self, calculator: &ConfidenceCalculator) {
-        self.confidence_score = calculator.calculate_confidence(&self.evidences);
-    }
-}
+/// Molecule representation, shared by every module
+pub use processing::Molecule;
 
 /// Public API for the core library
 pub fn rectify_molecule_identity(molecule: &mut Molecule, calculator: &ConfidenceCalculator) -> Result<(), HegelError> {
@@ -188,16 +198,30 @@ pub mod api {
         Ok(ValidationResult::default())
     }
     
-    /// Compare two molecules for similarity
-    pub fn compare_molecules(smiles1: &str, smiles2: &str) -> Result<f64> {
-        // Implement molecular comparison
-        Ok(0.0)
+    /// Compare two molecules for similarity using the named metric
+    pub fn compare_molecules(smiles1: &str, smiles2: &str, metric: &str) -> Result<f64> {
+        let mol1 = processing::Molecule::from_smiles(smiles1)?;
+        let mol2 = processing::Molecule::from_smiles(smiles2)?;
+        graph::similarity::SimilarityRegistry::global().compute(metric, &mol1, &mol2)
     }
     
-    /// Build a similarity network for a set of molecules
-    pub fn build_similarity_network(molecules: &[&str]) -> Result<NetworkGraph> {
-        // Implement network building
-        Ok(NetworkGraph::default())
+    /// Build a similarity network for a set of molecules using the named metric
+    pub fn build_similarity_network(molecules: &[&str], metric: &str) -> Result<NetworkGraph> {
+        let mut builder = graph::NetworkBuilder::new(0.0, molecules.len()).with_metric(metric);
+        for smiles in molecules {
+            builder.add_molecule(&processing::Molecule::from_smiles(smiles)?)?;
+        }
+        builder.build_similarities()?;
+        
+        let network = builder.build().to_serializable();
+        Ok(NetworkGraph {
+            nodes: network.nodes.into_iter()
+                .map(|node| Node { id: node.id, smiles: node.smiles, properties: node.properties })
+                .collect(),
+            edges: network.edges.into_iter()
+                .map(|edge| Edge { source: edge.source, target: edge.target, similarity: edge.weight })
+                .collect(),
+        })
     }
     
     /// Result of molecule validation
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 use std::process;
 use anyhow::{Result, Context};
 use hegel::{self, api};
+use hegel::graph::similarity::DEFAULT_METRIC;
 
 fn main() -> Result<()> {
     // Initialize the core engine
@@ -34,7 +35,8 @@ fn main() -> Result<()> {
             
             let smiles1 = &args[2];
             let smiles2 = &args[3];
-            compare_molecules(smiles1, smiles2)?;
+            let metric = args.get(4).map(String::as_str).unwrap_or(DEFAULT_METRIC);
+            compare_molecules(smiles1, smiles2, metric)?;
         },
         "network" => {
             if args.len() < 3 {
@@ -43,7 +45,8 @@ fn main() -> Result<()> {
             }
             
             let filepath = &args[2];
-            build_network(filepath)?;
+            let metric = args.get(3).map(String::as_str).unwrap_or(DEFAULT_METRIC);
+            build_network(filepath, metric)?;
         },
         "serve" => {
             let port = if args.len() >= 3 {
@@ -71,8 +74,8 @@ fn print_usage() {
     println!("Hegel Molecular Identity Platform CLI");
     println!("Usage:");
     println!("  hegel-cli validate <SMILES>              - Validate a molecule");
-    println!("  hegel-cli compare <SMILES1> <SMILES2>    - Compare two molecules");
-    println!("  hegel-cli network <FILE>                 - Build a similarity network");
+    println!("  hegel-cli compare <SMILES1> <SMILES2> [METRIC] - Compare two molecules");
+    println!("  hegel-cli network <FILE> [METRIC]        - Build a similarity network");
     println!("  hegel-cli serve [PORT]                   - Start the API server");
     println!("  hegel-cli help                           - Show this help message");
 }
@@ -109,12 +112,13 @@ fn validate_molecule(smiles: &str) -> Result<()> {
     }
 }
 
-fn compare_molecules(smiles1: &str, smiles2: &str) -> Result<()> {
+fn compare_molecules(smiles1: &str, smiles2: &str, metric: &str) -> Result<()> {
     println!("Comparing molecules:");
     println!("  Molecule 1: {}", smiles1);
     println!("  Molecule 2: {}", smiles2);
+    println!("  Metric: {}", metric);
     
-    match api::compare_molecules(smiles1, smiles2) {
+    match api::compare_molecules(smiles1, smiles2, metric) {
         Ok(similarity) => {
             println!("Similarity: {:.2}%", similarity * 100.0);
             Ok(())
@@ -126,7 +130,7 @@ fn compare_molecules(smiles1: &str, smiles2: &str) -> Result<()> {
     }
 }
 
-fn build_network(filepath: &str) -> Result<()> {
+fn build_network(filepath: &str, metric: &str) -> Result<()> {
     println!("Building molecular similarity network from: {}", filepath);
     
     // Read the file with SMILES strings
@@ -141,7 +145,7 @@ fn build_network(filepath: &str) -> Result<()> {
     println!("Found {} molecules", smiles_list.len());
     
     // Build the network
-    match api::build_similarity_network(&smiles_list) {
+    match api::build_similarity_network(&smiles_list, metric) {
         Ok(network) => {
             println!("Network built successfully:");
             println!("  Nodes: {}", network.nodes.len());
@@ -0,0 +1,343 @@
+//! Literature Claim Extraction
+//!
+//! Structured claims about a molecule pulled from article abstracts by the
+//! LLM: where it was detected, which pathways it takes part in and what it is
+//! associated with. A language model will happily paraphrase or invent the
+//! sentence it cites, so every claim is checked against the abstract before
+//! it is used: the cited sentence must occur in the text (its offsets are
+//! corrected when the model miscounted them) and must name both the molecule
+//! and the claim's object. Only verified claims become `Literature` evidence,
+//! with the PMID and sentence offsets recorded as their provenance.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use super::templates::TemplateSet;
+use crate::processing::evidence::{Evidence, EvidenceType};
+
+/// Source recorded on evidence produced from extracted claims
+pub const CLAIM_SOURCE: &str = "llm-claims";
+
+/// Name of the template the extraction prompt is rendered from
+pub const CLAIM_TEMPLATE: &str = "claim_extraction";
+
+/// Confidence assumed for a claim the model gave no confidence for
+const DEFAULT_CLAIM_CONFIDENCE: f64 = 0.5;
+
+/// Highest confidence evidence from a single extracted claim can carry
+const MAX_CLAIM_CONFIDENCE: f64 = 0.8;
+
+/// Initialize the claim extraction module
+pub fn initialize() -> Result<()> {
+    info!("Initializing claim extraction module");
+    info!("Claim extraction module initialized successfully");
+    Ok(())
+}
+
+/// An article abstract to extract claims from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArticleAbstract {
+    /// PubMed ID
+    pub pmid: String,
+
+    /// Article title
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Abstract text; claim offsets are character offsets into it
+    pub text: String,
+}
+
+/// What a claim asserts about the molecule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimKind {
+    /// Detected or measured in a sample matrix, tissue or organism
+    DetectedIn,
+
+    /// Takes part in a metabolic pathway
+    InPathway,
+
+    /// Associated with a disease, condition or phenotype
+    AssociatedWith,
+}
+
+impl fmt::Display for ClaimKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClaimKind::DetectedIn => write!(f, "detected_in"),
+            ClaimKind::InPathway => write!(f, "in_pathway"),
+            ClaimKind::AssociatedWith => write!(f, "associated_with"),
+        }
+    }
+}
+
+/// A claim as reported by the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claim {
+    /// What the claim asserts
+    pub kind: ClaimKind,
+
+    /// Matrix, pathway or condition the claim is about
+    pub object: String,
+
+    /// Supporting sentence, as quoted by the model
+    pub sentence: String,
+
+    /// Character offset of the sentence in the abstract
+    pub start: usize,
+
+    /// Character offset just past the end of the sentence
+    pub end: usize,
+
+    /// Model's confidence in the claim (0.0 - 1.0)
+    #[serde(default)]
+    pub confidence: Option<f64>,
+}
+
+/// Outcome of checking a claim against its abstract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimCheck {
+    /// The sentence is at the reported offsets
+    Verified,
+
+    /// The sentence occurs in the abstract, but not at the reported offsets
+    Relocated,
+
+    /// The sentence does not occur in the abstract
+    NotInSource,
+
+    /// The sentence does not name the molecule
+    MoleculeNotMentioned,
+
+    /// The sentence does not name the claim's object
+    ObjectNotMentioned,
+}
+
+impl ClaimCheck {
+    /// Whether a claim with this outcome can be used as evidence
+    pub fn is_verified(&self) -> bool {
+        matches!(self, ClaimCheck::Verified | ClaimCheck::Relocated)
+    }
+}
+
+impl fmt::Display for ClaimCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClaimCheck::Verified => write!(f, "verified"),
+            ClaimCheck::Relocated => write!(f, "relocated"),
+            ClaimCheck::NotInSource => write!(f, "not_in_source"),
+            ClaimCheck::MoleculeNotMentioned => write!(f, "molecule_not_mentioned"),
+            ClaimCheck::ObjectNotMentioned => write!(f, "object_not_mentioned"),
+        }
+    }
+}
+
+/// A claim after checking, with the offsets corrected when it was relocated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckedClaim {
+    /// PubMed ID of the abstract the claim came from
+    pub pmid: String,
+
+    /// The claim
+    pub claim: Claim,
+
+    /// Outcome of the check
+    pub check: ClaimCheck,
+}
+
+impl CheckedClaim {
+    /// Literature evidence for the molecule, citing the article
+    pub fn to_evidence(&self, molecule_id: &str) -> Evidence {
+        let confidence = self.claim.confidence
+            .unwrap_or(DEFAULT_CLAIM_CONFIDENCE)
+            .clamp(0.0, MAX_CLAIM_CONFIDENCE);
+
+        let mut metadata = HashMap::new();
+        metadata.insert("provenance".to_string(), serde_json::json!({
+            "extractor": CLAIM_SOURCE,
+            "pmid": self.pmid,
+            "sentence_offsets": [self.claim.start, self.claim.end],
+            "check": self.check,
+        }));
+        metadata.insert("claim_kind".to_string(), serde_json::json!(self.claim.kind));
+
+        Evidence {
+            id: format!("claim-{}-{}-{}", self.pmid, self.claim.start, self.claim.kind),
+            molecule_id: molecule_id.to_string(),
+            evidence_type: EvidenceType::Literature,
+            source: CLAIM_SOURCE.to_string(),
+            confidence,
+            data: serde_json::json!({
+                "pmid": self.pmid,
+                "kind": self.claim.kind,
+                "object": self.claim.object,
+                "sentence": self.claim.sentence,
+            }),
+            metadata,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
+
+/// Variables of the claim extraction template
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaimPromptContext {
+    /// Molecule name
+    pub molecule: String,
+
+    /// Other names of the molecule, comma-separated
+    pub synonyms: String,
+
+    /// PubMed ID
+    pub pmid: String,
+
+    /// Article title, or `(untitled)`
+    pub title: String,
+
+    /// Abstract text
+    pub text: String,
+}
+
+/// Render the extraction prompt for one abstract
+pub fn claim_prompt(templates: &TemplateSet, molecule: &str, synonyms: &[String], article: &ArticleAbstract) -> Result<String> {
+    let context = ClaimPromptContext {
+        molecule: molecule.to_string(),
+        synonyms: if synonyms.is_empty() { "no other names".to_string() } else { synonyms.join(", ") },
+        pmid: article.pmid.clone(),
+        title: article.title.clone().unwrap_or_else(|| "(untitled)".to_string()),
+        text: article.text.clone(),
+    };
+    templates.render(CLAIM_TEMPLATE, None, &context)
+}
+
+/// Parse the claims out of a model response
+///
+/// Models often wrap the JSON in prose or a code fence, so the outermost
+/// array in the response is parsed.
+pub fn parse_claims(response: &str) -> Result<Vec<Claim>> {
+    let start = response.find('[').ok_or_else(|| anyhow!("Response contains no JSON array of claims"))?;
+    let end = response.rfind(']').filter(|end| *end > start)
+        .ok_or_else(|| anyhow!("Response contains no JSON array of claims"))?;
+    serde_json::from_str(&response[start..=end]).context("Failed to parse claims from the response")
+}
+
+/// Check claims against the abstract they were extracted from
+///
+/// `names` are the molecule's name and synonyms; a claim's sentence must
+/// contain one of them.
+pub fn check_claims(article: &ArticleAbstract, names: &[String], claims: Vec<Claim>) -> Vec<CheckedClaim> {
+    claims.into_iter()
+        .map(|mut claim| {
+            let check = check_claim(&article.text, names, &mut claim);
+            debug!("Claim from PMID {} ({} {}): {}", article.pmid, claim.kind, claim.object, check);
+            CheckedClaim { pmid: article.pmid.clone(), claim, check }
+        })
+        .collect()
+}
+
+/// Check one claim, moving its offsets to where the sentence actually is
+fn check_claim(text: &str, names: &[String], claim: &mut Claim) -> ClaimCheck {
+    let sentence = claim.sentence.trim();
+    if sentence.is_empty() {
+        return ClaimCheck::NotInSource;
+    }
+
+    let at_offsets = char_slice(text, claim.start, claim.end).map(str::trim) == Some(sentence);
+    let located = if at_offsets {
+        ClaimCheck::Verified
+    } else {
+        match text.find(sentence) {
+            Some(byte_start) => {
+                claim.start = text[..byte_start].chars().count();
+                claim.end = claim.start + sentence.chars().count();
+                ClaimCheck::Relocated
+            }
+            None => return ClaimCheck::NotInSource,
+        }
+    };
+
+    let lowered = sentence.to_lowercase();
+    let mentions = |term: &str| !term.trim().is_empty() && lowered.contains(&term.trim().to_lowercase());
+    if !names.iter().any(|name| mentions(name)) {
+        ClaimCheck::MoleculeNotMentioned
+    } else if !mentions(&claim.object) {
+        ClaimCheck::ObjectNotMentioned
+    } else {
+        located
+    }
+}
+
+/// Slice of `text` between two character offsets
+fn char_slice(text: &str, start: usize, end: usize) -> Option<&str> {
+    if start >= end {
+        return None;
+    }
+    let mut boundaries = text.char_indices().map(|(i, _)| i).chain(std::iter::once(text.len()));
+    let byte_start = boundaries.nth(start)?;
+    let byte_end = boundaries.nth(end - start - 1)?;
+    Some(&text[byte_start..byte_end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article() -> ArticleAbstract {
+        ArticleAbstract {
+            pmid: "31234567".to_string(),
+            title: None,
+            text: "Background matters. Trimethylamine N-oxide was detected in human urine. TMAO levels correlate with atherosclerosis.".to_string(),
+        }
+    }
+
+    fn claim(kind: ClaimKind, object: &str, sentence: &str, start: usize, end: usize) -> Claim {
+        Claim { kind, object: object.to_string(), sentence: sentence.to_string(), start, end, confidence: Some(0.9) }
+    }
+
+    #[test]
+    fn claims_are_checked_against_the_abstract() {
+        let names = vec!["trimethylamine N-oxide".to_string(), "TMAO".to_string()];
+        let response = r#"Here are the claims:
+```json
+[{"kind": "detected_in", "object": "urine", "sentence": "Trimethylamine N-oxide was detected in human urine.", "start": 20, "end": 71},
+ {"kind": "associated_with", "object": "atherosclerosis", "sentence": "TMAO levels correlate with atherosclerosis.", "start": 0, "end": 10},
+ {"kind": "associated_with", "object": "diabetes", "sentence": "TMAO causes diabetes.", "start": 72, "end": 93},
+ {"kind": "in_pathway", "object": "choline metabolism", "sentence": "Background matters.", "start": 0, "end": 19}]
+```"#;
+
+        let checked = check_claims(&article(), &names, parse_claims(response).unwrap());
+        let checks: Vec<ClaimCheck> = checked.iter().map(|c| c.check).collect();
+        assert_eq!(checks, vec![
+            ClaimCheck::Verified,
+            ClaimCheck::Relocated,
+            ClaimCheck::NotInSource,
+            ClaimCheck::MoleculeNotMentioned,
+        ]);
+        // Relocated claims point at where the sentence really is
+        assert_eq!(checked[1].claim.start, 72);
+        assert_eq!(char_slice(&article().text, checked[1].claim.start, checked[1].claim.end),
+                   Some("TMAO levels correlate with atherosclerosis."));
+    }
+
+    #[test]
+    fn verified_claims_cite_their_article() {
+        let checked = CheckedClaim {
+            pmid: "31234567".to_string(),
+            claim: claim(ClaimKind::DetectedIn, "urine", "Trimethylamine N-oxide was detected in human urine.", 20, 71),
+            check: ClaimCheck::Verified,
+        };
+        let evidence = checked.to_evidence("tmao");
+        assert_eq!(evidence.evidence_type, EvidenceType::Literature);
+        assert_eq!(evidence.metadata["provenance"]["pmid"], "31234567");
+        assert_eq!(evidence.data["pmid"], "31234567");
+        assert!((evidence.confidence - MAX_CLAIM_CONFIDENCE).abs() < 1e-12);
+
+        let prompt = claim_prompt(&TemplateSet::builtin(), "TMAO", &[], &article()).unwrap();
+        assert!(prompt.contains("PubMed article 31234567"));
+    }
+}
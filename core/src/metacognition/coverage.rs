@@ -0,0 +1,246 @@
+//! Evidence Coverage Module
+//!
+//! Confidence built from one kind of evidence is fragile: three spectral
+//! library matches agree with each other for the same reasons they might all
+//! be wrong. The coverage analyzer groups a molecule's evidence into broad
+//! categories, scores how many of them are covered, scales confidence by that
+//! score and suggests which missing category would add the most.
+
+use anyhow::Result;
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::processing::evidence::EvidenceType;
+
+/// Initialize the evidence coverage module
+pub fn initialize() -> Result<()> {
+    info!("Initializing evidence coverage module");
+    info!("Evidence coverage module initialized successfully");
+    Ok(())
+}
+
+/// Broad category of evidence about a molecule's identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum EvidenceCategory {
+    /// Mass spectra, NMR and other spectroscopic measurements
+    Spectral,
+    /// Sequencing and gene expression
+    Sequence,
+    /// Crystal structures, structure elucidation and reference standards
+    Structural,
+    /// Pathway and reaction context
+    Pathway,
+    /// Publications and curated databases
+    Literature,
+}
+
+impl EvidenceCategory {
+    /// All categories, in reporting order
+    pub const ALL: [EvidenceCategory; 5] = [
+        EvidenceCategory::Spectral,
+        EvidenceCategory::Sequence,
+        EvidenceCategory::Structural,
+        EvidenceCategory::Pathway,
+        EvidenceCategory::Literature,
+    ];
+
+    /// Category of an evidence item
+    ///
+    /// The evidence type decides where it can; evidence of type `Other` is
+    /// classified by keywords in its source name.
+    pub fn classify(evidence_type: EvidenceType, source: &str) -> Option<Self> {
+        match evidence_type {
+            EvidenceType::MassSpec => return Some(EvidenceCategory::Spectral),
+            EvidenceType::Genomics => return Some(EvidenceCategory::Sequence),
+            EvidenceType::Pathway | EvidenceType::Reactome => return Some(EvidenceCategory::Pathway),
+            EvidenceType::Literature => return Some(EvidenceCategory::Literature),
+            EvidenceType::Structural => return Some(EvidenceCategory::Structural),
+            EvidenceType::Other => {}
+        }
+
+        let source = source.to_lowercase();
+        let mentions = |keywords: &[&str]| keywords.iter().any(|k| source.contains(k));
+        if mentions(&["xray", "x-ray", "crystal", "pdb", "cryo", "structure", "standard"]) {
+            Some(EvidenceCategory::Structural)
+        } else if mentions(&["nmr", "spectr", "ms2", "msms", "infrared"]) {
+            Some(EvidenceCategory::Spectral)
+        } else if mentions(&["sequenc", "genom", "transcript", "rna", "proteom"]) {
+            Some(EvidenceCategory::Sequence)
+        } else if mentions(&["pathway", "kegg", "reactome", "metacyc"]) {
+            Some(EvidenceCategory::Pathway)
+        } else if mentions(&["pubmed", "literature", "publication", "patent"]) {
+            Some(EvidenceCategory::Literature)
+        } else {
+            None
+        }
+    }
+
+    /// What acquiring evidence of this category involves
+    fn acquisition_hint(&self) -> &'static str {
+        match self {
+            EvidenceCategory::Spectral => "acquire MS/MS or NMR spectra and match them against a reference library",
+            EvidenceCategory::Sequence => "check for the biosynthetic genes or their expression",
+            EvidenceCategory::Structural => "compare against an authentic reference standard or a solved structure",
+            EvidenceCategory::Pathway => "check that the molecule's pathway is active in the sample",
+            EvidenceCategory::Literature => "look for prior reports of the molecule in this organism or matrix",
+        }
+    }
+}
+
+impl std::fmt::Display for EvidenceCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvidenceCategory::Spectral => write!(f, "spectral"),
+            EvidenceCategory::Sequence => write!(f, "sequence"),
+            EvidenceCategory::Structural => write!(f, "structural"),
+            EvidenceCategory::Pathway => write!(f, "pathway"),
+            EvidenceCategory::Literature => write!(f, "literature"),
+        }
+    }
+}
+
+/// Suggested next piece of evidence to acquire
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceSuggestion {
+    /// Missing category
+    pub category: EvidenceCategory,
+
+    /// Increase in coverage score if the category were covered
+    pub score_gain: f64,
+
+    /// What acquiring the evidence involves
+    pub rationale: String,
+}
+
+/// Coverage of a molecule's evidence across categories
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageReport {
+    /// Categories with at least one evidence item
+    pub present: Vec<EvidenceCategory>,
+
+    /// Categories without evidence
+    pub missing: Vec<EvidenceCategory>,
+
+    /// Evidence items per present category
+    pub counts: HashMap<EvidenceCategory, usize>,
+
+    /// Weighted share of categories covered (0.0 - 1.0)
+    pub score: f64,
+
+    /// Missing categories ordered by how much they would add
+    pub suggestions: Vec<EvidenceSuggestion>,
+}
+
+impl CoverageReport {
+    /// The single most valuable category to acquire next, if any is missing
+    pub fn next_best(&self) -> Option<&EvidenceSuggestion> {
+        self.suggestions.first()
+    }
+}
+
+/// Analyzer scoring evidence coverage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageAnalyzer {
+    /// Importance of each category to an identification
+    pub weights: HashMap<EvidenceCategory, f64>,
+
+    /// Fraction of confidence kept when no category is covered
+    pub confidence_floor: f64,
+}
+
+impl Default for CoverageAnalyzer {
+    fn default() -> Self {
+        Self {
+            weights: HashMap::from([
+                (EvidenceCategory::Spectral, 0.3),
+                (EvidenceCategory::Structural, 0.25),
+                (EvidenceCategory::Sequence, 0.15),
+                (EvidenceCategory::Pathway, 0.15),
+                (EvidenceCategory::Literature, 0.15),
+            ]),
+            confidence_floor: 0.6,
+        }
+    }
+}
+
+impl CoverageAnalyzer {
+    /// Set the weight of a category
+    pub fn with_weight(mut self, category: EvidenceCategory, weight: f64) -> Self {
+        self.weights.insert(category, weight.max(0.0));
+        self
+    }
+
+    /// Set the fraction of confidence kept when no category is covered
+    pub fn with_confidence_floor(mut self, confidence_floor: f64) -> Self {
+        self.confidence_floor = confidence_floor.clamp(0.0, 1.0);
+        self
+    }
+
+    fn weight(&self, category: EvidenceCategory) -> f64 {
+        self.weights.get(&category).copied().unwrap_or(0.0)
+    }
+
+    /// Report which categories the given evidence covers
+    pub fn analyze(&self, categories: &[EvidenceCategory]) -> CoverageReport {
+        let mut counts = HashMap::new();
+        for category in categories {
+            *counts.entry(*category).or_insert(0) += 1;
+        }
+
+        let (present, missing): (Vec<_>, Vec<_>) = EvidenceCategory::ALL.iter()
+            .partition(|c| counts.contains_key(*c));
+        let total: f64 = EvidenceCategory::ALL.iter().map(|c| self.weight(*c)).sum();
+        let covered: f64 = present.iter().map(|c| self.weight(*c)).sum();
+        let score = if total > 0.0 { covered / total } else { 0.0 };
+
+        let mut suggestions: Vec<EvidenceSuggestion> = missing.iter()
+            .map(|c| EvidenceSuggestion {
+                category: *c,
+                score_gain: if total > 0.0 { self.weight(*c) / total } else { 0.0 },
+                rationale: c.acquisition_hint().to_string(),
+            })
+            .collect();
+        suggestions.sort_by(|a, b| b.score_gain.total_cmp(&a.score_gain));
+
+        debug!("Evidence coverage {:.2}: present {:?}, missing {:?}", score, present, missing);
+        CoverageReport {
+            present,
+            missing,
+            counts,
+            score,
+            suggestions,
+        }
+    }
+
+    /// Scale a confidence by coverage; full coverage leaves it unchanged
+    pub fn modulate(&self, confidence: f64, report: &CoverageReport) -> f64 {
+        confidence * (self.confidence_floor + (1.0 - self.confidence_floor) * report.score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage_gaps() {
+        let analyzer = CoverageAnalyzer::default();
+        let categories = [
+            EvidenceCategory::classify(EvidenceType::MassSpec, "orbitrap").unwrap(),
+            EvidenceCategory::classify(EvidenceType::Other, "in-house NMR").unwrap(),
+            EvidenceCategory::classify(EvidenceType::Reactome, "reactome").unwrap(),
+        ];
+        assert_eq!(EvidenceCategory::classify(EvidenceType::Other, "vendor"), None);
+
+        let report = analyzer.analyze(&categories);
+        assert_eq!(report.present, vec![EvidenceCategory::Spectral, EvidenceCategory::Pathway]);
+        assert_eq!(report.counts[&EvidenceCategory::Spectral], 2);
+        assert!((report.score - 0.45).abs() < 1e-9);
+        assert_eq!(report.next_best().unwrap().category, EvidenceCategory::Structural);
+
+        let full = analyzer.analyze(&EvidenceCategory::ALL);
+        assert_eq!(analyzer.modulate(0.8, &full), 0.8);
+        assert!(analyzer.modulate(0.8, &report) < 0.8);
+    }
+}
@@ -4,12 +4,18 @@
 //! about molecular structures, properties, and identities.
 
 use anyhow::{Result, Context};
+use async_trait::async_trait;
 use log::{debug, info, warn};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use tokio::time::timeout;
 use std::time::Duration;
 
+use super::claims::{self, ArticleAbstract, CheckedClaim};
+use super::templates::TemplateSet;
+use crate::offline::{self, NetworkFeature};
+use crate::processing::formula::Formula;
+
 /// Initialize the LLM module
 pub fn initialize() -> Result<()> {
     info!("Initializing LLM integration module");
@@ -23,6 +29,16 @@ pub fn initialize() -> Result<()> {
     Ok(())
 }
 
+/// Language model that completes free-form prompts
+///
+/// Implemented by [`LLMInterface`]; code that only needs completions takes
+/// this trait so it can run against a stub.
+#[async_trait]
+pub trait CompletionModel: Send + Sync {
+    /// Send a prompt and return the model's answer
+    async fn complete_prompt(&self, prompt: &str) -> Result<String>;
+}
+
 /// Interface for interacting with Language Models
 #[derive(Debug, Clone)]
 pub struct LLMInterface {
@@ -99,11 +115,11 @@ impl LLMInterface {
              potential biological activity differences, and whether they could be considered the same entity.",
             molecule1.name.as_deref().unwrap_or("Unknown"),
             molecule1.smiles,
-            molecule1.formula.as_deref().unwrap_or("Unknown"),
+            molecule1.formula.as_ref().map_or_else(|| "Unknown".to_string(), Formula::hill),
             serde_json::to_string_pretty(&molecule1.properties).unwrap_or_else(|_| "{}".to_string()),
             molecule2.name.as_deref().unwrap_or("Unknown"),
             molecule2.smiles,
-            molecule2.formula.as_deref().unwrap_or("Unknown"),
+            molecule2.formula.as_ref().map_or_else(|| "Unknown".to_string(), Formula::hill),
             serde_json::to_string_pretty(&molecule2.properties).unwrap_or_else(|_| "{}".to_string()),
         );
         
@@ -125,6 +141,28 @@ impl LLMInterface {
         Ok(comparison)
     }
     
+    /// Extract claims about a molecule from an abstract and check them against its text
+    ///
+    /// `synonyms` are other names of the molecule; a claim counts as being
+    /// about it when its sentence contains the name or any synonym.
+    pub async fn extract_claims(
+        &self,
+        templates: &TemplateSet,
+        molecule: &str,
+        synonyms: &[String],
+        article: &ArticleAbstract,
+    ) -> Result<Vec<CheckedClaim>> {
+        debug!("Extracting claims about {} from PMID {}", molecule, article.pmid);
+        
+        let prompt = claims::claim_prompt(templates, molecule, synonyms, article)?;
+        let response = self.send_query(&prompt).await?;
+        let extracted = claims::parse_claims(&response)
+            .with_context(|| format!("Failed to extract claims from PMID {}", article.pmid))?;
+        
+        let names: Vec<String> = std::iter::once(molecule.to_string()).chain(synonyms.iter().cloned()).collect();
+        Ok(claims::check_claims(article, &names, extracted))
+    }
+    
     /// Prepare a prompt for querying about a molecule
     fn prepare_molecule_prompt(&self, molecule: &MoleculeData, question: &str) -> String {
         format!(
@@ -135,7 +173,7 @@ impl LLMInterface {
              Provide a concise, accurate, and scientific answer based on the given information.",
             molecule.name.as_deref().unwrap_or("Unknown"),
             molecule.smiles,
-            molecule.formula.as_deref().unwrap_or("Unknown"),
+            molecule.formula.as_ref().map_or_else(|| "Unknown".to_string(), Formula::hill),
             serde_json::to_string_pretty(&molecule.properties).unwrap_or_else(|_| "{}".to_string()),
             question
         )
@@ -143,6 +181,8 @@ impl LLMInterface {
     
     /// Send a query to the LLM service
     async fn send_query(&self, prompt: &str) -> Result<String> {
+        offline::ensure_available(NetworkFeature::Llm)?;
+        
         // Check if API key is available
         let api_key = self.api_key.as_ref()
             .context("LLM API key not set")?;
@@ -193,6 +233,13 @@ impl LLMInterface {
     }
 }
 
+#[async_trait]
+impl CompletionModel for LLMInterface {
+    async fn complete_prompt(&self, prompt: &str) -> Result<String> {
+        self.send_query(prompt).await
+    }
+}
+
 /// Data about a molecule to be sent to the LLM
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoleculeData {
@@ -206,7 +253,7 @@ pub struct MoleculeData {
     pub name: Option<String>,
     
     /// Optional molecular formula
-    pub formula: Option<String>,
+    pub formula: Option<Formula>,
     
     /// Additional properties and metadata
     pub properties: HashMap<String, serde_json::Value>,
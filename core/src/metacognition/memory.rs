@@ -72,7 +72,7 @@ impl MemorySystem {
         // Check in-memory cache first
         {
             let mut cache = self.context_cache.lock().unwrap();
-            if let Some(context) = cache.get(context_id) {
+            if let Some(context) = cache.get(&context_id.to_string()) {
                 return Ok(Some(context.clone()));
             }
         }
@@ -7,14 +7,26 @@
 use anyhow::Result;
 use log::{info, debug};
 use crate::HegelError;
+use crate::cancellation::CancellationToken;
 use crate::Molecule;
 use crate::MolecularEvidence;
+use crate::processing::attribution::{self, ConfidenceDecomposition};
+use crate::processing::evidence::EvidenceType;
+use crate::processing::versioning::{ConfidenceTrend, ConfidenceTrigger, VersionedEvidenceStore};
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 pub mod molecule_processor;
 pub mod decision;
 pub mod llm;
+pub mod claims;
 pub mod memory;
+pub mod policy;
+pub mod coverage;
+pub mod planner;
+pub mod resolution;
+pub mod templates;
+pub mod sidecar;
 
 /// Initialize the metacognition module
 pub fn initialize() -> Result<()> {
@@ -23,7 +35,14 @@ pub fn initialize() -> Result<()> {
     // Initialize submodules
     decision::initialize()?;
     llm::initialize()?;
+    claims::initialize()?;
     memory::initialize()?;
+    policy::initialize()?;
+    coverage::initialize()?;
+    planner::initialize()?;
+    resolution::initialize()?;
+    templates::initialize()?;
+    sidecar::initialize()?;
     
     info!("Metacognition module initialized successfully");
     Ok(())
@@ -42,6 +61,18 @@ pub struct MetacognitionSystem {
     
     /// Molecule processor for data retrieval and integration
     molecule_processor: molecule_processor::MoleculeProcessor,
+    
+    /// Policy deciding whether an identity is accepted
+    identity_policy: policy::IdentityPolicy,
+    
+    /// Last identity decision per molecule, for hysteresis
+    identity_states: Mutex<HashMap<String, bool>>,
+    
+    /// Confidence of each validation per molecule, for the trend
+    confidence_history: Mutex<VersionedEvidenceStore>,
+    
+    /// Analyzer scaling confidence by evidence coverage
+    coverage_analyzer: coverage::CoverageAnalyzer,
 }
 
 impl MetacognitionSystem {
@@ -51,28 +82,48 @@ impl MetacognitionSystem {
         let llm_interface = llm::LLMInterface::new()?;
         let memory_system = memory::MemorySystem::new()?;
         
-        let python_api_endpoint = std::env::var("HEGEL_PYTHON_API_ENDPOINT")
-            .unwrap_or_else(|_| "http://localhost:8000".to_string());
-        
+        let sidecar_config = sidecar::SidecarConfig::from_env()?;
         let molecule_processor = molecule_processor::MoleculeProcessor::new(
             decision_engine.clone(),
             llm_interface.clone(),
-            python_api_endpoint,
-        );
+            sidecar_config.connect()?,
+        ).with_timeout(sidecar_config.timeout);
+        let molecule_processor = match std::env::var("HEGEL_BATCH_PARALLELISM") {
+            Ok(value) => molecule_processor.with_batch_parallelism(value.parse()
+                .map_err(|_| anyhow::anyhow!("Invalid HEGEL_BATCH_PARALLELISM value: {}", value))?),
+            Err(_) => molecule_processor,
+        };
         
         Ok(Self {
             decision_engine,
             llm_interface,
             memory_system,
             molecule_processor,
+            identity_policy: policy::IdentityPolicy::from_env()?,
+            identity_states: Mutex::new(HashMap::new()),
+            confidence_history: Mutex::new(VersionedEvidenceStore::new()),
+            coverage_analyzer: coverage::CoverageAnalyzer::default(),
         })
     }
     
+    /// Use a different identity policy
+    pub fn with_identity_policy(mut self, identity_policy: policy::IdentityPolicy) -> Self {
+        self.identity_policy = identity_policy;
+        self
+    }
+    
+    /// Use a different evidence coverage analyzer
+    pub fn with_coverage_analyzer(mut self, coverage_analyzer: coverage::CoverageAnalyzer) -> Self {
+        self.coverage_analyzer = coverage_analyzer;
+        self
+    }
+    
     /// Process a molecule and make decisions about its identity
     pub async fn process_molecule(
         &self,
         identifier: &str,
         id_type: molecule_processor::MoleculeIdType,
+        cancel: &CancellationToken,
     ) -> Result<molecule_processor::MoleculeResponse> {
         // Create a new context for this processing session
         let mut context = memory::context::Context::new();
@@ -89,7 +140,7 @@ impl MetacognitionSystem {
         };
         
         // Process the molecule
-        let response = self.molecule_processor.process_molecule(request, &mut context).await?;
+        let response = self.molecule_processor.process_molecule(request, &mut context, cancel).await?;
         
         // Store the context for future reference
         self.memory_system.store_context(context)?;
@@ -115,30 +166,106 @@ impl MetacognitionSystem {
         };
         
         // Get sources that validate this molecule
-        let sources = if let Some(sources_array) = evidence.get("sources") {
-            sources_array.as_array()
-                .map(|arr| arr.len())
-                .unwrap_or(0)
-        } else {
-            0
+        let source_list = evidence.get("sources")
+            .and_then(|s| s.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let sources = source_list.len();
+        let classified: Vec<(EvidenceType, String)> = source_list.iter()
+            .map(source_evidence)
+            .collect();
+        let evidence_types: Vec<EvidenceType> = classified.iter().map(|(t, _)| *t).collect();
+        
+        // Calculate confidence based on number of confirming sources and properties,
+        // scaled down when the sources all come from the same few kinds of evidence
+        let categories: Vec<coverage::EvidenceCategory> = classified.iter()
+            .filter_map(|(t, source)| coverage::EvidenceCategory::classify(*t, source))
+            .collect();
+        let coverage = self.coverage_analyzer.analyze(&categories);
+        let confidence = self.coverage_analyzer.modulate(calculate_confidence(sources, &properties), &coverage);
+        let contributions = self.decompose_confidence(&classified, &properties)?;
+        
+        // Determine if the molecule is valid, taking its previous state into account
+        let decision = {
+            let mut states = self.identity_states.lock().unwrap();
+            let previously_valid = states.get(molecule_id).copied().unwrap_or(false);
+            let decision = self.identity_policy.evaluate(confidence, &evidence_types, previously_valid);
+            states.insert(molecule_id.to_string(), decision.is_valid);
+            decision
         };
         
-        // Calculate confidence based on number of confirming sources and properties
-        let confidence = calculate_confidence(sources, &properties);
+        let trend = {
+            let mut history = self.confidence_history.lock().unwrap();
+            history.record_confidence(molecule_id, confidence, ConfidenceTrigger::Validation);
+            history.confidence_trend(molecule_id)
+        };
         
-        // Determine if the molecule is valid
-        let is_valid = confidence > 0.5;
+        let mut explanation = format!(
+            "Molecule {} with {:.1}% confidence based on {} sources (threshold {:.1}%)",
+            if decision.is_valid { "validated" } else { "not validated" },
+            confidence * 100.0,
+            sources,
+            decision.threshold * 100.0
+        );
+        if !decision.unmet_requirements.is_empty() {
+            explanation.push_str(&format!("; missing {}", decision.unmet_requirements.join(", ")));
+        }
+        explanation.push_str(&format!("; evidence coverage {:.0}%", coverage.score * 100.0));
+        if let Some(next) = coverage.next_best() {
+            explanation.push_str(&format!(", next best: {} evidence", next.category));
+        }
+        if let Some(trend) = trend {
+            explanation.push_str(&format!("; confidence {}", trend));
+        }
         
         Ok(ValidationResult {
             molecule_id: molecule_id.to_string(),
-            is_valid,
+            is_valid: decision.is_valid,
             confidence,
             evidence: evidence.clone(),
-            explanation: format!(
-                "Molecule validated with {:.1}% confidence based on {} sources",
-                confidence * 100.0,
-                sources
-            ),
+            explanation,
+            unmet_requirements: decision.unmet_requirements,
+            coverage: Some(coverage),
+            trend,
+            contributions: Some(contributions),
+        })
+    }
+    
+    /// Attribute a validation confidence to each source and to the molecule's properties
+    fn decompose_confidence(
+        &self,
+        classified: &[(EvidenceType, String)],
+        properties: &serde_json::Value,
+    ) -> Result<ConfidenceDecomposition> {
+        let mut contributors: Vec<attribution::Contributor> = Vec::new();
+        let mut members: Vec<Vec<usize>> = Vec::new();
+        for (idx, (_, source)) in classified.iter().enumerate() {
+            match contributors.iter().position(|(key, _)| key == source) {
+                Some(existing) => members[existing].push(idx),
+                None => {
+                    contributors.push((source.clone(), Vec::new()));
+                    members.push(vec![idx]);
+                }
+            }
+        }
+        // The properties count as one more contributor, after the sources
+        let properties_index = contributors.len();
+        if properties.as_object().is_some_and(|props| !props.is_empty()) {
+            contributors.push(("properties".to_string(), Vec::new()));
+        }
+        let no_properties = serde_json::Value::Object(serde_json::Map::new());
+        
+        attribution::decompose(contributors, |included| {
+            let sources: Vec<usize> = included.iter()
+                .filter(|group| **group < properties_index)
+                .flat_map(|group| members[*group].iter().copied())
+                .collect();
+            let categories: Vec<coverage::EvidenceCategory> = sources.iter()
+                .filter_map(|idx| coverage::EvidenceCategory::classify(classified[*idx].0, &classified[*idx].1))
+                .collect();
+            let properties = if included.contains(&properties_index) { properties } else { &no_properties };
+            let coverage = self.coverage_analyzer.analyze(&categories);
+            Ok(self.coverage_analyzer.modulate(calculate_confidence(sources.len(), properties), &coverage))
         })
     }
 }
@@ -160,6 +287,39 @@ pub struct ValidationResult {
     
     /// Human-readable explanation of the validation result
     pub explanation: String,
+    
+    /// Evidence requirements of the identity policy that were not met
+    #[serde(default)]
+    pub unmet_requirements: Vec<String>,
+    
+    /// Coverage of the evidence across categories, with suggested next evidence
+    #[serde(default)]
+    pub coverage: Option<coverage::CoverageReport>,
+    
+    /// Direction the molecule's confidence has been moving in over repeated validations
+    #[serde(default)]
+    pub trend: Option<ConfidenceTrend>,
+    
+    /// Confidence broken down into the contributions of each source and the properties
+    #[serde(default)]
+    pub contributions: Option<ConfidenceDecomposition>,
+}
+
+/// Evidence type and source name of an entry in an evidence summary's source list
+///
+/// Entries are either source names or objects with a `source` and a `type` or
+/// `evidence_type` field; unrecognised types count as `Other`.
+fn source_evidence(source: &serde_json::Value) -> (EvidenceType, String) {
+    let name = source.as_str()
+        .or_else(|| source.get("source").and_then(|s| s.as_str()))
+        .unwrap_or_default();
+    let evidence_type = source.get("evidence_type")
+        .or_else(|| source.get("type"))
+        .and_then(|t| t.as_str())
+        .or(source.as_str())
+        .and_then(|t| t.parse().ok())
+        .unwrap_or(EvidenceType::Other);
+    (evidence_type, name.to_string())
 }
 
 /// Calculate confidence in a molecule's identity based on evidence
@@ -199,49 +359,39 @@ fn calculate_confidence(sources: usize, properties: &serde_json::Value) -> f64 {
 pub struct MetacognitiveSystem {
     llm_endpoint: String,
     confidence_threshold: f64,
-    reasoning_templates: HashMap<String, String>,
+    templates: templates::TemplateSet,
+    locale: Option<String>,
+    resolution_policy: resolution::ResolutionPolicy,
 }
 
 impl MetacognitiveSystem {
     /// Create a new MetacognitiveSystem
     pub fn new(llm_endpoint: &str, confidence_threshold: f64) -> Self {
-        let mut system = MetacognitiveSystem {
+        MetacognitiveSystem {
             llm_endpoint: llm_endpoint.to_string(),
             confidence_threshold,
-            reasoning_templates: HashMap::new(),
-        };
-        
-        // Initialize reasoning templates
-        system.initialize_templates();
-        
-        system
+            templates: templates::TemplateSet::builtin(),
+            locale: None,
+            resolution_policy: resolution::ResolutionPolicy::default(),
+        }
     }
     
-    /// Initialize reasoning templates for different evidence types
-    fn initialize_templates(&mut self) {
-        // Template for spectral evidence conflict
-        self.reasoning_templates.insert(
-            "spectral_conflict".to_string(),
-            "Given the following spectral evidence:\n\n{evidence}\n\nReason about the conflicting identifications:".to_string()
-        );
-        
-        // Template for sequence evidence conflict
-        self.reasoning_templates.insert(
-            "sequence_conflict".to_string(),
-            "Given the following sequence evidence:\n\n{evidence}\n\nReason about the conflicting identifications:".to_string()
-        );
-        
-        // Template for pathway evidence conflict
-        self.reasoning_templates.insert(
-            "pathway_conflict".to_string(),
-            "Given the following pathway evidence:\n\n{evidence}\n\nReason about whether this molecule is part of the pathway:".to_string()
-        );
-        
-        // Template for multi-evidence integration
-        self.reasoning_templates.insert(
-            "evidence_integration".to_string(),
-            "Integrate the following evidence sources:\n\n{evidence}\n\nProvide a justified conclusion about the molecule's identity:".to_string()
-        );
+    /// Use a deployment's reasoning templates instead of the built-in ones
+    pub fn with_templates(mut self, templates: templates::TemplateSet) -> Self {
+        self.templates = templates;
+        self
+    }
+    
+    /// Render prompts and explanations in a locale, where a variant exists
+    pub fn with_locale(mut self, locale: &str) -> Self {
+        self.locale = Some(locale.to_string());
+        self
+    }
+    
+    /// Choose conflict resolution strategies per conflict class
+    pub fn with_resolution_policy(mut self, policy: resolution::ResolutionPolicy) -> Self {
+        self.resolution_policy = policy;
+        self
     }
     
     /// Evaluate confidence in molecule identification
@@ -273,81 +423,52 @@ impl MetacognitiveSystem {
         confidence_diff > 0.3
     }
     
-    /// Rectify conflicting evidence using LLM
+    /// Resolve conflicting evidence with the strategy the policy chooses for each conflict's class
+    ///
+    /// Escalated conflicts are returned unchanged; check `needs_review` on the results.
     pub fn rectify_conflicts(
         &self,
         molecule: &mut Molecule,
         conflicts: &[(usize, usize)],
-    ) -> Result<(), HegelError> {
-        if conflicts.is_empty() {
-            return Ok(());
-        }
-        
-        // For each conflict, apply reasoning
+    ) -> Result<Vec<resolution::ConflictResolution>, HegelError> {
+        let mut resolutions = Vec::new();
         for &(i, j) in conflicts {
             if i < molecule.evidences.len() && j < molecule.evidences.len() {
-                self.resolve_conflict(molecule, i, j)?;
+                resolutions.push(resolution::resolve(&mut molecule.evidences, i, j, &self.resolution_policy));
             }
         }
         
-        Ok(())
+        Ok(resolutions)
     }
     
-    /// Resolve a specific conflict between two pieces of evidence
-    fn resolve_conflict(
+    /// Render a reasoning template for a molecule in the configured locale
+    ///
+    /// See `templates::ReasoningContext` for the variables a template can use.
+    pub fn render_template(
         &self,
-        molecule: &mut Molecule,
-        evidence1_idx: usize,
-        evidence2_idx: usize,
-    ) -> Result<(), HegelError> {
-        // In a real implementation, this would:
-        // 1. Format evidence for LLM input
-        // 2. Call the LLM with appropriate prompting
-        // 3. Parse the response to update confidence scores
-        
-        // For demonstration, adjust confidence of the lower-confidence evidence
-        if evidence1_idx < molecule.evidences.len() && evidence2_idx < molecule.evidences.len() {
-            if molecule.evidences[evidence1_idx].confidence < molecule.evidences[evidence2_idx].confidence {
-                molecule.evidences[evidence1_idx].confidence *= 0.8;
-            } else {
-                molecule.evidences[evidence2_idx].confidence *= 0.8;
-            }
-        }
-        
-        Ok(())
+        name: &str,
+        molecule: &Molecule,
+        resolutions: &[resolution::ConflictResolution],
+    ) -> Result<String, HegelError> {
+        let context = templates::ReasoningContext::new(molecule, resolutions);
+        self.templates.render(name, self.locale.as_deref(), &context)
+            .map_err(|e| HegelError::ComputationError(e.to_string()))
     }
     
     /// Generate explanation for evidence rectification
-    pub fn generate_explanation(&self, molecule: &Molecule) -> Result<String, HegelError> {
-        // Format evidence for explanation template
-        let evidence_str = molecule.evidences.iter()
-            .map(|e| format!("- {}: {} (confidence: {:.2})", e.source, e.value, e.confidence))
-            .collect::<Vec<String>>()
-            .join("\n");
-        
+    ///
+    /// Each resolved conflict is described with the strategy chosen for it.
+    pub fn generate_explanation(
+        &self,
+        molecule: &Molecule,
+        resolutions: &[resolution::ConflictResolution],
+    ) -> Result<String, HegelError> {
         // Create prompt from template
-        let template = self.reasoning_templates.get("evidence_integration")
-            .ok_or_else(|| HegelError::ComputationError("Template not found".to_string()))?;
+        let prompt = self.render_template("evidence_integration", molecule, resolutions)?;
+        debug!("Explanation prompt for {}: {} characters", molecule.display_name(), prompt.len());
         
-        let prompt = template.replace("{evidence}", &evidence_str);
-        
-        // In a real implementation, this would call the LLM
-        // For demonstration, return a mock explanation
-        let explanation = format!(
-            "Based on analysis of the evidence for {}, the molecule is identified with {:.2}% confidence. \
-             The most reliable evidence comes from {}.", 
-            molecule.name, 
-            molecule.confidence_score * 100.0,
-            self.get_strongest_evidence(molecule).unwrap_or("unknown source")
-        );
-        
-        Ok(explanation)
-    }
-    
-    /// Get the strongest evidence source
-    fn get_strongest_evidence(&self, molecule: &Molecule) -> Option<String> {
-        molecule.evidences.iter()
-            .max_by(|a, b| a.confidence.partial_cmp(&b.confidence).unwrap())
-            .map(|e| e.source.clone())
+        // In a real implementation, this would send the prompt to the LLM
+        // For demonstration, render the explanation template directly
+        self.render_template("explanation", molecule, resolutions)
     }
 }
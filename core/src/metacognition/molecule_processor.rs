@@ -1,12 +1,18 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
+use log::info;
 use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use crate::cancellation::{self, CancellationToken};
 use crate::memory::context::Context as HegelContext;
 use crate::metacognition::decision::{Decision, DecisionEngine, DecisionFactor};
 use crate::metacognition::llm::LLMInterface;
+use crate::metacognition::sidecar::{Deadline, NeighborhoodQuery, RetrieveRequest, SidecarClient, DEFAULT_TIMEOUT};
+use crate::offline::{self, NetworkFeature};
 
 /// The set of data sources that can be queried
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,43 +50,7 @@ impl DataSource {
     }
 }
 
-/// Molecule identifier types
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum MoleculeIdType {
-    InChIKey,
-    InChI,
-    SMILES,
-    Name,
-    Formula,
-    CAS,
-    PubChemCID,
-    ChEMBLID,
-    KEGGID,
-    HMDBID,
-    DrugBankID,
-    ChEBIID,
-    Custom(String),
-}
-
-impl MoleculeIdType {
-    pub fn to_string(&self) -> String {
-        match self {
-            MoleculeIdType::InChIKey => "inchikey".to_string(),
-            MoleculeIdType::InChI => "inchi".to_string(),
-            MoleculeIdType::SMILES => "smiles".to_string(),
-            MoleculeIdType::Name => "name".to_string(),
-            MoleculeIdType::Formula => "formula".to_string(),
-            MoleculeIdType::CAS => "cas".to_string(),
-            MoleculeIdType::PubChemCID => "pubchem_cid".to_string(),
-            MoleculeIdType::ChEMBLID => "chembl_id".to_string(),
-            MoleculeIdType::KEGGID => "kegg_id".to_string(),
-            MoleculeIdType::HMDBID => "hmdb_id".to_string(),
-            MoleculeIdType::DrugBankID => "drugbank_id".to_string(),
-            MoleculeIdType::ChEBIID => "chebi_id".to_string(),
-            MoleculeIdType::Custom(name) => name.clone(),
-        }
-    }
-}
+pub use crate::identity::MoleculeIdType;
 
 /// Molecule retrieval request
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,66 +73,178 @@ pub struct MoleculeResponse {
     pub error: Option<String>,
     pub sources_queried: Vec<String>,
     pub processing_time_ms: u64,
+    /// Whether the request was cancelled; the other fields hold what was done until then
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Time spent in a batch waiting for a free slot before processing started
+    #[serde(default)]
+    pub queued_ms: u64,
+}
+
+/// Number of molecules a batch processes at once unless configured
+pub const DEFAULT_BATCH_PARALLELISM: usize = 4;
+
+/// A batch request that did not succeed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchFailure {
+    /// Position of the request in the batch
+    pub index: usize,
+    /// Identifier of the requested molecule
+    pub identifier: String,
+    /// Why it failed
+    pub error: String,
+}
+
+/// Responses to a batch of molecule requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResponse {
+    /// One response per request, in request order
+    pub responses: Vec<MoleculeResponse>,
+    /// Number of requests processed successfully
+    pub succeeded: usize,
+    /// Requests that failed, in request order
+    pub failures: Vec<BatchFailure>,
+    /// Number of requests cancelled before they finished
+    pub cancelled: usize,
+    /// Molecules processed at once
+    pub parallelism: usize,
+    /// Wall-clock time for the whole batch
+    pub total_time_ms: u64,
+}
+
+impl BatchResponse {
+    /// Tally responses given with the identifiers they were requested for
+    pub fn from_responses(items: Vec<(String, MoleculeResponse)>, parallelism: usize, total_time_ms: u64) -> Self {
+        let mut failures = Vec::new();
+        let mut responses = Vec::with_capacity(items.len());
+        for (index, (identifier, response)) in items.into_iter().enumerate() {
+            if !response.success && !response.cancelled {
+                failures.push(BatchFailure {
+                    index,
+                    identifier,
+                    error: response.error.clone().unwrap_or_else(|| "Unknown error".to_string()),
+                });
+            }
+            responses.push(response);
+        }
+        
+        Self {
+            succeeded: responses.iter().filter(|r| r.success).count(),
+            cancelled: responses.iter().filter(|r| r.cancelled).count(),
+            failures,
+            responses,
+            parallelism,
+            total_time_ms,
+        }
+    }
 }
 
 /// Molecule processor orchestrates the retrieval and integration of molecular data
 pub struct MoleculeProcessor {
     decision_engine: DecisionEngine,
     llm_interface: LLMInterface,
-    python_api_endpoint: String,
+    sidecar: Arc<dyn SidecarClient>,
+    timeout: Duration,
+    batch_parallelism: usize,
 }
 
 impl MoleculeProcessor {
-    pub fn new(decision_engine: DecisionEngine, llm_interface: LLMInterface, api_endpoint: String) -> Self {
+    pub fn new(decision_engine: DecisionEngine, llm_interface: LLMInterface, sidecar: Arc<dyn SidecarClient>) -> Self {
         Self {
             decision_engine,
             llm_interface,
-            python_api_endpoint: api_endpoint,
+            sidecar,
+            timeout: DEFAULT_TIMEOUT,
+            batch_parallelism: DEFAULT_BATCH_PARALLELISM,
         }
     }
     
+    /// Time allowed for each request to the sidecar, shared by the calls it makes
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// Number of molecules a batch processes at once (at least one)
+    pub fn with_batch_parallelism(mut self, parallelism: usize) -> Self {
+        self.batch_parallelism = parallelism.max(1);
+        self
+    }
+    
     /// Process a molecule request by retrieving data from multiple sources and building
     /// the molecule network
-    pub async fn process_molecule(&self, request: MoleculeRequest, context: &mut HegelContext) -> Result<MoleculeResponse> {
-        let start_time = std::time::Instant::now();
-        
+    ///
+    /// Cancelling `cancel` aborts the step in progress; the response is then
+    /// marked as cancelled and keeps whatever was retrieved before.
+    pub async fn process_molecule(&self, request: MoleculeRequest, context: &mut HegelContext, cancel: &CancellationToken) -> Result<MoleculeResponse> {
+        offline::ensure_available(NetworkFeature::PythonApi)?;
+        let start_time = Instant::now();
+        let deadline = Deadline::after(self.timeout);
+        
+        let mut response = MoleculeResponse {
+            success: false,
+            molecule_id: None,
+            data: None,
+            error: None,
+            sources_queried: Vec::new(),
+            processing_time_ms: 0,
+            cancelled: false,
+            queued_ms: 0,
+        };
+        let outcome = self.run_processing_steps(&request, context, deadline, cancel, &mut response).await;
+        response.processing_time_ms = start_time.elapsed().as_millis() as u64;
+        
+        match outcome {
+            Ok(()) => Ok(response),
+            Err(e) if cancellation::is_cancelled(&e) => {
+                info!("Processing of molecule {} cancelled: {}", request.identifier, e);
+                response.success = false;
+                response.cancelled = true;
+                response.error = Some(e.to_string());
+                Ok(response)
+            }
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Run each processing step, recording its output in `response` as it completes
+    async fn run_processing_steps(
+        &self,
+        request: &MoleculeRequest,
+        context: &mut HegelContext,
+        deadline: Deadline,
+        cancel: &CancellationToken,
+        response: &mut MoleculeResponse,
+    ) -> Result<()> {
         // Determine optimal sources to query based on the molecule type and ID
-        let sources = self.determine_data_sources(&request, context).await?;
-        
-        // Collect sources as strings for the response
-        let sources_queried: Vec<String> = sources.iter().map(|s| s.to_string()).collect();
+        let sources = cancellation::run(cancel, "Data source selection",
+            self.determine_data_sources(request, context)).await?;
+        response.sources_queried = sources.iter().map(|s| s.to_string()).collect();
         
         // Call the Python API to retrieve molecule data
-        let molecule_data = self.retrieve_molecule_data(&request, &sources).await
+        let molecule_data = cancellation::run(cancel, "Molecule retrieval",
+            self.retrieve_molecule_data(request, &sources, deadline)).await
             .context("Failed to retrieve molecule data")?;
         
         // Check if we got valid data
         if molecule_data.is_null() || !molecule_data.is_object() {
-            return Ok(MoleculeResponse {
-                success: false,
-                molecule_id: None,
-                data: None,
-                error: Some("No valid molecule data retrieved".to_string()),
-                sources_queried,
-                processing_time_ms: start_time.elapsed().as_millis() as u64,
-            });
+            response.error = Some("No valid molecule data retrieved".to_string());
+            return Ok(());
         }
+        response.data = Some(molecule_data.clone());
         
         // Add molecule to the network
-        let molecule_id = self.add_to_molecule_network(&molecule_data).await
+        let molecule_id = cancellation::run(cancel, "Molecule network update",
+            self.add_to_molecule_network(&molecule_data, deadline)).await
             .context("Failed to add molecule to network")?;
+        response.molecule_id = Some(molecule_id);
         
         // Extract context information from the molecule data to update the context
-        self.update_context_with_molecule(&molecule_data, context).await?;
+        cancellation::run(cancel, "Context update",
+            self.update_context_with_molecule(&molecule_data, context)).await?;
         
-        Ok(MoleculeResponse {
-            success: true,
-            molecule_id: Some(molecule_id),
-            data: Some(molecule_data.clone()),
-            error: None,
-            sources_queried,
-            processing_time_ms: start_time.elapsed().as_millis() as u64,
-        })
+        response.success = true;
+        Ok(())
     }
     
     /// Determine the most relevant data sources to query for a given molecule
@@ -232,76 +314,26 @@ impl MoleculeProcessor {
         Ok(sources)
     }
     
-    /// Retrieve molecule data from the Python API
-    async fn retrieve_molecule_data(&self, request: &MoleculeRequest, sources: &[DataSource]) -> Result<serde_json::Value> {
-        // Convert sources to strings
-        let source_strings: Vec<String> = sources.iter().map(|s| s.to_string()).collect();
-        
-        // Prepare the HTTP client
-        let client = reqwest::Client::new();
-        
-        // Prepare the request payload
-        let payload = serde_json::json!({
-            "identifier": request.identifier,
-            "id_type": request.id_type.to_string(),
-            "primary_source": request.primary_source.to_string(),
-            "include_sources": source_strings,
-            "include_pathways": request.include_pathways,
-            "include_interactions": request.include_interactions,
-            "include_targets": request.include_targets,
-        });
-        
-        // Call the Python API
-        let response = client.post(&format!("{}/api/molecules/retrieve", self.python_api_endpoint))
-            .json(&payload)
-            .timeout(Duration::from_secs(30))
-            .send()
-            .await
-            .context("Failed to send request to Python API")?;
-        
-        // Check response status
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow!("API request failed with status {}: {}", response.status(), error_text));
-        }
-        
-        // Parse response JSON
-        let data = response.json::<serde_json::Value>().await
-            .context("Failed to parse response JSON")?;
-        
-        Ok(data)
+    /// Retrieve molecule data through the sidecar
+    async fn retrieve_molecule_data(&self, request: &MoleculeRequest, sources: &[DataSource], deadline: Deadline) -> Result<serde_json::Value> {
+        let retrieve = RetrieveRequest {
+            identifier: request.identifier.clone(),
+            id_type: request.id_type.to_string(),
+            primary_source: request.primary_source.to_string(),
+            include_sources: sources.iter().map(|s| s.to_string()).collect(),
+            include_pathways: request.include_pathways,
+            include_interactions: request.include_interactions,
+            include_targets: request.include_targets,
+        };
+        
+        self.sidecar.retrieve_molecule(&retrieve, deadline).await
+            .with_context(|| format!("Failed to retrieve molecule over the {} sidecar protocol", self.sidecar.protocol()))
     }
     
     /// Add the molecule to the network database
-    async fn add_to_molecule_network(&self, molecule_data: &serde_json::Value) -> Result<String> {
-        // Prepare the HTTP client
-        let client = reqwest::Client::new();
-        
-        // Call the Python API to add the molecule to the network
-        let response = client.post(&format!("{}/api/molecules/network/add", self.python_api_endpoint))
-            .json(molecule_data)
-            .timeout(Duration::from_secs(30))
-            .send()
-            .await
-            .context("Failed to send request to add molecule to network")?;
-        
-        // Check response status
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow!("API request failed with status {}: {}", response.status(), error_text));
-        }
-        
-        // Parse response JSON
-        let data = response.json::<serde_json::Value>().await
-            .context("Failed to parse response JSON")?;
-        
-        // Extract the molecule ID
-        let molecule_id = data.get("id")
-            .and_then(|id| id.as_str())
-            .ok_or_else(|| anyhow!("No molecule ID in response"))?
-            .to_string();
-        
-        Ok(molecule_id)
+    async fn add_to_molecule_network(&self, molecule_data: &serde_json::Value, deadline: Deadline) -> Result<String> {
+        self.sidecar.add_to_network(molecule_data, deadline).await
+            .with_context(|| format!("Failed to add molecule to network over the {} sidecar protocol", self.sidecar.protocol()))
     }
     
     /// Update the context with information from the molecule
@@ -370,27 +402,10 @@ impl MoleculeProcessor {
     
     /// Get a summary of evidence for a molecule's identity
     pub async fn get_evidence_summary(&self, molecule_id: &str) -> Result<serde_json::Value> {
-        // Prepare the HTTP client
-        let client = reqwest::Client::new();
+        offline::ensure_available(NetworkFeature::PythonApi)?;
         
-        // Call the Python API to get evidence summary
-        let response = client.get(&format!("{}/api/molecules/{}/evidence", self.python_api_endpoint, molecule_id))
-            .timeout(Duration::from_secs(30))
-            .send()
-            .await
-            .context("Failed to send request for evidence summary")?;
-        
-        // Check response status
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow!("API request failed with status {}: {}", response.status(), error_text));
-        }
-        
-        // Parse response JSON
-        let data = response.json::<serde_json::Value>().await
-            .context("Failed to parse response JSON")?;
-        
-        Ok(data)
+        self.sidecar.get_evidence_summary(molecule_id, Deadline::after(self.timeout)).await
+            .context("Failed to get evidence summary")
     }
     
     /// Get the molecule network neighborhood
@@ -399,71 +414,116 @@ impl MoleculeProcessor {
                                          relationship_types: Option<Vec<String>>,
                                          max_depth: Option<u32>,
                                          limit: Option<u32>) -> Result<serde_json::Value> {
-        // Prepare the HTTP client
-        let client = reqwest::Client::new();
-        
-        // Prepare query parameters
-        let mut params = Vec::new();
-        
-        if let Some(rel_types) = &relationship_types {
-            for rel_type in rel_types {
-                params.push(("relationship_types", rel_type));
-            }
-        }
-        
-        if let Some(depth) = max_depth {
-            params.push(("max_depth", &depth.to_string()));
-        }
-        
-        if let Some(lim) = limit {
-            params.push(("limit", &lim.to_string()));
-        }
-        
-        // Call the Python API to get molecule neighborhood
-        let response = client.get(&format!("{}/api/molecules/{}/neighborhood", self.python_api_endpoint, molecule_id))
-            .query(&params)
-            .timeout(Duration::from_secs(30))
-            .send()
-            .await
-            .context("Failed to send request for molecule neighborhood")?;
-        
-        // Check response status
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow!("API request failed with status {}: {}", response.status(), error_text));
-        }
-        
-        // Parse response JSON
-        let data = response.json::<serde_json::Value>().await
-            .context("Failed to parse response JSON")?;
-        
-        Ok(data)
+        offline::ensure_available(NetworkFeature::PythonApi)?;
+        
+        let query = NeighborhoodQuery {
+            molecule_id: molecule_id.to_string(),
+            relationship_types,
+            max_depth,
+            limit,
+        };
+        self.sidecar.get_neighborhood(&query, Deadline::after(self.timeout)).await
+            .context("Failed to get molecule neighborhood")
     }
     
-    /// Process a batch of molecules
+    /// Process a batch of molecules, up to the batch parallelism at a time
+    ///
+    /// Responses come back in request order whatever order the molecules
+    /// finish in, and a failed request is reported in the batch without
+    /// failing the others. Each request works on its own copy of `context`;
+    /// afterwards `context` holds the copy of the last request that succeeded,
+    /// as it would after processing the batch one request at a time.
+    ///
+    /// Once `cancel` is cancelled, the remaining requests are answered as cancelled.
     pub async fn process_molecule_batch(&self, 
                                       requests: Vec<MoleculeRequest>,
-                                      context: &mut HegelContext) -> Result<Vec<MoleculeResponse>> {
-        let mut responses = Vec::with_capacity(requests.len());
-        
-        // Process each molecule request
-        for request in requests {
-            match self.process_molecule(request, context).await {
-                Ok(response) => responses.push(response),
-                Err(e) => {
-                    // Create an error response
-                    responses.push(MoleculeResponse {
+                                      context: &mut HegelContext,
+                                      cancel: &CancellationToken) -> Result<BatchResponse> {
+        let start_time = Instant::now();
+        let permits = Semaphore::new(self.batch_parallelism);
+        let base_context: &HegelContext = context;
+        
+        let items = requests.into_iter().map(|request| {
+            let permits = &permits;
+            let mut item_context = base_context.clone();
+            async move {
+                let queued_at = Instant::now();
+                let _permit = permits.acquire().await.context("Batch semaphore closed")?;
+                let queued_ms = queued_at.elapsed().as_millis() as u64;
+                
+                let started_at = Instant::now();
+                let identifier = request.identifier.clone();
+                let mut response = match self.process_molecule(request, &mut item_context, cancel).await {
+                    Ok(response) => response,
+                    Err(e) => MoleculeResponse {
                         success: false,
                         molecule_id: None,
                         data: None,
                         error: Some(format!("Failed to process molecule: {}", e)),
                         sources_queried: vec![],
-                        processing_time_ms: 0,
-                    });
-                }
+                        processing_time_ms: started_at.elapsed().as_millis() as u64,
+                        cancelled: false,
+                        queued_ms: 0,
+                    },
+                };
+                response.queued_ms = queued_ms;
+                Ok::<_, anyhow::Error>((identifier, response, item_context))
+            }
+        });
+        let outcomes = futures::future::join_all(items).await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        
+        let mut responses = Vec::with_capacity(outcomes.len());
+        for (identifier, response, item_context) in outcomes {
+            if response.success {
+                *context = item_context;
             }
+            responses.push((identifier, response));
         }
         
-        Ok(responses)
+        let batch = BatchResponse::from_responses(responses, self.batch_parallelism, start_time.elapsed().as_millis() as u64);
+        info!(
+            "Processed batch of {} molecules ({} at a time) in {}ms: {} succeeded, {} failed, {} cancelled",
+            batch.responses.len(), batch.parallelism, batch.total_time_ms,
+            batch.succeeded, batch.failures.len(), batch.cancelled
+        );
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn response(success: bool, error: Option<&str>, cancelled: bool) -> MoleculeResponse {
+        MoleculeResponse {
+            success,
+            molecule_id: success.then(|| "mol".to_string()),
+            data: None,
+            error: error.map(str::to_string),
+            sources_queried: vec!["pubchem".to_string()],
+            processing_time_ms: 5,
+            cancelled,
+            queued_ms: 0,
+        }
     }
-} 
\ No newline at end of file
+    
+    #[test]
+    fn test_batch_response_keeps_order_and_tallies_failures() {
+        let batch = BatchResponse::from_responses(vec![
+            ("caffeine".to_string(), response(true, None, false)),
+            ("unobtainium".to_string(), response(false, Some("No valid molecule data retrieved"), false)),
+            ("aspirin".to_string(), response(false, Some("Molecule retrieval was cancelled"), true)),
+            ("glucose".to_string(), response(true, None, false)),
+        ], 2, 40);
+        
+        assert_eq!(batch.responses.len(), 4);
+        assert!(batch.responses[3].success);
+        assert_eq!(batch.succeeded, 2);
+        assert_eq!(batch.cancelled, 1);
+        assert_eq!(batch.failures.len(), 1);
+        assert_eq!(batch.failures[0].index, 1);
+        assert_eq!(batch.failures[0].identifier, "unobtainium");
+    }
+}
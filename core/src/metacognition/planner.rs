@@ -0,0 +1,482 @@
+//! Evidence Acquisition Planner
+//!
+//! Recommends the next experiment for molecules whose identity is still
+//! uncertain. Each candidate experiment (MS/MS, NMR, injection of an
+//! authentic standard, a literature search) is simulated: the evidence it
+//! would produce if it confirmed the identity, and if it contradicted it, is
+//! run through the identity pipeline and scaled by the coverage the new
+//! evidence category would bring. Weighting the two outcomes by how likely
+//! each is gives the expected confidence after the experiment, and
+//! experiments are ranked by expected gain per unit of cost.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+
+use crate::processing::evidence::{Evidence, EvidenceType};
+use crate::processing::pipeline::IdentityPipeline;
+use super::coverage::{CoverageAnalyzer, EvidenceCategory};
+
+/// Source name given to the simulated evidence of a planned experiment
+pub const PLANNED_SOURCE_PREFIX: &str = "planned";
+
+/// Initialize the evidence acquisition planner module
+pub fn initialize() -> Result<()> {
+    info!("Initializing evidence acquisition planner module");
+    info!("Evidence acquisition planner module initialized successfully");
+    Ok(())
+}
+
+/// Experiment that can be run to gather more evidence about a molecule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Experiment {
+    /// Fragmentation spectrum matched against a spectral library
+    MsMs,
+    /// NMR of the isolated compound
+    Nmr,
+    /// Co-injection of an authentic reference standard
+    StandardInjection,
+    /// Search for prior reports of the molecule
+    LiteratureSearch,
+}
+
+impl Experiment {
+    /// All experiments, in reporting order
+    pub const ALL: [Experiment; 4] = [
+        Experiment::MsMs,
+        Experiment::Nmr,
+        Experiment::StandardInjection,
+        Experiment::LiteratureSearch,
+    ];
+
+    /// Type of the evidence the experiment produces
+    pub fn evidence_type(&self) -> EvidenceType {
+        match self {
+            Experiment::MsMs => EvidenceType::MassSpec,
+            Experiment::Nmr | Experiment::StandardInjection => EvidenceType::Structural,
+            Experiment::LiteratureSearch => EvidenceType::Literature,
+        }
+    }
+
+    /// Coverage category the experiment's evidence falls in
+    pub fn category(&self) -> EvidenceCategory {
+        EvidenceCategory::classify(self.evidence_type(), "").expect("experiment evidence types are classified")
+    }
+
+    /// Whether an evidence item already comes from this kind of experiment
+    ///
+    /// Recognized by evidence type and keywords in the source, as the
+    /// coverage analyzer does for evidence of type `Other`.
+    pub fn produced(&self, evidence: &Evidence) -> bool {
+        let source = evidence.source.to_lowercase();
+        let mentions = |keywords: &[&str]| keywords.iter().any(|k| source.contains(k));
+        match self {
+            Experiment::MsMs => mentions(&["ms2", "msms", "ms/ms", "fragment"]),
+            Experiment::Nmr => mentions(&["nmr"]),
+            Experiment::StandardInjection => mentions(&["standard"]),
+            Experiment::LiteratureSearch => evidence.evidence_type == EvidenceType::Literature
+                || mentions(&["pubmed", "literature", "publication"]),
+        }
+    }
+
+    /// What running the experiment involves
+    fn description(&self) -> &'static str {
+        match self {
+            Experiment::MsMs => "acquire an MS/MS spectrum and match it against a spectral library",
+            Experiment::Nmr => "isolate the compound and compare its NMR spectra with the candidate structure",
+            Experiment::StandardInjection => "co-inject an authentic standard and compare retention time and spectra",
+            Experiment::LiteratureSearch => "search the literature for reports of the molecule in this organism or matrix",
+        }
+    }
+}
+
+impl std::fmt::Display for Experiment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Experiment::MsMs => write!(f, "ms_ms"),
+            Experiment::Nmr => write!(f, "nmr"),
+            Experiment::StandardInjection => write!(f, "standard_injection"),
+            Experiment::LiteratureSearch => write!(f, "literature_search"),
+        }
+    }
+}
+
+impl std::str::FromStr for Experiment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ms_ms" | "msms" | "ms2" => Ok(Experiment::MsMs),
+            "nmr" => Ok(Experiment::Nmr),
+            "standard_injection" | "standard" => Ok(Experiment::StandardInjection),
+            "literature_search" | "literature" => Ok(Experiment::LiteratureSearch),
+            other => Err(anyhow!("Unknown experiment: {}", other)),
+        }
+    }
+}
+
+/// How an experiment behaves, for simulating its outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentModel {
+    /// Experiment modelled
+    pub experiment: Experiment,
+
+    /// Probability the experiment supports the identity when it is correct
+    pub sensitivity: f64,
+
+    /// Probability the experiment supports the identity when it is wrong
+    pub false_positive_rate: f64,
+
+    /// Confidence of the evidence produced when the experiment supports the identity
+    pub supporting_confidence: f64,
+
+    /// Confidence of the evidence produced when the experiment contradicts the identity
+    pub contradicting_confidence: f64,
+
+    /// Relative cost of running the experiment (time, material, instrument use)
+    pub cost: f64,
+}
+
+impl ExperimentModel {
+    /// Typical behaviour of an experiment
+    pub fn default_for(experiment: Experiment) -> Self {
+        let (sensitivity, false_positive_rate, supporting_confidence, cost) = match experiment {
+            Experiment::MsMs => (0.85, 0.20, 0.85, 2.0),
+            Experiment::Nmr => (0.90, 0.05, 0.92, 8.0),
+            Experiment::StandardInjection => (0.95, 0.02, 0.95, 5.0),
+            Experiment::LiteratureSearch => (0.60, 0.30, 0.70, 1.0),
+        };
+        Self {
+            experiment,
+            sensitivity,
+            false_positive_rate,
+            supporting_confidence,
+            contradicting_confidence: 1.0 - supporting_confidence,
+            cost,
+        }
+    }
+
+    /// Check the probabilities and confidences lie in 0 - 1 and the cost is positive
+    pub fn validate(&self) -> Result<()> {
+        let in_range = |value: f64| (0.0..=1.0).contains(&value);
+        if !(in_range(self.sensitivity) && in_range(self.false_positive_rate)
+            && in_range(self.supporting_confidence) && in_range(self.contradicting_confidence)) {
+            return Err(anyhow!("Probabilities and confidences of experiment {} must be between 0 and 1", self.experiment));
+        }
+        if self.cost.is_nan() || self.cost <= 0.0 {
+            return Err(anyhow!("Cost of experiment {} must be positive, got {}", self.experiment, self.cost));
+        }
+        Ok(())
+    }
+}
+
+/// Options of the acquisition planner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannerOptions {
+    /// Molecules at or above this confidence need no further evidence
+    pub confidence_threshold: f64,
+
+    /// Most experiments recommended per molecule
+    pub max_recommendations: usize,
+
+    /// Smallest expected confidence gain worth recommending
+    pub min_gain: f64,
+
+    /// Experiments the planner may recommend
+    pub experiments: Vec<ExperimentModel>,
+}
+
+impl Default for PlannerOptions {
+    fn default() -> Self {
+        Self {
+            confidence_threshold: 0.8,
+            max_recommendations: 3,
+            min_gain: 0.01,
+            experiments: Experiment::ALL.iter().map(|e| ExperimentModel::default_for(*e)).collect(),
+        }
+    }
+}
+
+impl PlannerOptions {
+    /// Set the confidence above which molecules are left alone
+    pub fn with_confidence_threshold(mut self, threshold: f64) -> Self {
+        self.confidence_threshold = threshold;
+        self
+    }
+
+    /// Set the most experiments recommended per molecule
+    pub fn with_max_recommendations(mut self, max: usize) -> Self {
+        self.max_recommendations = max;
+        self
+    }
+
+    /// Replace the model of an experiment
+    pub fn with_experiment(mut self, model: ExperimentModel) -> Self {
+        self.experiments.retain(|m| m.experiment != model.experiment);
+        self.experiments.push(model);
+        self
+    }
+
+    /// Check the threshold and every experiment model
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.confidence_threshold) {
+            return Err(anyhow!("Confidence threshold must be between 0 and 1, got {}", self.confidence_threshold));
+        }
+        if self.max_recommendations == 0 {
+            return Err(anyhow!("Planner must recommend at least one experiment"));
+        }
+        self.experiments.iter().try_for_each(ExperimentModel::validate)
+    }
+}
+
+/// Recommended experiment for a molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recommendation {
+    /// Experiment to run
+    pub experiment: Experiment,
+
+    /// Coverage category the experiment adds evidence to
+    pub category: EvidenceCategory,
+
+    /// Expected confidence after the experiment
+    pub expected_confidence: f64,
+
+    /// Expected confidence gain over the current confidence
+    pub expected_gain: f64,
+
+    /// Confidence if the experiment supports the identity
+    pub confidence_if_supported: f64,
+
+    /// Confidence if the experiment contradicts the identity
+    pub confidence_if_contradicted: f64,
+
+    /// Probability the experiment supports the identity
+    pub probability_supported: f64,
+
+    /// Relative cost of the experiment
+    pub cost: f64,
+
+    /// Expected gain per unit of cost, which recommendations are ranked by
+    pub value: f64,
+
+    /// What running the experiment involves
+    pub rationale: String,
+}
+
+/// Acquisition plan for one molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoleculePlan {
+    /// Molecule the plan is for
+    pub molecule_id: String,
+
+    /// Current confidence, scaled by evidence coverage
+    pub confidence: f64,
+
+    /// Current evidence coverage score
+    pub coverage: f64,
+
+    /// Experiments already reflected in the evidence, which are not recommended again
+    pub performed: Vec<Experiment>,
+
+    /// Recommended experiments, most valuable first
+    pub recommendations: Vec<Recommendation>,
+}
+
+impl MoleculePlan {
+    /// The single most valuable experiment, if any is worth running
+    pub fn next_best(&self) -> Option<&Recommendation> {
+        self.recommendations.first()
+    }
+}
+
+/// Acquisition plans for a set of molecules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcquisitionPlan {
+    /// Plans for molecules below the confidence threshold, the most valuable next experiment first
+    pub plans: Vec<MoleculePlan>,
+
+    /// Molecules already at or above the confidence threshold
+    pub confident: Vec<String>,
+}
+
+/// Planner recommending experiments by their expected confidence gain
+#[derive(Debug, Clone, Default)]
+pub struct AcquisitionPlanner {
+    /// Planner options
+    options: PlannerOptions,
+
+    /// Coverage analyzer scaling confidence, as in identity validation
+    coverage: CoverageAnalyzer,
+}
+
+impl AcquisitionPlanner {
+    /// Create a planner with the given options
+    pub fn new(options: PlannerOptions) -> Result<Self> {
+        options.validate()?;
+        Ok(Self { options, coverage: CoverageAnalyzer::default() })
+    }
+
+    /// Use a different coverage analyzer
+    pub fn with_coverage_analyzer(mut self, coverage: CoverageAnalyzer) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// Planner options
+    pub fn options(&self) -> &PlannerOptions {
+        &self.options
+    }
+
+    /// Confidence and coverage score of a set of evidence
+    fn assess(&self, pipeline: &IdentityPipeline, evidence: &[Evidence]) -> Result<(f64, f64)> {
+        let categories: Vec<EvidenceCategory> = evidence.iter()
+            .filter_map(|e| EvidenceCategory::classify(e.evidence_type, &e.source))
+            .collect();
+        let report = self.coverage.analyze(&categories);
+        let posterior = pipeline.posterior_confidence(evidence)?;
+        Ok((self.coverage.modulate(posterior, &report), report.score))
+    }
+
+    /// Plan the next experiments for one molecule, whatever its confidence
+    pub fn plan_molecule(&self, pipeline: &IdentityPipeline, molecule_id: &str, evidence: &[Evidence]) -> Result<MoleculePlan> {
+        let (confidence, coverage) = self.assess(pipeline, evidence)?;
+        let performed: Vec<Experiment> = Experiment::ALL.iter()
+            .copied()
+            .filter(|experiment| evidence.iter().any(|e| experiment.produced(e)))
+            .collect();
+
+        let mut recommendations = Vec::new();
+        for model in self.options.experiments.iter().filter(|m| !performed.contains(&m.experiment)) {
+            let outcome = |evidence_confidence: f64| -> Result<f64> {
+                let mut simulated = evidence.to_vec();
+                simulated.push(planned_evidence(molecule_id, model.experiment, evidence_confidence));
+                Ok(self.assess(pipeline, &simulated)?.0)
+            };
+            let if_supported = outcome(model.supporting_confidence)?;
+            let if_contradicted = outcome(model.contradicting_confidence)?;
+
+            // The current confidence stands in for the probability the identity is correct
+            let probability_supported = confidence * model.sensitivity + (1.0 - confidence) * model.false_positive_rate;
+            let expected_confidence = probability_supported * if_supported + (1.0 - probability_supported) * if_contradicted;
+            let expected_gain = expected_confidence - confidence;
+            if expected_gain < self.options.min_gain {
+                continue;
+            }
+            recommendations.push(Recommendation {
+                experiment: model.experiment,
+                category: model.experiment.category(),
+                expected_confidence,
+                expected_gain,
+                confidence_if_supported: if_supported,
+                confidence_if_contradicted: if_contradicted,
+                probability_supported,
+                cost: model.cost,
+                value: expected_gain / model.cost,
+                rationale: model.experiment.description().to_string(),
+            });
+        }
+        recommendations.sort_by(|a, b| b.value.total_cmp(&a.value).then_with(|| a.experiment.cmp(&b.experiment)));
+        recommendations.truncate(self.options.max_recommendations);
+
+        debug!("Planned {} experiments for {} at confidence {:.2}", recommendations.len(), molecule_id, confidence);
+        Ok(MoleculePlan {
+            molecule_id: molecule_id.to_string(),
+            confidence,
+            coverage,
+            performed,
+            recommendations,
+        })
+    }
+
+    /// Plan experiments for every molecule below the confidence threshold
+    pub fn plan(&self, pipeline: &IdentityPipeline, molecules: &BTreeMap<String, Vec<Evidence>>) -> Result<AcquisitionPlan> {
+        let mut plans = Vec::new();
+        let mut confident = Vec::new();
+        for (molecule_id, evidence) in molecules {
+            let plan = self.plan_molecule(pipeline, molecule_id, evidence)?;
+            if plan.confidence >= self.options.confidence_threshold {
+                confident.push(molecule_id.clone());
+            } else {
+                plans.push(plan);
+            }
+        }
+        let best_value = |plan: &MoleculePlan| plan.next_best().map_or(0.0, |r| r.value);
+        plans.sort_by(|a, b| best_value(b).total_cmp(&best_value(a)).then_with(|| a.molecule_id.cmp(&b.molecule_id)));
+
+        info!("Planned evidence acquisition for {} molecules ({} already confident)", plans.len(), confident.len());
+        Ok(AcquisitionPlan { plans, confident })
+    }
+}
+
+/// Evidence an experiment would produce, with the given confidence
+fn planned_evidence(molecule_id: &str, experiment: Experiment, confidence: f64) -> Evidence {
+    Evidence {
+        id: format!("{}-{}-{}", PLANNED_SOURCE_PREFIX, experiment, molecule_id),
+        molecule_id: molecule_id.to_string(),
+        evidence_type: experiment.evidence_type(),
+        source: format!("{}:{}", PLANNED_SOURCE_PREFIX, experiment),
+        confidence,
+        data: serde_json::Value::Null,
+        metadata: Default::default(),
+        timestamp: chrono::Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(molecule_id: &str, evidence_type: EvidenceType, source: &str, confidence: f64) -> Evidence {
+        let mut item = planned_evidence(molecule_id, Experiment::MsMs, confidence);
+        item.id = format!("{}-{}", source, molecule_id);
+        item.evidence_type = evidence_type;
+        item.source = source.to_string();
+        item
+    }
+
+    #[test]
+    fn test_plan_prefers_informative_missing_evidence() {
+        let pipeline = IdentityPipeline::new();
+        let planner = AcquisitionPlanner::new(PlannerOptions::default()).unwrap();
+        let items = vec![
+            evidence("m1", EvidenceType::MassSpec, "ms1-feature-table", 0.7),
+            evidence("m1", EvidenceType::MassSpec, "msms-library", 0.75),
+        ];
+        let plan = planner.plan_molecule(&pipeline, "m1", &items).unwrap();
+
+        assert_eq!(plan.performed, vec![Experiment::MsMs]);
+        assert!(plan.recommendations.iter().all(|r| r.experiment != Experiment::MsMs));
+        assert!(!plan.recommendations.is_empty());
+        for recommendation in &plan.recommendations {
+            assert!(recommendation.expected_gain >= 0.01);
+            assert!(recommendation.probability_supported > 0.0 && recommendation.probability_supported < 1.0);
+        }
+        let values: Vec<f64> = plan.recommendations.iter().map(|r| r.value).collect();
+        assert!(values.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_plan_skips_confident_molecules() {
+        let pipeline = IdentityPipeline::new();
+        let planner = AcquisitionPlanner::new(PlannerOptions::default()).unwrap();
+        let molecules = BTreeMap::from([
+            ("weak".to_string(), vec![evidence("weak", EvidenceType::MassSpec, "ms1", 0.55)]),
+            ("strong".to_string(), vec![
+                evidence("strong", EvidenceType::MassSpec, "msms", 0.95),
+                evidence("strong", EvidenceType::Structural, "reference standard", 0.95),
+                evidence("strong", EvidenceType::Literature, "pubmed", 0.9),
+                evidence("strong", EvidenceType::Pathway, "kegg", 0.9),
+                evidence("strong", EvidenceType::Genomics, "rnaseq", 0.9),
+            ]),
+        ]);
+        let plan = planner.plan(&pipeline, &molecules).unwrap();
+        assert_eq!(plan.confident, vec!["strong".to_string()]);
+        assert_eq!(plan.plans.len(), 1);
+        assert_eq!(plan.plans[0].molecule_id, "weak");
+
+        assert!(AcquisitionPlanner::new(PlannerOptions::default().with_max_recommendations(0)).is_err());
+        assert_eq!("nmr".parse::<Experiment>().unwrap(), Experiment::Nmr);
+    }
+}
@@ -0,0 +1,208 @@
+//! Identity Policy Module
+//!
+//! Decides whether a molecule's identity is accepted. A single threshold makes
+//! the decision flip back and forth as confidence drifts around it, so the
+//! policy uses two: an identity is accepted once confidence reaches the accept
+//! threshold and only revoked when it falls below the lower revoke threshold.
+//! The policy can also demand a minimum amount of evidence and evidence of
+//! particular types, e.g. a spectral match backed by an orthogonal method.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::path::Path;
+
+use crate::processing::evidence::EvidenceType;
+
+/// Initialize the identity policy module
+pub fn initialize() -> Result<()> {
+    info!("Initializing identity policy module");
+    info!("Identity policy module initialized successfully");
+    Ok(())
+}
+
+/// Evidence types of which at least one must be present
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageRequirement {
+    /// Name used when reporting the requirement as unmet (e.g. "orthogonal")
+    pub name: String,
+
+    /// Evidence types satisfying the requirement
+    pub any_of: Vec<EvidenceType>,
+}
+
+impl CoverageRequirement {
+    /// Whether any of the given evidence types satisfies the requirement
+    pub fn is_met_by(&self, evidence_types: &[EvidenceType]) -> bool {
+        evidence_types.iter().any(|t| self.any_of.contains(t))
+    }
+}
+
+/// Policy deciding whether a molecule's identity is accepted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdentityPolicy {
+    /// Confidence at which an identity not yet accepted becomes accepted
+    pub accept_threshold: f64,
+
+    /// Confidence below which an accepted identity is revoked
+    pub revoke_threshold: f64,
+
+    /// Minimum number of evidence items
+    pub min_evidence_count: usize,
+
+    /// Evidence types that must be covered
+    pub required_coverage: Vec<CoverageRequirement>,
+}
+
+impl Default for IdentityPolicy {
+    fn default() -> Self {
+        Self {
+            accept_threshold: 0.7,
+            revoke_threshold: 0.5,
+            min_evidence_count: 1,
+            required_coverage: Vec::new(),
+        }
+    }
+}
+
+impl IdentityPolicy {
+    /// Set the accept and revoke thresholds
+    pub fn with_thresholds(mut self, accept_threshold: f64, revoke_threshold: f64) -> Self {
+        self.accept_threshold = accept_threshold;
+        self.revoke_threshold = revoke_threshold;
+        self
+    }
+
+    /// Set the minimum number of evidence items
+    pub fn with_min_evidence_count(mut self, min_evidence_count: usize) -> Self {
+        self.min_evidence_count = min_evidence_count;
+        self
+    }
+
+    /// Require at least one evidence item of the given types
+    pub fn with_requirement(mut self, name: &str, any_of: Vec<EvidenceType>) -> Self {
+        self.required_coverage.push(CoverageRequirement {
+            name: name.to_string(),
+            any_of,
+        });
+        self
+    }
+
+    /// Require a mass spectrometry match and at least one orthogonal type of evidence
+    pub fn spectral_and_orthogonal() -> Self {
+        Self::default()
+            .with_min_evidence_count(2)
+            .with_requirement("spectral", vec![EvidenceType::MassSpec])
+            .with_requirement("orthogonal", vec![
+                EvidenceType::Genomics,
+                EvidenceType::Literature,
+                EvidenceType::Pathway,
+                EvidenceType::Reactome,
+                EvidenceType::Structural,
+            ])
+    }
+
+    /// Load a policy from a JSON file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read identity policy: {}", path.display()))?;
+        let policy: Self = serde_json::from_str(&content).context("Failed to parse identity policy")?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Load the policy named by `HEGEL_IDENTITY_POLICY`, or the default policy
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("HEGEL_IDENTITY_POLICY") {
+            Ok(path) => Self::from_file(Path::new(&path)),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Check that the thresholds are ordered and in range
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.accept_threshold) || !(0.0..=1.0).contains(&self.revoke_threshold) {
+            return Err(anyhow!("Identity policy thresholds must be between 0 and 1"));
+        }
+        if self.revoke_threshold > self.accept_threshold {
+            return Err(anyhow!("Revoke threshold {} is above accept threshold {}",
+                               self.revoke_threshold, self.accept_threshold));
+        }
+        if let Some(empty) = self.required_coverage.iter().find(|r| r.any_of.is_empty()) {
+            return Err(anyhow!("Coverage requirement '{}' lists no evidence types", empty.name));
+        }
+        Ok(())
+    }
+
+    /// Decide whether an identity is accepted
+    ///
+    /// `previously_valid` is the molecule's last decision; it selects which
+    /// threshold applies. Evidence count and coverage are checked every time.
+    pub fn evaluate(&self, confidence: f64, evidence_types: &[EvidenceType], previously_valid: bool) -> PolicyDecision {
+        let mut unmet = Vec::new();
+
+        if evidence_types.len() < self.min_evidence_count {
+            unmet.push(format!("at least {} evidence items (found {})",
+                               self.min_evidence_count, evidence_types.len()));
+        }
+        for requirement in &self.required_coverage {
+            if !requirement.is_met_by(evidence_types) {
+                unmet.push(format!("{} evidence", requirement.name));
+            }
+        }
+
+        let threshold = if previously_valid { self.revoke_threshold } else { self.accept_threshold };
+        let is_valid = unmet.is_empty() && confidence >= threshold;
+        debug!("Identity policy decision: confidence {:.3}, threshold {:.3}, unmet {:?}, valid {}",
+               confidence, threshold, unmet, is_valid);
+
+        PolicyDecision {
+            is_valid,
+            threshold,
+            unmet_requirements: unmet,
+        }
+    }
+}
+
+/// Outcome of applying an identity policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyDecision {
+    /// Whether the identity is accepted
+    pub is_valid: bool,
+
+    /// Confidence threshold that was applied
+    pub threshold: f64,
+
+    /// Evidence requirements that were not met
+    pub unmet_requirements: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hysteresis() {
+        let policy = IdentityPolicy::default();
+        let evidence = [EvidenceType::MassSpec];
+
+        assert!(!policy.evaluate(0.6, &evidence, false).is_valid);
+        assert!(policy.evaluate(0.7, &evidence, false).is_valid);
+        // Once accepted, the identity survives a drop that stays above the revoke threshold
+        assert!(policy.evaluate(0.6, &evidence, true).is_valid);
+        assert!(!policy.evaluate(0.45, &evidence, true).is_valid);
+    }
+
+    #[test]
+    fn test_required_coverage() {
+        let policy = IdentityPolicy::spectral_and_orthogonal();
+
+        let decision = policy.evaluate(0.95, &[EvidenceType::MassSpec, EvidenceType::MassSpec], false);
+        assert!(!decision.is_valid);
+        assert_eq!(decision.unmet_requirements, vec!["orthogonal evidence".to_string()]);
+
+        assert!(policy.evaluate(0.95, &[EvidenceType::MassSpec, EvidenceType::Literature], true).is_valid);
+        assert!(!policy.evaluate(0.95, &[EvidenceType::Literature], true).is_valid);
+    }
+}
@@ -0,0 +1,298 @@
+//! Conflict Resolution Strategies
+//!
+//! How a conflict between two pieces of molecular evidence is resolved
+//! depends on its class: two results of the same method disagreeing, an
+//! orthogonal method disagreeing with another, or a disagreement so large
+//! that no automatic adjustment should be trusted. A `ResolutionPolicy` maps
+//! each class to a strategy, and every resolution records which strategy was
+//! chosen and what it changed, for the explanation.
+
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{EvidenceType, MolecularEvidence};
+
+/// Factor the legacy strategy applies to the lower-confidence evidence
+const DOWNWEIGHT_FACTOR: f64 = 0.8;
+
+/// Share of the severity taken off each item by the proportional discount
+///
+/// At the detection threshold of 0.3 both items lose 15%; at a severity of
+/// 0.4 they lose the same 20% the legacy strategy takes off one of them.
+const PROPORTIONAL_DISCOUNT: f64 = 0.5;
+
+/// Severity from which a conflict is treated as severe by default
+const DEFAULT_ESCALATION_SEVERITY: f64 = 0.6;
+
+/// Initialize the conflict resolution module
+pub fn initialize() -> Result<()> {
+    info!("Initializing conflict resolution module");
+    info!("Conflict resolution module initialized successfully");
+    Ok(())
+}
+
+/// Kind of disagreement between two pieces of evidence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictClass {
+    /// Two results of the same kind of method disagree
+    SameMethod,
+    /// Results of different kinds of method disagree
+    CrossMethod,
+    /// The disagreement reaches the escalation severity, whatever the methods
+    Severe,
+}
+
+impl fmt::Display for ConflictClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConflictClass::SameMethod => write!(f, "same_method"),
+            ConflictClass::CrossMethod => write!(f, "cross_method"),
+            ConflictClass::Severe => write!(f, "severe"),
+        }
+    }
+}
+
+impl FromStr for ConflictClass {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "same_method" => Ok(ConflictClass::SameMethod),
+            "cross_method" => Ok(ConflictClass::CrossMethod),
+            "severe" => Ok(ConflictClass::Severe),
+            _ => Err(anyhow!("Unknown conflict class: {}", s)),
+        }
+    }
+}
+
+/// How a conflict is resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionStrategy {
+    /// Multiply the lower-confidence evidence by 0.8
+    DownweightLower,
+    /// Discount both pieces of evidence in proportion to the severity
+    ProportionalDiscount,
+    /// Keep evidence from an orthogonal (experimental) method and discount the other
+    PreferOrthogonal,
+    /// Leave the confidences alone and flag the conflict for a human
+    Escalate,
+}
+
+impl fmt::Display for ResolutionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResolutionStrategy::DownweightLower => write!(f, "downweight_lower"),
+            ResolutionStrategy::ProportionalDiscount => write!(f, "proportional_discount"),
+            ResolutionStrategy::PreferOrthogonal => write!(f, "prefer_orthogonal"),
+            ResolutionStrategy::Escalate => write!(f, "escalate"),
+        }
+    }
+}
+
+impl FromStr for ResolutionStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "downweight_lower" => Ok(ResolutionStrategy::DownweightLower),
+            "proportional_discount" => Ok(ResolutionStrategy::ProportionalDiscount),
+            "prefer_orthogonal" => Ok(ResolutionStrategy::PreferOrthogonal),
+            "escalate" => Ok(ResolutionStrategy::Escalate),
+            _ => Err(anyhow!("Unknown resolution strategy: {}", s)),
+        }
+    }
+}
+
+/// Strategy chosen for each conflict class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolutionPolicy {
+    /// Strategy by class; unlisted classes use `DownweightLower`
+    pub strategies: HashMap<ConflictClass, ResolutionStrategy>,
+
+    /// Severity from which a conflict is classed as severe
+    pub escalation_severity: f64,
+}
+
+impl Default for ResolutionPolicy {
+    fn default() -> Self {
+        Self {
+            strategies: HashMap::from([
+                (ConflictClass::SameMethod, ResolutionStrategy::ProportionalDiscount),
+                (ConflictClass::CrossMethod, ResolutionStrategy::PreferOrthogonal),
+                (ConflictClass::Severe, ResolutionStrategy::Escalate),
+            ]),
+            escalation_severity: DEFAULT_ESCALATION_SEVERITY,
+        }
+    }
+}
+
+impl ResolutionPolicy {
+    /// Use a strategy for one class of conflict
+    pub fn with_strategy(mut self, class: ConflictClass, strategy: ResolutionStrategy) -> Self {
+        self.strategies.insert(class, strategy);
+        self
+    }
+
+    /// Class conflicts at or above a severity as severe
+    pub fn with_escalation_severity(mut self, severity: f64) -> Self {
+        self.escalation_severity = severity;
+        self
+    }
+
+    /// Strategy for a class
+    pub fn strategy(&self, class: ConflictClass) -> ResolutionStrategy {
+        self.strategies.get(&class).copied().unwrap_or(ResolutionStrategy::DownweightLower)
+    }
+
+    /// Class of a conflict between two pieces of evidence
+    pub fn classify(&self, first: &MolecularEvidence, second: &MolecularEvidence) -> ConflictClass {
+        if severity(first, second) >= self.escalation_severity {
+            ConflictClass::Severe
+        } else if first.data_type == second.data_type {
+            ConflictClass::SameMethod
+        } else {
+            ConflictClass::CrossMethod
+        }
+    }
+}
+
+/// Severity of a conflict: how far apart the two confidences are
+pub fn severity(first: &MolecularEvidence, second: &MolecularEvidence) -> f64 {
+    (first.confidence - second.confidence).abs()
+}
+
+/// Whether evidence comes from an experimental method independent of the others
+///
+/// Pathway membership and literature reports are contextual: they say what a
+/// molecule is likely to be, not what was measured.
+pub fn is_orthogonal(data_type: EvidenceType) -> bool {
+    matches!(data_type, EvidenceType::Spectral | EvidenceType::Sequence | EvidenceType::Structural)
+}
+
+/// Record of one resolved conflict
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictResolution {
+    /// Positions of the two pieces of evidence
+    pub evidence: (usize, usize),
+
+    /// Class of the conflict
+    pub class: ConflictClass,
+
+    /// Strategy the policy chose for the class
+    pub strategy: ResolutionStrategy,
+
+    /// Severity of the conflict
+    pub severity: f64,
+
+    /// Confidences of the two pieces of evidence before resolution
+    pub before: (f64, f64),
+
+    /// Confidences after resolution
+    pub after: (f64, f64),
+
+    /// What the strategy did
+    pub explanation: String,
+}
+
+impl ConflictResolution {
+    /// Whether the conflict was left for a human to resolve
+    pub fn needs_review(&self) -> bool {
+        self.strategy == ResolutionStrategy::Escalate
+    }
+}
+
+/// Resolve the conflict between two pieces of evidence in place
+pub fn resolve(evidences: &mut [MolecularEvidence], i: usize, j: usize, policy: &ResolutionPolicy) -> ConflictResolution {
+    let class = policy.classify(&evidences[i], &evidences[j]);
+    let strategy = policy.strategy(class);
+    let severity = severity(&evidences[i], &evidences[j]);
+    let before = (evidences[i].confidence, evidences[j].confidence);
+    let (lower, higher) = if before.0 < before.1 { (i, j) } else { (j, i) };
+
+    let explanation = match strategy {
+        ResolutionStrategy::DownweightLower => {
+            evidences[lower].confidence *= DOWNWEIGHT_FACTOR;
+            format!("down-weighted the less confident {} evidence by {:.1}", evidences[lower].source, DOWNWEIGHT_FACTOR)
+        }
+        ResolutionStrategy::ProportionalDiscount => discount_both(evidences, i, j, severity),
+        ResolutionStrategy::PreferOrthogonal => {
+            match (is_orthogonal(evidences[i].data_type), is_orthogonal(evidences[j].data_type)) {
+                (true, false) | (false, true) => {
+                    let (kept, discounted) = if is_orthogonal(evidences[i].data_type) { (i, j) } else { (j, i) };
+                    evidences[discounted].confidence *= 1.0 - severity;
+                    format!("kept the orthogonal {} evidence and discounted {} by the severity",
+                            evidences[kept].source, evidences[discounted].source)
+                }
+                _ => format!("no single orthogonal method, so {}", discount_both(evidences, i, j, severity)),
+            }
+        }
+        ResolutionStrategy::Escalate => {
+            format!("left unchanged for review: {} ({:.2}) against {} ({:.2})",
+                    evidences[higher].source, evidences[higher].confidence,
+                    evidences[lower].source, evidences[lower].confidence)
+        }
+    };
+    debug!("Resolved {} conflict between evidence {} and {} with {}", class, i, j, strategy);
+
+    ConflictResolution {
+        evidence: (i, j),
+        class,
+        strategy,
+        severity,
+        before,
+        after: (evidences[i].confidence, evidences[j].confidence),
+        explanation,
+    }
+}
+
+fn discount_both(evidences: &mut [MolecularEvidence], i: usize, j: usize, severity: f64) -> String {
+    let factor = 1.0 - PROPORTIONAL_DISCOUNT * severity;
+    evidences[i].confidence *= factor;
+    evidences[j].confidence *= factor;
+    format!("discounted both {} and {} by {:.2}", evidences[i].source, evidences[j].source, factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(source: &str, data_type: EvidenceType, confidence: f64) -> MolecularEvidence {
+        MolecularEvidence { source: source.to_string(), confidence, data_type, value: "glucose".to_string() }
+    }
+
+    #[test]
+    fn test_strategy_follows_conflict_class() {
+        let policy = ResolutionPolicy::default();
+        let mut evidences = vec![
+            evidence("nmr", EvidenceType::Spectral, 0.9),
+            evidence("pubmed", EvidenceType::Literature, 0.5),
+            evidence("ms", EvidenceType::Spectral, 0.5),
+            evidence("kegg", EvidenceType::Pathway, 0.1),
+        ];
+
+        let cross = resolve(&mut evidences, 0, 1, &policy);
+        assert_eq!(cross.class, ConflictClass::CrossMethod);
+        assert_eq!(cross.strategy, ResolutionStrategy::PreferOrthogonal);
+        assert_eq!(cross.after.0, 0.9);
+        assert!((cross.after.1 - 0.3).abs() < 1e-9);
+
+        let same = resolve(&mut evidences, 0, 2, &policy);
+        assert_eq!(same.class, ConflictClass::SameMethod);
+        assert!(same.after.0 < 0.9 && same.after.1 < 0.5);
+
+        let severe = resolve(&mut evidences, 0, 3, &policy);
+        assert!(severe.needs_review());
+        assert_eq!(severe.before, severe.after);
+
+        let legacy = ResolutionPolicy::default().with_strategy(ConflictClass::Severe, ResolutionStrategy::DownweightLower);
+        let resolution = resolve(&mut evidences, 0, 3, &legacy);
+        assert!((resolution.after.1 - 0.08).abs() < 1e-9);
+        assert_eq!("prefer-orthogonal".parse::<ResolutionStrategy>().unwrap(), ResolutionStrategy::PreferOrthogonal);
+    }
+}
@@ -0,0 +1,184 @@
+//! gRPC Sidecar Client
+//!
+//! Protocol v2 client generated by `tonic-build` from
+//! `proto/hegel/sidecar/v2/sidecar.proto`. Free-form molecule data travels as
+//! `google.protobuf.Struct` and is converted to and from JSON at this boundary,
+//! so the rest of the core is unaffected by the transport.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::{debug, info};
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Value};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Status};
+
+use super::{Deadline, NeighborhoodQuery, RetrieveRequest, SidecarClient};
+
+/// Generated protocol v2 messages and client
+pub mod proto {
+    tonic::include_proto!("hegel.sidecar.v2");
+}
+
+use proto::molecule_sidecar_client::MoleculeSidecarClient;
+
+/// Structural similarity from which the sidecar connects a new molecule to existing ones
+const SIMILARITY_THRESHOLD: f64 = 0.7;
+
+/// Sidecar client speaking protocol v2
+#[derive(Debug, Clone)]
+pub struct GrpcSidecarClient {
+    /// Generated client over a shared channel; cloning it is cheap
+    client: MoleculeSidecarClient<Channel>,
+}
+
+impl GrpcSidecarClient {
+    /// Client for an endpoint such as `http://localhost:50051`, connecting on first use
+    pub fn connect_lazy(endpoint: &str) -> Result<Self> {
+        let channel = Endpoint::from_shared(endpoint.to_string())
+            .with_context(|| format!("Invalid sidecar endpoint: {}", endpoint))?
+            .connect_lazy();
+        info!("Using gRPC sidecar at {}", endpoint);
+        Ok(Self { client: MoleculeSidecarClient::new(channel) })
+    }
+
+    /// Wrap a message in a request carrying the time left before the deadline
+    fn request<T>(message: T, deadline: Deadline) -> Result<Request<T>> {
+        let mut request = Request::new(message);
+        request.set_timeout(deadline.remaining()?);
+        Ok(request)
+    }
+}
+
+fn status_error(call: &str, status: Status) -> anyhow::Error {
+    anyhow!("Sidecar {} failed with {:?}: {}", call, status.code(), status.message())
+}
+
+#[async_trait]
+impl SidecarClient for GrpcSidecarClient {
+    fn protocol(&self) -> &str {
+        "grpc"
+    }
+
+    async fn retrieve_molecule(&self, request: &RetrieveRequest, deadline: Deadline) -> Result<serde_json::Value> {
+        let message = proto::RetrieveMoleculeRequest {
+            identifier: request.identifier.clone(),
+            id_type: request.id_type.clone(),
+            primary_source: request.primary_source.clone(),
+            include_sources: request.include_sources.clone(),
+            include_pathways: request.include_pathways,
+            include_interactions: request.include_interactions,
+            include_targets: request.include_targets,
+        };
+        match self.client.clone().retrieve_molecule(Self::request(message, deadline)?).await {
+            Ok(response) => {
+                let response = response.into_inner();
+                debug!("Sidecar retrieved {} from {}", response.molecule_id, response.sources.join(", "));
+                Ok(response.data.as_ref().map(struct_to_json).unwrap_or(serde_json::Value::Null))
+            }
+            Err(status) if status.code() == Code::NotFound => Ok(serde_json::Value::Null),
+            Err(status) => Err(status_error("RetrieveMolecule", status)),
+        }
+    }
+
+    async fn add_to_network(&self, molecule_data: &serde_json::Value, deadline: Deadline) -> Result<String> {
+        let fields = molecule_data.as_object()
+            .ok_or_else(|| anyhow!("Molecule data must be a JSON object"))?;
+        let message = proto::AddToNetworkRequest {
+            molecule_data: Some(json_to_struct(fields)),
+            build_connections: true,
+            similarity_threshold: SIMILARITY_THRESHOLD,
+        };
+        let response = self.client.clone().add_to_network(Self::request(message, deadline)?).await
+            .map_err(|status| status_error("AddToNetwork", status))?;
+        Ok(response.into_inner().molecule_id)
+    }
+
+    async fn get_evidence_summary(&self, molecule_id: &str, deadline: Deadline) -> Result<serde_json::Value> {
+        let message = proto::GetEvidenceSummaryRequest { molecule_id: molecule_id.to_string() };
+        let response = self.client.clone().get_evidence_summary(Self::request(message, deadline)?).await
+            .map_err(|status| status_error("GetEvidenceSummary", status))?
+            .into_inner();
+        Ok(serde_json::json!({
+            "molecule": response.molecule.as_ref().map(struct_to_json).unwrap_or(serde_json::Value::Null),
+            "evidence": response.evidence.iter().map(struct_to_json).collect::<Vec<_>>(),
+        }))
+    }
+
+    async fn get_neighborhood(&self, query: &NeighborhoodQuery, deadline: Deadline) -> Result<serde_json::Value> {
+        let message = proto::GetNeighborhoodRequest {
+            molecule_id: query.molecule_id.clone(),
+            relationship_types: query.relationship_types.clone().unwrap_or_default(),
+            max_depth: query.max_depth,
+            limit: query.limit,
+        };
+        let response = self.client.clone().get_neighborhood(Self::request(message, deadline)?).await
+            .map_err(|status| status_error("GetNeighborhood", status))?
+            .into_inner();
+        Ok(serde_json::json!({
+            "nodes": response.nodes.iter().map(struct_to_json).collect::<Vec<_>>(),
+            "edges": response.edges.iter().map(struct_to_json).collect::<Vec<_>>(),
+        }))
+    }
+}
+
+/// Convert a JSON object to a protobuf `Struct`
+pub fn json_to_struct(fields: &serde_json::Map<String, serde_json::Value>) -> Struct {
+    Struct {
+        fields: fields.iter().map(|(key, value)| (key.clone(), json_to_value(value))).collect(),
+    }
+}
+
+fn json_to_value(value: &serde_json::Value) -> Value {
+    let kind = match value {
+        serde_json::Value::Null => Kind::NullValue(0),
+        serde_json::Value::Bool(b) => Kind::BoolValue(*b),
+        serde_json::Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => Kind::StringValue(s.clone()),
+        serde_json::Value::Array(items) => Kind::ListValue(ListValue {
+            values: items.iter().map(json_to_value).collect(),
+        }),
+        serde_json::Value::Object(fields) => Kind::StructValue(json_to_struct(fields)),
+    };
+    Value { kind: Some(kind) }
+}
+
+/// Convert a protobuf `Struct` to a JSON object
+///
+/// `Struct` numbers are all doubles; whole numbers come back as JSON integers
+/// so identifiers such as PubChem CIDs keep their form.
+pub fn struct_to_json(value: &Struct) -> serde_json::Value {
+    serde_json::Value::Object(value.fields.iter().map(|(key, value)| (key.clone(), value_to_json(value))).collect())
+}
+
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match &value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(b)) => serde_json::Value::Bool(*b),
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => serde_json::json!(*n as i64),
+        Some(Kind::NumberValue(n)) => serde_json::Number::from_f64(*n)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Some(Kind::StringValue(s)) => serde_json::Value::String(s.clone()),
+        Some(Kind::ListValue(list)) => serde_json::Value::Array(list.values.iter().map(value_to_json).collect()),
+        Some(Kind::StructValue(fields)) => struct_to_json(fields),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_struct_round_trip() {
+        let data = serde_json::json!({
+            "name": "aspirin",
+            "pubchem_cid": 2244,
+            "exact_mass": 180.042,
+            "synonyms": ["acetylsalicylic acid", null],
+            "pathways": [{"id": "R-HSA-2162123", "predicted": true}],
+        });
+        let converted = json_to_struct(data.as_object().unwrap());
+        assert_eq!(struct_to_json(&converted), data);
+    }
+}
@@ -0,0 +1,96 @@
+//! HTTP Sidecar Client (compat)
+//!
+//! The JSON-over-HTTP protocol used before protocol v2, against the
+//! `/api/molecules` routes of the Python API. Kept for sidecars that do not
+//! serve gRPC yet; requests and responses are untyped JSON, and the deadline
+//! is applied as the request timeout.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::{info, warn};
+
+use super::{Deadline, NeighborhoodQuery, RetrieveRequest, SidecarClient};
+
+/// Sidecar client speaking the JSON-over-HTTP protocol
+pub struct HttpSidecarClient {
+    /// Base URL of the Python API
+    endpoint: String,
+
+    /// HTTP client
+    client: reqwest::Client,
+}
+
+impl HttpSidecarClient {
+    /// Client for a Python API base URL such as `http://localhost:8000`
+    pub fn new(endpoint: &str) -> Self {
+        warn!("Using the deprecated JSON-over-HTTP sidecar protocol at {}", endpoint);
+        info!("Set HEGEL_SIDECAR_PROTOCOL=grpc once the sidecar serves protocol v2");
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send a request and parse the JSON response, failing on a non-success status
+    async fn send(&self, request: reqwest::RequestBuilder, deadline: Deadline, what: &str) -> Result<serde_json::Value> {
+        let response = request
+            .timeout(deadline.remaining()?)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {}", what))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow!("API request failed with status {}: {}", status, error_text));
+        }
+
+        response.json::<serde_json::Value>().await
+            .context("Failed to parse response JSON")
+    }
+}
+
+#[async_trait]
+impl SidecarClient for HttpSidecarClient {
+    fn protocol(&self) -> &str {
+        "http"
+    }
+
+    async fn retrieve_molecule(&self, request: &RetrieveRequest, deadline: Deadline) -> Result<serde_json::Value> {
+        let builder = self.client.post(format!("{}/api/molecules/retrieve", self.endpoint)).json(request);
+        self.send(builder, deadline, "Python API").await
+    }
+
+    async fn add_to_network(&self, molecule_data: &serde_json::Value, deadline: Deadline) -> Result<String> {
+        let builder = self.client.post(format!("{}/api/molecules/network/add", self.endpoint)).json(molecule_data);
+        let data = self.send(builder, deadline, "add molecule to network").await?;
+
+        data.get("id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("No molecule ID in response"))
+    }
+
+    async fn get_evidence_summary(&self, molecule_id: &str, deadline: Deadline) -> Result<serde_json::Value> {
+        let builder = self.client.get(format!("{}/api/molecules/{}/evidence", self.endpoint, molecule_id));
+        self.send(builder, deadline, "evidence summary").await
+    }
+
+    async fn get_neighborhood(&self, query: &NeighborhoodQuery, deadline: Deadline) -> Result<serde_json::Value> {
+        let mut params: Vec<(&str, String)> = Vec::new();
+        for rel_type in query.relationship_types.iter().flatten() {
+            params.push(("relationship_types", rel_type.clone()));
+        }
+        if let Some(depth) = query.max_depth {
+            params.push(("max_depth", depth.to_string()));
+        }
+        if let Some(limit) = query.limit {
+            params.push(("limit", limit.to_string()));
+        }
+
+        let builder = self.client
+            .get(format!("{}/api/molecules/{}/neighborhood", self.endpoint, query.molecule_id))
+            .query(&params);
+        self.send(builder, deadline, "molecule neighborhood").await
+    }
+}
@@ -0,0 +1,192 @@
+//! Python Sidecar Client
+//!
+//! The Python sidecar (`backend/api`) retrieves molecules from external
+//! databases and maintains the molecule network. The core talks to it over
+//! protocol v2, the gRPC contract in `proto/hegel/sidecar/v2/sidecar.proto`
+//! (`grpc` feature, on by default). The untyped JSON-over-HTTP protocol it
+//! replaces is kept behind the `python-http` compat feature for sidecars
+//! that have not been upgraded.
+//!
+//! Every call carries a `Deadline`. Calls made on behalf of one request
+//! share its deadline, and each passes on only the time that remains, so a
+//! slow retrieval leaves less time for the network update rather than
+//! extending the request.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::info;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "python-http")]
+pub mod http;
+
+/// Time allowed for a sidecar request when none is configured
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Initialize the sidecar module
+pub fn initialize() -> Result<()> {
+    info!("Initializing Python sidecar module");
+    info!("Python sidecar module initialized successfully");
+    Ok(())
+}
+
+/// Point in time by which a sidecar call must complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    /// When the time runs out
+    at: Instant,
+}
+
+impl Deadline {
+    /// Deadline a duration from now
+    pub fn after(timeout: Duration) -> Self {
+        Self { at: Instant::now() + timeout }
+    }
+
+    /// Time left, or an error once the deadline has passed
+    pub fn remaining(&self) -> Result<Duration> {
+        let remaining = self.at.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            Err(anyhow!("Sidecar deadline exceeded"))
+        } else {
+            Ok(remaining)
+        }
+    }
+}
+
+/// Molecule lookup sent to the sidecar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrieveRequest {
+    /// Identifier to look up
+    pub identifier: String,
+
+    /// Identifier type
+    pub id_type: String,
+
+    /// Database queried first
+    pub primary_source: String,
+
+    /// Further databases to merge data from
+    pub include_sources: Vec<String>,
+
+    /// Whether to include pathway data
+    pub include_pathways: bool,
+
+    /// Whether to include interaction data
+    pub include_interactions: bool,
+
+    /// Whether to include target data
+    pub include_targets: bool,
+}
+
+/// Neighborhood query sent to the sidecar
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NeighborhoodQuery {
+    /// Molecule at the centre
+    pub molecule_id: String,
+
+    /// Relationship types to follow; all when `None`
+    pub relationship_types: Option<Vec<String>>,
+
+    /// Maximum path length from the molecule
+    pub max_depth: Option<u32>,
+
+    /// Maximum number of nodes
+    pub limit: Option<u32>,
+}
+
+/// Connection to the Python sidecar
+#[async_trait]
+pub trait SidecarClient: Send + Sync {
+    /// Protocol name, used in logs
+    fn protocol(&self) -> &str;
+
+    /// Retrieve molecule data; `Value::Null` when no source knows the molecule
+    async fn retrieve_molecule(&self, request: &RetrieveRequest, deadline: Deadline) -> Result<serde_json::Value>;
+
+    /// Add molecule data to the network, returning the molecule's network ID
+    async fn add_to_network(&self, molecule_data: &serde_json::Value, deadline: Deadline) -> Result<String>;
+
+    /// Evidence summary of a molecule in the network
+    async fn get_evidence_summary(&self, molecule_id: &str, deadline: Deadline) -> Result<serde_json::Value>;
+
+    /// Network neighborhood of a molecule
+    async fn get_neighborhood(&self, query: &NeighborhoodQuery, deadline: Deadline) -> Result<serde_json::Value>;
+}
+
+/// Sidecar connection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidecarConfig {
+    /// Protocol: `grpc`, or `http` for the compat protocol
+    pub protocol: String,
+
+    /// Sidecar endpoint
+    pub endpoint: String,
+
+    /// Time allowed for each request made through the sidecar
+    pub timeout: Duration,
+}
+
+impl SidecarConfig {
+    /// Read `HEGEL_SIDECAR_PROTOCOL` (default `grpc`), `HEGEL_SIDECAR_ENDPOINT`
+    /// and `HEGEL_SIDECAR_TIMEOUT_SECONDS`
+    ///
+    /// For the `http` protocol the endpoint defaults to `HEGEL_PYTHON_API_ENDPOINT`,
+    /// which it used before protocol v2.
+    pub fn from_env() -> Result<Self> {
+        let protocol = std::env::var("HEGEL_SIDECAR_PROTOCOL")
+            .unwrap_or_else(|_| "grpc".to_string())
+            .to_lowercase();
+        let endpoint = match std::env::var("HEGEL_SIDECAR_ENDPOINT") {
+            Ok(endpoint) => endpoint,
+            Err(_) if protocol == "http" => std::env::var("HEGEL_PYTHON_API_ENDPOINT")
+                .unwrap_or_else(|_| "http://localhost:8000".to_string()),
+            Err(_) => "http://localhost:50051".to_string(),
+        };
+        let timeout = match std::env::var("HEGEL_SIDECAR_TIMEOUT_SECONDS") {
+            Ok(value) => Duration::from_secs(value.parse()
+                .with_context(|| format!("Invalid HEGEL_SIDECAR_TIMEOUT_SECONDS value: {}", value))?),
+            Err(_) => DEFAULT_TIMEOUT,
+        };
+
+        Ok(Self { protocol, endpoint, timeout })
+    }
+
+    /// Client for the configured protocol
+    ///
+    /// The gRPC channel connects lazily, so this succeeds while the sidecar is
+    /// still starting; the first call reports an unreachable sidecar.
+    pub fn connect(&self) -> Result<Arc<dyn SidecarClient>> {
+        match self.protocol.as_str() {
+            #[cfg(feature = "grpc")]
+            "grpc" => Ok(Arc::new(grpc::GrpcSidecarClient::connect_lazy(&self.endpoint)?)),
+            #[cfg(feature = "python-http")]
+            "http" => Ok(Arc::new(http::HttpSidecarClient::new(&self.endpoint))),
+            other => Err(anyhow!(
+                "Unsupported or disabled sidecar protocol: {} (enable the `grpc` or `python-http` feature)", other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_runs_out() {
+        let deadline = Deadline::after(Duration::from_secs(60));
+        let remaining = deadline.remaining().unwrap();
+        assert!(remaining > Duration::from_secs(59) && remaining <= Duration::from_secs(60));
+
+        let expired = Deadline::after(Duration::ZERO);
+        assert!(expired.remaining().is_err());
+
+        let config = SidecarConfig { protocol: "carrier-pigeon".to_string(), endpoint: String::new(), timeout: DEFAULT_TIMEOUT };
+        assert!(config.connect().is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! Reasoning Templates
+//!
+//! LLM prompts and rectification explanations are rendered from templates
+//! rather than strings compiled into the code. The defaults live in
+//! `templates/reasoning` and are embedded at build time; a deployment can
+//! override any of them, or add locale variants, by pointing
+//! `HEGEL_TEMPLATES_DIR` at a directory of `<name>.tpl` and
+//! `<name>.<locale>.tpl` files.
+//!
+//! Templates use TinyTemplate syntax: `{molecule}` inserts a value and
+//! `{{ for conflict in conflicts }}...{{ endfor }}` loops. The variables are
+//! the fields of `ReasoningContext`.
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tinytemplate::TinyTemplate;
+
+use super::resolution::ConflictResolution;
+use crate::Molecule;
+
+/// File extension of template files
+const TEMPLATE_EXTENSION: &str = "tpl";
+
+/// Templates shipped with Hegel, by name and locale
+const BUILTIN: [(&str, Option<&str>, &str); 7] = [
+    ("spectral_conflict", None, include_str!("../../templates/reasoning/spectral_conflict.tpl")),
+    ("sequence_conflict", None, include_str!("../../templates/reasoning/sequence_conflict.tpl")),
+    ("pathway_conflict", None, include_str!("../../templates/reasoning/pathway_conflict.tpl")),
+    ("evidence_integration", None, include_str!("../../templates/reasoning/evidence_integration.tpl")),
+    ("explanation", None, include_str!("../../templates/reasoning/explanation.tpl")),
+    ("explanation", Some("de"), include_str!("../../templates/reasoning/explanation.de.tpl")),
+    ("claim_extraction", None, include_str!("../../templates/reasoning/claim_extraction.tpl")),
+];
+
+/// Initialize the reasoning templates module
+pub fn initialize() -> Result<()> {
+    info!("Initializing reasoning templates module");
+    info!("Reasoning templates module initialized successfully");
+    Ok(())
+}
+
+/// Lower-case a locale and use `-` as its separator, so `pt_BR` and `pt-br` match
+fn normalize_locale(locale: &str) -> String {
+    locale.trim().to_lowercase().replace('_', "-")
+}
+
+fn key(name: &str, locale: Option<&str>) -> String {
+    match locale {
+        Some(locale) => format!("{}.{}", name, normalize_locale(locale)),
+        None => name.to_string(),
+    }
+}
+
+/// Named templates with optional locale variants
+#[derive(Debug, Clone)]
+pub struct TemplateSet {
+    /// Template sources keyed by `name` or `name.locale`
+    templates: HashMap<String, String>,
+}
+
+impl Default for TemplateSet {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl TemplateSet {
+    /// Templates shipped with Hegel
+    pub fn builtin() -> Self {
+        let templates = BUILTIN.iter()
+            .map(|(name, locale, source)| (key(name, *locale), source.to_string()))
+            .collect();
+        Self { templates }
+    }
+
+    /// Built-in templates overridden by the `.tpl` files in a directory
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let mut set = Self::builtin();
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read template directory {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(TEMPLATE_EXTENSION) {
+                continue;
+            }
+            let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem,
+                None => continue,
+            };
+            let (name, locale) = match stem.split_once('.') {
+                Some((name, locale)) => (name, Some(locale)),
+                None => (stem, None),
+            };
+            let source = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template {}", path.display()))?;
+            set.insert(name, locale, source)
+                .with_context(|| format!("Invalid template {}", path.display()))?;
+            debug!("Loaded reasoning template {} from {}", key(name, locale), path.display());
+        }
+        Ok(set)
+    }
+
+    /// Built-in templates, overridden from `HEGEL_TEMPLATES_DIR` when it is set
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("HEGEL_TEMPLATES_DIR") {
+            Ok(dir) if !dir.is_empty() => Self::load_dir(Path::new(&dir)),
+            _ => Ok(Self::builtin()),
+        }
+    }
+
+    /// Add or replace a template, checking that it parses
+    pub fn insert(&mut self, name: &str, locale: Option<&str>, source: String) -> Result<()> {
+        let mut parser = TinyTemplate::new();
+        parser.add_template(name, &source).map_err(|e| anyhow!("{}", e))?;
+        self.templates.insert(key(name, locale), source);
+        Ok(())
+    }
+
+    /// Source of a template for a locale
+    ///
+    /// Falls back from the full locale (`de-at`) to its language (`de`) and
+    /// then to the template without a locale.
+    pub fn get(&self, name: &str, locale: Option<&str>) -> Option<&str> {
+        let mut candidates = Vec::new();
+        if let Some(locale) = locale.map(normalize_locale) {
+            candidates.push(key(name, Some(&locale)));
+            if let Some((language, _)) = locale.split_once('-') {
+                candidates.push(key(name, Some(language)));
+            }
+        }
+        candidates.push(name.to_string());
+        candidates.iter().find_map(|candidate| self.templates.get(candidate).map(String::as_str))
+    }
+
+    /// Render a template for a locale
+    pub fn render<C: Serialize>(&self, name: &str, locale: Option<&str>, context: &C) -> Result<String> {
+        let source = self.get(name, locale).ok_or_else(|| anyhow!("Template not found: {}", name))?;
+        let mut renderer = TinyTemplate::new();
+        renderer.set_default_formatter(&tinytemplate::format_unescaped);
+        renderer.add_template(name, source).map_err(|e| anyhow!("{}", e))?;
+        let rendered = renderer.render(name, context)
+            .map_err(|e| anyhow!("Failed to render template {}: {}", name, e))?;
+        Ok(rendered.trim_end().to_string())
+    }
+}
+
+/// One row of the evidence table
+#[derive(Debug, Clone, Serialize)]
+pub struct EvidenceRow {
+    /// Evidence source
+    pub source: String,
+
+    /// Kind of evidence
+    pub data_type: String,
+
+    /// Identification the evidence supports
+    pub value: String,
+
+    /// Confidence, formatted to two decimals
+    pub confidence: String,
+}
+
+/// One resolved conflict
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictRow {
+    /// Source of the first piece of evidence
+    pub first: String,
+
+    /// Source of the second piece of evidence
+    pub second: String,
+
+    /// Conflict class
+    pub class: String,
+
+    /// Strategy used to resolve it
+    pub strategy: String,
+
+    /// Severity, formatted to two decimals
+    pub severity: String,
+
+    /// What the strategy did
+    pub explanation: String,
+
+    /// Whether the conflict was escalated for review
+    pub needs_review: bool,
+}
+
+/// Variables available to reasoning templates
+#[derive(Debug, Clone, Serialize)]
+pub struct ReasoningContext {
+    /// Molecule name
+    pub molecule: String,
+
+    /// Molecule confidence as a percentage, formatted to two decimals
+    pub confidence_percent: String,
+
+    /// Source of the most confident evidence
+    pub strongest_source: String,
+
+    /// Evidence rows, for templates that lay out their own table
+    pub evidence: Vec<EvidenceRow>,
+
+    /// Resolved conflicts
+    pub conflicts: Vec<ConflictRow>,
+
+    /// Evidence as a ready-made Markdown table
+    pub evidence_table: String,
+
+    /// One-line summary of the conflicts and escalations
+    pub conflict_summary: String,
+
+    /// Number of conflicts escalated for review
+    pub review_count: usize,
+}
+
+impl ReasoningContext {
+    /// Build the context for a molecule and the conflicts resolved on it
+    pub fn new(molecule: &Molecule, resolutions: &[ConflictResolution]) -> Self {
+        let evidence: Vec<EvidenceRow> = molecule.evidences.iter()
+            .map(|e| EvidenceRow {
+                source: e.source.clone(),
+                data_type: format!("{:?}", e.data_type).to_lowercase(),
+                value: e.value.clone(),
+                confidence: format!("{:.2}", e.confidence),
+            })
+            .collect();
+
+        let source = |index: usize| molecule.evidences.get(index)
+            .map(|e| e.source.clone())
+            .unwrap_or_else(|| format!("evidence {}", index));
+        let conflicts: Vec<ConflictRow> = resolutions.iter()
+            .map(|r| ConflictRow {
+                first: source(r.evidence.0),
+                second: source(r.evidence.1),
+                class: r.class.to_string(),
+                strategy: r.strategy.to_string(),
+                severity: format!("{:.2}", r.severity),
+                explanation: r.explanation.clone(),
+                needs_review: r.needs_review(),
+            })
+            .collect();
+        let review_count = conflicts.iter().filter(|c| c.needs_review).count();
+
+        let mut evidence_table = "| Source | Type | Value | Confidence |\n|---|---|---|---|".to_string();
+        for row in &evidence {
+            evidence_table.push_str(&format!("\n| {} | {} | {} | {} |", row.source, row.data_type, row.value, row.confidence));
+        }
+        let conflict_summary = match (conflicts.len(), review_count) {
+            (0, _) => "No conflicts between the evidence.".to_string(),
+            (total, 0) => format!("{} conflict(s) resolved automatically.", total),
+            (total, review) => format!("{} conflict(s), {} escalated for review.", total, review),
+        };
+
+        let strongest_source = molecule.evidences.iter()
+            .max_by(|a, b| a.confidence.partial_cmp(&b.confidence).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|e| e.source.clone())
+            .unwrap_or_else(|| "unknown source".to_string());
+
+        Self {
+            molecule: molecule.display_name().to_string(),
+            confidence_percent: format!("{:.2}", molecule.confidence_score * 100.0),
+            strongest_source,
+            evidence,
+            conflicts,
+            evidence_table,
+            conflict_summary,
+            review_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metacognition::resolution::{resolve, ResolutionPolicy};
+    use crate::{EvidenceType, MolecularEvidence};
+
+    fn molecule() -> Molecule {
+        let evidence = |source: &str, data_type, confidence| MolecularEvidence {
+            source: source.to_string(),
+            confidence,
+            data_type,
+            value: "glucose".to_string(),
+        };
+        let mut molecule = Molecule::new("glucose".to_string(), "D-glucose".to_string(), "C6H12O6".parse().unwrap());
+        molecule.add_evidence(evidence("nmr", EvidenceType::Spectral, 0.9));
+        molecule.add_evidence(evidence("pubmed", EvidenceType::Literature, 0.5));
+        molecule.confidence_score = 0.8;
+        molecule
+    }
+
+    #[test]
+    fn test_builtin_explanation_and_locale_fallback() {
+        let mut molecule = molecule();
+        let resolution = resolve(&mut molecule.evidences, 0, 1, &ResolutionPolicy::default());
+        let context = ReasoningContext::new(&molecule, &[resolution]);
+        let set = TemplateSet::builtin();
+
+        let english = set.render("explanation", None, &context).unwrap();
+        assert!(english.starts_with("Based on analysis of the evidence for D-glucose, the molecule is identified with 80.00% confidence."));
+        assert!(english.contains("Conflict between nmr and pubmed (cross_method, severity 0.40) resolved with prefer_orthogonal"));
+
+        let german = set.render("explanation", Some("de_AT"), &context).unwrap();
+        assert!(german.starts_with("Auf Grundlage der Evidenz für D-glucose"));
+        assert_eq!(set.render("explanation", Some("fr"), &context).unwrap(), english);
+
+        let prompt = set.render("evidence_integration", None, &context).unwrap();
+        assert!(prompt.contains("| pubmed | literature | glucose | 0.30 |"));
+    }
+
+    #[test]
+    fn test_directory_overrides_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("explanation.tpl"), "{molecule}: {conflict_summary}").unwrap();
+        fs::write(dir.path().join("explanation.fr.tpl"), "{molecule} : {review_count} conflit(s) à revoir").unwrap();
+        let set = TemplateSet::load_dir(dir.path()).unwrap();
+        let context = ReasoningContext::new(&molecule(), &[]);
+
+        assert_eq!(set.render("explanation", None, &context).unwrap(), "D-glucose: No conflicts between the evidence.");
+        assert_eq!(set.render("explanation", Some("fr-CA"), &context).unwrap(), "D-glucose : 0 conflit(s) à revoir");
+        assert!(set.get("pathway_conflict", None).is_some());
+
+        fs::write(dir.path().join("broken.tpl"), "{{ for row in evidence }}").unwrap();
+        assert!(TemplateSet::load_dir(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Offline Mode
+//!
+//! Regulated labs often run Hegel on machines that cannot reach PubChem,
+//! UniChem, PubMed, an LLM provider or the Python API. In offline mode every
+//! subsystem that would contact an external service refuses to, with an
+//! `OfflineError` naming the features that are unavailable, and optional
+//! enrichment steps are skipped so the pipeline works from local data and
+//! caches alone.
+//!
+//! Offline mode is switched on at run time with `HEGEL_OFFLINE=1` (or
+//! `hegel --offline`), or at compile time with the `offline` feature, in which
+//! case it cannot be switched off.
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::{Serialize, Deserialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Offline mode requested at run time
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Initialize the offline module, reading `HEGEL_OFFLINE` if set
+pub fn initialize() -> Result<()> {
+    info!("Initializing offline mode module");
+
+    if let Ok(value) = std::env::var("HEGEL_OFFLINE") {
+        set_offline(matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on"));
+    }
+    if is_offline() {
+        warn!("Running offline; unavailable features: {}", feature_list(&unavailable_features()));
+    }
+
+    info!("Offline mode module initialized successfully");
+    Ok(())
+}
+
+/// Switch offline mode on or off for the whole process
+///
+/// Has no effect on builds with the `offline` feature, which are always offline.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::SeqCst);
+}
+
+/// Whether external calls are disabled
+pub fn is_offline() -> bool {
+    cfg!(feature = "offline") || OFFLINE.load(Ordering::SeqCst)
+}
+
+/// Features that are unavailable in the current mode
+pub fn unavailable_features() -> Vec<NetworkFeature> {
+    if is_offline() {
+        NetworkFeature::ALL.to_vec()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Fail with an `OfflineError` if a network feature is unavailable
+pub fn ensure_available(feature: NetworkFeature) -> Result<()> {
+    if is_offline() {
+        Err(OfflineError { feature }.into())
+    } else {
+        Ok(())
+    }
+}
+
+fn feature_list(features: &[NetworkFeature]) -> String {
+    features.iter().map(|f| f.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+/// Subsystem that depends on an external service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkFeature {
+    /// LLM completions for AI-guided rectification and molecule queries
+    Llm,
+    /// Molecule retrieval and network building through the Python API
+    PythonApi,
+    /// Online cross-reference lookups (UniChem)
+    OnlineXrefs,
+    /// PubMed co-mention searches for literature evidence
+    Literature,
+    /// Webhook delivery to external endpoints
+    Webhooks,
+}
+
+impl NetworkFeature {
+    /// Every network feature
+    pub const ALL: [NetworkFeature; 5] = [
+        NetworkFeature::Llm,
+        NetworkFeature::PythonApi,
+        NetworkFeature::OnlineXrefs,
+        NetworkFeature::Literature,
+        NetworkFeature::Webhooks,
+    ];
+
+    /// Name used in configuration and messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkFeature::Llm => "llm",
+            NetworkFeature::PythonApi => "python_api",
+            NetworkFeature::OnlineXrefs => "online_xrefs",
+            NetworkFeature::Literature => "literature",
+            NetworkFeature::Webhooks => "webhooks",
+        }
+    }
+
+    /// External service the feature needs
+    pub fn service(&self) -> &'static str {
+        match self {
+            NetworkFeature::Llm => "the LLM provider",
+            NetworkFeature::PythonApi => "the Python API",
+            NetworkFeature::OnlineXrefs => "UniChem",
+            NetworkFeature::Literature => "PubMed",
+            NetworkFeature::Webhooks => "webhook endpoints",
+        }
+    }
+}
+
+impl fmt::Display for NetworkFeature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for NetworkFeature {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let normalized = s.trim().to_lowercase().replace('-', "_");
+        NetworkFeature::ALL.iter()
+            .find(|f| f.as_str() == normalized)
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown network feature: {}", s))
+    }
+}
+
+/// A network feature was used while offline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfflineError {
+    /// Feature that was refused
+    pub feature: NetworkFeature,
+}
+
+impl fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is unavailable in offline mode because it needs {}; unavailable features: {}",
+            self.feature, self.feature.service(), feature_list(&NetworkFeature::ALL)
+        )
+    }
+}
+
+impl Error for OfflineError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_error_lists_unavailable_features() {
+        let error: anyhow::Error = OfflineError { feature: NetworkFeature::Literature }.into();
+        assert!(error.is::<OfflineError>());
+        assert_eq!(
+            error.to_string(),
+            "literature is unavailable in offline mode because it needs PubMed; \
+             unavailable features: llm, python_api, online_xrefs, literature, webhooks"
+        );
+        assert_eq!("python-api".parse::<NetworkFeature>().unwrap(), NetworkFeature::PythonApi);
+    }
+}
@@ -0,0 +1,319 @@
+//! Evidence Plugins
+//!
+//! Third parties can ship evidence generators as shared libraries that are
+//! discovered in the plugins directory (`HEGEL_PLUGINS_DIR`) at startup, with
+//! no need to fork Hegel. A plugin exports one C function, `hegel_plugin_v1`,
+//! returning a static `PluginDescriptor`; requests and generated evidence
+//! cross the boundary as NUL-terminated JSON, so plugins can be written in
+//! any language with a C ABI.
+//!
+//! The plugin's `generate` receives `{"molecule_id": ..., "evidence": [...]}`
+//! and returns either a JSON array of evidence items or `{"error": "..."}`.
+//! Returned strings are handed back to the plugin's `free_string`. Hegel may
+//! call `generate` from several threads at once.
+
+use anyhow::{anyhow, Context, Result};
+use libloading::Library;
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::processing::evidence::Evidence;
+
+/// ABI version of `PluginDescriptor` this build understands
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Symbol a plugin library exports its descriptor under
+pub const PLUGIN_ENTRY_SYMBOL: &str = "hegel_plugin_v1";
+
+/// Environment variable naming the plugins directory
+pub const PLUGINS_DIR_ENV: &str = "HEGEL_PLUGINS_DIR";
+
+/// Metadata key recording the generator that produced an evidence item
+pub const PLUGIN_METADATA_KEY: &str = "generator";
+
+/// Initialize the plugins module, registering the plugins in `HEGEL_PLUGINS_DIR`
+pub fn initialize() -> Result<()> {
+    info!("Initializing plugins module");
+    if let Some(dir) = std::env::var_os(PLUGINS_DIR_ENV) {
+        let loaded = EvidenceGeneratorRegistry::global().load_dir(Path::new(&dir))?;
+        info!("Registered {} evidence plugins from {}", loaded, Path::new(&dir).display());
+    }
+    info!("Plugins module initialized successfully");
+    Ok(())
+}
+
+/// A source of new evidence for a molecule
+pub trait EvidenceGenerator: Send + Sync {
+    /// Name under which the generator is registered
+    fn name(&self) -> &str;
+
+    /// Evidence for a molecule, given the evidence already collected for it
+    fn generate(&self, molecule_id: &str, evidence: &[Evidence]) -> Result<Vec<Evidence>>;
+}
+
+/// Descriptor a plugin library returns from `hegel_plugin_v1`
+#[repr(C)]
+pub struct PluginDescriptor {
+    /// Must equal `PLUGIN_ABI_VERSION`
+    pub abi_version: u32,
+
+    /// Plugin name, NUL-terminated and valid for the lifetime of the library
+    pub name: *const c_char,
+
+    /// Generate evidence for a JSON request, returning a JSON string owned by the plugin
+    pub generate: unsafe extern "C" fn(request: *const c_char) -> *mut c_char,
+
+    /// Free a string returned by `generate`
+    pub free_string: unsafe extern "C" fn(value: *mut c_char),
+}
+
+// Descriptors are immutable once returned, so plugins may hand out statics.
+unsafe impl Sync for PluginDescriptor {}
+
+/// Entry point every plugin library exports
+type PluginEntry = unsafe extern "C" fn() -> *const PluginDescriptor;
+
+/// Evidence generator loaded from a shared library
+pub struct DynamicPlugin {
+    /// Plugin name, copied out of the descriptor
+    name: String,
+
+    /// Descriptor, valid while `library` is loaded
+    descriptor: *const PluginDescriptor,
+
+    /// Library the descriptor lives in, kept loaded for the plugin's lifetime
+    _library: Option<Library>,
+}
+
+// Plugins must accept calls from any thread.
+unsafe impl Send for DynamicPlugin {}
+unsafe impl Sync for DynamicPlugin {}
+
+impl DynamicPlugin {
+    /// Load a plugin library and check its descriptor
+    pub fn load(path: &Path) -> Result<Self> {
+        // Running the library's initializers is the point of loading a plugin
+        let library = unsafe { Library::new(path) }
+            .with_context(|| format!("Failed to load plugin library {}", path.display()))?;
+        let descriptor = unsafe {
+            let entry = library.get::<PluginEntry>(PLUGIN_ENTRY_SYMBOL.as_bytes())
+                .with_context(|| format!("{} does not export {}", path.display(), PLUGIN_ENTRY_SYMBOL))?;
+            entry()
+        };
+        unsafe { Self::from_descriptor(descriptor, Some(library)) }
+            .with_context(|| format!("Invalid plugin {}", path.display()))
+    }
+
+    /// Wrap a descriptor, checking its ABI version and name
+    ///
+    /// # Safety
+    ///
+    /// `descriptor` must be null or point to a descriptor that stays valid for
+    /// as long as `library` is loaded (or forever, without a library).
+    unsafe fn from_descriptor(descriptor: *const PluginDescriptor, library: Option<Library>) -> Result<Self> {
+        let plugin = descriptor.as_ref().ok_or_else(|| anyhow!("Plugin returned no descriptor"))?;
+        if plugin.abi_version != PLUGIN_ABI_VERSION {
+            return Err(anyhow!("Plugin ABI version {} is not supported (expected {})", plugin.abi_version, PLUGIN_ABI_VERSION));
+        }
+        if plugin.name.is_null() {
+            return Err(anyhow!("Plugin has no name"));
+        }
+        let name = CStr::from_ptr(plugin.name).to_str().context("Plugin name is not UTF-8")?.to_string();
+        Ok(Self { name, descriptor, _library: library })
+    }
+}
+
+impl EvidenceGenerator for DynamicPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn generate(&self, molecule_id: &str, evidence: &[Evidence]) -> Result<Vec<Evidence>> {
+        let request = serde_json::json!({ "molecule_id": molecule_id, "evidence": evidence });
+        let request = CString::new(serde_json::to_string(&request)?)?;
+
+        let response = unsafe {
+            let plugin = &*self.descriptor;
+            let raw = (plugin.generate)(request.as_ptr());
+            if raw.is_null() {
+                return Err(anyhow!("Plugin {} returned no response", self.name));
+            }
+            let response = CStr::from_ptr(raw).to_str().map(str::to_string);
+            (plugin.free_string)(raw);
+            response.with_context(|| format!("Plugin {} returned a response that is not UTF-8", self.name))?
+        };
+
+        let value: serde_json::Value = serde_json::from_str(&response)
+            .with_context(|| format!("Plugin {} returned invalid JSON", self.name))?;
+        if let Some(error) = value.get("error") {
+            return Err(anyhow!("Plugin {} failed: {}", self.name, error.as_str().unwrap_or_default()));
+        }
+        serde_json::from_value(value).with_context(|| format!("Plugin {} returned malformed evidence", self.name))
+    }
+}
+
+/// Registry of evidence generators keyed by name
+pub struct EvidenceGeneratorRegistry {
+    /// Registered generators
+    generators: RwLock<HashMap<String, Arc<dyn EvidenceGenerator>>>,
+}
+
+impl EvidenceGeneratorRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            generators: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the process-wide registry that startup plugins are registered in
+    pub fn global() -> Arc<EvidenceGeneratorRegistry> {
+        static REGISTRY: OnceLock<Arc<EvidenceGeneratorRegistry>> = OnceLock::new();
+        REGISTRY.get_or_init(|| Arc::new(EvidenceGeneratorRegistry::new())).clone()
+    }
+
+    /// Register a generator, replacing any generator with the same name
+    pub fn register(&self, generator: Arc<dyn EvidenceGenerator>) {
+        let name = generator.name().to_string();
+        debug!("Registering evidence generator: {}", name);
+        self.generators.write().unwrap().insert(name, generator);
+    }
+
+    /// Register every plugin library in a directory, returning how many were registered
+    ///
+    /// Libraries that fail to load are logged and skipped, so one broken
+    /// plugin doesn't keep the others from starting.
+    pub fn load_dir(&self, dir: &Path) -> Result<usize> {
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read plugins directory: {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(std::env::consts::DLL_EXTENSION))
+            .collect();
+        paths.sort();
+
+        let mut loaded = 0;
+        for path in paths {
+            match DynamicPlugin::load(&path) {
+                Ok(plugin) => {
+                    info!("Loaded evidence plugin {} from {}", plugin.name(), path.display());
+                    self.register(Arc::new(plugin));
+                    loaded += 1;
+                }
+                Err(e) => warn!("Skipping plugin {}: {:#}", path.display(), e),
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Names of all registered generators
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.generators.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Whether no generator is registered
+    pub fn is_empty(&self) -> bool {
+        self.generators.read().unwrap().is_empty()
+    }
+
+    /// Run every generator for a molecule, in name order
+    ///
+    /// Items for another molecule or with a confidence outside 0 - 1 are
+    /// dropped, and the rest are tagged with the generator's name. A failing
+    /// generator is logged and contributes nothing.
+    pub fn generate(&self, molecule_id: &str, evidence: &[Evidence]) -> Vec<Evidence> {
+        let mut generators: Vec<Arc<dyn EvidenceGenerator>> = self.generators.read().unwrap().values().cloned().collect();
+        generators.sort_by(|a, b| a.name().cmp(b.name()));
+
+        let mut generated = Vec::new();
+        for generator in generators {
+            let items = match generator.generate(molecule_id, evidence) {
+                Ok(items) => items,
+                Err(e) => {
+                    warn!("Evidence generator {} failed for {}: {:#}", generator.name(), molecule_id, e);
+                    continue;
+                }
+            };
+            for mut item in items {
+                if item.molecule_id != molecule_id || !(0.0..=1.0).contains(&item.confidence) {
+                    warn!("Dropping evidence {} from generator {}: wrong molecule or confidence out of range",
+                          item.id, generator.name());
+                    continue;
+                }
+                item.metadata.insert(PLUGIN_METADATA_KEY.to_string(), serde_json::json!(generator.name()));
+                generated.push(item);
+            }
+        }
+        generated
+    }
+}
+
+impl Default for EvidenceGeneratorRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    /// A plugin written against the C ABI, linked in rather than loaded
+    mod echo_plugin {
+        use super::*;
+
+        unsafe extern "C" fn generate(request: *const c_char) -> *mut c_char {
+            let request: serde_json::Value = serde_json::from_str(CStr::from_ptr(request).to_str().unwrap()).unwrap();
+            let molecule_id = request["molecule_id"].as_str().unwrap();
+            let mut evidence = Evidence::manual(molecule_id, EvidenceType::Structural, 0.7, None, "echo").unwrap();
+            evidence.id = format!("echo-{}", molecule_id);
+            let bogus = Evidence::manual("someone-else", EvidenceType::Structural, 0.9, None, "echo").unwrap();
+            CString::new(serde_json::to_string(&vec![evidence, bogus]).unwrap()).unwrap().into_raw()
+        }
+
+        unsafe extern "C" fn free_string(value: *mut c_char) {
+            drop(CString::from_raw(value));
+        }
+
+        pub static DESCRIPTOR: PluginDescriptor = PluginDescriptor {
+            abi_version: PLUGIN_ABI_VERSION,
+            name: c"echo".as_ptr(),
+            generate,
+            free_string,
+        };
+    }
+
+    #[test]
+    fn test_c_abi_plugin_generates_tagged_evidence() {
+        let plugin = unsafe { DynamicPlugin::from_descriptor(&echo_plugin::DESCRIPTOR, None) }.unwrap();
+        let registry = EvidenceGeneratorRegistry::new();
+        registry.register(Arc::new(plugin));
+        assert_eq!(registry.names(), vec!["echo"]);
+
+        let generated = registry.generate("caffeine", &[]);
+        assert_eq!(generated.len(), 1);
+        assert_eq!(generated[0].id, "echo-caffeine");
+        assert_eq!(generated[0].metadata[PLUGIN_METADATA_KEY], "echo");
+    }
+
+    #[test]
+    fn test_rejects_incompatible_descriptor() {
+        unsafe extern "C" fn free_string(_: *mut c_char) {}
+        unsafe extern "C" fn generate(_: *const c_char) -> *mut c_char {
+            std::ptr::null_mut()
+        }
+        static OLD: PluginDescriptor = PluginDescriptor {
+            abi_version: 0,
+            name: c"old".as_ptr(),
+            generate,
+            free_string,
+        };
+        assert!(unsafe { DynamicPlugin::from_descriptor(&OLD, None) }.is_err());
+        assert!(unsafe { DynamicPlugin::from_descriptor(std::ptr::null(), None) }.is_err());
+    }
+}
@@ -0,0 +1,535 @@
+//! Cross-Run Feature Alignment
+//!
+//! Longitudinal studies measure the same samples in runs months apart, and
+//! over that time retention times shift and mass calibration wanders. Before
+//! evidence from several runs can be aggregated, features that are the same
+//! compound must be linked. Each run is first corrected against a reference
+//! run: anchor features, unambiguous pairs within a narrow mass window, give
+//! the run's mass offset (their median ppm error) and its retention time
+//! drift (a running median of the shifts, interpolated between anchors).
+//! Corrected features are then greedily matched to consensus features by
+//! their normalized mass and retention time distance, with at most one
+//! feature per run in each consensus feature. Evidence from the runs is
+//! finally merged per consensus feature and candidate molecule.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::processing::drift::RUN_METADATA_KEY;
+use crate::processing::evidence::{Evidence, EvidenceType};
+use crate::processing::features::FeatureTable;
+use crate::processing::mass_accuracy::ppm_error;
+use crate::processing::units::{Quantity, Unit};
+
+/// Source of evidence merged across aligned runs
+pub const ALIGNMENT_SOURCE: &str = "feature-alignment";
+
+/// Metadata key holding the consensus feature an evidence item was aligned to
+pub const ALIGNED_FEATURE_KEY: &str = "aligned_feature_id";
+
+/// Initialize the feature alignment module
+pub fn initialize() -> Result<()> {
+    info!("Initializing feature alignment module");
+    info!("Feature alignment module initialized successfully");
+    Ok(())
+}
+
+/// Features of one instrument run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+    /// Run ID, matching the `run_id` metadata of the run's evidence
+    pub id: String,
+
+    /// When the run was acquired
+    #[serde(default)]
+    pub acquired_at: Option<DateTime<Utc>>,
+
+    /// Features detected in the run
+    pub table: FeatureTable,
+}
+
+/// Options for aligning runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignmentOptions {
+    /// Mass tolerance for linking corrected features, in ppm or Daltons
+    pub mass_tolerance: Quantity,
+
+    /// Retention time tolerance for linking corrected features
+    pub rt_tolerance: Quantity,
+
+    /// Mass tolerance for anchor pairs, before mass correction
+    pub anchor_mass_tolerance: Quantity,
+
+    /// Largest retention time drift searched for anchor pairs
+    pub max_rt_drift: Quantity,
+
+    /// Fewest anchors for a drift curve; with fewer, a constant shift is used
+    pub min_anchors: usize,
+
+    /// Anchors in the running median that smooths the drift curve
+    pub smoothing_window: usize,
+
+    /// Run the others are corrected against; the first run when unset
+    #[serde(default)]
+    pub reference_run: Option<String>,
+}
+
+impl Default for AlignmentOptions {
+    fn default() -> Self {
+        Self {
+            mass_tolerance: Quantity::ppm(10.0),
+            rt_tolerance: Quantity::minutes(0.2),
+            anchor_mass_tolerance: Quantity::ppm(15.0),
+            max_rt_drift: Quantity::minutes(1.0),
+            min_anchors: 5,
+            smoothing_window: 5,
+            reference_run: None,
+        }
+    }
+}
+
+/// Correction mapping a run onto the reference run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftCorrection {
+    /// Run the correction applies to
+    pub run_id: String,
+
+    /// Median mass error of the run against the reference, in ppm
+    pub mass_offset_ppm: f64,
+
+    /// Retention time shift in minutes at anchor retention times, as `(rt, shift)` sorted by rt
+    pub rt_shifts: Vec<(f64, f64)>,
+
+    /// Anchor pairs the correction was estimated from
+    pub anchors: usize,
+}
+
+impl DriftCorrection {
+    /// No correction, as for the reference run
+    pub fn identity(run_id: &str) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            mass_offset_ppm: 0.0,
+            rt_shifts: Vec::new(),
+            anchors: 0,
+        }
+    }
+
+    /// m/z on the reference run's calibration
+    pub fn corrected_mz(&self, mz: f64) -> f64 {
+        mz / (1.0 + self.mass_offset_ppm * 1e-6)
+    }
+
+    /// Retention time in minutes on the reference run's time scale
+    ///
+    /// The shift is interpolated linearly between anchors and held constant
+    /// beyond the first and last anchor.
+    pub fn corrected_rt(&self, rt: f64) -> f64 {
+        let shift = match self.rt_shifts.as_slice() {
+            [] => 0.0,
+            [(_, shift)] => *shift,
+            shifts => {
+                let after = shifts.partition_point(|(anchor_rt, _)| *anchor_rt < rt);
+                if after == 0 {
+                    shifts[0].1
+                } else if after == shifts.len() {
+                    shifts[shifts.len() - 1].1
+                } else {
+                    let (rt0, shift0) = shifts[after - 1];
+                    let (rt1, shift1) = shifts[after];
+                    if rt1 > rt0 { shift0 + (shift1 - shift0) * (rt - rt0) / (rt1 - rt0) } else { shift0 }
+                }
+            }
+        };
+        rt + shift
+    }
+}
+
+/// A run's feature linked into a consensus feature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignedMember {
+    /// Run the feature was detected in
+    pub run_id: String,
+
+    /// Feature ID within the run
+    pub feature_id: String,
+
+    /// Observed m/z
+    pub mz: f64,
+
+    /// Observed retention time in minutes
+    pub retention_time: f64,
+
+    /// Retention time in minutes after drift correction
+    pub corrected_rt: f64,
+}
+
+/// Features of several runs judged to be the same compound
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignedFeature {
+    /// Consensus feature ID
+    pub id: String,
+
+    /// Mean corrected m/z of the members
+    pub mz: f64,
+
+    /// Mean corrected retention time of the members, in minutes
+    pub retention_time: f64,
+
+    /// Linked features, at most one per run
+    pub members: Vec<AlignedMember>,
+}
+
+/// Result of aligning runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureAlignment {
+    /// Run the others were corrected against
+    pub reference_run: String,
+
+    /// Correction of every run, in run order
+    pub corrections: Vec<DriftCorrection>,
+
+    /// Consensus features
+    pub features: Vec<AlignedFeature>,
+}
+
+impl FeatureAlignment {
+    /// Consensus feature a run's feature was linked into
+    pub fn aligned_id(&self, run_id: &str, feature_id: &str) -> Option<&str> {
+        self.features.iter()
+            .find(|f| f.members.iter().any(|m| m.run_id == run_id && m.feature_id == feature_id))
+            .map(|f| f.id.as_str())
+    }
+
+    /// Consensus features found in at least `runs` runs
+    pub fn found_in(&self, runs: usize) -> impl Iterator<Item = &AlignedFeature> {
+        self.features.iter().filter(move |f| f.members.len() >= runs)
+    }
+
+    /// Merge evidence of aligned features across runs
+    ///
+    /// Evidence items carrying `run_id` and `feature_id` metadata for the
+    /// same consensus feature, candidate molecule and adduct become one item
+    /// whose confidence is the mean over the runs and whose data lists the
+    /// runs. Items of unaligned features pass through unchanged.
+    pub fn merge_evidence(&self, evidence: Vec<Evidence>) -> Vec<Evidence> {
+        let index: HashMap<(&str, &str), &AlignedFeature> = self.features.iter()
+            .flat_map(|f| f.members.iter().map(move |m| ((m.run_id.as_str(), m.feature_id.as_str()), f)))
+            .collect();
+
+        let mut groups: BTreeMap<(String, String, String), Vec<Evidence>> = BTreeMap::new();
+        let mut merged = Vec::new();
+        for item in evidence {
+            let key = {
+                let run = item.metadata.get(RUN_METADATA_KEY).and_then(|v| v.as_str());
+                let feature = item.metadata.get("feature_id").and_then(|v| v.as_str());
+                match (run, feature) {
+                    (Some(run), Some(feature)) => index.get(&(run, feature)).map(|aligned| {
+                        let adduct = item.metadata.get("adduct").and_then(|v| v.as_str()).unwrap_or_default();
+                        (aligned.id.clone(), item.molecule_id.clone(), adduct.to_string())
+                    }),
+                    _ => None,
+                }
+            };
+            match key {
+                Some(key) => groups.entry(key).or_default().push(item),
+                None => merged.push(item),
+            }
+        }
+
+        let by_id: HashMap<&str, &AlignedFeature> = self.features.iter().map(|f| (f.id.as_str(), f)).collect();
+        for ((aligned_id, molecule_id, adduct), items) in groups {
+            let aligned = by_id[aligned_id.as_str()];
+            let confidence = items.iter().map(|e| e.confidence).sum::<f64>() / items.len() as f64;
+            let runs: Vec<serde_json::Value> = items.iter()
+                .map(|e| serde_json::json!({
+                    "run_id": e.metadata.get(RUN_METADATA_KEY),
+                    "feature_id": e.metadata.get("feature_id"),
+                    "evidence_id": e.id,
+                    "confidence": e.confidence,
+                }))
+                .collect();
+
+            let mut metadata = HashMap::new();
+            metadata.insert(ALIGNED_FEATURE_KEY.to_string(), serde_json::json!(aligned_id));
+            metadata.insert("feature_id".to_string(), serde_json::json!(aligned_id));
+            metadata.insert("aligned_runs".to_string(), serde_json::json!(items.len()));
+            if !adduct.is_empty() {
+                metadata.insert("adduct".to_string(), serde_json::json!(adduct));
+            }
+            merged.push(Evidence {
+                id: format!("{}-{}-{}-{}", ALIGNMENT_SOURCE, aligned_id, molecule_id, adduct),
+                molecule_id,
+                evidence_type: items.first().map(|e| e.evidence_type).unwrap_or(EvidenceType::MassSpec),
+                source: ALIGNMENT_SOURCE.to_string(),
+                confidence,
+                data: serde_json::json!({
+                    "mz": aligned.mz,
+                    "retention_time": Quantity::minutes(aligned.retention_time),
+                    "runs": runs,
+                }),
+                metadata,
+                timestamp: Utc::now(),
+            });
+        }
+        merged
+    }
+}
+
+/// A feature ready for alignment, in minutes and before correction
+#[derive(Debug, Clone)]
+struct RunFeature {
+    id: String,
+    mz: f64,
+    rt: f64,
+}
+
+/// Features of a run with retention times in minutes
+fn run_features(run: &Run) -> Result<Vec<RunFeature>> {
+    run.table.features.iter()
+        .map(|f| Ok(RunFeature { id: f.id.clone(), mz: f.mz, rt: f.retention_time.value_in(Unit::Minute)? }))
+        .collect()
+}
+
+/// Align the features of several runs
+pub fn align_runs(runs: &[Run], options: &AlignmentOptions) -> Result<FeatureAlignment> {
+    if runs.is_empty() {
+        return Err(anyhow!("No runs to align"));
+    }
+    let mut seen = HashSet::new();
+    if let Some(duplicate) = runs.iter().find(|run| !seen.insert(run.id.as_str())) {
+        return Err(anyhow!("Run {} is given more than once", duplicate.id));
+    }
+    let reference = match &options.reference_run {
+        Some(id) => runs.iter().find(|run| &run.id == id).ok_or_else(|| anyhow!("Unknown reference run: {}", id))?,
+        None => &runs[0],
+    };
+    let rt_tolerance = options.rt_tolerance.value_in(Unit::Minute)?;
+    let reference_features = run_features(reference)?;
+
+    let mut corrections = Vec::with_capacity(runs.len());
+    let mut consensus: Vec<AlignedFeature> = Vec::new();
+    // The reference seeds the consensus features, then the others follow in run order
+    let order = std::iter::once(reference).chain(runs.iter().filter(|run| run.id != reference.id));
+    for run in order {
+        let features = run_features(run)?;
+        let correction = if run.id == reference.id {
+            DriftCorrection::identity(&run.id)
+        } else {
+            estimate_drift(&run.id, &features, &reference_features, options)?
+        };
+
+        // Every pair within tolerance, closest first
+        let mut pairs = Vec::new();
+        for (i, feature) in features.iter().enumerate() {
+            let (mz, rt) = (correction.corrected_mz(feature.mz), correction.corrected_rt(feature.rt));
+            let mz_window = options.mass_tolerance.to_at(Unit::Dalton, mz)?.value;
+            for (j, aligned) in consensus.iter().enumerate() {
+                let (dmz, drt) = ((mz - aligned.mz).abs(), (rt - aligned.retention_time).abs());
+                if dmz <= mz_window && drt <= rt_tolerance {
+                    pairs.push(((dmz / mz_window).powi(2) + (drt / rt_tolerance).powi(2), i, j));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let (mut linked, mut filled) = (vec![false; features.len()], vec![false; consensus.len()]);
+        for (_, i, j) in pairs {
+            if linked[i] || filled[j] {
+                continue;
+            }
+            linked[i] = true;
+            filled[j] = true;
+            let aligned = &mut consensus[j];
+            let n = aligned.members.len() as f64;
+            aligned.mz = (aligned.mz * n + correction.corrected_mz(features[i].mz)) / (n + 1.0);
+            aligned.retention_time = (aligned.retention_time * n + correction.corrected_rt(features[i].rt)) / (n + 1.0);
+            aligned.members.push(member(&run.id, &features[i], &correction));
+        }
+        for (feature, _) in features.iter().zip(&linked).filter(|(_, linked)| !**linked) {
+            consensus.push(AlignedFeature {
+                id: String::new(),
+                mz: correction.corrected_mz(feature.mz),
+                retention_time: correction.corrected_rt(feature.rt),
+                members: vec![member(&run.id, feature, &correction)],
+            });
+        }
+        debug!("Aligned run {}: {} features, {} consensus features so far", run.id, features.len(), consensus.len());
+        corrections.push(correction);
+    }
+
+    // Corrections are reported in the order the runs were given
+    corrections.sort_by_key(|c| runs.iter().position(|run| run.id == c.run_id));
+    consensus.sort_by(|a, b| a.mz.total_cmp(&b.mz).then_with(|| a.retention_time.total_cmp(&b.retention_time)));
+    for (index, aligned) in consensus.iter_mut().enumerate() {
+        aligned.id = format!("AF{:05}", index + 1);
+    }
+
+    let shared = consensus.iter().filter(|f| f.members.len() > 1).count();
+    info!("Aligned {} runs into {} consensus features, {} found in more than one run",
+          runs.len(), consensus.len(), shared);
+    Ok(FeatureAlignment {
+        reference_run: reference.id.clone(),
+        corrections,
+        features: consensus,
+    })
+}
+
+/// Member record of a feature under a run's correction
+fn member(run_id: &str, feature: &RunFeature, correction: &DriftCorrection) -> AlignedMember {
+    AlignedMember {
+        run_id: run_id.to_string(),
+        feature_id: feature.id.clone(),
+        mz: feature.mz,
+        retention_time: feature.rt,
+        corrected_rt: correction.corrected_rt(feature.rt),
+    }
+}
+
+/// Estimate a run's mass offset and retention time drift against the reference
+fn estimate_drift(
+    run_id: &str,
+    features: &[RunFeature],
+    reference: &[RunFeature],
+    options: &AlignmentOptions,
+) -> Result<DriftCorrection> {
+    let max_drift = options.max_rt_drift.value_in(Unit::Minute)?;
+
+    // Candidate partners of each feature; anchors are pairs that are each other's only candidate
+    let mut partners: Vec<Vec<usize>> = vec![Vec::new(); features.len()];
+    let mut reverse: Vec<usize> = vec![0; reference.len()];
+    for (i, feature) in features.iter().enumerate() {
+        for (j, candidate) in reference.iter().enumerate() {
+            if (feature.rt - candidate.rt).abs() <= max_drift
+                && options.anchor_mass_tolerance.matches_mz(feature.mz, candidate.mz)? {
+                partners[i].push(j);
+                reverse[j] += 1;
+            }
+        }
+    }
+    let mut anchors: Vec<(f64, f64, f64)> = partners.iter().enumerate()
+        .filter_map(|(i, candidates)| match candidates.as_slice() {
+            [j] if reverse[*j] == 1 => Some((
+                features[i].rt,
+                reference[*j].rt - features[i].rt,
+                ppm_error(features[i].mz, reference[*j].mz),
+            )),
+            _ => None,
+        })
+        .collect();
+    anchors.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    if anchors.is_empty() {
+        warn!("No anchor features between run {} and the reference; aligning it uncorrected", run_id);
+        return Ok(DriftCorrection::identity(run_id));
+    }
+    let mass_offset_ppm = median(anchors.iter().map(|a| a.2).collect());
+    let rt_shifts = if anchors.len() < options.min_anchors {
+        warn!("Only {} anchor features for run {}; correcting by a constant shift", anchors.len(), run_id);
+        vec![(anchors[anchors.len() / 2].0, median(anchors.iter().map(|a| a.1).collect()))]
+    } else {
+        let half = options.smoothing_window.max(1) / 2;
+        (0..anchors.len())
+            .map(|k| {
+                let window = &anchors[k.saturating_sub(half)..(k + half + 1).min(anchors.len())];
+                (anchors[k].0, median(window.iter().map(|a| a.1).collect()))
+            })
+            .collect()
+    };
+    debug!("Run {}: {} anchors, mass offset {:+.2} ppm", run_id, anchors.len(), mass_offset_ppm);
+
+    Ok(DriftCorrection {
+        run_id: run_id.to_string(),
+        mass_offset_ppm,
+        rt_shifts,
+        anchors: anchors.len(),
+    })
+}
+
+/// Median of a non-empty list
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::features::{Feature, FeatureTableFormat};
+
+    fn run(id: &str, features: &[(&str, f64, f64)]) -> Run {
+        Run {
+            id: id.to_string(),
+            acquired_at: None,
+            table: FeatureTable {
+                format: FeatureTableFormat::Mzmine,
+                samples: vec!["pool".to_string()],
+                features: features.iter()
+                    .map(|(id, mz, rt)| Feature {
+                        id: id.to_string(),
+                        mz: *mz,
+                        retention_time: Quantity::minutes(*rt),
+                        intensities: vec![Some(1000.0)],
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    /// Six well-separated compounds, as (m/z, retention time)
+    const COMPOUNDS: [(f64, f64); 6] = [
+        (132.0768, 1.5), (166.0863, 3.0), (181.0707, 4.5), (205.0972, 6.0), (268.1040, 7.5), (304.2999, 9.0),
+    ];
+
+    #[test]
+    fn corrects_drift_before_linking_runs() {
+        let january = run("jan", &COMPOUNDS.iter().enumerate()
+            .map(|(i, (mz, rt))| (["a", "b", "c", "d", "e", "f"][i], *mz, *rt)).collect::<Vec<_>>());
+        // Months later: masses read 3 ppm high and retention times drift by up to 0.5 min
+        let june = run("jun", &COMPOUNDS.iter().enumerate()
+            .map(|(i, (mz, rt))| (["u", "v", "w", "x", "y", "z"][i], mz * (1.0 + 3e-6), rt + 0.2 + 0.03 * rt)).collect::<Vec<_>>());
+
+        let alignment = align_runs(&[january, june], &AlignmentOptions::default()).unwrap();
+        let correction = &alignment.corrections[1];
+        assert_eq!(correction.anchors, 6);
+        assert!((correction.mass_offset_ppm - 3.0).abs() < 0.1);
+        assert!((correction.corrected_rt(4.5 + 0.2 + 0.135) - 4.5).abs() < 0.05);
+
+        assert_eq!(alignment.features.len(), 6);
+        assert_eq!(alignment.found_in(2).count(), 6);
+        assert_eq!(alignment.aligned_id("jun", "w"), alignment.aligned_id("jan", "c"));
+    }
+
+    #[test]
+    fn merges_evidence_of_aligned_features() {
+        let alignment = align_runs(
+            &[run("jan", &[("a", 181.0707, 4.5)]), run("jun", &[("u", 181.0708, 4.55)])],
+            &AlignmentOptions::default(),
+        ).unwrap();
+        let evidence = |run: &str, feature: &str, confidence: f64| Evidence {
+            id: format!("{}-{}", run, feature),
+            molecule_id: "glucose".to_string(),
+            evidence_type: EvidenceType::MassSpec,
+            source: "feature-table:mzmine".to_string(),
+            confidence,
+            data: serde_json::Value::Null,
+            metadata: HashMap::from([
+                (RUN_METADATA_KEY.to_string(), serde_json::json!(run)),
+                ("feature_id".to_string(), serde_json::json!(feature)),
+                ("adduct".to_string(), serde_json::json!("[M+H]+")),
+            ]),
+            timestamp: Utc::now(),
+        };
+
+        let merged = alignment.merge_evidence(vec![evidence("jan", "a", 0.8), evidence("jun", "u", 0.6), evidence("jun", "missing", 0.5)]);
+        assert_eq!(merged.len(), 2);
+        let aligned = merged.iter().find(|e| e.source == ALIGNMENT_SOURCE).unwrap();
+        assert!((aligned.confidence - 0.7).abs() < 1e-9);
+        assert_eq!(aligned.metadata["aligned_runs"], serde_json::json!(2));
+        assert_eq!(aligned.data["runs"].as_array().unwrap().len(), 2);
+    }
+}
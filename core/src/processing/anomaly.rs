@@ -0,0 +1,423 @@
+//! Evidence Anomaly Detection
+//!
+//! Flags evidence whose values are impossible or far outside what its source
+//! normally reports, such as a 400 ppm mass error from an instrument that
+//! usually stays within 3 ppm, or a sudden intensity spike. Each source's
+//! history is kept as running statistics per numeric field; flagged evidence
+//! is held in a quarantine, out of integration, until someone releases or
+//! rejects it.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::processing::evidence::Evidence;
+use crate::projects::scoped_key;
+
+/// Initialize the evidence anomaly detection module
+pub fn initialize() -> Result<()> {
+    info!("Initializing evidence anomaly detection module");
+    info!("Evidence anomaly detection module initialized successfully");
+    Ok(())
+}
+
+/// Running mean and variance of a field (Welford's algorithm)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RunningStats {
+    /// Number of observations
+    pub count: u64,
+
+    /// Mean of the observations
+    pub mean: f64,
+
+    /// Sum of squared deviations from the mean
+    m2: f64,
+}
+
+impl RunningStats {
+    /// Add an observation
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Sample standard deviation
+    pub fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+}
+
+/// Why a value was flagged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Outside the physically or logically possible range
+    OutOfRange {
+        /// Lowest allowed value
+        min: f64,
+        /// Highest allowed value
+        max: f64,
+    },
+    /// Far from what the source usually reports
+    Deviation {
+        /// Distance from the source's mean in standard deviations
+        z_score: f64,
+        /// Source's mean for the field
+        mean: f64,
+        /// Source's standard deviation for the field
+        std_dev: f64,
+    },
+}
+
+/// Anomalous value found in an evidence item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyFinding {
+    /// Field holding the value (`confidence` or a numeric field of the data)
+    pub field: String,
+
+    /// Flagged value
+    pub value: f64,
+
+    /// Why it was flagged
+    #[serde(flatten)]
+    pub kind: AnomalyKind,
+}
+
+impl std::fmt::Display for AnomalyFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            AnomalyKind::OutOfRange { min, max } => {
+                write!(f, "{} = {} outside [{}, {}]", self.field, self.value, min, max)
+            }
+            AnomalyKind::Deviation { z_score, mean, std_dev } => {
+                write!(f, "{} = {} is {:.1} standard deviations from the source's mean {:.3} (sd {:.3})",
+                       self.field, self.value, z_score, mean, std_dev)
+            }
+        }
+    }
+}
+
+/// Detector comparing evidence against hard limits and each source's history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyDetector {
+    /// Distance from a source's mean, in standard deviations, that is flagged
+    pub z_threshold: f64,
+
+    /// Observations a source needs before deviations from it are flagged
+    pub min_history: u64,
+
+    /// Allowed range of fields, whatever the source
+    pub limits: HashMap<String, (f64, f64)>,
+
+    /// Statistics per source and field
+    history: HashMap<String, HashMap<String, RunningStats>>,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self {
+            z_threshold: 4.0,
+            min_history: 20,
+            limits: HashMap::from([
+                ("confidence".to_string(), (0.0, 1.0)),
+                ("mass_error_ppm".to_string(), (-100.0, 100.0)),
+                ("intensity".to_string(), (0.0, f64::INFINITY)),
+            ]),
+            history: HashMap::new(),
+        }
+    }
+}
+
+impl AnomalyDetector {
+    /// Set how far from a source's mean a value may be
+    pub fn with_z_threshold(mut self, z_threshold: f64) -> Self {
+        self.z_threshold = z_threshold;
+        self
+    }
+
+    /// Set how much history a source needs before deviations are flagged
+    pub fn with_min_history(mut self, min_history: u64) -> Self {
+        self.min_history = min_history;
+        self
+    }
+
+    /// Allow a field only within `[min, max]`
+    pub fn with_limit(mut self, field: &str, min: f64, max: f64) -> Self {
+        self.limits.insert(field.to_string(), (min, max));
+        self
+    }
+
+    /// Statistics of a source's field, if any have been recorded
+    pub fn stats(&self, source: &str, field: &str) -> Option<&RunningStats> {
+        self.history.get(source)?.get(field)
+    }
+
+    /// Anomalies in an evidence item, without recording it
+    pub fn check(&self, evidence: &Evidence) -> Vec<AnomalyFinding> {
+        let mut findings = Vec::new();
+        for (field, value) in numeric_fields(evidence) {
+            if let Some(&(min, max)) = self.limits.get(&field) {
+                if !(min..=max).contains(&value) {
+                    findings.push(AnomalyFinding { field, value, kind: AnomalyKind::OutOfRange { min, max } });
+                    continue;
+                }
+            }
+            if let Some(stats) = self.stats(&evidence.source, &field) {
+                let std_dev = stats.std_dev();
+                if stats.count >= self.min_history && std_dev > 0.0 {
+                    let z_score = (value - stats.mean) / std_dev;
+                    if z_score.abs() > self.z_threshold {
+                        findings.push(AnomalyFinding {
+                            field,
+                            value,
+                            kind: AnomalyKind::Deviation { z_score, mean: stats.mean, std_dev },
+                        });
+                    }
+                }
+            }
+        }
+        findings
+    }
+
+    /// Add an evidence item to its source's history
+    pub fn observe(&mut self, evidence: &Evidence) {
+        let fields = self.history.entry(evidence.source.clone()).or_default();
+        for (field, value) in numeric_fields(evidence) {
+            fields.entry(field).or_default().push(value);
+        }
+    }
+
+    /// Check an evidence item, learning from it only if it looks normal
+    ///
+    /// Keeping anomalies out of the history stops a burst of bad values from
+    /// becoming the new normal.
+    pub fn inspect(&mut self, evidence: &Evidence) -> Vec<AnomalyFinding> {
+        let findings = self.check(evidence);
+        if findings.is_empty() {
+            self.observe(evidence);
+        } else {
+            debug!("Evidence {} from {} is anomalous: {:?}", evidence.id, evidence.source, findings);
+        }
+        findings
+    }
+}
+
+/// Confidence and the top-level numeric fields of an evidence item's data
+///
+/// Data stored as a JSON string, as in Neo4j, is parsed first.
+fn numeric_fields(evidence: &Evidence) -> Vec<(String, f64)> {
+    let mut fields = vec![("confidence".to_string(), evidence.confidence)];
+    let parsed;
+    let data = match &evidence.data {
+        serde_json::Value::String(s) => match serde_json::from_str::<serde_json::Value>(s) {
+            Ok(value) => {
+                parsed = value;
+                &parsed
+            }
+            Err(_) => return fields,
+        },
+        other => other,
+    };
+    if let Some(object) = data.as_object() {
+        fields.extend(object.iter().filter_map(|(key, value)| Some((key.clone(), value.as_f64()?))));
+    }
+    fields
+}
+
+/// Review state of a quarantined item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineStatus {
+    /// Awaiting review; kept out of integration
+    Pending,
+    /// Reviewed and accepted; integrated again
+    Released,
+    /// Reviewed and confirmed bad; kept out for good
+    Rejected,
+}
+
+/// Decision of a reviewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    /// Accept the evidence despite the findings
+    Release,
+    /// Keep the evidence out of integration
+    Reject,
+}
+
+/// Evidence held back from integration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedEvidence {
+    /// Project the evidence belongs to
+    pub project_id: String,
+
+    /// The evidence item
+    pub evidence: Evidence,
+
+    /// Why it was quarantined
+    pub findings: Vec<AnomalyFinding>,
+
+    /// When it was quarantined
+    pub quarantined_at: chrono::DateTime<chrono::Utc>,
+
+    /// Review state
+    pub status: QuarantineStatus,
+
+    /// Who resolved it
+    pub resolved_by: Option<String>,
+
+    /// When it was resolved
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Reviewer's note
+    pub note: Option<String>,
+}
+
+/// Quarantined evidence of all projects
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuarantineStore {
+    /// Items by project-scoped evidence ID
+    items: HashMap<String, QuarantinedEvidence>,
+}
+
+impl QuarantineStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold an evidence item back for review
+    ///
+    /// An item that was already reviewed keeps its resolution.
+    pub fn quarantine(&mut self, project_id: &str, evidence: Evidence, findings: Vec<AnomalyFinding>) {
+        let key = scoped_key(project_id, &evidence.id);
+        if self.items.get(&key).is_some_and(|item| item.status != QuarantineStatus::Pending) {
+            return;
+        }
+        warn!("Quarantining evidence {} of molecule {}: {}", evidence.id, evidence.molecule_id,
+              findings.iter().map(|f| f.to_string()).collect::<Vec<_>>().join("; "));
+        self.items.insert(key, QuarantinedEvidence {
+            project_id: project_id.to_string(),
+            evidence,
+            findings,
+            quarantined_at: chrono::Utc::now(),
+            status: QuarantineStatus::Pending,
+            resolved_by: None,
+            resolved_at: None,
+            note: None,
+        });
+    }
+
+    /// Get a quarantined item
+    pub fn get(&self, project_id: &str, evidence_id: &str) -> Option<&QuarantinedEvidence> {
+        self.items.get(&scoped_key(project_id, evidence_id))
+    }
+
+    /// Whether an evidence item must be kept out of integration
+    pub fn is_held(&self, project_id: &str, evidence_id: &str) -> bool {
+        self.get(project_id, evidence_id).is_some_and(|item| item.status != QuarantineStatus::Released)
+    }
+
+    /// Whether an evidence item was reviewed and released
+    pub fn is_released(&self, project_id: &str, evidence_id: &str) -> bool {
+        self.get(project_id, evidence_id).is_some_and(|item| item.status == QuarantineStatus::Released)
+    }
+
+    /// Items of a project, optionally only those in one state, oldest first
+    pub fn list(&self, project_id: &str, status: Option<QuarantineStatus>) -> Vec<&QuarantinedEvidence> {
+        let mut items: Vec<&QuarantinedEvidence> = self.items.values()
+            .filter(|item| item.project_id == project_id)
+            .filter(|item| status.is_none_or(|s| item.status == s))
+            .collect();
+        items.sort_by(|a, b| a.quarantined_at.cmp(&b.quarantined_at).then_with(|| a.evidence.id.cmp(&b.evidence.id)));
+        items
+    }
+
+    /// Record a reviewer's decision on a pending item
+    pub fn resolve(
+        &mut self,
+        project_id: &str,
+        evidence_id: &str,
+        resolution: Resolution,
+        resolved_by: &str,
+        note: Option<String>,
+    ) -> Result<&QuarantinedEvidence> {
+        let item = self.items.get_mut(&scoped_key(project_id, evidence_id))
+            .ok_or_else(|| anyhow!("Evidence not in quarantine: {}", evidence_id))?;
+        if item.status != QuarantineStatus::Pending {
+            return Err(anyhow!("Evidence {} was already resolved", evidence_id));
+        }
+        item.status = match resolution {
+            Resolution::Release => QuarantineStatus::Released,
+            Resolution::Reject => QuarantineStatus::Rejected,
+        };
+        item.resolved_by = Some(resolved_by.to_string());
+        item.resolved_at = Some(chrono::Utc::now());
+        item.note = note;
+        info!("Evidence {} {:?} by {}", evidence_id, item.status, resolved_by);
+        Ok(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    fn evidence(id: &str, mass_error_ppm: f64) -> Evidence {
+        Evidence {
+            id: id.to_string(),
+            molecule_id: "m1".to_string(),
+            evidence_type: EvidenceType::MassSpec,
+            source: "orbitrap".to_string(),
+            confidence: 0.9,
+            data: serde_json::json!({"mass_error_ppm": mass_error_ppm}),
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_detects_impossible_and_unusual_values() {
+        let mut detector = AnomalyDetector::default().with_min_history(10);
+        for i in 0..20 {
+            let normal = evidence(&format!("e{}", i), if i % 2 == 0 { 1.0 } else { -1.0 });
+            assert!(detector.inspect(&normal).is_empty());
+        }
+
+        let impossible = detector.inspect(&evidence("bad", 400.0));
+        assert!(matches!(impossible[0].kind, AnomalyKind::OutOfRange { .. }));
+        let unusual = detector.inspect(&evidence("odd", 12.0));
+        assert!(matches!(unusual[0].kind, AnomalyKind::Deviation { .. }));
+        assert_eq!(detector.stats("orbitrap", "mass_error_ppm").unwrap().count, 20);
+    }
+
+    #[test]
+    fn test_quarantine_review() {
+        let mut store = QuarantineStore::new();
+        let finding = AnomalyFinding {
+            field: "mass_error_ppm".to_string(),
+            value: 400.0,
+            kind: AnomalyKind::OutOfRange { min: -100.0, max: 100.0 },
+        };
+        store.quarantine("p1", evidence("bad", 400.0), vec![finding.clone()]);
+        assert!(store.is_held("p1", "bad"));
+        assert!(!store.is_held("p2", "bad"));
+        assert_eq!(store.list("p1", Some(QuarantineStatus::Pending)).len(), 1);
+
+        store.resolve("p1", "bad", Resolution::Release, "alice", Some("recalibrated".into())).unwrap();
+        assert!(store.is_released("p1", "bad"));
+        assert!(store.resolve("p1", "bad", Resolution::Reject, "alice", None).is_err());
+
+        // A released item is not quarantined again
+        store.quarantine("p1", evidence("bad", 400.0), vec![finding]);
+        assert!(!store.is_held("p1", "bad"));
+    }
+}
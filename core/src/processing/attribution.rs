@@ -0,0 +1,219 @@
+//! Confidence Attribution
+//!
+//! Splits a final confidence into the contributions of the evidence behind
+//! it. Each contributor (an evidence item, a source, or any other group) is
+//! credited with its Shapley value: its average marginal effect on the
+//! confidence over every order in which the contributors could have been
+//! added. Shapley values sum exactly to the confidence minus the confidence
+//! with no evidence at all. Past `MAX_EXACT_CONTRIBUTORS` the exponential
+//! number of subsets is avoided by a leave-one-out approximation rescaled to
+//! the same total.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+/// Most contributors whose Shapley values are computed exactly
+///
+/// The exact computation evaluates the confidence for every subset of
+/// contributors, 4096 evaluations at this size.
+pub const MAX_EXACT_CONTRIBUTORS: usize = 12;
+
+/// Initialize the confidence attribution module
+pub fn initialize() -> Result<()> {
+    info!("Initializing confidence attribution module");
+    info!("Confidence attribution module initialized successfully");
+    Ok(())
+}
+
+/// How contributions were attributed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributionMethod {
+    /// Exact Shapley values over every subset of contributors
+    Shapley,
+
+    /// Leave-one-out influences rescaled to sum to the total
+    LeaveOneOut,
+}
+
+impl fmt::Display for AttributionMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AttributionMethod::Shapley => write!(f, "shapley"),
+            AttributionMethod::LeaveOneOut => write!(f, "leave_one_out"),
+        }
+    }
+}
+
+/// Share of the confidence credited to one contributor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceContribution {
+    /// Evidence ID, source name or other label of the contributor
+    pub key: String,
+
+    /// IDs of the evidence items making up the contributor
+    #[serde(default)]
+    pub evidence_ids: Vec<String>,
+
+    /// Confidence credited to the contributor; negative when it lowers the confidence
+    pub contribution: f64,
+}
+
+/// Final confidence broken down by contributor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceDecomposition {
+    /// Confidence with every contributor included
+    pub total: f64,
+
+    /// Confidence with no contributors; the contributions account for the rest
+    pub baseline: f64,
+
+    /// How the contributions were attributed
+    pub method: AttributionMethod,
+
+    /// Contributions, largest in magnitude first
+    pub contributions: Vec<ConfidenceContribution>,
+}
+
+impl ConfidenceDecomposition {
+    /// Sum of the contributions, which equals `total - baseline`
+    pub fn attributed(&self) -> f64 {
+        self.contributions.iter().map(|c| c.contribution).sum()
+    }
+
+    /// Contribution of a contributor by key
+    pub fn contribution_of(&self, key: &str) -> Option<f64> {
+        self.contributions.iter().find(|c| c.key == key).map(|c| c.contribution)
+    }
+}
+
+/// A contributor to be credited: its key and the evidence IDs it stands for
+pub type Contributor = (String, Vec<String>);
+
+/// Attribute `confidence(all contributors)` to the individual contributors
+///
+/// `confidence` is called with the indices, into `contributors`, of the
+/// subset to evaluate.
+pub fn decompose<F>(contributors: Vec<Contributor>, confidence: F) -> Result<ConfidenceDecomposition>
+where
+    F: Fn(&[usize]) -> Result<f64>,
+{
+    let n = contributors.len();
+    let all: Vec<usize> = (0..n).collect();
+    let total = confidence(&all)?;
+    let baseline = confidence(&[])?;
+
+    let (method, values) = if n <= MAX_EXACT_CONTRIBUTORS {
+        (AttributionMethod::Shapley, shapley_values(n, &confidence)?)
+    } else {
+        (AttributionMethod::LeaveOneOut, leave_one_out_values(n, total, baseline, &confidence)?)
+    };
+    debug!("Attributed confidence {:.4} to {} contributors by {}", total, n, method);
+
+    let mut contributions: Vec<ConfidenceContribution> = contributors.into_iter()
+        .zip(values)
+        .map(|((key, evidence_ids), contribution)| ConfidenceContribution { key, evidence_ids, contribution })
+        .collect();
+    contributions.sort_by(|a, b| {
+        b.contribution.abs().partial_cmp(&a.contribution.abs()).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Ok(ConfidenceDecomposition { total, baseline, method, contributions })
+}
+
+/// Exact Shapley values, evaluating the confidence once per subset
+fn shapley_values<F>(n: usize, confidence: &F) -> Result<Vec<f64>>
+where
+    F: Fn(&[usize]) -> Result<f64>,
+{
+    let subsets = 1usize << n;
+    let mut value = Vec::with_capacity(subsets);
+    for mask in 0..subsets {
+        let members: Vec<usize> = (0..n).filter(|i| mask & (1 << i) != 0).collect();
+        value.push(confidence(&members)?);
+    }
+
+    // Weight of a subset of size s not containing the player: s! (n - s - 1)! / n!
+    let mut factorial = vec![1.0f64; n + 1];
+    for i in 1..=n {
+        factorial[i] = factorial[i - 1] * i as f64;
+    }
+    let weight = |size: usize| factorial[size] * factorial[n - size - 1] / factorial[n];
+
+    let mut values = vec![0.0; n];
+    for (player, total) in values.iter_mut().enumerate() {
+        let bit = 1 << player;
+        for mask in (0..subsets).filter(|mask| mask & bit == 0) {
+            *total += weight(mask.count_ones() as usize) * (value[mask | bit] - value[mask]);
+        }
+    }
+    Ok(values)
+}
+
+/// Leave-one-out influences rescaled so they sum to `total - baseline`
+///
+/// When the influences cancel out, the difference is split evenly.
+fn leave_one_out_values<F>(n: usize, total: f64, baseline: f64, confidence: &F) -> Result<Vec<f64>>
+where
+    F: Fn(&[usize]) -> Result<f64>,
+{
+    if n == 0 {
+        return Err(anyhow!("No contributors to attribute confidence to"));
+    }
+    let mut influences = Vec::with_capacity(n);
+    for held_out in 0..n {
+        let remaining: Vec<usize> = (0..n).filter(|i| *i != held_out).collect();
+        influences.push(total - confidence(&remaining)?);
+    }
+
+    let target = total - baseline;
+    let sum: f64 = influences.iter().sum();
+    if sum.abs() < 1e-12 {
+        return Ok(vec![target / n as f64; n]);
+    }
+    Ok(influences.into_iter().map(|influence| influence * target / sum).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contributors(keys: &[&str]) -> Vec<Contributor> {
+        keys.iter().map(|k| (k.to_string(), vec![k.to_string()])).collect()
+    }
+
+    #[test]
+    fn shapley_values_sum_to_total() {
+        // Mean of the included confidences, zero when none are included
+        let confidences = [0.9, 0.9, 0.3];
+        let mean = |members: &[usize]| -> Result<f64> {
+            if members.is_empty() {
+                return Ok(0.0);
+            }
+            Ok(members.iter().map(|i| confidences[*i]).sum::<f64>() / members.len() as f64)
+        };
+
+        let decomposition = decompose(contributors(&["a", "b", "c"]), mean).unwrap();
+        assert_eq!(decomposition.method, AttributionMethod::Shapley);
+        assert!((decomposition.total - 0.7).abs() < 1e-9);
+        assert!((decomposition.attributed() - (decomposition.total - decomposition.baseline)).abs() < 1e-9);
+        // Symmetric contributors are credited equally, and the weak one least
+        let a = decomposition.contribution_of("a").unwrap();
+        assert!((a - decomposition.contribution_of("b").unwrap()).abs() < 1e-9);
+        assert!(decomposition.contribution_of("c").unwrap() < a);
+    }
+
+    #[test]
+    fn many_contributors_fall_back_to_leave_one_out() {
+        let keys: Vec<String> = (0..MAX_EXACT_CONTRIBUTORS + 2).map(|i| format!("ev-{}", i)).collect();
+        let keys: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+        let count = |members: &[usize]| -> Result<f64> { Ok(members.len() as f64 * 0.05) };
+
+        let decomposition = decompose(contributors(&keys), count).unwrap();
+        assert_eq!(decomposition.method, AttributionMethod::LeaveOneOut);
+        assert!((decomposition.attributed() - decomposition.total).abs() < 1e-9);
+        assert!((decomposition.contributions[0].contribution - 0.05).abs() < 1e-9);
+    }
+}
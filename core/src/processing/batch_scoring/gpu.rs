@@ -0,0 +1,312 @@
+//! wgpu compute backend for batch scoring
+//!
+//! Each invocation scores one query-library pair. Spectra are uploaded as
+//! concatenated sparse vectors (sorted bin indices and unit-norm weights with
+//! per-spectrum offsets) and merged pairwise on the device; fingerprints are
+//! uploaded as packed 32-bit words. Large batches are split into dispatches
+//! that stay within the device's workgroup limit.
+
+use anyhow::{anyhow, Context, Result};
+use bytemuck::{Pod, Zeroable};
+use log::info;
+use wgpu::util::DeviceExt;
+
+use super::{BinnedVector, Fingerprint};
+
+/// Invocations per workgroup, matching `@workgroup_size` in the shaders
+const WORKGROUP_SIZE: u32 = 64;
+
+const COSINE_SHADER: &str = r#"
+struct Params {
+    queries: u32,
+    candidates: u32,
+    offset: u32,
+    count: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> offsets: array<u32>;
+@group(0) @binding(2) var<storage, read> bins: array<i32>;
+@group(0) @binding(3) var<storage, read> weights: array<f32>;
+@group(0) @binding(4) var<storage, read_write> scores: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.count) {
+        return;
+    }
+    let pair = params.offset + id.x;
+    let q = pair / params.candidates;
+    let c = params.queries + pair % params.candidates;
+    var i = offsets[q];
+    let i_end = offsets[q + 1u];
+    var j = offsets[c];
+    let j_end = offsets[c + 1u];
+    var total = 0.0;
+    loop {
+        if (i >= i_end || j >= j_end) {
+            break;
+        }
+        let a = bins[i];
+        let b = bins[j];
+        if (a == b) {
+            total += weights[i] * weights[j];
+            i += 1u;
+            j += 1u;
+        } else if (a < b) {
+            i += 1u;
+        } else {
+            j += 1u;
+        }
+    }
+    scores[id.x] = total;
+}
+"#;
+
+const TANIMOTO_SHADER: &str = r#"
+struct Params {
+    queries: u32,
+    candidates: u32,
+    offset: u32,
+    count: u32,
+    words: u32,
+    _pad0: u32,
+    _pad1: u32,
+    _pad2: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> fingerprints: array<u32>;
+@group(0) @binding(2) var<storage, read_write> scores: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.count) {
+        return;
+    }
+    let pair = params.offset + id.x;
+    let q = (pair / params.candidates) * params.words;
+    let c = (params.queries + pair % params.candidates) * params.words;
+    var intersection = 0u;
+    var either = 0u;
+    for (var k = 0u; k < params.words; k += 1u) {
+        let a = fingerprints[q + k];
+        let b = fingerprints[c + k];
+        intersection += countOneBits(a & b);
+        either += countOneBits(a | b);
+    }
+    if (either == 0u) {
+        scores[id.x] = 0.0;
+    } else {
+        scores[id.x] = f32(intersection) / f32(either);
+    }
+}
+"#;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct CosineParams {
+    queries: u32,
+    candidates: u32,
+    offset: u32,
+    count: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct TanimotoParams {
+    queries: u32,
+    candidates: u32,
+    offset: u32,
+    count: u32,
+    words: u32,
+    _pad: [u32; 3],
+}
+
+/// Device, queue and compiled pipelines
+pub(super) struct GpuScorer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    cosine: wgpu::ComputePipeline,
+    tanimoto: wgpu::ComputePipeline,
+    max_pairs_per_dispatch: u32,
+}
+
+impl GpuScorer {
+    /// Open the first high-performance adapter and compile the shaders
+    pub(super) fn new() -> Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))
+        .ok_or_else(|| anyhow!("No GPU adapter found"))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("hegel-batch-scoring"),
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+        }, None))
+        .context("Failed to open GPU device")?;
+        info!("Batch scoring on GPU: {}", adapter.get_info().name);
+
+        let pipeline = |label: &str, source: &str| {
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: None,
+                module: &module,
+                entry_point: "main",
+            })
+        };
+        let cosine = pipeline("binned-cosine", COSINE_SHADER);
+        let tanimoto = pipeline("tanimoto", TANIMOTO_SHADER);
+        let max_pairs_per_dispatch = device.limits().max_compute_workgroups_per_dimension * WORKGROUP_SIZE;
+
+        Ok(Self {
+            device,
+            queue,
+            cosine,
+            tanimoto,
+            max_pairs_per_dispatch,
+        })
+    }
+
+    /// Binned cosine of every query against every library vector
+    pub(super) fn spectral_cosine(&self, queries: &[BinnedVector], library: &[BinnedVector]) -> Result<Vec<f64>> {
+        let mut offsets = vec![0u32];
+        let mut bins = Vec::new();
+        let mut weights = Vec::new();
+        for vector in queries.iter().chain(library) {
+            bins.extend_from_slice(&vector.bins);
+            weights.extend(vector.weights.iter().map(|w| *w as f32));
+            offsets.push(u32::try_from(bins.len()).context("Too many peaks for one GPU batch")?);
+        }
+        let inputs = [
+            self.storage("offsets", bytemuck::cast_slice(&offsets)),
+            self.storage("bins", bytemuck::cast_slice(&padded(bins))),
+            self.storage("weights", bytemuck::cast_slice(&padded(weights))),
+        ];
+        self.run(&self.cosine, &inputs, queries.len(), library.len(), |offset, count| {
+            bytemuck::bytes_of(&CosineParams {
+                queries: queries.len() as u32,
+                candidates: library.len() as u32,
+                offset,
+                count,
+            }).to_vec()
+        })
+    }
+
+    /// Tanimoto of every query against every library fingerprint
+    pub(super) fn tanimoto(&self, queries: &[Fingerprint], library: &[Fingerprint], words: usize) -> Result<Vec<f64>> {
+        let packed: Vec<u32> = queries.iter().chain(library).flat_map(|f| f.words.iter().copied()).collect();
+        let inputs = [self.storage("fingerprints", bytemuck::cast_slice(&padded(packed)))];
+        self.run(&self.tanimoto, &inputs, queries.len(), library.len(), |offset, count| {
+            bytemuck::bytes_of(&TanimotoParams {
+                queries: queries.len() as u32,
+                candidates: library.len() as u32,
+                offset,
+                count,
+                words: words as u32,
+                _pad: [0; 3],
+            }).to_vec()
+        })
+    }
+
+    fn storage(&self, label: &str, contents: &[u8]) -> wgpu::Buffer {
+        self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents,
+            usage: wgpu::BufferUsages::STORAGE,
+        })
+    }
+
+    /// Dispatch a pipeline over all pairs in chunks, returning scores in row-major order
+    ///
+    /// Binding 0 is the uniform parameters for the chunk, followed by `inputs`
+    /// and finally the output scores.
+    fn run(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        inputs: &[wgpu::Buffer],
+        queries: usize,
+        candidates: usize,
+        params: impl Fn(u32, u32) -> Vec<u8>,
+    ) -> Result<Vec<f64>> {
+        let pairs = queries.checked_mul(candidates)
+            .and_then(|p| u32::try_from(p).ok())
+            .ok_or_else(|| anyhow!("Batch of {} x {} pairs is too large for one GPU run", queries, candidates))?;
+        let mut scores = Vec::with_capacity(pairs as usize);
+        let layout = pipeline.get_bind_group_layout(0);
+
+        let mut offset = 0;
+        while offset < pairs {
+            let count = (pairs - offset).min(self.max_pairs_per_dispatch);
+            let size = count as u64 * std::mem::size_of::<f32>() as u64;
+            let uniform = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: &params(offset, count),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("scores"),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("staging"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut entries = vec![wgpu::BindGroupEntry { binding: 0, resource: uniform.as_entire_binding() }];
+            for (i, buffer) in inputs.iter().enumerate() {
+                entries.push(wgpu::BindGroupEntry { binding: i as u32 + 1, resource: buffer.as_entire_binding() });
+            }
+            entries.push(wgpu::BindGroupEntry { binding: inputs.len() as u32 + 1, resource: output.as_entire_binding() });
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &layout,
+                entries: &entries,
+            });
+
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None, timestamp_writes: None });
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups(count.div_ceil(WORKGROUP_SIZE), 1, 1);
+            }
+            encoder.copy_buffer_to_buffer(&output, 0, &staging, 0, size);
+            self.queue.submit(Some(encoder.finish()));
+
+            let slice = staging.slice(..);
+            let (sender, receiver) = std::sync::mpsc::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            self.device.poll(wgpu::Maintain::Wait);
+            receiver.recv()
+                .context("GPU device lost while scoring")?
+                .context("Failed to read GPU scores")?;
+            scores.extend(bytemuck::cast_slice::<u8, f32>(&slice.get_mapped_range()).iter().map(|s| *s as f64));
+            staging.unmap();
+
+            offset += count;
+        }
+        Ok(scores)
+    }
+}
+
+/// Storage bindings cannot be empty, so pad empty inputs with one element
+fn padded<T: Default>(mut values: Vec<T>) -> Vec<T> {
+    if values.is_empty() {
+        values.push(T::default());
+    }
+    values
+}
@@ -0,0 +1,255 @@
+//! Biotransformation Module
+//!
+//! Metabolites differ from their parent compound by the mass of a known
+//! reaction: an oxidation adds an oxygen, a glucuronidation a glucuronic acid
+//! less water. When the mass difference between two molecules, or between an
+//! unknown peak and a known compound, matches one of these within tolerance,
+//! the pair is proposed as a transformation, which is stored as a
+//! `TransformsTo` edge and counts as evidence for the unknown side.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::graph::schema::{Edge, EdgeType};
+use crate::processing::evidence::{Evidence, EvidenceType};
+use crate::processing::mass_accuracy::ppm_error;
+use crate::processing::units::{Quantity, Unit};
+use crate::processing::Molecule;
+
+/// Initialize the biotransformation module
+pub fn initialize() -> Result<()> {
+    info!("Initializing biotransformation module");
+    info!("Biotransformation module initialized successfully");
+    Ok(())
+}
+
+/// Source of evidence from biotransformation matches
+pub const BIOTRANSFORM_SOURCE: &str = "biotransform";
+
+/// Molecule properties that may hold the monoisotopic neutral mass
+pub const MONOISOTOPIC_MASS_PROPERTIES: [&str; 2] = ["monoisotopic_mass", "exact_mass"];
+
+/// A reaction and the monoisotopic mass it adds to its substrate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Biotransformation {
+    /// Reaction name
+    pub name: &'static str,
+
+    /// Change in elemental composition
+    pub formula_change: &'static str,
+
+    /// Product mass minus substrate mass, in Daltons
+    pub mass_delta: f64,
+}
+
+const fn transform(name: &'static str, formula_change: &'static str, mass_delta: f64) -> Biotransformation {
+    Biotransformation { name, formula_change, mass_delta }
+}
+
+/// Common phase I and phase II biotransformations
+pub const BIOTRANSFORMATIONS: [Biotransformation; 16] = [
+    transform("hydroxylation", "+O", 15.994915),
+    transform("dihydroxylation", "+O2", 31.989829),
+    transform("methylation", "+CH2", 14.015650),
+    transform("demethylation", "-CH2", -14.015650),
+    transform("reduction", "+H2", 2.015650),
+    transform("dehydrogenation", "-H2", -2.015650),
+    transform("hydration", "+H2O", 18.010565),
+    transform("dehydration", "-H2O", -18.010565),
+    transform("decarboxylation", "-CO2", -43.989829),
+    transform("acetylation", "+C2H2O", 42.010565),
+    transform("glucuronidation", "+C6H8O6", 176.032088),
+    transform("glucosylation", "+C6H10O5", 162.052824),
+    transform("sulfation", "+SO3", 79.956815),
+    transform("phosphorylation", "+HPO3", 79.966331),
+    transform("glycine conjugation", "+C2H3NO", 57.021464),
+    transform("glutathione conjugation", "+C10H15N3O6S", 305.068156),
+];
+
+/// A proposed transformation of one molecule into another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformProposal {
+    /// Molecule (or peak) transformed
+    pub substrate_id: String,
+
+    /// Molecule (or peak) produced
+    pub product_id: String,
+
+    /// Reaction name
+    pub transformation: String,
+
+    /// Change in elemental composition
+    pub formula_change: String,
+
+    /// Mass difference expected for the reaction, in Daltons
+    pub expected_delta: f64,
+
+    /// Mass difference observed, in Daltons
+    pub observed_delta: f64,
+
+    /// Error of the product mass under this reaction, in ppm
+    pub error_ppm: f64,
+
+    /// Confidence in the proposal, falling linearly to 0 at the tolerance (0.0 - 1.0)
+    pub confidence: f64,
+}
+
+impl TransformProposal {
+    /// `TransformsTo` edge from the substrate to the product
+    pub fn to_edge(&self) -> Edge {
+        let mut edge = Edge::new(self.substrate_id.clone(), self.product_id.clone(), EdgeType::TransformsTo);
+        edge.add_property("transformation", serde_json::json!(self.transformation))
+            .add_property("formula_change", serde_json::json!(self.formula_change))
+            .add_property("mass_delta", serde_json::json!(self.observed_delta))
+            .add_property("error_ppm", serde_json::json!(self.error_ppm))
+            .add_property("confidence", serde_json::json!(self.confidence));
+        edge
+    }
+
+    /// Evidence for one side of the transformation, usually the unknown one
+    pub fn to_evidence(&self, molecule_id: &str) -> Result<Evidence> {
+        let partner = if molecule_id == self.product_id {
+            &self.substrate_id
+        } else if molecule_id == self.substrate_id {
+            &self.product_id
+        } else {
+            return Err(anyhow!("Molecule {} is not part of the {} of {} into {}",
+                               molecule_id, self.transformation, self.substrate_id, self.product_id));
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("transformation".to_string(), serde_json::json!(self.transformation));
+        metadata.insert("related_molecule".to_string(), serde_json::json!(partner));
+
+        Ok(Evidence {
+            id: format!("{}-{}-{}", BIOTRANSFORM_SOURCE, self.substrate_id, self.product_id),
+            molecule_id: molecule_id.to_string(),
+            evidence_type: EvidenceType::MassSpec,
+            source: BIOTRANSFORM_SOURCE.to_string(),
+            confidence: self.confidence,
+            data: serde_json::to_value(self)?,
+            metadata,
+            timestamp: chrono::Utc::now(),
+        })
+    }
+}
+
+/// Propose the transformations relating two masses
+///
+/// Both masses must be of the same kind: neutral monoisotopic masses, or m/z
+/// values of the same ion type, whose difference is then the neutral one.
+/// Either may be the substrate; proposals are ordered by absolute error.
+/// `tolerance` is in ppm of the product mass or in Daltons.
+pub fn propose(
+    (a_id, a_mass): (&str, f64),
+    (b_id, b_mass): (&str, f64),
+    tolerance: Quantity,
+) -> Result<Vec<TransformProposal>> {
+    if !matches!(tolerance.unit, Unit::Ppm | Unit::Dalton) {
+        return Err(anyhow!("Biotransformation tolerance must be in ppm or Da, got {}", tolerance));
+    }
+    if a_mass <= 0.0 || b_mass <= 0.0 {
+        return Err(anyhow!("Masses must be positive, got {} and {}", a_mass, b_mass));
+    }
+
+    let mut proposals = Vec::new();
+    for ((substrate_id, substrate_mass), (product_id, product_mass)) in
+        [((a_id, a_mass), (b_id, b_mass)), ((b_id, b_mass), (a_id, a_mass))]
+    {
+        let window = tolerance.to_at(Unit::Dalton, product_mass)?.value;
+        let observed_delta = product_mass - substrate_mass;
+        for transformation in &BIOTRANSFORMATIONS {
+            let error = observed_delta - transformation.mass_delta;
+            if error.abs() > window {
+                continue;
+            }
+            proposals.push(TransformProposal {
+                substrate_id: substrate_id.to_string(),
+                product_id: product_id.to_string(),
+                transformation: transformation.name.to_string(),
+                formula_change: transformation.formula_change.to_string(),
+                expected_delta: transformation.mass_delta,
+                observed_delta,
+                error_ppm: ppm_error(product_mass, substrate_mass + transformation.mass_delta),
+                confidence: if window > 0.0 { 1.0 - error.abs() / window } else { 1.0 },
+            });
+        }
+    }
+    proposals.sort_by(|a, b| a.error_ppm.abs().total_cmp(&b.error_ppm.abs()));
+
+    debug!("{} biotransformations relate {} and {}", proposals.len(), a_id, b_id);
+    Ok(proposals)
+}
+
+/// Propose the transformations relating two molecules, from their monoisotopic masses
+pub fn propose_for_molecules(a: &Molecule, b: &Molecule, tolerance: Quantity) -> Result<Vec<TransformProposal>> {
+    propose((&a.id, monoisotopic_mass(a)?), (&b.id, monoisotopic_mass(b)?), tolerance)
+}
+
+/// Propose the transformations relating an unknown peak to a known compound
+///
+/// `known_mz` is the m/z of the known compound as the same ion type as the
+/// peak, e.g. both [M+H]+.
+pub fn propose_for_peak(
+    peak_id: &str,
+    peak_mz: f64,
+    known_id: &str,
+    known_mz: f64,
+    tolerance: Quantity,
+) -> Result<Vec<TransformProposal>> {
+    propose((known_id, known_mz), (peak_id, peak_mz), tolerance)
+}
+
+/// Monoisotopic neutral mass of a molecule, from its properties or else its formula
+///
+/// The molecular weight is an average mass and too coarse to tell
+/// transformations apart, so it is not used.
+pub fn monoisotopic_mass(molecule: &Molecule) -> Result<f64> {
+    MONOISOTOPIC_MASS_PROPERTIES.iter()
+        .find_map(|key| molecule.properties.get(*key)?.as_f64())
+        .or_else(|| molecule.formula.as_ref().map(|formula| formula.monoisotopic_mass()))
+        .ok_or_else(|| anyhow!("Molecule {} has no monoisotopic mass", molecule.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proposes_glucuronide_in_either_order() {
+        // Paracetamol and its glucuronide, as [M+H]+
+        let paracetamol = ("paracetamol", 152.070605);
+        let glucuronide = ("peak-328", 328.102693);
+
+        let proposals = propose(glucuronide, paracetamol, Quantity::ppm(5.0)).unwrap();
+        assert_eq!(proposals.len(), 1);
+        let proposal = &proposals[0];
+        assert_eq!(proposal.transformation, "glucuronidation");
+        assert_eq!((proposal.substrate_id.as_str(), proposal.product_id.as_str()), ("paracetamol", "peak-328"));
+        assert!(proposal.error_ppm.abs() < 1.0 && proposal.confidence > 0.8);
+
+        let edge = proposal.to_edge();
+        assert_eq!(edge.edge_type, EdgeType::TransformsTo);
+        assert_eq!(edge.get_property("transformation"), Some(&serde_json::json!("glucuronidation")));
+
+        let evidence = proposal.to_evidence("peak-328").unwrap();
+        assert_eq!(evidence.metadata["related_molecule"], "paracetamol");
+        assert!(proposal.to_evidence("caffeine").is_err());
+    }
+
+    #[test]
+    fn test_tolerance_separates_sulfation_from_phosphorylation() {
+        // Sulfation and phosphorylation differ by 9.5 mDa
+        let sulfate = propose_for_peak("peak", 232.027 + 79.956815, "parent", 232.027, Quantity::ppm(5.0)).unwrap();
+        assert_eq!(sulfate.len(), 1);
+        assert_eq!(sulfate[0].transformation, "sulfation");
+
+        let loose = propose_for_peak("peak", 232.027 + 79.956815, "parent", 232.027, Quantity::daltons(0.02)).unwrap();
+        assert_eq!(loose.len(), 2);
+        assert_eq!(loose[0].transformation, "sulfation");
+
+        assert!(propose(("a", 100.0), ("b", 100.0), Quantity::minutes(1.0)).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+//! Confidence Policy Module
+//!
+//! Lab rules bounding the confidence integration may conclude from a given
+//! mix of evidence, such as "literature evidence alone never exceeds 0.6" or
+//! "confidence above 0.8 needs both a mass spectrometry match and structural
+//! evidence". The policy is applied to the aggregate confidence after
+//! integration:
+//!
+//! - floors raise the confidence to a minimum when evidence of given types is
+//!   present, e.g. for a reference standard match;
+//! - ceilings cap it when all evidence is of given types;
+//! - combination requirements cap it at a level until the evidence covers
+//!   the required types.
+//!
+//! Ceilings and requirements are applied after floors, so a cap always holds.
+//! Every rule that changed the confidence is recorded as a violation on the
+//! integrated evidence, with the confidence integration arrived at and an
+//! explanation, so the adjustment is visible rather than silently clipped.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::processing::evidence::{Evidence, EvidenceType};
+
+/// Environment variable naming the confidence policy file
+pub const POLICY_ENV: &str = "HEGEL_CONFIDENCE_POLICY";
+
+/// Initialize the confidence policy module
+pub fn initialize() -> Result<()> {
+    info!("Initializing confidence policy module");
+    info!("Confidence policy module initialized successfully");
+    Ok(())
+}
+
+/// Minimum confidence when evidence of given types is present
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceFloor {
+    /// Name reported when the floor applies
+    pub name: String,
+
+    /// Evidence types any one of which brings the floor into force
+    pub any_of: Vec<EvidenceType>,
+
+    /// Lowest confidence such evidence allows
+    pub min_confidence: f64,
+}
+
+/// Maximum confidence when all evidence is of given types
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceCeiling {
+    /// Name reported when the ceiling applies (e.g. "literature-only")
+    pub name: String,
+
+    /// Evidence types the ceiling covers; it applies when no other type is present
+    pub only: Vec<EvidenceType>,
+
+    /// Highest confidence such evidence may reach
+    pub max_confidence: f64,
+}
+
+/// Evidence needed for confidence to rise above a level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CombinationRequirement {
+    /// Name reported when the requirement is unmet
+    pub name: String,
+
+    /// Confidence above which the requirement must be met
+    pub above: f64,
+
+    /// Evidence types that must all be present
+    #[serde(default)]
+    pub all_of: Vec<EvidenceType>,
+
+    /// Minimum number of distinct evidence types
+    #[serde(default)]
+    pub min_distinct_types: usize,
+}
+
+impl CombinationRequirement {
+    /// What the evidence lacks to meet the requirement, if anything
+    fn shortfall(&self, present: &BTreeSet<String>) -> Option<String> {
+        let missing: Vec<String> = self.all_of.iter()
+            .map(|t| t.to_string())
+            .filter(|t| !present.contains(t))
+            .collect();
+        let mut shortfall = Vec::new();
+        if !missing.is_empty() {
+            shortfall.push(format!("missing {} evidence", missing.join(" and ")));
+        }
+        if present.len() < self.min_distinct_types {
+            shortfall.push(format!("{} of {} distinct evidence types", present.len(), self.min_distinct_types));
+        }
+        (!shortfall.is_empty()).then(|| shortfall.join(", "))
+    }
+}
+
+/// Kind of rule that adjusted a confidence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRuleKind {
+    /// A floor raised the confidence
+    Floor,
+
+    /// A ceiling capped the confidence
+    Ceiling,
+
+    /// An unmet combination requirement capped the confidence
+    Combination,
+}
+
+/// A policy rule that adjusted the integrated confidence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    /// Name of the rule
+    pub rule: String,
+
+    /// Kind of rule
+    pub kind: PolicyRuleKind,
+
+    /// Bound the rule imposes
+    pub limit: f64,
+
+    /// Confidence integration arrived at before the policy was applied
+    pub unconstrained_confidence: f64,
+
+    /// Human-readable account of the adjustment
+    pub explanation: String,
+}
+
+/// Per-evidence-type bounds on integrated confidence
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfidencePolicy {
+    /// Minimum confidences
+    pub floors: Vec<ConfidenceFloor>,
+
+    /// Maximum confidences
+    pub ceilings: Vec<ConfidenceCeiling>,
+
+    /// Evidence combinations required above given confidences
+    pub combinations: Vec<CombinationRequirement>,
+}
+
+impl ConfidencePolicy {
+    /// Raise confidence to at least `min_confidence` when any of the given types is present
+    pub fn with_floor(mut self, name: &str, any_of: Vec<EvidenceType>, min_confidence: f64) -> Self {
+        self.floors.push(ConfidenceFloor { name: name.to_string(), any_of, min_confidence });
+        self
+    }
+
+    /// Cap confidence at `max_confidence` when only the given types are present
+    pub fn with_ceiling(mut self, name: &str, only: Vec<EvidenceType>, max_confidence: f64) -> Self {
+        self.ceilings.push(ConfidenceCeiling { name: name.to_string(), only, max_confidence });
+        self
+    }
+
+    /// Cap confidence at `above` unless all of the given types are present
+    pub fn with_combination(mut self, name: &str, above: f64, all_of: Vec<EvidenceType>) -> Self {
+        self.combinations.push(CombinationRequirement {
+            name: name.to_string(),
+            above,
+            all_of,
+            min_distinct_types: 0,
+        });
+        self
+    }
+
+    /// Load a policy from a JSON file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read confidence policy: {}", path.display()))?;
+        let policy: Self = serde_json::from_str(&content).context("Failed to parse confidence policy")?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Load the policy named by `HEGEL_CONFIDENCE_POLICY`, or an empty policy
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(POLICY_ENV) {
+            Ok(path) => Self::from_file(Path::new(&path)),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Whether the policy has no rules
+    pub fn is_empty(&self) -> bool {
+        self.floors.is_empty() && self.ceilings.is_empty() && self.combinations.is_empty()
+    }
+
+    /// Check that every rule names evidence types and bounds within 0 - 1
+    pub fn validate(&self) -> Result<()> {
+        let in_range = |value: f64| (0.0..=1.0).contains(&value);
+        for floor in &self.floors {
+            if floor.any_of.is_empty() || !in_range(floor.min_confidence) {
+                return Err(anyhow!("Confidence floor '{}' needs evidence types and a minimum between 0 and 1", floor.name));
+            }
+        }
+        for ceiling in &self.ceilings {
+            if ceiling.only.is_empty() || !in_range(ceiling.max_confidence) {
+                return Err(anyhow!("Confidence ceiling '{}' needs evidence types and a maximum between 0 and 1", ceiling.name));
+            }
+        }
+        for requirement in &self.combinations {
+            if (requirement.all_of.is_empty() && requirement.min_distinct_types == 0) || !in_range(requirement.above) {
+                return Err(anyhow!("Combination requirement '{}' needs evidence types and a level between 0 and 1", requirement.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Bound an integrated confidence, returning it with the rules that changed it
+    pub fn apply(&self, evidence: &[Evidence], confidence: f64) -> (f64, Vec<PolicyViolation>) {
+        if evidence.is_empty() || self.is_empty() {
+            return (confidence, Vec::new());
+        }
+        let types: Vec<EvidenceType> = evidence.iter().map(|ev| ev.evidence_type).collect();
+        let present: BTreeSet<String> = types.iter().map(|t| t.to_string()).collect();
+        let mut violations = Vec::new();
+        let violation = |rule: &str, kind, limit, explanation| PolicyViolation {
+            rule: rule.to_string(),
+            kind,
+            limit,
+            unconstrained_confidence: confidence,
+            explanation,
+        };
+
+        let mut bounded = confidence;
+        for floor in &self.floors {
+            if bounded < floor.min_confidence && types.iter().any(|t| floor.any_of.contains(t)) {
+                violations.push(violation(&floor.name, PolicyRuleKind::Floor, floor.min_confidence, format!(
+                    "{}: confidence raised from {:.2} to {:.2} because {} evidence is present",
+                    floor.name, bounded, floor.min_confidence, type_list(&floor.any_of, " or "),
+                )));
+                bounded = floor.min_confidence;
+            }
+        }
+
+        let mut cap = f64::INFINITY;
+        for ceiling in &self.ceilings {
+            if confidence.max(bounded) > ceiling.max_confidence && types.iter().all(|t| ceiling.only.contains(t)) {
+                violations.push(violation(&ceiling.name, PolicyRuleKind::Ceiling, ceiling.max_confidence, format!(
+                    "{}: {} evidence alone may not exceed {:.2}",
+                    ceiling.name, type_list(&ceiling.only, " and "), ceiling.max_confidence,
+                )));
+                cap = cap.min(ceiling.max_confidence);
+            }
+        }
+        for requirement in &self.combinations {
+            if bounded > requirement.above {
+                if let Some(shortfall) = requirement.shortfall(&present) {
+                    violations.push(violation(&requirement.name, PolicyRuleKind::Combination, requirement.above, format!(
+                        "{}: confidence above {:.2} requires more evidence ({})",
+                        requirement.name, requirement.above, shortfall,
+                    )));
+                    cap = cap.min(requirement.above);
+                }
+            }
+        }
+
+        let bounded = bounded.min(cap);
+        if !violations.is_empty() {
+            debug!("Confidence policy bounded {:.3} to {:.3} ({} rules applied)", confidence, bounded, violations.len());
+        }
+        (bounded, violations)
+    }
+}
+
+/// Evidence type names joined with a conjunction
+fn type_list(types: &[EvidenceType], conjunction: &str) -> String {
+    types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(conjunction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(evidence_type: EvidenceType) -> Evidence {
+        Evidence::manual("mol1", evidence_type, 0.9, None, "tester").unwrap()
+    }
+
+    fn lab_policy() -> ConfidencePolicy {
+        ConfidencePolicy::default()
+            .with_ceiling("literature-only", vec![EvidenceType::Literature], 0.6)
+            .with_combination("confirmed", 0.8, vec![EvidenceType::MassSpec, EvidenceType::Structural])
+            .with_floor("reference-standard", vec![EvidenceType::Structural], 0.5)
+    }
+
+    #[test]
+    fn test_ceilings_and_combinations_are_reported() {
+        let policy = lab_policy();
+        policy.validate().unwrap();
+
+        let literature = [evidence(EvidenceType::Literature), evidence(EvidenceType::Literature)];
+        let (confidence, violations) = policy.apply(&literature, 0.9);
+        assert_eq!(confidence, 0.6);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].kind, PolicyRuleKind::Ceiling);
+        assert_eq!(violations[0].unconstrained_confidence, 0.9);
+        assert!(violations[1].explanation.contains("missing mass_spec and structural evidence"));
+
+        let spectral = [evidence(EvidenceType::MassSpec), evidence(EvidenceType::Literature)];
+        let (confidence, violations) = policy.apply(&spectral, 0.95);
+        assert_eq!(confidence, 0.8);
+        assert_eq!(violations[0].rule, "confirmed");
+
+        let confirmed = [evidence(EvidenceType::MassSpec), evidence(EvidenceType::Structural)];
+        assert_eq!(policy.apply(&confirmed, 0.95), (0.95, Vec::new()));
+    }
+
+    #[test]
+    fn test_floor_never_overrides_a_cap() {
+        let policy = lab_policy().with_ceiling("structural-only", vec![EvidenceType::Structural], 0.4);
+        let structural = [evidence(EvidenceType::Structural)];
+
+        let (confidence, violations) = policy.apply(&structural, 0.2);
+        assert_eq!(confidence, 0.4);
+        let kinds: Vec<PolicyRuleKind> = violations.iter().map(|v| v.kind).collect();
+        assert_eq!(kinds, vec![PolicyRuleKind::Floor, PolicyRuleKind::Ceiling]);
+
+        assert!(ConfidencePolicy::default().with_ceiling("empty", Vec::new(), 0.5).validate().is_err());
+        let parsed: ConfidencePolicy = serde_json::from_str(
+            r#"{"ceilings": [{"name": "literature-only", "only": ["Literature"], "max_confidence": 0.6}]}"#,
+        ).unwrap();
+        assert_eq!(parsed.ceilings[0].max_confidence, 0.6);
+    }
+}
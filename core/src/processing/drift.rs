@@ -0,0 +1,463 @@
+//! Instrument Drift Detection
+//!
+//! An instrument that slowly loses calibration keeps producing evidence, just
+//! worse evidence: confidences shift as a whole rather than one item at a
+//! time, which the per-item anomaly checks can't see. Evidence is grouped by
+//! source and by the run it was acquired in (the `run_id` metadata key), and
+//! the confidence distribution of each run is compared against the pooled
+//! runs before it with a two-sample Kolmogorov-Smirnov test and the
+//! population stability index. Runs that drift raise an alert, and their
+//! evidence is flagged so integration gives it less weight.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::alerts::{Alert, AlertSeverity};
+use crate::processing::evidence::{Evidence, DRIFT_METADATA_KEY};
+
+/// Initialize the drift detection module
+pub fn initialize() -> Result<()> {
+    info!("Initializing drift detection module");
+    info!("Drift detection module initialized successfully");
+    Ok(())
+}
+
+/// Metadata key holding the ID of the instrument run evidence was acquired in
+pub const RUN_METADATA_KEY: &str = "run_id";
+
+/// Rule ID of drift alerts
+pub const DRIFT_ALERT_RULE: &str = "instrument-drift";
+
+/// Bins used for the population stability index
+const PSI_BINS: usize = 10;
+
+/// Share given to empty bins so the stability index stays finite
+const PSI_EPSILON: f64 = 1e-4;
+
+/// When a run counts as drifted, and how much its evidence is discounted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftThresholds {
+    /// Fewest confidences a run (and its baseline) needs to be assessed
+    pub min_samples: usize,
+
+    /// KS p-value below which the distributions differ
+    pub ks_p_value: f64,
+
+    /// Smallest KS statistic worth reporting, so huge runs don't flag trivial shifts
+    pub ks_statistic: f64,
+
+    /// Stability index from which a run drifts (warning)
+    pub psi_warning: f64,
+
+    /// Stability index from which a run drifts badly (critical)
+    pub psi_critical: f64,
+
+    /// Weight of evidence from a run with a warning
+    pub warning_weight: f64,
+
+    /// Weight of evidence from a run with a critical drift
+    pub critical_weight: f64,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> Self {
+        Self {
+            min_samples: 20,
+            ks_p_value: 0.01,
+            ks_statistic: 0.2,
+            psi_warning: 0.1,
+            psi_critical: 0.25,
+            warning_weight: 0.75,
+            critical_weight: 0.5,
+        }
+    }
+}
+
+impl DriftThresholds {
+    /// Check that the thresholds are usable
+    pub fn validate(&self) -> Result<()> {
+        if self.min_samples < 2 {
+            return Err(anyhow!("Drift detection needs at least 2 samples per run, got {}", self.min_samples));
+        }
+        if self.psi_warning > self.psi_critical {
+            return Err(anyhow!("PSI warning threshold {} exceeds the critical threshold {}", self.psi_warning, self.psi_critical));
+        }
+        for (name, value) in [("ks_p_value", self.ks_p_value), ("warning_weight", self.warning_weight), ("critical_weight", self.critical_weight)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(anyhow!("Drift {} must be between 0 and 1, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Two-sample Kolmogorov-Smirnov statistic and its asymptotic p-value
+pub fn ks_two_sample(a: &[f64], b: &[f64]) -> (f64, f64) {
+    if a.is_empty() || b.is_empty() {
+        return (0.0, 1.0);
+    }
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_by(f64::total_cmp);
+    b.sort_by(f64::total_cmp);
+
+    let (mut i, mut j, mut statistic) = (0, 0, 0.0f64);
+    while i < a.len() && j < b.len() {
+        let value = a[i].min(b[j]);
+        while i < a.len() && a[i] <= value {
+            i += 1;
+        }
+        while j < b.len() && b[j] <= value {
+            j += 1;
+        }
+        statistic = statistic.max((i as f64 / a.len() as f64 - j as f64 / b.len() as f64).abs());
+    }
+
+    let effective = (a.len() * b.len()) as f64 / (a.len() + b.len()) as f64;
+    let lambda = (effective.sqrt() + 0.12 + 0.11 / effective.sqrt()) * statistic;
+    (statistic, kolmogorov_survival(lambda))
+}
+
+/// Probability that the Kolmogorov distribution exceeds `lambda`
+fn kolmogorov_survival(lambda: f64) -> f64 {
+    if lambda < 1e-3 {
+        return 1.0;
+    }
+    let mut sum = 0.0;
+    for j in 1..=100 {
+        let term = (-2.0 * (j * j) as f64 * lambda * lambda).exp();
+        sum += if j % 2 == 1 { term } else { -term };
+        if term < 1e-12 {
+            break;
+        }
+    }
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// Population stability index of `current` against `reference`
+///
+/// Both are binned into equal-width bins over their combined range; below
+/// 0.1 is usually read as stable, above 0.25 as a major shift.
+pub fn population_stability_index(reference: &[f64], current: &[f64]) -> f64 {
+    if reference.is_empty() || current.is_empty() {
+        return 0.0;
+    }
+    let (low, high) = reference.iter().chain(current)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if high <= low {
+        return 0.0;
+    }
+
+    let shares = |values: &[f64]| {
+        let mut counts = [0usize; PSI_BINS];
+        for &value in values {
+            let bin = (((value - low) / (high - low)) * PSI_BINS as f64) as usize;
+            counts[bin.min(PSI_BINS - 1)] += 1;
+        }
+        counts.map(|c| (c as f64 / values.len() as f64).max(PSI_EPSILON))
+    };
+    let (expected, actual) = (shares(reference), shares(current));
+    expected.iter().zip(&actual)
+        .map(|(e, a)| (a - e) * (a / e).ln())
+        .sum()
+}
+
+/// How the confidences of one run compare with the runs before it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunDrift {
+    /// Source (instrument) of the evidence
+    pub source: String,
+
+    /// Run assessed
+    pub run_id: String,
+
+    /// Earliest evidence timestamp of the run
+    pub started_at: DateTime<Utc>,
+
+    /// Runs pooled into the baseline
+    pub baseline_runs: Vec<String>,
+
+    /// Confidences in the baseline
+    pub baseline_size: usize,
+
+    /// Confidences in the run
+    pub run_size: usize,
+
+    /// Mean confidence of the run minus that of the baseline
+    pub mean_shift: f64,
+
+    /// Kolmogorov-Smirnov statistic
+    pub ks_statistic: f64,
+
+    /// Kolmogorov-Smirnov p-value
+    pub ks_p_value: f64,
+
+    /// Population stability index
+    pub psi: f64,
+
+    /// Severity of the drift, `None` when the run is stable
+    pub severity: Option<AlertSeverity>,
+
+    /// Weight given to the run's evidence during integration (0.0 - 1.0)
+    pub weight: f64,
+}
+
+impl RunDrift {
+    /// Whether the run drifted from its baseline
+    pub fn drifted(&self) -> bool {
+        self.severity.is_some()
+    }
+
+    /// Alert for a drifted run, naming the run in place of a molecule
+    pub fn to_alert(&self, project_id: &str) -> Option<Alert> {
+        let severity = self.severity?;
+        Some(Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            rule_id: DRIFT_ALERT_RULE.to_string(),
+            rule_name: "Instrument drift".to_string(),
+            project_id: project_id.to_string(),
+            molecule_id: self.run_id.clone(),
+            severity,
+            message: format!(
+                "run {} of {} drifted from the {} previous run(s): PSI {:.3}, KS {:.3} (p = {:.2e}), mean confidence shift {:+.3}",
+                self.run_id, self.source, self.baseline_runs.len(), self.psi, self.ks_statistic, self.ks_p_value, self.mean_shift,
+            ),
+            value: self.psi,
+            raised_at: Utc::now(),
+        })
+    }
+}
+
+/// Confidences of one run of a source
+struct Run {
+    /// Run ID
+    id: String,
+
+    /// Earliest evidence timestamp
+    started_at: DateTime<Utc>,
+
+    /// Confidence of each evidence item
+    confidences: Vec<f64>,
+}
+
+/// Compares each run of a source with the runs acquired before it
+#[derive(Debug, Clone)]
+pub struct DriftDetector {
+    /// When runs count as drifted
+    thresholds: DriftThresholds,
+
+    /// Most previous runs pooled into a baseline
+    baseline_runs: usize,
+}
+
+impl DriftDetector {
+    /// Create a detector with default thresholds, pooling up to five previous runs
+    pub fn new() -> Self {
+        Self {
+            thresholds: DriftThresholds::default(),
+            baseline_runs: 5,
+        }
+    }
+
+    /// Use other thresholds
+    pub fn with_thresholds(mut self, thresholds: DriftThresholds) -> Result<Self> {
+        thresholds.validate()?;
+        self.thresholds = thresholds;
+        Ok(self)
+    }
+
+    /// Pool up to `runs` previous runs into each baseline
+    pub fn with_baseline_runs(mut self, runs: usize) -> Self {
+        self.baseline_runs = runs.max(1);
+        self
+    }
+
+    /// KS statistic, KS p-value, stability index and severity of a run against a baseline
+    ///
+    /// `None` when either is too small to compare.
+    fn assess(&self, source: &str, run_id: &str, baseline: &[f64], run: &[f64]) -> Option<(f64, f64, f64, Option<AlertSeverity>)> {
+        if baseline.len() < self.thresholds.min_samples || run.len() < self.thresholds.min_samples {
+            debug!("Too few confidences to assess run {} of {}", run_id, source);
+            return None;
+        }
+        let (ks_statistic, ks_p_value) = ks_two_sample(baseline, run);
+        let psi = population_stability_index(baseline, run);
+        let ks_drift = ks_p_value < self.thresholds.ks_p_value && ks_statistic >= self.thresholds.ks_statistic;
+
+        let severity = if psi >= self.thresholds.psi_critical {
+            Some(AlertSeverity::Critical)
+        } else if psi >= self.thresholds.psi_warning || ks_drift {
+            Some(AlertSeverity::Warning)
+        } else {
+            None
+        };
+        Some((ks_statistic, ks_p_value, psi, severity))
+    }
+
+    /// Assess every run of every source, in acquisition order
+    ///
+    /// Runs are ordered by their earliest evidence. The first runs of a source
+    /// only form a baseline, and drifted runs are kept out of later baselines
+    /// so a failing instrument doesn't become its own reference.
+    pub fn detect(&self, evidence: &[Evidence]) -> Vec<RunDrift> {
+        let mut runs: BTreeMap<(&str, &str), Run> = BTreeMap::new();
+        for item in evidence {
+            let run_id = match item.metadata.get(RUN_METADATA_KEY).and_then(|v| v.as_str()) {
+                Some(run_id) => run_id,
+                None => continue,
+            };
+            let run = runs.entry((item.source.as_str(), run_id))
+                .or_insert_with(|| Run { id: run_id.to_string(), started_at: item.timestamp, confidences: Vec::new() });
+            run.started_at = run.started_at.min(item.timestamp);
+            run.confidences.push(item.confidence);
+        }
+        let mut by_source: BTreeMap<&str, Vec<Run>> = BTreeMap::new();
+        for ((source, _), run) in runs {
+            by_source.entry(source).or_default().push(run);
+        }
+
+        let mut assessments = Vec::new();
+        for (source, mut ordered) in by_source {
+            ordered.sort_by(|a, b| a.started_at.cmp(&b.started_at).then_with(|| a.id.cmp(&b.id)));
+
+            let mut stable: Vec<usize> = Vec::new();
+            for (index, Run { id: run_id, started_at, confidences }) in ordered.iter().enumerate() {
+                let pooled: Vec<usize> = stable.iter().rev().take(self.baseline_runs).rev().copied().collect();
+                let baseline: Vec<f64> = pooled.iter().flat_map(|&i| ordered[i].confidences.iter().copied()).collect();
+                // Runs too small to assess still serve as a baseline for later ones
+                let (ks_statistic, ks_p_value, psi, severity) = match self.assess(source, run_id, &baseline, confidences) {
+                    Some(assessment) => assessment,
+                    None => {
+                        stable.push(index);
+                        continue;
+                    }
+                };
+
+                let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+                let drift = RunDrift {
+                    source: source.to_string(),
+                    run_id: run_id.clone(),
+                    started_at: *started_at,
+                    baseline_runs: pooled.iter().map(|&i| ordered[i].id.clone()).collect(),
+                    baseline_size: baseline.len(),
+                    run_size: confidences.len(),
+                    mean_shift: mean(confidences) - mean(&baseline),
+                    ks_statistic,
+                    ks_p_value,
+                    psi,
+                    severity,
+                    weight: match severity {
+                        Some(AlertSeverity::Critical) => self.thresholds.critical_weight,
+                        Some(_) => self.thresholds.warning_weight,
+                        None => 1.0,
+                    },
+                };
+                if drift.drifted() {
+                    warn!("Run {} of {} drifted (PSI {:.3}, KS {:.3})", run_id, source, psi, ks_statistic);
+                } else {
+                    stable.push(index);
+                }
+                assessments.push(drift);
+            }
+        }
+        assessments
+    }
+}
+
+impl Default for DriftDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Flag the evidence of drifted runs, returning how many items were flagged
+///
+/// The flag records the run's drift figures and the weight integration
+/// gives the item.
+pub fn flag_drifted_evidence(evidence: &mut [Evidence], drifts: &[RunDrift]) -> usize {
+    let drifted: HashMap<(&str, &str), &RunDrift> = drifts.iter()
+        .filter(|d| d.drifted())
+        .map(|d| ((d.source.as_str(), d.run_id.as_str()), d))
+        .collect();
+
+    let mut flagged = 0;
+    for item in evidence.iter_mut() {
+        let run_id = match item.metadata.get(RUN_METADATA_KEY).and_then(|v| v.as_str()) {
+            Some(run_id) => run_id.to_string(),
+            None => continue,
+        };
+        if let Some(drift) = drifted.get(&(item.source.as_str(), run_id.as_str())) {
+            item.metadata.insert(DRIFT_METADATA_KEY.to_string(), serde_json::json!({
+                "run_id": drift.run_id,
+                "severity": drift.severity,
+                "psi": drift.psi,
+                "ks_statistic": drift.ks_statistic,
+                "weight": drift.weight,
+            }));
+            flagged += 1;
+        }
+    }
+    flagged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    fn run(run_id: &str, day: u32, confidences: impl Iterator<Item = f64>) -> Vec<Evidence> {
+        let timestamp = format!("2026-05-{:02}T09:00:00Z", day).parse::<DateTime<Utc>>().unwrap();
+        confidences.enumerate()
+            .map(|(i, confidence)| {
+                let mut evidence = Evidence::manual("m", EvidenceType::MassSpec, confidence, None, "lab").unwrap();
+                evidence.id = format!("{}-{}", run_id, i);
+                evidence.source = "orbitrap-1".to_string();
+                evidence.timestamp = timestamp;
+                evidence.metadata.insert(RUN_METADATA_KEY.to_string(), serde_json::json!(run_id));
+                evidence
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_statistics_separate_shifted_distributions() {
+        let a: Vec<f64> = (0..100).map(|i| 0.5 + i as f64 / 250.0).collect();
+        let (statistic, p_value) = ks_two_sample(&a, &a);
+        assert_eq!(statistic, 0.0);
+        assert!(p_value > 0.99);
+        assert!(population_stability_index(&a, &a).abs() < 1e-12);
+
+        let shifted: Vec<f64> = a.iter().map(|v| v - 0.3).collect();
+        let (statistic, p_value) = ks_two_sample(&a, &shifted);
+        assert!(statistic > 0.7 && p_value < 1e-6);
+        assert!(population_stability_index(&a, &shifted) > 0.25);
+    }
+
+    #[test]
+    fn test_detects_drifted_run_and_flags_its_evidence() {
+        let good = |offset: f64| (0..40).map(move |i| 0.8 + offset + (i % 10) as f64 / 100.0);
+        let mut evidence = run("r1", 1, good(0.0));
+        evidence.extend(run("r2", 2, good(0.005)));
+        evidence.extend(run("r3", 3, (0..40).map(|i| 0.45 + (i % 10) as f64 / 100.0)));
+        evidence.extend(run("r4", 4, good(0.0)));
+
+        let drifts = DriftDetector::new().detect(&evidence);
+        assert_eq!(drifts.len(), 3);
+        assert!(!drifts[0].drifted());
+        let r3 = &drifts[1];
+        assert_eq!((r3.run_id.as_str(), r3.severity), ("r3", Some(AlertSeverity::Critical)));
+        assert!(r3.mean_shift < -0.3);
+        // The drifted run is not part of the next baseline
+        assert_eq!(drifts[2].baseline_runs, vec!["r1", "r2"]);
+        assert!(!drifts[2].drifted());
+
+        assert_eq!(flag_drifted_evidence(&mut evidence, &drifts), 40);
+        let flagged = evidence.iter().find(|e| e.id == "r3-0").unwrap();
+        assert_eq!(flagged.drift_weight(), 0.5);
+        assert_eq!(evidence[0].drift_weight(), 1.0);
+        assert_eq!(r3.to_alert("default").unwrap().rule_id, DRIFT_ALERT_RULE);
+    }
+}
@@ -9,9 +9,13 @@ use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::processing::genomics::{GenomicsData, GenomicsProcessor};
+use crate::processing::genomics::{GenomicsData, GenomicsProcessor, GenomicsResult};
 use crate::processing::mass_spec::{MassSpecData, MassSpecProcessor};
 use crate::graph::neo4j::Neo4jClient;
+use crate::processing::confidence_policy::{ConfidencePolicy, PolicyViolation};
+use crate::processing::fingerprint::PipelineFingerprint;
+use crate::processing::results::SCHEMA_VERSION;
+use crate::processing::scoring_scripts::ScoringScriptRegistry;
 
 /// Initialize the evidence processing module
 pub fn initialize() -> Result<()> {
@@ -38,6 +42,9 @@ pub enum EvidenceType {
     /// Evidence from reactome analysis
     Reactome,
     
+    /// Evidence from structure elucidation (NMR, crystallography, reference standards)
+    Structural,
+    
     /// Custom or other evidence source
     Other,
 }
@@ -50,11 +57,30 @@ impl std::fmt::Display for EvidenceType {
             EvidenceType::Literature => write!(f, "literature"),
             EvidenceType::Pathway => write!(f, "pathway"),
             EvidenceType::Reactome => write!(f, "reactome"),
+            EvidenceType::Structural => write!(f, "structural"),
             EvidenceType::Other => write!(f, "other"),
         }
     }
 }
 
+impl std::str::FromStr for EvidenceType {
+    type Err = anyhow::Error;
+    
+    /// Parse the stored form of an evidence type, ignoring case
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "genomics" => Ok(EvidenceType::Genomics),
+            "mass_spec" | "massspec" => Ok(EvidenceType::MassSpec),
+            "literature" => Ok(EvidenceType::Literature),
+            "pathway" => Ok(EvidenceType::Pathway),
+            "reactome" => Ok(EvidenceType::Reactome),
+            "structural" => Ok(EvidenceType::Structural),
+            "other" => Ok(EvidenceType::Other),
+            _ => Err(anyhow::anyhow!("Unknown evidence type: {}", s)),
+        }
+    }
+}
+
 /// Evidence item for a molecule
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Evidence {
@@ -83,6 +109,95 @@ pub struct Evidence {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+impl Evidence {
+    /// Evidence entered by hand, e.g. a curator's NMR confirmation
+    ///
+    /// The source is `MANUAL_ENTRY_SOURCE`, and who entered it and any note
+    /// are kept in the metadata as its provenance.
+    pub fn manual(
+        molecule_id: &str,
+        evidence_type: EvidenceType,
+        confidence: f64,
+        note: Option<&str>,
+        entered_by: &str,
+    ) -> Result<Self> {
+        if molecule_id.trim().is_empty() {
+            return Err(anyhow::anyhow!("Manual evidence needs a molecule ID"));
+        }
+        if !(0.0..=1.0).contains(&confidence) {
+            return Err(anyhow::anyhow!("Confidence must be between 0.0 and 1.0, got {}", confidence));
+        }
+        if entered_by.trim().is_empty() {
+            return Err(anyhow::anyhow!("Manual evidence needs the name of the person entering it"));
+        }
+        
+        let mut metadata = HashMap::new();
+        metadata.insert("provenance".to_string(), serde_json::json!(MANUAL_ENTRY_SOURCE));
+        metadata.insert("entered_by".to_string(), serde_json::json!(entered_by));
+        if let Some(note) = note {
+            metadata.insert("note".to_string(), serde_json::json!(note));
+        }
+        
+        Ok(Self {
+            id: format!("manual-{}", uuid::Uuid::new_v4()),
+            molecule_id: molecule_id.to_string(),
+            evidence_type,
+            source: MANUAL_ENTRY_SOURCE.to_string(),
+            confidence,
+            data: serde_json::json!({ "note": note }),
+            metadata,
+            timestamp: chrono::Utc::now(),
+        })
+    }
+    
+    /// Record the sample the evidence was measured in
+    pub fn with_sample(mut self, sample_id: &str) -> Self {
+        self.metadata.insert(SAMPLE_METADATA_KEY.to_string(), serde_json::json!(sample_id));
+        self
+    }
+    
+    /// Sample the evidence was measured in, if recorded
+    pub fn sample_id(&self) -> Option<&str> {
+        self.metadata.get(SAMPLE_METADATA_KEY)?.as_str()
+    }
+    
+    /// Integration weight left after a drift flag, 1.0 for unflagged evidence
+    pub fn drift_weight(&self) -> f64 {
+        self.metadata.get(DRIFT_METADATA_KEY)
+            .and_then(|drift| drift.get("weight")?.as_f64())
+            .map_or(1.0, |weight| weight.clamp(0.0, 1.0))
+    }
+    
+    /// Integration weight left after a QC flag, 1.0 for unflagged evidence
+    pub fn qc_weight(&self) -> f64 {
+        self.metadata.get(QC_METADATA_KEY)
+            .and_then(|qc| qc.get("weight")?.as_f64())
+            .map_or(1.0, |weight| weight.clamp(0.0, 1.0))
+    }
+    
+    /// Integration weight of evidence derived from predicted properties, 1.0 for other evidence
+    pub fn prediction_weight(&self) -> f64 {
+        self.metadata.get(PREDICTION_METADATA_KEY)
+            .and_then(|prediction| prediction.get("weight")?.as_f64())
+            .map_or(1.0, |weight| weight.clamp(0.0, 1.0))
+    }
+}
+
+/// Source of evidence entered by hand
+pub const MANUAL_ENTRY_SOURCE: &str = "manual-entry";
+
+/// Metadata key holding the ID of the sample evidence was measured in
+pub const SAMPLE_METADATA_KEY: &str = "sample_id";
+
+/// Metadata key holding the drift flag of evidence from a drifted instrument run
+pub const DRIFT_METADATA_KEY: &str = "drift";
+
+/// Metadata key holding the QC flag of evidence from a run that failed system suitability
+pub const QC_METADATA_KEY: &str = "qc";
+
+/// Metadata key holding the predictor and weight of evidence derived from predicted properties
+pub const PREDICTION_METADATA_KEY: &str = "property_prediction";
+
 /// Integrated evidence for a molecule from multiple sources
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IntegratedEvidence {
@@ -100,6 +215,14 @@ pub struct IntegratedEvidence {
     
     /// Timestamp of the integration
     pub integration_timestamp: chrono::DateTime<chrono::Utc>,
+    
+    /// Pipeline that produced the integration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_fingerprint: Option<PipelineFingerprint>,
+    
+    /// Confidence policy rules that adjusted the aggregate confidence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy_violations: Vec<PolicyViolation>,
 }
 
 /// Conflict between evidence items
@@ -132,6 +255,18 @@ pub struct EvidenceProcessingOptions {
     
     /// Sources to prioritize
     pub priority_sources: Vec<EvidenceType>,
+    
+    /// Per-source reliability weights (sources not listed use 1.0)
+    #[serde(default)]
+    pub source_weights: HashMap<String, f64>,
+    
+    /// Registered scoring script that replaces the built-in weighted average
+    #[serde(default)]
+    pub scoring_script: Option<String>,
+    
+    /// Bounds applied to the aggregate confidence after integration
+    #[serde(default)]
+    pub confidence_policy: ConfidencePolicy,
 }
 
 impl Default for EvidenceProcessingOptions {
@@ -141,6 +276,9 @@ impl Default for EvidenceProcessingOptions {
             use_ai_guidance: true,
             max_conflicts: 10,
             priority_sources: vec![EvidenceType::Genomics, EvidenceType::MassSpec],
+            source_weights: HashMap::new(),
+            scoring_script: None,
+            confidence_policy: ConfidencePolicy::default(),
         }
     }
 }
@@ -177,6 +315,29 @@ impl EvidenceProcessor {
         self
     }
     
+    /// Set per-source reliability weights, e.g. from a `ReliabilityTracker`
+    pub fn with_source_weights(mut self, weights: HashMap<String, f64>) -> Self {
+        self.options.source_weights = weights;
+        self
+    }
+    
+    /// Replace the per-source reliability weights, e.g. after the tracker learned from a review
+    pub fn set_source_weights(&mut self, weights: HashMap<String, f64>) {
+        self.options.source_weights = weights;
+    }
+    
+    /// Combine evidence with a script from the `ScoringScriptRegistry`
+    pub fn with_scoring_script(mut self, name: &str) -> Self {
+        self.options.scoring_script = Some(name.to_string());
+        self
+    }
+    
+    /// Bound the aggregate confidence with a lab's confidence policy
+    pub fn with_confidence_policy(mut self, policy: ConfidencePolicy) -> Self {
+        self.options.confidence_policy = policy;
+        self
+    }
+    
     /// Process and integrate evidence for a molecule
     pub async fn process_evidence(&self, molecule_id: &str, evidence: Vec<Evidence>) -> Result<IntegratedEvidence> {
         debug!("Processing {} evidence items for molecule {}", evidence.len(), molecule_id);
@@ -196,6 +357,13 @@ impl EvidenceProcessor {
         let aggregate_confidence = self.calculate_aggregate_confidence(&filtered_evidence, &conflicts)?;
         debug!("Calculated aggregate confidence: {:.2}", aggregate_confidence);
         
+        // Bound it by the confidence policy, keeping a record of every adjustment
+        let (aggregate_confidence, policy_violations) = self.options.confidence_policy
+            .apply(&filtered_evidence, aggregate_confidence);
+        for violation in &policy_violations {
+            debug!("Confidence policy for {}: {}", molecule_id, violation.explanation);
+        }
+        
         // Create integrated evidence
         let integrated = IntegratedEvidence {
             molecule_id: molecule_id.to_string(),
@@ -203,31 +371,51 @@ impl EvidenceProcessor {
             aggregate_confidence,
             conflicts,
             integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: Some(self.fingerprint()?),
+            policy_violations,
         };
         
         Ok(integrated)
     }
     
+    /// Fingerprint of the processor's options and the scoring it uses
+    pub fn fingerprint(&self) -> Result<PipelineFingerprint> {
+        let mut fingerprint = PipelineFingerprint::new()
+            .with_parameters("evidence", &self.options)?
+            .with_component("results_schema", SCHEMA_VERSION);
+        if let Some(name) = &self.options.scoring_script {
+            let script = ScoringScriptRegistry::global().get(name)?;
+            fingerprint = fingerprint.with_component("scoring_script", &format!("{}@{}", name, script.digest()));
+        }
+        Ok(fingerprint)
+    }
+    
+    /// Calculate the aggregate confidence the processor would assign to a set of evidence
+    pub fn aggregate_confidence(&self, evidence: &[Evidence]) -> Result<f64> {
+        let filtered: Vec<Evidence> = evidence.iter()
+            .filter(|e| e.confidence >= self.options.confidence_threshold)
+            .cloned()
+            .collect();
+        
+        let conflicts = self.detect_conflicts(&filtered)?;
+        let confidence = self.calculate_aggregate_confidence(&filtered, &conflicts)?;
+        Ok(self.options.confidence_policy.apply(&filtered, confidence).0)
+    }
+    
     /// Process genomics data and convert to evidence
     pub fn process_genomics_data(&self, molecule_id: &str, data: &GenomicsData) -> Result<Vec<Evidence>> {
         self.genomics_processor.process(molecule_id, data)
-            .map(|results| {
-                results.into_iter()
-                    .map(|result| Evidence {
-                        id: format!("genomics-{}-{}", molecule_id, uuid::Uuid::new_v4()),
-                        molecule_id: molecule_id.to_string(),
-                        evidence_type: EvidenceType::Genomics,
-                        source: "genomics_analysis".to_string(),
-                        confidence: result.confidence,
-                        data: serde_json::to_value(&result).unwrap_or_default(),
-                        metadata: HashMap::new(),
-                        timestamp: chrono::Utc::now(),
-                    })
-                    .collect()
-            })
+            .map(|results| genomics_evidence(molecule_id, results))
             .context("Failed to process genomics data")
     }
     
+    /// Process a dataset of genomics samples together, batch-correcting them first
+    pub fn process_genomics_dataset(&self, molecule_id: &str, samples: &[GenomicsData]) -> Result<Vec<Evidence>> {
+        self.genomics_processor.process_dataset(molecule_id, samples)
+            .map(|results| genomics_evidence(molecule_id, results))
+            .context("Failed to process genomics dataset")
+    }
+    
     /// Process mass spectrometry data and convert to evidence
     pub fn process_mass_spec_data(&self, molecule_id: &str, data: &MassSpecData) -> Result<Vec<Evidence>> {
         self.mass_spec_processor.process(molecule_id, data)
@@ -307,22 +495,28 @@ impl EvidenceProcessor {
             return Ok(0.0);
         }
         
+        let weighted: Vec<(&Evidence, f64)> = evidence.iter()
+            .map(|ev| (ev, self.evidence_weight(ev)))
+            .collect();
+        
+        if let Some(name) = &self.options.scoring_script {
+            let script = ScoringScriptRegistry::global().get(name)?;
+            return script.score(&weighted, conflicts);
+        }
+        
         // Start with weighted average of individual confidences
         let mut total_weight = 0.0;
         let mut weighted_sum = 0.0;
         
-        for ev in evidence {
-            // Prioritize evidence from priority sources
-            let weight = if self.options.priority_sources.contains(&ev.evidence_type) {
-                2.0
-            } else {
-                1.0
-            };
-            
+        for (ev, weight) in weighted {
             weighted_sum += ev.confidence * weight;
             total_weight += weight;
         }
         
+        if total_weight <= 0.0 {
+            return Ok(0.0);
+        }
+        
         let mut aggregate = weighted_sum / total_weight;
         
         // Adjust for conflicts
@@ -340,6 +534,34 @@ impl EvidenceProcessor {
         
         Ok(aggregate)
     }
+    
+    /// Integration weight of one evidence item
+    fn evidence_weight(&self, ev: &Evidence) -> f64 {
+        // Prioritize evidence from priority sources
+        let priority_weight = if self.options.priority_sources.contains(&ev.evidence_type) {
+            2.0
+        } else {
+            1.0
+        };
+        let reliability = self.options.source_weights.get(&ev.source).copied().unwrap_or(1.0);
+        priority_weight * reliability * ev.drift_weight() * ev.qc_weight() * ev.prediction_weight()
+    }
+}
+
+/// Genomics evidence, one item per processing result
+fn genomics_evidence(molecule_id: &str, results: Vec<GenomicsResult>) -> Vec<Evidence> {
+    results.into_iter()
+        .map(|result| Evidence {
+            id: format!("genomics-{}-{}", molecule_id, uuid::Uuid::new_v4()),
+            molecule_id: molecule_id.to_string(),
+            evidence_type: EvidenceType::Genomics,
+            source: "genomics_analysis".to_string(),
+            confidence: result.confidence,
+            data: serde_json::to_value(&result).unwrap_or_default(),
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -350,6 +572,19 @@ mod tests {
     fn test_evidence_type_display() {
         assert_eq!(EvidenceType::Genomics.to_string(), "genomics");
         assert_eq!(EvidenceType::MassSpec.to_string(), "mass_spec");
+        assert_eq!("structural".parse::<EvidenceType>().unwrap(), EvidenceType::Structural);
+    }
+    
+    #[test]
+    fn test_manual_evidence_validation() {
+        let evidence = Evidence::manual("glucose", EvidenceType::Structural, 0.95, Some("1H NMR matches standard"), "ada").unwrap();
+        assert_eq!(evidence.source, MANUAL_ENTRY_SOURCE);
+        assert_eq!(evidence.metadata["entered_by"], "ada");
+        assert!(evidence.id.starts_with("manual-"));
+        
+        assert!(Evidence::manual("glucose", EvidenceType::Structural, 1.5, None, "ada").is_err());
+        assert!(Evidence::manual("", EvidenceType::Structural, 0.9, None, "ada").is_err());
+        assert!(Evidence::manual("glucose", EvidenceType::Structural, 0.9, None, " ").is_err());
     }
     
     #[test]
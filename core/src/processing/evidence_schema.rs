@@ -0,0 +1,272 @@
+//! Evidence Schema Registry
+//!
+//! `Evidence.data` is free-form JSON whose shape depends on the evidence type.
+//! The registry maps each evidence type to a JSON Schema (draft 7) that the
+//! data must satisfy, and ingestion validates evidence against it before it
+//! enters the pipeline. Evidence that passes is stamped with the schema
+//! version in its metadata, so later readers know which contract it met.
+//!
+//! The built-in schemas live in `schemas/evidence` and are embedded at build
+//! time. `HEGEL_EVIDENCE_SCHEMAS_DIR` names a directory of `<type>.json`
+//! files that replace them; a schema's version is its top-level `version`
+//! annotation. Types without a schema are accepted as they are.
+
+use anyhow::{anyhow, Context, Result};
+use jsonschema::{Draft, JSONSchema};
+use log::{debug, info};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::processing::evidence::{Evidence, EvidenceType};
+
+/// Metadata key recording the schema an evidence item was validated against
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Schemas shipped with Hegel, by evidence type
+const BUILTIN: [(EvidenceType, &str); 6] = [
+    (EvidenceType::MassSpec, include_str!("../../schemas/evidence/mass_spec.json")),
+    (EvidenceType::Genomics, include_str!("../../schemas/evidence/genomics.json")),
+    (EvidenceType::Literature, include_str!("../../schemas/evidence/literature.json")),
+    (EvidenceType::Pathway, include_str!("../../schemas/evidence/pathway.json")),
+    (EvidenceType::Reactome, include_str!("../../schemas/evidence/reactome.json")),
+    (EvidenceType::Structural, include_str!("../../schemas/evidence/structural.json")),
+];
+
+/// Initialize the evidence schema module
+pub fn initialize() -> Result<()> {
+    info!("Initializing evidence schema module");
+    EvidenceSchemaRegistry::builtin()?;
+    info!("Evidence schema module initialized successfully");
+    Ok(())
+}
+
+/// A compiled schema for one evidence type
+#[derive(Clone)]
+pub struct EvidenceSchema {
+    /// Schema version, from the `version` annotation
+    pub version: String,
+
+    /// Schema document
+    pub document: serde_json::Value,
+
+    /// Compiled validator
+    compiled: Arc<JSONSchema>,
+}
+
+impl fmt::Debug for EvidenceSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EvidenceSchema").field("version", &self.version).finish()
+    }
+}
+
+impl EvidenceSchema {
+    /// Compile a schema document
+    pub fn compile(document: serde_json::Value) -> Result<Self> {
+        let version = document.get("version")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Evidence schema has no `version` string"))?
+            .to_string();
+        let compiled = JSONSchema::options()
+            .with_draft(Draft::Draft7)
+            .compile(&document)
+            .map_err(|e| anyhow!("Invalid evidence schema: {}", e))?;
+        Ok(Self { version, document, compiled: Arc::new(compiled) })
+    }
+
+    /// Every way in which `data` breaks the schema
+    pub fn violations(&self, data: &serde_json::Value) -> Vec<SchemaViolation> {
+        match self.compiled.validate(data) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .map(|e| SchemaViolation {
+                    path: e.instance_path.to_string(),
+                    message: e.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Schemas for evidence data, by evidence type
+#[derive(Debug, Clone, Default)]
+pub struct EvidenceSchemaRegistry {
+    /// Schema per evidence type
+    schemas: HashMap<EvidenceType, EvidenceSchema>,
+}
+
+impl EvidenceSchemaRegistry {
+    /// Schemas shipped with Hegel
+    pub fn builtin() -> Result<Self> {
+        let mut registry = Self::default();
+        for (evidence_type, source) in BUILTIN {
+            let document = serde_json::from_str(source)
+                .with_context(|| format!("Built-in {} evidence schema is not valid JSON", evidence_type))?;
+            registry.register(evidence_type, document)?;
+        }
+        Ok(registry)
+    }
+
+    /// Built-in schemas replaced by the `<type>.json` files in a directory
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let mut registry = Self::builtin()?;
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read evidence schema directory {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let evidence_type: EvidenceType = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem.parse()
+                    .with_context(|| format!("Evidence schema {} is not named after an evidence type", path.display()))?,
+                None => continue,
+            };
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read evidence schema {}", path.display()))?;
+            let document = serde_json::from_str(&content)
+                .with_context(|| format!("Evidence schema {} is not valid JSON", path.display()))?;
+            registry.register(evidence_type, document)
+                .with_context(|| format!("Invalid evidence schema {}", path.display()))?;
+            debug!("Loaded {} evidence schema from {}", evidence_type, path.display());
+        }
+        Ok(registry)
+    }
+
+    /// Built-in schemas, replaced from `HEGEL_EVIDENCE_SCHEMAS_DIR` when it is set
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("HEGEL_EVIDENCE_SCHEMAS_DIR") {
+            Ok(dir) if !dir.is_empty() => Self::load_dir(Path::new(&dir)),
+            _ => Self::builtin(),
+        }
+    }
+
+    /// Add or replace the schema for an evidence type
+    pub fn register(&mut self, evidence_type: EvidenceType, document: serde_json::Value) -> Result<()> {
+        self.schemas.insert(evidence_type, EvidenceSchema::compile(document)?);
+        Ok(())
+    }
+
+    /// Schema for an evidence type, if it has one
+    pub fn get(&self, evidence_type: EvidenceType) -> Option<&EvidenceSchema> {
+        self.schemas.get(&evidence_type)
+    }
+
+    /// Schema documents by evidence type name
+    pub fn documents(&self) -> HashMap<String, serde_json::Value> {
+        self.schemas.iter()
+            .map(|(evidence_type, schema)| (evidence_type.to_string(), schema.document.clone()))
+            .collect()
+    }
+
+    /// Validate an evidence item and record the schema version in its metadata
+    pub fn validate(&self, evidence: &mut Evidence) -> std::result::Result<(), EvidenceValidationError> {
+        let schema = match self.get(evidence.evidence_type) {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+        let violations = schema.violations(&evidence.data);
+        if !violations.is_empty() {
+            return Err(EvidenceValidationError {
+                evidence_id: evidence.id.clone(),
+                evidence_type: evidence.evidence_type,
+                schema_version: schema.version.clone(),
+                violations,
+            });
+        }
+        evidence.metadata.insert(
+            SCHEMA_VERSION_KEY.to_string(),
+            serde_json::json!(format!("{}/v{}", evidence.evidence_type, schema.version)),
+        );
+        Ok(())
+    }
+
+    /// Validate every item, collecting the failures of all of them
+    pub fn validate_all(&self, evidence: &mut [Evidence]) -> std::result::Result<(), Vec<EvidenceValidationError>> {
+        let errors: Vec<_> = evidence.iter_mut()
+            .filter_map(|item| self.validate(item).err())
+            .collect();
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+/// One way in which evidence data breaks its schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value within the data; empty for the root
+    pub path: String,
+
+    /// What is wrong with it
+    pub message: String,
+}
+
+/// Evidence data does not match the schema for its type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceValidationError {
+    /// Evidence item that failed
+    pub evidence_id: String,
+
+    /// Its evidence type
+    pub evidence_type: EvidenceType,
+
+    /// Version of the schema it was checked against
+    pub schema_version: String,
+
+    /// Every violation found
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl fmt::Display for EvidenceValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let violations: Vec<String> = self.violations.iter()
+            .map(|v| if v.path.is_empty() { v.message.clone() } else { format!("{}: {}", v.path, v.message) })
+            .collect();
+        write!(
+            f,
+            "Evidence {} does not match the {} schema v{}: {}",
+            self.evidence_id, self.evidence_type, self.schema_version, violations.join("; ")
+        )
+    }
+}
+
+impl Error for EvidenceValidationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(evidence_type: EvidenceType, data: serde_json::Value) -> Evidence {
+        Evidence {
+            id: "ev-1".to_string(),
+            molecule_id: "caffeine".to_string(),
+            evidence_type,
+            source: "orbitrap".to_string(),
+            confidence: 0.8,
+            data,
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_validation_reports_violations_and_stamps_version() {
+        let registry = EvidenceSchemaRegistry::builtin().unwrap();
+
+        let mut valid = evidence(EvidenceType::MassSpec, serde_json::json!({"mass_error_ppm": 1.2, "intensity": 5.0e6}));
+        registry.validate(&mut valid).unwrap();
+        assert_eq!(valid.metadata[SCHEMA_VERSION_KEY], "mass_spec/v1");
+
+        let mut invalid = evidence(EvidenceType::MassSpec, serde_json::json!({"mass_error_ppm": "1.2 ppm", "intensity": -3}));
+        let error = registry.validate(&mut invalid).unwrap_err();
+        assert_eq!(error.violations.len(), 2);
+        assert!(error.violations.iter().any(|v| v.path == "/mass_error_ppm"));
+        assert!(!invalid.metadata.contains_key(SCHEMA_VERSION_KEY));
+
+        let mut untyped = evidence(EvidenceType::Other, serde_json::json!("anything"));
+        assert!(registry.validate(&mut untyped).is_ok());
+    }
+}
@@ -0,0 +1,649 @@
+//! Feature Table Module
+//!
+//! Untargeted metabolomics starts from a feature table: one row per detected
+//! feature with its m/z, retention time and intensity in every sample, as
+//! exported by XCMS or MZmine. Features are matched against a local compound
+//! store by the m/z of the compound's adducts and, when the compound has a
+//! known retention time, by retention time, and every candidate becomes a
+//! mass spec evidence item for the candidate molecule. Stable-isotope labeled
+//! standards are matched at their labeled mass and their evidence supports
+//! the unlabeled parent compound.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::processing::biotransform::{monoisotopic_mass, MONOISOTOPIC_MASS_PROPERTIES};
+use crate::processing::evidence::{Evidence, EvidenceType};
+use crate::processing::formula::IsotopeLabel;
+use crate::processing::mass_accuracy::{ppm_error, MassAccuracyModel};
+use crate::processing::mass_spec::MassSpecType;
+use crate::processing::units::{Quantity, Unit};
+use crate::processing::Molecule;
+
+/// Initialize the feature table module
+pub fn initialize() -> Result<()> {
+    info!("Initializing feature table module");
+    info!("Feature table module initialized successfully");
+    Ok(())
+}
+
+/// Source prefix of evidence created from feature tables
+pub const FEATURE_TABLE_SOURCE: &str = "feature-table";
+
+/// Molecule property holding a compound's reference retention time in minutes
+pub const RETENTION_TIME_PROPERTY: &str = "retention_time";
+
+/// Molecule property holding the stable-isotope label of a labeled compound, e.g. `13C6`
+pub const ISOTOPE_LABEL_PROPERTY: &str = "isotope_label";
+
+/// Molecule property holding the ID of a labeled compound's unlabeled parent
+pub const LABELED_PARENT_PROPERTY: &str = "unlabeled_parent";
+
+/// XCMS columns that describe a feature rather than hold a sample intensity
+const XCMS_METADATA_COLUMNS: [&str; 16] = [
+    "name", "featureid", "feature_id", "mz", "mzmed", "mzmin", "mzmax", "rt", "rtmed", "rtmin",
+    "rtmax", "npeaks", "fold", "tstat", "pvalue", "peakidx",
+];
+
+/// Tool a feature table was exported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeatureTableFormat {
+    /// XCMS `featureDefinitions`/`featureValues` or diffreport export, retention times in seconds
+    Xcms,
+
+    /// MZmine 2 or 3 CSV export, retention times in minutes
+    Mzmine,
+}
+
+impl FeatureTableFormat {
+    /// Recognize the exporting tool from the header row
+    pub fn detect(header: &[String]) -> Option<Self> {
+        let has = |name: &str| header.iter().any(|h| h.eq_ignore_ascii_case(name));
+        if has("row m/z") || header.iter().any(|h| h.starts_with("datafile:")) {
+            Some(FeatureTableFormat::Mzmine)
+        } else if has("mzmed") || (has("mz") && has("rt")) {
+            Some(FeatureTableFormat::Xcms)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for FeatureTableFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FeatureTableFormat::Xcms => write!(f, "xcms"),
+            FeatureTableFormat::Mzmine => write!(f, "mzmine"),
+        }
+    }
+}
+
+impl FromStr for FeatureTableFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "xcms" => Ok(FeatureTableFormat::Xcms),
+            "mzmine" | "mzmine2" | "mzmine3" => Ok(FeatureTableFormat::Mzmine),
+            other => Err(anyhow!("Unknown feature table format: {}", other)),
+        }
+    }
+}
+
+/// A detected feature and its intensity in each sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feature {
+    /// Feature ID from the export
+    pub id: String,
+
+    /// Feature m/z
+    pub mz: f64,
+
+    /// Feature retention time
+    pub retention_time: Quantity,
+
+    /// Intensity per sample, in the order of the table's samples; `None` when not detected
+    pub intensities: Vec<Option<f64>>,
+}
+
+impl Feature {
+    /// Mean intensity over the samples the feature was detected in
+    pub fn mean_intensity(&self) -> Option<f64> {
+        let detected: Vec<f64> = self.intensities.iter().flatten().copied().collect();
+        if detected.is_empty() {
+            return None;
+        }
+        Some(detected.iter().sum::<f64>() / detected.len() as f64)
+    }
+}
+
+/// Features of an XCMS or MZmine export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureTable {
+    /// Tool the table was exported from
+    pub format: FeatureTableFormat,
+
+    /// Sample names, in column order
+    pub samples: Vec<String>,
+
+    /// Features, in row order
+    pub features: Vec<Feature>,
+}
+
+impl FeatureTable {
+    /// Load a feature table, detecting the format from the header unless given
+    pub fn load(path: &Path, format: Option<FeatureTableFormat>) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read feature table: {}", path.display()))?;
+        Self::parse(&content, format)
+    }
+
+    /// Parse a comma- or tab-separated feature table
+    pub fn parse(content: &str, format: Option<FeatureTableFormat>) -> Result<Self> {
+        let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+        let header_line = lines.next().ok_or_else(|| anyhow!("Feature table is empty"))?;
+        let delimiter = if header_line.contains('\t') { '\t' } else { ',' };
+        let header = split_row(header_line, delimiter);
+        let format = match format {
+            Some(format) => format,
+            None => FeatureTableFormat::detect(&header)
+                .ok_or_else(|| anyhow!("Unrecognized feature table header; pass the format explicitly"))?,
+        };
+
+        let column = |names: &[&str]| header.iter().position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)));
+        let (id_column, mz_column, rt_column, rt_unit, sample_columns) = match format {
+            FeatureTableFormat::Xcms => {
+                let samples: Vec<(usize, String)> = header.iter().enumerate().skip(1)
+                    .filter(|(_, h)| !XCMS_METADATA_COLUMNS.contains(&h.to_lowercase().as_str()))
+                    .map(|(i, h)| (i, h.clone()))
+                    .collect();
+                (column(&["name", "featureid", "feature_id"]).unwrap_or(0),
+                 column(&["mzmed", "mz"]), column(&["rtmed", "rt"]), Unit::Second, samples)
+            }
+            FeatureTableFormat::Mzmine => {
+                let mut samples = mzmine_samples(&header, "area");
+                if samples.is_empty() {
+                    samples = mzmine_samples(&header, "height");
+                }
+                (column(&["row ID", "id"]).unwrap_or(0),
+                 column(&["row m/z", "mz"]), column(&["row retention time", "rt"]), Unit::Minute, samples)
+            }
+        };
+        let mz_column = mz_column.ok_or_else(|| anyhow!("Feature table has no m/z column"))?;
+        let rt_column = rt_column.ok_or_else(|| anyhow!("Feature table has no retention time column"))?;
+
+        let mut features = Vec::new();
+        let mut skipped = 0;
+        for line in lines {
+            let cells = split_row(line, delimiter);
+            let number = |i: usize| cells.get(i).and_then(|c| c.parse::<f64>().ok());
+            let (mz, rt) = match (number(mz_column), number(rt_column)) {
+                (Some(mz), Some(rt)) if mz > 0.0 && rt >= 0.0 => (mz, rt),
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            features.push(Feature {
+                id: cells.get(id_column).filter(|c| !c.is_empty()).cloned()
+                    .unwrap_or_else(|| format!("FT{:05}", features.len() + 1)),
+                mz,
+                retention_time: Quantity::new(rt, rt_unit),
+                intensities: sample_columns.iter()
+                    .map(|(i, _)| number(*i).filter(|v| v.is_finite() && *v > 0.0))
+                    .collect(),
+            });
+        }
+
+        if features.is_empty() {
+            return Err(anyhow!("Feature table has no valid rows"));
+        }
+        if skipped > 0 {
+            warn!("Skipped {} feature rows without a valid m/z and retention time", skipped);
+        }
+        debug!("Parsed {} {} features in {} samples", features.len(), format, sample_columns.len());
+        Ok(Self {
+            format,
+            samples: sample_columns.into_iter().map(|(_, name)| name).collect(),
+            features,
+        })
+    }
+}
+
+/// Sample columns of an MZmine export holding the given quantity (`area` or `height`)
+///
+/// MZmine 2 names them `<file> Peak area`, MZmine 3 `datafile:<file>:area`.
+fn mzmine_samples(header: &[String], quantity: &str) -> Vec<(usize, String)> {
+    let mzmine2_suffix = format!(" peak {}", quantity);
+    let mzmine3_suffix = format!(":{}", quantity);
+    header.iter().enumerate()
+        .filter_map(|(i, h)| {
+            let lower = h.to_lowercase();
+            if lower.ends_with(&mzmine2_suffix) {
+                Some((i, h[..h.len() - mzmine2_suffix.len()].to_string()))
+            } else if lower.starts_with("datafile:") && lower.ends_with(&mzmine3_suffix) {
+                Some((i, h["datafile:".len()..h.len() - mzmine3_suffix.len()].to_string()))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Split a delimited row, honouring double-quoted cells
+fn split_row(line: &str, delimiter: char) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// Ionization polarity of the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Polarity {
+    /// Positive ion mode
+    Positive,
+
+    /// Negative ion mode
+    Negative,
+}
+
+impl FromStr for Polarity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "positive" | "pos" | "+" => Ok(Polarity::Positive),
+            "negative" | "neg" | "-" => Ok(Polarity::Negative),
+            other => Err(anyhow!("Unknown polarity: {}", other)),
+        }
+    }
+}
+
+/// A singly charged adduct and the mass it adds to the neutral molecule
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Adduct {
+    /// Adduct notation
+    pub name: &'static str,
+
+    /// Mass added to the neutral monoisotopic mass, in Daltons
+    pub mass_shift: f64,
+
+    /// Polarity the adduct forms in
+    pub polarity: Polarity,
+}
+
+/// Common adducts in electrospray ionization
+pub const ADDUCTS: [Adduct; 7] = [
+    Adduct { name: "[M+H]+", mass_shift: 1.007276, polarity: Polarity::Positive },
+    Adduct { name: "[M+NH4]+", mass_shift: 18.033823, polarity: Polarity::Positive },
+    Adduct { name: "[M+Na]+", mass_shift: 22.989218, polarity: Polarity::Positive },
+    Adduct { name: "[M+K]+", mass_shift: 38.963158, polarity: Polarity::Positive },
+    Adduct { name: "[M-H]-", mass_shift: -1.007276, polarity: Polarity::Negative },
+    Adduct { name: "[M+Cl]-", mass_shift: 34.969402, polarity: Polarity::Negative },
+    Adduct { name: "[M+FA-H]-", mass_shift: 44.998201, polarity: Polarity::Negative },
+];
+
+/// A compound of the local store, with the values features are matched on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCompound {
+    /// Molecule ID
+    pub id: String,
+
+    /// Compound name
+    pub name: Option<String>,
+
+    /// Neutral monoisotopic mass
+    pub monoisotopic_mass: f64,
+
+    /// Reference retention time on the lab's method, if measured
+    pub retention_time: Option<Quantity>,
+
+    /// Stable-isotope label, for labeled standards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isotope_label: Option<IsotopeLabel>,
+
+    /// Unlabeled compound a labeled standard stands in for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labeled_parent: Option<String>,
+}
+
+/// Local compounds that features are matched against
+#[derive(Debug, Clone, Default)]
+pub struct CompoundStore {
+    /// Compounds ordered by monoisotopic mass
+    compounds: Vec<StoredCompound>,
+}
+
+impl CompoundStore {
+    /// Store of the molecules that have a monoisotopic mass
+    ///
+    /// A `retention_time` property, in minutes, is used as the reference
+    /// retention time. A compound with an `isotope_label` property is matched
+    /// at its labeled mass: a stored monoisotopic mass is taken as already
+    /// labeled, otherwise the label's shift is added to the formula's mass.
+    pub fn from_molecules(molecules: &[Molecule]) -> Self {
+        let mut compounds: Vec<StoredCompound> = molecules.iter()
+            .filter_map(|molecule| {
+                let isotope_label = match molecule.properties.get(ISOTOPE_LABEL_PROPERTY).and_then(|l| l.as_str()) {
+                    Some(label) => match label.parse::<IsotopeLabel>() {
+                        Ok(label) => Some(label),
+                        Err(e) => {
+                            warn!("Ignoring compound {}: {}", molecule.id, e);
+                            return None;
+                        }
+                    },
+                    None => None,
+                };
+                let mass = match (&isotope_label, &molecule.formula) {
+                    (Some(label), Some(formula)) if !has_stored_mass(molecule) => formula.labeled_mass(label).ok()?,
+                    _ => monoisotopic_mass(molecule).ok()?,
+                };
+                Some(StoredCompound {
+                    id: molecule.id.clone(),
+                    name: molecule.name.clone(),
+                    monoisotopic_mass: mass,
+                    retention_time: molecule.properties.get(RETENTION_TIME_PROPERTY)
+                        .and_then(|rt| rt.as_f64())
+                        .map(Quantity::minutes),
+                    labeled_parent: isotope_label.as_ref()
+                        .and_then(|_| molecule.properties.get(LABELED_PARENT_PROPERTY)?.as_str())
+                        .map(str::to_string),
+                    isotope_label,
+                })
+            })
+            .collect();
+        if compounds.len() < molecules.len() {
+            warn!("{} of {} compounds have no monoisotopic mass and can't be matched",
+                  molecules.len() - compounds.len(), molecules.len());
+        }
+        compounds.sort_by(|a, b| a.monoisotopic_mass.total_cmp(&b.monoisotopic_mass));
+        Self { compounds }
+    }
+
+    /// Load a JSON array of molecules
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read compound store: {}", path.display()))?;
+        let molecules: Vec<Molecule> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse compound store: {}", path.display()))?;
+        Ok(Self::from_molecules(&molecules))
+    }
+
+    /// Number of matchable compounds
+    pub fn len(&self) -> usize {
+        self.compounds.len()
+    }
+
+    /// Whether no compound can be matched
+    pub fn is_empty(&self) -> bool {
+        self.compounds.is_empty()
+    }
+
+    /// Compounds whose neutral mass lies in `[low, high]`
+    fn in_mass_range(&self, low: f64, high: f64) -> &[StoredCompound] {
+        let start = self.compounds.partition_point(|c| c.monoisotopic_mass < low);
+        let end = self.compounds.partition_point(|c| c.monoisotopic_mass <= high);
+        &self.compounds[start..end.max(start)]
+    }
+}
+
+/// Whether a molecule carries its monoisotopic mass as a property
+fn has_stored_mass(molecule: &Molecule) -> bool {
+    MONOISOTOPIC_MASS_PROPERTIES.iter().any(|key| molecule.properties.get(*key).is_some_and(|m| m.is_f64()))
+}
+
+/// Options for matching features to compounds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureMatchOptions {
+    /// Mass tolerance, in ppm or Daltons
+    pub mass_tolerance: Quantity,
+
+    /// Retention time tolerance, for compounds with a reference retention time
+    pub rt_tolerance: Quantity,
+
+    /// Polarity of the run, selecting the adducts considered
+    pub polarity: Polarity,
+
+    /// Instrument type, selecting the mass error model
+    pub ms_type: MassSpecType,
+
+    /// Most candidates kept per feature
+    pub max_candidates: usize,
+}
+
+impl Default for FeatureMatchOptions {
+    fn default() -> Self {
+        Self {
+            mass_tolerance: Quantity::ppm(10.0),
+            rt_tolerance: Quantity::minutes(0.5),
+            polarity: Polarity::Positive,
+            ms_type: MassSpecType::LCMSMS,
+            max_candidates: 5,
+        }
+    }
+}
+
+/// A compound that may explain a feature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureCandidate {
+    /// Feature ID
+    pub feature_id: String,
+
+    /// Candidate molecule ID, the unlabeled parent when a labeled standard matched
+    pub molecule_id: String,
+
+    /// Labeled standard that matched, when the feature is a labeled analog of the candidate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labeled_standard: Option<String>,
+
+    /// Candidate name
+    pub name: Option<String>,
+
+    /// Adduct the feature is explained as
+    pub adduct: String,
+
+    /// m/z of the candidate as that adduct
+    pub theoretical_mz: f64,
+
+    /// Mass error in ppm
+    pub ppm_error: f64,
+
+    /// Retention time difference in minutes, when the candidate has a reference retention time
+    pub rt_error: Option<f64>,
+
+    /// Match score from mass accuracy and retention time agreement (0.0 - 1.0)
+    pub score: f64,
+}
+
+/// Candidate compounds for a feature, best first
+pub fn match_feature(feature: &Feature, store: &CompoundStore, options: &FeatureMatchOptions) -> Result<Vec<FeatureCandidate>> {
+    let model = MassAccuracyModel::default();
+    let window = options.mass_tolerance.to_at(Unit::Dalton, feature.mz)?.value;
+    let rt_tolerance = options.rt_tolerance.value_in(Unit::Minute)?;
+    let feature_rt = feature.retention_time.value_in(Unit::Minute)?;
+
+    let mut candidates = Vec::new();
+    for adduct in ADDUCTS.iter().filter(|a| a.polarity == options.polarity) {
+        let neutral = feature.mz - adduct.mass_shift;
+        for compound in store.in_mass_range(neutral - window, neutral + window) {
+            let theoretical_mz = compound.monoisotopic_mass + adduct.mass_shift;
+            if !options.mass_tolerance.matches_mz(feature.mz, theoretical_mz)? {
+                continue;
+            }
+            let rt_error = compound.retention_time
+                .map(|rt| rt.value_in(Unit::Minute).map(|rt| feature_rt - rt))
+                .transpose()?;
+            if rt_error.is_some_and(|error| error.abs() > rt_tolerance) {
+                continue;
+            }
+
+            let error = ppm_error(feature.mz, theoretical_mz);
+            let rt_agreement = rt_error.map_or(1.0, |error| 1.0 - 0.5 * (error / rt_tolerance).powi(2));
+            candidates.push(FeatureCandidate {
+                feature_id: feature.id.clone(),
+                molecule_id: compound.labeled_parent.clone().unwrap_or_else(|| compound.id.clone()),
+                labeled_standard: compound.labeled_parent.as_ref().map(|_| compound.id.clone()),
+                name: compound.name.clone(),
+                adduct: adduct.name.to_string(),
+                theoretical_mz,
+                ppm_error: error,
+                rt_error,
+                score: (model.likelihood(options.ms_type, error) * rt_agreement).clamp(0.0, 1.0),
+            });
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.molecule_id.cmp(&b.molecule_id)));
+    candidates.truncate(options.max_candidates);
+    Ok(candidates)
+}
+
+/// Match every feature of a table and create one evidence item per candidate
+///
+/// Each item supports the candidate molecule with the match score as its
+/// confidence, and carries the feature and its intensities as data. A match
+/// to a labeled standard corroborates its unlabeled parent rather than
+/// proposing the standard as a competing identification.
+pub fn feature_evidence(table: &FeatureTable, store: &CompoundStore, options: &FeatureMatchOptions) -> Result<Vec<Evidence>> {
+    let mut evidence = Vec::new();
+    let mut matched = 0;
+    for feature in &table.features {
+        let candidates = match_feature(feature, store, options)?;
+        if !candidates.is_empty() {
+            matched += 1;
+        }
+        for candidate in candidates {
+            let intensities: HashMap<&str, f64> = table.samples.iter()
+                .zip(&feature.intensities)
+                .filter_map(|(sample, intensity)| Some((sample.as_str(), (*intensity)?)))
+                .collect();
+            let mut metadata = HashMap::new();
+            metadata.insert("feature_id".to_string(), serde_json::json!(feature.id));
+            metadata.insert("adduct".to_string(), serde_json::json!(candidate.adduct));
+            metadata.insert("theoretical_mz".to_string(), serde_json::json!(candidate.theoretical_mz));
+            metadata.insert("table_format".to_string(), serde_json::json!(table.format));
+            if let Some(standard) = &candidate.labeled_standard {
+                metadata.insert("labeled_standard".to_string(), serde_json::json!(standard));
+            }
+
+            let matched_id = candidate.labeled_standard.as_deref().unwrap_or(&candidate.molecule_id);
+            evidence.push(Evidence {
+                id: format!("{}-{}-{}-{}", FEATURE_TABLE_SOURCE, feature.id, matched_id, candidate.adduct),
+                molecule_id: candidate.molecule_id.clone(),
+                evidence_type: EvidenceType::MassSpec,
+                source: format!("{}:{}", FEATURE_TABLE_SOURCE, table.format),
+                confidence: candidate.score,
+                data: serde_json::json!({
+                    "mz": feature.mz,
+                    "retention_time": feature.retention_time,
+                    "ppm_error": candidate.ppm_error,
+                    "rt_error": candidate.rt_error,
+                    "mean_intensity": feature.mean_intensity(),
+                    "intensities": intensities,
+                }),
+                metadata,
+                timestamp: chrono::Utc::now(),
+            });
+        }
+    }
+
+    info!("Matched {} of {} features to {} compounds ({} evidence items)",
+          matched, table.features.len(), store.len(), evidence.len());
+    Ok(evidence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compound(id: &str, mass: f64, rt: Option<f64>) -> Molecule {
+        let mut molecule = Molecule::from_smiles("C").unwrap();
+        molecule.id = id.to_string();
+        molecule.properties.insert("monoisotopic_mass".to_string(), serde_json::json!(mass));
+        if let Some(rt) = rt {
+            molecule.properties.insert(RETENTION_TIME_PROPERTY.to_string(), serde_json::json!(rt));
+        }
+        molecule
+    }
+
+    #[test]
+    fn test_parse_xcms_and_mzmine_exports() {
+        let xcms = "\"\"\t\"mzmed\"\t\"mzmin\"\t\"mzmax\"\t\"rtmed\"\t\"rtmin\"\t\"rtmax\"\t\"npeaks\"\t\"S1\"\t\"S2\"\n\
+                    \"FT001\"\t195.0877\t195.0875\t195.0879\t120\t118\t122\t2\t15000\tNA\n";
+        let table = FeatureTable::parse(xcms, None).unwrap();
+        assert_eq!(table.format, FeatureTableFormat::Xcms);
+        assert_eq!(table.samples, vec!["S1", "S2"]);
+        assert_eq!(table.features[0].id, "FT001");
+        assert_eq!(table.features[0].retention_time.value_in(Unit::Minute).unwrap(), 2.0);
+        assert_eq!(table.features[0].intensities, vec![Some(15000.0), None]);
+
+        let mzmine = "row ID,row m/z,row retention time,a.mzML Peak area,b.mzML Peak area,a.mzML Peak height\n\
+                      7,195.0878,2.05,1200,1300,400\n";
+        let table = FeatureTable::parse(mzmine, None).unwrap();
+        assert_eq!(table.format, FeatureTableFormat::Mzmine);
+        assert_eq!(table.samples, vec!["a.mzML", "b.mzML"]);
+        assert_eq!(table.features[0].mean_intensity(), Some(1250.0));
+
+        assert!(FeatureTable::parse("id,intensity\n1,2\n", None).is_err());
+    }
+
+    #[test]
+    fn test_feature_evidence_uses_adducts_and_retention_time() {
+        // Caffeine [M+H]+ at 195.0877, [M+Na]+ at 217.0696
+        let store = CompoundStore::from_molecules(&[
+            compound("caffeine", 194.080376, Some(2.0)),
+            compound("caffeine-late", 194.080376, Some(5.0)),
+            compound("no-mass", 0.0, None),
+        ]);
+        let table = FeatureTable::parse(
+            "name,mzmed,rtmed,S1\nFT1,195.0877,121,1000\nFT2,217.0696,118,800\nFT3,300.0,60,50\n", None,
+        ).unwrap();
+
+        let evidence = feature_evidence(&table, &store, &FeatureMatchOptions::default()).unwrap();
+        assert_eq!(evidence.len(), 2);
+        assert!(evidence.iter().all(|e| e.molecule_id == "caffeine"));
+        assert_eq!(evidence[1].metadata["adduct"], "[M+Na]+");
+        assert!(evidence[0].confidence > 0.9);
+    }
+
+    #[test]
+    fn test_labeled_standard_corroborates_parent() {
+        let mut standard = compound("caffeine-13c3", 0.0, Some(2.0));
+        standard.properties.remove("monoisotopic_mass");
+        standard.formula = Some("C8H10N4O2".parse().unwrap());
+        standard.properties.insert(ISOTOPE_LABEL_PROPERTY.to_string(), serde_json::json!("13C3"));
+        standard.properties.insert(LABELED_PARENT_PROPERTY.to_string(), serde_json::json!("caffeine"));
+        let store = CompoundStore::from_molecules(&[compound("caffeine", 194.080376, Some(2.0)), standard]);
+
+        // Unlabeled [M+H]+ at 195.0877, 13C3 [M+H]+ at 198.0977
+        let table = FeatureTable::parse("name,mzmed,rtmed,S1
+FT1,195.0877,120,1000
+FT2,198.0977,120,900
+", None).unwrap();
+        let evidence = feature_evidence(&table, &store, &FeatureMatchOptions::default()).unwrap();
+        assert_eq!(evidence.len(), 2);
+        assert!(evidence.iter().all(|e| e.molecule_id == "caffeine"));
+        assert_eq!(evidence[1].metadata["labeled_standard"], "caffeine-13c3");
+        assert!(evidence[1].confidence > 0.9);
+    }
+}
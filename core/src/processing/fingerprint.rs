@@ -0,0 +1,174 @@
+//! Pipeline Fingerprint Module
+//!
+//! A fingerprint identifies the exact pipeline that produced a result: the
+//! code version, every configuration parameter that affects scoring, and the
+//! versions of the models, rules and scripts involved. Its ID is a SHA-256
+//! over all of these, so two results with the same fingerprint ID were
+//! produced by the same pipeline and any change to it gives a new ID.
+//!
+//! Integrated evidence carries the fingerprint of the pipeline that
+//! integrated it, result files record its ID per row, and the graph keeps one
+//! `Pipeline` node per fingerprint that concluded molecules link to.
+
+use anyhow::Result;
+use log::info;
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Initialize the pipeline fingerprint module
+pub fn initialize() -> Result<()> {
+    info!("Initializing pipeline fingerprint module");
+    info!("Pipeline fingerprint module initialized successfully");
+    Ok(())
+}
+
+/// Version of the running code, with the build's git revision when it was known
+pub fn code_version() -> String {
+    match option_env!("HEGEL_BUILD_REVISION") {
+        Some(revision) if !revision.is_empty() => format!("{}+{}", crate::VERSION, revision),
+        _ => crate::VERSION.to_string(),
+    }
+}
+
+/// Identity of the pipeline that produced a result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineFingerprint {
+    /// SHA-256 (hex) over the code version, parameters and components
+    pub id: String,
+
+    /// Version of the code, see `code_version`
+    pub code_version: String,
+
+    /// Configuration sections by name, with object keys in sorted order
+    pub parameters: BTreeMap<String, serde_json::Value>,
+
+    /// Versions of the models, rules and scripts used, by name
+    pub components: BTreeMap<String, String>,
+}
+
+impl PipelineFingerprint {
+    /// Fingerprint of the running code with no parameters or components yet
+    pub fn new() -> Self {
+        Self::with_code_version(&code_version())
+    }
+
+    /// Fingerprint of a given code version, e.g. to reproduce one recorded earlier
+    pub fn with_code_version(code_version: &str) -> Self {
+        let mut fingerprint = Self {
+            id: String::new(),
+            code_version: code_version.to_string(),
+            parameters: BTreeMap::new(),
+            components: BTreeMap::new(),
+        };
+        fingerprint.id = fingerprint.digest();
+        fingerprint
+    }
+
+    /// Add a configuration section, replacing any section with the same name
+    pub fn with_parameters<T: Serialize>(mut self, name: &str, parameters: &T) -> Result<Self> {
+        let value = serde_json::to_value(parameters)?;
+        self.parameters.insert(name.to_string(), canonical(value));
+        self.id = self.digest();
+        Ok(self)
+    }
+
+    /// Add the version of a model, rule set or script
+    pub fn with_component(mut self, name: &str, version: &str) -> Self {
+        self.components.insert(name.to_string(), version.to_string());
+        self.id = self.digest();
+        self
+    }
+
+    /// Shortened ID for display
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(12)]
+    }
+
+    /// Whether the ID still matches the recorded contents
+    pub fn verify(&self) -> bool {
+        self.id == self.digest()
+    }
+
+    /// SHA-256 over the contents, independent of how they were built up
+    fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.code_version.as_bytes());
+        for (name, value) in &self.parameters {
+            hasher.update([0]);
+            hasher.update(name.as_bytes());
+            hasher.update([0]);
+            hasher.update(value.to_string().as_bytes());
+        }
+        hasher.update([1]);
+        for (name, version) in &self.components {
+            hasher.update([0]);
+            hasher.update(name.as_bytes());
+            hasher.update([0]);
+            hasher.update(version.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+impl Default for PipelineFingerprint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for PipelineFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.short_id(), self.code_version)
+    }
+}
+
+/// A JSON value with every object's keys in sorted order
+///
+/// Maps such as per-source weights serialize in iteration order, which would
+/// otherwise give equal configurations different fingerprints.
+fn canonical(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => {
+            let sorted: BTreeMap<String, serde_json::Value> = object.into_iter()
+                .map(|(key, value)| (key, canonical(value)))
+                .collect();
+            serde_json::Value::Object(sorted.into_iter().collect())
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(canonical).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_fingerprint_tracks_every_input() {
+        let weights: HashMap<String, f64> = (0..20).map(|i| (format!("source-{}", i), i as f64 / 20.0)).collect();
+        let mut entries: Vec<(String, f64)> = weights.clone().into_iter().collect();
+        entries.reverse();
+        let reordered: HashMap<String, f64> = entries.into_iter().collect();
+
+        let base = PipelineFingerprint::with_code_version("1.0.0")
+            .with_component("results_schema", "2")
+            .with_parameters("weights", &weights).unwrap();
+        let same = PipelineFingerprint::with_code_version("1.0.0")
+            .with_parameters("weights", &reordered).unwrap()
+            .with_component("results_schema", "2");
+        assert_eq!(base.id, same.id);
+        assert!(base.verify());
+
+        assert_ne!(base.id, base.clone().with_component("results_schema", "3").id);
+        assert_ne!(base.id, base.clone().with_parameters("weights", &HashMap::from([("a", 1.0)])).unwrap().id);
+        let mut rebuilt = base.clone();
+        rebuilt.code_version = "1.0.1".to_string();
+        assert!(!rebuilt.verify());
+
+        let restored: PipelineFingerprint = serde_json::from_str(&serde_json::to_string(&base).unwrap()).unwrap();
+        assert!(restored.verify());
+        assert_eq!(restored, base);
+    }
+}
@@ -0,0 +1,449 @@
+//! Molecular Formulas
+//!
+//! This module parses molecular formula strings into element counts, checks
+//! the element symbols, computes monoisotopic and average masses and applies
+//! adducts and neutral losses. Formulas print in Hill order (carbon, then
+//! hydrogen, then the rest alphabetically) and serialize as that string.
+//! Stable-isotope labels (`13C6`, `15N2`, `D3`) are kept apart from the
+//! formula and add their mass shift on top of its monoisotopic mass.
+
+use anyhow::{anyhow, Result};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Add;
+use std::str::FromStr;
+
+/// Element symbol, monoisotopic mass of the most abundant isotope and standard atomic weight
+const ELEMENTS: [(&str, f64, f64); 26] = [
+    ("H", 1.007825032, 1.00794),
+    ("Li", 7.0160045, 6.941),
+    ("B", 11.0093054, 10.811),
+    ("C", 12.0, 12.0107),
+    ("N", 14.003074005, 14.0067),
+    ("O", 15.99491462, 15.9994),
+    ("F", 18.99840322, 18.9984032),
+    ("Na", 22.98976928, 22.98976928),
+    ("Mg", 23.9850417, 24.305),
+    ("Al", 26.98153863, 26.9815386),
+    ("Si", 27.97692653, 28.0855),
+    ("P", 30.97376163, 30.973762),
+    ("S", 31.97207100, 32.065),
+    ("Cl", 34.96885268, 35.453),
+    ("K", 38.96370668, 39.0983),
+    ("Ca", 39.96259098, 40.078),
+    ("Mn", 54.9380451, 54.938045),
+    ("Fe", 55.9349375, 55.845),
+    ("Co", 58.9331950, 58.933195),
+    ("Ni", 57.9353429, 58.6934),
+    ("Cu", 62.9295975, 63.546),
+    ("Zn", 63.9291422, 65.38),
+    ("As", 74.9215965, 74.9216),
+    ("Se", 79.9165213, 78.96),
+    ("Br", 78.9183371, 79.904),
+    ("I", 126.904473, 126.90447),
+];
+
+/// Heavy isotopes used for labeling: isotope, element replaced, mass added per labeled atom
+const HEAVY_ISOTOPES: [(&str, &str, f64); 5] = [
+    ("2H", "H", 1.006276746),
+    ("13C", "C", 1.003354835),
+    ("15N", "N", 0.997034886),
+    ("18O", "O", 2.004245778),
+    ("34S", "S", 1.995795830),
+];
+
+/// Monoisotopic and average mass of an element
+fn element_masses(symbol: &str) -> Option<(f64, f64)> {
+    ELEMENTS.iter()
+        .find(|(s, _, _)| *s == symbol)
+        .map(|(_, mono, average)| (*mono, *average))
+}
+
+/// Elemental composition of a molecule, adduct or neutral loss
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Formula {
+    /// Atom count per element symbol
+    counts: BTreeMap<String, u32>,
+}
+
+impl Formula {
+    /// Number of atoms of an element
+    pub fn count(&self, element: &str) -> u32 {
+        self.counts.get(element).copied().unwrap_or(0)
+    }
+
+    /// Elements and their atom counts, alphabetically
+    pub fn elements(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.counts.iter().map(|(element, count)| (element.as_str(), *count))
+    }
+
+    /// Whether the formula has no atoms
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Monoisotopic mass, in Daltons
+    pub fn monoisotopic_mass(&self) -> f64 {
+        self.mass(|(mono, _)| mono)
+    }
+
+    /// Average (molecular weight) mass, in Daltons
+    pub fn average_mass(&self) -> f64 {
+        self.mass(|(_, average)| average)
+    }
+
+    /// Remove the atoms of another formula, e.g. a neutral loss
+    ///
+    /// Fails if this formula has fewer atoms of any element than `other`.
+    pub fn checked_sub(&self, other: &Formula) -> Result<Formula> {
+        let mut result = self.clone();
+        for (element, count) in other.elements() {
+            let remaining = result.count(element).checked_sub(count)
+                .ok_or_else(|| anyhow!("Cannot remove {} from {}: not enough {}", other, self, element))?;
+            if remaining == 0 {
+                result.counts.remove(element);
+            } else {
+                result.counts.insert(element.to_string(), remaining);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Add the atoms of another formula, e.g. an adduct
+    ///
+    /// Fails if the count of any element overflows.
+    pub fn checked_add(&self, other: &Formula) -> Result<Formula> {
+        let mut result = self.clone();
+        for (element, count) in other.elements() {
+            result.add_atoms(element, count)?;
+        }
+        Ok(result)
+    }
+
+    /// Apply a signed composition change such as `+H2O` or `-CO2`
+    pub fn apply_change(&self, change: &str) -> Result<Formula> {
+        let change = change.trim();
+        match change.strip_prefix('-') {
+            Some(loss) => self.checked_sub(&loss.parse()?),
+            None => self.checked_add(&change.trim_start_matches('+').parse()?),
+        }
+    }
+
+    /// Formula in Hill order: C, then H, then the other elements alphabetically
+    ///
+    /// Without carbon every element, hydrogen included, is alphabetical.
+    pub fn hill(&self) -> String {
+        let has_carbon = self.counts.contains_key("C");
+        let mut ordered: Vec<(&str, u32)> = Vec::with_capacity(self.counts.len());
+        if has_carbon {
+            ordered.extend(["C", "H"].iter().filter_map(|e| self.counts.get(*e).map(|c| (*e, *c))));
+        }
+        ordered.extend(self.elements().filter(|(e, _)| !has_carbon || (*e != "C" && *e != "H")));
+
+        ordered.iter()
+            .map(|(element, count)| match count {
+                1 => element.to_string(),
+                n => format!("{}{}", element, n),
+            })
+            .collect()
+    }
+
+    /// Monoisotopic mass with the labeled atoms replaced by their heavy isotope
+    ///
+    /// Fails if the label replaces more atoms of an element than the formula has.
+    pub fn labeled_mass(&self, label: &IsotopeLabel) -> Result<f64> {
+        for (isotope, count) in label.isotopes() {
+            let element = heavy_isotope(isotope).map_or("", |(_, element, _)| *element);
+            if count > self.count(element) {
+                return Err(anyhow!("Label {} replaces {} {} atoms but {} has {}",
+                                   label, count, element, self, self.count(element)));
+            }
+        }
+        Ok(self.monoisotopic_mass() + label.mass_shift())
+    }
+
+    fn mass(&self, pick: impl Fn((f64, f64)) -> f64) -> f64 {
+        self.elements()
+            .filter_map(|(element, count)| element_masses(element).map(|m| pick(m) * count as f64))
+            .sum()
+    }
+
+    fn add_atoms(&mut self, element: &str, count: u32) -> Result<()> {
+        if count > 0 {
+            let total = self.counts.entry(element.to_string()).or_insert(0);
+            *total = total.checked_add(count)
+                .ok_or_else(|| anyhow!("Too many {} atoms in formula", element))?;
+        }
+        Ok(())
+    }
+}
+
+impl Add for Formula {
+    type Output = Formula;
+
+    /// Combine two formulas
+    ///
+    /// # Panics
+    ///
+    /// Panics if the count of any element overflows; use
+    /// [`Formula::checked_add`] for untrusted formulas.
+    fn add(self, other: Formula) -> Formula {
+        self.checked_add(&other).expect("formula atom count overflow")
+    }
+}
+
+impl FromStr for Formula {
+    type Err = anyhow::Error;
+
+    /// Parse a formula such as `C6H12O6`, `Ca(OH)2` or `CuSO4·5H2O`
+    ///
+    /// Element symbols must be known; parenthesized groups take a multiplier
+    /// and `.`/`·` separate hydrate parts, each with an optional leading count.
+    fn from_str(s: &str) -> Result<Self> {
+        let value = s.trim();
+        if value.is_empty() {
+            return Err(anyhow!("Empty molecular formula"));
+        }
+
+        let mut formula = Formula::default();
+        for part in value.split(['.', '·', '*']) {
+            let chars: Vec<char> = part.trim().chars().collect();
+            let mut pos = 0;
+            let multiplier = read_count(&chars, &mut pos, value)?.unwrap_or(1);
+            let group = parse_group(&chars, &mut pos, value)?;
+            if pos != chars.len() {
+                return Err(anyhow!("Unexpected '{}' in formula {}", chars[pos], value));
+            }
+            if group.is_empty() {
+                return Err(anyhow!("Formula {} has an empty part", value));
+            }
+            for (element, count) in group.counts {
+                formula.add_atoms(&element, multiply(count, multiplier, value)?)?;
+            }
+        }
+        Ok(formula)
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.hill())
+    }
+}
+
+impl TryFrom<String> for Formula {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Formula> for String {
+    fn from(formula: Formula) -> String {
+        formula.hill()
+    }
+}
+
+/// Stable-isotope label: how many atoms of each element are a heavy isotope
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IsotopeLabel {
+    /// Labeled atom count per heavy isotope, e.g. `13C` -> 6
+    counts: BTreeMap<String, u32>,
+}
+
+impl IsotopeLabel {
+    /// Heavy isotopes and their atom counts
+    pub fn isotopes(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.counts.iter().map(|(isotope, count)| (isotope.as_str(), *count))
+    }
+
+    /// Mass the label adds to the unlabeled monoisotopic mass, in Daltons
+    pub fn mass_shift(&self) -> f64 {
+        self.isotopes()
+            .filter_map(|(isotope, count)| heavy_isotope(isotope).map(|(_, _, shift)| shift * count as f64))
+            .sum()
+    }
+}
+
+impl FromStr for IsotopeLabel {
+    type Err = anyhow::Error;
+
+    /// Parse a label such as `13C6`, `[13C6,15N2]` or `D3` (deuterium)
+    fn from_str(s: &str) -> Result<Self> {
+        let mut label = IsotopeLabel::default();
+        for token in s.split(|c: char| matches!(c, ',' | ';' | '[' | ']') || c.is_whitespace()) {
+            if token.is_empty() {
+                continue;
+            }
+            let mass_number_end = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(token.len());
+            let symbol_end = token[mass_number_end..].find(|c: char| c.is_ascii_digit())
+                .map_or(token.len(), |i| mass_number_end + i);
+            let isotope = match (&token[..mass_number_end], &token[mass_number_end..symbol_end]) {
+                ("", "D" | "d") => "2H".to_string(),
+                (mass_number, symbol) => format!("{}{}", mass_number, symbol),
+            };
+            if heavy_isotope(&isotope).is_none() {
+                return Err(anyhow!("Unsupported isotope label '{}' in {}", token, s));
+            }
+            let count = match &token[symbol_end..] {
+                "" => 1,
+                digits => digits.parse().map_err(|_| anyhow!("Invalid label count in {}", token))?,
+            };
+            let total = label.counts.entry(isotope).or_insert(0);
+            *total = total.checked_add(count)
+                .ok_or_else(|| anyhow!("Label count overflows in {}", s))?;
+        }
+
+        if label.counts.is_empty() {
+            return Err(anyhow!("Empty isotope label"));
+        }
+        Ok(label)
+    }
+}
+
+impl fmt::Display for IsotopeLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.isotopes()
+            .map(|(isotope, count)| format!("{}{}", isotope, count))
+            .collect();
+        f.write_str(&parts.join(","))
+    }
+}
+
+impl TryFrom<String> for IsotopeLabel {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<IsotopeLabel> for String {
+    fn from(label: IsotopeLabel) -> String {
+        label.to_string()
+    }
+}
+
+fn heavy_isotope(isotope: &str) -> Option<&'static (&'static str, &'static str, f64)> {
+    HEAVY_ISOTOPES.iter().find(|(name, _, _)| *name == isotope)
+}
+
+/// Parse elements and parenthesized groups up to a closing parenthesis or the end
+fn parse_group(chars: &[char], pos: &mut usize, formula: &str) -> Result<Formula> {
+    let mut group = Formula::default();
+    while let Some(&c) = chars.get(*pos) {
+        match c {
+            '(' | '[' => {
+                let close = if c == '(' { ')' } else { ']' };
+                *pos += 1;
+                let inner = parse_group(chars, pos, formula)?;
+                if chars.get(*pos) != Some(&close) {
+                    return Err(anyhow!("Unbalanced '{}' in formula {}", c, formula));
+                }
+                *pos += 1;
+                let multiplier = read_count(chars, pos, formula)?.unwrap_or(1);
+                for (element, count) in inner.counts {
+                    group.add_atoms(&element, multiply(count, multiplier, formula)?)?;
+                }
+            }
+            ')' | ']' => break,
+            c if c.is_ascii_uppercase() => {
+                let mut symbol = c.to_string();
+                *pos += 1;
+                if let Some(&next) = chars.get(*pos).filter(|n| n.is_ascii_lowercase()) {
+                    symbol.push(next);
+                    *pos += 1;
+                }
+                if element_masses(&symbol).is_none() {
+                    return Err(anyhow!("Unknown element '{}' in formula {}", symbol, formula));
+                }
+                let count = read_count(chars, pos, formula)?.unwrap_or(1);
+                group.add_atoms(&symbol, count)?;
+            }
+            c => return Err(anyhow!("Unexpected '{}' in formula {}", c, formula)),
+        }
+    }
+    Ok(group)
+}
+
+/// Read a run of digits as a count, if there is one
+fn read_count(chars: &[char], pos: &mut usize, formula: &str) -> Result<Option<u32>> {
+    let start = *pos;
+    while chars.get(*pos).is_some_and(|c| c.is_ascii_digit()) {
+        *pos += 1;
+    }
+    if start == *pos {
+        return Ok(None);
+    }
+    let digits: String = chars[start..*pos].iter().collect();
+    digits.parse().map(Some).map_err(|_| anyhow!("Count {} is too large in formula {}", digits, formula))
+}
+
+/// Multiply an atom count by a group or hydrate multiplier
+fn multiply(count: u32, multiplier: u32, formula: &str) -> Result<u32> {
+    count.checked_mul(multiplier).ok_or_else(|| anyhow!("Atom count overflows in formula {}", formula))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_hill_order() {
+        let glucose: Formula = "O6H12C6".parse().unwrap();
+        assert_eq!(glucose.to_string(), "C6H12O6");
+        assert!((glucose.monoisotopic_mass() - 180.063388).abs() < 1e-5);
+        assert!((glucose.average_mass() - 180.156).abs() < 1e-2);
+
+        assert_eq!("Ca(OH)2".parse::<Formula>().unwrap().to_string(), "CaH2O2");
+        assert_eq!("CuSO4·5H2O".parse::<Formula>().unwrap().to_string(), "CuH10O9S");
+        assert_eq!(serde_json::to_value("ClNa".parse::<Formula>().unwrap()).unwrap(), "ClNa");
+
+        assert!("C6H12Xx".parse::<Formula>().is_err());
+        assert!("C6(H12".parse::<Formula>().is_err());
+        assert!("c6h12".parse::<Formula>().is_err());
+    }
+
+    #[test]
+    fn test_rejects_overflowing_counts() {
+        assert!("C99999999999".parse::<Formula>().is_err());
+        assert!("(C100000)100000".parse::<Formula>().is_err());
+        assert!("100000(C100000)".parse::<Formula>().is_err());
+        assert!("C4294967295C".parse::<Formula>().is_err());
+
+        let carbon: Formula = "C4294967295".parse().unwrap();
+        assert!(carbon.checked_add(&"C".parse().unwrap()).is_err());
+        assert!(carbon.apply_change("+C").is_err());
+    }
+
+    #[test]
+    fn test_adducts_and_losses() {
+        let glucose: Formula = "C6H12O6".parse().unwrap();
+        let sodiated = glucose.clone() + "Na".parse().unwrap();
+        assert_eq!(sodiated.to_string(), "C6H12NaO6");
+
+        let dehydrated = glucose.apply_change("-H2O").unwrap();
+        assert_eq!(dehydrated.to_string(), "C6H10O5");
+        assert!((glucose.monoisotopic_mass() - dehydrated.monoisotopic_mass() - 18.010565).abs() < 1e-5);
+        assert_eq!(dehydrated.apply_change("+H2O").unwrap(), glucose);
+        assert!(glucose.apply_change("-S").is_err());
+    }
+
+    #[test]
+    fn test_isotope_labels() {
+        let label: IsotopeLabel = "[13C6, 15N2]".parse().unwrap();
+        assert_eq!(label.to_string(), "13C6,15N2");
+        assert!((label.mass_shift() - 8.014199).abs() < 1e-5);
+        assert_eq!("d3".parse::<IsotopeLabel>().unwrap().to_string(), "2H3");
+        assert!("14C2".parse::<IsotopeLabel>().is_err());
+
+        let glucose: Formula = "C6H12O6".parse().unwrap();
+        let labeled = glucose.labeled_mass(&"13C6".parse().unwrap()).unwrap();
+        assert!((labeled - 186.083517).abs() < 1e-5);
+        assert!(glucose.labeled_mass(&"15N1".parse().unwrap()).is_err());
+    }
+}
@@ -2,6 +2,11 @@ use crate::fuzzy_evidence::{
     FuzzyBayesianNetwork, FuzzyEvidence, EvidenceNode, EvidenceEdge, 
     EvidenceRelationship, EvidencePrediction
 };
+use crate::fuzzy_evidence::propagation::PropagationMethod;
+use crate::fuzzy_evidence::optimization::OptimizerKind;
+use crate::fuzzy_evidence::objectives::ObjectiveConfig;
+use crate::fuzzy_evidence::variables::LinguisticVariableConfig;
+use crate::fuzzy_evidence::structure::{IntegrationRecord, LearnedStructure, StructureLearner};
 use crate::processing::evidence::{Evidence, IntegratedEvidence, EvidenceProcessor};
 use anyhow::{Result, Context};
 use std::collections::HashMap;
@@ -13,6 +18,7 @@ pub struct FuzzyEvidenceIntegrator {
     network: FuzzyBayesianNetwork,
     evidence_processor: EvidenceProcessor,
     integration_config: IntegrationConfig,
+    history: Vec<IntegrationRecord>,
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +28,11 @@ pub struct IntegrationConfig {
     pub max_prediction_iterations: usize,
     pub enable_temporal_decay: bool,
     pub enable_network_learning: bool,
+    pub propagation: PropagationMethod,
+    pub optimizer: OptimizerKind,
+    pub objectives: ObjectiveConfig,
+    pub variables: LinguisticVariableConfig,
+    pub learned_structure: Option<LearnedStructure>,
 }
 
 impl Default for IntegrationConfig {
@@ -32,6 +43,11 @@ impl Default for IntegrationConfig {
             max_prediction_iterations: 10,
             enable_temporal_decay: true,
             enable_network_learning: true,
+            propagation: PropagationMethod::SinglePass,
+            optimizer: OptimizerKind::default(),
+            objectives: ObjectiveConfig::default(),
+            variables: LinguisticVariableConfig::default(),
+            learned_structure: None,
         }
     }
 }
@@ -39,10 +55,19 @@ impl Default for IntegrationConfig {
 impl FuzzyEvidenceIntegrator {
     /// Create a new fuzzy evidence integrator
     pub fn new(evidence_processor: EvidenceProcessor, config: IntegrationConfig) -> Self {
+        let mut network = FuzzyBayesianNetwork::new();
+        network.propagation = config.propagation.clone();
+        network.optimizer = config.optimizer.clone();
+        network.apply_objective_config(&config.objectives);
+        for variable in config.variables.variables.values() {
+            network.linguistic_variables.insert(variable.name.clone(), variable.clone());
+        }
+        
         FuzzyEvidenceIntegrator {
-            network: FuzzyBayesianNetwork::new(),
+            network,
             evidence_processor,
             integration_config: config,
+            history: Vec::new(),
         }
     }
     
@@ -50,13 +75,24 @@ impl FuzzyEvidenceIntegrator {
     pub fn convert_to_fuzzy_evidence(&self, evidence: &Evidence) -> Result<FuzzyEvidence> {
         let timestamp = chrono::Utc::now(); // In practice, would use evidence timestamp
         
-        let fuzzy_evidence = FuzzyEvidence::from_raw_evidence(
-            evidence.id.clone(),
-            evidence.source.clone(),
-            evidence.evidence_type.to_string(),
-            evidence.confidence,
-            timestamp,
-        );
+        let evidence_type = evidence.evidence_type.to_string();
+        let fuzzy_evidence = match self.integration_config.variables.variable_for(&evidence_type) {
+            Some(variable) => FuzzyEvidence::from_raw_evidence_with(
+                evidence.id.clone(),
+                evidence.source.clone(),
+                evidence_type,
+                evidence.confidence,
+                timestamp,
+                variable,
+            ),
+            None => FuzzyEvidence::from_raw_evidence(
+                evidence.id.clone(),
+                evidence.source.clone(),
+                evidence_type,
+                evidence.confidence,
+                timestamp,
+            ),
+        };
         
         Ok(fuzzy_evidence)
     }
@@ -91,6 +127,11 @@ impl FuzzyEvidenceIntegrator {
         
         // Build evidence relationships
         self.build_evidence_relationships(&evidences)?;
+        if let Some(structure) = &self.integration_config.learned_structure {
+            structure.apply(&mut self.network);
+        }
+        self.history.push(IntegrationRecord::from_observations(
+            evidences.iter().map(|e| (e.evidence_type.to_string(), e.source.as_str(), e.confidence))));
         
         // Update network using fuzzy-Bayesian inference
         if let Err(e) = self.network.update_network() {
@@ -300,6 +341,16 @@ impl FuzzyEvidenceIntegrator {
         Ok(coherence)
     }
     
+    /// Evidence channels seen in each integration so far
+    pub fn history(&self) -> &[IntegrationRecord] {
+        &self.history
+    }
+    
+    /// Learn evidence relationships from the integrations so far
+    pub fn learn_structure(&self, learner: &StructureLearner) -> LearnedStructure {
+        learner.learn(&self.history)
+    }
+    
     /// Get network statistics for analysis
     pub fn get_network_statistics(&self) -> NetworkStatistics {
         let node_count = self.network.nodes.len();
@@ -0,0 +1,477 @@
+//! Batch Effect Correction
+//!
+//! Samples processed in different batches (library preparations, sequencing
+//! runs, array lots) differ systematically in ways unrelated to biology. This
+//! module collects per-sample expression data into a genes × samples matrix
+//! and removes those differences: median-ratio normalization (as in DESeq)
+//! scales away differences in sequencing depth, and ComBat adjusts each
+//! gene's location and scale per batch, shrinking the per-batch estimates
+//! towards what the other genes show with an empirical Bayes prior so that
+//! small batches are not overcorrected. Diagnostics computed before and after
+//! show how much of the variance the batches accounted for.
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use ndarray::Array2;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+
+use super::{GenomicsData, GenomicsDataContent};
+
+/// Sample metadata key holding the batch a sample was processed in
+pub const BATCH_METADATA_KEY: &str = "batch";
+
+/// Batch assigned to samples whose metadata names none
+pub const DEFAULT_BATCH: &str = "unbatched";
+
+/// Key under which the correction report is stored in processing metadata
+pub const BATCH_CORRECTION_METADATA_KEY: &str = "batch_correction";
+
+/// Expression values of several samples, one row per gene and one column per sample
+#[derive(Debug, Clone)]
+pub struct ExpressionMatrix {
+    /// Gene or region IDs, one per row
+    gene_ids: Vec<String>,
+
+    /// Sample IDs, one per column
+    sample_ids: Vec<String>,
+
+    /// Batch of each sample
+    batches: Vec<String>,
+
+    /// Values, genes × samples
+    values: Array2<f64>,
+}
+
+impl ExpressionMatrix {
+    /// Create a matrix, checking that the labels fit its shape
+    pub fn new(gene_ids: Vec<String>, sample_ids: Vec<String>, batches: Vec<String>, values: Array2<f64>) -> Result<Self> {
+        if values.nrows() != gene_ids.len() || values.ncols() != sample_ids.len() {
+            return Err(anyhow!(
+                "Matrix is {}×{} but has {} gene IDs and {} sample IDs",
+                values.nrows(), values.ncols(), gene_ids.len(), sample_ids.len()
+            ));
+        }
+        if batches.len() != sample_ids.len() {
+            return Err(anyhow!("{} batches given for {} samples", batches.len(), sample_ids.len()));
+        }
+        Ok(Self { gene_ids, sample_ids, batches, values })
+    }
+
+    /// Collect gene expression or read count samples into a matrix
+    ///
+    /// Every sample must cover the same genes; the first sample's order is
+    /// used. The batch comes from each sample's `batch` metadata.
+    pub fn from_samples(samples: &[GenomicsData]) -> Result<Self> {
+        let first = samples.first().ok_or_else(|| anyhow!("No samples to build an expression matrix from"))?;
+        let gene_ids = expression_values(first)?.0.to_vec();
+        let mut values = Array2::zeros((gene_ids.len(), samples.len()));
+
+        for (column, sample) in samples.iter().enumerate() {
+            let (ids, sample_values) = expression_values(sample)?;
+            if ids.len() != sample_values.len() {
+                return Err(anyhow!("Sample {} has {} IDs but {} values", sample.sample_id, ids.len(), sample_values.len()));
+            }
+            let by_id: BTreeMap<&str, f64> = ids.iter().map(|id| id.as_str()).zip(sample_values).collect();
+            if by_id.len() != gene_ids.len() {
+                return Err(anyhow!("Sample {} has {} genes, expected {}", sample.sample_id, by_id.len(), gene_ids.len()));
+            }
+            for (row, gene_id) in gene_ids.iter().enumerate() {
+                values[[row, column]] = *by_id.get(gene_id.as_str())
+                    .ok_or_else(|| anyhow!("Sample {} has no value for {}", sample.sample_id, gene_id))?;
+            }
+        }
+
+        let sample_ids = samples.iter().map(|s| s.sample_id.clone()).collect();
+        let batches = samples.iter().map(sample_batch).collect();
+        Self::new(gene_ids, sample_ids, batches, values)
+    }
+
+    /// Gene or region IDs, one per row
+    pub fn gene_ids(&self) -> &[String] {
+        &self.gene_ids
+    }
+
+    /// Sample IDs, one per column
+    pub fn sample_ids(&self) -> &[String] {
+        &self.sample_ids
+    }
+
+    /// Batch of each sample
+    pub fn batches(&self) -> &[String] {
+        &self.batches
+    }
+
+    /// Values, genes × samples
+    pub fn values(&self) -> &Array2<f64> {
+        &self.values
+    }
+
+    /// Values of one sample
+    pub fn sample_values(&self, column: usize) -> Vec<f64> {
+        self.values.column(column).to_vec()
+    }
+
+    /// Sample columns grouped by batch
+    fn batch_columns(&self) -> BTreeMap<&str, Vec<usize>> {
+        let mut groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (column, batch) in self.batches.iter().enumerate() {
+            groups.entry(batch.as_str()).or_default().push(column);
+        }
+        groups
+    }
+
+    /// Same labels with new values
+    fn with_values(&self, values: Array2<f64>) -> Self {
+        Self { values, ..self.clone() }
+    }
+}
+
+/// IDs and values of a gene expression or read count sample
+fn expression_values(sample: &GenomicsData) -> Result<(&[String], Vec<f64>)> {
+    match &sample.data {
+        GenomicsDataContent::GeneExpression { gene_ids, expression_values } => Ok((gene_ids, expression_values.clone())),
+        GenomicsDataContent::ReadCounts { region_ids, counts } => Ok((region_ids, counts.iter().map(|c| *c as f64).collect())),
+        _ => Err(anyhow!("Sample {} holds neither gene expression nor read counts", sample.sample_id)),
+    }
+}
+
+/// Batch named in a sample's metadata, as a string or a number
+fn sample_batch(sample: &GenomicsData) -> String {
+    match sample.metadata.get(BATCH_METADATA_KEY) {
+        Some(serde_json::Value::String(batch)) => batch.clone(),
+        Some(serde_json::Value::Number(batch)) => batch.to_string(),
+        _ => DEFAULT_BATCH.to_string(),
+    }
+}
+
+/// Divide each sample by its DESeq size factor
+///
+/// The size factor of a sample is the median, over genes expressed in every
+/// sample, of its value relative to the gene's geometric mean. Returns the
+/// normalized matrix and the size factors.
+pub fn median_ratio_normalize(matrix: &ExpressionMatrix) -> Result<(ExpressionMatrix, Vec<f64>)> {
+    let values = matrix.values();
+    let log_means: Vec<Option<f64>> = values.rows().into_iter()
+        .map(|row| {
+            if row.iter().all(|v| *v > 0.0) {
+                Some(row.iter().map(|v| v.ln()).sum::<f64>() / row.len() as f64)
+            } else {
+                None
+            }
+        })
+        .collect();
+    if log_means.iter().all(Option::is_none) {
+        return Err(anyhow!("No gene is expressed in every sample; cannot compute size factors"));
+    }
+
+    let mut size_factors = Vec::with_capacity(values.ncols());
+    for column in values.columns() {
+        let mut ratios: Vec<f64> = column.iter().zip(&log_means)
+            .filter_map(|(value, log_mean)| log_mean.map(|m| value.ln() - m))
+            .collect();
+        size_factors.push(median(&mut ratios).exp());
+    }
+    debug!("Median-ratio size factors: {:?}", size_factors);
+
+    let mut normalized = values.clone();
+    for (mut column, factor) in normalized.columns_mut().into_iter().zip(&size_factors) {
+        column.mapv_inplace(|v| v / factor);
+    }
+    Ok((matrix.with_values(normalized), size_factors))
+}
+
+/// Options for ComBat batch correction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComBatOptions {
+    /// Correct `log2(x + 1)` values and transform back, as suits counts
+    pub log_transform: bool,
+
+    /// Most iterations of the empirical Bayes estimates per batch
+    pub max_iterations: usize,
+
+    /// Largest relative change of the estimates at which iteration stops
+    pub tolerance: f64,
+}
+
+impl Default for ComBatOptions {
+    fn default() -> Self {
+        Self {
+            log_transform: false,
+            max_iterations: 100,
+            tolerance: 1e-4,
+        }
+    }
+}
+
+/// Remove batch effects with parametric empirical Bayes ComBat
+///
+/// Every batch needs at least two samples, and there must be at least two
+/// batches. Genes with no variance are left as they are.
+pub fn combat(matrix: &ExpressionMatrix, options: &ComBatOptions) -> Result<ExpressionMatrix> {
+    let groups = matrix.batch_columns();
+    if groups.len() < 2 {
+        return Err(anyhow!("Batch correction needs at least two batches, found {}", groups.len()));
+    }
+    if let Some((batch, _)) = groups.iter().find(|(_, columns)| columns.len() < 2) {
+        return Err(anyhow!("Batch {} has a single sample; ComBat needs at least two per batch", batch));
+    }
+
+    let mut data = matrix.values().clone();
+    if options.log_transform {
+        data.mapv_inplace(|v| (v.max(0.0) + 1.0).log2());
+    }
+    let (genes, samples) = data.dim();
+
+    // Standardize each gene by its grand mean and pooled within-batch variance
+    let mut grand_mean = vec![0.0; genes];
+    let mut pooled_sd = vec![0.0; genes];
+    for g in 0..genes {
+        let row = data.row(g);
+        grand_mean[g] = row.sum() / samples as f64;
+        let mut residual = 0.0;
+        for columns in groups.values() {
+            let batch_mean = columns.iter().map(|c| row[*c]).sum::<f64>() / columns.len() as f64;
+            residual += columns.iter().map(|c| (row[*c] - batch_mean).powi(2)).sum::<f64>();
+        }
+        pooled_sd[g] = (residual / samples as f64).sqrt();
+    }
+    let varying: Vec<usize> = (0..genes).filter(|g| pooled_sd[*g] > 1e-12).collect();
+    if varying.is_empty() {
+        return Ok(matrix.clone());
+    }
+    let standardized = |g: usize, c: usize| (data[[g, c]] - grand_mean[g]) / pooled_sd[g];
+
+    let mut corrected = data.clone();
+    for (batch, columns) in &groups {
+        let n = columns.len() as f64;
+
+        // Per-gene batch location and scale on the standardized data
+        let gamma_hat: Vec<f64> = varying.iter()
+            .map(|g| columns.iter().map(|c| standardized(*g, *c)).sum::<f64>() / n)
+            .collect();
+        let delta_hat: Vec<f64> = varying.iter().zip(&gamma_hat)
+            .map(|(g, mean)| columns.iter().map(|c| (standardized(*g, *c) - mean).powi(2)).sum::<f64>() / (n - 1.0))
+            .collect();
+
+        // Normal prior on the locations, inverse gamma prior on the scales
+        let gamma_bar = mean(&gamma_hat);
+        let tau2 = sample_variance(&gamma_hat);
+        let (lambda, theta) = inverse_gamma_prior(&delta_hat);
+
+        let mut gamma_star = gamma_hat.clone();
+        let mut delta_star = delta_hat.clone();
+        for (i, g) in varying.iter().enumerate() {
+            let z: Vec<f64> = columns.iter().map(|c| standardized(*g, *c)).collect();
+            for _ in 0..options.max_iterations {
+                let gamma = if tau2 > 0.0 {
+                    (n * tau2 * gamma_hat[i] + delta_star[i] * gamma_bar) / (n * tau2 + delta_star[i])
+                } else {
+                    gamma_bar
+                };
+                let squares: f64 = z.iter().map(|v| (v - gamma).powi(2)).sum();
+                let delta = match (lambda, theta) {
+                    (Some(lambda), Some(theta)) => (theta + 0.5 * squares) / (n / 2.0 + lambda - 1.0),
+                    _ => squares / (n - 1.0),
+                };
+                let change = ((gamma - gamma_star[i]).abs() / gamma_star[i].abs().max(1e-12))
+                    .max((delta - delta_star[i]).abs() / delta_star[i].max(1e-12));
+                gamma_star[i] = gamma;
+                delta_star[i] = delta.max(1e-12);
+                if change < options.tolerance {
+                    break;
+                }
+            }
+            for (c, value) in columns.iter().zip(&z) {
+                corrected[[*g, *c]] = pooled_sd[*g] * (value - gamma_star[i]) / delta_star[i].sqrt() + grand_mean[*g];
+            }
+        }
+        debug!("ComBat batch {} ({} samples): prior location {:.3}, variance {:.3}", batch, columns.len(), gamma_bar, tau2);
+    }
+
+    if options.log_transform {
+        corrected.mapv_inplace(|v| (v.exp2() - 1.0).max(0.0));
+    }
+    Ok(matrix.with_values(corrected))
+}
+
+/// Method-of-moments inverse gamma hyperparameters (λ, θ) for batch variances
+///
+/// `None` when the variances do not vary, in which case no shrinkage is applied.
+fn inverse_gamma_prior(variances: &[f64]) -> (Option<f64>, Option<f64>) {
+    let m = mean(variances);
+    let s2 = sample_variance(variances);
+    if s2 <= 0.0 {
+        return (None, None);
+    }
+    (Some((m * m + 2.0 * s2) / s2), Some((m * m * m + m * s2) / s2))
+}
+
+/// How strongly the batches show in a matrix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchDiagnostics {
+    /// Number of samples per batch
+    pub batch_sizes: BTreeMap<String, usize>,
+
+    /// Mean, over genes, of the fraction of a gene's variance explained by batch (0.0 - 1.0)
+    pub batch_variance_fraction: f64,
+
+    /// Mean expression of each batch across all genes
+    pub batch_means: BTreeMap<String, f64>,
+}
+
+impl BatchDiagnostics {
+    /// Diagnostics of a matrix
+    pub fn of(matrix: &ExpressionMatrix) -> Self {
+        let groups = matrix.batch_columns();
+        let values = matrix.values();
+
+        let mut fractions = Vec::new();
+        for row in values.rows() {
+            let grand = row.sum() / row.len() as f64;
+            let total: f64 = row.iter().map(|v| (v - grand).powi(2)).sum();
+            if total <= 1e-12 {
+                continue;
+            }
+            let between: f64 = groups.values()
+                .map(|columns| {
+                    let batch_mean = columns.iter().map(|c| row[*c]).sum::<f64>() / columns.len() as f64;
+                    columns.len() as f64 * (batch_mean - grand).powi(2)
+                })
+                .sum();
+            fractions.push(between / total);
+        }
+
+        let batch_means = groups.iter()
+            .map(|(batch, columns)| {
+                let sum: f64 = columns.iter().map(|c| values.column(*c).sum()).sum();
+                (batch.to_string(), sum / (columns.len() * values.nrows()).max(1) as f64)
+            })
+            .collect();
+
+        Self {
+            batch_sizes: groups.iter().map(|(batch, columns)| (batch.to_string(), columns.len())).collect(),
+            batch_variance_fraction: if fractions.is_empty() { 0.0 } else { mean(&fractions) },
+            batch_means,
+        }
+    }
+}
+
+/// What batch correction did to a dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchCorrectionReport {
+    /// Size factor per sample, when median-ratio normalization was applied
+    pub size_factors: Option<BTreeMap<String, f64>>,
+
+    /// Whether ComBat was applied
+    pub combat: bool,
+
+    /// Diagnostics of the input matrix
+    pub before: BatchDiagnostics,
+
+    /// Diagnostics of the corrected matrix
+    pub after: BatchDiagnostics,
+}
+
+/// Normalize and batch-correct a matrix, reporting what changed
+///
+/// ComBat is skipped, with the reason logged, when the batches cannot
+/// support it (a single batch, or a batch of one sample).
+pub fn correct_batches(
+    matrix: &ExpressionMatrix,
+    normalize: bool,
+    options: &ComBatOptions,
+) -> Result<(ExpressionMatrix, BatchCorrectionReport)> {
+    let before = BatchDiagnostics::of(matrix);
+
+    let (normalized, size_factors) = if normalize {
+        let (normalized, factors) = median_ratio_normalize(matrix)?;
+        let factors = matrix.sample_ids().iter().cloned().zip(factors).collect();
+        (normalized, Some(factors))
+    } else {
+        (matrix.clone(), None)
+    };
+
+    let (corrected, combat_applied) = match combat(&normalized, options) {
+        Ok(corrected) => (corrected, true),
+        Err(e) => {
+            debug!("Skipping ComBat: {}", e);
+            (normalized, false)
+        }
+    };
+
+    let report = BatchCorrectionReport {
+        size_factors,
+        combat: combat_applied,
+        before,
+        after: BatchDiagnostics::of(&corrected),
+    };
+    Ok((corrected, report))
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+fn sample_variance(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(values: &[[f64; 4]], batches: [&str; 4]) -> ExpressionMatrix {
+        let genes = values.len();
+        let flat: Vec<f64> = values.iter().flatten().copied().collect();
+        ExpressionMatrix::new(
+            (0..genes).map(|g| format!("gene{}", g)).collect(),
+            (0..4).map(|s| format!("s{}", s)).collect(),
+            batches.iter().map(|b| b.to_string()).collect(),
+            Array2::from_shape_vec((genes, 4), flat).unwrap(),
+        ).unwrap()
+    }
+
+    #[test]
+    fn median_ratio_removes_sequencing_depth() {
+        // The second pair of samples was sequenced twice as deep
+        let counts = matrix(&[[10.0, 12.0, 20.0, 24.0], [100.0, 90.0, 200.0, 180.0], [5.0, 6.0, 10.0, 12.0]], ["a", "a", "b", "b"]);
+        let (normalized, factors) = median_ratio_normalize(&counts).unwrap();
+        assert!((factors[2] / factors[0] - 2.0).abs() < 1e-9);
+        assert!((normalized.values()[[0, 0]] - normalized.values()[[0, 2]]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn combat_removes_a_batch_shift() {
+        // Batch b reads every gene 3 units higher
+        let shifted = matrix(&[
+            [5.0, 6.0, 8.0, 9.0],
+            [10.0, 11.5, 13.0, 14.5],
+            [2.0, 2.5, 5.0, 5.5],
+            [7.0, 8.0, 10.0, 11.0],
+        ], ["a", "a", "b", "b"]);
+        let (corrected, report) = correct_batches(&shifted, false, &ComBatOptions::default()).unwrap();
+
+        assert!(report.combat);
+        assert!(report.before.batch_variance_fraction > 0.8);
+        // Shrinkage towards the other genes leaves the outlying gene slightly shifted
+        assert!(report.after.batch_variance_fraction < report.before.batch_variance_fraction / 4.0);
+        let means = &report.after.batch_means;
+        assert!((means["a"] - means["b"]).abs() < 0.1);
+        assert_eq!(corrected.sample_values(0).len(), 4);
+    }
+}
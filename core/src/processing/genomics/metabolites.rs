@@ -0,0 +1,427 @@
+//! Gene to Metabolite Mapping
+//!
+//! Connects genomics to metabolomics through the reactions genes catalyse:
+//! gene → enzyme (EC number) → reaction → metabolites. The mapping is loaded
+//! from KEGG `link` files (gene–EC, EC–reaction and reaction–compound pairs)
+//! or from Reactome's lowest-level reaction files (`NCBI2ReactomeReactions`,
+//! `ChEBI2ReactomeReactions`), which link genes to reactions directly.
+//!
+//! A significant gene is evidence for the metabolites its reactions produce
+//! or consume, and more weakly for metabolites further along the pathway. The
+//! strength of a link decays with the number of reaction steps between the
+//! gene's own reaction and the metabolite. Hub metabolites such as water,
+//! ATP and NAD+ take part in so many reactions that walking through them
+//! would connect every gene to everything, so the walk never passes through
+//! them.
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use super::GenomicsResult;
+use crate::processing::evidence::{Evidence, EvidenceType};
+
+/// Source of the evidence derived from gene–metabolite links
+pub const ENZYME_LINKAGE_SOURCE: &str = "enzyme-linkage";
+
+/// KEGG compounds excluded from the walk by default: water, ATP, NAD(P)(H),
+/// O2, ADP, phosphate, CoA, CO2, diphosphate, NH3 and H+
+pub const DEFAULT_HUB_METABOLITES: [&str; 14] = [
+    "C00001", "C00002", "C00003", "C00004", "C00005", "C00006", "C00007",
+    "C00008", "C00009", "C00010", "C00011", "C00013", "C00014", "C00080",
+];
+
+/// Kind of entity a Reactome mapping file lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactomeEntity {
+    /// NCBI gene IDs (`NCBI2ReactomeReactions.txt`)
+    Gene,
+
+    /// ChEBI compound IDs (`ChEBI2ReactomeReactions.txt`)
+    Metabolite,
+}
+
+/// Options for following genes to their metabolites
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnzymeLinkOptions {
+    /// Most reaction steps between a gene's reaction and a linked metabolite
+    pub max_distance: usize,
+
+    /// Factor the link strength is multiplied by per reaction step
+    pub decay: f64,
+
+    /// Metabolites the walk does not pass through
+    pub hub_metabolites: BTreeSet<String>,
+}
+
+impl Default for EnzymeLinkOptions {
+    fn default() -> Self {
+        Self {
+            max_distance: 2,
+            decay: 0.5,
+            hub_metabolites: DEFAULT_HUB_METABOLITES.iter().map(|id| id.to_string()).collect(),
+        }
+    }
+}
+
+/// A metabolite reached from a gene
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaboliteLink {
+    /// Gene the walk started from
+    pub gene: String,
+
+    /// Metabolite reached
+    pub metabolite: String,
+
+    /// Reaction steps beyond the gene's own reaction; 0 for its substrates and products
+    pub distance: usize,
+
+    /// Enzyme the first reaction was reached through, absent for direct gene–reaction links
+    pub enzyme: Option<String>,
+
+    /// Reactions walked, starting with the gene's own
+    pub reactions: Vec<String>,
+
+    /// Link strength, `decay ^ distance`
+    pub strength: f64,
+}
+
+/// Gene → enzyme → reaction → metabolite mapping tables
+#[derive(Debug, Clone, Default)]
+pub struct GeneMetaboliteMap {
+    /// EC numbers of each gene's products
+    gene_enzymes: BTreeMap<String, BTreeSet<String>>,
+
+    /// Reactions catalysed by each enzyme
+    enzyme_reactions: BTreeMap<String, BTreeSet<String>>,
+
+    /// Reactions linked to genes without an enzyme in between (Reactome)
+    gene_reactions: BTreeMap<String, BTreeSet<String>>,
+
+    /// Substrates and products of each reaction
+    reaction_metabolites: BTreeMap<String, BTreeSet<String>>,
+
+    /// Reactions each metabolite takes part in
+    metabolite_reactions: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl GeneMetaboliteMap {
+    /// Create an empty mapping
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of genes with a reaction, through an enzyme or directly
+    pub fn gene_count(&self) -> usize {
+        self.gene_enzymes.keys().chain(self.gene_reactions.keys()).collect::<BTreeSet<_>>().len()
+    }
+
+    /// Number of reactions with known metabolites
+    pub fn reaction_count(&self) -> usize {
+        self.reaction_metabolites.len()
+    }
+
+    /// Record that a gene encodes an enzyme
+    pub fn add_gene_enzyme(&mut self, gene: &str, enzyme: &str) {
+        self.gene_enzymes.entry(gene.to_string()).or_default().insert(enzyme.to_string());
+    }
+
+    /// Record that an enzyme catalyses a reaction
+    pub fn add_enzyme_reaction(&mut self, enzyme: &str, reaction: &str) {
+        self.enzyme_reactions.entry(enzyme.to_string()).or_default().insert(reaction.to_string());
+    }
+
+    /// Record that a gene's product takes part in a reaction
+    pub fn add_gene_reaction(&mut self, gene: &str, reaction: &str) {
+        self.gene_reactions.entry(gene.to_string()).or_default().insert(reaction.to_string());
+    }
+
+    /// Record that a metabolite is a substrate or product of a reaction
+    pub fn add_reaction_metabolite(&mut self, reaction: &str, metabolite: &str) {
+        self.reaction_metabolites.entry(reaction.to_string()).or_default().insert(metabolite.to_string());
+        self.metabolite_reactions.entry(metabolite.to_string()).or_default().insert(reaction.to_string());
+    }
+
+    /// Load a KEGG `link` file, returning the number of pairs added
+    pub fn load_kegg(&mut self, path: &Path) -> Result<usize> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open KEGG link file: {}", path.display()))?;
+        let loaded = self.read_kegg(BufReader::new(file))
+            .with_context(|| format!("Failed to read KEGG link file: {}", path.display()))?;
+        info!("Loaded {} KEGG links from {}", loaded, path.display());
+        Ok(loaded)
+    }
+
+    /// Read KEGG `link` pairs, telling the tables apart by their ID prefixes
+    ///
+    /// `ec:` marks enzymes, `rn:` reactions and `cpd:` compounds; any other
+    /// prefix is an organism code marking a gene (`hsa:3098`), which is
+    /// stored as the bare gene ID (`3098`, the NCBI gene ID for human). The
+    /// pairs may come in either order, and pairs between other kinds of
+    /// entries (orthologs, pathways) are skipped.
+    pub fn read_kegg<R: BufRead>(&mut self, reader: R) -> Result<usize> {
+        let mut loaded = 0;
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (left, right) = line.split_once('\t')
+                .ok_or_else(|| anyhow!("Line {} is not a tab-separated pair", number + 1))?;
+            let (a, b) = (KeggEntry::parse(left.trim()), KeggEntry::parse(right.trim()));
+            let added = match (a, b) {
+                (KeggEntry::Gene(gene), KeggEntry::Enzyme(ec)) | (KeggEntry::Enzyme(ec), KeggEntry::Gene(gene)) => {
+                    self.add_gene_enzyme(gene, ec);
+                    true
+                }
+                (KeggEntry::Enzyme(ec), KeggEntry::Reaction(rn)) | (KeggEntry::Reaction(rn), KeggEntry::Enzyme(ec)) => {
+                    self.add_enzyme_reaction(ec, rn);
+                    true
+                }
+                (KeggEntry::Reaction(rn), KeggEntry::Compound(cpd)) | (KeggEntry::Compound(cpd), KeggEntry::Reaction(rn)) => {
+                    self.add_reaction_metabolite(rn, cpd);
+                    true
+                }
+                _ => false,
+            };
+            if added {
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Load a Reactome lowest-level reaction mapping file, returning the number of pairs added
+    pub fn load_reactome(&mut self, path: &Path, entity: ReactomeEntity, species: Option<&str>) -> Result<usize> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open Reactome mapping file: {}", path.display()))?;
+        let loaded = self.read_reactome(BufReader::new(file), entity, species)
+            .with_context(|| format!("Failed to read Reactome mapping file: {}", path.display()))?;
+        info!("Loaded {} Reactome links from {}", loaded, path.display());
+        Ok(loaded)
+    }
+
+    /// Read Reactome `source ID, reaction ID, URL, name, evidence code, species` rows
+    ///
+    /// Rows of other species are skipped when a species (e.g. `Homo sapiens`)
+    /// is given. ChEBI IDs are stored as `CHEBI:<number>`.
+    pub fn read_reactome<R: BufRead>(&mut self, reader: R, entity: ReactomeEntity, species: Option<&str>) -> Result<usize> {
+        let mut loaded = 0;
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            if fields.len() < 2 {
+                return Err(anyhow!("Line {} has fewer than two columns", number + 1));
+            }
+            if let (Some(wanted), Some(row_species)) = (species, fields.get(5)) {
+                if !row_species.eq_ignore_ascii_case(wanted) {
+                    continue;
+                }
+            }
+            let (id, reaction) = (fields[0], fields[1]);
+            match entity {
+                ReactomeEntity::Gene => self.add_gene_reaction(id, reaction),
+                ReactomeEntity::Metabolite => {
+                    let chebi = format!("CHEBI:{}", id.trim_start_matches("CHEBI:"));
+                    self.add_reaction_metabolite(reaction, &chebi);
+                }
+            }
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /// Reactions of a gene, each with the enzyme it was reached through
+    fn gene_reactions_of(&self, gene: &str) -> BTreeMap<&str, Option<&str>> {
+        let mut reactions = BTreeMap::new();
+        for reaction in self.gene_reactions.get(gene).into_iter().flatten() {
+            reactions.insert(reaction.as_str(), None);
+        }
+        for enzyme in self.gene_enzymes.get(gene).into_iter().flatten() {
+            for reaction in self.enzyme_reactions.get(enzyme).into_iter().flatten() {
+                reactions.entry(reaction.as_str()).or_insert(Some(enzyme.as_str()));
+            }
+        }
+        reactions
+    }
+
+    /// Metabolites reachable from a gene, nearest first
+    ///
+    /// The walk goes breadth-first from the gene's reactions to their
+    /// metabolites and on to the other reactions of those metabolites,
+    /// skipping hub metabolites, so each metabolite is reported at its
+    /// shortest distance.
+    pub fn downstream(&self, gene: &str, options: &EnzymeLinkOptions) -> Vec<MetaboliteLink> {
+        let mut links: BTreeMap<&str, MetaboliteLink> = BTreeMap::new();
+        let mut visited_reactions: BTreeSet<&str> = BTreeSet::new();
+        // (reaction, enzyme of the first reaction, reactions walked so far)
+        let mut queue: VecDeque<(&str, Option<&str>, Vec<String>)> = VecDeque::new();
+        for (reaction, enzyme) in self.gene_reactions_of(gene) {
+            visited_reactions.insert(reaction);
+            queue.push_back((reaction, enzyme, vec![reaction.to_string()]));
+        }
+
+        while let Some((reaction, enzyme, path)) = queue.pop_front() {
+            let distance = path.len() - 1;
+            for metabolite in self.reaction_metabolites.get(reaction).into_iter().flatten() {
+                if options.hub_metabolites.contains(metabolite) || links.contains_key(metabolite.as_str()) {
+                    continue;
+                }
+                links.insert(metabolite, MetaboliteLink {
+                    gene: gene.to_string(),
+                    metabolite: metabolite.clone(),
+                    distance,
+                    enzyme: enzyme.map(str::to_string),
+                    reactions: path.clone(),
+                    strength: options.decay.powi(distance as i32),
+                });
+                if distance >= options.max_distance {
+                    continue;
+                }
+                for next in self.metabolite_reactions.get(metabolite).into_iter().flatten() {
+                    if visited_reactions.insert(next.as_str()) {
+                        let mut next_path = path.clone();
+                        next_path.push(next.clone());
+                        queue.push_back((next, enzyme, next_path));
+                    }
+                }
+            }
+        }
+
+        let mut links: Vec<MetaboliteLink> = links.into_values().collect();
+        links.sort_by(|a, b| a.distance.cmp(&b.distance).then_with(|| a.metabolite.cmp(&b.metabolite)));
+        debug!("Gene {} reaches {} metabolites", gene, links.len());
+        links
+    }
+}
+
+/// An ID in a KEGG link file, by kind
+enum KeggEntry<'a> {
+    Gene(&'a str),
+    Enzyme(&'a str),
+    Reaction(&'a str),
+    Compound(&'a str),
+    Other,
+}
+
+impl<'a> KeggEntry<'a> {
+    fn parse(id: &'a str) -> Self {
+        match id.split_once(':') {
+            Some(("ec", ec)) => KeggEntry::Enzyme(ec),
+            Some(("rn", rn)) => KeggEntry::Reaction(rn),
+            Some(("cpd", cpd)) => KeggEntry::Compound(cpd),
+            Some(("ko" | "path" | "md" | "gl" | "dr", _)) | None => KeggEntry::Other,
+            Some((_, gene)) => KeggEntry::Gene(gene),
+        }
+    }
+}
+
+/// Scores of the significant genes in gene expression results, by gene ID
+///
+/// A gene found in several results keeps its highest score.
+pub fn significant_genes(results: &[GenomicsResult]) -> HashMap<String, f64> {
+    let mut genes: HashMap<String, f64> = HashMap::new();
+    for finding in results.iter().flat_map(|r| &r.findings).filter(|f| f.finding_type == "gene_expression") {
+        if let Some(gene) = finding.details.get("gene_id").and_then(|g| g.as_str()) {
+            let score = genes.entry(gene.to_string()).or_insert(0.0);
+            *score = score.max(finding.score);
+        }
+    }
+    genes
+}
+
+/// Pathway evidence for the metabolites downstream of significant genes
+///
+/// Each metabolite gets one item whose confidence is the best gene score
+/// times link strength over the genes reaching it; every contributing link
+/// is kept in the data. Items come out in metabolite order.
+pub fn enzyme_linkage_evidence(
+    genes: &HashMap<String, f64>,
+    map: &GeneMetaboliteMap,
+    options: &EnzymeLinkOptions,
+) -> Vec<Evidence> {
+    let mut per_metabolite: BTreeMap<String, (f64, Vec<MetaboliteLink>)> = BTreeMap::new();
+    for (gene, score) in genes {
+        for link in map.downstream(gene, options) {
+            let entry = per_metabolite.entry(link.metabolite.clone()).or_insert((0.0, Vec::new()));
+            entry.0 = entry.0.max(score.clamp(0.0, 1.0) * link.strength);
+            entry.1.push(link);
+        }
+    }
+
+    per_metabolite.into_iter()
+        .map(|(metabolite, (confidence, mut links))| {
+            links.sort_by(|a, b| b.strength.total_cmp(&a.strength).then_with(|| a.gene.cmp(&b.gene)));
+            let mut metadata = HashMap::new();
+            metadata.insert("genes".to_string(), serde_json::json!(links.iter().map(|l| &l.gene).collect::<BTreeSet<_>>()));
+            metadata.insert("min_distance".to_string(), serde_json::json!(links[0].distance));
+            Evidence {
+                id: format!("{}-{}", ENZYME_LINKAGE_SOURCE, metabolite),
+                molecule_id: metabolite,
+                evidence_type: EvidenceType::Pathway,
+                source: ENZYME_LINKAGE_SOURCE.to_string(),
+                confidence,
+                data: serde_json::json!({ "links": links }),
+                metadata,
+                timestamp: chrono::Utc::now(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hexokinase (HK1, 3098) phosphorylates glucose in R01786; the product
+    /// G6P is isomerized to F6P in R02740
+    const KEGG_LINKS: &str = "hsa:3098\tec:2.7.1.1\n\
+                              ec:2.7.1.1\trn:R01786\n\
+                              ec:5.3.1.9\trn:R02740\n\
+                              rn:R01786\tcpd:C00031\n\
+                              rn:R01786\tcpd:C00002\n\
+                              rn:R01786\tcpd:C00092\n\
+                              rn:R01786\tcpd:C00008\n\
+                              rn:R02740\tcpd:C00092\n\
+                              rn:R02740\tcpd:C00085\n\
+                              hsa:3098\tko:K00844\n";
+
+    #[test]
+    fn kegg_links_are_walked_with_decaying_strength() {
+        let mut map = GeneMetaboliteMap::new();
+        assert_eq!(map.read_kegg(KEGG_LINKS.as_bytes()).unwrap(), 9);
+        assert_eq!(map.gene_count(), 1);
+
+        let links = map.downstream("3098", &EnzymeLinkOptions::default());
+        let found: Vec<(&str, usize)> = links.iter().map(|l| (l.metabolite.as_str(), l.distance)).collect();
+        // ATP and ADP are hubs; F6P is one step past hexokinase's own reaction
+        assert_eq!(found, vec![("C00031", 0), ("C00092", 0), ("C00085", 1)]);
+        assert_eq!(links[2].reactions, vec!["R01786", "R02740"]);
+        assert_eq!(links[2].enzyme.as_deref(), Some("2.7.1.1"));
+        assert!((links[2].strength - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn significant_genes_become_pathway_evidence() {
+        let mut map = GeneMetaboliteMap::new();
+        map.read_reactome("3098\tR-HSA-70420\turl\tHK1 phosphorylates Glc\tIEA\tHomo sapiens\n\
+                           3098\tR-MMU-70420\turl\tHk1 phosphorylates Glc\tIEA\tMus musculus\n".as_bytes(),
+                          ReactomeEntity::Gene, Some("Homo sapiens")).unwrap();
+        map.read_reactome("17634\tR-HSA-70420\turl\tHK1 phosphorylates Glc\tIEA\tHomo sapiens\n".as_bytes(),
+                          ReactomeEntity::Metabolite, None).unwrap();
+
+        let genes = HashMap::from([("3098".to_string(), 0.8)]);
+        let evidence = enzyme_linkage_evidence(&genes, &map, &EnzymeLinkOptions::default());
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].molecule_id, "CHEBI:17634");
+        assert_eq!(evidence[0].evidence_type, EvidenceType::Pathway);
+        assert!((evidence[0].confidence - 0.8).abs() < 1e-12);
+    }
+}
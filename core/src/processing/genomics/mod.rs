@@ -2,6 +2,9 @@
 //! 
 //! This module handles the processing and analysis of genomics data
 //! for molecular identification and evidence generation.
+//! Datasets of several samples can be batch-corrected first (see `batch`),
+//! and significant genes followed to the metabolites of the reactions they
+//! catalyse (see `metabolites`).
 
 use anyhow::{Result, Context, anyhow};
 use log::{info, debug, warn, error};
@@ -10,6 +13,11 @@ use std::collections::HashMap;
 use ndarray::{Array1, Array2};
 use rayon::prelude::*;
 
+pub mod batch;
+pub mod metabolites;
+
+use batch::{correct_batches, ComBatOptions, ExpressionMatrix, BATCH_CORRECTION_METADATA_KEY};
+
 /// Initialize the genomics processing module
 pub fn initialize() -> Result<()> {
     info!("Initializing genomics processing module");
@@ -180,11 +188,15 @@ pub struct GenomicsProcessingOptions {
     /// Minimum read count
     pub min_read_count: u32,
     
-    /// Whether to use batch correction
+    /// Whether to remove batch effects (ComBat) when processing a dataset of samples
     pub use_batch_correction: bool,
     
-    /// Whether to normalize data
+    /// Whether to normalize data; read count datasets are also median-ratio normalized
     pub normalize_data: bool,
+    
+    /// Options for ComBat batch correction
+    #[serde(default)]
+    pub combat: ComBatOptions,
 }
 
 impl Default for GenomicsProcessingOptions {
@@ -195,6 +207,7 @@ impl Default for GenomicsProcessingOptions {
             min_read_count: 10,
             use_batch_correction: true,
             normalize_data: true,
+            combat: ComBatOptions::default(),
         }
     }
 }
@@ -244,6 +257,43 @@ impl GenomicsProcessor {
         }
     }
     
+    /// Process a dataset of gene expression or read count samples together
+    ///
+    /// With batch correction on, the samples are collected into one matrix,
+    /// read counts are median-ratio normalized (when normalizing) and batch
+    /// effects are removed before each sample is processed as gene
+    /// expression. The correction report, with diagnostics from before and
+    /// after, is stored in every result's processing metadata. Without batch
+    /// correction, each sample is processed on its own.
+    pub fn process_dataset(&self, molecule_id: &str, samples: &[GenomicsData]) -> Result<Vec<GenomicsResult>> {
+        if !self.options.use_batch_correction || samples.len() < 2 {
+            let mut results = Vec::new();
+            for sample in samples {
+                results.extend(self.process(molecule_id, sample)?);
+            }
+            return Ok(results);
+        }
+        
+        let counts = samples.iter().all(|s| matches!(s.data, GenomicsDataContent::ReadCounts { .. }));
+        let matrix = ExpressionMatrix::from_samples(samples)
+            .context("Failed to assemble samples for batch correction")?;
+        let combat_options = ComBatOptions { log_transform: counts, ..self.options.combat.clone() };
+        let (corrected, report) = correct_batches(&matrix, counts && self.options.normalize_data, &combat_options)?;
+        info!(
+            "Batch correction of {} samples: batch explains {:.1}% of variance before, {:.1}% after",
+            samples.len(), report.before.batch_variance_fraction * 100.0, report.after.batch_variance_fraction * 100.0
+        );
+        let report = serde_json::to_value(&report)?;
+        
+        let mut results = Vec::new();
+        for (column, sample) in samples.iter().enumerate() {
+            let mut metadata = sample.metadata.clone();
+            metadata.insert(BATCH_CORRECTION_METADATA_KEY.to_string(), report.clone());
+            results.extend(self.process_gene_expression(molecule_id, corrected.gene_ids(), &corrected.sample_values(column), &metadata)?);
+        }
+        Ok(results)
+    }
+    
     /// Process gene expression data
     fn process_gene_expression(
         &self,
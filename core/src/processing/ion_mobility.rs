@@ -0,0 +1,215 @@
+//! Ion Mobility Module
+//!
+//! This module scores measured collision cross sections (CCS) against
+//! reference values, which come either from a lookup table of measured CCS
+//! values or from a prediction model behind the `CcsPredictor` trait.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Initialize the ion mobility module
+pub fn initialize() -> Result<()> {
+    info!("Initializing ion mobility module");
+    info!("Ion mobility module initialized successfully");
+    Ok(())
+}
+
+/// Adduct assumed when the evidence metadata doesn't name one
+pub const DEFAULT_ADDUCT: &str = "[M+H]+";
+
+/// Source of reference CCS values, either measured or predicted
+pub trait CcsPredictor: Send + Sync {
+    /// Name of the source, reported in CCS findings
+    fn name(&self) -> &str;
+
+    /// Reference CCS in Å² for a molecule and adduct, if known
+    fn predict(&self, molecule_id: &str, adduct: &str) -> Option<f64>;
+}
+
+/// Lookup table of reference CCS values keyed by molecule and adduct
+#[derive(Debug, Clone, Default)]
+pub struct CcsTable {
+    /// Name of the table
+    name: String,
+
+    /// CCS values in Å² keyed by (molecule ID, adduct)
+    values: HashMap<(String, String), f64>,
+}
+
+impl CcsTable {
+    /// Create an empty table
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a reference value
+    pub fn insert(&mut self, molecule_id: &str, adduct: &str, ccs: f64) {
+        self.values.insert((molecule_id.to_string(), adduct.to_string()), ccs);
+    }
+
+    /// Number of reference values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether the table holds no values
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Load a `molecule_id,adduct,ccs` CSV file with a header line
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read CCS table: {}", path.display()))?;
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("ccs_table");
+        Self::parse(name, &content)
+    }
+
+    /// Parse a `molecule_id,adduct,ccs` table from a string
+    pub fn parse(name: &str, content: &str) -> Result<Self> {
+        let mut table = Self::new(name);
+        let mut skipped = 0;
+
+        for line in content.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+            let cells: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+            match cells.as_slice() {
+                [molecule_id, adduct, ccs] => match ccs.parse::<f64>() {
+                    Ok(ccs) if ccs > 0.0 => table.insert(molecule_id, adduct, ccs),
+                    _ => skipped += 1,
+                },
+                _ => skipped += 1,
+            }
+        }
+
+        if table.is_empty() && skipped > 0 {
+            return Err(anyhow!("CCS table {} has no valid rows", name));
+        }
+        if skipped > 0 {
+            warn!("Skipped {} malformed rows in CCS table {}", skipped, name);
+        }
+        debug!("Loaded {} reference CCS values from {}", table.len(), name);
+        Ok(table)
+    }
+}
+
+impl CcsPredictor for CcsTable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn predict(&self, molecule_id: &str, adduct: &str) -> Option<f64> {
+        self.values.get(&(molecule_id.to_string(), adduct.to_string())).copied()
+    }
+}
+
+/// Options for CCS matching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CcsMatchOptions {
+    /// Expected relative CCS error in percent (one standard deviation)
+    pub tolerance_percent: f64,
+
+    /// Weight of the CCS match in the overall confidence (0.0 - 1.0)
+    pub weight: f64,
+}
+
+impl Default for CcsMatchOptions {
+    fn default() -> Self {
+        Self {
+            tolerance_percent: 2.0,
+            weight: 0.3,
+        }
+    }
+}
+
+/// Result of matching a measured CCS against a reference
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CcsMatch {
+    /// Measured CCS in Å²
+    pub observed_ccs: f64,
+
+    /// Reference CCS in Å²
+    pub reference_ccs: f64,
+
+    /// Adduct the reference applies to
+    pub adduct: String,
+
+    /// Signed relative error in percent
+    pub error_percent: f64,
+
+    /// Match score (0.0 - 1.0)
+    pub score: f64,
+
+    /// Where the reference value came from
+    pub source: String,
+}
+
+impl CcsMatchOptions {
+    /// Score a measured CCS against a reference value
+    pub fn score(&self, observed_ccs: f64, reference_ccs: f64, adduct: &str, source: &str) -> CcsMatch {
+        let error_percent = (observed_ccs - reference_ccs) / reference_ccs * 100.0;
+        let z = error_percent / self.tolerance_percent;
+
+        CcsMatch {
+            observed_ccs,
+            reference_ccs,
+            adduct: adduct.to_string(),
+            error_percent,
+            score: (-0.5 * z * z).exp(),
+            source: source.to_string(),
+        }
+    }
+
+    /// Blend a CCS match score into an existing confidence
+    pub fn combine(&self, confidence: f64, ccs_score: f64) -> f64 {
+        ((1.0 - self.weight) * confidence + self.weight * ccs_score).clamp(0.0, 1.0)
+    }
+}
+
+/// Reference CCS for a molecule
+///
+/// A `reference_ccs` value in the evidence metadata takes precedence over the
+/// predictor. Returns the value and the name of its source.
+pub fn reference_ccs(
+    molecule_id: &str,
+    adduct: &str,
+    metadata: &HashMap<String, serde_json::Value>,
+    predictor: Option<&dyn CcsPredictor>,
+) -> Option<(f64, String)> {
+    if let Some(ccs) = metadata.get("reference_ccs").and_then(|v| v.as_f64()) {
+        return Some((ccs, "metadata".to_string()));
+    }
+    let predictor = predictor?;
+    predictor.predict(molecule_id, adduct).map(|ccs| (ccs, predictor.name().to_string()))
+}
+
+/// Adduct named in the evidence metadata, or the default
+pub fn adduct(metadata: &HashMap<String, serde_json::Value>) -> &str {
+    metadata.get("adduct").and_then(|v| v.as_str()).unwrap_or(DEFAULT_ADDUCT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ccs_table_match() {
+        let table = CcsTable::parse("ref", "molecule_id,adduct,ccs\nglucose,[M+Na]+,147.6\nbad,row\n").unwrap();
+        assert_eq!(table.len(), 1);
+
+        let metadata = [("adduct".to_string(), serde_json::json!("[M+Na]+"))].into_iter().collect();
+        let (reference, source) = reference_ccs("glucose", adduct(&metadata), &metadata, Some(&table)).unwrap();
+        assert_eq!(source, "ref");
+
+        let options = CcsMatchOptions::default();
+        let close = options.score(148.0, reference, "[M+Na]+", &source);
+        let far = options.score(158.0, reference, "[M+Na]+", &source);
+        assert!(close.score > 0.9 && far.score < 0.01);
+        assert!(options.combine(0.5, close.score) > options.combine(0.5, far.score));
+    }
+}
@@ -0,0 +1,426 @@
+//! Compound Library Module
+//!
+//! Validates and standardizes a compound library before it is used to build
+//! a network. Every record of a SMILES or SD file is parsed and checked in
+//! parallel; records that fail are classified as syntax errors, valence
+//! errors or disallowed elements and reported with the line they start on,
+//! and the rest are standardized (counter-ions and solvents stripped, atoms
+//! written as plainly as SMILES allows) into a clean library.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug};
+use rayon::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::processing::Molecule;
+use crate::processing::results::csv_field;
+
+pub mod molfile;
+pub mod smiles;
+pub mod structure;
+
+use structure::Structure;
+
+/// Initialize the compound library module
+pub fn initialize() -> Result<()> {
+    info!("Initializing compound library module");
+    info!("Compound library module initialized successfully");
+    Ok(())
+}
+
+/// File format of a compound library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LibraryFormat {
+    /// One SMILES per line, optionally followed by a name
+    Smiles,
+    /// SD file of V2000 molfiles separated by `$$$$`
+    Sdf,
+}
+
+impl std::str::FromStr for LibraryFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "smi" | "smiles" => Ok(LibraryFormat::Smiles),
+            "sdf" | "sd" | "mol" => Ok(LibraryFormat::Sdf),
+            other => Err(anyhow!("Unknown library format: {}", other)),
+        }
+    }
+}
+
+impl LibraryFormat {
+    /// Format implied by a file's extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .ok_or_else(|| anyhow!("Cannot tell the library format of {} from its extension", path.display()))?
+            .parse()
+    }
+}
+
+/// Why a library record was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The record could not be parsed
+    Syntax,
+    /// An atom has more bonds than its element allows
+    Valence,
+    /// The structure contains an element outside the allowed set
+    DisallowedElement,
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureKind::Syntax => write!(f, "syntax"),
+            FailureKind::Valence => write!(f, "valence"),
+            FailureKind::DisallowedElement => write!(f, "disallowed_element"),
+        }
+    }
+}
+
+/// Rejected library record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryError {
+    /// Line of the input file the record starts on, counting from 1
+    pub line: usize,
+
+    /// Name given to the record, if any
+    pub name: Option<String>,
+
+    /// Class of the failure
+    pub kind: FailureKind,
+
+    /// What was wrong with the record
+    pub message: String,
+}
+
+/// Record that passed validation, in standardized form
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandardizedEntry {
+    /// Line of the input file the record starts on, counting from 1
+    pub line: usize,
+
+    /// Name given to the record, if any
+    pub name: Option<String>,
+
+    /// Standardized SMILES
+    pub smiles: String,
+
+    /// Molecule ID derived from the standardized SMILES
+    pub id: String,
+
+    /// Disconnected fragments dropped to keep the largest
+    pub removed_fragments: usize,
+}
+
+/// Elements allowed by default: those of organic and drug-like molecules
+pub const DEFAULT_ALLOWED_ELEMENTS: &[&str] = &["H", "B", "C", "N", "O", "F", "Si", "P", "S", "Cl", "Se", "Br", "I"];
+
+/// How a library is validated and standardized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationOptions {
+    /// Elements a structure may contain, after fragments are stripped
+    pub allowed_elements: Vec<String>,
+
+    /// Keep only the fragment with the most heavy atoms, dropping counter-ions and solvents
+    pub keep_largest_fragment: bool,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            allowed_elements: DEFAULT_ALLOWED_ELEMENTS.iter().map(|e| e.to_string()).collect(),
+            keep_largest_fragment: true,
+        }
+    }
+}
+
+impl ValidationOptions {
+    /// Replace the allowed elements
+    pub fn with_allowed_elements<I, S>(mut self, elements: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_elements = elements.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set whether only the largest fragment is kept
+    pub fn with_keep_largest_fragment(mut self, keep: bool) -> Self {
+        self.keep_largest_fragment = keep;
+        self
+    }
+
+    /// Check every allowed element is a real element symbol
+    pub fn validate(&self) -> Result<()> {
+        if self.allowed_elements.is_empty() {
+            return Err(anyhow!("At least one element must be allowed"));
+        }
+        for element in &self.allowed_elements {
+            if !structure::ELEMENTS.contains(&element.as_str()) {
+                return Err(anyhow!("Unknown element symbol in allowed elements: {}", element));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a comma-separated list of allowed elements, such as `C,H,N,O`
+    pub fn parse_elements(list: &str) -> Vec<String> {
+        list.split(',').map(|e| e.trim().to_string()).filter(|e| !e.is_empty()).collect()
+    }
+}
+
+/// One record of a library file, before parsing
+#[derive(Debug, Clone, PartialEq)]
+struct Record<'a> {
+    /// Line the record starts on, counting from 1
+    line: usize,
+    /// Name of the record, if any
+    name: Option<String>,
+    /// SMILES string, or molfile lines
+    body: RecordBody<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RecordBody<'a> {
+    Smiles(&'a str),
+    Molfile(Vec<&'a str>),
+}
+
+/// SMILES records: one per non-blank line not starting with `#`, with the
+/// rest of the line taken as the name
+fn smiles_records(content: &str) -> Vec<Record<'_>> {
+    content.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            let line_text = line.trim();
+            let (smiles, name) = match line_text.split_once(char::is_whitespace) {
+                Some((smiles, name)) => (smiles, Some(name.trim().to_string())),
+                None => (line_text, None),
+            };
+            Record { line: i + 1, name, body: RecordBody::Smiles(smiles) }
+        })
+        .collect()
+}
+
+/// SD records: molfiles separated by `$$$$` lines, each named by its first line
+fn sdf_records(content: &str) -> Vec<Record<'_>> {
+    let mut records = Vec::new();
+    let mut start = 0;
+    let mut lines: Vec<&str> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim_end() == "$$$$" {
+            if lines.iter().any(|l| !l.trim().is_empty()) {
+                records.push(sdf_record(start, std::mem::take(&mut lines)));
+            }
+            lines.clear();
+            start = i + 1;
+        } else {
+            lines.push(line);
+        }
+    }
+    if lines.iter().any(|l| !l.trim().is_empty()) {
+        records.push(sdf_record(start, lines));
+    }
+    records
+}
+
+fn sdf_record(start: usize, lines: Vec<&str>) -> Record<'_> {
+    let name = lines.first().map(|l| l.trim()).filter(|l| !l.is_empty()).map(str::to_string);
+    Record { line: start + 1, name, body: RecordBody::Molfile(lines) }
+}
+
+/// Parse, check and standardize one record
+fn validate_record(record: Record<'_>, options: &ValidationOptions) -> std::result::Result<StandardizedEntry, LibraryError> {
+    let fail = |kind: FailureKind, message: String| LibraryError {
+        line: record.line,
+        name: record.name.clone(),
+        kind,
+        message,
+    };
+
+    let parsed = match &record.body {
+        RecordBody::Smiles(text) => smiles::parse_smiles(text),
+        RecordBody::Molfile(lines) => molfile::parse_molfile(lines),
+    };
+    let parsed: Structure = parsed.map_err(|e| fail(FailureKind::Syntax, e.to_string()))?;
+    if parsed.atoms.is_empty() {
+        return Err(fail(FailureKind::Syntax, "Record has no atoms".to_string()));
+    }
+
+    let (structure, removed_fragments) = if options.keep_largest_fragment {
+        parsed.largest_fragment()
+    } else {
+        (parsed, 0)
+    };
+
+    let disallowed: Vec<&str> = structure.elements().into_iter()
+        .filter(|e| !options.allowed_elements.iter().any(|allowed| allowed == e))
+        .collect();
+    if !disallowed.is_empty() {
+        return Err(fail(FailureKind::DisallowedElement, format!("Contains disallowed elements: {}", disallowed.join(", "))));
+    }
+
+    let violations = structure.valence_violations();
+    if !violations.is_empty() {
+        let details: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        return Err(fail(FailureKind::Valence, format!("Invalid valence: {}", details.join("; "))));
+    }
+
+    let smiles = structure.to_smiles();
+    let id = Molecule::from_smiles(&smiles).map_err(|e| fail(FailureKind::Syntax, e.to_string()))?.id;
+    Ok(StandardizedEntry {
+        line: record.line,
+        name: record.name,
+        smiles,
+        id,
+        removed_fragments,
+    })
+}
+
+/// Validate and standardize every record of a library
+pub fn validate_library(content: &str, format: LibraryFormat, options: &ValidationOptions) -> Result<LibraryReport> {
+    options.validate()?;
+    let records = match format {
+        LibraryFormat::Smiles => smiles_records(content),
+        LibraryFormat::Sdf => sdf_records(content),
+    };
+    let total = records.len();
+    debug!("Validating {} library records", total);
+
+    let outcomes: Vec<_> = records.into_par_iter()
+        .map(|record| validate_record(record, options))
+        .collect();
+    let mut report = LibraryReport { records: total, entries: Vec::new(), errors: Vec::new() };
+    for outcome in outcomes {
+        match outcome {
+            Ok(entry) => report.entries.push(entry),
+            Err(error) => report.errors.push(error),
+        }
+    }
+    info!("Validated {} library records: {} standardized, {} rejected",
+          total, report.entries.len(), report.errors.len());
+    Ok(report)
+}
+
+/// Outcome of validating a library, with records in file order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryReport {
+    /// Records read from the file
+    pub records: usize,
+
+    /// Records that passed, standardized
+    pub entries: Vec<StandardizedEntry>,
+
+    /// Records that were rejected
+    pub errors: Vec<LibraryError>,
+}
+
+impl LibraryReport {
+    /// Number of rejected records of each failure kind
+    pub fn failure_counts(&self) -> BTreeMap<FailureKind, usize> {
+        let mut counts = BTreeMap::new();
+        for error in &self.errors {
+            *counts.entry(error.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Entries that had fragments stripped
+    pub fn stripped(&self) -> usize {
+        self.entries.iter().filter(|e| e.removed_fragments > 0).count()
+    }
+
+    /// Write the standardized library as a SMILES file, one `SMILES name` line per entry
+    pub fn write_smiles(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create library file: {}", path.display()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        for entry in &self.entries {
+            match &entry.name {
+                Some(name) => writeln!(writer, "{} {}", entry.smiles, name)?,
+                None => writeln!(writer, "{}", entry.smiles)?,
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the rejected records as CSV with `line,name,kind,message` columns
+    pub fn write_error_csv(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create error report: {}", path.display()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        writeln!(writer, "line,name,kind,message")?;
+        for error in &self.errors {
+            writeln!(writer, "{},{},{},{}",
+                     error.line,
+                     csv_field(error.name.as_deref().unwrap_or_default()),
+                     error.kind,
+                     csv_field(&error.message))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_smiles_library() {
+        let content = "# screening library\n\
+                       CCO ethanol\n\
+                       \n\
+                       CC(=O)[O-].[Na+] sodium acetate\n\
+                       C(C bad branch\n\
+                       C=O=C\n\
+                       [Pt](Cl)Cl platinum\n";
+        let report = validate_library(content, LibraryFormat::Smiles, &ValidationOptions::default()).unwrap();
+
+        assert_eq!(report.records, 5);
+        let smiles: Vec<&str> = report.entries.iter().map(|e| e.smiles.as_str()).collect();
+        assert_eq!(smiles, vec!["CCO", "CC(=O)[O-]"]);
+        assert_eq!(report.entries[1].name.as_deref(), Some("sodium acetate"));
+        assert_eq!(report.stripped(), 1);
+
+        let failures: Vec<(usize, FailureKind)> = report.errors.iter().map(|e| (e.line, e.kind)).collect();
+        assert_eq!(failures, vec![
+            (5, FailureKind::Syntax),
+            (6, FailureKind::Valence),
+            (7, FailureKind::DisallowedElement),
+        ]);
+        assert_eq!(report.failure_counts()[&FailureKind::Syntax], 1);
+
+        let relaxed = ValidationOptions::default().with_allowed_elements(["C", "Cl", "Pt"]);
+        let report = validate_library("[Pt](Cl)Cl\n", LibraryFormat::Smiles, &relaxed).unwrap();
+        assert_eq!(report.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_sdf_records_and_error_report() {
+        let content = "water\n\n\n  1  0  0  0  0  0  0  0  0  0999 V2000\n    0.0000    0.0000    0.0000 O   0  0\nM  END\n$$$$\nbroken\n\n\n  2  1\n$$$$\n";
+        let report = validate_library(content, LibraryFormat::Sdf, &ValidationOptions::default()).unwrap();
+        assert_eq!(report.entries.len(), 1);
+        assert_eq!(report.entries[0].smiles, "O");
+        assert_eq!(report.errors[0].line, 8);
+        assert_eq!(report.errors[0].name.as_deref(), Some("broken"));
+
+        let path = std::env::temp_dir().join(format!("hegel-library-errors-{}.csv", std::process::id()));
+        report.write_error_csv(&path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(csv.starts_with("line,name,kind,message\n8,broken,syntax,"));
+        assert_eq!(LibraryFormat::from_path(Path::new("library.sdf")).unwrap(), LibraryFormat::Sdf);
+    }
+}
@@ -0,0 +1,128 @@
+//! Molfile parsing
+//!
+//! Reads the connection table of a V2000 molfile, as found in each record of
+//! an SD file, into a `Structure`. Coordinates and stereo parities are not
+//! needed for standardization and are ignored; V3000 tables are rejected.
+
+use anyhow::{anyhow, Context, Result};
+
+use super::structure::{Atom, Bond, BondOrder, Structure, ELEMENTS};
+
+/// Parse a V2000 molfile: the header block, counts line, atom and bond
+/// blocks and any `M  CHG` properties
+pub fn parse_molfile(lines: &[&str]) -> Result<Structure> {
+    let counts = lines.get(3).ok_or_else(|| anyhow!("Molfile ends before its counts line"))?;
+    if counts.contains("V3000") {
+        return Err(anyhow!("V3000 molfiles are not supported"));
+    }
+    let atom_count = fixed_field(counts, 0).context("Invalid atom count on the counts line")?;
+    let bond_count = fixed_field(counts, 1).context("Invalid bond count on the counts line")?;
+
+    let block_end = 4 + atom_count + bond_count;
+    if lines.len() < block_end {
+        return Err(anyhow!("Molfile declares {} atoms and {} bonds but ends after {} lines",
+                           atom_count, bond_count, lines.len()));
+    }
+
+    let mut structure = Structure::default();
+    for (i, line) in lines[4..4 + atom_count].iter().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let element = *fields.get(3).ok_or_else(|| anyhow!("Atom {} has no element", i + 1))?;
+        if !ELEMENTS.contains(&element) {
+            return Err(anyhow!("Atom {} has unknown element '{}'", i + 1, element));
+        }
+        // Charge codes 1-7 stand for +3 down to -3, with 4 a doublet radical
+        let charge = match fields.get(5).map(|c| c.parse::<i32>()) {
+            None | Some(Ok(0)) | Some(Ok(4)) => 0,
+            Some(Ok(code @ 1..=7)) => 4 - code,
+            _ => return Err(anyhow!("Atom {} has an invalid charge code", i + 1)),
+        };
+        structure.add_atom(Atom { charge, ..Atom::new(element) });
+    }
+
+    for (i, line) in lines[4 + atom_count..block_end].iter().enumerate() {
+        let invalid = || anyhow!("Bond {} is malformed", i + 1);
+        let a = fixed_field(line, 0).ok_or_else(invalid)?;
+        let b = fixed_field(line, 1).ok_or_else(invalid)?;
+        if a == 0 || b == 0 || a > atom_count || b > atom_count || a == b {
+            return Err(anyhow!("Bond {} joins atoms {} and {}, which are not a pair of the {} atoms", i + 1, a, b, atom_count));
+        }
+        let order = match fixed_field(line, 2) {
+            Some(1) => BondOrder::Single,
+            Some(2) => BondOrder::Double,
+            Some(3) => BondOrder::Triple,
+            Some(4) => BondOrder::Aromatic,
+            _ => return Err(anyhow!("Bond {} has an unsupported bond type", i + 1)),
+        };
+        if order == BondOrder::Aromatic {
+            structure.atoms[a - 1].aromatic = true;
+            structure.atoms[b - 1].aromatic = true;
+        }
+        structure.bonds.push(Bond { a: a - 1, b: b - 1, order, direction: None });
+    }
+
+    // A charge property block replaces every charge of the atom block
+    let mut charges_reset = false;
+    for line in &lines[block_end..] {
+        if line.starts_with("M  END") {
+            return Ok(structure);
+        }
+        if let Some(entries) = line.strip_prefix("M  CHG") {
+            if !charges_reset {
+                structure.atoms.iter_mut().for_each(|atom| atom.charge = 0);
+                charges_reset = true;
+            }
+            let values: Vec<i64> = entries.split_whitespace()
+                .map(|v| v.parse())
+                .collect::<std::result::Result<_, _>>()
+                .context("Invalid M  CHG line")?;
+            for pair in values.get(1..).unwrap_or_default().chunks(2) {
+                let [atom, charge] = pair else {
+                    return Err(anyhow!("Incomplete M  CHG line"));
+                };
+                let atom = structure.atoms.get_mut((*atom as usize).wrapping_sub(1))
+                    .ok_or_else(|| anyhow!("M  CHG refers to atom {}, which does not exist", atom))?;
+                atom.charge = *charge as i32;
+            }
+        }
+    }
+    Err(anyhow!("Molfile has no M  END line"))
+}
+
+/// Integer in the `index`th three-column field of a fixed-width line
+fn fixed_field(line: &str, index: usize) -> Option<usize> {
+    line.get(index * 3..(index * 3 + 3).min(line.len()))?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACETATE: &str = "acetate
+  hegel
+
+  4  3  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.5000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    2.2500    1.3000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    2.2500   -1.3000    0.0000 O   0  5  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0
+  2  3  2  0
+  2  4  1  0
+M  CHG  1   4  -1
+M  END";
+
+    #[test]
+    fn test_parse_molfile() {
+        let lines: Vec<&str> = ACETATE.lines().collect();
+        let structure = parse_molfile(&lines).unwrap();
+        assert_eq!(structure.atoms.len(), 4);
+        assert_eq!(structure.atoms[3].charge, -1);
+        assert_eq!(structure.to_smiles(), "CC(=O)[O-]");
+
+        let truncated: Vec<&str> = lines[..7].to_vec();
+        assert!(parse_molfile(&truncated).is_err());
+        let no_end: Vec<&str> = lines[..lines.len() - 1].to_vec();
+        assert!(parse_molfile(&no_end).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+//! SMILES parsing
+//!
+//! Reads a SMILES string into a `Structure`: organic-subset and bracket
+//! atoms, explicit bonds, branches, ring closures and dot-separated
+//! fragments. Any error here is a syntax failure of the record; chemistry
+//! is checked on the parsed structure.
+
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+
+use super::structure::{Atom, Bond, BondOrder, Structure, ELEMENTS};
+
+/// Elements that may be written in lowercase, inside brackets, as aromatic
+const AROMATIC_BRACKET: &[&str] = &["B", "C", "N", "O", "P", "S", "Se", "As", "Te"];
+
+/// Bond written before an atom or ring closure label
+type PendingBond = (BondOrder, Option<char>);
+
+/// Parse a SMILES string
+pub fn parse_smiles(smiles: &str) -> Result<Structure> {
+    let mut parser = Parser {
+        chars: smiles.chars().collect(),
+        pos: 0,
+        structure: Structure::default(),
+        previous: None,
+        bond: None,
+        branches: Vec::new(),
+        rings: BTreeMap::new(),
+    };
+    parser.parse()?;
+    Ok(parser.structure)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    structure: Structure,
+    /// Atom the next atom bonds to
+    previous: Option<usize>,
+    /// Bond symbol read but not yet used
+    bond: Option<PendingBond>,
+    /// Atoms that open branches
+    branches: Vec<usize>,
+    /// Open ring closures by label: atom and the bond written at the opening
+    rings: BTreeMap<u32, (usize, Option<PendingBond>)>,
+}
+
+impl Parser {
+    fn parse(&mut self) -> Result<()> {
+        if self.chars.is_empty() {
+            return Err(anyhow!("Empty SMILES"));
+        }
+        while let Some(c) = self.peek() {
+            let at = self.pos + 1;
+            match c {
+                '(' => {
+                    self.pos += 1;
+                    let atom = self.previous.ok_or_else(|| anyhow!("Branch without a preceding atom at position {}", at))?;
+                    if self.bond.is_some() {
+                        return Err(anyhow!("Bond before a branch at position {}", at));
+                    }
+                    self.branches.push(atom);
+                }
+                ')' => {
+                    self.pos += 1;
+                    if self.bond.is_some() {
+                        return Err(anyhow!("Bond without an atom at position {}", at));
+                    }
+                    self.previous = Some(self.branches.pop().ok_or_else(|| anyhow!("Unmatched ')' at position {}", at))?);
+                }
+                '.' => {
+                    self.pos += 1;
+                    if self.bond.is_some() || self.previous.is_none() {
+                        return Err(anyhow!("Misplaced '.' at position {}", at));
+                    }
+                    if !self.branches.is_empty() {
+                        return Err(anyhow!("'.' inside a branch at position {}", at));
+                    }
+                    self.previous = None;
+                }
+                '-' | '=' | '#' | '$' | ':' | '/' | '\\' => {
+                    self.pos += 1;
+                    if self.bond.is_some() || self.previous.is_none() {
+                        return Err(anyhow!("Misplaced bond '{}' at position {}", c, at));
+                    }
+                    self.bond = Some(match c {
+                        '-' => (BondOrder::Single, None),
+                        '=' => (BondOrder::Double, None),
+                        '#' => (BondOrder::Triple, None),
+                        '$' => (BondOrder::Quadruple, None),
+                        ':' => (BondOrder::Aromatic, None),
+                        direction => (BondOrder::Single, Some(direction)),
+                    });
+                }
+                '%' | '0'..='9' => {
+                    let label = self.ring_label()?;
+                    self.ring_closure(label, at)?;
+                }
+                '[' => {
+                    let atom = self.bracket_atom()?;
+                    self.add_atom(atom);
+                }
+                _ => {
+                    let atom = self.organic_atom()?;
+                    self.add_atom(atom);
+                }
+            }
+        }
+
+        if self.bond.is_some() {
+            return Err(anyhow!("SMILES ends with a bond"));
+        }
+        if !self.branches.is_empty() {
+            return Err(anyhow!("Unclosed branch"));
+        }
+        if let Some(label) = self.rings.keys().next() {
+            return Err(anyhow!("Unclosed ring bond {}", label));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    /// Read a run of digits, if any
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+    }
+
+    /// Bond from the previous atom to a new one, when there is a previous atom
+    fn add_atom(&mut self, atom: Atom) {
+        let index = self.structure.add_atom(atom);
+        if let Some(previous) = self.previous {
+            let bond = self.bond.take();
+            self.add_bond(previous, index, bond);
+        }
+        self.previous = Some(index);
+    }
+
+    fn add_bond(&mut self, a: usize, b: usize, bond: Option<PendingBond>) {
+        let aromatic = self.structure.atoms[a].aromatic && self.structure.atoms[b].aromatic;
+        let (order, direction) = bond.unwrap_or(if aromatic { (BondOrder::Aromatic, None) } else { (BondOrder::Single, None) });
+        self.structure.bonds.push(Bond { a, b, order, direction });
+    }
+
+    fn ring_label(&mut self) -> Result<u32> {
+        let at = self.pos + 1;
+        if self.next() == Some('%') {
+            let digits: String = self.chars.iter().skip(self.pos).take(2).collect();
+            self.pos += 2;
+            if digits.len() != 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(anyhow!("Invalid ring closure label at position {}", at));
+            }
+            Ok(digits.parse()?)
+        } else {
+            Ok(self.chars[self.pos - 1].to_digit(10).expect("digit"))
+        }
+    }
+
+    fn ring_closure(&mut self, label: u32, at: usize) -> Result<()> {
+        let atom = self.previous.ok_or_else(|| anyhow!("Ring closure without an atom at position {}", at))?;
+        let bond = self.bond.take();
+        let Some((opening, opening_bond)) = self.rings.remove(&label) else {
+            self.rings.insert(label, (atom, bond));
+            return Ok(());
+        };
+
+        if opening == atom {
+            return Err(anyhow!("Ring bond {} closes on its own atom at position {}", label, at));
+        }
+        if self.structure.bond_between(opening, atom).is_some() {
+            return Err(anyhow!("Ring bond {} duplicates an existing bond at position {}", label, at));
+        }
+        let bond = match (opening_bond, bond) {
+            (Some(a), Some(b)) if a.0 != b.0 => {
+                return Err(anyhow!("Ring bond {} has conflicting bond orders at position {}", label, at));
+            }
+            (Some(a), _) => Some(a),
+            // A direction written at the closing atom reads from that atom
+            (None, Some((order, direction))) => Some((order, direction.map(|d| if d == '/' { '\\' } else { '/' }))),
+            (None, None) => None,
+        };
+        self.add_bond(opening, atom, bond);
+        Ok(())
+    }
+
+    fn organic_atom(&mut self) -> Result<Atom> {
+        let at = self.pos + 1;
+        let c = self.next().expect("atom character");
+        let (element, aromatic) = match c {
+            'C' if self.peek() == Some('l') => { self.pos += 1; ("Cl", false) }
+            'B' if self.peek() == Some('r') => { self.pos += 1; ("Br", false) }
+            'B' => ("B", false),
+            'C' => ("C", false),
+            'N' => ("N", false),
+            'O' => ("O", false),
+            'P' => ("P", false),
+            'S' => ("S", false),
+            'F' => ("F", false),
+            'I' => ("I", false),
+            'b' => ("B", true),
+            'c' => ("C", true),
+            'n' => ("N", true),
+            'o' => ("O", true),
+            'p' => ("P", true),
+            's' => ("S", true),
+            '*' => ("*", false),
+            other => return Err(anyhow!("Unexpected character '{}' at position {}", other, at)),
+        };
+        Ok(Atom { aromatic, ..Atom::new(element) })
+    }
+
+    fn bracket_atom(&mut self) -> Result<Atom> {
+        let at = self.pos + 1;
+        self.pos += 1;
+        let isotope = self.number();
+
+        let first = self.next().ok_or_else(|| anyhow!("Unclosed bracket atom at position {}", at))?;
+        let (element, aromatic) = if first == '*' {
+            ("*".to_string(), false)
+        } else if first.is_ascii_alphabetic() {
+            let mut symbol = first.to_ascii_uppercase().to_string();
+            if let Some(second) = self.peek().filter(|c| c.is_ascii_lowercase()) {
+                let two = format!("{}{}", symbol, second);
+                if ELEMENTS.contains(&two.as_str()) && (first.is_ascii_uppercase() || AROMATIC_BRACKET.contains(&two.as_str())) {
+                    symbol = two;
+                    self.pos += 1;
+                }
+            }
+            let aromatic = first.is_ascii_lowercase();
+            if !ELEMENTS.contains(&symbol.as_str()) || (aromatic && !AROMATIC_BRACKET.contains(&symbol.as_str())) {
+                return Err(anyhow!("Unknown element '{}' in bracket atom at position {}", symbol, at));
+            }
+            (symbol, aromatic)
+        } else {
+            return Err(anyhow!("Bracket atom without an element at position {}", at));
+        };
+
+        let chirality = if self.peek() == Some('@') {
+            let start = self.pos;
+            self.pos += 1;
+            if self.peek() == Some('@') {
+                self.pos += 1;
+            } else if self.peek().is_some_and(|c| c.is_ascii_uppercase() && c != 'H') {
+                self.pos += 2;
+                self.number();
+            }
+            Some(self.chars[start..self.pos.min(self.chars.len())].iter().collect())
+        } else {
+            None
+        };
+
+        let hydrogens = if self.peek() == Some('H') {
+            self.pos += 1;
+            self.number().unwrap_or(1)
+        } else {
+            0
+        };
+
+        let mut charge = 0i32;
+        if let Some(sign @ ('+' | '-')) = self.peek() {
+            let unit = if sign == '+' { 1 } else { -1 };
+            self.pos += 1;
+            charge = match self.number() {
+                Some(n) => unit * n as i32,
+                None => {
+                    let mut count = 1;
+                    while self.peek() == Some(sign) {
+                        self.pos += 1;
+                        count += 1;
+                    }
+                    unit * count
+                }
+            };
+        }
+
+        // Atom classes carry no chemistry and are dropped
+        if self.peek() == Some(':') {
+            self.pos += 1;
+            if self.number().is_none() {
+                return Err(anyhow!("Invalid atom class in bracket atom at position {}", at));
+            }
+        }
+
+        if self.next() != Some(']') {
+            return Err(anyhow!("Malformed bracket atom at position {}", at));
+        }
+        Ok(Atom {
+            element,
+            aromatic,
+            charge,
+            hydrogens: Some(hydrogens),
+            isotope,
+            chirality,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_write_round_trip() {
+        for smiles in [
+            "CCO",
+            "c1ccccc1O",
+            "CC(=O)[O-].[Na+]",
+            "C[C@@H](N)C(=O)O",
+            "F/C=C/F",
+            "C1CC2CCC1CC2",
+            "[13CH4]",
+            "c1cc[nH]c1",
+        ] {
+            let structure = parse_smiles(smiles).unwrap();
+            assert_eq!(structure.to_smiles(), smiles, "round trip of {}", smiles);
+        }
+
+        let ring = parse_smiles("C1=CC=CC=C1").unwrap();
+        assert_eq!(ring.atoms.len(), 6);
+        assert_eq!(ring.bonds.len(), 6);
+        // Bracket atoms that say nothing the organic subset cannot are written bare
+        assert_eq!(parse_smiles("[CH3][CH2][OH]").unwrap().to_smiles(), "CCO");
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        for smiles in ["", "C(C", "CC)", "C1CC", "C=", "[Xx]", "C[NH", "C%1", "C11", "=C", "CQ"] {
+            assert!(parse_smiles(smiles).is_err(), "{} should not parse", smiles);
+        }
+    }
+}
@@ -0,0 +1,540 @@
+//! Molecular graphs read from library files
+//!
+//! The atoms and bonds of one library record, with the checks and rewriting
+//! needed to standardize it: connected fragments, valences against a table
+//! of normal valences, and a SMILES writer.
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// Elements that may be written outside brackets in SMILES
+const ORGANIC_SUBSET: &[&str] = &["B", "C", "N", "O", "P", "S", "F", "Cl", "Br", "I"];
+
+/// Elements that may be aromatic outside brackets in SMILES
+const AROMATIC_SUBSET: &[&str] = &["B", "C", "N", "O", "P", "S"];
+
+/// Every element symbol, in atomic number order
+pub const ELEMENTS: &[&str] = &[
+    "H", "He", "Li", "Be", "B", "C", "N", "O", "F", "Ne", "Na", "Mg", "Al", "Si", "P", "S", "Cl", "Ar",
+    "K", "Ca", "Sc", "Ti", "V", "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn", "Ga", "Ge", "As", "Se", "Br", "Kr",
+    "Rb", "Sr", "Y", "Zr", "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In", "Sn", "Sb", "Te", "I", "Xe",
+    "Cs", "Ba", "La", "Ce", "Pr", "Nd", "Pm", "Sm", "Eu", "Gd", "Tb", "Dy", "Ho", "Er", "Tm", "Yb", "Lu",
+    "Hf", "Ta", "W", "Re", "Os", "Ir", "Pt", "Au", "Hg", "Tl", "Pb", "Bi", "Po", "At", "Rn",
+    "Fr", "Ra", "Ac", "Th", "Pa", "U", "Np", "Pu", "Am", "Cm", "Bk", "Cf", "Es", "Fm", "Md", "No", "Lr",
+    "Rf", "Db", "Sg", "Bh", "Hs", "Mt", "Ds", "Rg", "Cn", "Nh", "Fl", "Mc", "Lv", "Ts", "Og",
+];
+
+/// Normal valences of the neutral element, or `None` for elements whose
+/// valence is not checked (metals, noble gases, wildcards)
+fn normal_valences(element: &str) -> Option<&'static [u32]> {
+    match element {
+        "H" => Some(&[1]),
+        "B" => Some(&[3]),
+        "C" | "Si" => Some(&[4]),
+        "N" | "As" => Some(&[3, 5]),
+        "P" => Some(&[3, 5]),
+        "O" => Some(&[2]),
+        "S" | "Se" => Some(&[2, 4, 6]),
+        "F" => Some(&[1]),
+        "Cl" | "Br" | "I" => Some(&[1, 3, 5, 7]),
+        _ => None,
+    }
+}
+
+/// Normal valences of an element carrying a formal charge
+///
+/// A charge on an electron-rich atom (N+, O-) shifts its valence by the
+/// charge; carbon loses a bond either way and boron gains one when negative.
+fn charged_valences(element: &str, charge: i32) -> Option<Vec<u32>> {
+    let valences = normal_valences(element)?;
+    let shift = match element {
+        "H" if charge != 0 => return Some(vec![0]),
+        "B" => -charge,
+        "C" | "Si" => -charge.abs(),
+        _ => charge,
+    };
+    Some(valences.iter()
+        .filter_map(|&v| u32::try_from(v as i32 + shift).ok())
+        .collect())
+}
+
+/// Bond multiplicity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondOrder {
+    Single,
+    Double,
+    Triple,
+    Quadruple,
+    Aromatic,
+}
+
+impl BondOrder {
+    /// Contribution to an atom's valence; aromatic bonds count as single so
+    /// the check does not depend on how a ring would be kekulized
+    fn valence(self) -> u32 {
+        match self {
+            BondOrder::Single | BondOrder::Aromatic => 1,
+            BondOrder::Double => 2,
+            BondOrder::Triple => 3,
+            BondOrder::Quadruple => 4,
+        }
+    }
+}
+
+/// Atom of a structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atom {
+    /// Element symbol, capitalized even for aromatic atoms; `*` for a wildcard
+    pub element: String,
+
+    /// Whether the atom is part of an aromatic ring
+    pub aromatic: bool,
+
+    /// Formal charge
+    pub charge: i32,
+
+    /// Attached hydrogens, or `None` for the number implied by normal valence
+    pub hydrogens: Option<u32>,
+
+    /// Isotope mass number, if one was given
+    pub isotope: Option<u32>,
+
+    /// Tetrahedral or other chirality marker as written (`@`, `@@`, `@TH1`, ...)
+    pub chirality: Option<String>,
+}
+
+impl Atom {
+    /// Neutral atom of an element with implied hydrogens
+    pub fn new(element: &str) -> Self {
+        Self {
+            element: element.to_string(),
+            aromatic: false,
+            charge: 0,
+            hydrogens: None,
+            isotope: None,
+            chirality: None,
+        }
+    }
+}
+
+/// Bond between two atoms of a structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bond {
+    /// Index of the first atom
+    pub a: usize,
+
+    /// Index of the second atom
+    pub b: usize,
+
+    /// Bond multiplicity
+    pub order: BondOrder,
+
+    /// Double-bond geometry marker (`/` or `\`), read in the direction from `a` to `b`
+    pub direction: Option<char>,
+}
+
+/// Atom whose bonds exceed every normal valence of its element
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValenceViolation {
+    /// Index of the atom
+    pub atom: usize,
+
+    /// Element of the atom
+    pub element: String,
+
+    /// Formal charge of the atom
+    pub charge: i32,
+
+    /// Bonds and explicit hydrogens of the atom
+    pub valence: u32,
+
+    /// Largest valence the element allows at that charge
+    pub max_valence: u32,
+}
+
+impl std::fmt::Display for ValenceViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let charge = match self.charge {
+            0 => String::new(),
+            c if c > 0 => format!("{:+}", c),
+            c => c.to_string(),
+        };
+        write!(f, "atom {} ({}{}) has valence {}, at most {} allowed",
+               self.atom + 1, self.element, charge, self.valence, self.max_valence)
+    }
+}
+
+/// Atoms and bonds of a molecule, possibly in several disconnected fragments
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Structure {
+    /// Atoms in the order they were read
+    pub atoms: Vec<Atom>,
+
+    /// Bonds in the order they were read
+    pub bonds: Vec<Bond>,
+}
+
+impl Structure {
+    /// Add an atom, returning its index
+    pub fn add_atom(&mut self, atom: Atom) -> usize {
+        self.atoms.push(atom);
+        self.atoms.len() - 1
+    }
+
+    /// Index of the bond between two atoms, if they are bonded
+    pub fn bond_between(&self, a: usize, b: usize) -> Option<usize> {
+        self.bonds.iter().position(|bond| (bond.a == a && bond.b == b) || (bond.a == b && bond.b == a))
+    }
+
+    /// Neighbors of an atom with the order of the bond to each
+    pub fn neighbors(&self, atom: usize) -> impl Iterator<Item = (usize, BondOrder)> + '_ {
+        self.bonds.iter()
+            .filter(move |bond| bond.a == atom || bond.b == atom)
+            .map(move |bond| (if bond.a == atom { bond.b } else { bond.a }, bond.order))
+    }
+
+    /// Hydrogens on an atom, explicit or implied
+    pub fn hydrogen_count(&self, atom: usize) -> u32 {
+        self.atoms[atom].hydrogens.unwrap_or_else(|| self.implicit_hydrogens(atom))
+    }
+
+    /// Whether a bond is part of a ring, i.e. its atoms stay connected without it
+    pub fn is_ring_bond(&self, bond: usize) -> bool {
+        let Bond { a, b, .. } = self.bonds[bond];
+        let adjacency = self.adjacency();
+        let mut seen = vec![false; self.atoms.len()];
+        seen[a] = true;
+        let mut stack = vec![a];
+        while let Some(atom) = stack.pop() {
+            for &next in &adjacency[atom] {
+                if next == bond {
+                    continue;
+                }
+                let other = self.other(next, atom);
+                if other == b {
+                    return true;
+                }
+                if !seen[other] {
+                    seen[other] = true;
+                    stack.push(other);
+                }
+            }
+        }
+        false
+    }
+
+    /// Bond indices of every atom, in bond order
+    fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.atoms.len()];
+        for (i, bond) in self.bonds.iter().enumerate() {
+            adjacency[bond.a].push(i);
+            adjacency[bond.b].push(i);
+        }
+        adjacency
+    }
+
+    /// Sum of the bond valences of an atom
+    fn bond_valence(&self, atom: usize) -> u32 {
+        self.bonds.iter()
+            .filter(|bond| bond.a == atom || bond.b == atom)
+            .map(|bond| bond.order.valence())
+            .sum()
+    }
+
+    /// Hydrogens implied for an atom: enough to reach its smallest normal
+    /// valence at or above its bonds, counting one extra bond for aromatic atoms
+    pub fn implicit_hydrogens(&self, atom: usize) -> u32 {
+        let a = &self.atoms[atom];
+        let Some(valences) = charged_valences(&a.element, a.charge) else {
+            return 0;
+        };
+        let used = self.bond_valence(atom) + u32::from(a.aromatic);
+        valences.iter()
+            .find(|&&v| v >= used)
+            .map(|v| v - used)
+            .unwrap_or(0)
+    }
+
+    /// Atoms whose bonds and explicit hydrogens exceed their element's normal valences
+    pub fn valence_violations(&self) -> Vec<ValenceViolation> {
+        (0..self.atoms.len())
+            .filter_map(|i| {
+                let atom = &self.atoms[i];
+                let max_valence = *charged_valences(&atom.element, atom.charge)?.iter().max()?;
+                let valence = self.bond_valence(i) + atom.hydrogens.unwrap_or(0);
+                (valence > max_valence).then(|| ValenceViolation {
+                    atom: i,
+                    element: atom.element.clone(),
+                    charge: atom.charge,
+                    valence,
+                    max_valence,
+                })
+            })
+            .collect()
+    }
+
+    /// Distinct elements of the structure, sorted
+    pub fn elements(&self) -> Vec<&str> {
+        let mut elements: Vec<&str> = self.atoms.iter().map(|a| a.element.as_str()).collect();
+        elements.sort_unstable();
+        elements.dedup();
+        elements
+    }
+
+    /// Atom indices of each connected fragment, ordered by their first atom
+    pub fn fragments(&self) -> Vec<Vec<usize>> {
+        let adjacency = self.adjacency();
+        let mut seen = vec![false; self.atoms.len()];
+        let mut fragments = Vec::new();
+        for start in 0..self.atoms.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut fragment = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            while let Some(atom) = queue.pop_front() {
+                fragment.push(atom);
+                for &bond in &adjacency[atom] {
+                    let other = self.other(bond, atom);
+                    if !seen[other] {
+                        seen[other] = true;
+                        queue.push_back(other);
+                    }
+                }
+            }
+            fragment.sort_unstable();
+            fragments.push(fragment);
+        }
+        fragments
+    }
+
+    /// The fragment with the most heavy atoms (the first on ties), and how
+    /// many fragments were dropped to get it
+    pub fn largest_fragment(&self) -> (Structure, usize) {
+        let fragments = self.fragments();
+        if fragments.len() <= 1 {
+            return (self.clone(), 0);
+        }
+        let heavy = |fragment: &Vec<usize>| fragment.iter().filter(|&&i| self.atoms[i].element != "H").count();
+        let largest = fragments.iter()
+            .reduce(|best, fragment| if heavy(fragment) > heavy(best) { fragment } else { best })
+            .expect("structure has fragments");
+        (self.subset(largest), fragments.len() - 1)
+    }
+
+    /// The structure made of the given atoms and the bonds between them
+    fn subset(&self, atoms: &[usize]) -> Structure {
+        let index: BTreeMap<usize, usize> = atoms.iter().enumerate().map(|(new, &old)| (old, new)).collect();
+        Structure {
+            atoms: atoms.iter().map(|&i| self.atoms[i].clone()).collect(),
+            bonds: self.bonds.iter()
+                .filter_map(|bond| Some(Bond { a: *index.get(&bond.a)?, b: *index.get(&bond.b)?, ..bond.clone() }))
+                .collect(),
+        }
+    }
+
+    /// Atom at the other end of a bond
+    fn other(&self, bond: usize, atom: usize) -> usize {
+        let bond = &self.bonds[bond];
+        if bond.a == atom { bond.b } else { bond.a }
+    }
+
+    /// Write the structure as SMILES
+    ///
+    /// Each fragment is walked depth-first from its first atom, visiting
+    /// neighbors in the order their bonds were read, so a structure read from
+    /// SMILES keeps its atom order and chirality markers keep their meaning.
+    /// Atoms are written outside brackets whenever SMILES allows it.
+    pub fn to_smiles(&self) -> String {
+        let adjacency = self.adjacency();
+        let mut writer = SmilesWriter {
+            structure: self,
+            adjacency: &adjacency,
+            visited: vec![false; self.atoms.len()],
+            tree_bonds: vec![false; self.bonds.len()],
+            ring_bonds: vec![Vec::new(); self.atoms.len()],
+            labels: BTreeMap::new(),
+            out: String::new(),
+        };
+        let mut fragments = Vec::new();
+        for fragment in self.fragments() {
+            writer.out.clear();
+            writer.find_rings(fragment[0], None);
+            writer.write_atom(fragment[0], None);
+            fragments.push(std::mem::take(&mut writer.out));
+        }
+        fragments.join(".")
+    }
+
+    /// Atom as written in SMILES, bare when the organic subset allows it
+    fn atom_smiles(&self, atom: usize) -> String {
+        let a = &self.atoms[atom];
+        let symbol = if a.aromatic { a.element.to_lowercase() } else { a.element.clone() };
+        let bare = a.charge == 0
+            && a.isotope.is_none()
+            && a.chirality.is_none()
+            && if a.aromatic { AROMATIC_SUBSET.contains(&a.element.as_str()) } else { ORGANIC_SUBSET.contains(&a.element.as_str()) }
+            && a.hydrogens.is_none_or(|h| h == self.implicit_hydrogens(atom));
+        if bare {
+            return symbol;
+        }
+
+        let mut text = String::from("[");
+        if let Some(isotope) = a.isotope {
+            text.push_str(&isotope.to_string());
+        }
+        text.push_str(&symbol);
+        if let Some(chirality) = &a.chirality {
+            text.push_str(chirality);
+        }
+        match a.hydrogens.unwrap_or_else(|| self.implicit_hydrogens(atom)) {
+            0 => {}
+            1 => text.push('H'),
+            n => text.push_str(&format!("H{}", n)),
+        }
+        match a.charge {
+            0 => {}
+            1 => text.push('+'),
+            -1 => text.push('-'),
+            c => text.push_str(&format!("{:+}", c)),
+        }
+        text.push(']');
+        text
+    }
+
+    /// Bond symbol written when going from `from` across a bond
+    fn bond_smiles(&self, bond: usize, from: usize) -> String {
+        let b = &self.bonds[bond];
+        let both_aromatic = self.atoms[b.a].aromatic && self.atoms[b.b].aromatic;
+        match b.order {
+            BondOrder::Single => match b.direction {
+                Some(direction) if from == b.a => direction.to_string(),
+                Some('/') => "\\".to_string(),
+                Some(_) => "/".to_string(),
+                None if both_aromatic => "-".to_string(),
+                None => String::new(),
+            },
+            BondOrder::Double => "=".to_string(),
+            BondOrder::Triple => "#".to_string(),
+            BondOrder::Quadruple => "$".to_string(),
+            BondOrder::Aromatic if both_aromatic => String::new(),
+            BondOrder::Aromatic => ":".to_string(),
+        }
+    }
+}
+
+/// Depth-first SMILES writer: a first pass splits bonds into tree bonds and
+/// ring closures, a second writes atoms, closures and branches
+struct SmilesWriter<'a> {
+    structure: &'a Structure,
+    adjacency: &'a [Vec<usize>],
+    visited: Vec<bool>,
+    tree_bonds: Vec<bool>,
+    ring_bonds: Vec<Vec<usize>>,
+    labels: BTreeMap<usize, u32>,
+    out: String,
+}
+
+impl SmilesWriter<'_> {
+    fn find_rings(&mut self, atom: usize, parent: Option<usize>) {
+        self.visited[atom] = true;
+        for &bond in self.adjacency[atom].iter() {
+            if Some(bond) == parent || self.tree_bonds[bond] || self.ring_bonds[atom].contains(&bond) {
+                continue;
+            }
+            let other = self.structure.other(bond, atom);
+            if self.visited[other] {
+                self.ring_bonds[atom].push(bond);
+                self.ring_bonds[other].push(bond);
+            } else {
+                self.tree_bonds[bond] = true;
+                self.find_rings(other, Some(bond));
+            }
+        }
+    }
+
+    fn write_atom(&mut self, atom: usize, parent: Option<usize>) {
+        self.out.push_str(&self.structure.atom_smiles(atom));
+
+        for bond in self.ring_bonds[atom].clone() {
+            match self.labels.remove(&bond) {
+                Some(label) => push_label(&mut self.out, label),
+                None => {
+                    let label = (1..).find(|l| !self.labels.values().any(|used| used == l)).expect("free ring label");
+                    self.labels.insert(bond, label);
+                    self.out.push_str(&self.structure.bond_smiles(bond, atom));
+                    push_label(&mut self.out, label);
+                }
+            }
+        }
+
+        let children: Vec<usize> = self.adjacency[atom].iter()
+            .copied()
+            .filter(|&bond| Some(bond) != parent && self.tree_bonds[bond])
+            .collect();
+        for (i, &bond) in children.iter().enumerate() {
+            let branch = i + 1 < children.len();
+            if branch {
+                self.out.push('(');
+            }
+            self.out.push_str(&self.structure.bond_smiles(bond, atom));
+            self.write_atom(self.structure.other(bond, atom), Some(bond));
+            if branch {
+                self.out.push(')');
+            }
+        }
+    }
+}
+
+fn push_label(out: &mut String, label: u32) {
+    if label < 10 {
+        out.push_str(&label.to_string());
+    } else {
+        out.push_str(&format!("%{:02}", label));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ethanol written atom by atom, with a sodium ion alongside
+    fn ethanol_with_sodium() -> Structure {
+        let mut structure = Structure::default();
+        let c1 = structure.add_atom(Atom::new("C"));
+        let c2 = structure.add_atom(Atom::new("C"));
+        let o = structure.add_atom(Atom::new("O"));
+        structure.add_atom(Atom { charge: 1, hydrogens: Some(0), ..Atom::new("Na") });
+        structure.bonds.push(Bond { a: c1, b: c2, order: BondOrder::Single, direction: None });
+        structure.bonds.push(Bond { a: c2, b: o, order: BondOrder::Single, direction: None });
+        structure
+    }
+
+    #[test]
+    fn test_fragments_and_writing() {
+        let structure = ethanol_with_sodium();
+        assert_eq!(structure.fragments(), vec![vec![0, 1, 2], vec![3]]);
+        assert_eq!(structure.to_smiles(), "CCO.[Na+]");
+
+        let (largest, removed) = structure.largest_fragment();
+        assert_eq!(removed, 1);
+        assert_eq!(largest.to_smiles(), "CCO");
+        assert_eq!(structure.implicit_hydrogens(0), 3);
+        assert_eq!(structure.implicit_hydrogens(2), 1);
+    }
+
+    #[test]
+    fn test_valence_violations() {
+        let mut structure = ethanol_with_sodium();
+        assert!(structure.valence_violations().is_empty());
+
+        structure.bonds[1].order = BondOrder::Triple;
+        let violations = structure.valence_violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].atom, 2);
+        assert_eq!(violations[0].to_string(), "atom 3 (O) has valence 3, at most 2 allowed");
+
+        structure.atoms[2].charge = 1;
+        assert!(structure.valence_violations().is_empty());
+    }
+}
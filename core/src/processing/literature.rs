@@ -0,0 +1,378 @@
+//! Literature Processing Module
+//!
+//! This module produces literature evidence for molecules. It queries PubMed via
+//! the NCBI E-utilities for articles that mention a molecule (by any of its
+//! synonyms) together with target terms, and turns the co-mention counts into
+//! `Literature` evidence whose confidence favours recent publications.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Datelike;
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::identity::xref::CrossReferences;
+use crate::identity::MoleculeIdType;
+use crate::offline::{self, NetworkFeature};
+use crate::processing::evidence::{Evidence, EvidenceType};
+
+/// Initialize the literature processing module
+pub fn initialize() -> Result<()> {
+    info!("Initializing literature processing module");
+    info!("Literature processing module initialized successfully");
+    Ok(())
+}
+
+/// Options for literature processing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiteratureOptions {
+    /// Base URL of the E-utilities API
+    pub endpoint: String,
+
+    /// NCBI API key (raises the rate limit from 3 to 10 requests per second)
+    pub api_key: Option<String>,
+
+    /// Contact email sent with each request, as NCBI asks of tools
+    pub email: Option<String>,
+
+    /// Maximum number of article IDs retrieved per co-mention query
+    pub max_results: usize,
+
+    /// Terms to count co-mentions with (e.g. a disease, tissue or pathway)
+    pub target_terms: Vec<String>,
+
+    /// Age in years at which an article's weight halves
+    pub recency_half_life_years: f64,
+
+    /// Co-mention count at which confidence reaches about 63% of its maximum
+    pub count_scale: f64,
+
+    /// Request timeout in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for LiteratureOptions {
+    fn default() -> Self {
+        Self {
+            endpoint: "https://eutils.ncbi.nlm.nih.gov/entrez/eutils".to_string(),
+            api_key: std::env::var("NCBI_API_KEY").ok(),
+            email: None,
+            max_results: 100,
+            target_terms: Vec::new(),
+            recency_half_life_years: 5.0,
+            count_scale: 10.0,
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Summary of a PubMed article
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubMedArticle {
+    /// PubMed ID
+    pub pmid: String,
+
+    /// Article title
+    pub title: String,
+
+    /// Journal name
+    pub journal: Option<String>,
+
+    /// Publication year
+    pub year: Option<i32>,
+}
+
+/// Result of a PubMed search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PubMedSearch {
+    /// Total number of matching articles
+    pub count: usize,
+
+    /// IDs of the returned articles (at most `max_results`)
+    pub pmids: Vec<String>,
+}
+
+/// Co-mention of a molecule with a target term
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoMention {
+    /// Target term
+    pub term: String,
+
+    /// PubMed query that was run
+    pub query: String,
+
+    /// Number of articles mentioning both the molecule and the term
+    pub count: usize,
+
+    /// Most recent co-mentioning articles
+    pub articles: Vec<PubMedArticle>,
+
+    /// Mean recency weight of the retrieved articles (0.0 - 1.0)
+    pub recency: f64,
+}
+
+/// Client for the NCBI E-utilities PubMed API
+pub struct PubMedClient {
+    /// Options including the endpoint and credentials
+    options: LiteratureOptions,
+
+    /// HTTP client
+    http: reqwest::Client,
+}
+
+impl PubMedClient {
+    /// Create a new client
+    pub fn new(options: LiteratureOptions) -> Self {
+        Self {
+            options,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Search PubMed, newest articles first
+    pub async fn search(&self, query: &str, max_results: usize) -> Result<PubMedSearch> {
+        let retmax = max_results.to_string();
+        let data = self.get("esearch.fcgi", &[
+            ("term", query),
+            ("retmax", &retmax),
+            ("sort", "pub_date"),
+        ]).await?;
+
+        let result = &data["esearchresult"];
+        let count = result["count"].as_str()
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| anyhow!("PubMed search returned no count for query: {}", query))?;
+        let pmids = result["idlist"].as_array()
+            .map(|ids| ids.iter().filter_map(|id| id.as_str().map(String::from)).collect())
+            .unwrap_or_default();
+
+        Ok(PubMedSearch { count, pmids })
+    }
+
+    /// Fetch article summaries for a set of PubMed IDs
+    pub async fn summaries(&self, pmids: &[String]) -> Result<Vec<PubMedArticle>> {
+        if pmids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids = pmids.join(",");
+        let data = self.get("esummary.fcgi", &[("id", &ids)]).await?;
+
+        Ok(pmids.iter()
+            .filter_map(|pmid| {
+                let doc = data["result"].get(pmid)?;
+                Some(PubMedArticle {
+                    pmid: pmid.clone(),
+                    title: doc["title"].as_str().unwrap_or_default().to_string(),
+                    journal: doc["fulljournalname"].as_str().map(String::from),
+                    year: doc["pubdate"].as_str().and_then(parse_year),
+                })
+            })
+            .collect())
+    }
+
+    async fn get(&self, utility: &str, params: &[(&str, &str)]) -> Result<serde_json::Value> {
+        offline::ensure_available(NetworkFeature::Literature)?;
+        let mut query: Vec<(&str, &str)> = vec![("db", "pubmed"), ("retmode", "json"), ("tool", "hegel")];
+        query.extend_from_slice(params);
+        if let Some(key) = &self.options.api_key {
+            query.push(("api_key", key));
+        }
+        if let Some(email) = &self.options.email {
+            query.push(("email", email));
+        }
+
+        let response = self.http.get(format!("{}/{}", self.options.endpoint, utility))
+            .query(&query)
+            .timeout(Duration::from_secs(self.options.timeout_secs))
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to PubMed {}", utility))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("PubMed {} request failed with status {}", utility, response.status()));
+        }
+
+        response.json::<serde_json::Value>().await
+            .with_context(|| format!("Failed to parse PubMed {} response", utility))
+    }
+}
+
+/// Processor that turns PubMed co-mentions into literature evidence
+pub struct LiteratureProcessor {
+    /// Processing options
+    options: LiteratureOptions,
+
+    /// PubMed client
+    client: PubMedClient,
+}
+
+impl LiteratureProcessor {
+    /// Create a new literature processor with default options
+    pub fn new() -> Self {
+        Self::with_options(LiteratureOptions::default())
+    }
+
+    /// Create a new literature processor with the given options
+    pub fn with_options(options: LiteratureOptions) -> Self {
+        Self {
+            client: PubMedClient::new(options.clone()),
+            options,
+        }
+    }
+
+    /// Count co-mentions of a molecule's synonyms with each target term
+    pub async fn co_mentions(&self, synonyms: &[String]) -> Result<Vec<CoMention>> {
+        let molecule_query = build_query(synonyms)?;
+        let current_year = chrono::Utc::now().year();
+        let mut co_mentions = Vec::with_capacity(self.options.target_terms.len());
+
+        for term in &self.options.target_terms {
+            let query = format!("{} AND \"{}\"[Title/Abstract]", molecule_query, escape_term(term));
+            debug!("Searching PubMed: {}", query);
+
+            let search = self.client.search(&query, self.options.max_results).await?;
+            let articles = match self.client.summaries(&search.pmids).await {
+                Ok(articles) => articles,
+                Err(e) => {
+                    warn!("Failed to fetch PubMed summaries for {}: {}", term, e);
+                    Vec::new()
+                }
+            };
+            let recency = mean_recency(&articles, current_year, self.options.recency_half_life_years);
+
+            co_mentions.push(CoMention {
+                term: term.clone(),
+                query,
+                count: search.count,
+                articles,
+                recency,
+            });
+        }
+
+        Ok(co_mentions)
+    }
+
+    /// Gather literature evidence for a molecule, one item per target term with co-mentions
+    pub async fn gather_evidence(&self, molecule_id: &str, synonyms: &[String]) -> Result<Vec<Evidence>> {
+        let co_mentions = self.co_mentions(synonyms).await
+            .context("Failed to gather literature evidence")?;
+
+        Ok(co_mentions.into_iter()
+            .filter(|c| c.count > 0)
+            .map(|c| self.to_evidence(molecule_id, c))
+            .collect())
+    }
+
+    /// Convert a co-mention into literature evidence
+    pub fn to_evidence(&self, molecule_id: &str, co_mention: CoMention) -> Evidence {
+        let confidence = literature_confidence(co_mention.count, co_mention.recency, self.options.count_scale);
+
+        let mut metadata = HashMap::new();
+        metadata.insert("term".to_string(), serde_json::json!(co_mention.term));
+        metadata.insert("article_count".to_string(), serde_json::json!(co_mention.count));
+
+        Evidence {
+            id: format!("literature-{}-{}", molecule_id, uuid::Uuid::new_v4()),
+            molecule_id: molecule_id.to_string(),
+            evidence_type: EvidenceType::Literature,
+            source: "pubmed".to_string(),
+            confidence,
+            data: serde_json::to_value(&co_mention).unwrap_or_default(),
+            metadata,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
+
+impl Default for LiteratureProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Collect search synonyms for a molecule from its name and cross-references
+///
+/// Database IDs that PubMed indexes (CAS registry numbers, ChEBI and HMDB IDs)
+/// are included alongside the name.
+pub fn synonyms_from_xrefs(name: Option<&str>, xrefs: &CrossReferences) -> Vec<String> {
+    let mut synonyms: Vec<String> = name.into_iter().map(String::from).collect();
+    for id_type in [MoleculeIdType::CAS, MoleculeIdType::ChEBIID, MoleculeIdType::HMDBID] {
+        if let Some(id) = xrefs.get(&id_type) {
+            synonyms.push(id.to_string());
+        }
+    }
+    synonyms.dedup();
+    synonyms
+}
+
+/// Build a PubMed query matching any of the synonyms
+pub fn build_query(synonyms: &[String]) -> Result<String> {
+    let terms: Vec<String> = synonyms.iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| format!("\"{}\"[All Fields]", escape_term(s)))
+        .collect();
+
+    match terms.len() {
+        0 => Err(anyhow!("Cannot build a PubMed query without synonyms")),
+        1 => Ok(terms[0].clone()),
+        _ => Ok(format!("({})", terms.join(" OR "))),
+    }
+}
+
+/// Weight of an article published in `year`, halving every `half_life` years
+pub fn recency_weight(year: i32, current_year: i32, half_life: f64) -> f64 {
+    let age = (current_year - year).max(0) as f64;
+    0.5f64.powf(age / half_life.max(f64::EPSILON))
+}
+
+/// Confidence from a co-mention count and mean recency
+///
+/// Saturates with the count, and scales between half and full strength with recency.
+pub fn literature_confidence(count: usize, recency: f64, count_scale: f64) -> f64 {
+    let support = 1.0 - (-(count as f64) / count_scale.max(f64::EPSILON)).exp();
+    support * (0.5 + 0.5 * recency.clamp(0.0, 1.0))
+}
+
+fn mean_recency(articles: &[PubMedArticle], current_year: i32, half_life: f64) -> f64 {
+    let weights: Vec<f64> = articles.iter()
+        .filter_map(|a| a.year)
+        .map(|year| recency_weight(year, current_year, half_life))
+        .collect();
+    if weights.is_empty() {
+        0.0
+    } else {
+        weights.iter().sum::<f64>() / weights.len() as f64
+    }
+}
+
+/// PubMed publication dates look like `2021 Mar 4` or `2019`
+fn parse_year(pubdate: &str) -> Option<i32> {
+    pubdate.split_whitespace().next()?.get(..4)?.parse().ok()
+}
+
+fn escape_term(term: &str) -> String {
+    term.replace('"', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_and_scoring() {
+        let query = build_query(&["aspirin".to_string(), "50-78-2".to_string()]).unwrap();
+        assert_eq!(query, "(\"aspirin\"[All Fields] OR \"50-78-2\"[All Fields])");
+        assert!(build_query(&[]).is_err());
+
+        assert!((recency_weight(2015, 2020, 5.0) - 0.5).abs() < 1e-9);
+        assert_eq!(parse_year("2021 Mar 4"), Some(2021));
+
+        let recent = literature_confidence(20, 1.0, 10.0);
+        let old = literature_confidence(20, 0.0, 10.0);
+        assert!(recent > old && recent < 1.0);
+        assert_eq!(literature_confidence(0, 1.0, 10.0), 0.0);
+    }
+}
@@ -0,0 +1,158 @@
+//! Mass Accuracy Module
+//!
+//! This module turns the ppm error between an observed and a theoretical m/z
+//! into a likelihood, using a Gaussian error distribution whose width depends
+//! on the instrument type, and combines it with spectral evidence.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::processing::mass_spec::MassSpecType;
+
+/// Initialize the mass accuracy module
+pub fn initialize() -> Result<()> {
+    info!("Initializing mass accuracy module");
+    info!("Mass accuracy module initialized successfully");
+    Ok(())
+}
+
+/// Metadata keys under which the theoretical m/z of the candidate may be given
+pub const THEORETICAL_MZ_KEYS: [&str; 2] = ["theoretical_mz", "expected_mz"];
+
+/// Instrument-specific mass error model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassAccuracyModel {
+    /// Standard deviation of the mass error in ppm, per MS type
+    pub sigma_ppm: HashMap<MassSpecType, f64>,
+
+    /// Standard deviation used for MS types without an entry
+    pub default_sigma_ppm: f64,
+
+    /// Systematic calibration offset in ppm, subtracted before scoring
+    pub offset_ppm: f64,
+
+    /// Weight of mass accuracy against the fragmentation score (0.0 - 1.0)
+    pub mass_weight: f64,
+}
+
+impl Default for MassAccuracyModel {
+    fn default() -> Self {
+        let sigma_ppm = [
+            (MassSpecType::LCMSMS, 3.0),
+            (MassSpecType::GCMS, 20.0),
+            (MassSpecType::MALDITOF, 10.0),
+            (MassSpecType::DirectInfusion, 2.0),
+            (MassSpecType::IonMobility, 5.0),
+        ].into_iter().collect();
+
+        Self {
+            sigma_ppm,
+            default_sigma_ppm: 10.0,
+            offset_ppm: 0.0,
+            mass_weight: 0.5,
+        }
+    }
+}
+
+/// Mass accuracy assessment of one observed ion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassAccuracy {
+    /// Observed m/z
+    pub observed_mz: f64,
+
+    /// Theoretical m/z of the candidate
+    pub theoretical_mz: f64,
+
+    /// Signed mass error in ppm (after offset correction)
+    pub ppm_error: f64,
+
+    /// Standard deviation of the error distribution used
+    pub sigma_ppm: f64,
+
+    /// Likelihood of the error relative to a perfect match (0.0 - 1.0)
+    pub likelihood: f64,
+}
+
+impl MassAccuracyModel {
+    /// Set the error standard deviation for an MS type
+    pub fn with_sigma(mut self, ms_type: MassSpecType, sigma_ppm: f64) -> Self {
+        self.sigma_ppm.insert(ms_type, sigma_ppm);
+        self
+    }
+
+    /// Error standard deviation for an MS type
+    pub fn sigma_for(&self, ms_type: MassSpecType) -> f64 {
+        self.sigma_ppm.get(&ms_type).copied().unwrap_or(self.default_sigma_ppm)
+    }
+
+    /// Check that all widths and weights are usable
+    pub fn validate(&self) -> Result<()> {
+        if self.default_sigma_ppm <= 0.0 || self.sigma_ppm.values().any(|s| *s <= 0.0) {
+            return Err(anyhow!("Mass error standard deviations must be positive"));
+        }
+        if !(0.0..=1.0).contains(&self.mass_weight) {
+            return Err(anyhow!("Mass weight must be between 0.0 and 1.0, got {}", self.mass_weight));
+        }
+        Ok(())
+    }
+
+    /// Likelihood of a ppm error under the Gaussian error model, scaled so a zero error scores 1.0
+    pub fn likelihood(&self, ms_type: MassSpecType, ppm: f64) -> f64 {
+        let z = (ppm - self.offset_ppm) / self.sigma_for(ms_type);
+        (-0.5 * z * z).exp()
+    }
+
+    /// Assess an observed m/z against the theoretical value
+    pub fn assess(&self, ms_type: MassSpecType, observed_mz: f64, theoretical_mz: f64) -> MassAccuracy {
+        let raw = ppm_error(observed_mz, theoretical_mz);
+        let likelihood = self.likelihood(ms_type, raw);
+        debug!("Mass error {:.2} ppm for m/z {:.4} (likelihood {:.3})", raw, observed_mz, likelihood);
+
+        MassAccuracy {
+            observed_mz,
+            theoretical_mz,
+            ppm_error: raw - self.offset_ppm,
+            sigma_ppm: self.sigma_for(ms_type),
+            likelihood,
+        }
+    }
+
+    /// Combine mass likelihood with a fragmentation score as a weighted geometric mean
+    ///
+    /// A geometric mean lets a gross mass error veto a good spectral match.
+    pub fn combine(&self, likelihood: f64, fragmentation_score: f64) -> f64 {
+        let w = self.mass_weight;
+        (likelihood.clamp(0.0, 1.0).powf(w) * fragmentation_score.clamp(0.0, 1.0).powf(1.0 - w)).min(1.0)
+    }
+}
+
+/// Signed mass error in parts per million
+pub fn ppm_error(observed_mz: f64, theoretical_mz: f64) -> f64 {
+    (observed_mz - theoretical_mz) / theoretical_mz * 1e6
+}
+
+/// Theoretical m/z given in evidence metadata, if any
+pub fn theoretical_mz(metadata: &HashMap<String, serde_json::Value>) -> Option<f64> {
+    THEORETICAL_MZ_KEYS.iter().find_map(|key| metadata.get(*key)?.as_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mass_accuracy_likelihood() {
+        let model = MassAccuracyModel::default();
+        assert!((ppm_error(180.0636, 180.0634) - 1.1107).abs() < 1e-3);
+
+        // The same 6 ppm error is poor for an LC-MS/MS instrument but fine for GC-MS
+        let lc = model.assess(MassSpecType::LCMSMS, 180.06448, 180.0634);
+        let gc = model.assess(MassSpecType::GCMS, 180.06448, 180.0634);
+        assert!(lc.likelihood < 0.2 && gc.likelihood > 0.9);
+
+        assert!(model.combine(lc.likelihood, 0.9) < model.combine(gc.likelihood, 0.9));
+        assert_eq!(model.combine(1.0, 1.0), 1.0);
+    }
+}
@@ -7,8 +7,14 @@ use anyhow::{Result, Context, anyhow};
 use log::{info, debug, warn, error};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use ndarray::Array1;
 
+use crate::processing::chromatography::{self, ChromatographicPeak, ChromatographyOptions};
+use crate::processing::ion_mobility::{self, CcsMatchOptions, CcsPredictor};
+use crate::processing::mass_accuracy::{self, MassAccuracyModel};
+use crate::processing::units::{self, Dimension, Quantity, Unit};
+
 /// Initialize the mass spectrometry processing module
 pub fn initialize() -> Result<()> {
     info!("Initializing mass spectrometry processing module");
@@ -71,6 +77,14 @@ pub enum MassSpecContent {
         
         /// Retention times (optional)
         retention_times: Option<Vec<f64>>,
+
+        /// Unit of the retention times
+        #[serde(default = "units::default_time_unit")]
+        time_unit: Unit,
+        
+        /// Collision cross sections in Å² for ion mobility data (optional)
+        #[serde(default)]
+        ccs_values: Option<Vec<f64>>,
     },
     
     /// MS/MS spectrum data
@@ -86,12 +100,20 @@ pub enum MassSpecContent {
         
         /// Fragment intensities
         fragment_intensities: Vec<f64>,
+        
+        /// Precursor collision cross section in Å² (optional)
+        #[serde(default)]
+        precursor_ccs: Option<f64>,
     },
     
     /// Chromatogram data
     Chromatogram {
         /// Retention times
         retention_times: Vec<f64>,
+
+        /// Unit of the retention times
+        #[serde(default = "units::default_time_unit")]
+        time_unit: Unit,
         
         /// Intensities
         intensities: Vec<f64>,
@@ -148,11 +170,8 @@ pub struct MassSpecFinding {
 /// Options for mass spectrometry data processing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MassSpecProcessingOptions {
-    /// Mass tolerance in Da or ppm
-    pub mass_tolerance: f64,
-    
-    /// Whether mass tolerance is in ppm (true) or Da (false)
-    pub mass_tolerance_in_ppm: bool,
+    /// Mass tolerance, in ppm or Da
+    pub mass_tolerance: Quantity,
     
     /// Minimum intensity threshold
     pub min_intensity: f64,
@@ -160,18 +179,155 @@ pub struct MassSpecProcessingOptions {
     /// Signal-to-noise ratio threshold
     pub snr_threshold: f64,
     
-    /// Retention time tolerance in minutes
-    pub rt_tolerance: f64,
+    /// Retention time tolerance
+    pub rt_tolerance: Quantity,
+
+    /// Instrument-specific mass error model used to score mass accuracy
+    #[serde(default)]
+    pub mass_accuracy: MassAccuracyModel,
+
+    /// Options for matching measured CCS values against references
+    #[serde(default)]
+    pub ccs_matching: CcsMatchOptions,
+
+    /// Smoothing, baseline and peak fitting options for chromatograms
+    #[serde(default)]
+    pub chromatography: ChromatographyOptions,
+
+    /// Instrument profile these options were derived from, if any
+    #[serde(default)]
+    pub profile: Option<InstrumentProfile>,
 }
 
 impl Default for MassSpecProcessingOptions {
     fn default() -> Self {
         Self {
-            mass_tolerance: 10.0,
-            mass_tolerance_in_ppm: true,
+            mass_tolerance: Quantity::ppm(10.0),
             min_intensity: 1000.0,
             snr_threshold: 3.0,
-            rt_tolerance: 0.5,
+            rt_tolerance: Quantity::minutes(0.5),
+            mass_accuracy: MassAccuracyModel::default(),
+            ccs_matching: CcsMatchOptions::default(),
+            chromatography: ChromatographyOptions::default(),
+            profile: None,
+        }
+    }
+}
+
+impl MassSpecProcessingOptions {
+    /// Options tuned for an instrument profile
+    pub fn for_profile(profile: InstrumentProfile) -> Self {
+        let defaults = Self::default();
+        let mass_accuracy = MassAccuracyModel::default();
+
+        match profile {
+            InstrumentProfile::Orbitrap => Self {
+                mass_tolerance: Quantity::ppm(5.0),
+                min_intensity: 10_000.0,
+                rt_tolerance: Quantity::minutes(0.2),
+                mass_accuracy: MassAccuracyModel { default_sigma_ppm: 2.0, ..mass_accuracy }
+                    .with_sigma(MassSpecType::LCMSMS, 2.0)
+                    .with_sigma(MassSpecType::DirectInfusion, 1.5),
+                profile: Some(profile),
+                ..defaults
+            },
+            InstrumentProfile::QTOF => Self {
+                mass_tolerance: Quantity::ppm(10.0),
+                min_intensity: 500.0,
+                rt_tolerance: Quantity::minutes(0.3),
+                mass_accuracy: MassAccuracyModel { default_sigma_ppm: 5.0, ..mass_accuracy }
+                    .with_sigma(MassSpecType::LCMSMS, 5.0)
+                    .with_sigma(MassSpecType::IonMobility, 5.0),
+                profile: Some(profile),
+                ..defaults
+            },
+            InstrumentProfile::TOFMALDI => Self {
+                mass_tolerance: Quantity::ppm(50.0),
+                min_intensity: 100.0,
+                snr_threshold: 5.0,
+                mass_accuracy: MassAccuracyModel { default_sigma_ppm: 25.0, ..mass_accuracy }
+                    .with_sigma(MassSpecType::MALDITOF, 25.0),
+                profile: Some(profile),
+                ..defaults
+            },
+        }
+    }
+
+    /// Check that the options are usable
+    pub fn validate(&self) -> Result<()> {
+        if !matches!(self.mass_tolerance.dimension(), Dimension::Mass | Dimension::RelativeMass) {
+            return Err(anyhow!("Mass tolerance must be in ppm or Da, got {}", self.mass_tolerance));
+        }
+        if self.mass_tolerance.value <= 0.0 {
+            return Err(anyhow!("Mass tolerance must be positive, got {}", self.mass_tolerance));
+        }
+        if self.rt_tolerance.dimension() != Dimension::Time {
+            return Err(anyhow!("Retention time tolerance must be a time, got {}", self.rt_tolerance));
+        }
+        if self.min_intensity < 0.0 || self.snr_threshold < 0.0 || self.rt_tolerance.value < 0.0 {
+            return Err(anyhow!("Intensity, SNR and retention time thresholds must not be negative"));
+        }
+        self.mass_accuracy.validate()?;
+        if self.ccs_matching.tolerance_percent <= 0.0 || !(0.0..=1.0).contains(&self.ccs_matching.weight) {
+            return Err(anyhow!("CCS tolerance must be positive and its weight between 0.0 and 1.0"));
+        }
+        let chromatography = &self.chromatography;
+        if chromatography.smoothing_window >= 3
+            && (chromatography.smoothing_window.is_multiple_of(2) || chromatography.smoothing_window <= chromatography.smoothing_order) {
+            return Err(anyhow!("Smoothing window must be odd and larger than the smoothing order"));
+        }
+        Ok(())
+    }
+}
+
+/// Named instrument profiles with tuned processing defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InstrumentProfile {
+    /// Orbitrap high-resolution LC-MS
+    Orbitrap,
+
+    /// Quadrupole time-of-flight
+    QTOF,
+
+    /// MALDI time-of-flight
+    TOFMALDI,
+}
+
+impl InstrumentProfile {
+    /// All available profiles
+    pub const ALL: [InstrumentProfile; 3] = [
+        InstrumentProfile::Orbitrap,
+        InstrumentProfile::QTOF,
+        InstrumentProfile::TOFMALDI,
+    ];
+
+    /// Canonical profile name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstrumentProfile::Orbitrap => "orbitrap",
+            InstrumentProfile::QTOF => "q-tof",
+            InstrumentProfile::TOFMALDI => "tof-maldi",
+        }
+    }
+}
+
+impl std::fmt::Display for InstrumentProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for InstrumentProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let key: String = s.to_lowercase().chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        match key.as_str() {
+            "orbitrap" => Ok(InstrumentProfile::Orbitrap),
+            "qtof" => Ok(InstrumentProfile::QTOF),
+            "tofmaldi" | "malditof" => Ok(InstrumentProfile::TOFMALDI),
+            _ => Err(anyhow!("Unknown instrument profile: {} (expected one of: {})", s,
+                             Self::ALL.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", "))),
         }
     }
 }
@@ -180,6 +336,9 @@ impl Default for MassSpecProcessingOptions {
 pub struct MassSpecProcessor {
     /// Processing options
     options: MassSpecProcessingOptions,
+    
+    /// Source of reference CCS values for ion mobility data
+    ccs_reference: Option<Arc<dyn CcsPredictor>>,
 }
 
 impl MassSpecProcessor {
@@ -187,43 +346,76 @@ impl MassSpecProcessor {
     pub fn new() -> Self {
         Self {
             options: MassSpecProcessingOptions::default(),
+            ccs_reference: None,
         }
     }
     
     /// Create a new processor with the given options
     pub fn with_options(options: MassSpecProcessingOptions) -> Self {
-        Self { options }
+        Self { options, ccs_reference: None }
+    }
+    
+    /// Create a new processor using a named instrument profile
+    pub fn with_profile(profile: &str) -> Result<Self> {
+        let options = MassSpecProcessingOptions::for_profile(profile.parse()?);
+        options.validate()?;
+        Ok(Self::with_options(options))
+    }
+    
+    /// Processing options in use
+    pub fn options(&self) -> &MassSpecProcessingOptions {
+        &self.options
+    }
+    
+    /// Use the given table or model for reference CCS values
+    pub fn with_ccs_reference(mut self, reference: Arc<dyn CcsPredictor>) -> Self {
+        self.ccs_reference = Some(reference);
+        self
     }
     
     /// Process mass spectrometry data for a molecule
     pub fn process(&self, molecule_id: &str, data: &MassSpecData) -> Result<Vec<MassSpecResult>> {
         debug!("Processing mass spec data for molecule {}: {}", molecule_id, data.experiment_id);
         
-        match &data.data {
-            MassSpecContent::Peaks { mz_values, intensities, retention_times } => {
-                self.process_peaks(molecule_id, mz_values, intensities, retention_times.as_ref(), &data.metadata)
+        let mut results = match &data.data {
+            MassSpecContent::Peaks { mz_values, intensities, retention_times, time_unit, ccs_values } => {
+                let retention_times = retention_times.as_deref().map(|rts| (rts, *time_unit));
+                self.process_peaks(molecule_id, data.ms_type, mz_values, intensities, retention_times,
+                                   ccs_values.as_ref(), &data.metadata)
             },
-            MassSpecContent::MSMS { precursor_mz, precursor_charge, fragment_mz, fragment_intensities } => {
-                self.process_msms(molecule_id, *precursor_mz, *precursor_charge, 
-                                  fragment_mz, fragment_intensities, &data.metadata)
+            MassSpecContent::MSMS { precursor_mz, precursor_charge, fragment_mz, fragment_intensities, precursor_ccs } => {
+                self.process_msms(molecule_id, data.ms_type, *precursor_mz, *precursor_charge, 
+                                  fragment_mz, fragment_intensities, *precursor_ccs, &data.metadata)
             },
-            MassSpecContent::Chromatogram { retention_times, intensities, mz_channel } => {
-                self.process_chromatogram(molecule_id, retention_times, intensities, *mz_channel, &data.metadata)
+            MassSpecContent::Chromatogram { retention_times, time_unit, intensities, mz_channel } => {
+                self.process_chromatogram(molecule_id, (retention_times, *time_unit), intensities, *mz_channel, &data.metadata)
             },
             MassSpecContent::Other { format_description, data } => {
                 warn!("Processing custom mass spec data format: {}", format_description);
                 Err(anyhow!("Custom mass spec data format not supported: {}", format_description))
             },
+        }?;
+        
+        // Record which settings produced the results
+        let profile = self.options.profile.map(|p| p.to_string()).unwrap_or_else(|| "default".to_string());
+        let options = serde_json::to_value(&self.options)?;
+        for result in &mut results {
+            result.processing_metadata.insert("instrument_profile".to_string(), serde_json::json!(profile));
+            result.processing_metadata.insert("processing_options".to_string(), options.clone());
         }
+        
+        Ok(results)
     }
     
     /// Process peak list data
     fn process_peaks(
         &self,
         molecule_id: &str,
+        ms_type: MassSpecType,
         mz_values: &[f64],
         intensities: &[f64],
-        retention_times: Option<&Vec<f64>>,
+        retention_times: Option<(&[f64], Unit)>,
+        ccs_values: Option<&Vec<f64>>,
         metadata: &HashMap<String, serde_json::Value>,
     ) -> Result<Vec<MassSpecResult>> {
         debug!("Processing mass spec peak data with {} peaks", mz_values.len());
@@ -257,21 +449,18 @@ impl MassSpecProcessor {
         debug!("Found {} peaks with high SNR", high_snr_peaks.len());
         
         // Create findings for each high SNR peak
-        let findings = high_snr_peaks.iter()
+        let mut findings = high_snr_peaks.iter()
             .map(|&(idx, &mz, &intensity)| {
                 // Calculate score based on intensity
                 let max_intensity = intensities.iter().fold(0.0, |max, &i| max.max(i));
                 let normalized_intensity = intensity / max_intensity;
                 
                 // If we have retention time, include it
-                let rt_info = if let Some(rts) = retention_times {
-                    if idx < rts.len() {
-                        format!(", RT: {:.2} min", rts[idx])
-                    } else {
-                        String::new()
-                    }
-                } else {
-                    String::new()
+                let retention_time = retention_times
+                    .and_then(|(rts, unit)| rts.get(idx).map(|&rt| Quantity::new(rt, unit)));
+                let rt_info = match retention_time {
+                    Some(rt) => format!(", RT: {:.2} {}", rt.value, rt.unit),
+                    None => String::new(),
                 };
                 
                 MassSpecFinding {
@@ -282,7 +471,7 @@ impl MassSpecProcessor {
                     details: serde_json::json!({
                         "mz": mz,
                         "intensity": intensity,
-                        "retention_time": retention_times.and_then(|rts| rts.get(idx).copied()),
+                        "retention_time": retention_time,
                         "snr": intensity / noise_level,
                     }),
                 }
@@ -298,6 +487,28 @@ impl MassSpecProcessor {
             let avg_score = findings.iter().map(|f| f.score).sum::<f64>() / findings.len() as f64;
             (0.7 * avg_score + 0.3 * peak_count_factor).min(1.0)
         };
+
+        // Score the peak closest to the candidate's theoretical m/z
+        let closest_peak = mass_accuracy::theoretical_mz(metadata).and_then(|theoretical| {
+            high_snr_peaks.iter()
+                .min_by(|(_, a, _), (_, b, _)| (*a - theoretical).abs().partial_cmp(&(*b - theoretical).abs())
+                    .unwrap_or(std::cmp::Ordering::Equal))
+                .copied()
+        });
+        let confidence = match closest_peak {
+            Some((_, &mz, _)) => self.apply_mass_accuracy(ms_type, mz, metadata, confidence, &mut findings),
+            None => confidence,
+        };
+        
+        // Match the CCS of that peak, or of the most intense peak without a theoretical m/z
+        let ccs_peak = closest_peak.or_else(|| high_snr_peaks.iter()
+            .max_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .copied());
+        let observed_ccs = ccs_peak.and_then(|(idx, _, _)| ccs_values.and_then(|ccs| ccs.get(idx).copied()));
+        let confidence = match observed_ccs {
+            Some(ccs) => self.apply_ccs_match(molecule_id, ccs, metadata, confidence, &mut findings),
+            None => confidence,
+        };
         
         // Create the result
         let result = MassSpecResult {
@@ -315,10 +526,12 @@ impl MassSpecProcessor {
     fn process_msms(
         &self,
         molecule_id: &str,
+        ms_type: MassSpecType,
         precursor_mz: f64,
         precursor_charge: i32,
         fragment_mz: &[f64],
         fragment_intensities: &[f64],
+        precursor_ccs: Option<f64>,
         metadata: &HashMap<String, serde_json::Value>,
     ) -> Result<Vec<MassSpecResult>> {
         debug!("Processing MS/MS spectrum with precursor m/z {}", precursor_mz);
@@ -387,6 +600,11 @@ impl MassSpecProcessor {
             let fragment_count_factor = (top_fragments.len() as f64).min(10.0) / 10.0;
             (0.3 + 0.7 * fragment_count_factor).min(1.0)
         };
+        let confidence = self.apply_mass_accuracy(ms_type, precursor_mz, metadata, confidence, &mut findings);
+        let confidence = match precursor_ccs {
+            Some(ccs) => self.apply_ccs_match(molecule_id, ccs, metadata, confidence, &mut findings),
+            None => confidence,
+        };
         
         // Create the result
         let result = MassSpecResult {
@@ -400,11 +618,83 @@ impl MassSpecProcessor {
         Ok(vec![result])
     }
     
+    /// Fold the mass error of an observed ion into a confidence score
+    ///
+    /// Without a theoretical m/z in the metadata the confidence is returned
+    /// unchanged; otherwise a `mass_accuracy` finding is added.
+    fn apply_mass_accuracy(
+        &self,
+        ms_type: MassSpecType,
+        observed_mz: f64,
+        metadata: &HashMap<String, serde_json::Value>,
+        confidence: f64,
+        findings: &mut Vec<MassSpecFinding>,
+    ) -> f64 {
+        let theoretical_mz = match mass_accuracy::theoretical_mz(metadata) {
+            Some(mz) if mz > 0.0 => mz,
+            _ => return confidence,
+        };
+
+        let model = &self.options.mass_accuracy;
+        let accuracy = model.assess(ms_type, observed_mz, theoretical_mz);
+        // Validated options hold a ppm or Da tolerance, which converts at any positive m/z
+        let within_tolerance = self.options.mass_tolerance.matches_mz(observed_mz, theoretical_mz).unwrap_or(false);
+        let mut details = serde_json::to_value(&accuracy).unwrap_or_default();
+        if let Some(details) = details.as_object_mut() {
+            details.insert("within_tolerance".to_string(), serde_json::json!(within_tolerance));
+            details.insert("tolerance".to_string(), serde_json::json!(self.options.mass_tolerance));
+        }
+        findings.push(MassSpecFinding {
+            finding_type: "mass_accuracy".to_string(),
+            description: format!("Mass error {:.2} ppm against theoretical m/z {:.4} (sigma {:.1} ppm, {} tolerance {})",
+                                 accuracy.ppm_error, theoretical_mz, accuracy.sigma_ppm,
+                                 if within_tolerance { "within" } else { "outside" }, self.options.mass_tolerance),
+            score: accuracy.likelihood,
+            details,
+        });
+
+        model.combine(accuracy.likelihood, confidence)
+    }
+
+    /// Fold the match between a measured and a reference CCS into a confidence score
+    ///
+    /// Without a reference CCS for the molecule and adduct the confidence is
+    /// returned unchanged; otherwise a `ccs_match` finding is added.
+    fn apply_ccs_match(
+        &self,
+        molecule_id: &str,
+        observed_ccs: f64,
+        metadata: &HashMap<String, serde_json::Value>,
+        confidence: f64,
+        findings: &mut Vec<MassSpecFinding>,
+    ) -> f64 {
+        let adduct = ion_mobility::adduct(metadata);
+        let (reference, source) = match ion_mobility::reference_ccs(
+            molecule_id, adduct, metadata, self.ccs_reference.as_deref()) {
+            Some((ccs, source)) if ccs > 0.0 => (ccs, source),
+            _ => {
+                debug!("No reference CCS for {} {}", molecule_id, adduct);
+                return confidence;
+            }
+        };
+
+        let ccs_match = self.options.ccs_matching.score(observed_ccs, reference, adduct, &source);
+        findings.push(MassSpecFinding {
+            finding_type: "ccs_match".to_string(),
+            description: format!("CCS {:.1} Å² vs reference {:.1} Å² for {} ({:+.2}%)",
+                                 observed_ccs, reference, adduct, ccs_match.error_percent),
+            score: ccs_match.score,
+            details: serde_json::to_value(&ccs_match).unwrap_or_default(),
+        });
+
+        self.options.ccs_matching.combine(confidence, ccs_match.score)
+    }
+
     /// Process chromatogram data
     fn process_chromatogram(
         &self,
         molecule_id: &str,
-        retention_times: &[f64],
+        (retention_times, time_unit): (&[f64], Unit),
         intensities: &[f64],
         mz_channel: Option<f64>,
         metadata: &HashMap<String, serde_json::Value>,
@@ -415,36 +705,54 @@ impl MassSpecProcessor {
             return Err(anyhow!("Mismatch between retention times and intensities"));
         }
         
-        // Find chromatographic peaks (simple algorithm - local maxima)
-        let chrom_peaks = self.find_chromatographic_peaks(retention_times, intensities)?;
+        // Find chromatographic peaks, deconvolving overlapping ones
+        let chrom_peaks = self.find_chromatographic_peaks(retention_times, time_unit, intensities)?;
         debug!("Found {} chromatographic peaks", chrom_peaks.len());
+
+        // Areas are intensity times trace time units; score them in intensity-minutes
+        let minutes_per_unit = Quantity::new(1.0, time_unit).value_in(Unit::Minute)?;
         
         // Create findings for each chromatographic peak
         let findings = chrom_peaks.iter()
-            .map(|&(idx, height, area, fwhm)| {
+            .map(|peak| -> Result<MassSpecFinding> {
                 // Normalize score based on peak height and width
                 let max_intensity = intensities.iter().fold(0.0, |max, &i| max.max(i));
-                let normalized_height = height / max_intensity;
+                let normalized_height = peak.height / max_intensity;
                 
-                // Peak quality score combines height, area and width
-                let quality_score = normalized_height * 0.7 + (area / 1_000_000.0).min(1.0) * 0.2 
-                                    + (fwhm / 0.5).min(1.0) * 0.1;
+                // Peak quality score combines height, area and width, rewarding widths up to half a minute
+                let fwhm_minutes = peak.fwhm.value_in(Unit::Minute)?;
+                let quality_score = normalized_height * 0.7 + (peak.area * minutes_per_unit / 1_000_000.0).min(1.0) * 0.2 
+                                    + (fwhm_minutes / 0.5).min(1.0) * 0.1;
                 
-                MassSpecFinding {
+                // A poor fit means the peak shape is unreliable
+                let fit_quality = peak.fit.as_ref().map(|fit| fit.r_squared.clamp(0.0, 1.0)).unwrap_or(1.0);
+                let quality_score = quality_score * (0.5 + 0.5 * fit_quality);
+                
+                let deconvolved = if peak.group_size > 1 {
+                    format!(", deconvolved from {} overlapping peaks", peak.group_size)
+                } else {
+                    String::new()
+                };
+                
+                Ok(MassSpecFinding {
                     finding_type: "chromatographic_peak".to_string(),
-                    description: format!("Chromatographic peak at RT {:.2} min, height: {:.0e}, area: {:.0e}", 
-                                        retention_times[idx], height, area),
+                    description: format!("Chromatographic peak at RT {:.2} {}, height: {:.0e}, area: {:.0e}{}", 
+                                        peak.retention_time.value, peak.retention_time.unit,
+                                        peak.height, peak.area, deconvolved),
                     score: quality_score.min(1.0),
                     details: serde_json::json!({
-                        "retention_time": retention_times[idx],
-                        "height": height,
-                        "area": area,
-                        "fwhm": fwhm,
+                        "retention_time": peak.retention_time,
+                        "height": peak.height,
+                        "area": peak.area,
+                        "fwhm": peak.fwhm,
                         "mz_channel": mz_channel,
+                        "group": peak.group,
+                        "group_size": peak.group_size,
+                        "fit": peak.fit,
                     }),
-                }
+                })
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>>>()?;
         
         // Calculate overall confidence based on chromatographic peak quality
         let confidence = if findings.is_empty() {
@@ -468,53 +776,8 @@ impl MassSpecProcessor {
     }
     
     /// Find chromatographic peaks in the data
-    /// Returns vec of (peak_index, height, area, fwhm)
-    fn find_chromatographic_peaks(&self, times: &[f64], intensities: &[f64]) -> Result<Vec<(usize, f64, f64, f64)>> {
-        if times.is_empty() || intensities.is_empty() {
-            return Ok(Vec::new());
-        }
-        
-        let mut peaks = Vec::new();
-        
-        // Simple algorithm to find local maxima
-        for i in 1..intensities.len()-1 {
-            if intensities[i] > intensities[i-1] && intensities[i] > intensities[i+1] && 
-               intensities[i] >= self.options.min_intensity {
-                
-                // Found a local maximum
-                let peak_index = i;
-                let peak_height = intensities[i];
-                
-                // Estimate peak width (FWHM)
-                let half_height = peak_height / 2.0;
-                
-                // Find left boundary (first point below half height)
-                let mut left_idx = i;
-                while left_idx > 0 && intensities[left_idx] > half_height {
-                    left_idx -= 1;
-                }
-                
-                // Find right boundary (first point below half height)
-                let mut right_idx = i;
-                while right_idx < intensities.len() - 1 && intensities[right_idx] > half_height {
-                    right_idx += 1;
-                }
-                
-                // Calculate FWHM in time units
-                let fwhm = times[right_idx] - times[left_idx];
-                
-                // Estimate peak area by trapezoidal rule
-                let mut area = 0.0;
-                for j in left_idx..right_idx {
-                    let dt = times[j+1] - times[j];
-                    area += dt * (intensities[j] + intensities[j+1]) / 2.0;
-                }
-                
-                peaks.push((peak_index, peak_height, area, fwhm));
-            }
-        }
-        
-        Ok(peaks)
+    fn find_chromatographic_peaks(&self, times: &[f64], time_unit: Unit, intensities: &[f64]) -> Result<Vec<ChromatographicPeak>> {
+        chromatography::detect_peaks(times, time_unit, intensities, self.options.min_intensity, &self.options.chromatography)
     }
 }
 
@@ -530,11 +793,88 @@ mod tests {
         let times = vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
         let intensities = vec![1000.0, 2000.0, 5000.0, 12000.0, 20000.0, 12000.0, 5000.0, 2000.0, 1000.0, 500.0, 500.0];
         
-        let peaks = processor.find_chromatographic_peaks(&times, &intensities).unwrap();
+        let peaks = processor.find_chromatographic_peaks(&times, Unit::Minute, &intensities).unwrap();
         
         // Should find one peak at index 4 (time 4.0)
         assert_eq!(peaks.len(), 1);
-        assert_eq!(peaks[0].0, 4);
-        assert_eq!(peaks[0].1, 20000.0); // height
+        assert_eq!(peaks[0].index, 4);
+        assert_eq!(peaks[0].height, 20000.0); // height
+    }
+
+    #[test]
+    fn test_chromatogram_scoring_ignores_time_unit() {
+        let processor = MassSpecProcessor::new();
+        let minutes: Vec<f64> = (0..41).map(|i| i as f64 * 0.025).collect();
+        let intensities: Vec<f64> = minutes.iter()
+            .map(|&t| 500.0 + 20000.0 * (-0.5 * ((t - 0.5) / 0.05_f64).powi(2)).exp())
+            .collect();
+        let seconds: Vec<f64> = minutes.iter().map(|t| t * 60.0).collect();
+
+        let score = |times: &[f64], unit: Unit| {
+            let results = processor.process_chromatogram("caffeine", (times, unit), &intensities, None, &HashMap::new()).unwrap();
+            let peak = &results[0].findings[0];
+            assert_eq!(peak.details["retention_time"]["unit"], unit.symbol());
+            peak.score
+        };
+        assert!((score(&minutes, Unit::Minute) - score(&seconds, Unit::Second)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_msms_confidence_reflects_mass_error() {
+        let processor = MassSpecProcessor::new();
+        let fragments = vec![85.0284, 97.0284, 127.0390];
+        let intensities = vec![5000.0, 8000.0, 20000.0];
+        let confidence = |precursor_mz: f64| {
+            let metadata = [("theoretical_mz".to_string(), serde_json::json!(181.0707))].into_iter().collect();
+            let results = processor.process_msms("glucose", MassSpecType::LCMSMS, precursor_mz, 1,
+                                                 &fragments, &intensities, None, &metadata).unwrap();
+            assert!(results[0].findings.iter().any(|f| f.finding_type == "mass_accuracy"));
+            results[0].confidence
+        };
+
+        // 1 ppm off versus 20 ppm off
+        assert!(confidence(181.0709) > confidence(181.0743));
+    }
+
+    #[test]
+    fn test_msms_ccs_match() {
+        let mut table = ion_mobility::CcsTable::new("reference");
+        table.insert("glucose", "[M+H]+", 130.2);
+        let processor = MassSpecProcessor::new().with_ccs_reference(Arc::new(table));
+
+        let results = processor.process_msms("glucose", MassSpecType::IonMobility, 181.0707, 1,
+                                             &[127.0390], &[20000.0], Some(130.9), &HashMap::new()).unwrap();
+        let ccs = results[0].findings.iter().find(|f| f.finding_type == "ccs_match").unwrap();
+        assert!(ccs.score > 0.9);
+        assert_eq!(ccs.details["source"], "reference");
+    }
+
+    #[test]
+    fn test_instrument_profiles() {
+        assert_eq!("Q-TOF".parse::<InstrumentProfile>().unwrap(), InstrumentProfile::QTOF);
+        assert!("fticr".parse::<InstrumentProfile>().is_err());
+
+        for profile in InstrumentProfile::ALL {
+            MassSpecProcessingOptions::for_profile(profile).validate().unwrap();
+        }
+        let swapped = MassSpecProcessingOptions { rt_tolerance: Quantity::ppm(5.0), ..Default::default() };
+        assert!(swapped.validate().is_err());
+
+        let processor = MassSpecProcessor::with_profile("orbitrap").unwrap();
+        let data = MassSpecData {
+            ms_type: MassSpecType::LCMSMS,
+            experiment_id: "exp1".to_string(),
+            sample_id: "s1".to_string(),
+            data: MassSpecContent::MSMS {
+                precursor_mz: 181.0707,
+                precursor_charge: 1,
+                fragment_mz: vec![127.0390],
+                fragment_intensities: vec![20000.0],
+                precursor_ccs: None,
+            },
+            metadata: HashMap::new(),
+        };
+        let results = processor.process("glucose", &data).unwrap();
+        assert_eq!(results[0].processing_metadata["instrument_profile"], "orbitrap");
     }
 } 
\ No newline at end of file
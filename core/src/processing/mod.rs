@@ -7,16 +7,55 @@ use log::{info, warn, debug};
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 
+use crate::identity::MoleculeIdType;
+use formula::Formula;
+use crate::{EvidenceType, HegelError, MolecularEvidence};
+
 pub mod schema;
 pub mod neo4j;
 pub mod evidence;
+pub mod evidence_schema;
 pub mod genomics;
 pub mod mass_spec;
+pub mod chromatography;
+pub mod mass_accuracy;
+pub mod units;
+pub mod biotransform;
+pub mod formula;
+pub mod features;
+pub mod alignment;
+pub mod mztab;
+pub mod ion_mobility;
 pub mod rectifier;
+pub mod proposals;
+pub mod prompt_budget;
+pub mod time_budget;
 pub mod spectral;
 pub mod sequence;
 pub mod structural;
 pub mod fuzzy_integration;
+pub mod versioning;
+pub mod reevaluation;
+pub mod retention;
+pub mod pipeline;
+pub mod attribution;
+pub mod fingerprint;
+pub mod reliability;
+pub mod uncertainty;
+pub mod literature;
+pub mod profiles;
+pub mod anomaly;
+pub mod drift;
+pub mod qc;
+pub mod scoring_scripts;
+pub mod confidence_policy;
+pub mod batch_scoring;
+pub mod library;
+pub mod reference_library;
+pub mod properties;
+pub mod spill;
+pub mod results;
+pub mod targets;
 
 /// Initialize the processing module
 pub fn initialize() -> Result<()> {
@@ -26,18 +65,56 @@ pub fn initialize() -> Result<()> {
     schema::initialize()?;
     neo4j::initialize()?;
     evidence::initialize()?;
+    evidence_schema::initialize()?;
     genomics::initialize()?;
     mass_spec::initialize()?;
+    chromatography::initialize()?;
+    mass_accuracy::initialize()?;
+    units::initialize()?;
+    biotransform::initialize()?;
+    features::initialize()?;
+    alignment::initialize()?;
+    mztab::initialize()?;
+    ion_mobility::initialize()?;
     rectifier::initialize()?;
+    proposals::initialize()?;
+    prompt_budget::initialize()?;
+    time_budget::initialize()?;
+    versioning::initialize()?;
+    reevaluation::initialize()?;
+    retention::initialize()?;
+    pipeline::initialize()?;
+    attribution::initialize()?;
+    fingerprint::initialize()?;
+    reliability::initialize()?;
+    uncertainty::initialize()?;
+    literature::initialize()?;
+    profiles::initialize()?;
+    anomaly::initialize()?;
+    drift::initialize()?;
+    qc::initialize()?;
+    scoring_scripts::initialize()?;
+    confidence_policy::initialize()?;
+    batch_scoring::initialize()?;
+    library::initialize()?;
+    reference_library::initialize()?;
+    properties::initialize()?;
+    spill::initialize()?;
+    results::initialize()?;
+    targets::initialize()?;
     
     info!("Molecular processing module initialized successfully");
     Ok(())
 }
 
 /// Molecular structure representation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The one molecule type of the crate: its structure and identifiers, and
+/// the evidence gathered for its identification with the confidence derived
+/// from it. Graph nodes convert to and from it with `From`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Molecule {
-    /// Unique identifier for the molecule
+    /// Unique identifier for the molecule: its InChIKey when known, a structure hash otherwise
     pub id: String,
     
     /// SMILES representation of the molecule
@@ -53,33 +130,132 @@ pub struct Molecule {
     pub name: Option<String>,
     
     /// Optional molecular formula
-    pub formula: Option<String>,
+    pub formula: Option<Formula>,
     
     /// Optional molecular weight
     pub molecular_weight: Option<f64>,
     
     /// Additional properties and metadata
     pub properties: HashMap<String, serde_json::Value>,
+    
+    /// Identifiers the molecule is known by, keyed by identifier type
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub identifiers: HashMap<String, String>,
+    
+    /// Evidence gathered for the identification
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidences: Vec<MolecularEvidence>,
+    
+    /// Confidence in the identification (0.0 - 1.0), from `update_confidence`
+    #[serde(default)]
+    pub confidence_score: f64,
 }
 
 impl Molecule {
+    /// Create a named molecule of a known formula
+    pub fn new(id: String, name: String, formula: Formula) -> Self {
+        Molecule {
+            id,
+            name: Some(name),
+            molecular_weight: Some(formula.average_mass()),
+            formula: Some(formula),
+            ..Default::default()
+        }
+    }
+    
     /// Create a new molecule from a SMILES string
     pub fn from_smiles(smiles: &str) -> Result<Self> {
         // This would use RDKit or another library to parse and validate the SMILES
         // For now, just create a stub with minimal information
         
+        // Without a toolkit the InChIKey cannot be derived, so the ID is a structure
+        // hash until one is attached with `set_inchi_key`
         Ok(Molecule {
-            id: generate_id(smiles),
+            id: structure_hash(smiles.trim()),
             smiles: smiles.to_string(),
-            inchi: None,
-            inchi_key: None,
-            name: None,
-            formula: None,
-            molecular_weight: None,
-            properties: HashMap::new(),
+            ..Default::default()
         })
     }
     
+    /// Create a molecule from an identifier of the given type
+    ///
+    /// The identifier is validated and normalized first. Structural identifiers
+    /// also populate the matching field. Every identifier is kept in `identifiers`.
+    /// The ID is derived with `canonical_id`.
+    pub fn from_identifier(identifier: &str, id_type: &MoleculeIdType) -> Result<Self> {
+        let value = id_type.normalize(identifier)?;
+        if *id_type == MoleculeIdType::SMILES {
+            return Self::from_smiles(&value);
+        }
+        
+        let mut molecule = Molecule::default();
+        
+        match id_type {
+            MoleculeIdType::InChI => molecule.inchi = Some(value.clone()),
+            MoleculeIdType::InChIKey => molecule.inchi_key = Some(value.clone()),
+            MoleculeIdType::Formula => {
+                let formula: Formula = value.parse()?;
+                molecule.molecular_weight = Some(formula.average_mass());
+                molecule.formula = Some(formula);
+            }
+            MoleculeIdType::Name => molecule.name = Some(value.clone()),
+            _ => {}
+        }
+        molecule.identifiers.insert(id_type.to_string(), value);
+        molecule.id = molecule.canonical_id();
+        
+        Ok(molecule)
+    }
+    
+    /// Internal ID the molecule should have
+    ///
+    /// The canonical InChIKey when the molecule has one, so the same compound
+    /// gets the same ID however its SMILES was drawn. Otherwise a hash of the
+    /// best structure available (InChI, then SMILES), and failing that of the
+    /// identifier the molecule was created from.
+    pub fn canonical_id(&self) -> String {
+        if let Some(key) = self.inchi_key.as_deref()
+            .and_then(|key| MoleculeIdType::InChIKey.normalize(key).ok()) {
+            return key;
+        }
+        if let Some(inchi) = self.inchi.as_deref().filter(|inchi| !inchi.trim().is_empty()) {
+            return structure_hash(inchi.trim());
+        }
+        if !self.smiles.trim().is_empty() {
+            return structure_hash(self.smiles.trim());
+        }
+        let identifier = self.identifiers.iter()
+            .min()
+            .map(|(id_type, value)| format!("{}:{}", id_type, value));
+        match identifier {
+            Some(identifier) => structure_hash(&identifier),
+            None => self.id.clone(),
+        }
+    }
+    
+    /// Attach an InChIKey, making it the molecule's ID
+    pub fn set_inchi_key(&mut self, inchi_key: &str) -> Result<()> {
+        let key = MoleculeIdType::InChIKey.normalize(inchi_key)?;
+        self.inchi_key = Some(key.clone());
+        self.id = key;
+        Ok(())
+    }
+    
+    /// Name to show for the molecule: its name, or its ID when it has none
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
+    
+    /// Add a piece of evidence for the identification
+    pub fn add_evidence(&mut self, evidence: MolecularEvidence) {
+        self.evidences.push(evidence);
+    }
+    
+    /// Recompute the confidence score from the evidence
+    pub fn update_confidence(&mut self, calculator: &crate::ConfidenceCalculator) {
+        self.confidence_score = calculator.calculate_confidence(&self.evidences);
+    }
+    
     /// Validate the molecule structure
     pub fn validate(&self) -> Result<ValidationReport> {
         // This would use RDKit or another library to validate the molecular structure
@@ -117,11 +293,8 @@ impl Molecule {
     
     /// Calculate similarity to another molecule
     pub fn similarity(&self, other: &Molecule) -> Result<f64> {
-        // This would calculate Tanimoto similarity or another similarity measure
-        // For now, just return a random value
-        
-        let similarity = rand::random::<f64>();
-        Ok(similarity)
+        crate::graph::similarity::SimilarityRegistry::global()
+            .compute(crate::graph::similarity::DEFAULT_METRIC, self, other)
     }
 }
 
@@ -222,16 +395,14 @@ pub enum BondType {
     Aromatic,
 }
 
-/// Generate a unique ID for a molecule based on its SMILES
-fn generate_id(smiles: &str) -> String {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    
-    let mut hasher = DefaultHasher::new();
-    smiles.hash(&mut hasher);
-    let hash = hasher.finish();
-    
-    format!("mol-{:016x}", hash)
+/// Fallback molecule ID: a SHA-256 hash of a structure or identifier string
+///
+/// Stable across builds and platforms, so stored graphs keep resolving.
+pub fn structure_hash(structure: &str) -> String {
+    use sha2::{Digest, Sha256};
+    
+    let digest = Sha256::digest(structure.as_bytes());
+    format!("mol-{}", hex::encode(&digest[..8]))
 }
 
 /// Processes spectral data and generates evidence
@@ -239,13 +410,21 @@ pub fn process_spectral_data(
     spectral_data: &str,
     reference_data: &str,
 ) -> Result<MolecularEvidence, HegelError> {
-    let similarity = spectral::calculate_spectral_similarity(spectral_data, reference_data)?;
+    let spectral_match = spectral::calculate_spectral_match(
+        spectral_data,
+        reference_data,
+        &spectral::SpectralOptions::default(),
+    )?;
+    
+    // Keep the match diagnostics, not just the score
+    let value = serde_json::to_string(&spectral_match)
+        .map_err(|e| HegelError::ComputationError(format!("Error serializing spectral match: {}", e)))?;
     
     Ok(MolecularEvidence {
         source: "spectral_analysis".to_string(),
-        confidence: similarity,
+        confidence: spectral_match.similarity,
         data_type: EvidenceType::Spectral,
-        value: format!("Spectral similarity: {:.4}", similarity),
+        value,
     })
 }
 
@@ -0,0 +1,644 @@
+//! mzTab-M Export
+//!
+//! Writes feature-table identification results as an mzTab-M 2.0 file for
+//! metabolomics repositories such as MetaboLights. The metadata section
+//! declares one MS run and assay per sample, a single study variable over
+//! all of them, and the confidence measures used. Each feature becomes an
+//! SMF row, each candidate compound of a feature an SME row ranked by match
+//! score, and each feature's best candidate an SML row carrying the
+//! molecule's integrated confidence, its evidence types and an MSI
+//! identification level. Features without a candidate are reported as
+//! unknowns so that every SMF row is summarized.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+use crate::processing::biotransform::monoisotopic_mass;
+use crate::processing::evidence::{EvidenceType, IntegratedEvidence};
+use crate::processing::features::{Feature, FeatureCandidate, FeatureTable, Polarity, ADDUCTS, FEATURE_TABLE_SOURCE};
+use crate::processing::units::Unit;
+use crate::processing::Molecule;
+
+/// mzTab-M version written
+pub const MZTAB_VERSION: &str = "2.0.0-M";
+
+/// Database prefix of the molecule IDs in `database_identifier` columns
+pub const DATABASE_PREFIX: &str = "hegel";
+
+/// Initialize the mzTab-M export module
+pub fn initialize() -> Result<()> {
+    info!("Initializing mzTab-M export module");
+    info!("mzTab-M export module initialized successfully");
+    Ok(())
+}
+
+/// Identification level of the Metabolomics Standards Initiative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentificationLevel {
+    /// Level 1: matched against an authentic standard on two orthogonal properties
+    Identified,
+
+    /// Level 2: supported by spectral or structural evidence beyond the accurate mass
+    Putative,
+
+    /// Level 3: accurate mass only, placing the feature in a compound class at best
+    PutativeClass,
+
+    /// Level 4: no candidate identity
+    Unknown,
+}
+
+impl IdentificationLevel {
+    /// Code written to the `reliability` column
+    pub fn code(&self) -> u8 {
+        match self {
+            IdentificationLevel::Identified => 1,
+            IdentificationLevel::Putative => 2,
+            IdentificationLevel::PutativeClass => 3,
+            IdentificationLevel::Unknown => 4,
+        }
+    }
+
+    /// Level of a feature's candidate given the molecule's integrated evidence
+    ///
+    /// A candidate whose reference retention time, measured on the lab's
+    /// method, agrees with the feature has been compared to an authentic
+    /// standard on mass and retention time. Structural evidence, or mass spec
+    /// evidence from outside the feature table such as a spectral library
+    /// match, makes the identity putative; otherwise only the mass matched.
+    pub fn assess(candidate: &FeatureCandidate, integrated: Option<&IntegratedEvidence>) -> Self {
+        if candidate.rt_error.is_some() {
+            return IdentificationLevel::Identified;
+        }
+        let corroborated = integrated.is_some_and(|integrated| {
+            integrated.evidence_items.iter().any(|item| match item.evidence_type {
+                EvidenceType::Structural => true,
+                EvidenceType::MassSpec => !item.source.starts_with(FEATURE_TABLE_SOURCE),
+                _ => false,
+            })
+        });
+        if corroborated {
+            IdentificationLevel::Putative
+        } else {
+            IdentificationLevel::PutativeClass
+        }
+    }
+}
+
+impl fmt::Display for IdentificationLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdentificationLevel::Identified => write!(f, "identified"),
+            IdentificationLevel::Putative => write!(f, "putative"),
+            IdentificationLevel::PutativeClass => write!(f, "putative_class"),
+            IdentificationLevel::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Options for an mzTab-M export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MzTabOptions {
+    /// Value of `mzTab-ID`, such as a MetaboLights study accession
+    pub id: String,
+
+    /// Free-text description of the study
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Polarity of the runs, written as each run's scan polarity and the features' charge
+    pub polarity: Polarity,
+}
+
+impl MzTabOptions {
+    /// Options for the given `mzTab-ID` in positive mode
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            description: None,
+            polarity: Polarity::Positive,
+        }
+    }
+}
+
+/// Counts of the rows written to an mzTab-M file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MzTabSummary {
+    /// SML rows
+    pub small_molecules: usize,
+
+    /// SMF rows
+    pub features: usize,
+
+    /// SME rows
+    pub evidence: usize,
+
+    /// SML rows per identification level code
+    pub levels: BTreeMap<u8, usize>,
+}
+
+/// Feature-table results to be written as mzTab-M
+pub struct MzTabExport<'a> {
+    /// Feature table the results came from
+    table: &'a FeatureTable,
+
+    /// Candidates of each feature, in the table's feature order, best first
+    candidates: Vec<Vec<FeatureCandidate>>,
+
+    /// Compounds by ID, for names and structures
+    compounds: HashMap<&'a str, &'a Molecule>,
+
+    /// Integrated evidence by molecule ID
+    results: HashMap<String, IntegratedEvidence>,
+
+    /// Export options
+    options: MzTabOptions,
+}
+
+impl<'a> MzTabExport<'a> {
+    /// Export of a table whose features have the given candidates
+    pub fn new(table: &'a FeatureTable, candidates: Vec<Vec<FeatureCandidate>>, options: MzTabOptions) -> Result<Self> {
+        if candidates.len() != table.features.len() {
+            return Err(anyhow!("Got candidates for {} features but the table has {}", candidates.len(), table.features.len()));
+        }
+        if table.samples.is_empty() {
+            return Err(anyhow!("mzTab-M needs at least one assay, but the feature table has no samples"));
+        }
+        if options.id.trim().is_empty() {
+            return Err(anyhow!("mzTab-M needs an mzTab-ID"));
+        }
+        Ok(Self {
+            table,
+            candidates,
+            compounds: HashMap::new(),
+            results: HashMap::new(),
+            options,
+        })
+    }
+
+    /// Take candidate names, formulas and structures from these compounds
+    pub fn with_compounds(mut self, compounds: &'a [Molecule]) -> Self {
+        self.compounds = compounds.iter().map(|molecule| (molecule.id.as_str(), molecule)).collect();
+        self
+    }
+
+    /// Report the integrated confidence and evidence types of these molecules
+    pub fn with_results<I: IntoIterator<Item = IntegratedEvidence>>(mut self, results: I) -> Self {
+        self.results = results.into_iter().map(|result| (result.molecule_id.clone(), result)).collect();
+        self
+    }
+
+    /// Write the file
+    pub fn save(&self, path: &Path) -> Result<MzTabSummary> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create mzTab-M file: {}", path.display()))?;
+        let mut out = std::io::BufWriter::new(file);
+        let summary = self.write(&mut out)?;
+        out.flush()?;
+        info!("Wrote {} small molecules and {} features to {}", summary.small_molecules, summary.features, path.display());
+        Ok(summary)
+    }
+
+    /// Write the metadata, small molecule, feature and evidence sections
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<MzTabSummary> {
+        let mut summary = MzTabSummary::default();
+        self.write_metadata(out)?;
+
+        // SME rows are numbered first so that features and molecules can refer to them
+        let mut evidence_ids: Vec<Vec<usize>> = Vec::with_capacity(self.candidates.len());
+        let mut next_evidence = 1;
+        for candidates in &self.candidates {
+            evidence_ids.push((next_evidence..next_evidence + candidates.len()).collect());
+            next_evidence += candidates.len();
+        }
+
+        // Features grouped under the molecule of their best candidate; unmatched ones stand alone
+        let mut molecules: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        let mut unknowns = Vec::new();
+        for (index, candidates) in self.candidates.iter().enumerate() {
+            match candidates.first() {
+                Some(best) => molecules.entry(best.molecule_id.as_str()).or_default().push(index),
+                None => unknowns.push(index),
+            }
+        }
+
+        writeln!(out)?;
+        let mut header = vec![
+            "SMH", "SML_ID", "SMF_ID_REFS", "database_identifier", "chemical_formula", "smiles", "inchi",
+            "chemical_name", "uri", "theoretical_neutral_mass", "adduct_ions", "reliability",
+            "best_id_confidence_measure", "best_id_confidence_value",
+        ].into_iter().map(str::to_string).collect::<Vec<_>>();
+        header.extend(self.assay_columns("abundance_assay"));
+        header.push("abundance_study_variable[1]".to_string());
+        header.push("abundance_variation_study_variable[1]".to_string());
+        header.push("opt_global_evidence_types".to_string());
+        write_row(out, &header)?;
+
+        let mut sml_id = 0;
+        for (molecule_id, features) in &molecules {
+            sml_id += 1;
+            let best: Vec<&FeatureCandidate> = features.iter().map(|i| &self.candidates[*i][0]).collect();
+            let integrated = self.results.get(*molecule_id);
+            let level = best.iter()
+                .map(|candidate| IdentificationLevel::assess(candidate, integrated))
+                .min()
+                .unwrap_or(IdentificationLevel::Unknown);
+            *summary.levels.entry(level.code()).or_default() += 1;
+
+            let compound = self.compounds.get(molecule_id).copied();
+            let adducts: BTreeSet<String> = best.iter().map(|c| mztab_adduct(&c.adduct)).collect();
+            let (measure, confidence) = match integrated {
+                Some(integrated) => ("[,, hegel aggregate confidence, ]", integrated.aggregate_confidence),
+                None => ("[,, hegel feature match score, ]", best.iter().map(|c| c.score).fold(0.0, f64::max)),
+            };
+            let evidence_types: BTreeSet<String> = integrated
+                .map(|integrated| integrated.evidence_items.iter().map(|item| item.evidence_type.to_string()).collect())
+                .unwrap_or_default();
+
+            let mut row = vec![
+                "SML".to_string(),
+                sml_id.to_string(),
+                join_ids(features.iter().map(|i| i + 1)),
+                format!("{}:{}", DATABASE_PREFIX, molecule_id),
+                optional(compound.and_then(|m| m.formula.as_ref()).map(|f| f.to_string())),
+                optional(compound.map(|m| m.smiles.clone()).filter(|s| !s.is_empty())),
+                optional(compound.and_then(|m| m.inchi.clone())),
+                optional(best[0].name.clone().or_else(|| compound.and_then(|m| m.name.clone()))),
+                "null".to_string(),
+                optional(neutral_mass(best[0], compound).map(|mass| format!("{:.6}", mass))),
+                adducts.into_iter().collect::<Vec<_>>().join("|"),
+                level.code().to_string(),
+                measure.to_string(),
+                format!("{:.4}", confidence),
+            ];
+            let abundances = self.summed_abundances(features);
+            row.extend(abundances.iter().map(|a| optional(a.map(|a| a.to_string()))));
+            row.push(optional(mean(&abundances).map(|a| a.to_string())));
+            row.push(optional(coefficient_of_variation(&abundances).map(|cv| format!("{:.2}", cv))));
+            row.push(if evidence_types.is_empty() {
+                "null".to_string()
+            } else {
+                evidence_types.into_iter().collect::<Vec<_>>().join("|")
+            });
+            write_row(out, &row)?;
+        }
+        for index in &unknowns {
+            sml_id += 1;
+            *summary.levels.entry(IdentificationLevel::Unknown.code()).or_default() += 1;
+            let mut row = vec![
+                "SML".to_string(),
+                sml_id.to_string(),
+                (index + 1).to_string(),
+            ];
+            row.extend(std::iter::repeat_n("null".to_string(), 8));
+            row.push(IdentificationLevel::Unknown.code().to_string());
+            row.extend(["null".to_string(), "null".to_string()]);
+            let abundances = self.summed_abundances(&[*index]);
+            row.extend(abundances.iter().map(|a| optional(a.map(|a| a.to_string()))));
+            row.push(optional(mean(&abundances).map(|a| a.to_string())));
+            row.push(optional(coefficient_of_variation(&abundances).map(|cv| format!("{:.2}", cv))));
+            row.push("null".to_string());
+            write_row(out, &row)?;
+        }
+        summary.small_molecules = sml_id;
+
+        writeln!(out)?;
+        let mut header = vec![
+            "SFH", "SMF_ID", "SME_ID_REFS", "SME_ID_REF_ambiguity_code", "adduct_ion", "isotopomer",
+            "exp_mass_to_charge", "charge", "retention_time_in_seconds", "retention_time_in_seconds_start",
+            "retention_time_in_seconds_end",
+        ].into_iter().map(str::to_string).collect::<Vec<_>>();
+        header.extend(self.assay_columns("abundance_assay"));
+        write_row(out, &header)?;
+
+        for (index, (feature, candidates)) in self.table.features.iter().zip(&self.candidates).enumerate() {
+            let mut row = vec![
+                "SMF".to_string(),
+                (index + 1).to_string(),
+                if candidates.is_empty() { "null".to_string() } else { join_ids(evidence_ids[index].iter().copied()) },
+                // 1 marks an ambiguous identification: several candidate compounds for one feature
+                if candidates.len() > 1 { "1".to_string() } else { "null".to_string() },
+                optional(candidates.first().map(|c| mztab_adduct(&c.adduct))),
+                "null".to_string(),
+                format!("{:.6}", feature.mz),
+                self.charge().to_string(),
+                format!("{:.2}", feature.retention_time.value_in(Unit::Second)?),
+                "null".to_string(),
+                "null".to_string(),
+            ];
+            row.extend(feature.intensities.iter().map(|i| optional(i.map(|i| i.to_string()))));
+            write_row(out, &row)?;
+        }
+        summary.features = self.table.features.len();
+
+        writeln!(out)?;
+        let header: Vec<String> = [
+            "SEH", "SME_ID", "evidence_input_id", "database_identifier", "chemical_formula", "smiles", "inchi",
+            "chemical_name", "uri", "derivatized_form", "adduct_ion", "exp_mass_to_charge", "charge",
+            "theoretical_mass_to_charge", "spectra_ref", "identification_method", "ms_level",
+            "id_confidence_measure[1]", "id_confidence_measure[2]", "rank",
+        ].into_iter().map(str::to_string).collect();
+        write_row(out, &header)?;
+
+        for (index, (feature, candidates)) in self.table.features.iter().zip(&self.candidates).enumerate() {
+            for (rank, candidate) in candidates.iter().enumerate() {
+                let compound = self.compounds.get(candidate.molecule_id.as_str()).copied();
+                let method = if candidate.rt_error.is_some() {
+                    "[,, accurate mass and retention time match, ]"
+                } else {
+                    "[,, accurate mass match, ]"
+                };
+                let row = vec![
+                    "SME".to_string(),
+                    evidence_ids[index][rank].to_string(),
+                    feature.id.clone(),
+                    format!("{}:{}", DATABASE_PREFIX, candidate.molecule_id),
+                    optional(compound.and_then(|m| m.formula.as_ref()).map(|f| f.to_string())),
+                    optional(compound.map(|m| m.smiles.clone()).filter(|s| !s.is_empty())),
+                    optional(compound.and_then(|m| m.inchi.clone())),
+                    optional(candidate.name.clone().or_else(|| compound.and_then(|m| m.name.clone()))),
+                    "null".to_string(),
+                    "null".to_string(),
+                    mztab_adduct(&candidate.adduct),
+                    format!("{:.6}", feature.mz),
+                    self.charge().to_string(),
+                    format!("{:.6}", candidate.theoretical_mz),
+                    // Features are detected in MS1 across runs, so no single spectrum is referenced
+                    "null".to_string(),
+                    method.to_string(),
+                    "[MS, MS:1000511, ms level, 1]".to_string(),
+                    format!("{:.4}", candidate.score),
+                    optional(self.results.get(&candidate.molecule_id).map(|r| format!("{:.4}", r.aggregate_confidence))),
+                    (rank + 1).to_string(),
+                ];
+                write_row(out, &row)?;
+                summary.evidence += 1;
+            }
+        }
+
+        debug!("mzTab-M export: {} SML, {} SMF, {} SME rows", summary.small_molecules, summary.features, summary.evidence);
+        Ok(summary)
+    }
+
+    /// Write the `MTD` section
+    fn write_metadata<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut metadata: Vec<(String, String)> = vec![
+            ("mzTab-version".to_string(), MZTAB_VERSION.to_string()),
+            ("mzTab-ID".to_string(), self.options.id.clone()),
+        ];
+        if let Some(description) = &self.options.description {
+            metadata.push(("description".to_string(), description.clone()));
+        }
+        metadata.push((
+            "software[1]".to_string(),
+            format!("[MS, MS:1000799, custom unreleased software tool, hegel {}]", env!("CARGO_PKG_VERSION")),
+        ));
+        metadata.push((
+            "quantification_method".to_string(),
+            "[MS, MS:1001834, LC-MS label-free quantitation analysis, ]".to_string(),
+        ));
+
+        let polarity = match self.options.polarity {
+            Polarity::Positive => "[MS, MS:1000130, positive scan, ]",
+            Polarity::Negative => "[MS, MS:1000129, negative scan, ]",
+        };
+        for (index, sample) in self.table.samples.iter().enumerate() {
+            let run = index + 1;
+            metadata.push((format!("ms_run[{}]-location", run), format!("file:///{}", sample.replace(' ', "%20"))));
+            metadata.push((format!("ms_run[{}]-scan_polarity[1]", run), polarity.to_string()));
+        }
+        for (index, sample) in self.table.samples.iter().enumerate() {
+            let assay = index + 1;
+            metadata.push((format!("assay[{}]", assay), sample.clone()));
+            metadata.push((format!("assay[{}]-ms_run_ref", assay), format!("ms_run[{}]", assay)));
+        }
+        metadata.push(("study_variable[1]".to_string(), "all samples".to_string()));
+        metadata.push((
+            "study_variable[1]-assay_refs".to_string(),
+            (1..=self.table.samples.len()).map(|a| format!("assay[{}]", a)).collect::<Vec<_>>().join("|"),
+        ));
+        metadata.push((
+            "study_variable[1]-description".to_string(),
+            format!("Every sample of the {} feature table", self.table.format),
+        ));
+
+        let vocabularies = [
+            ("MS", "PSI-MS controlled vocabulary", "4.1.0", "https://raw.githubusercontent.com/HUPO-PSI/psi-ms-CV/master/psi-ms.obo"),
+            ("PRIDE", "PRIDE PRoteomics IDEntifications (PRIDE) database controlled vocabulary", "16:10:2023 11:38", "https://www.ebi.ac.uk/ols/ontologies/pride"),
+        ];
+        for (position, (label, name, version, uri)) in vocabularies.into_iter().enumerate() {
+            let index = position + 1;
+            metadata.push((format!("cv[{}]-label", index), label.to_string()));
+            metadata.push((format!("cv[{}]-full_name", index), name.to_string()));
+            metadata.push((format!("cv[{}]-version", index), version.to_string()));
+            metadata.push((format!("cv[{}]-uri", index), uri.to_string()));
+        }
+
+        metadata.push(("database[1]".to_string(), "[,, hegel compound store, ]".to_string()));
+        metadata.push(("database[1]-prefix".to_string(), DATABASE_PREFIX.to_string()));
+        metadata.push(("database[1]-version".to_string(), "Unknown".to_string()));
+        metadata.push(("database[1]-uri".to_string(), "null".to_string()));
+
+        let unit = "[PRIDE, PRIDE:0000330, Arbitrary quantification unit, ]";
+        metadata.push(("small_molecule-quantification_unit".to_string(), unit.to_string()));
+        metadata.push(("small_molecule_feature-quantification_unit".to_string(), unit.to_string()));
+        metadata.push((
+            "small_molecule-identification_reliability".to_string(),
+            "[MS, MS:1002896, compound identification confidence level, ]".to_string(),
+        ));
+        metadata.push(("id_confidence_measure[1]".to_string(), "[,, hegel feature match score, ]".to_string()));
+        metadata.push(("id_confidence_measure[2]".to_string(), "[,, hegel aggregate confidence, ]".to_string()));
+
+        for (key, value) in metadata {
+            write_row(out, &["MTD".to_string(), key, value])?;
+        }
+        Ok(())
+    }
+
+    /// `prefix[1]` to `prefix[n]`, one column per assay
+    fn assay_columns(&self, prefix: &str) -> Vec<String> {
+        (1..=self.table.samples.len()).map(|assay| format!("{}[{}]", prefix, assay)).collect()
+    }
+
+    /// Charge of every feature; the matched adducts are singly charged
+    fn charge(&self) -> i32 {
+        match self.options.polarity {
+            Polarity::Positive => 1,
+            Polarity::Negative => -1,
+        }
+    }
+
+    /// Intensity per assay summed over features, `None` where no feature was detected
+    fn summed_abundances(&self, features: &[usize]) -> Vec<Option<f64>> {
+        (0..self.table.samples.len())
+            .map(|assay| {
+                let detected: Vec<f64> = features.iter()
+                    .filter_map(|i| feature_intensity(&self.table.features[*i], assay))
+                    .collect();
+                if detected.is_empty() { None } else { Some(detected.iter().sum()) }
+            })
+            .collect()
+    }
+}
+
+/// Intensity of a feature in one assay
+fn feature_intensity(feature: &Feature, assay: usize) -> Option<f64> {
+    feature.intensities.get(assay).copied().flatten()
+}
+
+/// Write one tab-separated line, replacing characters that would break the format
+fn write_row<W: Write>(out: &mut W, cells: &[String]) -> Result<()> {
+    let cells: Vec<String> = cells.iter()
+        .map(|cell| cell.replace(['\t', '\n', '\r'], " "))
+        .collect();
+    writeln!(out, "{}", cells.join("\t"))?;
+    Ok(())
+}
+
+/// Value of an optional cell, `null` when absent
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "null".to_string())
+}
+
+/// `|`-separated list of row IDs
+fn join_ids<I: Iterator<Item = usize>>(ids: I) -> String {
+    ids.map(|id| id.to_string()).collect::<Vec<_>>().join("|")
+}
+
+/// Adduct in mzTab-M notation, which states the charge: `[M+H]+` becomes `[M+H]1+`
+pub fn mztab_adduct(adduct: &str) -> String {
+    match adduct.strip_suffix(['+', '-']) {
+        Some(ion) if ion.ends_with(']') => format!("{}1{}", ion, &adduct[ion.len()..]),
+        _ => adduct.to_string(),
+    }
+}
+
+/// Neutral mass of a candidate, from the compound or from its adduct's m/z
+fn neutral_mass(candidate: &FeatureCandidate, compound: Option<&Molecule>) -> Option<f64> {
+    if let Some(mass) = compound.and_then(|m| monoisotopic_mass(m).ok()) {
+        return Some(mass);
+    }
+    // A labeled standard's m/z is at the labeled mass, not the parent's
+    if candidate.labeled_standard.is_some() {
+        return None;
+    }
+    ADDUCTS.iter()
+        .find(|adduct| adduct.name == candidate.adduct)
+        .map(|adduct| candidate.theoretical_mz - adduct.mass_shift)
+}
+
+/// Mean of the detected abundances
+fn mean(values: &[Option<f64>]) -> Option<f64> {
+    let detected: Vec<f64> = values.iter().flatten().copied().collect();
+    if detected.is_empty() {
+        return None;
+    }
+    Some(detected.iter().sum::<f64>() / detected.len() as f64)
+}
+
+/// Coefficient of variation of the detected abundances, in percent
+fn coefficient_of_variation(values: &[Option<f64>]) -> Option<f64> {
+    let detected: Vec<f64> = values.iter().flatten().copied().collect();
+    if detected.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    if mean == 0.0 {
+        return None;
+    }
+    let variance = detected.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (detected.len() - 1) as f64;
+    Some(100.0 * variance.sqrt() / mean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::Evidence;
+
+    fn table() -> FeatureTable {
+        let csv = "row ID,row m/z,row retention time,a.mzML Peak area,b.mzML Peak area\n\
+                   1,181.070665,2.0,1000,3000\n\
+                   2,250.5,5.0,200,\n";
+        FeatureTable::parse(csv, None).unwrap()
+    }
+
+    fn candidate(feature_id: &str, molecule_id: &str, rt_error: Option<f64>, score: f64) -> FeatureCandidate {
+        FeatureCandidate {
+            feature_id: feature_id.to_string(),
+            molecule_id: molecule_id.to_string(),
+            labeled_standard: None,
+            name: Some(molecule_id.to_string()),
+            adduct: "[M+H]+".to_string(),
+            theoretical_mz: 181.070665,
+            ppm_error: 0.0,
+            rt_error,
+            score,
+        }
+    }
+
+    #[test]
+    fn writes_sections_with_required_metadata() {
+        let table = table();
+        let candidates = vec![
+            vec![candidate("1", "glucose", Some(0.05), 0.9), candidate("1", "fructose", None, 0.7)],
+            Vec::new(),
+        ];
+        let export = MzTabExport::new(&table, candidates, MzTabOptions::new("MTBLS0001")).unwrap();
+        let mut out = Vec::new();
+        let summary = export.write(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.starts_with("MTD\tmzTab-version\t2.0.0-M\nMTD\tmzTab-ID\tMTBLS0001\n"));
+        for key in ["assay[2]-ms_run_ref", "study_variable[1]-assay_refs", "id_confidence_measure[1]",
+                    "small_molecule-quantification_unit", "database[1]-prefix", "cv[2]-label"] {
+            assert!(text.contains(&format!("MTD\t{}\t", key)), "missing {}", key);
+        }
+        assert_eq!((summary.small_molecules, summary.features, summary.evidence), (2, 2, 2));
+        assert_eq!(summary.levels.get(&1), Some(&1));
+        assert_eq!(summary.levels.get(&4), Some(&1));
+
+        let lines: Vec<&str> = text.lines().collect();
+        let sml = lines.iter().find(|l| l.starts_with("SML\t1\t")).unwrap();
+        let header = lines.iter().find(|l| l.starts_with("SMH\t")).unwrap();
+        assert_eq!(sml.split('\t').count(), header.split('\t').count());
+        assert!(sml.contains("\thegel:glucose\t") && sml.contains("\t[M+H]1+\t1\t"));
+        let smf = lines.iter().find(|l| l.starts_with("SMF\t1\t")).unwrap();
+        assert!(smf.starts_with("SMF\t1\t1|2\t1\t[M+H]1+\tnull\t181.070665\t1\t120.00\t"));
+        assert!(lines.iter().any(|l| l.starts_with("SME\t2\t1\thegel:fructose\t") && l.ends_with("\t2")));
+    }
+
+    #[test]
+    fn assesses_identification_levels() {
+        let mass_only = candidate("1", "glucose", None, 0.8);
+        assert_eq!(IdentificationLevel::assess(&candidate("1", "glucose", Some(0.1), 0.8), None), IdentificationLevel::Identified);
+        assert_eq!(IdentificationLevel::assess(&mass_only, None), IdentificationLevel::PutativeClass);
+
+        let library_match = Evidence {
+            id: "ms2".to_string(),
+            molecule_id: "glucose".to_string(),
+            evidence_type: EvidenceType::MassSpec,
+            source: "mona".to_string(),
+            confidence: 0.8,
+            data: serde_json::Value::Null,
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        };
+        let integrated = IntegratedEvidence {
+            molecule_id: "glucose".to_string(),
+            evidence_items: vec![library_match],
+            aggregate_confidence: 0.8,
+            conflicts: Vec::new(),
+            integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        };
+        assert_eq!(IdentificationLevel::assess(&mass_only, Some(&integrated)), IdentificationLevel::Putative);
+        assert_eq!(mztab_adduct("[M-H]-"), "[M-H]1-");
+    }
+}
@@ -10,6 +10,7 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use super::Molecule;
+use super::formula::Formula;
 
 /// Database configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,9 +118,10 @@ impl Neo4jClient {
                 inchi: Some("InChI=1S/C6H6/c1-2-4-6-5-3-1/h1-6H".to_string()),
                 inchi_key: Some("UHOVQNZJYSORNB-UHFFFAOYSA-N".to_string()),
                 name: Some("Benzene".to_string()),
-                formula: Some("C6H6".to_string()),
+                formula: "C6H6".parse().ok(),
                 molecular_weight: Some(78.11),
                 properties: HashMap::new(),
+                ..Default::default()
             };
             
             debug!("Molecule {} retrieved from Neo4j database", id);
@@ -148,9 +150,10 @@ impl Neo4jClient {
                     inchi: Some("InChI=1S/C7H8/c1-7-5-3-2-4-6-7/h2-6H,1H3".to_string()),
                     inchi_key: Some("YXFVVABEGXRONW-UHFFFAOYSA-N".to_string()),
                     name: Some("Toluene".to_string()),
-                    formula: Some("C7H8".to_string()),
+                    formula: "C7H8".parse().ok(),
                     molecular_weight: Some(92.14),
                     properties: HashMap::new(),
+                    ..Default::default()
                 },
                 0.85
             ),
@@ -161,9 +164,10 @@ impl Neo4jClient {
                     inchi: Some("InChI=1S/C6H6O/c7-6-4-2-1-3-5-6/h1-5,7H".to_string()),
                     inchi_key: Some("ISWSIDIOOBJBQZ-UHFFFAOYSA-N".to_string()),
                     name: Some("Phenol".to_string()),
-                    formula: Some("C6H6O".to_string()),
+                    formula: "C6H6O".parse().ok(),
                     molecular_weight: Some(94.11),
                     properties: HashMap::new(),
+                    ..Default::default()
                 },
                 0.75
             ),
@@ -251,7 +255,7 @@ pub struct NetworkNode {
     pub name: Option<String>,
     
     /// Optional molecular formula
-    pub formula: Option<String>,
+    pub formula: Option<Formula>,
     
     /// Additional properties and metadata
     pub properties: HashMap<String, serde_json::Value>,
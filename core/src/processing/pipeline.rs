@@ -0,0 +1,400 @@
+//! Identity Pipeline Module
+//!
+//! This module ties evidence processing together into a single identity pipeline
+//! and provides what-if analysis of how individual evidence drives the result.
+
+use anyhow::Result;
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::cancellation::{self, CancellationToken, Cancelled};
+use crate::plugins::EvidenceGeneratorRegistry;
+use crate::processing::attribution::{self, ConfidenceDecomposition};
+use crate::processing::confidence_policy::ConfidencePolicy;
+use crate::processing::evidence::{
+    Evidence, EvidenceProcessingOptions, EvidenceProcessor, IntegratedEvidence,
+};
+use crate::processing::fingerprint::PipelineFingerprint;
+use crate::processing::rectifier::EvidenceRectifier;
+use crate::processing::reliability::ReliabilityTracker;
+use crate::processing::spill::{MemoryBudget, SpillBuffer};
+use crate::processing::uncertainty::{self, MonteCarloConfig, UncertaintySummary};
+
+/// Initialize the identity pipeline module
+pub fn initialize() -> Result<()> {
+    info!("Initializing identity pipeline module");
+    info!("Identity pipeline module initialized successfully");
+    Ok(())
+}
+
+/// How evidence is grouped when held out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AblationMode {
+    /// Hold out one evidence item at a time
+    Item,
+
+    /// Hold out all evidence from one source at a time
+    Source,
+}
+
+/// Influence of one held-out group of evidence on the posterior confidence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceInfluence {
+    /// Evidence ID or source name that was held out
+    pub key: String,
+
+    /// IDs of the evidence items that were held out
+    pub held_out: Vec<String>,
+
+    /// Posterior confidence without the held-out evidence
+    pub confidence_without: f64,
+
+    /// Baseline confidence minus confidence without the evidence
+    ///
+    /// Positive values mean the evidence supports the conclusion.
+    pub influence: f64,
+}
+
+/// Result of a what-if ablation analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AblationReport {
+    /// Molecule the evidence relates to
+    pub molecule_id: String,
+
+    /// How evidence was grouped
+    pub mode: AblationMode,
+
+    /// Posterior confidence with all evidence included
+    pub baseline_confidence: f64,
+
+    /// Influence of each held-out group, strongest first
+    pub influences: Vec<EvidenceInfluence>,
+}
+
+/// End-to-end pipeline from raw evidence to an identity conclusion
+pub struct IdentityPipeline {
+    /// Evidence processor used for integration
+    processor: EvidenceProcessor,
+    
+    /// Optional rectifier applied after integration
+    rectifier: Option<EvidenceRectifier>,
+
+    /// Optional generators whose evidence is added before integration
+    generators: Option<Arc<EvidenceGeneratorRegistry>>,
+}
+
+impl IdentityPipeline {
+    /// Create a new pipeline with default options
+    pub fn new() -> Self {
+        Self::with_options(EvidenceProcessingOptions::default())
+    }
+
+    /// Create a new pipeline with the given evidence processing options
+    pub fn with_options(options: EvidenceProcessingOptions) -> Self {
+        Self {
+            processor: EvidenceProcessor::new(options),
+            rectifier: None,
+            generators: None,
+        }
+    }
+    
+    /// Create a pipeline bounded by the confidence policy named by `HEGEL_CONFIDENCE_POLICY`
+    /// and weighting sources by the reliability saved in `HEGEL_RELIABILITY_FILE`
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::with_options(EvidenceProcessingOptions {
+            confidence_policy: ConfidencePolicy::from_env()?,
+            source_weights: ReliabilityTracker::from_env()?.learned_weights(),
+            ..Default::default()
+        }))
+    }
+    
+    /// Rectify integrated evidence before drawing a conclusion
+    pub fn with_rectifier(mut self, rectifier: EvidenceRectifier) -> Self {
+        self.rectifier = Some(rectifier);
+        self
+    }
+
+    /// Add the evidence of registered generators, such as startup plugins, before integration
+    pub fn with_generators(mut self, generators: Arc<EvidenceGeneratorRegistry>) -> Self {
+        self.generators = Some(generators);
+        self
+    }
+
+    /// Get the underlying evidence processor
+    pub fn processor(&self) -> &EvidenceProcessor {
+        &self.processor
+    }
+
+    /// Fingerprint of the processor, rectifier and generators making up the pipeline
+    pub fn fingerprint(&self) -> Result<PipelineFingerprint> {
+        let mut fingerprint = self.processor.fingerprint()?;
+        if let Some(rectifier) = &self.rectifier {
+            fingerprint = fingerprint.with_parameters("rectification", rectifier.options())?;
+        }
+        if let Some(generators) = self.generators.as_ref().filter(|g| !g.is_empty()) {
+            fingerprint = fingerprint.with_component("generators", &generators.names().join(","));
+        }
+        Ok(fingerprint)
+    }
+
+    /// Run the full pipeline for a molecule
+    pub async fn run(&self, molecule_id: &str, mut evidence: Vec<Evidence>) -> Result<IntegratedEvidence> {
+        if let Some(generators) = self.generators.as_ref().filter(|g| !g.is_empty()) {
+            let generated = generators.generate(molecule_id, &evidence);
+            debug!("Generators added {} evidence items for {}", generated.len(), molecule_id);
+            evidence.extend(generated);
+        }
+        let mut integrated = self.processor.process_evidence(molecule_id, evidence).await?;
+        integrated.pipeline_fingerprint = Some(self.fingerprint()?);
+        Ok(integrated)
+    }
+
+    /// Run the pipeline for many molecules, keeping results within a memory budget
+    ///
+    /// The batch is consumed lazily, and results beyond the budget are spilled
+    /// to disk and streamed back when the returned buffer is iterated.
+    /// Cancelling `cancel` stops the batch before the next molecule.
+    pub async fn run_batch<I>(&self, batch: I, budget: &MemoryBudget, cancel: &CancellationToken) -> Result<SpillBuffer<IntegratedEvidence>>
+    where
+        I: IntoIterator<Item = (String, Vec<Evidence>)>,
+    {
+        let mut results = SpillBuffer::new(budget.clone());
+        for (molecule_id, evidence) in batch {
+            if cancel.is_cancelled() {
+                warn!("Batch cancelled after integrating {} molecules", results.len());
+                return Err(Cancelled::new("Batch integration").into());
+            }
+            results.push(self.run(&molecule_id, evidence).await?)?;
+        }
+        let stats = results.stats();
+        info!("Integrated {} molecules ({} results spilled to disk in {} files, {} bytes)",
+              results.len(), stats.items_spilled, stats.spill_files, stats.bytes_spilled);
+        Ok(results)
+    }
+
+    /// Integrate (and, if configured, rectify) evidence into a final confidence
+    ///
+    /// A cancelled rectification fails with `Cancelled` rather than concluding
+    /// from partly rectified evidence.
+    pub async fn conclude(&self, molecule_id: &str, evidence: &[Evidence], cancel: &CancellationToken) -> Result<f64> {
+        cancellation::check(cancel, "Identity conclusion")?;
+        let rectifier = match &self.rectifier {
+            Some(rectifier) => rectifier,
+            None => return self.posterior_confidence(evidence),
+        };
+        
+        let integrated = self.processor.process_evidence(molecule_id, evidence.to_vec()).await?;
+        // Only the proposed confidences are needed; they are applied to `evidence` below
+        let rectified = rectifier.rectify(integrated, true, cancel).await?;
+        if rectified.cancelled {
+            return Err(Cancelled::new("Identity conclusion").into());
+        }
+        
+        let adjusted: Vec<Evidence> = evidence.iter()
+            .map(|ev| {
+                let mut ev = ev.clone();
+                if let Some(r) = rectified.rectified_evidence.iter().find(|r| r.original_id == ev.id) {
+                    ev.confidence = r.rectified_confidence;
+                }
+                ev
+            })
+            .collect();
+        
+        self.posterior_confidence(&adjusted)
+    }
+    
+    /// Propagate evidence uncertainty through the pipeline by Monte Carlo sampling
+    pub async fn monte_carlo(
+        &self,
+        molecule_id: &str,
+        evidence: &[Evidence],
+        config: &MonteCarloConfig,
+        cancel: &CancellationToken,
+    ) -> Result<UncertaintySummary> {
+        debug!("Running {} Monte Carlo samples for molecule {}", config.samples, molecule_id);
+        
+        let (mut rng, seed) = uncertainty::rng_for(config);
+        let mut samples = Vec::with_capacity(config.samples);
+        
+        for _ in 0..config.samples {
+            let sampled = uncertainty::sample_evidence(evidence, &mut rng);
+            samples.push(self.conclude(molecule_id, &sampled, cancel).await?);
+        }
+        
+        Ok(UncertaintySummary::from_samples(samples, config.credible_level)?.with_seed(seed))
+    }
+    
+    /// Posterior confidence for a set of evidence
+    pub fn posterior_confidence(&self, evidence: &[Evidence]) -> Result<f64> {
+        self.processor.aggregate_confidence(evidence)
+    }
+
+    /// Recompute the posterior with each evidence item or source held out
+    pub fn ablate_evidence(
+        &self,
+        molecule_id: &str,
+        evidence: &[Evidence],
+        mode: AblationMode,
+    ) -> Result<AblationReport> {
+        debug!("Running {:?} ablation over {} evidence items for molecule {}",
+               mode, evidence.len(), molecule_id);
+
+        let baseline_confidence = self.posterior_confidence(evidence)?;
+
+        // Group evidence IDs by the key being held out
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for ev in evidence {
+            let key = match mode {
+                AblationMode::Item => ev.id.clone(),
+                AblationMode::Source => ev.source.clone(),
+            };
+            groups.entry(key).or_default().push(ev.id.clone());
+        }
+
+        let mut influences = Vec::with_capacity(groups.len());
+        for (key, held_out) in groups {
+            let remaining: Vec<Evidence> = evidence.iter()
+                .filter(|ev| !held_out.contains(&ev.id))
+                .cloned()
+                .collect();
+
+            let confidence_without = self.posterior_confidence(&remaining)?;
+            influences.push(EvidenceInfluence {
+                key,
+                held_out,
+                confidence_without,
+                influence: baseline_confidence - confidence_without,
+            });
+        }
+
+        influences.sort_by(|a, b| {
+            b.influence.abs().partial_cmp(&a.influence.abs()).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        Ok(AblationReport {
+            molecule_id: molecule_id.to_string(),
+            mode,
+            baseline_confidence,
+            influences,
+        })
+    }
+
+    /// Break the posterior confidence down into per-item or per-source contributions
+    ///
+    /// Unlike the influences of an ablation, the contributions add up to the
+    /// posterior confidence.
+    pub fn decompose_confidence(
+        &self,
+        molecule_id: &str,
+        evidence: &[Evidence],
+        mode: AblationMode,
+    ) -> Result<ConfidenceDecomposition> {
+        debug!("Decomposing confidence of molecule {} over {} evidence items", molecule_id, evidence.len());
+
+        let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (idx, ev) in evidence.iter().enumerate() {
+            let key = match mode {
+                AblationMode::Item => ev.id.clone(),
+                AblationMode::Source => ev.source.clone(),
+            };
+            groups.entry(key).or_default().push(idx);
+        }
+        let members: Vec<Vec<usize>> = groups.values().cloned().collect();
+        let contributors = groups.into_iter()
+            .map(|(key, items)| (key, items.iter().map(|idx| evidence[*idx].id.clone()).collect()))
+            .collect();
+
+        attribution::decompose(contributors, |included| {
+            let subset: Vec<Evidence> = included.iter()
+                .flat_map(|group| members[*group].iter().map(|idx| evidence[*idx].clone()))
+                .collect();
+            self.posterior_confidence(&subset)
+        })
+    }
+}
+
+impl Default for IdentityPipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+    use std::collections::HashMap;
+
+    fn evidence(id: &str, source: &str, confidence: f64) -> Evidence {
+        Evidence {
+            id: id.to_string(),
+            molecule_id: "mol-1".to_string(),
+            evidence_type: EvidenceType::Literature,
+            source: source.to_string(),
+            confidence,
+            data: serde_json::Value::Null,
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_ablate_evidence() {
+        let pipeline = IdentityPipeline::new();
+        let items = vec![
+            evidence("ev-1", "hmdb", 0.9),
+            evidence("ev-2", "hmdb", 0.9),
+            evidence("ev-3", "pubchem", 0.6),
+        ];
+
+        let report = pipeline.ablate_evidence("mol-1", &items, AblationMode::Item).unwrap();
+        assert_eq!(report.influences.len(), 3);
+        assert!((report.baseline_confidence - 0.8).abs() < 1e-9);
+        // Removing the weakest item raises the average the most
+        assert_eq!(report.influences[0].key, "ev-3");
+        assert!(report.influences[0].influence < 0.0);
+
+        let report = pipeline.ablate_evidence("mol-1", &items, AblationMode::Source).unwrap();
+        assert_eq!(report.influences.len(), 2);
+    }
+
+    #[test]
+    fn test_decompose_confidence() {
+        let pipeline = IdentityPipeline::new();
+        let items = vec![
+            evidence("ev-1", "hmdb", 0.9),
+            evidence("ev-2", "hmdb", 0.9),
+            evidence("ev-3", "pubchem", 0.6),
+        ];
+
+        let decomposition = pipeline.decompose_confidence("mol-1", &items, AblationMode::Source).unwrap();
+        assert_eq!(decomposition.contributions.len(), 2);
+        assert!((decomposition.attributed() - decomposition.total).abs() < 1e-9);
+        assert_eq!(decomposition.contributions[0].key, "hmdb");
+        assert_eq!(decomposition.contributions[0].evidence_ids, vec!["ev-1", "ev-2"]);
+    }
+    
+    #[tokio::test]
+    async fn test_monte_carlo() {
+        let pipeline = IdentityPipeline::new();
+        let items = vec![evidence("ev-1", "hmdb", 0.8), evidence("ev-2", "pubchem", 0.7)];
+        let config = MonteCarloConfig { samples: 200, seed: Some(7), ..Default::default() };
+        
+        let cancel = CancellationToken::new();
+        let summary = pipeline.monte_carlo("mol-1", &items, &config, &cancel).await.unwrap();
+        assert_eq!(summary.samples, 200);
+        assert!(summary.variance > 0.0);
+        assert!(summary.lower_bound <= summary.mean && summary.mean <= summary.upper_bound);
+        assert_eq!(summary.seed, Some(7));
+        
+        let again = pipeline.monte_carlo("mol-1", &items, &config, &cancel).await.unwrap();
+        assert_eq!(again.mean, summary.mean);
+        
+        cancel.cancel();
+        let error = pipeline.monte_carlo("mol-1", &items, &config, &cancel).await.unwrap_err();
+        assert!(cancellation::is_cancelled(&error));
+    }
+}
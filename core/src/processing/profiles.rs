@@ -0,0 +1,481 @@
+//! Evidence Profile Clustering
+//!
+//! Groups molecules by how they were identified rather than by what they
+//! look like. Each molecule's evidence is summarised as a profile of which
+//! sources and evidence types support it and how confidently; molecules with
+//! similar profiles are clustered with k-medoids or average-linkage
+//! hierarchical clustering. A tight cluster resting on one source is a
+//! candidate systematic bias: if that instrument or database is wrong, the
+//! whole group is wrong together.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::processing::evidence::Evidence;
+
+/// Prefix of profile features recording a source's confidence
+const SOURCE_FEATURE: &str = "source:";
+
+/// Prefix of profile features recording an evidence type's confidence
+const TYPE_FEATURE: &str = "type:";
+
+/// Initialize the evidence profile clustering module
+pub fn initialize() -> Result<()> {
+    info!("Initializing evidence profile clustering module");
+    info!("Evidence profile clustering module initialized successfully");
+    Ok(())
+}
+
+/// Summary of the evidence behind one molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceProfile {
+    /// Molecule the evidence relates to
+    pub molecule_id: String,
+
+    /// Highest confidence per source (`source:<name>`) and mean confidence
+    /// per evidence type (`type:<name>`)
+    pub features: BTreeMap<String, f64>,
+}
+
+impl EvidenceProfile {
+    /// Build a molecule's profile from its evidence items
+    pub fn from_evidence(molecule_id: &str, evidence: &[&Evidence]) -> Self {
+        let mut features = BTreeMap::new();
+        let mut by_type: HashMap<String, Vec<f64>> = HashMap::new();
+        for item in evidence {
+            let confidence = item.confidence.clamp(0.0, 1.0);
+            let source = features.entry(format!("{}{}", SOURCE_FEATURE, item.source)).or_insert(0.0);
+            *source = f64::max(*source, confidence);
+            by_type.entry(item.evidence_type.to_string()).or_default().push(confidence);
+        }
+        for (evidence_type, confidences) in by_type {
+            let mean = confidences.iter().sum::<f64>() / confidences.len() as f64;
+            features.insert(format!("{}{}", TYPE_FEATURE, evidence_type), mean);
+        }
+
+        Self {
+            molecule_id: molecule_id.to_string(),
+            features,
+        }
+    }
+
+    /// Profiles of every molecule in a set of evidence, ordered by molecule ID
+    pub fn from_evidence_set(evidence: &[Evidence]) -> Vec<Self> {
+        let mut by_molecule: BTreeMap<&str, Vec<&Evidence>> = BTreeMap::new();
+        for item in evidence {
+            by_molecule.entry(item.molecule_id.as_str()).or_default().push(item);
+        }
+        by_molecule.into_iter()
+            .map(|(molecule_id, items)| Self::from_evidence(molecule_id, &items))
+            .collect()
+    }
+
+    /// Sources supporting the molecule with their highest confidence
+    pub fn sources(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.features.iter().filter_map(|(key, value)| Some((key.strip_prefix(SOURCE_FEATURE)?, *value)))
+    }
+
+    /// Source carrying at least half of the molecule's summed source confidence
+    pub fn dominant_source(&self) -> Option<&str> {
+        let total: f64 = self.sources().map(|(_, confidence)| confidence).sum();
+        self.sources()
+            .find(|(_, confidence)| total > 0.0 && *confidence >= 0.5 * total)
+            .map(|(source, _)| source)
+    }
+
+    /// Root-mean-square difference over the union of both profiles' features
+    /// (0.0 - 1.0); a feature missing from one profile counts as zero
+    pub fn distance(&self, other: &EvidenceProfile) -> f64 {
+        let mut sum = 0.0;
+        let mut count = 0;
+        for (key, value) in &self.features {
+            let diff = value - other.features.get(key).copied().unwrap_or(0.0);
+            sum += diff * diff;
+            count += 1;
+        }
+        for (key, value) in &other.features {
+            if !self.features.contains_key(key) {
+                sum += value * value;
+                count += 1;
+            }
+        }
+        if count == 0 { 0.0 } else { (sum / count as f64).sqrt() }
+    }
+}
+
+/// Clustering algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClusterMethod {
+    /// Partitioning around medoids
+    KMedoids,
+    /// Agglomerative clustering with average linkage
+    Hierarchical,
+}
+
+impl std::str::FromStr for ClusterMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "k-medoids" | "kmedoids" => Ok(ClusterMethod::KMedoids),
+            "hierarchical" => Ok(ClusterMethod::Hierarchical),
+            other => Err(anyhow!("Unknown clustering method: {}", other)),
+        }
+    }
+}
+
+/// Group of molecules with similar evidence profiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileCluster {
+    /// Index of the cluster, largest first
+    pub id: usize,
+
+    /// Molecules in the cluster
+    pub members: Vec<String>,
+
+    /// Member whose profile is most central to the cluster
+    pub medoid: String,
+
+    /// Mean silhouette of the members (-1.0 - 1.0)
+    pub silhouette: f64,
+
+    /// Share of members supported by each source, most common first
+    pub source_shares: Vec<(String, f64)>,
+}
+
+/// Cluster whose members mostly rest on one source, so an error in that
+/// source would misidentify them all together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BiasCandidate {
+    /// Cluster concerned
+    pub cluster: usize,
+
+    /// Source shared by the cluster
+    pub source: String,
+
+    /// Share of the cluster's members for which the source is dominant
+    pub share: f64,
+
+    /// Number of molecules in the cluster
+    pub molecules: usize,
+}
+
+/// Result of clustering evidence profiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusteringReport {
+    /// Algorithm used
+    pub method: ClusterMethod,
+
+    /// Clusters, largest first
+    pub clusters: Vec<ProfileCluster>,
+
+    /// Mean silhouette over all molecules (-1.0 - 1.0)
+    pub silhouette: f64,
+
+    /// Clusters that may share a systematic bias
+    pub bias_candidates: Vec<BiasCandidate>,
+}
+
+/// Clusterer of evidence profiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileClusterer {
+    /// Algorithm to use
+    pub method: ClusterMethod,
+
+    /// Share of a cluster a source must dominate to flag a possible bias
+    pub min_bias_share: f64,
+
+    /// Smallest cluster worth flagging
+    pub min_bias_size: usize,
+}
+
+impl Default for ProfileClusterer {
+    fn default() -> Self {
+        Self {
+            method: ClusterMethod::KMedoids,
+            min_bias_share: 0.8,
+            min_bias_size: 3,
+        }
+    }
+}
+
+impl ProfileClusterer {
+    /// Use a different algorithm
+    pub fn with_method(mut self, method: ClusterMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Set when a shared source is flagged as a possible bias
+    pub fn with_bias_thresholds(mut self, min_share: f64, min_size: usize) -> Self {
+        self.min_bias_share = min_share.clamp(0.0, 1.0);
+        self.min_bias_size = min_size;
+        self
+    }
+
+    /// Cluster profiles into `k` groups
+    pub fn cluster(&self, profiles: &[EvidenceProfile], k: usize) -> Result<ClusteringReport> {
+        if k == 0 || k > profiles.len() {
+            return Err(anyhow!("Cannot form {} clusters from {} molecules", k, profiles.len()));
+        }
+        let distances: Vec<Vec<f64>> = profiles.iter()
+            .map(|a| profiles.iter().map(|b| a.distance(b)).collect())
+            .collect();
+        let labels = match self.method {
+            ClusterMethod::KMedoids => k_medoids(&distances, k),
+            ClusterMethod::Hierarchical => average_linkage(&distances, k),
+        };
+        Ok(self.report(profiles, &distances, &labels, k))
+    }
+
+    /// Cluster with the number of groups (2 to `max_k`) that gives the best
+    /// mean silhouette
+    pub fn cluster_auto(&self, profiles: &[EvidenceProfile], max_k: usize) -> Result<ClusteringReport> {
+        let max_k = max_k.min(profiles.len().saturating_sub(1));
+        if max_k < 2 {
+            return self.cluster(profiles, 1);
+        }
+        let mut best: Option<ClusteringReport> = None;
+        for k in 2..=max_k {
+            let report = self.cluster(profiles, k)?;
+            debug!("{} clusters: silhouette {:.3}", k, report.silhouette);
+            if best.as_ref().is_none_or(|b| report.silhouette > b.silhouette) {
+                best = Some(report);
+            }
+        }
+        best.ok_or_else(|| anyhow!("No clustering found"))
+    }
+
+    fn report(&self, profiles: &[EvidenceProfile], distances: &[Vec<f64>], labels: &[usize], k: usize) -> ClusteringReport {
+        let scores = silhouettes(distances, labels, k);
+        let mut groups: Vec<Vec<usize>> = vec![Vec::new(); k];
+        for (i, label) in labels.iter().enumerate() {
+            groups[*label].push(i);
+        }
+        groups.retain(|g| !g.is_empty());
+        groups.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+
+        let mut clusters = Vec::new();
+        let mut bias_candidates = Vec::new();
+        for (id, members) in groups.iter().enumerate() {
+            let shares = |counts: HashMap<&str, usize>| -> Vec<(String, f64)> {
+                let mut shares: Vec<(String, f64)> = counts.into_iter()
+                    .map(|(source, count)| (source.to_string(), count as f64 / members.len() as f64))
+                    .collect();
+                shares.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                shares
+            };
+            let mut support: HashMap<&str, usize> = HashMap::new();
+            let mut dominance: HashMap<&str, usize> = HashMap::new();
+            for i in members {
+                for (source, _) in profiles[*i].sources() {
+                    *support.entry(source).or_insert(0) += 1;
+                }
+                if let Some(source) = profiles[*i].dominant_source() {
+                    *dominance.entry(source).or_insert(0) += 1;
+                }
+            }
+            let source_shares = shares(support);
+
+            if members.len() >= self.min_bias_size {
+                if let Some((source, share)) = shares(dominance).into_iter().next().filter(|(_, s)| *s >= self.min_bias_share) {
+                    bias_candidates.push(BiasCandidate {
+                        cluster: id,
+                        source,
+                        share,
+                        molecules: members.len(),
+                    });
+                }
+            }
+
+            clusters.push(ProfileCluster {
+                id,
+                members: members.iter().map(|i| profiles[*i].molecule_id.clone()).collect(),
+                medoid: profiles[medoid(distances, members)].molecule_id.clone(),
+                silhouette: members.iter().map(|i| scores[*i]).sum::<f64>() / members.len() as f64,
+                source_shares,
+            });
+        }
+
+        let silhouette = if scores.is_empty() { 0.0 } else { scores.iter().sum::<f64>() / scores.len() as f64 };
+        info!("Clustered {} molecules into {} groups (silhouette {:.3}, {} possible biases)",
+              profiles.len(), clusters.len(), silhouette, bias_candidates.len());
+        ClusteringReport {
+            method: self.method,
+            clusters,
+            silhouette,
+            bias_candidates,
+        }
+    }
+}
+
+/// Member with the smallest total distance to the rest of the group
+fn medoid(distances: &[Vec<f64>], members: &[usize]) -> usize {
+    members.iter()
+        .copied()
+        .min_by(|&a, &b| {
+            let cost = |m: usize| members.iter().map(|j| distances[m][*j]).sum::<f64>();
+            cost(a).total_cmp(&cost(b))
+        })
+        .unwrap_or(0)
+}
+
+/// Partitioning around medoids with a greedy build, returning cluster labels
+fn k_medoids(distances: &[Vec<f64>], k: usize) -> Vec<usize> {
+    let n = distances.len();
+    let all: Vec<usize> = (0..n).collect();
+    let cost = |medoids: &[usize]| -> f64 {
+        (0..n).map(|j| medoids.iter().map(|m| distances[*m][j]).fold(f64::INFINITY, f64::min)).sum()
+    };
+
+    // Build: start from the most central point, then add whichever point lowers the cost most
+    let mut medoids = vec![medoid(distances, &all)];
+    while medoids.len() < k {
+        let next = all.iter()
+            .copied()
+            .filter(|c| !medoids.contains(c))
+            .min_by(|&a, &b| {
+                let with = |c: usize| cost(&[medoids.as_slice(), &[c]].concat());
+                with(a).total_cmp(&with(b))
+            })
+            .unwrap_or(0);
+        medoids.push(next);
+    }
+
+    let assign = |medoids: &[usize]| -> Vec<usize> {
+        (0..n)
+            .map(|j| {
+                (0..medoids.len())
+                    .min_by(|&a, &b| distances[medoids[a]][j].total_cmp(&distances[medoids[b]][j]))
+                    .unwrap_or(0)
+            })
+            .collect()
+    };
+
+    // Alternate assignment and medoid updates until the medoids settle
+    let mut labels = assign(&medoids);
+    for _ in 0..100 {
+        let updated: Vec<usize> = (0..k)
+            .map(|c| {
+                let members: Vec<usize> = (0..n).filter(|j| labels[*j] == c).collect();
+                if members.is_empty() { medoids[c] } else { medoid(distances, &members) }
+            })
+            .collect();
+        if updated == medoids {
+            break;
+        }
+        medoids = updated;
+        labels = assign(&medoids);
+    }
+    labels
+}
+
+/// Agglomerative clustering merging the closest pair by mean distance until
+/// `k` clusters remain, returning cluster labels
+fn average_linkage(distances: &[Vec<f64>], k: usize) -> Vec<usize> {
+    let mut clusters: Vec<Vec<usize>> = (0..distances.len()).map(|i| vec![i]).collect();
+    let linkage = |a: &[usize], b: &[usize]| -> f64 {
+        let total: f64 = a.iter().flat_map(|i| b.iter().map(move |j| distances[*i][*j])).sum();
+        total / (a.len() * b.len()) as f64
+    };
+
+    while clusters.len() > k {
+        let mut closest = (0, 1, f64::INFINITY);
+        for a in 0..clusters.len() {
+            for b in a + 1..clusters.len() {
+                let d = linkage(&clusters[a], &clusters[b]);
+                if d < closest.2 {
+                    closest = (a, b, d);
+                }
+            }
+        }
+        let merged = clusters.remove(closest.1);
+        clusters[closest.0].extend(merged);
+    }
+
+    let mut labels = vec![0; distances.len()];
+    for (label, members) in clusters.iter().enumerate() {
+        for i in members {
+            labels[*i] = label;
+        }
+    }
+    labels
+}
+
+/// Silhouette of every point; points alone in their cluster score zero
+fn silhouettes(distances: &[Vec<f64>], labels: &[usize], k: usize) -> Vec<f64> {
+    let n = distances.len();
+    (0..n)
+        .map(|i| {
+            let mut totals = vec![0.0; k];
+            let mut counts = vec![0usize; k];
+            for j in (0..n).filter(|j| *j != i) {
+                totals[labels[j]] += distances[i][j];
+                counts[labels[j]] += 1;
+            }
+            if counts[labels[i]] == 0 {
+                return 0.0;
+            }
+            let within = totals[labels[i]] / counts[labels[i]] as f64;
+            let nearest = (0..k)
+                .filter(|c| *c != labels[i] && counts[*c] > 0)
+                .map(|c| totals[c] / counts[c] as f64)
+                .fold(f64::INFINITY, f64::min);
+            if !nearest.is_finite() {
+                return 0.0;
+            }
+            let scale = within.max(nearest);
+            if scale > 0.0 { (nearest - within) / scale } else { 0.0 }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    fn evidence(molecule_id: &str, source: &str, evidence_type: EvidenceType, confidence: f64) -> Evidence {
+        Evidence {
+            id: format!("{}-{}", molecule_id, source),
+            molecule_id: molecule_id.to_string(),
+            evidence_type,
+            source: source.to_string(),
+            confidence,
+            data: serde_json::Value::Null,
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_clusters_flag_shared_source() {
+        // A batch identified by one instrument alone, and molecules with broad support
+        let mut items = Vec::new();
+        for m in ["m1", "m2", "m3", "m4"] {
+            items.push(evidence(m, "qtof-2", EvidenceType::MassSpec, 0.95));
+        }
+        for m in ["m5", "m6", "m7"] {
+            items.push(evidence(m, "orbitrap", EvidenceType::MassSpec, 0.7));
+            items.push(evidence(m, "kegg", EvidenceType::Pathway, 0.8));
+            items.push(evidence(m, "pubmed", EvidenceType::Literature, 0.6));
+        }
+        let profiles = EvidenceProfile::from_evidence_set(&items);
+        assert_eq!(profiles.len(), 7);
+
+        for method in [ClusterMethod::KMedoids, ClusterMethod::Hierarchical] {
+            let report = ProfileClusterer::default().with_method(method).cluster(&profiles, 2).unwrap();
+            assert_eq!(report.clusters[0].members, vec!["m1", "m2", "m3", "m4"]);
+            assert!(report.silhouette > 0.5);
+            assert_eq!(report.bias_candidates.len(), 1);
+            assert_eq!(report.bias_candidates[0].source, "qtof-2");
+        }
+
+        let auto = ProfileClusterer::default().cluster_auto(&profiles, 4).unwrap();
+        assert_eq!(auto.clusters.len(), 2);
+        assert!(ProfileClusterer::default().cluster(&profiles, 8).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! LLM Prompt Budget
+//!
+//! Keeps AI-guided rectification prompts inside the model's context window.
+//! When the full evidence listing is over budget, items are kept in priority
+//! order (those involved in conflicts, then the most and least confident)
+//! with their data truncated, and the rest are split into chunks that are
+//! summarized by separate LLM calls and included as summaries.
+
+use anyhow::Result;
+use log::info;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::processing::evidence::IntegratedEvidence;
+
+/// Rough number of characters per token for English text and JSON
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Fewest words a chunk summary is allowed, however many chunks there are
+const MIN_SUMMARY_WORDS: usize = 20;
+
+/// Initialize the prompt budget module
+pub fn initialize() -> Result<()> {
+    info!("Initializing prompt budget module");
+    info!("Prompt budget module initialized successfully");
+    Ok(())
+}
+
+/// Estimate the number of tokens a text uses
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Cut a text to a number of characters, marking the cut
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}… [truncated]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Cut a text to a number of words
+pub fn truncate_words(text: &str, max_words: usize) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() <= max_words {
+        words.join(" ")
+    } else {
+        format!("{} …", words[..max_words].join(" "))
+    }
+}
+
+/// Limits on the size of a rectification prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptBudget {
+    /// Tokens the whole prompt may use
+    pub max_prompt_tokens: usize,
+
+    /// Characters of each item's data kept once the prompt is over budget
+    pub max_data_chars: usize,
+
+    /// Tokens reserved in a truncated prompt for the summaries of omitted items
+    pub summary_tokens: usize,
+}
+
+impl Default for PromptBudget {
+    fn default() -> Self {
+        Self {
+            max_prompt_tokens: 3000,
+            max_data_chars: 300,
+            summary_tokens: 600,
+        }
+    }
+}
+
+/// Evidence items kept verbatim and those left to be summarized
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// Indices of the items kept, in their original order
+    pub kept: Vec<usize>,
+
+    /// Indices of the items omitted, in priority order
+    pub omitted: Vec<usize>,
+}
+
+impl PromptBudget {
+    /// Budget from `HEGEL_LLM_PROMPT_TOKENS`, `HEGEL_LLM_PROMPT_DATA_CHARS` and
+    /// `HEGEL_LLM_SUMMARY_TOKENS`, with defaults for any that are unset
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str, default: usize| std::env::var(name).ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default);
+        Self {
+            max_prompt_tokens: var("HEGEL_LLM_PROMPT_TOKENS", defaults.max_prompt_tokens),
+            max_data_chars: var("HEGEL_LLM_PROMPT_DATA_CHARS", defaults.max_data_chars),
+            summary_tokens: var("HEGEL_LLM_SUMMARY_TOKENS", defaults.summary_tokens),
+        }
+    }
+
+    /// Whether a prompt is within the budget
+    pub fn fits(&self, prompt: &str) -> bool {
+        estimate_tokens(prompt) <= self.max_prompt_tokens
+    }
+
+    /// Order in which evidence items are kept when the prompt is truncated
+    ///
+    /// Items involved in conflicts come first, most severe conflict first.
+    /// The rest follow from the extremes inwards, alternating between the
+    /// most and the least confident, since those are the items a confidence
+    /// adjustment is most likely to concern.
+    pub fn priority_order(evidence: &IntegratedEvidence) -> Vec<usize> {
+        let mut severity: HashMap<&str, f64> = HashMap::new();
+        for conflict in &evidence.conflicts {
+            for id in &conflict.evidence_ids {
+                let entry = severity.entry(id.as_str()).or_insert(0.0);
+                *entry = entry.max(conflict.severity);
+            }
+        }
+
+        let items = &evidence.evidence_items;
+        let mut conflicting: Vec<usize> = (0..items.len())
+            .filter(|&i| severity.contains_key(items[i].id.as_str()))
+            .collect();
+        conflicting.sort_by(|&a, &b| severity[items[b].id.as_str()].total_cmp(&severity[items[a].id.as_str()]));
+
+        let mut rest: Vec<usize> = (0..items.len())
+            .filter(|&i| !severity.contains_key(items[i].id.as_str()))
+            .collect();
+        rest.sort_by(|&a, &b| items[b].confidence.total_cmp(&items[a].confidence));
+
+        let mut order = conflicting;
+        let (mut high, mut low) = (0, rest.len());
+        while high < low {
+            order.push(rest[high]);
+            high += 1;
+            if high < low {
+                low -= 1;
+                order.push(rest[low]);
+            }
+        }
+        order
+    }
+
+    /// Keep items in priority order while they fit alongside `fixed_tokens`
+    ///
+    /// `rendered` holds each item as it would appear in the prompt. Selection
+    /// stops at the first item that does not fit, so a lower-priority item
+    /// never displaces a higher-priority one.
+    pub fn select(&self, evidence: &IntegratedEvidence, rendered: &[String], fixed_tokens: usize) -> Selection {
+        let mut selection = Selection::default();
+        let mut used = fixed_tokens;
+        for index in Self::priority_order(evidence) {
+            let tokens = estimate_tokens(&rendered[index]);
+            if selection.omitted.is_empty() && used + tokens <= self.max_prompt_tokens {
+                used += tokens;
+                selection.kept.push(index);
+            } else {
+                selection.omitted.push(index);
+            }
+        }
+        selection.kept.sort_unstable();
+        selection
+    }
+
+    /// Group rendered items into chunks that each fit in one summary call
+    ///
+    /// An item too large for a chunk of its own is cut to fit.
+    pub fn chunks<'a>(&self, items: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let capacity = self.max_prompt_tokens.saturating_sub(self.summary_tokens).max(1);
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+        for item in items {
+            let item = truncate_chars(item, capacity * CHARS_PER_TOKEN);
+            if !current.is_empty() && estimate_tokens(&current) + estimate_tokens(&item) > capacity {
+                chunks.push(std::mem::take(&mut current));
+            }
+            current.push_str(&item);
+        }
+        if !current.is_empty() {
+            chunks.push(current);
+        }
+        chunks
+    }
+
+    /// Words each of `chunk_count` summaries may use to stay within `summary_tokens`
+    pub fn summary_words(&self, chunk_count: usize) -> usize {
+        (self.summary_tokens * 3 / 4 / chunk_count.max(1)).max(MIN_SUMMARY_WORDS)
+    }
+
+    /// Prompt asking the LLM to summarize one chunk of evidence
+    pub fn summary_prompt(&self, molecule_id: &str, chunk: &str, max_words: usize) -> String {
+        format!(
+            "Summarize the following molecular evidence for molecule ID '{}' in at most {} words. \
+             Mention the evidence IDs whose confidence looks unusually high or low and any disagreement \
+             between them.\n\n{}",
+            molecule_id, max_words, chunk
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::{Evidence, EvidenceConflict, EvidenceType};
+
+    fn integrated(confidences: &[f64], conflicting: &[&str]) -> IntegratedEvidence {
+        IntegratedEvidence {
+            molecule_id: "glucose".to_string(),
+            evidence_items: confidences.iter().enumerate()
+                .map(|(i, &confidence)| Evidence {
+                    id: format!("e{}", i),
+                    molecule_id: "glucose".to_string(),
+                    evidence_type: EvidenceType::MassSpec,
+                    source: "lab".to_string(),
+                    confidence,
+                    data: serde_json::Value::Null,
+                    metadata: HashMap::new(),
+                    timestamp: chrono::Utc::now(),
+                })
+                .collect(),
+            aggregate_confidence: 0.5,
+            conflicts: vec![EvidenceConflict {
+                description: "Mass disagrees".to_string(),
+                evidence_ids: conflicting.iter().map(|id| id.to_string()).collect(),
+                severity: 0.7,
+                resolution_suggestions: Vec::new(),
+            }],
+            integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_priority_keeps_conflicts_then_extremes() {
+        let evidence = integrated(&[0.5, 0.9, 0.1, 0.6, 0.4], &["e3"]);
+        assert_eq!(PromptBudget::priority_order(&evidence), vec![3, 1, 2, 0, 4]);
+
+        let budget = PromptBudget { max_prompt_tokens: 100, max_data_chars: 50, summary_tokens: 40 };
+        let rendered: Vec<String> = (0..5).map(|i| format!("{:0>80}", i)).collect();
+        let selection = budget.select(&evidence, &rendered, 40);
+        assert_eq!(selection, Selection { kept: vec![1, 2, 3], omitted: vec![0, 4] });
+
+        let chunks = budget.chunks(selection.omitted.iter().map(|&i| rendered[i].as_str()));
+        assert_eq!(chunks, vec![format!("{}{}", rendered[0], rendered[4])]);
+        assert_eq!(budget.summary_words(chunks.len()), 30);
+
+        let small = PromptBudget { max_prompt_tokens: 80, ..budget };
+        let chunks = small.chunks(rendered.iter().map(String::as_str));
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(small.summary_words(chunks.len()), 20);
+    }
+
+    #[test]
+    fn test_truncation_helpers() {
+        assert_eq!(estimate_tokens("abcdefghi"), 3);
+        assert_eq!(truncate_chars("abcdef", 3), "abc… [truncated]");
+        assert_eq!(truncate_chars("abc", 3), "abc");
+        assert_eq!(truncate_words("one two  three four", 2), "one two …");
+    }
+}
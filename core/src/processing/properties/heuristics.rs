@@ -0,0 +1,319 @@
+//! Built-in Property Heuristics
+//!
+//! Fast estimates computed from the structure alone, for when no trained
+//! model is available. pKa comes from a table of typical values for common
+//! ionizable groups, logS from the ESOL equation over an MLOGP-style logP,
+//! and permeability from topological polar surface area. They are coarse,
+//! and their uncertainties say so.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::processing::library::smiles::parse_smiles;
+use crate::processing::library::structure::{BondOrder, Structure};
+use crate::processing::Molecule;
+
+use super::{PropertyKind, PropertyPrediction, PropertyPredictor};
+
+/// Name the heuristics are registered under
+pub const HEURISTIC_PREDICTOR: &str = "heuristic";
+
+/// Standard deviations of the heuristic estimates
+const PKA_UNCERTAINTY: f64 = 1.5;
+const LOGS_UNCERTAINTY: f64 = 1.0;
+const PERMEABILITY_UNCERTAINTY: f64 = 0.7;
+
+/// Average atomic mass of the elements the heuristics know about
+fn atomic_mass(element: &str) -> Option<f64> {
+    Some(match element {
+        "H" => 1.008,
+        "B" => 10.81,
+        "C" => 12.011,
+        "N" => 14.007,
+        "O" => 15.999,
+        "F" => 18.998,
+        "Si" => 28.085,
+        "P" => 30.974,
+        "S" => 32.06,
+        "Cl" => 35.45,
+        "Se" => 78.97,
+        "Br" => 79.904,
+        "I" => 126.904,
+        _ => return None,
+    })
+}
+
+/// Structure-based property estimates
+#[derive(Debug, Clone, Default)]
+pub struct HeuristicPredictor;
+
+impl HeuristicPredictor {
+    /// Create the predictor
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl PropertyPredictor for HeuristicPredictor {
+    fn name(&self) -> &str {
+        HEURISTIC_PREDICTOR
+    }
+
+    fn properties(&self) -> Vec<PropertyKind> {
+        PropertyKind::all().to_vec()
+    }
+
+    async fn predict(&self, molecule: &Molecule, property: PropertyKind) -> Result<Option<PropertyPrediction>> {
+        let (structure, _) = parse_smiles(&molecule.smiles)?.largest_fragment();
+        let Some(descriptors) = Descriptors::compute(&structure) else {
+            return Ok(None);
+        };
+        let estimate = match property {
+            PropertyKind::AcidicPka => acidic_pka(&structure).map(|pka| (pka, PKA_UNCERTAINTY)),
+            PropertyKind::BasicPka => basic_pka(&structure).map(|pka| (pka, PKA_UNCERTAINTY)),
+            PropertyKind::LogS => Some((descriptors.esol_logs(), LOGS_UNCERTAINTY)),
+            PropertyKind::Permeability => Some((descriptors.log_papp(), PERMEABILITY_UNCERTAINTY)),
+        };
+        Ok(estimate.map(|(value, uncertainty)| PropertyPrediction::new(property, value, uncertainty, HEURISTIC_PREDICTOR)))
+    }
+}
+
+/// Whole-molecule descriptors the logS and permeability estimates are built from
+#[derive(Debug, Clone, PartialEq)]
+struct Descriptors {
+    /// Molecular weight including hydrogens
+    molecular_weight: f64,
+
+    /// Estimated octanol-water partition coefficient
+    log_p: f64,
+
+    /// Single non-ring bonds between non-terminal heavy atoms
+    rotatable_bonds: usize,
+
+    /// Fraction of heavy atoms that are aromatic
+    aromatic_proportion: f64,
+
+    /// Topological polar surface area of N and O atoms in Å²
+    tpsa: f64,
+}
+
+impl Descriptors {
+    /// Descriptors of a structure, or `None` if it has an element without a known mass
+    fn compute(structure: &Structure) -> Option<Self> {
+        let heavy: Vec<usize> = (0..structure.atoms.len()).filter(|&i| structure.atoms[i].element != "H").collect();
+        if heavy.is_empty() {
+            return None;
+        }
+
+        let mut molecular_weight = 0.0;
+        for i in 0..structure.atoms.len() {
+            molecular_weight += atomic_mass(&structure.atoms[i].element)?
+                + structure.hydrogen_count(i) as f64 * atomic_mass("H")?;
+        }
+
+        // The two leading terms of Moriguchi's MLOGP: carbons and halogens
+        // raise logP, nitrogens and oxygens lower it
+        let (mut cx, mut no) = (0.0, 0.0);
+        for &i in &heavy {
+            match structure.atoms[i].element.as_str() {
+                "C" | "Cl" => cx += 1.0,
+                "F" => cx += 0.5,
+                "Br" => cx += 1.5,
+                "I" => cx += 2.0,
+                "N" | "O" => no += 1.0,
+                _ => {}
+            }
+        }
+        let log_p = 1.244 * f64::powf(cx, 0.6) - 1.017 * f64::powf(no, 0.9) - 1.041;
+
+        let heavy_degree = |atom: usize| structure.neighbors(atom).filter(|&(n, _)| structure.atoms[n].element != "H").count();
+        let rotatable_bonds = structure.bonds.iter().enumerate()
+            .filter(|(i, bond)| bond.order == BondOrder::Single
+                && heavy_degree(bond.a) > 1 && heavy_degree(bond.b) > 1
+                && !structure.is_ring_bond(*i))
+            .count();
+
+        let aromatic = heavy.iter().filter(|&&i| structure.atoms[i].aromatic).count();
+        Some(Self {
+            molecular_weight,
+            log_p,
+            rotatable_bonds,
+            aromatic_proportion: aromatic as f64 / heavy.len() as f64,
+            tpsa: heavy.iter().map(|&i| polar_surface(structure, i)).sum(),
+        })
+    }
+
+    /// Delaney's ESOL estimate of log10 aqueous solubility in mol/L
+    fn esol_logs(&self) -> f64 {
+        0.16 - 0.63 * self.log_p - 0.0062 * self.molecular_weight + 0.066 * self.rotatable_bonds as f64
+            - 0.74 * self.aromatic_proportion
+    }
+
+    /// log10 apparent permeability in cm/s, falling linearly with polar
+    /// surface area from about -4.3 for apolar molecules to -6.3 at 140 Å²
+    fn log_papp(&self) -> f64 {
+        -4.3 - self.tpsa / 70.0
+    }
+}
+
+/// Ertl's polar surface contribution of an N or O atom
+fn polar_surface(structure: &Structure, atom: usize) -> f64 {
+    let a = &structure.atoms[atom];
+    let hydrogens = structure.hydrogen_count(atom);
+    let max_order = structure.neighbors(atom).map(|(_, order)| order).fold(BondOrder::Single, |max, order| {
+        match (max, order) {
+            (BondOrder::Triple, _) | (_, BondOrder::Triple) => BondOrder::Triple,
+            (BondOrder::Double, _) | (_, BondOrder::Double) => BondOrder::Double,
+            _ => BondOrder::Single,
+        }
+    });
+    match (a.element.as_str(), a.aromatic) {
+        ("N", true) if hydrogens > 0 => 15.79,
+        ("N", true) => 12.89,
+        ("N", false) => match (max_order, hydrogens) {
+            (BondOrder::Triple, _) => 23.79,
+            (BondOrder::Double, 0) => 12.36,
+            (BondOrder::Double, _) => 23.85,
+            (_, 0) => 3.24,
+            (_, 1) => 12.03,
+            _ => 26.02,
+        },
+        ("O", true) => 13.14,
+        ("O", false) if a.charge < 0 => 23.06,
+        ("O", false) => match (max_order, hydrogens) {
+            (BondOrder::Double, _) => 17.07,
+            (_, 0) => 9.23,
+            _ => 20.23,
+        },
+        _ => 0.0,
+    }
+}
+
+/// Whether an atom has a double bond to an oxygen
+fn has_oxo(structure: &Structure, atom: usize) -> bool {
+    oxo_count(structure, atom) > 0
+}
+
+fn oxo_count(structure: &Structure, atom: usize) -> usize {
+    structure.neighbors(atom)
+        .filter(|&(n, order)| order == BondOrder::Double && structure.atoms[n].element == "O")
+        .count()
+}
+
+/// pKa of the most acidic group: sulfonic, phosphoric and carboxylic acids,
+/// phenols and thiols
+fn acidic_pka(structure: &Structure) -> Option<f64> {
+    let mut pkas = Vec::new();
+    for (i, atom) in structure.atoms.iter().enumerate() {
+        if structure.hydrogen_count(i) == 0 || atom.charge != 0 {
+            continue;
+        }
+        let heavy: Vec<usize> = structure.neighbors(i).map(|(n, _)| n).collect();
+        match (atom.element.as_str(), heavy.as_slice()) {
+            ("O", [center]) => {
+                let center_atom = &structure.atoms[*center];
+                match center_atom.element.as_str() {
+                    "S" if oxo_count(structure, *center) >= 2 => pkas.push(-1.9),
+                    "P" if has_oxo(structure, *center) => pkas.push(2.1),
+                    "C" if has_oxo(structure, *center) => pkas.push(4.2),
+                    "C" if center_atom.aromatic => pkas.push(10.0),
+                    _ => {}
+                }
+            }
+            ("S", [center]) if !structure.atoms[*center].aromatic && !atom.aromatic => pkas.push(10.5),
+            _ => {}
+        }
+    }
+    pkas.into_iter().reduce(f64::min)
+}
+
+/// pKa of the conjugate acid of the most basic group: amidines and
+/// guanidines, aliphatic amines, pyridine-like nitrogens and anilines
+fn basic_pka(structure: &Structure) -> Option<f64> {
+    let mut pkas = Vec::new();
+    for (i, atom) in structure.atoms.iter().enumerate() {
+        if atom.element != "N" || atom.charge != 0 {
+            continue;
+        }
+        let neighbors: Vec<(usize, BondOrder)> = structure.neighbors(i).collect();
+        if atom.aromatic {
+            if structure.hydrogen_count(i) == 0 && neighbors.len() == 2 {
+                pkas.push(5.2);
+            }
+            continue;
+        }
+        if let Some(&(carbon, _)) = neighbors.iter().find(|&&(n, order)| order == BondOrder::Double && structure.atoms[n].element == "C") {
+            let amino = structure.neighbors(carbon)
+                .filter(|&(n, order)| n != i && order == BondOrder::Single && structure.atoms[n].element == "N")
+                .count();
+            match amino {
+                0 => {}
+                1 => pkas.push(12.4),
+                _ => pkas.push(13.6),
+            }
+            continue;
+        }
+        if neighbors.iter().any(|&(_, order)| order != BondOrder::Single) {
+            continue;
+        }
+        // Nitrogens next to a carbonyl, sulfonyl or heteroatom are not basic
+        let deactivated = neighbors.iter().any(|&(n, _)| {
+            let neighbor = &structure.atoms[n];
+            has_oxo(structure, n) || !matches!(neighbor.element.as_str(), "C" | "H")
+        });
+        if deactivated {
+            continue;
+        }
+        if neighbors.iter().any(|&(n, _)| structure.atoms[n].aromatic) {
+            pkas.push(4.6);
+        } else {
+            pkas.push(match structure.hydrogen_count(i) {
+                0 => 9.8,
+                1 => 11.0,
+                _ => 10.6,
+            });
+        }
+    }
+    pkas.into_iter().reduce(f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn predict(smiles: &str, property: PropertyKind) -> Option<f64> {
+        let molecule = Molecule::from_smiles(smiles).unwrap();
+        HeuristicPredictor::new().predict(&molecule, property).await.unwrap().map(|p| p.value)
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_pka() {
+        assert_eq!(predict("CC(=O)O", PropertyKind::AcidicPka).await, Some(4.2));
+        assert_eq!(predict("Oc1ccccc1", PropertyKind::AcidicPka).await, Some(10.0));
+        assert_eq!(predict("CCN", PropertyKind::BasicPka).await, Some(10.6));
+        assert_eq!(predict("c1ccncc1", PropertyKind::BasicPka).await, Some(5.2));
+        // Amide nitrogens are not basic, and alkanes have no ionizable group
+        assert_eq!(predict("CC(=O)NC", PropertyKind::BasicPka).await, None);
+        assert_eq!(predict("CCCC", PropertyKind::AcidicPka).await, None);
+        // Counter-ions are stripped before prediction
+        assert_eq!(predict("CC(=O)O.[Na+]", PropertyKind::AcidicPka).await, Some(4.2));
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_solubility_and_permeability() {
+        let glucose = predict("OCC1OC(O)C(O)C(O)C1O", PropertyKind::LogS).await.unwrap();
+        let octane = predict("CCCCCCCC", PropertyKind::LogS).await.unwrap();
+        assert!(glucose > octane);
+
+        let structure = parse_smiles("CC(=O)Nc1ccc(O)cc1").unwrap();
+        let paracetamol = Descriptors::compute(&structure).unwrap();
+        assert!((paracetamol.molecular_weight - 151.16).abs() < 0.1);
+        assert!((paracetamol.tpsa - 49.33).abs() < 0.01);
+        assert_eq!(paracetamol.rotatable_bonds, 2);
+
+        let octane_papp = predict("CCCCCCCC", PropertyKind::Permeability).await.unwrap();
+        let glucose_papp = predict("OCC1OC(O)C(O)C(O)C1O", PropertyKind::Permeability).await.unwrap();
+        assert!(octane_papp > glucose_papp);
+    }
+}
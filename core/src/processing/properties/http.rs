@@ -0,0 +1,137 @@
+//! Property Model Servers
+//!
+//! Predictor backed by a trained model served over HTTP. For each property
+//! the server receives `POST {endpoint}/predict` with
+//! `{"molecule_id", "smiles", "property"}` and answers
+//! `{"value", "uncertainty", "model_version"}`, with a null value when the
+//! property does not apply to the molecule.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::processing::Molecule;
+
+use super::{PropertyKind, PropertyPrediction, PropertyPredictor};
+
+/// A model server and the properties it predicts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelServerConfig {
+    /// Name the server's predictions are attributed to
+    pub name: String,
+
+    /// Base URL of the server, such as `http://models:8500`
+    pub endpoint: String,
+
+    /// Properties the server predicts
+    pub properties: Vec<PropertyKind>,
+
+    /// Request timeout in seconds
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+impl ModelServerConfig {
+    /// Load a JSON array of model servers
+    pub fn load(path: &Path) -> Result<Vec<Self>> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read property model servers: {}", path.display()))?;
+        let servers: Vec<Self> = serde_json::from_str(&content).context("Failed to parse property model servers")?;
+        for server in &servers {
+            server.validate()?;
+        }
+        Ok(servers)
+    }
+
+    /// Check the server has a name, an HTTP endpoint and properties
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("Property model server needs a name"));
+        }
+        if !(self.endpoint.starts_with("http://") || self.endpoint.starts_with("https://")) {
+            return Err(anyhow!("Property model server {} needs an http(s) endpoint, got {}", self.name, self.endpoint));
+        }
+        if self.properties.is_empty() {
+            return Err(anyhow!("Property model server {} predicts no properties", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// Response of a model server to one prediction request
+#[derive(Debug, Deserialize)]
+struct PredictResponse {
+    value: Option<f64>,
+    uncertainty: f64,
+    #[serde(default)]
+    model_version: Option<String>,
+}
+
+/// Property predictor that calls a model server
+pub struct HttpPropertyPredictor {
+    /// Server configuration
+    config: ModelServerConfig,
+
+    /// HTTP client
+    client: reqwest::Client,
+}
+
+impl HttpPropertyPredictor {
+    /// Predictor for a model server
+    pub fn new(config: ModelServerConfig) -> Self {
+        Self {
+            config: ModelServerConfig {
+                endpoint: config.endpoint.trim_end_matches('/').to_string(),
+                ..config
+            },
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl PropertyPredictor for HttpPropertyPredictor {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn properties(&self) -> Vec<PropertyKind> {
+        self.config.properties.clone()
+    }
+
+    async fn predict(&self, molecule: &Molecule, property: PropertyKind) -> Result<Option<PropertyPrediction>> {
+        let request = serde_json::json!({
+            "molecule_id": molecule.id,
+            "smiles": molecule.smiles,
+            "property": property,
+        });
+        let response = self.client.post(format!("{}/predict", self.config.endpoint))
+            .json(&request)
+            .timeout(Duration::from_secs(self.config.timeout_secs))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach property model server {}", self.config.name))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow!("Property model server {} failed with status {}: {}", self.config.name, status, error_text));
+        }
+        let body: PredictResponse = response.json().await
+            .with_context(|| format!("Property model server {} returned a malformed prediction", self.config.name))?;
+
+        Ok(body.value.map(|value| {
+            let prediction = PropertyPrediction::new(property, value, body.uncertainty, &self.config.name);
+            match &body.model_version {
+                Some(version) => prediction.with_model_version(version),
+                None => prediction,
+            }
+        }))
+    }
+}
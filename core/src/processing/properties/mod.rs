@@ -0,0 +1,390 @@
+//! Property Prediction Module
+//!
+//! Predicts physicochemical properties of a molecule (acidic and basic pKa,
+//! aqueous solubility as logS, membrane permeability) with pluggable
+//! `PropertyPredictor`s: the built-in structural heuristics, or trained
+//! models behind an HTTP model server. Predictions carry the predictor that
+//! made them and a standard deviation, are kept in `Molecule.properties`,
+//! and can be compared against measured values to give weak structural
+//! evidence for a candidate identity.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::processing::evidence::{Evidence, EvidenceType, PREDICTION_METADATA_KEY};
+use crate::processing::Molecule;
+
+pub mod heuristics;
+pub mod http;
+
+pub use heuristics::HeuristicPredictor;
+pub use http::{HttpPropertyPredictor, ModelServerConfig};
+
+/// Environment variable naming a JSON file of model servers to register
+pub const MODEL_SERVERS_ENV: &str = "HEGEL_PROPERTY_MODELS";
+
+/// Key of `Molecule.properties` predictions are stored under
+pub const PREDICTED_PROPERTIES_KEY: &str = "predicted_properties";
+
+/// Source of evidence from comparing predicted and measured properties
+pub const PROPERTY_EVIDENCE_SOURCE: &str = "property_prediction";
+
+/// Initialize the property prediction module
+pub fn initialize() -> Result<()> {
+    info!("Initializing property prediction module");
+    info!("Property prediction module initialized successfully");
+    Ok(())
+}
+
+/// Physicochemical property a predictor can estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyKind {
+    /// pKa of the most acidic group
+    AcidicPka,
+    /// pKa of the conjugate acid of the most basic group
+    BasicPka,
+    /// log10 aqueous solubility in mol/L
+    LogS,
+    /// log10 apparent membrane permeability in cm/s
+    Permeability,
+}
+
+impl PropertyKind {
+    /// Every property, in display order
+    pub fn all() -> &'static [PropertyKind] {
+        &[PropertyKind::AcidicPka, PropertyKind::BasicPka, PropertyKind::LogS, PropertyKind::Permeability]
+    }
+
+    /// Unit values of the property are given in
+    pub fn unit(&self) -> &'static str {
+        match self {
+            PropertyKind::AcidicPka | PropertyKind::BasicPka => "pH units",
+            PropertyKind::LogS => "log10 mol/L",
+            PropertyKind::Permeability => "log10 cm/s",
+        }
+    }
+}
+
+impl std::fmt::Display for PropertyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropertyKind::AcidicPka => write!(f, "acidic_pka"),
+            PropertyKind::BasicPka => write!(f, "basic_pka"),
+            PropertyKind::LogS => write!(f, "log_s"),
+            PropertyKind::Permeability => write!(f, "permeability"),
+        }
+    }
+}
+
+impl std::str::FromStr for PropertyKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "acidic_pka" | "pka" => Ok(PropertyKind::AcidicPka),
+            "basic_pka" => Ok(PropertyKind::BasicPka),
+            "log_s" | "logs" | "solubility" => Ok(PropertyKind::LogS),
+            "permeability" | "log_papp" => Ok(PropertyKind::Permeability),
+            other => Err(anyhow!("Unknown property: {}", other)),
+        }
+    }
+}
+
+/// Predicted value of a property, with where it came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyPrediction {
+    /// Property predicted
+    pub property: PropertyKind,
+
+    /// Predicted value, in the property's unit
+    pub value: f64,
+
+    /// Standard deviation of the prediction
+    pub uncertainty: f64,
+
+    /// Name of the predictor that made the prediction
+    pub predictor: String,
+
+    /// Version of the model behind the predictor, if it reports one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+
+    /// When the prediction was made
+    pub predicted_at: DateTime<Utc>,
+}
+
+impl PropertyPrediction {
+    /// Prediction made now
+    pub fn new(property: PropertyKind, value: f64, uncertainty: f64, predictor: &str) -> Self {
+        Self {
+            property,
+            value,
+            uncertainty,
+            predictor: predictor.to_string(),
+            model_version: None,
+            predicted_at: Utc::now(),
+        }
+    }
+
+    /// Record the version of the model that made the prediction
+    pub fn with_model_version(mut self, version: &str) -> Self {
+        self.model_version = Some(version.to_string());
+        self
+    }
+}
+
+/// Source of property predictions for molecules
+#[async_trait]
+pub trait PropertyPredictor: Send + Sync {
+    /// Name under which the predictor is registered
+    fn name(&self) -> &str;
+
+    /// Properties the predictor can estimate
+    fn properties(&self) -> Vec<PropertyKind>;
+
+    /// Predict a property, or `None` if it does not apply to the molecule
+    /// (a pKa for a molecule with no ionizable group)
+    async fn predict(&self, molecule: &Molecule, property: PropertyKind) -> Result<Option<PropertyPrediction>>;
+}
+
+/// Registry of property predictors keyed by name
+pub struct PropertyPredictorRegistry {
+    /// Registered predictors
+    predictors: RwLock<HashMap<String, Arc<dyn PropertyPredictor>>>,
+}
+
+impl PropertyPredictorRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            predictors: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Registry holding only the built-in heuristics
+    pub fn with_builtin() -> Self {
+        let registry = Self::new();
+        registry.register(Arc::new(HeuristicPredictor::new()));
+        registry
+    }
+
+    /// Built-in heuristics plus the model servers listed in the file named by
+    /// `HEGEL_PROPERTY_MODELS`, if set
+    pub fn from_env() -> Result<Self> {
+        let registry = Self::with_builtin();
+        if let Ok(path) = std::env::var(MODEL_SERVERS_ENV) {
+            let servers = ModelServerConfig::load(Path::new(&path))?;
+            for server in servers {
+                info!("Registering property model server {} at {}", server.name, server.endpoint);
+                registry.register(Arc::new(HttpPropertyPredictor::new(server)));
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Register a predictor, replacing any predictor with the same name
+    pub fn register(&self, predictor: Arc<dyn PropertyPredictor>) {
+        let name = predictor.name().to_string();
+        debug!("Registering property predictor: {}", name);
+        self.predictors.write().unwrap().insert(name, predictor);
+    }
+
+    /// Names of all registered predictors
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.predictors.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Predict every property each predictor supports, in predictor name order
+    ///
+    /// A failing predictor is logged and contributes nothing, and predictions
+    /// with a non-finite value or a non-positive uncertainty are dropped.
+    pub async fn predict(&self, molecule: &Molecule) -> Vec<PropertyPrediction> {
+        let mut predictors: Vec<Arc<dyn PropertyPredictor>> = self.predictors.read().unwrap().values().cloned().collect();
+        predictors.sort_by(|a, b| a.name().cmp(b.name()));
+
+        let mut predictions = Vec::new();
+        for predictor in predictors {
+            for property in predictor.properties() {
+                match predictor.predict(molecule, property).await {
+                    Ok(Some(prediction)) if prediction.value.is_finite() && prediction.uncertainty > 0.0 => {
+                        predictions.push(prediction);
+                    }
+                    Ok(Some(_)) => warn!("Dropping invalid {} prediction from {} for {}", property, predictor.name(), molecule.id),
+                    Ok(None) => {}
+                    Err(e) => warn!("Property predictor {} failed on {} for {}: {:#}", predictor.name(), property, molecule.id, e),
+                }
+            }
+        }
+        predictions
+    }
+
+    /// Predict every property and store the predictions on the molecule
+    pub async fn predict_and_store(&self, molecule: &mut Molecule) -> Vec<PropertyPrediction> {
+        let predictions = self.predict(molecule).await;
+        store_predictions(molecule, &predictions);
+        predictions
+    }
+}
+
+impl Default for PropertyPredictorRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Store predictions in `Molecule.properties`, replacing earlier predictions
+/// of the same property by the same predictor
+pub fn store_predictions(molecule: &mut Molecule, predictions: &[PropertyPrediction]) {
+    let mut stored: BTreeMap<(PropertyKind, String), PropertyPrediction> = predicted_properties(molecule).into_iter()
+        .map(|p| ((p.property, p.predictor.clone()), p))
+        .collect();
+    for prediction in predictions {
+        stored.insert((prediction.property, prediction.predictor.clone()), prediction.clone());
+    }
+    let stored: Vec<PropertyPrediction> = stored.into_values().collect();
+    molecule.properties.insert(PREDICTED_PROPERTIES_KEY.to_string(), serde_json::json!(stored));
+}
+
+/// Predictions stored on a molecule
+pub fn predicted_properties(molecule: &Molecule) -> Vec<PropertyPrediction> {
+    molecule.properties.get(PREDICTED_PROPERTIES_KEY)
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// The stored prediction of a property with the smallest uncertainty
+pub fn best_prediction(molecule: &Molecule, property: PropertyKind) -> Option<PropertyPrediction> {
+    predicted_properties(molecule).into_iter()
+        .filter(|p| p.property == property)
+        .min_by(|a, b| a.uncertainty.total_cmp(&b.uncertainty))
+}
+
+/// Measured value of a property, e.g. a titrated pKa
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyObservation {
+    /// Property measured
+    pub property: PropertyKind,
+
+    /// Measured value, in the property's unit
+    pub value: f64,
+
+    /// Standard deviation of the measurement
+    pub uncertainty: f64,
+
+    /// Experiment or assay the value comes from
+    pub source: String,
+}
+
+/// How predicted properties are turned into evidence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyEvidenceOptions {
+    /// Integration weight of property evidence relative to direct evidence (0.0 - 1.0)
+    pub weight: f64,
+}
+
+impl Default for PropertyEvidenceOptions {
+    fn default() -> Self {
+        Self { weight: 0.25 }
+    }
+}
+
+/// Structural evidence from comparing measured properties with the
+/// molecule's stored predictions
+///
+/// Each observation with a prediction of the same property gives one item,
+/// scored by how many combined standard deviations apart the two values
+/// are. Predictions are indirect, so the items carry a reduced integration
+/// weight in their `PREDICTION_METADATA_KEY` metadata.
+pub fn property_evidence(
+    molecule: &Molecule,
+    observations: &[PropertyObservation],
+    options: &PropertyEvidenceOptions,
+) -> Vec<Evidence> {
+    observations.iter()
+        .filter_map(|observation| {
+            let prediction = best_prediction(molecule, observation.property)?;
+            let sigma = (prediction.uncertainty.powi(2) + observation.uncertainty.powi(2)).sqrt();
+            let z = (observation.value - prediction.value) / sigma;
+
+            let mut metadata = HashMap::new();
+            metadata.insert(PREDICTION_METADATA_KEY.to_string(), serde_json::json!({
+                "weight": options.weight,
+                "predictor": prediction.predictor,
+                "model_version": prediction.model_version,
+            }));
+            Some(Evidence {
+                id: format!("{}-{}-{}", PROPERTY_EVIDENCE_SOURCE, molecule.id, observation.property),
+                molecule_id: molecule.id.clone(),
+                evidence_type: EvidenceType::Structural,
+                source: format!("{}:{}", PROPERTY_EVIDENCE_SOURCE, prediction.predictor),
+                confidence: (-0.5 * z * z).exp(),
+                data: serde_json::json!({
+                    "property": observation.property,
+                    "unit": observation.property.unit(),
+                    "observed": observation.value,
+                    "observed_uncertainty": observation.uncertainty,
+                    "observation_source": observation.source,
+                    "predicted": prediction.value,
+                    "predicted_uncertainty": prediction.uncertainty,
+                    "z_score": z,
+                }),
+                metadata,
+                timestamp: Utc::now(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_predictions_are_stored_with_provenance() {
+        let mut molecule = Molecule::from_smiles("CC(=O)O").unwrap();
+        let registry = PropertyPredictorRegistry::with_builtin();
+        let predictions = registry.predict_and_store(&mut molecule).await;
+        // Acetic acid has no basic group
+        assert_eq!(predictions.len(), 3);
+
+        let stored = predicted_properties(&molecule);
+        assert_eq!(stored.len(), 3);
+        let pka = best_prediction(&molecule, PropertyKind::AcidicPka).unwrap();
+        assert_eq!(pka.predictor, heuristics::HEURISTIC_PREDICTOR);
+        assert!(pka.uncertainty > 0.0);
+
+        // Predicting again replaces rather than duplicates
+        registry.predict_and_store(&mut molecule).await;
+        assert_eq!(predicted_properties(&molecule).len(), 3);
+    }
+
+    #[test]
+    fn test_property_evidence_is_weak_structural_evidence() {
+        let mut molecule = Molecule::from_smiles("CC(=O)O").unwrap();
+        store_predictions(&mut molecule, &[
+            PropertyPrediction::new(PropertyKind::AcidicPka, 4.2, 1.5, "heuristic"),
+            PropertyPrediction::new(PropertyKind::AcidicPka, 4.7, 0.3, "model").with_model_version("2.1"),
+        ]);
+        let observations = [
+            PropertyObservation { property: PropertyKind::AcidicPka, value: 4.76, uncertainty: 0.1, source: "titration".to_string() },
+            PropertyObservation { property: PropertyKind::LogS, value: 1.0, uncertainty: 0.2, source: "shake flask".to_string() },
+        ];
+        let evidence = property_evidence(&molecule, &observations, &PropertyEvidenceOptions::default());
+
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].evidence_type, EvidenceType::Structural);
+        assert_eq!(evidence[0].source, "property_prediction:model");
+        assert!(evidence[0].confidence > 0.9);
+        assert_eq!(evidence[0].prediction_weight(), 0.25);
+        assert_eq!(evidence[0].metadata[PREDICTION_METADATA_KEY]["model_version"], "2.1");
+    }
+}
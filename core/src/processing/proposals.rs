@@ -0,0 +1,398 @@
+//! Rectification Proposals
+//!
+//! Two-phase rectification: the adjustments of a dry run are stored as a
+//! proposal, reviewers approve or reject each adjustment, and applying the
+//! proposal takes effect with the approved adjustments only. The store is
+//! written to a JSON file after every change, so pending reviews survive a
+//! restart, and each step is added to the proposal's decision trail in the
+//! memory system.
+
+use anyhow::{anyhow, Context as _, Result};
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::metacognition::memory::context::{Context, ProcessingStep, StepResult, StepType};
+use crate::metacognition::memory::MemorySystem;
+
+/// Initialize the rectification proposals module
+pub fn initialize() -> Result<()> {
+    info!("Initializing rectification proposals module");
+    info!("Rectification proposals module initialized successfully");
+    Ok(())
+}
+
+/// Review state of a proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    /// Some adjustments await a decision
+    Pending,
+    /// Every adjustment was decided; ready to apply
+    Reviewed,
+    /// The approved adjustments took effect
+    Applied,
+}
+
+/// Decision on a single adjustment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentDecision {
+    /// Not reviewed yet
+    Pending,
+    /// Takes effect when the proposal is applied
+    Approved,
+    /// The original confidence is kept
+    Rejected,
+}
+
+/// Decision of a reviewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    /// Accept the proposed confidence
+    Approve,
+    /// Keep the original confidence
+    Reject,
+}
+
+/// A confidence change proposed for one evidence item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposedAdjustment {
+    /// Source of the evidence
+    pub source: String,
+
+    /// Confidence as submitted
+    pub original_confidence: f64,
+
+    /// Confidence rectification arrived at
+    pub proposed_confidence: f64,
+
+    /// Why rectification proposed it
+    pub reason: Option<String>,
+
+    /// Review decision
+    pub decision: AdjustmentDecision,
+
+    /// Who decided
+    pub decided_by: Option<String>,
+
+    /// When it was decided
+    pub decided_at: Option<DateTime<Utc>>,
+
+    /// Reviewer's note
+    pub note: Option<String>,
+}
+
+impl ProposedAdjustment {
+    /// Create an undecided adjustment
+    pub fn new(source: &str, original_confidence: f64, proposed_confidence: f64, reason: Option<String>) -> Self {
+        Self {
+            source: source.to_string(),
+            original_confidence,
+            proposed_confidence,
+            reason,
+            decision: AdjustmentDecision::Pending,
+            decided_by: None,
+            decided_at: None,
+            note: None,
+        }
+    }
+
+    /// Confidence the item ends up with: the proposed one only if approved
+    pub fn effective_confidence(&self) -> f64 {
+        match self.decision {
+            AdjustmentDecision::Approved => self.proposed_confidence,
+            _ => self.original_confidence,
+        }
+    }
+}
+
+/// Rectification of one molecule awaiting approval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RectificationProposal {
+    /// Proposal identifier
+    pub id: String,
+
+    /// Project the molecule belongs to
+    pub project_id: String,
+
+    /// Molecule whose evidence would be rectified
+    pub molecule_id: String,
+
+    /// Proposed adjustments, one per evidence item
+    pub adjustments: Vec<ProposedAdjustment>,
+
+    /// Who requested the proposal
+    pub proposed_by: String,
+
+    /// When it was proposed
+    pub created_at: DateTime<Utc>,
+
+    /// Review state
+    pub status: ProposalStatus,
+
+    /// Who applied it
+    pub applied_by: Option<String>,
+
+    /// When it was applied
+    pub applied_at: Option<DateTime<Utc>>,
+
+    /// Confidence recorded when it was applied
+    pub applied_confidence: Option<f64>,
+}
+
+impl RectificationProposal {
+    /// Create a pending proposal
+    pub fn new(project_id: &str, molecule_id: &str, adjustments: Vec<ProposedAdjustment>, proposed_by: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: project_id.to_string(),
+            molecule_id: molecule_id.to_string(),
+            adjustments,
+            proposed_by: proposed_by.to_string(),
+            created_at: Utc::now(),
+            status: ProposalStatus::Pending,
+            applied_by: None,
+            applied_at: None,
+            applied_confidence: None,
+        }
+    }
+
+    /// Molecule confidence with the approved adjustments: the mean effective confidence
+    pub fn confidence(&self) -> f64 {
+        if self.adjustments.is_empty() {
+            return 0.0;
+        }
+        self.adjustments.iter().map(|a| a.effective_confidence()).sum::<f64>() / self.adjustments.len() as f64
+    }
+
+    /// Decide some adjustments, or all of them when `indices` is `None`
+    ///
+    /// Decisions can be revised until the proposal is applied.
+    pub fn review(&mut self, indices: Option<&[usize]>, decision: ReviewDecision, reviewer: &str, note: Option<String>) -> Result<()> {
+        if self.status == ProposalStatus::Applied {
+            return Err(anyhow!("Proposal {} was already applied", self.id));
+        }
+        let all: Vec<usize> = (0..self.adjustments.len()).collect();
+        let indices = indices.unwrap_or(&all);
+        if let Some(index) = indices.iter().find(|&&i| i >= self.adjustments.len()) {
+            return Err(anyhow!("Proposal {} has no adjustment {}", self.id, index));
+        }
+
+        let now = Utc::now();
+        for &index in indices {
+            let adjustment = &mut self.adjustments[index];
+            adjustment.decision = match decision {
+                ReviewDecision::Approve => AdjustmentDecision::Approved,
+                ReviewDecision::Reject => AdjustmentDecision::Rejected,
+            };
+            adjustment.decided_by = Some(reviewer.to_string());
+            adjustment.decided_at = Some(now);
+            adjustment.note = note.clone();
+        }
+        self.status = if self.adjustments.iter().any(|a| a.decision == AdjustmentDecision::Pending) {
+            ProposalStatus::Pending
+        } else {
+            ProposalStatus::Reviewed
+        };
+        Ok(())
+    }
+
+    /// Mark the proposal applied, returning the resulting confidence
+    ///
+    /// Every adjustment must have been decided first.
+    pub fn apply(&mut self, applied_by: &str) -> Result<f64> {
+        match self.status {
+            ProposalStatus::Applied => return Err(anyhow!("Proposal {} was already applied", self.id)),
+            ProposalStatus::Pending => {
+                let pending = self.adjustments.iter().filter(|a| a.decision == AdjustmentDecision::Pending).count();
+                return Err(anyhow!("Proposal {} has {} adjustments awaiting review", self.id, pending));
+            }
+            ProposalStatus::Reviewed => {}
+        }
+        let confidence = self.confidence();
+        self.status = ProposalStatus::Applied;
+        self.applied_by = Some(applied_by.to_string());
+        self.applied_at = Some(Utc::now());
+        self.applied_confidence = Some(confidence);
+        Ok(confidence)
+    }
+
+    /// ID of the memory-system context holding the decision trail
+    pub fn trail_id(&self) -> String {
+        format!("rectification_{}", self.id)
+    }
+
+    /// Add a step to the proposal's decision trail in the memory system
+    pub fn record_trail(&self, memory: &MemorySystem, action: &str, actor: &str) -> Result<()> {
+        let mut context = match memory.retrieve_context(&self.trail_id())? {
+            Some(context) => context,
+            None => {
+                let mut context = Context::new();
+                context.id = self.trail_id();
+                context.add_molecule(&self.molecule_id);
+                context.add_metadata("project_id", serde_json::json!(self.project_id));
+                context.add_metadata("proposal_id", serde_json::json!(self.id));
+                context
+            }
+        };
+        let approved = self.adjustments.iter().filter(|a| a.decision == AdjustmentDecision::Approved).count();
+        let rejected = self.adjustments.iter().filter(|a| a.decision == AdjustmentDecision::Rejected).count();
+        context.add_step(ProcessingStep {
+            step_type: StepType::Decision,
+            description: format!("Rectification proposal {} by {}", action, actor),
+            timestamp: Utc::now().timestamp().max(0) as u64,
+            result: StepResult::Decision {
+                decision: action.to_string(),
+                confidence: self.applied_confidence.unwrap_or_else(|| self.confidence()),
+                explanation: format!("{} of {} adjustments approved, {} rejected",
+                                     approved, self.adjustments.len(), rejected),
+            },
+        });
+        context.add_metadata("proposal", serde_json::to_value(self)?);
+        memory.store_context(context)
+    }
+}
+
+/// Rectification proposals of all projects, saved to a JSON file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProposalStore {
+    /// Proposals by ID
+    proposals: HashMap<String, RectificationProposal>,
+
+    /// File the store is saved to; kept in memory only when unset
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl ProposalStore {
+    /// Create an empty store that is not saved
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the store saved at a path, or start an empty one saved there
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut store = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read rectification proposals: {}", path.display()))?;
+            serde_json::from_str::<Self>(&content).context("Failed to parse rectification proposals")?
+        } else {
+            Self::new()
+        };
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    /// Open the store named by `HEGEL_PROPOSALS_FILE`, or `proposals.json`
+    pub fn from_env() -> Result<Self> {
+        Self::open(std::env::var("HEGEL_PROPOSALS_FILE").unwrap_or_else(|_| "proposals.json".to_string()))
+    }
+
+    /// File the store is saved to
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Store a new proposal
+    pub fn insert(&mut self, proposal: RectificationProposal) -> Result<&RectificationProposal> {
+        let id = proposal.id.clone();
+        self.proposals.insert(id.clone(), proposal);
+        self.save()?;
+        Ok(&self.proposals[&id])
+    }
+
+    /// Get a proposal of a project
+    pub fn get(&self, project_id: &str, proposal_id: &str) -> Option<&RectificationProposal> {
+        self.proposals.get(proposal_id).filter(|p| p.project_id == project_id)
+    }
+
+    /// Proposals of a project, optionally only those in one state, newest first
+    pub fn list(&self, project_id: &str, status: Option<ProposalStatus>) -> Vec<&RectificationProposal> {
+        let mut proposals: Vec<&RectificationProposal> = self.proposals.values()
+            .filter(|p| p.project_id == project_id)
+            .filter(|p| status.is_none_or(|s| p.status == s))
+            .collect();
+        proposals.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        proposals
+    }
+
+    /// Record a reviewer's decision on some or all adjustments of a proposal
+    pub fn review(
+        &mut self,
+        project_id: &str,
+        proposal_id: &str,
+        indices: Option<&[usize]>,
+        decision: ReviewDecision,
+        reviewer: &str,
+        note: Option<String>,
+    ) -> Result<&RectificationProposal> {
+        self.get_mut(project_id, proposal_id)?.review(indices, decision, reviewer, note)?;
+        self.save()?;
+        Ok(&self.proposals[proposal_id])
+    }
+
+    /// Apply a reviewed proposal, returning it with the resulting confidence
+    pub fn apply(&mut self, project_id: &str, proposal_id: &str, applied_by: &str) -> Result<&RectificationProposal> {
+        self.get_mut(project_id, proposal_id)?.apply(applied_by)?;
+        self.save()?;
+        info!("Rectification proposal {} applied by {}", proposal_id, applied_by);
+        Ok(&self.proposals[proposal_id])
+    }
+
+    fn get_mut(&mut self, project_id: &str, proposal_id: &str) -> Result<&mut RectificationProposal> {
+        self.proposals.get_mut(proposal_id)
+            .filter(|p| p.project_id == project_id)
+            .ok_or_else(|| anyhow!("Unknown rectification proposal: {}", proposal_id))
+    }
+
+    /// Write the store to its file, replacing the previous version in one step
+    fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write rectification proposals: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to save rectification proposals: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_approved_adjustments_apply() {
+        let path = std::env::temp_dir().join(format!("hegel-proposals-{}.json", uuid::Uuid::new_v4()));
+        let mut store = ProposalStore::open(&path).unwrap();
+        let proposal = RectificationProposal::new("p1", "glucose", vec![
+            ProposedAdjustment::new("genomics", 0.6, 0.69, None),
+            ProposedAdjustment::new("literature", 0.5, 0.6, Some("Rule-based".to_string())),
+        ], "alice");
+        let id = store.insert(proposal).unwrap().id.clone();
+        assert!(store.get("p2", &id).is_none());
+
+        store.review("p1", &id, Some(&[0]), ReviewDecision::Approve, "bob", None).unwrap();
+        assert!(store.apply("p1", &id, "bob").is_err());
+        assert!(store.review("p1", &id, Some(&[5]), ReviewDecision::Reject, "bob", None).is_err());
+        store.review("p1", &id, Some(&[1]), ReviewDecision::Reject, "bob", Some("unsourced".to_string())).unwrap();
+
+        let applied = store.apply("p1", &id, "bob").unwrap();
+        assert_eq!(applied.status, ProposalStatus::Applied);
+        assert!((applied.applied_confidence.unwrap() - 0.595).abs() < 1e-9);
+
+        // The saved store survives a reopen
+        let reopened = ProposalStore::open(&path).unwrap();
+        assert_eq!(reopened.list("p1", Some(ProposalStatus::Applied)).len(), 1);
+        assert!(reopened.clone().review("p1", &id, None, ReviewDecision::Approve, "bob", None).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,478 @@
+//! Quality Control
+//!
+//! Labs spike every run with QC compounds and internal standards whose
+//! identity is known in advance. How well a run identifies them says how far
+//! the rest of its evidence can be trusted: each run (the `run_id` metadata
+//! key) is scored on the share of its QC compounds detected, their mean
+//! identification confidence and their worst mass and retention time errors.
+//! Runs that miss the system suitability thresholds raise an alert, and their
+//! evidence is either blocked or flagged so integration gives it less weight.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use log::{info, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::alerts::{Alert, AlertSeverity};
+use crate::processing::drift::RUN_METADATA_KEY;
+use crate::processing::evidence::{Evidence, QC_METADATA_KEY};
+
+/// Initialize the quality control module
+pub fn initialize() -> Result<()> {
+    info!("Initializing quality control module");
+    info!("Quality control module initialized successfully");
+    Ok(())
+}
+
+/// Rule ID of system suitability alerts
+pub const QC_ALERT_RULE: &str = "system-suitability";
+
+/// Why a compound is in the QC panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QcRole {
+    /// Compound of a pooled or reference QC sample
+    QualityControl,
+
+    /// Internal standard spiked into every sample
+    InternalStandard,
+}
+
+/// A compound every run is expected to identify
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QcCompound {
+    /// Molecule ID the compound's evidence is recorded under
+    pub molecule_id: String,
+
+    /// Role of the compound
+    pub role: QcRole,
+
+    /// Compound name, for reports
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// The QC compounds of a method
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QcPanel {
+    /// Compounds of the panel
+    pub compounds: Vec<QcCompound>,
+}
+
+impl QcPanel {
+    /// Create a panel from its compounds
+    pub fn new(compounds: Vec<QcCompound>) -> Self {
+        Self { compounds }
+    }
+
+    /// Load a JSON array of QC compounds
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read QC panel: {}", path.display()))?;
+        let compounds: Vec<QcCompound> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse QC panel: {}", path.display()))?;
+        if compounds.is_empty() {
+            return Err(anyhow!("QC panel {} has no compounds", path.display()));
+        }
+        Ok(Self::new(compounds))
+    }
+}
+
+/// System suitability thresholds, and how much evidence of failing runs is discounted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QcThresholds {
+    /// Smallest share of QC compounds a run must detect
+    pub min_detection_rate: f64,
+
+    /// Lowest mean identification confidence of the detected QC compounds
+    pub min_confidence: f64,
+
+    /// Largest absolute mass error of a QC compound, in ppm
+    pub max_ppm_error: f64,
+
+    /// Largest absolute retention time error of a QC compound, in minutes
+    pub max_rt_error: f64,
+
+    /// Weight of evidence from a run with a warning
+    pub warning_weight: f64,
+
+    /// Weight of evidence from a failed run, when it is down-weighted rather than blocked
+    pub failing_weight: f64,
+}
+
+impl Default for QcThresholds {
+    fn default() -> Self {
+        Self {
+            min_detection_rate: 0.8,
+            min_confidence: 0.6,
+            max_ppm_error: 5.0,
+            max_rt_error: 0.3,
+            warning_weight: 0.75,
+            failing_weight: 0.25,
+        }
+    }
+}
+
+impl QcThresholds {
+    /// Check that the thresholds are usable
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [
+            ("min_detection_rate", self.min_detection_rate),
+            ("min_confidence", self.min_confidence),
+            ("warning_weight", self.warning_weight),
+            ("failing_weight", self.failing_weight),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(anyhow!("{} must be between 0 and 1, got {}", name, value));
+            }
+        }
+        if self.max_ppm_error <= 0.0 || self.max_rt_error <= 0.0 {
+            return Err(anyhow!("QC mass and retention time tolerances must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a run's system suitability check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QcStatus {
+    /// Every threshold met
+    Pass,
+
+    /// QC compounds found, but with mass or retention time errors out of tolerance
+    Warning,
+
+    /// Too few QC compounds detected, or identified with too little confidence
+    Fail,
+}
+
+impl fmt::Display for QcStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QcStatus::Pass => write!(f, "pass"),
+            QcStatus::Warning => write!(f, "warning"),
+            QcStatus::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+/// How one QC compound was identified in a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QcCompoundResult {
+    /// Molecule ID of the compound
+    pub molecule_id: String,
+
+    /// Role of the compound
+    pub role: QcRole,
+
+    /// Whether the run has evidence for the compound
+    pub detected: bool,
+
+    /// Highest identification confidence in the run
+    pub confidence: Option<f64>,
+
+    /// Mass error of that identification, in ppm
+    pub ppm_error: Option<f64>,
+
+    /// Retention time error of that identification, in minutes
+    pub rt_error: Option<f64>,
+}
+
+/// System suitability of one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSuitability {
+    /// Run assessed
+    pub run_id: String,
+
+    /// Result per QC compound, in panel order
+    pub compounds: Vec<QcCompoundResult>,
+
+    /// Share of the panel detected
+    pub detection_rate: f64,
+
+    /// Mean confidence of the detected QC compounds
+    pub mean_confidence: Option<f64>,
+
+    /// Largest absolute mass error among the detected QC compounds, in ppm
+    pub max_ppm_error: Option<f64>,
+
+    /// Largest absolute retention time error among the detected QC compounds, in minutes
+    pub max_rt_error: Option<f64>,
+
+    /// Thresholds the run missed
+    pub failures: Vec<String>,
+
+    /// Outcome of the check
+    pub status: QcStatus,
+
+    /// Weight given to the run's evidence during integration (0.0 - 1.0)
+    pub weight: f64,
+}
+
+impl RunSuitability {
+    /// Alert for a run that did not pass, naming the run in place of a molecule
+    pub fn to_alert(&self, project_id: &str) -> Option<Alert> {
+        let severity = match self.status {
+            QcStatus::Pass => return None,
+            QcStatus::Warning => AlertSeverity::Warning,
+            QcStatus::Fail => AlertSeverity::Critical,
+        };
+        Some(Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            rule_id: QC_ALERT_RULE.to_string(),
+            rule_name: "System suitability".to_string(),
+            project_id: project_id.to_string(),
+            molecule_id: self.run_id.clone(),
+            severity,
+            message: format!("run {} {} system suitability: {}", self.run_id, self.status, self.failures.join("; ")),
+            value: self.detection_rate,
+            raised_at: Utc::now(),
+        })
+    }
+}
+
+/// What happens to the evidence of runs that fail QC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QcAction {
+    /// Drop the evidence of failed runs
+    Block,
+
+    /// Keep the evidence, flagged with the run's weight
+    #[default]
+    DownWeight,
+}
+
+impl FromStr for QcAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "block" => Ok(QcAction::Block),
+            "down-weight" | "down_weight" | "downweight" => Ok(QcAction::DownWeight),
+            _ => Err(anyhow!("Unsupported QC action: {}", s)),
+        }
+    }
+}
+
+/// Checks every run against a QC panel
+#[derive(Debug, Clone)]
+pub struct QcEvaluator {
+    /// Compounds every run should identify
+    panel: QcPanel,
+
+    /// System suitability thresholds
+    thresholds: QcThresholds,
+}
+
+impl QcEvaluator {
+    /// Create an evaluator with default thresholds
+    pub fn new(panel: QcPanel) -> Self {
+        Self {
+            panel,
+            thresholds: QcThresholds::default(),
+        }
+    }
+
+    /// Use other thresholds
+    pub fn with_thresholds(mut self, thresholds: QcThresholds) -> Result<Self> {
+        thresholds.validate()?;
+        self.thresholds = thresholds;
+        Ok(self)
+    }
+
+    /// Check the system suitability of every run, ordered by run ID
+    ///
+    /// A QC compound counts as detected when the run has evidence for it; its
+    /// best evidence item gives the confidence and, when the item carries
+    /// them (as feature table matches do), the mass and retention time errors.
+    pub fn evaluate(&self, evidence: &[Evidence]) -> Vec<RunSuitability> {
+        let mut runs: BTreeMap<&str, HashMap<&str, &Evidence>> = BTreeMap::new();
+        for item in evidence {
+            let run_id = match item.metadata.get(RUN_METADATA_KEY).and_then(|v| v.as_str()) {
+                Some(run_id) => run_id,
+                None => continue,
+            };
+            let best = runs.entry(run_id).or_default();
+            if self.panel.compounds.iter().any(|c| c.molecule_id == item.molecule_id) {
+                best.entry(item.molecule_id.as_str())
+                    .and_modify(|current| if item.confidence > current.confidence { *current = item })
+                    .or_insert(item);
+            }
+        }
+
+        runs.into_iter()
+            .map(|(run_id, best)| self.assess(run_id, &best))
+            .collect()
+    }
+
+    fn assess(&self, run_id: &str, best: &HashMap<&str, &Evidence>) -> RunSuitability {
+        let compounds: Vec<QcCompoundResult> = self.panel.compounds.iter()
+            .map(|compound| {
+                let item = best.get(compound.molecule_id.as_str());
+                let number = |key: &str| item.and_then(|e| e.data.get(key)?.as_f64());
+                QcCompoundResult {
+                    molecule_id: compound.molecule_id.clone(),
+                    role: compound.role,
+                    detected: item.is_some(),
+                    confidence: item.map(|e| e.confidence),
+                    ppm_error: number("ppm_error"),
+                    rt_error: number("rt_error"),
+                }
+            })
+            .collect();
+
+        let detected: Vec<&QcCompoundResult> = compounds.iter().filter(|c| c.detected).collect();
+        let detection_rate = if compounds.is_empty() { 1.0 } else { detected.len() as f64 / compounds.len() as f64 };
+        let mean_confidence = (!detected.is_empty())
+            .then(|| detected.iter().filter_map(|c| c.confidence).sum::<f64>() / detected.len() as f64);
+        let max_abs = |values: Vec<f64>| values.into_iter().map(f64::abs).reduce(f64::max);
+        let max_ppm_error = max_abs(detected.iter().filter_map(|c| c.ppm_error).collect());
+        let max_rt_error = max_abs(detected.iter().filter_map(|c| c.rt_error).collect());
+
+        let t = &self.thresholds;
+        let mut failures = Vec::new();
+        let mut status = QcStatus::Pass;
+        if detection_rate < t.min_detection_rate {
+            failures.push(format!("detected {:.0}% of QC compounds (minimum {:.0}%)", detection_rate * 100.0, t.min_detection_rate * 100.0));
+            status = QcStatus::Fail;
+        }
+        if let Some(confidence) = mean_confidence.filter(|c| *c < t.min_confidence) {
+            failures.push(format!("mean QC confidence {:.2} (minimum {:.2})", confidence, t.min_confidence));
+            status = QcStatus::Fail;
+        }
+        if let Some(error) = max_ppm_error.filter(|e| *e > t.max_ppm_error) {
+            failures.push(format!("QC mass error {:.1} ppm (maximum {:.1})", error, t.max_ppm_error));
+            status = status.max(QcStatus::Warning);
+        }
+        if let Some(error) = max_rt_error.filter(|e| *e > t.max_rt_error) {
+            failures.push(format!("QC retention time error {:.2} min (maximum {:.2})", error, t.max_rt_error));
+            status = status.max(QcStatus::Warning);
+        }
+        if status != QcStatus::Pass {
+            warn!("Run {} did not pass system suitability: {}", run_id, failures.join("; "));
+        }
+
+        RunSuitability {
+            run_id: run_id.to_string(),
+            compounds,
+            detection_rate,
+            mean_confidence,
+            max_ppm_error,
+            max_rt_error,
+            failures,
+            status,
+            weight: match status {
+                QcStatus::Pass => 1.0,
+                QcStatus::Warning => t.warning_weight,
+                QcStatus::Fail => t.failing_weight,
+            },
+        }
+    }
+}
+
+/// Act on the evidence of runs that did not pass, returning how many items were blocked or flagged
+///
+/// Evidence of runs with a warning is always flagged with the run's weight;
+/// evidence of failed runs is dropped under `QcAction::Block`.
+pub fn apply_qc(evidence: &mut Vec<Evidence>, runs: &[RunSuitability], action: QcAction) -> usize {
+    let outcomes: HashMap<&str, &RunSuitability> = runs.iter()
+        .filter(|r| r.status != QcStatus::Pass)
+        .map(|r| (r.run_id.as_str(), r))
+        .collect();
+    let run_of = |item: &Evidence| -> Option<&RunSuitability> {
+        outcomes.get(item.metadata.get(RUN_METADATA_KEY)?.as_str()?).copied()
+    };
+
+    let before = evidence.len();
+    if action == QcAction::Block {
+        evidence.retain(|item| run_of(item).is_none_or(|run| run.status != QcStatus::Fail));
+    }
+    let mut affected = before - evidence.len();
+
+    for item in evidence.iter_mut() {
+        if let Some(run) = run_of(item) {
+            item.metadata.insert(QC_METADATA_KEY.to_string(), serde_json::json!({
+                "run_id": run.run_id,
+                "status": run.status,
+                "weight": run.weight,
+            }));
+            affected += 1;
+        }
+    }
+    affected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    fn item(run_id: &str, molecule_id: &str, confidence: f64, ppm_error: f64) -> Evidence {
+        let mut evidence = Evidence::manual(molecule_id, EvidenceType::MassSpec, confidence, None, "lab").unwrap();
+        evidence.id = format!("{}-{}", run_id, molecule_id);
+        evidence.data = serde_json::json!({ "ppm_error": ppm_error, "rt_error": 0.05 });
+        evidence.metadata.insert(RUN_METADATA_KEY.to_string(), serde_json::json!(run_id));
+        evidence
+    }
+
+    fn panel() -> QcPanel {
+        let compound = |id: &str, role| QcCompound { molecule_id: id.to_string(), role, name: None };
+        QcPanel::new(vec![
+            compound("caffeine-13c3", QcRole::InternalStandard),
+            compound("tryptophan-d5", QcRole::InternalStandard),
+            compound("creatinine", QcRole::QualityControl),
+        ])
+    }
+
+    fn runs() -> Vec<Evidence> {
+        vec![
+            // r1 passes
+            item("r1", "caffeine-13c3", 0.95, 1.2),
+            item("r1", "tryptophan-d5", 0.9, -2.0),
+            item("r1", "creatinine", 0.85, 0.4),
+            item("r1", "glucose", 0.7, 1.0),
+            // r2 finds everything, but with a mass error out of tolerance
+            item("r2", "caffeine-13c3", 0.9, 8.5),
+            item("r2", "tryptophan-d5", 0.9, 1.0),
+            item("r2", "creatinine", 0.8, 0.5),
+            item("r2", "glucose", 0.7, 1.0),
+            // r3 misses two of three
+            item("r3", "caffeine-13c3", 0.4, 1.0),
+            item("r3", "glucose", 0.7, 1.0),
+        ]
+    }
+
+    #[test]
+    fn test_evaluates_system_suitability_per_run() {
+        let results = QcEvaluator::new(panel()).evaluate(&runs());
+        let statuses: Vec<(&str, QcStatus)> = results.iter().map(|r| (r.run_id.as_str(), r.status)).collect();
+        assert_eq!(statuses, vec![("r1", QcStatus::Pass), ("r2", QcStatus::Warning), ("r3", QcStatus::Fail)]);
+
+        assert_eq!(results[1].max_ppm_error, Some(8.5));
+        assert!((results[2].detection_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(results[2].failures.len(), 2);
+        assert!(results[0].to_alert("default").is_none());
+        assert_eq!(results[2].to_alert("default").unwrap().severity, AlertSeverity::Critical);
+    }
+
+    #[test]
+    fn test_blocks_or_down_weights_failed_runs() {
+        let results = QcEvaluator::new(panel()).evaluate(&runs());
+
+        let mut blocked = runs();
+        assert_eq!(apply_qc(&mut blocked, &results, QcAction::Block), 6);
+        assert_eq!(blocked.len(), 8);
+        assert_eq!(blocked.iter().find(|e| e.id == "r2-glucose").unwrap().qc_weight(), 0.75);
+        assert_eq!(blocked[0].qc_weight(), 1.0);
+
+        let mut weighted = runs();
+        assert_eq!(apply_qc(&mut weighted, &results, QcAction::DownWeight), 6);
+        assert_eq!(weighted.iter().find(|e| e.id == "r3-glucose").unwrap().qc_weight(), 0.25);
+    }
+}
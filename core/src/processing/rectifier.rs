@@ -9,9 +9,15 @@ use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::cancellation::{self, CancellationToken};
 use crate::graph::neo4j::Neo4jClient;
-use crate::metacognition::llm::LLMClient;
+use crate::graph::store::GraphStore;
+use crate::metacognition::llm::CompletionModel;
+use crate::offline::{self, NetworkFeature};
 use crate::processing::evidence::{Evidence, IntegratedEvidence, EvidenceType};
+use crate::projects::DEFAULT_PROJECT;
+use crate::processing::prompt_budget::{estimate_tokens, truncate_chars, truncate_words, PromptBudget};
+use crate::processing::time_budget::{AnalysisStage, BudgetClock, TimeBudget};
 
 /// Initialize the evidence rectifier module
 pub fn initialize() -> Result<()> {
@@ -59,6 +65,51 @@ pub struct RectificationResult {
     
     /// Timestamp of rectification
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    
+    /// Whether the adjustments were only proposed
+    #[serde(default)]
+    pub dry_run: bool,
+    
+    /// Evidence with the adjustments applied; `None` for a dry run or a
+    /// cancelled rectification
+    #[serde(default)]
+    pub applied: Option<IntegratedEvidence>,
+    
+    /// Whether the request was cancelled before every strategy ran
+    ///
+    /// The rectified evidence then reflects only `strategies_used`.
+    #[serde(default)]
+    pub cancelled: bool,
+    
+    /// Stages left out to stay within the time budget
+    #[serde(default)]
+    pub skipped_stages: Vec<AnalysisStage>,
+}
+
+impl RectificationResult {
+    /// Items whose confidence the rectification changes
+    pub fn adjustments(&self) -> impl Iterator<Item = &RectifiedEvidence> {
+        self.rectified_evidence.iter()
+            .filter(|re| (re.rectified_confidence - re.original_confidence).abs() > f64::EPSILON)
+    }
+    
+    /// The original evidence with every rectified confidence written back
+    ///
+    /// The aggregate confidence becomes the mean of the rectified confidences.
+    fn apply(&self) -> IntegratedEvidence {
+        let mut applied = self.original_evidence.clone();
+        for item in &mut applied.evidence_items {
+            if let Some(re) = self.rectified_evidence.iter().find(|re| re.original_id == item.id) {
+                item.confidence = re.rectified_confidence;
+            }
+        }
+        if !self.rectified_evidence.is_empty() {
+            applied.aggregate_confidence = self.rectified_evidence.iter()
+                .map(|re| re.rectified_confidence)
+                .sum::<f64>() / self.rectified_evidence.len() as f64;
+        }
+        applied
+    }
 }
 
 /// Rectified evidence item
@@ -100,6 +151,26 @@ pub struct RectificationOptions {
     
     /// Whether to use interactome analysis
     pub use_interactome_analysis: bool,
+    
+    /// Per-source reliability weights applied to corroborating evidence
+    #[serde(default)]
+    pub source_weights: HashMap<String, f64>,
+    
+    /// Size limits for the AI-guided rectification prompt
+    #[serde(default)]
+    pub prompt_budget: PromptBudget,
+    
+    /// Project whose graph is consulted for pathways and interactions
+    #[serde(default = "default_project")]
+    pub project_id: String,
+    
+    /// Time allowed per molecule; the LLM and graph lookups are skipped once it runs short
+    #[serde(default)]
+    pub time_budget: Option<TimeBudget>,
+}
+
+fn default_project() -> String {
+    DEFAULT_PROJECT.to_string()
 }
 
 impl Default for RectificationOptions {
@@ -114,6 +185,10 @@ impl Default for RectificationOptions {
             min_original_confidence: 0.2,
             use_pathway_analysis: true,
             use_interactome_analysis: true,
+            source_weights: HashMap::new(),
+            prompt_budget: PromptBudget::default(),
+            project_id: default_project(),
+            time_budget: None,
         }
     }
 }
@@ -123,11 +198,11 @@ pub struct EvidenceRectifier {
     /// Options for rectification
     options: RectificationOptions,
     
-    /// Neo4j client for graph database operations
-    neo4j_client: Option<Arc<Neo4jClient>>,
+    /// Graph store for pathway and interaction lookups
+    graph_store: Option<Arc<dyn GraphStore>>,
     
     /// LLM client for AI-guided rectification
-    llm_client: Option<Arc<LLMClient>>,
+    llm_client: Option<Arc<dyn CompletionModel>>,
 }
 
 impl EvidenceRectifier {
@@ -135,7 +210,7 @@ impl EvidenceRectifier {
     pub fn new(options: RectificationOptions) -> Self {
         Self {
             options,
-            neo4j_client: None,
+            graph_store: None,
             llm_client: None,
         }
     }
@@ -147,19 +222,60 @@ impl EvidenceRectifier {
     
     /// Set the Neo4j client for database operations
     pub fn with_neo4j_client(mut self, client: Arc<Neo4jClient>) -> Self {
-        self.neo4j_client = Some(client);
+        self.graph_store = Some(client);
+        self
+    }
+    
+    /// Set the graph store for database operations, whichever backend it uses
+    pub fn with_graph_store(mut self, store: Arc<dyn GraphStore>) -> Self {
+        self.graph_store = Some(store);
         self
     }
     
     /// Set the LLM client for AI-guided rectification
-    pub fn with_llm_client(mut self, client: Arc<LLMClient>) -> Self {
+    pub fn with_llm_client(mut self, client: Arc<dyn CompletionModel>) -> Self {
         self.llm_client = Some(client);
         self
     }
     
+    /// Set per-source reliability weights, e.g. from a `ReliabilityTracker`
+    pub fn with_source_weights(mut self, weights: HashMap<String, f64>) -> Self {
+        self.options.source_weights = weights;
+        self
+    }
+    
+    /// Replace the per-source reliability weights, e.g. after the tracker learned from a review
+    pub fn set_source_weights(&mut self, weights: HashMap<String, f64>) {
+        self.options.source_weights = weights;
+    }
+    
+    /// Set the size limits for AI-guided rectification prompts
+    pub fn with_prompt_budget(mut self, budget: PromptBudget) -> Self {
+        self.options.prompt_budget = budget;
+        self
+    }
+    
+    /// Bound the time spent rectifying each molecule
+    pub fn with_time_budget(mut self, budget: TimeBudget) -> Self {
+        self.options.time_budget = Some(budget);
+        self
+    }
+    
+    /// Options the rectifier was configured with
+    pub fn options(&self) -> &RectificationOptions {
+        &self.options
+    }
+    
     /// Rectify the evidence for a molecule
-    pub async fn rectify(&self, evidence: IntegratedEvidence) -> Result<RectificationResult> {
-        debug!("Rectifying evidence for molecule {}", evidence.molecule_id);
+    ///
+    /// A dry run computes the same adjustments and reasoning but leaves
+    /// `applied` empty, so the proposal can be reviewed before it takes effect.
+    ///
+    /// Cancelling `cancel` aborts the LLM or Neo4j call in progress. The
+    /// result then keeps the adjustments made so far, marked as cancelled, and
+    /// nothing is applied.
+    pub async fn rectify(&self, evidence: IntegratedEvidence, dry_run: bool, cancel: &CancellationToken) -> Result<RectificationResult> {
+        debug!("Rectifying evidence for molecule {}{}", evidence.molecule_id, if dry_run { " (dry run)" } else { "" });
         
         // Skip rectification if no evidence items
         if evidence.evidence_items.is_empty() {
@@ -170,11 +286,16 @@ impl EvidenceRectifier {
                 reasoning: vec!["No evidence items to rectify".to_string()],
                 strategies_used: Vec::new(),
                 timestamp: chrono::Utc::now(),
+                dry_run,
+                applied: (!dry_run).then_some(evidence),
+                cancelled: false,
+                skipped_stages: Vec::new(),
             });
         }
         
         // Track strategies used
         let mut strategies_used = Vec::new();
+        let mut clock = BudgetClock::start(self.options.time_budget.as_ref(), &self.planned_stages());
         
         // Initial rectification using consensus strategy if enabled
         let mut rectified_evidence = if self.options.strategies.contains(&RectificationStrategy::Consensus) {
@@ -194,32 +315,15 @@ impl EvidenceRectifier {
                 .collect()
         };
         
-        // Apply AI-guided strategy if enabled
-        if self.options.strategies.contains(&RectificationStrategy::AIGuided) {
-            if let Some(llm_client) = &self.llm_client {
-                strategies_used.push(RectificationStrategy::AIGuided);
-                self.apply_ai_guided_strategy(llm_client, &evidence, &mut rectified_evidence).await?;
-            } else {
-                warn!("AI-guided strategy enabled but no LLM client provided");
-            }
-        }
-        
-        // Apply pathway-based strategy if enabled
-        if self.options.strategies.contains(&RectificationStrategy::PathwayBased) && self.options.use_pathway_analysis {
-            if let Some(neo4j_client) = &self.neo4j_client {
-                strategies_used.push(RectificationStrategy::PathwayBased);
-                self.apply_pathway_strategy(neo4j_client, &evidence, &mut rectified_evidence).await?;
-            } else {
-                warn!("Pathway-based strategy enabled but no Neo4j client provided");
+        // Apply the strategies that call out to the LLM and Neo4j
+        let cancelled = match self.apply_external_strategies(&evidence, &mut rectified_evidence, &mut strategies_used, &mut clock, cancel).await {
+            Ok(()) => false,
+            Err(e) if cancellation::is_cancelled(&e) => {
+                info!("Rectification of molecule {} stopped early: {}", evidence.molecule_id, e);
+                true
             }
-        }
-        
-        // Apply interactome-based adjustments if enabled
-        if self.options.use_interactome_analysis {
-            if let Some(neo4j_client) = &self.neo4j_client {
-                self.apply_interactome_adjustments(neo4j_client, &evidence.molecule_id, &mut rectified_evidence).await?;
-            }
-        }
+            Err(e) => return Err(e),
+        };
         
         // Calculate overall confidence improvement
         let original_avg_confidence = evidence.evidence_items.iter()
@@ -233,21 +337,109 @@ impl EvidenceRectifier {
         let confidence_improvement = rectified_avg_confidence - original_avg_confidence;
         
         // Generate reasoning for rectification
-        let reasoning = self.generate_rectification_reasoning(&evidence, &rectified_evidence, &strategies_used)?;
+        let mut reasoning = self.generate_rectification_reasoning(&evidence, &rectified_evidence, &strategies_used)?;
+        if cancelled {
+            reasoning.push("Rectification was cancelled; remaining strategies were not applied".to_string());
+        }
+        let skipped_stages = clock.into_skipped();
+        if !skipped_stages.is_empty() {
+            let names: Vec<String> = skipped_stages.iter().map(|s| s.to_string()).collect();
+            reasoning.push(format!("Skipped to stay within the time budget: {}", names.join(", ")));
+        }
         
         // Create result
-        let result = RectificationResult {
+        let mut result = RectificationResult {
             original_evidence: evidence,
             rectified_evidence,
             confidence_improvement,
             reasoning,
             strategies_used,
             timestamp: chrono::Utc::now(),
+            dry_run,
+            applied: None,
+            cancelled,
+            skipped_stages,
         };
+        if !dry_run && !cancelled {
+            result.applied = Some(result.apply());
+        }
         
         Ok(result)
     }
     
+    /// Apply the AI-guided, pathway and interactome steps that are enabled
+    ///
+    /// A strategy is recorded in `strategies_used` once it has been applied, so
+    /// after a cancellation it lists exactly the completed ones. Steps the
+    /// clock does not admit are skipped and left for it to report.
+    async fn apply_external_strategies(
+        &self,
+        evidence: &IntegratedEvidence,
+        rectified_evidence: &mut Vec<RectifiedEvidence>,
+        strategies_used: &mut Vec<RectificationStrategy>,
+        clock: &mut BudgetClock,
+        cancel: &CancellationToken,
+    ) -> Result<()> {
+        // Apply AI-guided strategy if enabled
+        if self.options.strategies.contains(&RectificationStrategy::AIGuided) {
+            if offline::is_offline() {
+                info!("AI-guided strategy skipped: {} is unavailable in offline mode", NetworkFeature::Llm);
+            } else if let Some(llm_client) = &self.llm_client {
+                if clock.admit(AnalysisStage::Llm) {
+                    cancellation::run(cancel, "AI-guided rectification",
+                        self.apply_ai_guided_strategy(llm_client.as_ref(), evidence, rectified_evidence)).await?;
+                    strategies_used.push(RectificationStrategy::AIGuided);
+                } else {
+                    info!("AI-guided strategy skipped for {}: time budget exhausted", evidence.molecule_id);
+                }
+            } else {
+                warn!("AI-guided strategy enabled but no LLM client provided");
+            }
+        }
+        
+        // Apply pathway-based strategy if enabled
+        if self.options.strategies.contains(&RectificationStrategy::PathwayBased) && self.options.use_pathway_analysis {
+            if let Some(graph_store) = &self.graph_store {
+                if clock.admit(AnalysisStage::Pathways) {
+                    cancellation::run(cancel, "Pathway-based rectification",
+                        self.apply_pathway_strategy(graph_store.as_ref(), evidence, rectified_evidence)).await?;
+                    strategies_used.push(RectificationStrategy::PathwayBased);
+                }
+            } else {
+                warn!("Pathway-based strategy enabled but no graph store provided");
+            }
+        }
+        
+        // Apply interactome-based adjustments if enabled
+        if self.options.use_interactome_analysis {
+            if let Some(graph_store) = &self.graph_store {
+                if clock.admit(AnalysisStage::Interactions) {
+                    cancellation::run(cancel, "Interactome adjustment",
+                        self.apply_interactome_adjustments(graph_store.as_ref(), &evidence.molecule_id, rectified_evidence)).await?;
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Budgeted stages the enabled strategies would run, given the clients available
+    fn planned_stages(&self) -> Vec<AnalysisStage> {
+        let mut planned = Vec::new();
+        if self.options.strategies.contains(&RectificationStrategy::AIGuided) && self.llm_client.is_some() && !offline::is_offline() {
+            planned.push(AnalysisStage::Llm);
+        }
+        if self.graph_store.is_some() {
+            if self.options.strategies.contains(&RectificationStrategy::PathwayBased) && self.options.use_pathway_analysis {
+                planned.push(AnalysisStage::Pathways);
+            }
+            if self.options.use_interactome_analysis {
+                planned.push(AnalysisStage::Interactions);
+            }
+        }
+        planned
+    }
+    
     /// Apply consensus strategy for rectification
     fn apply_consensus_strategy(&self, evidence: &IntegratedEvidence) -> Result<Vec<RectifiedEvidence>> {
         debug!("Applying consensus strategy for rectification");
@@ -279,7 +471,7 @@ impl EvidenceRectifier {
                 // increase confidence proportionally to that evidence's confidence
                 if !corr_evidence.is_empty() {
                     let corr_confidence = corr_evidence.iter()
-                        .map(|e| e.confidence)
+                        .map(|e| e.confidence * self.options.source_weights.get(&e.source).copied().unwrap_or(1.0))
                         .fold(0.0, f64::max);
                     
                     // Smaller boost for each corroborating type
@@ -323,17 +515,17 @@ impl EvidenceRectifier {
     /// Apply AI-guided strategy for rectification
     async fn apply_ai_guided_strategy(
         &self,
-        llm_client: &LLMClient,
+        llm_client: &dyn CompletionModel,
         evidence: &IntegratedEvidence,
         rectified_evidence: &mut Vec<RectifiedEvidence>,
     ) -> Result<()> {
         debug!("Applying AI-guided strategy for rectification");
         
         // Create a prompt for the LLM to analyze the evidence
-        let prompt = self.create_llm_prompt(evidence)?;
+        let prompt = self.budgeted_llm_prompt(llm_client, evidence).await?;
         
         // Get LLM response
-        let llm_response = llm_client.generate_completion(&prompt).await
+        let llm_response = llm_client.complete_prompt(&prompt).await
             .context("Failed to get LLM response for evidence rectification")?;
         
         // Parse the LLM response to extract confidence adjustments
@@ -364,24 +556,16 @@ impl EvidenceRectifier {
     /// Apply pathway-based strategy for rectification
     async fn apply_pathway_strategy(
         &self,
-        neo4j_client: &Neo4jClient,
+        graph_store: &dyn GraphStore,
         evidence: &IntegratedEvidence,
         rectified_evidence: &mut Vec<RectifiedEvidence>,
     ) -> Result<()> {
         debug!("Applying pathway-based strategy for rectification");
         
-        // Query Neo4j for pathway information about the molecule
+        // Query the graph store for pathway information about the molecule
         let molecule_id = &evidence.molecule_id;
-        let pathway_query = format!(
-            "MATCH (m:Molecule {{id: '{}'}})-[:PARTICIPATES_IN]->(p:Pathway)
-             MATCH (p)<-[:PARTICIPATES_IN]-(other:Molecule)
-             RETURN p.id AS pathway_id, p.name AS pathway_name, 
-                    COUNT(other) AS molecule_count",
-            molecule_id
-        );
-        
-        let pathway_results = neo4j_client.execute_query(&pathway_query).await
-            .context("Failed to query pathways from Neo4j")?;
+        let pathway_results = graph_store.molecule_pathways(&self.options.project_id, molecule_id).await
+            .context("Failed to query pathways from the graph store")?;
         
         if pathway_results.is_empty() {
             debug!("No pathway information found for molecule {}", molecule_id);
@@ -399,9 +583,7 @@ impl EvidenceRectifier {
             
             // Update reason
             let pathway_names: Vec<String> = pathway_results.iter()
-                .filter_map(|row| {
-                    row.get::<String>("pathway_name").ok()
-                })
+                .filter_map(|pathway| pathway.name.clone())
                 .take(3)
                 .collect();
             
@@ -422,21 +604,18 @@ impl EvidenceRectifier {
     /// Apply interactome-based adjustments
     async fn apply_interactome_adjustments(
         &self,
-        neo4j_client: &Neo4jClient,
+        graph_store: &dyn GraphStore,
         molecule_id: &str,
         rectified_evidence: &mut Vec<RectifiedEvidence>,
     ) -> Result<()> {
         debug!("Applying interactome-based adjustments for molecule {}", molecule_id);
         
-        // Query Neo4j for interaction information
-        let interaction_query = format!(
-            "MATCH (m:Molecule {{id: '{}'}})-[r:INTERACTS_WITH]-(other:Molecule)
-             RETURN type(r) AS interaction_type, COUNT(other) AS interaction_count",
-            molecule_id
-        );
-        
-        let interaction_results = neo4j_client.execute_query(&interaction_query).await
-            .context("Failed to query interactions from Neo4j")?;
+        // Query the graph store for interaction information
+        let interaction_results: Vec<_> = graph_store.molecule_interactions(&self.options.project_id, molecule_id).await
+            .context("Failed to query interactions from the graph store")?
+            .into_iter()
+            .filter(|interaction| interaction.interaction_type == "INTERACTS_WITH")
+            .collect();
         
         if interaction_results.is_empty() {
             debug!("No interaction information found for molecule {}", molecule_id);
@@ -446,9 +625,7 @@ impl EvidenceRectifier {
         // Apply confidence adjustments based on interaction network
         for rect_ev in rectified_evidence.iter_mut() {
             // Higher confidence for molecules with more interactions
-            let total_interactions: i64 = interaction_results.iter()
-                .filter_map(|row| row.get::<i64>("interaction_count").ok())
-                .sum();
+            let total_interactions = interaction_results.len();
             
             // Apply boost based on interaction count
             let interaction_boost = (0.005 * total_interactions as f64).min(0.1);
@@ -465,8 +642,53 @@ impl EvidenceRectifier {
         Ok(())
     }
     
+    /// Create the AI-guided prompt within the prompt budget
+    ///
+    /// When the full listing is over budget, the highest-priority items are
+    /// kept with truncated data and the rest are summarized chunk by chunk,
+    /// one extra LLM call per chunk.
+    async fn budgeted_llm_prompt(&self, llm_client: &dyn CompletionModel, evidence: &IntegratedEvidence) -> Result<String> {
+        let budget = &self.options.prompt_budget;
+        let all: Vec<usize> = (0..evidence.evidence_items.len()).collect();
+        let prompt = self.create_llm_prompt(evidence, &all, None, &[]);
+        if budget.fits(&prompt) {
+            return Ok(prompt);
+        }
+        
+        let rendered: Vec<String> = evidence.evidence_items.iter().enumerate()
+            .map(|(i, ev)| format_evidence_item(i, ev, Some(budget.max_data_chars)))
+            .collect();
+        let fixed_tokens = estimate_tokens(&self.create_llm_prompt(evidence, &[], None, &[])) + budget.summary_tokens;
+        let selection = budget.select(evidence, &rendered, fixed_tokens);
+        
+        let chunks = budget.chunks(selection.omitted.iter().map(|&i| rendered[i].as_str()));
+        let max_words = budget.summary_words(chunks.len());
+        let mut summaries = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            let summary = llm_client.complete_prompt(&budget.summary_prompt(&evidence.molecule_id, chunk, max_words)).await
+                .context("Failed to get LLM summary of evidence chunk")?;
+            summaries.push(truncate_words(&summary, max_words));
+        }
+        
+        info!(
+            "Prompt for molecule {} over budget ({} tokens): kept {} of {} evidence items, summarized {} in {} chunks",
+            evidence.molecule_id, estimate_tokens(&prompt), selection.kept.len(),
+            evidence.evidence_items.len(), selection.omitted.len(), chunks.len()
+        );
+        Ok(self.create_llm_prompt(evidence, &selection.kept, Some(budget.max_data_chars), &summaries))
+    }
+    
     /// Create a prompt for the LLM to analyze evidence
-    fn create_llm_prompt(&self, evidence: &IntegratedEvidence) -> Result<String> {
+    ///
+    /// Lists the evidence items at `items`, with their data cut to
+    /// `max_data_chars` when given, followed by summaries of the rest.
+    fn create_llm_prompt(
+        &self,
+        evidence: &IntegratedEvidence,
+        items: &[usize],
+        max_data_chars: Option<usize>,
+        summaries: &[String],
+    ) -> String {
         let mut prompt = format!(
             "Analyze the molecular evidence for molecule ID '{}' and suggest confidence adjustments.\n\n",
             evidence.molecule_id
@@ -475,12 +697,20 @@ impl EvidenceRectifier {
         // Add evidence items to the prompt
         prompt.push_str("Evidence items:\n");
         
-        for (i, ev) in evidence.evidence_items.iter().enumerate() {
+        for &i in items {
+            prompt.push_str(&format_evidence_item(i, &evidence.evidence_items[i], max_data_chars));
+        }
+        
+        // Add summaries of the items that did not fit
+        if !summaries.is_empty() {
             prompt.push_str(&format!(
-                "{}. ID: {}, Type: {}, Source: {}, Confidence: {:.2}\n   Data: {}\n\n",
-                i + 1, ev.id, ev.evidence_type, ev.source, ev.confidence, 
-                serde_json::to_string_pretty(&ev.data).unwrap_or_default()
+                "Summaries of the other {} evidence items:\n",
+                evidence.evidence_items.len() - items.len()
             ));
+            for summary in summaries {
+                prompt.push_str(&format!("- {}\n", summary));
+            }
+            prompt.push('\n');
         }
         
         // Add conflicts if any
@@ -503,7 +733,7 @@ impl EvidenceRectifier {
         prompt.push_str("Format your response as follows for each evidence item:\n");
         prompt.push_str("Evidence ID: <id>\nAdjustment: <value>\nReason: <reason>\n\n");
         
-        Ok(prompt)
+        prompt
     }
     
     /// Parse the LLM response to extract confidence adjustments
@@ -628,6 +858,20 @@ impl EvidenceRectifier {
     }
 }
 
+/// One evidence item as listed in the rectification prompt
+///
+/// Data is pretty-printed in full, or compacted and cut to `max_data_chars`.
+fn format_evidence_item(index: usize, ev: &Evidence, max_data_chars: Option<usize>) -> String {
+    let data = match max_data_chars {
+        Some(max_chars) => truncate_chars(&serde_json::to_string(&ev.data).unwrap_or_default(), max_chars),
+        None => serde_json::to_string_pretty(&ev.data).unwrap_or_default(),
+    };
+    format!(
+        "{}. ID: {}, Type: {}, Source: {}, Confidence: {:.2}\n   Data: {}\n\n",
+        index + 1, ev.id, ev.evidence_type, ev.source, ev.confidence, data
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -645,4 +889,106 @@ mod tests {
         assert!(options.max_confidence_improvement <= 0.5);
         assert!(options.use_pathway_analysis);
     }
+    
+    #[tokio::test]
+    async fn test_dry_run_proposes_without_applying() {
+        let item = |id: &str, evidence_type, confidence| Evidence {
+            id: id.to_string(),
+            molecule_id: "glucose".to_string(),
+            evidence_type,
+            source: "lab".to_string(),
+            confidence,
+            data: serde_json::Value::Null,
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        };
+        let integrated = IntegratedEvidence {
+            molecule_id: "glucose".to_string(),
+            evidence_items: vec![item("ms", EvidenceType::MassSpec, 0.6), item("nmr", EvidenceType::Structural, 0.8)],
+            aggregate_confidence: 0.7,
+            conflicts: Vec::new(),
+            integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        };
+        let rectifier = EvidenceRectifier::new(RectificationOptions {
+            strategies: vec![RectificationStrategy::Consensus],
+            ..RectificationOptions::default()
+        });
+        
+        let cancel = CancellationToken::new();
+        let proposal = rectifier.rectify(integrated.clone(), true, &cancel).await.unwrap();
+        assert!(proposal.dry_run);
+        assert!(proposal.applied.is_none());
+        assert_eq!(proposal.adjustments().count(), 2);
+        assert_eq!(proposal.original_evidence.evidence_items[0].confidence, 0.6);
+        
+        let result = rectifier.rectify(integrated, false, &cancel).await.unwrap();
+        let applied = result.applied.unwrap();
+        assert_eq!(applied.evidence_items[0].confidence, proposal.rectified_evidence[0].rectified_confidence);
+        assert!(applied.aggregate_confidence > 0.7);
+    }
+    
+    /// Completion model that records its prompts and answers them from a script
+    #[derive(Default)]
+    struct StubModel {
+        prompts: std::sync::Mutex<Vec<String>>,
+    }
+    
+    #[async_trait::async_trait]
+    impl CompletionModel for StubModel {
+        async fn complete_prompt(&self, prompt: &str) -> Result<String> {
+            self.prompts.lock().unwrap().push(prompt.to_string());
+            if prompt.starts_with("Summarize") {
+                Ok("Confidences agree; nothing stands out.".to_string())
+            } else {
+                Ok("Evidence ID: ev-0\nAdjustment: 0.1\nReason: Matches the reference standard".to_string())
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_ai_guided_prompt_is_summarized_within_budget() {
+        let evidence_items = (0..40)
+            .map(|i| Evidence {
+                id: format!("ev-{}", i),
+                molecule_id: "glucose".to_string(),
+                evidence_type: EvidenceType::MassSpec,
+                source: "lab".to_string(),
+                confidence: 0.5,
+                data: serde_json::json!({ "spectrum": "x".repeat(2000) }),
+                metadata: HashMap::new(),
+                timestamp: chrono::Utc::now(),
+            })
+            .collect();
+        let integrated = IntegratedEvidence {
+            molecule_id: "glucose".to_string(),
+            evidence_items,
+            aggregate_confidence: 0.5,
+            conflicts: Vec::new(),
+            integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        };
+        let budget = PromptBudget { max_prompt_tokens: 800, max_data_chars: 50, summary_tokens: 200 };
+        let model = Arc::new(StubModel::default());
+        let rectifier = EvidenceRectifier::new(RectificationOptions {
+            strategies: vec![RectificationStrategy::AIGuided],
+            ..RectificationOptions::default()
+        })
+        .with_prompt_budget(budget.clone())
+        .with_llm_client(model.clone());
+        
+        let result = rectifier.rectify(integrated, false, &CancellationToken::new()).await.unwrap();
+        assert_eq!(result.strategies_used, vec![RectificationStrategy::AIGuided]);
+        assert!((result.rectified_evidence[0].rectified_confidence - 0.6).abs() < 1e-9);
+        
+        let prompts = model.prompts.lock().unwrap();
+        let (analysis, summaries) = prompts.split_last().unwrap();
+        assert!(!summaries.is_empty());
+        assert!(summaries.iter().all(|p| p.starts_with("Summarize")));
+        assert!(analysis.contains("Summaries of the other"));
+        assert!(analysis.contains("nothing stands out"));
+        assert!(budget.fits(analysis));
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,265 @@
+//! Scheduled Re-evaluation Module
+//!
+//! Evidence loses reliability as it ages, so identity conclusions go stale.
+//! This module periodically re-integrates the evidence of molecules whose
+//! average temporal decay has crossed one of a set of thresholds, records the
+//! new confidence in the versioned evidence store and optionally passes the
+//! changes to a notifier such as the webhook dispatcher.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::processing::evidence::Evidence;
+use crate::processing::pipeline::IdentityPipeline;
+use crate::processing::versioning::{ConfidenceTrigger, VersionedEvidenceStore};
+
+/// Initialize the re-evaluation module
+pub fn initialize() -> Result<()> {
+    info!("Initializing re-evaluation module");
+    info!("Re-evaluation module initialized successfully");
+    Ok(())
+}
+
+/// Options for scheduled re-evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReevaluationOptions {
+    /// Seconds between re-evaluation passes
+    pub interval_secs: u64,
+
+    /// Time constant of the exponential evidence decay, in days
+    pub decay_time_constant_days: f64,
+
+    /// Decay levels that trigger re-evaluation when a molecule's average decay falls below them
+    pub decay_thresholds: Vec<f64>,
+
+    /// Smallest confidence change worth recording
+    pub min_confidence_change: f64,
+}
+
+impl Default for ReevaluationOptions {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3600,
+            decay_time_constant_days: 30.0,
+            decay_thresholds: vec![0.75, 0.5, 0.25],
+            min_confidence_change: 0.01,
+        }
+    }
+}
+
+impl ReevaluationOptions {
+    /// Read options from the environment, falling back to defaults
+    ///
+    /// `HEGEL_REEVALUATION_INTERVAL_SECS` sets the interval and
+    /// `HEGEL_REEVALUATION_THRESHOLDS` a comma-separated list of decay levels.
+    pub fn from_env() -> Self {
+        let mut options = Self::default();
+        if let Some(interval) = std::env::var("HEGEL_REEVALUATION_INTERVAL_SECS").ok().and_then(|v| v.parse().ok()) {
+            options.interval_secs = interval;
+        }
+        if let Ok(thresholds) = std::env::var("HEGEL_REEVALUATION_THRESHOLDS") {
+            let parsed: Vec<f64> = thresholds.split(',').filter_map(|t| t.trim().parse().ok()).collect();
+            if !parsed.is_empty() {
+                options.decay_thresholds = parsed;
+            }
+        }
+        options
+    }
+}
+
+/// Decay factor of evidence recorded at `timestamp` (1.0 when fresh)
+pub fn temporal_decay(timestamp: DateTime<Utc>, now: DateTime<Utc>, time_constant_days: f64) -> f64 {
+    let age_days = (now - timestamp).num_seconds().max(0) as f64 / 86_400.0;
+    (-age_days / time_constant_days).exp()
+}
+
+/// A confidence change produced by re-evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReevaluationChange {
+    /// Molecule that was re-evaluated
+    pub molecule_id: String,
+
+    /// Confidence before re-evaluation, if any was recorded
+    pub confidence_before: Option<f64>,
+
+    /// Confidence after applying decay
+    pub confidence_after: f64,
+
+    /// Average decay factor of the molecule's evidence
+    pub average_decay: f64,
+
+    /// Revision under which the new confidence was recorded
+    pub revision: u64,
+
+    /// When the re-evaluation ran
+    pub evaluated_at: DateTime<Utc>,
+}
+
+/// Receiver of re-evaluation changes
+#[async_trait]
+pub trait ChangeNotifier: Send + Sync {
+    /// Deliver a batch of changes
+    async fn notify(&self, changes: &[ReevaluationChange]) -> Result<()>;
+}
+
+/// Background scheduler that re-evaluates stale identities
+pub struct ReevaluationScheduler {
+    /// Store holding the evidence and confidence history
+    store: Arc<Mutex<VersionedEvidenceStore>>,
+
+    /// Pipeline used to recompute confidence
+    pipeline: IdentityPipeline,
+
+    /// Scheduling options
+    options: ReevaluationOptions,
+
+    /// Optional receiver of changes
+    notifier: Option<Box<dyn ChangeNotifier>>,
+
+    /// Number of thresholds each molecule had crossed at its last evaluation
+    crossed: HashMap<String, usize>,
+}
+
+impl ReevaluationScheduler {
+    /// Create a scheduler over the given store
+    pub fn new(store: Arc<Mutex<VersionedEvidenceStore>>, options: ReevaluationOptions) -> Self {
+        Self {
+            store,
+            pipeline: IdentityPipeline::new(),
+            options,
+            notifier: None,
+            crossed: HashMap::new(),
+        }
+    }
+
+    /// Recompute confidence with the given pipeline
+    pub fn with_pipeline(mut self, pipeline: IdentityPipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Notify a receiver of every batch of changes
+    pub fn with_notifier(mut self, notifier: Box<dyn ChangeNotifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Run one re-evaluation pass as of `now`
+    pub async fn run_once(&mut self, now: DateTime<Utc>) -> Result<Vec<ReevaluationChange>> {
+        let mut changes = Vec::new();
+        let mut store = self.store.lock().await;
+
+        for molecule_id in store.molecule_ids() {
+            let snapshot = store.snapshot_at(&molecule_id, now);
+            if snapshot.evidence.is_empty() {
+                continue;
+            }
+
+            let decays: Vec<f64> = snapshot.evidence.iter()
+                .map(|e| temporal_decay(e.timestamp, now, self.options.decay_time_constant_days))
+                .collect();
+            let average_decay = decays.iter().sum::<f64>() / decays.len() as f64;
+
+            // Only molecules that crossed a new threshold since their last evaluation are stale
+            let crossed = self.options.decay_thresholds.iter().filter(|&&t| average_decay < t).count();
+            let previous = self.crossed.insert(molecule_id.clone(), crossed).unwrap_or(0);
+            if crossed <= previous {
+                continue;
+            }
+
+            let decayed: Vec<Evidence> = snapshot.evidence.iter().zip(&decays)
+                .map(|(e, decay)| Evidence { confidence: e.confidence * decay, ..e.clone() })
+                .collect();
+            let confidence = self.pipeline.posterior_confidence(&decayed)?;
+
+            if let Some(before) = snapshot.confidence {
+                if (confidence - before).abs() < self.options.min_confidence_change {
+                    debug!("Confidence of {} unchanged after re-evaluation", molecule_id);
+                    continue;
+                }
+            }
+
+            let revision = store.record_confidence_at(&molecule_id, confidence, ConfidenceTrigger::Reevaluation, now);
+            info!("Re-evaluated {}: confidence {:?} -> {:.3} (average decay {:.2})",
+                  molecule_id, snapshot.confidence, confidence, average_decay);
+            changes.push(ReevaluationChange {
+                molecule_id,
+                confidence_before: snapshot.confidence,
+                confidence_after: confidence,
+                average_decay,
+                revision,
+                evaluated_at: now,
+            });
+        }
+        if !changes.is_empty() {
+            store.save()?;
+        }
+        drop(store);
+
+        if let (Some(notifier), false) = (&self.notifier, changes.is_empty()) {
+            if let Err(e) = notifier.notify(&changes).await {
+                warn!("Failed to deliver re-evaluation notification: {}", e);
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Re-evaluate on a fixed interval until the task is dropped
+    pub async fn run(mut self) {
+        info!("Re-evaluating stale identities every {} seconds", self.options.interval_secs);
+        let mut interval = tokio::time::interval(Duration::from_secs(self.options.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            match self.run_once(Utc::now()).await {
+                Ok(changes) => debug!("Re-evaluation pass recorded {} changes", changes.len()),
+                Err(e) => warn!("Re-evaluation pass failed: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    #[tokio::test]
+    async fn test_reevaluates_after_threshold_crossed() {
+        let recorded = Utc::now() - chrono::Duration::days(60);
+        let store = Arc::new(Mutex::new(VersionedEvidenceStore::new()));
+        {
+            let mut store = store.lock().await;
+            for (id, confidence) in [("ev-1", 0.9), ("ev-2", 0.8)] {
+                store.record_evidence_at(Evidence {
+                    id: id.to_string(),
+                    molecule_id: "mol-1".to_string(),
+                    evidence_type: EvidenceType::Literature,
+                    source: "hmdb".to_string(),
+                    confidence,
+                    data: serde_json::Value::Null,
+                    metadata: HashMap::new(),
+                    timestamp: recorded,
+                }, recorded);
+            }
+            store.record_confidence_at("mol-1", 0.85, ConfidenceTrigger::Analysis, recorded);
+        }
+
+        let mut scheduler = ReevaluationScheduler::new(store.clone(), ReevaluationOptions::default());
+        let changes = scheduler.run_once(Utc::now()).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].confidence_after < 0.85);
+        assert!((changes[0].average_decay - (-2.0f64).exp()).abs() < 0.01);
+
+        // Nothing new has been crossed on the next pass
+        assert!(scheduler.run_once(Utc::now()).await.unwrap().is_empty());
+        assert_eq!(store.lock().await.confidence_history("mol-1").len(), 2);
+    }
+}
@@ -0,0 +1,653 @@
+//! In-House Reference Library
+//!
+//! Molecules identified with high confidence are promoted, together with
+//! their best MS/MS spectra, the retention time they were observed at and
+//! their measured CCS values, into a local reference library. The library is
+//! saved to a JSON file and versioned: every promotion that changes it adds a
+//! revision, and each entry records the revision it was added and last
+//! updated in. Because its values were measured on the lab's own methods,
+//! the library takes precedence when features are matched: its compounds,
+//! with their measured retention times, replace compounds of the same ID in
+//! a compound store. MS/MS spectra searched against it become mass spec
+//! evidence weighted by the confidence of the promoted identification.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::processing::evidence::{Evidence, EvidenceType, IntegratedEvidence};
+use crate::processing::features::{CompoundStore, RETENTION_TIME_PROPERTY};
+use crate::processing::ion_mobility::{self, CcsTable};
+use crate::processing::mass_accuracy::ppm_error;
+use crate::processing::spectral::{modified_cosine, MsMsSpectrum, Peak, Spectrum};
+use crate::processing::units::{Quantity, Unit};
+use crate::processing::Molecule;
+
+/// Source prefix of evidence from reference library matches
+pub const REFERENCE_LIBRARY_SOURCE: &str = "reference-library";
+
+/// Initialize the reference library module
+pub fn initialize() -> Result<()> {
+    info!("Initializing reference library module");
+    info!("Reference library module initialized successfully");
+    Ok(())
+}
+
+/// An MS/MS spectrum kept as a reference
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceSpectrum {
+    /// Evidence item the spectrum came from
+    pub evidence_id: String,
+
+    /// Precursor m/z
+    pub precursor_mz: f64,
+
+    /// Precursor adduct, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adduct: Option<String>,
+
+    /// Fragment peaks
+    pub spectrum: Spectrum,
+
+    /// Confidence of the evidence item the spectrum came from
+    pub confidence: f64,
+}
+
+/// A compound of the library with the values measured for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceEntry {
+    /// Structure and properties of the compound
+    pub molecule: Molecule,
+
+    /// Best spectra, most confident first
+    #[serde(default)]
+    pub spectra: Vec<ReferenceSpectrum>,
+
+    /// Retention time observed on the lab's method
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_time: Option<Quantity>,
+
+    /// Measured CCS in Å² by adduct
+    #[serde(default)]
+    pub ccs: BTreeMap<String, f64>,
+
+    /// Aggregate confidence of the identification that was promoted
+    pub confidence: f64,
+
+    /// Evidence items the library values were taken from
+    #[serde(default)]
+    pub evidence_ids: Vec<String>,
+
+    /// Revision the entry was added in
+    pub added_in: u32,
+
+    /// Revision the entry was last updated in
+    pub updated_in: u32,
+}
+
+impl ReferenceEntry {
+    /// The compound with its library retention time as a property, for compound stores
+    pub fn to_molecule(&self) -> Molecule {
+        let mut molecule = self.molecule.clone();
+        if let Some(minutes) = self.retention_time.and_then(|rt| rt.value_in(Unit::Minute).ok()) {
+            molecule.properties.insert(RETENTION_TIME_PROPERTY.to_string(), serde_json::json!(minutes));
+        }
+        molecule
+    }
+}
+
+/// A change to the library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryRevision {
+    /// Revision number, starting at 1
+    pub version: u32,
+
+    /// When the revision was made
+    pub timestamp: DateTime<Utc>,
+
+    /// Molecules added
+    pub added: Vec<String>,
+
+    /// Molecules whose values were updated
+    pub updated: Vec<String>,
+}
+
+/// When an identification is confident enough to become a reference
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionCriteria {
+    /// Lowest aggregate confidence promoted
+    pub min_confidence: f64,
+
+    /// Highest conflict severity tolerated among the molecule's evidence
+    pub max_conflict_severity: f64,
+
+    /// Only promote molecules with an MS/MS spectrum
+    pub require_spectrum: bool,
+
+    /// Most spectra kept per compound
+    pub max_spectra: usize,
+}
+
+impl Default for PromotionCriteria {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.9,
+            max_conflict_severity: 0.5,
+            require_spectrum: false,
+            max_spectra: 3,
+        }
+    }
+}
+
+/// Outcome of a promotion
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromotionReport {
+    /// Library version after the promotion
+    pub version: u32,
+
+    /// Molecules added to the library
+    pub added: Vec<String>,
+
+    /// Molecules already in the library whose values were updated
+    pub updated: Vec<String>,
+
+    /// Molecules not promoted, with the reason
+    pub rejected: Vec<(String, String)>,
+}
+
+/// Options for searching spectra against the library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibrarySearchOptions {
+    /// Precursor tolerance, in ppm or Daltons
+    pub precursor_tolerance: Quantity,
+
+    /// Fragment tolerance in Daltons
+    pub fragment_tolerance: f64,
+
+    /// Exponent applied to intensities before comparing
+    pub intensity_power: f64,
+
+    /// Lowest similarity reported as a hit
+    pub min_score: f64,
+
+    /// Fewest matched fragments for a hit
+    pub min_matched_peaks: usize,
+
+    /// Most hits per query spectrum
+    pub max_hits: usize,
+}
+
+impl Default for LibrarySearchOptions {
+    fn default() -> Self {
+        Self {
+            precursor_tolerance: Quantity::ppm(10.0),
+            fragment_tolerance: 0.02,
+            intensity_power: 0.5,
+            min_score: 0.7,
+            min_matched_peaks: 3,
+            max_hits: 3,
+        }
+    }
+}
+
+/// A library compound matching a query spectrum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryHit {
+    /// Query spectrum ID
+    pub query_id: String,
+
+    /// Library compound ID
+    pub molecule_id: String,
+
+    /// Library compound name
+    pub name: Option<String>,
+
+    /// Modified cosine similarity to the best reference spectrum (0.0 - 1.0)
+    pub score: f64,
+
+    /// Fragments matched
+    pub matched_peaks: usize,
+
+    /// Precursor error in ppm
+    pub precursor_ppm_error: f64,
+
+    /// Adduct of the matched reference spectrum
+    pub adduct: Option<String>,
+
+    /// Confidence of the hit: the similarity weighted by the entry's confidence
+    pub confidence: f64,
+}
+
+/// The lab's reference library, saved to a JSON file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReferenceLibrary {
+    /// Name of the library, used in evidence sources
+    pub name: String,
+
+    /// Current revision number; 0 for an empty library
+    pub version: u32,
+
+    /// Entries by molecule ID
+    entries: BTreeMap<String, ReferenceEntry>,
+
+    /// Revisions, oldest first
+    revisions: Vec<LibraryRevision>,
+
+    /// File the library is saved to; kept in memory only when unset
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl ReferenceLibrary {
+    /// Create an empty library that is not saved
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Load the library saved at a path, or start an empty one saved there
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut library = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read reference library: {}", path.display()))?;
+            serde_json::from_str::<Self>(&content).context("Failed to parse reference library")?
+        } else {
+            Self::new(path.file_stem().and_then(|s| s.to_str()).unwrap_or("reference_library"))
+        };
+        library.path = Some(path);
+        Ok(library)
+    }
+
+    /// Open the library named by `HEGEL_REFERENCE_LIBRARY`, or `reference_library.json`
+    pub fn from_env() -> Result<Self> {
+        Self::open(std::env::var("HEGEL_REFERENCE_LIBRARY").unwrap_or_else(|_| "reference_library.json".to_string()))
+    }
+
+    /// File the library is saved to
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Number of compounds
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the library holds no compounds
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entry of a compound
+    pub fn get(&self, molecule_id: &str) -> Option<&ReferenceEntry> {
+        self.entries.get(molecule_id)
+    }
+
+    /// Entries in molecule ID order
+    pub fn entries(&self) -> impl Iterator<Item = &ReferenceEntry> {
+        self.entries.values()
+    }
+
+    /// Revisions, oldest first
+    pub fn revisions(&self) -> &[LibraryRevision] {
+        &self.revisions
+    }
+
+    /// Promote confident identifications, adding a revision if anything changed
+    ///
+    /// Each identification is given with the molecule's structure. Spectra
+    /// are merged with those already in the library and the most confident
+    /// are kept; retention time and CCS values are replaced only by a more
+    /// confident identification.
+    pub fn promote<'a, I>(&mut self, identifications: I, criteria: &PromotionCriteria) -> Result<PromotionReport>
+    where
+        I: IntoIterator<Item = (&'a Molecule, &'a IntegratedEvidence)>,
+    {
+        let version = self.version + 1;
+        let mut report = PromotionReport::default();
+
+        for (molecule, integrated) in identifications {
+            if let Some(reason) = rejection(integrated, criteria) {
+                debug!("Not promoting {}: {}", molecule.id, reason);
+                report.rejected.push((molecule.id.clone(), reason));
+                continue;
+            }
+            let measured = Measurements::from_evidence(&integrated.evidence_items);
+            if measured.spectra.is_empty() && (criteria.require_spectrum || measured.retention_time.is_none()) {
+                let reason = if criteria.require_spectrum { "no MS/MS spectrum" } else { "no spectrum or retention time to match on" };
+                report.rejected.push((molecule.id.clone(), reason.to_string()));
+                continue;
+            }
+
+            match self.entries.get_mut(&molecule.id) {
+                Some(entry) => {
+                    measured.merge_into(entry, integrated, criteria.max_spectra);
+                    entry.molecule = molecule.clone();
+                    entry.updated_in = version;
+                    report.updated.push(molecule.id.clone());
+                }
+                None => {
+                    let mut entry = ReferenceEntry {
+                        molecule: molecule.clone(),
+                        spectra: Vec::new(),
+                        retention_time: None,
+                        ccs: BTreeMap::new(),
+                        confidence: 0.0,
+                        evidence_ids: Vec::new(),
+                        added_in: version,
+                        updated_in: version,
+                    };
+                    measured.merge_into(&mut entry, integrated, criteria.max_spectra);
+                    self.entries.insert(molecule.id.clone(), entry);
+                    report.added.push(molecule.id.clone());
+                }
+            }
+        }
+
+        if !report.added.is_empty() || !report.updated.is_empty() {
+            self.version = version;
+            self.revisions.push(LibraryRevision {
+                version,
+                timestamp: Utc::now(),
+                added: report.added.clone(),
+                updated: report.updated.clone(),
+            });
+            self.save()?;
+            info!("Reference library {} version {}: {} added, {} updated",
+                  self.name, version, report.added.len(), report.updated.len());
+        }
+        report.version = self.version;
+        Ok(report)
+    }
+
+    /// Compound store of the library compounds and `molecules`
+    ///
+    /// A library compound replaces a molecule with the same ID, so its
+    /// measured retention time is matched on.
+    pub fn compound_store_over(&self, molecules: &[Molecule]) -> CompoundStore {
+        let mut combined: Vec<Molecule> = self.entries.values().map(ReferenceEntry::to_molecule).collect();
+        combined.extend(molecules.iter().filter(|m| !self.entries.contains_key(&m.id)).cloned());
+        CompoundStore::from_molecules(&combined)
+    }
+
+    /// Measured CCS values as a reference table for CCS matching
+    pub fn ccs_table(&self) -> CcsTable {
+        let mut table = CcsTable::new(&self.name);
+        for entry in self.entries.values() {
+            for (adduct, ccs) in &entry.ccs {
+                table.insert(&entry.molecule.id, adduct, *ccs);
+            }
+        }
+        table
+    }
+
+    /// Library compounds whose spectra match a query, best first
+    pub fn search(&self, query: &MsMsSpectrum, options: &LibrarySearchOptions) -> Result<Vec<LibraryHit>> {
+        let mut hits = Vec::new();
+        for entry in self.entries.values() {
+            let mut best: Option<LibraryHit> = None;
+            for reference in &entry.spectra {
+                if !options.precursor_tolerance.matches_mz(query.precursor_mz, reference.precursor_mz)? {
+                    continue;
+                }
+                let similarity = modified_cosine(
+                    &query.spectrum, query.precursor_mz,
+                    &reference.spectrum, reference.precursor_mz,
+                    options.fragment_tolerance, options.intensity_power,
+                );
+                if similarity.score < options.min_score || similarity.matched_peaks < options.min_matched_peaks {
+                    continue;
+                }
+                if best.as_ref().is_some_and(|hit| hit.score >= similarity.score) {
+                    continue;
+                }
+                best = Some(LibraryHit {
+                    query_id: query.id.clone(),
+                    molecule_id: entry.molecule.id.clone(),
+                    name: entry.molecule.name.clone(),
+                    score: similarity.score,
+                    matched_peaks: similarity.matched_peaks,
+                    precursor_ppm_error: ppm_error(query.precursor_mz, reference.precursor_mz),
+                    adduct: reference.adduct.clone(),
+                    confidence: (similarity.score * entry.confidence).clamp(0.0, 1.0),
+                });
+            }
+            hits.extend(best);
+        }
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.molecule_id.cmp(&b.molecule_id)));
+        hits.truncate(options.max_hits);
+        Ok(hits)
+    }
+
+    /// Mass spec evidence for a library hit
+    pub fn hit_evidence(&self, hit: &LibraryHit) -> Evidence {
+        let mut metadata = HashMap::new();
+        metadata.insert("feature_id".to_string(), serde_json::json!(hit.query_id));
+        metadata.insert("library_version".to_string(), serde_json::json!(self.version));
+        if let Some(adduct) = &hit.adduct {
+            metadata.insert("adduct".to_string(), serde_json::json!(adduct));
+        }
+        Evidence {
+            id: format!("{}-{}-{}-v{}", REFERENCE_LIBRARY_SOURCE, hit.query_id, hit.molecule_id, self.version),
+            molecule_id: hit.molecule_id.clone(),
+            evidence_type: EvidenceType::MassSpec,
+            source: format!("{}:{}", REFERENCE_LIBRARY_SOURCE, self.name),
+            confidence: hit.confidence,
+            data: serde_json::to_value(hit).unwrap_or_default(),
+            metadata,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Write the library to its file, replacing the previous version in one step
+    fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write reference library: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to save reference library: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Why an identification falls short of the criteria, if it does
+fn rejection(integrated: &IntegratedEvidence, criteria: &PromotionCriteria) -> Option<String> {
+    if integrated.aggregate_confidence < criteria.min_confidence {
+        return Some(format!("confidence {:.2} below {:.2}", integrated.aggregate_confidence, criteria.min_confidence));
+    }
+    let worst = integrated.conflicts.iter().map(|c| c.severity).fold(0.0, f64::max);
+    if worst > criteria.max_conflict_severity {
+        return Some(format!("conflict of severity {:.2}", worst));
+    }
+    None
+}
+
+/// Spectra, retention time and CCS values found in a molecule's evidence
+#[derive(Default)]
+struct Measurements {
+    spectra: Vec<ReferenceSpectrum>,
+    retention_time: Option<Quantity>,
+    ccs: BTreeMap<String, f64>,
+}
+
+impl Measurements {
+    /// Collect measurements from evidence, taking each value from the most confident item that has it
+    fn from_evidence(evidence: &[Evidence]) -> Self {
+        let mut items: Vec<&Evidence> = evidence.iter().filter(|e| e.evidence_type == EvidenceType::MassSpec).collect();
+        items.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+        let mut measured = Self::default();
+        for item in items {
+            let adduct = item.metadata.get("adduct").and_then(|a| a.as_str()).map(str::to_string);
+            if let Some((precursor_mz, spectrum, ccs)) = evidence_spectrum(&item.data) {
+                if let Some(ccs) = ccs {
+                    let key = adduct.clone().unwrap_or_else(|| ion_mobility::DEFAULT_ADDUCT.to_string());
+                    measured.ccs.entry(key).or_insert(ccs);
+                }
+                measured.spectra.push(ReferenceSpectrum {
+                    evidence_id: item.id.clone(),
+                    precursor_mz,
+                    adduct,
+                    spectrum,
+                    confidence: item.confidence,
+                });
+            } else if measured.retention_time.is_none() {
+                measured.retention_time = item.data.get("retention_time")
+                    .and_then(|rt| serde_json::from_value::<Quantity>(rt.clone()).ok());
+            }
+        }
+        measured
+    }
+
+    /// Fold the measurements of an identification into a library entry
+    fn merge_into(self, entry: &mut ReferenceEntry, integrated: &IntegratedEvidence, max_spectra: usize) {
+        let known: HashSet<String> = entry.spectra.iter().map(|s| s.evidence_id.clone()).collect();
+        entry.spectra.extend(self.spectra.into_iter().filter(|s| !known.contains(&s.evidence_id)));
+        entry.spectra.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        entry.spectra.truncate(max_spectra);
+
+        if integrated.aggregate_confidence >= entry.confidence {
+            entry.confidence = integrated.aggregate_confidence;
+            entry.retention_time = self.retention_time.or(entry.retention_time);
+            entry.ccs.extend(self.ccs);
+        }
+        for item in &integrated.evidence_items {
+            if !entry.evidence_ids.contains(&item.id) {
+                entry.evidence_ids.push(item.id.clone());
+            }
+        }
+    }
+}
+
+/// Precursor m/z, fragments and precursor CCS of an MS/MS evidence payload
+///
+/// Reads MS/MS mass spec data (`format: "MSMS"`) as well as a `spectrum` of
+/// `[mz, intensity]` pairs next to a `precursor_mz`.
+fn evidence_spectrum(data: &serde_json::Value) -> Option<(f64, Spectrum, Option<f64>)> {
+    if data.get("format").and_then(|f| f.as_str()) == Some("MSMS") {
+        let content = data.get("content")?;
+        let numbers = |key: &str| -> Option<Vec<f64>> {
+            content.get(key)?.as_array()?.iter().map(|v| v.as_f64()).collect()
+        };
+        let precursor_mz = content.get("precursor_mz")?.as_f64()?;
+        let peaks: Vec<Peak> = numbers("fragment_mz")?.into_iter()
+            .zip(numbers("fragment_intensities")?)
+            .map(|(mz, intensity)| Peak { mz, intensity })
+            .collect();
+        if peaks.is_empty() {
+            return None;
+        }
+        return Some((precursor_mz, Spectrum::new(peaks), content.get("precursor_ccs").and_then(|c| c.as_f64())));
+    }
+
+    let precursor_mz = data.get("precursor_mz")?.as_f64()?;
+    let peaks: Vec<Peak> = data.get("spectrum")?.as_array()?.iter()
+        .filter_map(|peak| {
+            let pair = peak.as_array()?;
+            Some(Peak { mz: pair.first()?.as_f64()?, intensity: pair.get(1)?.as_f64()? })
+        })
+        .collect();
+    if peaks.is_empty() {
+        return None;
+    }
+    Some((precursor_mz, Spectrum::new(peaks), data.get("ccs").and_then(|c| c.as_f64())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn molecule(id: &str, mass: f64) -> Molecule {
+        let mut molecule = Molecule::from_smiles("C").unwrap();
+        molecule.id = id.to_string();
+        molecule.name = Some(id.to_string());
+        molecule.properties.insert("monoisotopic_mass".to_string(), serde_json::json!(mass));
+        molecule
+    }
+
+    fn identification(id: &str, confidence: f64, peaks: &[[f64; 2]]) -> IntegratedEvidence {
+        let msms = Evidence {
+            id: format!("{}-msms", id),
+            molecule_id: id.to_string(),
+            evidence_type: EvidenceType::MassSpec,
+            source: "lab".to_string(),
+            confidence,
+            data: serde_json::json!({"precursor_mz": 181.0707, "spectrum": peaks, "ccs": 140.2}),
+            metadata: HashMap::from([("adduct".to_string(), serde_json::json!("[M+H]+"))]),
+            timestamp: Utc::now(),
+        };
+        let feature = Evidence {
+            id: format!("{}-feature", id),
+            data: serde_json::json!({"retention_time": Quantity::minutes(3.2)}),
+            confidence: confidence - 0.05,
+            ..msms.clone()
+        };
+        IntegratedEvidence {
+            molecule_id: id.to_string(),
+            evidence_items: vec![msms, feature],
+            aggregate_confidence: confidence,
+            conflicts: Vec::new(),
+            integration_timestamp: Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        }
+    }
+
+    const PEAKS: [[f64; 2]; 4] = [[85.03, 40.0], [97.03, 100.0], [127.04, 60.0], [163.06, 80.0]];
+
+    #[test]
+    fn promotes_confident_identifications_as_new_revisions() {
+        let mut library = ReferenceLibrary::new("lab");
+        let (glucose, weak) = (molecule("glucose", 180.0634), molecule("weak", 200.0));
+        let (confident, doubtful) = (identification("glucose", 0.95, &PEAKS), identification("weak", 0.6, &PEAKS));
+
+        let report = library.promote([(&glucose, &confident), (&weak, &doubtful)], &PromotionCriteria::default()).unwrap();
+        assert_eq!((report.version, report.added.clone(), report.rejected.len()), (1, vec!["glucose".to_string()], 1));
+        let entry = library.get("glucose").unwrap();
+        assert_eq!(entry.spectra.len(), 1);
+        assert_eq!(entry.ccs.get("[M+H]+"), Some(&140.2));
+        assert_eq!(entry.retention_time, Some(Quantity::minutes(3.2)));
+
+        let report = library.promote([(&glucose, &confident)], &PromotionCriteria::default()).unwrap();
+        assert_eq!((report.version, report.updated.len()), (2, 1));
+        assert_eq!((library.get("glucose").unwrap().added_in, library.get("glucose").unwrap().updated_in), (1, 2));
+        assert_eq!(library.revisions().len(), 2);
+        assert_eq!(library.ccs_table().len(), 1);
+    }
+
+    #[test]
+    fn library_spectra_and_compounds_take_precedence() {
+        let mut library = ReferenceLibrary::new("lab");
+        let glucose = molecule("glucose", 180.0634);
+        library.promote([(&glucose, &identification("glucose", 0.95, &PEAKS))], &PromotionCriteria::default()).unwrap();
+
+        let query = MsMsSpectrum {
+            id: "FT12".to_string(),
+            precursor_mz: 181.0709,
+            spectrum: Spectrum::new(PEAKS.iter().map(|[mz, intensity]| Peak { mz: mz + 0.002, intensity: *intensity }).collect()),
+            name: None,
+            smiles: None,
+            identification_confidence: None,
+        };
+        let hits = library.search(&query, &LibrarySearchOptions::default()).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].score > 0.99 && hits[0].confidence <= 0.95);
+        let evidence = library.hit_evidence(&hits[0]);
+        assert_eq!(evidence.source, "reference-library:lab");
+
+        // The library's glucose, with its measured retention time, shadows the store's
+        let store = library.compound_store_over(&[molecule("glucose", 180.0634), molecule("fructose", 180.0634)]);
+        assert_eq!(store.len(), 2);
+        assert!(library.get("glucose").unwrap().to_molecule().properties.contains_key(RETENTION_TIME_PROPERTY));
+    }
+}
@@ -0,0 +1,312 @@
+//! Source Reliability Module
+//!
+//! This module tracks how often each evidence source agreed with confirmed
+//! molecular identities and turns that history into learned source weights.
+//! The tracker is saved to a JSON file, so the weights it has learned survive
+//! restarts of the API and carry over between CLI runs.
+
+use anyhow::{Context, Result, anyhow};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::processing::evidence::Evidence;
+use crate::ConfidenceCalculator;
+
+/// Initialize the source reliability module
+pub fn initialize() -> Result<()> {
+    info!("Initializing source reliability module");
+    info!("Source reliability module initialized successfully");
+    Ok(())
+}
+
+/// Agreement counts for a single source
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SourceRecord {
+    /// Number of times the source agreed with the confirmed identity
+    pub agreements: u64,
+
+    /// Number of times the source disagreed with the confirmed identity
+    pub disagreements: u64,
+}
+
+/// Empirical reliability of a source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceReliability {
+    /// Source name
+    pub source: String,
+
+    /// Posterior mean reliability (0.0 - 1.0)
+    pub mean: f64,
+
+    /// Posterior standard deviation
+    pub std_dev: f64,
+
+    /// Lower bound of the ~95% credible interval
+    pub lower_bound: f64,
+
+    /// Upper bound of the ~95% credible interval
+    pub upper_bound: f64,
+
+    /// Number of recorded outcomes
+    pub observations: u64,
+
+    /// Manually set weight that takes precedence over the learned value
+    pub override_weight: Option<f64>,
+}
+
+impl SourceReliability {
+    /// Weight to use for the source in integration
+    pub fn weight(&self) -> f64 {
+        self.override_weight.unwrap_or(self.mean)
+    }
+}
+
+/// Tracks per-source agreement with confirmed identities
+///
+/// Reliability is modelled as a Beta posterior over the agreement rate, so
+/// sources with few observations stay close to the prior.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReliabilityTracker {
+    /// Agreement counts by source
+    records: HashMap<String, SourceRecord>,
+
+    /// Manual weight overrides by source
+    overrides: HashMap<String, f64>,
+
+    /// Beta prior pseudo-count for agreements
+    prior_alpha: f64,
+
+    /// Beta prior pseudo-count for disagreements
+    prior_beta: f64,
+
+    /// Confidence at or above which evidence is taken to assert the identity
+    assertion_threshold: f64,
+
+    /// File the tracker is saved to; kept in memory only when unset
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl ReliabilityTracker {
+    /// Create a tracker with a uniform Beta(1, 1) prior
+    pub fn new() -> Self {
+        Self::with_prior(1.0, 1.0)
+    }
+
+    /// Create a tracker with the given Beta prior
+    pub fn with_prior(alpha: f64, beta: f64) -> Self {
+        Self {
+            records: HashMap::new(),
+            overrides: HashMap::new(),
+            prior_alpha: alpha.max(f64::EPSILON),
+            prior_beta: beta.max(f64::EPSILON),
+            assertion_threshold: 0.5,
+            path: None,
+        }
+    }
+
+    /// Load the tracker saved at a path, or start a new one saved there
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut tracker = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read source reliability: {}", path.display()))?;
+            serde_json::from_str::<Self>(&content).context("Failed to parse source reliability")?
+        } else {
+            Self::new()
+        };
+        tracker.path = Some(path);
+        Ok(tracker)
+    }
+
+    /// Open the tracker named by `HEGEL_RELIABILITY_FILE`, or `reliability.json`
+    pub fn from_env() -> Result<Self> {
+        Self::open(std::env::var("HEGEL_RELIABILITY_FILE").unwrap_or_else(|_| "reliability.json".to_string()))
+    }
+
+    /// File the tracker is saved to
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Record a single agreement or disagreement for a source
+    pub fn record_outcome(&mut self, source: &str, agreed: bool) -> Result<()> {
+        self.count_outcome(source, agreed);
+        self.save()
+    }
+
+    /// Record how a set of evidence compared with a confirmed identity
+    ///
+    /// Evidence agrees when it asserts the confirmed molecule, or when it
+    /// doubts a molecule that turned out not to be the confirmed one.
+    pub fn record_confirmation(&mut self, evidence: &[Evidence], confirmed_molecule_id: &str) -> Result<()> {
+        debug!("Recording confirmation of {} against {} evidence items", confirmed_molecule_id, evidence.len());
+
+        for ev in evidence {
+            let asserted = ev.confidence >= self.assertion_threshold;
+            let is_confirmed = ev.molecule_id == confirmed_molecule_id;
+            self.count_outcome(&ev.source, asserted == is_confirmed);
+        }
+        self.save()
+    }
+
+    /// Record how a molecule's evidence compared with a reviewer's verdict on it
+    ///
+    /// The identity counts as confirmed when the reviewed confidence reaches
+    /// the assertion threshold. Evidence agrees when it asserted a confirmed
+    /// identity or doubted a rejected one.
+    pub fn record_verdict(&mut self, evidence: &[Evidence], reviewed_confidence: f64) -> Result<()> {
+        let confirmed = reviewed_confidence >= self.assertion_threshold;
+        debug!("Recording {} verdict against {} evidence items",
+               if confirmed { "confirming" } else { "rejecting" }, evidence.len());
+
+        for ev in evidence {
+            let asserted = ev.confidence >= self.assertion_threshold;
+            self.count_outcome(&ev.source, asserted == confirmed);
+        }
+        self.save()
+    }
+
+    /// Set a manual weight for a source
+    pub fn set_override(&mut self, source: &str, weight: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&weight) {
+            return Err(anyhow!("Override weight must be between 0.0 and 1.0, got {}", weight));
+        }
+        self.overrides.insert(source.to_string(), weight);
+        self.save()
+    }
+
+    /// Remove a manual weight for a source
+    pub fn clear_override(&mut self, source: &str) -> Result<()> {
+        self.overrides.remove(source);
+        self.save()
+    }
+
+    /// Empirical reliability of a source
+    pub fn reliability(&self, source: &str) -> SourceReliability {
+        let record = self.records.get(source).copied().unwrap_or_default();
+        let alpha = self.prior_alpha + record.agreements as f64;
+        let beta = self.prior_beta + record.disagreements as f64;
+        let total = alpha + beta;
+
+        let mean = alpha / total;
+        let variance = (alpha * beta) / (total * total * (total + 1.0));
+        let std_dev = variance.sqrt();
+
+        SourceReliability {
+            source: source.to_string(),
+            mean,
+            std_dev,
+            lower_bound: (mean - 1.96 * std_dev).max(0.0),
+            upper_bound: (mean + 1.96 * std_dev).min(1.0),
+            observations: record.agreements + record.disagreements,
+            override_weight: self.overrides.get(source).copied(),
+        }
+    }
+
+    /// Reliability of every source that has been observed or overridden
+    pub fn all_reliabilities(&self) -> Vec<SourceReliability> {
+        let mut sources: Vec<&String> = self.records.keys().chain(self.overrides.keys()).collect();
+        sources.sort();
+        sources.dedup();
+        sources.into_iter().map(|s| self.reliability(s)).collect()
+    }
+
+    /// Learned (or overridden) weight of every known source
+    pub fn learned_weights(&self) -> HashMap<String, f64> {
+        self.all_reliabilities()
+            .into_iter()
+            .map(|r| (r.source.clone(), r.weight()))
+            .collect()
+    }
+
+    /// Feed the learned weights into a confidence calculator
+    pub fn apply_to_calculator(&self, calculator: &mut ConfidenceCalculator) {
+        for (source, weight) in self.learned_weights() {
+            calculator.add_evidence_weight(source, weight);
+        }
+    }
+
+    /// Count an outcome without saving
+    fn count_outcome(&mut self, source: &str, agreed: bool) {
+        let record = self.records.entry(source.to_string()).or_default();
+        if agreed {
+            record.agreements += 1;
+        } else {
+            record.disagreements += 1;
+        }
+    }
+
+    /// Write the tracker to its file, replacing the previous version atomically
+    fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write source reliability: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to save source reliability: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl Default for ReliabilityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reliability_and_override() {
+        let mut tracker = ReliabilityTracker::new();
+        for _ in 0..8 {
+            tracker.record_outcome("hmdb", true).unwrap();
+        }
+        tracker.record_outcome("hmdb", false).unwrap();
+        tracker.record_outcome("noisy", false).unwrap();
+
+        let hmdb = tracker.reliability("hmdb");
+        assert!((hmdb.mean - 9.0 / 11.0).abs() < 1e-9);
+        assert!(hmdb.lower_bound < hmdb.mean && hmdb.mean < hmdb.upper_bound);
+        assert!(tracker.reliability("noisy").mean < 0.5);
+
+        tracker.set_override("noisy", 0.9).unwrap();
+        assert_eq!(tracker.learned_weights()["noisy"], 0.9);
+        assert!(tracker.set_override("noisy", 1.5).is_err());
+    }
+
+    #[test]
+    fn test_verdicts_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reliability.json");
+        let evidence = |source: &str, confidence: f64| Evidence {
+            id: format!("{}-ev", source),
+            molecule_id: "mol".to_string(),
+            evidence_type: crate::processing::evidence::EvidenceType::MassSpec,
+            source: source.to_string(),
+            confidence,
+            data: serde_json::Value::Null,
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        };
+
+        let mut tracker = ReliabilityTracker::open(&path).unwrap();
+        tracker.record_verdict(&[evidence("hmdb", 0.9), evidence("noisy", 0.2)], 0.95).unwrap();
+        tracker.record_verdict(&[evidence("hmdb", 0.1), evidence("noisy", 0.8)], 0.0).unwrap();
+        tracker.set_override("manual", 0.3).unwrap();
+
+        let reopened = ReliabilityTracker::open(&path).unwrap();
+        assert_eq!(reopened.reliability("hmdb").observations, 2);
+        assert!((reopened.reliability("hmdb").mean - 0.75).abs() < 1e-9);
+        assert!((reopened.reliability("noisy").mean - 0.25).abs() < 1e-9);
+        assert_eq!(reopened.learned_weights()["manual"], 0.3);
+    }
+}
@@ -0,0 +1,370 @@
+//! Analysis Results Module
+//!
+//! Writes one row per molecule with a fixed set of columns: the molecule ID,
+//! aggregate confidence, evidence and conflict counts, the worst conflict's
+//! severity, the mean confidence of each evidence type, the integration
+//! time and the ID of the pipeline fingerprint that produced the result. The same columns are used for JSON lines, CSV and Parquet output.
+//! Parquet files record `SCHEMA_VERSION` in their metadata and are written a
+//! row group at a time, so results streamed out of a spilled batch are never
+//! all held in memory.
+
+use anyhow::{anyhow, Context, Result};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMicrosecondArray, UInt32Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use log::{info, debug};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::processing::evidence::{EvidenceType, IntegratedEvidence};
+
+/// Version of the result columns, bumped whenever they change
+pub const SCHEMA_VERSION: &str = "3";
+
+/// Evidence types with a score column, in column order
+pub const SCORED_EVIDENCE_TYPES: [EvidenceType; 7] = [
+    EvidenceType::Genomics,
+    EvidenceType::MassSpec,
+    EvidenceType::Literature,
+    EvidenceType::Pathway,
+    EvidenceType::Reactome,
+    EvidenceType::Structural,
+    EvidenceType::Other,
+];
+
+/// Rows per Parquet row group
+const ROW_GROUP_SIZE: usize = 8192;
+
+/// Initialize the analysis results module
+pub fn initialize() -> Result<()> {
+    info!("Initializing analysis results module");
+    info!("Analysis results module initialized successfully");
+    Ok(())
+}
+
+/// File format for analysis results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultFormat {
+    /// One JSON object per line
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Snappy-compressed Parquet
+    Parquet,
+}
+
+impl std::str::FromStr for ResultFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" | "jsonl" => Ok(ResultFormat::Json),
+            "csv" => Ok(ResultFormat::Csv),
+            "parquet" => Ok(ResultFormat::Parquet),
+            other => Err(anyhow!("Unknown result format: {}", other)),
+        }
+    }
+}
+
+/// Analysis result for one molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisRow {
+    /// Molecule the result is for
+    pub molecule_id: String,
+
+    /// Overall confidence after integration
+    pub aggregate_confidence: f64,
+
+    /// Number of evidence items integrated
+    pub evidence_count: u32,
+
+    /// Number of conflicts found between evidence items
+    pub conflict_count: u32,
+
+    /// Severity of the worst conflict (0.0 if there were none)
+    pub max_conflict_severity: f64,
+
+    /// Mean confidence of each evidence type present
+    pub type_scores: HashMap<EvidenceType, f64>,
+
+    /// When the evidence was integrated
+    pub integrated_at: chrono::DateTime<chrono::Utc>,
+
+    /// ID of the fingerprint of the pipeline that integrated it, if recorded
+    #[serde(default)]
+    pub pipeline_fingerprint: Option<String>,
+}
+
+impl AnalysisRow {
+    /// Summarize integrated evidence as a result row
+    pub fn from_integrated(integrated: &IntegratedEvidence) -> Self {
+        let mut totals: HashMap<EvidenceType, (f64, usize)> = HashMap::new();
+        for item in &integrated.evidence_items {
+            let entry = totals.entry(item.evidence_type).or_insert((0.0, 0));
+            entry.0 += item.confidence;
+            entry.1 += 1;
+        }
+
+        Self {
+            molecule_id: integrated.molecule_id.clone(),
+            aggregate_confidence: integrated.aggregate_confidence,
+            evidence_count: integrated.evidence_items.len() as u32,
+            conflict_count: integrated.conflicts.len() as u32,
+            max_conflict_severity: integrated.conflicts.iter().map(|c| c.severity).fold(0.0, f64::max),
+            type_scores: totals.into_iter().map(|(t, (sum, n))| (t, sum / n as f64)).collect(),
+            integrated_at: integrated.integration_timestamp,
+            pipeline_fingerprint: integrated.pipeline_fingerprint.as_ref().map(|f| f.id.clone()),
+        }
+    }
+
+    /// Score of an evidence type, if the molecule had any evidence of it
+    pub fn score(&self, evidence_type: EvidenceType) -> Option<f64> {
+        self.type_scores.get(&evidence_type).copied()
+    }
+
+    /// The row as a flat JSON object keyed by column name
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        object.insert("molecule_id".to_string(), self.molecule_id.clone().into());
+        object.insert("aggregate_confidence".to_string(), self.aggregate_confidence.into());
+        object.insert("evidence_count".to_string(), self.evidence_count.into());
+        object.insert("conflict_count".to_string(), self.conflict_count.into());
+        object.insert("max_conflict_severity".to_string(), self.max_conflict_severity.into());
+        for evidence_type in SCORED_EVIDENCE_TYPES {
+            object.insert(score_column(evidence_type), self.score(evidence_type).into());
+        }
+        object.insert("integrated_at".to_string(), self.integrated_at.to_rfc3339().into());
+        object.insert("pipeline_fingerprint".to_string(), self.pipeline_fingerprint.clone().into());
+        serde_json::Value::Object(object)
+    }
+
+    /// The row as a CSV line, without the trailing newline
+    fn to_csv(&self) -> String {
+        let mut fields = vec![
+            csv_field(&self.molecule_id),
+            self.aggregate_confidence.to_string(),
+            self.evidence_count.to_string(),
+            self.conflict_count.to_string(),
+            self.max_conflict_severity.to_string(),
+        ];
+        for evidence_type in SCORED_EVIDENCE_TYPES {
+            fields.push(self.score(evidence_type).map(|s| s.to_string()).unwrap_or_default());
+        }
+        fields.push(self.integrated_at.to_rfc3339());
+        fields.push(self.pipeline_fingerprint.clone().unwrap_or_default());
+        fields.join(",")
+    }
+}
+
+/// Name of an evidence type's score column
+fn score_column(evidence_type: EvidenceType) -> String {
+    format!("{}_score", evidence_type)
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Column names in output order
+pub fn columns() -> Vec<String> {
+    let mut columns: Vec<String> = ["molecule_id", "aggregate_confidence", "evidence_count", "conflict_count", "max_conflict_severity"]
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    columns.extend(SCORED_EVIDENCE_TYPES.iter().map(|t| score_column(*t)));
+    columns.push("integrated_at".to_string());
+    columns.push("pipeline_fingerprint".to_string());
+    columns
+}
+
+/// Arrow schema of Parquet results
+pub fn schema() -> SchemaRef {
+    let mut fields = vec![
+        Field::new("molecule_id", DataType::Utf8, false),
+        Field::new("aggregate_confidence", DataType::Float64, false),
+        Field::new("evidence_count", DataType::UInt32, false),
+        Field::new("conflict_count", DataType::UInt32, false),
+        Field::new("max_conflict_severity", DataType::Float64, false),
+    ];
+    fields.extend(SCORED_EVIDENCE_TYPES.iter().map(|t| Field::new(score_column(*t), DataType::Float64, true)));
+    fields.push(Field::new("integrated_at", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), false));
+    fields.push(Field::new("pipeline_fingerprint", DataType::Utf8, true));
+
+    let metadata = HashMap::from([("hegel.schema_version".to_string(), SCHEMA_VERSION.to_string())]);
+    Arc::new(Schema::new_with_metadata(fields, metadata))
+}
+
+/// Record batch of result rows in the `schema()` layout
+fn record_batch(rows: &[AnalysisRow]) -> Result<RecordBatch> {
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.molecule_id.as_str()))),
+        Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.aggregate_confidence))),
+        Arc::new(UInt32Array::from_iter_values(rows.iter().map(|r| r.evidence_count))),
+        Arc::new(UInt32Array::from_iter_values(rows.iter().map(|r| r.conflict_count))),
+        Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.max_conflict_severity))),
+    ];
+    for evidence_type in SCORED_EVIDENCE_TYPES {
+        columns.push(Arc::new(rows.iter().map(|r| r.score(evidence_type)).collect::<Float64Array>()));
+    }
+    columns.push(Arc::new(
+        TimestampMicrosecondArray::from_iter_values(rows.iter().map(|r| r.integrated_at.timestamp_micros()))
+            .with_timezone("UTC"),
+    ));
+    columns.push(Arc::new(rows.iter().map(|r| r.pipeline_fingerprint.as_deref()).collect::<StringArray>()));
+    RecordBatch::try_new(schema(), columns).context("Failed to build result batch")
+}
+
+/// Destination of a results writer
+enum Sink {
+    Json(BufWriter<File>),
+    Csv(BufWriter<File>),
+    Parquet {
+        writer: Box<ArrowWriter<File>>,
+        pending: Vec<AnalysisRow>,
+    },
+}
+
+/// Streaming writer of analysis rows to a file
+pub struct ResultsWriter {
+    /// Open output
+    sink: Sink,
+
+    /// Rows written so far
+    rows: usize,
+}
+
+impl ResultsWriter {
+    /// Create (or truncate) `path` and prepare to write rows in `format`
+    pub fn create(path: &Path, format: ResultFormat) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create results file: {}", path.display()))?;
+        let sink = match format {
+            ResultFormat::Json => Sink::Json(BufWriter::new(file)),
+            ResultFormat::Csv => {
+                let mut writer = BufWriter::new(file);
+                writeln!(writer, "{}", columns().join(","))?;
+                Sink::Csv(writer)
+            }
+            ResultFormat::Parquet => {
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .set_max_row_group_size(ROW_GROUP_SIZE)
+                    .build();
+                let writer = ArrowWriter::try_new(file, schema(), Some(properties))
+                    .context("Failed to start Parquet results file")?;
+                Sink::Parquet { writer: Box::new(writer), pending: Vec::with_capacity(ROW_GROUP_SIZE) }
+            }
+        };
+        debug!("Writing {:?} results to {}", format, path.display());
+        Ok(Self { sink, rows: 0 })
+    }
+
+    /// Append a row
+    pub fn write(&mut self, row: AnalysisRow) -> Result<()> {
+        match &mut self.sink {
+            Sink::Json(writer) => writeln!(writer, "{}", row.to_json())?,
+            Sink::Csv(writer) => writeln!(writer, "{}", row.to_csv())?,
+            Sink::Parquet { writer, pending } => {
+                pending.push(row);
+                if pending.len() >= ROW_GROUP_SIZE {
+                    writer.write(&record_batch(pending)?).context("Failed to write Parquet row group")?;
+                    pending.clear();
+                }
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Flush everything to disk, returning the number of rows written
+    pub fn finish(self) -> Result<usize> {
+        match self.sink {
+            Sink::Json(mut writer) | Sink::Csv(mut writer) => writer.flush()?,
+            Sink::Parquet { mut writer, pending } => {
+                if !pending.is_empty() {
+                    writer.write(&record_batch(&pending)?).context("Failed to write Parquet row group")?;
+                }
+                writer.close().context("Failed to finish Parquet results file")?;
+            }
+        }
+        Ok(self.rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::{Evidence, EvidenceConflict};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn integrated(molecule_id: &str) -> IntegratedEvidence {
+        let evidence = |id: &str, evidence_type, confidence| Evidence {
+            id: id.to_string(),
+            molecule_id: molecule_id.to_string(),
+            evidence_type,
+            source: "test".to_string(),
+            confidence,
+            data: serde_json::Value::Null,
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        };
+        IntegratedEvidence {
+            molecule_id: molecule_id.to_string(),
+            evidence_items: vec![
+                evidence("a", EvidenceType::MassSpec, 0.9),
+                evidence("b", EvidenceType::MassSpec, 0.7),
+                evidence("c", EvidenceType::Literature, 0.4),
+            ],
+            aggregate_confidence: 0.75,
+            conflicts: vec![EvidenceConflict {
+                description: "disagreement".to_string(),
+                evidence_ids: vec!["a".to_string(), "c".to_string()],
+                severity: 0.5,
+                resolution_suggestions: Vec::new(),
+            }],
+            integration_timestamp: chrono::Utc::now(),
+            pipeline_fingerprint: None,
+            policy_violations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let path = std::env::temp_dir().join(format!("hegel-results-{}.parquet", std::process::id()));
+        let mut writer = ResultsWriter::create(&path, ResultFormat::Parquet).unwrap();
+        for id in ["glucose", "fructose, d-"] {
+            writer.write(AnalysisRow::from_integrated(&integrated(id))).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), 2);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.schema().metadata()["hegel.schema_version"], SCHEMA_VERSION);
+        let batches: Vec<RecordBatch> = reader.build().unwrap().collect::<std::result::Result<_, _>>().unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        let names: Vec<String> = batch.schema().fields().iter().map(|f| f.name().clone()).collect();
+        assert_eq!(names, columns());
+        let mass_spec = batch.column_by_name("mass_spec_score").unwrap().as_any().downcast_ref::<Float64Array>().unwrap();
+        assert!((mass_spec.value(0) - 0.8).abs() < 1e-12);
+        let genomics = batch.column_by_name("genomics_score").unwrap();
+        assert_eq!(genomics.null_count(), 2);
+
+        let row = AnalysisRow::from_integrated(&integrated("fructose, d-"));
+        assert!(row.to_csv().starts_with("\"fructose, d-\",0.75,3,1,0.5,,"));
+        assert_eq!(row.to_json()["literature_score"], 0.4);
+    }
+}
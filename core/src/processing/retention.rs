@@ -0,0 +1,296 @@
+//! Evidence Retention Module
+//!
+//! Retention policies let deployments that handle clinical data purge raw
+//! evidence payloads after a number of days while keeping what was concluded
+//! from them. A redacted evidence item keeps its type, source, confidence and
+//! timestamp; its payload is replaced by a SHA-256 hash so the provenance of
+//! past conclusions can still be checked against an archived copy. Every
+//! redaction is appended to an audit log.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::processing::evidence::{Evidence, EvidenceType};
+
+/// Metadata key holding the hash of a redacted payload
+pub const PAYLOAD_HASH_KEY: &str = "payload_sha256";
+
+/// Metadata key holding the redaction time
+pub const REDACTED_AT_KEY: &str = "redacted_at";
+
+/// Initialize the retention module
+pub fn initialize() -> Result<()> {
+    info!("Initializing retention module");
+    info!("Retention module initialized successfully");
+    Ok(())
+}
+
+/// How long raw payloads of matching evidence are kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionRule {
+    /// Project the rule applies to; all projects if absent
+    #[serde(default)]
+    pub project_id: Option<String>,
+
+    /// Evidence type the rule applies to; all types if absent
+    #[serde(default)]
+    pub evidence_type: Option<EvidenceType>,
+
+    /// Days a raw payload is kept after the evidence was recorded
+    pub max_age_days: u32,
+}
+
+impl RetentionRule {
+    fn matches(&self, project_id: &str, evidence_type: &str) -> bool {
+        self.project_id.as_deref().is_none_or(|p| p == project_id)
+            && self.evidence_type.is_none_or(|t| t.to_string() == evidence_type)
+    }
+
+    /// Rules naming both a project and a type are the most specific
+    fn specificity(&self) -> u8 {
+        (self.project_id.is_some() as u8) * 2 + self.evidence_type.is_some() as u8
+    }
+}
+
+/// Set of retention rules
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Rules; the most specific matching rule applies
+    pub rules: Vec<RetentionRule>,
+}
+
+impl RetentionPolicy {
+    /// Load a policy from a JSON file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read retention policy: {}", path.display()))?;
+        let policy: Self = serde_json::from_str(&content).context("Failed to parse retention policy")?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Check that no two rules cover exactly the same evidence
+    pub fn validate(&self) -> Result<()> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if self.rules[..i].iter().any(|r| r.project_id == rule.project_id && r.evidence_type == rule.evidence_type) {
+                return Err(anyhow!("Duplicate retention rule for project {:?} and evidence type {:?}",
+                                   rule.project_id, rule.evidence_type));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rule governing evidence of a type in a project, if any
+    ///
+    /// `evidence_type` is the stored form of the type (e.g. `mass_spec`).
+    pub fn rule_for(&self, project_id: &str, evidence_type: &str) -> Option<&RetentionRule> {
+        self.rules.iter()
+            .filter(|r| r.matches(project_id, evidence_type))
+            .max_by_key(|r| r.specificity())
+    }
+
+    /// Rule under which the raw payload of evidence recorded at `recorded_at` has expired, if any
+    pub fn expired_rule(&self, project_id: &str, evidence_type: &str, recorded_at: DateTime<Utc>, now: DateTime<Utc>) -> Option<&RetentionRule> {
+        self.rule_for(project_id, evidence_type)
+            .filter(|r| recorded_at + Duration::days(r.max_age_days as i64) <= now)
+    }
+}
+
+/// Audit record of one redacted payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRecord {
+    /// Redacted evidence item
+    pub evidence_id: String,
+
+    /// Molecule the evidence supports
+    pub molecule_id: String,
+
+    /// Project the evidence belongs to
+    pub project_id: String,
+
+    /// Stored form of the evidence type
+    pub evidence_type: String,
+
+    /// SHA-256 of the removed payload
+    pub payload_sha256: String,
+
+    /// Size of the removed payload in bytes
+    pub payload_bytes: usize,
+
+    /// When the evidence was recorded
+    pub recorded_at: DateTime<Utc>,
+
+    /// When the payload was removed
+    pub redacted_at: DateTime<Utc>,
+
+    /// Retention period that expired, in days
+    pub max_age_days: u32,
+}
+
+impl RedactionRecord {
+    /// Record the redaction of a payload under a rule
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        evidence_id: &str,
+        molecule_id: &str,
+        project_id: &str,
+        evidence_type: &str,
+        payload: &serde_json::Value,
+        recorded_at: DateTime<Utc>,
+        rule: &RetentionRule,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let (payload_sha256, payload_bytes) = payload_hash(payload);
+        Self {
+            evidence_id: evidence_id.to_string(),
+            molecule_id: molecule_id.to_string(),
+            project_id: project_id.to_string(),
+            evidence_type: evidence_type.to_string(),
+            payload_sha256,
+            payload_bytes,
+            recorded_at,
+            redacted_at: now,
+            max_age_days: rule.max_age_days,
+        }
+    }
+}
+
+/// Hash of a payload as kept after redaction
+pub fn payload_hash(payload: &serde_json::Value) -> (String, usize) {
+    let bytes = serde_json::to_vec(payload).unwrap_or_default();
+    (hex::encode(Sha256::digest(&bytes)), bytes.len())
+}
+
+/// Remove the raw payload of an evidence item if the policy says it has expired
+///
+/// Returns `None` for evidence that is already redacted or still retained.
+pub fn redact_expired(
+    evidence: &mut Evidence,
+    project_id: &str,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Option<RedactionRecord> {
+    if evidence.data.is_null() || evidence.metadata.contains_key(PAYLOAD_HASH_KEY) {
+        return None;
+    }
+    let evidence_type = evidence.evidence_type.to_string();
+    let rule = policy.expired_rule(project_id, &evidence_type, evidence.timestamp, now)?;
+
+    let record = RedactionRecord::new(&evidence.id, &evidence.molecule_id, project_id, &evidence_type,
+                                      &evidence.data, evidence.timestamp, rule, now);
+    evidence.data = serde_json::Value::Null;
+    evidence.metadata.insert(PAYLOAD_HASH_KEY.to_string(), serde_json::json!(record.payload_sha256));
+    evidence.metadata.insert(REDACTED_AT_KEY.to_string(), serde_json::json!(now.to_rfc3339()));
+    debug!("Redacted payload of evidence {} ({} bytes)", evidence.id, record.payload_bytes);
+
+    Some(record)
+}
+
+/// Append-only JSON-lines log of redactions
+#[derive(Debug, Clone)]
+pub struct RedactionAuditLog {
+    /// Log file
+    path: PathBuf,
+}
+
+impl RedactionAuditLog {
+    /// Use the log at the given path, created on first append
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Use the log named by `HEGEL_REDACTION_AUDIT_LOG`, or `redactions.jsonl`
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("HEGEL_REDACTION_AUDIT_LOG").unwrap_or_else(|_| "redactions.jsonl".to_string()))
+    }
+
+    /// Path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append records and flush them to disk
+    pub fn append(&self, records: &[RedactionRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open redaction audit log: {}", self.path.display()))?;
+        for record in records {
+            serde_json::to_writer(&mut file, record)?;
+            file.write_all(b"\n")?;
+        }
+        file.sync_all().context("Failed to flush redaction audit log")?;
+        Ok(())
+    }
+
+    /// Read every record in the log
+    pub fn records(&self) -> Result<Vec<RedactionRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = std::fs::File::open(&self.path)
+            .with_context(|| format!("Failed to open redaction audit log: {}", self.path.display()))?;
+        std::io::BufReader::new(file).lines()
+            .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn evidence(evidence_type: EvidenceType, age_days: i64) -> Evidence {
+        Evidence {
+            id: "ev-1".to_string(),
+            molecule_id: "mol-1".to_string(),
+            evidence_type,
+            source: "sequencer".to_string(),
+            confidence: 0.9,
+            data: serde_json::json!({"reads": [1, 2, 3]}),
+            metadata: HashMap::new(),
+            timestamp: Utc::now() - Duration::days(age_days),
+        }
+    }
+
+    #[test]
+    fn test_redacts_expired_payloads() {
+        let policy: RetentionPolicy = serde_json::from_value(serde_json::json!({
+            "rules": [
+                {"max_age_days": 365},
+                {"project_id": "clinical", "evidence_type": "Genomics", "max_age_days": 30},
+            ]
+        })).unwrap();
+        let now = Utc::now();
+
+        let mut recent = evidence(EvidenceType::Genomics, 10);
+        assert!(redact_expired(&mut recent, "clinical", &policy, now).is_none());
+
+        // The project-specific rule is stricter than the global one
+        let mut old = evidence(EvidenceType::Genomics, 45);
+        let expected_hash = payload_hash(&old.data).0;
+        assert!(redact_expired(&mut old.clone(), "research", &policy, now).is_none());
+        let record = redact_expired(&mut old, "clinical", &policy, now).unwrap();
+        assert_eq!(record.max_age_days, 30);
+        assert_eq!(record.payload_sha256, expected_hash);
+        assert!(old.data.is_null());
+        assert_eq!(old.confidence, 0.9);
+        assert!(redact_expired(&mut old, "clinical", &policy, now).is_none());
+
+        let log = RedactionAuditLog::new(std::env::temp_dir().join(format!("hegel-redactions-{}.jsonl", uuid::Uuid::new_v4())));
+        log.append(&[record]).unwrap();
+        assert_eq!(log.records().unwrap()[0].evidence_id, "ev-1");
+        std::fs::remove_file(log.path()).unwrap();
+    }
+}
@@ -0,0 +1,318 @@
+//! Scoring Scripts
+//!
+//! Custom evidence combination written in Rhai, so a lab can change how
+//! confidence is aggregated without recompiling. A script defines
+//! `fn score(evidence, conflicts)` and returns the aggregate confidence;
+//! `EvidenceProcessingOptions::scoring_script` names the script to use in
+//! place of the built-in weighted average.
+//!
+//! Each evidence item reaches the script as a map with `id`, `type`,
+//! `source`, `confidence`, `weight` and `sample` fields, and each conflict as
+//! a map with `description`, `severity` and `evidence_ids`. Besides the Rhai
+//! standard library the script may call `clamp(x, lo, hi)` and
+//! `noisy_or(confidences)`.
+//!
+//! Scripts run sandboxed: modules cannot be imported, `eval` is disabled,
+//! `print` and `debug` go to the log, and every call is bounded by
+//! operation, call-depth, size and wall-clock limits. `HEGEL_SCORING_SCRIPTS_DIR`
+//! names a directory of `<name>.rhai` files registered at startup.
+
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST, FLOAT};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::processing::evidence::{Evidence, EvidenceConflict};
+
+/// Environment variable naming the directory of scripts registered at startup
+pub const SCRIPTS_DIR_ENV: &str = "HEGEL_SCORING_SCRIPTS_DIR";
+
+/// Function every scoring script must define
+pub const SCORE_FN: &str = "score";
+
+/// Initialize the scoring scripts module
+pub fn initialize() -> Result<()> {
+    info!("Initializing scoring scripts module");
+    if let Some(dir) = std::env::var_os(SCRIPTS_DIR_ENV) {
+        let loaded = ScoringScriptRegistry::global().load_dir(Path::new(&dir))?;
+        info!("Registered {} scoring scripts from {}", loaded, Path::new(&dir).display());
+    }
+    info!("Scoring scripts module initialized successfully");
+    Ok(())
+}
+
+/// Resource limits applied to every script call
+#[derive(Debug, Clone)]
+pub struct ScriptLimits {
+    /// Maximum number of operations per call
+    pub max_operations: u64,
+
+    /// Maximum wall-clock time per call
+    pub timeout: Duration,
+
+    /// Maximum depth of nested function calls
+    pub max_call_levels: usize,
+
+    /// Maximum length of any string
+    pub max_string_size: usize,
+
+    /// Maximum length of any array
+    pub max_array_size: usize,
+
+    /// Maximum number of entries in any map
+    pub max_map_size: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 1_000_000,
+            timeout: Duration::from_millis(250),
+            max_call_levels: 32,
+            max_string_size: 4096,
+            max_array_size: 100_000,
+            max_map_size: 1000,
+        }
+    }
+}
+
+/// A compiled scoring script
+pub struct ScoringScript {
+    /// Name the script is registered under
+    name: String,
+
+    /// SHA-256 (hex) of the script source
+    digest: String,
+
+    /// Compiled script
+    ast: AST,
+
+    /// Limits applied to each call
+    limits: ScriptLimits,
+}
+
+impl std::fmt::Debug for ScoringScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ScoringScript").field("name", &self.name).finish()
+    }
+}
+
+impl ScoringScript {
+    /// Compile a script with the default limits
+    pub fn compile(name: &str, source: &str) -> Result<Self> {
+        Self::compile_with_limits(name, source, ScriptLimits::default())
+    }
+
+    /// Compile a script, checking that it defines `score(evidence, conflicts)`
+    pub fn compile_with_limits(name: &str, source: &str, limits: ScriptLimits) -> Result<Self> {
+        let ast = sandboxed_engine(&limits, None)
+            .compile(source)
+            .map_err(|e| anyhow!("Scoring script {} does not compile: {}", name, e))?;
+        if !ast.iter_functions().any(|f| f.name == SCORE_FN && f.params.len() == 2) {
+            return Err(anyhow!("Scoring script {} does not define {}(evidence, conflicts)", name, SCORE_FN));
+        }
+        let digest = hex::encode(Sha256::digest(source.as_bytes()));
+        Ok(Self { name: name.to_string(), digest, ast, limits })
+    }
+
+    /// Name the script is registered under
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// SHA-256 (hex) of the script source, identifying its exact version
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    /// Aggregate confidence for weighted evidence items and their conflicts
+    pub fn score(&self, evidence: &[(&Evidence, f64)], conflicts: &[EvidenceConflict]) -> Result<f64> {
+        let evidence: Array = evidence.iter()
+            .map(|(ev, weight)| {
+                let mut item = Map::new();
+                item.insert("id".into(), ev.id.clone().into());
+                item.insert("type".into(), ev.evidence_type.to_string().into());
+                item.insert("source".into(), ev.source.clone().into());
+                item.insert("confidence".into(), Dynamic::from_float(ev.confidence));
+                item.insert("weight".into(), Dynamic::from_float(*weight));
+                item.insert("sample".into(), ev.sample_id().map_or(Dynamic::UNIT, |s| s.to_string().into()));
+                Dynamic::from_map(item)
+            })
+            .collect();
+        let conflicts: Array = conflicts.iter()
+            .map(|conflict| {
+                let mut item = Map::new();
+                item.insert("description".into(), conflict.description.clone().into());
+                item.insert("severity".into(), Dynamic::from_float(conflict.severity));
+                let ids: Array = conflict.evidence_ids.iter().map(|id| id.clone().into()).collect();
+                item.insert("evidence_ids".into(), Dynamic::from_array(ids));
+                Dynamic::from_map(item)
+            })
+            .collect();
+
+        let engine = sandboxed_engine(&self.limits, Some(Instant::now() + self.limits.timeout));
+        let result: Dynamic = engine
+            .call_fn(&mut Scope::new(), &self.ast, SCORE_FN, (evidence, conflicts))
+            .map_err(|e| anyhow!("Scoring script {} failed: {}", self.name, e))?;
+
+        let score = match result.as_float() {
+            Ok(score) => score,
+            Err(_) => result.as_int()
+                .map(|score| score as FLOAT)
+                .map_err(|kind| anyhow!("Scoring script {} returned {} instead of a number", self.name, kind))?,
+        };
+        if !score.is_finite() {
+            return Err(anyhow!("Scoring script {} returned {}", self.name, score));
+        }
+        Ok(score.clamp(0.0, 1.0))
+    }
+}
+
+/// Engine exposing only the safe API, with limits and an optional deadline
+fn sandboxed_engine(limits: &ScriptLimits, deadline: Option<Instant>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .set_max_operations(limits.max_operations)
+        .set_max_call_levels(limits.max_call_levels)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(limits.max_string_size)
+        .set_max_array_size(limits.max_array_size)
+        .set_max_map_size(limits.max_map_size)
+        .on_print(|text| info!("[scoring script] {}", text))
+        .on_debug(|text, _, pos| debug!("[scoring script] {} at {}", text, pos));
+    if let Some(deadline) = deadline {
+        engine.on_progress(move |_| {
+            if Instant::now() > deadline {
+                Some("time limit exceeded".into())
+            } else {
+                None
+            }
+        });
+    }
+    engine
+        .register_fn("clamp", |x: FLOAT, lo: FLOAT, hi: FLOAT| x.max(lo).min(hi))
+        .register_fn("noisy_or", |confidences: Array| {
+            1.0 - confidences.iter()
+                .filter_map(|c| c.as_float().ok())
+                .fold(1.0, |acc, c| acc * (1.0 - c.clamp(0.0, 1.0)))
+        });
+    engine
+}
+
+/// Registry of named scoring scripts
+pub struct ScoringScriptRegistry {
+    /// Registered scripts
+    scripts: RwLock<HashMap<String, Arc<ScoringScript>>>,
+}
+
+impl ScoringScriptRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            scripts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the process-wide registry
+    pub fn global() -> &'static ScoringScriptRegistry {
+        static REGISTRY: OnceLock<ScoringScriptRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(ScoringScriptRegistry::new)
+    }
+
+    /// Register a script, replacing any script with the same name
+    pub fn register(&self, script: ScoringScript) {
+        debug!("Registering scoring script: {}", script.name());
+        self.scripts.write().unwrap().insert(script.name().to_string(), Arc::new(script));
+    }
+
+    /// Compile and register every `<name>.rhai` file in a directory, returning how many were registered
+    pub fn load_dir(&self, dir: &Path) -> Result<usize> {
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read scoring script directory {}", dir.display()))?;
+        let mut loaded = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("rhai") {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let source = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read scoring script {}", path.display()))?;
+            self.register(ScoringScript::compile(&name, &source)?);
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /// Get a script by name
+    pub fn get(&self, name: &str) -> Result<Arc<ScoringScript>> {
+        self.scripts.read().unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown scoring script: {}", name))
+    }
+
+    /// Names of all registered scripts
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.scripts.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for ScoringScriptRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+
+    fn evidence(evidence_type: EvidenceType, confidence: f64) -> Evidence {
+        Evidence::manual("mol1", evidence_type, confidence, None, "tester").unwrap()
+    }
+
+    #[test]
+    fn test_script_combines_evidence() {
+        let script = ScoringScript::compile("literature-capped", r#"
+            fn score(evidence, conflicts) {
+                let lit = evidence.filter(|e| e.type == "literature").map(|e| e.confidence);
+                let other = evidence.filter(|e| e.type != "literature").map(|e| e.confidence * e.weight);
+                let capped = clamp(noisy_or(lit), 0.0, 0.6);
+                noisy_or(other + [capped]) - 0.1 * conflicts.len()
+            }
+        "#).unwrap();
+
+        let lit = evidence(EvidenceType::Literature, 0.9);
+        let ms = evidence(EvidenceType::MassSpec, 0.5);
+        assert!((script.score(&[(&lit, 1.0)], &[]).unwrap() - 0.6).abs() < 1e-9);
+        assert!((script.score(&[(&lit, 1.0), (&ms, 1.0)], &[]).unwrap() - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_script_is_sandboxed() {
+        assert!(ScoringScript::compile("no-score", "fn other(x) { x }").is_err());
+        assert!(ScoringScript::compile("imports", r#"import "fs" as fs; fn score(e, c) { 1.0 }"#)
+            .and_then(|script| script.score(&[], &[])).is_err());
+        assert!(ScoringScript::compile("evals", r#"fn score(e, c) { eval("1.0") }"#).is_err());
+
+        let limits = ScriptLimits { timeout: Duration::from_millis(50), ..ScriptLimits::default() };
+        let spin = ScoringScript::compile_with_limits("spins", "fn score(e, c) { loop {} }", limits).unwrap();
+        let err = spin.score(&[], &[]).unwrap_err();
+        assert!(err.to_string().contains("spins"));
+    }
+}
@@ -1,128 +1,544 @@
-// Spectral analysis module for processing mass spectrometry data
+//! Spectral analysis module for processing mass spectrometry data
+//!
+//! Peak lists are parsed, filtered for noise, binned on a fixed m/z grid and
+//! compared with either cosine or spectral entropy similarity. Comparisons
+//! return a `SpectralMatch` with the matched peaks alongside the score.
+//! MS/MS spectra of features, read from MGF files, are compared with the
+//! modified cosine, which also matches fragments shifted by the difference
+//! between the precursor masses.
 
 use crate::HegelError;
-use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A single peak in a mass spectrum
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Peak {
+    /// Mass-to-charge ratio
+    pub mz: f64,
+
+    /// Peak intensity
+    pub intensity: f64,
+}
+
+/// A centroided mass spectrum, with peaks sorted by m/z
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Spectrum {
+    /// Peaks sorted by ascending m/z
+    pub peaks: Vec<Peak>,
+}
+
+impl Spectrum {
+    /// Create a spectrum from peaks in any order
+    pub fn new(mut peaks: Vec<Peak>) -> Self {
+        peaks.sort_by(|a, b| a.mz.partial_cmp(&b.mz).unwrap_or(std::cmp::Ordering::Equal));
+        Self { peaks }
+    }
+
+    /// Intensity of the most intense peak
+    pub fn base_peak_intensity(&self) -> f64 {
+        self.peaks.iter().map(|p| p.intensity).fold(0.0, f64::max)
+    }
+
+    /// Remove peaks below a fraction of the base peak intensity
+    pub fn filter_noise(&self, relative_threshold: f64) -> Spectrum {
+        let threshold = self.base_peak_intensity() * relative_threshold;
+        Spectrum {
+            peaks: self.peaks.iter().filter(|p| p.intensity > threshold).cloned().collect(),
+        }
+    }
+
+    /// Sum intensities into bins of the given m/z width, keyed by bin index
+    pub fn bin(&self, bin_width: f64) -> BTreeMap<i64, f64> {
+        let mut bins = BTreeMap::new();
+        for peak in &self.peaks {
+            *bins.entry((peak.mz / bin_width).floor() as i64).or_insert(0.0) += peak.intensity;
+        }
+        bins
+    }
+}
+
+/// Similarity measure used to compare binned spectra
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpectralSimilarityMethod {
+    /// Cosine of the (intensity-scaled) binned vectors
+    Cosine,
+
+    /// Spectral entropy similarity with low-entropy reweighting (Li et al., 2021)
+    Entropy,
+}
+
+/// Options for spectral comparison
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectralOptions {
+    /// Width of the m/z bins in Da
+    pub bin_width: f64,
+
+    /// Peaks below this fraction of the base peak are treated as noise
+    pub noise_threshold: f64,
+
+    /// Similarity measure
+    pub method: SpectralSimilarityMethod,
+
+    /// Exponent applied to intensities before cosine comparison (0.5 = square root)
+    pub intensity_power: f64,
+}
+
+impl Default for SpectralOptions {
+    fn default() -> Self {
+        Self {
+            bin_width: 0.1,
+            noise_threshold: 0.01,
+            method: SpectralSimilarityMethod::Cosine,
+            intensity_power: 0.5,
+        }
+    }
+}
+
+/// A bin in which both spectra have signal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeakMatch {
+    /// Lower m/z edge of the bin
+    pub mz: f64,
+
+    /// Experimental intensity, relative to the experimental base peak
+    pub experimental_intensity: f64,
+
+    /// Reference intensity, relative to the reference base peak
+    pub reference_intensity: f64,
+}
+
+/// Detailed result of comparing two spectra
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectralMatch {
+    /// Similarity measure used
+    pub method: SpectralSimilarityMethod,
+
+    /// Similarity score (0.0 - 1.0)
+    pub similarity: f64,
+
+    /// Bin width used for the comparison
+    pub bin_width: f64,
+
+    /// Experimental peaks remaining after noise filtering
+    pub experimental_peaks: usize,
+
+    /// Reference peaks remaining after noise filtering
+    pub reference_peaks: usize,
+
+    /// Peaks removed as noise from both spectra
+    pub noise_peaks_removed: usize,
+
+    /// Bins in which both spectra have signal
+    pub matches: Vec<PeakMatch>,
+
+    /// Fraction of the reference intensity found in matched bins
+    pub explained_reference_intensity: f64,
+}
 
 /// Calculate similarity between two spectral data points
 pub fn calculate_spectral_similarity(
     spectral_data: &str,
     reference_data: &str,
 ) -> Result<f64, HegelError> {
-    // Parse spectral data
-    let experimental_peaks = parse_spectral_data(spectral_data)
+    calculate_spectral_match(spectral_data, reference_data, &SpectralOptions::default())
+        .map(|m| m.similarity)
+}
+
+/// Compare two peak lists and return the detailed match
+pub fn calculate_spectral_match(
+    spectral_data: &str,
+    reference_data: &str,
+    options: &SpectralOptions,
+) -> Result<SpectralMatch, HegelError> {
+    let experimental = parse_peak_list(spectral_data)
         .map_err(|e| HegelError::ComputationError(format!("Error parsing experimental data: {}", e)))?;
-    
-    let reference_peaks = parse_spectral_data(reference_data)
+
+    let reference = parse_peak_list(reference_data)
         .map_err(|e| HegelError::ComputationError(format!("Error parsing reference data: {}", e)))?;
-    
-    // Calculate cosine similarity between spectra
-    let similarity = calculate_cosine_similarity(&experimental_peaks, &reference_peaks);
-    
-    Ok(similarity)
+
+    compare_spectra(&experimental, &reference, options)
 }
 
-/// Parse spectral data in m/z,intensity format
-fn parse_spectral_data(data: &str) -> Result<HashMap<f64, f64>, String> {
-    let mut peaks = HashMap::new();
-    
-    for line in data.lines() {
-        if line.trim().is_empty() || line.starts_with('#') {
+/// Compare two parsed spectra
+pub fn compare_spectra(
+    experimental: &Spectrum,
+    reference: &Spectrum,
+    options: &SpectralOptions,
+) -> Result<SpectralMatch, HegelError> {
+    if options.bin_width <= 0.0 {
+        return Err(HegelError::ConfigError(format!("Bin width must be positive, got {}", options.bin_width)));
+    }
+
+    let filtered_exp = experimental.filter_noise(options.noise_threshold);
+    let filtered_ref = reference.filter_noise(options.noise_threshold);
+    let noise_peaks_removed = experimental.peaks.len() + reference.peaks.len()
+        - filtered_exp.peaks.len() - filtered_ref.peaks.len();
+
+    let exp_bins = filtered_exp.bin(options.bin_width);
+    let ref_bins = filtered_ref.bin(options.bin_width);
+
+    let similarity = match options.method {
+        SpectralSimilarityMethod::Cosine => cosine_similarity(&exp_bins, &ref_bins, options.intensity_power),
+        SpectralSimilarityMethod::Entropy => entropy_similarity(&exp_bins, &ref_bins),
+    };
+
+    let exp_base = filtered_exp.base_peak_intensity();
+    let ref_base = filtered_ref.base_peak_intensity();
+    let matches: Vec<PeakMatch> = exp_bins.iter()
+        .filter_map(|(bin, exp)| {
+            let reference = ref_bins.get(bin)?;
+            Some(PeakMatch {
+                mz: *bin as f64 * options.bin_width,
+                experimental_intensity: exp / exp_base,
+                reference_intensity: reference / ref_base,
+            })
+        })
+        .collect();
+
+    let ref_total: f64 = ref_bins.values().sum();
+    let ref_matched: f64 = ref_bins.iter()
+        .filter(|(bin, _)| exp_bins.contains_key(bin))
+        .map(|(_, intensity)| intensity)
+        .sum();
+
+    Ok(SpectralMatch {
+        method: options.method,
+        similarity: similarity.clamp(0.0, 1.0),
+        bin_width: options.bin_width,
+        experimental_peaks: filtered_exp.peaks.len(),
+        reference_peaks: filtered_ref.peaks.len(),
+        noise_peaks_removed,
+        matches,
+        explained_reference_intensity: if ref_total > 0.0 { ref_matched / ref_total } else { 0.0 },
+    })
+}
+
+/// Result of a modified cosine comparison
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModifiedCosine {
+    /// Similarity score (0.0 - 1.0)
+    pub score: f64,
+
+    /// Peaks paired between the two spectra
+    pub matched_peaks: usize,
+}
+
+/// Modified cosine similarity of two MS/MS spectra
+///
+/// Peaks are paired when their m/z agree within `tolerance` Da, or when they
+/// differ by the precursor mass difference, so that fragments carrying a
+/// modification still match. Each peak is paired at most once, greedily by
+/// the product of the scaled intensities, as in GNPS molecular networking.
+pub fn modified_cosine(
+    a: &Spectrum,
+    a_precursor_mz: f64,
+    b: &Spectrum,
+    b_precursor_mz: f64,
+    tolerance: f64,
+    intensity_power: f64,
+) -> ModifiedCosine {
+    let scaled = |spectrum: &Spectrum| -> Vec<f64> {
+        let intensities: Vec<f64> = spectrum.peaks.iter().map(|p| p.intensity.max(0.0).powf(intensity_power)).collect();
+        let norm = intensities.iter().map(|x| x * x).sum::<f64>().sqrt();
+        intensities.into_iter().map(|x| if norm > 0.0 { x / norm } else { 0.0 }).collect()
+    };
+    let (weights_a, weights_b) = (scaled(a), scaled(b));
+    let shift = b_precursor_mz - a_precursor_mz;
+
+    let mut candidates = Vec::new();
+    for (i, peak_a) in a.peaks.iter().enumerate() {
+        for (j, peak_b) in b.peaks.iter().enumerate() {
+            let difference = peak_b.mz - peak_a.mz;
+            if difference.abs() <= tolerance || (difference - shift).abs() <= tolerance {
+                candidates.push((weights_a[i] * weights_b[j], i, j));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| y.0.total_cmp(&x.0));
+
+    let (mut used_a, mut used_b) = (vec![false; a.peaks.len()], vec![false; b.peaks.len()]);
+    let mut score = 0.0;
+    let mut matched_peaks = 0;
+    for (product, i, j) in candidates {
+        if used_a[i] || used_b[j] {
             continue;
         }
-        
-        let parts: Vec<&str> = line.split(',').collect();
-        if parts.len() != 2 {
-            return Err(format!("Invalid format in line: {}", line));
+        used_a[i] = true;
+        used_b[j] = true;
+        score += product;
+        matched_peaks += 1;
+    }
+
+    ModifiedCosine { score: score.clamp(0.0, 1.0), matched_peaks }
+}
+
+/// An MS/MS spectrum of a feature, with its library identification if any
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MsMsSpectrum {
+    /// Feature ID (`FEATURE_ID`, `SCANS` or `TITLE`, else the position in the file)
+    pub id: String,
+
+    /// Precursor m/z
+    pub precursor_mz: f64,
+
+    /// Fragment peaks
+    pub spectrum: Spectrum,
+
+    /// Compound name, for identified features
+    pub name: Option<String>,
+
+    /// Compound structure, for identified features
+    pub smiles: Option<String>,
+
+    /// Confidence of the identification (0.0 - 1.0)
+    pub identification_confidence: Option<f64>,
+}
+
+/// Parse the spectra of an MGF file
+///
+/// Identified spectra carry `NAME` and optionally `SMILES`; their confidence
+/// is read from `CONFIDENCE` and is 1.0 when absent, as for library spectra.
+pub fn parse_mgf(data: &str) -> Result<Vec<MsMsSpectrum>, String> {
+    let mut spectra = Vec::new();
+    let mut current: Option<(HashMap<String, String>, Vec<Peak>)> = None;
+
+    for (line_number, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(['#', ';', '!']) {
+            continue;
         }
-        
-        let mz = parts[0].trim().parse::<f64>()
-            .map_err(|e| format!("Invalid m/z value: {}", e))?;
-        
-        let intensity = parts[1].trim().parse::<f64>()
-            .map_err(|e| format!("Invalid intensity value: {}", e))?;
-        
-        peaks.insert(mz, intensity);
-    }
-    
-    Ok(peaks)
-}
-
-/// Calculate cosine similarity between two spectra
-fn calculate_cosine_similarity(
-    spectrum1: &HashMap<f64, f64>,
-    spectrum2: &HashMap<f64, f64>,
-) -> f64 {
-    let mut dot_product = 0.0;
-    let mut norm1 = 0.0;
-    let mut norm2 = 0.0;
-    
-    // Calculate dot product and norms
-    for (mz, intensity) in spectrum1 {
-        norm1 += intensity * intensity;
-        
-        // Find matching peak in spectrum2 within a tolerance
-        if let Some(matched_intensity) = find_matching_peak(spectrum2, *mz, 0.1) {
-            dot_product += intensity * matched_intensity;
+        match (line, current.as_mut()) {
+            ("BEGIN IONS", None) => current = Some((HashMap::new(), Vec::new())),
+            ("BEGIN IONS", Some(_)) => return Err(format!("Unterminated spectrum before line {}", line_number + 1)),
+            ("END IONS", Some(_)) => {
+                let (fields, peaks) = current.take().unwrap_or_default();
+                spectra.push(mgf_spectrum(fields, peaks, spectra.len())?);
+            }
+            (_, None) => return Err(format!("Line {} is outside a spectrum", line_number + 1)),
+            (_, Some((fields, peaks))) => {
+                if let Some((key, value)) = line.split_once('=') {
+                    fields.insert(key.trim().to_uppercase(), value.trim().to_string());
+                    continue;
+                }
+                let mut parts = line.split_whitespace();
+                let peak = match (parts.next().map(str::parse::<f64>), parts.next().map(str::parse::<f64>)) {
+                    (Some(Ok(mz)), Some(Ok(intensity)))
+                        if mz.is_finite() && intensity.is_finite() && mz > 0.0 && intensity >= 0.0 => Peak { mz, intensity },
+                    _ => return Err(format!("Invalid peak in line {}: {}", line_number + 1, line)),
+                };
+                peaks.push(peak);
+            }
         }
     }
-    
-    // Calculate norm for spectrum2
-    for (_, intensity) in spectrum2 {
-        norm2 += intensity * intensity;
+    if current.is_some() {
+        return Err("Unterminated spectrum at end of file".to_string());
     }
-    
-    // Calculate cosine similarity
-    if norm1 > 0.0 && norm2 > 0.0 {
-        dot_product / (norm1.sqrt() * norm2.sqrt())
+
+    Ok(spectra)
+}
+
+fn mgf_spectrum(fields: HashMap<String, String>, peaks: Vec<Peak>, index: usize) -> Result<MsMsSpectrum, String> {
+    let id = ["FEATURE_ID", "SCANS", "TITLE"].iter()
+        .find_map(|key| fields.get(*key).filter(|value| !value.is_empty()).cloned())
+        .unwrap_or_else(|| format!("feature-{}", index + 1));
+    let precursor_mz = fields.get("PEPMASS")
+        .and_then(|value| value.split_whitespace().next()?.parse::<f64>().ok())
+        .ok_or_else(|| format!("Spectrum {} has no valid PEPMASS", id))?;
+    let name = fields.get("NAME").filter(|value| !value.is_empty()).cloned();
+    let confidence = match fields.get("CONFIDENCE") {
+        Some(value) => Some(value.parse::<f64>().map_err(|_| format!("Invalid CONFIDENCE for spectrum {}: {}", id, value))?),
+        None => name.as_ref().map(|_| 1.0),
+    };
+
+    Ok(MsMsSpectrum {
+        id,
+        precursor_mz,
+        spectrum: Spectrum::new(peaks),
+        name,
+        smiles: fields.get("SMILES").filter(|value| !value.is_empty() && value.as_str() != "N/A").cloned(),
+        identification_confidence: confidence,
+    })
+}
+
+/// Parse a peak list
+///
+/// Accepts one `m/z,intensity` pair per line (comma, tab or space separated,
+/// `#` comments allowed) as well as single-line `mz:intensity mz:intensity` lists.
+pub fn parse_peak_list(data: &str) -> Result<Spectrum, String> {
+    let mut peaks = Vec::new();
+
+    for line in data.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let pairs: Vec<&str> = if line.contains(':') {
+            line.split(|c: char| c.is_whitespace() || c == ';').filter(|p| !p.is_empty()).collect()
+        } else {
+            vec![line]
+        };
+
+        for pair in pairs {
+            let parts: Vec<&str> = pair
+                .split([',', ':', '\t', ' '])
+                .filter(|p| !p.is_empty())
+                .collect();
+            if parts.len() != 2 {
+                return Err(format!("Invalid format in line: {}", line));
+            }
+
+            let mz = parts[0].parse::<f64>()
+                .map_err(|e| format!("Invalid m/z value: {}", e))?;
+            let intensity = parts[1].parse::<f64>()
+                .map_err(|e| format!("Invalid intensity value: {}", e))?;
+            if !mz.is_finite() || !intensity.is_finite() || mz <= 0.0 || intensity < 0.0 {
+                return Err(format!("Out of range peak in line: {}", line));
+            }
+
+            peaks.push(Peak { mz, intensity });
+        }
+    }
+
+    Ok(Spectrum::new(peaks))
+}
+
+/// Cosine similarity of binned spectra after raising intensities to `power`
+fn cosine_similarity(a: &BTreeMap<i64, f64>, b: &BTreeMap<i64, f64>, power: f64) -> f64 {
+    let scale = |x: f64| x.powf(power);
+    let dot: f64 = a.iter()
+        .filter_map(|(bin, x)| b.get(bin).map(|y| scale(*x) * scale(*y)))
+        .sum();
+    let norm_a = a.values().map(|x| scale(*x).powi(2)).sum::<f64>().sqrt();
+    let norm_b = b.values().map(|y| scale(*y).powi(2)).sum::<f64>().sqrt();
+
+    if norm_a > 0.0 && norm_b > 0.0 {
+        dot / (norm_a * norm_b)
     } else {
         0.0
     }
 }
 
-/// Find a matching peak within a tolerance in the spectrum
-fn find_matching_peak(
-    spectrum: &HashMap<f64, f64>,
-    target_mz: f64,
-    tolerance: f64,
-) -> Option<f64> {
-    for (mz, intensity) in spectrum {
-        if (mz - target_mz).abs() <= tolerance {
-            return Some(*intensity);
-        }
+/// Spectral entropy similarity: 1 - (2 H(AB) - H(A) - H(B)) / ln 4
+fn entropy_similarity(a: &BTreeMap<i64, f64>, b: &BTreeMap<i64, f64>) -> f64 {
+    let a = entropy_weighted(a);
+    let b = entropy_weighted(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let mut merged: BTreeMap<i64, f64> = BTreeMap::new();
+    for (bin, p) in a.iter().chain(b.iter()) {
+        *merged.entry(*bin).or_insert(0.0) += p / 2.0;
+    }
+
+    let divergence = 2.0 * entropy(&merged) - entropy(&a) - entropy(&b);
+    1.0 - divergence / 4f64.ln()
+}
+
+/// Normalize to unit sum, boosting low-entropy spectra so minor peaks count
+fn entropy_weighted(bins: &BTreeMap<i64, f64>) -> BTreeMap<i64, f64> {
+    let normalized = normalize(bins);
+    let h = entropy(&normalized);
+    if h >= 3.0 {
+        return normalized;
+    }
+
+    let weight = 0.25 + 0.25 * h;
+    normalize(&normalized.into_iter().map(|(bin, p)| (bin, p.powf(weight))).collect())
+}
+
+fn normalize(bins: &BTreeMap<i64, f64>) -> BTreeMap<i64, f64> {
+    let total: f64 = bins.values().sum();
+    if total <= 0.0 {
+        return BTreeMap::new();
     }
-    
-    None
+    bins.iter().map(|(bin, x)| (*bin, x / total)).collect()
+}
+
+fn entropy(distribution: &BTreeMap<i64, f64>) -> f64 {
+    distribution.values()
+        .filter(|p| **p > 0.0)
+        .map(|p| -p * p.ln())
+        .sum()
 }
 
 /// Process a mass spectrum to identify significant peaks
 pub fn identify_significant_peaks(
-    spectrum: &HashMap<f64, f64>,
+    spectrum: &Spectrum,
     threshold_percentage: f64,
 ) -> Vec<(f64, f64)> {
-    if spectrum.is_empty() {
-        return Vec::new();
-    }
-    
-    // Find maximum intensity
-    let max_intensity = spectrum.values().cloned().fold(0.0, f64::max);
-    let threshold = max_intensity * threshold_percentage;
-    
-    // Extract significant peaks
-    spectrum
+    let threshold = spectrum.base_peak_intensity() * threshold_percentage;
+
+    spectrum.peaks
         .iter()
-        .filter(|(_, &intensity)| intensity >= threshold)
-        .map(|(&mz, &intensity)| (mz, intensity))
+        .filter(|p| p.intensity >= threshold)
+        .map(|p| (p.mz, p.intensity))
         .collect()
 }
 
 /// De-noise a spectrum by removing low intensity peaks
 pub fn denoise_spectrum(
-    spectrum: &HashMap<f64, f64>,
+    spectrum: &Spectrum,
     noise_threshold: f64,
-) -> HashMap<f64, f64> {
-    spectrum
-        .iter()
-        .filter(|(_, &intensity)| intensity > noise_threshold)
-        .map(|(&mz, &intensity)| (mz, intensity))
-        .collect()
-} 
\ No newline at end of file
+) -> Spectrum {
+    Spectrum {
+        peaks: spectrum.peaks.iter().filter(|p| p.intensity > noise_threshold).cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REFERENCE: &str = "# aspirin fragments\n121.03,100\n93.03,35\n65.04,20\n138.03,5\n";
+
+    #[test]
+    fn test_parse_peak_list_formats() {
+        let lines = parse_peak_list(REFERENCE).unwrap();
+        let inline = parse_peak_list("121.03:100 93.03:35 65.04:20 138.03:5").unwrap();
+        assert_eq!(lines.peaks, inline.peaks);
+        assert_eq!(lines.peaks[0].mz, 65.04);
+        assert!(parse_peak_list("121.03,100,3").is_err());
+        assert!(parse_peak_list("NaN 100").is_err());
+        assert!(parse_peak_list("121.03:inf").is_err());
+        assert!(parse_peak_list("inf,100").is_err());
+    }
+
+    #[test]
+    fn test_spectral_match() {
+        let experimental = "121.04,90\n93.02,40\n65.04,15\n300.1,0.5\n";
+
+        for method in [SpectralSimilarityMethod::Cosine, SpectralSimilarityMethod::Entropy] {
+            let options = SpectralOptions { method, ..Default::default() };
+            let result = calculate_spectral_match(experimental, REFERENCE, &options).unwrap();
+            assert!(result.similarity > 0.8, "{:?} similarity {}", method, result.similarity);
+            assert_eq!(result.matches.len(), 3);
+            assert_eq!(result.noise_peaks_removed, 1);
+        }
+
+        let unrelated = calculate_spectral_match("200.1,100\n", REFERENCE, &SpectralOptions::default()).unwrap();
+        assert_eq!(unrelated.similarity, 0.0);
+    }
+
+    #[test]
+    fn test_modified_cosine_matches_shifted_fragments() {
+        let mgf = "BEGIN IONS\nFEATURE_ID=1\nPEPMASS=181.07\nNAME=caffeine\n138.07 100\n110.07 40\n83.06 20\nEND IONS\n\
+                   BEGIN IONS\nFEATURE_ID=2\nPEPMASS=197.07 1200\n154.07 90\n110.07 45\n83.06 15\nEND IONS\n";
+        let spectra = parse_mgf(mgf).unwrap();
+        assert_eq!(spectra.len(), 2);
+        assert_eq!(spectra[0].identification_confidence, Some(1.0));
+        assert_eq!(spectra[1].precursor_mz, 197.07);
+        assert!(spectra[1].name.is_none() && spectra[1].identification_confidence.is_none());
+
+        let (parent, hydroxylated) = (&spectra[0], &spectra[1]);
+        let plain = modified_cosine(&parent.spectrum, parent.precursor_mz, &hydroxylated.spectrum, parent.precursor_mz, 0.02, 0.5);
+        let modified = modified_cosine(&parent.spectrum, parent.precursor_mz, &hydroxylated.spectrum, hydroxylated.precursor_mz, 0.02, 0.5);
+        assert_eq!((plain.matched_peaks, modified.matched_peaks), (2, 3));
+        assert!(modified.score > 0.95 && plain.score < 0.7);
+
+        assert!(parse_mgf("BEGIN IONS\n138.07 100\nEND IONS\n").is_err());
+    }
+}
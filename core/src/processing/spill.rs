@@ -0,0 +1,317 @@
+//! Spill-to-Disk Module
+//!
+//! Keeps batch results within a memory budget. A `SpillBuffer` holds items in
+//! memory until their encoded size would exceed the budget, then writes
+//! everything it holds to a bincode run file in a private temporary directory
+//! and starts again. Iterating the buffer streams the runs back from disk
+//! followed by the items still in memory, in the order they were pushed. The
+//! directory is removed once the buffer (or its iterator) is dropped.
+
+use anyhow::{Context, Result};
+use log::{info, debug};
+use serde::{de::DeserializeOwned, Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Items spilled by all buffers since the process started
+static TOTAL_ITEMS_SPILLED: AtomicU64 = AtomicU64::new(0);
+
+/// Bytes spilled by all buffers since the process started
+static TOTAL_BYTES_SPILLED: AtomicU64 = AtomicU64::new(0);
+
+/// Run files written by all buffers since the process started
+static TOTAL_SPILL_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// Largest in-memory footprint of any buffer since the process started
+static PEAK_RESIDENT_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Initialize the spill-to-disk module
+pub fn initialize() -> Result<()> {
+    info!("Initializing spill-to-disk module");
+    info!("Spill-to-disk module initialized successfully");
+    Ok(())
+}
+
+/// Memory a batch may use for its results before spilling to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryBudget {
+    /// Encoded bytes of results held in memory before they are spilled
+    pub max_bytes: u64,
+
+    /// Directory spill files are created under; the system temp directory if unset
+    pub spill_dir: Option<PathBuf>,
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1 << 30,
+            spill_dir: None,
+        }
+    }
+}
+
+impl MemoryBudget {
+    /// Budget that never spills
+    pub fn unlimited() -> Self {
+        Self {
+            max_bytes: u64::MAX,
+            spill_dir: None,
+        }
+    }
+
+    /// Read the budget from the environment, falling back to defaults
+    ///
+    /// `HEGEL_MEMORY_BUDGET_MB` sets the budget in mebibytes and
+    /// `HEGEL_SPILL_DIR` where spill files go.
+    pub fn from_env() -> Self {
+        let mut budget = Self::default();
+        if let Some(mb) = std::env::var("HEGEL_MEMORY_BUDGET_MB").ok().and_then(|v| v.parse::<u64>().ok()) {
+            budget.max_bytes = mb.saturating_mul(1 << 20);
+        }
+        if let Ok(dir) = std::env::var("HEGEL_SPILL_DIR") {
+            budget.spill_dir = Some(PathBuf::from(dir));
+        }
+        budget
+    }
+
+    /// Set the number of bytes held in memory before spilling
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Create spill files under `dir`
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = Some(dir.into());
+        self
+    }
+}
+
+/// Spill volume of one buffer, or of all buffers together
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpillStats {
+    /// Items written to disk
+    pub items_spilled: u64,
+
+    /// Encoded bytes written to disk
+    pub bytes_spilled: u64,
+
+    /// Run files written
+    pub spill_files: u64,
+
+    /// Largest number of encoded bytes held in memory at once
+    pub peak_resident_bytes: u64,
+}
+
+/// Spill volume across every buffer since the process started
+pub fn spill_totals() -> SpillStats {
+    SpillStats {
+        items_spilled: TOTAL_ITEMS_SPILLED.load(Ordering::Relaxed),
+        bytes_spilled: TOTAL_BYTES_SPILLED.load(Ordering::Relaxed),
+        spill_files: TOTAL_SPILL_FILES.load(Ordering::Relaxed),
+        peak_resident_bytes: PEAK_RESIDENT_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Append-only collection that spills to disk past its memory budget
+pub struct SpillBuffer<T> {
+    /// Memory budget and spill location
+    budget: MemoryBudget,
+
+    /// Items not yet spilled
+    resident: Vec<T>,
+
+    /// Encoded size of the resident items
+    resident_bytes: u64,
+
+    /// Spilled run files with their item counts, oldest first
+    runs: Vec<(PathBuf, usize)>,
+
+    /// Directory holding the run files, created on the first spill
+    dir: Option<tempfile::TempDir>,
+
+    /// Total number of items pushed
+    len: usize,
+
+    /// Spill volume of this buffer
+    stats: SpillStats,
+}
+
+impl<T: Serialize + DeserializeOwned> SpillBuffer<T> {
+    /// Create an empty buffer with the given budget
+    pub fn new(budget: MemoryBudget) -> Self {
+        Self {
+            budget,
+            resident: Vec::new(),
+            resident_bytes: 0,
+            runs: Vec::new(),
+            dir: None,
+            len: 0,
+            stats: SpillStats::default(),
+        }
+    }
+
+    /// Add an item, spilling the items held so far if it would exceed the budget
+    pub fn push(&mut self, item: T) -> Result<()> {
+        let size = bincode::serialized_size(&item).context("Failed to encode batch item")?;
+        if !self.resident.is_empty() && self.resident_bytes.saturating_add(size) > self.budget.max_bytes {
+            self.spill()?;
+        }
+        self.resident.push(item);
+        self.resident_bytes += size;
+        self.len += 1;
+        self.stats.peak_resident_bytes = self.stats.peak_resident_bytes.max(self.resident_bytes);
+        PEAK_RESIDENT_BYTES.fetch_max(self.resident_bytes, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Number of items pushed
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no items have been pushed
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Spill volume so far
+    pub fn stats(&self) -> &SpillStats {
+        &self.stats
+    }
+
+    /// Write the resident items to a new run file
+    fn spill(&mut self) -> Result<()> {
+        let dir = match &self.dir {
+            Some(dir) => dir.path().to_path_buf(),
+            None => {
+                let parent = self.budget.spill_dir.clone().unwrap_or_else(std::env::temp_dir);
+                std::fs::create_dir_all(&parent)
+                    .with_context(|| format!("Failed to create spill directory: {}", parent.display()))?;
+                let dir = tempfile::Builder::new()
+                    .prefix("hegel-spill-")
+                    .tempdir_in(&parent)
+                    .with_context(|| format!("Failed to create spill directory in {}", parent.display()))?;
+                let path = dir.path().to_path_buf();
+                self.dir = Some(dir);
+                path
+            }
+        };
+
+        let path = dir.join(format!("run-{:05}.bin", self.runs.len()));
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create spill file: {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        let count = self.resident.len();
+        for item in self.resident.drain(..) {
+            bincode::serialize_into(&mut writer, &item)
+                .with_context(|| format!("Failed to write spill file: {}", path.display()))?;
+        }
+        writer.flush().with_context(|| format!("Failed to write spill file: {}", path.display()))?;
+
+        debug!("Spilled {} items ({} bytes) to {}", count, self.resident_bytes, path.display());
+        self.stats.items_spilled += count as u64;
+        self.stats.bytes_spilled += self.resident_bytes;
+        self.stats.spill_files += 1;
+        TOTAL_ITEMS_SPILLED.fetch_add(count as u64, Ordering::Relaxed);
+        TOTAL_BYTES_SPILLED.fetch_add(self.resident_bytes, Ordering::Relaxed);
+        TOTAL_SPILL_FILES.fetch_add(1, Ordering::Relaxed);
+
+        self.runs.push((path, count));
+        self.resident_bytes = 0;
+        Ok(())
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> IntoIterator for SpillBuffer<T> {
+    type Item = Result<T>;
+    type IntoIter = SpillIter<T>;
+
+    /// Stream the items back in the order they were pushed
+    fn into_iter(self) -> SpillIter<T> {
+        if !self.runs.is_empty() {
+            debug!("Streaming {} items back from {} spill files", self.stats.items_spilled, self.runs.len());
+        }
+        SpillIter {
+            runs: self.runs.into(),
+            current: None,
+            resident: self.resident.into_iter(),
+            _dir: self.dir,
+        }
+    }
+}
+
+/// Iterator over a spill buffer's items, reading spilled runs lazily
+pub struct SpillIter<T> {
+    /// Run files not yet opened
+    runs: VecDeque<(PathBuf, usize)>,
+
+    /// Run being read and the number of items left in it
+    current: Option<(BufReader<File>, usize)>,
+
+    /// Items that were never spilled
+    resident: std::vec::IntoIter<T>,
+
+    /// Keeps the spill directory alive until iteration is done
+    _dir: Option<tempfile::TempDir>,
+}
+
+impl<T: DeserializeOwned> Iterator for SpillIter<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        loop {
+            if let Some((reader, remaining)) = &mut self.current {
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    return Some(bincode::deserialize_from(reader).context("Failed to read spilled batch item"));
+                }
+                self.current = None;
+            }
+            match self.runs.pop_front() {
+                Some((path, count)) => match File::open(&path) {
+                    Ok(file) => self.current = Some((BufReader::new(file), count)),
+                    Err(e) => {
+                        return Some(Err(anyhow::Error::new(e)
+                            .context(format!("Failed to open spill file: {}", path.display()))));
+                    }
+                },
+                None => return self.resident.next().map(Ok),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spills_past_budget_and_streams_back_in_order() {
+        let root = std::env::temp_dir().join(format!("hegel-spill-test-{}", std::process::id()));
+        let mut buffer = SpillBuffer::new(MemoryBudget::default().with_max_bytes(256).with_spill_dir(&root));
+        for i in 0..100u64 {
+            buffer.push((i, format!("molecule-{}", i))).unwrap();
+        }
+
+        let stats = buffer.stats().clone();
+        assert_eq!(buffer.len(), 100);
+        assert!(stats.spill_files > 1 && stats.items_spilled < 100);
+        assert!(stats.peak_resident_bytes <= 256);
+        assert!(spill_totals().bytes_spilled >= stats.bytes_spilled);
+
+        let items: Vec<(u64, String)> = buffer.into_iter().collect::<Result<_>>().unwrap();
+        assert_eq!(items.len(), 100);
+        assert!(items.iter().enumerate().all(|(i, (n, name))| *n == i as u64 && *name == format!("molecule-{}", i)));
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 0);
+        std::fs::remove_dir_all(&root).ok();
+
+        let mut unlimited = SpillBuffer::new(MemoryBudget::unlimited());
+        unlimited.push(1u8).unwrap();
+        assert_eq!(unlimited.stats().spill_files, 0);
+    }
+}
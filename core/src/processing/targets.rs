@@ -0,0 +1,596 @@
+//! Protein Target Processing Module
+//!
+//! This module loads bioactivity measurements from ChEMBL activity exports
+//! and BindingDB TSV dumps and turns them into knowledge graph edges and
+//! evidence. Each measurement links a molecule to a protein target with an
+//! affinity (IC50, Ki, Kd, EC50), stored as an `INHIBITS`, `ACTIVATES` or
+//! `INTERACTS_WITH` edge.
+//!
+//! Two kinds of evidence come from a molecule's target profile. Independent
+//! measurements that agree on its affinity for a target are `Structural`
+//! evidence: binding data this consistent was recorded for this structure.
+//! Potent targets that are also observed in the sample (expressed genes or
+//! detected proteins) are `Pathway` evidence, in the same spirit as pathway
+//! coherence.
+
+use anyhow::{anyhow, Context, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::graph::schema::{Edge, EdgeType, MolecularGraph, Node, NodeType};
+use crate::processing::evidence::{Evidence, EvidenceType};
+
+/// Source of evidence from target binding data
+pub const TARGET_EVIDENCE_SOURCE: &str = "target-binding";
+
+/// pAffinity at or above which a target counts as potently bound (1 µM)
+pub const POTENT_P_AFFINITY: f64 = 6.0;
+
+/// Spread of pAffinity, in log units, at which agreement drops to about 37%
+const CONSISTENCY_SCALE: f64 = 0.5;
+
+/// Initialize the protein target processing module
+pub fn initialize() -> Result<()> {
+    info!("Initializing protein target processing module");
+    info!("Protein target processing module initialized successfully");
+    Ok(())
+}
+
+/// Database a bioactivity file was exported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivitySource {
+    /// ChEMBL activity export (web download or API column names)
+    ChEMBL,
+
+    /// BindingDB TSV dump
+    BindingDB,
+}
+
+impl fmt::Display for ActivitySource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActivitySource::ChEMBL => write!(f, "chembl"),
+            ActivitySource::BindingDB => write!(f, "bindingdb"),
+        }
+    }
+}
+
+impl FromStr for ActivitySource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "chembl" => Ok(ActivitySource::ChEMBL),
+            "bindingdb" => Ok(ActivitySource::BindingDB),
+            _ => Err(anyhow!("Unknown activity source: {} (expected chembl or bindingdb)", s)),
+        }
+    }
+}
+
+/// What a molecule does to its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetAction {
+    /// Inhibits or antagonizes the target
+    Inhibits,
+
+    /// Activates or agonizes the target
+    Activates,
+
+    /// Binds the target with no direction recorded
+    Binds,
+}
+
+impl TargetAction {
+    /// Action implied by a ChEMBL action type, or failing that the activity type
+    ///
+    /// IC50 and Ki are measured by inhibition and EC50 by activation; Kd only
+    /// says that the molecule binds.
+    pub fn infer(action_type: Option<&str>, activity_type: &str) -> Self {
+        if let Some(action) = action_type.map(str::to_uppercase) {
+            if ["INHIBITOR", "ANTAGONIST", "BLOCKER", "NEGATIVE"].iter().any(|a| action.contains(a)) {
+                return TargetAction::Inhibits;
+            }
+            if ["AGONIST", "ACTIVATOR", "OPENER", "POSITIVE"].iter().any(|a| action.contains(a)) {
+                return TargetAction::Activates;
+            }
+        }
+        match activity_type.to_uppercase().as_str() {
+            "IC50" | "KI" => TargetAction::Inhibits,
+            "EC50" | "AC50" => TargetAction::Activates,
+            _ => TargetAction::Binds,
+        }
+    }
+
+    /// Edge type for the action
+    pub fn edge_type(&self) -> EdgeType {
+        match self {
+            TargetAction::Inhibits => EdgeType::Inhibits,
+            TargetAction::Activates => EdgeType::Activates,
+            TargetAction::Binds => EdgeType::InteractsWith,
+        }
+    }
+}
+
+/// One bioactivity measurement of a molecule against a protein target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityRecord {
+    /// Molecule ID (ChEMBL ID, InChIKey or PubChem CID, as the source gives it)
+    pub molecule_id: String,
+
+    /// Target ID (UniProt accession or ChEMBL target ID)
+    pub target_id: String,
+
+    /// Target name
+    pub target_name: Option<String>,
+
+    /// Organism of the target
+    pub organism: Option<String>,
+
+    /// Activity type, e.g. `IC50`, `Ki`, `Kd` or `EC50`
+    pub activity_type: String,
+
+    /// Measured value in nM
+    pub value_nm: f64,
+
+    /// Relation of the true value to the measured one (`=`, `<`, `>`)
+    pub relation: String,
+
+    /// What the molecule does to the target
+    pub action: TargetAction,
+
+    /// Database the record came from
+    pub source: ActivitySource,
+}
+
+impl ActivityRecord {
+    /// Affinity as `-log10(molar)`, e.g. 7.0 for 100 nM
+    pub fn p_affinity(&self) -> f64 {
+        9.0 - self.value_nm.log10()
+    }
+
+    /// Whether the value is exact rather than a bound such as `>10000`
+    pub fn is_exact(&self) -> bool {
+        self.relation == "=" || self.relation == "~"
+    }
+
+    /// Edge from the molecule to the target, with the affinity as properties
+    pub fn to_edge(&self) -> Edge {
+        let mut edge = Edge::new(self.molecule_id.clone(), self.target_id.clone(), self.action.edge_type());
+        edge.add_property("activity_type", serde_json::json!(self.activity_type))
+            .add_property("value_nm", serde_json::json!(self.value_nm))
+            .add_property("relation", serde_json::json!(self.relation))
+            .add_property("p_affinity", serde_json::json!(self.p_affinity()))
+            .add_property("source", serde_json::json!(self.source));
+        edge
+    }
+}
+
+/// Agreement of the measurements of one molecule against one target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetConsistency {
+    /// Target ID
+    pub target_id: String,
+
+    /// Target name
+    pub target_name: Option<String>,
+
+    /// Action most of the records agree on
+    pub action: TargetAction,
+
+    /// Number of exact measurements
+    pub measurements: usize,
+
+    /// Databases the measurements came from
+    pub sources: Vec<ActivitySource>,
+
+    /// Median pAffinity of the exact measurements
+    pub p_affinity: f64,
+
+    /// Standard deviation of the pAffinities, in log units
+    pub spread: f64,
+
+    /// Agreement of the measurements, `exp(-spread / 0.5)` (0.0 - 1.0)
+    pub consistency: f64,
+}
+
+/// Bioactivity measurements loaded from one or more databases
+#[derive(Debug, Clone, Default)]
+pub struct TargetActivities {
+    /// Measurements in load order
+    records: Vec<ActivityRecord>,
+}
+
+impl TargetActivities {
+    /// Create an empty collection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All measurements
+    pub fn records(&self) -> &[ActivityRecord] {
+        &self.records
+    }
+
+    /// Add a measurement
+    pub fn add(&mut self, record: ActivityRecord) {
+        self.records.push(record);
+    }
+
+    /// Load a ChEMBL or BindingDB export, returning the number of measurements added
+    pub fn load(&mut self, path: &Path, source: ActivitySource) -> Result<usize> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open {} activity file: {}", source, path.display()))?;
+        let loaded = self.read(BufReader::new(file), source)
+            .with_context(|| format!("Failed to read {} activity file: {}", source, path.display()))?;
+        info!("Loaded {} {} activities from {}", loaded, source, path.display());
+        Ok(loaded)
+    }
+
+    /// Read a delimited activity export with a header row
+    ///
+    /// The delimiter (tab, `;` or `,`) is taken from the header. Rows without
+    /// a molecule, a target or a positive value in nM are skipped.
+    pub fn read<R: BufRead>(&mut self, reader: R, source: ActivitySource) -> Result<usize> {
+        let mut lines = reader.lines();
+        let header = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(0),
+        };
+        let delimiter = ['\t', ';', ','].into_iter()
+            .max_by_key(|d| header.matches(*d).count())
+            .unwrap_or('\t');
+        let columns: Vec<String> = split_row(&header, delimiter).iter().map(|c| c.to_lowercase()).collect();
+        let find = |names: &[&str]| names.iter().find_map(|name| columns.iter().position(|c| c == name));
+
+        let before = self.records.len();
+        let mut skipped = 0;
+        match source {
+            ActivitySource::ChEMBL => {
+                let molecule = find(&["molecule chembl id", "molecule_chembl_id"])
+                    .ok_or_else(|| anyhow!("ChEMBL export has no molecule ChEMBL ID column"))?;
+                let target = find(&["target chembl id", "target_chembl_id"])
+                    .ok_or_else(|| anyhow!("ChEMBL export has no target ChEMBL ID column"))?;
+                let activity_type = find(&["standard type", "standard_type"])
+                    .ok_or_else(|| anyhow!("ChEMBL export has no standard type column"))?;
+                let value = find(&["standard value", "standard_value"])
+                    .ok_or_else(|| anyhow!("ChEMBL export has no standard value column"))?;
+                let units = find(&["standard units", "standard_units"]);
+                let relation = find(&["standard relation", "standard_relation"]);
+                let name = find(&["target name", "target_pref_name"]);
+                let organism = find(&["target organism", "target_organism"]);
+                let action = find(&["action type", "action_type"]);
+
+                for line in lines {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let row = split_row(&line, delimiter);
+                    let cell = |index: Option<usize>| index.and_then(|i| row.get(i)).map(|s| s.as_str()).filter(|s| !s.is_empty());
+                    let units = cell(units).unwrap_or("nM");
+                    let parsed = cell(Some(value)).and_then(|v| v.parse::<f64>().ok()).and_then(|v| to_nanomolar(v, units));
+                    match (cell(Some(molecule)), cell(Some(target)), cell(Some(activity_type)), parsed) {
+                        (Some(molecule), Some(target), Some(activity_type), Some(value_nm)) => self.records.push(ActivityRecord {
+                            molecule_id: molecule.to_string(),
+                            target_id: target.to_string(),
+                            target_name: cell(name).map(str::to_string),
+                            organism: cell(organism).map(str::to_string),
+                            activity_type: activity_type.to_string(),
+                            value_nm,
+                            relation: cell(relation).unwrap_or("=").trim_matches('\'').to_string(),
+                            action: TargetAction::infer(cell(action), activity_type),
+                            source,
+                        }),
+                        _ => skipped += 1,
+                    }
+                }
+            }
+            ActivitySource::BindingDB => {
+                let molecule = [
+                    find(&["chembl id of ligand"]),
+                    find(&["ligand inchi key"]),
+                    find(&["pubchem cid"]),
+                ];
+                if molecule.iter().all(Option::is_none) {
+                    return Err(anyhow!("BindingDB dump has no ligand ChEMBL ID, InChIKey or PubChem CID column"));
+                }
+                let target = find(&["uniprot (swissprot) primary id of target chain"])
+                    .ok_or_else(|| anyhow!("BindingDB dump has no target UniProt column"))?;
+                let name = find(&["target name", "target name assigned by curator or datasource"]);
+                let organism = find(&["target source organism according to curator or datasource"]);
+                let measures: Vec<(&str, usize)> = [("Ki", "ki (nm)"), ("IC50", "ic50 (nm)"), ("Kd", "kd (nm)"), ("EC50", "ec50 (nm)")]
+                    .into_iter()
+                    .filter_map(|(kind, column)| Some((kind, find(&[column])?)))
+                    .collect();
+
+                for line in lines {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let row = split_row(&line, '\t');
+                    let cell = |index: Option<usize>| index.and_then(|i| row.get(i)).map(|s| s.as_str()).filter(|s| !s.is_empty());
+                    let (molecule_id, target_id) = match (molecule.iter().find_map(|column| cell(*column)), cell(Some(target))) {
+                        (Some(molecule_id), Some(target_id)) => (molecule_id, target_id),
+                        _ => {
+                            skipped += 1;
+                            continue;
+                        }
+                    };
+                    for (kind, column) in &measures {
+                        let Some(raw) = cell(Some(*column)) else { continue };
+                        let (relation, number) = split_relation(raw);
+                        let Some(value_nm) = number.parse::<f64>().ok().filter(|v| *v > 0.0) else {
+                            skipped += 1;
+                            continue;
+                        };
+                        self.records.push(ActivityRecord {
+                            molecule_id: molecule_id.to_string(),
+                            target_id: target_id.to_string(),
+                            target_name: cell(name).map(str::to_string),
+                            organism: cell(organism).map(str::to_string),
+                            activity_type: kind.to_string(),
+                            value_nm,
+                            relation: relation.to_string(),
+                            action: TargetAction::infer(None, kind),
+                            source,
+                        });
+                    }
+                }
+            }
+        }
+        if skipped > 0 {
+            debug!("Skipped {} {} activity rows without a usable measurement", skipped, source);
+        }
+        Ok(self.records.len() - before)
+    }
+
+    /// Measurements of one molecule
+    pub fn for_molecule<'a>(&'a self, molecule_id: &'a str) -> impl Iterator<Item = &'a ActivityRecord> + 'a {
+        self.records.iter().filter(move |r| r.molecule_id == molecule_id)
+    }
+
+    /// Agreement of a molecule's measurements, per target, most potent first
+    ///
+    /// Only exact measurements count; targets with none are left out.
+    pub fn consistency(&self, molecule_id: &str) -> Vec<TargetConsistency> {
+        let mut by_target: BTreeMap<&str, Vec<&ActivityRecord>> = BTreeMap::new();
+        for record in self.for_molecule(molecule_id).filter(|r| r.is_exact()) {
+            by_target.entry(record.target_id.as_str()).or_default().push(record);
+        }
+
+        let mut targets: Vec<TargetConsistency> = by_target.into_iter()
+            .map(|(target_id, records)| {
+                let mut affinities: Vec<f64> = records.iter().map(|r| r.p_affinity()).collect();
+                affinities.sort_by(f64::total_cmp);
+                let mid = affinities.len() / 2;
+                let median = if affinities.len().is_multiple_of(2) {
+                    (affinities[mid - 1] + affinities[mid]) / 2.0
+                } else {
+                    affinities[mid]
+                };
+                let mean = affinities.iter().sum::<f64>() / affinities.len() as f64;
+                let spread = (affinities.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / affinities.len() as f64).sqrt();
+
+                let mut actions: HashMap<TargetAction, usize> = HashMap::new();
+                for record in &records {
+                    *actions.entry(record.action).or_default() += 1;
+                }
+                let action = [TargetAction::Inhibits, TargetAction::Activates, TargetAction::Binds].into_iter()
+                    .max_by_key(|a| actions.get(a).copied().unwrap_or(0))
+                    .unwrap_or(TargetAction::Binds);
+                let mut sources: Vec<ActivitySource> = records.iter().map(|r| r.source).collect();
+                sources.sort_by_key(|s| s.to_string());
+                sources.dedup();
+
+                TargetConsistency {
+                    target_id: target_id.to_string(),
+                    target_name: records.iter().find_map(|r| r.target_name.clone()),
+                    action,
+                    measurements: records.len(),
+                    sources,
+                    p_affinity: median,
+                    spread,
+                    consistency: (-spread / CONSISTENCY_SCALE).exp(),
+                }
+            })
+            .collect();
+        targets.sort_by(|a, b| b.p_affinity.total_cmp(&a.p_affinity).then_with(|| a.target_id.cmp(&b.target_id)));
+        targets
+    }
+
+    /// Molecule and protein nodes with one edge per molecule–target pair
+    ///
+    /// Each edge carries the most potent exact measurement of the pair, or
+    /// the first bound when the pair has no exact one.
+    pub fn to_graph(&self, id: &str, name: &str) -> MolecularGraph {
+        let mut best: BTreeMap<(&str, &str), &ActivityRecord> = BTreeMap::new();
+        for record in &self.records {
+            let key = (record.molecule_id.as_str(), record.target_id.as_str());
+            let better = match best.get(&key) {
+                None => true,
+                Some(current) => (record.is_exact(), record.p_affinity()) > (current.is_exact(), current.p_affinity()),
+            };
+            if better {
+                best.insert(key, record);
+            }
+        }
+
+        let mut graph = MolecularGraph::new(id.to_string(), name.to_string());
+        let mut molecules: BTreeMap<&str, Node> = BTreeMap::new();
+        let mut proteins: BTreeMap<&str, Node> = BTreeMap::new();
+        for record in best.values() {
+            molecules.entry(record.molecule_id.as_str())
+                .or_insert_with(|| Node::new(record.molecule_id.clone(), NodeType::Molecule, record.molecule_id.clone()));
+            proteins.entry(record.target_id.as_str()).or_insert_with(|| {
+                let label = record.target_name.clone().unwrap_or_else(|| record.target_id.clone());
+                let mut node = Node::new(record.target_id.clone(), NodeType::Protein, label);
+                if let Some(organism) = &record.organism {
+                    node.add_property("organism", serde_json::json!(organism));
+                }
+                node
+            });
+        }
+        for node in molecules.into_values().chain(proteins.into_values()) {
+            graph.add_node(node);
+        }
+        for record in best.values() {
+            graph.add_edge(record.to_edge());
+        }
+        graph
+    }
+
+    /// Structural evidence from the agreement of a molecule's potent measurements
+    ///
+    /// The confidence is the consistency of the best-agreeing potent target,
+    /// scaled down when only one measurement backs it. `None` when the
+    /// molecule has no potent target.
+    pub fn structural_evidence(&self, molecule_id: &str) -> Option<Evidence> {
+        let potent: Vec<TargetConsistency> = self.consistency(molecule_id).into_iter()
+            .filter(|t| t.p_affinity >= POTENT_P_AFFINITY)
+            .collect();
+        let support = |t: &TargetConsistency| t.consistency * (1.0 - 0.5f64.powi(t.measurements as i32));
+        let best = potent.iter().max_by(|a, b| support(a).total_cmp(&support(b)))?;
+        Some(target_evidence(molecule_id, EvidenceType::Structural, support(best), &potent))
+    }
+
+    /// Pathway evidence from potent targets observed in the sample
+    ///
+    /// `observed` maps target IDs (genes or proteins seen in the sample) to
+    /// their confidence. The confidence is the confidence-weighted share of
+    /// the molecule's potent targets that were observed; `None` when none
+    /// was.
+    pub fn pathway_evidence(&self, molecule_id: &str, observed: &HashMap<String, f64>) -> Option<Evidence> {
+        let potent: Vec<TargetConsistency> = self.consistency(molecule_id).into_iter()
+            .filter(|t| t.p_affinity >= POTENT_P_AFFINITY)
+            .collect();
+        let seen: Vec<TargetConsistency> = potent.iter()
+            .filter(|t| observed.contains_key(&t.target_id))
+            .cloned()
+            .collect();
+        if seen.is_empty() {
+            return None;
+        }
+        let weight: f64 = seen.iter().map(|t| observed[&t.target_id].clamp(0.0, 1.0)).sum();
+        Some(target_evidence(molecule_id, EvidenceType::Pathway, weight / potent.len() as f64, &seen))
+    }
+}
+
+/// Evidence for a molecule from the targets that support it
+fn target_evidence(molecule_id: &str, evidence_type: EvidenceType, confidence: f64, targets: &[TargetConsistency]) -> Evidence {
+    let mut metadata = HashMap::new();
+    metadata.insert("targets".to_string(), serde_json::json!(targets.iter().map(|t| &t.target_id).collect::<Vec<_>>()));
+
+    Evidence {
+        id: format!("{}-{}-{}", TARGET_EVIDENCE_SOURCE, evidence_type, molecule_id),
+        molecule_id: molecule_id.to_string(),
+        evidence_type,
+        source: TARGET_EVIDENCE_SOURCE.to_string(),
+        confidence: confidence.clamp(0.0, 1.0),
+        data: serde_json::json!({ "targets": targets }),
+        metadata,
+        timestamp: chrono::Utc::now(),
+    }
+}
+
+/// Split a delimited row, trimming cells and the quotes around them
+fn split_row(line: &str, delimiter: char) -> Vec<String> {
+    line.split(delimiter).map(|cell| cell.trim().trim_matches('"').trim().to_string()).collect()
+}
+
+/// Split a BindingDB value such as `>10000` into its relation and number
+fn split_relation(raw: &str) -> (&str, &str) {
+    let raw = raw.trim();
+    for relation in ["<=", ">=", "<", ">", "~"] {
+        if let Some(rest) = raw.strip_prefix(relation) {
+            return (relation, rest.trim());
+        }
+    }
+    ("=", raw)
+}
+
+/// Convert a concentration to nM
+fn to_nanomolar(value: f64, units: &str) -> Option<f64> {
+    let factor = match units.trim() {
+        "nM" => 1.0,
+        "pM" => 1e-3,
+        "uM" | "µM" | "μM" => 1e3,
+        "mM" => 1e6,
+        "M" => 1e9,
+        _ => return None,
+    };
+    Some(value * factor).filter(|v| *v > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHEMBL: &str = "\"Molecule ChEMBL ID\";\"Standard Type\";\"Standard Relation\";\"Standard Value\";\"Standard Units\";\"Target ChEMBL ID\";\"Target Name\";\"Action Type\"\n\
+                          \"CHEMBL25\";\"IC50\";\"'='\";\"1500\";\"nM\";\"CHEMBL221\";\"Cyclooxygenase-1\";\"\"\n\
+                          \"CHEMBL25\";\"IC50\";\"'='\";\"1.2\";\"uM\";\"CHEMBL221\";\"Cyclooxygenase-1\";\"\"\n\
+                          \"CHEMBL25\";\"Ki\";\"'>'\";\"100000\";\"nM\";\"CHEMBL230\";\"Cyclooxygenase-2\";\"\"\n";
+
+    const BINDINGDB: &str = "Ligand InChI Key\tTarget Name\tKi (nM)\tIC50 (nM)\tUniProt (SwissProt) Primary ID of Target Chain\n\
+                             BSYNRYMUTXBXSQ-UHFFFAOYSA-N\tProstaglandin G/H synthase 1\t\t>1000\tP23219\n\
+                             RZVAJINKPMORJF-UHFFFAOYSA-N\tAdenosine receptor A2a\t40\t\tP29274\n";
+
+    #[test]
+    fn chembl_and_bindingdb_exports_are_read() {
+        let mut activities = TargetActivities::new();
+        assert_eq!(activities.read(CHEMBL.as_bytes(), ActivitySource::ChEMBL).unwrap(), 3);
+        assert_eq!(activities.read(BINDINGDB.as_bytes(), ActivitySource::BindingDB).unwrap(), 2);
+
+        let records = activities.records();
+        assert!((records[1].value_nm - 1200.0).abs() < 1e-9);
+        assert_eq!(records[2].relation, ">");
+        assert_eq!(records[3].relation, ">");
+        assert_eq!(records[3].target_id, "P23219");
+        assert!((records[4].p_affinity() - (9.0 - 40f64.log10())).abs() < 1e-12);
+
+        let graph = activities.to_graph("targets", "Targets");
+        assert_eq!(graph.find_nodes_by_type(NodeType::Protein).len(), 4);
+        assert_eq!(graph.find_edges_by_type(EdgeType::Inhibits).len(), 4);
+    }
+
+    #[test]
+    fn agreeing_potent_measurements_become_evidence() {
+        let record = |value_nm: f64, target: &str| ActivityRecord {
+            molecule_id: "CHEMBL1".to_string(),
+            target_id: target.to_string(),
+            target_name: None,
+            organism: None,
+            activity_type: "Ki".to_string(),
+            value_nm,
+            relation: "=".to_string(),
+            action: TargetAction::Inhibits,
+            source: ActivitySource::ChEMBL,
+        };
+        let mut activities = TargetActivities::new();
+        for value in [10.0, 12.0, 9.0] {
+            activities.add(record(value, "P00533"));
+        }
+        activities.add(record(50.0, "P04626"));
+
+        let consistency = activities.consistency("CHEMBL1");
+        assert_eq!(consistency[0].target_id, "P00533");
+        assert!(consistency[0].consistency > 0.8);
+
+        let structural = activities.structural_evidence("CHEMBL1").unwrap();
+        assert_eq!(structural.evidence_type, EvidenceType::Structural);
+        assert!(structural.confidence > 0.7);
+
+        let observed = HashMap::from([("P04626".to_string(), 0.9)]);
+        let pathway = activities.pathway_evidence("CHEMBL1", &observed).unwrap();
+        assert!((pathway.confidence - 0.45).abs() < 1e-9);
+        assert!(activities.pathway_evidence("CHEMBL1", &HashMap::new()).is_none());
+    }
+}
@@ -0,0 +1,235 @@
+//! Time-Budgeted Analysis
+//!
+//! Lets interactive callers bound how long the analysis of one molecule may
+//! take. Each expensive stage has an estimated cost; before a stage runs, the
+//! clock checks whether it still fits in the budget alongside the more
+//! important stages that have yet to run. Stages are given up in a fixed
+//! priority order (LLM calls first, graph lookups last) and every skipped
+//! stage is reported, so a fast answer is never mistaken for a complete one.
+
+use anyhow::{anyhow, Result};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Initialize the time budget module
+pub fn initialize() -> Result<()> {
+    info!("Initializing time budget module");
+    info!("Time budget module initialized successfully");
+    Ok(())
+}
+
+/// Optional analysis stage that can be skipped to stay within a time budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisStage {
+    /// AI-guided rectification through the LLM
+    Llm,
+
+    /// Integration of the evidence into a conflict graph
+    ConflictGraph,
+
+    /// Interaction lookups in the graph store
+    Interactions,
+
+    /// Pathway lookups in the graph store
+    Pathways,
+}
+
+impl AnalysisStage {
+    /// Stages in the order they are given up, the first skipped first
+    pub const SKIP_ORDER: [AnalysisStage; 4] = [
+        AnalysisStage::Llm,
+        AnalysisStage::ConflictGraph,
+        AnalysisStage::Interactions,
+        AnalysisStage::Pathways,
+    ];
+
+    /// Rank of the stage in `SKIP_ORDER`; higher ranks are kept longer
+    pub fn priority(&self) -> usize {
+        Self::SKIP_ORDER.iter().position(|stage| stage == self).unwrap_or(0)
+    }
+
+    /// Typical time the stage takes for one molecule
+    pub fn default_estimate(&self) -> Duration {
+        match self {
+            AnalysisStage::Llm => Duration::from_millis(1500),
+            AnalysisStage::ConflictGraph => Duration::from_millis(200),
+            AnalysisStage::Interactions => Duration::from_millis(250),
+            AnalysisStage::Pathways => Duration::from_millis(250),
+        }
+    }
+}
+
+impl fmt::Display for AnalysisStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnalysisStage::Llm => write!(f, "llm"),
+            AnalysisStage::ConflictGraph => write!(f, "conflict_graph"),
+            AnalysisStage::Interactions => write!(f, "interactions"),
+            AnalysisStage::Pathways => write!(f, "pathways"),
+        }
+    }
+}
+
+impl FromStr for AnalysisStage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "llm" => Ok(AnalysisStage::Llm),
+            "conflict_graph" => Ok(AnalysisStage::ConflictGraph),
+            "interactions" => Ok(AnalysisStage::Interactions),
+            "pathways" => Ok(AnalysisStage::Pathways),
+            _ => Err(anyhow!("Unknown analysis stage: {} (expected llm, conflict_graph, interactions or pathways)", s)),
+        }
+    }
+}
+
+/// Time allowed for analyzing one molecule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeBudget {
+    /// Milliseconds each molecule may take
+    pub per_molecule_ms: u64,
+
+    /// Estimated milliseconds per stage, overriding the stage's default estimate
+    #[serde(default)]
+    pub estimates_ms: BTreeMap<AnalysisStage, u64>,
+}
+
+impl TimeBudget {
+    /// Budget of the given number of milliseconds per molecule
+    pub fn from_millis(per_molecule_ms: u64) -> Result<Self> {
+        if per_molecule_ms == 0 {
+            return Err(anyhow!("Time budget must be at least 1 ms per molecule"));
+        }
+        Ok(Self {
+            per_molecule_ms,
+            estimates_ms: BTreeMap::new(),
+        })
+    }
+
+    /// Override the estimated cost of a stage, e.g. for a slow LLM endpoint
+    pub fn with_estimate(mut self, stage: AnalysisStage, estimate: Duration) -> Self {
+        self.estimates_ms.insert(stage, estimate.as_millis() as u64);
+        self
+    }
+
+    /// Time allowed per molecule
+    pub fn per_molecule(&self) -> Duration {
+        Duration::from_millis(self.per_molecule_ms)
+    }
+
+    /// Estimated cost of a stage
+    pub fn estimate(&self, stage: AnalysisStage) -> Duration {
+        self.estimates_ms.get(&stage)
+            .map(|ms| Duration::from_millis(*ms))
+            .unwrap_or_else(|| stage.default_estimate())
+    }
+}
+
+/// Clock deciding which stages of one molecule's analysis still fit
+#[derive(Debug, Clone)]
+pub struct BudgetClock {
+    /// Budget being enforced; `None` admits every stage
+    budget: Option<TimeBudget>,
+
+    /// When the molecule's analysis started
+    started: Instant,
+
+    /// Planned stages that have not been admitted or skipped yet
+    pending: Vec<AnalysisStage>,
+
+    /// Stages skipped because of the budget, in the order they were skipped
+    skipped: Vec<AnalysisStage>,
+}
+
+impl BudgetClock {
+    /// Start timing a molecule whose analysis plans to run `planned`
+    pub fn start(budget: Option<&TimeBudget>, planned: &[AnalysisStage]) -> Self {
+        Self {
+            budget: budget.cloned(),
+            started: Instant::now(),
+            pending: planned.to_vec(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Time spent on the molecule so far
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether `stage` should run now
+    ///
+    /// A stage runs when its estimate fits in the remaining budget after
+    /// reserving time for the pending stages that are kept longer than it.
+    /// A stage that does not fit is recorded as skipped.
+    pub fn admit(&mut self, stage: AnalysisStage) -> bool {
+        let elapsed = self.elapsed();
+        self.admit_after(stage, elapsed)
+    }
+
+    fn admit_after(&mut self, stage: AnalysisStage, elapsed: Duration) -> bool {
+        self.pending.retain(|pending| *pending != stage);
+        let budget = match &self.budget {
+            Some(budget) => budget,
+            None => return true,
+        };
+
+        let reserved: Duration = self.pending.iter()
+            .filter(|pending| pending.priority() > stage.priority())
+            .map(|pending| budget.estimate(*pending))
+            .sum();
+        let fits = elapsed + reserved + budget.estimate(stage) <= budget.per_molecule();
+        if !fits {
+            debug!("Skipping {} stage: {} ms spent of a {} ms budget", stage, elapsed.as_millis(), budget.per_molecule_ms);
+            self.skipped.push(stage);
+        }
+        fits
+    }
+
+    /// Stages skipped so far because of the budget
+    pub fn skipped(&self) -> &[AnalysisStage] {
+        &self.skipped
+    }
+
+    /// Stop timing and return the skipped stages
+    pub fn into_skipped(self) -> Vec<AnalysisStage> {
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_stages_in_priority_order() {
+        let budget = TimeBudget::from_millis(800).unwrap();
+        let mut clock = BudgetClock::start(Some(&budget), &AnalysisStage::SKIP_ORDER);
+
+        // The LLM would leave no room for the lookups; the conflict graph still
+        // fits alongside them, but a slow interaction lookup crowds out pathways
+        assert!(!clock.admit_after(AnalysisStage::Llm, Duration::ZERO));
+        assert!(clock.admit_after(AnalysisStage::ConflictGraph, Duration::from_millis(10)));
+        assert!(clock.admit_after(AnalysisStage::Interactions, Duration::from_millis(210)));
+        assert!(!clock.admit_after(AnalysisStage::Pathways, Duration::from_millis(600)));
+        assert_eq!(clock.into_skipped(), vec![AnalysisStage::Llm, AnalysisStage::Pathways]);
+    }
+
+    #[test]
+    fn admits_everything_without_a_budget() {
+        let mut clock = BudgetClock::start(None, &AnalysisStage::SKIP_ORDER);
+        assert!(clock.admit_after(AnalysisStage::Llm, Duration::from_secs(60)));
+        assert!(clock.skipped().is_empty());
+
+        let budget = TimeBudget::from_millis(5000).unwrap().with_estimate(AnalysisStage::Llm, Duration::from_secs(4));
+        assert_eq!(budget.estimate(AnalysisStage::Llm), Duration::from_secs(4));
+        assert!(TimeBudget::from_millis(0).is_err());
+        assert_eq!("conflict_graph".parse::<AnalysisStage>().unwrap(), AnalysisStage::ConflictGraph);
+    }
+}
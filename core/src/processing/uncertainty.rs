@@ -0,0 +1,168 @@
+//! Uncertainty Propagation Module
+//!
+//! This module provides Monte Carlo propagation of evidence uncertainty: evidence
+//! confidences are sampled from their uncertainty bounds and pushed through the
+//! identity pipeline repeatedly to summarize the spread of the posterior.
+
+use anyhow::{Result, anyhow};
+use log::{info, debug};
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::fuzzy_evidence::FuzzyEvidence;
+use crate::processing::evidence::Evidence;
+use crate::rng;
+
+/// Initialize the uncertainty propagation module
+pub fn initialize() -> Result<()> {
+    info!("Initializing uncertainty propagation module");
+    info!("Uncertainty propagation module initialized successfully");
+    Ok(())
+}
+
+/// Configuration for Monte Carlo propagation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonteCarloConfig {
+    /// Number of samples to draw
+    pub samples: usize,
+
+    /// Mass of the reported credible interval (e.g. 0.95)
+    pub credible_level: f64,
+
+    /// Seed for the random number generator (falls back to the global seed, then random)
+    pub seed: Option<u64>,
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        Self {
+            samples: 1000,
+            credible_level: 0.95,
+            seed: None,
+        }
+    }
+}
+
+/// Summary of the sampled posterior confidence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncertaintySummary {
+    /// Number of samples drawn
+    pub samples: usize,
+
+    /// Posterior mean confidence
+    pub mean: f64,
+
+    /// Posterior variance
+    pub variance: f64,
+
+    /// Posterior standard deviation
+    pub std_dev: f64,
+
+    /// Mass of the credible interval
+    pub credible_level: f64,
+
+    /// Lower bound of the credible interval
+    pub lower_bound: f64,
+
+    /// Upper bound of the credible interval
+    pub upper_bound: f64,
+    
+    /// Seed the samples were drawn with, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl UncertaintySummary {
+    /// Summarize a set of posterior samples
+    pub fn from_samples(mut samples: Vec<f64>, credible_level: f64) -> Result<Self> {
+        if samples.is_empty() {
+            return Err(anyhow!("Cannot summarize an empty set of samples"));
+        }
+        if !(0.0..=1.0).contains(&credible_level) {
+            return Err(anyhow!("Credible level must be between 0.0 and 1.0, got {}", credible_level));
+        }
+
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+
+        samples.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let tail = (1.0 - credible_level) / 2.0;
+
+        Ok(Self {
+            samples: samples.len(),
+            mean,
+            variance,
+            std_dev: variance.sqrt(),
+            credible_level,
+            lower_bound: percentile(&samples, tail),
+            upper_bound: percentile(&samples, 1.0 - tail),
+            seed: None,
+        })
+    }
+    
+    /// Record the seed the samples were drawn with
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// Uncertainty bounds of an evidence item's confidence, clamped to [0, 1]
+pub fn confidence_bounds(evidence: &Evidence) -> (f64, f64) {
+    let fuzzy = FuzzyEvidence::from_raw_evidence(
+        evidence.id.clone(),
+        evidence.source.clone(),
+        evidence.evidence_type.to_string(),
+        evidence.confidence,
+        evidence.timestamp,
+    );
+
+    let (low, high) = fuzzy.uncertainty_bounds;
+    (low.clamp(0.0, 1.0), high.clamp(0.0, 1.0))
+}
+
+/// Draw one perturbed copy of the evidence, sampling each confidence from its bounds
+pub fn sample_evidence<R: Rng>(evidence: &[Evidence], rng: &mut R) -> Vec<Evidence> {
+    evidence.iter()
+        .map(|ev| {
+            let (low, high) = confidence_bounds(ev);
+            let mut sampled = ev.clone();
+            sampled.confidence = if high > low { rng.gen_range(low..=high) } else { low };
+            sampled
+        })
+        .collect()
+}
+
+/// Create the random number generator for a configuration, returning the seed used
+pub fn rng_for(config: &MonteCarloConfig) -> (StdRng, u64) {
+    let seed = rng::resolve_seed(config.seed);
+    debug!("Seeding Monte Carlo sampler with {}", seed);
+    (rng::stream_rng(seed, "monte_carlo"), seed)
+}
+
+/// Linear-interpolated percentile of sorted samples
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let fraction = position - lower as f64;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_from_samples() {
+        let samples: Vec<f64> = (0..=100).map(|i| i as f64 / 100.0).collect();
+        let summary = UncertaintySummary::from_samples(samples, 0.9).unwrap();
+
+        assert!((summary.mean - 0.5).abs() < 1e-9);
+        assert!((summary.lower_bound - 0.05).abs() < 1e-9);
+        assert!((summary.upper_bound - 0.95).abs() < 1e-9);
+        assert!(UncertaintySummary::from_samples(Vec::new(), 0.9).is_err());
+    }
+}
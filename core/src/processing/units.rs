@@ -0,0 +1,280 @@
+//! Units Module
+//!
+//! Evidence values come in ppm, Daltons, minutes, seconds and unitless
+//! scores. A `Quantity` carries its unit with it, so values are converted
+//! explicitly before they are compared: a ppm tolerance becomes a Dalton
+//! window only at a given m/z, and a trace recorded in seconds is read in
+//! minutes only through a conversion. Converting between units of different
+//! dimensions is an error rather than a silent mix-up.
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::{Serialize, Deserialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Initialize the units module
+pub fn initialize() -> Result<()> {
+    info!("Initializing units module");
+    info!("Units module initialized successfully");
+    Ok(())
+}
+
+/// What a unit measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Dimension {
+    /// Absolute mass or m/z difference
+    Mass,
+
+    /// Mass difference relative to a reference m/z
+    RelativeMass,
+
+    /// Elapsed or retention time
+    Time,
+
+    /// Scores, ratios and counts
+    Dimensionless,
+}
+
+/// Unit of an evidence value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Unit {
+    /// Parts per million of a reference m/z
+    #[serde(rename = "ppm")]
+    Ppm,
+
+    /// Daltons (also used for m/z differences)
+    #[serde(rename = "Da")]
+    Dalton,
+
+    /// Minutes
+    #[serde(rename = "min")]
+    Minute,
+
+    /// Seconds
+    #[serde(rename = "s")]
+    Second,
+
+    /// No unit
+    #[serde(rename = "unitless")]
+    Dimensionless,
+}
+
+impl Unit {
+    /// What the unit measures
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Unit::Ppm => Dimension::RelativeMass,
+            Unit::Dalton => Dimension::Mass,
+            Unit::Minute | Unit::Second => Dimension::Time,
+            Unit::Dimensionless => Dimension::Dimensionless,
+        }
+    }
+
+    /// Unit symbol
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Ppm => "ppm",
+            Unit::Dalton => "Da",
+            Unit::Minute => "min",
+            Unit::Second => "s",
+            Unit::Dimensionless => "unitless",
+        }
+    }
+
+    /// Factor converting a value in this unit to the base unit of its dimension
+    fn scale(&self) -> f64 {
+        match self {
+            Unit::Second => 1.0 / 60.0,
+            _ => 1.0,
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl FromStr for Unit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "ppm" => Ok(Unit::Ppm),
+            "da" | "dalton" | "daltons" => Ok(Unit::Dalton),
+            "min" | "minute" | "minutes" => Ok(Unit::Minute),
+            "s" | "sec" | "second" | "seconds" => Ok(Unit::Second),
+            "" | "unitless" => Ok(Unit::Dimensionless),
+            other => Err(anyhow!("Unknown unit: {}", other)),
+        }
+    }
+}
+
+/// Retention times are recorded in minutes unless stated otherwise
+pub fn default_time_unit() -> Unit {
+    Unit::Minute
+}
+
+/// A value together with its unit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    /// Numeric value
+    pub value: f64,
+
+    /// Unit of the value
+    pub unit: Unit,
+}
+
+impl Quantity {
+    /// A value in the given unit
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Self { value, unit }
+    }
+
+    /// A value in ppm
+    pub fn ppm(value: f64) -> Self {
+        Self::new(value, Unit::Ppm)
+    }
+
+    /// A value in Daltons
+    pub fn daltons(value: f64) -> Self {
+        Self::new(value, Unit::Dalton)
+    }
+
+    /// A value in minutes
+    pub fn minutes(value: f64) -> Self {
+        Self::new(value, Unit::Minute)
+    }
+
+    /// A value in seconds
+    pub fn seconds(value: f64) -> Self {
+        Self::new(value, Unit::Second)
+    }
+
+    /// A unitless value
+    pub fn unitless(value: f64) -> Self {
+        Self::new(value, Unit::Dimensionless)
+    }
+
+    /// What the quantity measures
+    pub fn dimension(&self) -> Dimension {
+        self.unit.dimension()
+    }
+
+    /// The same quantity in another unit of the same dimension
+    ///
+    /// ppm and Daltons are related only through a reference m/z; use `to_at`
+    /// to convert between them.
+    pub fn to(&self, unit: Unit) -> Result<Quantity> {
+        if self.unit.dimension() != unit.dimension() {
+            return Err(IncompatibleUnits { from: self.unit, to: unit }.into());
+        }
+        Ok(Quantity::new(self.value * self.unit.scale() / unit.scale(), unit))
+    }
+
+    /// The same quantity in another unit, converting between ppm and Daltons at `mz`
+    pub fn to_at(&self, unit: Unit, mz: f64) -> Result<Quantity> {
+        match (self.unit, unit) {
+            (Unit::Ppm, Unit::Dalton) => Ok(Quantity::daltons(self.value * mz / 1e6)),
+            (Unit::Dalton, Unit::Ppm) => {
+                if mz <= 0.0 {
+                    return Err(anyhow!("Cannot express a mass difference in ppm of m/z {}", mz));
+                }
+                Ok(Quantity::ppm(self.value / mz * 1e6))
+            }
+            _ => self.to(unit),
+        }
+    }
+
+    /// Numeric value in another unit of the same dimension
+    pub fn value_in(&self, unit: Unit) -> Result<f64> {
+        Ok(self.to(unit)?.value)
+    }
+
+    /// Whether an observed m/z lies within this mass tolerance of a theoretical one
+    ///
+    /// The tolerance must be in ppm or Daltons; ppm are taken relative to the
+    /// theoretical m/z.
+    pub fn matches_mz(&self, observed_mz: f64, theoretical_mz: f64) -> Result<bool> {
+        let window = self.to_at(Unit::Dalton, theoretical_mz)?.value;
+        Ok((observed_mz - theoretical_mz).abs() <= window)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.unit {
+            Unit::Dimensionless => write!(f, "{}", self.value),
+            unit => write!(f, "{} {}", self.value, unit),
+        }
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = anyhow::Error;
+
+    /// Parse a value followed by an optional unit, such as `10 ppm` or `0.02Da`
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let split = s.find(|c: char| c.is_alphabetic() && c != 'e' && c != 'E')
+            .unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+        let value = value.trim().parse::<f64>()
+            .map_err(|_| anyhow!("Invalid quantity: {}", s))?;
+        Ok(Quantity::new(value, unit.parse()?))
+    }
+}
+
+/// A quantity was converted to a unit of a different dimension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncompatibleUnits {
+    /// Unit of the quantity
+    pub from: Unit,
+
+    /// Unit it was converted to
+    pub to: Unit,
+}
+
+impl fmt::Display for IncompatibleUnits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cannot convert {} to {}", self.from, self.to)
+    }
+}
+
+impl Error for IncompatibleUnits {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_respect_dimensions() {
+        assert!((Quantity::seconds(90.0).value_in(Unit::Minute).unwrap() - 1.5).abs() < 1e-12);
+        assert!((Quantity::ppm(10.0).to_at(Unit::Dalton, 500.0).unwrap().value - 0.005).abs() < 1e-12);
+        assert!((Quantity::daltons(0.005).to_at(Unit::Ppm, 500.0).unwrap().value - 10.0).abs() < 1e-9);
+
+        // A ppm tolerance is not a Dalton error, and neither is a retention time
+        let error = Quantity::ppm(10.0).to(Unit::Dalton).unwrap_err();
+        assert!(error.is::<IncompatibleUnits>());
+        assert!(Quantity::minutes(0.2).to_at(Unit::Dalton, 500.0).is_err());
+
+        let tolerance = Quantity::ppm(5.0);
+        assert!(tolerance.matches_mz(181.0708, 181.0707).unwrap());
+        assert!(!tolerance.matches_mz(181.0743, 181.0707).unwrap());
+        assert!(Quantity::daltons(0.01).matches_mz(181.0743, 181.0707).unwrap());
+    }
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!("10 ppm".parse::<Quantity>().unwrap(), Quantity::ppm(10.0));
+        assert_eq!("0.02Da".parse::<Quantity>().unwrap(), Quantity::daltons(0.02));
+        assert_eq!("1.5e1 s".parse::<Quantity>().unwrap(), Quantity::seconds(15.0));
+        assert_eq!("0.85".parse::<Quantity>().unwrap(), Quantity::unitless(0.85));
+        assert!("ten ppm".parse::<Quantity>().is_err());
+        assert!("3 furlongs".parse::<Quantity>().is_err());
+        assert_eq!(Quantity::minutes(0.2).to_string(), "0.2 min");
+    }
+}
@@ -0,0 +1,658 @@
+//! Evidence Versioning Module
+//!
+//! This module keeps a revision history of evidence items and molecule confidence
+//! scores, so that past beliefs about a molecule can be reconstructed and compared.
+//! Each confidence revision records what triggered it, and the recent revisions
+//! give the direction the confidence is moving in. The store can be saved to
+//! a JSON file and loaded again at startup; each molecule keeps a bounded
+//! number of revisions.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::processing::evidence::Evidence;
+
+/// Confidence revisions considered when computing a trend
+pub const TREND_WINDOW: usize = 5;
+
+/// Net change in confidence over the trend window below which it counts as stable
+pub const TREND_TOLERANCE: f64 = 0.01;
+
+/// Revisions kept for each molecule, counted separately for its evidence and its confidence
+pub const MAX_REVISIONS_PER_MOLECULE: usize = 200;
+
+/// Initialize the evidence versioning module
+pub fn initialize() -> Result<()> {
+    info!("Initializing evidence versioning module");
+    info!("Evidence versioning module initialized successfully");
+    Ok(())
+}
+
+/// Kind of change recorded by a revision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RevisionChange {
+    /// Evidence was recorded for the first time
+    Added,
+
+    /// Existing evidence was replaced with a new version
+    Updated,
+
+    /// Evidence was withdrawn
+    Retracted,
+}
+
+/// A single revision of an evidence item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceRevision {
+    /// Store-wide revision number
+    pub revision: u64,
+
+    /// When the revision was recorded
+    pub recorded_at: DateTime<Utc>,
+
+    /// Kind of change
+    pub change: RevisionChange,
+
+    /// Evidence content as of this revision
+    pub evidence: Evidence,
+}
+
+/// What caused a molecule's confidence to be recomputed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidenceTrigger {
+    /// Evidence integration with no more specific cause
+    #[default]
+    Integration,
+
+    /// Identity validation of a molecule
+    Validation,
+
+    /// Evidence submitted for analysis through the API
+    Analysis,
+
+    /// Evidence rectification through the API
+    Rectification,
+
+    /// Scheduled re-evaluation of decayed evidence
+    Reevaluation,
+
+    /// Evidence entered by hand
+    ManualEntry,
+
+    /// Re-validation requested by a user
+    Revalidation,
+
+    /// Evidence consumed from a message stream
+    Stream,
+}
+
+impl fmt::Display for ConfidenceTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfidenceTrigger::Integration => write!(f, "integration"),
+            ConfidenceTrigger::Validation => write!(f, "validation"),
+            ConfidenceTrigger::Analysis => write!(f, "analysis"),
+            ConfidenceTrigger::Rectification => write!(f, "rectification"),
+            ConfidenceTrigger::Reevaluation => write!(f, "reevaluation"),
+            ConfidenceTrigger::ManualEntry => write!(f, "manual_entry"),
+            ConfidenceTrigger::Revalidation => write!(f, "revalidation"),
+            ConfidenceTrigger::Stream => write!(f, "stream"),
+        }
+    }
+}
+
+impl FromStr for ConfidenceTrigger {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "integration" => Ok(ConfidenceTrigger::Integration),
+            "validation" => Ok(ConfidenceTrigger::Validation),
+            "analysis" => Ok(ConfidenceTrigger::Analysis),
+            "rectification" => Ok(ConfidenceTrigger::Rectification),
+            "reevaluation" => Ok(ConfidenceTrigger::Reevaluation),
+            "manual_entry" => Ok(ConfidenceTrigger::ManualEntry),
+            "revalidation" => Ok(ConfidenceTrigger::Revalidation),
+            "stream" => Ok(ConfidenceTrigger::Stream),
+            _ => Err(anyhow!("Unknown confidence trigger: {}", s)),
+        }
+    }
+}
+
+/// Direction a molecule's confidence has been moving in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidenceTrend {
+    /// Confidence has been increasing
+    Rising,
+
+    /// Confidence has been decreasing
+    Falling,
+
+    /// Confidence has changed by less than `TREND_TOLERANCE`
+    Stable,
+}
+
+impl ConfidenceTrend {
+    /// Trend of a confidence series, oldest first, over its last `TREND_WINDOW` values
+    ///
+    /// The net change is taken from a least-squares line through the window,
+    /// so a single outlier does not flip the trend. Returns `None` for fewer
+    /// than two values.
+    pub fn from_series(confidences: &[f64]) -> Option<Self> {
+        let window = &confidences[confidences.len().saturating_sub(TREND_WINDOW)..];
+        if window.len() < 2 {
+            return None;
+        }
+
+        let n = window.len() as f64;
+        let mean_x = (n - 1.0) / 2.0;
+        let mean_y = window.iter().sum::<f64>() / n;
+        let (covariance, variance) = window.iter().enumerate()
+            .fold((0.0, 0.0), |(cov, var), (i, y)| {
+                let dx = i as f64 - mean_x;
+                (cov + dx * (y - mean_y), var + dx * dx)
+            });
+        let change = covariance / variance * (n - 1.0);
+
+        Some(if change > TREND_TOLERANCE {
+            ConfidenceTrend::Rising
+        } else if change < -TREND_TOLERANCE {
+            ConfidenceTrend::Falling
+        } else {
+            ConfidenceTrend::Stable
+        })
+    }
+}
+
+impl fmt::Display for ConfidenceTrend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfidenceTrend::Rising => write!(f, "rising"),
+            ConfidenceTrend::Falling => write!(f, "falling"),
+            ConfidenceTrend::Stable => write!(f, "stable"),
+        }
+    }
+}
+
+/// A single revision of a molecule's confidence score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceRevision {
+    /// Store-wide revision number
+    pub revision: u64,
+
+    /// When the revision was recorded
+    pub recorded_at: DateTime<Utc>,
+
+    /// Confidence score (0.0 - 1.0)
+    pub confidence: f64,
+
+    /// What caused the confidence to be recomputed
+    #[serde(default)]
+    pub trigger: ConfidenceTrigger,
+}
+
+/// What was believed about a molecule at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoleculeSnapshot {
+    /// Molecule ID
+    pub molecule_id: String,
+
+    /// Time the snapshot was taken at
+    pub as_of: DateTime<Utc>,
+
+    /// Latest revision included in the snapshot
+    pub revision: u64,
+
+    /// Evidence that was live at that time
+    pub evidence: Vec<Evidence>,
+
+    /// Confidence score at that time, if one had been recorded
+    pub confidence: Option<f64>,
+}
+
+/// Change to a single evidence item between two snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceChange {
+    /// Evidence ID
+    pub evidence_id: String,
+
+    /// Confidence in the earlier snapshot
+    pub confidence_before: f64,
+
+    /// Confidence in the later snapshot
+    pub confidence_after: f64,
+}
+
+/// Difference between two snapshots of the same molecule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    /// Molecule ID
+    pub molecule_id: String,
+
+    /// Time of the earlier snapshot
+    pub from: DateTime<Utc>,
+
+    /// Time of the later snapshot
+    pub to: DateTime<Utc>,
+
+    /// Evidence IDs present only in the later snapshot
+    pub added: Vec<String>,
+
+    /// Evidence IDs present only in the earlier snapshot
+    pub removed: Vec<String>,
+
+    /// Evidence present in both snapshots whose content changed
+    pub changed: Vec<EvidenceChange>,
+
+    /// Molecule confidence in the earlier snapshot
+    pub confidence_before: Option<f64>,
+
+    /// Molecule confidence in the later snapshot
+    pub confidence_after: Option<f64>,
+}
+
+/// Append-only store of evidence and confidence revisions
+///
+/// Once a molecule has more than `MAX_REVISIONS_PER_MOLECULE` evidence or
+/// confidence revisions, the oldest are dropped. The latest revision of each
+/// evidence item is always kept, so current snapshots stay complete while
+/// snapshots from before the kept revisions lose detail.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VersionedEvidenceStore {
+    /// Revisions of each evidence item, by evidence ID
+    evidence: HashMap<String, Vec<EvidenceRevision>>,
+
+    /// Revisions of each molecule's confidence, by molecule ID
+    confidence: HashMap<String, Vec<ConfidenceRevision>>,
+
+    /// Last revision number handed out
+    last_revision: u64,
+
+    /// File the store is saved to; kept in memory only when unset
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl VersionedEvidenceStore {
+    /// Create an empty store that is not saved
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the store saved at a path, or start an empty one saved there
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut store = if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read evidence history: {}", path.display()))?;
+            serde_json::from_str::<Self>(&content).context("Failed to parse evidence history")?
+        } else {
+            Self::new()
+        };
+        info!("Loaded evidence history of {} evidence items from {}", store.evidence.len(), path.display());
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    /// Open the store named by `HEGEL_EVIDENCE_HISTORY_FILE`, or `evidence_history.json`
+    pub fn from_env() -> Result<Self> {
+        Self::open(std::env::var("HEGEL_EVIDENCE_HISTORY_FILE").unwrap_or_else(|_| "evidence_history.json".to_string()))
+    }
+
+    /// File the store is saved to
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Write the store to its file, replacing the previous version in one step
+    ///
+    /// Recording does not save, so a batch of revisions is written once
+    /// after it is recorded. Does nothing for a store that is not saved.
+    pub fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(self)?)
+            .with_context(|| format!("Failed to write evidence history: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to save evidence history: {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Record a new version of an evidence item
+    pub fn record_evidence(&mut self, evidence: Evidence) -> u64 {
+        self.record_evidence_at(evidence, Utc::now())
+    }
+
+    /// Record a new version of an evidence item unless it matches the latest one
+    ///
+    /// Re-reading unchanged evidence for analysis then leaves its history
+    /// alone. Returns the new revision, or `None` if nothing changed.
+    pub fn record_evidence_if_changed(&mut self, evidence: Evidence) -> Option<u64> {
+        let unchanged = self.evidence_history(&evidence.id).last()
+            .filter(|latest| latest.change != RevisionChange::Retracted)
+            .is_some_and(|latest| serde_json::to_value(&latest.evidence).ok() == serde_json::to_value(&evidence).ok());
+        if unchanged {
+            return None;
+        }
+        Some(self.record_evidence(evidence))
+    }
+
+    /// Record a new version of an evidence item at the given time
+    pub fn record_evidence_at(&mut self, evidence: Evidence, at: DateTime<Utc>) -> u64 {
+        let revision = self.next_revision();
+        let revisions = self.evidence.entry(evidence.id.clone()).or_default();
+        let change = if revisions.is_empty() {
+            RevisionChange::Added
+        } else {
+            RevisionChange::Updated
+        };
+
+        debug!("Recording revision {} of evidence {} ({:?})", revision, evidence.id, change);
+        let molecule_id = evidence.molecule_id.clone();
+        revisions.push(EvidenceRevision { revision, recorded_at: at, change, evidence });
+        self.prune_evidence(&molecule_id);
+        revision
+    }
+
+    /// Retract an evidence item
+    pub fn retract_evidence(&mut self, evidence_id: &str) -> Result<u64> {
+        self.retract_evidence_at(evidence_id, Utc::now())
+    }
+
+    /// Retract an evidence item at the given time
+    pub fn retract_evidence_at(&mut self, evidence_id: &str, at: DateTime<Utc>) -> Result<u64> {
+        let latest = self.evidence.get(evidence_id)
+            .and_then(|revisions| revisions.last())
+            .ok_or_else(|| anyhow!("Unknown evidence: {}", evidence_id))?;
+
+        if latest.change == RevisionChange::Retracted {
+            return Err(anyhow!("Evidence already retracted: {}", evidence_id));
+        }
+
+        let evidence = latest.evidence.clone();
+        let molecule_id = evidence.molecule_id.clone();
+        let revision = self.next_revision();
+        self.evidence.get_mut(evidence_id).unwrap().push(EvidenceRevision {
+            revision,
+            recorded_at: at,
+            change: RevisionChange::Retracted,
+            evidence,
+        });
+        self.prune_evidence(&molecule_id);
+
+        Ok(revision)
+    }
+
+    /// Record a new confidence score for a molecule
+    pub fn record_confidence(&mut self, molecule_id: &str, confidence: f64, trigger: ConfidenceTrigger) -> u64 {
+        self.record_confidence_at(molecule_id, confidence, trigger, Utc::now())
+    }
+
+    /// Record a new confidence score for a molecule at the given time
+    pub fn record_confidence_at(&mut self, molecule_id: &str, confidence: f64, trigger: ConfidenceTrigger, at: DateTime<Utc>) -> u64 {
+        let revision = self.next_revision();
+        let revisions = self.confidence.entry(molecule_id.to_string()).or_default();
+        revisions.push(ConfidenceRevision {
+            revision,
+            recorded_at: at,
+            confidence,
+            trigger,
+        });
+        revisions.drain(..revisions.len().saturating_sub(MAX_REVISIONS_PER_MOLECULE));
+        revision
+    }
+
+    /// IDs of all molecules with recorded evidence, sorted
+    pub fn molecule_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.evidence.values()
+            .filter_map(|revisions| revisions.last())
+            .map(|r| r.evidence.molecule_id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Full revision history of an evidence item
+    pub fn evidence_history(&self, evidence_id: &str) -> &[EvidenceRevision] {
+        self.evidence.get(evidence_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Full confidence history of a molecule
+    pub fn confidence_history(&self, molecule_id: &str) -> &[ConfidenceRevision] {
+        self.confidence.get(molecule_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Direction a molecule's confidence has been moving in, if it has been recorded twice
+    pub fn confidence_trend(&self, molecule_id: &str) -> Option<ConfidenceTrend> {
+        let confidences: Vec<f64> = self.confidence_history(molecule_id).iter().map(|r| r.confidence).collect();
+        ConfidenceTrend::from_series(&confidences)
+    }
+
+    /// Reconstruct what was believed about a molecule at the given time
+    pub fn snapshot_at(&self, molecule_id: &str, at: DateTime<Utc>) -> MoleculeSnapshot {
+        let mut revision = 0;
+        let mut evidence = Vec::new();
+
+        for revisions in self.evidence.values() {
+            let latest = revisions.iter()
+                .filter(|r| r.recorded_at <= at && r.evidence.molecule_id == molecule_id)
+                .max_by_key(|r| (r.recorded_at, r.revision));
+
+            if let Some(latest) = latest {
+                revision = revision.max(latest.revision);
+                if latest.change != RevisionChange::Retracted {
+                    evidence.push(latest.evidence.clone());
+                }
+            }
+        }
+        evidence.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let confidence = self.confidence_history(molecule_id).iter()
+            .filter(|r| r.recorded_at <= at)
+            .max_by_key(|r| (r.recorded_at, r.revision));
+        if let Some(c) = confidence {
+            revision = revision.max(c.revision);
+        }
+
+        MoleculeSnapshot {
+            molecule_id: molecule_id.to_string(),
+            as_of: at,
+            revision,
+            evidence,
+            confidence: confidence.map(|c| c.confidence),
+        }
+    }
+
+    /// Compare what was believed about a molecule at two points in time
+    pub fn diff(&self, molecule_id: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> SnapshotDiff {
+        let before = self.snapshot_at(molecule_id, from);
+        let after = self.snapshot_at(molecule_id, to);
+
+        let before_by_id: HashMap<&str, &Evidence> = before.evidence.iter()
+            .map(|e| (e.id.as_str(), e))
+            .collect();
+        let after_by_id: HashMap<&str, &Evidence> = after.evidence.iter()
+            .map(|e| (e.id.as_str(), e))
+            .collect();
+
+        let added = after.evidence.iter()
+            .filter(|e| !before_by_id.contains_key(e.id.as_str()))
+            .map(|e| e.id.clone())
+            .collect();
+        let removed = before.evidence.iter()
+            .filter(|e| !after_by_id.contains_key(e.id.as_str()))
+            .map(|e| e.id.clone())
+            .collect();
+        let changed = after.evidence.iter()
+            .filter_map(|e| {
+                let old = before_by_id.get(e.id.as_str())?;
+                let same = old.confidence == e.confidence && old.data == e.data;
+                (!same).then(|| EvidenceChange {
+                    evidence_id: e.id.clone(),
+                    confidence_before: old.confidence,
+                    confidence_after: e.confidence,
+                })
+            })
+            .collect();
+
+        SnapshotDiff {
+            molecule_id: molecule_id.to_string(),
+            from,
+            to,
+            added,
+            removed,
+            changed,
+            confidence_before: before.confidence,
+            confidence_after: after.confidence,
+        }
+    }
+
+    /// Drop a molecule's oldest superseded evidence revisions beyond `MAX_REVISIONS_PER_MOLECULE`
+    fn prune_evidence(&mut self, molecule_id: &str) {
+        let items: Vec<(&String, &Vec<EvidenceRevision>)> = self.evidence.iter()
+            .filter(|(_, revisions)| revisions.last().is_some_and(|r| r.evidence.molecule_id == molecule_id))
+            .collect();
+        let total: usize = items.iter().map(|(_, revisions)| revisions.len()).sum();
+        let excess = total.saturating_sub(MAX_REVISIONS_PER_MOLECULE);
+        if excess == 0 {
+            return;
+        }
+
+        // Every revision but the last of an item is superseded; the oldest go first
+        let mut superseded: Vec<(u64, String)> = items.iter()
+            .flat_map(|(id, revisions)| {
+                revisions[..revisions.len() - 1].iter().map(move |r| (r.revision, (*id).clone()))
+            })
+            .collect();
+        superseded.sort();
+        for (_, id) in superseded.into_iter().take(excess) {
+            if let Some(revisions) = self.evidence.get_mut(&id) {
+                revisions.remove(0);
+            }
+        }
+    }
+
+    /// Hand out the next revision number
+    fn next_revision(&mut self) -> u64 {
+        self.last_revision += 1;
+        self.last_revision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::evidence::EvidenceType;
+    use chrono::Duration;
+
+    fn evidence(id: &str, confidence: f64) -> Evidence {
+        Evidence {
+            id: id.to_string(),
+            molecule_id: "mol-1".to_string(),
+            evidence_type: EvidenceType::MassSpec,
+            source: "test".to_string(),
+            confidence,
+            data: serde_json::Value::Null,
+            metadata: HashMap::new(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_and_diff() {
+        let t0 = Utc::now();
+        let t1 = t0 + Duration::hours(1);
+        let t2 = t0 + Duration::hours(2);
+
+        let mut store = VersionedEvidenceStore::new();
+        store.record_evidence_at(evidence("ev-1", 0.6), t0);
+        store.record_confidence_at("mol-1", 0.6, ConfidenceTrigger::Analysis, t0);
+        store.record_evidence_at(evidence("ev-1", 0.8), t1);
+        store.record_evidence_at(evidence("ev-2", 0.7), t1);
+        store.retract_evidence_at("ev-2", t2).unwrap();
+        store.record_confidence_at("mol-1", 0.8, ConfidenceTrigger::Rectification, t2);
+
+        let snapshot = store.snapshot_at("mol-1", t1);
+        assert_eq!(snapshot.evidence.len(), 2);
+        assert_eq!(snapshot.confidence, Some(0.6));
+
+        let diff = store.diff("mol-1", t0, t2);
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].confidence_after, 0.8);
+        assert_eq!(diff.confidence_after, Some(0.8));
+        assert_eq!(store.evidence_history("ev-2").len(), 2);
+        assert_eq!(store.confidence_history("mol-1")[1].trigger, ConfidenceTrigger::Rectification);
+        assert_eq!(store.confidence_trend("mol-1"), Some(ConfidenceTrend::Rising));
+    }
+
+    #[test]
+    fn test_snapshot_ignores_backdated_revisions() {
+        let t0 = Utc::now();
+        let t1 = t0 + Duration::hours(1);
+
+        let mut store = VersionedEvidenceStore::new();
+        store.record_evidence_at(evidence("ev-1", 0.9), t1);
+        store.record_evidence_at(evidence("ev-1", 0.5), t0);
+        store.record_confidence_at("mol-1", 0.9, ConfidenceTrigger::Analysis, t1);
+        store.record_confidence_at("mol-1", 0.5, ConfidenceTrigger::Reevaluation, t0);
+
+        let snapshot = store.snapshot_at("mol-1", t1);
+        assert_eq!(snapshot.evidence[0].confidence, 0.9);
+        assert_eq!(snapshot.confidence, Some(0.9));
+    }
+
+    #[test]
+    fn test_record_only_changed_evidence() {
+        let mut store = VersionedEvidenceStore::new();
+        let item = evidence("ev-1", 0.6);
+        assert!(store.record_evidence_if_changed(item.clone()).is_some());
+        assert!(store.record_evidence_if_changed(item.clone()).is_none());
+        assert!(store.record_evidence_if_changed(Evidence { confidence: 0.7, ..item }).is_some());
+        assert_eq!(store.evidence_history("ev-1").len(), 2);
+    }
+
+    #[test]
+    fn test_history_is_saved_and_capped() {
+        let path = std::env::temp_dir().join(format!("hegel-evidence-history-{}.json", uuid::Uuid::new_v4()));
+        let t0 = Utc::now();
+        let mut store = VersionedEvidenceStore::open(&path).unwrap();
+        store.record_evidence_at(evidence("ev-1", 0.5), t0);
+        for i in 0..MAX_REVISIONS_PER_MOLECULE + 10 {
+            let at = t0 + Duration::minutes(i as i64 + 1);
+            store.record_evidence_at(evidence("ev-2", 0.6), at);
+            store.record_confidence_at("mol-1", 0.6, ConfidenceTrigger::Analysis, at);
+        }
+        store.save().unwrap();
+
+        let reopened = VersionedEvidenceStore::open(&path).unwrap();
+        assert_eq!(reopened.confidence_history("mol-1").len(), MAX_REVISIONS_PER_MOLECULE);
+        assert_eq!(reopened.evidence_history("ev-2").len(), MAX_REVISIONS_PER_MOLECULE - 1);
+        // The only revision of an item is never dropped
+        assert_eq!(reopened.evidence_history("ev-1").len(), 1);
+        assert_eq!(reopened.snapshot_at("mol-1", Utc::now() + Duration::days(1)).evidence.len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_confidence_trend() {
+        assert_eq!(ConfidenceTrend::from_series(&[0.7]), None);
+        assert_eq!(ConfidenceTrend::from_series(&[0.9, 0.8, 0.85, 0.7]), Some(ConfidenceTrend::Falling));
+        assert_eq!(ConfidenceTrend::from_series(&[0.8, 0.805, 0.798, 0.802]), Some(ConfidenceTrend::Stable));
+        // Only the last TREND_WINDOW revisions count
+        assert_eq!(ConfidenceTrend::from_series(&[0.1, 0.9, 0.5, 0.55, 0.6, 0.65, 0.7]), Some(ConfidenceTrend::Rising));
+        assert_eq!("manual_entry".parse::<ConfidenceTrigger>().unwrap(), ConfidenceTrigger::ManualEntry);
+    }
+}
@@ -0,0 +1,236 @@
+//! Projects
+//!
+//! Every molecule, evidence item and network belongs to a project. Projects
+//! isolate one team's data from another's: graph queries are scoped by
+//! project ID and callers need a role in a project to read or change it.
+//! Data created before projects existed lives in the default project, which
+//! every authenticated user may use.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::auth::Principal;
+
+/// ID of the project holding unscoped data
+pub const DEFAULT_PROJECT: &str = "default";
+
+/// Initialize the projects module
+pub fn initialize() -> Result<()> {
+    info!("Initializing projects module");
+    info!("Projects module initialized successfully");
+    Ok(())
+}
+
+/// Key identifying a molecule or other item within a project
+pub fn scoped_key(project_id: &str, id: &str) -> String {
+    format!("{}/{}", project_id, id)
+}
+
+/// Role of a user within a project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectRole {
+    /// May read project data
+    Viewer,
+    /// May read and write project data
+    Editor,
+    /// May also manage members
+    Owner,
+}
+
+/// Kind of access a request needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Access {
+    /// Read molecules, evidence and networks
+    Read,
+    /// Add or change data
+    Write,
+    /// Manage the project itself
+    Manage,
+}
+
+impl ProjectRole {
+    /// Whether the role grants the given access
+    pub fn grants(&self, access: Access) -> bool {
+        match access {
+            Access::Read => true,
+            Access::Write => *self >= ProjectRole::Editor,
+            Access::Manage => *self == ProjectRole::Owner,
+        }
+    }
+}
+
+/// A project scoping molecules, evidence and networks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    /// Project identifier
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Optional description
+    pub description: Option<String>,
+
+    /// Roles of the project's members by user ID
+    pub members: HashMap<String, ProjectRole>,
+
+    /// Role granted to authenticated users who are not members
+    pub default_role: Option<ProjectRole>,
+
+    /// When the project was created
+    pub created_at: DateTime<Utc>,
+}
+
+impl Project {
+    /// Role of a caller in this project, if any
+    pub fn role_of(&self, principal: &Principal) -> Option<ProjectRole> {
+        if principal.is_admin() {
+            return Some(ProjectRole::Owner);
+        }
+        let role = self.members.get(&principal.user_id).copied().or(self.default_role)?;
+        // Read-only accounts never write, whatever their project role
+        if principal.is_read_only() {
+            Some(ProjectRole::Viewer)
+        } else {
+            Some(role)
+        }
+    }
+}
+
+/// Registry of projects and their members
+#[derive(Debug, Clone)]
+pub struct ProjectRegistry {
+    /// Projects by ID
+    projects: HashMap<String, Project>,
+}
+
+impl ProjectRegistry {
+    /// Create a registry holding only the default project
+    pub fn new() -> Self {
+        let default = Project {
+            id: DEFAULT_PROJECT.to_string(),
+            name: "Default".to_string(),
+            description: Some("Data not assigned to a specific project".to_string()),
+            members: HashMap::new(),
+            default_role: Some(ProjectRole::Editor),
+            created_at: Utc::now(),
+        };
+        Self {
+            projects: HashMap::from([(default.id.clone(), default)]),
+        }
+    }
+
+    /// Create a project owned by the caller
+    pub fn create(&mut self, name: &str, description: Option<String>, owner: &Principal) -> Result<Project> {
+        if name.trim().is_empty() {
+            return Err(anyhow!("Project name must not be empty"));
+        }
+        if owner.is_read_only() {
+            return Err(anyhow!("Read-only accounts cannot create projects"));
+        }
+
+        let project = Project {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            description,
+            members: HashMap::from([(owner.user_id.clone(), ProjectRole::Owner)]),
+            default_role: None,
+            created_at: Utc::now(),
+        };
+        info!("Created project {} ({}) for {}", project.id, project.name, owner.user_id);
+        self.projects.insert(project.id.clone(), project.clone());
+        Ok(project)
+    }
+
+    /// Get a project by ID
+    pub fn get(&self, project_id: &str) -> Option<&Project> {
+        self.projects.get(project_id)
+    }
+
+    /// Projects the caller has a role in, ordered by creation time
+    pub fn visible_to(&self, principal: &Principal) -> Vec<&Project> {
+        let mut projects: Vec<&Project> = self.projects.values()
+            .filter(|p| p.role_of(principal).is_some())
+            .collect();
+        projects.sort_by_key(|p| p.created_at);
+        projects
+    }
+
+    /// Check that the caller has the given access to a project
+    ///
+    /// Unknown projects and projects the caller has no role in produce the
+    /// same error, so project IDs cannot be probed.
+    pub fn authorize(&self, principal: &Principal, project_id: &str, access: Access) -> Result<&Project> {
+        let project = self.projects.get(project_id)
+            .filter(|p| p.role_of(principal).is_some())
+            .ok_or_else(|| anyhow!("Project not found: {}", project_id))?;
+        match project.role_of(principal) {
+            Some(role) if role.grants(access) => Ok(project),
+            _ => Err(anyhow!("{:?} access to project {} denied for {}", access, project_id, principal.user_id)),
+        }
+    }
+
+    /// Add a member or change their role
+    pub fn set_member(&mut self, project_id: &str, user_id: &str, role: ProjectRole) -> Result<()> {
+        let project = self.projects.get_mut(project_id)
+            .ok_or_else(|| anyhow!("Project not found: {}", project_id))?;
+        project.members.insert(user_id.to_string(), role);
+        Ok(())
+    }
+
+    /// Remove a member, keeping at least one owner
+    pub fn remove_member(&mut self, project_id: &str, user_id: &str) -> Result<()> {
+        let project = self.projects.get_mut(project_id)
+            .ok_or_else(|| anyhow!("Project not found: {}", project_id))?;
+        let owners = project.members.values().filter(|&&r| r == ProjectRole::Owner).count();
+        if project.members.get(user_id) == Some(&ProjectRole::Owner) && owners == 1 {
+            return Err(anyhow!("Cannot remove the last owner of project {}", project_id));
+        }
+        project.members.remove(user_id)
+            .ok_or_else(|| anyhow!("{} is not a member of project {}", user_id, project_id))?;
+        Ok(())
+    }
+}
+
+impl Default for ProjectRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::UserRole;
+
+    fn principal(user_id: &str, role: UserRole) -> Principal {
+        Principal { user_id: user_id.to_string(), role }
+    }
+
+    #[test]
+    fn test_project_isolation() {
+        let mut registry = ProjectRegistry::new();
+        let alice = principal("alice", UserRole::Researcher);
+        let bob = principal("bob", UserRole::Researcher);
+        let project = registry.create("Lipidomics", None, &alice).unwrap();
+
+        assert!(registry.authorize(&alice, &project.id, Access::Manage).is_ok());
+        assert!(registry.authorize(&bob, &project.id, Access::Read).is_err());
+        assert!(registry.authorize(&principal("root", UserRole::Admin), &project.id, Access::Write).is_ok());
+
+        registry.set_member(&project.id, "bob", ProjectRole::Viewer).unwrap();
+        assert!(registry.authorize(&bob, &project.id, Access::Read).is_ok());
+        assert!(registry.authorize(&bob, &project.id, Access::Write).is_err());
+
+        // Everyone shares the default project; viewers cannot write to it
+        assert!(registry.authorize(&bob, DEFAULT_PROJECT, Access::Write).is_ok());
+        assert!(registry.authorize(&principal("carol", UserRole::Viewer), DEFAULT_PROJECT, Access::Write).is_err());
+        assert_eq!(registry.visible_to(&bob).len(), 2);
+
+        assert!(registry.remove_member(&project.id, "alice").is_err());
+    }
+}
@@ -0,0 +1,458 @@
+//! Full-Text Search
+//!
+//! An embedded tantivy index over what people type into a search box:
+//! molecule names, synonyms and descriptions, and the free text of
+//! evidence, such as a curator's notes. Queries match exactly and, when
+//! fuzzy matching is on, within one edit of each term, exact matches
+//! ranking first. The index is written alongside the graph store by
+//! wrapping it in an `IndexedStore`, so it follows every molecule write.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::{info, debug};
+use serde::{Serialize, Deserialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value, STORED, STRING, TEXT};
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+use crate::bundle::ProjectBundle;
+use crate::graph::inspect::MoleculeSummary;
+use crate::graph::neighborhood::NeighborhoodOptions;
+use crate::graph::paths::MoleculePath;
+use crate::graph::pathways::PathwayMembership;
+use crate::graph::schema::Node;
+use crate::graph::stats::ProjectStats;
+use crate::graph::store::{GenePhenotypes, GraphStore, MoleculeInteraction, StoreBackend};
+use crate::graph::MoleculeNetwork;
+use crate::processing::evidence::{Evidence, IntegratedEvidence};
+use crate::processing::versioning::ConfidenceTrigger;
+use crate::projects::Project;
+
+/// Memory the index writer may use before flushing a segment
+const WRITER_MEMORY_BYTES: usize = 15_000_000;
+
+/// Results returned when no limit is given
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Boost of a match on a molecule's name over other fields
+const NAME_BOOST: f32 = 3.0;
+
+/// Boost of a match on a synonym over descriptions and notes
+const SYNONYM_BOOST: f32 = 2.0;
+
+/// Initialize the search module
+pub fn initialize() -> Result<()> {
+    info!("Initializing search module");
+    info!("Search module initialized successfully");
+    Ok(())
+}
+
+/// What a search hit refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    /// A molecule, matched on its name, synonyms or description
+    Molecule,
+
+    /// An evidence item, matched on its free text
+    Evidence,
+}
+
+impl SearchKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::Molecule => "molecule",
+            SearchKind::Evidence => "evidence",
+        }
+    }
+}
+
+/// A document matching a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Whether a molecule or an evidence item matched
+    pub kind: SearchKind,
+
+    /// ID of the molecule or evidence item
+    pub id: String,
+
+    /// Molecule the hit belongs to
+    pub molecule_id: String,
+
+    /// Molecule name, or evidence source
+    pub title: String,
+
+    /// Relevance, higher first
+    pub score: f32,
+}
+
+/// Fields of the index schema
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    /// Unique key of a document: project, kind and ID
+    key: Field,
+    /// Key of the molecule a document belongs to: project and molecule ID
+    molecule_key: Field,
+    /// Project the document belongs to
+    project_id: Field,
+    /// `molecule` or `evidence`
+    kind: Field,
+    /// Molecule or evidence ID
+    id: Field,
+    /// Molecule ID
+    molecule_id: Field,
+    /// Title shown with a hit
+    title: Field,
+    /// Molecule name
+    name: Field,
+    /// Molecule synonyms and external IDs
+    synonyms: Field,
+    /// Molecule description
+    description: Field,
+    /// Free text of evidence
+    notes: Field,
+}
+
+impl Fields {
+    fn schema() -> (Schema, Self) {
+        let mut builder = Schema::builder();
+        let fields = Self {
+            key: builder.add_text_field("key", STRING),
+            molecule_key: builder.add_text_field("molecule_key", STRING),
+            project_id: builder.add_text_field("project_id", STRING),
+            kind: builder.add_text_field("kind", STRING | STORED),
+            id: builder.add_text_field("id", STRING | STORED),
+            molecule_id: builder.add_text_field("molecule_id", STRING | STORED),
+            title: builder.add_text_field("title", STORED),
+            name: builder.add_text_field("name", TEXT),
+            synonyms: builder.add_text_field("synonyms", TEXT),
+            description: builder.add_text_field("description", TEXT),
+            notes: builder.add_text_field("notes", TEXT),
+        };
+        (builder.build(), fields)
+    }
+}
+
+/// Key of a document
+fn document_key(project_id: &str, kind: SearchKind, id: &str) -> String {
+    format!("{}\u{1f}{}\u{1f}{}", project_id, kind.as_str(), id)
+}
+
+/// Key shared by a molecule and its evidence
+fn molecule_key(project_id: &str, molecule_id: &str) -> String {
+    format!("{}\u{1f}{}", project_id, molecule_id)
+}
+
+/// Strings of a JSON value, e.g. the notes inside evidence data
+fn collect_text(value: &serde_json::Value, text: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(s) => text.push(s.clone()),
+        serde_json::Value::Array(items) => items.iter().for_each(|item| collect_text(item, text)),
+        serde_json::Value::Object(map) => map.values().for_each(|item| collect_text(item, text)),
+        _ => {}
+    }
+}
+
+/// Synonyms of a molecule: its `synonyms` and `aliases` properties and its external IDs
+fn molecule_synonyms(node: &Node) -> Vec<String> {
+    let mut synonyms = Vec::new();
+    for key in ["synonyms", "aliases"] {
+        if let Some(value) = node.get_property(key) {
+            collect_text(value, &mut synonyms);
+        }
+    }
+    synonyms.extend(node.external_ids.values().cloned());
+    synonyms
+}
+
+/// Full-text index over molecules and evidence
+pub struct SearchIndex {
+    /// Tantivy index
+    index: Index,
+
+    /// Reader serving searches
+    reader: IndexReader,
+
+    /// Writer, one at a time
+    writer: Mutex<IndexWriter>,
+
+    /// Schema fields
+    fields: Fields,
+}
+
+impl SearchIndex {
+    /// Open (or create) an index in the given directory
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create search index directory: {}", dir.display()))?;
+        let (schema, fields) = Fields::schema();
+        let directory = tantivy::directory::MmapDirectory::open(dir)
+            .with_context(|| format!("Failed to open search index: {}", dir.display()))?;
+        Self::from_index(Index::open_or_create(directory, schema)?, fields)
+    }
+
+    /// Create an index that lives only in memory
+    pub fn in_memory() -> Result<Self> {
+        let (schema, fields) = Fields::schema();
+        Self::from_index(Index::create_in_ram(schema), fields)
+    }
+
+    /// Open the index in `HEGEL_SEARCH_INDEX_DIR`, or keep it in memory when unset
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("HEGEL_SEARCH_INDEX_DIR") {
+            Ok(dir) => Self::open(Path::new(&dir)),
+            Err(_) => Self::in_memory(),
+        }
+    }
+
+    fn from_index(index: Index, fields: Fields) -> Result<Self> {
+        let reader = index.reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let writer = index.writer_with_num_threads(1, WRITER_MEMORY_BYTES)?;
+        Ok(Self { index, reader, writer: Mutex::new(writer), fields })
+    }
+
+    /// Run `f` with the writer, then commit and make the changes searchable
+    fn write(&self, f: impl FnOnce(&IndexWriter, &Fields) -> Result<()>) -> Result<()> {
+        let mut writer = self.writer.lock().map_err(|_| anyhow!("Search index writer lock poisoned"))?;
+        f(&writer, &self.fields)?;
+        writer.commit()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Index a molecule, replacing its previous document
+    pub fn index_molecule(&self, project_id: &str, molecule: &Node) -> Result<()> {
+        let description = molecule.get_property("description").and_then(|v| v.as_str()).unwrap_or_default();
+        self.write(|writer, f| {
+            let key = document_key(project_id, SearchKind::Molecule, &molecule.id);
+            writer.delete_term(Term::from_field_text(f.key, &key));
+            writer.add_document(doc!(
+                f.key => key,
+                f.molecule_key => molecule_key(project_id, &molecule.id),
+                f.project_id => project_id,
+                f.kind => SearchKind::Molecule.as_str(),
+                f.id => molecule.id.as_str(),
+                f.molecule_id => molecule.id.as_str(),
+                f.title => molecule.name.as_str(),
+                f.name => molecule.name.as_str(),
+                f.synonyms => molecule_synonyms(molecule).join("\n"),
+                f.description => description,
+            ))?;
+            Ok(())
+        })
+    }
+
+    /// Index the free text of evidence items, replacing their previous documents
+    pub fn index_evidence(&self, project_id: &str, evidence: &[Evidence]) -> Result<()> {
+        self.write(|writer, f| {
+            for item in evidence {
+                let mut notes = Vec::new();
+                collect_text(&item.data, &mut notes);
+                for value in item.metadata.values() {
+                    collect_text(value, &mut notes);
+                }
+                let key = document_key(project_id, SearchKind::Evidence, &item.id);
+                writer.delete_term(Term::from_field_text(f.key, &key));
+                writer.add_document(doc!(
+                    f.key => key,
+                    f.molecule_key => molecule_key(project_id, &item.molecule_id),
+                    f.project_id => project_id,
+                    f.kind => SearchKind::Evidence.as_str(),
+                    f.id => item.id.as_str(),
+                    f.molecule_id => item.molecule_id.as_str(),
+                    f.title => item.source.as_str(),
+                    f.notes => notes.join("\n"),
+                ))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Remove a molecule and its evidence from the index
+    pub fn remove_molecule(&self, project_id: &str, molecule_id: &str) -> Result<()> {
+        self.write(|writer, f| {
+            writer.delete_term(Term::from_field_text(f.molecule_key, &molecule_key(project_id, molecule_id)));
+            Ok(())
+        })
+    }
+
+    /// Search a project's molecules and evidence, best matches first
+    ///
+    /// The query uses tantivy's syntax (`"exact phrase"`, `name:citrate`);
+    /// parts it cannot parse are ignored. With `fuzzy`, terms also match
+    /// words one edit away, e.g. `glutamin` finds glutamine.
+    pub fn search(&self, project_id: &str, query: &str, limit: usize, fuzzy: bool) -> Result<Vec<SearchHit>> {
+        let f = &self.fields;
+        let text_fields = vec![f.name, f.synonyms, f.description, f.notes];
+        let mut parser = QueryParser::for_index(&self.index, text_fields.clone());
+        parser.set_field_boost(f.name, NAME_BOOST);
+        parser.set_field_boost(f.synonyms, SYNONYM_BOOST);
+        let (exact, _) = parser.parse_query_lenient(query);
+
+        let mut text: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Should, exact)];
+        if fuzzy {
+            for &field in &text_fields {
+                parser.set_field_fuzzy(field, false, 1, true);
+            }
+            text.push((Occur::Should, parser.parse_query_lenient(query).0));
+        }
+        let scoped = BooleanQuery::new(vec![
+            (Occur::Must, Box::new(TermQuery::new(
+                Term::from_field_text(f.project_id, project_id),
+                IndexRecordOption::Basic,
+            )) as Box<dyn Query>),
+            (Occur::Must, Box::new(BooleanQuery::new(text))),
+        ]);
+
+        let searcher = self.reader.searcher();
+        let mut hits = Vec::new();
+        for (score, address) in searcher.search(&scoped, &TopDocs::with_limit(limit))? {
+            let document: TantivyDocument = searcher.doc(address)?;
+            let text = |field: Field| document.get_first(field).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+            hits.push(SearchHit {
+                kind: if text(f.kind) == SearchKind::Evidence.as_str() { SearchKind::Evidence } else { SearchKind::Molecule },
+                id: text(f.id),
+                molecule_id: text(f.molecule_id),
+                title: text(f.title),
+                score,
+            });
+        }
+        debug!("Search for {:?} in project {} found {} hits", query, project_id, hits.len());
+        Ok(hits)
+    }
+}
+
+/// A graph store whose molecule writes are also applied to a search index
+pub struct IndexedStore {
+    /// Store the graph is kept in
+    inner: Arc<dyn GraphStore>,
+
+    /// Index kept in step with the store
+    index: Arc<SearchIndex>,
+}
+
+impl IndexedStore {
+    /// Index the molecules written to `inner`
+    pub fn new(inner: Arc<dyn GraphStore>, index: Arc<SearchIndex>) -> Self {
+        Self { inner, index }
+    }
+}
+
+#[async_trait]
+impl GraphStore for IndexedStore {
+    fn backend(&self) -> StoreBackend {
+        self.inner.backend()
+    }
+
+    async fn store_molecule(&self, project_id: &str, molecule: &Node) -> Result<()> {
+        self.inner.store_molecule(project_id, molecule).await?;
+        self.index.index_molecule(project_id, molecule)
+    }
+
+    async fn get_molecule(&self, project_id: &str, molecule_id: &str) -> Result<Option<Node>> {
+        self.inner.get_molecule(project_id, molecule_id).await
+    }
+
+    async fn list_molecules(&self, project_id: &str) -> Result<Vec<MoleculeSummary>> {
+        self.inner.list_molecules(project_id).await
+    }
+
+    async fn delete_molecule(&self, project_id: &str, molecule_id: &str) -> Result<bool> {
+        let deleted = self.inner.delete_molecule(project_id, molecule_id).await?;
+        self.index.remove_molecule(project_id, molecule_id)?;
+        Ok(deleted)
+    }
+
+    async fn molecule_pathways(&self, project_id: &str, molecule_id: &str) -> Result<Vec<PathwayMembership>> {
+        self.inner.molecule_pathways(project_id, molecule_id).await
+    }
+
+    async fn molecule_interactions(&self, project_id: &str, molecule_id: &str) -> Result<Vec<MoleculeInteraction>> {
+        self.inner.molecule_interactions(project_id, molecule_id).await
+    }
+
+    async fn find_paths(&self, project_id: &str, from: &str, to: &str, max_hops: usize, limit: usize) -> Result<Vec<MoleculePath>> {
+        self.inner.find_paths(project_id, from, to, max_hops, limit).await
+    }
+
+    async fn neighborhood(&self, project_id: &str, molecule_id: &str, options: &NeighborhoodOptions) -> Result<Option<MoleculeNetwork>> {
+        self.inner.neighborhood(project_id, molecule_id, options).await
+    }
+
+    async fn store_integrated_evidence(&self, project_id: &str, integrated: &IntegratedEvidence, trigger: ConfidenceTrigger) -> Result<()> {
+        self.inner.store_integrated_evidence(project_id, integrated, trigger).await?;
+        self.index.index_evidence(project_id, &integrated.evidence_items)
+    }
+
+    async fn molecule_evidence(&self, project_id: &str, molecule_id: &str) -> Result<Vec<Evidence>> {
+        self.inner.molecule_evidence(project_id, molecule_id).await
+    }
+
+    async fn gene_phenotypes(&self, project_id: &str, limit: usize) -> Result<Vec<GenePhenotypes>> {
+        self.inner.gene_phenotypes(project_id, limit).await
+    }
+
+    async fn store_project(&self, project: &Project) -> Result<()> {
+        self.inner.store_project(project).await
+    }
+
+    async fn project_stats(&self, project_id: &str) -> Result<ProjectStats> {
+        self.inner.project_stats(project_id).await
+    }
+
+    async fn export_project(&self, project_id: &str) -> Result<ProjectBundle> {
+        self.inner.export_project(project_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::embedded::EmbeddedStore;
+    use crate::graph::schema::NodeType;
+    use crate::processing::evidence::EvidenceType;
+
+    fn molecule(id: &str, name: &str, synonyms: &[&str]) -> Node {
+        let mut node = Node::new(id.to_string(), NodeType::Molecule, name.to_string());
+        node.add_property("synonyms", serde_json::json!(synonyms));
+        node
+    }
+
+    #[test]
+    fn test_search_ranks_and_scopes() {
+        let index = SearchIndex::in_memory().unwrap();
+        index.index_molecule("default", &molecule("m1", "glutamine", &["L-glutamine", "Gln"])).unwrap();
+        index.index_molecule("default", &molecule("m2", "glutamate", &["glutamic acid"])).unwrap();
+        index.index_molecule("other", &molecule("m3", "glutamine", &[])).unwrap();
+        let evidence = Evidence::manual("m2", EvidenceType::Structural, 0.9, Some("NMR confirms the glutamine standard"), "lab").unwrap();
+        index.index_evidence("default", &[evidence]).unwrap();
+
+        let hits = index.search("default", "glutamine", 10, false).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!((hits[0].kind, hits[0].id.as_str()), (SearchKind::Molecule, "m1"));
+        assert_eq!((hits[1].kind, hits[1].molecule_id.as_str()), (SearchKind::Evidence, "m2"));
+
+        assert!(index.search("default", "glutamin", 10, false).unwrap().is_empty());
+        let fuzzy = index.search("default", "glutamin", 10, true).unwrap();
+        assert!(fuzzy.iter().any(|hit| hit.id == "m1"));
+        assert_eq!(index.search("default", "gln", 10, false).unwrap()[0].id, "m1");
+    }
+
+    #[tokio::test]
+    async fn test_indexed_store_follows_writes() {
+        let index = Arc::new(SearchIndex::in_memory().unwrap());
+        let store = IndexedStore::new(Arc::new(EmbeddedStore::temporary().unwrap()), index.clone());
+        store.store_molecule("default", &molecule("m1", "citrate", &[])).await.unwrap();
+        store.store_molecule("default", &molecule("m1", "isocitrate", &[])).await.unwrap();
+        assert!(index.search("default", "citrate", 10, false).unwrap().is_empty());
+        assert_eq!(index.search("default", "isocitrate", 10, false).unwrap().len(), 1);
+
+        store.delete_molecule("default", "m1").await.unwrap();
+        assert!(index.search("default", "isocitrate", 10, false).unwrap().is_empty());
+    }
+}
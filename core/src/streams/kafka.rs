@@ -0,0 +1,144 @@
+//! Kafka Stream Backend
+//!
+//! Evidence messages are consumed with auto-commit disabled; offsets are
+//! committed per message once the consumer acknowledges it. A released
+//! message is redelivered by seeking its partition back to its offset.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::info;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::{Header, Message, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::{Offset, TopicPartitionList};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{DeadLetterSink, MessageSource, StreamMessage};
+
+/// Kafka subscription to an evidence topic
+pub struct KafkaSource {
+    /// Underlying consumer
+    consumer: StreamConsumer,
+
+    /// Name reported in logs
+    name: String,
+
+    /// Deliveries seen so far per message, so redeliveries can be counted
+    deliveries: Mutex<HashMap<String, u32>>,
+}
+
+impl KafkaSource {
+    /// Subscribe to a topic as a member of a consumer group
+    pub fn connect(brokers: &str, topic: &str, group: &str) -> Result<Self> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", group)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .context("Failed to create Kafka consumer")?;
+        consumer.subscribe(&[topic]).context("Failed to subscribe to Kafka topic")?;
+
+        info!("Subscribed to Kafka topic {} on {} as {}", topic, brokers, group);
+        Ok(Self {
+            consumer,
+            name: format!("kafka:{}", topic),
+            deliveries: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn position(message: &StreamMessage) -> Result<(String, i32, i64)> {
+        let mut parts = message.id.rsplitn(3, '/');
+        let offset = parts.next().and_then(|o| o.parse().ok());
+        let partition = parts.next().and_then(|p| p.parse().ok());
+        match (parts.next(), partition, offset) {
+            (Some(topic), Some(partition), Some(offset)) => Ok((topic.to_string(), partition, offset)),
+            _ => Err(anyhow!("Not a Kafka message ID: {}", message.id)),
+        }
+    }
+}
+
+#[async_trait]
+impl MessageSource for KafkaSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn next(&self) -> Result<Option<StreamMessage>> {
+        let message = self.consumer.recv().await.context("Failed to receive Kafka message")?;
+        let id = format!("{}/{}/{}", message.topic(), message.partition(), message.offset());
+
+        let delivery_count = {
+            let mut deliveries = self.deliveries.lock().unwrap();
+            let count = deliveries.entry(id.clone()).or_insert(0);
+            *count += 1;
+            *count
+        };
+
+        Ok(Some(StreamMessage {
+            key: message.key().map(|k| String::from_utf8_lossy(k).into_owned()),
+            payload: message.payload().unwrap_or_default().to_vec(),
+            delivery_count,
+            id,
+        }))
+    }
+
+    async fn ack(&self, message: &StreamMessage) -> Result<()> {
+        let (topic, partition, offset) = Self::position(message)?;
+        let mut offsets = TopicPartitionList::new();
+        offsets.add_partition_offset(&topic, partition, Offset::Offset(offset + 1))?;
+        self.consumer.commit(&offsets, CommitMode::Async)?;
+        self.deliveries.lock().unwrap().remove(&message.id);
+        Ok(())
+    }
+
+    async fn nack(&self, message: &StreamMessage) -> Result<()> {
+        let (topic, partition, offset) = Self::position(message)?;
+        self.consumer
+            .seek(&topic, partition, Offset::Offset(offset), Duration::from_secs(10))
+            .context("Failed to rewind Kafka partition")?;
+        Ok(())
+    }
+}
+
+/// Dead-letter topic on Kafka
+pub struct KafkaDeadLetter {
+    /// Producer for the dead-letter topic
+    producer: FutureProducer,
+
+    /// Dead-letter topic
+    topic: String,
+}
+
+impl KafkaDeadLetter {
+    /// Create a producer for the dead-letter topic
+    pub fn connect(brokers: &str, topic: &str) -> Result<Self> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .create()
+            .context("Failed to create Kafka producer")?;
+        Ok(Self { producer, topic: topic.to_string() })
+    }
+}
+
+#[async_trait]
+impl DeadLetterSink for KafkaDeadLetter {
+    async fn send(&self, message: &StreamMessage, reason: &str) -> Result<()> {
+        let headers = OwnedHeaders::new()
+            .insert(Header { key: "hegel-error", value: Some(reason) })
+            .insert(Header { key: "hegel-source-id", value: Some(message.id.as_str()) });
+        let mut record = FutureRecord::<str, [u8]>::to(&self.topic)
+            .payload(&message.payload)
+            .headers(headers);
+        if let Some(key) = &message.key {
+            record = record.key(key);
+        }
+
+        self.producer.send(record, Duration::from_secs(10)).await
+            .map_err(|(e, _)| anyhow!("Failed to publish dead letter: {}", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,497 @@
+//! Streaming Evidence Ingestion
+//!
+//! This module consumes evidence messages pushed by instruments over a message
+//! broker (Kafka or NATS JetStream), runs them through the identity pipeline
+//! and writes the integrated result to Neo4j.
+//!
+//! Delivery is at-least-once: a message is acknowledged only after its result
+//! has been written, or after it has been handed to the dead-letter sink.
+//! Messages that cannot be parsed, or whose evidence breaks its schema, are
+//! dead-lettered immediately; messages that fail to process are retried and
+//! dead-lettered once they have been delivered `max_attempts` times.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use log::{info, debug, warn, error};
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::alerts::AlertEngine;
+use crate::graph::embedded::EmbeddedStore;
+use crate::graph::neo4j::Neo4jClient;
+use crate::processing::evidence::{Evidence, IntegratedEvidence};
+use crate::processing::evidence_schema::EvidenceSchemaRegistry;
+use crate::processing::pipeline::IdentityPipeline;
+use crate::processing::versioning::ConfidenceTrigger;
+use crate::projects::DEFAULT_PROJECT;
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+
+/// Initialize the streams module
+pub fn initialize() -> Result<()> {
+    info!("Initializing streams module");
+    info!("Streams module initialized successfully");
+    Ok(())
+}
+
+/// A message received from a broker
+#[derive(Debug, Clone)]
+pub struct StreamMessage {
+    /// Broker-specific message identifier (e.g. `topic/partition/offset`)
+    pub id: String,
+
+    /// Message key, if any
+    pub key: Option<String>,
+
+    /// Raw message payload
+    pub payload: Vec<u8>,
+
+    /// Number of times the broker has delivered this message, including this delivery
+    pub delivery_count: u32,
+}
+
+/// Payload of an evidence message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceMessage {
+    /// Project the molecule belongs to
+    #[serde(default = "default_project")]
+    pub project_id: String,
+
+    /// Molecule the evidence relates to
+    pub molecule_id: String,
+
+    /// Evidence items to integrate
+    pub evidence: Vec<Evidence>,
+}
+
+fn default_project() -> String {
+    DEFAULT_PROJECT.to_string()
+}
+
+/// A subscription to a topic of evidence messages
+#[async_trait]
+pub trait MessageSource: Send + Sync {
+    /// Name of the source, used in logs
+    fn name(&self) -> &str;
+
+    /// Wait for the next message; `None` means the subscription has ended
+    async fn next(&self) -> Result<Option<StreamMessage>>;
+
+    /// Acknowledge a message so it is not delivered again
+    async fn ack(&self, message: &StreamMessage) -> Result<()>;
+
+    /// Release a message without acknowledging it, so the broker redelivers it
+    async fn nack(&self, message: &StreamMessage) -> Result<()>;
+}
+
+/// Destination for messages that cannot be processed
+#[async_trait]
+pub trait DeadLetterSink: Send + Sync {
+    /// Forward a message together with the reason it failed
+    async fn send(&self, message: &StreamMessage, reason: &str) -> Result<()>;
+}
+
+/// Destination for integrated evidence
+#[async_trait]
+pub trait ResultSink: Send + Sync {
+    /// Persist the integration result for one molecule of a project
+    async fn write(&self, project_id: &str, integrated: &IntegratedEvidence) -> Result<()>;
+}
+
+#[async_trait]
+impl ResultSink for Neo4jClient {
+    async fn write(&self, project_id: &str, integrated: &IntegratedEvidence) -> Result<()> {
+        self.store_integrated_evidence(project_id, integrated, ConfidenceTrigger::Stream).await
+    }
+}
+
+#[async_trait]
+impl ResultSink for EmbeddedStore {
+    async fn write(&self, project_id: &str, integrated: &IntegratedEvidence) -> Result<()> {
+        self.store_integrated_evidence(project_id, integrated, ConfidenceTrigger::Stream)
+    }
+}
+
+/// Options for stream consumption
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamOptions {
+    /// Deliveries after which a failing message is dead-lettered
+    pub max_attempts: u32,
+
+    /// Delay before a failing message is released for redelivery, doubled per delivery
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            retry_backoff_ms: 500,
+        }
+    }
+}
+
+/// What happened to a consumed message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageOutcome {
+    /// Processed, written and acknowledged
+    Processed,
+
+    /// Released for redelivery
+    Retried,
+
+    /// Forwarded to the dead-letter sink and acknowledged
+    DeadLettered,
+}
+
+/// Counters kept by a running consumer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsumerStats {
+    /// Messages processed and acknowledged
+    pub processed: u64,
+
+    /// Messages released for redelivery
+    pub retried: u64,
+
+    /// Messages forwarded to the dead-letter sink
+    pub dead_lettered: u64,
+}
+
+/// Consumer that turns evidence messages into integrated results
+pub struct EvidenceStreamConsumer {
+    /// Subscription to consume from
+    source: Box<dyn MessageSource>,
+
+    /// Where integrated results are written
+    sink: Box<dyn ResultSink>,
+
+    /// Where unprocessable messages go
+    dead_letter: Option<Box<dyn DeadLetterSink>>,
+
+    /// Pipeline used for integration
+    pipeline: IdentityPipeline,
+
+    /// Consumption options
+    options: StreamOptions,
+
+    /// Rules evaluated against every written result
+    alerts: Option<Arc<AlertEngine>>,
+
+    /// Schemas evidence data is validated against
+    schemas: Arc<EvidenceSchemaRegistry>,
+}
+
+impl EvidenceStreamConsumer {
+    /// Create a consumer with the default pipeline and options
+    pub fn new(source: Box<dyn MessageSource>, sink: Box<dyn ResultSink>) -> Self {
+        Self {
+            source,
+            sink,
+            dead_letter: None,
+            pipeline: IdentityPipeline::new(),
+            options: StreamOptions::default(),
+            alerts: None,
+            schemas: Arc::new(EvidenceSchemaRegistry::builtin().expect("built-in evidence schemas are valid")),
+        }
+    }
+
+    /// Forward unprocessable messages to a dead-letter sink
+    ///
+    /// Without one, failing messages are redelivered indefinitely.
+    pub fn with_dead_letter(mut self, dead_letter: Box<dyn DeadLetterSink>) -> Self {
+        self.dead_letter = Some(dead_letter);
+        self
+    }
+
+    /// Integrate evidence with the given pipeline
+    pub fn with_pipeline(mut self, pipeline: IdentityPipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Use the given consumption options
+    pub fn with_options(mut self, options: StreamOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Evaluate alerting rules against every written result
+    pub fn with_alerts(mut self, alerts: Arc<AlertEngine>) -> Self {
+        self.alerts = Some(alerts);
+        self
+    }
+
+    /// Validate evidence against the given schemas instead of the built-in ones
+    pub fn with_schemas(mut self, schemas: Arc<EvidenceSchemaRegistry>) -> Self {
+        self.schemas = schemas;
+        self
+    }
+
+    /// Consume until the subscription ends or `limit` messages have been handled
+    pub async fn run(&self, limit: Option<u64>) -> Result<ConsumerStats> {
+        info!("Consuming evidence from {}", self.source.name());
+        let mut stats = ConsumerStats::default();
+
+        while limit.is_none_or(|limit| stats.processed + stats.retried + stats.dead_lettered < limit) {
+            let message = match self.source.next().await? {
+                Some(message) => message,
+                None => break,
+            };
+
+            match self.handle(&message).await? {
+                MessageOutcome::Processed => stats.processed += 1,
+                MessageOutcome::Retried => stats.retried += 1,
+                MessageOutcome::DeadLettered => stats.dead_lettered += 1,
+            }
+        }
+
+        info!("Stopped consuming from {}: {} processed, {} retried, {} dead-lettered",
+              self.source.name(), stats.processed, stats.retried, stats.dead_lettered);
+        Ok(stats)
+    }
+
+    /// Handle one message, acknowledging it only once it is safely dealt with
+    ///
+    /// Errors are returned only when the broker itself fails.
+    pub async fn handle(&self, message: &StreamMessage) -> Result<MessageOutcome> {
+        let mut parsed: EvidenceMessage = match serde_json::from_slice(&message.payload) {
+            Ok(parsed) => parsed,
+            // Redelivering a malformed payload can't help
+            Err(e) => return self.reject(message, &format!("Malformed evidence message: {}", e), true).await,
+        };
+        if let Err(errors) = self.schemas.validate_all(&mut parsed.evidence) {
+            let reason = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
+            return self.reject(message, &reason, true).await;
+        }
+
+        match self.process(&parsed).await {
+            Ok(()) => {
+                self.source.ack(message).await?;
+                debug!("Processed message {} for molecule {}", message.id, parsed.molecule_id);
+                Ok(MessageOutcome::Processed)
+            }
+            Err(e) => {
+                let permanent = message.delivery_count >= self.options.max_attempts;
+                self.reject(message, &format!("{:#}", e), permanent).await
+            }
+        }
+    }
+
+    async fn process(&self, message: &EvidenceMessage) -> Result<()> {
+        if let Some(other) = message.evidence.iter().find(|e| e.molecule_id != message.molecule_id) {
+            return Err(anyhow!("Evidence {} belongs to molecule {}, not {}",
+                               other.id, other.molecule_id, message.molecule_id));
+        }
+        let integrated = self.pipeline.run(&message.molecule_id, message.evidence.clone()).await?;
+        self.sink.write(&message.project_id, &integrated).await.context("Failed to write integrated evidence")?;
+        if let Some(alerts) = &self.alerts {
+            alerts.observe_integrated(&message.project_id, &integrated).await;
+        }
+        Ok(())
+    }
+
+    async fn reject(&self, message: &StreamMessage, reason: &str, permanent: bool) -> Result<MessageOutcome> {
+        if permanent {
+            if let Some(dead_letter) = &self.dead_letter {
+                match dead_letter.send(message, reason).await {
+                    Ok(()) => {
+                        warn!("Dead-lettered message {}: {}", message.id, reason);
+                        self.source.ack(message).await?;
+                        return Ok(MessageOutcome::DeadLettered);
+                    }
+                    Err(e) => error!("Failed to dead-letter message {}: {}", message.id, e),
+                }
+            }
+        }
+
+        warn!("Message {} failed on delivery {}: {}", message.id, message.delivery_count, reason);
+        let shift = message.delivery_count.saturating_sub(1).min(6);
+        tokio::time::sleep(Duration::from_millis(self.options.retry_backoff_ms << shift)).await;
+        self.source.nack(message).await?;
+        Ok(MessageOutcome::Retried)
+    }
+}
+
+/// Broker connection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamConfig {
+    /// Broker type (`kafka` or `nats`)
+    pub backend: String,
+
+    /// Kafka bootstrap servers or NATS server URL
+    pub url: String,
+
+    /// Topic (Kafka) or JetStream stream name (NATS) carrying evidence messages
+    pub topic: String,
+
+    /// Consumer group (Kafka) or durable consumer name (NATS)
+    pub group: String,
+
+    /// Topic (Kafka) or subject (NATS) for dead letters, if any
+    pub dead_letter_topic: Option<String>,
+}
+
+impl StreamConfig {
+    /// Read the configuration from `HEGEL_STREAM_*` environment variables
+    pub fn from_env() -> Result<Self> {
+        let backend = std::env::var("HEGEL_STREAM_BACKEND").unwrap_or_else(|_| "kafka".to_string());
+        let default_url = match backend.as_str() {
+            "nats" => "nats://localhost:4222",
+            _ => "localhost:9092",
+        };
+
+        Ok(Self {
+            url: std::env::var("HEGEL_STREAM_URL").unwrap_or_else(|_| default_url.to_string()),
+            topic: std::env::var("HEGEL_STREAM_TOPIC")
+                .context("Stream topic environment variable HEGEL_STREAM_TOPIC not set")?,
+            group: std::env::var("HEGEL_STREAM_GROUP").unwrap_or_else(|_| "hegel".to_string()),
+            dead_letter_topic: std::env::var("HEGEL_STREAM_DEAD_LETTER_TOPIC").ok(),
+            backend,
+        })
+    }
+
+    /// Connect to the configured broker
+    pub async fn connect(&self) -> Result<(Box<dyn MessageSource>, Option<Box<dyn DeadLetterSink>>)> {
+        match self.backend.as_str() {
+            #[cfg(feature = "kafka")]
+            "kafka" => {
+                let source = kafka::KafkaSource::connect(&self.url, &self.topic, &self.group)?;
+                let dead_letter = match &self.dead_letter_topic {
+                    Some(topic) => Some(Box::new(kafka::KafkaDeadLetter::connect(&self.url, topic)?) as Box<dyn DeadLetterSink>),
+                    None => None,
+                };
+                Ok((Box::new(source), dead_letter))
+            }
+            #[cfg(feature = "nats")]
+            "nats" => {
+                let source = nats::NatsSource::connect(&self.url, &self.topic, &self.group).await?;
+                let dead_letter = self.dead_letter_topic.as_ref()
+                    .map(|subject| Box::new(source.dead_letter(subject)) as Box<dyn DeadLetterSink>);
+                Ok((Box::new(source), dead_letter))
+            }
+            other => Err(anyhow!("Unsupported or disabled stream backend: {} (enable the `kafka` or `nats` feature)", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Source that replays a fixed queue and records acknowledgements
+    struct QueueSource {
+        queue: Mutex<Vec<StreamMessage>>,
+        acked: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl MessageSource for QueueSource {
+        fn name(&self) -> &str {
+            "queue"
+        }
+
+        async fn next(&self) -> Result<Option<StreamMessage>> {
+            let mut queue = self.queue.lock().unwrap();
+            Ok(if queue.is_empty() { None } else { Some(queue.remove(0)) })
+        }
+
+        async fn ack(&self, message: &StreamMessage) -> Result<()> {
+            self.acked.lock().unwrap().push(message.id.clone());
+            Ok(())
+        }
+
+        async fn nack(&self, message: &StreamMessage) -> Result<()> {
+            let mut redelivered = message.clone();
+            redelivered.delivery_count += 1;
+            self.queue.lock().unwrap().push(redelivered);
+            Ok(())
+        }
+    }
+
+    /// Sink that fails for one molecule
+    struct RecordingSink {
+        written: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ResultSink for RecordingSink {
+        async fn write(&self, _project_id: &str, integrated: &IntegratedEvidence) -> Result<()> {
+            if integrated.molecule_id == "broken" {
+                return Err(anyhow!("write refused"));
+            }
+            self.written.lock().unwrap().push(integrated.molecule_id.clone());
+            Ok(())
+        }
+    }
+
+    struct RecordingDeadLetter {
+        sent: std::sync::Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl DeadLetterSink for RecordingDeadLetter {
+        async fn send(&self, message: &StreamMessage, _reason: &str) -> Result<()> {
+            self.sent.lock().unwrap().push(message.id.clone());
+            Ok(())
+        }
+    }
+
+    fn message(id: &str, molecule_id: &str) -> StreamMessage {
+        let evidence = Evidence {
+            id: format!("ev-{}", id),
+            molecule_id: molecule_id.to_string(),
+            evidence_type: crate::processing::evidence::EvidenceType::MassSpec,
+            source: "instrument".to_string(),
+            confidence: 0.8,
+            data: serde_json::json!({"mass_error_ppm": 1.5}),
+            metadata: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+        };
+        let payload = EvidenceMessage {
+            project_id: DEFAULT_PROJECT.to_string(),
+            molecule_id: molecule_id.to_string(),
+            evidence: vec![evidence],
+        };
+        StreamMessage {
+            id: id.to_string(),
+            key: None,
+            payload: serde_json::to_vec(&payload).unwrap(),
+            delivery_count: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consumer_retries_and_dead_letters() {
+        let mut malformed = message("m3", "mol-3");
+        malformed.payload = b"not json".to_vec();
+        let mut off_schema = message("m4", "mol-4");
+        off_schema.payload = String::from_utf8(off_schema.payload).unwrap()
+            .replace(r#""mass_error_ppm":1.5"#, r#""mass_error_ppm":"1.5 ppm""#)
+            .into_bytes();
+        let source = QueueSource {
+            queue: Mutex::new(vec![message("m1", "mol-1"), message("m2", "broken"), malformed, off_schema]),
+            acked: Mutex::new(Vec::new()),
+        };
+        let sent = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let consumer = EvidenceStreamConsumer::new(
+                Box::new(source),
+                Box::new(RecordingSink { written: Mutex::new(Vec::new()) }),
+            )
+            .with_dead_letter(Box::new(RecordingDeadLetter { sent: sent.clone() }))
+            .with_options(StreamOptions { max_attempts: 2, retry_backoff_ms: 0 });
+
+        let stats = consumer.run(None).await.unwrap();
+        assert_eq!(stats.processed, 1);
+        // The failing write is retried once, then dead-lettered with the malformed
+        // and off-schema messages
+        assert_eq!(stats.retried, 1);
+        assert_eq!(stats.dead_lettered, 3);
+        assert_eq!(*sent.lock().unwrap(), vec!["m3", "m4", "m2"]);
+    }
+}
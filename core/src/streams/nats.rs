@@ -0,0 +1,136 @@
+//! NATS JetStream Backend
+//!
+//! Evidence messages are pulled by a durable consumer on a JetStream stream,
+//! so acknowledgements and redelivery counts are tracked by the server.
+
+use anyhow::{anyhow, Context, Result};
+use async_nats::jetstream::{self, consumer::{pull, PullConsumer}};
+use async_trait::async_trait;
+use futures::StreamExt;
+use log::info;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use super::{DeadLetterSink, MessageSource, StreamMessage};
+
+/// Durable pull subscription to a JetStream stream
+pub struct NatsSource {
+    /// JetStream context, shared with the dead-letter publisher
+    context: jetstream::Context,
+
+    /// Message stream of the durable consumer
+    messages: Mutex<pull::Stream>,
+
+    /// Messages handed out and not yet acknowledged or released
+    pending: Mutex<HashMap<String, jetstream::Message>>,
+
+    /// Name reported in logs
+    name: String,
+}
+
+impl NatsSource {
+    /// Bind a durable consumer to a JetStream stream
+    pub async fn connect(url: &str, stream: &str, durable: &str) -> Result<Self> {
+        let client = async_nats::connect(url).await
+            .with_context(|| format!("Failed to connect to NATS at {}", url))?;
+        let context = jetstream::new(client);
+
+        let consumer: PullConsumer = context.get_stream(stream).await
+            .map_err(|e| anyhow!("Failed to get JetStream stream {}: {}", stream, e))?
+            .get_or_create_consumer(durable, pull::Config {
+                durable_name: Some(durable.to_string()),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to create JetStream consumer {}: {}", durable, e))?;
+        let messages = consumer.messages().await
+            .map_err(|e| anyhow!("Failed to pull from JetStream consumer {}: {}", durable, e))?;
+
+        info!("Consuming JetStream stream {} on {} as {}", stream, url, durable);
+        Ok(Self {
+            context,
+            messages: Mutex::new(messages),
+            pending: Mutex::new(HashMap::new()),
+            name: format!("nats:{}", stream),
+        })
+    }
+
+    /// Dead-letter publisher on the same connection
+    pub fn dead_letter(&self, subject: &str) -> NatsDeadLetter {
+        NatsDeadLetter {
+            context: self.context.clone(),
+            subject: subject.to_string(),
+        }
+    }
+
+    async fn take(&self, message: &StreamMessage) -> Result<jetstream::Message> {
+        self.pending.lock().await.remove(&message.id)
+            .ok_or_else(|| anyhow!("Unknown or already settled NATS message: {}", message.id))
+    }
+}
+
+#[async_trait]
+impl MessageSource for NatsSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn next(&self) -> Result<Option<StreamMessage>> {
+        let message = match self.messages.lock().await.next().await {
+            Some(message) => message.map_err(|e| anyhow!("Failed to receive NATS message: {}", e))?,
+            None => return Ok(None),
+        };
+
+        let info = message.info().map_err(|e| anyhow!("Invalid JetStream message metadata: {}", e))?;
+        let id = format!("{}/{}", info.stream, info.stream_sequence);
+        let delivery_count = info.delivered.max(1) as u32;
+
+        let received = StreamMessage {
+            key: Some(message.subject.to_string()),
+            payload: message.payload.to_vec(),
+            delivery_count,
+            id: id.clone(),
+        };
+        self.pending.lock().await.insert(id, message);
+        Ok(Some(received))
+    }
+
+    async fn ack(&self, message: &StreamMessage) -> Result<()> {
+        self.take(message).await?.ack().await
+            .map_err(|e| anyhow!("Failed to acknowledge NATS message {}: {}", message.id, e))
+    }
+
+    async fn nack(&self, message: &StreamMessage) -> Result<()> {
+        self.take(message).await?
+            .ack_with(jetstream::AckKind::Nak(Some(Duration::from_secs(1))))
+            .await
+            .map_err(|e| anyhow!("Failed to release NATS message {}: {}", message.id, e))
+    }
+}
+
+/// Dead-letter subject on JetStream
+pub struct NatsDeadLetter {
+    /// JetStream context
+    context: jetstream::Context,
+
+    /// Subject dead letters are published to
+    subject: String,
+}
+
+#[async_trait]
+impl DeadLetterSink for NatsDeadLetter {
+    async fn send(&self, message: &StreamMessage, reason: &str) -> Result<()> {
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert("Hegel-Error", reason);
+        headers.insert("Hegel-Source-Id", message.id.as_str());
+
+        self.context
+            .publish_with_headers(self.subject.clone(), headers, message.payload.clone().into())
+            .await
+            .map_err(|e| anyhow!("Failed to publish dead letter: {}", e))?
+            .await
+            .map_err(|e| anyhow!("Dead letter was not acknowledged: {}", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+//! TUI State
+//!
+//! Screen state of `hegel tui` and how key presses change it. Keys that need
+//! data from the graph store do not fetch it themselves; they return an
+//! `Action` for the runner to carry out, which keeps this state testable
+//! without a terminal or a database.
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::graph::inspect::{InspectedEvidence, MoleculeInspection, MoleculeSummary};
+
+/// Pane that receives the arrow keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    /// The molecule list
+    Molecules,
+
+    /// The evidence table of the inspected molecule
+    Evidence,
+}
+
+/// Work the runner has to do after a key press
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Nothing beyond redrawing
+    None,
+
+    /// Leave the interface
+    Quit,
+
+    /// Reload the molecule list
+    Refresh,
+
+    /// Load everything stored about a molecule
+    Inspect(String),
+
+    /// Re-integrate a molecule's stored evidence and store the result
+    Revalidate(String),
+}
+
+/// State of the interface
+#[derive(Debug, Clone)]
+pub struct App {
+    /// Project being browsed
+    pub project_id: String,
+
+    /// Molecules of the project
+    pub molecules: Vec<MoleculeSummary>,
+
+    /// Index of the highlighted molecule
+    pub selected: usize,
+
+    /// The molecule being drilled into, once loaded
+    pub inspection: Option<MoleculeInspection>,
+
+    /// Index of the highlighted evidence item of the inspection
+    pub selected_evidence: usize,
+
+    /// Pane that receives the arrow keys
+    pub focus: Focus,
+
+    /// Message shown in the status line, replacing the key hints
+    pub status: Option<String>,
+
+    /// Whether the key reference is shown
+    pub show_help: bool,
+}
+
+impl App {
+    /// Create the state for browsing a project
+    pub fn new(project_id: &str) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+            molecules: Vec::new(),
+            selected: 0,
+            inspection: None,
+            selected_evidence: 0,
+            focus: Focus::Molecules,
+            status: None,
+            show_help: false,
+        }
+    }
+
+    /// Replace the molecule list, keeping the highlighted molecule if it is still there
+    pub fn set_molecules(&mut self, molecules: Vec<MoleculeSummary>) {
+        let current = self.selected_molecule().map(|m| m.molecule_id.clone());
+        self.selected = current
+            .and_then(|id| molecules.iter().position(|m| m.molecule_id == id))
+            .unwrap_or(0)
+            .min(molecules.len().saturating_sub(1));
+        self.molecules = molecules;
+    }
+
+    /// Show a freshly loaded molecule
+    pub fn set_inspection(&mut self, inspection: MoleculeInspection) {
+        self.selected_evidence = self.selected_evidence.min(inspection.evidence.len().saturating_sub(1));
+        self.inspection = Some(inspection);
+    }
+
+    /// Show a message in the status line until the next key press
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    /// The highlighted molecule
+    pub fn selected_molecule(&self) -> Option<&MoleculeSummary> {
+        self.molecules.get(self.selected)
+    }
+
+    /// The highlighted evidence item of the inspected molecule
+    pub fn selected_evidence(&self) -> Option<&InspectedEvidence> {
+        self.inspection.as_ref()?.evidence.get(self.selected_evidence)
+    }
+
+    /// Apply a key press
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        self.status = None;
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        if self.show_help {
+            self.show_help = false;
+            return Action::None;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => Action::Quit,
+            KeyCode::Char('?') => {
+                self.show_help = true;
+                Action::None
+            }
+            KeyCode::Char('R') => Action::Refresh,
+            KeyCode::Char('r') => match self.selected_molecule() {
+                Some(molecule) => Action::Revalidate(molecule.molecule_id.clone()),
+                None => Action::None,
+            },
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.step(-1);
+                Action::None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.step(1);
+                Action::None
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') if self.focus == Focus::Molecules => {
+                match self.selected_molecule() {
+                    Some(molecule) => {
+                        let id = molecule.molecule_id.clone();
+                        self.focus = Focus::Evidence;
+                        self.selected_evidence = 0;
+                        Action::Inspect(id)
+                    }
+                    None => Action::None,
+                }
+            }
+            KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') if self.focus == Focus::Evidence => {
+                self.focus = Focus::Molecules;
+                Action::None
+            }
+            _ => Action::None,
+        }
+    }
+
+    /// Move the highlight of the focused pane, stopping at either end
+    fn step(&mut self, delta: isize) {
+        let (index, len) = match self.focus {
+            Focus::Molecules => (&mut self.selected, self.molecules.len()),
+            Focus::Evidence => (
+                &mut self.selected_evidence,
+                self.inspection.as_ref().map(|i| i.evidence.len()).unwrap_or(0),
+            ),
+        };
+        *index = index.saturating_add_signed(delta).min(len.saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(id: &str) -> MoleculeSummary {
+        MoleculeSummary { molecule_id: id.to_string(), name: None, confidence: Some(0.5), evidence_count: 1 }
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Action {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_navigation_and_actions() {
+        let mut app = App::new("default");
+        assert_eq!(press(&mut app, KeyCode::Enter), Action::None);
+
+        app.set_molecules(vec![summary("alanine"), summary("glucose"), summary("serine")]);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_molecule().unwrap().molecule_id, "serine");
+
+        // A refreshed list keeps the highlighted molecule
+        app.set_molecules(vec![summary("serine"), summary("valine")]);
+        assert_eq!(app.selected, 0);
+
+        assert_eq!(press(&mut app, KeyCode::Enter), Action::Inspect("serine".to_string()));
+        assert_eq!(app.focus, Focus::Evidence);
+        assert_eq!(press(&mut app, KeyCode::Char('r')), Action::Revalidate("serine".to_string()));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.focus, Focus::Molecules);
+
+        press(&mut app, KeyCode::Char('?'));
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Action::None);
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Action::Quit);
+    }
+}
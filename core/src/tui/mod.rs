@@ -0,0 +1,112 @@
+//! Terminal User Interface
+//!
+//! `hegel tui` lets analysts who do not use the web frontend browse the
+//! molecules of a project, drill into their evidence and confidence history,
+//! and re-validate a molecule from its stored evidence. The screen state and
+//! key handling live in `app` and the drawing in `ui`; this module owns the
+//! terminal and talks to Neo4j on the interface's behalf.
+
+use anyhow::{anyhow, Result};
+use log::{info, LevelFilter};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+use std::time::Duration;
+
+use crate::graph::inspect::MoleculeInspection;
+use crate::graph::neo4j::Neo4jClient;
+use crate::processing::pipeline::IdentityPipeline;
+use crate::processing::versioning::ConfidenceTrigger;
+
+pub mod app;
+pub mod ui;
+
+pub use app::{Action, App, Focus};
+
+/// Confidence revisions loaded for an inspected molecule
+const HISTORY_LIMIT: usize = 50;
+
+/// How long to wait for a key before redrawing
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Initialize the TUI module
+pub fn initialize() -> Result<()> {
+    info!("Initializing TUI module");
+    info!("TUI module initialized successfully");
+    Ok(())
+}
+
+/// Browse a project until the user quits
+///
+/// Logging is silenced while the interface is up, since log lines written to
+/// the terminal would tear the screen.
+pub async fn run(client: &Neo4jClient, project_id: &str) -> Result<()> {
+    let mut app = App::new(project_id);
+    app.set_molecules(client.list_molecules(project_id).await?);
+    if app.molecules.is_empty() {
+        app.set_status(format!("No molecules stored in project {}", project_id));
+    }
+
+    let level = log::max_level();
+    log::set_max_level(LevelFilter::Off);
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, client).await;
+    ratatui::restore();
+    log::set_max_level(level);
+    result
+}
+
+async fn event_loop(terminal: &mut DefaultTerminal, app: &mut App, client: &Neo4jClient) -> Result<()> {
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        if !event::poll(POLL_INTERVAL)? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        let project_id = app.project_id.clone();
+        match app.handle_key(key) {
+            Action::None => {}
+            Action::Quit => return Ok(()),
+            Action::Refresh => match client.list_molecules(&project_id).await {
+                Ok(molecules) => {
+                    app.set_status(format!("Loaded {} molecules", molecules.len()));
+                    app.set_molecules(molecules);
+                }
+                Err(e) => app.set_status(format!("Could not load molecules: {:#}", e)),
+            },
+            Action::Inspect(molecule_id) => match client.inspect_molecule(&project_id, &molecule_id, HISTORY_LIMIT).await {
+                Ok(inspection) => app.set_inspection(inspection),
+                Err(e) => app.set_status(format!("Could not load {}: {:#}", molecule_id, e)),
+            },
+            Action::Revalidate(molecule_id) => {
+                app.set_status(format!("Re-validating {}...", molecule_id));
+                terminal.draw(|frame| ui::draw(frame, app))?;
+                match revalidate(client, &project_id, &molecule_id).await {
+                    Ok(inspection) => {
+                        let confidence = inspection.confidence.unwrap_or(0.0);
+                        app.set_inspection(inspection);
+                        if let Ok(molecules) = client.list_molecules(&project_id).await {
+                            app.set_molecules(molecules);
+                        }
+                        app.set_status(format!("Re-validated {}: confidence {:.1}%", molecule_id, confidence * 100.0));
+                    }
+                    Err(e) => app.set_status(format!("Re-validation of {} failed: {:#}", molecule_id, e)),
+                }
+            }
+        }
+    }
+}
+
+/// Integrate a molecule's stored evidence again and return the updated molecule
+async fn revalidate(client: &Neo4jClient, project_id: &str, molecule_id: &str) -> Result<MoleculeInspection> {
+    let evidence = client.molecule_evidence(project_id, molecule_id).await?;
+    if evidence.is_empty() {
+        return Err(anyhow!("no evidence is stored for {}", molecule_id));
+    }
+    let integrated = IdentityPipeline::new().run(molecule_id, evidence).await?;
+    client.store_integrated_evidence(project_id, &integrated, ConfidenceTrigger::Revalidation).await?;
+    client.inspect_molecule(project_id, molecule_id, HISTORY_LIMIT).await
+}
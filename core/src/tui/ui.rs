@@ -0,0 +1,232 @@
+//! TUI Layout
+//!
+//! Draws the `hegel tui` screen: the molecule list on the left, the
+//! inspected molecule on the right (summary and conflicts, evidence table,
+//! details of the highlighted evidence item and the confidence history), and
+//! a status line with key hints at the bottom.
+
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Sparkline, Table, TableState, Wrap};
+use ratatui::Frame;
+
+use super::app::{App, Focus};
+use crate::graph::inspect::MoleculeInspection;
+
+/// Key reference shown by `?`
+const HELP: [(&str, &str); 8] = [
+    ("Up/Down, j/k", "Move the highlight"),
+    ("Enter, Right", "Inspect the highlighted molecule"),
+    ("Esc, Left", "Back to the molecule list"),
+    ("r", "Re-validate the highlighted molecule"),
+    ("R", "Reload the molecule list"),
+    ("?", "Show this help"),
+    ("q, Ctrl-C", "Quit"),
+    ("", "Press any key to close"),
+];
+
+/// Draw the whole screen
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [list, detail] = Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(main);
+
+    draw_molecules(frame, app, list);
+    match &app.inspection {
+        Some(inspection) => draw_inspection(frame, app, inspection, detail),
+        None => {
+            let hint = Paragraph::new("Press Enter to inspect the highlighted molecule")
+                .block(Block::default().borders(Borders::ALL).title("Molecule"));
+            frame.render_widget(hint, detail);
+        }
+    }
+
+    let status_line = match &app.status {
+        Some(message) => Line::from(message.as_str()),
+        None => Line::from(vec![
+            Span::styled(format!(" {} ", app.project_id), Style::default().add_modifier(Modifier::REVERSED)),
+            Span::raw("  Enter inspect  r re-validate  R reload  ? help  q quit"),
+        ]),
+    };
+    frame.render_widget(Paragraph::new(status_line), status);
+
+    if app.show_help {
+        draw_help(frame, main);
+    }
+}
+
+/// Colour of a confidence value
+fn confidence_style(confidence: Option<f64>) -> Style {
+    match confidence {
+        Some(c) if c >= 0.8 => Style::default().fg(Color::Green),
+        Some(c) if c >= 0.5 => Style::default().fg(Color::Yellow),
+        Some(_) => Style::default().fg(Color::Red),
+        None => Style::default().fg(Color::DarkGray),
+    }
+}
+
+/// Block title, highlighted when the pane has focus
+fn pane(title: String, focused: bool) -> Block<'static> {
+    let style = if focused { Style::default().fg(Color::Cyan) } else { Style::default() };
+    Block::default().borders(Borders::ALL).border_style(style).title(title)
+}
+
+fn draw_molecules(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app.molecules.iter()
+        .map(|molecule| {
+            let confidence = molecule.confidence
+                .map(|c| format!("{:>5.1}%", c * 100.0))
+                .unwrap_or_else(|| "    - ".to_string());
+            ListItem::new(Line::from(vec![
+                Span::styled(confidence, confidence_style(molecule.confidence)),
+                Span::raw(format!("  {}", molecule.molecule_id)),
+                Span::styled(
+                    molecule.name.as_ref().map(|name| format!("  {}", name)).unwrap_or_default(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(pane(format!("Molecules ({})", app.molecules.len()), app.focus == Focus::Molecules))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected((!app.molecules.is_empty()).then_some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_inspection(frame: &mut Frame, app: &App, inspection: &MoleculeInspection, area: Rect) {
+    let summary_height = 3 + inspection.properties.len().min(4) as u16 + inspection.conflicts.len().min(4) as u16;
+    let [summary, evidence, selected, history] = Layout::vertical([
+        Constraint::Length(summary_height),
+        Constraint::Min(5),
+        Constraint::Length(6),
+        Constraint::Length(6),
+    ]).areas(area);
+
+    let mut lines = vec![Line::from(vec![
+        Span::raw("Confidence: "),
+        Span::styled(
+            inspection.confidence.map(|c| format!("{:.1}%", c * 100.0)).unwrap_or_else(|| "not yet integrated".to_string()),
+            confidence_style(inspection.confidence),
+        ),
+        Span::raw(inspection.integrated_at
+            .map(|at| format!("  (integrated {})", at.format("%Y-%m-%d %H:%M UTC")))
+            .unwrap_or_default()),
+    ])];
+    for (key, value) in inspection.properties.iter().take(4) {
+        lines.push(Line::from(format!("{}: {}", key, value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string()))));
+    }
+    for conflict in inspection.conflicts.iter().take(4) {
+        lines.push(Line::styled(
+            format!("! {} (severity {:.2})", conflict.description, conflict.severity),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    frame.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: true }).block(pane(format!("Molecule {}", inspection.molecule_id), false)),
+        summary,
+    );
+
+    let rows: Vec<Row> = inspection.evidence.iter()
+        .map(|item| {
+            Row::new(vec![
+                Cell::from(item.id.clone()),
+                Cell::from(item.evidence_type.clone()),
+                Cell::from(item.source.clone()),
+                Cell::from(format!("{:.1}%", item.confidence * 100.0)).style(confidence_style(Some(item.confidence))),
+            ])
+        })
+        .collect();
+    let table = Table::new(rows, [Constraint::Percentage(35), Constraint::Length(12), Constraint::Min(10), Constraint::Length(7)])
+        .header(Row::new(vec!["ID", "Type", "Source", "Conf."]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(pane(format!("Evidence ({})", inspection.evidence.len()), app.focus == Focus::Evidence))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = TableState::default()
+        .with_selected((app.focus == Focus::Evidence && !inspection.evidence.is_empty()).then_some(app.selected_evidence));
+    frame.render_stateful_widget(table, evidence, &mut state);
+
+    let details = match app.selected_evidence() {
+        Some(item) => vec![
+            Line::from(format!("Recorded: {}", item.recorded_at.map(|t| t.to_rfc3339()).unwrap_or_else(|| "-".to_string()))),
+            Line::from(format!("Entered by: {}", item.entered_by.as_deref().unwrap_or("-"))),
+            Line::from(match &item.payload_sha256 {
+                Some(hash) => format!("Payload redacted (sha256 {})", hash),
+                None => "Payload stored".to_string(),
+            }),
+        ],
+        None => vec![Line::from("No evidence stored")],
+    };
+    frame.render_widget(Paragraph::new(details).block(pane("Evidence details".to_string(), false)), selected);
+
+    let points: Vec<u64> = inspection.history.iter().map(|p| (p.confidence * 100.0).round() as u64).collect();
+    let title = match (inspection.history.first(), inspection.history.last()) {
+        (Some(first), Some(last)) => format!(
+            "Confidence history ({} revisions, {:.1}% -> {:.1}%{})",
+            inspection.history.len(), first.confidence * 100.0, last.confidence * 100.0,
+            inspection.trend.map(|trend| format!(", {}", trend)).unwrap_or_default(),
+        ),
+        _ => "Confidence history".to_string(),
+    };
+    frame.render_widget(
+        Sparkline::default().data(&points).max(100).style(Style::default().fg(Color::Cyan)).block(pane(title, false)),
+        history,
+    );
+}
+
+fn draw_help(frame: &mut Frame, area: Rect) {
+    let width = 52.min(area.width);
+    let height = (HELP.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let lines: Vec<Line> = HELP.iter()
+        .map(|(keys, description)| Line::from(vec![
+            Span::styled(format!("{:<14}", keys), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(*description),
+        ]))
+        .collect();
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Keys")), popup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::inspect::MoleculeSummary;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use serde_json::json;
+
+    #[test]
+    fn test_draws_list_and_inspection() {
+        let mut app = App::new("default");
+        app.set_molecules(vec![MoleculeSummary {
+            molecule_id: "glucose".to_string(),
+            name: Some("D-glucose".to_string()),
+            confidence: Some(0.82),
+            evidence_count: 1,
+        }]);
+        let molecule = json!({
+            "id": "glucose",
+            "confidence": 0.82,
+            "confidence_history": [0.6, 0.82],
+            "confidence_history_at": ["2026-03-01T10:00:00Z", "2026-03-02T10:00:00Z"],
+        });
+        let evidence = vec![json!({"id": "ms1", "type": "mass_spec", "source": "qtof", "confidence": 0.9})];
+        app.set_inspection(MoleculeInspection::from_properties("default", molecule, evidence, 10).unwrap());
+        app.focus = Focus::Evidence;
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+
+        assert!(screen.contains("D-glucose"));
+        assert!(screen.contains("Evidence (1)"));
+        assert!(screen.contains("60.0% -> 82.0%"));
+    }
+}
@@ -0,0 +1,720 @@
+//! Webhook Notifications
+//!
+//! Delivers events to user-registered HTTP endpoints: a molecule's confidence
+//! crossing one of the webhook's thresholds, newly detected evidence conflicts,
+//! completed jobs and raised alerts. Payloads are signed with HMAC-SHA256 using the
+//! webhook's secret, failed deliveries are retried with exponential backoff,
+//! and every delivery is kept in a bounded log that the API exposes.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use log::{info, debug, warn};
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::alerts::Alert;
+use crate::offline::{self, NetworkFeature, OfflineError};
+use crate::processing::evidence::EvidenceConflict;
+use crate::processing::reevaluation::{ChangeNotifier, ReevaluationChange};
+
+/// Header carrying the payload signature
+pub const SIGNATURE_HEADER: &str = "X-Hegel-Signature";
+
+/// Header carrying the event kind
+pub const EVENT_HEADER: &str = "X-Hegel-Event";
+
+/// Header carrying the delivery ID
+pub const DELIVERY_HEADER: &str = "X-Hegel-Delivery";
+
+/// Initialize the webhook module
+pub fn initialize() -> Result<()> {
+    info!("Initializing webhook module");
+    info!("Webhook module initialized successfully");
+    Ok(())
+}
+
+/// Kinds of events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    /// A molecule's confidence crossed a threshold
+    ConfidenceThreshold,
+    /// Conflicts were detected between evidence items
+    ConflictDetected,
+    /// A processing job finished
+    JobCompleted,
+    /// An alerting rule fired
+    AlertRaised,
+}
+
+impl WebhookEventKind {
+    /// All event kinds
+    pub const ALL: [WebhookEventKind; 4] = [
+        WebhookEventKind::ConfidenceThreshold,
+        WebhookEventKind::ConflictDetected,
+        WebhookEventKind::JobCompleted,
+        WebhookEventKind::AlertRaised,
+    ];
+
+    /// Name used in payloads and headers
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventKind::ConfidenceThreshold => "confidence_threshold",
+            WebhookEventKind::ConflictDetected => "conflict_detected",
+            WebhookEventKind::JobCompleted => "job_completed",
+            WebhookEventKind::AlertRaised => "alert_raised",
+        }
+    }
+}
+
+impl fmt::Display for WebhookEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WebhookEventKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL.iter()
+            .copied()
+            .find(|kind| kind.as_str() == s.trim())
+            .ok_or_else(|| anyhow!("Unknown webhook event: {}", s))
+    }
+}
+
+/// Direction of a threshold crossing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossingDirection {
+    /// Confidence rose to or above the threshold
+    Rising,
+    /// Confidence fell below the threshold
+    Falling,
+}
+
+/// An event delivered to webhooks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A molecule's confidence crossed one or more thresholds
+    ConfidenceThreshold {
+        /// Molecule whose confidence changed
+        molecule_id: String,
+        /// Thresholds that were crossed
+        thresholds: Vec<f64>,
+        /// Direction of the crossing
+        direction: CrossingDirection,
+        /// Confidence before the change
+        previous: f64,
+        /// Confidence after the change
+        current: f64,
+    },
+    /// Conflicts were detected while integrating a molecule's evidence
+    ConflictDetected {
+        /// Molecule whose evidence conflicts
+        molecule_id: String,
+        /// The detected conflicts
+        conflicts: Vec<EvidenceConflict>,
+    },
+    /// A processing job finished
+    JobCompleted {
+        /// Job identifier
+        job_id: String,
+        /// What kind of job ran (e.g. "analyze")
+        job_type: String,
+        /// Whether the job succeeded
+        success: bool,
+        /// Job-specific summary
+        summary: serde_json::Value,
+    },
+    /// An alerting rule fired
+    AlertRaised {
+        /// The raised alert
+        alert: Alert,
+    },
+}
+
+impl WebhookEvent {
+    /// Kind of this event
+    pub fn kind(&self) -> WebhookEventKind {
+        match self {
+            WebhookEvent::ConfidenceThreshold { .. } => WebhookEventKind::ConfidenceThreshold,
+            WebhookEvent::ConflictDetected { .. } => WebhookEventKind::ConflictDetected,
+            WebhookEvent::JobCompleted { .. } => WebhookEventKind::JobCompleted,
+            WebhookEvent::AlertRaised { .. } => WebhookEventKind::AlertRaised,
+        }
+    }
+}
+
+/// A registered webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    /// Webhook identifier
+    pub id: String,
+
+    /// Endpoint events are posted to
+    pub url: String,
+
+    /// Shared secret used to sign payloads; never serialized
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+
+    /// Events this webhook receives
+    pub events: Vec<WebhookEventKind>,
+
+    /// Confidence thresholds that trigger `confidence_threshold` events
+    pub thresholds: Vec<f64>,
+
+    /// When the webhook was registered
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    /// Create a webhook subscribed to every event
+    pub fn new(url: &str, secret: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: url.to_string(),
+            secret: secret.to_string(),
+            events: WebhookEventKind::ALL.to_vec(),
+            thresholds: vec![0.5],
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Restrict the webhook to the given events
+    pub fn with_events(mut self, events: Vec<WebhookEventKind>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Set the confidence thresholds
+    pub fn with_thresholds(mut self, thresholds: Vec<f64>) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Check that the webhook can be delivered to
+    pub fn validate(&self) -> Result<()> {
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(anyhow!("Webhook URL must be http(s): {}", self.url));
+        }
+        if self.secret.is_empty() {
+            return Err(anyhow!("Webhook secret must not be empty"));
+        }
+        if self.events.is_empty() {
+            return Err(anyhow!("Webhook must subscribe to at least one event"));
+        }
+        if let Some(t) = self.thresholds.iter().find(|t| !(0.0..=1.0).contains(*t)) {
+            return Err(anyhow!("Confidence threshold out of range: {}", t));
+        }
+        Ok(())
+    }
+
+    /// Whether the webhook receives events of this kind
+    pub fn subscribes_to(&self, kind: WebhookEventKind) -> bool {
+        self.events.contains(&kind)
+    }
+
+    /// Thresholds crossed by a confidence change, with the direction
+    pub fn crossed_thresholds(&self, previous: f64, current: f64) -> Option<(Vec<f64>, CrossingDirection)> {
+        let direction = if current > previous {
+            CrossingDirection::Rising
+        } else {
+            CrossingDirection::Falling
+        };
+        let crossed: Vec<f64> = self.thresholds.iter()
+            .copied()
+            .filter(|&t| (previous < t) != (current < t))
+            .collect();
+        if crossed.is_empty() {
+            None
+        } else {
+            Some((crossed, direction))
+        }
+    }
+}
+
+/// Sign a payload with HMAC-SHA256, formatted as `sha256=<hex>`
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Verify a `sha256=<hex>` signature in constant time
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let expected = match signature.strip_prefix("sha256=").and_then(|s| hex::decode(s).ok()) {
+        Some(bytes) => bytes,
+        None => return false,
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Retry schedule for failed deliveries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total delivery attempts, including the first
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, in milliseconds
+    pub initial_backoff_ms: u64,
+
+    /// Factor applied to the delay after each retry
+    pub multiplier: f64,
+
+    /// Upper bound on the delay, in milliseconds
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            multiplier: 2.0,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the given retry (1 = first retry)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let delay = self.initial_backoff_ms as f64 * self.multiplier.powi(retry.saturating_sub(1) as i32);
+        Duration::from_millis(delay.min(self.max_backoff_ms as f64) as u64)
+    }
+}
+
+/// Outcome of a delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Still being attempted
+    Pending,
+    /// Accepted by the endpoint
+    Delivered,
+    /// Every attempt failed
+    Failed,
+}
+
+/// One attempt to post an event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    /// When the attempt was made
+    pub attempted_at: DateTime<Utc>,
+
+    /// HTTP status returned, if a response was received
+    pub status_code: Option<u16>,
+
+    /// Error message for failed attempts
+    pub error: Option<String>,
+}
+
+/// Log entry for an event posted to one webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Delivery identifier, sent in the delivery header
+    pub id: String,
+
+    /// Webhook the event was posted to
+    pub webhook_id: String,
+
+    /// Kind of event delivered
+    pub event: WebhookEventKind,
+
+    /// Signed request body
+    pub payload: serde_json::Value,
+
+    /// Current status
+    pub status: DeliveryStatus,
+
+    /// Attempts made so far
+    pub attempts: Vec<DeliveryAttempt>,
+
+    /// When the delivery was created
+    pub created_at: DateTime<Utc>,
+}
+
+/// HTTP transport used to post signed payloads
+#[async_trait]
+pub trait WebhookTransport: Send + Sync {
+    /// Post a body with headers, returning the response status
+    async fn post(&self, url: &str, headers: &[(&str, String)], body: Vec<u8>) -> Result<u16>;
+}
+
+/// Transport backed by reqwest
+pub struct HttpTransport {
+    /// HTTP client
+    client: reqwest::Client,
+
+    /// Per-request timeout
+    timeout: Duration,
+}
+
+impl HttpTransport {
+    /// Create a transport with a 10 second timeout
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WebhookTransport for HttpTransport {
+    async fn post(&self, url: &str, headers: &[(&str, String)], body: Vec<u8>) -> Result<u16> {
+        offline::ensure_available(NetworkFeature::Webhooks)?;
+        let mut request = self.client.post(url)
+            .header("Content-Type", "application/json")
+            .timeout(self.timeout)
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(*name, value.as_str());
+        }
+        let response = request.send().await.context("Failed to send webhook")?;
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Registry of webhooks and dispatcher of their events
+pub struct WebhookDispatcher {
+    /// Registered webhooks by ID
+    webhooks: RwLock<HashMap<String, Webhook>>,
+
+    /// Most recent deliveries, oldest first
+    deliveries: Mutex<VecDeque<WebhookDelivery>>,
+
+    /// Transport used for delivery
+    transport: Arc<dyn WebhookTransport>,
+
+    /// Retry schedule
+    retry: RetryPolicy,
+
+    /// Number of deliveries kept in the log
+    log_capacity: usize,
+}
+
+impl WebhookDispatcher {
+    /// Create a dispatcher delivering over HTTP
+    pub fn new() -> Self {
+        Self::with_transport(Arc::new(HttpTransport::new()))
+    }
+
+    /// Create a dispatcher delivering over the given transport
+    pub fn with_transport(transport: Arc<dyn WebhookTransport>) -> Self {
+        Self {
+            webhooks: RwLock::new(HashMap::new()),
+            deliveries: Mutex::new(VecDeque::new()),
+            transport,
+            retry: RetryPolicy::default(),
+            log_capacity: 1000,
+        }
+    }
+
+    /// Use the given retry schedule
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Keep at most this many deliveries in the log
+    pub fn with_log_capacity(mut self, capacity: usize) -> Self {
+        self.log_capacity = capacity.max(1);
+        self
+    }
+
+    /// Create a dispatcher, registering a webhook from the environment if configured
+    ///
+    /// `HEGEL_WEBHOOK_URL` and `HEGEL_WEBHOOK_SECRET` register a webhook for
+    /// every event; `HEGEL_WEBHOOK_THRESHOLDS` is a comma-separated list of
+    /// confidence thresholds.
+    pub async fn from_env() -> Result<Self> {
+        let dispatcher = Self::new();
+        if let Ok(url) = std::env::var("HEGEL_WEBHOOK_URL") {
+            let secret = std::env::var("HEGEL_WEBHOOK_SECRET")
+                .context("HEGEL_WEBHOOK_SECRET must be set when HEGEL_WEBHOOK_URL is")?;
+            let mut webhook = Webhook::new(&url, &secret);
+            if let Ok(thresholds) = std::env::var("HEGEL_WEBHOOK_THRESHOLDS") {
+                webhook.thresholds = thresholds.split(',')
+                    .map(|t| t.trim().parse().with_context(|| format!("Invalid webhook threshold: {}", t)))
+                    .collect::<Result<_>>()?;
+            }
+            dispatcher.register(webhook).await?;
+        }
+        Ok(dispatcher)
+    }
+
+    /// Register a webhook, returning its ID
+    pub async fn register(&self, webhook: Webhook) -> Result<String> {
+        webhook.validate()?;
+        info!("Registered webhook {} for {}", webhook.id, webhook.url);
+        let id = webhook.id.clone();
+        self.webhooks.write().await.insert(id.clone(), webhook);
+        Ok(id)
+    }
+
+    /// Remove a webhook, returning whether it existed
+    pub async fn unregister(&self, webhook_id: &str) -> bool {
+        self.webhooks.write().await.remove(webhook_id).is_some()
+    }
+
+    /// Registered webhooks, ordered by creation time
+    pub async fn webhooks(&self) -> Vec<Webhook> {
+        let mut webhooks: Vec<Webhook> = self.webhooks.read().await.values().cloned().collect();
+        webhooks.sort_by_key(|w| w.created_at);
+        webhooks
+    }
+
+    /// Logged deliveries, newest first, optionally for one webhook
+    pub async fn deliveries(&self, webhook_id: Option<&str>, limit: usize) -> Vec<WebhookDelivery> {
+        self.deliveries.lock().await.iter()
+            .rev()
+            .filter(|d| webhook_id.is_none_or(|id| d.webhook_id == id))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Deliver an event to every subscribed webhook
+    pub async fn emit(&self, event: WebhookEvent) -> Vec<WebhookDelivery> {
+        let targets: Vec<Webhook> = self.webhooks.read().await.values()
+            .filter(|w| w.subscribes_to(event.kind()))
+            .cloned()
+            .collect();
+
+        let mut deliveries = Vec::with_capacity(targets.len());
+        for webhook in &targets {
+            deliveries.push(self.deliver(webhook, &event).await);
+        }
+        deliveries
+    }
+
+    /// Deliver threshold events for a confidence change
+    ///
+    /// Each webhook is checked against its own thresholds; nothing is sent
+    /// when no threshold lies between the two values.
+    pub async fn confidence_changed(&self, molecule_id: &str, previous: f64, current: f64) -> Vec<WebhookDelivery> {
+        let targets: Vec<Webhook> = self.webhooks.read().await.values()
+            .filter(|w| w.subscribes_to(WebhookEventKind::ConfidenceThreshold))
+            .cloned()
+            .collect();
+
+        let mut deliveries = Vec::new();
+        for webhook in &targets {
+            if let Some((thresholds, direction)) = webhook.crossed_thresholds(previous, current) {
+                let event = WebhookEvent::ConfidenceThreshold {
+                    molecule_id: molecule_id.to_string(),
+                    thresholds,
+                    direction,
+                    previous,
+                    current,
+                };
+                deliveries.push(self.deliver(webhook, &event).await);
+            }
+        }
+        deliveries
+    }
+
+    /// Post an event to the webhook of a logged delivery again
+    pub async fn redeliver(&self, delivery_id: &str) -> Result<WebhookDelivery> {
+        let previous = self.deliveries.lock().await.iter()
+            .find(|d| d.id == delivery_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown delivery: {}", delivery_id))?;
+        let webhook = self.webhooks.read().await.get(&previous.webhook_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Webhook no longer registered: {}", previous.webhook_id))?;
+        let event: WebhookEvent = serde_json::from_value(previous.payload["data"].clone())
+            .context("Logged payload is not a webhook event")?;
+        Ok(self.deliver(&webhook, &event).await)
+    }
+
+    async fn deliver(&self, webhook: &Webhook, event: &WebhookEvent) -> WebhookDelivery {
+        let id = uuid::Uuid::new_v4().to_string();
+        let payload = serde_json::json!({
+            "id": id,
+            "webhook_id": webhook.id,
+            "created_at": Utc::now().to_rfc3339(),
+            "data": event,
+        });
+        let body = serde_json::to_vec(&payload).unwrap_or_default();
+        let headers = [
+            (SIGNATURE_HEADER, sign_payload(&webhook.secret, &body)),
+            (EVENT_HEADER, event.kind().to_string()),
+            (DELIVERY_HEADER, id.clone()),
+        ];
+
+        let mut delivery = WebhookDelivery {
+            id,
+            webhook_id: webhook.id.clone(),
+            event: event.kind(),
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: Vec::new(),
+            created_at: Utc::now(),
+        };
+
+        for attempt in 1..=self.retry.max_attempts.max(1) {
+            if attempt > 1 {
+                tokio::time::sleep(self.retry.backoff(attempt - 1)).await;
+            }
+
+            let result = self.transport.post(&webhook.url, &headers, body.clone()).await;
+            let refused_offline = matches!(&result, Err(e) if e.is::<OfflineError>());
+            let (status_code, error) = match result {
+                Ok(code) if (200..300).contains(&code) => (Some(code), None),
+                Ok(code) => (Some(code), Some(format!("Endpoint responded with HTTP {}", code))),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            let succeeded = error.is_none();
+            delivery.attempts.push(DeliveryAttempt {
+                attempted_at: Utc::now(),
+                status_code,
+                error,
+            });
+
+            if succeeded {
+                delivery.status = DeliveryStatus::Delivered;
+                debug!("Delivered {} to webhook {} on attempt {}", delivery.event, webhook.id, attempt);
+                break;
+            }
+            // Client errors other than rate limiting, and offline mode, will not succeed on retry
+            if refused_offline || matches!(status_code, Some(code) if (400..500).contains(&code) && code != 429) {
+                break;
+            }
+        }
+
+        if delivery.status != DeliveryStatus::Delivered {
+            delivery.status = DeliveryStatus::Failed;
+            warn!("Failed to deliver {} to webhook {} after {} attempts",
+                  delivery.event, webhook.id, delivery.attempts.len());
+        }
+
+        let mut log = self.deliveries.lock().await;
+        if log.len() >= self.log_capacity {
+            log.pop_front();
+        }
+        log.push_back(delivery.clone());
+        delivery
+    }
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ChangeNotifier for Arc<WebhookDispatcher> {
+    async fn notify(&self, changes: &[ReevaluationChange]) -> Result<()> {
+        for change in changes {
+            if let Some(before) = change.confidence_before {
+                self.confidence_changed(&change.molecule_id, before, change.confidence_after).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    /// Transport that replays a fixed sequence of responses
+    struct ScriptedTransport {
+        responses: StdMutex<VecDeque<u16>>,
+        /// Signature header and body of every request
+        requests: StdMutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    #[async_trait]
+    impl WebhookTransport for ScriptedTransport {
+        async fn post(&self, _url: &str, headers: &[(&str, String)], body: Vec<u8>) -> Result<u16> {
+            let signature = headers.iter()
+                .find(|(name, _)| *name == SIGNATURE_HEADER)
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default();
+            self.requests.lock().unwrap().push((signature, body));
+            Ok(self.responses.lock().unwrap().pop_front().unwrap_or(200))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery_with_retry() {
+        let transport = Arc::new(ScriptedTransport {
+            responses: StdMutex::new(VecDeque::from(vec![503, 500, 200])),
+            requests: StdMutex::new(Vec::new()),
+        });
+        let dispatcher = WebhookDispatcher::with_transport(transport.clone())
+            .with_retry_policy(RetryPolicy { initial_backoff_ms: 1, ..RetryPolicy::default() });
+        let webhook_id = dispatcher.register(Webhook::new("https://example.org/hook", "s3cret")
+            .with_thresholds(vec![0.5, 0.8])).await.unwrap();
+
+        // No threshold between the two values
+        assert!(dispatcher.confidence_changed("mol-1", 0.6, 0.7).await.is_empty());
+
+        let deliveries = dispatcher.confidence_changed("mol-1", 0.9, 0.4).await;
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[0].attempts.len(), 3);
+        assert_eq!(deliveries[0].payload["data"]["thresholds"], serde_json::json!([0.5, 0.8]));
+        assert_eq!(deliveries[0].payload["data"]["direction"], "falling");
+
+        let (signature, body) = transport.requests.lock().unwrap().last().cloned().unwrap();
+        assert!(verify_signature("s3cret", &body, &signature));
+        assert!(!verify_signature("other", &body, &signature));
+
+        let log = dispatcher.deliveries(Some(&webhook_id), 10).await;
+        assert_eq!(log.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let transport = Arc::new(ScriptedTransport {
+            responses: StdMutex::new(VecDeque::from(vec![410])),
+            requests: StdMutex::new(Vec::new()),
+        });
+        let dispatcher = WebhookDispatcher::with_transport(transport);
+        dispatcher.register(Webhook::new("https://example.org/hook", "s3cret")
+            .with_events(vec![WebhookEventKind::JobCompleted])).await.unwrap();
+
+        let deliveries = dispatcher.emit(WebhookEvent::JobCompleted {
+            job_id: "job-1".to_string(),
+            job_type: "analyze".to_string(),
+            success: true,
+            summary: serde_json::Value::Null,
+        }).await;
+        assert_eq!(deliveries[0].status, DeliveryStatus::Failed);
+        assert_eq!(deliveries[0].attempts.len(), 1);
+
+        // Unsubscribed events are not delivered
+        assert!(dispatcher.emit(WebhookEvent::ConflictDetected {
+            molecule_id: "mol-1".to_string(),
+            conflicts: Vec::new(),
+        }).await.is_empty());
+    }
+}
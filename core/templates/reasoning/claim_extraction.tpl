@@ -0,0 +1,13 @@
+Extract claims about {molecule} (also known as: {synonyms}) from the abstract of PubMed article {pmid}.
+
+Title: {title}
+
+Abstract:
+{text}
+
+Report only claims the abstract states about {molecule} itself, of these kinds:
+- detected_in: the molecule was detected or measured in a sample matrix, tissue or organism (object: the matrix)
+- in_pathway: the molecule takes part in a metabolic pathway (object: the pathway)
+- associated_with: the molecule is associated with a disease, condition or phenotype (object: the condition)
+
+Answer with a JSON array only. Give each claim the fields kind, object, sentence (the supporting sentence copied verbatim from the abstract), start and end (character offsets of that sentence in the abstract text, end exclusive) and confidence (0.0 to 1.0). Answer [] if the abstract makes no such claim.
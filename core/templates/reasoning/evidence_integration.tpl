@@ -0,0 +1,7 @@
+Integrate the following evidence sources for {molecule}:
+
+{evidence_table}
+
+{conflict_summary}
+
+Provide a justified conclusion about the molecule's identity:
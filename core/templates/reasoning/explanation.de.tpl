@@ -0,0 +1 @@
+Auf Grundlage der Evidenz für {molecule} wird das Molekül mit {confidence_percent} % Konfidenz identifiziert. Die verlässlichste Evidenz stammt von {strongest_source}.{{ for conflict in conflicts }} Konflikt zwischen {conflict.first} und {conflict.second} ({conflict.class}, Schweregrad {conflict.severity}) aufgelöst mit {conflict.strategy}: {conflict.explanation}.{{ endfor }}
@@ -0,0 +1 @@
+Based on analysis of the evidence for {molecule}, the molecule is identified with {confidence_percent}% confidence. The most reliable evidence comes from {strongest_source}.{{ for conflict in conflicts }} Conflict between {conflict.first} and {conflict.second} ({conflict.class}, severity {conflict.severity}) resolved with {conflict.strategy}: {conflict.explanation}.{{ endfor }}
@@ -0,0 +1,5 @@
+Given the following pathway evidence for {molecule}:
+
+{evidence_table}
+
+Reason about whether this molecule is part of the pathway:
@@ -0,0 +1,7 @@
+Given the following sequence evidence for {molecule}:
+
+{evidence_table}
+
+{conflict_summary}
+
+Reason about the conflicting identifications: